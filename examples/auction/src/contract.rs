@@ -0,0 +1,279 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use auction::{AuctionAbi, Bid, InstantiationArgument, Message, Operation};
+use linera_sdk::{
+    base::{Amount, Owner, WithContractAbi},
+    views::{RootView, View},
+    Contract, ContractRuntime,
+};
+use state::{AuctionState, Status};
+
+pub struct AuctionContract {
+    state: AuctionState,
+    runtime: ContractRuntime<Self>,
+}
+
+linera_sdk::contract!(AuctionContract);
+
+impl WithContractAbi for AuctionContract {
+    type Abi = AuctionAbi;
+}
+
+impl Contract for AuctionContract {
+    type Message = Message;
+    type InstantiationArgument = InstantiationArgument;
+    type Parameters = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = AuctionState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        AuctionContract { state, runtime }
+    }
+
+    async fn instantiate(&mut self, argument: InstantiationArgument) {
+        // Validate that the application parameters were configured correctly.
+        self.runtime.application_parameters();
+
+        assert!(
+            argument.end_time > self.runtime.system_time(),
+            "Auction cannot start after its end time"
+        );
+        self.state.instantiation_argument.set(Some(argument));
+
+        // The chain must execute this message in its first block at or after the end time.
+        self.runtime
+            .schedule_message(argument.end_time, Message::Settle);
+    }
+
+    async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
+        match operation {
+            Operation::Bid { amount } => {
+                let bidder = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Bids must be signed by the bidder");
+                let auction_chain = self.runtime.application_creator_chain_id();
+                if self.runtime.chain_id() == auction_chain {
+                    self.place_bid(bidder, amount);
+                } else {
+                    self.runtime
+                        .prepare_message(Message::Bid { amount })
+                        .with_authentication()
+                        .send_to(auction_chain);
+                }
+            }
+        }
+    }
+
+    async fn execute_message(&mut self, message: Message) {
+        assert_eq!(
+            self.runtime.chain_id(),
+            self.runtime.application_creator_chain_id(),
+            "Messages can only be executed on the chain that created the auction"
+        );
+        match message {
+            Message::Bid { amount } => {
+                let bidder = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Bids must be signed by the bidder");
+                self.place_bid(bidder, amount);
+            }
+            Message::Settle => {
+                let message_id = self.runtime.message_id().expect("Messages have an ID");
+                assert_eq!(
+                    message_id.chain_id,
+                    self.runtime.chain_id(),
+                    "Only the auction chain can settle the auction"
+                );
+                self.settle();
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl AuctionContract {
+    fn instantiation_argument(&self) -> &InstantiationArgument {
+        self.state
+            .instantiation_argument
+            .get()
+            .as_ref()
+            .expect("Application is not running on the host chain or was not instantiated yet")
+    }
+
+    /// Records a bid, if it is the highest one so far.
+    fn place_bid(&mut self, bidder: Owner, amount: Amount) {
+        let InstantiationArgument {
+            end_time,
+            minimum_bid,
+        } = *self.instantiation_argument();
+        assert_eq!(
+            *self.state.status.get(),
+            Status::Open,
+            "The auction is already settled"
+        );
+        assert!(
+            self.runtime.system_time() < end_time,
+            "The auction has ended"
+        );
+        assert!(amount >= minimum_bid, "Bid is below the minimum bid");
+        if let Some(highest_bid) = self.state.highest_bid.get() {
+            assert!(
+                amount > highest_bid.amount,
+                "Bid must be higher than the current highest bid"
+            );
+        }
+        self.state.highest_bid.set(Some(Bid { bidder, amount }));
+    }
+
+    /// Closes the auction. The highest bid, if any, wins.
+    fn settle(&mut self) {
+        let end_time = self.instantiation_argument().end_time;
+        assert!(
+            self.runtime.system_time() >= end_time,
+            "The auction can't be settled before its end time"
+        );
+        self.state.status.set(Status::Settled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use auction::{Bid, InstantiationArgument, Message, Operation};
+    use futures::FutureExt as _;
+    use linera_sdk::{
+        base::{Amount, ChainId, CryptoHash, MessageId, Owner, Timestamp},
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use super::{AuctionContract, AuctionState, Status};
+
+    const END_TIME: u64 = 1_000;
+
+    #[test]
+    fn instantiation_schedules_settlement() {
+        let auction = create_and_instantiate_auction();
+
+        let scheduled_messages = auction.runtime.scheduled_messages();
+        assert_eq!(scheduled_messages.len(), 1);
+        assert_eq!(scheduled_messages[0].0, Timestamp::from(END_TIME));
+        assert!(matches!(scheduled_messages[0].1, Message::Settle));
+    }
+
+    #[test]
+    fn highest_bid_wins() {
+        let mut auction = create_and_instantiate_auction();
+        let first_bidder = owner(1);
+        let second_bidder = owner(2);
+
+        bid(&mut auction, first_bidder, Amount::from_tokens(5));
+        bid(&mut auction, second_bidder, Amount::from_tokens(7));
+
+        auction.runtime.set_system_time(Timestamp::from(END_TIME));
+        auction.runtime.set_message_id(MessageId {
+            chain_id: auction_chain_id(),
+            height: 1.into(),
+            index: 0,
+        });
+        auction
+            .execute_message(Message::Settle)
+            .now_or_never()
+            .expect("Execution of auction message should not await anything");
+
+        assert_eq!(*auction.state.status.get(), Status::Settled);
+        assert_eq!(
+            *auction.state.highest_bid.get(),
+            Some(Bid {
+                bidder: second_bidder,
+                amount: Amount::from_tokens(7),
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Bid must be higher than the current highest bid")]
+    fn lower_bid_is_rejected() {
+        let mut auction = create_and_instantiate_auction();
+
+        bid(&mut auction, owner(1), Amount::from_tokens(5));
+        bid(&mut auction, owner(2), Amount::from_tokens(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has ended")]
+    fn late_bid_is_rejected() {
+        let mut auction = create_and_instantiate_auction();
+
+        auction.runtime.set_system_time(Timestamp::from(END_TIME));
+        bid(&mut auction, owner(1), Amount::from_tokens(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the auction chain can settle the auction")]
+    fn settlement_from_another_chain_is_rejected() {
+        let mut auction = create_and_instantiate_auction();
+
+        auction.runtime.set_system_time(Timestamp::from(END_TIME));
+        auction.runtime.set_message_id(MessageId {
+            chain_id: ChainId::root(1),
+            height: 1.into(),
+            index: 0,
+        });
+        auction
+            .execute_message(Message::Settle)
+            .now_or_never()
+            .expect("Execution of auction message should not await anything");
+    }
+
+    fn owner(index: u64) -> Owner {
+        Owner(CryptoHash::from([index; 4]))
+    }
+
+    fn auction_chain_id() -> ChainId {
+        ChainId::root(0)
+    }
+
+    fn bid(auction: &mut AuctionContract, bidder: Owner, amount: Amount) {
+        auction.runtime.set_authenticated_signer(bidder);
+        auction
+            .execute_operation(Operation::Bid { amount })
+            .now_or_never()
+            .expect("Execution of auction operation should not await anything");
+    }
+
+    fn create_and_instantiate_auction() -> AuctionContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_chain_id(auction_chain_id())
+            .with_application_creator_chain_id(auction_chain_id())
+            .with_system_time(Timestamp::from(0));
+        let mut contract = AuctionContract {
+            state: AuctionState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        contract
+            .instantiate(InstantiationArgument {
+                end_time: Timestamp::from(END_TIME),
+                minimum_bid: Amount::from_tokens(1),
+            })
+            .now_or_never()
+            .expect("Instantiation of auction state should not await anything");
+
+        contract
+    }
+}
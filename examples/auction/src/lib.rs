@@ -0,0 +1,59 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! ABI of the Auction Example Application */
+
+use async_graphql::{Request, Response, SimpleObject};
+use linera_sdk::{
+    base::{Amount, ContractAbi, Owner, ServiceAbi, Timestamp},
+    graphql::GraphQLMutationRoot,
+};
+use serde::{Deserialize, Serialize};
+
+pub struct AuctionAbi;
+
+impl ContractAbi for AuctionAbi {
+    type Operation = Operation;
+    type Response = ();
+}
+
+impl ServiceAbi for AuctionAbi {
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+/// The instantiation data required to open an auction.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct InstantiationArgument {
+    /// The time at which the auction closes and is settled.
+    pub end_time: Timestamp,
+    /// The lowest accepted bid.
+    pub minimum_bid: Amount,
+}
+
+/// A bid in the auction.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SimpleObject)]
+pub struct Bid {
+    /// The owner who placed the bid.
+    pub bidder: Owner,
+    /// The amount offered.
+    pub amount: Amount,
+}
+
+/// Operations that can be executed by the application.
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum Operation {
+    /// Bids for the item, from the auction chain or from any other chain. The bidder is the
+    /// signer of the block.
+    Bid { amount: Amount },
+}
+
+/// Messages that can be exchanged across chains from the same application instance.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Message {
+    /// Forwards a bid to the auction chain.
+    Bid { amount: Amount },
+    /// Settles the auction. The auction chain schedules this message to itself for the end
+    /// time, so that the auction is settled without anyone having to close it.
+    Settle,
+}
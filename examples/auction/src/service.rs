@@ -0,0 +1,50 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Request, Response, Schema};
+use auction::Operation;
+use linera_sdk::{
+    base::WithServiceAbi, graphql::GraphQLMutationRoot, views::View, Service, ServiceRuntime,
+};
+use state::AuctionState;
+
+pub struct AuctionService {
+    state: Arc<AuctionState>,
+    runtime: Arc<ServiceRuntime<Self>>,
+}
+
+linera_sdk::service!(AuctionService);
+
+impl WithServiceAbi for AuctionService {
+    type Abi = auction::AuctionAbi;
+}
+
+impl Service for AuctionService {
+    type Parameters = ();
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = AuctionState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        AuctionService {
+            state: Arc::new(state),
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    async fn handle_query(&self, request: Request) -> Response {
+        let schema = Schema::build(
+            self.state.clone(),
+            Operation::mutation_root(self.runtime.clone()),
+            EmptySubscription,
+        )
+        .finish();
+        schema.execute(request).await
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_graphql::scalar;
+use auction::{Bid, InstantiationArgument};
+use linera_sdk::views::{linera_views, RegisterView, RootView, ViewStorageContext};
+use serde::{Deserialize, Serialize};
+
+/// The status of an auction.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Status {
+    /// The auction accepts bids.
+    #[default]
+    Open,
+    /// The auction has ended, and the highest bid, if any, won.
+    Settled,
+}
+
+scalar!(Status);
+
+/// The auction's state.
+#[derive(RootView, async_graphql::SimpleObject)]
+#[view(context = "ViewStorageContext")]
+pub struct AuctionState {
+    /// The status of the auction.
+    pub status: RegisterView<Status>,
+    /// The highest bid so far, which is the winning bid once the auction is settled.
+    pub highest_bid: RegisterView<Option<Bid>>,
+    /// The instantiation data that determine the end time and minimum bid of the auction.
+    pub instantiation_argument: RegisterView<Option<InstantiationArgument>>,
+}
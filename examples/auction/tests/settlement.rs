@@ -0,0 +1,118 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integration tests for the Auction application.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use auction::{AuctionAbi, InstantiationArgument, Operation};
+use linera_sdk::{
+    base::{Amount, ApplicationId, Owner, Timestamp},
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+
+/// Test that an auction is settled by the message it scheduled to itself.
+///
+/// Creates an auction on an `auction_chain`, places bids from the auction chain and from a
+/// bidder chain, and then adds a block after the end time with no operation for the auction.
+/// The scheduled settlement message is executed by that block, so the auction is settled with
+/// the highest bid winning, and later bids are rejected.
+#[tokio::test(flavor = "multi_thread")]
+async fn scheduled_settlement() {
+    let end_time = Timestamp::from(1_000);
+    let (validator, bytecode_id) =
+        TestValidator::with_current_bytecode::<AuctionAbi, (), InstantiationArgument>().await;
+    let mut auction_chain = validator.new_chain().await;
+    let bidder_chain = validator.new_chain().await;
+
+    let argument = InstantiationArgument {
+        end_time,
+        minimum_bid: Amount::from_tokens(1),
+    };
+    let application_id = auction_chain
+        .create_application(bytecode_id, (), argument, vec![])
+        .await;
+    // Register the settlement message the application scheduled to itself.
+    auction_chain.handle_received_messages().await;
+
+    auction_chain
+        .add_block(|block| {
+            block.with_timestamp(Timestamp::from(100)).with_operation(
+                application_id,
+                Operation::Bid {
+                    amount: Amount::from_tokens(3),
+                },
+            );
+        })
+        .await;
+
+    bidder_chain.register_application(application_id).await;
+    let bid_certificate = bidder_chain
+        .add_block(|block| {
+            block.with_timestamp(Timestamp::from(200)).with_operation(
+                application_id,
+                Operation::Bid {
+                    amount: Amount::from_tokens(5),
+                },
+            );
+        })
+        .await;
+    auction_chain
+        .add_block(|block| {
+            block
+                .with_timestamp(Timestamp::from(300))
+                .with_messages_from(&bid_certificate);
+        })
+        .await;
+
+    assert_eq!(query_status(&auction_chain, application_id).await, "Open");
+
+    // Nobody closes the auction: the first block after the end time settles it.
+    auction_chain
+        .add_block(|block| {
+            block.with_timestamp(end_time);
+        })
+        .await;
+
+    assert_eq!(
+        query_status(&auction_chain, application_id).await,
+        "Settled"
+    );
+    let QueryOutcome { response, .. } = auction_chain
+        .graphql_query(application_id, "query { highestBid { bidder amount } }")
+        .await;
+    let bidder = Owner::from(bidder_chain.public_key());
+    assert_eq!(
+        response["highestBid"]["bidder"].as_str(),
+        Some(bidder.to_string().as_str())
+    );
+    let amount = response["highestBid"]["amount"]
+        .as_str()
+        .expect("Failed to get the amount")
+        .parse::<Amount>()
+        .expect("Failed to parse the amount");
+    assert_eq!(amount, Amount::from_tokens(5));
+
+    // Bids after the settlement are rejected.
+    let result = auction_chain
+        .try_add_block(|block| {
+            block.with_timestamp(Timestamp::from(2_000)).with_operation(
+                application_id,
+                Operation::Bid {
+                    amount: Amount::from_tokens(10),
+                },
+            );
+        })
+        .await;
+    assert!(result.is_err());
+}
+
+async fn query_status(chain: &ActiveChain, application_id: ApplicationId<AuctionAbi>) -> String {
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { status }")
+        .await;
+    response["status"]
+        .as_str()
+        .expect("Failed to get the status")
+        .to_owned()
+}
@@ -5,9 +5,10 @@
 
 mod state;
 
-use counter::CounterAbi;
+use counter::{CounterAbi, STREAM_NAME};
 use linera_sdk::{
-    base::WithContractAbi,
+    abi::AbiSchemas,
+    base::{StreamName, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
@@ -30,6 +31,8 @@ impl Contract for CounterContract {
     type InstantiationArgument = u64;
     type Parameters = ();
 
+    const ABI_SCHEMAS: Option<AbiSchemas> = Some(AbiSchemas::new::<CounterAbi, (), (), u64>());
+
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = CounterState::load(runtime.root_view_storage_context())
             .await
@@ -47,6 +50,8 @@ impl Contract for CounterContract {
     async fn execute_operation(&mut self, operation: u64) -> u64 {
         let new_value = self.state.value.get() + operation;
         self.state.value.set(new_value);
+        self.runtime
+            .emit_event(StreamName(STREAM_NAME.to_vec()), &[], &new_value);
         new_value
     }
 
@@ -61,8 +66,11 @@ impl Contract for CounterContract {
 
 #[cfg(test)]
 mod tests {
+    use counter::STREAM_NAME;
     use futures::FutureExt as _;
-    use linera_sdk::{util::BlockingWait, views::View, Contract, ContractRuntime};
+    use linera_sdk::{
+        base::StreamName, bcs, util::BlockingWait, views::View, Contract, ContractRuntime,
+    };
 
     use super::{CounterContract, CounterState};
 
@@ -82,6 +90,16 @@ mod tests {
 
         assert_eq!(response, expected_value);
         assert_eq!(*counter.state.value.get(), initial_value + increment);
+
+        let outcome = counter.runtime.take_execution_outcome();
+        assert_eq!(
+            outcome.events(),
+            [(
+                StreamName(STREAM_NAME.to_vec()),
+                vec![],
+                bcs::to_bytes(&expected_value).unwrap()
+            )]
+        );
     }
 
     #[test]
@@ -1,16 +1,34 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-/*! ABI of the Counter Example Application */
+/*! ABI of the Counter Example Application
+
+The ABI only depends on `linera-base`, so that native programs can build the operations of
+the counter without the `sdk` feature:
+
+```
+use counter::CounterAbi;
+use linera_base::abi::ContractAbi as _;
+
+let bytes = CounterAbi::serialize_operation(&3).unwrap();
+assert_eq!(bytes, 3u64.to_le_bytes());
+assert_eq!(CounterAbi::deserialize_operation(&bytes).unwrap(), 3);
+```
+*/
 
 use async_graphql::{Request, Response};
-use linera_sdk::base::{ContractAbi, ServiceAbi};
+use linera_base::abi::{EventAbi, ServiceAbi};
+use linera_sdk_derive::ContractAbi;
+
+/// The name of the stream on which the counter emits its new value after each increment.
+pub const STREAM_NAME: &[u8] = b"counter";
 
+#[derive(ContractAbi)]
+#[contract_abi(operation = u64, response = u64, crate = linera_base)]
 pub struct CounterAbi;
 
-impl ContractAbi for CounterAbi {
-    type Operation = u64;
-    type Response = u64;
+impl EventAbi for CounterAbi {
+    type Event = u64;
 }
 
 impl ServiceAbi for CounterAbi {
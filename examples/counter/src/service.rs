@@ -80,7 +80,11 @@ mod tests {
 
     use async_graphql::{Request, Response, Value};
     use futures::FutureExt as _;
-    use linera_sdk::{util::BlockingWait, views::View, Service, ServiceRuntime};
+    use linera_sdk::{
+        util::BlockingWait,
+        views::{linera_views::store::ReadableKeyValueStore as _, RootView, View},
+        KeyValueStore, Service, ServiceRuntime, ViewStorageContext,
+    };
     use serde_json::json;
 
     use super::{CounterService, CounterState};
@@ -106,4 +110,40 @@ mod tests {
 
         assert_eq!(response, expected)
     }
+
+    #[test]
+    fn query_state_written_by_contract() {
+        let value = 4_718_u64;
+        let key_value_store = KeyValueStore::mock().to_mut();
+        let context = ViewStorageContext::new_unsafe(key_value_store.clone(), Vec::new(), ());
+        let mut contract_state = CounterState::load(context)
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        contract_state.value.set(value);
+        contract_state
+            .save()
+            .blocking_wait()
+            .expect("Failed to write to mock key value store");
+
+        let stored_values = key_value_store
+            .find_key_values_by_prefix(&[])
+            .blocking_wait()
+            .expect("Failed to read from mock key value store")
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        assert_eq!(stored_values, [linera_sdk::bcs::to_bytes(&value).unwrap()]);
+
+        let runtime =
+            ServiceRuntime::<CounterService>::new().with_key_value_store(&key_value_store);
+        let service = CounterService::new(runtime).blocking_wait();
+        assert_eq!(*service.state.value.get(), value);
+
+        let response = service
+            .handle_query(Request::new("{ value }"))
+            .now_or_never()
+            .expect("Query should not await anything");
+        let expected = Response::new(Value::from_json(json!({"value" : 4_718})).unwrap());
+        assert_eq!(response, expected)
+    }
 }
@@ -0,0 +1,65 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for verifying the published bytecode of the Counter application.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::Path;
+
+use linera_sdk::{
+    base::Bytecode,
+    reproducible_build::{
+        bytecode_id_of, PinnedToolchain, ReproducibleBuild, SectionChange, SectionDifference,
+    },
+};
+
+/// Returns a reproducible build of this crate, with the toolchain pinned by the examples.
+fn reproducible_build() -> ReproducibleBuild {
+    let crate_path = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let toolchain = PinnedToolchain::from_file(crate_path.join("../rust-toolchain.toml"))
+        .expect("Failed to read the toolchain file");
+    ReproducibleBuild::new(crate_path, toolchain)
+}
+
+/// Rebuilds the Counter application and checks it against its own published artifact.
+#[test]
+fn rebuilt_bytecode_matches_published_artifact() {
+    let build = reproducible_build();
+    let (contract, service) = build.build().expect("Failed to build the bytecode");
+    let bytecode_id = bytecode_id_of(&contract, &service);
+
+    let report = build
+        .verify(bytecode_id, Some((&contract, &service)))
+        .expect("Failed to verify the bytecode");
+    assert!(report.matches());
+    assert!(report.contract.differences.is_empty());
+    assert!(report.service.differences.is_empty());
+}
+
+/// Rebuilds the Counter application and checks it against a tampered contract.
+#[test]
+fn tampered_bytecode_is_detected() {
+    let build = reproducible_build();
+    let (contract, service) = build.build().expect("Failed to build the bytecode");
+
+    // Flip the last byte of the module, which belongs to the last section.
+    let mut tampered_bytes = contract.bytes.clone();
+    *tampered_bytes.last_mut().unwrap() ^= 0xff;
+    let tampered = Bytecode::new(tampered_bytes);
+    let bytecode_id = bytecode_id_of(&tampered, &service);
+
+    let report = build
+        .verify(bytecode_id, Some((&tampered, &service)))
+        .expect("Failed to verify the bytecode");
+    assert!(!report.matches());
+    assert!(report.service.matches());
+    let [SectionDifference { section, change }] = report.contract.differences.as_slice() else {
+        panic!(
+            "Expected exactly one differing section, got {:?}",
+            report.contract.differences
+        );
+    };
+    assert!(matches!(change, SectionChange::Changed { .. }));
+    assert!(!section.is_empty());
+}
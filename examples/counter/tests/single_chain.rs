@@ -5,7 +5,10 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use linera_sdk::test::{QueryOutcome, TestValidator};
+use linera_sdk::{
+    abi::ContractAbi as _,
+    test::{Operation, QueryOutcome, TestValidator},
+};
 
 /// Test setting a counter and testing its coherency across microchains.
 ///
@@ -35,3 +38,40 @@ async fn single_chain_test() {
     let state_value = response["value"].as_u64().expect("Failed to get the u64");
     assert_eq!(state_value, final_value);
 }
+
+/// Test that the operations serialized natively through the ABI are the same bytes as the ones
+/// scheduled by the service compiled to Wasm, and that the contract accepts them.
+#[tokio::test(flavor = "multi_thread")]
+async fn native_operation_bytes_match_wasm() {
+    let (validator, bytecode_id) =
+        TestValidator::with_current_bytecode::<counter::CounterAbi, (), u64>().await;
+    let mut chain = validator.new_chain().await;
+
+    let initial_state = 42u64;
+    let application_id = chain
+        .create_application(bytecode_id, (), initial_state, vec![])
+        .await;
+
+    let increment = 15u64;
+    let native_bytes = counter::CounterAbi::serialize_operation(&increment)
+        .expect("Failed to serialize operation");
+
+    let QueryOutcome { operations, .. } = chain
+        .graphql_query(application_id, "mutation { increment(value: 15) }")
+        .await;
+    let [Operation::User { bytes, .. }] = operations.as_slice() else {
+        panic!("Expected a single user operation, got {operations:?}");
+    };
+    assert_eq!(*bytes, native_bytes);
+
+    chain
+        .add_block(|block| {
+            block.with_raw_operation(application_id, native_bytes);
+        })
+        .await;
+
+    let QueryOutcome { response, .. } =
+        chain.graphql_query(application_id, "query { value }").await;
+    let state_value = response["value"].as_u64().expect("Failed to get the u64");
+    assert_eq!(state_value, initial_state + increment);
+}
@@ -5,12 +5,16 @@
 
 mod state;
 
-use crowd_funding::{CrowdFundingAbi, InstantiationArgument, Message, Operation};
+use crowd_funding::{
+    CrowdFundingAbi, CrowdFundingError, InstantiationArgument, Message, Operation,
+};
 use fungible::{Account, FungibleTokenAbi};
 use linera_sdk::{
+    bail,
     base::{AccountOwner, Amount, ApplicationId, WithContractAbi},
+    ensure,
     views::{RootView, View},
-    Contract, ContractRuntime,
+    Contract, ContractError as _, ContractRuntime,
 };
 use state::{CrowdFundingState, Status};
 
@@ -54,9 +58,9 @@ impl Contract for CrowdFundingContract {
         match operation {
             Operation::Pledge { owner, amount } => {
                 if self.runtime.chain_id() == self.runtime.application_creator_chain_id() {
-                    self.execute_pledge_with_account(owner, amount).await;
+                    self.execute_pledge_with_account(owner, amount).await
                 } else {
-                    self.execute_pledge_with_transfer(owner, amount);
+                    self.execute_pledge_with_transfer(owner, amount)
                 }
             }
             Operation::Collect => self.collect_pledges(),
@@ -73,7 +77,9 @@ impl Contract for CrowdFundingContract {
                     "Action can only be executed on the chain that created the crowd-funding \
                     campaign"
                 );
-                self.execute_pledge_with_account(owner, amount).await;
+                if let Err(error) = self.execute_pledge_with_account(owner, amount).await {
+                    error.abort();
+                }
             }
         }
     }
@@ -91,8 +97,12 @@ impl CrowdFundingContract {
     }
 
     /// Adds a pledge from a local account to the remote campaign chain.
-    fn execute_pledge_with_transfer(&mut self, owner: AccountOwner, amount: Amount) {
-        assert!(amount > Amount::ZERO, "Pledge is empty");
+    fn execute_pledge_with_transfer(
+        &mut self,
+        owner: AccountOwner,
+        amount: Amount,
+    ) -> Result<(), CrowdFundingError> {
+        ensure!(amount > Amount::ZERO, CrowdFundingError::EmptyPledge);
         // The campaign chain.
         let chain_id = self.runtime.application_creator_chain_id();
         // First, move the funds to the campaign chain (under the same owner).
@@ -112,13 +122,23 @@ impl CrowdFundingContract {
             .prepare_message(Message::PledgeWithAccount { owner, amount })
             .with_authentication()
             .send_to(chain_id);
+        Ok(())
     }
 
     /// Adds a pledge from a local account to the campaign chain.
-    async fn execute_pledge_with_account(&mut self, owner: AccountOwner, amount: Amount) {
-        assert!(amount > Amount::ZERO, "Pledge is empty");
+    async fn execute_pledge_with_account(
+        &mut self,
+        owner: AccountOwner,
+        amount: Amount,
+    ) -> Result<(), CrowdFundingError> {
+        ensure!(amount > Amount::ZERO, CrowdFundingError::EmptyPledge);
+        ensure!(
+            !self.state.status.get().is_cancelled(),
+            CrowdFundingError::CampaignCancelled
+        );
         self.receive_from_account(owner, amount);
-        self.finish_pledge(owner, amount).await
+        self.finish_pledge(owner, amount).await;
+        Ok(())
     }
 
     /// Marks a pledge in the application state, so that it can be returned if the campaign is
@@ -133,42 +153,41 @@ impl CrowdFundingContract {
                 .expect("view access should not fail")
                 .saturating_add_assign(amount),
             Status::Complete => self.send_to(amount, self.instantiation_argument().owner),
-            Status::Cancelled => panic!("Crowd-funding campaign has been cancelled"),
+            Status::Cancelled => unreachable!("Pledges to cancelled campaigns are refused"),
         }
     }
 
     /// Collects all pledges and completes the campaign if the target has been reached.
-    fn collect_pledges(&mut self) {
+    fn collect_pledges(&mut self) -> Result<(), CrowdFundingError> {
         let total = self.balance();
 
         match self.state.status.get() {
-            Status::Active => {
-                assert!(
-                    total >= self.instantiation_argument().target,
-                    "Crowd-funding campaign has not reached its target yet"
-                );
-            }
+            Status::Active => ensure!(
+                total >= self.instantiation_argument().target,
+                CrowdFundingError::TargetNotReached
+            ),
             Status::Complete => (),
-            Status::Cancelled => panic!("Crowd-funding campaign has been cancelled"),
+            Status::Cancelled => bail!(CrowdFundingError::CampaignCancelled),
         }
 
         self.send_to(total, self.instantiation_argument().owner);
         self.state.pledges.clear();
         self.state.status.set(Status::Complete);
+        Ok(())
     }
 
     /// Cancels the campaign if the deadline has passed, refunding all pledges.
-    async fn cancel_campaign(&mut self) {
-        assert!(
+    async fn cancel_campaign(&mut self) -> Result<(), CrowdFundingError> {
+        ensure!(
             !self.state.status.get().is_complete(),
-            "Crowd-funding campaign has already been completed"
+            CrowdFundingError::CampaignCompleted
         );
 
         // TODO(#728): Remove this.
         #[cfg(not(test))]
-        assert!(
+        ensure!(
             self.runtime.system_time() >= self.instantiation_argument().deadline,
-            "Crowd-funding campaign has not reached its deadline yet"
+            CrowdFundingError::DeadlineNotReached
         );
 
         let mut pledges = Vec::new();
@@ -188,6 +207,7 @@ impl CrowdFundingContract {
         let balance = self.balance();
         self.send_to(balance, self.instantiation_argument().owner);
         self.state.status.set(Status::Cancelled);
+        Ok(())
     }
 
     /// Queries the token application to determine the total amount of tokens in custody.
@@ -243,3 +263,149 @@ impl CrowdFundingContract {
             .expect("Application is not running on the host chain or was not instantiated yet")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crowd_funding::{CrowdFundingError, InstantiationArgument, Operation};
+    use fungible::{Account, FungibleResponse, FungibleTokenAbi};
+    use linera_sdk::{
+        base::{
+            AccountOwner, Amount, ApplicationId, BytecodeId, ChainId, CryptoHash, MessageId, Owner,
+            Timestamp,
+        },
+        bcs,
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use super::{CrowdFundingContract, CrowdFundingState};
+
+    #[test]
+    fn pledge_transfers_tokens_through_the_typed_fungible_id() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let recorded_calls = calls.clone();
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(fungible_id())
+            .with_application_id(crowd_funding_id())
+            .with_chain_id(ChainId::root(1))
+            .with_application_creator_chain_id(ChainId::root(0))
+            .with_system_time(Timestamp::from(0))
+            .with_call_application_handler(move |authenticated, application_id, bytes| {
+                // The handler receives the raw ID and the serialized operation.
+                let operation = bcs::from_bytes::<fungible::Operation>(&bytes)
+                    .expect("Crowd-funding should only send fungible operations");
+                recorded_calls
+                    .borrow_mut()
+                    .push((authenticated, application_id, operation));
+                bcs::to_bytes(&FungibleResponse::Ok).unwrap()
+            });
+        let mut contract = CrowdFundingContract {
+            state: CrowdFundingState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+        contract
+            .instantiate(InstantiationArgument {
+                owner: owner(0),
+                deadline: Timestamp::from(1_000),
+                target: Amount::from_tokens(10),
+            })
+            .blocking_wait();
+
+        let pledger = owner(1);
+        let response = contract
+            .execute_operation(Operation::Pledge {
+                owner: pledger,
+                amount: Amount::ONE,
+            })
+            .blocking_wait();
+        assert_eq!(response, Ok(()));
+
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 1);
+        let (authenticated, application_id, operation) = &calls[0];
+        assert!(*authenticated);
+        assert_eq!(*application_id, fungible_id().forget_abi());
+        assert!(matches!(
+            operation,
+            fungible::Operation::Transfer {
+                owner,
+                amount,
+                target_account: Account { chain_id, owner: target_owner },
+            } if *owner == pledger
+                && *amount == Amount::ONE
+                && *chain_id == ChainId::root(0)
+                && *target_owner == pledger
+        ));
+    }
+
+    #[test]
+    fn failed_guards_return_typed_errors() {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(fungible_id())
+            .with_application_id(crowd_funding_id())
+            .with_chain_id(ChainId::root(0))
+            .with_application_creator_chain_id(ChainId::root(0))
+            .with_system_time(Timestamp::from(0))
+            .with_call_application_handler(|_, _, bytes| {
+                // The campaign holds no tokens.
+                let operation = bcs::from_bytes::<fungible::Operation>(&bytes).unwrap();
+                assert!(matches!(operation, fungible::Operation::Balance { .. }));
+                bcs::to_bytes(&FungibleResponse::Balance(Amount::ZERO)).unwrap()
+            });
+        let mut contract = CrowdFundingContract {
+            state: CrowdFundingState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+        contract
+            .instantiate(InstantiationArgument {
+                owner: owner(0),
+                deadline: Timestamp::from(1_000),
+                target: Amount::from_tokens(10),
+            })
+            .blocking_wait();
+
+        let response = contract
+            .execute_operation(Operation::Pledge {
+                owner: owner(1),
+                amount: Amount::ZERO,
+            })
+            .blocking_wait();
+        assert_eq!(response, Err(CrowdFundingError::EmptyPledge));
+
+        let response = contract
+            .execute_operation(Operation::Collect)
+            .blocking_wait();
+        assert_eq!(response, Err(CrowdFundingError::TargetNotReached));
+        assert!(!contract.state.status.get().is_complete());
+    }
+
+    fn owner(index: u64) -> AccountOwner {
+        AccountOwner::User(Owner(CryptoHash::from([index; 4])))
+    }
+
+    fn application_id(index: u32) -> ApplicationId {
+        ApplicationId {
+            bytecode_id: BytecodeId::new(CryptoHash::from([1; 4]), CryptoHash::from([2; 4])),
+            creation: MessageId {
+                chain_id: ChainId::root(0),
+                height: 0.into(),
+                index,
+            },
+        }
+    }
+
+    fn fungible_id() -> ApplicationId<FungibleTokenAbi> {
+        application_id(0).with_abi()
+    }
+
+    fn crowd_funding_id() -> ApplicationId<crowd_funding::CrowdFundingAbi> {
+        application_id(1).with_abi()
+    }
+}
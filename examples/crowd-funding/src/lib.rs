@@ -7,6 +7,7 @@ use async_graphql::{Request, Response, SimpleObject};
 use linera_sdk::{
     base::{AccountOwner, Amount, ContractAbi, ServiceAbi, Timestamp},
     graphql::GraphQLMutationRoot,
+    ContractError,
 };
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +15,7 @@ pub struct CrowdFundingAbi;
 
 impl ContractAbi for CrowdFundingAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = Result<(), CrowdFundingError>;
 }
 
 impl ServiceAbi for CrowdFundingAbi {
@@ -54,6 +55,21 @@ pub enum Operation {
     Cancel,
 }
 
+/// The errors returned by the operations of the application, before changing its state.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ContractError)]
+pub enum CrowdFundingError {
+    /// The pledge has no tokens.
+    EmptyPledge,
+    /// The campaign was cancelled, so it can't receive pledges or be collected anymore.
+    CampaignCancelled,
+    /// The campaign was completed, so it can't be cancelled anymore.
+    CampaignCompleted,
+    /// The pledges can't be collected before the campaign reaches its target.
+    TargetNotReached,
+    /// The campaign can't be cancelled before its deadline.
+    DeadlineNotReached,
+}
+
 /// Messages that can be exchanged across chains from the same application instance.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Message {
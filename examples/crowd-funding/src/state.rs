@@ -41,4 +41,9 @@ impl Status {
     pub fn is_complete(&self) -> bool {
         matches!(self, Status::Complete)
     }
+
+    /// Returns `true` if the campaign status is [`Status::Cancelled`].
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Status::Cancelled)
+    }
 }
@@ -223,3 +223,91 @@ async fn cancel_successful_campaign() {
         );
     }
 }
+
+/// Test that a campaign can't pledge tokens owned by another application.
+///
+/// Creates two campaigns, and gives some tokens to the first one on a backer chain. Pledging
+/// them to the second campaign makes it call the fungible token on behalf of the first one,
+/// which the fungible token refuses.
+#[tokio::test(flavor = "multi_thread")]
+async fn pledge_from_another_application_is_rejected() {
+    let initial_amount = Amount::from_tokens(100);
+    let application_amount = Amount::from_tokens(10);
+
+    let (validator, bytecode_id) = TestValidator::with_current_bytecode::<
+        CrowdFundingAbi,
+        ApplicationId<FungibleTokenAbi>,
+        InstantiationArgument,
+    >()
+    .await;
+
+    let fungible_publisher_chain = validator.new_chain().await;
+    let mut campaign_chain = validator.new_chain().await;
+    let campaign_account = AccountOwner::from(campaign_chain.public_key());
+
+    let fungible_bytecode_id = fungible_publisher_chain
+        .publish_bytecodes_in("../fungible")
+        .await;
+
+    let (token_id, backers) = fungible::create_with_accounts(
+        &validator,
+        fungible_bytecode_id,
+        iter::once(initial_amount),
+    )
+    .await;
+    let (backer_chain, backer_account, _balance) = &backers[0];
+
+    let campaign_state = InstantiationArgument {
+        owner: campaign_account,
+        deadline: Timestamp::from(u64::MAX),
+        target: Amount::from_tokens(220),
+    };
+    let mut campaign_ids = Vec::new();
+    for _ in 0..2 {
+        let campaign_id = campaign_chain
+            .create_application(
+                bytecode_id,
+                token_id,
+                campaign_state,
+                vec![token_id.forget_abi()],
+            )
+            .await;
+        backer_chain.register_application(campaign_id).await;
+        campaign_ids.push(campaign_id);
+    }
+    let first_campaign = AccountOwner::Application(campaign_ids[0].forget_abi());
+
+    backer_chain
+        .add_block(|block| {
+            block.with_operation(
+                token_id,
+                fungible::Operation::Transfer {
+                    owner: *backer_account,
+                    amount: application_amount,
+                    target_account: fungible::Account {
+                        chain_id: backer_chain.id(),
+                        owner: first_campaign,
+                    },
+                },
+            );
+        })
+        .await;
+
+    let result = backer_chain
+        .try_add_block(|block| {
+            block.with_operation(
+                campaign_ids[1],
+                Operation::Pledge {
+                    owner: first_campaign,
+                    amount: application_amount,
+                },
+            );
+        })
+        .await;
+    assert!(result.is_err());
+
+    assert_eq!(
+        fungible::query_account(token_id, backer_chain, first_campaign).await,
+        Some(application_amount),
+    );
+}
@@ -137,11 +137,9 @@ impl FungibleTokenContract {
                 )
             }
             AccountOwner::Application(id) => {
-                assert_eq!(
-                    self.runtime.authenticated_caller_id(),
-                    Some(id),
-                    "The requested transfer is not correctly authenticated."
-                )
+                if let Err(error) = self.runtime.require_caller(id) {
+                    panic!("The requested transfer is not correctly authenticated: {error}");
+                }
             }
         }
     }
@@ -187,3 +185,134 @@ impl FungibleTokenContract {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fungible::{Account, FungibleResponse, InitialState, Message, Operation, Parameters};
+    use linera_sdk::{
+        base::{AccountOwner, Amount, ChainId, CryptoHash, Owner},
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use super::{FungibleTokenContract, FungibleTokenState};
+
+    #[test]
+    fn transfer_to_another_chain_sends_a_credit_message() {
+        let mut fungible = create_and_instantiate_fungible(Amount::from_tokens(10));
+
+        let response = fungible
+            .execute_operation(Operation::Transfer {
+                owner: owner(0),
+                amount: Amount::from_tokens(3),
+                target_account: Account {
+                    chain_id: ChainId::root(1),
+                    owner: owner(1),
+                },
+            })
+            .blocking_wait();
+        assert!(matches!(response, FungibleResponse::Ok));
+
+        let outcome = fungible.runtime.take_execution_outcome();
+        outcome.assert_message_to(
+            ChainId::root(1),
+            &Message::Credit {
+                target: owner(1),
+                amount: Amount::from_tokens(3),
+                source: owner(0),
+            },
+        );
+        assert!(outcome.messages()[0].authenticated);
+        assert!(outcome.messages()[0].is_tracked);
+        assert_eq!(
+            fungible.state.balance_or_default(&owner(0)).blocking_wait(),
+            Amount::from_tokens(7)
+        );
+    }
+
+    #[test]
+    fn transfer_on_the_same_chain_sends_no_messages() {
+        let mut fungible = create_and_instantiate_fungible(Amount::from_tokens(10));
+
+        fungible
+            .execute_operation(Operation::Transfer {
+                owner: owner(0),
+                amount: Amount::from_tokens(3),
+                target_account: Account {
+                    chain_id: ChainId::root(0),
+                    owner: owner(1),
+                },
+            })
+            .blocking_wait();
+
+        fungible
+            .runtime
+            .take_execution_outcome()
+            .assert_no_messages();
+        assert_eq!(
+            fungible.state.balance_or_default(&owner(1)).blocking_wait(),
+            Amount::from_tokens(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "to be sent to")]
+    fn credit_message_is_not_sent_to_another_destination() {
+        let mut fungible = create_and_instantiate_fungible(Amount::from_tokens(10));
+
+        fungible
+            .execute_operation(Operation::Transfer {
+                owner: owner(0),
+                amount: Amount::from_tokens(3),
+                target_account: Account {
+                    chain_id: ChainId::root(1),
+                    owner: owner(1),
+                },
+            })
+            .blocking_wait();
+
+        fungible.runtime.take_execution_outcome().assert_message_to(
+            ChainId::root(2),
+            &Message::Credit {
+                target: owner(1),
+                amount: Amount::from_tokens(3),
+                source: owner(0),
+            },
+        );
+    }
+
+    fn create_and_instantiate_fungible(initial_balance: Amount) -> FungibleTokenContract {
+        let AccountOwner::User(signer) = owner(0) else {
+            unreachable!("Test owners are users");
+        };
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(Parameters::new("FUN"))
+            .with_chain_id(ChainId::root(0))
+            .with_authenticated_signer(signer);
+        let mut contract = FungibleTokenContract {
+            state: FungibleTokenState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        contract
+            .instantiate(InitialState {
+                accounts: BTreeMap::from([(owner(0), initial_balance)]),
+            })
+            .blocking_wait();
+        contract
+            .runtime
+            .take_execution_outcome()
+            .assert_no_messages();
+
+        contract
+    }
+
+    fn owner(index: u64) -> AccountOwner {
+        AccountOwner::User(Owner(CryptoHash::from([index; 4])))
+    }
+}
@@ -3,11 +3,42 @@
 
 //! This module defines the notion of Application Binary Interface (ABI) for Linera
 //! applications across Wasm and native architectures.
+//!
+//! An application's ABI only depends on this crate, so native programs can build the
+//! operations of an application, and decode its responses, without depending on
+//! `linera-sdk`. The bytes are the same that the contract deserializes when compiled to Wasm:
+//!
+//! ```
+//! use linera_base::abi::ContractAbi;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Serialize)]
+//! enum Operation {
+//!     Increment { value: u64 },
+//! }
+//!
+//! struct CounterAbi;
+//!
+//! impl ContractAbi for CounterAbi {
+//!     type Operation = Operation;
+//!     type Response = u64;
+//! }
+//!
+//! let operation = Operation::Increment { value: 7 };
+//! let bytes = CounterAbi::serialize_operation(&operation).unwrap();
+//! assert_eq!(bytes, [0, 7, 0, 0, 0, 0, 0, 0, 0]);
+//! assert_eq!(
+//!     CounterAbi::deserialize_operation(&bytes).unwrap(),
+//!     operation
+//! );
+//! ```
 
 use std::fmt::Debug;
 
 use serde::{de::DeserializeOwned, Serialize};
 
+pub use crate::abi_descriptor::{AbiSchema, AbiSchemas, Schema};
+
 // ANCHOR: abi
 /// A trait that includes all the types exported by a Linera application (both contract
 /// and service).
@@ -29,9 +60,36 @@ pub trait ContractAbi {
 
     /// The response type of an application call.
     type Response: Serialize + DeserializeOwned + Send + Sync + Debug + 'static;
+
+    /// Serializes an operation into the bytes that the contract receives.
+    fn serialize_operation(operation: &Self::Operation) -> Result<Vec<u8>, bcs::Error> {
+        bcs::to_bytes(operation)
+    }
+
+    /// Deserializes an operation, as done by the contract before executing it.
+    fn deserialize_operation(bytes: &[u8]) -> Result<Self::Operation, bcs::Error> {
+        bcs::from_bytes(bytes)
+    }
+
+    /// Serializes a response into the bytes returned by the contract.
+    fn serialize_response(response: &Self::Response) -> Result<Vec<u8>, bcs::Error> {
+        bcs::to_bytes(response)
+    }
+
+    /// Deserializes the response returned by the contract.
+    fn deserialize_response(bytes: &[u8]) -> Result<Self::Response, bcs::Error> {
+        bcs::from_bytes(bytes)
+    }
 }
 // ANCHOR_END: contract_abi
 
+/// A trait for the ABIs of application contracts that emit events, so that clients can decode
+/// them.
+pub trait EventAbi: ContractAbi {
+    /// The type of the events emitted by the application, serialized using BCS.
+    type Event: Serialize + DeserializeOwned + Send + Sync + Debug + 'static;
+}
+
 // ANCHOR: service_abi
 /// A trait that includes all the types exported by a Linera application service.
 pub trait ServiceAbi {
@@ -55,6 +113,22 @@ where
 {
     type Operation = <<A as WithContractAbi>::Abi as ContractAbi>::Operation;
     type Response = <<A as WithContractAbi>::Abi as ContractAbi>::Response;
+
+    fn serialize_operation(operation: &Self::Operation) -> Result<Vec<u8>, bcs::Error> {
+        <<A as WithContractAbi>::Abi as ContractAbi>::serialize_operation(operation)
+    }
+
+    fn deserialize_operation(bytes: &[u8]) -> Result<Self::Operation, bcs::Error> {
+        <<A as WithContractAbi>::Abi as ContractAbi>::deserialize_operation(bytes)
+    }
+
+    fn serialize_response(response: &Self::Response) -> Result<Vec<u8>, bcs::Error> {
+        <<A as WithContractAbi>::Abi as ContractAbi>::serialize_response(response)
+    }
+
+    fn deserialize_response(bytes: &[u8]) -> Result<Self::Response, bcs::Error> {
+        <<A as WithContractAbi>::Abi as ContractAbi>::deserialize_response(bytes)
+    }
 }
 
 /// Marker trait to help importing service types.
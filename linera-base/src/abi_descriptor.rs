@@ -0,0 +1,882 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Descriptions of the types of an application's ABI, embedded in its contract bytecode.
+//!
+//! The shapes of the JSON values accepted as parameters and instantiation argument are
+//! computed at compile time, so that validators can reject the creation of an application
+//! with arguments that the contract would fail to deserialize.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    abi::{ContractAbi, ServiceAbi},
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Timestamp},
+    identifiers::{ChainId, Owner},
+};
+
+/// The maximal size of an encoded [`Schema`]. Larger types are described as accepting any
+/// value.
+pub const MAX_SCHEMA_SIZE: usize = 512;
+
+/// The tags of the encoded schema nodes.
+mod tag {
+    pub const ANY: u8 = 0;
+    pub const NULL: u8 = 1;
+    pub const BOOL: u8 = 2;
+    pub const NUMBER: u8 = 3;
+    pub const STRING: u8 = 4;
+    pub const OPTION: u8 = 5;
+    pub const LIST: u8 = 6;
+    pub const MAP: u8 = 7;
+    pub const TUPLE: u8 = 8;
+    pub const OBJECT: u8 = 9;
+    pub const ENUM: u8 = 10;
+    pub const UNIT_VARIANT: u8 = 11;
+}
+
+/// A type with a [`Schema`] known at compile time.
+///
+/// Use `#[derive(AbiSchema)]` from `linera-sdk` to implement it for structs and enums.
+pub trait AbiSchema {
+    /// The shape of the values of this type, when serialized to JSON.
+    const SCHEMA: Schema;
+}
+
+/// A compact description of the shape of a JSON value, which can be built in constant
+/// expressions.
+#[derive(Clone, Copy, Debug)]
+pub struct Schema {
+    bytes: [u8; MAX_SCHEMA_SIZE],
+    len: usize,
+    overflowed: bool,
+}
+
+impl Schema {
+    /// Accepts any value.
+    pub const ANY: Schema = Schema::leaf(tag::ANY);
+    /// Accepts `null`, e.g. for `()` and unit structs.
+    pub const NULL: Schema = Schema::leaf(tag::NULL);
+    /// Accepts booleans.
+    pub const BOOL: Schema = Schema::leaf(tag::BOOL);
+    /// Accepts numbers.
+    pub const NUMBER: Schema = Schema::leaf(tag::NUMBER);
+    /// Accepts strings.
+    pub const STRING: Schema = Schema::leaf(tag::STRING);
+    /// The content of an enum variant without fields, serialized as the variant name.
+    pub const UNIT_VARIANT: Schema = Schema::leaf(tag::UNIT_VARIANT);
+
+    const fn leaf(tag: u8) -> Schema {
+        let mut bytes = [0; MAX_SCHEMA_SIZE];
+        bytes[0] = tag;
+        Schema {
+            bytes,
+            len: 1,
+            overflowed: false,
+        }
+    }
+
+    /// Accepts `null` or a value of the `inner` schema.
+    pub const fn option(inner: Schema) -> Schema {
+        Schema::leaf(tag::OPTION).push_schema(inner)
+    }
+
+    /// Accepts arrays of `item` values.
+    pub const fn list(item: Schema) -> Schema {
+        Schema::leaf(tag::LIST).push_schema(item)
+    }
+
+    /// Accepts objects with arbitrary keys and `value` values.
+    pub const fn map(value: Schema) -> Schema {
+        Schema::leaf(tag::MAP).push_schema(value)
+    }
+
+    /// Accepts arrays with exactly one value of each of the `items` schemas.
+    pub const fn tuple(items: &[Schema]) -> Schema {
+        if items.len() > u8::MAX as usize {
+            return Schema::ANY;
+        }
+        let mut schema = Schema::leaf(tag::TUPLE).push_byte(items.len() as u8);
+        let mut index = 0;
+        while index < items.len() {
+            schema = schema.push_schema(items[index]);
+            index += 1;
+        }
+        schema
+    }
+
+    /// Accepts arrays of exactly `count` values of the `item` schema.
+    pub const fn array(item: Schema, count: usize) -> Schema {
+        if count > u8::MAX as usize {
+            return Schema::list(item);
+        }
+        let mut schema = Schema::leaf(tag::TUPLE).push_byte(count as u8);
+        let mut index = 0;
+        while index < count {
+            schema = schema.push_schema(item);
+            index += 1;
+        }
+        schema
+    }
+
+    /// Accepts objects with the given `fields`, where fields accepting `null` may be missing.
+    pub const fn object(fields: &[(&str, Schema)]) -> Schema {
+        Schema::leaf(tag::OBJECT).push_named(fields)
+    }
+
+    /// Accepts the externally tagged `variants` of an enum: the name of a variant without
+    /// fields, or an object with a single key naming the variant.
+    pub const fn enumeration(variants: &[(&str, Schema)]) -> Schema {
+        Schema::leaf(tag::ENUM).push_named(variants)
+    }
+
+    /// Returns the encoded schema.
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.overflowed {
+            &[tag::ANY]
+        } else {
+            &self.bytes[..self.len]
+        }
+    }
+
+    /// Returns a hash of the encoded schema.
+    pub const fn hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        if self.overflowed {
+            return (OFFSET_BASIS ^ tag::ANY as u64).wrapping_mul(PRIME);
+        }
+        let mut hash = OFFSET_BASIS;
+        let mut index = 0;
+        while index < self.len {
+            hash = (hash ^ self.bytes[index] as u64).wrapping_mul(PRIME);
+            index += 1;
+        }
+        hash
+    }
+
+    const fn push_named(mut self, entries: &[(&str, Schema)]) -> Schema {
+        if entries.len() > u8::MAX as usize {
+            return Schema::ANY;
+        }
+        self = self.push_byte(entries.len() as u8);
+        let mut index = 0;
+        while index < entries.len() {
+            let (name, schema) = entries[index];
+            let name = name.as_bytes();
+            if name.len() > u8::MAX as usize {
+                return Schema::ANY;
+            }
+            self = self.push_byte(name.len() as u8);
+            let mut byte = 0;
+            while byte < name.len() {
+                self = self.push_byte(name[byte]);
+                byte += 1;
+            }
+            self = self.push_schema(schema);
+            index += 1;
+        }
+        self
+    }
+
+    const fn push_byte(mut self, byte: u8) -> Schema {
+        if self.len == MAX_SCHEMA_SIZE {
+            self.overflowed = true;
+        }
+        if !self.overflowed {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+        self
+    }
+
+    const fn push_schema(mut self, schema: Schema) -> Schema {
+        if schema.overflowed {
+            return self.push_byte(tag::ANY);
+        }
+        let mut index = 0;
+        while index < schema.len {
+            self = self.push_byte(schema.bytes[index]);
+            index += 1;
+        }
+        self
+    }
+}
+
+/// The schemas of the types exported by a contract, to be embedded in its bytecode.
+#[derive(Clone, Copy, Debug)]
+pub struct AbiSchemas {
+    /// The schema of the operations.
+    pub operation: Schema,
+    /// The schema of the messages.
+    pub message: Schema,
+    /// The schema of the service queries.
+    pub query: Schema,
+    /// The schema of the application parameters.
+    pub parameters: Schema,
+    /// The schema of the instantiation argument.
+    pub instantiation_argument: Schema,
+}
+
+impl AbiSchemas {
+    /// Returns the schemas of the types of an application with the ABI `A`.
+    pub const fn new<A, Message, Parameters, InstantiationArgument>() -> Self
+    where
+        A: ContractAbi + ServiceAbi,
+        A::Operation: AbiSchema,
+        A::Query: AbiSchema,
+        Message: AbiSchema,
+        Parameters: AbiSchema,
+        InstantiationArgument: AbiSchema,
+    {
+        AbiSchemas {
+            operation: <A::Operation as AbiSchema>::SCHEMA,
+            message: Message::SCHEMA,
+            query: <A::Query as AbiSchema>::SCHEMA,
+            parameters: Parameters::SCHEMA,
+            instantiation_argument: InstantiationArgument::SCHEMA,
+        }
+    }
+
+    /// Returns the size of the custom section encoding the `schemas`, if any.
+    pub const fn encoded_len(schemas: &Option<AbiSchemas>) -> usize {
+        match schemas {
+            None => 0,
+            Some(schemas) => {
+                let parameters = schemas.parameters.encoded_len();
+                let argument = schemas.instantiation_argument.encoded_len();
+                1 + 3 * 8 + uleb128_len(parameters) + parameters + uleb128_len(argument) + argument
+            }
+        }
+    }
+
+    /// Encodes the `schemas` as the contents of the [`AbiDescriptor::SECTION_NAME`] custom
+    /// section. `N` must be [`AbiSchemas::encoded_len`].
+    pub const fn encode<const N: usize>(schemas: &Option<AbiSchemas>) -> [u8; N] {
+        let mut buffer = [0; N];
+        let Some(schemas) = schemas else {
+            return buffer;
+        };
+        buffer[0] = AbiDescriptor::VERSION;
+        let mut offset = 1;
+        (buffer, offset) = write_u64(buffer, offset, schemas.operation.hash());
+        (buffer, offset) = write_u64(buffer, offset, schemas.message.hash());
+        (buffer, offset) = write_u64(buffer, offset, schemas.query.hash());
+        (buffer, offset) = write_schema(buffer, offset, &schemas.parameters);
+        (buffer, offset) = write_schema(buffer, offset, &schemas.instantiation_argument);
+        assert!(offset == N, "Invalid size for the ABI descriptor");
+        buffer
+    }
+}
+
+impl Schema {
+    const fn encoded_len(&self) -> usize {
+        if self.overflowed {
+            1
+        } else {
+            self.len
+        }
+    }
+}
+
+const fn uleb128_len(mut value: usize) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+const fn write_u64<const N: usize>(
+    mut buffer: [u8; N],
+    offset: usize,
+    value: u64,
+) -> ([u8; N], usize) {
+    let bytes = value.to_le_bytes();
+    let mut index = 0;
+    while index < 8 {
+        buffer[offset + index] = bytes[index];
+        index += 1;
+    }
+    (buffer, offset + 8)
+}
+
+const fn write_schema<const N: usize>(
+    mut buffer: [u8; N],
+    mut offset: usize,
+    schema: &Schema,
+) -> ([u8; N], usize) {
+    let mut len = schema.encoded_len();
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            buffer[offset] = byte;
+            offset += 1;
+            break;
+        }
+        buffer[offset] = byte | 0x80;
+        offset += 1;
+    }
+    if schema.overflowed {
+        buffer[offset] = tag::ANY;
+        return (buffer, offset + 1);
+    }
+    let mut index = 0;
+    while index < schema.len {
+        buffer[offset] = schema.bytes[index];
+        offset += 1;
+        index += 1;
+    }
+    (buffer, offset)
+}
+
+/// The description of an application's ABI, read from the custom section
+/// [`AbiDescriptor::SECTION_NAME`] of its contract bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiDescriptor {
+    /// The hash of the schema of the operations.
+    pub operation_hash: u64,
+    /// The hash of the schema of the messages.
+    pub message_hash: u64,
+    /// The hash of the schema of the service queries.
+    pub query_hash: u64,
+    /// The encoded schema of the application parameters.
+    pub parameters: Vec<u8>,
+    /// The encoded schema of the instantiation argument.
+    pub instantiation_argument: Vec<u8>,
+}
+
+/// An error when reading an [`AbiDescriptor`] or checking a value against it.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum AbiDescriptorError {
+    /// The custom section is not a valid ABI descriptor.
+    #[error("Invalid ABI descriptor: {0}")]
+    InvalidDescriptor(String),
+    /// The value is not valid JSON.
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+    /// The value doesn't have the shape described by the schema.
+    #[error("At `{path}`: expected {expected}, found {found}")]
+    Mismatch {
+        /// The location of the mismatch in the value.
+        path: String,
+        /// A description of the expected value.
+        expected: String,
+        /// A description of the actual value.
+        found: String,
+    },
+}
+
+impl AbiDescriptor {
+    /// The name of the custom section of the contract bytecode containing the descriptor.
+    pub const SECTION_NAME: &'static str = "linera:abi";
+
+    /// The version of the encoding of the descriptor.
+    pub const VERSION: u8 = 0;
+
+    /// The first bytes of every WebAssembly module.
+    pub const WASM_MAGIC: &'static [u8] = b"\0asm";
+
+    /// Reads the descriptor from the contents of its custom section. An empty section means
+    /// that the application doesn't describe its ABI.
+    pub fn from_section(bytes: &[u8]) -> Result<Option<Self>, AbiDescriptorError> {
+        let Some((&version, bytes)) = bytes.split_first() else {
+            return Ok(None);
+        };
+        if version != Self::VERSION {
+            return Err(AbiDescriptorError::InvalidDescriptor(format!(
+                "unsupported version {version}"
+            )));
+        }
+        let descriptor: AbiDescriptor = bcs::from_bytes(bytes)
+            .map_err(|error| AbiDescriptorError::InvalidDescriptor(error.to_string()))?;
+        for schema in [&descriptor.parameters, &descriptor.instantiation_argument] {
+            let mut reader = SchemaReader { bytes: schema };
+            reader.skip()?;
+            if !reader.bytes.is_empty() {
+                return Err(AbiDescriptorError::InvalidDescriptor(
+                    "trailing bytes after schema".to_owned(),
+                ));
+            }
+        }
+        Ok(Some(descriptor))
+    }
+
+    /// Reads the descriptor from the custom section of a WebAssembly `module`, if present.
+    pub fn from_wasm(module: &[u8]) -> Result<Option<Self>, AbiDescriptorError> {
+        let invalid = |reason: &str| {
+            AbiDescriptorError::InvalidDescriptor(format!("malformed module: {reason}"))
+        };
+        if !module.starts_with(Self::WASM_MAGIC) {
+            return Err(invalid("not a WebAssembly module"));
+        }
+        let mut bytes = module.get(8..).ok_or_else(|| invalid("missing version"))?;
+        while let Some((&section_id, rest)) = bytes.split_first() {
+            let (section, rest) =
+                split_length_prefixed(rest).ok_or_else(|| invalid("truncated section"))?;
+            bytes = rest;
+            // Custom sections have the ID 0 and start with their name.
+            if section_id == 0 {
+                let (name, contents) = split_length_prefixed(section)
+                    .ok_or_else(|| invalid("truncated custom section name"))?;
+                if name == Self::SECTION_NAME.as_bytes() {
+                    return Self::from_section(contents);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks that the JSON `parameters` have the shape expected by the application.
+    pub fn check_parameters(&self, parameters: &[u8]) -> Result<(), AbiDescriptorError> {
+        check_json(&self.parameters, parameters)
+    }
+
+    /// Checks that the JSON instantiation `argument` has the shape expected by the
+    /// application.
+    pub fn check_instantiation_argument(&self, argument: &[u8]) -> Result<(), AbiDescriptorError> {
+        check_json(&self.instantiation_argument, argument)
+    }
+}
+
+/// Splits `bytes` after a prefix of the size given by its leading LEB128 length.
+fn split_length_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut len = 0usize;
+    for (index, &byte) in bytes.iter().enumerate().take(5) {
+        len |= usize::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            let rest = &bytes[index + 1..];
+            return (len <= rest.len()).then(|| rest.split_at(len));
+        }
+    }
+    None
+}
+
+/// Checks that the serialized JSON `value` matches the encoded `schema`.
+fn check_json(schema: &[u8], value: &[u8]) -> Result<(), AbiDescriptorError> {
+    let value: Value = serde_json::from_slice(value)
+        .map_err(|error| AbiDescriptorError::InvalidJson(error.to_string()))?;
+    let mut reader = SchemaReader { bytes: schema };
+    reader.check(&value, &mut String::new())
+}
+
+/// A cursor over an encoded [`Schema`].
+struct SchemaReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SchemaReader<'a> {
+    fn read_byte(&mut self) -> Result<u8, AbiDescriptorError> {
+        let (&byte, rest) = self
+            .bytes
+            .split_first()
+            .ok_or_else(|| AbiDescriptorError::InvalidDescriptor("truncated schema".to_owned()))?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn read_name(&mut self) -> Result<&'a str, AbiDescriptorError> {
+        let len = self.read_byte()? as usize;
+        if self.bytes.len() < len {
+            return Err(AbiDescriptorError::InvalidDescriptor(
+                "truncated schema".to_owned(),
+            ));
+        }
+        let (name, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        std::str::from_utf8(name)
+            .map_err(|error| AbiDescriptorError::InvalidDescriptor(error.to_string()))
+    }
+
+    /// Skips the next schema node, checking that it is well-formed.
+    fn skip(&mut self) -> Result<(), AbiDescriptorError> {
+        match self.read_byte()? {
+            tag::ANY | tag::NULL | tag::BOOL | tag::NUMBER | tag::STRING | tag::UNIT_VARIANT => {}
+            tag::OPTION | tag::LIST | tag::MAP => self.skip()?,
+            tag::TUPLE => {
+                for _ in 0..self.read_byte()? {
+                    self.skip()?;
+                }
+            }
+            tag::OBJECT | tag::ENUM => {
+                for _ in 0..self.read_byte()? {
+                    self.read_name()?;
+                    self.skip()?;
+                }
+            }
+            other => {
+                return Err(AbiDescriptorError::InvalidDescriptor(format!(
+                    "unknown schema tag {other}"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `value` against the next schema node, consuming it. `path` locates `value`
+    /// for error messages.
+    fn check(&mut self, value: &Value, path: &mut String) -> Result<(), AbiDescriptorError> {
+        let schema_tag = self.read_byte()?;
+        let expected = match schema_tag {
+            tag::ANY => return Ok(()),
+            tag::NULL | tag::UNIT_VARIANT if value.is_null() => return Ok(()),
+            tag::NULL | tag::UNIT_VARIANT => "null",
+            tag::BOOL if value.is_boolean() => return Ok(()),
+            tag::BOOL => "a boolean",
+            tag::NUMBER if value.is_number() => return Ok(()),
+            tag::NUMBER => "a number",
+            tag::STRING if value.is_string() => return Ok(()),
+            tag::STRING => "a string",
+            tag::OPTION if value.is_null() => return self.skip(),
+            tag::OPTION => return self.check(value, path),
+            tag::LIST => {
+                let Value::Array(items) = value else {
+                    self.skip()?;
+                    return Err(mismatch(path, "an array", value));
+                };
+                let item_schema = self.bytes;
+                self.skip()?;
+                for (index, item) in items.iter().enumerate() {
+                    let mut reader = SchemaReader { bytes: item_schema };
+                    with_segment(path, &format!("[{index}]"), |path| reader.check(item, path))?;
+                }
+                return Ok(());
+            }
+            tag::MAP => {
+                let Value::Object(entries) = value else {
+                    self.skip()?;
+                    return Err(mismatch(path, "an object", value));
+                };
+                let value_schema = self.bytes;
+                self.skip()?;
+                for (key, entry) in entries {
+                    let mut reader = SchemaReader {
+                        bytes: value_schema,
+                    };
+                    with_segment(path, &format!(".{key}"), |path| reader.check(entry, path))?;
+                }
+                return Ok(());
+            }
+            tag::TUPLE => {
+                let count = self.read_byte()? as usize;
+                let Value::Array(items) = value else {
+                    return Err(mismatch(
+                        path,
+                        &format!("an array of {count} values"),
+                        value,
+                    ));
+                };
+                if items.len() != count {
+                    return Err(mismatch(
+                        path,
+                        &format!("an array of {count} values"),
+                        value,
+                    ));
+                }
+                for (index, item) in items.iter().enumerate() {
+                    with_segment(path, &format!("[{index}]"), |path| self.check(item, path))?;
+                }
+                return Ok(());
+            }
+            tag::OBJECT => return self.check_object(value, path),
+            tag::ENUM => return self.check_enum(value, path),
+            other => {
+                return Err(AbiDescriptorError::InvalidDescriptor(format!(
+                    "unknown schema tag {other}"
+                )))
+            }
+        };
+        Err(mismatch(path, expected, value))
+    }
+
+    fn check_object(&mut self, value: &Value, path: &mut String) -> Result<(), AbiDescriptorError> {
+        let count = self.read_byte()?;
+        let Value::Object(entries) = value else {
+            for _ in 0..count {
+                self.read_name()?;
+                self.skip()?;
+            }
+            return Err(mismatch(path, "an object", value));
+        };
+        for _ in 0..count {
+            let name = self.read_name()?;
+            let field = entries.get(name).unwrap_or(&Value::Null);
+            with_segment(path, &format!(".{name}"), |path| self.check(field, path))?;
+        }
+        Ok(())
+    }
+
+    fn check_enum(&mut self, value: &Value, path: &mut String) -> Result<(), AbiDescriptorError> {
+        let count = self.read_byte()?;
+        let (variant_name, content) = match value {
+            Value::String(name) => (name.as_str(), None),
+            Value::Object(entries) if entries.len() == 1 => {
+                let (name, content) = entries.iter().next().expect("one entry");
+                (name.as_str(), Some(content))
+            }
+            _ => {
+                for _ in 0..count {
+                    self.read_name()?;
+                    self.skip()?;
+                }
+                return Err(mismatch(path, "an enum variant", value));
+            }
+        };
+        let mut names = Vec::new();
+        for _ in 0..count {
+            let name = self.read_name()?;
+            if name != variant_name {
+                names.push(name);
+                self.skip()?;
+                continue;
+            }
+            let is_unit = self.bytes.first() == Some(&tag::UNIT_VARIANT);
+            let result = match content {
+                None if is_unit => self.skip(),
+                None => {
+                    self.skip()?;
+                    Err(mismatch(path, "a variant with fields", value))
+                }
+                Some(content) => {
+                    with_segment(path, &format!(".{name}"), |path| self.check(content, path))
+                }
+            };
+            // Skip the remaining variants, to leave the reader after this schema.
+            while names.len() + 1 < count as usize {
+                names.push(self.read_name()?);
+                self.skip()?;
+            }
+            return result;
+        }
+        Err(AbiDescriptorError::Mismatch {
+            path: display_path(path),
+            expected: format!("one of the variants {}", names.join(", ")),
+            found: format!("`{variant_name}`"),
+        })
+    }
+}
+
+/// Runs `check` with `segment` appended to `path`.
+fn with_segment<T>(path: &mut String, segment: &str, check: impl FnOnce(&mut String) -> T) -> T {
+    let len = path.len();
+    path.push_str(segment);
+    let result = check(path);
+    path.truncate(len);
+    result
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        ".".to_owned()
+    } else {
+        path.to_owned()
+    }
+}
+
+fn mismatch(path: &str, expected: &str, value: &Value) -> AbiDescriptorError {
+    let found = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    AbiDescriptorError::Mismatch {
+        path: display_path(path),
+        expected: expected.to_owned(),
+        found: found.to_owned(),
+    }
+}
+
+macro_rules! impl_abi_schema {
+    ($schema:expr => $($type:ty),*) => {
+        $(
+            impl AbiSchema for $type {
+                const SCHEMA: Schema = $schema;
+            }
+        )*
+    };
+}
+
+impl_abi_schema!(Schema::NULL => ());
+impl_abi_schema!(Schema::BOOL => bool);
+impl_abi_schema!(
+    Schema::NUMBER => u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+impl_abi_schema!(Schema::NUMBER => BlockHeight, Timestamp);
+impl_abi_schema!(Schema::STRING => char, String, Amount, CryptoHash, ChainId, Owner);
+
+impl<T: AbiSchema + ?Sized> AbiSchema for Box<T> {
+    const SCHEMA: Schema = T::SCHEMA;
+}
+
+impl<T: AbiSchema> AbiSchema for Option<T> {
+    const SCHEMA: Schema = Schema::option(T::SCHEMA);
+}
+
+impl<T: AbiSchema> AbiSchema for Vec<T> {
+    const SCHEMA: Schema = Schema::list(T::SCHEMA);
+}
+
+impl<T: AbiSchema> AbiSchema for VecDeque<T> {
+    const SCHEMA: Schema = Schema::list(T::SCHEMA);
+}
+
+impl<T: AbiSchema> AbiSchema for BTreeSet<T> {
+    const SCHEMA: Schema = Schema::list(T::SCHEMA);
+}
+
+impl<T: AbiSchema, S> AbiSchema for HashSet<T, S> {
+    const SCHEMA: Schema = Schema::list(T::SCHEMA);
+}
+
+impl<K, V: AbiSchema> AbiSchema for BTreeMap<K, V> {
+    const SCHEMA: Schema = Schema::map(V::SCHEMA);
+}
+
+impl<K, V: AbiSchema, S> AbiSchema for HashMap<K, V, S> {
+    const SCHEMA: Schema = Schema::map(V::SCHEMA);
+}
+
+impl<T: AbiSchema, const N: usize> AbiSchema for [T; N] {
+    const SCHEMA: Schema = Schema::array(T::SCHEMA, N);
+}
+
+impl AbiSchema for Value {
+    const SCHEMA: Schema = Schema::ANY;
+}
+
+impl AbiSchema for async_graphql::Request {
+    const SCHEMA: Schema = Schema::ANY;
+}
+
+macro_rules! impl_abi_schema_for_tuples {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: AbiSchema),+> AbiSchema for ($($name,)+) {
+                const SCHEMA: Schema = Schema::tuple(&[$($name::SCHEMA),+]);
+            }
+        )*
+    };
+}
+
+impl_abi_schema_for_tuples!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const CONFIG: Schema = Schema::object(&[
+        ("name", String::SCHEMA),
+        ("supply", Amount::SCHEMA),
+        ("limits", <Option<Vec<(u64, bool)>>>::SCHEMA),
+    ]);
+
+    const KIND: Schema =
+        Schema::enumeration(&[("Open", Schema::UNIT_VARIANT), ("Capped", u64::SCHEMA)]);
+
+    const SCHEMAS: AbiSchemas = AbiSchemas {
+        operation: KIND,
+        message: Schema::NULL,
+        query: Schema::ANY,
+        parameters: CONFIG,
+        instantiation_argument: u64::SCHEMA,
+    };
+
+    fn check(schema: &Schema, value: serde_json::Value) -> Result<(), AbiDescriptorError> {
+        check_json(schema.as_bytes(), &serde_json::to_vec(&value).unwrap())
+    }
+
+    #[test]
+    fn test_values_are_checked_against_schemas() {
+        check(&CONFIG, json!({ "name": "Token", "supply": "1.5" })).unwrap();
+        check(
+            &CONFIG,
+            json!({ "name": "Token", "supply": "1.5", "limits": [[10, true]] }),
+        )
+        .unwrap();
+        assert_eq!(
+            check(&CONFIG, json!({ "name": "Token", "supply": 15 })),
+            Err(AbiDescriptorError::Mismatch {
+                path: ".supply".to_owned(),
+                expected: "a string".to_owned(),
+                found: "a number".to_owned(),
+            })
+        );
+        assert_eq!(
+            check(
+                &CONFIG,
+                json!({ "name": "Token", "supply": "1", "limits": [[10]] })
+            ),
+            Err(AbiDescriptorError::Mismatch {
+                path: ".limits[0]".to_owned(),
+                expected: "an array of 2 values".to_owned(),
+                found: "an array".to_owned(),
+            })
+        );
+
+        check(&KIND, json!("Open")).unwrap();
+        check(&KIND, json!({ "Capped": 100 })).unwrap();
+        assert!(check(&KIND, json!("Capped")).is_err());
+        assert_eq!(
+            check(&KIND, json!("Closed")),
+            Err(AbiDescriptorError::Mismatch {
+                path: ".".to_owned(),
+                expected: "one of the variants Open, Capped".to_owned(),
+                found: "`Closed`".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_descriptor_section_roundtrip() {
+        const SECTION: [u8; AbiSchemas::encoded_len(&Some(SCHEMAS))] =
+            AbiSchemas::encode(&Some(SCHEMAS));
+
+        let descriptor = AbiDescriptor::from_section(&SECTION).unwrap().unwrap();
+        assert_eq!(descriptor.operation_hash, KIND.hash());
+        assert_eq!(descriptor.parameters, CONFIG.as_bytes());
+        descriptor.check_instantiation_argument(b"42").unwrap();
+        assert!(descriptor.check_instantiation_argument(b"\"42\"").is_err());
+
+        assert_eq!(AbiSchemas::encoded_len(&None), 0);
+        assert_eq!(AbiDescriptor::from_section(&[]), Ok(None));
+        assert!(AbiDescriptor::from_section(&SECTION[..SECTION.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_descriptor_is_read_from_custom_section() {
+        let section =
+            AbiSchemas::encode::<{ AbiSchemas::encoded_len(&Some(SCHEMAS)) }>(&Some(SCHEMAS));
+        let name = AbiDescriptor::SECTION_NAME.as_bytes();
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        // A type section with no types, followed by the ABI descriptor.
+        module.extend([1, 1, 0]);
+        module.extend([0, (1 + name.len() + section.len()) as u8, name.len() as u8]);
+        module.extend(name);
+        module.extend(section);
+
+        let descriptor = AbiDescriptor::from_wasm(&module).unwrap().unwrap();
+        assert_eq!(descriptor.instantiation_argument, u64::SCHEMA.as_bytes());
+        assert_eq!(AbiDescriptor::from_wasm(&module[..11]), Ok(None));
+        assert!(AbiDescriptor::from_wasm(&module[..module.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_oversized_schemas_accept_anything() {
+        const WIDE: Schema = <[[u64; 200]; 200]>::SCHEMA;
+        assert_eq!(WIDE.as_bytes(), Schema::ANY.as_bytes());
+        assert_eq!(WIDE.hash(), Schema::ANY.hash());
+        check(&WIDE, json!("anything")).unwrap();
+    }
+}
@@ -75,6 +75,13 @@ pub enum CryptoError {
     IncorrectPublicKeySize(usize),
     #[error("Could not parse integer: {0}")]
     ParseIntError(#[from] ParseIntError),
+    #[error(
+        "Byte slice has length {0} but a `Signature` requires exactly {expected} bytes",
+        expected = dalek::SIGNATURE_LENGTH,
+    )]
+    IncorrectSignatureSize(usize),
+    #[error(transparent)]
+    InvalidChecksummedId(#[from] crate::identifiers::IdParseError),
 }
 
 impl PublicKey {
@@ -329,6 +336,38 @@ impl From<CryptoHash> for [u64; 4] {
     }
 }
 
+impl From<[u64; 8]> for Signature {
+    fn from(integers: [u64; 8]) -> Self {
+        let mut bytes = [0u8; dalek::SIGNATURE_LENGTH];
+        for (chunk, integer) in bytes.chunks_exact_mut(8).zip(integers) {
+            chunk.copy_from_slice(&integer.to_le_bytes());
+        }
+        Signature(dalek::Signature::from_bytes(&bytes))
+    }
+}
+
+impl From<Signature> for [u64; 8] {
+    fn from(signature: Signature) -> Self {
+        let bytes = signature.0.to_bytes();
+        let mut integers = [0u64; 8];
+        for (integer, chunk) in integers.iter_mut().zip(bytes.chunks_exact(8)) {
+            *integer = u64::from_le_bytes(chunk.try_into().expect("incorrect indices"));
+        }
+        integers
+    }
+}
+
+impl FromStr for Signature {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        let signature = dalek::Signature::from_slice(&value)
+            .map_err(|_| CryptoError::IncorrectSignatureSize(value.len()))?;
+        Ok(Signature(signature))
+    }
+}
+
 impl fmt::Display for Signature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = hex::encode(self.0.to_bytes());
@@ -432,6 +471,13 @@ impl CryptoHash {
         CryptoHash(hasher.finalize())
     }
 
+    /// Computes the hash of some raw bytes, such as the bytes written by a [`Hashable`] value.
+    pub fn from_raw_bytes(bytes: &[u8]) -> Self {
+        use sha3::digest::Digest;
+
+        CryptoHash(sha3::Sha3_256::digest(bytes))
+    }
+
     /// Reads the bytes of the hash value.
     pub fn as_bytes(&self) -> &HasherOutput {
         &self.0
@@ -456,6 +502,20 @@ impl Signature {
         Signature(signature)
     }
 
+    /// Computes a signature of some raw bytes.
+    pub fn new_for_raw_bytes(message: &[u8], secret: &KeyPair) -> Self {
+        Signature(secret.0.sign(message))
+    }
+
+    /// Returns `true` if this is a valid signature of the raw `message` bytes by `author`.
+    ///
+    /// A malformed public key is treated like any other signer: the check fails.
+    pub fn verify_raw_bytes(&self, message: &[u8], author: PublicKey) -> bool {
+        dalek::VerifyingKey::from_bytes(&author.0)
+            .and_then(|public_key| public_key.verify(message, &self.0))
+            .is_ok()
+    }
+
     fn check_internal<'de, T>(
         &self,
         value: &T,
@@ -685,6 +745,91 @@ impl WitStore for PublicKey {
     }
 }
 
+impl WitType for Signature {
+    const SIZE: u32 = <(u64, u64, u64, u64, u64, u64, u64, u64) as WitType>::SIZE;
+    type Layout = <(u64, u64, u64, u64, u64, u64, u64, u64) as WitType>::Layout;
+    type Dependencies = HList![];
+
+    fn wit_type_name() -> Cow<'static, str> {
+        "signature".into()
+    }
+
+    fn wit_type_declaration() -> Cow<'static, str> {
+        concat!(
+            "    record signature {\n",
+            "        part1: u64,\n",
+            "        part2: u64,\n",
+            "        part3: u64,\n",
+            "        part4: u64,\n",
+            "        part5: u64,\n",
+            "        part6: u64,\n",
+            "        part7: u64,\n",
+            "        part8: u64,\n",
+            "    }\n",
+        )
+        .into()
+    }
+}
+
+impl WitLoad for Signature {
+    fn load<Instance>(
+        memory: &Memory<'_, Instance>,
+        location: GuestPointer,
+    ) -> Result<Self, RuntimeError>
+    where
+        Instance: InstanceWithMemory,
+        <Instance::Runtime as Runtime>::Memory: RuntimeMemory<Instance>,
+    {
+        let (part1, part2, part3, part4, part5, part6, part7, part8) =
+            WitLoad::load(memory, location)?;
+        Ok(Signature::from([
+            part1, part2, part3, part4, part5, part6, part7, part8,
+        ]))
+    }
+
+    fn lift_from<Instance>(
+        flat_layout: <Self::Layout as Layout>::Flat,
+        memory: &Memory<'_, Instance>,
+    ) -> Result<Self, RuntimeError>
+    where
+        Instance: InstanceWithMemory,
+        <Instance::Runtime as Runtime>::Memory: RuntimeMemory<Instance>,
+    {
+        let (part1, part2, part3, part4, part5, part6, part7, part8) =
+            WitLoad::lift_from(flat_layout, memory)?;
+        Ok(Signature::from([
+            part1, part2, part3, part4, part5, part6, part7, part8,
+        ]))
+    }
+}
+
+impl WitStore for Signature {
+    fn store<Instance>(
+        &self,
+        memory: &mut Memory<'_, Instance>,
+        location: GuestPointer,
+    ) -> Result<(), RuntimeError>
+    where
+        Instance: InstanceWithMemory,
+        <Instance::Runtime as Runtime>::Memory: RuntimeMemory<Instance>,
+    {
+        let [part1, part2, part3, part4, part5, part6, part7, part8] = (*self).into();
+        (part1, part2, part3, part4, part5, part6, part7, part8).store(memory, location)
+    }
+
+    fn lower<Instance>(
+        &self,
+        memory: &mut Memory<'_, Instance>,
+    ) -> Result<<Self::Layout as Layout>::Flat, RuntimeError>
+    where
+        Instance: InstanceWithMemory,
+        <Instance::Runtime as Runtime>::Memory: RuntimeMemory<Instance>,
+    {
+        let [part1, part2, part3, part4, part5, part6, part7, part8] = (*self).into();
+        (part1, part2, part3, part4, part5, part6, part7, part8).lower(memory)
+    }
+}
+
 #[cfg(with_testing)]
 impl Arbitrary for CryptoHash {
     type Parameters = ();
@@ -743,6 +888,31 @@ fn test_signatures() {
     assert!(s.check(&foo, addr1).is_err());
 }
 
+#[cfg(with_getrandom)]
+#[test]
+fn test_raw_bytes_signatures() {
+    let key1 = KeyPair::generate();
+    let addr1 = key1.public();
+    let addr2 = KeyPair::generate().public();
+    // Not the encoding of a point of the curve.
+    let mut malformed_key = PublicKey([0; dalek::PUBLIC_KEY_LENGTH]);
+    malformed_key.0[0] = 2;
+
+    let s = Signature::new_for_raw_bytes(b"hello", &key1);
+    assert!(s.verify_raw_bytes(b"hello", addr1));
+    assert!(!s.verify_raw_bytes(b"hello", addr2));
+    assert!(!s.verify_raw_bytes(b"hellox", addr1));
+    assert!(!s.verify_raw_bytes(b"hello", malformed_key));
+
+    let parts = <[u64; 8]>::from(s);
+    assert_eq!(Signature::from(parts), s);
+    assert_eq!(s.to_string().parse::<Signature>().unwrap(), s);
+    assert!(matches!(
+        "abcd".parse::<Signature>(),
+        Err(CryptoError::IncorrectSignatureSize(2))
+    ));
+}
+
 /// Reads the `bytes` as four little-endian unsigned 64-bit integers and returns them.
 fn le_bytes_to_u64_array(bytes: &[u8]) -> [u64; 4] {
     let mut integers = [0u64; 4];
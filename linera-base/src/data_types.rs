@@ -140,7 +140,7 @@ pub struct TimeDelta(u64);
 
 impl TimeDelta {
     /// Returns the given number of microseconds as a [`TimeDelta`].
-    pub fn from_micros(micros: u64) -> Self {
+    pub const fn from_micros(micros: u64) -> Self {
         TimeDelta(micros)
     }
 
@@ -323,6 +323,108 @@ where
     }
 }
 
+/// The limits on the messages an application can send during one execution, i.e. while
+/// executing an operation, a message or an application call, and how much of them is used.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, WitLoad, WitStore, WitType)]
+pub struct MessageLimits {
+    /// The maximum number of messages per execution.
+    pub maximum_messages: u64,
+    /// The maximum size of a message, in bytes.
+    pub maximum_message_size: u64,
+    /// The maximum total size of the messages per execution, in bytes.
+    pub maximum_message_bytes: u64,
+    /// The number of messages sent so far in this execution.
+    pub sent_messages: u64,
+    /// The total size of the messages sent so far in this execution, in bytes.
+    pub sent_message_bytes: u64,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        MessageLimits {
+            maximum_messages: u64::MAX,
+            maximum_message_size: u64::MAX,
+            maximum_message_bytes: u64::MAX,
+            sent_messages: 0,
+            sent_message_bytes: 0,
+        }
+    }
+}
+
+impl MessageLimits {
+    /// Checks that one more message of `size` bytes can be sent.
+    pub fn check(&self, size: u64) -> Result<(), MessageLimitError> {
+        if self.sent_messages >= self.maximum_messages {
+            return Err(MessageLimitError::TooManyMessages {
+                maximum_messages: self.maximum_messages,
+            });
+        }
+        if size > self.maximum_message_size {
+            return Err(MessageLimitError::MessageTooLarge {
+                size,
+                maximum_size: self.maximum_message_size,
+            });
+        }
+        if self.sent_message_bytes.saturating_add(size) > self.maximum_message_bytes {
+            return Err(MessageLimitError::TooManyMessageBytes {
+                maximum_bytes: self.maximum_message_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Records that a message of `size` bytes was sent.
+    pub fn record(&mut self, size: u64) {
+        self.sent_messages = self.sent_messages.saturating_add(1);
+        self.sent_message_bytes = self.sent_message_bytes.saturating_add(size);
+    }
+
+    /// Checks that messages of the given `sizes` can all be sent, one after the other.
+    pub fn check_all(&self, sizes: impl IntoIterator<Item = u64>) -> Result<(), MessageLimitError> {
+        let mut limits = *self;
+        for size in sizes {
+            limits.check(size)?;
+            limits.record(size);
+        }
+        Ok(())
+    }
+}
+
+/// The reasons why an application can't send a message.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum MessageLimitError {
+    /// The application already sent the maximum number of messages.
+    #[error("Applications can send at most {maximum_messages} messages per execution")]
+    TooManyMessages {
+        /// The maximum number of messages per execution.
+        maximum_messages: u64,
+    },
+    /// The message is too large.
+    #[error("Message of {size} bytes exceeds the limit of {maximum_size} bytes")]
+    MessageTooLarge {
+        /// The size of the message.
+        size: u64,
+        /// The maximum size of a message.
+        maximum_size: u64,
+    },
+    /// The message would exceed the total size of the messages allowed per execution.
+    #[error("Messages sent in one execution can have at most {maximum_bytes} bytes in total")]
+    TooManyMessageBytes {
+        /// The maximum total size of the messages per execution.
+        maximum_bytes: u64,
+    },
+}
+
+/// A value read from the state of an application on another chain, as known locally.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, WitLoad, WitStore, WitType)]
+pub struct RemoteValue {
+    /// The height of the last block of the other chain included in the state that was read.
+    pub height: BlockHeight,
+    /// The value stored under the key, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub value: Option<Vec<u8>>,
+}
+
 /// An error type for arithmetic errors.
 #[derive(Debug, Error)]
 #[allow(missing_docs)]
@@ -784,6 +886,13 @@ pub enum OracleResponse {
     Assert,
     /// The block's validation round.
     Round(Option<u32>),
+    /// The value an application read from the state of another chain, or `None` if the state
+    /// was not available. It is replayed when the block is executed again, since that chain
+    /// may have moved on in the meantime.
+    RemoteState(Option<RemoteValue>),
+    /// The description of an application that was unknown to the chain, as found in the state
+    /// of its creator chain.
+    Application(Box<UserApplicationDescription>),
 }
 
 impl Display for OracleResponse {
@@ -797,6 +906,13 @@ impl Display for OracleResponse {
             OracleResponse::Assert => write!(f, "Assert")?,
             OracleResponse::Round(Some(round)) => write!(f, "Round:{round}")?,
             OracleResponse::Round(None) => write!(f, "Round:None")?,
+            OracleResponse::RemoteState(Some(remote_value)) => {
+                write!(f, "RemoteState:{}", remote_value.height)?
+            }
+            OracleResponse::RemoteState(None) => write!(f, "RemoteState:None")?,
+            OracleResponse::Application(description) => {
+                write!(f, "Application:{}", UserApplicationId::from(&**description))?
+            }
         };
 
         Ok(())
@@ -841,6 +957,10 @@ pub struct UserApplicationDescription {
     pub parameters: Vec<u8>,
     /// Required dependencies.
     pub required_application_ids: Vec<UserApplicationId>,
+    /// The maximum size in bytes of the responses to queries of the application, if it
+    /// overrides the network-wide limit. It can only lower that limit.
+    #[debug(skip_if = Option::is_none)]
+    pub max_query_response_bytes: Option<u64>,
 }
 
 impl From<&UserApplicationDescription> for UserApplicationId {
@@ -899,6 +1019,14 @@ pub enum DecompressionError {
     InvalidCompressedBytecode(#[from] io::Error),
 }
 
+/// Errors that can happen when a contract attempts to call another application.
+#[derive(Clone, Copy, Debug, Error, WitStore, WitType)]
+pub enum CallApplicationError {
+    /// The called application is not registered on the current chain.
+    #[error("Application {0} is not registered on this chain")]
+    UnknownApplication(ApplicationId),
+}
+
 /// A compressed WebAssembly module's bytecode.
 #[derive(Clone, Debug, Deserialize, Hash, Serialize, WitType, WitStore)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
@@ -1205,7 +1333,7 @@ static BYTECODE_DECOMPRESSION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(||
 mod tests {
     use std::str::FromStr;
 
-    use super::Amount;
+    use super::{Amount, MessageLimitError, MessageLimits};
 
     #[test]
     fn display_amount() {
@@ -1232,4 +1360,57 @@ mod tests {
             format!("{:~^+9.1}", Amount::from_str("12.34").unwrap())
         );
     }
+
+    #[test]
+    fn message_limits() {
+        let mut limits = MessageLimits {
+            maximum_messages: 2,
+            maximum_message_size: 10,
+            maximum_message_bytes: 15,
+            ..MessageLimits::default()
+        };
+        assert_eq!(
+            limits.check(11),
+            Err(MessageLimitError::MessageTooLarge {
+                size: 11,
+                maximum_size: 10
+            })
+        );
+        assert_eq!(limits.check(10), Ok(()));
+        limits.record(10);
+        assert_eq!(
+            limits.check(6),
+            Err(MessageLimitError::TooManyMessageBytes { maximum_bytes: 15 })
+        );
+        assert_eq!(limits.check(5), Ok(()));
+        limits.record(5);
+        assert_eq!(
+            limits.check(0),
+            Err(MessageLimitError::TooManyMessages {
+                maximum_messages: 2
+            })
+        );
+    }
+
+    #[test]
+    fn message_limits_of_a_batch() {
+        let limits = MessageLimits {
+            maximum_messages: 3,
+            maximum_message_size: 10,
+            maximum_message_bytes: 15,
+            ..MessageLimits::default()
+        };
+        assert_eq!(limits.check_all([5, 5, 5]), Ok(()));
+        assert_eq!(
+            limits.check_all([5, 5, 6]),
+            Err(MessageLimitError::TooManyMessageBytes { maximum_bytes: 15 })
+        );
+        assert_eq!(
+            limits.check_all([1, 1, 1, 1]),
+            Err(MessageLimitError::TooManyMessages {
+                maximum_messages: 3
+            })
+        );
+        assert_eq!(limits.sent_messages, 0);
+    }
 }
@@ -24,12 +24,16 @@ pub enum BcsHexParseError {
     BcsError(#[from] bcs::Error),
     #[error("Invalid hexadecimal: {0}")]
     Hex(#[from] hex::FromHexError),
+    #[error(transparent)]
+    Checksummed(#[from] crate::identifiers::IdParseError),
 }
 
 /// Defines a GraphQL scalar type using the hex-representation of the value's BCS-serialized form.
 ///
 /// This is a modified implementation of [`async_graphql::scalar`].
 /// In addition, it implements `Display` and `FromStr`, also using hex-representation.
+/// The type must implement [`ChecksummedId`](crate::identifiers::ChecksummedId), and
+/// `FromStr` and GraphQL inputs also accept its checksummed text encoding.
 #[macro_export]
 macro_rules! bcs_scalar {
     ($ty:ty, $desc:literal) => {
@@ -37,10 +41,8 @@ macro_rules! bcs_scalar {
             fn parse(
                 value: $crate::async_graphql::Value,
             ) -> $crate::async_graphql::InputValueResult<Self> {
-                let hex: String = $crate::async_graphql::from_value(value)?;
-                let bytes = $crate::hex::decode(&hex)?;
-                let result = $crate::bcs::from_bytes(&bytes)?;
-                ::std::result::Result::Ok(result)
+                let string: String = $crate::async_graphql::from_value(value)?;
+                ::std::result::Result::Ok(<$ty as ::std::str::FromStr>::from_str(&string)?)
             }
 
             fn to_value(&self) -> $crate::async_graphql::Value {
@@ -153,6 +155,11 @@ macro_rules! bcs_scalar {
             type Err = $crate::BcsHexParseError;
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if $crate::identifiers::is_checksummed_id(s) {
+                    return ::std::result::Result::Ok(
+                        <$ty as $crate::identifiers::ChecksummedId>::from_checksummed_str(s)?,
+                    );
+                }
                 let bytes = $crate::hex::decode(s)?;
                 ::std::result::Result::Ok($crate::bcs::from_bytes(&bytes)?)
             }
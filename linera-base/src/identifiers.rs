@@ -20,7 +20,7 @@ use crate::{
     bcs_scalar,
     crypto::{BcsHashable, CryptoError, CryptoHash, PublicKey},
     data_types::BlockHeight,
-    doc_scalar, hex_debug,
+    doc_scalar, ensure, hex_debug,
 };
 
 /// The owner of a chain. This is currently the hash of the owner's public key used to
@@ -119,18 +119,7 @@ impl ChainDescription {
 /// The unique identifier (UID) of a chain. This is currently computed as the hash value
 /// of a [`ChainDescription`].
 #[derive(
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-    Copy,
-    Clone,
-    Hash,
-    Serialize,
-    Deserialize,
-    WitLoad,
-    WitStore,
-    WitType,
+    Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Serialize, WitLoad, WitStore, WitType,
 )]
 #[cfg_attr(with_testing, derive(test_strategy::Arbitrary))]
 #[cfg_attr(with_testing, derive(Default))]
@@ -289,6 +278,11 @@ pub struct MessageId {
 }
 
 /// A unique identifier for a user application.
+///
+/// The type parameter is the ABI of the application, if known, so that calls made with the ID
+/// through `ContractRuntime::call_application` in `linera-sdk` only accept the matching
+/// operation type. It doesn't change how the ID is serialized, and
+/// [`ApplicationId::forget_abi`] returns the raw ID.
 #[derive(Debug, WitLoad, WitStore, WitType)]
 #[cfg_attr(with_testing, derive(Default, test_strategy::Arbitrary))]
 pub struct ApplicationId<A = ()> {
@@ -593,6 +587,11 @@ impl<'de, Abi, Parameters, InstantiationArgument> Deserialize<'de>
     {
         if deserializer.is_human_readable() {
             let s = String::deserialize(deserializer)?;
+            if is_checksummed_id(&s) {
+                let bytecode_id =
+                    BytecodeId::from_checksummed_str(&s).map_err(serde::de::Error::custom)?;
+                return Ok(bytecode_id.with_abi());
+            }
             let bytecode_id_bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
             let serializable_bytecode_id: SerializableBytecodeId =
                 bcs::from_bytes(&bytecode_id_bytes).map_err(serde::de::Error::custom)?;
@@ -750,6 +749,11 @@ impl<'de, A> Deserialize<'de> for ApplicationId<A> {
     {
         if deserializer.is_human_readable() {
             let s = String::deserialize(deserializer)?;
+            if is_checksummed_id(&s) {
+                let application_id =
+                    ApplicationId::from_checksummed_str(&s).map_err(serde::de::Error::custom)?;
+                return Ok(application_id.with_abi());
+            }
             let application_id_bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
             let application_id: SerializableApplicationId =
                 bcs::from_bytes(&application_id_bytes).map_err(serde::de::Error::custom)?;
@@ -944,10 +948,31 @@ impl FromStr for ChainId {
     type Err = CryptoError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if is_checksummed_id(s) {
+            return Ok(ChainId::from_checksummed_str(s)?);
+        }
         Ok(ChainId(CryptoHash::from_str(s)?))
     }
 }
 
+impl<'de> Deserialize<'de> for ChainId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            ChainId::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "ChainId")]
+            struct SerializableChainId(CryptoHash);
+
+            Ok(ChainId(SerializableChainId::deserialize(deserializer)?.0))
+        }
+    }
+}
+
 impl TryFrom<&[u8]> for ChainId {
     type Error = CryptoError;
 
@@ -982,6 +1007,217 @@ impl ChainId {
 
 impl<'de> BcsHashable<'de> for ChainDescription {}
 
+/// The version of the checksummed text encoding of identifiers.
+const CHECKSUMMED_ID_VERSION: &str = "v1";
+
+/// The number of hexadecimal digits of the checksum of a checksummed identifier.
+const CHECKSUM_DIGITS: usize = 8;
+
+/// An identifier with a checksummed text encoding, to detect identifiers that were mistyped
+/// or truncated when copied between tools.
+///
+/// The encoding is `<prefix>_v1_<hex>_<checksum>`, where `<hex>` is the BCS serialization of
+/// the identifier in lowercase hexadecimal, and `<checksum>` the first four bytes of the
+/// SHA3-256 hash of everything before it. The plain hexadecimal form returned by `Display`
+/// is unchanged, and `FromStr` accepts both forms.
+pub trait ChecksummedId: Serialize + for<'de> Deserialize<'de> {
+    /// The prefix naming the kind of identifier.
+    const PREFIX: &'static str;
+
+    /// The length of the BCS serialization of the identifier, in bytes.
+    const BCS_LENGTH: usize;
+
+    /// Returns the checksummed text encoding of the identifier.
+    fn to_checksummed_string(&self) -> String {
+        let bytes = bcs::to_bytes(self).expect("Identifiers are serializable");
+        let text = format!(
+            "{}_{CHECKSUMMED_ID_VERSION}_{}",
+            Self::PREFIX,
+            hex::encode(bytes)
+        );
+        let checksum = checksum(&text);
+        format!("{text}_{checksum}")
+    }
+
+    /// Parses the checksummed text encoding of an identifier.
+    fn from_checksummed_str(s: &str) -> Result<Self, IdParseError> {
+        let prefix = s.split('_').next().unwrap_or_default();
+        ensure!(
+            prefix == Self::PREFIX,
+            IdParseError::WrongPrefix {
+                expected: Self::PREFIX,
+                found: prefix.to_owned(),
+            }
+        );
+        let mut parts = s[prefix.len()..].splitn(4, '_').skip(1);
+        let version = parts.next().unwrap_or_default();
+        ensure!(
+            version == CHECKSUMMED_ID_VERSION,
+            IdParseError::UnsupportedVersion(version.to_owned())
+        );
+        let digits_start = prefix.len() + version.len() + 2;
+        if let Some((index, character)) = s
+            .get(digits_start..)
+            .unwrap_or_default()
+            .char_indices()
+            .find(|(_, character)| !matches!(character, '0'..='9' | 'a'..='f' | '_'))
+        {
+            return Err(IdParseError::InvalidCharacter {
+                character,
+                index: digits_start + index,
+            });
+        }
+        let (Some(digits), Some(found_checksum)) = (parts.next(), parts.next()) else {
+            return Err(IdParseError::MissingChecksum);
+        };
+        ensure!(
+            digits.len() == 2 * Self::BCS_LENGTH,
+            IdParseError::InvalidLength {
+                expected: 2 * Self::BCS_LENGTH,
+                found: digits.len(),
+            }
+        );
+        ensure!(
+            found_checksum.len() == CHECKSUM_DIGITS,
+            IdParseError::InvalidLength {
+                expected: CHECKSUM_DIGITS,
+                found: found_checksum.len(),
+            }
+        );
+        let expected_checksum = checksum(&s[..s.len() - CHECKSUM_DIGITS - 1]);
+        ensure!(
+            found_checksum == expected_checksum,
+            IdParseError::ChecksumMismatch {
+                expected: expected_checksum,
+                found: found_checksum.to_owned(),
+            }
+        );
+        let bytes = hex::decode(digits).expect("The digits were checked to be hexadecimal");
+        Ok(bcs::from_bytes(&bytes)?)
+    }
+}
+
+/// Returns the checksum of the beginning of a checksummed identifier.
+fn checksum(text: &str) -> String {
+    use sha3::digest::Digest;
+
+    let hash = sha3::Sha3_256::digest(text.as_bytes());
+    hex::encode(&hash[..CHECKSUM_DIGITS / 2])
+}
+
+/// Returns whether the string uses the checksummed text encoding of identifiers, rather
+/// than plain hexadecimal.
+pub fn is_checksummed_id(s: &str) -> bool {
+    s.contains('_')
+}
+
+/// An error parsing the checksummed text encoding of an identifier.
+#[derive(Debug, thiserror::Error)]
+pub enum IdParseError {
+    /// The prefix names another kind of identifier.
+    #[error("expected an identifier starting with `{expected}_`, found `{found}_`")]
+    WrongPrefix {
+        /// The prefix of this kind of identifier.
+        expected: &'static str,
+        /// The prefix that was found.
+        found: String,
+    },
+    /// The version of the encoding is not supported.
+    #[error("unsupported identifier encoding version `{0}`")]
+    UnsupportedVersion(String),
+    /// A character is not a lowercase hexadecimal digit.
+    #[error("invalid character {character:?} at position {index}")]
+    InvalidCharacter {
+        /// The invalid character.
+        character: char,
+        /// The position of the invalid character in the string, in bytes.
+        index: usize,
+    },
+    /// The identifier has no checksum, or too many parts.
+    #[error("expected the identifier to end with `_` and a checksum")]
+    MissingChecksum,
+    /// The identifier or its checksum has the wrong number of digits.
+    #[error("expected {expected} hexadecimal digits, found {found}")]
+    InvalidLength {
+        /// The expected number of digits.
+        expected: usize,
+        /// The number of digits that were found.
+        found: usize,
+    },
+    /// The checksum doesn't match the identifier, which was probably mistyped.
+    #[error("checksum mismatch: expected `{expected}`, found `{found}`")]
+    ChecksumMismatch {
+        /// The checksum of the identifier.
+        expected: String,
+        /// The checksum that was found.
+        found: String,
+    },
+    /// The digits don't encode a valid identifier.
+    #[error(transparent)]
+    Bcs(#[from] bcs::Error),
+}
+
+impl ChecksummedId for ChainId {
+    const PREFIX: &'static str = "chain";
+    const BCS_LENGTH: usize = 32;
+}
+
+impl ChecksummedId for MessageId {
+    const PREFIX: &'static str = "msg";
+    const BCS_LENGTH: usize = 32 + 8 + 4;
+}
+
+impl ChecksummedId for BytecodeId {
+    const PREFIX: &'static str = "bytecode";
+    const BCS_LENGTH: usize = 32 + 32;
+}
+
+impl ChecksummedId for ApplicationId {
+    const PREFIX: &'static str = "app";
+    const BCS_LENGTH: usize =
+        <BytecodeId as ChecksummedId>::BCS_LENGTH + <MessageId as ChecksummedId>::BCS_LENGTH;
+}
+
+/// Serializes and deserializes identifiers with their checksummed text encoding in
+/// human-readable formats, for use with `#[serde(with = "checksummed")]`.
+///
+/// Deserializing also accepts the plain hexadecimal form. Other formats are unaffected.
+pub mod checksummed {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::ChecksummedId;
+
+    /// Serializes an identifier.
+    pub fn serialize<T, S>(id: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ChecksummedId,
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&id.to_checksummed_string())
+        } else {
+            id.serialize(serializer)
+        }
+    }
+
+    /// Deserializes an identifier.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: ChecksummedId + FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            T::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            T::deserialize(deserializer)
+        }
+    }
+}
+
 bcs_scalar!(ApplicationId, "A unique identifier for a user application");
 doc_scalar!(
     GenericApplicationId,
@@ -1018,7 +1254,170 @@ doc_scalar!(
 
 #[cfg(test)]
 mod tests {
-    use super::ChainId;
+    use std::str::FromStr;
+
+    use super::{
+        checksummed, ApplicationId, BytecodeId, ChainId, ChecksummedId, IdParseError, MessageId,
+    };
+    use crate::{crypto::CryptoHash, data_types::BlockHeight};
+
+    fn test_message_id() -> MessageId {
+        MessageId {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(3),
+            index: 4,
+        }
+    }
+
+    fn test_application_id() -> ApplicationId {
+        ApplicationId {
+            bytecode_id: BytecodeId::new(CryptoHash::from([1; 4]), CryptoHash::from([2; 4])),
+            creation: test_message_id(),
+        }
+    }
+
+    /// Checks that an identifier round-trips through both text forms, and that every
+    /// truncation and every single-character change of the checksummed form is rejected.
+    fn check_text_forms<T>(id: T)
+    where
+        T: ChecksummedId + FromStr + ToString + PartialEq + std::fmt::Debug,
+        T::Err: std::fmt::Debug,
+    {
+        let checksummed = id.to_checksummed_string();
+        assert_eq!(T::from_checksummed_str(&checksummed).unwrap(), id);
+        assert_eq!(checksummed.parse::<T>().unwrap(), id);
+        assert_eq!(id.to_string().parse::<T>().unwrap(), id);
+
+        for length in 0..checksummed.len() {
+            assert!(T::from_checksummed_str(&checksummed[..length]).is_err());
+        }
+        let digits_start = T::PREFIX.len() + 4;
+        for index in digits_start..checksummed.len() {
+            let original = checksummed.as_bytes()[index];
+            if original == b'_' {
+                continue;
+            }
+            let replacement = if original == b'0' { '1' } else { '0' };
+            let mut changed = checksummed.clone();
+            changed.replace_range(index..=index, &replacement.to_string());
+            assert!(matches!(
+                T::from_checksummed_str(&changed),
+                Err(IdParseError::ChecksumMismatch { .. })
+            ));
+            for invalid in ['g', 'A', ' ', 'é'] {
+                let mut changed = checksummed.clone();
+                changed.replace_range(index..=index, &invalid.to_string());
+                assert!(matches!(
+                    T::from_checksummed_str(&changed),
+                    Err(IdParseError::InvalidCharacter { character, index: found })
+                        if character == invalid && found == index
+                ));
+            }
+        }
+    }
+
+    /// Verifies that the checksummed text encoding is stable.
+    #[test]
+    fn checksummed_chain_id() {
+        assert_eq!(
+            ChainId::root(0).to_checksummed_string(),
+            "chain_v1_e476187f6ddfeb9d588c7b45d3df334d5501d6499b3f9ad5595cae86cce16a65_2ef76f27"
+        );
+    }
+
+    #[test]
+    fn checksummed_id_round_trips() {
+        check_text_forms(ChainId::root(9));
+        check_text_forms(test_message_id());
+        check_text_forms(test_application_id().bytecode_id);
+        check_text_forms(test_application_id());
+    }
+
+    #[test]
+    fn checksummed_id_errors() {
+        let chain_id = ChainId::root(0).to_checksummed_string();
+        let application_id = test_application_id().to_checksummed_string();
+
+        assert!(matches!(
+            ChainId::from_checksummed_str(&application_id),
+            Err(IdParseError::WrongPrefix { expected: "chain", found }) if found == "app"
+        ));
+        assert!(matches!(
+            ChainId::from_checksummed_str(&chain_id.replace("_v1_", "_v2_")),
+            Err(IdParseError::UnsupportedVersion(version)) if version == "v2"
+        ));
+        assert!(matches!(
+            ChainId::from_checksummed_str(&chain_id[..chain_id.len() - 9]),
+            Err(IdParseError::MissingChecksum)
+        ));
+        assert!(matches!(
+            ChainId::from_checksummed_str(&chain_id.replacen("_v1_", "_v1_00", 1)),
+            Err(IdParseError::InvalidLength {
+                expected: 64,
+                found: 66
+            })
+        ));
+        assert!(matches!(
+            ChainId::from_checksummed_str(&format!("{chain_id}0")),
+            Err(IdParseError::InvalidLength {
+                expected: 8,
+                found: 9
+            })
+        ));
+        assert!(matches!(
+            ChainId::from_checksummed_str(&format!("{chain_id}_0")),
+            Err(IdParseError::InvalidLength {
+                expected: 8,
+                found: 10
+            })
+        ));
+        assert!("chain_".parse::<ChainId>().is_err());
+        assert!("".parse::<ChainId>().is_err());
+        assert!("_".parse::<MessageId>().is_err());
+    }
+
+    /// Verifies the serde helper, and that the default serialization is unchanged while
+    /// both forms are accepted.
+    #[test]
+    fn checksummed_id_serde() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Ids {
+            #[serde(with = "checksummed")]
+            chain_id: ChainId,
+            #[serde(with = "checksummed")]
+            application_id: ApplicationId,
+        }
+
+        let chain_id = ChainId::root(0);
+        let application_id = test_application_id();
+        let ids = Ids {
+            chain_id,
+            application_id,
+        };
+        let json = serde_json::to_string(&ids).unwrap();
+        assert!(json.contains(&chain_id.to_checksummed_string()));
+        assert!(json.contains(&application_id.to_checksummed_string()));
+        let ids = serde_json::from_str::<Ids>(&json).unwrap();
+        assert_eq!(ids.chain_id, chain_id);
+        assert_eq!(ids.application_id, application_id);
+        let bytes = bcs::to_bytes(&ids).unwrap();
+        assert_eq!(bcs::from_bytes::<Ids>(&bytes).unwrap().chain_id, chain_id);
+
+        assert_eq!(
+            serde_json::to_string(&chain_id).unwrap(),
+            format!("\"{chain_id}\"")
+        );
+        let checksummed_json = format!("\"{}\"", chain_id.to_checksummed_string());
+        assert_eq!(
+            serde_json::from_str::<ChainId>(&checksummed_json).unwrap(),
+            chain_id
+        );
+        let checksummed_json = format!("\"{}\"", application_id.to_checksummed_string());
+        assert_eq!(
+            serde_json::from_str::<ApplicationId>(&checksummed_json).unwrap(),
+            application_id
+        );
+    }
 
     /// Verifies that chain IDs that are explicitly used in some example and test scripts don't
     /// change.
@@ -1037,4 +1436,31 @@ mod tests {
             "9c8a838e8f7b63194f6c7585455667a8379d2b5db19a3300e9961f0b1e9091ea"
         );
     }
+
+    /// Verifies that specializing an application ID for an ABI doesn't change how it is
+    /// serialized, so that nodes see the same raw ID.
+    #[test]
+    fn typed_application_id_serialization() {
+        struct TestAbi;
+
+        let application_id = ApplicationId {
+            bytecode_id: BytecodeId::new(CryptoHash::from([1; 4]), CryptoHash::from([2; 4])),
+            creation: MessageId {
+                chain_id: ChainId::root(0),
+                height: BlockHeight(3),
+                index: 4,
+            },
+        };
+        let typed_id = application_id.with_abi::<TestAbi>();
+
+        let bytes = bcs::to_bytes(&application_id).unwrap();
+        assert_eq!(bcs::to_bytes(&typed_id).unwrap(), bytes);
+        let json = serde_json::to_string(&application_id).unwrap();
+        assert_eq!(serde_json::to_string(&typed_id).unwrap(), json);
+
+        let typed_id = bcs::from_bytes::<ApplicationId<TestAbi>>(&bytes).unwrap();
+        assert_eq!(typed_id.forget_abi(), application_id);
+        let typed_id = serde_json::from_str::<ApplicationId<TestAbi>>(&json).unwrap();
+        assert_eq!(typed_id.forget_abi(), application_id);
+    }
 }
@@ -14,6 +14,7 @@ use std::fmt;
 pub use async_trait::async_trait;
 
 pub mod abi;
+pub mod abi_descriptor;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod command;
 pub mod crypto;
@@ -65,6 +66,30 @@ macro_rules! ensure {
     };
 }
 
+/// A macro for returning an error, converted into the error type of the function.
+///
+/// # Examples
+///
+/// ```
+/// # use linera_base::bail;
+/// fn parse_flag(value: &str) -> Result<bool, String> {
+///     match value {
+///         "yes" => Ok(true),
+///         "no" => Ok(false),
+///         _ => bail!("invalid flag"),
+///     }
+/// }
+///
+/// assert_eq!(parse_flag("yes"), Ok(true));
+/// assert_eq!(parse_flag("maybe"), Err(String::from("invalid flag")));
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($e:expr) => {
+        return Err($e.into())
+    };
+}
+
 /// Formats a byte sequence as a hexadecimal string, and elides bytes in the middle if it is longer
 /// than 32 bytes.
 ///
@@ -3,9 +3,11 @@
 
 //! This module defines util functions for interacting with Prometheus (logging metrics, etc)
 
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use prometheus::{
     exponential_buckets, histogram_opts, register_histogram_vec, register_int_counter_vec,
-    HistogramVec, IntCounterVec, Opts,
+    register_int_gauge_vec, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
 };
 
 use crate::time::Instant;
@@ -22,6 +24,12 @@ pub fn register_int_counter_vec(
     register_int_counter_vec!(counter_opts, label_names).expect("IntCounter can be created")
 }
 
+/// Wrapper around prometheus register_int_gauge_vec! macro which also sets the linera namespace
+pub fn register_int_gauge_vec(name: &str, description: &str, label_names: &[&str]) -> IntGaugeVec {
+    let gauge_opts = Opts::new(name, description).namespace(LINERA_NAMESPACE);
+    register_int_gauge_vec!(gauge_opts, label_names).expect("IntGauge can be created")
+}
+
 /// Wrapper around prometheus register_histogram_vec! macro which also sets the linera namespace
 pub fn register_histogram_vec(
     name: &str,
@@ -38,6 +46,16 @@ pub fn register_histogram_vec(
     register_histogram_vec!(histogram_opts, label_names).expect("Histogram can be created")
 }
 
+/// Returns a label value for `value` that takes at most `num_buckets` different values.
+///
+/// Use this for labels like chain IDs that would otherwise create one time series per
+/// chain: all values that hash to the same bucket are aggregated into one series.
+pub fn hash_bucket_label(value: &impl Hash, num_buckets: u64) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    (hasher.finish() % num_buckets.max(1)).to_string()
+}
+
 /// Construct the bucket interval starting from a value and an ending value.
 pub fn bucket_interval(start_value: f64, end_value: f64) -> Option<Vec<f64>> {
     let quot = end_value / start_value;
@@ -10,9 +10,9 @@ use std::{
 use async_graphql::SimpleObject;
 use linera_base::{
     crypto::{BcsHashable, CryptoHash},
-    data_types::{BlockHeight, OracleResponse, Timestamp},
+    data_types::{BlockHeight, OracleResponse, Timestamp, UserApplicationDescription},
     hashed::Hashed,
-    identifiers::{BlobId, BlobType, ChainId, MessageId, Owner},
+    identifiers::{BlobId, BlobType, ChainId, MessageId, Owner, UserApplicationId},
 };
 use linera_execution::{committee::Epoch, Operation, SystemOperation};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
@@ -21,7 +21,7 @@ use thiserror::Error;
 use crate::{
     data_types::{
         BlockExecutionOutcome, EventRecord, ExecutedBlock, IncomingBundle, Medium, MessageBundle,
-        OutgoingMessage, ProposedBlock,
+        OperationOutcome, OutgoingMessage, ProposedBlock,
     },
     types::CertificateValue,
     ChainError,
@@ -166,6 +166,7 @@ impl ConfirmedBlock {
             height,
             timestamp,
             authenticated_signer,
+            fee_payer,
             previous_block_hash,
         } = block;
         *chain_id == self.chain_id()
@@ -175,6 +176,7 @@ impl ConfirmedBlock {
             && *height == self.block().header.height
             && *timestamp == self.block().header.timestamp
             && *authenticated_signer == self.block().header.authenticated_signer
+            && *fee_payer == self.block().header.fee_payer
             && *previous_block_hash == self.block().header.previous_block_hash
     }
 }
@@ -256,6 +258,7 @@ impl Serialize for Block {
             state_hash: self.header.state_hash,
             previous_block_hash: self.header.previous_block_hash,
             authenticated_signer: self.header.authenticated_signer,
+            fee_payer: self.header.fee_payer,
         };
         state.serialize_field("header", &header)?;
         state.serialize_field("body", &self.body)?;
@@ -287,6 +290,7 @@ impl<'de> Deserialize<'de> for Block {
             state_hash: inner.header.state_hash,
             previous_block_hash: inner.header.previous_block_hash,
             authenticated_signer: inner.header.authenticated_signer,
+            fee_payer: inner.header.fee_payer,
             bundles_hash,
             operations_hash,
             messages_hash,
@@ -323,6 +327,9 @@ pub struct BlockHeader {
     /// the default account of the chain is used. This value is also used as recipient of
     /// potential refunds for the message grants created by the operations.
     pub authenticated_signer: Option<Owner>,
+    /// The chain sponsoring the execution fees of the block, instead of the chain and the
+    /// authenticated signer.
+    pub fee_payer: Option<ChainId>,
 
     // Inputs to the block, chosen by the block proposer.
     /// Cryptographic hash of all the incoming bundles in the block.
@@ -371,6 +378,7 @@ impl Block {
             state_hash: outcome.state_hash,
             previous_block_hash: block.previous_block_hash,
             authenticated_signer: block.authenticated_signer,
+            fee_payer: block.fee_payer,
             bundles_hash,
             operations_hash,
             messages_hash,
@@ -480,6 +488,87 @@ impl Block {
         None
     }
 
+    /// Returns what each operation of this block produced, in the order of the operations.
+    pub fn operation_outcomes(&self) -> Vec<OperationOutcome> {
+        let transactions = self
+            .body
+            .messages
+            .iter()
+            .zip(&self.body.events)
+            .zip(&self.body.oracle_responses);
+        let mut first_message_index = 0u32;
+        let mut outcomes = Vec::with_capacity(self.body.operations.len());
+        for (transaction_index, ((messages, events), oracle_responses)) in transactions.enumerate()
+        {
+            let message_indices = first_message_index..;
+            first_message_index += messages.len() as u32;
+            if transaction_index < self.body.incoming_bundles.len() {
+                continue;
+            }
+            outcomes.push(OperationOutcome {
+                messages: message_indices
+                    .zip(messages)
+                    .map(|(index, message)| (self.message_id(index), message.clone()))
+                    .collect(),
+                events: events.clone(),
+                oracle_responses: oracle_responses.clone(),
+            });
+        }
+        outcomes
+    }
+
+    /// Returns the `index`th outgoing message in this block, together with the index of the
+    /// transaction that sent it, or `None` if there is no such message.
+    pub fn message_and_transaction_index(&self, index: u32) -> Option<(u32, &OutgoingMessage)> {
+        let mut index = usize::try_from(index).ok()?;
+        for (transaction_index, messages) in (0u32..).zip(self.messages()) {
+            if let Some(message) = messages.get(index) {
+                return Some((transaction_index, message));
+            }
+            index -= messages.len();
+        }
+        None
+    }
+
+    /// Returns the description of the application with the given ID, if it was created in this
+    /// block.
+    pub fn created_application(
+        &self,
+        id: &UserApplicationId,
+    ) -> Option<UserApplicationDescription> {
+        let creation = id.creation;
+        if self.header.chain_id != creation.chain_id || self.header.height != creation.height {
+            return None;
+        }
+        let (transaction_index, _) = self.message_and_transaction_index(creation.index)?;
+        let operation_index = usize::try_from(transaction_index)
+            .ok()?
+            .checked_sub(self.body.incoming_bundles.len())?;
+        let Operation::System(SystemOperation::CreateApplication {
+            bytecode_id,
+            parameters,
+            required_application_ids,
+            max_query_response_bytes,
+            ..
+        }) = self.body.operations.get(operation_index)?
+        else {
+            return None;
+        };
+        // The application is identified by the first message of the operation that created it.
+        if *bytecode_id != id.bytecode_id
+            || self.message_id_for_operation(operation_index, 0) != Some(creation)
+        {
+            return None;
+        }
+        Some(UserApplicationDescription {
+            bytecode_id: *bytecode_id,
+            creation,
+            parameters: parameters.clone(),
+            required_application_ids: required_application_ids.clone(),
+            max_query_response_bytes: *max_query_response_bytes,
+        })
+    }
+
     /// Returns all the blob IDs required by this block.
     /// Either as oracle responses or as published blobs.
     pub fn required_blob_ids(&self) -> HashSet<BlobId> {
@@ -543,6 +632,7 @@ impl From<Block> for ExecutedBlock {
                     state_hash,
                     previous_block_hash,
                     authenticated_signer,
+                    fee_payer,
                     bundles_hash: _,
                     operations_hash: _,
                     messages_hash: _,
@@ -567,6 +657,7 @@ impl From<Block> for ExecutedBlock {
             incoming_bundles,
             operations,
             authenticated_signer,
+            fee_payer,
             previous_block_hash,
         };
 
@@ -593,6 +684,7 @@ struct SerializedHeader {
     state_hash: CryptoHash,
     previous_block_hash: Option<CryptoHash>,
     authenticated_signer: Option<Owner>,
+    fee_payer: Option<ChainId>,
 }
 
 mod hashing {
@@ -4,11 +4,11 @@
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     sync::Arc,
 };
 
-use async_graphql::SimpleObject;
+use async_graphql::{Enum, SimpleObject};
 use futures::stream::{self, StreamExt, TryStreamExt};
 use linera_base::{
     crypto::CryptoHash,
@@ -21,16 +21,18 @@ use linera_base::{
         UserApplicationId,
     },
     ownership::ChainOwnership,
+    time::Duration,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
-    system::OpenChainConfig,
-    ExecutionOutcome, ExecutionRuntimeContext, ExecutionStateView, Message, MessageContext,
-    Operation, OperationContext, Query, QueryContext, QueryOutcome, RawExecutionOutcome,
-    RawOutgoingMessage, ResourceController, ResourceTracker, ServiceRuntimeEndpoint,
-    TransactionTracker,
+    system::{OpenChainConfig, SystemOperation},
+    ExecutionOutcome, ExecutionRuntimeContext, ExecutionStateView, FeeAccount, HeapUsage, Message,
+    MessageContext, Operation, OperationContext, Query, QueryContext, QueryOutcome,
+    RawExecutionOutcome, RawOutgoingMessage, ResourceController, ResourceTracker,
+    ServiceRuntimeEndpoint, TransactionTracker,
 };
 use linera_views::{
+    collection_view::CollectionView,
     context::Context,
     log_view::LogView,
     queue_view::QueueView,
@@ -43,11 +45,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     data_types::{
-        BlockExecutionOutcome, ChainAndHeight, ChannelFullName, EventRecord, IncomingBundle,
-        MessageAction, MessageBundle, Origin, OutgoingMessage, PostedMessage, ProposedBlock,
-        Target, Transaction,
+        BlockExecutionOutcome, BlockResourceUsage, ChainAndHeight, ChannelFullName, EventRecord,
+        IncomingBundle, MessageAction, MessageBundle, Origin, OutgoingMessage, PendingMessage,
+        PostedMessage, ProposedBlock, ResourceEstimate, Target, Transaction,
     },
-    inbox::{Cursor, InboxError, InboxStateView},
+    inbox::{Cursor, InboxBundleState, InboxError, InboxStateView},
     manager::ChainManager,
     outbox::OutboxStateView,
     pending_blobs::PendingBlobsView,
@@ -228,6 +230,37 @@ where
     pub outbox_counters: RegisterView<C, BTreeMap<BlockHeight, u32>>,
     /// Channels able to multicast messages to subscribers.
     pub channels: ReentrantCollectionView<C, ChannelFullName, ChannelStateView<C>>,
+    /// Heights of the certified blocks with operations of each user application.
+    #[graphql(skip)]
+    pub application_block_heights: CollectionView<C, UserApplicationId, LogView<C, BlockHeight>>,
+    /// Heights of the retained snapshots of this chain's state, oldest first.
+    #[graphql(skip)]
+    pub state_snapshot_heights: RegisterView<C, Vec<BlockHeight>>,
+    /// Counters of the blocks executed on this chain by this node.
+    pub execution_statistics: RegisterView<C, ChainStatistics>,
+    /// The incoming messages that were rejected because an owner asked to with
+    /// `RejectMessage`, although their bundle was accepted.
+    #[graphql(skip)]
+    pub messages_rejected_by_owner: SetView<C, MessageId>,
+}
+
+/// The order in which the blocks of a chain are listed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Enum)]
+pub enum BlockHistoryDirection {
+    /// From the newest to the oldest block.
+    #[default]
+    Backward,
+    /// From the oldest to the newest block.
+    Forward,
+}
+
+/// A page of the certified blocks of a chain.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockHistoryPage {
+    /// The heights and hashes of the blocks, in the requested order.
+    pub blocks: Vec<(BlockHeight, CryptoHash)>,
+    /// The height to start the next page from, if there are more blocks.
+    pub next_height: Option<BlockHeight>,
 }
 
 /// Block-chaining state.
@@ -308,6 +341,59 @@ impl ChainTipState {
     }
 }
 
+/// Counters of the blocks executed on a chain, kept by each node for monitoring.
+///
+/// These are not part of any state hash. The execution time in particular depends on the
+/// hardware of the node.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct ChainStatistics {
+    /// Number of blocks executed.
+    pub blocks: u64,
+    /// Number of operations executed.
+    pub operations: u64,
+    /// Number of incoming messages received.
+    pub incoming_messages: u64,
+    /// Number of outgoing messages sent.
+    pub outgoing_messages: u64,
+    /// Fuel consumed by the applications.
+    pub fuel: u64,
+    /// Number of bytes read from storage by the applications.
+    pub bytes_read: u64,
+    /// Number of bytes written to storage by the applications.
+    pub bytes_written: u64,
+    /// Time spent executing the blocks, in microseconds.
+    pub execution_micros: u64,
+}
+
+impl ChainStatistics {
+    /// Adds an executed block to the counters.
+    pub fn record_block(
+        &mut self,
+        block: &ProposedBlock,
+        outcome: &BlockExecutionOutcome,
+        resources: ResourceEstimate,
+        execution_time: Duration,
+    ) {
+        let incoming_messages = block
+            .incoming_bundles
+            .iter()
+            .map(|bundle| bundle.bundle.messages.len() as u64)
+            .sum::<u64>();
+        let outgoing_messages = outcome.messages.iter().map(Vec::len).sum::<usize>() as u64;
+        let execution_micros = u64::try_from(execution_time.as_micros()).unwrap_or(u64::MAX);
+        self.blocks = self.blocks.saturating_add(1);
+        self.operations = self
+            .operations
+            .saturating_add(block.operations.len() as u64);
+        self.incoming_messages = self.incoming_messages.saturating_add(incoming_messages);
+        self.outgoing_messages = self.outgoing_messages.saturating_add(outgoing_messages);
+        self.fuel = self.fuel.saturating_add(resources.fuel);
+        self.bytes_read = self.bytes_read.saturating_add(resources.bytes_read);
+        self.bytes_written = self.bytes_written.saturating_add(resources.bytes_written);
+        self.execution_micros = self.execution_micros.saturating_add(execution_micros);
+    }
+}
+
 /// The state of a channel followed by subscribers.
 #[derive(Debug, ClonableView, View, SimpleObject)]
 pub struct ChannelStateView<C>
@@ -340,6 +426,10 @@ where
             chain_id: self.chain_id(),
             next_block_height: self.tip_state.get().next_block_height,
             local_time,
+            block_hash: self.tip_state.get().block_hash,
+            block_timestamp: *self.execution_state.system.timestamp.get(),
+            maximum_response_size: self.execution_state.system.maximum_query_response_size(),
+            authenticated_signer: None,
         };
         self.execution_state
             .query_application(context, query, service_runtime_endpoint)
@@ -445,6 +535,50 @@ where
         Ok(())
     }
 
+    /// Verifies that the chain has no bundles left in its inbox in which it scheduled messages
+    /// to itself, unless the block already receives as many bundles as allowed, or the chain is
+    /// closed.
+    ///
+    /// This is checked when validating proposals, after the bundles of the `block` were removed
+    /// from the inboxes, so that block proposers can't delay a schedule.
+    pub async fn validate_schedule_message_bundles(
+        &self,
+        block: &ProposedBlock,
+    ) -> Result<(), ChainError> {
+        let chain_id = self.chain_id();
+        let origin = Origin::chain(chain_id);
+        let policy = self.current_committee()?.1.policy();
+        let from_origin = block
+            .incoming_bundles
+            .iter()
+            .filter(|incoming_bundle| incoming_bundle.origin == origin)
+            .count();
+        if self.is_closed()
+            || u64::try_from(block.incoming_bundles.len()).unwrap_or(u64::MAX)
+                >= policy.maximum_incoming_bundles_per_block
+            || u64::try_from(from_origin).unwrap_or(u64::MAX)
+                >= policy.maximum_incoming_bundles_per_origin
+        {
+            return Ok(());
+        }
+        let Some(inbox) = self.inboxes.try_load_entry(&origin).await? else {
+            return Ok(());
+        };
+        for bundle in inbox.added_bundles.elements().await? {
+            if bundle
+                .messages
+                .iter()
+                .any(|posted_message| posted_message.message.matches_schedule_message().is_some())
+            {
+                return Err(ChainError::MissingScheduleMessageBundle {
+                    chain_id,
+                    height: bundle.height,
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub async fn next_block_height_to_receive(
         &self,
         origin: &Origin,
@@ -456,6 +590,231 @@ where
         }
     }
 
+    /// Returns the state, in the inbox for `origin`, of the bundle of messages sent by the
+    /// transaction with index `transaction_index` of the block at `height`.
+    pub async fn inbox_bundle_state(
+        &self,
+        origin: &Origin,
+        height: BlockHeight,
+        transaction_index: u32,
+    ) -> Result<InboxBundleState, ChainError> {
+        let inbox = self.inboxes.try_load_entry(origin).await?;
+        match inbox {
+            Some(inbox) => Ok(inbox.bundle_state(height, transaction_index)),
+            None => Ok(InboxBundleState::NotReceived),
+        }
+    }
+
+    /// Returns the height of the block of this chain that removed the bundle sent by the
+    /// transaction with index `transaction_index` of the block at `height` from the inbox for
+    /// `origin`, if it is known.
+    pub async fn bundle_removal_height(
+        &self,
+        origin: &Origin,
+        height: BlockHeight,
+        transaction_index: u32,
+    ) -> Result<Option<BlockHeight>, ChainError> {
+        let inbox = self.inboxes.try_load_entry(origin).await?;
+        match inbox {
+            Some(inbox) => Ok(inbox.removal_height(height, transaction_index).await?),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of bundles from `origin` that were received but not executed yet.
+    pub async fn inbox_backlog(&self, origin: &Origin) -> Result<usize, ChainError> {
+        let inbox = self.inboxes.try_load_entry(origin).await?;
+        Ok(inbox.map_or(0, |inbox| inbox.added_bundles.count()))
+    }
+
+    /// Returns the number of bundles that were received but not executed yet, for each origin
+    /// with a non-empty inbox.
+    pub async fn inbox_depths(&self) -> Result<Vec<(Origin, usize)>, ChainError> {
+        let pairs = self.inboxes.try_load_all_entries().await?;
+        Ok(pairs
+            .into_iter()
+            .map(|(origin, inbox)| (origin, inbox.added_bundles.count()))
+            .filter(|(_, depth)| *depth > 0)
+            .collect())
+    }
+
+    /// Returns the messages that were received but not executed yet, in the order of their
+    /// inboxes and of their arrival.
+    pub async fn pending_messages(&self) -> Result<Vec<PendingMessage>, ChainError> {
+        let pairs = self.inboxes.try_load_all_entries().await?;
+        let mut messages = Vec::new();
+        for (origin, inbox) in pairs {
+            for bundle in inbox.added_bundles.elements().await? {
+                messages.extend(PendingMessage::from_bundle(&origin, &bundle));
+            }
+        }
+        let rejected_messages = &self.execution_state.system.rejected_messages;
+        for message in &mut messages {
+            message.rejected = rejected_messages.contains(&message.message_id()).await?;
+        }
+        Ok(messages)
+    }
+
+    /// Returns whether the message with the given ID is in one of the inboxes, waiting to be
+    /// received in a block.
+    async fn is_message_pending(&self, message_id: &MessageId) -> Result<bool, ChainError> {
+        let pairs = self.inboxes.try_load_all_entries().await?;
+        for (origin, inbox) in pairs {
+            if origin.sender != message_id.chain_id {
+                continue;
+            }
+            for bundle in inbox.added_bundles.elements().await? {
+                if bundle.height == message_id.height
+                    && bundle
+                        .messages
+                        .iter()
+                        .any(|posted_message| posted_message.index == message_id.index)
+                {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Records the height of a newly certified `block` in the logs of the user applications
+    /// it has operations for.
+    pub async fn index_block_applications(
+        &mut self,
+        block: &ProposedBlock,
+    ) -> Result<(), ChainError> {
+        let application_ids = block
+            .operations
+            .iter()
+            .filter_map(|operation| match operation {
+                Operation::User { application_id, .. } => Some(*application_id),
+                Operation::System(_) => None,
+            })
+            .collect::<BTreeSet<_>>();
+        for application_id in application_ids {
+            self.application_block_heights
+                .load_entry_mut(&application_id)
+                .await?
+                .push(block.height);
+        }
+        Ok(())
+    }
+
+    /// Returns a page of at most `limit` certified blocks listed in the given `direction`,
+    /// starting at `from_height` if provided, or with the first block in that direction.
+    ///
+    /// If `application_id` is provided, only the blocks with operations of that application
+    /// are listed.
+    pub async fn block_history(
+        &self,
+        from_height: Option<BlockHeight>,
+        limit: usize,
+        direction: BlockHistoryDirection,
+        application_id: Option<UserApplicationId>,
+    ) -> Result<BlockHistoryPage, ChainError> {
+        let application_heights = match application_id {
+            Some(application_id) => {
+                match self
+                    .application_block_heights
+                    .try_load_entry(&application_id)
+                    .await?
+                {
+                    Some(heights) => Some(heights),
+                    None => return Ok(BlockHistoryPage::default()),
+                }
+            }
+            None => None,
+        };
+        let heights = application_heights.as_deref();
+        let count = heights.map_or(self.confirmed_log.count(), LogView::count);
+        let (start, end, next_index) = match direction {
+            BlockHistoryDirection::Forward => {
+                let start = match from_height {
+                    Some(height) => Self::first_index_at_or_above(heights, count, height).await?,
+                    None => 0,
+                };
+                let end = start.saturating_add(limit).min(count);
+                (start, end, (end < count).then_some(end))
+            }
+            BlockHistoryDirection::Backward => {
+                let end = match from_height {
+                    Some(height) => {
+                        let next_height = height.try_add_one()?;
+                        Self::first_index_at_or_above(heights, count, next_height).await?
+                    }
+                    None => count,
+                };
+                let start = end.saturating_sub(limit);
+                (start, end, start.checked_sub(1))
+            }
+        };
+        let (mut block_heights, next_height) = match heights {
+            None => {
+                let block_heights = (start..end)
+                    .map(|index| BlockHeight::from(index as u64))
+                    .collect::<Vec<_>>();
+                (
+                    block_heights,
+                    next_index.map(|index| BlockHeight::from(index as u64)),
+                )
+            }
+            Some(heights) => {
+                let block_heights = heights.read(start..end).await?;
+                let next_height = match next_index {
+                    Some(index) => heights.get(index).await?,
+                    None => None,
+                };
+                (block_heights, next_height)
+            }
+        };
+        if direction == BlockHistoryDirection::Backward {
+            block_heights.reverse();
+        }
+        let indices = block_heights
+            .iter()
+            .map(|height| usize::try_from(*height))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hashes = self.confirmed_log.multi_get(indices).await?;
+        let blocks = block_heights
+            .into_iter()
+            .zip(hashes)
+            .map(|(height, hash)| {
+                let hash = hash.ok_or_else(|| {
+                    ChainError::InternalError(format!(
+                        "Missing hash of the block at height {height}"
+                    ))
+                })?;
+                Ok((height, hash))
+            })
+            .collect::<Result<_, ChainError>>()?;
+        Ok(BlockHistoryPage {
+            blocks,
+            next_height,
+        })
+    }
+
+    /// Returns the index of the first listed block with a height of at least `height`: a
+    /// position in the application's log of `heights` if provided, or else in the
+    /// `count` blocks of the chain.
+    async fn first_index_at_or_above(
+        heights: Option<&LogView<C, BlockHeight>>,
+        count: usize,
+        height: BlockHeight,
+    ) -> Result<usize, ChainError> {
+        let Some(heights) = heights else {
+            return Ok(usize::try_from(height).unwrap_or(usize::MAX).min(count));
+        };
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match heights.get(middle).await? {
+                Some(middle_height) if middle_height < height => low = middle + 1,
+                _ => high = middle,
+            }
+        }
+        Ok(low)
+    }
+
     pub async fn last_anticipated_block_height(
         &self,
         origin: &Origin,
@@ -608,6 +967,7 @@ where
         incoming_bundles: &[IncomingBundle],
     ) -> Result<(), ChainError> {
         let chain_id = self.chain_id();
+        let height = self.tip_state.get().next_block_height;
         let mut bundles_by_origin: BTreeMap<_, Vec<&MessageBundle>> = Default::default();
         for IncomingBundle { bundle, origin, .. } in incoming_bundles {
             ensure!(
@@ -642,6 +1002,7 @@ where
                     removed_unskippable.insert(BundleInInbox::new(origin.clone(), bundle));
                 }
             }
+            inbox.record_removal(height);
         }
         if !removed_unskippable.is_empty() {
             // Delete all removed bundles from the front of the unskippable queue.
@@ -681,6 +1042,21 @@ where
         round: Option<u32>,
         replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
     ) -> Result<BlockExecutionOutcome, ChainError> {
+        let (outcome, _) = self
+            .execute_block_with_resource_usage(block, local_time, round, replaying_oracle_responses)
+            .await?;
+        Ok(outcome)
+    }
+
+    /// Executes a block like [`ChainStateView::execute_block`], and also returns the
+    /// resources used by each of its operations and by the whole block.
+    pub async fn execute_block_with_resource_usage(
+        &mut self,
+        block: &ProposedBlock,
+        local_time: Timestamp,
+        round: Option<u32>,
+        replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
+    ) -> Result<(BlockExecutionOutcome, BlockResourceUsage), ChainError> {
         #[cfg(with_metrics)]
         let _execution_latency = BLOCK_EXECUTION_LATENCY.measure_latency();
 
@@ -716,10 +1092,11 @@ where
         );
         self.execution_state.system.timestamp.set(block.timestamp);
         let (_, committee) = self.current_committee()?;
+        block.check_transaction_counts(committee.policy())?;
         let mut resource_controller = ResourceController {
             policy: Arc::new(committee.policy().clone()),
             tracker: ResourceTracker::default(),
-            account: block.authenticated_signer,
+            account: FeeAccount::new(block.authenticated_signer, block.fee_payer),
         };
         resource_controller
             .track_block_size(EMPTY_BLOCK_SIZE)
@@ -767,6 +1144,57 @@ where
             ChainError::MissingMandatoryApplications(mandatory.into_iter().collect())
         );
 
+        // Every scheduled message that is due must be executed, or parked if it fails, unless
+        // the block is already full of other due scheduled messages.
+        if !self.is_closed() {
+            let mut due_scheduled_messages = self
+                .execution_state
+                .system
+                .due_scheduled_messages(block.timestamp)
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>();
+            let mut only_scheduled_messages = true;
+            for operation in &block.operations {
+                match operation {
+                    Operation::System(
+                        SystemOperation::ExecuteScheduledMessage { message_id }
+                        | SystemOperation::ParkScheduledMessage { message_id },
+                    ) => {
+                        due_scheduled_messages.remove(message_id);
+                    }
+                    _ => only_scheduled_messages = false,
+                }
+            }
+            let maximum = committee.policy().maximum_operations_per_block;
+            let is_full = only_scheduled_messages
+                && u64::try_from(block.operations.len()).unwrap_or(u64::MAX) >= maximum;
+            ensure!(
+                due_scheduled_messages.is_empty() || is_full,
+                ChainError::MissingScheduledMessages(due_scheduled_messages.into_iter().collect())
+            );
+        }
+
+        // Owners can only reject messages that are waiting in an inbox.
+        for operation in &block.operations {
+            if let Operation::System(SystemOperation::RejectMessage { message_id }) = operation {
+                ensure!(
+                    self.is_message_pending(message_id).await?,
+                    ChainError::RejectedMessageNotPending(*message_id)
+                );
+            }
+        }
+
+        // Applications can read the state of the chains the block receives messages from, as of
+        // the last of their blocks it receives messages from.
+        let mut received_heights = BTreeMap::new();
+        for IncomingBundle { origin, bundle, .. } in &block.incoming_bundles {
+            let height = received_heights
+                .entry(origin.sender)
+                .or_insert(bundle.height);
+            *height = (*height).max(bundle.height);
+        }
+
         // Execute each incoming bundle as a transaction, then each operation.
         // Collect messages, events and oracle responses, each as one list per transaction.
         let mut replaying_oracle_responses = replaying_oracle_responses.map(Vec::into_iter);
@@ -774,6 +1202,8 @@ where
         let mut oracle_responses = Vec::new();
         let mut events = Vec::new();
         let mut messages = Vec::new();
+        let mut operation_usage = Vec::with_capacity(block.operations.len());
+        let mut heap_usage = BTreeMap::<UserApplicationId, HeapUsage>::new();
         for (txn_index, transaction) in block.transactions() {
             let chain_execution_context = match transaction {
                 Transaction::ReceiveMessages(_) => ChainExecutionContext::IncomingBundle(txn_index),
@@ -785,6 +1215,7 @@ where
                 None => None,
             };
             let mut txn_tracker = TransactionTracker::new(next_message_index, maybe_responses);
+            txn_tracker.set_received_heights(received_heights.clone());
             match transaction {
                 Transaction::ReceiveMessages(incoming_bundle) => {
                     resource_controller
@@ -811,10 +1242,12 @@ where
                         .with_execution_context(chain_execution_context)?;
                     #[cfg(with_metrics)]
                     let _operation_latency = OPERATION_EXECUTION_LATENCY.measure_latency();
+                    let used_before = resource_controller.tracker;
                     let context = OperationContext {
                         chain_id,
                         height: block.height,
                         index: Some(txn_index),
+                        call_depth: 0,
                         round,
                         authenticated_signer: block.authenticated_signer,
                         authenticated_caller_id: None,
@@ -833,6 +1266,14 @@ where
                         .await?
                         .track_operation(operation)
                         .with_execution_context(chain_execution_context)?;
+                    let used_after = resource_controller.tracker;
+                    operation_usage.push(ResourceEstimate {
+                        fuel: used_after.fuel.saturating_sub(used_before.fuel),
+                        bytes_read: used_after.bytes_read.saturating_sub(used_before.bytes_read),
+                        bytes_written: used_after
+                            .bytes_written
+                            .saturating_sub(used_before.bytes_written),
+                    });
                 }
             }
 
@@ -840,6 +1281,30 @@ where
                 .update_execution_outcomes_with_app_registrations(&mut txn_tracker)
                 .await
                 .with_execution_context(chain_execution_context)?;
+            if let Some(audit) = txn_tracker.guest_input_audit() {
+                tracing::info!(
+                    %chain_id,
+                    height = %block.height,
+                    txn_index,
+                    hashes = ?audit.hashes(),
+                    "Inputs observed by the applications"
+                );
+            }
+            if let Some(reports) = txn_tracker.view_access_reports() {
+                for (application_id, report) in reports {
+                    tracing::info!(
+                        %chain_id,
+                        height = %block.height,
+                        txn_index,
+                        ?application_id,
+                        %report,
+                        "Accesses to the views of the application"
+                    );
+                }
+            }
+            for (application_id, usage) in txn_tracker.heap_usage() {
+                heap_usage.entry(*application_id).or_default().merge(*usage);
+            }
             let (txn_outcomes, txn_oracle_responses, new_next_message_index) = txn_tracker
                 .destructure()
                 .with_execution_context(chain_execution_context)?;
@@ -937,7 +1402,16 @@ where
             oracle_responses,
             events,
         };
-        Ok(outcome)
+        let resource_usage = BlockResourceUsage {
+            operations: operation_usage,
+            total: ResourceEstimate {
+                fuel: resource_controller.tracker.fuel,
+                bytes_read: resource_controller.tracker.bytes_read,
+                bytes_written: resource_controller.tracker.bytes_written,
+            },
+            heap_usage,
+        };
+        Ok((outcome, resource_usage))
     }
 
     /// Executes a message as part of an incoming bundle in a block.
@@ -952,7 +1426,7 @@ where
         txn_index: u32,
         local_time: Timestamp,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeeAccount>,
     ) -> Result<(), ChainError> {
         #[cfg(with_metrics)]
         let _message_latency = MESSAGE_EXECUTION_LATENCY.measure_latency();
@@ -967,7 +1441,18 @@ where
             refund_grant_to: posted_message.refund_grant_to,
         };
         let mut grant = posted_message.grant;
-        match incoming_bundle.action {
+        let mut action = incoming_bundle.action;
+        let rejected_messages = &mut self.execution_state.system.rejected_messages;
+        if rejected_messages.contains(&message_id).await? {
+            // The owner asked to reject this message. The mark is dropped now that the message is
+            // processed. Protected messages can't be rejected, so they are handled as usual.
+            rejected_messages.remove(&message_id)?;
+            if action != MessageAction::Reject && !posted_message.is_protected() {
+                action = MessageAction::Reject;
+                self.messages_rejected_by_owner.insert(&message_id)?;
+            }
+        }
+        match action {
             MessageAction::Accept => {
                 // Once a chain is closed, accepting incoming messages is not allowed.
                 ensure!(!self.is_closed(), ChainError::ClosedChain);
@@ -982,7 +1467,8 @@ where
                 ))
                 .await
                 .with_execution_context(ChainExecutionContext::IncomingBundle(txn_index))?;
-                if grant > Amount::ZERO {
+                // Depending on the policy, the unused part of the grant is refunded or burned.
+                if grant > Amount::ZERO && !resource_controller.policy.burn_unused_grants {
                     if let Some(refund_grant_to) = posted_message.refund_grant_to {
                         self.execution_state
                             .send_refund(context, grant, refund_grant_to, txn_tracker)
@@ -1025,6 +1511,23 @@ where
                         .with_execution_context(ChainExecutionContext::Block)?;
                 }
             }
+            MessageAction::Park => {
+                // Parking defers the message indefinitely, so protected messages can't be
+                // parked any more than they can be rejected.
+                ensure!(
+                    !posted_message.is_protected() || self.is_closed(),
+                    ChainError::CannotParkMessage {
+                        chain_id: block.chain_id,
+                        origin: Box::new(incoming_bundle.origin.clone()),
+                        posted_message: Box::new(posted_message.clone()),
+                    }
+                );
+                // The grant is kept with the message, to pay for its execution when retried.
+                self.execution_state
+                    .park_message(context, grant, posted_message.message.clone())
+                    .await
+                    .with_execution_context(ChainExecutionContext::Block)?;
+            }
         }
         Ok(())
     }
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     fmt,
 };
 
@@ -12,19 +12,20 @@ use custom_debug_derive::Debug;
 use linera_base::{
     bcs,
     crypto::{BcsHashable, BcsSignable, CryptoError, CryptoHash, KeyPair, PublicKey, Signature},
-    data_types::{Amount, BlockHeight, OracleResponse, Round, Timestamp},
+    data_types::{Amount, ArithmeticError, BlockHeight, OracleResponse, Round, Timestamp},
     doc_scalar, ensure,
     hashed::Hashed,
     hex_debug,
     identifiers::{
         Account, BlobId, BlobType, ChainId, ChannelName, Destination, GenericApplicationId,
-        MessageId, Owner, StreamId,
+        MessageId, Owner, StreamId, UserApplicationId,
     },
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::OpenChainConfig,
-    Message, MessageKind, Operation, SystemMessage, SystemOperation,
+    HeapUsage, Message, MessageKind, Operation, ResourceControlPolicy, SystemMessage,
+    SystemOperation,
 };
 use serde::{Deserialize, Serialize};
 
@@ -72,6 +73,12 @@ pub struct ProposedBlock {
     /// potential refunds for the message grants created by the operations.
     #[debug(skip_if = Option::is_none)]
     pub authenticated_signer: Option<Owner>,
+    /// The chain sponsoring the execution fees of the block, instead of the chain and the
+    /// authenticated signer. The fees are paid from the funds that the sponsoring chain set
+    /// aside for this chain, with `SystemOperation::Sponsor`. If set, the block proposal must
+    /// carry the signature of the sponsorship's owner.
+    #[debug(skip_if = Option::is_none)]
+    pub fee_payer: Option<ChainId>,
     /// Certified hash (see `Certificate` below) of the previous block in the
     /// chain, if any.
     pub previous_block_hash: Option<CryptoHash>,
@@ -96,6 +103,15 @@ impl ProposedBlock {
         blob_ids
     }
 
+    /// Returns the fees charged for the block whatever its transactions do: the block fee
+    /// and the fee of each operation.
+    pub fn base_fees(&self, policy: &ResourceControlPolicy) -> Result<Amount, ArithmeticError> {
+        let operations = u128::try_from(self.operations.len()).unwrap_or(u128::MAX);
+        policy
+            .block_price()
+            .try_add(policy.operation.try_mul(operations)?)
+    }
+
     /// Returns whether the block contains only rejected incoming messages, which
     /// makes it admissible even on closed chains.
     pub fn has_only_rejected_messages(&self) -> bool {
@@ -149,10 +165,49 @@ impl ProposedBlock {
         let size = bcs::serialized_size(self)?;
         ensure!(
             size <= usize::try_from(maximum_block_proposal_size).unwrap_or(usize::MAX),
-            ChainError::BlockProposalTooLarge
+            ChainError::BlockProposalTooLarge {
+                size,
+                maximum: maximum_block_proposal_size,
+            }
         );
         Ok(())
     }
+
+    /// Checks that the block doesn't have more operations or incoming message bundles, in total
+    /// or from any single origin, than the policy allows.
+    pub fn check_transaction_counts(
+        &self,
+        policy: &ResourceControlPolicy,
+    ) -> Result<(), ChainError> {
+        let maximum = policy.maximum_operations_per_block;
+        let count = self.operations.len();
+        ensure!(
+            count <= usize::try_from(maximum).unwrap_or(usize::MAX),
+            ChainError::TooManyOperations { count, maximum }
+        );
+        let maximum = policy.maximum_incoming_bundles_per_block;
+        let count = self.incoming_bundles.len();
+        ensure!(
+            count <= usize::try_from(maximum).unwrap_or(usize::MAX),
+            ChainError::TooManyIncomingBundles { count, maximum }
+        );
+        let maximum = policy.maximum_incoming_bundles_per_origin;
+        let mut counts = BTreeMap::<&Origin, usize>::new();
+        for bundle in &self.incoming_bundles {
+            *counts.entry(&bundle.origin).or_default() += 1;
+        }
+        if let Some((origin, count)) = counts
+            .into_iter()
+            .find(|(_, count)| *count > usize::try_from(maximum).unwrap_or(usize::MAX))
+        {
+            return Err(ChainError::TooManyIncomingBundlesFromOrigin {
+                origin: Box::new(origin.clone()),
+                count,
+                maximum,
+            });
+        }
+        Ok(())
+    }
 }
 
 /// A transaction in a block: incoming messages or an operation.
@@ -207,6 +262,27 @@ impl IncomingBundle {
         })
     }
 
+    /// Sorts the bundles in the canonical order in which they are proposed: by sender chain,
+    /// then by height and transaction index. Since these increase within each inbox, the
+    /// bundles of every origin remain in order.
+    pub fn sort_canonically(bundles: &mut [IncomingBundle]) {
+        bundles.sort_by(|bundle1, bundle2| {
+            let key1 = (
+                bundle1.origin.sender,
+                bundle1.bundle.height,
+                bundle1.bundle.transaction_index,
+                &bundle1.origin.medium,
+            );
+            let key2 = (
+                bundle2.origin.sender,
+                bundle2.bundle.height,
+                bundle2.bundle.transaction_index,
+                &bundle2.origin.medium,
+            );
+            key1.cmp(&key2)
+        });
+    }
+
     /// Rearranges the messages in the bundle so that the first message is an `OpenChain` message.
     /// Returns whether the `OpenChain` message was found at all.
     pub fn put_openchain_at_front(bundles: &mut [IncomingBundle]) -> bool {
@@ -229,6 +305,66 @@ impl IncomingBundle {
 
 impl<'de> BcsHashable<'de> for IncomingBundle {}
 
+/// A message received in an inbox but not executed yet.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PendingMessage {
+    /// The origin of the message (chain and channel if any).
+    pub origin: Origin,
+    /// The height of the block that sent the message.
+    pub height: BlockHeight,
+    /// The timestamp of the block that sent the message.
+    pub timestamp: Timestamp,
+    /// The index of the message in the sending block.
+    pub index: u32,
+    /// The application that sent the message.
+    pub application_id: GenericApplicationId,
+    /// The kind of message being sent.
+    pub kind: MessageKind,
+    /// The amount of tokens granted to pay for the message execution.
+    pub grant: Amount,
+    /// A readable description of the message, if it can be decoded without knowing the
+    /// sending application.
+    pub preview: Option<String>,
+    /// Whether the owner of the chain rejected the message. It will be skipped, or bounced if
+    /// it is tracked, instead of being executed.
+    pub rejected: bool,
+}
+
+impl PendingMessage {
+    /// Returns the pending messages of a bundle received from `origin`.
+    pub fn from_bundle<'a>(
+        origin: &'a Origin,
+        bundle: &'a MessageBundle,
+    ) -> impl Iterator<Item = PendingMessage> + 'a {
+        bundle.messages.iter().map(move |posted_message| {
+            let preview = match &posted_message.message {
+                Message::System(message) => Some(format!("{message:?}")),
+                Message::User { .. } => None,
+            };
+            PendingMessage {
+                origin: origin.clone(),
+                height: bundle.height,
+                timestamp: bundle.timestamp,
+                index: posted_message.index,
+                application_id: posted_message.message.application_id(),
+                kind: posted_message.kind,
+                grant: posted_message.grant,
+                preview,
+                rejected: false,
+            }
+        })
+    }
+
+    /// Returns the ID of the message.
+    pub fn message_id(&self) -> MessageId {
+        MessageId {
+            chain_id: self.origin.sender,
+            height: self.height,
+            index: self.index,
+        }
+    }
+}
+
 /// What to do with a message picked from the inbox.
 #[derive(Copy, Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum MessageAction {
@@ -236,6 +372,10 @@ pub enum MessageAction {
     Accept,
     /// Do not execute the incoming message.
     Reject,
+    /// Do not execute the incoming message now, but store it in the chain's dead-letter queue
+    /// so that it can be retried later with `SystemOperation::RetryDeadLetter`. Protected messages
+    /// can only be parked once the chain is closed.
+    Park,
 }
 
 /// The origin of a message, relative to a particular application. Used to identify each inbox.
@@ -311,6 +451,38 @@ pub struct BlockProposal {
     pub signature: Signature,
     #[debug(skip_if = Option::is_none)]
     pub validated_block_certificate: Option<LiteCertificate<'static>>,
+    /// The signature of the sponsorship's owner, if a sponsoring chain pays for the fees of
+    /// the block.
+    #[debug(skip_if = Option::is_none)]
+    pub fee_payer: Option<FeePayer>,
+}
+
+/// The signature of a sponsorship's owner, agreeing that the sponsoring chain pays for the
+/// execution fees of a proposed block.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FeePayer {
+    pub public_key: PublicKey,
+    pub signature: Signature,
+}
+
+impl FeePayer {
+    /// Signs the `block` as the owner of the sponsorship paying for its fees.
+    pub fn new(block: &ProposedBlock, secret: &KeyPair) -> Self {
+        Self {
+            public_key: secret.public(),
+            signature: Signature::new(block, secret),
+        }
+    }
+
+    /// Returns the owner who signed for the sponsorship.
+    pub fn owner(&self) -> Owner {
+        Owner::from(self.public_key)
+    }
+
+    /// Checks that this is the signer's signature of the `block`.
+    pub fn check(&self, block: &ProposedBlock) -> Result<(), CryptoError> {
+        self.signature.check(block, self.public_key)
+    }
 }
 
 /// A posted message together with routing information.
@@ -416,6 +588,17 @@ pub struct BlockExecutionOutcome {
     pub events: Vec<Vec<EventRecord>>,
 }
 
+/// What one operation of a block produced when it was executed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperationOutcome {
+    /// The messages sent by the operation, with their IDs.
+    pub messages: Vec<(MessageId, OutgoingMessage)>,
+    /// The events emitted by the operation.
+    pub events: Vec<EventRecord>,
+    /// The oracle responses recorded while executing the operation.
+    pub oracle_responses: Vec<OracleResponse>,
+}
+
 /// An event recorded in an executed block.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct EventRecord {
@@ -759,6 +942,54 @@ impl BlockExecutionOutcome {
     }
 }
 
+/// The resources used by the execution of an operation, as measured when executing it or as
+/// claimed by a block proposer.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ResourceEstimate {
+    /// The fuel used to execute the operation.
+    pub fuel: u64,
+    /// The number of bytes read from storage.
+    pub bytes_read: u64,
+    /// The number of bytes written to storage.
+    pub bytes_written: u64,
+}
+
+impl ResourceEstimate {
+    /// Returns the sum of the `estimates`, saturating at the numeric bounds.
+    pub fn total<'a>(estimates: impl IntoIterator<Item = &'a ResourceEstimate>) -> Self {
+        estimates
+            .into_iter()
+            .fold(ResourceEstimate::default(), |total, estimate| {
+                ResourceEstimate {
+                    fuel: total.fuel.saturating_add(estimate.fuel),
+                    bytes_read: total.bytes_read.saturating_add(estimate.bytes_read),
+                    bytes_written: total.bytes_written.saturating_add(estimate.bytes_written),
+                }
+            })
+    }
+
+    /// Returns the amount of each resource, with a description of its unit.
+    fn resources(&self) -> [(&'static str, u64); 3] {
+        [
+            ("units of fuel", self.fuel),
+            ("bytes read", self.bytes_read),
+            ("bytes written", self.bytes_written),
+        ]
+    }
+}
+
+/// The resources used by the execution of a block.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct BlockResourceUsage {
+    /// The resources used by each operation of the block, in order.
+    pub operations: Vec<ResourceEstimate>,
+    /// The resources used by the whole block, including its incoming messages.
+    pub total: ResourceEstimate,
+    /// The heap usage reported by each application built with the `profiling` feature of
+    /// the SDK, over all transactions of the block.
+    pub heap_usage: BTreeMap<UserApplicationId, HeapUsage>,
+}
+
 /// The data a block proposer signs.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ProposalContent {
@@ -769,14 +1000,109 @@ pub struct ProposalContent {
     /// If this is a retry from an earlier round, the execution outcome.
     #[debug(skip_if = Option::is_none)]
     pub outcome: Option<BlockExecutionOutcome>,
+    /// The resources the proposer expects each operation to use, if it makes a claim. If not
+    /// empty, there is one estimate per operation of the block.
+    #[debug(skip_if = Vec::is_empty)]
+    pub estimates: Vec<ResourceEstimate>,
+}
+
+impl ProposalContent {
+    /// Checks that the resources claimed by the proposer fit in a block, so that a proposal
+    /// that would exceed the limits is rejected before being executed. If the policy requires
+    /// estimates, a new proposal with operations must claim their resources.
+    pub fn check_resource_estimates(
+        &self,
+        policy: &ResourceControlPolicy,
+    ) -> Result<(), ChainError> {
+        ensure!(
+            policy.resource_estimate_tolerance_percent.is_none()
+                || self.outcome.is_some()
+                || self.block.operations.is_empty()
+                || !self.estimates.is_empty(),
+            ChainError::MissingResourceEstimates
+        );
+        ensure!(
+            self.estimates.is_empty() || self.estimates.len() == self.block.operations.len(),
+            ChainError::ResourceEstimateCountMismatch {
+                estimates: self.estimates.len(),
+                operations: self.block.operations.len(),
+            }
+        );
+        let total = ResourceEstimate::total(&self.estimates);
+        let maximums = [
+            policy.maximum_fuel_per_block,
+            policy.maximum_bytes_read_per_block,
+            policy.maximum_bytes_written_per_block,
+        ];
+        for ((resource, claimed), maximum) in total.resources().into_iter().zip(maximums) {
+            ensure!(
+                claimed <= maximum,
+                ChainError::ResourceEstimateOverBudget {
+                    resource,
+                    claimed,
+                    maximum,
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that no operation used more resources than the proposer claimed, by more than
+    /// the tolerance of the policy. Nothing is checked if the policy doesn't require estimates.
+    pub fn check_resource_usage(
+        &self,
+        usage: &[ResourceEstimate],
+        policy: &ResourceControlPolicy,
+    ) -> Result<(), ChainError> {
+        let Some(tolerance_percent) = policy.resource_estimate_tolerance_percent else {
+            return Ok(());
+        };
+        ensure!(
+            self.estimates.is_empty() || self.estimates.len() == usage.len(),
+            ChainError::ResourceEstimateCountMismatch {
+                estimates: self.estimates.len(),
+                operations: usage.len(),
+            }
+        );
+        for (index, (estimate, used)) in self.estimates.iter().zip(usage).enumerate() {
+            for ((resource, claimed), (_, actual)) in
+                estimate.resources().into_iter().zip(used.resources())
+            {
+                let allowed =
+                    claimed.saturating_add(claimed.saturating_mul(tolerance_percent) / 100);
+                ensure!(
+                    actual <= allowed,
+                    ChainError::ResourceEstimateExceeded {
+                        index,
+                        resource,
+                        claimed,
+                        actual,
+                    }
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 impl BlockProposal {
     pub fn new_initial(round: Round, block: ProposedBlock, secret: &KeyPair) -> Self {
+        Self::new_initial_with_estimates(round, block, Vec::new(), secret)
+    }
+
+    /// Creates a proposal claiming that the operations of the `block` use the resources
+    /// in `estimates`, e.g. as measured when staging its execution.
+    pub fn new_initial_with_estimates(
+        round: Round,
+        block: ProposedBlock,
+        estimates: Vec<ResourceEstimate>,
+        secret: &KeyPair,
+    ) -> Self {
         let content = ProposalContent {
             round,
             block,
             outcome: None,
+            estimates,
         };
         let signature = Signature::new(&content, secret);
         Self {
@@ -785,6 +1111,7 @@ impl BlockProposal {
             owner: secret.public().into(),
             signature,
             validated_block_certificate: None,
+            fee_payer: None,
         }
     }
 
@@ -800,6 +1127,7 @@ impl BlockProposal {
             block: executed_block.block,
             round,
             outcome: Some(executed_block.outcome),
+            estimates: Vec::new(),
         };
         let signature = Signature::new(&content, secret);
         Self {
@@ -808,9 +1136,17 @@ impl BlockProposal {
             owner: secret.public().into(),
             signature,
             validated_block_certificate: Some(lite_cert),
+            fee_payer: None,
         }
     }
 
+    /// Adds the signature of the `sponsor` owning the sponsorship that pays for the fees of the
+    /// block, which must already name the sponsoring chain as its `fee_payer`.
+    pub fn with_fee_payer(mut self, sponsor: &KeyPair) -> Self {
+        self.fee_payer = Some(FeePayer::new(&self.content.block, sponsor));
+        self
+    }
+
     pub fn check_signature(&self) -> Result<(), CryptoError> {
         self.signature.check(&self.content, self.public_key)
     }
@@ -824,13 +1160,18 @@ impl BlockProposal {
         )
     }
 
-    /// Checks that the public key matches the owner and that the optional certificate matches
-    /// the outcome.
+    /// Checks that the public key matches the owner, that the optional certificate matches
+    /// the outcome, and that there is a resource estimate for each operation or none.
     pub fn check_invariants(&self) -> Result<(), &'static str> {
         ensure!(
             self.owner == Owner::from(&self.public_key),
             "Public key does not match owner"
         );
+        let estimates = &self.content.estimates;
+        ensure!(
+            estimates.is_empty() || estimates.len() == self.content.block.operations.len(),
+            "Must contain a resource estimate for each operation, or none"
+        );
         match (&self.validated_block_certificate, &self.content.outcome) {
             (None, None) => {}
             (None, Some(_)) | (Some(_), None) => {
@@ -962,13 +1303,15 @@ pub(crate) fn check_signatures(
     Ok(())
 }
 
+impl<'de> BcsSignable<'de> for ProposedBlock {}
+
 impl<'de> BcsSignable<'de> for ProposalContent {}
 
 impl<'de> BcsSignable<'de> for VoteValue {}
 
 doc_scalar!(
     MessageAction,
-    "Whether an incoming message is accepted or rejected."
+    "Whether an incoming message is accepted, rejected or parked."
 );
 doc_scalar!(
     ChannelFullName,
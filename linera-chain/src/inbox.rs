@@ -11,6 +11,7 @@ use linera_base::{
 use linera_views::context::{create_test_memory_context, MemoryContext};
 use linera_views::{
     context::Context,
+    log_view::LogView,
     queue_view::QueueView,
     register_view::RegisterView,
     views::{ClonableView, View, ViewError},
@@ -50,6 +51,10 @@ where
     /// These bundles have been removed by anticipation and are waiting to be added.
     /// At least one of `added_bundles` and `removed_bundles` should be empty.
     pub removed_bundles: QueueView<C, MessageBundle>,
+    /// For each block of this chain that removed bundles from this inbox, the next cursor to
+    /// remove after it and the height of that block, in order.
+    #[graphql(skip)]
+    pub removal_heights: LogView<C, (Cursor, BlockHeight)>,
 }
 
 #[derive(
@@ -71,6 +76,17 @@ pub struct Cursor {
     index: u32,
 }
 
+/// Whether a bundle has been received in an inbox, and whether it has been removed from it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InboxBundleState {
+    /// The bundle has not been added to the inbox yet.
+    NotReceived,
+    /// The bundle has been added to the inbox and is waiting to be removed.
+    Pending,
+    /// The bundle has been removed from the inbox by a block.
+    Removed,
+}
+
 #[derive(Error, Debug)]
 pub(crate) enum InboxError {
     #[error(transparent)]
@@ -163,6 +179,56 @@ where
         }
     }
 
+    /// Returns the state of the bundle of messages sent by the transaction with index
+    /// `transaction_index` of the block at `height`.
+    pub fn bundle_state(&self, height: BlockHeight, transaction_index: u32) -> InboxBundleState {
+        let cursor = Cursor {
+            height,
+            index: transaction_index,
+        };
+        if cursor < *self.next_cursor_to_remove.get() {
+            InboxBundleState::Removed
+        } else if cursor < *self.next_cursor_to_add.get() {
+            InboxBundleState::Pending
+        } else {
+            InboxBundleState::NotReceived
+        }
+    }
+
+    /// Returns the height of the block of this chain that removed the bundle of messages sent
+    /// by the transaction with index `transaction_index` of the block at `height`, if that
+    /// bundle was removed and the height was recorded.
+    pub async fn removal_height(
+        &self,
+        height: BlockHeight,
+        transaction_index: u32,
+    ) -> Result<Option<BlockHeight>, ViewError> {
+        let cursor = Cursor {
+            height,
+            index: transaction_index,
+        };
+        // Find the first block after which the next cursor to remove is above the bundle's.
+        let (mut low, mut high) = (0, self.removal_heights.count());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.removal_heights.get(middle).await? {
+                Some((next_cursor, _)) if next_cursor <= cursor => low = middle + 1,
+                _ => high = middle,
+            }
+        }
+        Ok(self
+            .removal_heights
+            .get(low)
+            .await?
+            .map(|(_, block_height)| block_height))
+    }
+
+    /// Records that the block of this chain at `height` removed bundles from this inbox.
+    pub(crate) fn record_removal(&mut self, height: BlockHeight) {
+        self.removal_heights
+            .push((*self.next_cursor_to_remove.get(), height));
+    }
+
     /// Consumes a bundle from the inbox.
     ///
     /// Returns `true` if the bundle was already known, i.e. it was present in `added_bundles`.
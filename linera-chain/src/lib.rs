@@ -21,13 +21,14 @@ mod pending_blobs;
 #[cfg(with_testing)]
 pub mod test;
 
-pub use chain::ChainStateView;
+pub use chain::{BlockHistoryDirection, BlockHistoryPage, ChainStateView, ChainStatistics};
 use data_types::{MessageBundle, Origin, PostedMessage};
+pub use inbox::InboxBundleState;
 use linera_base::{
     bcs,
     crypto::{CryptoError, CryptoHash},
     data_types::{ArithmeticError, BlockHeight, Round, Timestamp},
-    identifiers::{ApplicationId, BlobId, ChainId},
+    identifiers::{ApplicationId, BlobId, ChainId, MessageId},
 };
 use linera_execution::ExecutionError;
 use linera_views::views::ViewError;
@@ -87,6 +88,15 @@ pub enum ChainError {
         origin: Box<Origin>,
         posted_message: Box<PostedMessage>,
     },
+    #[error(
+        "Block proposed to {chain_id:?} is attempting to park protected message \
+        {posted_message:?}"
+    )]
+    CannotParkMessage {
+        chain_id: ChainId,
+        origin: Box<Origin>,
+        posted_message: Box<PostedMessage>,
+    },
     #[error(
         "Block proposed to {chain_id:?} is attempting to skip a message bundle \
          that cannot be skipped: {bundle:?}"
@@ -142,8 +152,41 @@ pub enum ChainError {
     CertificateSignatureVerificationFailed { error: String },
     #[error("Internal error {0}")]
     InternalError(String),
-    #[error("Block proposal is too large")]
-    BlockProposalTooLarge,
+    #[error("Block proposal has {size} bytes, more than the maximum of {maximum}")]
+    BlockProposalTooLarge { size: usize, maximum: u64 },
+    #[error("Block has {count} operations, more than the maximum of {maximum}")]
+    TooManyOperations { count: usize, maximum: u64 },
+    #[error("Block has {count} incoming message bundles, more than the maximum of {maximum}")]
+    TooManyIncomingBundles { count: usize, maximum: u64 },
+    #[error(
+        "Block has {count} incoming message bundles from {origin:?}, more than the maximum \
+        of {maximum} per origin"
+    )]
+    TooManyIncomingBundlesFromOrigin {
+        origin: Box<Origin>,
+        count: usize,
+        maximum: u64,
+    },
+    #[error("Block proposal claims {claimed} {resource}, more than the maximum of {maximum}")]
+    ResourceEstimateOverBudget {
+        resource: &'static str,
+        claimed: u64,
+        maximum: u64,
+    },
+    #[error(
+        "Operation {index} used {actual} {resource}, more than the {claimed} claimed by the \
+        block proposal"
+    )]
+    ResourceEstimateExceeded {
+        index: usize,
+        resource: &'static str,
+        claimed: u64,
+        actual: u64,
+    },
+    #[error("Block proposal must claim the resources used by each operation")]
+    MissingResourceEstimates,
+    #[error("Block proposal claims resources for {estimates} operations, but has {operations}")]
+    ResourceEstimateCountMismatch { estimates: usize, operations: usize },
     #[error(transparent)]
     BcsError(#[from] bcs::Error),
     #[error("Insufficient balance to pay the fees")]
@@ -156,6 +199,18 @@ pub enum ChainError {
     AuthorizedApplications(Vec<ApplicationId>),
     #[error("Missing operations or messages from mandatory applications: {0:?}")]
     MissingMandatoryApplications(Vec<ApplicationId>),
+    #[error("Missing operations to execute the due scheduled messages: {0:?}")]
+    MissingScheduledMessages(Vec<MessageId>),
+    #[error("Cannot reject message {0:?}, which is not waiting in an inbox")]
+    RejectedMessageNotPending(MessageId),
+    #[error(
+        "Block proposed to {chain_id:?} must receive the bundle at height {height:?} in which \
+         the chain scheduled messages to itself"
+    )]
+    MissingScheduleMessageBundle {
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
     #[error("Can't use grant across different broadcast messages")]
     GrantUseOnBroadcast,
     #[error("ExecutedBlock contains fewer oracle responses than requests")]
@@ -579,6 +579,22 @@ where
         self.update_current_round(local_time);
     }
 
+    /// Returns whether the owner of the sponsorship paying for the fees of the proposed block,
+    /// if any, signed it. The `sponsor` is the owner of the sponsorship by the block's
+    /// `fee_payer` chain, if there is such a sponsorship. A proposal retrying a validated block
+    /// needs no signature: it was checked by a quorum of validators in an earlier round.
+    pub fn verify_fee_payer(&self, proposal: &BlockProposal, sponsor: Option<Owner>) -> bool {
+        let block = &proposal.content.block;
+        match (&block.fee_payer, &proposal.fee_payer) {
+            (None, None) => true,
+            (None, Some(_)) => false,
+            (Some(_), None) => proposal.validated_block_certificate.is_some(),
+            (Some(_), Some(fee_payer)) => {
+                sponsor == Some(fee_payer.owner()) && fee_payer.check(block).is_ok()
+            }
+        }
+    }
+
     /// Returns whether the signer is a valid owner and allowed to propose a block in the
     /// proposal's round.
     pub fn verify_owner(&self, proposal: &BlockProposal) -> bool {
@@ -35,6 +35,7 @@ pub fn make_child_block(parent: &Hashed<ConfirmedBlock>) -> ProposedBlock {
         previous_block_hash: Some(parent.hash()),
         height: parent_header.height.try_add_one().unwrap(),
         authenticated_signer: parent_header.authenticated_signer,
+        fee_payer: None,
         timestamp: parent_header.timestamp,
     }
 }
@@ -49,6 +50,7 @@ pub fn make_first_block(chain_id: ChainId) -> ProposedBlock {
         previous_block_hash: None,
         height: BlockHeight::ZERO,
         authenticated_signer: None,
+        fee_payer: None,
         timestamp: Timestamp::default(),
     }
 }
@@ -58,6 +60,9 @@ pub trait BlockTestExt: Sized {
     /// Returns the block with the given authenticated signer.
     fn with_authenticated_signer(self, authenticated_signer: Option<Owner>) -> Self;
 
+    /// Returns the block with the given chain sponsoring its fees.
+    fn with_fee_payer(self, fee_payer: Option<ChainId>) -> Self;
+
     /// Returns the block with the given operation appended at the end.
     fn with_operation(self, operation: impl Into<Operation>) -> Self;
 
@@ -92,6 +97,11 @@ impl BlockTestExt for ProposedBlock {
         self
     }
 
+    fn with_fee_payer(mut self, fee_payer: Option<ChainId>) -> Self {
+        self.fee_payer = fee_payer;
+        self
+    }
+
     fn with_operation(mut self, operation: impl Into<Operation>) -> Self {
         self.operations.push(operation.into());
         self
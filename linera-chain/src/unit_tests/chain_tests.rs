@@ -3,38 +3,48 @@
 
 #![allow(clippy::large_futures)]
 
-use std::{collections::BTreeMap, iter};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+};
 
 use assert_matches::assert_matches;
 use linera_base::{
     crypto::{CryptoHash, PublicKey},
     data_types::{
-        Amount, ApplicationPermissions, Blob, BlockHeight, Bytecode, Timestamp,
-        UserApplicationDescription,
+        Amount, ApplicationPermissions, Blob, BlockHeight, Bytecode, Resources, SendMessageRequest,
+        Timestamp, UserApplicationDescription,
     },
     hashed::Hashed,
-    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId},
+    identifiers::{
+        Account, ApplicationId, BytecodeId, ChainDescription, ChainId, Destination, MessageId,
+        Owner, UserApplicationId,
+    },
     ownership::ChainOwnership,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName, ValidatorState},
-    system::{OpenChainConfig, Recipient},
-    test_utils::{ExpectedCall, MockApplication},
-    ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message, MessageKind,
-    Operation, ResourceControlPolicy, SystemMessage, SystemOperation, TestExecutionRuntimeContext,
+    system::{ApplicationAllowlist, OpenChainConfig, Recipient},
+    test_utils::{ExpectedCall, MockApplication, RegisterMockApplication},
+    ContractRuntime as _, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message,
+    MessageKind, Operation, Query, QueryResponse, ResourceControlPolicy, ServiceRuntime,
+    SystemExecutionError, SystemMessage, SystemOperation, TestExecutionRuntimeContext,
 };
 use linera_views::{
     context::{Context as _, MemoryContext},
     memory::TEST_MEMORY_MAX_STREAM_QUERIES,
     random::generate_test_namespace,
-    views::{View, ViewError},
+    views::{ClonableView as _, View, ViewError},
 };
 
 use crate::{
     block::{Block, ConfirmedBlock},
-    data_types::{IncomingBundle, MessageAction, MessageBundle, Origin},
+    data_types::{
+        BlockExecutionOutcome, IncomingBundle, MessageAction, MessageBundle, Origin,
+        OutgoingMessage,
+    },
     test::{make_child_block, make_first_block, BlockTestExt, MessageTestExt},
-    ChainError, ChainExecutionContext, ChainStateView,
+    BlockHistoryDirection, ChainError, ChainExecutionContext, ChainStateView,
 };
 
 impl ChainStateView<MemoryContext<TestExecutionRuntimeContext>>
@@ -74,6 +84,7 @@ fn make_app_description() -> (UserApplicationDescription, Blob, Blob) {
             creation: make_admin_message_id(BlockHeight(2)),
             required_application_ids: vec![],
             parameters: vec![],
+            max_query_response_bytes: None,
         },
         contract_blob,
         service_blob,
@@ -183,6 +194,87 @@ async fn test_block_size_limit() {
     );
 }
 
+#[tokio::test]
+async fn test_transaction_count_limits() {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Initialize the chain, allowing only one operation and one incoming bundle per block.
+    let mut config = make_open_chain_config();
+    config.committees.insert(
+        Epoch(0),
+        Committee::new(
+            BTreeMap::from([(
+                ValidatorName(PublicKey::test_key(1)),
+                ValidatorState {
+                    network_address: PublicKey::test_key(1).to_string(),
+                    votes: 1,
+                },
+            )]),
+            ResourceControlPolicy {
+                maximum_operations_per_block: 1,
+                maximum_incoming_bundles_per_block: 1,
+                ..ResourceControlPolicy::default()
+            },
+        ),
+    );
+
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await
+        .unwrap();
+    let open_chain_bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![Message::System(SystemMessage::OpenChain(config))
+                .to_posted(0, MessageKind::Protected)],
+        },
+        action: MessageAction::Accept,
+    };
+    let block = make_first_block(chain_id).with_incoming_bundle(open_chain_bundle.clone());
+    let transfer = SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::root(0),
+        amount: Amount::ONE,
+    };
+
+    // A block with two operations is rejected.
+    let invalid_block = block
+        .clone()
+        .with_operation(transfer.clone())
+        .with_operation(transfer);
+    let result = chain.execute_block(&invalid_block, time, None, None).await;
+    assert_matches!(
+        result,
+        Err(ChainError::TooManyOperations {
+            count: 2,
+            maximum: 1
+        })
+    );
+
+    // So is a block with two incoming bundles.
+    let mut other_bundle = open_chain_bundle;
+    other_bundle.bundle.height = BlockHeight(2);
+    let invalid_block = block.clone().with_incoming_bundle(other_bundle);
+    let result = chain.execute_block(&invalid_block, time, None, None).await;
+    assert_matches!(
+        result,
+        Err(ChainError::TooManyIncomingBundles {
+            count: 2,
+            maximum: 1
+        })
+    );
+
+    // A block within the limits is accepted.
+    chain.execute_block(&block, time, None, None).await.unwrap();
+}
+
 #[tokio::test]
 async fn test_application_permissions() -> anyhow::Result<()> {
     let time = Timestamp::from(0);
@@ -279,3 +371,1395 @@ async fn test_application_permissions() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_duplicate_message_delivery_is_rejected() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Create a mock application.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    // Initialize the chain and register the application in the first block.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+    let first_block = make_first_block(chain_id).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![
+                open_chain_message.to_posted(0, MessageKind::Protected),
+                register_app_message.to_posted(1, MessageKind::Simple),
+            ],
+        },
+        action: MessageAction::Accept,
+    });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    // Another chain sends a message to the application.
+    let sender = ChainId::root(1);
+    let origin = Origin::chain(sender);
+    let app_message = Message::User {
+        application_id,
+        bytes: b"foo".to_vec(),
+    };
+    let bundle = MessageBundle {
+        certificate_hash: CryptoHash::test_hash("sender certificate"),
+        height: BlockHeight(5),
+        transaction_index: 2,
+        timestamp: time,
+        messages: vec![app_message.to_posted(0, MessageKind::Simple)],
+    };
+    let incoming_bundle = IncomingBundle {
+        origin: origin.clone(),
+        bundle: bundle.clone(),
+        action: MessageAction::Accept,
+    };
+    chain
+        .receive_message_bundle(&origin, bundle.clone(), time, true)
+        .await?;
+
+    // The message is consumed and executed exactly once, with its full ID.
+    let expected_message_id = MessageId {
+        chain_id: sender,
+        height: BlockHeight(5),
+        index: 0,
+    };
+    application.expect_call(ExpectedCall::execute_message(move |_, context, _| {
+        assert_eq!(context.message_id, expected_message_id);
+        Ok(())
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+    let block = make_child_block(&value).with_incoming_bundle(incoming_bundle.clone());
+    chain
+        .remove_bundles_from_inboxes(block.timestamp, &block.incoming_bundles)
+        .await?;
+    chain.execute_block(&block, time, None, None).await?;
+    application.assert_no_more_expected_calls();
+
+    // Delivering the same bundle again is rejected by the inbox.
+    let result = chain
+        .receive_message_bundle(&origin, bundle, time, true)
+        .await;
+    assert_matches!(result, Err(ChainError::InternalError(_)));
+
+    // Including the same bundle in a later block is rejected before reaching the application.
+    let result = chain
+        .remove_bundles_from_inboxes(block.timestamp, &[incoming_bundle])
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::IncorrectMessageOrder { chain_id: id, next_height, next_index, .. })
+            if id == chain_id && next_height == BlockHeight(5) && next_index == 3
+    );
+    application.assert_no_more_expected_calls();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parked_message_can_be_retried() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Create a mock application.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    // Initialize the chain and register the application in the first block.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+    let first_block = make_first_block(chain_id).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![
+                open_chain_message.to_posted(0, MessageKind::Protected),
+                register_app_message.to_posted(1, MessageKind::Simple),
+            ],
+        },
+        action: MessageAction::Accept,
+    });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    // Another chain sends a poisoned message to the application, followed by a valid one.
+    let sender = ChainId::root(1);
+    let origin = Origin::chain(sender);
+    let make_bundle = |height, bytes: &[u8]| MessageBundle {
+        certificate_hash: CryptoHash::test_hash("sender certificate"),
+        height: BlockHeight(height),
+        transaction_index: 0,
+        timestamp: time,
+        messages: vec![Message::User {
+            application_id,
+            bytes: bytes.to_vec(),
+        }
+        .to_posted(0, MessageKind::Simple)],
+    };
+    let poisoned_bundle = make_bundle(5, b"poisoned");
+    let valid_bundle = make_bundle(6, b"valid");
+    for bundle in [&poisoned_bundle, &valid_bundle] {
+        chain
+            .receive_message_bundle(&origin, bundle.clone(), time, true)
+            .await?;
+    }
+    let poisoned_message_id = MessageId {
+        chain_id: sender,
+        height: BlockHeight(5),
+        index: 0,
+    };
+
+    // The poisoned message is parked, and the next one is still executed.
+    application.expect_call(ExpectedCall::execute_message(|_, _, bytes| {
+        assert_eq!(bytes, b"valid");
+        Ok(())
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+    let block = make_child_block(&value)
+        .with_incoming_bundle(IncomingBundle {
+            origin: origin.clone(),
+            bundle: poisoned_bundle,
+            action: MessageAction::Park,
+        })
+        .with_incoming_bundle(IncomingBundle {
+            origin,
+            bundle: valid_bundle,
+            action: MessageAction::Accept,
+        });
+    chain
+        .remove_bundles_from_inboxes(block.timestamp, &block.incoming_bundles)
+        .await?;
+    let executed_block = chain
+        .execute_block(&block, time, None, None)
+        .await?
+        .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    application.assert_no_more_expected_calls();
+    let dead_letters = &chain.execution_state.system.dead_letters;
+    assert_eq!(dead_letters.indices().await?, vec![poisoned_message_id]);
+
+    // Once the application is fixed, the parked message can be retried by an operation.
+    application.expect_call(ExpectedCall::execute_message(move |_, context, bytes| {
+        assert_eq!(context.message_id, poisoned_message_id);
+        assert_eq!(bytes, b"poisoned");
+        Ok(())
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+    let retry = Operation::System(SystemOperation::RetryDeadLetter {
+        message_id: poisoned_message_id,
+    });
+    let block = make_child_block(&value).with_operation(retry);
+    chain.execute_block(&block, time, None, None).await?;
+    application.assert_no_more_expected_calls();
+    assert!(chain
+        .execution_state
+        .system
+        .dead_letters
+        .indices()
+        .await?
+        .is_empty());
+
+    // A message can only be retried once.
+    let result = chain.execute_block(&block, time, None, None).await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(0)))
+            if matches!(
+                *error,
+                ExecutionError::SystemError(SystemExecutionError::DeadLetterNotFound(id))
+                    if id == poisoned_message_id
+            )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_protected_message_cannot_be_parked() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Initialize the chain.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let first_block = make_first_block(chain_id).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![open_chain_message.to_posted(0, MessageKind::Protected)],
+        },
+        action: MessageAction::Accept,
+    });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    // Another chain sends a protected message, which the block tries to park.
+    let (app_description, _, _) = make_app_description();
+    let sender = ChainId::root(1);
+    let block = make_child_block(&value).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(sender),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("sender certificate"),
+            height: BlockHeight(5),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![Message::User {
+                application_id: ApplicationId::from(&app_description),
+                bytes: b"protected".to_vec(),
+            }
+            .to_posted(0, MessageKind::Protected)],
+        },
+        action: MessageAction::Park,
+    });
+    let result = chain.execute_block(&block, time, None, None).await;
+    assert_matches!(
+        result,
+        Err(ChainError::CannotParkMessage { chain_id: id, .. }) if id == chain_id
+    );
+    assert!(chain
+        .execution_state
+        .system
+        .dead_letters
+        .indices()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_message_is_bounced() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+    let owner = Owner::from(PublicKey::test_key(0));
+
+    // Create a mock application.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    // Initialize the chain and register the application in the first block.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+    let first_block = make_first_block(chain_id)
+        .with_authenticated_signer(Some(owner))
+        .with_incoming_bundle(IncomingBundle {
+            origin: Origin::chain(admin_id()),
+            bundle: MessageBundle {
+                certificate_hash: CryptoHash::test_hash("certificate"),
+                height: BlockHeight(1),
+                transaction_index: 0,
+                timestamp: time,
+                messages: vec![
+                    open_chain_message.to_posted(0, MessageKind::Protected),
+                    register_app_message.to_posted(1, MessageKind::Simple),
+                ],
+            },
+            action: MessageAction::Accept,
+        });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    // Another chain sends three messages to the application. The second one is tracked.
+    let sender = ChainId::root(1);
+    let origin = Origin::chain(sender);
+    let make_bundle = |height, bytes: &[u8], kind| MessageBundle {
+        certificate_hash: CryptoHash::test_hash("sender certificate"),
+        height: BlockHeight(height),
+        transaction_index: 0,
+        timestamp: time,
+        messages: vec![Message::User {
+            application_id,
+            bytes: bytes.to_vec(),
+        }
+        .to_posted(0, kind)],
+    };
+    let bundles = [
+        make_bundle(5, b"first", MessageKind::Simple),
+        make_bundle(6, b"second", MessageKind::Tracked),
+        make_bundle(7, b"third", MessageKind::Simple),
+    ];
+    for bundle in &bundles {
+        chain
+            .receive_message_bundle(&origin, bundle.clone(), time, true)
+            .await?;
+    }
+    let rejected_message_id = MessageId {
+        chain_id: sender,
+        height: BlockHeight(6),
+        index: 0,
+    };
+    let reject = SystemOperation::RejectMessage {
+        message_id: rejected_message_id,
+    };
+
+    // Only chain owners can reject messages.
+    let block = make_child_block(&value)
+        .with_authenticated_signer(Some(Owner::from(PublicKey::test_key(1))))
+        .with_operation(reject.clone());
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(0)))
+            if matches!(
+                *error,
+                ExecutionError::SystemError(SystemExecutionError::UnauthorizedMessageRejection)
+            )
+    );
+
+    // Only messages waiting in an inbox can be rejected.
+    let unknown_message_id = MessageId {
+        chain_id: sender,
+        height: BlockHeight(8),
+        index: 0,
+    };
+    let block = make_child_block(&value).with_operation(SystemOperation::RejectMessage {
+        message_id: unknown_message_id,
+    });
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::RejectedMessageNotPending(id)) if id == unknown_message_id
+    );
+
+    // The owner rejects the second and third messages, which are still pending.
+    let block = make_child_block(&value)
+        .with_operation(reject)
+        .with_operation(SystemOperation::RejectMessage {
+            message_id: MessageId {
+                chain_id: sender,
+                height: BlockHeight(7),
+                index: 0,
+            },
+        });
+    let executed_block = chain
+        .execute_block(&block, time, None, None)
+        .await?
+        .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    let rejected = chain
+        .pending_messages()
+        .await?
+        .into_iter()
+        .map(|message| message.rejected)
+        .collect::<Vec<_>>();
+    assert_eq!(rejected, [false, true, true]);
+
+    // When the first two bundles are accepted, the first message is executed and the
+    // rejected one is bounced back to the sender. The third bundle is rejected as a whole.
+    application.expect_call(ExpectedCall::execute_message(|_, _, bytes| {
+        assert_eq!(bytes, b"first");
+        Ok(())
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+    let mut block = make_child_block(&value);
+    for (bundle, action) in bundles.into_iter().zip([
+        MessageAction::Accept,
+        MessageAction::Accept,
+        MessageAction::Reject,
+    ]) {
+        block = block.with_incoming_bundle(IncomingBundle {
+            origin: origin.clone(),
+            bundle,
+            action,
+        });
+    }
+    chain
+        .remove_bundles_from_inboxes(block.timestamp, &block.incoming_bundles)
+        .await?;
+    let outcome = chain.execute_block(&block, time, None, None).await?;
+    application.assert_no_more_expected_calls();
+    assert!(outcome.messages[0].is_empty());
+    assert_matches!(
+        &outcome.messages[1][..],
+        [OutgoingMessage {
+            destination: Destination::Recipient(recipient),
+            kind: MessageKind::Bouncing,
+            ..
+        }] if *recipient == sender
+    );
+    assert!(outcome.messages[2].is_empty());
+    // Both marks are dropped once their bundles are processed.
+    assert!(chain
+        .execution_state
+        .system
+        .rejected_messages
+        .indices()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+/// Executes a block in which a new chain accepts a message carrying `grant` from another
+/// chain. Executing the message costs one token with the prices used here.
+async fn execute_message_with_grant(
+    grant: Amount,
+    burn_unused_grants: bool,
+) -> anyhow::Result<Result<BlockExecutionOutcome, ChainError>> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Create a mock application, whose message handler consumes 1000 units of fuel.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+    application.expect_call(ExpectedCall::execute_message(|runtime, _, _| {
+        runtime.consume_fuel(1_000)
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    // Initialize the chain with a policy charging one token per 1000 units of fuel.
+    let mut config = make_open_chain_config();
+    config.committees.insert(
+        Epoch::ZERO,
+        Committee::new(
+            BTreeMap::from([(
+                ValidatorName(PublicKey::test_key(1)),
+                ValidatorState {
+                    network_address: PublicKey::test_key(1).to_string(),
+                    votes: 1,
+                },
+            )]),
+            ResourceControlPolicy {
+                fuel_unit: Amount::from_millis(1),
+                burn_unused_grants,
+                ..ResourceControlPolicy::default()
+            },
+        ),
+    );
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+
+    // The sender pays for the execution of its message with a grant.
+    let sender = ChainId::root(1);
+    let mut posted_message = Message::User {
+        application_id,
+        bytes: b"message".to_vec(),
+    }
+    .to_posted(0, MessageKind::Tracked);
+    posted_message.grant = grant;
+    posted_message.refund_grant_to = Some(Account::chain(sender));
+
+    let block = make_first_block(chain_id)
+        .with_incoming_bundle(IncomingBundle {
+            origin: Origin::chain(admin_id()),
+            bundle: MessageBundle {
+                certificate_hash: CryptoHash::test_hash("certificate"),
+                height: BlockHeight(1),
+                transaction_index: 0,
+                timestamp: time,
+                messages: vec![
+                    open_chain_message.to_posted(0, MessageKind::Protected),
+                    register_app_message.to_posted(1, MessageKind::Simple),
+                ],
+            },
+            action: MessageAction::Accept,
+        })
+        .with_incoming_bundle(IncomingBundle {
+            origin: Origin::chain(sender),
+            bundle: MessageBundle {
+                certificate_hash: CryptoHash::test_hash("sender certificate"),
+                height: BlockHeight(0),
+                transaction_index: 0,
+                timestamp: time,
+                messages: vec![posted_message],
+            },
+            action: MessageAction::Accept,
+        });
+    Ok(chain.execute_block(&block, time, None, None).await)
+}
+
+/// Returns the amounts refunded to `ChainId::root(1)` by the block.
+fn refunds(outcome: &BlockExecutionOutcome) -> Vec<Amount> {
+    outcome
+        .messages
+        .iter()
+        .flatten()
+        .filter_map(|message| match &message.message {
+            Message::System(SystemMessage::Credit { amount, .. })
+                if message.destination == Destination::Recipient(ChainId::root(1)) =>
+            {
+                Some(*amount)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_underfunded_message_grant_fails() -> anyhow::Result<()> {
+    // The grant does not cover the execution, and the receiving chain doesn't pay for it.
+    let result = execute_message_with_grant(Amount::from_millis(500), false).await?;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::IncomingBundle(1)))
+            if matches!(
+                *error,
+                ExecutionError::SystemError(SystemExecutionError::InsufficientFundingForFees {
+                    ..
+                })
+            )
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unused_message_grant_is_refunded() -> anyhow::Result<()> {
+    let outcome = execute_message_with_grant(Amount::from_tokens(3), false).await??;
+    assert_eq!(refunds(&outcome), vec![Amount::from_tokens(2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unused_message_grant_can_be_burned() -> anyhow::Result<()> {
+    let outcome = execute_message_with_grant(Amount::from_tokens(3), true).await??;
+    assert!(refunds(&outcome).is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_due_scheduled_messages_are_mandatory() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let due = Timestamp::from(1_000);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    // Create a mock application.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    // Initialize the chain and register the application in the first block.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+    let first_block = make_first_block(chain_id).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![
+                open_chain_message.to_posted(0, MessageKind::Protected),
+                register_app_message.to_posted(1, MessageKind::Simple),
+            ],
+        },
+        action: MessageAction::Accept,
+    });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    // The chain receives the message its application scheduled to itself in block 1.
+    let origin = Origin::chain(chain_id);
+    let bundle = MessageBundle {
+        certificate_hash: CryptoHash::test_hash("scheduling certificate"),
+        height: BlockHeight(1),
+        transaction_index: 0,
+        timestamp: time,
+        messages: vec![Message::System(SystemMessage::ScheduleMessage {
+            due,
+            application_id,
+            bytes: b"timeout".to_vec(),
+        })
+        .to_posted(0, MessageKind::Protected)],
+    };
+    chain
+        .receive_message_bundle(&origin, bundle.clone(), time, true)
+        .await?;
+    let scheduled_message_id = MessageId {
+        chain_id,
+        height: BlockHeight(1),
+        index: 0,
+    };
+    // The message is only scheduled once a block executes it.
+    assert!(chain
+        .execution_state
+        .system
+        .scheduled_messages
+        .indices()
+        .await?
+        .is_empty());
+
+    // Proposals must receive the bundle.
+    let empty_block = make_child_block(&value);
+    assert_matches!(
+        chain
+            .validate_schedule_message_bundles(&empty_block)
+            .await,
+        Err(ChainError::MissingScheduleMessageBundle { height, .. }) if height == BlockHeight(1)
+    );
+    let block = empty_block.with_incoming_bundle(IncomingBundle {
+        origin,
+        bundle,
+        action: MessageAction::Accept,
+    });
+    chain
+        .remove_bundles_from_inboxes(block.timestamp, &block.incoming_bundles)
+        .await?;
+    chain.validate_schedule_message_bundles(&block).await?;
+    let executed_block = chain
+        .execute_block(&block, time, None, None)
+        .await?
+        .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    assert_eq!(
+        chain
+            .execution_state
+            .system
+            .due_scheduled_messages(due)
+            .await?,
+        vec![scheduled_message_id]
+    );
+
+    // Before it is due, blocks don't need to execute it.
+    let early_block = make_child_block(&value).with_timestamp(Timestamp::from(999));
+    chain
+        .clone_unchecked()?
+        .execute_block(&early_block, time, None, None)
+        .await?;
+
+    // Once it is due, a block without it is rejected.
+    let late_block = make_child_block(&value).with_timestamp(due);
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&late_block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::MissingScheduledMessages(message_ids))
+            if message_ids == vec![scheduled_message_id]
+    );
+
+    // Chain owners can park it instead, e.g. if it fails.
+    let park = SystemOperation::ParkScheduledMessage {
+        message_id: scheduled_message_id,
+    };
+    let block = late_block.clone().with_operation(park.clone());
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(0)))
+            if matches!(
+                *error,
+                ExecutionError::SystemError(
+                    SystemExecutionError::UnauthorizedScheduledMessageParking
+                )
+            )
+    );
+    let mut parking_chain = chain.clone_unchecked()?;
+    let parking_block = late_block
+        .clone()
+        .with_authenticated_signer(Some(Owner::from(PublicKey::test_key(0))))
+        .with_operation(park);
+    parking_chain
+        .execute_block(&parking_block, time, None, None)
+        .await?;
+    let system = &parking_chain.execution_state.system;
+    assert!(system.scheduled_messages.indices().await?.is_empty());
+    assert!(system.due_scheduled_messages(due).await?.is_empty());
+    assert_eq!(
+        system.dead_letters.indices().await?,
+        vec![scheduled_message_id]
+    );
+
+    // A block executing it delivers the message to the application.
+    application.expect_call(ExpectedCall::execute_message(move |_, context, bytes| {
+        assert_eq!(context.message_id, scheduled_message_id);
+        assert_eq!(bytes, b"timeout");
+        Ok(())
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+    let block = late_block.with_operation(SystemOperation::ExecuteScheduledMessage {
+        message_id: scheduled_message_id,
+    });
+    chain.execute_block(&block, time, None, None).await?;
+    application.assert_no_more_expected_calls();
+    assert!(chain
+        .execution_state
+        .system
+        .scheduled_messages
+        .indices()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_application_allowlist() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+    let owner = Owner::from(PublicKey::test_key(0));
+
+    // Create a mock application, and another application from the same bytecode.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let other_app_description = UserApplicationDescription {
+        creation: make_admin_message_id(BlockHeight(4)),
+        ..app_description.clone()
+    };
+    let other_application_id = ApplicationId::from(&other_app_description);
+    let third_app_description = UserApplicationDescription {
+        creation: make_admin_message_id(BlockHeight(5)),
+        ..app_description.clone()
+    };
+    let third_application_id = ApplicationId::from(&third_app_description);
+    let application = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    // Initialize the chain and register the application in the first block.
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description],
+    };
+    let first_block = make_first_block(chain_id)
+        .with_authenticated_signer(Some(owner))
+        .with_incoming_bundle(IncomingBundle {
+            origin: Origin::chain(admin_id()),
+            bundle: MessageBundle {
+                certificate_hash: CryptoHash::test_hash("certificate"),
+                height: BlockHeight(1),
+                transaction_index: 0,
+                timestamp: time,
+                messages: vec![
+                    open_chain_message.to_posted(0, MessageKind::Protected),
+                    register_app_message.to_posted(1, MessageKind::Simple),
+                ],
+            },
+            action: MessageAction::Accept,
+        });
+    let executed_block = chain
+        .execute_block(&first_block, time, None, None)
+        .await?
+        .with(first_block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+
+    let app_operation = Operation::User {
+        application_id,
+        bytes: b"foo".to_vec(),
+    };
+    let only_other_application = ApplicationAllowlist {
+        applications: BTreeSet::from([other_application_id]),
+        bytecodes: BTreeSet::new(),
+    };
+
+    // Only chain owners can change the allowlist.
+    let block = make_child_block(&value)
+        .with_authenticated_signer(Some(Owner::from(PublicKey::test_key(1))))
+        .with_operation(SystemOperation::ChangeApplicationAllowlist(Some(
+            only_other_application.clone(),
+        )));
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(0)))
+            if matches!(
+                *error,
+                ExecutionError::SystemError(SystemExecutionError::UnauthorizedAllowlistChange)
+            )
+    );
+
+    // The application can execute until the allowlist changes, in the same block.
+    application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    application.expect_call(ExpectedCall::default_finalize());
+    let block = make_child_block(&value)
+        .with_operation(app_operation.clone())
+        .with_operation(SystemOperation::ChangeApplicationAllowlist(Some(
+            only_other_application.clone(),
+        )))
+        .with_operation(app_operation.clone());
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(2)))
+            if matches!(
+                *error,
+                ExecutionError::ApplicationNotPermitted(ref id) if **id == application_id
+            )
+    );
+    application.assert_no_more_expected_calls();
+
+    // Once the allowlist is changed, the application can't execute in later blocks.
+    let block = make_child_block(&value).with_operation(
+        SystemOperation::ChangeApplicationAllowlist(Some(only_other_application)),
+    );
+    let executed_block = chain
+        .execute_block(&block, time, None, None)
+        .await?
+        .with(block);
+    let value = Hashed::new(ConfirmedBlock::new(executed_block));
+    let block = make_child_block(&value).with_operation(app_operation.clone());
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::Operation(0)))
+            if matches!(
+                *error,
+                ExecutionError::ApplicationNotPermitted(ref id) if **id == application_id
+            )
+    );
+
+    // Applications that are not allowed can't be registered from other chains either.
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![third_app_description],
+    };
+    let block = make_child_block(&value).with_incoming_bundle(IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("other certificate"),
+            height: BlockHeight(2),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![register_app_message.to_posted(0, MessageKind::Simple)],
+        },
+        action: MessageAction::Accept,
+    });
+    let result = chain
+        .clone_unchecked()?
+        .execute_block(&block, time, None, None)
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainError::ExecutionError(error, ChainExecutionContext::IncomingBundle(0)))
+            if matches!(
+                *error,
+                ExecutionError::ApplicationNotPermitted(ref id) if **id == third_application_id
+            )
+    );
+
+    // Allowing the bytecode allows all its applications again.
+    application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    application.expect_call(ExpectedCall::default_finalize());
+    let block = make_child_block(&value)
+        .with_operation(SystemOperation::ChangeApplicationAllowlist(Some(
+            ApplicationAllowlist {
+                applications: BTreeSet::new(),
+                bytecodes: BTreeSet::from([application_id.bytecode_id]),
+            },
+        )))
+        .with_operation(app_operation);
+    chain.execute_block(&block, time, None, None).await?;
+    application.assert_no_more_expected_calls();
+
+    Ok(())
+}
+
+/// Appends the hashes of `count` blocks to the confirmed log of the `chain`, returning them
+/// with their heights.
+fn push_test_blocks(
+    chain: &mut ChainStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    count: u64,
+) -> Vec<(BlockHeight, CryptoHash)> {
+    (0..count)
+        .map(|height| {
+            let hash = CryptoHash::test_hash(format!("block {height}"));
+            chain.confirmed_log.push(hash);
+            (BlockHeight(height), hash)
+        })
+        .collect()
+}
+
+/// Reads the whole block history of the `chain`, one page of at most `limit` blocks at a time,
+/// returning the blocks and the number of pages.
+async fn read_block_history(
+    chain: &ChainStateView<MemoryContext<TestExecutionRuntimeContext>>,
+    limit: usize,
+    direction: BlockHistoryDirection,
+    application_id: Option<UserApplicationId>,
+) -> anyhow::Result<(Vec<(BlockHeight, CryptoHash)>, usize)> {
+    let mut blocks = Vec::new();
+    let mut from_height = None;
+    let mut pages = 0;
+    loop {
+        let page = chain
+            .block_history(from_height, limit, direction, application_id)
+            .await?;
+        assert!(page.blocks.len() <= limit);
+        blocks.extend(page.blocks);
+        pages += 1;
+        match page.next_height {
+            Some(next_height) => from_height = Some(next_height),
+            None => return Ok((blocks, pages)),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_block_history_pagination() -> anyhow::Result<()> {
+    let chain_id = ChainId::root(0);
+    let mut chain = ChainStateView::new(chain_id).await;
+    let mut blocks = push_test_blocks(&mut chain, 1000);
+
+    let (forward, pages) =
+        read_block_history(&chain, 100, BlockHistoryDirection::Forward, None).await?;
+    assert_eq!(forward, blocks);
+    assert_eq!(pages, 10);
+
+    blocks.reverse();
+    let (backward, pages) =
+        read_block_history(&chain, 64, BlockHistoryDirection::Backward, None).await?;
+    assert_eq!(backward, blocks);
+    assert_eq!(pages, 16);
+
+    // A page starts at the given height, in both directions.
+    let page = chain
+        .block_history(
+            Some(BlockHeight(500)),
+            3,
+            BlockHistoryDirection::Forward,
+            None,
+        )
+        .await?;
+    let heights = page
+        .blocks
+        .iter()
+        .map(|(height, _)| height.0)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [500, 501, 502]);
+    assert_eq!(page.next_height, Some(BlockHeight(503)));
+    let page = chain
+        .block_history(
+            Some(BlockHeight(500)),
+            3,
+            BlockHistoryDirection::Backward,
+            None,
+        )
+        .await?;
+    let heights = page
+        .blocks
+        .iter()
+        .map(|(height, _)| height.0)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [500, 499, 498]);
+    assert_eq!(page.next_height, Some(BlockHeight(497)));
+
+    // Heights past the tip are clamped.
+    let page = chain
+        .block_history(
+            Some(BlockHeight(5000)),
+            2,
+            BlockHistoryDirection::Backward,
+            None,
+        )
+        .await?;
+    let heights = page
+        .blocks
+        .iter()
+        .map(|(height, _)| height.0)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [999, 998]);
+    let page = chain
+        .block_history(
+            Some(BlockHeight(1000)),
+            2,
+            BlockHistoryDirection::Forward,
+            None,
+        )
+        .await?;
+    assert!(page.blocks.is_empty());
+    assert_eq!(page.next_height, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_history_application_filter() -> anyhow::Result<()> {
+    let chain_id = ChainId::root(0);
+    let mut chain = ChainStateView::new(chain_id).await;
+    let blocks = push_test_blocks(&mut chain, 1000);
+    let (description, _, _) = make_app_description();
+    let first_app_id = UserApplicationId::from(&description);
+    let second_app_id = ApplicationId {
+        creation: make_admin_message_id(BlockHeight(5)),
+        ..first_app_id
+    };
+    let unused_app_id = ApplicationId {
+        creation: make_admin_message_id(BlockHeight(7)),
+        ..first_app_id
+    };
+    let user_operation = |application_id| Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+
+    // The first application has operations in every third block, the second one has two
+    // operations in every seventh block, and the other blocks only have system operations.
+    for height in 0..1000 {
+        let mut block = make_first_block(chain_id).with_simple_transfer(chain_id, Amount::ONE);
+        block.height = BlockHeight(height);
+        if height % 3 == 0 {
+            block = block.with_operation(user_operation(first_app_id));
+        }
+        if height % 7 == 0 {
+            block = block
+                .with_operation(user_operation(second_app_id))
+                .with_operation(user_operation(second_app_id));
+        }
+        chain.index_block_applications(&block).await?;
+    }
+
+    let expected = blocks
+        .iter()
+        .filter(|(height, _)| height.0 % 3 == 0)
+        .copied()
+        .collect::<Vec<_>>();
+    let (forward, pages) = read_block_history(
+        &chain,
+        50,
+        BlockHistoryDirection::Forward,
+        Some(first_app_id),
+    )
+    .await?;
+    assert_eq!(forward, expected);
+    assert_eq!(pages, 7);
+
+    let expected = blocks
+        .iter()
+        .rev()
+        .filter(|(height, _)| height.0 % 7 == 0)
+        .copied()
+        .collect::<Vec<_>>();
+    let (backward, _) = read_block_history(
+        &chain,
+        10,
+        BlockHistoryDirection::Backward,
+        Some(second_app_id),
+    )
+    .await?;
+    assert_eq!(backward, expected);
+
+    // Pages start at the first matching block at or beyond the given height.
+    let page = chain
+        .block_history(
+            Some(BlockHeight(500)),
+            3,
+            BlockHistoryDirection::Backward,
+            Some(second_app_id),
+        )
+        .await?;
+    let heights = page
+        .blocks
+        .iter()
+        .map(|(height, _)| height.0)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [497, 490, 483]);
+    assert_eq!(page.next_height, Some(BlockHeight(476)));
+    let page = chain
+        .block_history(
+            Some(BlockHeight(500)),
+            3,
+            BlockHistoryDirection::Forward,
+            Some(second_app_id),
+        )
+        .await?;
+    let heights = page
+        .blocks
+        .iter()
+        .map(|(height, _)| height.0)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [504, 511, 518]);
+    assert_eq!(page.next_height, Some(BlockHeight(525)));
+
+    let page = chain
+        .block_history(
+            None,
+            10,
+            BlockHistoryDirection::Backward,
+            Some(unused_app_id),
+        )
+        .await?;
+    assert!(page.blocks.is_empty());
+    assert_eq!(page.next_height, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_query_context_reports_the_chain_tip() -> anyhow::Result<()> {
+    let chain_id = ChainId::root(0);
+    let mut chain = ChainStateView::new(chain_id).await;
+    chain
+        .execution_state
+        .system
+        .description
+        .set(Some(ChainDescription::Root(0)));
+    let (application_id, application) = chain.execution_state.register_mock_application().await?;
+
+    let tip_hash = CryptoHash::test_hash("tip");
+    let tip_timestamp = Timestamp::from(1_000);
+    chain.tip_state.get_mut().next_block_height = BlockHeight(3);
+    chain.tip_state.get_mut().block_hash = Some(tip_hash);
+    chain.execution_state.system.timestamp.set(tip_timestamp);
+
+    // The service echoes the context it was queried with.
+    application.expect_call(ExpectedCall::handle_query(|runtime, context, _query| {
+        assert_eq!(runtime.query_context()?, context);
+        Ok(bcs::to_bytes(&(
+            context.next_block_height,
+            context.block_hash,
+            context.block_timestamp,
+        ))
+        .unwrap())
+    }));
+
+    let query = Query::User {
+        application_id,
+        bytes: vec![],
+        authenticated_signer: None,
+    };
+    let outcome = chain
+        .query_application(Timestamp::from(2_000), query, None)
+        .await?;
+    let QueryResponse::User(bytes) = outcome.response else {
+        panic!(
+            "Unexpected response to a user query: {:?}",
+            outcome.response
+        );
+    };
+    let (next_block_height, block_hash, block_timestamp): (
+        BlockHeight,
+        Option<CryptoHash>,
+        Timestamp,
+    ) = bcs::from_bytes(&bytes)?;
+    assert_eq!(next_block_height, BlockHeight(3));
+    assert_eq!(block_hash, Some(tip_hash));
+    assert_eq!(block_timestamp, tip_timestamp);
+    application.assert_no_more_expected_calls();
+
+    Ok(())
+}
+
+/// Returns a request to send `bytes` to `ChainId::root(1)`.
+fn send_message_request(authenticated: bool, bytes: &[u8]) -> SendMessageRequest<Vec<u8>> {
+    SendMessageRequest {
+        destination: Destination::Recipient(ChainId::root(1)),
+        authenticated,
+        is_tracked: false,
+        grant: Resources::default(),
+        message: bytes.to_vec(),
+    }
+}
+
+/// Tests that the signer of a block is forwarded only with the messages that an application
+/// sends as authenticated, and never with the messages of an application that was called
+/// without authentication.
+#[tokio::test]
+async fn test_authenticated_signer_forwarding() -> anyhow::Result<()> {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+    let owner = Owner::from(PublicKey::test_key(0));
+
+    // Create an application, and another one it calls without authentication.
+    let (app_description, contract_blob, service_blob) = make_app_description();
+    let mut callee_description = app_description.clone();
+    callee_description.creation.index = 1;
+    let application_id = ApplicationId::from(&app_description);
+    let callee_id = ApplicationId::from(&callee_description);
+    let application = MockApplication::default();
+    let callee = MockApplication::default();
+    let extra = &chain.context().extra();
+    extra
+        .user_contracts()
+        .insert(application_id, application.clone().into());
+    extra
+        .user_contracts()
+        .insert(callee_id, callee.clone().into());
+    extra.add_blobs([contract_blob, service_blob]).await?;
+
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await?;
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let register_app_message = SystemMessage::RegisterApplications {
+        applications: vec![app_description, callee_description],
+    };
+    let bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: time,
+            messages: vec![
+                open_chain_message.to_posted(0, MessageKind::Protected),
+                register_app_message.to_posted(1, MessageKind::Simple),
+            ],
+        },
+        action: MessageAction::Accept,
+    };
+
+    application.expect_call(ExpectedCall::execute_operation(move |runtime, _, _| {
+        runtime.send_message(send_message_request(true, b"authenticated"))?;
+        runtime.send_message(send_message_request(false, b"anonymous"))?;
+        runtime.try_call_application(/* authenticated */ false, callee_id, vec![])?;
+        Ok(vec![])
+    }));
+    // The callee can request authentication, but doesn't know who signed the block.
+    callee.expect_call(ExpectedCall::execute_operation(|runtime, _, _| {
+        assert_eq!(runtime.authenticated_signer()?, None);
+        runtime.send_message(send_message_request(true, b"spoofed"))?;
+        Ok(vec![])
+    }));
+    callee.expect_call(ExpectedCall::default_finalize());
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let block = make_first_block(chain_id)
+        .with_incoming_bundle(bundle)
+        .with_operation(Operation::User {
+            application_id,
+            bytes: vec![],
+        })
+        .with_authenticated_signer(Some(owner));
+    let outcome = chain.execute_block(&block, time, None, None).await?;
+
+    let signers = outcome
+        .messages
+        .iter()
+        .flatten()
+        .filter_map(|message| match &message.message {
+            Message::User { bytes, .. } => Some((bytes.clone(), message.authenticated_signer)),
+            _ => None,
+        })
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(
+        signers,
+        BTreeMap::from([
+            (b"authenticated".to_vec(), Some(owner)),
+            (b"anonymous".to_vec(), None),
+            (b"spoofed".to_vec(), None),
+        ])
+    );
+    application.assert_no_more_expected_calls();
+    callee.assert_no_more_expected_calls();
+
+    Ok(())
+}
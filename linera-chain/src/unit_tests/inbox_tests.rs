@@ -338,3 +338,77 @@ async fn test_inbox_add_then_remove_mixed() {
     assert_eq!(view.added_bundles.count(), 0);
     assert_eq!(view.removed_bundles.count(), 0);
 }
+
+#[tokio::test]
+async fn test_inbox_bundle_state() {
+    let hash = CryptoHash::test_hash("1");
+    let mut view = InboxStateView::new().await;
+    let height = BlockHeight::from(1);
+    assert_eq!(view.bundle_state(height, 0), InboxBundleState::NotReceived);
+
+    // Receive the bundle.
+    assert!(view.add_bundle(make_bundle(hash, 1, 0, [0])).await.unwrap());
+    assert_eq!(view.bundle_state(height, 0), InboxBundleState::Pending);
+    assert_eq!(view.bundle_state(height, 1), InboxBundleState::NotReceived);
+
+    // Execute the bundle.
+    assert!(view
+        .remove_bundle(&make_bundle(hash, 1, 0, [0]))
+        .await
+        .unwrap());
+    assert_eq!(view.bundle_state(height, 0), InboxBundleState::Removed);
+
+    // Execute a bundle by anticipation.
+    assert!(!view
+        .remove_bundle(&make_bundle(hash, 2, 0, [1]))
+        .await
+        .unwrap());
+    assert_eq!(
+        view.bundle_state(BlockHeight::from(2), 0),
+        InboxBundleState::Removed
+    );
+}
+
+#[tokio::test]
+async fn test_inbox_removal_height() {
+    let hash = CryptoHash::test_hash("1");
+    let mut view = InboxStateView::new().await;
+    for (height, index) in [(1, 0), (1, 1), (2, 0), (4, 0)] {
+        assert!(view
+            .add_bundle(make_bundle(hash, height, index, [0]))
+            .await
+            .unwrap());
+    }
+    assert_eq!(
+        view.removal_height(BlockHeight::from(1), 0).await.unwrap(),
+        None
+    );
+
+    // The block at height 5 removes the first two bundles, and the one at height 8 the next.
+    for index in [0, 1] {
+        assert!(view
+            .remove_bundle(&make_bundle(hash, 1, index, [0]))
+            .await
+            .unwrap());
+    }
+    view.record_removal(BlockHeight::from(5));
+    assert!(view
+        .remove_bundle(&make_bundle(hash, 2, 0, [0]))
+        .await
+        .unwrap());
+    view.record_removal(BlockHeight::from(8));
+
+    for (height, index, removal_height) in [
+        (1, 0, Some(BlockHeight(5))),
+        (1, 1, Some(BlockHeight(5))),
+        (2, 0, Some(BlockHeight(8))),
+        (4, 0, None),
+    ] {
+        assert_eq!(
+            view.removal_height(BlockHeight(height), index)
+                .await
+                .unwrap(),
+            removal_height
+        );
+    }
+}
@@ -14,5 +14,9 @@ fn main() {
         with_indexed_db: { all(web, feature = "indexed-db") },
         with_testing: { any(test, feature = "test") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
+        with_wasm_runtime: { any(
+            feature = "wasmer",
+            all(not(target_arch = "wasm32"), feature = "wasmtime")
+        ) },
     };
 }
@@ -0,0 +1,415 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`KeyValueStore`](linera_views::store::KeyValueStore) over any of the backends
+//! the client is compiled with, so that the backend can be chosen at runtime from the
+//! configuration. The values can be encrypted on top of any of them.
+
+use std::{future::Future, pin::Pin};
+
+#[cfg(feature = "storage-service")]
+use linera_storage_service::{
+    client::ServiceStoreClient,
+    common::{ServiceStoreConfig, ServiceStoreError},
+};
+#[cfg(feature = "dynamodb")]
+use linera_views::dynamo_db::{DynamoDbStore, DynamoDbStoreConfig, DynamoDbStoreError};
+#[cfg(feature = "rocksdb")]
+use linera_views::rocks_db::{RocksDbStore, RocksDbStoreConfig, RocksDbStoreError};
+#[cfg(feature = "scylladb")]
+use linera_views::scylla_db::{ScyllaDbStore, ScyllaDbStoreConfig, ScyllaDbStoreError};
+use linera_views::{
+    batch::Batch,
+    encryption::{EncryptedStore, EncryptedStoreConfig, EncryptionError, ENVELOPE_OVERHEAD},
+    memory::{MemoryStore, MemoryStoreConfig, MemoryStoreError},
+    store::{
+        AdminKeyValueStore, KeyIterable, KeyValueIterable, KeyValueStoreError,
+        ReadableKeyValueStore, WithError, WritableKeyValueStore,
+    },
+};
+use thiserror::Error;
+
+/// The configuration of an [`AnyStore`], i.e. of one of the available backends.
+pub enum AnyStoreConfig {
+    /// The configuration of the memory store.
+    Memory(MemoryStoreConfig),
+    /// The configuration of the storage service.
+    #[cfg(feature = "storage-service")]
+    Service(ServiceStoreConfig),
+    /// The configuration of the RocksDB store.
+    #[cfg(feature = "rocksdb")]
+    RocksDb(RocksDbStoreConfig),
+    /// The configuration of the DynamoDB store.
+    #[cfg(feature = "dynamodb")]
+    DynamoDb(DynamoDbStoreConfig),
+    /// The configuration of the ScyllaDB store.
+    #[cfg(feature = "scylladb")]
+    ScyllaDb(ScyllaDbStoreConfig),
+    /// The configuration of a store with encrypted values.
+    Encrypted(Box<EncryptedStoreConfig<AnyStoreConfig>>),
+}
+
+/// A store using one of the available backends, selected by its [`AnyStoreConfig`].
+#[derive(Clone)]
+pub enum AnyStore {
+    /// The memory store.
+    Memory(MemoryStore),
+    /// The storage service.
+    #[cfg(feature = "storage-service")]
+    Service(ServiceStoreClient),
+    /// The RocksDB store.
+    #[cfg(feature = "rocksdb")]
+    RocksDb(RocksDbStore),
+    /// The DynamoDB store.
+    #[cfg(feature = "dynamodb")]
+    DynamoDb(DynamoDbStore),
+    /// The ScyllaDB store.
+    #[cfg(feature = "scylladb")]
+    ScyllaDb(ScyllaDbStore),
+    /// A store with encrypted values.
+    Encrypted(Box<EncryptedStore<AnyStore>>),
+}
+
+/// The error type for [`AnyStore`].
+#[derive(Error, Debug)]
+pub enum AnyStoreError {
+    /// Serialization error with BCS.
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
+
+    /// An error in the memory store.
+    #[error(transparent)]
+    Memory(#[from] MemoryStoreError),
+
+    /// An error in the storage service.
+    #[cfg(feature = "storage-service")]
+    #[error(transparent)]
+    Service(#[from] ServiceStoreError),
+
+    /// An error in the RocksDB store.
+    #[cfg(feature = "rocksdb")]
+    #[error(transparent)]
+    RocksDb(#[from] RocksDbStoreError),
+
+    /// An error in the DynamoDB store.
+    #[cfg(feature = "dynamodb")]
+    #[error(transparent)]
+    DynamoDb(#[from] DynamoDbStoreError),
+
+    /// An error in the ScyllaDB store.
+    #[cfg(feature = "scylladb")]
+    #[error(transparent)]
+    ScyllaDb(#[from] ScyllaDbStoreError),
+
+    /// An error in the encryption of the values.
+    #[error(transparent)]
+    Encrypted(Box<EncryptionError<AnyStoreError>>),
+}
+
+impl KeyValueStoreError for AnyStoreError {
+    const BACKEND: &'static str = "any_store";
+}
+
+impl From<EncryptionError<AnyStoreError>> for AnyStoreError {
+    fn from(error: EncryptionError<AnyStoreError>) -> Self {
+        AnyStoreError::Encrypted(Box::new(error))
+    }
+}
+
+/// The future of an operation of an encrypted store, whose type is erased since the
+/// encrypted store wraps an [`AnyStore`] in turn.
+type EncryptedFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, EncryptionError<AnyStoreError>>> + Send + 'a>>;
+
+/// Evaluates the asynchronous `$body` with `$store` bound to the store wrapped in the
+/// [`AnyStore`].
+macro_rules! with_store {
+    ($any_store:expr, |$store:ident| $body:expr) => {
+        match $any_store {
+            AnyStore::Memory($store) => Ok($body?),
+            #[cfg(feature = "storage-service")]
+            AnyStore::Service($store) => Ok($body?),
+            #[cfg(feature = "rocksdb")]
+            AnyStore::RocksDb($store) => Ok($body?),
+            #[cfg(feature = "dynamodb")]
+            AnyStore::DynamoDb($store) => Ok($body?),
+            #[cfg(feature = "scylladb")]
+            AnyStore::ScyllaDb($store) => Ok($body?),
+            AnyStore::Encrypted(store) => {
+                let $store = &**store;
+                let future: EncryptedFuture<'_, _> = Box::pin(async move { $body });
+                Ok(future.await?)
+            }
+        }
+    };
+}
+
+/// Evaluates the asynchronous `$body` with `$config` bound to the configuration wrapped in
+/// the [`AnyStoreConfig`] and `$Store` to the type of the corresponding store.
+macro_rules! with_config {
+    ($any_config:expr, |$Store:ident, $config:ident| $body:expr) => {
+        match $any_config {
+            AnyStoreConfig::Memory($config) => {
+                type $Store = MemoryStore;
+                Ok($body?)
+            }
+            #[cfg(feature = "storage-service")]
+            AnyStoreConfig::Service($config) => {
+                type $Store = ServiceStoreClient;
+                Ok($body?)
+            }
+            #[cfg(feature = "rocksdb")]
+            AnyStoreConfig::RocksDb($config) => {
+                type $Store = RocksDbStore;
+                Ok($body?)
+            }
+            #[cfg(feature = "dynamodb")]
+            AnyStoreConfig::DynamoDb($config) => {
+                type $Store = DynamoDbStore;
+                Ok($body?)
+            }
+            #[cfg(feature = "scylladb")]
+            AnyStoreConfig::ScyllaDb($config) => {
+                type $Store = ScyllaDbStore;
+                Ok($body?)
+            }
+            AnyStoreConfig::Encrypted(config) => {
+                type $Store = EncryptedStore<AnyStore>;
+                let $config = &**config;
+                let future: EncryptedFuture<'_, _> = Box::pin(async move { $body });
+                Ok(future.await?)
+            }
+        }
+    };
+}
+
+/// Returns the smaller of two sizes, in a constant context.
+const fn min_size(first: usize, second: usize) -> usize {
+    if first < second {
+        first
+    } else {
+        second
+    }
+}
+
+/// Finds the keys matching the prefix in one of the backends, and copies them.
+async fn find_keys_by_prefix<Store: ReadableKeyValueStore>(
+    store: &Store,
+    key_prefix: &[u8],
+) -> Result<Vec<Vec<u8>>, Store::Error> {
+    let keys = store.find_keys_by_prefix(key_prefix).await?;
+    keys.iterator().map(|key| key.map(<[u8]>::to_vec)).collect()
+}
+
+/// Finds the key-value pairs matching the prefix in one of the backends, and copies them.
+async fn find_key_values_by_prefix<Store: ReadableKeyValueStore>(
+    store: &Store,
+    key_prefix: &[u8],
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Store::Error> {
+    let key_values = store.find_key_values_by_prefix(key_prefix).await?;
+    key_values.into_iterator_owned().collect()
+}
+
+impl WithError for AnyStore {
+    type Error = AnyStoreError;
+}
+
+impl ReadableKeyValueStore for AnyStore {
+    // The smallest size supported by all the available backends.
+    const MAX_KEY_SIZE: usize = {
+        let size = MemoryStore::MAX_KEY_SIZE;
+        #[cfg(feature = "storage-service")]
+        let size = min_size(size, ServiceStoreClient::MAX_KEY_SIZE);
+        #[cfg(feature = "rocksdb")]
+        let size = min_size(size, RocksDbStore::MAX_KEY_SIZE);
+        #[cfg(feature = "dynamodb")]
+        let size = min_size(size, DynamoDbStore::MAX_KEY_SIZE);
+        #[cfg(feature = "scylladb")]
+        let size = min_size(size, ScyllaDbStore::MAX_KEY_SIZE);
+        size
+    };
+
+    // Whether all the available backends sort the results of searches. The encryption doesn't
+    // change the order of the keys, which are not encrypted.
+    const SORTED_SEARCHES: bool = {
+        let sorted = MemoryStore::SORTED_SEARCHES;
+        #[cfg(feature = "storage-service")]
+        let sorted = sorted && ServiceStoreClient::SORTED_SEARCHES;
+        #[cfg(feature = "rocksdb")]
+        let sorted = sorted && RocksDbStore::SORTED_SEARCHES;
+        #[cfg(feature = "dynamodb")]
+        let sorted = sorted && DynamoDbStore::SORTED_SEARCHES;
+        #[cfg(feature = "scylladb")]
+        let sorted = sorted && ScyllaDbStore::SORTED_SEARCHES;
+        sorted
+    };
+
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        match self {
+            AnyStore::Memory(store) => store.max_stream_queries(),
+            #[cfg(feature = "storage-service")]
+            AnyStore::Service(store) => store.max_stream_queries(),
+            #[cfg(feature = "rocksdb")]
+            AnyStore::RocksDb(store) => store.max_stream_queries(),
+            #[cfg(feature = "dynamodb")]
+            AnyStore::DynamoDb(store) => store.max_stream_queries(),
+            #[cfg(feature = "scylladb")]
+            AnyStore::ScyllaDb(store) => store.max_stream_queries(),
+            AnyStore::Encrypted(store) => store.max_stream_queries(),
+        }
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, AnyStoreError> {
+        with_store!(self, |store| store.read_value_bytes(key).await)
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, AnyStoreError> {
+        with_store!(self, |store| store.contains_key(key).await)
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, AnyStoreError> {
+        with_store!(self, |store| store.contains_keys(keys).await)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, AnyStoreError> {
+        with_store!(self, |store| store.read_multi_values_bytes(keys).await)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, AnyStoreError> {
+        with_store!(self, |store| find_keys_by_prefix(store, key_prefix).await)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, AnyStoreError> {
+        with_store!(self, |store| find_key_values_by_prefix(store, key_prefix)
+            .await)
+    }
+}
+
+impl WritableKeyValueStore for AnyStore {
+    // The smallest size supported by all the available backends, leaving room for the
+    // encryption.
+    const MAX_VALUE_SIZE: usize = {
+        let size = MemoryStore::MAX_VALUE_SIZE;
+        #[cfg(feature = "storage-service")]
+        let size = min_size(size, ServiceStoreClient::MAX_VALUE_SIZE);
+        #[cfg(feature = "rocksdb")]
+        let size = min_size(size, RocksDbStore::MAX_VALUE_SIZE);
+        #[cfg(feature = "dynamodb")]
+        let size = min_size(size, DynamoDbStore::MAX_VALUE_SIZE);
+        #[cfg(feature = "scylladb")]
+        let size = min_size(size, ScyllaDbStore::MAX_VALUE_SIZE);
+        size - ENVELOPE_OVERHEAD
+    };
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), AnyStoreError> {
+        with_store!(self, |store| store.write_batch(batch).await)
+    }
+
+    async fn clear_journal(&self) -> Result<(), AnyStoreError> {
+        with_store!(self, |store| store.clear_journal().await)
+    }
+}
+
+impl AdminKeyValueStore for AnyStore {
+    type Config = AnyStoreConfig;
+
+    fn get_name() -> String {
+        "any".to_string()
+    }
+
+    async fn connect(
+        config: &AnyStoreConfig,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, AnyStoreError> {
+        with_config!(config, |Store, config| Store::connect(
+            config, namespace, root_key
+        )
+        .await
+        .map(AnyStore::from))
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, AnyStoreError> {
+        let store = match self {
+            AnyStore::Memory(store) => store.clone_with_root_key(root_key)?.into(),
+            #[cfg(feature = "storage-service")]
+            AnyStore::Service(store) => store.clone_with_root_key(root_key)?.into(),
+            #[cfg(feature = "rocksdb")]
+            AnyStore::RocksDb(store) => store.clone_with_root_key(root_key)?.into(),
+            #[cfg(feature = "dynamodb")]
+            AnyStore::DynamoDb(store) => store.clone_with_root_key(root_key)?.into(),
+            #[cfg(feature = "scylladb")]
+            AnyStore::ScyllaDb(store) => store.clone_with_root_key(root_key)?.into(),
+            AnyStore::Encrypted(store) => store.clone_with_root_key(root_key)?.into(),
+        };
+        Ok(store)
+    }
+
+    async fn list_all(config: &AnyStoreConfig) -> Result<Vec<String>, AnyStoreError> {
+        with_config!(config, |Store, config| Store::list_all(config).await)
+    }
+
+    async fn exists(config: &AnyStoreConfig, namespace: &str) -> Result<bool, AnyStoreError> {
+        with_config!(config, |Store, config| Store::exists(config, namespace)
+            .await)
+    }
+
+    async fn create(config: &AnyStoreConfig, namespace: &str) -> Result<(), AnyStoreError> {
+        with_config!(config, |Store, config| Store::create(config, namespace)
+            .await)
+    }
+
+    async fn delete(config: &AnyStoreConfig, namespace: &str) -> Result<(), AnyStoreError> {
+        with_config!(config, |Store, config| Store::delete(config, namespace)
+            .await)
+    }
+}
+
+impl From<MemoryStore> for AnyStore {
+    fn from(store: MemoryStore) -> Self {
+        AnyStore::Memory(store)
+    }
+}
+
+#[cfg(feature = "storage-service")]
+impl From<ServiceStoreClient> for AnyStore {
+    fn from(store: ServiceStoreClient) -> Self {
+        AnyStore::Service(store)
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl From<RocksDbStore> for AnyStore {
+    fn from(store: RocksDbStore) -> Self {
+        AnyStore::RocksDb(store)
+    }
+}
+
+#[cfg(feature = "dynamodb")]
+impl From<DynamoDbStore> for AnyStore {
+    fn from(store: DynamoDbStore) -> Self {
+        AnyStore::DynamoDb(store)
+    }
+}
+
+#[cfg(feature = "scylladb")]
+impl From<ScyllaDbStore> for AnyStore {
+    fn from(store: ScyllaDbStore) -> Self {
+        AnyStore::ScyllaDb(store)
+    }
+}
+
+impl From<EncryptedStore<AnyStore>> for AnyStore {
+    fn from(store: EncryptedStore<AnyStore>) -> Self {
+        AnyStore::Encrypted(Box::new(store))
+    }
+}
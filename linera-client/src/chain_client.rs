@@ -0,0 +1,249 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A client for a single chain of the wallet, for applications that drive chains from their
+//! own backend instead of running the `linera` command-line tool.
+//!
+//! The methods of [`ChainClient`] keep the wallet up to date and wait for the blocks they
+//! create to be confirmed, exactly like the corresponding commands of the `linera` tool, which
+//! uses the same [`ClientContext`] methods.
+//!
+//! ```no_run
+//! # use linera_base::{data_types::Amount, identifiers::{Account, ChainId}};
+//! # use linera_client::{chain_client::ChainClient, persistent::Persist, wallet::Wallet, Error};
+//! # use linera_storage::Storage;
+//! async fn pay<S, W>(client: &ChainClient<S, W>, recipient: ChainId) -> Result<(), Error>
+//! where
+//!     S: Storage + Clone + Send + Sync + 'static,
+//!     W: Persist<Target = Wallet>,
+//! {
+//!     // Receive the incoming transfers first.
+//!     client.process_inbox().await?;
+//!     let certificate = client
+//!         .transfer(None, Amount::from_tokens(1), Account::chain(recipient))
+//!         .await?;
+//!     println!("Paid in block {}", certificate.hash());
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use futures::{lock::Mutex, StreamExt as _};
+use linera_base::{
+    abi::Abi,
+    data_types::Amount,
+    identifiers::{Account, ChainId, Owner, UserApplicationId},
+};
+use linera_chain::types::ConfirmedBlockCertificate;
+use linera_core::{client, join_set_ext::JoinSetExt as _, node::NotificationStream};
+use linera_execution::Operation;
+use linera_rpc::node_provider::NodeProvider;
+use linera_storage::Storage;
+
+#[cfg(web)]
+use crate::persistent::LocalPersist as Persist;
+#[cfg(not(web))]
+use crate::persistent::Persist;
+use crate::{
+    client_context::ClientContext, client_options::ClientOptions, error, wallet::Wallet, Error,
+};
+
+/// Creates a [`ChainClient`] from a storage, the client options and a wallet.
+///
+/// ```no_run
+/// # use linera_client::{chain_client::ChainClientBuilder, client_options::ClientOptions, storage::Runnable};
+/// # use linera_storage::Storage;
+/// struct ProcessInbox(ClientOptions);
+///
+/// #[async_trait::async_trait]
+/// impl Runnable for ProcessInbox {
+///     type Output = Result<(), linera_client::Error>;
+///
+///     async fn run<S>(self, storage: S) -> Self::Output
+///     where
+///         S: Storage + Clone + Send + Sync + 'static,
+///     {
+///         let ProcessInbox(options) = self;
+///         let wallet = options.wallet().await?;
+///         let client = ChainClientBuilder::new(storage, options, wallet).build()?;
+///         let certificates = client.process_inbox().await?;
+///         println!("Created {} blocks", certificates.len());
+///         Ok(())
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), linera_client::Error> {
+/// let options = <ClientOptions as clap::Parser>::parse();
+/// options.run_with_storage(ProcessInbox(options.clone())).await??;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ChainClientBuilder<S, W> {
+    storage: S,
+    options: ClientOptions,
+    wallet: W,
+    chain_id: Option<ChainId>,
+}
+
+impl<S, W> ChainClientBuilder<S, W>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    W: Persist<Target = Wallet>,
+{
+    /// Starts building a client for the default chain of the `wallet`.
+    pub fn new(storage: S, options: ClientOptions, wallet: W) -> Self {
+        ChainClientBuilder {
+            storage,
+            options,
+            wallet,
+            chain_id: None,
+        }
+    }
+
+    /// Sets the chain of the wallet to use instead of the default one.
+    pub fn with_chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Creates the [`ChainClient`].
+    pub fn build(self) -> Result<ChainClient<S, W>, Error> {
+        let context = ClientContext::new(self.storage, self.options, self.wallet);
+        let chain_id = self
+            .chain_id
+            .or_else(|| context.wallet().default_chain())
+            .ok_or(error::Inner::NoDefaultChain)?;
+        let chain_client = context.make_chain_client(chain_id)?;
+        Ok(ChainClient {
+            context: Arc::new(Mutex::new(context)),
+            chain_client,
+        })
+    }
+}
+
+/// A client for one chain of the wallet.
+///
+/// Clients for several chains can share the same [`ClientContext`], and are cheap to clone.
+pub struct ChainClient<S, W>
+where
+    S: Storage,
+{
+    context: Arc<Mutex<ClientContext<S, W>>>,
+    chain_client: client::ChainClient<NodeProvider, S>,
+}
+
+impl<S, W> Clone for ChainClient<S, W>
+where
+    S: Storage,
+{
+    fn clone(&self) -> Self {
+        ChainClient {
+            context: self.context.clone(),
+            chain_client: self.chain_client.clone(),
+        }
+    }
+}
+
+impl<S, W> ChainClient<S, W>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    W: Persist<Target = Wallet>,
+{
+    /// Creates a client for the chain with the given ID, which must be in the wallet.
+    pub async fn new(
+        context: Arc<Mutex<ClientContext<S, W>>>,
+        chain_id: ChainId,
+    ) -> Result<Self, Error> {
+        let chain_client = context.lock().await.make_chain_client(chain_id)?;
+        Ok(ChainClient {
+            context,
+            chain_client,
+        })
+    }
+
+    /// Returns the ID of the chain.
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_client.chain_id()
+    }
+
+    /// Returns the context shared by the clients of the wallet's chains.
+    pub fn context(&self) -> &Arc<Mutex<ClientContext<S, W>>> {
+        &self.context
+    }
+
+    /// Transfers `amount` tokens from the `owner`'s account, or from the chain's own account if
+    /// `owner` is `None`, to the `recipient`.
+    pub async fn transfer(
+        &self,
+        owner: Option<Owner>,
+        amount: Amount,
+        recipient: Account,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        self.context
+            .lock()
+            .await
+            .transfer(&self.chain_client, owner, amount, recipient)
+            .await
+    }
+
+    /// Executes an operation in a new block.
+    pub async fn execute_operation(
+        &self,
+        operation: Operation,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        self.execute_operations(vec![operation]).await
+    }
+
+    /// Executes the operations in a new block.
+    pub async fn execute_operations(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        self.context
+            .lock()
+            .await
+            .execute_operations(&self.chain_client, operations)
+            .await
+    }
+
+    /// Queries the service of an application on the chain, using the local state of the chain.
+    pub async fn query_application<A: Abi>(
+        &self,
+        application_id: UserApplicationId<A>,
+        query: &A::Query,
+    ) -> Result<A::QueryResponse, Error> {
+        let outcome = self
+            .chain_client
+            .query_user_application(application_id, query)
+            .await?;
+        Ok(outcome.response)
+    }
+
+    /// Executes the messages in the inbox of the chain, and returns the blocks created to
+    /// execute them.
+    pub async fn process_inbox(&self) -> Result<Vec<ConfirmedBlockCertificate>, Error> {
+        self.context
+            .lock()
+            .await
+            .process_inbox(&self.chain_client)
+            .await
+    }
+
+    /// Returns the notifications about the chain, such as new blocks and incoming messages.
+    ///
+    /// The client keeps listening to the validators to learn about new blocks until the
+    /// returned stream is dropped.
+    pub async fn subscribe_notifications(&self) -> Result<NotificationStream, Error> {
+        let (listener, listen_handle, notifications) = self.chain_client.listen().await?;
+        self.context
+            .lock()
+            .await
+            .chain_listeners
+            .spawn_task(listener);
+        Ok(Box::pin(notifications.map(move |notification| {
+            let _listen_handle = &listen_handle;
+            notification
+        })))
+    }
+}
@@ -1,10 +1,9 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-#[cfg(with_testing)]
-use std::num::NonZeroUsize;
 use std::{
     collections::{BTreeMap, HashSet},
+    num::NonZeroUsize,
     sync::Arc,
 };
 
@@ -12,18 +11,22 @@ use async_trait::async_trait;
 use futures::Future;
 use linera_base::{
     crypto::KeyPair,
-    data_types::{Blob, BlockHeight, Timestamp},
-    identifiers::{Account, BlobId, ChainId},
+    data_types::{Amount, Blob, BlockHeight, Timestamp},
+    identifiers::{Account, BlobId, ChainId, Owner},
     ownership::ChainOwnership,
     time::{Duration, Instant},
 };
-use linera_chain::types::ConfirmedBlockCertificate;
+use linera_chain::{data_types::PendingMessage, types::ConfirmedBlockCertificate};
 use linera_core::{
-    client::{BlanketMessagePolicy, ChainClient, Client, MessagePolicy},
-    data_types::ClientOutcome,
+    chain_worker::StateSnapshotOptions,
+    client::{BlanketMessagePolicy, ChainClient, ChainClientError, Client, MessagePolicy},
+    data_types::{ChainInfo, ClientOutcome},
     join_set_ext::{JoinSet, JoinSetExt as _},
-    node::CrossChainMessageDelivery,
+    node::{CrossChainMessageDelivery, ValidatorNodeProvider},
+    remote_node::RemoteNode,
+    ValidatorRequestOptions, DEFAULT_GRACE_PERIOD,
 };
+use linera_execution::Operation;
 use linera_rpc::node_provider::{NodeOptions, NodeProvider};
 use linera_storage::Storage;
 use thiserror_context::Context;
@@ -33,8 +36,7 @@ use {
     futures::{stream, StreamExt as _, TryStreamExt as _},
     linera_base::{
         crypto::PublicKey,
-        data_types::Amount,
-        identifiers::{AccountOwner, ApplicationId, Owner},
+        identifiers::{AccountOwner, ApplicationId},
     },
     linera_chain::data_types::{
         BlockProposal, ExecutedBlock, ProposedBlock, SignatureAggregator, Vote,
@@ -44,7 +46,6 @@ use {
     linera_execution::{
         committee::Epoch,
         system::{OpenChainConfig, Recipient, SystemOperation, OPEN_CHAIN_MESSAGE_INDEX},
-        Operation,
     },
     linera_rpc::{
         config::NetworkProtocol, grpc::GrpcClient, mass_client::MassClient,
@@ -71,7 +72,7 @@ use crate::persistent::{LocalPersist as Persist, LocalPersistExt as _};
 use crate::persistent::{Persist, PersistExt as _};
 use crate::{
     chain_listener,
-    client_options::{ChainOwnershipConfig, ClientOptions},
+    client_options::{ChainOwnershipConfig, ClientOptions, PendingMessagesOnClose},
     config::WalletState,
     error, util,
     wallet::{UserChain, Wallet},
@@ -91,6 +92,8 @@ where
     pub chain_listeners: JoinSet,
     pub blanket_message_policy: BlanketMessagePolicy,
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    pub park_failing_messages: bool,
+    pub max_pending_messages: Option<usize>,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -177,6 +180,8 @@ where
             name,
             options.max_loaded_chains,
             options.grace_period,
+            options.validator_request_options(),
+            options.state_snapshot_options(),
         );
 
         ClientContext {
@@ -189,13 +194,13 @@ where
             chain_listeners: JoinSet::default(),
             blanket_message_policy: options.blanket_message_policy,
             restrict_chain_ids_to: options.restrict_chain_ids_to,
+            park_failing_messages: options.park_failing_messages,
+            max_pending_messages: options.max_pending_messages,
         }
     }
 
     #[cfg(with_testing)]
     pub fn new_test_client_context(storage: S, wallet: W) -> Self {
-        use linera_core::DEFAULT_GRACE_PERIOD;
-
         let send_recv_timeout = Duration::from_millis(4000);
         let retry_delay = Duration::from_millis(1000);
         let max_retries = 10;
@@ -224,6 +229,8 @@ where
             name,
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
+            ValidatorRequestOptions::default(),
+            StateSnapshotOptions::default(),
         );
 
         ClientContext {
@@ -236,6 +243,8 @@ where
             chain_listeners: JoinSet::default(),
             blanket_message_policy: BlanketMessagePolicy::Accept,
             restrict_chain_ids_to: None,
+            park_failing_messages: false,
+            max_pending_messages: None,
         }
     }
 
@@ -252,7 +261,10 @@ where
             .expect("No chain specified in wallet with no default chain")
     }
 
-    fn make_chain_client(&self, chain_id: ChainId) -> Result<ChainClient<NodeProvider, S>, Error> {
+    pub(crate) fn make_chain_client(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<ChainClient<NodeProvider, S>, Error> {
         let chain = self
             .wallet
             .get(chain_id)
@@ -276,7 +288,11 @@ where
         chain_client.options_mut().message_policy = MessagePolicy::new(
             self.blanket_message_policy,
             self.restrict_chain_ids_to.clone(),
-        );
+        )
+        .with_park_failing_messages(self.park_failing_messages);
+        if let Some(max_pending_messages) = self.max_pending_messages {
+            chain_client.options_mut().max_pending_messages = max_pending_messages;
+        }
         Ok(chain_client)
     }
 
@@ -449,6 +465,40 @@ where
         }
     }
 
+    /// Transfers `amount` tokens from the `owner`'s account, or from the chain's own account if
+    /// `owner` is `None`, to the `recipient`, and waits for the block to be confirmed.
+    pub async fn transfer(
+        &mut self,
+        chain_client: &ChainClient<NodeProvider, S>,
+        owner: Option<Owner>,
+        amount: Amount,
+        recipient: Account,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        self.apply_client_command(chain_client, |chain_client| {
+            let chain_client = chain_client.clone();
+            async move {
+                chain_client
+                    .transfer_to_account(owner, amount, recipient)
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Executes the `operations` in a new block, and waits for the block to be confirmed.
+    pub async fn execute_operations(
+        &mut self,
+        chain_client: &ChainClient<NodeProvider, S>,
+        operations: Vec<Operation>,
+    ) -> Result<ConfirmedBlockCertificate, Error> {
+        self.apply_client_command(chain_client, |chain_client| {
+            let chain_client = chain_client.clone();
+            let operations = operations.clone();
+            async move { chain_client.execute_operations(operations).await }
+        })
+        .await
+    }
+
     pub async fn change_ownership(
         &mut self,
         chain_id: Option<ChainId>,
@@ -478,6 +528,154 @@ where
         debug!("{:?}", certificate);
         Ok(())
     }
+
+    /// Replaces the key of the chain with a newly generated one, which becomes the only super
+    /// owner. The old key is removed from the wallet.
+    pub async fn rotate_key(&mut self, chain_id: Option<ChainId>) -> Result<Owner, Error> {
+        let chain_id = chain_id.unwrap_or_else(|| self.default_chain());
+        let chain_client = self.make_chain_client(chain_id)?;
+        info!("Rotating the key of chain {}", chain_id);
+        let time_start = Instant::now();
+        let key_pair = self.wallet.generate_key_pair();
+        let owner = Owner::from(key_pair.public());
+        // Save the new key first, so that it isn't lost if the command is interrupted after
+        // the ownership change is committed.
+        self.mutate_wallet(|wallet| wallet.add_unassigned_key_pair(key_pair.copy()))
+            .await?;
+
+        let certificate = self
+            .apply_client_command(&chain_client, |chain_client| {
+                let key_pair = key_pair.copy();
+                let chain_client = chain_client.clone();
+                async move {
+                    chain_client
+                        .rotate_key_pair(key_pair)
+                        .await
+                        .map_err(Error::from)
+                        .context("Failed to rotate key")
+                }
+            })
+            .await?;
+        self.mutate_wallet(|wallet| wallet.unassigned_key_pairs.remove(&owner))
+            .await?;
+        let time_total = time_start.elapsed();
+        info!("Operation confirmed after {} ms", time_total.as_millis());
+        debug!("{:?}", certificate);
+        Ok(owner)
+    }
+
+    /// Transfers the chain to a new owner, who becomes its only super owner. The chain stays
+    /// in the wallet, but without its key, which cannot be used for it anymore.
+    pub async fn transfer_chain_ownership(
+        &mut self,
+        chain_id: ChainId,
+        new_owner: Owner,
+    ) -> Result<(), Error> {
+        let chain_client = self.make_chain_client(chain_id)?;
+        info!("Transferring chain {} to {}", chain_id, new_owner);
+        let time_start = Instant::now();
+
+        // Updating the wallet from the client removes the key, since the client doesn't find
+        // it among the chain's owners anymore.
+        let certificate = self
+            .apply_client_command(&chain_client, |chain_client| {
+                let chain_client = chain_client.clone();
+                async move {
+                    chain_client
+                        .transfer_ownership(new_owner)
+                        .await
+                        .map_err(Error::from)
+                        .context("Failed to transfer chain ownership")
+                }
+            })
+            .await?;
+        let time_total = time_start.elapsed();
+        info!("Operation confirmed after {} ms", time_total.as_millis());
+        debug!("{:?}", certificate);
+        Ok(())
+    }
+
+    /// Links the unassigned key of `owner` to an existing chain, e.g. one that was
+    /// transferred to that owner, after checking with the validators that it owns the chain.
+    pub async fn assign_chain(&mut self, owner: Owner, chain_id: ChainId) -> Result<(), Error> {
+        self.mutate_wallet(|wallet| {
+            wallet.assign_new_chain_to_owner(owner, chain_id, Timestamp::from(0))
+        })
+        .await??;
+        let chain_client = self.make_chain_client(chain_id)?;
+        let result = async {
+            chain_client.synchronize_from_validators().await?;
+            chain_client.identity().await
+        }
+        .await;
+        if let Err(error) = result {
+            // Give the key back, unassigned.
+            self.mutate_wallet(|wallet| {
+                if let Ok(chain) = wallet.forget_chain(&chain_id) {
+                    wallet.add_unassigned_key_pair(chain.key_pair.expect("key was just assigned"));
+                }
+            })
+            .await?;
+            return Err(Error::from(error)).context("The chain is not owned by this key");
+        }
+        self.update_and_save_wallet(&chain_client).await
+    }
+
+    /// Closes the chain and returns the certificate of the block that closed it, or `None`
+    /// if it was already closed.
+    ///
+    /// If incoming messages are still waiting in the chain's inbox, `pending_messages` decides
+    /// whether they are executed before closing the chain, or rejected afterwards so that
+    /// they bounce back to their senders. Without it, the chain is not closed.
+    pub async fn close_chain(
+        &mut self,
+        chain_id: ChainId,
+        pending_messages: Option<PendingMessagesOnClose>,
+    ) -> Result<Option<ConfirmedBlockCertificate>, Error> {
+        let chain_client = self.make_chain_client(chain_id)?;
+        info!("Closing chain {}", chain_id);
+        let time_start = Instant::now();
+
+        chain_client.synchronize_from_validators().await?;
+        let count = chain_client.pending_message_bundle_count().await?;
+        if count > 0 {
+            match pending_messages {
+                None => return Err(error::Inner::PendingMessages { chain_id, count }.into()),
+                Some(PendingMessagesOnClose::Process) => {
+                    self.process_inbox(&chain_client).await?;
+                }
+                Some(PendingMessagesOnClose::Bounce) => {}
+            }
+        }
+
+        // The block that closes the chain doesn't include incoming messages: the ones still
+        // pending are rejected in the next blocks.
+        let mut closing_client = chain_client.clone();
+        closing_client.options_mut().message_policy =
+            MessagePolicy::new(BlanketMessagePolicy::Ignore, None);
+        let certificate = self
+            .apply_client_command(&closing_client, |chain_client| {
+                let chain_client = chain_client.clone();
+                async move { chain_client.close_chain().await }
+            })
+            .await
+            .context("Failed to close chain")?;
+        if chain_client.pending_message_bundle_count().await? > 0 {
+            self.process_inbox(&chain_client).await?;
+        }
+        let time_total = time_start.elapsed();
+        info!("Operation confirmed after {} ms", time_total.as_millis());
+        debug!("{:?}", certificate);
+        Ok(certificate)
+    }
+
+    /// Returns the messages received by the given chain but not executed yet, after
+    /// synchronizing the chain with the validators.
+    pub async fn pending_messages(&self, chain_id: ChainId) -> Result<Vec<PendingMessage>, Error> {
+        let chain_client = self.make_chain_client(chain_id)?;
+        chain_client.synchronize_from_validators().await?;
+        Ok(chain_client.pending_messages().await?)
+    }
 }
 
 #[cfg(feature = "fs")]
@@ -500,6 +698,12 @@ where
             .await
             .with_context(|| format!("failed to load service bytecode from {:?}", &service))?;
 
+        #[cfg(with_wasm_runtime)]
+        {
+            info!("Validating bytecode");
+            linera_execution::validate_bytecode(&contract_bytecode, &service_bytecode)?;
+        }
+
         info!("Publishing bytecode");
         let (contract_blob, service_blob, bytecode_id) =
             create_bytecode_blobs(contract_bytecode, service_bytecode).await;
@@ -573,6 +777,75 @@ where
         info!("{}", "Data blob verified successfully!");
         Ok(())
     }
+
+    /// Returns the validators of the current committee, as seen by the default chain after
+    /// synchronizing it.
+    async fn current_validators(
+        &mut self,
+    ) -> Result<Vec<RemoteNode<<NodeProvider as ValidatorNodeProvider>::Node>>, Error> {
+        let chain_client = self.make_chain_client(self.default_chain())?;
+        chain_client.synchronize_from_validators().await?;
+        self.update_and_save_wallet(&chain_client).await?;
+        let committee = chain_client.local_committee().await?;
+        Ok(self
+            .make_node_provider()
+            .make_nodes(&committee)
+            .map_err(ChainClientError::from)?
+            .map(|(name, node)| RemoteNode {
+                name,
+                node,
+                misbehavior: self.client.misbehavior_log().clone(),
+            })
+            .collect())
+    }
+
+    /// Downloads the certificates of all the blocks of a chain from the validators.
+    pub async fn download_chain_certificates(
+        &mut self,
+        chain_id: ChainId,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, Error> {
+        let validators = self.current_validators().await?;
+        let certificates = self
+            .client
+            .download_chain_certificates(&validators, chain_id)
+            .await?;
+        Ok(certificates)
+    }
+
+    /// Verifies a chain from its genesis with the given certificates, checking their
+    /// signatures and executing all the blocks again.
+    ///
+    /// The verification runs in `storage`, which must not know any block of the chain, so it
+    /// doesn't depend on the local state of this client. See [`Client::verify_chain`].
+    pub async fn verify_chain<S2>(
+        &mut self,
+        storage: S2,
+        chain_id: ChainId,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        progress: impl FnMut(&ConfirmedBlockCertificate),
+    ) -> Result<Box<ChainInfo>, Error>
+    where
+        S2: Storage + Clone + Send + Sync + 'static,
+    {
+        let validators = self.current_validators().await?;
+        let client = Client::new(
+            self.make_node_provider(),
+            storage,
+            0, // The verifier never proposes blocks.
+            CrossChainMessageDelivery::NonBlocking,
+            false,
+            [chain_id],
+            format!("Verifier for {chain_id:.8}"),
+            NonZeroUsize::new(1).expect("One is not zero"),
+            DEFAULT_GRACE_PERIOD,
+            ValidatorRequestOptions::default(),
+            StateSnapshotOptions::default(),
+        );
+        let info = client
+            .verify_chain(&validators, chain_id, certificates, progress)
+            .await?;
+        Ok(info)
+    }
 }
 
 #[cfg(feature = "benchmark")]
@@ -793,6 +1066,7 @@ where
                 previous_block_hash: chain.block_hash,
                 height: chain.next_block_height,
                 authenticated_signer: Some(Owner::from(public_key)),
+                fee_payer: None,
                 timestamp: chain.timestamp.max(Timestamp::now()),
             };
             trace!("Preparing block proposal: {:?}", block);
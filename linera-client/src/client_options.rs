@@ -4,7 +4,7 @@
 use std::{
     collections::HashSet,
     env, fmt, iter,
-    num::{NonZeroU16, NonZeroUsize},
+    num::{NonZeroU16, NonZeroU64, NonZeroUsize},
     path::PathBuf,
     time::Duration,
 };
@@ -18,22 +18,27 @@ use linera_base::{
     },
     ownership::{ChainOwnership, TimeoutConfig},
 };
-use linera_core::{client::BlanketMessagePolicy, DEFAULT_GRACE_PERIOD};
+use linera_core::{
+    chain_worker::{StateSnapshotOptions, DEFAULT_RETAINED_STATE_SNAPSHOTS},
+    client::BlanketMessagePolicy,
+    ValidatorRequestOptions, DEFAULT_GRACE_PERIOD, DEFAULT_VALIDATOR_MAX_RETRIES,
+};
 use linera_execution::{
     committee::ValidatorName, ResourceControlPolicy, WasmRuntime, WithWasmDefault as _,
 };
-use linera_views::store::CommonStoreConfig;
+use linera_views::{encryption::EncryptionConfig, store::CommonStoreConfig};
 
-#[cfg(feature = "fs")]
-use crate::config::GenesisConfig;
 use crate::{
     chain_listener::ChainListenerConfig,
     config::WalletState,
     persistent,
+    service_override::ServiceOverride,
     storage::{full_initialize_storage, run_with_storage, Runnable, StorageConfigNamespace},
     util,
     wallet::Wallet,
 };
+#[cfg(feature = "fs")]
+use crate::{config::GenesisConfig, key_store};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -55,6 +60,8 @@ pub enum Error {
     Persistence(#[from] Box<dyn std::error::Error + Send + Sync>),
     #[error("config error: {0}")]
     Config(#[from] crate::config::Error),
+    #[error("key store error: {0}")]
+    KeyStore(#[from] crate::key_store::Error),
 }
 
 #[cfg(feature = "fs")]
@@ -80,6 +87,11 @@ pub struct ClientOptions {
     #[arg(long = "storage")]
     pub storage_config: Option<String>,
 
+    /// Path to a JSON file with the keys encrypting the values of the storage. The values are
+    /// not encrypted by default.
+    #[arg(long = "storage-encryption-config")]
+    pub storage_encryption_config_path: Option<PathBuf>,
+
     /// Given an integer value N, read the wallet state and the wallet storage config from the
     /// environment variables LINERA_WALLET_{N} and LINERA_STORAGE_{N} instead of
     /// LINERA_WALLET and LINERA_STORAGE.
@@ -98,6 +110,11 @@ pub struct ClientOptions {
     #[arg(long, default_value = "10")]
     pub max_pending_message_bundles: usize,
 
+    /// The maximum number of incoming messages, across all bundles, to include in a block
+    /// proposal. By default, only the number of bundles is limited.
+    #[arg(long)]
+    pub max_pending_messages: Option<usize>,
+
     /// The WebAssembly runtime to use.
     #[arg(long)]
     pub wasm_runtime: Option<WasmRuntime>,
@@ -157,10 +174,43 @@ pub struct ClientOptions {
     #[arg(long, value_parser = util::parse_chain_set)]
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
 
+    /// Park incoming messages that fail to execute in the chain's dead-letter queue, so that
+    /// they can be retried later, instead of rejecting them.
+    #[arg(long)]
+    pub park_failing_messages: bool,
+
     /// An additional delay, after reaching a quorum, to wait for additional validator signatures,
     /// as a fraction of time taken to reach quorum.
     #[arg(long, default_value_t = DEFAULT_GRACE_PERIOD)]
     pub grace_period: f64,
+
+    /// The maximal time to wait for a validator's response while communicating with a quorum,
+    /// before retrying. By default, only the network timeouts apply.
+    #[arg(long = "validator-request-timeout-ms", value_parser = util::parse_millis)]
+    pub validator_request_timeout: Option<Duration>,
+
+    /// Number of times to retry a request to a validator that timed out or failed with a
+    /// network error, while communicating with a quorum.
+    #[arg(long, default_value_t = DEFAULT_VALIDATOR_MAX_RETRIES)]
+    pub validator_max_retries: u32,
+
+    /// Delay before retrying a request to a validator for the first time. It is doubled after
+    /// each retry.
+    #[arg(
+        long = "validator-retry-delay-ms",
+        default_value = "100",
+        value_parser = util::parse_millis
+    )]
+    pub validator_retry_delay: Duration,
+
+    /// Keep a snapshot of the state of each chain every this many blocks, so that applications
+    /// can be queried at past heights. By default, only the current state is kept.
+    #[arg(long)]
+    pub state_snapshot_interval: Option<NonZeroU64>,
+
+    /// How many snapshots to keep for each chain. Queries at heights before the oldest one fail.
+    #[arg(long, default_value_t = DEFAULT_RETAINED_STATE_SNAPSHOTS)]
+    pub retained_state_snapshots: usize,
 }
 
 impl ClientOptions {
@@ -181,6 +231,21 @@ impl ClientOptions {
         Ok(options)
     }
 
+    pub fn validator_request_options(&self) -> ValidatorRequestOptions {
+        ValidatorRequestOptions {
+            timeout: self.validator_request_timeout,
+            max_retries: self.validator_max_retries,
+            retry_delay: self.validator_retry_delay,
+        }
+    }
+
+    pub fn state_snapshot_options(&self) -> StateSnapshotOptions {
+        StateSnapshotOptions {
+            interval: self.state_snapshot_interval,
+            retained: self.retained_state_snapshots,
+        }
+    }
+
     fn common_config(&self) -> CommonStoreConfig {
         CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -194,7 +259,8 @@ impl ClientOptions {
         let output = Box::pin(run_with_storage(
             self.storage_config()?
                 .add_common_config(self.common_config())
-                .await?,
+                .await?
+                .with_encryption(self.storage_encryption_config()?),
             &genesis_config,
             self.wasm_runtime.with_wasm_default(),
             job,
@@ -227,12 +293,23 @@ impl ClientOptions {
         }
     }
 
+    /// Returns the keys encrypting the values of the storage, if any.
+    pub fn storage_encryption_config(&self) -> Result<Option<EncryptionConfig>, Error> {
+        let Some(path) = &self.storage_encryption_config_path else {
+            return Ok(None);
+        };
+        let bytes = std::fs::read(path)?;
+        let config = serde_json::from_slice(&bytes).map_err(std::io::Error::from)?;
+        Ok(Some(config))
+    }
+
     pub async fn initialize_storage(&self) -> Result<(), Error> {
         let wallet = self.wallet().await?;
         full_initialize_storage(
             self.storage_config()?
                 .add_common_config(self.common_config())
-                .await?,
+                .await?
+                .with_encryption(self.storage_encryption_config()?),
             wallet.genesis_config(),
         )
         .await?;
@@ -243,7 +320,11 @@ impl ClientOptions {
 #[cfg(feature = "fs")]
 impl ClientOptions {
     pub async fn wallet(&self) -> Result<WalletState<persistent::File<Wallet>>, Error> {
-        let wallet = persistent::File::read(&self.wallet_path()?)?;
+        let mut wallet = persistent::File::<Wallet>::read(&self.wallet_path()?)?;
+        if wallet.is_locked() {
+            let passphrase = key_store::read_passphrase("Wallet passphrase: ")?;
+            wallet.unlock(&passphrase)?;
+        }
         Ok(WalletState::new(wallet))
     }
 
@@ -364,6 +445,15 @@ pub enum ClientCommand {
         ownership_config: ChainOwnershipConfig,
     },
 
+    /// Replace the key of a chain that we own with a newly generated one.
+    ///
+    /// The new key becomes the only super owner of the chain, and the old key is removed
+    /// from the wallet.
+    RotateKey {
+        /// The ID of the chain whose key will be rotated.
+        chain_id: Option<ChainId>,
+    },
+
     /// Changes the application permissions configuration.
     ChangeApplicationPermissions {
         /// The ID of the chain to which the new permissions will be applied.
@@ -374,6 +464,20 @@ pub enum ClientCommand {
         application_permissions_config: ApplicationPermissionsConfig,
     },
 
+    /// Transfer a chain that we own to a new owner.
+    ///
+    /// The new owner becomes the only super owner of the chain. The chain stays in the
+    /// wallet, but its key is removed.
+    TransferChainOwnership {
+        /// The ID of the chain to transfer.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// The new owner of the chain.
+        #[arg(long)]
+        new_owner: Owner,
+    },
+
     /// Close an existing chain.
     ///
     /// A closed chain cannot execute operations or accept messages anymore.
@@ -381,6 +485,11 @@ pub enum ClientCommand {
     CloseChain {
         /// Chain ID (must be one of our chains)
         chain_id: ChainId,
+
+        /// What to do with the incoming messages still in the inbox of the chain. Without
+        /// this option, the chain is not closed if there are any.
+        #[arg(long, value_enum)]
+        pending_messages: Option<PendingMessagesOnClose>,
     },
 
     /// Read the current native-token balance of the given account directly from the local
@@ -435,6 +544,45 @@ pub enum ClientCommand {
         chain_id: Option<ChainId>,
     },
 
+    /// List the messages waiting in the inbox of the given chain, after synchronizing it with
+    /// the validators. Nothing is executed.
+    Inbox {
+        /// The chain whose inbox to list. If omitted, uses the default chain of the wallet.
+        chain_id: Option<ChainId>,
+    },
+
+    /// Show whether a message was received and executed by its recipient, after synchronizing
+    /// the recipient chain with the validators.
+    QueryMessageStatus {
+        /// The hash of the certificate of the block that sent the message.
+        certificate_hash: CryptoHash,
+        /// The index of the message in the block.
+        index: u32,
+        /// The recipient chain. If omitted, uses the default chain of the wallet.
+        recipient: Option<ChainId>,
+    },
+
+    /// Download all the blocks of a chain from the validators and verify them from its genesis:
+    /// Check the signatures of their certificates and execute them again in a fresh storage.
+    VerifyChain {
+        /// The chain to verify. If omitted, verifies the default chain of the wallet.
+        chain_id: Option<ChainId>,
+        /// Verify the certificates stored in the given JSON file instead of downloading them.
+        #[arg(long)]
+        certificates: Option<PathBuf>,
+        /// Save the downloaded certificates to the given JSON file.
+        #[arg(long, conflicts_with = "certificates")]
+        save_certificates: Option<PathBuf>,
+    },
+
+    /// Synchronize a chain with the validators, then compare the hashes of the parts of its
+    /// execution state with theirs, to find out which applications diverged. Validators only
+    /// answer if they run with `--serve-state-hash-breakdowns`.
+    CompareStateHashes {
+        /// The chain to compare. If omitted, uses the default chain of the wallet.
+        chain_id: Option<ChainId>,
+    },
+
     /// Show the version and genesis config hash of a new validator, and print a warning if it is
     /// incompatible. Also print some information about the given chain while we are at it.
     QueryValidator {
@@ -562,6 +710,57 @@ pub enum ClientCommand {
         /// Set the maximum write data per block.
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
+
+        /// Set the maximum size of the stored state of each application, in bytes.
+        #[arg(long)]
+        maximum_application_state_size: Option<u64>,
+
+        /// Set the maximum number of operations per block.
+        #[arg(long)]
+        maximum_operations_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming message bundles per block.
+        #[arg(long)]
+        maximum_incoming_bundles_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming message bundles from the same origin per block.
+        #[arg(long)]
+        maximum_incoming_bundles_per_origin: Option<u64>,
+
+        /// Set whether the unused part of message grants is burned instead of refunded.
+        #[arg(long)]
+        burn_unused_grants: Option<bool>,
+
+        /// Set the maximum size of the response to a query of an application, in bytes.
+        #[arg(long)]
+        maximum_query_response_size: Option<u64>,
+
+        /// Set the maximum number of messages an application can send per execution.
+        #[arg(long)]
+        maximum_messages_per_execution: Option<u64>,
+
+        /// Set the maximum size of a message sent by an application, in bytes.
+        #[arg(long)]
+        maximum_message_size: Option<u64>,
+
+        /// Set the maximum total size of the messages an application can send per execution,
+        /// in bytes.
+        #[arg(long)]
+        maximum_message_bytes_per_execution: Option<u64>,
+
+        /// Set the maximum depth of nested calls between applications, and of nested queries
+        /// between services.
+        #[arg(long)]
+        maximum_call_depth: Option<u64>,
+
+        /// Require block proposals to claim the resources each operation uses, and reject them
+        /// if an operation uses more than its claim by more than this many percent of it.
+        #[arg(long)]
+        resource_estimate_tolerance_percent: Option<u64>,
+
+        /// Stop requiring block proposals to claim the resources each operation uses.
+        #[arg(long, conflicts_with = "resource_estimate_tolerance_percent")]
+        optional_resource_estimates: bool,
     },
 
     /// Send one transfer per chain in bulk mode
@@ -694,6 +893,53 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
 
+        /// Set the maximum size of the stored state of each application, in bytes.
+        #[arg(long)]
+        maximum_application_state_size: Option<u64>,
+
+        /// Set the maximum number of operations per block.
+        #[arg(long)]
+        maximum_operations_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming message bundles per block.
+        #[arg(long)]
+        maximum_incoming_bundles_per_block: Option<u64>,
+
+        /// Set the maximum number of incoming message bundles from the same origin per block.
+        #[arg(long)]
+        maximum_incoming_bundles_per_origin: Option<u64>,
+
+        /// Burn the unused part of message grants instead of refunding it.
+        #[arg(long)]
+        burn_unused_grants: bool,
+
+        /// Set the maximum size of the response to a query of an application, in bytes.
+        #[arg(long)]
+        maximum_query_response_size: Option<u64>,
+
+        /// Set the maximum number of messages an application can send per execution.
+        #[arg(long)]
+        maximum_messages_per_execution: Option<u64>,
+
+        /// Set the maximum size of a message sent by an application, in bytes.
+        #[arg(long)]
+        maximum_message_size: Option<u64>,
+
+        /// Set the maximum total size of the messages an application can send per execution,
+        /// in bytes.
+        #[arg(long)]
+        maximum_message_bytes_per_execution: Option<u64>,
+
+        /// Set the maximum depth of nested calls between applications, and of nested queries
+        /// between services.
+        #[arg(long)]
+        maximum_call_depth: Option<u64>,
+
+        /// Require block proposals to claim the resources each operation uses, and reject them
+        /// if an operation uses more than its claim by more than this many percent of it.
+        #[arg(long)]
+        resource_estimate_tolerance_percent: Option<u64>,
+
         /// Force this wallet to generate keys using a PRNG and a given seed. USE FOR
         /// TESTING ONLY.
         #[arg(long)]
@@ -722,6 +968,24 @@ pub enum ClientCommand {
         /// The port on which to run the server
         #[arg(long, default_value = "8080")]
         port: NonZeroU16,
+
+        /// Enable the options meant for developing applications, which must not be used
+        /// in production.
+        #[arg(long)]
+        dev: bool,
+
+        /// Answer the queries of an application with the service in a local Wasm file
+        /// instead of its published bytecode, and load the file again whenever it
+        /// changes. Operations and messages still execute the published contract.
+        /// Written as `APPLICATION_ID=PATH`, and requires `--dev`.
+        #[arg(long = "service-override", requires = "dev")]
+        service_overrides: Vec<ServiceOverride>,
+
+        #[command(flatten)]
+        readiness: ReadinessConfig,
+
+        #[command(flatten)]
+        query_rate_limits: QueryRateLimitConfig,
     },
 
     /// Run a GraphQL service that exposes a faucet where users can claim tokens.
@@ -743,6 +1007,11 @@ pub enum ClientCommand {
         #[arg(long)]
         limit_rate_until: Option<DateTime<Utc>>,
 
+        /// The minimum time between two claims from the `/claim` endpoint for the same recipient
+        /// or from the same IP address.
+        #[arg(long = "claim-interval-secs", default_value = "3600")]
+        claim_interval_secs: u64,
+
         /// Configuration for the faucet chain listener.
         #[command(flatten)]
         config: ChainListenerConfig,
@@ -808,6 +1077,11 @@ pub enum ClientCommand {
         /// The list of required dependencies of application, if any.
         #[arg(long, num_args(0..))]
         required_application_ids: Option<Vec<UserApplicationId>>,
+
+        /// The maximum size in bytes of the responses to queries of the application, if it
+        /// should be lower than the network-wide limit.
+        #[arg(long)]
+        max_query_response_bytes: Option<u64>,
     },
 
     /// Create an application, and publish the required bytecode.
@@ -841,6 +1115,16 @@ pub enum ClientCommand {
         /// The list of required dependencies of application, if any.
         #[arg(long, num_args(0..))]
         required_application_ids: Option<Vec<UserApplicationId>>,
+
+        /// The maximum size in bytes of the responses to queries of the application, if it
+        /// should be lower than the network-wide limit.
+        #[arg(long)]
+        max_query_response_bytes: Option<u64>,
+
+        /// Print the bytecode ID and the application ID as a JSON object, instead of only
+        /// printing the application ID.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Request an application from another chain, so it can be used on this one.
@@ -861,7 +1145,8 @@ pub enum ClientCommand {
     /// Create an unassigned key-pair.
     Keygen,
 
-    /// Link an owner with a key pair in the wallet to a chain that was created for that owner.
+    /// Link an owner with a key pair in the wallet to a chain that was created for that owner,
+    /// or transferred to them.
     Assign {
         /// The owner to assign.
         #[arg(long)]
@@ -869,17 +1154,33 @@ pub enum ClientCommand {
 
         /// The ID of the message that created the chain. (This uniquely describes the
         /// chain and where it was created.)
+        #[arg(
+            long,
+            required_unless_present = "chain_id",
+            conflicts_with = "chain_id"
+        )]
+        message_id: Option<MessageId>,
+
+        /// The ID of an existing chain that was transferred to the owner.
         #[arg(long)]
-        message_id: MessageId,
+        chain_id: Option<ChainId>,
     },
 
     /// Retry a block we unsuccessfully tried to propose earlier.
     ///
     /// As long as a block is pending most other commands will fail, since it is unsafe to propose
     /// multiple blocks at the same height.
+    ///
+    /// The block is checked and executed locally first. The problems found are printed, and if
+    /// the block is expected to be rejected, a confirmation is asked before proposing it.
     RetryPendingBlock {
         /// The chain with the pending block. If not specified, the wallet's default chain is used.
         chain_id: Option<ChainId>,
+
+        /// Propose the pending block without asking for confirmation, even if it is expected
+        /// to be rejected.
+        #[arg(long)]
+        yes: bool,
     },
 
     /// Show the contents of the wallet.
@@ -972,6 +1273,70 @@ pub enum DatabaseToolCommand {
         #[arg(long = "storage")]
         storage_config: String,
     },
+
+    /// Apply the pending migrations of the storage format of a namespace
+    ///
+    /// Validators and clients refuse to use a namespace while a migration is pending, so this
+    /// must be run after upgrading, while they are stopped. An interrupted migration resumes
+    /// where it stopped when it is run again.
+    #[command(name = "migrate")]
+    Migrate {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// Only report the pending migrations, without writing to the database.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Report the number and size of the keys of a RocksDB namespace, grouped by prefix
+    #[command(name = "stats")]
+    Stats {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// The number of leading bytes of the keys used to group them.
+        #[arg(long, default_value = "2")]
+        prefix_length: usize,
+
+        /// Scan at most this number of keys for each prefix, instead of all of them.
+        #[arg(long)]
+        sample_size: Option<u64>,
+    },
+
+    /// Compact a RocksDB namespace, discarding the deleted entries
+    #[command(name = "compact")]
+    Compact {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// Only compact the state of this chain.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
+    /// Check that the state of every chain matches the state hash of its latest block
+    #[command(name = "verify")]
+    Verify {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+    },
+
+    /// Reset the execution statistics of the chains, while the validator is stopped
+    #[command(name = "reset_statistics")]
+    ResetStatistics {
+        /// Storage configuration for the blockchain history.
+        #[arg(long = "storage")]
+        storage_config: String,
+
+        /// Only reset the statistics of this chain.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
 }
 
 impl DatabaseToolCommand {
@@ -983,6 +1348,11 @@ impl DatabaseToolCommand {
             DatabaseToolCommand::CheckAbsence { storage_config } => storage_config,
             DatabaseToolCommand::Initialize { storage_config } => storage_config,
             DatabaseToolCommand::ListNamespaces { storage_config } => storage_config,
+            DatabaseToolCommand::Migrate { storage_config, .. } => storage_config,
+            DatabaseToolCommand::Stats { storage_config, .. } => storage_config,
+            DatabaseToolCommand::Compact { storage_config, .. } => storage_config,
+            DatabaseToolCommand::Verify { storage_config } => storage_config,
+            DatabaseToolCommand::ResetStatistics { storage_config, .. } => storage_config,
         };
         Ok(storage_config.parse::<StorageConfigNamespace>()?)
     }
@@ -1055,6 +1425,7 @@ pub enum NetCommand {
 
         /// Run with a specific storage.
         /// If none, then a linera-storage-service is started on a random free port.
+        /// Memory and RocksDB storage are also served by a linera-storage-service.
         #[arg(long)]
         storage: Option<String>,
 
@@ -1081,6 +1452,16 @@ pub enum NetCommand {
     Helper,
 }
 
+/// What to do with the incoming messages left in the inbox of a chain that is being closed.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PendingMessagesOnClose {
+    /// Execute the messages before closing the chain.
+    Process,
+    /// Close the chain first, so that the messages are rejected and bounce back to their
+    /// senders.
+    Bounce,
+}
+
 #[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResourceControlPolicyConfig {
     Default,
@@ -1128,6 +1509,10 @@ pub enum WalletCommand {
         /// Print only the chains that we have a key pair for.
         #[arg(long)]
         owned: bool,
+        /// Print the recorded evidence of validator misbehavior instead of the chains, only
+        /// about the given chain if any.
+        #[arg(long, conflicts_with_all = ["short", "owned"])]
+        reports: bool,
     },
 
     /// Change the wallet default chain.
@@ -1163,6 +1548,13 @@ pub enum WalletCommand {
 
     /// Forgets the specified chain, including the associated key pair.
     ForgetChain { chain_id: ChainId },
+
+    /// Encrypt the secret keys of the wallet with a passphrase.
+    ///
+    /// This migrates a wallet whose keys are stored in plaintext. If the keys are already
+    /// encrypted, this changes the passphrase. The passphrase is read from the
+    /// `LINERA_WALLET_PASSPHRASE` environment variable, or asked for on the terminal.
+    Encrypt,
 }
 
 #[derive(Clone, clap::Parser)]
@@ -1219,6 +1611,11 @@ pub enum ProjectCommand {
         /// The list of required dependencies of application, if any.
         #[arg(long, num_args(0..))]
         required_application_ids: Option<Vec<UserApplicationId>>,
+
+        /// The maximum size in bytes of the responses to queries of the application, if it
+        /// should be lower than the network-wide limit.
+        #[arg(long)]
+        max_query_response_bytes: Option<u64>,
     },
 }
 
@@ -1355,3 +1752,125 @@ impl From<ApplicationPermissionsConfig> for ApplicationPermissions {
         }
     }
 }
+
+/// The thresholds of the readiness checks of the node service and of the validators.
+#[derive(Debug, Clone, clap::Args)]
+pub struct ReadinessConfig {
+    /// The largest number of requests waiting for the chain workers with which the service
+    /// is ready.
+    #[arg(long = "ready-max-queue-depth", default_value = "1000")]
+    pub max_queue_depth: usize,
+
+    /// The largest number of blocks that the local node may be missing on a chain, compared
+    /// to the validators, with which the service is ready.
+    #[arg(long = "ready-max-blocks-behind", default_value = "10")]
+    pub max_blocks_behind: u64,
+
+    /// The largest age of the last processed certificate with which the service is ready.
+    /// Not checked unless set, since idle networks don't produce certificates.
+    #[arg(long = "ready-max-certificate-age-secs", value_parser = util::parse_secs)]
+    pub max_certificate_age: Option<Duration>,
+
+    /// How long the number of blocks the local node is missing is reused before asking the
+    /// validators again.
+    #[arg(
+        long = "ready-sync-check-interval-secs",
+        default_value = "30",
+        value_parser = util::parse_secs
+    )]
+    pub sync_check_interval: Duration,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        ReadinessConfig {
+            max_queue_depth: 1000,
+            max_blocks_behind: 10,
+            max_certificate_age: None,
+            sync_check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The rate limits of the queries of the node service to the applications. Each application
+/// on each chain has its own budget.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct QueryRateLimitConfig {
+    /// The largest rate of queries to each application on each chain, including the ones made
+    /// through the `queryApplication` field of the GraphQL API, written as `RATE[/BURST]` where
+    /// `RATE` is the number of queries per second and `BURST` the number of queries that can
+    /// be made at once. Not limited unless set.
+    #[arg(long = "query-rate-limit")]
+    pub default_limit: Option<RateLimit>,
+
+    /// The rate limit of the queries to one application, instead of `--query-rate-limit`,
+    /// written as `APPLICATION_ID=RATE[/BURST]`.
+    #[arg(long = "query-rate-limit-override")]
+    pub overrides: Vec<ApplicationRateLimit>,
+
+    /// The largest rate of queries of the contracts to the service of each application on each
+    /// chain, while the node service executes blocks, written as `RATE[/BURST]`. These queries
+    /// don't count against the other limits. Not limited unless set.
+    #[arg(long = "internal-query-rate-limit")]
+    pub internal_limit: Option<RateLimit>,
+}
+
+/// A limit on the rate of some requests, enforced with a token bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// The number of requests allowed per second, on average.
+    pub per_second: f64,
+    /// The number of requests that can be made at once.
+    pub burst: u32,
+}
+
+impl std::str::FromStr for RateLimit {
+    type Err = String;
+
+    /// Parses a rate limit written as `RATE[/BURST]`. The burst is the rate rounded up if it
+    /// is not given.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rate, burst) = match s.split_once('/') {
+            Some((rate, burst)) => (rate, Some(burst)),
+            None => (s, None),
+        };
+        let per_second = rate
+            .parse::<f64>()
+            .ok()
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .ok_or_else(|| format!("expected a positive number of requests per second: {s:?}"))?;
+        let burst = match burst {
+            Some(burst) => burst
+                .parse::<u32>()
+                .ok()
+                .filter(|burst| *burst > 0)
+                .ok_or_else(|| format!("expected a positive burst size: {s:?}"))?,
+            None => per_second.ceil().min(u32::MAX as f64) as u32,
+        };
+        Ok(RateLimit { per_second, burst })
+    }
+}
+
+/// The rate limit of the queries to one application.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApplicationRateLimit {
+    pub application_id: UserApplicationId,
+    pub limit: RateLimit,
+}
+
+impl std::str::FromStr for ApplicationRateLimit {
+    type Err = String;
+
+    /// Parses a rate limit written as `APPLICATION_ID=RATE[/BURST]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (application_id, limit) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `APPLICATION_ID=RATE[/BURST]`, found {s:?}"))?;
+        Ok(ApplicationRateLimit {
+            application_id: application_id
+                .parse()
+                .map_err(|error| format!("invalid application ID: {error}"))?,
+            limit: limit.parse()?,
+        })
+    }
+}
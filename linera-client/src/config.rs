@@ -16,7 +16,9 @@ use linera_execution::{
     committee::{Committee, ValidatorName, ValidatorState},
     ResourceControlPolicy,
 };
-use linera_rpc::config::{ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig};
+use linera_rpc::config::{
+    RequestLimitsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+};
 use linera_storage::Storage;
 use serde::{Deserialize, Serialize};
 
@@ -40,6 +42,8 @@ util::impl_from_dynamic!(Error:Persistence, persistent::memory::Error);
 util::impl_from_dynamic!(Error:Persistence, persistent::indexed_db::Error);
 #[cfg(feature = "fs")]
 util::impl_from_dynamic!(Error:Persistence, persistent::file::Error);
+#[cfg(not(web))]
+util::impl_from_dynamic!(Error:Persistence, persistent::key_value::Error);
 
 /// The public configuration of a validator.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -56,6 +60,9 @@ pub struct ValidatorServerConfig {
     pub validator: ValidatorConfig,
     pub key: KeyPair,
     pub internal_network: ValidatorInternalNetworkConfig,
+    /// The limits on the requests accepted by the validator's public endpoint.
+    #[serde(default)]
+    pub request_limits: RequestLimitsConfig,
 }
 
 #[cfg(web)]
@@ -153,6 +160,22 @@ impl WalletState<persistent::File<Wallet>> {
     }
 }
 
+#[cfg(not(web))]
+impl<S> WalletState<persistent::KeyValue<S, Wallet>>
+where
+    S: linera_views::store::RestrictedKeyValueStore + Send + Sync,
+{
+    pub async fn create_from_store(store: S, wallet: Wallet) -> Result<Self, Error> {
+        Ok(Self::new(
+            persistent::KeyValue::read_or_create(store, wallet).await?,
+        ))
+    }
+
+    pub async fn read_from_store(store: S) -> Result<Option<Self>, Error> {
+        Ok(persistent::KeyValue::read(store).await?.map(Self::new))
+    }
+}
+
 #[cfg(with_indexed_db)]
 impl WalletState<persistent::IndexedDb<Wallet>> {
     pub async fn create_from_indexed_db(key: &str, wallet: Wallet) -> Result<Self, Error> {
@@ -22,8 +22,21 @@ pub(crate) enum Inner {
     NonexistentChain(linera_base::identifiers::ChainId),
     #[error("no keypair found for chain: {0:?}")]
     NonexistentKeypair(linera_base::identifiers::ChainId),
+    #[error(
+        "chain {chain_id} still has {count} pending incoming message bundles; \
+        process or bounce them to close it"
+    )]
+    PendingMessages {
+        chain_id: linera_base::identifiers::ChainId,
+        count: usize,
+    },
     #[error("error on the local node: {0}")]
     LocalNode(#[from] linera_core::local_node::LocalNodeError),
+    #[cfg(with_wasm_runtime)]
+    #[error("invalid bytecode: {0}")]
+    InvalidBytecode(#[from] linera_execution::WasmExecutionError),
+    #[error("no chain was specified and the wallet has no default chain")]
+    NoDefaultChain,
 }
 
 thiserror_context::impl_context!(Error(Inner));
@@ -33,3 +46,5 @@ util::impl_from_dynamic!(Inner:Persistence, persistent::memory::Error);
 util::impl_from_dynamic!(Inner:Persistence, persistent::file::Error);
 #[cfg(with_indexed_db)]
 util::impl_from_dynamic!(Inner:Persistence, persistent::indexed_db::Error);
+#[cfg(not(web))]
+util::impl_from_dynamic!(Inner:Persistence, persistent::key_value::Error);
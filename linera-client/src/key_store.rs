@@ -0,0 +1,172 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encryption of the secret keys stored in a wallet.
+//!
+//! The keys are encrypted with ChaCha20-Poly1305, using a key derived from a passphrase
+//! with PBKDF2-HMAC-SHA256. The derived key and the decrypted secret keys are only ever
+//! held in memory.
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use linera_base::{crypto::KeyPair, identifiers::ChainId};
+use ring::{
+    aead, pbkdf2,
+    rand::{SecureRandom as _, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+/// The environment variable from which the wallet passphrase is read, if it is set.
+pub const PASSPHRASE_ENV_VAR: &str = "LINERA_WALLET_PASSPHRASE";
+
+/// The number of PBKDF2 iterations used for new key stores.
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// The length of the random salt used for the key derivation.
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("wrong passphrase, or corrupted encrypted keys")]
+    WrongPassphrase,
+    #[error("the wallet keys are encrypted and must be unlocked first")]
+    Locked,
+    #[error("the number of key derivation iterations must not be zero")]
+    ZeroIterations,
+    #[error("failed to generate random bytes")]
+    Randomness,
+    #[error("failed to encrypt the keys")]
+    Encryption,
+    #[error("invalid key data: {0}")]
+    InvalidKeys(#[from] serde_json::Error),
+    #[error("failed to read the passphrase: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The secret keys of a wallet, encrypted with a passphrase.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedKeys {
+    #[serde(with = "hex_bytes")]
+    salt: Vec<u8>,
+    iterations: u32,
+    #[serde(with = "hex_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+/// The secret keys of a wallet, in plaintext.
+#[derive(Default, Serialize, Deserialize)]
+pub struct StoredKeys {
+    /// The key pairs of the chains in the wallet.
+    pub chains: BTreeMap<ChainId, KeyPair>,
+    /// The key pairs that are not assigned to a chain yet.
+    pub unassigned: Vec<KeyPair>,
+}
+
+/// A symmetric key derived from a wallet passphrase.
+pub struct KeyStore {
+    salt: Vec<u8>,
+    iterations: u32,
+    key: aead::LessSafeKey,
+}
+
+impl KeyStore {
+    /// Derives a new key from the `passphrase`, using a fresh random salt.
+    pub fn new(passphrase: &str) -> Result<Self, Error> {
+        let mut salt = vec![0; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| Error::Randomness)?;
+        Self::derive(passphrase, salt, PBKDF2_ITERATIONS)
+    }
+
+    /// Derives the key from the `passphrase` and uses it to decrypt the `encrypted` keys.
+    pub fn unlock(
+        passphrase: &str,
+        encrypted: &EncryptedKeys,
+    ) -> Result<(Self, StoredKeys), Error> {
+        let key_store = Self::derive(passphrase, encrypted.salt.clone(), encrypted.iterations)?;
+        let keys = key_store.decrypt(encrypted)?;
+        Ok((key_store, keys))
+    }
+
+    /// Encrypts the `keys`, using a fresh random nonce.
+    pub fn encrypt(&self, keys: &StoredKeys) -> Result<EncryptedKeys, Error> {
+        let mut nonce = [0; aead::NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| Error::Randomness)?;
+        let mut ciphertext = serde_json::to_vec(keys)?;
+        self.key
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::empty(),
+                &mut ciphertext,
+            )
+            .map_err(|_| Error::Encryption)?;
+        Ok(EncryptedKeys {
+            salt: self.salt.clone(),
+            iterations: self.iterations,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    fn decrypt(&self, encrypted: &EncryptedKeys) -> Result<StoredKeys, Error> {
+        let nonce = aead::Nonce::try_assume_unique_for_key(&encrypted.nonce)
+            .map_err(|_| Error::WrongPassphrase)?;
+        let mut buffer = encrypted.ciphertext.clone();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, aead::Aad::empty(), &mut buffer)
+            .map_err(|_| Error::WrongPassphrase)?;
+        Ok(serde_json::from_slice(plaintext)?)
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>, iterations: u32) -> Result<Self, Error> {
+        let rounds = NonZeroU32::new(iterations).ok_or(Error::ZeroIterations)?;
+        let mut key_bytes = [0; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            rounds,
+            &salt,
+            passphrase.as_bytes(),
+            &mut key_bytes,
+        );
+        let key = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &key_bytes)
+            .expect("ChaCha20-Poly1305 keys are 32 bytes long");
+        key_bytes.fill(0);
+        Ok(KeyStore {
+            salt,
+            iterations,
+            key: aead::LessSafeKey::new(key),
+        })
+    }
+}
+
+/// Returns the wallet passphrase from the [`PASSPHRASE_ENV_VAR`] environment variable if
+/// it is set, or asks for it on the terminal otherwise.
+pub fn read_passphrase(prompt: &str) -> Result<String, Error> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        return Ok(passphrase);
+    }
+    eprint!("{prompt}");
+    let mut passphrase = String::new();
+    std::io::stdin().read_line(&mut passphrase)?;
+    Ok(passphrase.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Serializes byte vectors as hexadecimal strings.
+mod hex_bytes {
+    use serde::{de::Error as _, Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        hex::decode(string).map_err(D::Error::custom)
+    }
+}
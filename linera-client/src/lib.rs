@@ -6,12 +6,16 @@
 #![recursion_limit = "256"]
 #![deny(clippy::large_futures)]
 
+pub mod any_store;
+pub mod chain_client;
 pub mod chain_listener;
 pub mod client_context;
 pub mod client_options;
 pub mod config;
 mod error;
+pub mod key_store;
 pub mod persistent;
+pub mod service_override;
 pub mod storage;
 pub mod util;
 pub mod wallet;
@@ -148,7 +148,8 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
         path: &Path,
         value: impl FnOnce() -> Result<T, Error>,
     ) -> Result<Self, Error> {
-        let lock = Lock::new(open_options().read(true).open(path)?)?;
+        let lock = Lock::new(open_options().read(true).open(path)?)
+            .with_context(|| format!("locking path {}", path.display()))?;
         let mut reader = io::BufReader::new(&lock.0);
         let file_is_empty = reader.fill_buf()?.is_empty();
 
@@ -167,7 +168,9 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
     fn save(&mut self) -> Result<(), Error> {
         let mut temp_file_path = self.path.clone();
         temp_file_path.set_extension("json.new");
-        let temp_file = open_options().open(&temp_file_path)?;
+        // A staging file left over by a crash must not leave trailing bytes after the new
+        // contents.
+        let temp_file = open_options().truncate(true).open(&temp_file_path)?;
         let mut temp_file_writer = std::io::BufWriter::new(temp_file);
 
         let remove_temp_file = || fs_err::remove_file(&temp_file_path);
@@ -179,6 +182,11 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned> File<T> {
             .flush()
             .map_err(Error::from)
             .or_cleanup(remove_temp_file)?;
+        temp_file_writer
+            .get_ref()
+            .sync_all()
+            .map_err(Error::from)
+            .or_cleanup(remove_temp_file)?;
         fs_err::rename(&temp_file_path, &self.path)?;
         *self.dirty = false;
         Ok(())
@@ -198,9 +206,9 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned + Send> Persist for File<
     /// a temporary file is created as a backup in case a crash occurs while
     /// writing to disk.
     ///
-    /// The temporary file is then renamed to the original filename. If
-    /// serialization or writing to disk fails, the temporary file is
-    /// deleted.
+    /// The temporary file is synced to disk and then renamed to the original
+    /// filename. If serialization or writing to disk fails, the temporary file
+    /// is deleted.
     async fn persist(&mut self) -> Result<(), Error> {
         self.save()
     }
@@ -0,0 +1,154 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use linera_views::{
+    batch::Batch,
+    store::{KeyValueIterable as _, RestrictedKeyValueStore},
+    views::ViewError,
+};
+
+use super::{Dirty, Entries, Persist};
+
+/// The first byte of the keys of the entries in the store.
+const ENTRY_KEY_PREFIX: u8 = 0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("storage error: {0}")]
+    Store(#[from] ViewError),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// An implementation of [`Persist`] based on a key-value store, such as RocksDB, for
+/// services running without a wallet file.
+///
+/// The value is saved as several [`Entries`], and only the entries that changed since they
+/// were last saved are written, in a single batch.
+#[derive(derive_more::Deref)]
+pub struct KeyValue<S, T> {
+    store: S,
+    #[deref]
+    value: T,
+    /// The entries as they were last written to the store.
+    saved_entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    dirty: Dirty,
+}
+
+impl<S, T> KeyValue<S, T>
+where
+    S: RestrictedKeyValueStore + Send + Sync,
+    T: Entries + Send,
+{
+    /// Creates a value to be saved in the `store`, overwriting the value there, if any.
+    pub async fn new(store: S, value: T) -> Result<Self, Error> {
+        let saved_entries = Self::read_entries(&store).await?;
+        Ok(Self {
+            store,
+            value,
+            saved_entries,
+            dirty: Dirty::new(true),
+        })
+    }
+
+    /// Reads the value from the `store`, returning [`None`] if it was never saved.
+    pub async fn read(store: S) -> Result<Option<Self>, Error> {
+        let saved_entries = Self::read_entries(&store).await?;
+        if saved_entries.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            value: T::from_entries(saved_entries.clone())?,
+            store,
+            saved_entries,
+            dirty: Dirty::new(false),
+        }))
+    }
+
+    /// Reads the value from the `store`, saving `value` there instead if there is none.
+    pub async fn read_or_create(store: S, value: T) -> Result<Self, Error> {
+        let saved_entries = Self::read_entries(&store).await?;
+        if saved_entries.is_empty() {
+            let mut this = Self {
+                store,
+                value,
+                saved_entries,
+                dirty: Dirty::new(true),
+            };
+            this.persist().await?;
+            Ok(this)
+        } else {
+            Ok(Self {
+                value: T::from_entries(saved_entries.clone())?,
+                store,
+                saved_entries,
+                dirty: Dirty::new(false),
+            })
+        }
+    }
+
+    async fn read_entries(store: &S) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, Error> {
+        let key_values = store
+            .find_key_values_by_prefix(&[ENTRY_KEY_PREFIX])
+            .await
+            .map_err(ViewError::from)?;
+        let mut entries = BTreeMap::new();
+        for entry in key_values.into_iterator_owned() {
+            let (key, value) = entry.map_err(ViewError::from)?;
+            entries.insert(key, value);
+        }
+        Ok(entries)
+    }
+}
+
+impl<S, T> Persist for KeyValue<S, T>
+where
+    S: RestrictedKeyValueStore + Send + Sync,
+    T: Entries + Send,
+{
+    type Error = Error;
+
+    fn as_mut(&mut self) -> &mut T {
+        *self.dirty = true;
+        &mut self.value
+    }
+
+    /// Writes the entries that changed since the last time the value was saved, and
+    /// deletes the entries that no longer exist.
+    async fn persist(&mut self) -> Result<(), Error> {
+        let entries = self.value.to_entries()?;
+        let mut batch = Batch::new();
+        for (key, value) in &entries {
+            if self.saved_entries.get(key) != Some(value) {
+                batch.put_key_value_bytes(entry_key(key), value.clone());
+            }
+        }
+        for key in self.saved_entries.keys() {
+            if !entries.contains_key(key) {
+                batch.delete_key(entry_key(key));
+            }
+        }
+        if !batch.is_empty() {
+            self.store
+                .write_batch(batch)
+                .await
+                .map_err(ViewError::from)?;
+        }
+        self.saved_entries = entries;
+        *self.dirty = false;
+        Ok(())
+    }
+
+    fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Returns the key in the store of the entry with the given key.
+fn entry_key(key: &[u8]) -> Vec<u8> {
+    let mut entry_key = vec![ENTRY_KEY_PREFIX];
+    entry_key.extend_from_slice(key);
+    entry_key
+}
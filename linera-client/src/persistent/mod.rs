@@ -18,11 +18,28 @@ cfg_if::cfg_if! {
     }
 }
 
+cfg_if::cfg_if! {
+    if #[cfg(not(web))] {
+        pub mod key_value;
+        pub use key_value::KeyValue;
+    }
+}
+
 pub mod memory;
-use std::ops::Deref;
+use std::{collections::BTreeMap, ops::Deref};
 
 pub use memory::Memory;
 
+/// A value that can be saved as several entries, indexed by their keys, so that saving it
+/// again only needs to write the entries that changed.
+pub trait Entries: Sized {
+    /// Returns the serialized entries of the value.
+    fn to_entries(&self) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, serde_json::Error>;
+
+    /// Recreates the value from its serialized entries.
+    fn from_entries(entries: BTreeMap<Vec<u8>, Vec<u8>>) -> Result<Self, serde_json::Error>;
+}
+
 /// The `Persist` trait provides a wrapper around a value that can be saved in a
 /// persistent way. A minimal implementation provides an `Error` type, a `persist`
 /// function to persist the value, and an `as_mut` function to get a mutable reference to
@@ -0,0 +1,211 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Services loaded from local files instead of the published bytecode of their applications,
+//! to try changes to the queries of an application during development without publishing it
+//! again.
+
+use std::{path::PathBuf, str::FromStr};
+
+use linera_base::{identifiers::UserApplicationId, BcsHexParseError};
+#[cfg(not(web))]
+use {
+    linera_base::data_types::Bytecode,
+    linera_execution::UserServiceCode,
+    linera_storage::Storage,
+    std::time::{Duration, SystemTime},
+    tracing::{info, warn},
+};
+
+/// How often the files of the overridden services are checked for changes by default.
+#[cfg(not(web))]
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("expected `APPLICATION_ID=PATH`, found {0:?}")]
+    InvalidFormat(String),
+    #[error("invalid application ID: {0}")]
+    ApplicationId(#[from] BcsHexParseError),
+    #[error("failed to read the service bytecode in {path:?}: {error}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("a Wasm runtime is required to load services from files")]
+    NoWasmRuntime,
+    #[cfg(with_wasm_runtime)]
+    #[error("invalid service bytecode in {path:?}: {error}")]
+    InvalidBytecode {
+        path: PathBuf,
+        #[source]
+        error: linera_execution::WasmExecutionError,
+    },
+}
+
+/// An application whose queries are answered by the service in a local Wasm file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceOverride {
+    pub application_id: UserApplicationId,
+    pub path: PathBuf,
+}
+
+impl FromStr for ServiceOverride {
+    type Err = Error;
+
+    /// Parses a service override written as `APPLICATION_ID=PATH`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (application_id, path) = s
+            .split_once('=')
+            .filter(|(_, path)| !path.is_empty())
+            .ok_or_else(|| Error::InvalidFormat(s.to_owned()))?;
+        Ok(ServiceOverride {
+            application_id: application_id.parse()?,
+            path: path.into(),
+        })
+    }
+}
+
+/// The last version of an overridden service that was loaded.
+#[cfg(not(web))]
+struct LoadedService {
+    service_override: ServiceOverride,
+    /// The modification time and the size of the file when it was last read.
+    file_version: Option<(SystemTime, u64)>,
+    bytecode: Option<Bytecode>,
+}
+
+/// Loads the services of some applications from local files into the
+/// [`service_overrides`][`Storage::service_overrides`] of a storage, and loads them again
+/// whenever the files change.
+#[cfg(not(web))]
+pub struct ServiceOverrideWatcher<S> {
+    storage: S,
+    services: Vec<LoadedService>,
+    poll_interval: Duration,
+}
+
+#[cfg(not(web))]
+impl<S: Storage> ServiceOverrideWatcher<S> {
+    /// Creates a watcher for the given `service_overrides`. Nothing is loaded until
+    /// [`reload`][`Self::reload`] or [`run`][`Self::run`] is called.
+    pub fn new(storage: S, service_overrides: Vec<ServiceOverride>) -> Self {
+        let services = service_overrides
+            .into_iter()
+            .map(|service_override| LoadedService {
+                service_override,
+                file_version: None,
+                bytecode: None,
+            })
+            .collect();
+        ServiceOverrideWatcher {
+            storage,
+            services,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets how often the files are checked for changes.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Loads the services whose files changed since they were last loaded, and returns how
+    /// many of them were replaced.
+    ///
+    /// The services that fail to load are left as they were, and the first error is
+    /// returned after trying all of them.
+    pub async fn reload(&mut self) -> Result<usize, Error> {
+        let mut reloaded = 0;
+        let mut first_error = None;
+        for index in 0..self.services.len() {
+            match self.reload_service(index).await {
+                Ok(true) => reloaded += 1,
+                Ok(false) => {}
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(reloaded),
+        }
+    }
+
+    /// Checks the files for changes every poll interval, forever. Failures to load a
+    /// service are logged, and the previous version keeps answering the queries.
+    pub async fn run(mut self) {
+        loop {
+            if let Err(error) = self.reload().await {
+                warn!("Failed to reload an overridden service: {error}");
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    async fn reload_service(&mut self, index: usize) -> Result<bool, Error> {
+        let service = &mut self.services[index];
+        let path = service.service_override.path.clone();
+        let io_error = |error| Error::Io {
+            path: path.clone(),
+            error,
+        };
+        let metadata = tokio::fs::metadata(&path).await.map_err(io_error)?;
+        let file_version = Some((metadata.modified().map_err(io_error)?, metadata.len()));
+        if service.bytecode.is_some() && file_version == service.file_version {
+            return Ok(false);
+        }
+        let bytecode = Bytecode::new(tokio::fs::read(&path).await.map_err(io_error)?);
+        service.file_version = file_version;
+        if service.bytecode.as_ref() == Some(&bytecode) {
+            return Ok(false);
+        }
+
+        let code = Self::compile(&self.storage, bytecode.clone(), &path).await?;
+        let service = &mut self.services[index];
+        let application_id = service.service_override.application_id;
+        self.storage
+            .service_overrides()
+            .insert(application_id, code);
+        if let Some(previous_bytecode) = service.bytecode.replace(bytecode) {
+            Self::evict(&previous_bytecode).await;
+        }
+        info!("Loaded the service of application {application_id} from {path:?}");
+        Ok(true)
+    }
+
+    #[cfg(with_wasm_runtime)]
+    async fn compile(
+        storage: &S,
+        bytecode: Bytecode,
+        path: &std::path::Path,
+    ) -> Result<UserServiceCode, Error> {
+        let wasm_runtime = storage.wasm_runtime().ok_or(Error::NoWasmRuntime)?;
+        let module = linera_execution::WasmServiceModule::new(bytecode, wasm_runtime)
+            .await
+            .map_err(|error| Error::InvalidBytecode {
+                path: path.to_owned(),
+                error,
+            })?;
+        Ok(module.into())
+    }
+
+    #[cfg(not(with_wasm_runtime))]
+    async fn compile(
+        _storage: &S,
+        _bytecode: Bytecode,
+        _path: &std::path::Path,
+    ) -> Result<UserServiceCode, Error> {
+        Err(Error::NoWasmRuntime)
+    }
+
+    /// Drops the module compiled from a previous version of a service, which will not be
+    /// used anymore.
+    async fn evict(_bytecode: &Bytecode) {
+        #[cfg(with_wasm_runtime)]
+        linera_execution::WasmServiceModule::evict_from_cache(_bytecode).await;
+    }
+}
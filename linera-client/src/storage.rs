@@ -4,36 +4,36 @@
 use std::{fmt, str::FromStr};
 
 use async_trait::async_trait;
+use linera_base::{data_types::Timestamp, identifiers::ChainId};
 use linera_execution::WasmRuntime;
-use linera_storage::{DbStorage, Storage};
+use linera_storage::{DbStorage, MigrationReport, Storage, VerificationReport, WallClock};
 #[cfg(feature = "storage-service")]
-use linera_storage_service::{
-    client::ServiceStoreClient,
-    common::{ServiceStoreConfig, ServiceStoreInternalConfig},
-};
+use linera_storage_service::common::{ServiceStoreConfig, ServiceStoreInternalConfig};
 #[cfg(feature = "dynamodb")]
-use linera_views::dynamo_db::{get_config, DynamoDbStore, DynamoDbStoreConfig};
-#[cfg(with_storage)]
-use linera_views::store::LocalAdminKeyValueStore as _;
+use linera_views::dynamo_db::{get_config, DynamoDbReadConsistency, DynamoDbStoreConfig};
 use linera_views::{
+    encryption::{EncryptedStoreConfig, EncryptionConfig, ReEncryptionProgress},
     memory::{MemoryStore, MemoryStoreConfig},
-    store::CommonStoreConfig,
+    store::{AdminKeyValueStore as _, CommonStoreConfig},
     views::ViewError,
 };
 use tracing::error;
 #[cfg(feature = "rocksdb")]
 use {
-    linera_views::rocks_db::{PathWithGuard, RocksDbSpawnMode, RocksDbStore, RocksDbStoreConfig},
+    linera_views::rocks_db::{
+        PathWithGuard, RocksDbSpawnMode, RocksDbStatistics, RocksDbStoreConfig,
+        RocksDbStoreInternal,
+    },
     std::path::PathBuf,
 };
 #[cfg(feature = "scylladb")]
-use {
-    linera_views::scylla_db::{ScyllaDbStore, ScyllaDbStoreConfig},
-    std::num::NonZeroU16,
-    tracing::debug,
-};
+use {linera_views::scylla_db::ScyllaDbStoreConfig, std::num::NonZeroU16, tracing::debug};
 
-use crate::{config::GenesisConfig, util};
+use crate::{
+    any_store::{AnyStore, AnyStoreConfig, AnyStoreError},
+    config::GenesisConfig,
+    util,
+};
 
 const DEFAULT_NAMESPACE: &str = "table_linera";
 
@@ -54,10 +54,15 @@ util::impl_from_dynamic!(Error:Backend, linera_views::memory::MemoryStoreError);
 util::impl_from_dynamic!(Error:Backend, linera_storage_service::common::ServiceStoreError);
 #[cfg(feature = "rocksdb")]
 util::impl_from_dynamic!(Error:Backend, linera_views::rocks_db::RocksDbStoreError);
+#[cfg(feature = "rocksdb")]
+util::impl_from_dynamic!(Error:Backend, linera_views::rocks_db::RocksDbStoreInternalError);
 #[cfg(feature = "dynamodb")]
 util::impl_from_dynamic!(Error:Backend, linera_views::dynamo_db::DynamoDbStoreError);
 #[cfg(feature = "scylladb")]
 util::impl_from_dynamic!(Error:Backend, linera_views::scylla_db::ScyllaDbStoreError);
+util::impl_from_dynamic!(Error:Backend, AnyStoreError);
+util::impl_from_dynamic!(Error:Backend, linera_storage::MigrationError);
+util::impl_from_dynamic!(Error:Backend, ViewError);
 
 /// The configuration of the key value store in use.
 pub enum StoreConfig {
@@ -75,6 +80,8 @@ pub enum StoreConfig {
     /// The ScyllaDb key value store
     #[cfg(feature = "scylladb")]
     ScyllaDb(ScyllaDbStoreConfig, String),
+    /// A key value store whose values are encrypted with the given keys
+    Encrypted(Box<StoreConfig>, EncryptionConfig),
 }
 
 /// The description of a storage implementation.
@@ -102,6 +109,8 @@ pub enum StorageConfig {
     DynamoDb {
         /// Whether to use the localstack system
         use_localstack: bool,
+        /// The consistency of the reads
+        read_consistency: DynamoDbReadConsistency,
     },
     /// The ScyllaDb description
     #[cfg(feature = "scylladb")]
@@ -234,7 +243,7 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
         }
         #[cfg(feature = "dynamodb")]
         if let Some(s) = input.strip_prefix(DYNAMO_DB) {
-            let mut parts = s.splitn(2, ':');
+            let mut parts = s.splitn(3, ':');
             let namespace = parts
                 .next()
                 .ok_or_else(|| {
@@ -249,11 +258,24 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
                 Some(unknown) => {
                     return Err(Error::Format(format!(
                         "Invalid DynamoDB endpoint {unknown:?}. \
-                        Expected {DYNAMO_DB}TABLE:[env|localstack]"
+                        Expected {DYNAMO_DB}TABLE:[env|localstack][:strong|eventual]"
                     )));
                 }
             };
-            let storage_config = StorageConfig::DynamoDb { use_localstack };
+            let read_consistency = match parts.next() {
+                None | Some("strong") => DynamoDbReadConsistency::Strong,
+                Some("eventual") => DynamoDbReadConsistency::Eventual,
+                Some(unknown) => {
+                    return Err(Error::Format(format!(
+                        "Invalid DynamoDB read consistency {unknown:?}. \
+                        Expected {DYNAMO_DB}TABLE:[env|localstack][:strong|eventual]"
+                    )));
+                }
+            };
+            let storage_config = StorageConfig::DynamoDb {
+                use_localstack,
+                read_consistency,
+            };
             return Ok(StorageConfigNamespace {
                 storage_config,
                 namespace,
@@ -362,9 +384,13 @@ impl StorageConfigNamespace {
                 Ok(StoreConfig::RocksDb(config, namespace))
             }
             #[cfg(feature = "dynamodb")]
-            StorageConfig::DynamoDb { use_localstack } => {
+            StorageConfig::DynamoDb {
+                use_localstack,
+                read_consistency,
+            } => {
                 let aws_config = get_config(*use_localstack).await?;
-                let config = DynamoDbStoreConfig::new(aws_config, common_config);
+                let config = DynamoDbStoreConfig::new(aws_config, common_config)
+                    .with_read_consistency(*read_consistency);
                 Ok(StoreConfig::DynamoDb(config, namespace))
             }
             #[cfg(feature = "scylladb")]
@@ -396,10 +422,19 @@ impl fmt::Display for StorageConfigNamespace {
                 write!(f, "rocksdb:{}:{}:{}", path.display(), spawn_mode, namespace)
             }
             #[cfg(feature = "dynamodb")]
-            StorageConfig::DynamoDb { use_localstack } => match use_localstack {
-                true => write!(f, "dynamodb:{}:localstack", namespace),
-                false => write!(f, "dynamodb:{}:env", namespace),
-            },
+            StorageConfig::DynamoDb {
+                use_localstack,
+                read_consistency,
+            } => {
+                match use_localstack {
+                    true => write!(f, "dynamodb:{}:localstack", namespace)?,
+                    false => write!(f, "dynamodb:{}:env", namespace)?,
+                }
+                match read_consistency {
+                    DynamoDbReadConsistency::Strong => Ok(()),
+                    DynamoDbReadConsistency::Eventual => write!(f, ":eventual"),
+                }
+            }
             #[cfg(feature = "scylladb")]
             StorageConfig::ScyllaDb { uri } => {
                 write!(f, "scylladb:tcp:{}:{}", uri, namespace)
@@ -409,150 +444,236 @@ impl fmt::Display for StorageConfigNamespace {
 }
 
 impl StoreConfig {
-    /// Deletes all the entries in the database
-    pub async fn delete_all(self) -> Result<(), ViewError> {
+    /// Returns the configuration of the store, for whichever backend it uses, and the
+    /// namespace.
+    pub fn into_any_store_config(self) -> (AnyStoreConfig, String) {
         match self {
-            StoreConfig::Memory(_, _) => Err(ViewError::StoreError {
-                backend: "memory".to_string(),
-                error: "delete_all does not make sense for memory storage".to_string(),
-            }),
+            StoreConfig::Memory(config, namespace) => (AnyStoreConfig::Memory(config), namespace),
             #[cfg(feature = "storage-service")]
-            StoreConfig::Service(config, _namespace) => {
-                ServiceStoreClient::delete_all(&config).await?;
-                Ok(())
-            }
+            StoreConfig::Service(config, namespace) => (AnyStoreConfig::Service(config), namespace),
             #[cfg(feature = "rocksdb")]
-            StoreConfig::RocksDb(config, _namespace) => {
-                RocksDbStore::delete_all(&config).await?;
-                Ok(())
-            }
+            StoreConfig::RocksDb(config, namespace) => (AnyStoreConfig::RocksDb(config), namespace),
             #[cfg(feature = "dynamodb")]
-            StoreConfig::DynamoDb(config, _namespace) => {
-                DynamoDbStore::delete_all(&config).await?;
-                Ok(())
+            StoreConfig::DynamoDb(config, namespace) => {
+                (AnyStoreConfig::DynamoDb(config), namespace)
             }
             #[cfg(feature = "scylladb")]
-            StoreConfig::ScyllaDb(config, _namespace) => {
-                ScyllaDbStore::delete_all(&config).await?;
-                Ok(())
+            StoreConfig::ScyllaDb(config, namespace) => {
+                (AnyStoreConfig::ScyllaDb(config), namespace)
+            }
+            StoreConfig::Encrypted(config, encryption) => {
+                let (inner_config, namespace) = config.into_any_store_config();
+                let config = EncryptedStoreConfig {
+                    inner_config,
+                    encryption,
+                };
+                (AnyStoreConfig::Encrypted(Box::new(config)), namespace)
             }
         }
     }
 
-    /// Deletes only one table of the database
-    pub async fn delete_namespace(self) -> Result<(), ViewError> {
+    /// Returns the configuration where the values are encrypted with the given keys, if any.
+    pub fn with_encryption(self, encryption: Option<EncryptionConfig>) -> StoreConfig {
+        match encryption {
+            Some(encryption) => StoreConfig::Encrypted(Box::new(self), encryption),
+            None => self,
+        }
+    }
+
+    /// Returns the configuration of the store where the values are stored, whether they are
+    /// encrypted or not.
+    fn without_encryption(self) -> StoreConfig {
         match self {
-            StoreConfig::Memory(_, _) => Err(ViewError::StoreError {
-                backend: "memory".to_string(),
-                error: "delete_namespace does not make sense for memory storage".to_string(),
-            }),
+            StoreConfig::Encrypted(config, _) => config.without_encryption(),
+            config => config,
+        }
+    }
+
+    /// Returns whether the store is in memory.
+    pub fn is_memory(&self) -> bool {
+        match self {
+            StoreConfig::Memory(_, _) => true,
             #[cfg(feature = "storage-service")]
-            StoreConfig::Service(config, namespace) => {
-                ServiceStoreClient::delete(&config, &namespace).await?;
-                Ok(())
-            }
+            StoreConfig::Service(_, _) => false,
             #[cfg(feature = "rocksdb")]
-            StoreConfig::RocksDb(config, namespace) => {
-                RocksDbStore::delete(&config, &namespace).await?;
-                Ok(())
-            }
+            StoreConfig::RocksDb(_, _) => false,
             #[cfg(feature = "dynamodb")]
-            StoreConfig::DynamoDb(config, namespace) => {
-                DynamoDbStore::delete(&config, &namespace).await?;
-                Ok(())
-            }
+            StoreConfig::DynamoDb(_, _) => false,
             #[cfg(feature = "scylladb")]
-            StoreConfig::ScyllaDb(config, namespace) => {
-                ScyllaDbStore::delete(&config, &namespace).await?;
-                Ok(())
-            }
+            StoreConfig::ScyllaDb(_, _) => false,
+            StoreConfig::Encrypted(config, _) => config.is_memory(),
+        }
+    }
+
+    /// Returns the configuration of the store and the namespace, unless the store is in
+    /// memory, where administrative operations make no sense.
+    fn into_persistent_store_config(self) -> Option<(AnyStoreConfig, String)> {
+        if self.is_memory() {
+            return None;
         }
+        Some(self.into_any_store_config())
+    }
+
+    /// Deletes all the entries in the database
+    pub async fn delete_all(self) -> Result<(), ViewError> {
+        let Some((config, _namespace)) = self.into_persistent_store_config() else {
+            return Err(ViewError::StoreError {
+                backend: "memory".to_string(),
+                error: "delete_all does not make sense for memory storage".to_string(),
+            });
+        };
+        AnyStore::delete_all(&config).await?;
+        Ok(())
+    }
+
+    /// Deletes only one table of the database
+    pub async fn delete_namespace(self) -> Result<(), ViewError> {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(ViewError::StoreError {
+                backend: "memory".to_string(),
+                error: "delete_namespace does not make sense for memory storage".to_string(),
+            });
+        };
+        AnyStore::delete(&config, &namespace).await?;
+        Ok(())
     }
 
     /// Test existence of one table in the database
     pub async fn test_existence(self) -> Result<bool, ViewError> {
-        match self {
-            StoreConfig::Memory(_, _) => Err(ViewError::StoreError {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(ViewError::StoreError {
                 backend: "memory".to_string(),
                 error: "test_existence does not make sense for memory storage".to_string(),
-            }),
-            #[cfg(feature = "storage-service")]
-            StoreConfig::Service(config, namespace) => {
-                Ok(ServiceStoreClient::exists(&config, &namespace).await?)
-            }
-            #[cfg(feature = "rocksdb")]
-            StoreConfig::RocksDb(config, namespace) => {
-                Ok(RocksDbStore::exists(&config, &namespace).await?)
-            }
-            #[cfg(feature = "dynamodb")]
-            StoreConfig::DynamoDb(config, namespace) => {
-                Ok(DynamoDbStore::exists(&config, &namespace).await?)
-            }
-            #[cfg(feature = "scylladb")]
-            StoreConfig::ScyllaDb(config, namespace) => {
-                Ok(ScyllaDbStore::exists(&config, &namespace).await?)
-            }
-        }
+            });
+        };
+        Ok(AnyStore::exists(&config, &namespace).await?)
     }
 
     /// Initializes the database
     pub async fn initialize(self) -> Result<(), ViewError> {
-        match self {
-            StoreConfig::Memory(_, _) => Err(ViewError::StoreError {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(ViewError::StoreError {
                 backend: "memory".to_string(),
                 error: "initialize does not make sense for memory storage".to_string(),
-            }),
-            #[cfg(feature = "storage-service")]
-            StoreConfig::Service(config, namespace) => {
-                ServiceStoreClient::maybe_create_and_connect(&config, &namespace, ROOT_KEY).await?;
-                Ok(())
-            }
-            #[cfg(feature = "rocksdb")]
-            StoreConfig::RocksDb(config, namespace) => {
-                RocksDbStore::maybe_create_and_connect(&config, &namespace, ROOT_KEY).await?;
-                Ok(())
-            }
-            #[cfg(feature = "dynamodb")]
-            StoreConfig::DynamoDb(config, namespace) => {
-                DynamoDbStore::maybe_create_and_connect(&config, &namespace, ROOT_KEY).await?;
-                Ok(())
-            }
-            #[cfg(feature = "scylladb")]
-            StoreConfig::ScyllaDb(config, namespace) => {
-                ScyllaDbStore::maybe_create_and_connect(&config, &namespace, ROOT_KEY).await?;
-                Ok(())
-            }
-        }
+            });
+        };
+        // Creating the namespace through the storage records its format version.
+        let wasm_runtime = None;
+        DbStorage::<AnyStore, _>::initialize(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+        Ok(())
     }
 
     /// Lists all the namespaces of the storage
     pub async fn list_all(self) -> Result<Vec<String>, ViewError> {
-        match self {
-            StoreConfig::Memory(_, _) => Err(ViewError::StoreError {
+        let Some((config, _namespace)) = self.into_persistent_store_config() else {
+            return Err(ViewError::StoreError {
                 backend: "memory".to_string(),
                 error: "list_all is not supported for the memory storage".to_string(),
-            }),
-            #[cfg(feature = "storage-service")]
-            StoreConfig::Service(config, _namespace) => {
-                let tables = ServiceStoreClient::list_all(&config).await?;
-                Ok(tables)
-            }
-            #[cfg(feature = "rocksdb")]
-            StoreConfig::RocksDb(config, _namespace) => {
-                let tables = RocksDbStore::list_all(&config).await?;
-                Ok(tables)
-            }
-            #[cfg(feature = "dynamodb")]
-            StoreConfig::DynamoDb(config, _namespace) => {
-                let tables = DynamoDbStore::list_all(&config).await?;
-                Ok(tables)
-            }
-            #[cfg(feature = "scylladb")]
-            StoreConfig::ScyllaDb(config, _namespace) => {
-                let tables = ScyllaDbStore::list_all(&config).await?;
-                Ok(tables)
-            }
+            });
+        };
+        Ok(AnyStore::list_all(&config).await?)
+    }
+
+    /// Applies the pending migrations of the storage format. With `dry_run`, the
+    /// migrations are only reported.
+    ///
+    /// No validator or client may use the database during the migration.
+    pub async fn migrate(self, dry_run: bool) -> Result<MigrationReport, Error> {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(Error::InvalidOperation(
+                "migrate does not make sense for memory storage".into(),
+            ));
+        };
+        let wasm_runtime = None;
+        let storage =
+            DbStorage::<AnyStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+        let owner = format!(
+            "process {} started at {}",
+            std::process::id(),
+            Timestamp::now()
+        );
+        Ok(storage.migrate(&owner, dry_run).await?)
+    }
+
+    /// Checks that the state of every chain matches the state hash certified in its latest
+    /// block. The `progress` function is called with the number of chains verified so far.
+    pub async fn verify(self, progress: impl FnMut(usize)) -> Result<VerificationReport, Error> {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(Error::InvalidOperation(
+                "verify does not make sense for memory storage".into(),
+            ));
+        };
+        let wasm_runtime = None;
+        let storage =
+            DbStorage::<AnyStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+        storage.check_format_version().await?;
+        Ok(storage.verify_chain_tips(progress).await?)
+    }
+
+    /// Resets the execution statistics of the given chain, or of every chain with blocks.
+    /// Returns the number of chains whose statistics were reset.
+    pub async fn reset_statistics(self, chain_id: Option<ChainId>) -> Result<usize, Error> {
+        let Some((config, namespace)) = self.into_persistent_store_config() else {
+            return Err(Error::InvalidOperation(
+                "reset_statistics does not make sense for memory storage".into(),
+            ));
+        };
+        let wasm_runtime = None;
+        let storage =
+            DbStorage::<AnyStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+        storage.check_format_version().await?;
+        Ok(storage.reset_chain_statistics(chain_id).await?)
+    }
+
+    /// Computes the number and size of the keys of a RocksDB namespace, grouped by their
+    /// first `prefix_len` bytes. With a `sample_size`, at most that many keys are scanned
+    /// for each prefix. The `progress` function is called with the number of keys scanned so
+    /// far.
+    #[cfg(feature = "rocksdb")]
+    pub async fn rocks_db_statistics(
+        self,
+        prefix_len: usize,
+        sample_size: Option<u64>,
+        progress: impl FnMut(u64) + Send + 'static,
+    ) -> Result<RocksDbStatistics, Error> {
+        let store = self.connect_rocks_db_namespace(&[]).await?;
+        Ok(store.statistics(prefix_len, sample_size, progress).await?)
+    }
+
+    /// Compacts a RocksDB namespace, discarding the deleted entries. With a `chain_id`, only
+    /// the state of that chain is compacted.
+    #[cfg(feature = "rocksdb")]
+    pub async fn rocks_db_compact(self, chain_id: Option<ChainId>) -> Result<(), Error> {
+        let Some(chain_id) = chain_id else {
+            let store = self.connect_rocks_db_namespace(&[]).await?;
+            return Ok(store.compact(None).await?);
+        };
+        let root_key = linera_storage::chain_state_root_key(chain_id)
+            .map_err(|error| Error::Backend(error.into()))?;
+        let store = self.connect_rocks_db_namespace(&root_key).await?;
+        Ok(store.compact(Some(&[])).await?)
+    }
+
+    /// Connects to an existing RocksDB namespace, without the caching and value splitting
+    /// layers, so that its keys are seen as they are stored.
+    #[cfg(feature = "rocksdb")]
+    async fn connect_rocks_db_namespace(
+        self,
+        root_key: &[u8],
+    ) -> Result<RocksDbStoreInternal, Error> {
+        // The keys are not encrypted, and the sizes of the values include the encryption.
+        let StoreConfig::RocksDb(config, namespace) = self.without_encryption() else {
+            return Err(Error::InvalidOperation(
+                "this command is only supported for RocksDB storage".into(),
+            ));
+        };
+        let config = config.inner_config;
+        if !RocksDbStoreInternal::exists(&config, &namespace).await? {
+            return Err(Error::InvalidOperation(format!(
+                "the namespace {namespace} does not exist"
+            )));
         }
+        Ok(RocksDbStoreInternal::connect(&config, &namespace, root_key).await?)
     }
 }
 
@@ -565,11 +686,8 @@ pub trait Runnable {
         S: Storage + Clone + Send + Sync + 'static;
 }
 
-// The design is that the initialization of the accounts should be separate
-// from the running of the database.
-// However, that does not apply to the memory storage which must be initialized
-// in the same context in which it is used.
-#[allow(unused_variables)]
+/// Connects to the storage described by `config`, whichever backend it uses, and runs the
+/// `job` with it.
 pub async fn run_with_storage<Job>(
     config: StoreConfig,
     genesis_config: &GenesisConfig,
@@ -579,104 +697,87 @@ pub async fn run_with_storage<Job>(
 where
     Job: Runnable,
 {
-    match config {
-        StoreConfig::Memory(config, namespace) => {
-            let store_config = MemoryStoreConfig::new(config.common_config.max_stream_queries);
-            let mut storage =
-                DbStorage::<MemoryStore, _>::new(store_config, &namespace, ROOT_KEY, wasm_runtime)
-                    .await?;
-            genesis_config.initialize_storage(&mut storage).await?;
-            Ok(job.run(storage).await)
-        }
-        #[cfg(feature = "storage-service")]
-        StoreConfig::Service(config, namespace) => {
-            let storage =
-                DbStorage::<ServiceStoreClient, _>::new(config, &namespace, ROOT_KEY, wasm_runtime)
-                    .await?;
-            Ok(job.run(storage).await)
-        }
-        #[cfg(feature = "rocksdb")]
-        StoreConfig::RocksDb(config, namespace) => {
-            let storage =
-                DbStorage::<RocksDbStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime)
-                    .await?;
-            Ok(job.run(storage).await)
-        }
-        #[cfg(feature = "dynamodb")]
-        StoreConfig::DynamoDb(config, namespace) => {
-            let storage =
-                DbStorage::<DynamoDbStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime)
-                    .await?;
-            Ok(job.run(storage).await)
-        }
-        #[cfg(feature = "scylladb")]
-        StoreConfig::ScyllaDb(config, namespace) => {
-            let storage =
-                DbStorage::<ScyllaDbStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime)
-                    .await?;
-            Ok(job.run(storage).await)
-        }
-    }
+    let storage = connect_storage(config, genesis_config, wasm_runtime).await?;
+    Ok(job.run(storage).await)
+}
+
+// The design is that the initialization of the accounts should be separate
+// from the running of the database.
+// However, that does not apply to the memory storage which must be initialized
+// in the same context in which it is used.
+/// Connects to the storage described by `config`, whichever backend it uses.
+pub async fn connect_storage(
+    config: StoreConfig,
+    genesis_config: &GenesisConfig,
+    wasm_runtime: Option<WasmRuntime>,
+) -> Result<DbStorage<AnyStore, WallClock>, Error> {
+    let is_memory = config.is_memory();
+    let (config, namespace) = config.into_any_store_config();
+    let storage = if is_memory {
+        let mut storage =
+            DbStorage::<AnyStore, _>::initialize(config, &namespace, ROOT_KEY, wasm_runtime)
+                .await?;
+        genesis_config.initialize_storage(&mut storage).await?;
+        storage
+    } else {
+        let storage =
+            DbStorage::<AnyStore, _>::new(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+        storage.check_format_version().await?;
+        storage
+    };
+    Ok(storage)
+}
+
+/// Rewrites under the current key the values of an encrypted storage that were encrypted
+/// with a retired key, starting from `progress`. The `checkpoint` function is called with
+/// the updated progress after each chunk of values, so that it can be persisted.
+///
+/// See [`EncryptedStore::reencrypt`](linera_views::encryption::EncryptedStore::reencrypt)
+/// for the processes that may use the storage in the meantime.
+pub async fn reencrypt_storage(
+    storage: &DbStorage<AnyStore, WallClock>,
+    progress: &mut ReEncryptionProgress,
+    checkpoint: impl FnMut(&ReEncryptionProgress) + Send,
+) -> Result<(), Error> {
+    let AnyStore::Encrypted(store) = storage.store() else {
+        return Err(Error::InvalidOperation(
+            "the values of the storage are not encrypted".into(),
+        ));
+    };
+    store
+        .reencrypt(progress, checkpoint)
+        .await
+        .map_err(AnyStoreError::from)?;
+    Ok(())
+}
+
+/// Creates a new storage in memory, initialized with the given genesis configuration.
+pub async fn create_memory_storage(
+    genesis_config: &GenesisConfig,
+    max_stream_queries: usize,
+    wasm_runtime: Option<WasmRuntime>,
+) -> Result<DbStorage<MemoryStore, WallClock>, Error> {
+    let store_config = MemoryStoreConfig::new(max_stream_queries);
+    let mut storage =
+        DbStorage::<MemoryStore, _>::new(store_config, DEFAULT_NAMESPACE, ROOT_KEY, wasm_runtime)
+            .await?;
+    genesis_config.initialize_storage(&mut storage).await?;
+    Ok(storage)
 }
 
-#[allow(unused_variables)]
 pub async fn full_initialize_storage(
     config: StoreConfig,
     genesis_config: &GenesisConfig,
 ) -> Result<(), Error> {
-    match config {
-        StoreConfig::Memory(_, _) => Err(Error::InvalidOperation(
+    let Some((config, namespace)) = config.into_persistent_store_config() else {
+        return Err(Error::InvalidOperation(
             "The initialization should not be called for memory".into(),
-        )),
-        #[cfg(feature = "storage-service")]
-        StoreConfig::Service(config, namespace) => {
-            let wasm_runtime = None;
-            let mut storage = DbStorage::<ServiceStoreClient, _>::initialize(
-                config,
-                &namespace,
-                ROOT_KEY,
-                wasm_runtime,
-            )
-            .await?;
-            Ok(genesis_config.initialize_storage(&mut storage).await?)
-        }
-        #[cfg(feature = "rocksdb")]
-        StoreConfig::RocksDb(config, namespace) => {
-            let wasm_runtime = None;
-            let mut storage = DbStorage::<RocksDbStore, _>::initialize(
-                config,
-                &namespace,
-                ROOT_KEY,
-                wasm_runtime,
-            )
-            .await?;
-            Ok(genesis_config.initialize_storage(&mut storage).await?)
-        }
-        #[cfg(feature = "dynamodb")]
-        StoreConfig::DynamoDb(config, namespace) => {
-            let wasm_runtime = None;
-            let mut storage = DbStorage::<DynamoDbStore, _>::initialize(
-                config,
-                &namespace,
-                ROOT_KEY,
-                wasm_runtime,
-            )
-            .await?;
-            Ok(genesis_config.initialize_storage(&mut storage).await?)
-        }
-        #[cfg(feature = "scylladb")]
-        StoreConfig::ScyllaDb(config, namespace) => {
-            let wasm_runtime = None;
-            let mut storage = DbStorage::<ScyllaDbStore, _>::initialize(
-                config,
-                &namespace,
-                ROOT_KEY,
-                wasm_runtime,
-            )
-            .await?;
-            Ok(genesis_config.initialize_storage(&mut storage).await?)
-        }
-    }
+        ));
+    };
+    let wasm_runtime = None;
+    let mut storage =
+        DbStorage::<AnyStore, _>::initialize(config, &namespace, ROOT_KEY, wasm_runtime).await?;
+    Ok(genesis_config.initialize_storage(&mut storage).await?)
 }
 
 #[test]
@@ -753,7 +854,8 @@ fn test_aws_storage_config_from_str() {
         StorageConfigNamespace::from_str("dynamodb:table").unwrap(),
         StorageConfigNamespace {
             storage_config: StorageConfig::DynamoDb {
-                use_localstack: false
+                use_localstack: false,
+                read_consistency: DynamoDbReadConsistency::Strong,
             },
             namespace: "table".to_string()
         }
@@ -762,7 +864,8 @@ fn test_aws_storage_config_from_str() {
         StorageConfigNamespace::from_str("dynamodb:table:env").unwrap(),
         StorageConfigNamespace {
             storage_config: StorageConfig::DynamoDb {
-                use_localstack: false
+                use_localstack: false,
+                read_consistency: DynamoDbReadConsistency::Strong,
             },
             namespace: "table".to_string()
         }
@@ -771,11 +874,23 @@ fn test_aws_storage_config_from_str() {
         StorageConfigNamespace::from_str("dynamodb:table:localstack").unwrap(),
         StorageConfigNamespace {
             storage_config: StorageConfig::DynamoDb {
-                use_localstack: true
+                use_localstack: true,
+                read_consistency: DynamoDbReadConsistency::Strong,
+            },
+            namespace: "table".to_string()
+        }
+    );
+    assert_eq!(
+        StorageConfigNamespace::from_str("dynamodb:table:env:eventual").unwrap(),
+        StorageConfigNamespace {
+            storage_config: StorageConfig::DynamoDb {
+                use_localstack: false,
+                read_consistency: DynamoDbReadConsistency::Eventual,
             },
             namespace: "table".to_string()
         }
     );
+    assert!(StorageConfigNamespace::from_str("dynamodb:table:env:weak").is_err());
     assert!(StorageConfigNamespace::from_str("dynamodb").is_err());
     assert!(StorageConfigNamespace::from_str("dynamodb:").is_err());
     assert!(StorageConfigNamespace::from_str("dynamodb:1").is_err());
@@ -820,3 +935,137 @@ fn test_scylla_db_storage_config_from_str() {
     assert!(StorageConfigNamespace::from_str("scylladb:tcp:address1:tcp:/address2").is_err());
     assert!(StorageConfigNamespace::from_str("scylladb:wrong").is_err());
 }
+
+#[cfg(feature = "rocksdb")]
+#[tokio::test]
+async fn test_rocks_db_statistics_and_compaction() -> anyhow::Result<()> {
+    use linera_base::data_types::Blob;
+    use linera_views::rocks_db::RocksDbStore;
+
+    let dir = tempfile::TempDir::new()?;
+    let store_config = |namespace: &str| {
+        let path_with_guard = PathWithGuard::new(dir.path().to_path_buf());
+        let config = RocksDbStoreConfig::new(
+            RocksDbSpawnMode::SpawnBlocking,
+            path_with_guard,
+            CommonStoreConfig::default(),
+        );
+        StoreConfig::RocksDb(config, namespace.to_string())
+    };
+    let StoreConfig::RocksDb(config, _) = store_config("linera") else {
+        unreachable!();
+    };
+    let storage =
+        DbStorage::<RocksDbStore, _>::initialize(config, "linera", ROOT_KEY, None).await?;
+    storage
+        .write_blob(&Blob::new_data(b"blob".to_vec()))
+        .await?;
+    // Close the database before reopening it.
+    drop(storage);
+
+    // The keys are grouped by the root key and the variant of the stored entry: the blob,
+    // and the version of the storage format.
+    let statistics = store_config("linera")
+        .rocks_db_statistics(2, None, |_| ())
+        .await?;
+    assert_eq!(
+        statistics.prefixes.keys().collect::<Vec<_>>(),
+        [&vec![0, 3], &vec![0, 6]]
+    );
+    assert!(statistics.prefixes.values().all(|stats| stats.keys == 1));
+
+    store_config("linera").rocks_db_compact(None).await?;
+    store_config("linera")
+        .rocks_db_compact(Some(ChainId::root(0)))
+        .await?;
+    assert!(matches!(
+        store_config("missing").rocks_db_compact(None).await,
+        Err(Error::InvalidOperation(_))
+    ));
+    Ok(())
+}
+
+/// A job returning the description of a chain, and the type of the storage it runs with.
+#[cfg(test)]
+struct ReadChainDescription(linera_base::identifiers::ChainId);
+
+#[cfg(test)]
+#[async_trait]
+impl Runnable for ReadChainDescription {
+    type Output = Result<
+        (
+            Option<linera_base::identifiers::ChainDescription>,
+            &'static str,
+        ),
+        ViewError,
+    >;
+
+    async fn run<S>(self, storage: S) -> Self::Output
+    where
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        let chain = storage.load_chain(self.0).await?;
+        let description = *chain.execution_state.system.description.get();
+        Ok((description, std::any::type_name::<S>()))
+    }
+}
+
+/// Runs [`ReadChainDescription`] on the admin chain of a new network, with the storage
+/// described by `input`, and checks that the storage was set up through [`AnyStore`].
+#[cfg(test)]
+async fn check_run_with_storage_from_str(input: &str) -> anyhow::Result<()> {
+    use linera_base::{
+        crypto::PublicKey,
+        data_types::{Amount, Timestamp},
+        identifiers::{ChainDescription, ChainId},
+    };
+    use linera_execution::ResourceControlPolicy;
+
+    use crate::config::CommitteeConfig;
+
+    let mut genesis_config = GenesisConfig::new(
+        CommitteeConfig { validators: vec![] },
+        ChainId::root(0),
+        Timestamp::from(0),
+        ResourceControlPolicy::default(),
+        "test network".to_string(),
+    );
+    genesis_config
+        .chains
+        .push((PublicKey::test_key(0), Amount::ONE));
+
+    let storage_config = StorageConfigNamespace::from_str(input)?;
+    let common_config = CommonStoreConfig::default();
+    if !matches!(storage_config.storage_config, StorageConfig::Memory) {
+        let config = storage_config
+            .add_common_config(common_config.clone())
+            .await?;
+        full_initialize_storage(config, &genesis_config).await?;
+    }
+    let config = storage_config.add_common_config(common_config).await?;
+    let job = ReadChainDescription(ChainId::root(0));
+    let (description, storage_type) =
+        run_with_storage(config, &genesis_config, None, job).await??;
+    assert_eq!(description, Some(ChainDescription::Root(0)));
+    assert_eq!(
+        storage_type,
+        std::any::type_name::<DbStorage<AnyStore, WallClock>>()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_run_with_memory_storage_from_str() -> anyhow::Result<()> {
+    check_run_with_storage_from_str("memory:table_run_with_storage").await
+}
+
+#[cfg(feature = "rocksdb")]
+#[tokio::test]
+async fn test_run_with_rocks_db_storage_from_str() -> anyhow::Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let input = format!(
+        "rocksdb:{}:spawn_blocking:table_run_with_storage",
+        dir.path().display()
+    );
+    check_run_with_storage_from_str(&input).await
+}
@@ -14,10 +14,11 @@ use linera_base::{
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_core::{
+    chain_worker::StateSnapshotOptions,
     client::{ChainClient, Client},
     node::CrossChainMessageDelivery,
     test_utils::{MemoryStorageBuilder, NodeProvider, StorageBuilder as _, TestBuilder},
-    DEFAULT_GRACE_PERIOD,
+    ValidatorRequestOptions, DEFAULT_GRACE_PERIOD,
 };
 use linera_execution::system::Recipient;
 use linera_storage::{DbStorage, TestClock};
@@ -135,6 +136,8 @@ async fn test_chain_listener() -> anyhow::Result<()> {
             format!("Client node for {:.8}", chain_id0),
             NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
             DEFAULT_GRACE_PERIOD,
+            ValidatorRequestOptions::default(),
+            StateSnapshotOptions::default(),
         )),
     };
     let key_pair = KeyPair::generate_from(&mut rng);
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod chain_listener;
+#[cfg(all(with_wasm_runtime, not(web)))]
+mod service_override;
 mod util;
 #[cfg(feature = "fs")]
 mod wallet;
@@ -0,0 +1,131 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::large_futures)]
+
+use async_graphql::{Request, Value};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Amount, BlockHeight, Bytecode},
+    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId},
+};
+use linera_core::test_utils::{MemoryStorageBuilder, TestBuilder};
+use linera_execution::{Operation, WasmRuntime};
+use serde_json::json;
+
+use crate::service_override::{Error, ServiceOverride, ServiceOverrideWatcher};
+
+/// Checks that replacing the file of an overridden service changes how queries are answered,
+/// without publishing a new bytecode, while operations still run the published contract.
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_service_override_is_reloaded_when_the_file_changes() -> anyhow::Result<()> {
+    let storage_builder = MemoryStorageBuilder::with_wasm_runtime(WasmRuntime::default());
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let chain = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+
+    let (contract_path, service_path) =
+        linera_execution::wasm_test::get_example_bytecode_paths("counter")?;
+    let (_, other_service_path) =
+        linera_execution::wasm_test::get_example_bytecode_paths("fungible")?;
+    let counter_service = tokio::fs::read(&service_path).await?;
+    let other_service = tokio::fs::read(&other_service_path).await?;
+
+    let (bytecode_id, _) = chain
+        .publish_bytecode(
+            Bytecode::load_from_file(contract_path).await?,
+            Bytecode::new(counter_service.clone()),
+        )
+        .await?
+        .unwrap();
+    let bytecode_id = bytecode_id.with_abi::<counter::CounterAbi, (), u64>();
+    let (application_id, _) = chain
+        .create_application(bytecode_id, &(), &10_u64, vec![])
+        .await?
+        .unwrap();
+
+    let query = Request::new("{ value }");
+    let counter_value = |value: u64| Value::from_json(json!({ "value": value })).unwrap();
+    let response = chain.query_user_application(application_id, &query).await?;
+    assert_eq!(response.response.data, counter_value(10));
+
+    // Answer the queries with a service that doesn't know about the counter.
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("service.wasm");
+    tokio::fs::write(&path, &other_service).await?;
+    let mut watcher = ServiceOverrideWatcher::new(
+        chain.storage_client(),
+        vec![ServiceOverride {
+            application_id: application_id.forget_abi(),
+            path: path.clone(),
+        }],
+    );
+    assert_eq!(watcher.reload().await?, 1);
+    assert_eq!(watcher.reload().await?, 0);
+    let result = chain.query_user_application(application_id, &query).await;
+    assert!(!matches!(
+        result,
+        Ok(outcome) if outcome.response.data == counter_value(10)
+    ));
+
+    // Swap the file back to the counter service: the next reload picks it up.
+    tokio::fs::write(&path, &counter_service).await?;
+    assert_eq!(watcher.reload().await?, 1);
+    let response = chain.query_user_application(application_id, &query).await?;
+    assert_eq!(response.response.data, counter_value(10));
+
+    // Operations still execute the published contract.
+    chain
+        .execute_operation(Operation::user(application_id, &5_u64)?)
+        .await?
+        .unwrap();
+    let response = chain.query_user_application(application_id, &query).await?;
+    assert_eq!(response.response.data, counter_value(15));
+
+    // An invalid file is reported, and the last loaded service keeps answering.
+    tokio::fs::write(&path, b"not a Wasm module").await?;
+    assert!(matches!(
+        watcher.reload().await,
+        Err(Error::InvalidBytecode { .. })
+    ));
+    let response = chain.query_user_application(application_id, &query).await?;
+    assert_eq!(response.response.data, counter_value(15));
+
+    tokio::fs::remove_file(&path).await?;
+    assert!(matches!(watcher.reload().await, Err(Error::Io { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_parse_service_override() -> anyhow::Result<()> {
+    let application_id = ApplicationId {
+        bytecode_id: BytecodeId::new(
+            CryptoHash::test_hash("contract"),
+            CryptoHash::test_hash("service"),
+        ),
+        creation: MessageId {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(1),
+            index: 0,
+        },
+    };
+    assert_eq!(
+        format!("{application_id}=target/service.wasm").parse::<ServiceOverride>()?,
+        ServiceOverride {
+            application_id,
+            path: "target/service.wasm".into(),
+        }
+    );
+    assert!(matches!(
+        "invalid=service.wasm".parse::<ServiceOverride>(),
+        Err(Error::ApplicationId(_))
+    ));
+    assert!(matches!(
+        "service.wasm".parse::<ServiceOverride>(),
+        Err(Error::InvalidFormat(_))
+    ));
+    assert!(matches!(
+        "app=".parse::<ServiceOverride>(),
+        Err(Error::InvalidFormat(_))
+    ));
+    Ok(())
+}
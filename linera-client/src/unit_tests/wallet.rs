@@ -5,15 +5,33 @@ use std::collections::BTreeMap;
 
 use anyhow::anyhow;
 use linera_base::{
-    crypto::KeyPair,
-    data_types::{Amount, Blob},
-    identifiers::ChainId,
+    crypto::{CryptoHash, KeyPair},
+    data_types::{Amount, Blob, BlockHeight, Round, Timestamp},
+    identifiers::{ChainDescription, ChainId, Owner},
+};
+use linera_chain::{
+    data_types::{LiteValue, LiteVote},
+    types::CertificateKind,
+};
+use linera_core::{
+    misbehavior::{Misbehavior, MisbehaviorReport},
+    test_utils::{MemoryStorageBuilder, StorageBuilder, TestBuilder},
+};
+use linera_execution::committee::ValidatorName;
+use linera_views::{
+    memory::MemoryStore,
+    store::{ReadableKeyValueStore as _, TestKeyValueStore as _},
 };
-use linera_core::test_utils::{MemoryStorageBuilder, StorageBuilder, TestBuilder};
 use rand::SeedableRng as _;
 
 use super::util::make_genesis_config;
-use crate::{client_context::ClientContext, config::WalletState, wallet::Wallet};
+use crate::{
+    client_context::ClientContext,
+    config::WalletState,
+    key_store,
+    persistent::{self, Persist as _, PersistExt as _},
+    wallet::{UserChain, Wallet},
+};
 
 /// Tests whether we can correctly save a wallet that contains pending blobs.
 #[test_log::test(tokio::test)]
@@ -58,3 +76,257 @@ async fn test_save_wallet_with_pending_blobs() -> anyhow::Result<()> {
     context.save_wallet().await?;
     Ok(())
 }
+
+/// Returns a wallet with a chain and an unassigned key, and the owners of these keys.
+async fn make_wallet_with_keys() -> anyhow::Result<(Wallet, Vec<Owner>)> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let storage_builder = MemoryStorageBuilder::default();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    builder.add_root_chain(0, Amount::ONE).await?;
+    let mut wallet = Wallet::new(make_genesis_config(&builder), Some(37));
+    let chain = UserChain::make_initial(&mut rng, ChainDescription::Root(0), Timestamp::from(0));
+    let unassigned_key_pair = KeyPair::generate_from(&mut rng);
+    let owners = vec![
+        Owner::from(chain.key_pair.as_ref().unwrap().public()),
+        Owner::from(unassigned_key_pair.public()),
+    ];
+    wallet.insert(chain);
+    wallet.add_unassigned_key_pair(unassigned_key_pair);
+    Ok((wallet, owners))
+}
+
+/// Returns the secret keys of the given `owners` as they appear in a plaintext wallet file.
+fn plaintext_keys(wallet: &Wallet, owners: &[Owner]) -> anyhow::Result<Vec<String>> {
+    owners
+        .iter()
+        .map(|owner| {
+            let key_pair = wallet.key_pair_for_owner(owner).unwrap();
+            Ok(serde_json::to_string(&key_pair)?)
+        })
+        .collect()
+}
+
+/// Tests that an encrypted wallet can only be unlocked with the right passphrase.
+#[test_log::test(tokio::test)]
+async fn test_encrypted_wallet_wrong_passphrase() -> anyhow::Result<()> {
+    let (mut wallet, owners) = make_wallet_with_keys().await?;
+    let secret_keys = plaintext_keys(&wallet, &owners)?;
+    wallet.encrypt("correct horse")?;
+    assert!(wallet.is_encrypted());
+    assert!(!wallet.is_locked());
+
+    let json = serde_json::to_string(&wallet)?;
+    for secret_key in &secret_keys {
+        assert!(!json.contains(secret_key.as_str()));
+    }
+
+    let mut wallet: Wallet = serde_json::from_str(&json)?;
+    assert!(wallet.is_locked());
+    for owner in &owners {
+        assert!(wallet.key_pair_for_owner(owner).is_none());
+    }
+    assert!(matches!(
+        wallet.unlock("battery staple"),
+        Err(key_store::Error::WrongPassphrase)
+    ));
+    assert!(wallet.is_locked());
+    assert!(wallet.encrypt("battery staple").is_err());
+
+    wallet.unlock("correct horse")?;
+    assert!(!wallet.is_locked());
+    assert_eq!(plaintext_keys(&wallet, &owners)?, secret_keys);
+    assert!(wallet.get(ChainId::root(0)).unwrap().key_pair.is_some());
+    Ok(())
+}
+
+/// Tests migrating a wallet file with plaintext keys to encrypted keys.
+#[test_log::test(tokio::test)]
+async fn test_migrate_plaintext_wallet() -> anyhow::Result<()> {
+    let (wallet, owners) = make_wallet_with_keys().await?;
+    let secret_keys = plaintext_keys(&wallet, &owners)?;
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+
+    let mut wallet_state = WalletState::create_from_file(&wallet_path, wallet)?;
+    wallet_state.persist().await?;
+    drop(wallet_state);
+    let contents = fs_err::read_to_string(&wallet_path)?;
+    for secret_key in &secret_keys {
+        assert!(contents.contains(secret_key.as_str()));
+    }
+
+    let mut wallet_state = WalletState::new(persistent::File::<Wallet>::read(&wallet_path)?);
+    assert!(!wallet_state.is_encrypted());
+    wallet_state
+        .mutate(|wallet| wallet.encrypt("passphrase"))
+        .await??;
+    drop(wallet_state);
+    let contents = fs_err::read_to_string(&wallet_path)?;
+    for secret_key in &secret_keys {
+        assert!(!contents.contains(secret_key.as_str()));
+    }
+
+    let mut wallet = persistent::File::<Wallet>::read(&wallet_path)?.into_value();
+    assert!(wallet.is_locked());
+    wallet.unlock("passphrase")?;
+    assert_eq!(plaintext_keys(&wallet, &owners)?, secret_keys);
+    Ok(())
+}
+
+/// Tests that a staging file left over by a crash while saving the wallet neither affects
+/// the saved wallet nor the next save.
+#[test_log::test(tokio::test)]
+async fn test_recover_from_interrupted_save() -> anyhow::Result<()> {
+    let (wallet, _) = make_wallet_with_keys().await?;
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+    let staging_path = tmp_dir.path().join("wallet.json.new");
+
+    let mut wallet_state = WalletState::create_from_file(&wallet_path, wallet)?;
+    wallet_state.persist().await?;
+    drop(wallet_state);
+    let contents = fs_err::read(&wallet_path)?;
+
+    // A crash in the middle of a save leaves a truncated staging file behind. Make it longer
+    // than the next contents, so that any leftover bytes would corrupt them.
+    let mut truncated = contents[..contents.len() / 2].to_vec();
+    truncated.extend(std::iter::repeat(b' ').take(contents.len()));
+    truncated.extend_from_slice(b"garbage");
+    fs_err::write(&staging_path, truncated)?;
+
+    let mut wallet_state = WalletState::read_from_file(&wallet_path)?;
+    assert_eq!(fs_err::read(&wallet_path)?, contents);
+    wallet_state
+        .mutate(|wallet| wallet.set_default_chain(ChainId::root(0)))
+        .await??;
+    drop(wallet_state);
+    assert!(!staging_path.exists());
+
+    let wallet = persistent::File::<Wallet>::read(&wallet_path)?.into_value();
+    assert_eq!(wallet.default_chain(), Some(ChainId::root(0)));
+    assert!(wallet.get(ChainId::root(0)).is_some());
+    Ok(())
+}
+
+/// Tests that misbehavior reports are saved with the wallet, including their evidence.
+#[test_log::test(tokio::test)]
+async fn test_save_wallet_with_misbehavior_reports() -> anyhow::Result<()> {
+    let (wallet, _) = make_wallet_with_keys().await?;
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let key_pair = KeyPair::generate_from(&mut rng);
+    let vote = |value_hash| {
+        let value = LiteValue {
+            value_hash,
+            chain_id: ChainId::root(0),
+            kind: CertificateKind::Validated,
+        };
+        LiteVote::new(value, Round::MultiLeader(0), &key_pair)
+    };
+    let report = MisbehaviorReport {
+        validator: ValidatorName(key_pair.public()),
+        timestamp: Timestamp::from(1),
+        misbehavior: Misbehavior::ConflictingVotes {
+            height: BlockHeight::ZERO,
+            first: vote(CryptoHash::test_hash("first")),
+            second: vote(CryptoHash::test_hash("second")),
+        },
+    };
+
+    let mut wallet_state = WalletState::create_from_file(&wallet_path, wallet)?;
+    assert!(wallet_state.misbehavior_reports().is_empty());
+    wallet_state
+        .mutate(|wallet| wallet.add_misbehavior_reports([report.clone()]))
+        .await?;
+    drop(wallet_state);
+
+    let wallet = persistent::File::<Wallet>::read(&wallet_path)?.into_value();
+    let reports = wallet.misbehavior_reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        serde_json::to_string(&reports[0])?,
+        serde_json::to_string(&report)?
+    );
+    let Misbehavior::ConflictingVotes { first, second, .. } = &reports[0].misbehavior else {
+        panic!("Unexpected misbehavior: {:?}", reports[0].misbehavior);
+    };
+    assert!(first.check().is_ok());
+    assert!(second.check().is_ok());
+    Ok(())
+}
+
+/// Tests that two clients cannot use the same wallet file at the same time.
+#[test_log::test(tokio::test)]
+async fn test_concurrent_clients_contend_for_the_wallet_lock() -> anyhow::Result<()> {
+    let (wallet, _) = make_wallet_with_keys().await?;
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+
+    let mut wallet_state = WalletState::create_from_file(&wallet_path, wallet)?;
+    wallet_state.persist().await?;
+
+    assert!(WalletState::read_from_file(&wallet_path).is_err());
+
+    wallet_state
+        .mutate(|wallet| wallet.forget_chain(&ChainId::root(0)))
+        .await??;
+    drop(wallet_state);
+
+    let wallet_state = WalletState::read_from_file(&wallet_path)?;
+    assert_eq!(wallet_state.num_chains(), 0);
+    Ok(())
+}
+
+/// Tests saving a wallet in a key-value store, one entry per chain.
+#[test_log::test(tokio::test)]
+async fn test_wallet_in_key_value_store() -> anyhow::Result<()> {
+    let (wallet, owners) = make_wallet_with_keys().await?;
+    let secret_keys = plaintext_keys(&wallet, &owners)?;
+    let store = MemoryStore::new_test_store().await?;
+    let other_chain_id = ChainId::root(1);
+
+    let mut wallet_state = WalletState::create_from_store(store.clone(), wallet).await?;
+    wallet_state
+        .mutate(|wallet| {
+            wallet.insert(UserChain::make_other(other_chain_id, Timestamp::from(0)));
+            wallet
+                .chains
+                .get_mut(&ChainId::root(0))
+                .unwrap()
+                .next_block_height = BlockHeight(3);
+            wallet.encrypt("passphrase")
+        })
+        .await??;
+    drop(wallet_state);
+    let keys = store.find_keys_by_prefix(&[0]).await?;
+    assert_eq!(keys.len(), 3);
+
+    let mut wallet_state = WalletState::read_from_store(store.clone()).await?.unwrap();
+    assert!(wallet_state.is_locked());
+    assert_eq!(
+        wallet_state
+            .get(ChainId::root(0))
+            .unwrap()
+            .next_block_height,
+        BlockHeight(3)
+    );
+    wallet_state
+        .mutate(|wallet| -> anyhow::Result<_> {
+            wallet.unlock("passphrase")?;
+            Ok(wallet.forget_chain(&other_chain_id)?)
+        })
+        .await??;
+    drop(wallet_state);
+    let keys = store.find_keys_by_prefix(&[0]).await?;
+    assert_eq!(keys.len(), 2);
+
+    let mut wallet = persistent::KeyValue::<_, Wallet>::read(store)
+        .await?
+        .unwrap()
+        .into_value();
+    assert!(wallet.get(other_chain_id).is_none());
+    wallet.unlock("passphrase")?;
+    assert_eq!(plaintext_keys(&wallet, &owners)?, secret_keys);
+    Ok(())
+}
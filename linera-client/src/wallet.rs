@@ -13,20 +13,135 @@ use linera_base::{
     identifiers::{BlobId, ChainDescription, ChainId, Owner},
 };
 use linera_chain::data_types::ProposedBlock;
-use linera_core::{client::ChainClient, node::ValidatorNodeProvider};
+use linera_core::{
+    client::ChainClient, misbehavior::MisbehaviorReport, node::ValidatorNodeProvider,
+};
 use linera_storage::Storage;
 use rand::Rng as _;
-use serde::{Deserialize, Serialize};
+use serde::{ser::Error as _, Deserialize, Serialize, Serializer};
 
-use crate::{config::GenesisConfig, error, Error};
+use crate::{
+    config::GenesisConfig,
+    error,
+    key_store::{self, EncryptedKeys, KeyStore, StoredKeys},
+    persistent::Entries,
+    Error,
+};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
 pub struct Wallet {
     pub chains: BTreeMap<ChainId, UserChain>,
     pub unassigned_key_pairs: HashMap<Owner, KeyPair>,
     pub default: Option<ChainId>,
     pub genesis_config: GenesisConfig,
     pub testing_prng_seed: Option<u64>,
+    /// The secret keys, if they are encrypted with a passphrase. In that case, they are
+    /// not stored in plaintext in `chains` and `unassigned_key_pairs`.
+    #[serde(default)]
+    encrypted_keys: Option<EncryptedKeys>,
+    /// Evidence of validator misbehavior observed by the client.
+    #[serde(default)]
+    misbehavior_reports: Vec<MisbehaviorReport>,
+    /// The key used to encrypt the secret keys whenever the wallet is saved, once the
+    /// wallet is unlocked.
+    #[serde(skip)]
+    key_store: Option<KeyStore>,
+}
+
+impl Serialize for Wallet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_serialized()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// The key of the entry with everything but the chains, when the wallet is saved as
+/// [`Entries`].
+const WALLET_ENTRY_KEY: &[u8] = b"wallet";
+
+/// The prefix of the keys of the entries with one chain each, when the wallet is saved as
+/// [`Entries`].
+const CHAIN_ENTRY_KEY_PREFIX: &[u8] = b"chain:";
+
+impl Entries for Wallet {
+    /// Returns one entry for each chain, so that updating a chain only writes its own entry,
+    /// and one entry for the rest of the wallet.
+    fn to_entries(&self) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, serde_json::Error> {
+        let mut wallet = self.as_serialized().map_err(serde_json::Error::custom)?;
+        let chains = std::mem::take(&mut wallet.chains);
+        let mut entries = BTreeMap::new();
+        entries.insert(WALLET_ENTRY_KEY.to_vec(), serde_json::to_vec(&wallet)?);
+        for (chain_id, chain) in chains {
+            let mut key = CHAIN_ENTRY_KEY_PREFIX.to_vec();
+            key.extend_from_slice(chain_id.to_string().as_bytes());
+            entries.insert(key, serde_json::to_vec(&chain)?);
+        }
+        Ok(entries)
+    }
+
+    fn from_entries(mut entries: BTreeMap<Vec<u8>, Vec<u8>>) -> Result<Self, serde_json::Error> {
+        let wallet = entries
+            .remove(WALLET_ENTRY_KEY)
+            .ok_or_else(|| <serde_json::Error as serde::de::Error>::missing_field("wallet"))?;
+        let mut wallet: Wallet = serde_json::from_slice(&wallet)?;
+        for (key, chain) in entries {
+            if key.starts_with(CHAIN_ENTRY_KEY_PREFIX) {
+                let chain: UserChain = serde_json::from_slice(&chain)?;
+                wallet.chains.insert(chain.chain_id, chain);
+            }
+        }
+        Ok(wallet)
+    }
+}
+
+impl Wallet {
+    /// Returns the serialized form of the wallet, with the secret keys encrypted if a
+    /// passphrase was set.
+    fn as_serialized(&self) -> Result<WalletRef<'_>, key_store::Error> {
+        let encrypted_keys = match &self.key_store {
+            Some(key_store) => Some(key_store.encrypt(&self.stored_keys())?),
+            None => {
+                ensure!(
+                    self.encrypted_keys.is_none() || !self.has_plaintext_keys(),
+                    key_store::Error::Locked
+                );
+                self.encrypted_keys.clone()
+            }
+        };
+        let with_keys = encrypted_keys.is_none();
+        Ok(WalletRef {
+            chains: self
+                .chains
+                .iter()
+                .map(|(chain_id, chain)| (chain_id, chain.as_serialized(with_keys)))
+                .collect(),
+            unassigned_key_pairs: if with_keys {
+                self.unassigned_key_pairs.iter().collect()
+            } else {
+                HashMap::new()
+            },
+            default: &self.default,
+            genesis_config: &self.genesis_config,
+            testing_prng_seed: &self.testing_prng_seed,
+            encrypted_keys,
+            misbehavior_reports: &self.misbehavior_reports,
+        })
+    }
+}
+
+/// The serialized form of a [`Wallet`].
+#[derive(Serialize)]
+struct WalletRef<'a> {
+    chains: BTreeMap<&'a ChainId, UserChainRef<'a>>,
+    unassigned_key_pairs: HashMap<&'a Owner, &'a KeyPair>,
+    default: &'a Option<ChainId>,
+    genesis_config: &'a GenesisConfig,
+    testing_prng_seed: &'a Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encrypted_keys: Option<EncryptedKeys>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    misbehavior_reports: &'a Vec<MisbehaviorReport>,
 }
 
 impl Extend<UserChain> for Wallet {
@@ -51,6 +166,68 @@ impl Wallet {
             default: None,
             genesis_config,
             testing_prng_seed,
+            encrypted_keys: None,
+            misbehavior_reports: Vec::new(),
+            key_store: None,
+        }
+    }
+
+    /// Returns whether the secret keys are encrypted with a passphrase.
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted_keys.is_some() || self.key_store.is_some()
+    }
+
+    /// Returns whether the secret keys are encrypted and haven't been decrypted yet.
+    pub fn is_locked(&self) -> bool {
+        self.encrypted_keys.is_some() && self.key_store.is_none()
+    }
+
+    /// Decrypts the secret keys with the `passphrase`. The decrypted keys are only kept in
+    /// memory, and are encrypted again whenever the wallet is saved.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), key_store::Error> {
+        let Some(encrypted_keys) = &self.encrypted_keys else {
+            return Ok(());
+        };
+        let (key_store, keys) = KeyStore::unlock(passphrase, encrypted_keys)?;
+        for (chain_id, key_pair) in keys.chains {
+            if let Some(chain) = self.chains.get_mut(&chain_id) {
+                chain.key_pair = Some(key_pair);
+            }
+        }
+        for key_pair in keys.unassigned {
+            self.add_unassigned_key_pair(key_pair);
+        }
+        self.key_store = Some(key_store);
+        Ok(())
+    }
+
+    /// Encrypts the secret keys with the `passphrase` from now on. If the keys are already
+    /// encrypted, this changes the passphrase.
+    pub fn encrypt(&mut self, passphrase: &str) -> Result<(), key_store::Error> {
+        ensure!(!self.is_locked(), key_store::Error::Locked);
+        self.key_store = Some(KeyStore::new(passphrase)?);
+        Ok(())
+    }
+
+    /// Returns whether any secret key is held in plaintext.
+    fn has_plaintext_keys(&self) -> bool {
+        !self.unassigned_key_pairs.is_empty()
+            || self.chains.values().any(|chain| chain.key_pair.is_some())
+    }
+
+    /// Returns a copy of all the secret keys.
+    fn stored_keys(&self) -> StoredKeys {
+        StoredKeys {
+            chains: self
+                .chains
+                .iter()
+                .filter_map(|(chain_id, chain)| Some((*chain_id, chain.key_pair.as_ref()?.copy())))
+                .collect(),
+            unassigned: self
+                .unassigned_key_pairs
+                .values()
+                .map(KeyPair::copy)
+                .collect(),
         }
     }
 
@@ -182,6 +359,20 @@ impl Wallet {
                 pending_blobs: state.pending_blobs().clone(),
             },
         );
+        self.add_misbehavior_reports(chain_client.take_misbehavior_reports());
+    }
+
+    /// Returns the evidence of validator misbehavior recorded so far.
+    pub fn misbehavior_reports(&self) -> &[MisbehaviorReport] {
+        &self.misbehavior_reports
+    }
+
+    /// Records evidence of validator misbehavior.
+    pub fn add_misbehavior_reports(
+        &mut self,
+        reports: impl IntoIterator<Item = MisbehaviorReport>,
+    ) {
+        self.misbehavior_reports.extend(reports);
     }
 
     pub fn genesis_admin_chain(&self) -> ChainId {
@@ -214,7 +405,33 @@ pub struct UserChain {
     pub pending_blobs: BTreeMap<BlobId, Blob>,
 }
 
+/// The serialized form of a [`UserChain`].
+#[derive(Serialize)]
+struct UserChainRef<'a> {
+    chain_id: &'a ChainId,
+    key_pair: Option<&'a KeyPair>,
+    block_hash: &'a Option<CryptoHash>,
+    timestamp: &'a Timestamp,
+    next_block_height: &'a BlockHeight,
+    pending_block: &'a Option<ProposedBlock>,
+    pending_blobs: &'a BTreeMap<BlobId, Blob>,
+}
+
 impl UserChain {
+    /// Returns the serialized form of this chain, leaving out the key pair unless
+    /// `with_key_pair` is `true`.
+    fn as_serialized(&self, with_key_pair: bool) -> UserChainRef<'_> {
+        UserChainRef {
+            chain_id: &self.chain_id,
+            key_pair: self.key_pair.as_ref().filter(|_| with_key_pair),
+            block_hash: &self.block_hash,
+            timestamp: &self.timestamp,
+            next_block_height: &self.next_block_height,
+            pending_block: &self.pending_block,
+            pending_blobs: &self.pending_blobs,
+        }
+    }
+
     /// Create a user chain that we own.
     pub fn make_initial<R: CryptoRng>(
         rng: &mut R,
@@ -28,14 +28,37 @@ use linera_execution::{
 use linera_storage::Storage;
 use tokio::sync::{mpsc, oneshot, OwnedRwLockReadGuard};
 use tracing::{instrument, trace, warn};
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::{bucket_interval, hash_bucket_label, register_histogram_vec},
+    prometheus::HistogramVec,
+    std::sync::LazyLock,
+};
 
-use super::{config::ChainWorkerConfig, state::ChainWorkerState, DeliveryNotifier};
+use super::{
+    config::ChainWorkerConfig, scheduler::ChainWorkerScheduler, state::ChainWorkerState,
+    DeliveryNotifier,
+};
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
     value_cache::ValueCache,
     worker::{NetworkActions, WorkerError},
 };
 
+/// The number of different values of the `chain_bucket` label.
+#[cfg(with_metrics)]
+const CHAIN_BUCKETS: u64 = 64;
+
+#[cfg(with_metrics)]
+static CHAIN_WORKER_QUEUE_LENGTH: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "chain_worker_queue_length",
+        "Number of requests waiting for a chain worker when it starts handling a request",
+        &["chain_bucket"],
+        bucket_interval(1.0, 10_000.0),
+    )
+});
+
 /// A request for the [`ChainWorkerActor`].
 #[derive(Debug)]
 pub enum ChainWorkerRequest<Context>
@@ -50,6 +73,13 @@ where
         callback: oneshot::Sender<Result<Option<ConfirmedBlockCertificate>, WorkerError>>,
     },
 
+    /// Count how many times the chain state was persisted.
+    #[cfg(with_testing)]
+    CountCommits {
+        #[debug(skip)]
+        callback: oneshot::Sender<Result<u64, WorkerError>>,
+    },
+
     /// Search for a bundle in one of the chain's inboxes.
     #[cfg(with_testing)]
     FindBundleInInbox {
@@ -68,9 +98,10 @@ where
             oneshot::Sender<Result<OwnedRwLockReadGuard<ChainStateView<Context>>, WorkerError>>,
     },
 
-    /// Query an application's state.
+    /// Query an application's state, optionally pinned to a specific block height.
     QueryApplication {
         query: Query,
+        height: Option<BlockHeight>,
         #[debug(skip)]
         callback: oneshot::Sender<Result<QueryOutcome, WorkerError>>,
     },
@@ -120,6 +151,15 @@ where
         callback: oneshot::Sender<Result<(ChainInfoResponse, NetworkActions), WorkerError>>,
     },
 
+    /// Process a sequence of consecutive confirmed blocks (a catch-up batch).
+    ProcessConfirmedBlocks {
+        certificates: Vec<ConfirmedBlockCertificate>,
+        #[debug(with = "elide_option")]
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+        #[debug(skip)]
+        callback: oneshot::Sender<Result<(ChainInfoResponse, NetworkActions), WorkerError>>,
+    },
+
     /// Process a cross-chain update.
     ProcessCrossChainUpdate {
         origin: Origin,
@@ -222,6 +262,10 @@ where
             chain_id,
             next_block_height: BlockHeight(0),
             local_time: Timestamp::from(0),
+            block_hash: None,
+            block_timestamp: Timestamp::from(0),
+            maximum_response_size: u64::MAX,
+            authenticated_signer: None,
         };
 
         let (execution_state_sender, incoming_execution_requests) =
@@ -241,6 +285,8 @@ where
     }
 
     /// Runs the worker until there are no more incoming requests.
+    ///
+    /// Each request is handled only after the `scheduler` gives the chain its turn.
     #[instrument(
         name = "ChainWorkerActor",
         skip_all,
@@ -249,10 +295,18 @@ where
     pub async fn run(
         mut self,
         mut incoming_requests: mpsc::UnboundedReceiver<ChainWorkerRequest<StorageClient::Context>>,
+        scheduler: ChainWorkerScheduler,
     ) {
         trace!("Starting `ChainWorkerActor`");
+        #[cfg(with_metrics)]
+        let chain_bucket = hash_bucket_label(&self.worker.chain_id(), CHAIN_BUCKETS);
 
         while let Some(request) = incoming_requests.recv().await {
+            #[cfg(with_metrics)]
+            CHAIN_WORKER_QUEUE_LENGTH
+                .with_label_values(&[&chain_bucket])
+                .observe(incoming_requests.len() as f64);
+            let _permit = scheduler.acquire().await;
             // TODO(#2237): Spawn concurrent tasks for read-only operations
             trace!("Handling `ChainWorkerRequest`: {request:?}");
 
@@ -262,6 +316,10 @@ where
                     .send(self.worker.read_certificate(height).await)
                     .is_ok(),
                 #[cfg(with_testing)]
+                ChainWorkerRequest::CountCommits { callback } => {
+                    callback.send(Ok(self.worker.num_commits())).is_ok()
+                }
+                #[cfg(with_testing)]
                 ChainWorkerRequest::FindBundleInInbox {
                     inbox_id,
                     certificate_hash,
@@ -278,8 +336,12 @@ where
                 ChainWorkerRequest::GetChainStateView { callback } => {
                     callback.send(self.worker.chain_state_view().await).is_ok()
                 }
-                ChainWorkerRequest::QueryApplication { query, callback } => callback
-                    .send(self.worker.query_application(query).await)
+                ChainWorkerRequest::QueryApplication {
+                    query,
+                    height,
+                    callback,
+                } => callback
+                    .send(self.worker.query_application(query, height).await)
                     .is_ok(),
                 ChainWorkerRequest::DescribeApplication {
                     application_id,
@@ -323,6 +385,20 @@ where
                             .await,
                     )
                     .is_ok(),
+                ChainWorkerRequest::ProcessConfirmedBlocks {
+                    certificates,
+                    notify_when_messages_are_delivered,
+                    callback,
+                } => callback
+                    .send(
+                        self.worker
+                            .process_confirmed_blocks(
+                                certificates,
+                                notify_when_messages_are_delivered,
+                            )
+                            .await,
+                    )
+                    .is_ok(),
                 ChainWorkerRequest::ProcessCrossChainUpdate {
                     origin,
                     bundles,
@@ -364,6 +440,8 @@ where
             if !responded {
                 warn!("Callback for `ChainWorkerActor` was dropped before a response was sent");
             }
+
+            self.worker.write_pending_state_snapshot().await;
         }
 
         if let Some(thread) = self.service_runtime_thread {
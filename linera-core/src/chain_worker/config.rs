@@ -3,7 +3,7 @@
 
 //! Configuration parameters for the chain worker.
 
-use std::sync::Arc;
+use std::{num::NonZeroU64, sync::Arc};
 
 use linera_base::{crypto::KeyPair, time::Duration};
 
@@ -22,6 +22,13 @@ pub struct ChainWorkerConfig {
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
     /// will wait until that timestamp before voting.
     pub grace_period: Duration,
+    /// Whether to answer queries for the hashes of the parts of the execution state.
+    pub serve_state_hash_breakdowns: bool,
+    /// How often the state of each chain is copied, to answer queries at past heights.
+    pub state_snapshots: StateSnapshotOptions,
+    /// Whether to export the execution statistics of each chain as Prometheus gauges,
+    /// labelled by chain ID.
+    pub export_chain_statistics: bool,
 }
 
 impl ChainWorkerConfig {
@@ -36,3 +43,28 @@ impl ChainWorkerConfig {
         self.key_pair.as_ref().map(Arc::as_ref)
     }
 }
+
+/// The default number of snapshots kept for each chain.
+pub const DEFAULT_RETAINED_STATE_SNAPSHOTS: usize = 10;
+
+/// How often the state of each chain is copied, to answer application queries at past heights.
+///
+/// A query at a past height starts from the latest snapshot at or below that height, and
+/// re-executes the blocks in between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateSnapshotOptions {
+    /// The number of blocks between two snapshots, or `None` to only keep the current state.
+    pub interval: Option<NonZeroU64>,
+    /// How many snapshots to keep for each chain. Queries at heights before the oldest one
+    /// fail.
+    pub retained: usize,
+}
+
+impl Default for StateSnapshotOptions {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            retained: DEFAULT_RETAINED_STATE_SNAPSHOTS,
+        }
+    }
+}
@@ -6,6 +6,7 @@
 mod actor;
 mod config;
 mod delivery_notifier;
+mod scheduler;
 mod state;
 
 pub(super) use self::delivery_notifier::DeliveryNotifier;
@@ -13,6 +14,7 @@ pub(super) use self::delivery_notifier::DeliveryNotifier;
 pub(crate) use self::state::CrossChainUpdateHelper;
 pub use self::{
     actor::{ChainWorkerActor, ChainWorkerRequest},
-    config::ChainWorkerConfig,
+    config::{ChainWorkerConfig, StateSnapshotOptions, DEFAULT_RETAINED_STATE_SNAPSHOTS},
+    scheduler::{ChainWorkerPermit, ChainWorkerScheduler},
     state::ChainWorkerState,
 };
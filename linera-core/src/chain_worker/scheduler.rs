@@ -0,0 +1,61 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scheduling of the requests handled by the chain workers.
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[cfg(test)]
+#[path = "../unit_tests/chain_worker_scheduler_tests.rs"]
+mod chain_worker_scheduler_tests;
+
+/// Bounds the number of [`ChainWorkerActor`][`super::ChainWorkerActor`]s handling a request
+/// at the same time.
+///
+/// Every actor handles the requests of its chain one at a time, in the order they were sent,
+/// while the actors of different chains run concurrently. An actor holds a
+/// [`ChainWorkerPermit`] only while it handles a single request, and asks for a new one before
+/// handling the next. Permits are granted in the order they were asked for, so the chains with
+/// pending requests are served in turn, and a chain with a long queue cannot delay the others
+/// by more than one request per running chain.
+///
+/// Clones share the same permits.
+#[derive(Clone, Debug, Default)]
+pub struct ChainWorkerScheduler {
+    /// The permits to handle a request, or [`None`] if the number of chains handling a request
+    /// is not limited.
+    permits: Option<Arc<Semaphore>>,
+}
+
+/// A permit to handle a request on a chain, released when dropped.
+#[derive(Debug)]
+pub struct ChainWorkerPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ChainWorkerScheduler {
+    /// Creates a scheduler letting at most `max_concurrent_chains` chains handle a request at
+    /// the same time, or any number of them if [`None`].
+    pub fn new(max_concurrent_chains: Option<NonZeroUsize>) -> Self {
+        ChainWorkerScheduler {
+            permits: max_concurrent_chains.map(|limit| Arc::new(Semaphore::new(limit.get()))),
+        }
+    }
+
+    /// Waits for the turn of a chain to handle its next request.
+    pub async fn acquire(&self) -> ChainWorkerPermit {
+        let permit = match &self.permits {
+            Some(permits) => Some(
+                permits
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("The semaphore of the `ChainWorkerScheduler` is never closed"),
+            ),
+            None => None,
+        };
+        ChainWorkerPermit { _permit: permit }
+    }
+}
@@ -7,9 +7,10 @@ use std::{borrow::Cow, collections::BTreeMap};
 
 use futures::future::Either;
 use linera_base::{
-    data_types::{Blob, BlobContent, BlockHeight, CompressedBytecode, Timestamp},
+    data_types::{Amount, Blob, BlobContent, BlockHeight, CompressedBytecode, Timestamp},
     ensure,
     identifiers::{BlobType, ChainId, MessageId},
+    time::Instant,
 };
 use linera_chain::{
     data_types::{
@@ -31,6 +32,14 @@ use linera_views::{
 };
 use tokio::sync::oneshot;
 use tracing::{debug, instrument, trace, warn};
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::{
+        bucket_interval, hash_bucket_label, register_histogram_vec, register_int_gauge_vec,
+    },
+    prometheus::{HistogramVec, IntGaugeVec},
+    std::sync::LazyLock,
+};
 
 use super::{check_block_epoch, ChainWorkerConfig, ChainWorkerState};
 use crate::{
@@ -38,6 +47,29 @@ use crate::{
     worker::{NetworkActions, Notification, Reason, WorkerError},
 };
 
+/// The number of different values of the `chain_bucket` label.
+#[cfg(with_metrics)]
+const CHAIN_BUCKETS: u64 = 64;
+
+#[cfg(with_metrics)]
+static INBOX_BACKLOG: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "inbox_backlog",
+        "Number of bundles waiting in an inbox after it received new bundles",
+        &["chain_bucket"],
+        bucket_interval(1.0, 10_000.0),
+    )
+});
+
+#[cfg(with_metrics)]
+static CHAIN_STATISTICS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "chain_statistics",
+        "Execution statistics of a chain, if exporting them is enabled",
+        &["chain_id", "statistic"],
+    )
+});
+
 /// Wrapper type that tracks if the changes to the `chain` state should be rolled back when
 /// dropped.
 pub struct ChainWorkerStateWithAttemptedChanges<'state, StorageClient>
@@ -130,11 +162,13 @@ where
                     block,
                     round,
                     outcome: _,
+                    estimates: _,
                 },
             public_key: _,
             owner,
             validated_block_certificate,
             signature: _,
+            fee_payer: _,
         } = proposal;
 
         let chain = &self.state.chain;
@@ -143,11 +177,44 @@ where
         check_block_epoch(epoch, block.chain_id, block.epoch)?;
         let policy = committee.policy().clone();
         block.check_proposal_size(policy.maximum_block_proposal_size)?;
+        // Admit the proposal only if the resources it claims to use fit in a block.
+        proposal.content.check_resource_estimates(&policy)?;
         // Check the authentication of the block.
         ensure!(
             chain.manager.verify_owner(proposal),
             WorkerError::InvalidOwner
         );
+        let sponsorship = match block.fee_payer {
+            Some(sponsor) => {
+                chain
+                    .execution_state
+                    .system
+                    .sponsorships
+                    .get(&sponsor)
+                    .await?
+            }
+            None => None,
+        };
+        ensure!(
+            chain.manager.verify_fee_payer(
+                proposal,
+                sponsorship.as_ref().map(|sponsorship| sponsorship.owner)
+            ),
+            WorkerError::InvalidFeePayer
+        );
+        // Refuse to execute a block that the sponsor cannot pay for in any case.
+        if let Some(sponsor) = block.fee_payer {
+            let balance = sponsorship.map_or(Amount::ZERO, |sponsorship| sponsorship.balance);
+            let fees = block.base_fees(&policy)?;
+            ensure!(
+                balance >= fees,
+                WorkerError::InsufficientSponsorFunding {
+                    sponsor,
+                    balance,
+                    fees,
+                }
+            );
+        }
         if let Some(lite_certificate) = validated_block_certificate {
             // Verify that this block has been validated by a quorum before.
             lite_certificate.check(committee)?;
@@ -301,6 +368,126 @@ where
         certificate: ConfirmedBlockCertificate,
         notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        let block_height = certificate.block().header.height;
+        let Some(notification) = self.execute_confirmed_block(&certificate).await? else {
+            // Block was already confirmed.
+            let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+            let actions = self.state.create_network_actions().await?;
+            return Ok((info, actions));
+        };
+        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        let mut actions = self.state.create_network_actions().await?;
+        actions.notifications.push(notification);
+        let snapshot = self.schedule_state_snapshot();
+        // Persist chain.
+        self.save().await?;
+        self.state.pending_state_snapshot = snapshot;
+        self.export_chain_statistics();
+
+        self.state
+            .block_values
+            .insert(Cow::Owned(certificate.into_inner().into_inner()));
+
+        self.register_delivery_notifier(block_height, &actions, notify_when_messages_are_delivered)
+            .await;
+
+        Ok((info, actions))
+    }
+
+    /// Processes a sequence of confirmed blocks, persisting the chain state only once at the end.
+    ///
+    /// If a certificate is rejected before it changes the chain state, the certificates before
+    /// it are still persisted, without executing them again. If it fails later, or if persisting
+    /// fails, none of the changes are persisted. In both cases, the error contains the number of
+    /// certificates that were committed.
+    pub(super) async fn process_confirmed_blocks(
+        &mut self,
+        certificates: &[ConfirmedBlockCertificate],
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), (usize, WorkerError)> {
+        let mut notifications = Vec::new();
+        let mut new_certificates = Vec::new();
+        let mut rejection = None;
+        // The number of leading certificates that were already confirmed.
+        let mut committed = 0;
+        for (index, certificate) in certificates.iter().enumerate() {
+            match self.execute_confirmed_block(certificate).await {
+                Ok(Some(notification)) => {
+                    notifications.push(notification);
+                    new_certificates.push(certificate);
+                }
+                Ok(None) => {
+                    if new_certificates.is_empty() {
+                        committed = index + 1;
+                    }
+                }
+                Err(ConfirmedBlockError::Rejected(error)) => {
+                    rejection = Some((index, error));
+                    break;
+                }
+                Err(ConfirmedBlockError::Failed(error)) => return Err((committed, error)),
+            }
+        }
+        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        let mut actions = self
+            .state
+            .create_network_actions()
+            .await
+            .map_err(|error| (committed, error))?;
+        if let Some(last_certificate) = new_certificates.last() {
+            actions.notifications.extend(notifications);
+            let snapshot = self.schedule_state_snapshot();
+            // Persist chain.
+            self.save().await.map_err(|error| (committed, error))?;
+            self.state.pending_state_snapshot = snapshot;
+            self.export_chain_statistics();
+            let block_height = last_certificate.block().header.height;
+            for certificate in &new_certificates {
+                self.state
+                    .block_values
+                    .insert(Cow::Borrowed(certificate.inner().inner()));
+            }
+            self.register_delivery_notifier(
+                block_height,
+                &actions,
+                notify_when_messages_are_delivered,
+            )
+            .await;
+        }
+        match rejection {
+            Some((index, error)) => Err((index, error)),
+            None => Ok((info, actions)),
+        }
+    }
+
+    /// Verifies and executes a confirmed block, without persisting the chain state.
+    ///
+    /// Returns the notification for the new block, or `None` if it was already confirmed.
+    async fn execute_confirmed_block(
+        &mut self,
+        certificate: &ConfirmedBlockCertificate,
+    ) -> Result<Option<Notification>, ConfirmedBlockError> {
+        let Some(executed_block) = self
+            .check_confirmed_block(certificate)
+            .await
+            .map_err(ConfirmedBlockError::Rejected)?
+        else {
+            return Ok(None);
+        };
+        self.apply_confirmed_block(certificate, executed_block)
+            .await
+            .map(Some)
+            .map_err(ConfirmedBlockError::Failed)
+    }
+
+    /// Verifies a confirmed block and writes its blobs and certificate to storage, without
+    /// changing the chain state, except for initializing a new chain with its first block.
+    ///
+    /// Returns `None` if the block was already confirmed.
+    async fn check_confirmed_block(
+        &mut self,
+        certificate: &ConfirmedBlockCertificate,
+    ) -> Result<Option<ExecutedBlock>, WorkerError> {
         let executed_block: ExecutedBlock = certificate.block().clone().into();
         let block_height = executed_block.block.height;
         // Check that the chain is active and ready for this confirmation.
@@ -312,9 +499,7 @@ where
         }
         if tip.next_block_height > block_height {
             // Block was already confirmed.
-            let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
-            let actions = self.state.create_network_actions().await?;
-            return Ok((info, actions));
+            return Ok(None);
         }
         // TODO(#2351): This sets the committee and then checks that committee's signatures.
         if tip.is_first_block() && !self.state.chain.is_active() {
@@ -363,7 +548,7 @@ where
         if let Ok(blobs) = &blobs_result {
             self.state
                 .storage
-                .write_blobs_and_certificate(blobs, &certificate)
+                .write_blobs_and_certificate(blobs, certificate)
                 .await?;
         }
 
@@ -381,7 +566,16 @@ where
             .maybe_write_blob_states(&blob_ids, blob_state, overwrite)
             .await?;
         blobs_result?;
+        Ok(Some(executed_block))
+    }
 
+    /// Executes a verified confirmed block and advances the chain, without persisting it.
+    async fn apply_confirmed_block(
+        &mut self,
+        certificate: &ConfirmedBlockCertificate,
+        executed_block: ExecutedBlock,
+    ) -> Result<Notification, WorkerError> {
+        let block_height = executed_block.block.height;
         // Execute the block and update inboxes.
         self.state
             .chain
@@ -391,21 +585,43 @@ where
             )
             .await?;
         let local_time = self.state.storage.clock().current_time();
-        let verified_outcome = Box::pin(self.state.chain.execute_block(
-            &executed_block.block,
-            local_time,
-            None,
-            Some(executed_block.outcome.oracle_responses.clone()),
-        ))
-        .await?;
+        let execution_start = Instant::now();
+        let (verified_outcome, resource_usage) =
+            Box::pin(self.state.chain.execute_block_with_resource_usage(
+                &executed_block.block,
+                local_time,
+                None,
+                Some(executed_block.outcome.oracle_responses.clone()),
+            ))
+            .await?;
+        let execution_time = execution_start.elapsed();
         // We should always agree on the messages and state hash.
-        ensure!(
-            executed_block.outcome == verified_outcome,
-            WorkerError::IncorrectOutcome {
+        if executed_block.outcome != verified_outcome {
+            if executed_block.outcome.state_hash != verified_outcome.state_hash {
+                // Log the hashes of the parts of our state, so that operators can compare them
+                // with the other validators'.
+                match self
+                    .state
+                    .chain
+                    .execution_state
+                    .state_hash_breakdown()
+                    .await
+                {
+                    Ok(breakdown) => warn!(
+                        height = %executed_block.block.height,
+                        submitted = %executed_block.outcome.state_hash,
+                        computed = %verified_outcome.state_hash,
+                        ?breakdown,
+                        "State hash mismatch"
+                    ),
+                    Err(error) => warn!(%error, "Failed to compute the state hash breakdown"),
+                }
+            }
+            return Err(WorkerError::IncorrectOutcome {
                 submitted: Box::new(executed_block.outcome.clone()),
                 computed: Box::new(verified_outcome),
-            }
-        );
+            });
+        }
         // Advance to next block height.
         let tip = self.state.chain.tip_state.get_mut();
         tip.block_hash = Some(certificate.hash());
@@ -413,33 +629,81 @@ where
         tip.num_incoming_bundles += executed_block.block.incoming_bundles.len() as u32;
         tip.num_operations += executed_block.block.operations.len() as u32;
         tip.num_outgoing_messages += executed_block.outcome.messages.len() as u32;
+        self.state
+            .chain
+            .execution_statistics
+            .get_mut()
+            .record_block(
+                &executed_block.block,
+                &executed_block.outcome,
+                resource_usage.total,
+                execution_time,
+            );
         self.state.chain.confirmed_log.push(certificate.hash());
-        let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
+        self.state
+            .chain
+            .index_block_applications(&executed_block.block)
+            .await?;
         self.state.track_newly_created_chains(&executed_block);
-        let mut actions = self.state.create_network_actions().await?;
         trace!(
             "Processed confirmed block {} on chain {:.8}",
             block_height,
             executed_block.block.chain_id
         );
-        actions.notifications.push(Notification {
+        Ok(Notification {
             chain_id: executed_block.block.chain_id,
             reason: Reason::NewBlock {
                 height: block_height,
                 hash: certificate.hash(),
             },
-        });
-        // Persist chain.
-        self.save().await?;
-
-        self.state
-            .block_values
-            .insert(Cow::Owned(certificate.into_inner().into_inner()));
+        })
+    }
 
-        self.register_delivery_notifier(block_height, &actions, notify_when_messages_are_delivered)
-            .await;
+    /// Records that the chain state at its new height is to be snapshotted, if the configured
+    /// interval has passed since the last snapshot.
+    ///
+    /// Returns the height of the new snapshot together with the heights of the snapshots that
+    /// are no longer retained, handled once the chain is saved and the request is answered.
+    fn schedule_state_snapshot(&mut self) -> Option<(BlockHeight, Vec<BlockHeight>)> {
+        let options = self.state.config.state_snapshots;
+        let interval = options.interval?;
+        if options.retained == 0 {
+            return None;
+        }
+        let height = self.state.chain.tip_state.get().next_block_height;
+        let heights = self.state.chain.state_snapshot_heights.get_mut();
+        if let Some(last) = heights.last() {
+            if height.0 < last.0.saturating_add(interval.get()) {
+                return None;
+            }
+        }
+        heights.push(height);
+        let excess = heights.len().saturating_sub(options.retained);
+        let expired = heights.drain(..excess).collect();
+        Some((height, expired))
+    }
 
-        Ok((info, actions))
+    /// Sets the Prometheus gauges of the chain's execution statistics, if enabled.
+    fn export_chain_statistics(&self) {
+        #[cfg(with_metrics)]
+        if self.state.config.export_chain_statistics {
+            let chain_id = self.state.chain_id().to_string();
+            let statistics = self.state.chain.execution_statistics.get();
+            for (statistic, value) in [
+                ("blocks", statistics.blocks),
+                ("operations", statistics.operations),
+                ("incoming_messages", statistics.incoming_messages),
+                ("outgoing_messages", statistics.outgoing_messages),
+                ("fuel", statistics.fuel),
+                ("bytes_read", statistics.bytes_read),
+                ("bytes_written", statistics.bytes_written),
+                ("execution_micros", statistics.execution_micros),
+            ] {
+                CHAIN_STATISTICS
+                    .with_label_values(&[&chain_id, statistic])
+                    .set(i64::try_from(value).unwrap_or(i64::MAX));
+            }
+        }
     }
 
     /// Schedules a notification for when cross-chain messages are delivered up to the given
@@ -532,6 +796,13 @@ where
         };
         // Save the chain.
         self.save().await?;
+        #[cfg(with_metrics)]
+        {
+            let backlog = self.state.chain.inbox_backlog(&origin).await?;
+            INBOX_BACKLOG
+                .with_label_values(&[&hash_bucket_label(&recipient, CHAIN_BUCKETS)])
+                .observe(backlog as f64);
+        }
         Ok(Some((last_updated_height, actions)))
     }
 
@@ -700,6 +971,10 @@ where
         };
 
         self.state.chain.save().await?;
+        #[cfg(with_testing)]
+        {
+            self.state.num_commits += 1;
+        }
         self.succeeded = true;
         Ok(())
     }
@@ -716,6 +991,22 @@ where
     }
 }
 
+/// The ways a confirmed block in a batch can fail.
+enum ConfirmedBlockError {
+    /// The block was rejected before it changed the chain state.
+    Rejected(WorkerError),
+    /// The block failed after it started changing the chain state.
+    Failed(WorkerError),
+}
+
+impl From<ConfirmedBlockError> for WorkerError {
+    fn from(error: ConfirmedBlockError) -> Self {
+        match error {
+            ConfirmedBlockError::Rejected(error) | ConfirmedBlockError::Failed(error) => error,
+        }
+    }
+}
+
 /// Helper type for handling cross-chain updates.
 pub(crate) struct CrossChainUpdateHelper<'a> {
     pub allow_messages_from_deprecated_epochs: bool,
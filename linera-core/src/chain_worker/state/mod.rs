@@ -33,6 +33,11 @@ use linera_execution::{
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::{ClonableView, ViewError};
 use tokio::sync::{oneshot, OwnedRwLockReadGuard, RwLock};
+use tracing::warn;
+
+/// The maximum number of confirmed blocks from a batch that are executed before the chain
+/// state is persisted.
+const CONFIRMED_BLOCKS_PER_COMMIT: usize = 100;
 
 #[cfg(test)]
 pub(crate) use self::attempted_changes::CrossChainUpdateHelper;
@@ -61,6 +66,12 @@ where
     tracked_chains: Option<Arc<sync::RwLock<HashSet<ChainId>>>>,
     delivery_notifier: DeliveryNotifier,
     knows_chain_is_active: bool,
+    /// The height of the state snapshot to write once the request is answered, with the
+    /// heights of the snapshots that are no longer retained.
+    pending_state_snapshot: Option<(BlockHeight, Vec<BlockHeight>)>,
+    /// How many times the chain state was persisted since it was loaded.
+    #[cfg(with_testing)]
+    num_commits: u64,
 }
 
 impl<StorageClient> ChainWorkerState<StorageClient>
@@ -90,6 +101,9 @@ where
             tracked_chains,
             delivery_notifier,
             knows_chain_is_active: false,
+            pending_state_snapshot: None,
+            #[cfg(with_testing)]
+            num_commits: 0,
         })
     }
 
@@ -104,6 +118,14 @@ where
             chain_id: self.chain_id(),
             next_block_height: self.chain.tip_state.get().next_block_height,
             local_time: self.storage.clock().current_time(),
+            block_hash: self.chain.tip_state.get().block_hash,
+            block_timestamp: *self.chain.execution_state.system.timestamp.get(),
+            maximum_response_size: self
+                .chain
+                .execution_state
+                .system
+                .maximum_query_response_size(),
+            authenticated_signer: None,
         }
     }
 
@@ -127,6 +149,12 @@ where
             .await)
     }
 
+    /// Returns how many times the chain state was persisted since it was loaded.
+    #[cfg(with_testing)]
+    pub(super) fn num_commits(&self) -> u64 {
+        self.num_commits
+    }
+
     /// Returns a stored [`Certificate`] for the chain's block at the requested [`BlockHeight`].
     #[cfg(with_testing)]
     pub(super) async fn read_certificate(
@@ -154,14 +182,15 @@ where
             .await
     }
 
-    /// Queries an application's state on the chain.
+    /// Queries an application's state on the chain, optionally pinned to a block height.
     pub(super) async fn query_application(
         &mut self,
         query: Query,
+        height: Option<BlockHeight>,
     ) -> Result<QueryOutcome, WorkerError> {
         ChainWorkerStateWithTemporaryChanges::new(self)
             .await
-            .query_application(query)
+            .query_application(query, height)
             .await
     }
 
@@ -257,6 +286,72 @@ where
             .await
     }
 
+    /// Processes a sequence of consecutive confirmed blocks of this chain.
+    ///
+    /// The chain state is persisted once for every group of `CONFIRMED_BLOCKS_PER_COMMIT`
+    /// blocks. If a certificate fails, the error is returned together with the index of the
+    /// first certificate that was not committed: that is the failing certificate if it was
+    /// rejected before changing the chain state, and otherwise the start of its group.
+    pub(super) async fn process_confirmed_blocks(
+        &mut self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        mut notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        let mut notifications = Vec::new();
+        let mut outcome = None;
+        let group_count = certificates.len().div_ceil(CONFIRMED_BLOCKS_PER_COMMIT);
+        for (group_index, group) in certificates.chunks(CONFIRMED_BLOCKS_PER_COMMIT).enumerate() {
+            let notify = if group_index + 1 == group_count {
+                notify_when_messages_are_delivered.take()
+            } else {
+                None
+            };
+            // The next group changes the persisted state, so the snapshot of this one is written
+            // first.
+            self.write_pending_state_snapshot().await;
+            let result = ChainWorkerStateWithAttemptedChanges::new(self)
+                .await
+                .process_confirmed_blocks(group, notify)
+                .await;
+            match result {
+                Ok((info, mut actions)) => {
+                    notifications.append(&mut actions.notifications);
+                    outcome = Some((info, actions));
+                }
+                Err((index, error)) => {
+                    return Err(WorkerError::CertificateBatch {
+                        index: group_index * CONFIRMED_BLOCKS_PER_COMMIT + index,
+                        error: Box::new(error),
+                    });
+                }
+            }
+        }
+        let (info, mut actions) = outcome.ok_or(WorkerError::EmptyCertificateBatch)?;
+        actions.notifications = notifications;
+        Ok((info, actions))
+    }
+
+    /// Writes the state snapshot scheduled by the last confirmed block, if any, and deletes the
+    /// snapshots that are no longer retained.
+    ///
+    /// The actor calls this after answering each request, so that confirming a block doesn't
+    /// wait for the copy. It must run before the chain state is persisted again.
+    pub(super) async fn write_pending_state_snapshot(&mut self) {
+        let Some((height, expired)) = self.pending_state_snapshot.take() else {
+            return;
+        };
+        let chain_id = self.chain_id();
+        // The chain is already committed, so a failed snapshot is only logged.
+        if let Err(error) = self.storage.write_chain_snapshot(chain_id, height).await {
+            warn!(%error, "Failed to write the chain state snapshot");
+        }
+        for height in expired {
+            if let Err(error) = self.storage.delete_chain_snapshot(chain_id, height).await {
+                warn!(%error, "Failed to delete an expired chain state snapshot");
+            }
+        }
+    }
+
     /// Updates the chain's inboxes, receiving messages from a cross-chain update.
     pub(super) async fn process_cross_chain_update(
         &mut self,
@@ -4,20 +4,23 @@
 //! Operations that don't persist any changes to the chain state.
 
 use linera_base::{
-    data_types::{ArithmeticError, Timestamp, UserApplicationDescription},
+    data_types::{ArithmeticError, BlockHeight, Timestamp, UserApplicationDescription},
     ensure,
     identifiers::{AccountOwner, GenericApplicationId, UserApplicationId},
 };
-use linera_chain::data_types::{
-    BlockExecutionOutcome, ChannelFullName, ExecutedBlock, IncomingBundle, Medium, MessageAction,
-    ProposalContent, ProposedBlock,
+use linera_chain::{
+    data_types::{
+        BlockExecutionOutcome, ChannelFullName, ExecutedBlock, IncomingBundle, Medium,
+        MessageAction, ProposalContent, ProposedBlock,
+    },
+    ChainStateView,
 };
 use linera_execution::{ChannelSubscription, Query, QueryOutcome};
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::View;
 #[cfg(with_testing)]
 use {
-    linera_base::{crypto::CryptoHash, data_types::BlockHeight},
+    linera_base::crypto::CryptoHash,
     linera_chain::{
         data_types::{MessageBundle, Origin},
         types::ConfirmedBlockCertificate,
@@ -91,11 +94,33 @@ where
     }
 
     /// Queries an application's state on the chain.
+    ///
+    /// A query pinned to a past height is answered from the latest retained snapshot of the
+    /// chain state at or below that height, re-executing the blocks in between. It fails if
+    /// there is no such snapshot.
     pub(super) async fn query_application(
         &mut self,
         query: Query,
+        height: Option<BlockHeight>,
     ) -> Result<QueryOutcome, WorkerError> {
         self.0.ensure_is_active()?;
+        if let Some(height) = height {
+            let next_block_height = self.0.chain.tip_state.get().next_block_height;
+            if height < next_block_height {
+                let mut chain = self.past_chain_state(height).await?;
+                let local_time = self.0.storage.clock().current_time();
+                // The long-lived service runtime, if any, is tied to the current state.
+                let outcome = chain.query_application(local_time, query, None).await?;
+                return Ok(outcome);
+            }
+            ensure!(
+                height == next_block_height,
+                WorkerError::UnexpectedBlockHeight {
+                    expected_block_height: height,
+                    found_block_height: next_block_height,
+                }
+            );
+        }
         let local_time = self.0.storage.clock().current_time();
         let outcome = self
             .0
@@ -105,6 +130,67 @@ where
         Ok(outcome)
     }
 
+    /// Reconstructs the chain state at a past `height`, i.e. before executing the block at that
+    /// height, from the latest retained snapshot at or below it.
+    async fn past_chain_state(
+        &self,
+        height: BlockHeight,
+    ) -> Result<ChainStateView<StorageClient::SpeculativeContext>, WorkerError> {
+        let chain_id = self.0.chain_id();
+        let snapshot_heights = self.0.chain.state_snapshot_heights.get();
+        let mut maybe_chain = None;
+        for snapshot_height in snapshot_heights.iter().rev() {
+            if *snapshot_height > height {
+                continue;
+            }
+            // A snapshot may be missing if the worker stopped right after saving the chain.
+            maybe_chain = self
+                .0
+                .storage
+                .load_chain_snapshot(chain_id, *snapshot_height)
+                .await?;
+            if maybe_chain.is_some() {
+                break;
+            }
+        }
+        let Some(mut chain) = maybe_chain else {
+            return Err(WorkerError::StateNotRetained {
+                chain_id,
+                height,
+                oldest_retained_height: snapshot_heights
+                    .first()
+                    .copied()
+                    .unwrap_or(self.0.chain.tip_state.get().next_block_height),
+            });
+        };
+        let start = usize::try_from(chain.tip_state.get().next_block_height)?;
+        let end = usize::try_from(height)?;
+        let hashes = self.0.chain.confirmed_log.read(start..end).await?;
+        let certificates = self.0.storage.read_certificates(hashes).await?;
+        let local_time = self.0.storage.clock().current_time();
+        for certificate in certificates {
+            let executed_block: ExecutedBlock = certificate.block().clone().into();
+            let outcome = Box::pin(chain.execute_block(
+                &executed_block.block,
+                local_time,
+                None,
+                Some(executed_block.outcome.oracle_responses.clone()),
+            ))
+            .await?;
+            if executed_block.outcome != outcome {
+                return Err(WorkerError::IncorrectOutcome {
+                    submitted: Box::new(executed_block.outcome),
+                    computed: Box::new(outcome),
+                });
+            }
+            // Only the inboxes are left behind, which queries don't read.
+            let tip = chain.tip_state.get_mut();
+            tip.block_hash = Some(certificate.hash());
+            tip.next_block_height.try_add_assign_one()?;
+        }
+        Ok(chain)
+    }
+
     /// Returns an application's description.
     pub(super) async fn describe_application(
         &mut self,
@@ -124,11 +210,17 @@ where
         let local_time = self.0.storage.clock().current_time();
         let signer = block.authenticated_signer;
 
-        let executed_block = Box::pin(self.0.chain.execute_block(&block, local_time, round, None))
-            .await?
-            .with(block);
+        let (outcome, resource_usage) = Box::pin(
+            self.0
+                .chain
+                .execute_block_with_resource_usage(&block, local_time, round, None),
+        )
+        .await?;
+        let executed_block = outcome.with(block);
 
         let mut response = ChainInfoResponse::new(&self.0.chain, None);
+        response.info.staged_resource_usage = resource_usage.operations;
+        response.info.staged_heap_usage = resource_usage.heap_usage;
         if let Some(signer) = signer {
             response.info.requested_owner_balance = self
                 .0
@@ -144,6 +236,9 @@ where
     }
 
     /// Validates a block proposed to extend this chain.
+    ///
+    /// The block is executed on a speculative copy of the chain state, so that nothing is
+    /// written to storage if the proposal is rejected.
     pub(super) async fn validate_proposal_content(
         &mut self,
         content: &ProposalContent,
@@ -152,6 +247,7 @@ where
             block,
             round,
             outcome,
+            estimates: _,
         } = content;
 
         let local_time = self.0.storage.clock().current_time();
@@ -162,14 +258,27 @@ where
         self.0.storage.clock().sleep_until(block.timestamp).await;
         let local_time = self.0.storage.clock().current_time();
 
-        let chain = &mut self.0.chain;
+        let mut chain = self
+            .0
+            .storage
+            .load_speculative_chain(self.0.chain_id())
+            .await?;
         chain
             .remove_bundles_from_inboxes(block.timestamp, &block.incoming_bundles)
             .await?;
         let outcome = if let Some(outcome) = outcome {
             outcome.clone()
         } else {
-            Box::pin(chain.execute_block(block, local_time, round.multi_leader(), None)).await?
+            let policy = chain.current_committee()?.1.policy().clone();
+            let (outcome, resource_usage) = Box::pin(chain.execute_block_with_resource_usage(
+                block,
+                local_time,
+                round.multi_leader(),
+                None,
+            ))
+            .await?;
+            content.check_resource_usage(&resource_usage.operations, &policy)?;
+            outcome
         };
 
         let executed_block = outcome.with(block.clone());
@@ -184,6 +293,8 @@ where
             .verify_counters(block, &executed_block.outcome)?;
         // Verify that the resulting chain would have no unconfirmed incoming messages.
         chain.validate_incoming_bundles().await?;
+        // Verify that the block receives the messages the chain scheduled to itself.
+        chain.validate_schedule_message_bundles(block).await?;
         Ok(Some((executed_block.outcome, local_time)))
     }
 
@@ -242,6 +353,7 @@ where
                 }
             }
 
+            IncomingBundle::sort_canonically(&mut messages);
             info.requested_pending_message_bundles = messages;
         }
         if let Some(range) = query.request_sent_certificate_hashes_in_range {
@@ -263,6 +375,14 @@ where
         if query.request_manager_values {
             info.manager.add_values(&chain.manager);
         }
+        if query.request_state_hash_breakdown {
+            ensure!(
+                self.0.config.serve_state_hash_breakdowns,
+                WorkerError::StateHashBreakdownsNotServed
+            );
+            let breakdown = chain.execution_state.state_hash_breakdown().await?;
+            info.requested_state_hash_breakdown = Some(Box::new(breakdown));
+        }
         Ok(ChainInfoResponse::new(info, self.0.config.key_pair()))
     }
 }
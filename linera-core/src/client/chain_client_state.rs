@@ -15,7 +15,7 @@ use linera_chain::data_types::ProposedBlock;
 use tokio::sync::Mutex;
 
 use super::ChainClientError;
-use crate::data_types::ChainInfo;
+use crate::{data_types::ChainInfo, updater::QuorumReport};
 
 /// The state of our interaction with a particular chain: how far we have synchronized it and
 /// whether we are currently attempting to propose a new block.
@@ -38,6 +38,10 @@ pub struct ChainClientState {
     /// been processed by (i.e. been proposed to) our own local chain manager yet.
     pending_blobs: BTreeMap<BlobId, Blob>,
 
+    /// Which validators misbehaved during the last communication with a quorum about this
+    /// chain.
+    last_quorum_report: QuorumReport,
+
     /// A mutex that is held whilst we are performing operations that should not be
     /// attempted by multiple clients at the same time.
     client_mutex: Arc<Mutex<()>>,
@@ -63,6 +67,7 @@ impl ChainClientState {
             next_block_height,
             pending_proposal: None,
             pending_blobs,
+            last_quorum_report: QuorumReport::default(),
             client_mutex: Arc::default(),
         };
         if let Some(block) = pending_block {
@@ -111,6 +116,14 @@ impl ChainClientState {
         &self.known_key_pairs
     }
 
+    pub fn last_quorum_report(&self) -> &QuorumReport {
+        &self.last_quorum_report
+    }
+
+    pub(super) fn set_last_quorum_report(&mut self, report: QuorumReport) {
+        self.last_quorum_report = report;
+    }
+
     /// Returns whether the given ownership includes anyone whose secret key we don't have.
     pub fn has_other_owners(&self, ownership: &ChainOwnership) -> bool {
         ownership
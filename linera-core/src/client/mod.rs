@@ -5,7 +5,7 @@
 use std::{
     collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet},
     convert::Infallible,
-    iter,
+    fmt, iter,
     num::NonZeroUsize,
     ops::{Deref, DerefMut},
     sync::{Arc, RwLock},
@@ -34,31 +34,31 @@ use linera_base::{
     ensure,
     hashed::Hashed,
     identifiers::{
-        Account, AccountOwner, ApplicationId, BlobId, BlobType, BytecodeId, ChainId, MessageId,
-        Owner, UserApplicationId,
+        Account, AccountOwner, ApplicationId, BlobId, BlobType, BytecodeId, ChainId, Destination,
+        MessageId, Owner, UserApplicationId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_chain::{
     data_types::{
-        BlockProposal, ChainAndHeight, ExecutedBlock, IncomingBundle, LiteVote, MessageAction,
-        ProposedBlock,
+        BlockProposal, ChainAndHeight, ChannelFullName, ExecutedBlock, IncomingBundle, LiteVote,
+        Medium, MessageAction, OperationOutcome, Origin, PendingMessage, ProposedBlock,
     },
     manager::LockingBlock,
     types::{
         CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate,
         LiteCertificate, Timeout, TimeoutCertificate, ValidatedBlock, ValidatedBlockCertificate,
     },
-    ChainError, ChainExecutionContext, ChainStateView,
+    ChainError, ChainExecutionContext, ChainStateView, InboxBundleState,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemOperation,
-        CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX,
+        AdminOperation, ApplicationAllowlist, OpenChainConfig, Recipient, SystemChannel,
+        SystemOperation, CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX,
     },
-    ExecutionError, Operation, Query, QueryOutcome, QueryResponse, SystemExecutionError,
-    SystemQuery, SystemResponse,
+    ExecutionError, Message, Operation, Query, QueryOutcome, QueryResponse, StateHashPart,
+    SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
 };
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::ViewError;
@@ -70,17 +70,23 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, instrument, warn, Instrument as _};
 
 use crate::{
+    chain_worker::StateSnapshotOptions,
     data_types::{
-        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome, RoundTimeout,
+        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome,
+        MessageState, MessageStatus, RoundTimeout,
     },
     local_node::{LocalNodeClient, LocalNodeError},
+    misbehavior::{Misbehavior, MisbehaviorLog, MisbehaviorReport},
     node::{
         CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
     notifier::ChannelNotifier,
     remote_node::RemoteNode,
-    updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
+    updater::{
+        communicate_with_quorum, CommunicateAction, CommunicationError, QuorumReport,
+        ValidatorHealth, ValidatorRequestOptions, ValidatorUpdater,
+    },
     worker::{Notification, ProcessableCertificate, Reason, WorkerError, WorkerState},
 };
 
@@ -162,6 +168,12 @@ where
     /// An additional delay, after reaching a quorum, to wait for additional validator signatures,
     /// as a fraction of time taken to reach quorum.
     grace_period: f64,
+    /// How requests to individual validators time out and are retried.
+    validator_requests: ValidatorRequestOptions,
+    /// The validators' circuit breakers, shared by all chain clients.
+    validator_health: ValidatorHealth,
+    /// Evidence of validator misbehavior, shared by all chain clients.
+    misbehavior: Arc<MisbehaviorLog>,
     /// Chains that should be tracked by the client.
     // TODO(#2412): Merge with set of chains the client is receiving notifications from validators
     tracked_chains: Arc<RwLock<HashSet<ChainId>>>,
@@ -190,6 +202,8 @@ impl<P, S: Storage + Clone> Client<P, S> {
         name: impl Into<String>,
         max_loaded_chains: NonZeroUsize,
         grace_period: f64,
+        validator_requests: ValidatorRequestOptions,
+        state_snapshots: StateSnapshotOptions,
     ) -> Self {
         let tracked_chains = Arc::new(RwLock::new(tracked_chains.into_iter().collect()));
         let state = WorkerState::new_for_client(
@@ -200,7 +214,8 @@ impl<P, S: Storage + Clone> Client<P, S> {
         )
         .with_long_lived_services(long_lived_services)
         .with_allow_inactive_chains(true)
-        .with_allow_messages_from_deprecated_epochs(true);
+        .with_allow_messages_from_deprecated_epochs(true)
+        .with_state_snapshots(state_snapshots);
         let local_node = LocalNodeClient::new(state);
 
         Self {
@@ -211,6 +226,9 @@ impl<P, S: Storage + Clone> Client<P, S> {
             message_policy: MessagePolicy::new(BlanketMessagePolicy::Accept, None),
             cross_chain_message_delivery,
             grace_period,
+            validator_requests,
+            validator_health: ValidatorHealth::default(),
+            misbehavior: Arc::default(),
             tracked_chains,
             notifier: Arc::new(ChannelNotifier::default()),
             storage,
@@ -230,6 +248,23 @@ impl<P, S: Storage + Clone> Client<P, S> {
         &self.local_node
     }
 
+    /// Returns the evidence of validator misbehavior collected so far.
+    #[instrument(level = "trace", skip(self))]
+    pub fn misbehavior_reports(&self) -> Vec<MisbehaviorReport> {
+        self.misbehavior.reports()
+    }
+
+    /// Removes and returns the evidence of validator misbehavior collected so far.
+    #[instrument(level = "trace", skip(self))]
+    pub fn take_misbehavior_reports(&self) -> Vec<MisbehaviorReport> {
+        self.misbehavior.take_reports()
+    }
+
+    /// Returns the log where evidence of validator misbehavior is recorded.
+    pub fn misbehavior_log(&self) -> &Arc<MisbehaviorLog> {
+        &self.misbehavior
+    }
+
     /// Adds a chain to the set of chains tracked by the local node.
     #[instrument(level = "trace", skip(self))]
     pub fn track_chain(&self, chain_id: ChainId) {
@@ -272,9 +307,11 @@ impl<P, S: Storage + Clone> Client<P, S> {
             admin_id,
             options: ChainClientOptions {
                 max_pending_message_bundles: self.max_pending_message_bundles,
+                max_pending_messages: usize::MAX,
                 message_policy: self.message_policy.clone(),
                 cross_chain_message_delivery: self.cross_chain_message_delivery,
                 grace_period: self.grace_period,
+                validator_requests: self.validator_requests,
             },
         }
     }
@@ -361,9 +398,35 @@ where
         &self,
         remote_node: &RemoteNode<impl ValidatorNode>,
         chain_id: ChainId,
-        certificates: Vec<ConfirmedBlockCertificate>,
+        mut certificates: Vec<ConfirmedBlockCertificate>,
     ) -> Option<Box<ChainInfo>> {
         let mut info = None;
+        if certificates.len() > 1
+            && certificates
+                .iter()
+                .all(|certificate| certificate.block().header.chain_id == chain_id)
+        {
+            // Try to process all certificates at once. If that fails, continue one by one from
+            // the first certificate that could not be processed, e.g. to download its blobs.
+            match self
+                .local_node
+                .handle_confirmed_certificates(certificates.clone(), &self.notifier)
+                .await
+            {
+                Ok(response) => return Some(response.info),
+                Err(LocalNodeError::WorkerError(WorkerError::CertificateBatch {
+                    index,
+                    error,
+                })) => {
+                    debug!("Failed to process the batch from certificate {index} on: {error}");
+                    certificates.drain(..index);
+                    if index > 0 {
+                        info = self.local_node.chain_info(chain_id).await.ok();
+                    }
+                }
+                Err(error) => debug!("Failed to process a batch of certificates: {error}"),
+            }
+        }
         for certificate in certificates {
             let hash = certificate.hash();
             if certificate.block().header.chain_id != chain_id {
@@ -376,13 +439,26 @@ where
             if let Err(LocalNodeError::BlobsNotFound(blob_ids)) = &result {
                 if let Some(blobs) = remote_node.try_download_blobs(blob_ids).await {
                     let _ = self.local_node.store_blobs(&blobs).await;
-                    result = self.handle_certificate(certificate).await;
+                    result = self.handle_certificate(certificate.clone()).await;
                 }
             }
 
             match result {
                 Ok(response) => info = Some(response.info),
                 Err(error) => {
+                    if let LocalNodeError::WorkerError(WorkerError::IncorrectOutcome {
+                        computed,
+                        ..
+                    }) = &error
+                    {
+                        remote_node.misbehavior.report(
+                            remote_node.name,
+                            Misbehavior::IncorrectOutcome {
+                                certificate: Box::new(certificate),
+                                computed_state_hash: computed.state_hash,
+                            },
+                        );
+                    }
                     // The certificate is not as expected. Give up.
                     warn!("Failed to process network certificate {}: {}", hash, error);
                     return info;
@@ -393,6 +469,107 @@ where
         info
     }
 
+    /// Downloads the certificates of all the blocks of a chain, starting from its genesis,
+    /// from the given validators.
+    ///
+    /// The certificates are not checked: Use [`Client::verify_chain`] for that.
+    #[instrument(level = "trace", skip(self, validators))]
+    pub async fn download_chain_certificates(
+        &self,
+        validators: &[RemoteNode<impl ValidatorNode>],
+        chain_id: ChainId,
+    ) -> Result<Vec<ConfirmedBlockCertificate>, ChainClientError> {
+        let mut validators = validators.iter().collect::<Vec<_>>();
+        validators.shuffle(&mut rand::thread_rng());
+        let mut certificates = Vec::new();
+        // Each validator may know more blocks than the previous ones.
+        for remote_node in validators {
+            loop {
+                let start = BlockHeight::from(certificates.len() as u64);
+                let batch = match remote_node
+                    .try_query_certificates_from(chain_id, start, 1000)
+                    .await
+                {
+                    Ok(Some(batch)) if !batch.is_empty() => batch,
+                    Ok(_) => break,
+                    Err(error) => {
+                        warn!(
+                            "Failed to download certificates from validator {}: {}",
+                            remote_node.name, error
+                        );
+                        break;
+                    }
+                };
+                certificates.extend(batch);
+            }
+        }
+        Ok(certificates)
+    }
+
+    /// Verifies a chain from its genesis by processing the certificates of its blocks in
+    /// order: The signatures are checked against the committees trusted by the chain at each
+    /// height, and every block is executed again and compared with its certified outcome,
+    /// including the resulting state hash.
+    ///
+    /// The local node must not know any block of the chain yet, e.g. because its storage was
+    /// only initialized with the genesis configuration. Missing blobs, such as the bytecodes
+    /// of applications published on other chains, are downloaded from the `validators`.
+    /// `progress` is called after each verified certificate. Returns the information about
+    /// the chain after the last block.
+    #[instrument(level = "trace", skip(self, validators, certificates, progress))]
+    pub async fn verify_chain(
+        &self,
+        validators: &[RemoteNode<impl ValidatorNode>],
+        chain_id: ChainId,
+        certificates: impl IntoIterator<Item = ConfirmedBlockCertificate>,
+        mut progress: impl FnMut(&ConfirmedBlockCertificate),
+    ) -> Result<Box<ChainInfo>, ChainClientError> {
+        let mut info = self.local_node.chain_info(chain_id).await?;
+        ensure!(
+            info.next_block_height == BlockHeight::ZERO,
+            ChainClientError::InternalError("The local node already knows blocks of the chain")
+        );
+        for certificate in certificates {
+            let hash = certificate.hash();
+            let header = &certificate.block().header;
+            let height = header.height;
+            let verification_failed = |reason: String| ChainClientError::ChainVerificationFailed {
+                chain_id,
+                height,
+                hash,
+                reason,
+            };
+            if header.chain_id != chain_id {
+                return Err(verification_failed(format!(
+                    "the certificate is for chain {}",
+                    header.chain_id
+                )));
+            }
+            if height != info.next_block_height {
+                return Err(verification_failed(format!(
+                    "expected the block at height {}",
+                    info.next_block_height
+                )));
+            }
+            let mut result = self.handle_certificate(certificate.clone()).await;
+            if let Err(LocalNodeError::BlobsNotFound(blob_ids)) = &result {
+                let blob_ids = blob_ids.clone();
+                for remote_node in validators {
+                    if let Some(blobs) = remote_node.try_download_blobs(&blob_ids).await {
+                        self.local_node.store_blobs(&blobs).await?;
+                        result = self.handle_certificate(certificate.clone()).await;
+                        break;
+                    }
+                }
+            }
+            info = result
+                .map_err(|error| verification_failed(error.to_string()))?
+                .info;
+            progress(&certificate);
+        }
+        Ok(info)
+    }
+
     async fn handle_certificate<T: ProcessableCertificate>(
         &self,
         certificate: GenericCertificate<T>,
@@ -412,6 +589,105 @@ pub struct MessagePolicy {
     /// accepted. `Option::None` means that messages from all chains are accepted. An empty
     /// `HashSet` denotes that messages from no chains are accepted.
     restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    /// Whether incoming messages that fail to execute are parked in the chain's dead-letter
+    /// queue instead of being rejected.
+    park_failing_messages: bool,
+}
+
+/// How the execution state of a chain on a validator compares with the one in the local node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateHashComparison {
+    /// The hashes of all the parts of the state are the same.
+    Match,
+    /// The hashes of these parts of the state differ.
+    Diverged(Vec<StateHashPart>),
+    /// The validator's chain is at another block height, with this next block height.
+    OtherHeight(BlockHeight),
+    /// The validator did not provide the hashes of the parts of its state.
+    Unavailable(String),
+}
+
+/// How serious a problem found in a block proposal is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProposalIssueSeverity {
+    /// The block may be rejected, but the local node cannot tell for sure.
+    Warning,
+    /// The block will be rejected.
+    Error,
+}
+
+impl fmt::Display for ProposalIssueSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposalIssueSeverity::Warning => write!(f, "warning"),
+            ProposalIssueSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in a block proposal before signing it.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ProposalIssue {
+    #[error("operation {operation_index} is for the unknown application {application_id}")]
+    UnknownApplication {
+        operation_index: usize,
+        application_id: UserApplicationId,
+    },
+    #[error(
+        "the transfers from {} need {required}, but only {available} is available",
+        .owner.map_or_else(|| "the chain".to_owned(), |owner| owner.to_string())
+    )]
+    InsufficientBalance {
+        owner: Option<AccountOwner>,
+        available: Amount,
+        required: Amount,
+    },
+    #[error(
+        "the bundle of transaction {transaction_index} at height {height} from {origin:?} \
+        is received out of order, or was already received"
+    )]
+    MessageOutOfOrder {
+        origin: Origin,
+        height: BlockHeight,
+        transaction_index: u32,
+    },
+    #[error(
+        "the bundle of transaction {transaction_index} at height {height} from {origin:?} \
+        cannot be skipped"
+    )]
+    MessageSkipped {
+        origin: Origin,
+        height: BlockHeight,
+        transaction_index: u32,
+    },
+    #[error(
+        "the bundle of transaction {transaction_index} at height {height} from {origin:?} \
+        has not been received by the local node"
+    )]
+    MessageNotReceived {
+        origin: Origin,
+        height: BlockHeight,
+        transaction_index: u32,
+    },
+    #[error("the proposal has {size} bytes, more than the maximum of {maximum}")]
+    ProposalTooLarge { size: usize, maximum: u64 },
+    #[error("the block fails to execute: {error}")]
+    ExecutionFailed { error: String },
+}
+
+impl ProposalIssue {
+    /// Returns how serious this issue is.
+    pub fn severity(&self) -> ProposalIssueSeverity {
+        match self {
+            ProposalIssue::MessageNotReceived { .. } => ProposalIssueSeverity::Warning,
+            ProposalIssue::UnknownApplication { .. }
+            | ProposalIssue::InsufficientBalance { .. }
+            | ProposalIssue::MessageOutOfOrder { .. }
+            | ProposalIssue::MessageSkipped { .. }
+            | ProposalIssue::ProposalTooLarge { .. }
+            | ProposalIssue::ExecutionFailed { .. } => ProposalIssueSeverity::Error,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, clap::ValueEnum)]
@@ -434,9 +710,19 @@ impl MessagePolicy {
         Self {
             blanket,
             restrict_chain_ids_to,
+            park_failing_messages: false,
         }
     }
 
+    /// Returns this policy with parking of failing messages enabled or disabled.
+    ///
+    /// Parked messages can be retried later with [`SystemOperation::RetryDeadLetter`]. Like
+    /// rejection, this never applies to protected messages.
+    pub fn with_park_failing_messages(mut self, park_failing_messages: bool) -> Self {
+        self.park_failing_messages = park_failing_messages;
+        self
+    }
+
     #[instrument(level = "trace", skip(self))]
     fn must_handle(&self, bundle: &mut IncomingBundle) -> bool {
         if self.is_reject() {
@@ -469,6 +755,9 @@ impl MessagePolicy {
 pub struct ChainClientOptions {
     /// Maximum number of pending message bundles processed at a time in a block.
     pub max_pending_message_bundles: usize,
+    /// Maximum number of incoming messages, across all bundles, processed at a time in a block.
+    /// The first bundle is always included, so that the inbox is never stuck.
+    pub max_pending_messages: usize,
     /// The policy for automatically handling incoming messages.
     pub message_policy: MessagePolicy,
     /// Whether to block on cross-chain message delivery.
@@ -476,6 +765,8 @@ pub struct ChainClientOptions {
     /// An additional delay, after reaching a quorum, to wait for additional validator signatures,
     /// as a fraction of time taken to reach quorum.
     pub grace_period: f64,
+    /// How requests to individual validators time out and are retried.
+    pub validator_requests: ValidatorRequestOptions,
 }
 
 /// Client to operate a chain by interacting with validators and the given local storage
@@ -577,6 +868,27 @@ pub enum ChainClientError {
         chain_id: ChainId,
         target_next_block_height: BlockHeight,
     },
+
+    #[error("Block {certificate_hash} has no message with index {index} sent to chain {chain_id}")]
+    MessageNotFound {
+        certificate_hash: CryptoHash,
+        index: u32,
+        chain_id: ChainId,
+    },
+
+    #[error("Failed to verify block {height} of chain {chain_id} (certificate {hash}): {reason}")]
+    ChainVerificationFailed {
+        chain_id: ChainId,
+        height: BlockHeight,
+        hash: CryptoHash,
+        reason: String,
+    },
+
+    #[error("Operation {index} of the block failed: {error}")]
+    OperationFailed {
+        index: usize,
+        error: Box<ExecutionError>,
+    },
 }
 
 impl From<Infallible> for ChainClientError {
@@ -585,6 +897,32 @@ impl From<Infallible> for ChainClientError {
     }
 }
 
+impl ChainClientError {
+    /// Converts an execution error of the transaction `first_operation + index` into an
+    /// [`ChainClientError::OperationFailed`] error for the `index`th operation.
+    fn with_operation_index(self, first_operation: usize) -> Self {
+        let ChainClientError::LocalNodeError(LocalNodeError::WorkerError(WorkerError::ChainError(
+            chain_error,
+        ))) = self
+        else {
+            return self;
+        };
+        match *chain_error {
+            ChainError::ExecutionError(error, ChainExecutionContext::Operation(transaction))
+                if transaction as usize >= first_operation =>
+            {
+                ChainClientError::OperationFailed {
+                    index: transaction as usize - first_operation,
+                    error,
+                }
+            }
+            chain_error => {
+                LocalNodeError::WorkerError(WorkerError::ChainError(Box::new(chain_error))).into()
+            }
+        }
+    }
+}
+
 // We never want to pass the DashMap references over an `await` point, for fear of
 // deadlocks. The following construct will cause a (relatively) helpful error if we do.
 
@@ -649,6 +987,26 @@ impl<P: 'static, S: Storage> ChainClient<P, S> {
         &mut self.options
     }
 
+    /// Returns which validators misbehaved during the last communication with a quorum about
+    /// this chain.
+    #[instrument(level = "trace", skip(self))]
+    pub fn last_quorum_report(&self) -> QuorumReport {
+        self.state().last_quorum_report().clone()
+    }
+
+    /// Returns the evidence of validator misbehavior collected so far by the client.
+    #[instrument(level = "trace", skip(self))]
+    pub fn misbehavior_reports(&self) -> Vec<MisbehaviorReport> {
+        self.client.misbehavior_reports()
+    }
+
+    /// Removes and returns the evidence of validator misbehavior collected so far by the
+    /// client.
+    #[instrument(level = "trace", skip(self))]
+    pub fn take_misbehavior_reports(&self) -> Vec<MisbehaviorReport> {
+        self.client.take_misbehavior_reports()
+    }
+
     /// Gets the ID of the associated chain.
     #[instrument(level = "trace", skip(self))]
     pub fn chain_id(&self) -> ChainId {
@@ -734,12 +1092,160 @@ where
         )))
     }
 
+    /// Subscribes to notifications from this client's chain, only keeping the latest pending
+    /// notification of each kind if the subscriber falls behind.
+    ///
+    /// Unlike [`ChainClient::subscribe`], this never buffers more than a bounded number of
+    /// notifications, but intermediate blocks or rounds may not be notified.
+    #[instrument(level = "trace")]
+    pub async fn subscribe_coalesced(&self) -> Result<NotificationStream, LocalNodeError> {
+        let receiver = self
+            .client
+            .notifier
+            .subscribe_coalesced(vec![self.chain_id]);
+        Ok(Box::pin(stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                let notification = receiver.recv().await?;
+                Some((notification, receiver))
+            },
+        )))
+    }
+
     /// Returns the storage client used by this client's local node.
     #[instrument(level = "trace")]
     pub fn storage_client(&self) -> S {
         self.client.storage_client().clone()
     }
 
+    /// Returns the number of requests waiting for the chain workers of the local node.
+    #[instrument(level = "trace")]
+    pub fn pending_requests(&self) -> usize {
+        self.client.local_node.pending_requests()
+    }
+
+    /// Returns the messages received by this chain but not executed yet, without executing
+    /// anything.
+    ///
+    /// Only the local node is queried, so messages not synchronized from the validators yet
+    /// are missing.
+    #[instrument(level = "trace")]
+    pub async fn pending_messages(&self) -> Result<Vec<PendingMessage>, ChainClientError> {
+        let chain = self.chain_state_view().await?;
+        Ok(chain.pending_messages().await?)
+    }
+
+    /// Returns the status of the `index`th message sent in the block with the given
+    /// certificate hash, as seen by this chain, which must be one of its recipients.
+    ///
+    /// Only the local node is queried, so the status may lag behind the validators'.
+    #[instrument(level = "trace")]
+    pub async fn message_status(
+        &self,
+        certificate_hash: CryptoHash,
+        index: u32,
+    ) -> Result<MessageStatus, ChainClientError> {
+        let storage = self.storage_client();
+        let certificate = storage.read_certificate(certificate_hash).await?;
+        let block = certificate.block();
+        let not_found = || ChainClientError::MessageNotFound {
+            certificate_hash,
+            index,
+            chain_id: self.chain_id,
+        };
+        let (transaction_index, message) = block
+            .message_and_transaction_index(index)
+            .ok_or_else(not_found)?;
+        let medium = match &message.destination {
+            Destination::Recipient(_) => Medium::Direct,
+            Destination::Subscribers(name) => Medium::Channel(ChannelFullName {
+                application_id: message.message.application_id(),
+                name: name.clone(),
+            }),
+        };
+        ensure!(message.has_destination(&medium, self.chain_id), not_found());
+        let origin = Origin {
+            sender: block.header.chain_id,
+            medium,
+        };
+
+        let message_id = MessageId {
+            chain_id: block.header.chain_id,
+            height: block.header.height,
+            index,
+        };
+        let (removal_height, hash, rejected_by_owner) = {
+            let chain = self.chain_state_view().await?;
+            let state = chain
+                .inbox_bundle_state(&origin, block.header.height, transaction_index)
+                .await?;
+            match state {
+                InboxBundleState::NotReceived => {
+                    return Ok(MessageStatus {
+                        state: MessageState::NotReceived,
+                        height: None,
+                    })
+                }
+                InboxBundleState::Pending => {
+                    return Ok(MessageStatus {
+                        state: MessageState::Pending,
+                        height: None,
+                    })
+                }
+                InboxBundleState::Removed => {}
+            }
+            let removal_height = chain
+                .bundle_removal_height(&origin, block.header.height, transaction_index)
+                .await?;
+            let hash = match removal_height {
+                Some(height) => chain.confirmed_log.get(usize::try_from(height)?).await?,
+                None => None,
+            };
+            let rejected_by_owner = chain
+                .messages_rejected_by_owner
+                .contains(&message_id)
+                .await?;
+            (removal_height, hash, rejected_by_owner)
+        };
+        // The heights of the bundles removed before they were recorded are not known.
+        let (Some(height), Some(hash)) = (removal_height, hash) else {
+            return Ok(MessageStatus {
+                state: MessageState::Executed,
+                height: None,
+            });
+        };
+
+        let receiving_certificate = storage.read_certificate(hash).await?;
+        let action = receiving_certificate
+            .block()
+            .body
+            .incoming_bundles
+            .iter()
+            .find(|bundle| {
+                bundle.origin == origin
+                    && bundle.bundle.certificate_hash == certificate_hash
+                    && bundle.bundle.transaction_index == transaction_index
+            })
+            .map(|bundle| bundle.action);
+        let state = match action {
+            Some(MessageAction::Reject) => MessageState::Rejected,
+            Some(_) if rejected_by_owner => MessageState::Rejected,
+            Some(MessageAction::Accept) => MessageState::Executed,
+            Some(MessageAction::Park) => MessageState::Parked,
+            // The bundle was skipped when the block removed a later one from the same inbox.
+            None => {
+                return Ok(MessageStatus {
+                    state: MessageState::Rejected,
+                    height: None,
+                })
+            }
+        };
+        Ok(MessageStatus {
+            state,
+            height: Some(height),
+        })
+    }
+
     /// Obtains the basic `ChainInfo` data for the local chain.
     #[instrument(level = "trace")]
     pub async fn chain_info(&self) -> Result<Box<ChainInfo>, LocalNodeError> {
@@ -753,6 +1259,25 @@ where
         Ok(response.info)
     }
 
+    /// Returns how many blocks of the chain the local node is missing, compared to the most
+    /// advanced of the validators trusted by the chain that answered.
+    #[instrument(level = "trace")]
+    pub async fn blocks_behind_validators(&self) -> Result<u64, ChainClientError> {
+        let local_height = self.chain_info().await?.next_block_height;
+        let validators = self.validator_nodes().await?;
+        let query = ChainInfoQuery::new(self.chain_id);
+        let heights = future::join_all(validators.iter().map(|remote_node| {
+            let query = query.clone();
+            async move {
+                let info = remote_node.handle_chain_info_query(query).await.ok()?;
+                Some(info.next_block_height)
+            }
+        }))
+        .await;
+        let highest_height = heights.into_iter().flatten().max().unwrap_or(local_height);
+        Ok(highest_height.0.saturating_sub(local_height.0))
+    }
+
     /// Obtains the basic `ChainInfo` data for the local chain, with chain manager values.
     #[instrument(level = "trace")]
     pub async fn chain_info_with_manager_values(&self) -> Result<Box<ChainInfo>, LocalNodeError> {
@@ -766,11 +1291,26 @@ where
         Ok(response.info)
     }
 
+    /// Returns the number of incoming message bundles waiting in the local inbox of this chain.
+    #[instrument(level = "trace")]
+    pub async fn pending_message_bundle_count(&self) -> Result<usize, LocalNodeError> {
+        let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
+        let response = self
+            .client
+            .local_node
+            .handle_chain_info_query(query)
+            .await?;
+        Ok(response.info.requested_pending_message_bundles.len())
+    }
+
     /// Obtains up to `self.options.max_pending_message_bundles` pending message bundles for the
-    /// local chain.
+    /// local chain, in the canonical order of [`IncomingBundle::sort_canonically`], so that the
+    /// proposed block only depends on the contents of the inbox.
     #[instrument(level = "trace")]
     async fn pending_message_bundles(&self) -> Result<Vec<IncomingBundle>, ChainClientError> {
-        let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
+        let query = ChainInfoQuery::new(self.chain_id)
+            .with_pending_message_bundles()
+            .with_committees();
         let info = self
             .client
             .local_node
@@ -789,8 +1329,26 @@ where
             return Ok(Vec::new()); // OpenChain is already received, others are ignored.
         }
 
+        // Blocks can't contain more bundles than the current committee allows, in total or from
+        // any single origin. The remaining bundles are left for the next blocks.
+        let policy = info
+            .epoch
+            .and_then(|epoch| info.requested_committees.as_ref()?.get(&epoch))
+            .map(|committee| committee.policy());
+        let maximum_bundles = policy
+            .map_or(usize::MAX, |policy| {
+                let maximum = policy.maximum_incoming_bundles_per_block;
+                usize::try_from(maximum).unwrap_or(usize::MAX)
+            })
+            .min(self.options.max_pending_message_bundles);
+        let maximum_bundles_per_origin = policy.map_or(usize::MAX, |policy| {
+            let maximum = policy.maximum_incoming_bundles_per_origin;
+            usize::try_from(maximum).unwrap_or(usize::MAX)
+        });
+
         let mut rearranged = false;
         let mut pending_message_bundles = info.requested_pending_message_bundles;
+        IncomingBundle::sort_canonically(&mut pending_message_bundles);
 
         // The first incoming message of any child chain must be `OpenChain`. We must have it in
         // our inbox, and include it before all other messages.
@@ -815,6 +1373,9 @@ where
             }
         }
 
+        // The bundles from each origin are in order, so this keeps the oldest ones.
+        let mut bundles_per_origin = HashMap::<Origin, usize>::new();
+        let mut message_count = 0;
         Ok(pending_message_bundles
             .into_iter()
             .filter_map(|mut bundle| {
@@ -823,7 +1384,17 @@ where
                     .must_handle(&mut bundle)
                     .then_some(bundle)
             })
-            .take(self.options.max_pending_message_bundles)
+            .filter(|bundle| {
+                let count = bundles_per_origin.entry(bundle.origin.clone()).or_default();
+                *count += 1;
+                *count <= maximum_bundles_per_origin
+            })
+            .take(maximum_bundles)
+            .take_while(|bundle| {
+                let is_first = message_count == 0;
+                message_count += bundle.bundle.messages.len();
+                is_first || message_count <= self.options.max_pending_messages
+            })
             .collect())
     }
 
@@ -880,13 +1451,27 @@ where
         Ok((committees, epoch))
     }
 
+    /// Obtains the committee of the latest epoch trusted by either the local chain or its admin
+    /// chain, together with that epoch.
+    #[instrument(level = "trace")]
+    async fn latest_committee(&self) -> Result<(Epoch, Committee), LocalNodeError> {
+        let (mut committees, _) = self.known_committees().await?;
+        committees
+            .pop_last()
+            .ok_or(LocalNodeError::InactiveChain(self.admin_id))
+    }
+
     #[instrument(level = "trace")]
     fn make_nodes(&self, committee: &Committee) -> Result<Vec<RemoteNode<P::Node>>, NodeError> {
         Ok(self
             .client
             .validator_node_provider
             .make_nodes(committee)?
-            .map(|(name, node)| RemoteNode { name, node })
+            .map(|(name, node)| RemoteNode {
+                name,
+                node,
+                misbehavior: self.client.misbehavior.clone(),
+            })
             .collect())
     }
 
@@ -1108,7 +1693,7 @@ where
         let n_validators = nodes.len();
         let chain_worker_count =
             std::cmp::max(1, self.client.max_loaded_chains.get() / n_validators);
-        communicate_with_quorum(
+        let (result, report) = communicate_with_quorum(
             &nodes,
             committee,
             |_: &()| (),
@@ -1125,8 +1710,12 @@ where
                 })
             },
             self.options.grace_period,
+            &self.options.validator_requests,
+            &self.client.validator_health,
         )
-        .await?;
+        .await;
+        self.state_mut().set_last_quorum_report(report);
+        result?;
         Ok(())
     }
 
@@ -1147,7 +1736,7 @@ where
         let n_validators = nodes.len();
         let chain_worker_count =
             std::cmp::max(1, self.client.max_loaded_chains.get() / n_validators);
-        let ((votes_hash, votes_round), votes) = communicate_with_quorum(
+        let (result, report) = communicate_with_quorum(
             &nodes,
             committee,
             |vote: &LiteVote| (vote.value.value_hash, vote.round),
@@ -1161,8 +1750,12 @@ where
                 Box::pin(async move { updater.send_chain_update(action).await })
             },
             self.options.grace_period,
+            &self.options.validator_requests,
+            &self.client.validator_health,
         )
-        .await?;
+        .await;
+        self.state_mut().set_last_quorum_report(report);
+        let ((votes_hash, votes_round), votes) = result?;
         ensure!(
             (votes_hash, votes_round) == (value.hash(), action.round()),
             ChainClientError::ProtocolError("Unexpected response from validators")
@@ -1334,6 +1927,7 @@ where
 
         // Check the signatures and keep only the ones that are valid.
         let mut certificates = Vec::new();
+        let mut has_future_epochs = false;
         for confirmed_block_certificate in remote_certificates {
             let block_header = &confirmed_block_certificate.inner().block().header;
             let sender_chain_id = block_header.chain_id;
@@ -1347,6 +1941,7 @@ where
                     );
                     // Do not process this certificate now. It can still be
                     // downloaded later, once our committee is updated.
+                    has_future_epochs = true;
                 }
                 CheckCertificateResult::OldEpoch => {
                     // This epoch is not recognized any more. Let's skip the certificate.
@@ -1382,6 +1977,7 @@ where
             tracker,
             certificates,
             other_sender_chains,
+            has_future_epochs,
         })
     }
 
@@ -1502,11 +2098,26 @@ where
     ///
     /// However, this should be the case whenever a sender's chain is still in use and
     /// is regularly upgraded to new committees.
+    ///
+    /// Certificates signed by a committee we don't know yet are checked again after
+    /// synchronizing the admin chain.
     #[instrument(level = "trace")]
     async fn find_received_certificates(&self) -> Result<(), ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::FIND_RECEIVED_CERTIFICATES_LATENCY.measure_latency();
 
+        if self.find_received_certificates_from_quorum().await? {
+            info!("Received certificates from a future epoch; synchronizing the admin chain");
+            self.synchronize_admin_chain().await?;
+            self.find_received_certificates_from_quorum().await?;
+        }
+        Ok(())
+    }
+
+    /// Downloads and processes new received certificates from a quorum of validators. Returns
+    /// whether some certificates were skipped because they are from an unknown future epoch.
+    #[instrument(level = "trace")]
+    async fn find_received_certificates_from_quorum(&self) -> Result<bool, ChainClientError> {
         // Use network information from the local chain.
         let chain_id = self.chain_id;
         let local_committee = self.local_committee().await?;
@@ -1516,7 +2127,7 @@ where
         // the tasks don't use more than the limit in total.
         let chain_worker_limit =
             (self.client.max_loaded_chains.get() / local_committee.validators().len()).max(1);
-        let result = communicate_with_quorum(
+        let (result, report) = communicate_with_quorum(
             &nodes,
             &local_committee,
             |_| (),
@@ -1533,22 +2144,28 @@ where
                 })
             },
             self.options.grace_period,
+            &self.options.validator_requests,
+            &self.client.validator_health,
         )
         .await;
+        self.state_mut().set_last_quorum_report(report);
         let received_certificate_batches = match result {
             Ok(((), received_certificate_batches)) => received_certificate_batches,
             Err(CommunicationError::Trusted(NodeError::InactiveChain(id))) if id == chain_id => {
                 // The chain is visibly not active (yet or any more) so there is no need
                 // to synchronize received certificates.
-                return Ok(());
+                return Ok(false);
             }
             Err(error) => {
                 return Err(error.into());
             }
         };
+        let has_future_epochs = received_certificate_batches
+            .iter()
+            .any(|batch| batch.has_future_epochs);
         self.receive_certificates_from_validators(received_certificate_batches)
             .await;
-        Ok(())
+        Ok(has_future_epochs)
     }
 
     /// Sends money.
@@ -1667,14 +2284,27 @@ where
         &self,
         validators: &[RemoteNode<P::Node>],
         chain_id: ChainId,
+    ) -> Result<Box<ChainInfo>, ChainClientError> {
+        let committee = self.local_committee().await?;
+        self.synchronize_chain_state_with_committee(validators, &committee, chain_id)
+            .await
+    }
+
+    /// Downloads and processes any certificates we are missing for the given chain, until a
+    /// quorum of the given committee was queried.
+    #[instrument(level = "trace", skip_all)]
+    async fn synchronize_chain_state_with_committee(
+        &self,
+        validators: &[RemoteNode<P::Node>],
+        committee: &Committee,
+        chain_id: ChainId,
     ) -> Result<Box<ChainInfo>, ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::SYNCHRONIZE_CHAIN_STATE_LATENCY.measure_latency();
 
-        let committee = self.local_committee().await?;
-        communicate_with_quorum(
+        let (result, report) = communicate_with_quorum(
             validators,
-            &committee,
+            committee,
             |_: &()| (),
             |remote_node| {
                 let client = self.clone();
@@ -1685,8 +2315,12 @@ where
                 }
             },
             self.options.grace_period,
+            &self.options.validator_requests,
+            &self.client.validator_health,
         )
-        .await?;
+        .await;
+        self.state_mut().set_last_quorum_report(report);
+        result?;
 
         self.client
             .local_node
@@ -1841,6 +2475,59 @@ where
         }
     }
 
+    /// Downloads the certificate of the block that created an application, and makes the
+    /// application's description known to the local node.
+    ///
+    /// Only that one block is downloaded, not the rest of its chain, and its certificate is
+    /// checked against the committees known to this chain.
+    #[instrument(level = "trace")]
+    async fn download_application_description(
+        &self,
+        application_id: UserApplicationId,
+    ) -> Result<(), ChainClientError> {
+        let creation = application_id.creation;
+        let (committees, _) = self.known_committees().await?;
+        let mut validators = self.validator_nodes().await?;
+        validators.shuffle(&mut rand::thread_rng());
+        for remote_node in &validators {
+            let certificate = match remote_node
+                .try_query_certificates_from(creation.chain_id, creation.height, 1)
+                .await
+            {
+                Ok(Some(certificates)) => certificates.into_iter().next(),
+                Ok(None) => None,
+                Err(error) => {
+                    warn!(
+                        "Failed to download a certificate from validator {}: {}",
+                        remote_node.name, error
+                    );
+                    None
+                }
+            };
+            let Some(certificate) = certificate else {
+                continue;
+            };
+            let block = certificate.block();
+            let Some(committee) = committees.get(&block.header.epoch) else {
+                continue;
+            };
+            if certificate.check(committee).is_err() {
+                continue;
+            }
+            if let Some(description) = block.created_application(&application_id) {
+                self.client
+                    .storage_client()
+                    .application_descriptions()
+                    .insert(application_id, description);
+                return Ok(());
+            }
+        }
+        Err(ChainClientError::CannotDownloadCertificates {
+            chain_id: creation.chain_id,
+            target_next_block_height: creation.height.try_add_one()?,
+        })
+    }
+
     /// Downloads and processes from the specified validator a confirmed block certificates that
     /// use the given blobs. If this succeeds, the blob will be in our storage.
     async fn update_local_node_with_blobs_from(
@@ -1915,8 +2602,12 @@ where
     }
 
     /// Attempts to execute the block locally. If any incoming message execution fails, that
-    /// message is rejected and execution is retried, until the block accepts only messages
-    /// that succeed.
+    /// message is rejected (or parked, depending on the message policy) and execution is
+    /// retried, until the block accepts only messages that succeed.
+    ///
+    /// Messages for an application unknown to this chain are retried once the block that
+    /// created it has been downloaded, so that the application can be registered
+    /// automatically. If that fails, they are parked.
     // TODO(#2806): Measure how failing messages affect the execution times.
     #[tracing::instrument(level = "trace", skip(block))]
     async fn stage_block_execution_and_discard_failing_messages(
@@ -1924,6 +2615,7 @@ where
         mut block: ProposedBlock,
         round: Option<u32>,
     ) -> Result<(ExecutedBlock, ChainInfoResponse), ChainClientError> {
+        let mut synchronized_applications = BTreeSet::new();
         loop {
             let result = self.stage_block_execution(block.clone(), round).await;
             if let Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
@@ -1935,12 +2627,36 @@ where
                     ChainExecutionContext::IncomingBundle(index),
                 ) = &**chain_error
                 {
+                    let unknown_application_id = error.unknown_application_id();
+                    if let Some(application_id) = unknown_application_id {
+                        // Download the application's description from the block that created
+                        // it, so that it can be registered on this chain, and retry.
+                        if synchronized_applications.insert(application_id) {
+                            match self.download_application_description(application_id).await {
+                                Ok(()) => continue,
+                                Err(error) => warn!(
+                                    %error, %application_id,
+                                    "Failed to download the description of an application"
+                                ),
+                            }
+                        }
+                    }
                     let message = block
                         .incoming_bundles
                         .get_mut(*index as usize)
                         .expect("Message at given index should exist");
                     if message.bundle.is_protected() {
                         error!("Protected incoming message failed to execute locally: {message:?}");
+                    } else if self.options.message_policy.park_failing_messages
+                        || unknown_application_id.is_some()
+                    {
+                        // Park the faulty message so that it can be retried later.
+                        info!(
+                            %error, origin = ?message.origin,
+                            "Message failed to execute locally and will be parked."
+                        );
+                        message.action = MessageAction::Park;
+                        continue;
                     } else {
                         // Reject the faulty message from the block and continue.
                         // TODO(#1420): This is potentially a bit heavy-handed for
@@ -1953,6 +2669,29 @@ where
                         continue;
                     }
                 }
+                if let ChainError::ExecutionError(error, ChainExecutionContext::Operation(index)) =
+                    &**chain_error
+                {
+                    // Transactions are numbered starting with the incoming bundles.
+                    let operation = block
+                        .operations
+                        .get_mut(*index as usize - block.incoming_bundles.len())
+                        .expect("Operation at given index should exist");
+                    if let Operation::System(SystemOperation::ExecuteScheduledMessage {
+                        message_id,
+                    }) = *operation
+                    {
+                        // Due scheduled messages must be handled, so park the faulty one
+                        // instead of executing it.
+                        info!(
+                            %error, ?message_id,
+                            "Scheduled message failed to execute locally and will be parked."
+                        );
+                        *operation =
+                            Operation::System(SystemOperation::ParkScheduledMessage { message_id });
+                        continue;
+                    }
+                }
             }
             return result;
         }
@@ -2049,6 +2788,25 @@ where
         }
     }
 
+    /// Executes a list of operations in a single block, and returns what each of them
+    /// produced.
+    ///
+    /// The block is atomic: if one of the operations fails, none of them is executed, and the
+    /// error is a [`ChainClientError::OperationFailed`] with the index of that operation.
+    #[instrument(level = "trace", skip(operations))]
+    pub async fn execute_operations_with_outcomes(
+        &self,
+        operations: Vec<Operation>,
+    ) -> Result<ClientOutcome<Vec<OperationOutcome>>, ChainClientError> {
+        let count = operations.len();
+        let outcome = self.execute_operations(operations).await?;
+        Ok(outcome.map(|certificate| {
+            // Scheduled operations may have been added in front of ours.
+            let mut outcomes = certificate.block().operation_outcomes();
+            outcomes.split_off(outcomes.len() - count)
+        }))
+    }
+
     /// Executes an operation.
     #[instrument(level = "trace", skip(operation))]
     pub async fn execute_operation(
@@ -2131,6 +2889,10 @@ where
                 self.next_timestamp(&incoming_bundles, state.timestamp()),
             )
         };
+        let mut operations = operations;
+        let scheduled_operations = self.due_scheduled_message_operations(timestamp).await?;
+        let first_operation = incoming_bundles.len() + scheduled_operations.len();
+        operations.splice(0..0, scheduled_operations);
         let block = ProposedBlock {
             epoch: self.epoch().await?,
             chain_id: self.chain_id,
@@ -2139,6 +2901,7 @@ where
             previous_block_hash,
             height,
             authenticated_signer: Some(identity),
+            fee_payer: None,
             timestamp,
         };
         // Make sure every incoming message succeeds and otherwise remove them.
@@ -2155,7 +2918,8 @@ where
         };
         let (executed_block, _) = self
             .stage_block_execution_and_discard_failing_messages(block, round)
-            .await?;
+            .await
+            .map_err(|error| error.with_operation_index(first_operation))?;
         let block = &executed_block.block;
         let committee = self.local_committee().await?;
         let max_size = committee.policy().maximum_block_proposal_size;
@@ -2164,6 +2928,36 @@ where
         Ok(Hashed::new(ConfirmedBlock::new(executed_block)))
     }
 
+    /// Returns the operations executing the scheduled messages that are due at `timestamp`.
+    ///
+    /// Blocks must execute these, so they are put in front of the other operations. If more are
+    /// due than a block can contain, the remaining ones are left for the next blocks.
+    #[instrument(level = "trace")]
+    async fn due_scheduled_message_operations(
+        &self,
+        timestamp: Timestamp,
+    ) -> Result<Vec<Operation>, ChainClientError> {
+        let maximum = self
+            .local_committee()
+            .await?
+            .policy()
+            .maximum_operations_per_block;
+        let message_ids = self
+            .chain_state_view()
+            .await?
+            .execution_state
+            .system
+            .due_scheduled_messages(timestamp)
+            .await?;
+        Ok(message_ids
+            .into_iter()
+            .take(usize::try_from(maximum).unwrap_or(usize::MAX))
+            .map(|message_id| {
+                Operation::System(SystemOperation::ExecuteScheduledMessage { message_id })
+            })
+            .collect())
+    }
+
     /// Returns a suitable timestamp for the next block.
     ///
     /// This will usually be the current time according to the local clock, but may be slightly
@@ -2184,12 +2978,19 @@ where
     }
 
     /// Queries an application.
+    ///
+    /// If a `height` is provided, the query is pinned to the chain state right before the block
+    /// at that height, and fails if the local node doesn't have that state.
     #[instrument(level = "trace", skip(query))]
-    pub async fn query_application(&self, query: Query) -> Result<QueryOutcome, ChainClientError> {
+    pub async fn query_application(
+        &self,
+        query: Query,
+        height: Option<BlockHeight>,
+    ) -> Result<QueryOutcome, ChainClientError> {
         let outcome = self
             .client
             .local_node
-            .query_application(self.chain_id, query)
+            .query_application(self.chain_id, query, height)
             .await?;
         Ok(outcome)
     }
@@ -2206,7 +3007,7 @@ where
         } = self
             .client
             .local_node
-            .query_application(self.chain_id, Query::System(query))
+            .query_application(self.chain_id, Query::System(query), None)
             .await?;
         match response {
             QueryResponse::System(response) => Ok(QueryOutcome {
@@ -2233,7 +3034,7 @@ where
         } = self
             .client
             .local_node
-            .query_application(self.chain_id, query)
+            .query_application(self.chain_id, query, None)
             .await?;
         match response {
             QueryResponse::User(response_bytes) => {
@@ -2299,17 +3100,19 @@ where
                 self.next_timestamp(&incoming_bundles, state.timestamp()),
             )
         };
+        let operations = self.due_scheduled_message_operations(timestamp).await?;
         let block = ProposedBlock {
             epoch: self.epoch().await?,
             chain_id: self.chain_id,
             incoming_bundles,
-            operations: Vec::new(),
+            operations,
             previous_block_hash,
             height,
             authenticated_signer: owner.and_then(|owner| match owner {
                 AccountOwner::User(user) => Some(user),
                 AccountOwner::Application(_) => None,
             }),
+            fee_payer: None,
             timestamp,
         };
         match self
@@ -2405,6 +3208,32 @@ where
         .await
     }
 
+    /// Executes an incoming message that was previously parked in this chain's dead-letter
+    /// queue.
+    #[instrument(level = "trace")]
+    pub async fn retry_dead_letter(
+        &self,
+        message_id: MessageId,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RetryDeadLetter {
+            message_id,
+        }))
+        .await
+    }
+
+    /// Rejects an incoming message that this chain has not executed yet, so that it is skipped
+    /// (and bounced if it is tracked) when its bundle is accepted.
+    #[instrument(level = "trace")]
+    pub async fn reject_message(
+        &self,
+        message_id: MessageId,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RejectMessage {
+            message_id,
+        }))
+        .await
+    }
+
     /// Sends tokens to a chain.
     #[instrument(level = "trace")]
     pub async fn transfer_to_account(
@@ -2436,15 +3265,244 @@ where
     pub async fn synchronize_from_validators(&self) -> Result<Box<ChainInfo>, ChainClientError> {
         if self.chain_id != self.admin_id {
             // Synchronize the state of the admin chain from the network.
-            let local_committee = self.local_committee().await?;
-            let nodes = self.make_nodes(&local_committee)?;
-            self.synchronize_chain_state(&nodes, self.admin_id).await?;
+            self.synchronize_admin_chain().await?;
         }
         let info = self.prepare_chain().await?;
         self.find_received_certificates().await?;
         Ok(info)
     }
 
+    /// Synchronizes the admin chain from the validators of the latest committee we know of.
+    ///
+    /// If this reveals new committees, we switch to the validators of the newest one and
+    /// synchronize again, until no new epoch is found. This way, we can follow a sequence of
+    /// committee changes even if the validators we started with have been removed since.
+    #[instrument(level = "trace")]
+    async fn synchronize_admin_chain(&self) -> Result<(), ChainClientError> {
+        let (mut epoch, mut committee) = self.latest_committee().await?;
+        loop {
+            let nodes = self.make_nodes(&committee)?;
+            self.synchronize_chain_state_with_committee(&nodes, &committee, self.admin_id)
+                .await?;
+            let (latest_epoch, latest_committee) = self.latest_committee().await?;
+            if latest_epoch <= epoch {
+                return Ok(());
+            }
+            info!("Found new committee at epoch {latest_epoch}; switching to its validators");
+            epoch = latest_epoch;
+            committee = latest_committee;
+        }
+    }
+
+    /// Checks a block proposal for this chain for problems that would make the validators
+    /// reject it, so that wallets can warn their users before signing it.
+    ///
+    /// The checks use the local node's view of the chain, which may be outdated, and don't
+    /// account for fees. If `dry_run` is set, the block is also executed in the local node,
+    /// without committing it.
+    #[instrument(level = "trace", skip(block))]
+    pub async fn validate_proposal(
+        &self,
+        block: &ProposedBlock,
+        dry_run: bool,
+    ) -> Result<Vec<ProposalIssue>, ChainClientError> {
+        let mut issues = Vec::new();
+        let maximum_size = self
+            .local_committee()
+            .await?
+            .policy()
+            .maximum_block_proposal_size;
+        match block.check_proposal_size(maximum_size) {
+            Ok(()) => {}
+            Err(ChainError::BlockProposalTooLarge { size, maximum }) => {
+                issues.push(ProposalIssue::ProposalTooLarge { size, maximum })
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        {
+            let chain = self.chain_state_view().await?;
+            let system = &chain.execution_state.system;
+
+            for (operation_index, operation) in block.operations.iter().enumerate() {
+                let Operation::User { application_id, .. } = operation else {
+                    continue;
+                };
+                // Applications created by this block are not registered yet.
+                let creation = application_id.creation;
+                if (creation.chain_id == block.chain_id && creation.height == block.height)
+                    || system
+                        .registry
+                        .known_applications
+                        .contains_key(application_id)
+                        .await?
+                {
+                    continue;
+                }
+                issues.push(ProposalIssue::UnknownApplication {
+                    operation_index,
+                    application_id: *application_id,
+                });
+            }
+
+            let mut credits = BTreeMap::<Option<AccountOwner>, Amount>::new();
+            for bundle in &block.incoming_bundles {
+                if bundle.action != MessageAction::Accept {
+                    continue;
+                }
+                for posted_message in &bundle.bundle.messages {
+                    if let Message::System(SystemMessage::Credit { target, amount, .. }) =
+                        &posted_message.message
+                    {
+                        credits
+                            .entry(*target)
+                            .or_insert(Amount::ZERO)
+                            .try_add_assign(*amount)?;
+                    }
+                }
+            }
+            let mut debits = BTreeMap::<Option<AccountOwner>, Amount>::new();
+            for operation in &block.operations {
+                if let Operation::System(SystemOperation::Transfer { owner, amount, .. }) =
+                    operation
+                {
+                    debits
+                        .entry(owner.map(AccountOwner::User))
+                        .or_insert(Amount::ZERO)
+                        .try_add_assign(*amount)?;
+                }
+            }
+            for (owner, required) in debits {
+                let balance = match &owner {
+                    None => *system.balance.get(),
+                    Some(owner) => system.balances.get(owner).await?.unwrap_or(Amount::ZERO),
+                };
+                let credit = credits.get(&owner).copied().unwrap_or(Amount::ZERO);
+                let available = balance.saturating_add(credit);
+                if required > available {
+                    issues.push(ProposalIssue::InsufficientBalance {
+                        owner,
+                        available,
+                        required,
+                    });
+                }
+            }
+
+            // Bundles from the same origin must be received in order, and can only be left
+            // out if they are skippable.
+            let mut highest_cursors = BTreeMap::<&Origin, (BlockHeight, u32)>::new();
+            for bundle in &block.incoming_bundles {
+                let origin = &bundle.origin;
+                let height = bundle.bundle.height;
+                let transaction_index = bundle.bundle.transaction_index;
+                let cursor = (height, transaction_index);
+                let previous = highest_cursors.get(origin).copied();
+                highest_cursors.insert(origin, previous.map_or(cursor, |p| p.max(cursor)));
+                let state = chain
+                    .inbox_bundle_state(origin, height, transaction_index)
+                    .await?;
+                if previous.is_some_and(|previous| previous >= cursor)
+                    || state == InboxBundleState::Removed
+                {
+                    issues.push(ProposalIssue::MessageOutOfOrder {
+                        origin: origin.clone(),
+                        height,
+                        transaction_index,
+                    });
+                } else if state == InboxBundleState::NotReceived {
+                    issues.push(ProposalIssue::MessageNotReceived {
+                        origin: origin.clone(),
+                        height,
+                        transaction_index,
+                    });
+                }
+            }
+            for (origin, highest_cursor) in highest_cursors {
+                let Some(inbox) = chain.inboxes.try_load_entry(origin).await? else {
+                    continue;
+                };
+                for pending in inbox.added_bundles.elements().await? {
+                    let cursor = (pending.height, pending.transaction_index);
+                    if cursor >= highest_cursor {
+                        break;
+                    }
+                    let is_included = block.incoming_bundles.iter().any(|bundle| {
+                        &bundle.origin == origin
+                            && (bundle.bundle.height, bundle.bundle.transaction_index) == cursor
+                    });
+                    if !is_included && !pending.is_skippable() {
+                        issues.push(ProposalIssue::MessageSkipped {
+                            origin: origin.clone(),
+                            height: pending.height,
+                            transaction_index: pending.transaction_index,
+                        });
+                    }
+                }
+            }
+        }
+
+        if dry_run {
+            match self.stage_block_execution(block.clone(), None).await {
+                Ok(_) => {}
+                Err(ChainClientError::LocalNodeError(error)) => {
+                    issues.push(ProposalIssue::ExecutionFailed {
+                        error: error.to_string(),
+                    })
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Compares the hashes of the parts of the chain's execution state in the local node
+    /// with the ones of each validator, to find out which applications or system fields
+    /// diverged when the validators disagree on a state hash.
+    ///
+    /// Validators only provide these hashes if their operators enabled it.
+    #[instrument(level = "trace")]
+    pub async fn compare_state_hashes(
+        &self,
+    ) -> Result<BTreeMap<ValidatorName, StateHashComparison>, ChainClientError> {
+        let (next_block_height, local_breakdown) = {
+            let chain = self.chain_state_view().await?;
+            let breakdown = chain.execution_state.state_hash_breakdown().await?;
+            (chain.tip_state.get().next_block_height, breakdown)
+        };
+        let nodes = self.validator_nodes().await?;
+        let query = ChainInfoQuery::new(self.chain_id).with_state_hash_breakdown();
+        let results = future::join_all(
+            nodes
+                .iter()
+                .map(|remote_node| remote_node.handle_chain_info_query(query.clone())),
+        )
+        .await;
+        let mut comparisons = BTreeMap::new();
+        for (remote_node, result) in nodes.iter().zip(results) {
+            let comparison = match result {
+                Err(error) => StateHashComparison::Unavailable(error.to_string()),
+                Ok(info) if info.next_block_height != next_block_height => {
+                    StateHashComparison::OtherHeight(info.next_block_height)
+                }
+                Ok(info) => match info.requested_state_hash_breakdown {
+                    None => StateHashComparison::Unavailable(
+                        "the state hash breakdown is missing".to_string(),
+                    ),
+                    Some(breakdown) => {
+                        let parts = local_breakdown.diverging_parts(&breakdown);
+                        if parts.is_empty() {
+                            StateHashComparison::Match
+                        } else {
+                            StateHashComparison::Diverged(parts)
+                        }
+                    }
+                },
+            };
+            comparisons.insert(remote_node.name, comparison);
+        }
+        Ok(comparisons)
+    }
+
     /// Processes the last pending block
     #[instrument(level = "trace")]
     pub async fn process_pending_block(
@@ -2470,13 +3528,19 @@ where
         let identity = self.identity().await?;
 
         // Otherwise we have to re-propose the highest validated block, if there is one.
+        // When staging the execution, also measure the resources used by each operation, to
+        // claim them in the proposal.
         let pending: Option<ProposedBlock> = self.state().pending_proposal().clone();
-        let executed_block = if let Some(locking) = &info.manager.requested_locking {
+        let (executed_block, estimates) = if let Some(locking) = &info.manager.requested_locking {
             match &**locking {
-                LockingBlock::Regular(certificate) => certificate.block().clone().into(),
+                LockingBlock::Regular(certificate) => {
+                    (certificate.block().clone().into(), Vec::new())
+                }
                 LockingBlock::Fast(proposal) => {
                     let block = proposal.content.block.clone();
-                    self.stage_block_execution(block, None).await?.0
+                    let (executed_block, response) =
+                        self.stage_block_execution(block, None).await?;
+                    (executed_block, response.info.staged_resource_usage)
                 }
             }
         } else if let Some(block) = pending {
@@ -2487,7 +3551,8 @@ where
                 Either::Left(round) => round.multi_leader(),
                 Either::Right(_) => None,
             };
-            self.stage_block_execution(block, round).await?.0
+            let (executed_block, response) = self.stage_block_execution(block, round).await?;
+            (executed_block, response.info.staged_resource_usage)
         } else {
             return Ok(ClientOutcome::Committed(None)); // Nothing to do.
         };
@@ -2510,13 +3575,18 @@ where
         let proposal = if let Some(locking) = info.manager.requested_locking {
             Box::new(match *locking {
                 LockingBlock::Regular(cert) => BlockProposal::new_retry(round, cert, &key_pair),
-                LockingBlock::Fast(proposal) => {
-                    BlockProposal::new_initial(round, proposal.content.block, &key_pair)
-                }
+                LockingBlock::Fast(proposal) => BlockProposal::new_initial_with_estimates(
+                    round,
+                    proposal.content.block,
+                    estimates,
+                    &key_pair,
+                ),
             })
         } else {
             let block = executed_block.block.clone();
-            Box::new(BlockProposal::new_initial(round, block, &key_pair))
+            Box::new(BlockProposal::new_initial_with_estimates(
+                round, block, estimates, &key_pair,
+            ))
         };
         if !already_handled_locally {
             // Check the final block proposal. This will be cheaper after #1401.
@@ -2777,6 +3847,16 @@ where
         self.execute_operation(operation.into()).await
     }
 
+    /// Restricts the applications that can execute on this chain, or lifts the restriction.
+    #[instrument(level = "trace", skip(allowlist))]
+    pub async fn change_application_allowlist(
+        &self,
+        allowlist: Option<ApplicationAllowlist>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let operation = SystemOperation::ChangeApplicationAllowlist(allowlist);
+        self.execute_operation(operation.into()).await
+    }
+
     /// Opens a new chain with a derived UID.
     #[instrument(level = "trace", skip(self))]
     pub async fn open_chain(
@@ -2928,6 +4008,7 @@ where
                 parameters,
                 instantiation_argument,
                 required_application_ids,
+                None,
             )
             .await?
             .map(|(app_id, cert)| (app_id.with_abi(), cert)))
@@ -2950,6 +4031,7 @@ where
         parameters: Vec<u8>,
         instantiation_argument: Vec<u8>,
         required_application_ids: Vec<UserApplicationId>,
+        max_query_response_bytes: Option<u64>,
     ) -> Result<ClientOutcome<(UserApplicationId, ConfirmedBlockCertificate)>, ChainClientError>
     {
         self.execute_operation(Operation::System(SystemOperation::CreateApplication {
@@ -2957,6 +4039,7 @@ where
             parameters,
             instantiation_argument,
             required_application_ids,
+            max_query_response_bytes,
         }))
         .await?
         .try_map(|certificate| {
@@ -3277,7 +4360,7 @@ where
 
         let mut senders = HashMap::new(); // Senders to cancel notification streams.
         let notifications = self.subscribe().await?;
-        let (abortable_notifications, abort) = stream::abortable(self.subscribe().await?);
+        let (abortable_notifications, abort) = stream::abortable(self.subscribe_coalesced().await?);
         if let Err(error) = self.synchronize_from_validators().await {
             error!("Failed to synchronize from validators: {}", error);
         }
@@ -3334,11 +4417,22 @@ where
     ) -> Result<impl Future<Output = ()>, ChainClientError> {
         let (chain_id, nodes, local_node) = {
             let committee = self.local_committee().await?;
-            let nodes: HashMap<_, _> = self
+            let mut nodes: HashMap<_, _> = self
                 .client
                 .validator_node_provider
                 .make_nodes(&committee)?
                 .collect();
+            // Also listen to the validators of a newer committee, if the admin chain already
+            // created one, so that we keep receiving notifications while the chain migrates.
+            if let Ok((_, latest_committee)) = self.latest_committee().await {
+                if latest_committee != committee {
+                    nodes.extend(
+                        self.client
+                            .validator_node_provider
+                            .make_nodes(&latest_committee)?,
+                    );
+                }
+            }
             (self.chain_id, nodes, self.client.local_node.clone())
         };
         // Drop removed validators.
@@ -3371,7 +4465,11 @@ where
             let mut stream = Box::pin(stream);
             let this = self.clone();
             let local_node = local_node.clone();
-            let remote_node = RemoteNode { name, node };
+            let remote_node = RemoteNode {
+                name,
+                node,
+                misbehavior: self.client.misbehavior.clone(),
+            };
             validator_tasks.push(async move {
                 while let Some(notification) = stream.next().await {
                     this.process_notification(
@@ -3390,7 +4488,7 @@ where
     /// Attempts to download new received certificates from a particular validator.
     ///
     /// This is similar to `find_received_certificates` but for only one validator.
-    /// We also don't try to synchronize the admin chain.
+    /// We only synchronize the admin chain if some certificates are from an unknown epoch.
     #[instrument(level = "trace")]
     async fn find_received_certificates_from_validator(
         &self,
@@ -3398,13 +4496,27 @@ where
     ) -> Result<(), ChainClientError> {
         let chain_id = self.chain_id;
         // Proceed to downloading received certificates.
-        let received_certificates = self
+        let mut received_certificates = self
             .synchronize_received_certificates_from_validator(
                 chain_id,
                 &remote_node,
                 self.client.max_loaded_chains.into(),
             )
             .await?;
+        if received_certificates.has_future_epochs {
+            // Learn about the new committees, then download and check the skipped
+            // certificates again.
+            self.receive_certificates_from_validators(vec![received_certificates])
+                .await;
+            self.synchronize_admin_chain().await?;
+            received_certificates = self
+                .synchronize_received_certificates_from_validator(
+                    chain_id,
+                    &remote_node,
+                    self.client.max_loaded_chains.into(),
+                )
+                .await?;
+        }
         // Process received certificates. If the client state has changed during the
         // network calls, we should still be fine.
         self.receive_certificates_from_validators(vec![received_certificates])
@@ -3464,4 +4576,7 @@ struct ReceivedCertificatesFromValidator {
     /// Sender chains that were already up to date locally. We need to ensure their messages
     /// are delivered.
     other_sender_chains: Vec<ChainId>,
+    /// Whether some certificates were skipped because they were signed by a committee of an
+    /// epoch that we don't know yet.
+    has_future_epochs: bool,
 }
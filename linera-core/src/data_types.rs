@@ -8,16 +8,16 @@ use custom_debug_derive::Debug;
 use linera_base::{
     crypto::{BcsSignable, CryptoError, CryptoHash, KeyPair, Signature},
     data_types::{Amount, BlockHeight, Round, Timestamp},
-    identifiers::{AccountOwner, ChainDescription, ChainId},
+    identifiers::{AccountOwner, ChainDescription, ChainId, UserApplicationId},
 };
 use linera_chain::{
-    data_types::{ChainAndHeight, IncomingBundle, Medium, MessageBundle},
+    data_types::{ChainAndHeight, IncomingBundle, Medium, MessageBundle, ResourceEstimate},
     manager::ChainManagerInfo,
     ChainStateView,
 };
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
-    ExecutionRuntimeContext,
+    ExecutionRuntimeContext, HeapUsage, StateHashBreakdown,
 };
 use linera_storage::ChainRuntimeContext;
 use linera_views::context::Context;
@@ -91,6 +91,9 @@ pub struct ChainInfoQuery {
     /// Include a vote to switch to fallback mode, if appropriate.
     #[debug(skip_if = Not::not)]
     pub request_fallback: bool,
+    /// Query the hashes of the parts of the execution state.
+    #[debug(skip_if = Not::not)]
+    pub request_state_hash_breakdown: bool,
 }
 
 impl ChainInfoQuery {
@@ -106,6 +109,7 @@ impl ChainInfoQuery {
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: false,
+            request_state_hash_breakdown: false,
         }
     }
 
@@ -153,6 +157,11 @@ impl ChainInfoQuery {
         self.request_fallback = true;
         self
     }
+
+    pub fn with_state_hash_breakdown(mut self) -> Self {
+        self.request_state_hash_breakdown = true;
+        self
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -186,7 +195,8 @@ pub struct ChainInfo {
     /// The current committees.
     #[debug(skip_if = Option::is_none)]
     pub requested_committees: Option<BTreeMap<Epoch, Committee>>,
-    /// The received messages that are waiting be picked in the next block (if requested).
+    /// The received messages that are waiting be picked in the next block (if requested), in
+    /// the order of `IncomingBundle::sort_canonically`.
     #[debug(skip_if = Vec::is_empty)]
     pub requested_pending_message_bundles: Vec<IncomingBundle>,
     /// The response to `request_sent_certificate_hashes_in_range`
@@ -197,6 +207,16 @@ pub struct ChainInfo {
     /// The response to `request_received_certificates_excluding_first_n`
     #[debug(skip_if = Vec::is_empty)]
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The hashes of the parts of the execution state (if requested).
+    #[debug(skip_if = Option::is_none)]
+    pub requested_state_hash_breakdown: Option<Box<StateHashBreakdown>>,
+    /// The resources used by each operation of the block whose execution was staged, if any.
+    #[debug(skip_if = Vec::is_empty)]
+    pub staged_resource_usage: Vec<ResourceEstimate>,
+    /// The heap usage reported by each profiled application in the block whose execution was
+    /// staged, if any.
+    #[debug(skip_if = BTreeMap::is_empty)]
+    pub staged_heap_usage: BTreeMap<UserApplicationId, HeapUsage>,
 }
 
 impl ChainInfo {
@@ -287,6 +307,9 @@ where
             requested_sent_certificate_hashes: Vec::new(),
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            requested_state_hash_breakdown: None,
+            staged_resource_usage: Vec::new(),
+            staged_heap_usage: BTreeMap::new(),
         }
     }
 }
@@ -370,3 +393,32 @@ impl<T> ClientOutcome<T> {
         }
     }
 }
+
+/// Whether a message has been received and executed by its recipient.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum MessageState {
+    /// The message has not been received by the recipient yet.
+    NotReceived,
+    /// The message is in the recipient's inbox, waiting to be executed.
+    Pending,
+    /// The message was executed by a block of the recipient.
+    Executed,
+    /// The message was rejected by a block of the recipient, or skipped without being
+    /// included in any block.
+    Rejected,
+    /// The message was stored in the recipient's dead-letter queue by a block, to be retried
+    /// later.
+    Parked,
+}
+
+/// The delivery status of a message, as seen by its recipient.
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, async_graphql::SimpleObject,
+)]
+pub struct MessageStatus {
+    /// Whether the message has been received and executed.
+    pub state: MessageState,
+    /// The height of the recipient's block that executed or rejected the message, if known.
+    #[debug(skip_if = Option::is_none)]
+    pub height: Option<BlockHeight>,
+}
@@ -12,6 +12,7 @@ pub mod client;
 pub mod data_types;
 pub mod join_set_ext;
 pub mod local_node;
+pub mod misbehavior;
 pub mod node;
 pub mod notifier;
 pub mod remote_node;
@@ -23,6 +24,9 @@ pub mod worker;
 pub(crate) mod updater;
 pub(crate) mod value_cache;
 
-pub use updater::DEFAULT_GRACE_PERIOD;
+pub use updater::{
+    QuorumReport, ValidatorFault, ValidatorRequestOptions, DEFAULT_GRACE_PERIOD,
+    DEFAULT_VALIDATOR_MAX_RETRIES, DEFAULT_VALIDATOR_RETRY_DELAY,
+};
 
 pub use crate::join_set_ext::{JoinSetExt, TaskHandle};
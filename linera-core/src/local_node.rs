@@ -133,6 +133,21 @@ where
         .await?)
     }
 
+    /// Processes a batch of confirmed certificates of consecutive blocks of the same chain.
+    #[instrument(level = "trace", skip_all)]
+    pub async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        notifier: &impl Notifier,
+    ) -> Result<ChainInfoResponse, LocalNodeError> {
+        Ok(Box::pin(
+            self.node
+                .state
+                .fully_handle_confirmed_certificates_with_notifications(certificates, notifier),
+        )
+        .await?)
+    }
+
     #[instrument(level = "trace", skip_all)]
     pub async fn handle_chain_info_query(
         &self,
@@ -154,6 +169,12 @@ where
             node: Arc::new(LocalNode { state }),
         }
     }
+
+    /// Returns the number of requests sent to the chain workers of the local node and not
+    /// answered yet.
+    pub fn pending_requests(&self) -> usize {
+        self.node.state.pending_requests()
+    }
 }
 
 impl<S> LocalNodeClient<S>
@@ -251,8 +272,13 @@ where
         &self,
         chain_id: ChainId,
         query: Query,
+        height: Option<BlockHeight>,
     ) -> Result<QueryOutcome, LocalNodeError> {
-        let outcome = self.node.state.query_application(chain_id, query).await?;
+        let outcome = self
+            .node
+            .state
+            .query_application(chain_id, query, height)
+            .await?;
         Ok(outcome)
     }
 
@@ -0,0 +1,185 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Evidence of validator misbehavior, collected by the client.
+
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{BlockHeight, Round, Timestamp},
+    identifiers::ChainId,
+};
+use linera_chain::{
+    data_types::LiteVote,
+    types::{CertificateKind, ConfirmedBlockCertificate},
+};
+use linera_execution::committee::ValidatorName;
+use serde::{Deserialize, Serialize};
+
+use crate::data_types::ChainInfoResponse;
+
+/// A validator response that failed verification, kept as evidence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct MisbehaviorReport {
+    /// The validator that sent the offending response.
+    pub validator: ValidatorName,
+    /// When the response was received, according to the client's clock.
+    pub timestamp: Timestamp,
+    /// What the validator did wrong, with the offending payload.
+    pub misbehavior: Misbehavior,
+}
+
+/// What a validator did wrong, with the offending payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub enum Misbehavior {
+    /// The validator signed votes for two different values at the same height and in the
+    /// same round of a chain.
+    ConflictingVotes {
+        height: BlockHeight,
+        first: LiteVote,
+        second: LiteVote,
+    },
+    /// The validator sent chain information with an invalid signature.
+    InvalidChainInfoSignature { response: ChainInfoResponse },
+    /// The validator sent a vote with an invalid signature.
+    InvalidVoteSignature { vote: LiteVote },
+    /// The validator sent a certificate whose execution outcome differs from ours, e.g. because
+    /// of its state hash. The certificate's signers all vouched for that outcome.
+    IncorrectOutcome {
+        certificate: Box<ConfirmedBlockCertificate>,
+        computed_state_hash: CryptoHash,
+    },
+}
+
+impl Misbehavior {
+    /// Returns the chain that the offending response was about.
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            Misbehavior::ConflictingVotes { first, .. } => first.value.chain_id,
+            Misbehavior::InvalidChainInfoSignature { response } => response.info.chain_id,
+            Misbehavior::InvalidVoteSignature { vote } => vote.value.chain_id,
+            Misbehavior::IncorrectOutcome { certificate, .. } => {
+                certificate.block().header.chain_id
+            }
+        }
+    }
+}
+
+impl fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Misbehavior::ConflictingVotes {
+                height,
+                first,
+                second,
+            } => write!(
+                f,
+                "Voted for both {} and {} in round {} at height {height} of chain {}",
+                first.value.value_hash, second.value.value_hash, first.round, first.value.chain_id,
+            ),
+            Misbehavior::InvalidChainInfoSignature { response } => write!(
+                f,
+                "Sent information about chain {} with an invalid signature",
+                response.info.chain_id
+            ),
+            Misbehavior::InvalidVoteSignature { vote } => write!(
+                f,
+                "Sent a vote for {} in round {} of chain {} with an invalid signature",
+                vote.value.value_hash, vote.round, vote.value.chain_id
+            ),
+            Misbehavior::IncorrectOutcome {
+                certificate,
+                computed_state_hash,
+            } => write!(
+                f,
+                "Sent certificate {} for height {} of chain {} with state hash {}, \
+                 but executing it results in {computed_state_hash}",
+                certificate.hash(),
+                certificate.block().header.height,
+                certificate.block().header.chain_id,
+                certificate.block().header.state_hash,
+            ),
+        }
+    }
+}
+
+/// Identifies the vote of a validator at a given height and in a given round of a chain.
+type VoteKey = (ValidatorName, ChainId, BlockHeight, Round, CertificateKind);
+
+/// The misbehavior reports collected so far, and the validators' recent votes, to detect
+/// conflicting ones.
+#[derive(Debug, Default)]
+pub struct MisbehaviorLog {
+    votes: Mutex<HashMap<VoteKey, LiteVote>>,
+    reports: Mutex<Vec<MisbehaviorReport>>,
+}
+
+impl MisbehaviorLog {
+    /// Returns the reports collected so far.
+    pub fn reports(&self) -> Vec<MisbehaviorReport> {
+        self.reports
+            .lock()
+            .expect("Panics should not happen while holding a lock to the reports")
+            .clone()
+    }
+
+    /// Removes and returns the reports collected so far.
+    pub fn take_reports(&self) -> Vec<MisbehaviorReport> {
+        std::mem::take(
+            &mut *self
+                .reports
+                .lock()
+                .expect("Panics should not happen while holding a lock to the reports"),
+        )
+    }
+
+    /// Records that `validator` sent an offending response.
+    pub(crate) fn report(&self, validator: ValidatorName, misbehavior: Misbehavior) {
+        tracing::warn!(%validator, %misbehavior, "Validator misbehaved");
+        self.reports
+            .lock()
+            .expect("Panics should not happen while holding a lock to the reports")
+            .push(MisbehaviorReport {
+                validator,
+                timestamp: Timestamp::now(),
+                misbehavior,
+            });
+    }
+
+    /// Remembers a vote with a valid signature for a block at `height`, and reports its
+    /// validator if they already voted for a different value in the same round.
+    ///
+    /// Only the votes for the latest height of each chain are kept.
+    pub(crate) fn record_vote(&self, height: BlockHeight, vote: &LiteVote) {
+        let chain_id = vote.value.chain_id;
+        let key = (vote.validator, chain_id, height, vote.round, vote.kind());
+        let first = {
+            let mut votes = self
+                .votes
+                .lock()
+                .expect("Panics should not happen while holding a lock to the votes");
+            votes.retain(|(_, other_chain_id, other_height, _, _), _| {
+                *other_chain_id != chain_id || *other_height >= height
+            });
+            match votes.get(&key) {
+                Some(first) if first.value.value_hash != vote.value.value_hash => first.clone(),
+                Some(_) => return,
+                None => {
+                    votes.insert(key, vote.clone());
+                    return;
+                }
+            }
+        };
+        self.report(
+            vote.validator,
+            Misbehavior::ConflictingVotes {
+                height,
+                first,
+                second: vote.clone(),
+            },
+        );
+    }
+}
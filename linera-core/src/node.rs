@@ -74,6 +74,13 @@ pub trait ValidatorNode {
         delivery: CrossChainMessageDelivery,
     ) -> Result<ChainInfoResponse, NodeError>;
 
+    /// Processes a batch of confirmed certificates of consecutive blocks of the same chain.
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<GenericCertificate<ConfirmedBlock>>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError>;
+
     /// Processes a validated certificate.
     async fn handle_validated_certificate(
         &self,
@@ -262,6 +269,15 @@ pub enum NodeError {
     EmptyBlobsNotFound,
     #[error("Local error handling validator response")]
     ResponseHandlingError { error: String },
+
+    #[error("Certificates from index {index} of the batch were not committed: {error}")]
+    CertificateBatchFailed { index: usize, error: Box<NodeError> },
+
+    #[error("The validator is throttling requests from this client; retry later")]
+    Throttled,
+
+    #[error("The request of {size} bytes exceeds the validator's limit of {max} bytes")]
+    RequestTooLarge { size: usize, max: usize },
 }
 
 impl From<tonic::Status> for NodeError {
@@ -352,6 +368,10 @@ impl From<WorkerError> for NodeError {
             WorkerError::ChainError(error) => (*error).into(),
             WorkerError::MissingCertificateValue => Self::MissingCertificateValue,
             WorkerError::BlobsNotFound(blob_ids) => Self::BlobsNotFound(blob_ids),
+            WorkerError::CertificateBatch { index, error } => Self::CertificateBatchFailed {
+                index,
+                error: Box::new((*error).into()),
+            },
             error => Self::WorkerError {
                 error: error.to_string(),
             },
@@ -1,14 +1,53 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
 
 use dashmap::DashMap;
 use linera_base::identifiers::ChainId;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tracing::trace;
-
-use crate::worker;
+use linera_chain::data_types::Origin;
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    Notify,
+};
+use tracing::{trace, warn};
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::{register_int_counter_vec, register_int_gauge_vec},
+    prometheus::{IntCounterVec, IntGaugeVec},
+};
+
+use crate::worker::{self, Reason};
+
+/// The maximum number of distinct notifications waiting in the mailbox of a coalescing
+/// subscription.
+pub const NOTIFICATION_MAILBOX_CAPACITY: usize = 1_000;
+
+/// The number of notifications waiting in the mailboxes of coalescing subscriptions.
+#[cfg(with_metrics)]
+static NOTIFICATION_MAILBOX_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "notification_mailbox_depth",
+        "Number of notifications waiting in the mailboxes of coalescing subscriptions",
+        &[],
+    )
+});
+
+/// The number of notifications dropped because the mailbox of a subscription was full.
+#[cfg(with_metrics)]
+static NOTIFICATION_MAILBOX_DROPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "notification_mailbox_dropped",
+        "Number of notifications dropped because the mailbox of a subscription was full",
+        &[],
+    )
+});
 
 // TODO(#2171): replace this with a Tokio broadcast channel
 
@@ -16,7 +55,19 @@ use crate::worker;
 /// from the validator.
 /// Clients will be evicted if their connections are terminated.
 pub struct ChannelNotifier<N> {
-    inner: DashMap<ChainId, Vec<UnboundedSender<N>>>,
+    inner: DashMap<ChainId, Vec<Arc<dyn Subscriber<N>>>>,
+}
+
+/// A client waiting to receive notifications.
+trait Subscriber<N>: Send + Sync {
+    /// Delivers the `notification`, or returns `false` if the client is gone.
+    fn deliver(&self, notification: N) -> bool;
+}
+
+impl<N: Send> Subscriber<N> for UnboundedSender<N> {
+    fn deliver(&self, notification: N) -> bool {
+        self.send(notification).is_ok()
+    }
 }
 
 impl<N> Default for ChannelNotifier<N> {
@@ -27,8 +78,8 @@ impl<N> Default for ChannelNotifier<N> {
     }
 }
 
-impl<N> ChannelNotifier<N> {
-    fn add_sender(&self, chain_ids: Vec<ChainId>, sender: &UnboundedSender<N>) {
+impl<N: Send + 'static> ChannelNotifier<N> {
+    fn add_sender(&self, chain_ids: Vec<ChainId>, sender: Arc<dyn Subscriber<N>>) {
         for id in chain_ids {
             let mut senders = self.inner.entry(id).or_default();
             senders.push(sender.clone());
@@ -38,7 +89,7 @@ impl<N> ChannelNotifier<N> {
     /// Creates a subscription given a collection of ChainIds and a sender to the client.
     pub fn subscribe(&self, chain_ids: Vec<ChainId>) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        self.add_sender(chain_ids, &tx);
+        self.add_sender(chain_ids, Arc::new(tx));
         rx
     }
 
@@ -46,16 +97,31 @@ impl<N> ChannelNotifier<N> {
     /// Immediately posts a first notification as an ACK.
     pub fn subscribe_with_ack(&self, chain_ids: Vec<ChainId>, ack: N) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        self.add_sender(chain_ids, &tx);
+        self.add_sender(chain_ids, Arc::new(tx.clone()));
         tx.send(ack)
             .expect("pushing to a new channel should succeed");
         rx
     }
+
+    /// Creates a subscription given a collection of ChainIds, whose pending notifications are
+    /// coalesced, so that a slow client never uses more than a bounded amount of memory.
+    ///
+    /// A new notification replaces the pending one with the same [`Coalesce::coalescing_key`]
+    /// instead of being queued after it. Notifications with a new key are dropped if
+    /// [`NOTIFICATION_MAILBOX_CAPACITY`] of them are already pending.
+    pub fn subscribe_coalesced(&self, chain_ids: Vec<ChainId>) -> MailboxReceiver<N>
+    where
+        N: Coalesce,
+    {
+        let (sender, receiver) = mailbox(NOTIFICATION_MAILBOX_CAPACITY);
+        self.add_sender(chain_ids, Arc::new(sender));
+        receiver
+    }
 }
 
 impl<N> ChannelNotifier<N>
 where
-    N: Clone,
+    N: Clone + Send + 'static,
 {
     /// Notifies all the clients waiting for a notification from a given chain.
     pub fn notify_chain(&self, chain_id: &ChainId, notification: &N) {
@@ -68,7 +134,7 @@ where
             let senders = senders.value_mut();
 
             for (index, sender) in senders.iter_mut().enumerate() {
-                if sender.send(notification.clone()).is_err() {
+                if !sender.deliver(notification.clone()) {
                     dead_senders.push(index);
                 }
             }
@@ -88,6 +154,173 @@ where
     }
 }
 
+/// A notification that supersedes the pending notifications with the same key, in a
+/// subscription created with [`ChannelNotifier::subscribe_coalesced`].
+pub trait Coalesce {
+    /// The key identifying the notifications that supersede each other.
+    type Key: Clone + Eq + Hash + Send;
+
+    /// Returns the key of this notification.
+    fn coalescing_key(&self) -> Self::Key;
+}
+
+/// The key of a [`worker::Notification`] in a coalescing subscription: only the latest
+/// notification about new blocks, new rounds, or new messages from a given origin is kept for
+/// each chain.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum NotificationKey {
+    /// A new block was added to the chain.
+    NewBlock(ChainId),
+    /// New messages from the origin were received by the chain.
+    NewIncomingBundle(ChainId, Origin),
+    /// The chain entered a new round.
+    NewRound(ChainId),
+}
+
+impl Coalesce for worker::Notification {
+    type Key = NotificationKey;
+
+    fn coalescing_key(&self) -> NotificationKey {
+        match &self.reason {
+            Reason::NewBlock { .. } => NotificationKey::NewBlock(self.chain_id),
+            Reason::NewIncomingBundle { origin, .. } => {
+                NotificationKey::NewIncomingBundle(self.chain_id, origin.clone())
+            }
+            Reason::NewRound { .. } => NotificationKey::NewRound(self.chain_id),
+        }
+    }
+}
+
+/// Creates a coalescing mailbox holding at most `capacity` pending notifications.
+fn mailbox<N: Coalesce>(capacity: usize) -> (MailboxSender<N>, MailboxReceiver<N>) {
+    let mailbox = Arc::new(Mailbox {
+        state: Mutex::new(MailboxState {
+            order: VecDeque::new(),
+            pending: HashMap::new(),
+            has_sender: true,
+            has_receiver: true,
+        }),
+        new_notification: Notify::new(),
+        capacity,
+    });
+    (
+        MailboxSender {
+            mailbox: mailbox.clone(),
+        },
+        MailboxReceiver { mailbox },
+    )
+}
+
+/// The notifications shared by the two ends of a coalescing subscription.
+struct Mailbox<N: Coalesce> {
+    state: Mutex<MailboxState<N>>,
+    new_notification: Notify,
+    capacity: usize,
+}
+
+struct MailboxState<N: Coalesce> {
+    /// The keys of the pending notifications, oldest first.
+    order: VecDeque<N::Key>,
+    /// The latest pending notification for each key.
+    pending: HashMap<N::Key, N>,
+    has_sender: bool,
+    has_receiver: bool,
+}
+
+impl<N: Coalesce> Mailbox<N> {
+    fn lock(&self) -> std::sync::MutexGuard<'_, MailboxState<N>> {
+        self.state
+            .lock()
+            .expect("Panics should not happen while holding a lock to a mailbox")
+    }
+}
+
+/// The end of a coalescing subscription held by the [`ChannelNotifier`].
+struct MailboxSender<N: Coalesce> {
+    mailbox: Arc<Mailbox<N>>,
+}
+
+impl<N> Subscriber<N> for MailboxSender<N>
+where
+    N: Coalesce + Send,
+{
+    fn deliver(&self, notification: N) -> bool {
+        {
+            let mut state = self.mailbox.lock();
+            if !state.has_receiver {
+                return false;
+            }
+            let key = notification.coalescing_key();
+            if let Some(pending) = state.pending.get_mut(&key) {
+                *pending = notification;
+            } else if state.order.len() >= self.mailbox.capacity {
+                warn!("Dropping a notification because the subscriber's mailbox is full");
+                #[cfg(with_metrics)]
+                NOTIFICATION_MAILBOX_DROPPED.with_label_values(&[]).inc();
+                return true;
+            } else {
+                state.order.push_back(key.clone());
+                state.pending.insert(key, notification);
+                #[cfg(with_metrics)]
+                NOTIFICATION_MAILBOX_DEPTH.with_label_values(&[]).inc();
+            }
+        }
+        self.mailbox.new_notification.notify_one();
+        true
+    }
+}
+
+impl<N: Coalesce> Drop for MailboxSender<N> {
+    fn drop(&mut self) {
+        self.mailbox.lock().has_sender = false;
+        self.mailbox.new_notification.notify_one();
+    }
+}
+
+/// The end of a subscription created with [`ChannelNotifier::subscribe_coalesced`], from which
+/// the client receives the notifications.
+pub struct MailboxReceiver<N: Coalesce> {
+    mailbox: Arc<Mailbox<N>>,
+}
+
+impl<N: Coalesce> MailboxReceiver<N> {
+    /// Waits for the oldest pending notification, or returns `None` if the subscription was
+    /// closed by the notifier.
+    pub async fn recv(&mut self) -> Option<N> {
+        loop {
+            if let Some(notification) = self.try_recv() {
+                return Some(notification);
+            }
+            if !self.mailbox.lock().has_sender {
+                return None;
+            }
+            self.mailbox.new_notification.notified().await;
+        }
+    }
+
+    /// Returns the oldest pending notification, if any, without waiting.
+    pub fn try_recv(&mut self) -> Option<N> {
+        let mut state = self.mailbox.lock();
+        let key = state.order.pop_front()?;
+        #[cfg(with_metrics)]
+        NOTIFICATION_MAILBOX_DEPTH.with_label_values(&[]).dec();
+        state.pending.remove(&key)
+    }
+}
+
+impl<N: Coalesce> Drop for MailboxReceiver<N> {
+    fn drop(&mut self) {
+        let mut state = self.mailbox.lock();
+        state.has_receiver = false;
+        #[cfg(with_metrics)]
+        NOTIFICATION_MAILBOX_DEPTH
+            .with_label_values(&[])
+            .sub(state.order.len() as i64);
+        state.order.clear();
+        state.pending.clear();
+    }
+}
+
 pub trait Notifier: Clone + Send + 'static {
     fn notify(&self, notifications: &[worker::Notification]);
 }
@@ -119,6 +352,8 @@ pub mod tests {
         time::Duration,
     };
 
+    use linera_base::{crypto::CryptoHash, data_types::BlockHeight};
+
     use super::*;
 
     #[test]
@@ -231,4 +466,72 @@ pub mod tests {
         notifier.notify_chain(&chain_d, &());
         assert_eq!(notifier.inner.len(), 0);
     }
+
+    fn new_block(chain_id: ChainId, height: u64) -> worker::Notification {
+        worker::Notification {
+            chain_id,
+            reason: Reason::NewBlock {
+                height: BlockHeight(height),
+                hash: CryptoHash::from([height, 0, 0, 0]),
+            },
+        }
+    }
+
+    #[test]
+    fn test_coalescing_flood() {
+        let notifier = Arc::new(ChannelNotifier::default());
+        let chain_ids = [ChainId::root(0), ChainId::root(1), ChainId::root(2)];
+        let mut receiver = notifier.subscribe_coalesced(chain_ids.to_vec());
+
+        const UPDATES: u64 = 10_000;
+        for height in 0..UPDATES {
+            let chain_id = chain_ids[(height % 3) as usize];
+            notifier.notify(&[new_block(chain_id, height)]);
+        }
+
+        let mut notifications = Vec::new();
+        while let Some(notification) = receiver.try_recv() {
+            notifications.push(notification);
+        }
+        assert!(notifications.len() <= chain_ids.len());
+        assert_eq!(
+            notifications,
+            [
+                new_block(chain_ids[0], UPDATES - 1),
+                new_block(chain_ids[1], UPDATES - 3),
+                new_block(chain_ids[2], UPDATES - 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_coalescing_mailbox_is_bounded() {
+        let (sender, mut receiver) = mailbox(2);
+        let chain_ids = [ChainId::root(0), ChainId::root(1), ChainId::root(2)];
+
+        for (height, chain_id) in chain_ids.into_iter().enumerate() {
+            assert!(sender.deliver(new_block(chain_id, height as u64)));
+        }
+        assert!(sender.deliver(new_block(chain_ids[0], 3)));
+
+        assert_eq!(receiver.recv().await, Some(new_block(chain_ids[0], 3)));
+        assert_eq!(receiver.recv().await, Some(new_block(chain_ids[1], 1)));
+        assert_eq!(receiver.try_recv(), None);
+
+        drop(sender);
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[test]
+    fn test_coalescing_subscriber_eviction() {
+        let notifier = ChannelNotifier::default();
+        let chain_id = ChainId::root(0);
+
+        let receiver = notifier.subscribe_coalesced(vec![chain_id]);
+        assert_eq!(notifier.inner.len(), 1);
+
+        drop(receiver);
+        notifier.notify_chain(&chain_id, &new_block(chain_id, 0));
+        assert_eq!(notifier.inner.len(), 0);
+    }
 }
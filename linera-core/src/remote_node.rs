@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 
 use custom_debug_derive::Debug;
 use futures::{future::try_join_all, stream::FuturesUnordered, StreamExt};
@@ -24,6 +24,7 @@ use tracing::{instrument, warn};
 
 use crate::{
     data_types::{BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse},
+    misbehavior::{Misbehavior, MisbehaviorLog},
     node::{CrossChainMessageDelivery, NodeError, ValidatorNode},
 };
 
@@ -33,6 +34,9 @@ pub struct RemoteNode<N> {
     pub name: ValidatorName,
     #[debug(skip)]
     pub node: N,
+    /// Where to record evidence if the validator misbehaves.
+    #[debug(skip)]
+    pub misbehavior: Arc<MisbehaviorLog>,
 }
 
 #[allow(clippy::result_large_err)]
@@ -78,6 +82,23 @@ impl<N: ValidatorNode> RemoteNode<N> {
         self.check_and_return_info(response, chain_id)
     }
 
+    pub(crate) async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Box<ChainInfo>, NodeError> {
+        let chain_id = certificates
+            .first()
+            .ok_or(NodeError::UnexpectedCertificateValue)?
+            .inner()
+            .chain_id();
+        let response = self
+            .node
+            .handle_confirmed_certificates(certificates, delivery)
+            .await?;
+        self.check_and_return_info(response, chain_id)
+    }
+
     pub(crate) async fn handle_validated_certificate(
         &self,
         certificate: ValidatedBlockCertificate,
@@ -151,6 +172,13 @@ impl<N: ValidatorNode> RemoteNode<N> {
         response: ChainInfoResponse,
         chain_id: ChainId,
     ) -> Result<Box<ChainInfo>, NodeError> {
+        if response.signature.is_some() && response.check(&self.name).is_err() {
+            self.misbehavior.report(
+                self.name,
+                Misbehavior::InvalidChainInfoSignature { response },
+            );
+            return Err(NodeError::InvalidChainInfoResponse);
+        }
         let manager = &response.info.manager;
         let proposed = manager.requested_proposed.as_ref();
         let locking = manager.requested_locking.as_ref();
@@ -0,0 +1,155 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::future::join_all;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{sleep, Instant},
+};
+
+use super::ChainWorkerScheduler;
+
+/// How long it takes to handle every request.
+const WORK: Duration = Duration::from_millis(10);
+
+/// A request to a simulated chain worker, answered with the time it finished.
+struct Request {
+    index: usize,
+    callback: oneshot::Sender<Instant>,
+}
+
+/// Counts the chains handling a request.
+#[derive(Clone, Default)]
+struct Occupancy {
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+}
+
+/// Spawns a task handling requests the same way as a
+/// [`ChainWorkerActor`][`crate::chain_worker::ChainWorkerActor`], and recording the indices of
+/// the requests in the order they were handled.
+fn spawn_chain(
+    scheduler: ChainWorkerScheduler,
+    occupancy: Occupancy,
+) -> (mpsc::UnboundedSender<Request>, Arc<Mutex<Vec<usize>>>) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Request>();
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let log = handled.clone();
+    tokio::spawn(async move {
+        while let Some(request) = receiver.recv().await {
+            let _permit = scheduler.acquire().await;
+            let running = occupancy.running.fetch_add(1, Ordering::SeqCst) + 1;
+            occupancy.max_running.fetch_max(running, Ordering::SeqCst);
+            sleep(WORK).await;
+            log.lock().unwrap().push(request.index);
+            occupancy.running.fetch_sub(1, Ordering::SeqCst);
+            let _ = request.callback.send(Instant::now());
+        }
+    });
+    (sender, handled)
+}
+
+/// Sends `count` requests to a chain, returning the receivers of the responses.
+fn send_requests(
+    chain: &mpsc::UnboundedSender<Request>,
+    count: usize,
+) -> Vec<oneshot::Receiver<Instant>> {
+    (0..count)
+        .map(|index| {
+            let (callback, response) = oneshot::channel();
+            chain.send(Request { index, callback }).unwrap();
+            response
+        })
+        .collect()
+}
+
+/// Checks that a chain with a long queue of requests does not delay the requests of the other
+/// chains by more than a few turns, and that each chain still handles its requests in order.
+#[tokio::test(start_paused = true)]
+async fn test_busy_chain_does_not_delay_the_other_chains() {
+    const LIMIT: usize = 4;
+    const CHAINS: usize = 50;
+    const REQUESTS_PER_CHAIN: usize = 5;
+    const BUSY_CHAIN_REQUESTS: usize = 1_000;
+
+    let scheduler = ChainWorkerScheduler::new(NonZeroUsize::new(LIMIT));
+    let occupancy = Occupancy::default();
+
+    let (busy_chain, busy_chain_log) = spawn_chain(scheduler.clone(), occupancy.clone());
+    let busy_responses = send_requests(&busy_chain, BUSY_CHAIN_REQUESTS);
+    // Let the busy chain start on its backlog before the other chains receive anything.
+    sleep(WORK / 2).await;
+
+    let start = Instant::now();
+    let mut logs = Vec::new();
+    let mut responses = Vec::new();
+    for _ in 0..CHAINS {
+        let (chain, log) = spawn_chain(scheduler.clone(), occupancy.clone());
+        responses.extend(send_requests(&chain, REQUESTS_PER_CHAIN));
+        logs.push(log);
+    }
+
+    let finish = join_all(responses)
+        .await
+        .into_iter()
+        .map(Result::unwrap)
+        .max()
+        .unwrap();
+    // The chains take turns: each round serves one request of every chain, with `LIMIT`
+    // requests handled at a time.
+    let rounds = REQUESTS_PER_CHAIN as u32;
+    let round_duration = WORK * (CHAINS + 1).div_ceil(LIMIT) as u32;
+    assert!(finish - start <= round_duration * (rounds + 1));
+    assert!(finish - start < WORK * BUSY_CHAIN_REQUESTS as u32 / 10);
+
+    // The busy chain was not starved either, and kept going after the others were done.
+    let busy_handled = busy_chain_log.lock().unwrap().len();
+    assert!(busy_handled >= REQUESTS_PER_CHAIN);
+    assert!(busy_handled < BUSY_CHAIN_REQUESTS);
+
+    join_all(busy_responses).await;
+    assert_eq!(
+        *busy_chain_log.lock().unwrap(),
+        (0..BUSY_CHAIN_REQUESTS).collect::<Vec<_>>()
+    );
+    for log in logs {
+        assert_eq!(
+            *log.lock().unwrap(),
+            (0..REQUESTS_PER_CHAIN).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(occupancy.max_running.load(Ordering::SeqCst), LIMIT);
+}
+
+/// Checks that all the chains run concurrently if the scheduler has no limit.
+#[tokio::test(start_paused = true)]
+async fn test_unlimited_scheduler_runs_all_chains_concurrently() {
+    const CHAINS: usize = 50;
+
+    let scheduler = ChainWorkerScheduler::new(None);
+    let occupancy = Occupancy::default();
+    let start = Instant::now();
+
+    let responses = (0..CHAINS).flat_map(|_| {
+        let (chain, _) = spawn_chain(scheduler.clone(), occupancy.clone());
+        send_requests(&chain, 1)
+    });
+    let finish = join_all(responses.collect::<Vec<_>>())
+        .await
+        .into_iter()
+        .map(Result::unwrap)
+        .max()
+        .unwrap();
+
+    assert_eq!(finish - start, WORK);
+    assert_eq!(occupancy.max_running.load(Ordering::SeqCst), CHAINS);
+}
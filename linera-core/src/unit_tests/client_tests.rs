@@ -10,23 +10,29 @@ use futures::StreamExt;
 use linera_base::{
     crypto::*,
     data_types::*,
-    identifiers::{Account, AccountOwner, ChainId, MessageId, Owner},
+    hashed::Hashed,
+    identifiers::{
+        Account, AccountOwner, ApplicationId, BytecodeId, ChainId, GenericApplicationId, MessageId,
+        Owner,
+    },
     ownership::{ChainOwnership, TimeoutConfig},
+    time::Duration,
 };
 use linera_chain::{
-    data_types::{IncomingBundle, Medium, MessageBundle, Origin, PostedMessage},
+    data_types::{IncomingBundle, Medium, MessageBundle, Origin, PostedMessage, Vote},
     manager::LockingBlock,
-    types::Timeout,
+    types::{ConfirmedBlock, ConfirmedBlockCertificate, Timeout},
     ChainError, ChainExecutionContext,
 };
 use linera_execution::{
     committee::{Committee, Epoch},
     system::{Recipient, SystemOperation},
     ExecutionError, Message, MessageKind, Operation, QueryOutcome, ResourceControlPolicy,
-    SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
+    StateHashPart, SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
+    UserApplicationId,
 };
-use linera_storage::{DbStorage, TestClock};
-use linera_views::memory::MemoryStore;
+use linera_storage::{DbStorage, Storage as _, TestClock};
+use linera_views::{memory::MemoryStore, views::RootView as _};
 use rand::Rng;
 use test_case::test_case;
 
@@ -41,16 +47,18 @@ use crate::test_utils::ServiceStorageBuilder;
 use crate::{
     client::{
         BlanketMessagePolicy, ChainClient, ChainClientError, ClientOutcome, MessageAction,
-        MessagePolicy,
+        MessagePolicy, ProposalIssue, ProposalIssueSeverity, StateHashComparison,
     },
+    data_types::{MessageState, MessageStatus},
     local_node::LocalNodeError,
+    misbehavior::Misbehavior,
     node::{
         CrossChainMessageDelivery,
         NodeError::{self, ClientIoError},
         ValidatorNode,
     },
     test_utils::{FaultType, MemoryStorageBuilder, NodeProvider, StorageBuilder, TestBuilder},
-    updater::CommunicationError,
+    updater::{CommunicationError, ValidatorFault, ValidatorRequestOptions},
     worker::{Notification, Reason, WorkerError},
 };
 
@@ -259,6 +267,347 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_message_status<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let mut receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let cert = sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    let hash = cert.hash();
+
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert_eq!(
+        receiver.message_status(hash, 0).await?,
+        MessageStatus {
+            state: MessageState::Pending,
+            height: None,
+        }
+    );
+    // There is no second message, and the message is not sent to the sender's chain.
+    assert_matches!(
+        receiver.message_status(hash, 1).await,
+        Err(ChainClientError::MessageNotFound { index: 1, .. })
+    );
+    assert_matches!(
+        sender.message_status(hash, 0).await,
+        Err(ChainClientError::MessageNotFound { index: 0, .. })
+    );
+
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(
+        receiver.message_status(hash, 0).await?,
+        MessageStatus {
+            state: MessageState::Executed,
+            height: Some(BlockHeight::ZERO),
+        }
+    );
+
+    // A message the owner asked to reject, in a block of its own, is reported as rejected by
+    // the block that received it.
+    let cert = sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    let hash = cert.hash();
+    let message_id = cert.block().message_id(0);
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Ignore, None);
+    receiver.reject_message(message_id).await?.unwrap();
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None);
+    assert_eq!(
+        receiver.message_status(hash, 0).await?.state,
+        MessageState::Pending
+    );
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert_eq!(
+        receiver.message_status(hash, 0).await?,
+        MessageStatus {
+            state: MessageState::Rejected,
+            height: Some(BlockHeight::from(2)),
+        }
+    );
+
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_pending_messages<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    for amount in [Amount::ONE, Amount::from_tokens(2)] {
+        let cert = sender
+            .transfer_to_account(None, amount, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+
+    // Both transfers are listed, without being executed.
+    let messages = receiver.pending_messages().await?;
+    assert_eq!(messages.len(), 2);
+    for (message, (height, amount)) in messages.iter().zip([
+        (BlockHeight::ZERO, Amount::ONE),
+        (BlockHeight::from(1), Amount::from_tokens(2)),
+    ]) {
+        assert_eq!(message.origin, Origin::chain(sender.chain_id()));
+        assert_eq!(message.height, height);
+        assert_eq!(message.index, 0);
+        assert_eq!(message.application_id, GenericApplicationId::System);
+        let expected_preview = format!(
+            "{:?}",
+            SystemMessage::Credit {
+                target: None,
+                amount,
+                source: None,
+            }
+        );
+        assert_eq!(message.preview, Some(expected_preview));
+    }
+    assert_eq!(receiver.local_balance().await?, Amount::ZERO);
+
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+    assert!(receiver.pending_messages().await?.is_empty());
+    assert_eq!(receiver.local_balance().await?, Amount::from_tokens(3));
+
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_chain_execution_statistics<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    for amount in [Amount::ONE, Amount::from_tokens(2)] {
+        let cert = sender
+            .transfer_to_account(None, amount, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 1);
+
+    // Two blocks with one transfer each, and one block receiving both transfers.
+    let sender_statistics = sender
+        .chain_state_view()
+        .await?
+        .execution_statistics
+        .get()
+        .clone();
+    assert_eq!(sender_statistics.blocks, 2);
+    assert_eq!(sender_statistics.operations, 2);
+    assert_eq!(sender_statistics.incoming_messages, 0);
+    assert_eq!(sender_statistics.outgoing_messages, 2);
+    let receiver_statistics = receiver
+        .chain_state_view()
+        .await?
+        .execution_statistics
+        .get()
+        .clone();
+    assert_eq!(receiver_statistics.blocks, 1);
+    assert_eq!(receiver_statistics.operations, 0);
+    assert_eq!(receiver_statistics.incoming_messages, 2);
+    assert_eq!(receiver_statistics.outgoing_messages, 0);
+    // System operations and messages don't consume fuel or application storage.
+    assert_eq!(receiver_statistics.fuel, 0);
+    assert_eq!(receiver_statistics.bytes_written, 0);
+
+    // The statistics are persisted together with the chain state.
+    let stored_chain = receiver
+        .storage_client()
+        .load_chain(receiver.chain_id())
+        .await?;
+    assert_eq!(
+        *stored_chain.execution_statistics.get(),
+        receiver_statistics
+    );
+
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_process_inbox_splits_bundles<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let policy = ResourceControlPolicy {
+        maximum_operations_per_block: 1,
+        maximum_incoming_bundles_per_block: 2,
+        ..ResourceControlPolicy::default()
+    };
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(policy);
+    let sender = builder.add_root_chain(1, Amount::from_tokens(5)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+
+    // Each transfer is in a separate block, so the receiver gets five bundles.
+    for _ in 0..5 {
+        let cert = sender
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+
+    // At most two of them fit into each block.
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 3);
+    for certificate in &certificates {
+        assert!(certificate.block().body.incoming_bundles.len() <= 2);
+    }
+    assert_eq!(
+        receiver.local_balance().await.unwrap(),
+        Amount::from_tokens(5)
+    );
+
+    // A block with more operations than allowed is rejected before it is proposed.
+    let burn = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::Burn,
+        amount: Amount::ONE,
+    });
+    assert_matches!(
+        receiver.execute_operations(vec![burn.clone(), burn]).await,
+        Err(ChainClientError::LocalNodeError(
+            LocalNodeError::WorkerError(WorkerError::ChainError(error))
+        )) if matches!(*error, ChainError::TooManyOperations { count: 2, maximum: 1 })
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_process_inbox_throttles_flooding_origin<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let policy = ResourceControlPolicy {
+        maximum_incoming_bundles_per_origin: 2,
+        ..ResourceControlPolicy::default()
+    };
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(policy);
+    let flooder = builder.add_root_chain(1, Amount::from_tokens(5)).await?;
+    let sender = builder.add_root_chain(2, Amount::from_tokens(1)).await?;
+    let receiver = builder.add_root_chain(3, Amount::ZERO).await?;
+
+    // The flooding chain sends five bundles, the other sender only one.
+    for _ in 0..5 {
+        let cert = flooder
+            .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+    let cert = sender
+        .transfer_to_account(None, Amount::ONE, Account::chain(receiver.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+
+    let depths = receiver.chain_state_view().await?.inbox_depths().await?;
+    assert_eq!(depths.len(), 2);
+    assert!(depths.contains(&(Origin::chain(flooder.chain_id()), 5)));
+
+    // The first block only contains two of the flooding chain's bundles, but the other
+    // sender's bundle is not held back.
+    let (certificates, _) = receiver.process_inbox().await?;
+    assert_eq!(certificates.len(), 3);
+    let senders_in_first_block = certificates[0]
+        .block()
+        .body
+        .incoming_bundles
+        .iter()
+        .map(|bundle| bundle.origin.sender)
+        .collect::<Vec<_>>();
+    assert_eq!(senders_in_first_block.len(), 3);
+    assert!(senders_in_first_block.contains(&sender.chain_id()));
+    for certificate in &certificates {
+        let from_flooder = certificate
+            .block()
+            .body
+            .incoming_bundles
+            .iter()
+            .filter(|bundle| bundle.origin.sender == flooder.chain_id())
+            .count();
+        assert!(from_flooder <= 2);
+    }
+    assert_eq!(
+        receiver.local_balance().await.unwrap(),
+        Amount::from_tokens(6)
+    );
+    assert!(receiver
+        .chain_state_view()
+        .await?
+        .inbox_depths()
+        .await?
+        .is_empty());
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -844,6 +1193,193 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_initiating_valid_transfer_with_unresponsive_and_garbage_validators<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    // With 7 validators, a quorum is reached despite two faulty ones.
+    let mut builder = TestBuilder::new(storage_builder, 7, 0).await?;
+    builder.set_fault_type([0], FaultType::Unresponsive).await;
+    builder.set_fault_type([1], FaultType::Garbage).await;
+    let unresponsive = builder.node(0).name();
+    let garbage = builder.node(1).name();
+    let mut sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    sender.options_mut().validator_requests = ValidatorRequestOptions {
+        timeout: Some(Duration::from_millis(100)),
+        max_retries: 1,
+        retry_delay: Duration::from_millis(10),
+    };
+    let certificate = sender
+        .transfer_to_account(
+            None,
+            Amount::from_tokens(3),
+            Account::chain(ChainId::root(2)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sender.next_block_height(), BlockHeight::from(1));
+    assert!(sender.pending_proposal().is_none());
+    assert_eq!(sender.local_balance().await.unwrap(), Amount::ONE);
+    assert!(!certificate.is_signed_by(&unresponsive));
+    assert!(!certificate.is_signed_by(&garbage));
+
+    // Only the faulty validators can appear in the report, if they were heard from in time.
+    let report = sender.last_quorum_report();
+    for (name, fault) in &report.faults {
+        if *name == unresponsive {
+            assert_eq!(*fault, ValidatorFault::Timeout);
+        } else {
+            assert_eq!(*name, garbage);
+            assert_eq!(
+                *fault,
+                ValidatorFault::Error(NodeError::InvalidChainInfoResponse)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_misbehavior_report_for_invalid_signature<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    // Without the unresponsive validator, the client has to wait for the garbage one.
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    builder.set_fault_type([0], FaultType::Garbage).await;
+    builder.set_fault_type([1], FaultType::Unresponsive).await;
+    let garbage = builder.node(0).name();
+    let mut sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    sender.options_mut().validator_requests = ValidatorRequestOptions {
+        timeout: Some(Duration::from_millis(100)),
+        max_retries: 0,
+        retry_delay: Duration::from_millis(10),
+    };
+    let result = sender.burn(None, Amount::ONE).await;
+    assert!(result.is_err());
+
+    let reports = sender.misbehavior_reports();
+    assert!(!reports.is_empty());
+    for report in reports {
+        assert_eq!(report.validator, garbage);
+        assert_matches!(
+            report.misbehavior,
+            Misbehavior::InvalidChainInfoSignature { response }
+                if response.info.chain_id == sender.chain_id()
+        );
+    }
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_misbehavior_report_for_conflicting_votes<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    // Two unresponsive validators make sure the proposal never gets a quorum, so that the
+    // client proposes it again in the same round.
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    builder.set_fault_type([0], FaultType::Equivocating).await;
+    builder
+        .set_fault_type([2, 3], FaultType::Unresponsive)
+        .await;
+    let equivocating = builder.node(0).name();
+    let mut sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    sender.options_mut().validator_requests = ValidatorRequestOptions {
+        timeout: Some(Duration::from_millis(100)),
+        max_retries: 0,
+        retry_delay: Duration::from_millis(10),
+    };
+    assert!(sender.burn(None, Amount::ONE).await.is_err());
+    assert!(sender.misbehavior_reports().is_empty());
+    assert!(sender.process_pending_block().await.is_err());
+
+    let reports = sender.misbehavior_reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].validator, equivocating);
+    assert_matches!(
+        &reports[0].misbehavior,
+        Misbehavior::ConflictingVotes { height, first, second }
+            if *height == BlockHeight::ZERO
+                && first.round == second.round
+                && first.value.value_hash != second.value.value_hash
+                && first.check().is_ok()
+                && second.check().is_ok()
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_misbehavior_report_for_incorrect_state_hash<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let honest = sender.burn(None, Amount::ONE).await.unwrap().unwrap();
+    let state_hash = honest.block().header.state_hash;
+
+    // The validators sign a block with a state hash that doesn't match its execution.
+    let mut block = honest.block().clone();
+    block.header.state_hash = CryptoHash::test_hash("forged state");
+    let value = Hashed::new(ConfirmedBlock::from_hashed(Hashed::new(block)));
+    let mut signatures = Vec::new();
+    for index in 0..4 {
+        let key_pair = builder.node(index).key_pair().await;
+        let vote = Vote::new(value.clone(), honest.round, &key_pair);
+        signatures.push((vote.validator, vote.signature));
+    }
+    let forged = ConfirmedBlockCertificate::new(value, honest.round, signatures);
+
+    // A new client for the same chain executes the block and notices the mismatch.
+    let key_pair = sender.key_pair().await?;
+    let client = builder
+        .make_client(sender.chain_id(), key_pair, None, BlockHeight::ZERO)
+        .await?;
+    let remote_node = client.make_nodes(&builder.initial_committee)?.remove(0);
+    let info = client
+        .client
+        .try_process_certificates(&remote_node, sender.chain_id(), vec![forged.clone()])
+        .await;
+    assert!(info.is_none());
+
+    let reports = client.misbehavior_reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].validator, remote_node.name);
+    assert_matches!(
+        &reports[0].misbehavior,
+        Misbehavior::IncorrectOutcome { certificate, computed_state_hash }
+            if **certificate == forged && *computed_state_hash == state_hash
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -1040,13 +1576,10 @@ where
             Account::chain(client3.chain_id),
         )
         .await,
-        Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(WorkerError::ChainError(
-            error
-        )))) if matches!(&*error, ChainError::ExecutionError(
-            execution_error, ChainExecutionContext::Operation(_)
-        ) if matches!(**execution_error, ExecutionError::SystemError(
-            SystemExecutionError::InsufficientFunding { .. }
-        )))
+        Err(ChainClientError::OperationFailed { index: 0, error })
+            if matches!(*error, ExecutionError::SystemError(
+                SystemExecutionError::InsufficientFunding { .. }
+            ))
     );
     // There is no pending block, since the proposal wasn't valid at the time.
     assert!(client2
@@ -1214,6 +1747,51 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[test_log::test(tokio::test)]
+async fn test_receive_certificates_from_future_epoch<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let admin = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+    let user = builder.add_root_chain(1, Amount::ZERO).await?;
+    let validators = builder.initial_committee.validators().clone();
+
+    // The admin chain moves to a new committee, retires the old one, and then sends tokens to
+    // the user chain from the new epoch.
+    let committee = Committee::new(validators, ResourceControlPolicy::only_fuel());
+    admin.stage_new_committee(committee).await.unwrap();
+    admin.finalize_committee().await.unwrap();
+    admin
+        .transfer_to_account(None, Amount::ONE, Account::chain(user.chain_id()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(admin.next_block_height(), BlockHeight::from(3));
+
+    // The user's client only knows about epoch 0. When looking for received certificates, it
+    // learns about the new committee from the admin chain and checks the skipped ones again.
+    let remote_node = user.make_nodes(&builder.initial_committee)?.remove(0);
+    user.find_received_certificates_from_validator(remote_node)
+        .await
+        .unwrap();
+    let admin_info = user
+        .client
+        .local_node
+        .chain_info(builder.admin_id())
+        .await?;
+    assert_eq!(admin_info.next_block_height, BlockHeight::from(3));
+    assert_eq!(admin_info.epoch, Some(Epoch::from(1)));
+
+    // The messages are in the inbox, so the user chain can migrate to the new epoch.
+    user.process_inbox().await.unwrap();
+    assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[test_log::test(tokio::test)]
@@ -1229,14 +1807,10 @@ where
 
     // TODO(#1649): Make this code nicer.
     assert_matches!(obtained_error,
-        Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
-            WorkerError::ChainError(error)
-        ))) if matches!(&*error, ChainError::ExecutionError(
-            execution_error,
-            ChainExecutionContext::Operation(0)
-        ) if matches!(**execution_error,
-            ExecutionError::SystemError(SystemExecutionError::InsufficientFunding { .. })
-        ))
+        Err(ChainClientError::OperationFailed { index: 0, error })
+            if matches!(*error,
+                ExecutionError::SystemError(SystemExecutionError::InsufficientFunding { .. })
+            )
     );
     let obtained_error = sender.burn(None, Amount::from_tokens(3)).await;
     // TODO(#1649): Make this code nicer.
@@ -1252,6 +1826,76 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_execute_operations_with_outcomes<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let transfer = Operation::System(SystemOperation::Transfer {
+        owner: None,
+        recipient: Recipient::chain(receiver.chain_id()),
+        amount: Amount::ONE,
+    });
+    let burn = |amount| {
+        Operation::System(SystemOperation::Transfer {
+            owner: None,
+            recipient: Recipient::Burn,
+            amount,
+        })
+    };
+
+    // The second operation is invalid, so the whole block is rejected.
+    let operations = vec![
+        transfer.clone(),
+        burn(Amount::from_tokens(5)),
+        transfer.clone(),
+    ];
+    assert_matches!(
+        sender.execute_operations_with_outcomes(operations).await,
+        Err(ChainClientError::OperationFailed { index: 1, error })
+            if matches!(*error, ExecutionError::SystemError(
+                SystemExecutionError::InsufficientFunding { .. }
+            ))
+    );
+    assert_eq!(sender.next_block_height(), BlockHeight::ZERO);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(3));
+
+    let operations = vec![transfer.clone(), burn(Amount::ONE), transfer];
+    let outcomes = sender
+        .execute_operations_with_outcomes(operations)
+        .await?
+        .unwrap();
+    assert_eq!(outcomes.len(), 3);
+    assert!(outcomes[1].messages.is_empty());
+    for (index, outcome) in [(0, &outcomes[0]), (1, &outcomes[2])] {
+        let [(message_id, message)] = &outcome.messages[..] else {
+            panic!("Expected a single message, got {:?}", outcome.messages);
+        };
+        assert_eq!(
+            *message_id,
+            MessageId {
+                chain_id: sender.chain_id(),
+                height: BlockHeight::ZERO,
+                index,
+            }
+        );
+        assert_matches!(
+            message.message,
+            Message::System(SystemMessage::Credit { amount, .. }) if amount == Amount::ONE
+        );
+    }
+    assert_eq!(sender.local_balance().await?, Amount::ZERO);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -2226,6 +2870,225 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_process_inbox_is_deterministic<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender1 = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let sender2 = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(3, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+    for sender in [&sender2, &sender1, &sender2, &sender1] {
+        sender
+            .transfer(None, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    // Three clients of the receiving chain start from the same inbox. The last one limits the
+    // number of incoming messages per block.
+    let key_pair = receiver.key_pair().await?;
+    let mut clients = vec![receiver];
+    for _ in 0..2 {
+        let client = builder
+            .make_client(
+                clients[0].chain_id(),
+                key_pair.copy(),
+                None,
+                BlockHeight::ZERO,
+            )
+            .await?;
+        clients.push(client);
+    }
+    for client in &clients {
+        client.synchronize_from_validators().await?;
+    }
+    clients[2].options_mut().max_pending_messages = 3;
+
+    // With the validators offline, the proposed blocks remain pending.
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Offline)
+        .await;
+    let mut blocks = Vec::new();
+    for client in &clients {
+        assert!(client.process_inbox_without_prepare().await.is_err());
+        let block = (*client.pending_proposal()).clone();
+        blocks.push(block.expect("the block should be pending"));
+    }
+    assert_eq!(bcs::to_bytes(&blocks[0])?, bcs::to_bytes(&blocks[1])?);
+
+    // The bundles are ordered by sender, then by height, regardless of their arrival.
+    let mut expected_origins = vec![
+        (sender1.chain_id(), BlockHeight::ZERO),
+        (sender1.chain_id(), BlockHeight::from(1)),
+        (sender2.chain_id(), BlockHeight::ZERO),
+        (sender2.chain_id(), BlockHeight::from(1)),
+    ];
+    expected_origins.sort();
+    let origins = blocks[0]
+        .incoming_bundles
+        .iter()
+        .map(|bundle| (bundle.origin.sender, bundle.bundle.height))
+        .collect::<Vec<_>>();
+    assert_eq!(origins, expected_origins);
+    assert_eq!(blocks[2].incoming_bundles, blocks[0].incoming_bundles[..3]);
+
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_validate_proposal<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let policy = ResourceControlPolicy {
+        maximum_block_proposal_size: 10_000,
+        ..ResourceControlPolicy::default()
+    };
+    let mut builder = TestBuilder::new(storage_builder, 4, 0)
+        .await?
+        .with_policy(policy);
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+    for _ in 0..2 {
+        sender
+            .transfer(None, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    receiver.synchronize_from_validators().await?;
+
+    // With the validators offline, the block receiving both transfers remains pending.
+    builder
+        .set_fault_type([0, 1, 2, 3], FaultType::Offline)
+        .await;
+    assert!(receiver.process_inbox_without_prepare().await.is_err());
+    let block = (*receiver.pending_proposal())
+        .clone()
+        .expect("the block should be pending");
+    assert_eq!(block.incoming_bundles.len(), 2);
+    assert_eq!(receiver.validate_proposal(&block, true).await?, vec![]);
+    let first = &block.incoming_bundles[0];
+    let second = &block.incoming_bundles[1];
+
+    // The bundles from an origin must be received in order.
+    let mut out_of_order = block.clone();
+    out_of_order.incoming_bundles.reverse();
+    let issues = receiver.validate_proposal(&out_of_order, false).await?;
+    assert_eq!(
+        issues,
+        vec![ProposalIssue::MessageOutOfOrder {
+            origin: first.origin.clone(),
+            height: first.bundle.height,
+            transaction_index: first.bundle.transaction_index,
+        }]
+    );
+
+    // Transfers are tracked, so they cannot be skipped.
+    let mut skipping = block.clone();
+    skipping.incoming_bundles.remove(0);
+    let issues = receiver.validate_proposal(&skipping, false).await?;
+    assert_eq!(
+        issues,
+        vec![ProposalIssue::MessageSkipped {
+            origin: first.origin.clone(),
+            height: first.bundle.height,
+            transaction_index: first.bundle.transaction_index,
+        }]
+    );
+
+    // A bundle that the local node didn't receive yet only causes a warning.
+    let mut unreceived_bundle = second.clone();
+    unreceived_bundle.bundle.height = BlockHeight::from(5);
+    let mut unreceived = block.clone();
+    unreceived.incoming_bundles.push(unreceived_bundle);
+    let issues = receiver.validate_proposal(&unreceived, false).await?;
+    assert_eq!(
+        issues,
+        vec![ProposalIssue::MessageNotReceived {
+            origin: second.origin.clone(),
+            height: BlockHeight::from(5),
+            transaction_index: second.bundle.transaction_index,
+        }]
+    );
+    assert_eq!(issues[0].severity(), ProposalIssueSeverity::Warning);
+
+    // Operations must be for applications registered on the chain.
+    let application_id = ApplicationId {
+        bytecode_id: BytecodeId::new(
+            CryptoHash::test_hash("contract"),
+            CryptoHash::test_hash("service"),
+        ),
+        creation: MessageId {
+            chain_id: sender.chain_id(),
+            height: BlockHeight::ZERO,
+            index: 0,
+        },
+    };
+    let mut unknown_application = block.clone();
+    unknown_application.operations.push(Operation::User {
+        application_id,
+        bytes: vec![],
+    });
+    let issues = receiver
+        .validate_proposal(&unknown_application, false)
+        .await?;
+    assert_eq!(
+        issues,
+        vec![ProposalIssue::UnknownApplication {
+            operation_index: 0,
+            application_id,
+        }]
+    );
+    assert_eq!(issues[0].severity(), ProposalIssueSeverity::Error);
+
+    // The received tokens count towards the balance, but are not enough.
+    let mut overspending = block.clone();
+    overspending
+        .operations
+        .push(Operation::System(SystemOperation::Transfer {
+            owner: None,
+            recipient: Recipient::chain(sender.chain_id()),
+            amount: Amount::from_tokens(3),
+        }));
+    let issues = receiver.validate_proposal(&overspending, false).await?;
+    assert_eq!(
+        issues,
+        vec![ProposalIssue::InsufficientBalance {
+            owner: None,
+            available: Amount::from_tokens(2),
+            required: Amount::from_tokens(3),
+        }]
+    );
+    // Executing the block in the local node finds the same problem.
+    let issues = receiver.validate_proposal(&overspending, true).await?;
+    assert_eq!(issues.len(), 2);
+    assert_matches!(issues[1], ProposalIssue::ExecutionFailed { .. });
+
+    // The proposal must not exceed the maximum size.
+    let mut oversized = block.clone();
+    oversized.operations.push(Operation::User {
+        application_id,
+        bytes: vec![0; 10_000],
+    });
+    let issues = receiver.validate_proposal(&oversized, false).await?;
+    assert_matches!(
+        issues[0],
+        ProposalIssue::ProposalTooLarge { size, maximum: 10_000 } if size > 10_000
+    );
+
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -2307,7 +3170,7 @@ where
     assert_matches!(
         result,
         Err(ChainClientError::ChainError(
-            ChainError::BlockProposalTooLarge
+            ChainError::BlockProposalTooLarge { .. }
         ))
     );
 
@@ -2321,3 +3184,43 @@ where
 
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_compare_state_hashes<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 2, 0).await?;
+    let client = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let chain_id = client.chain_id();
+
+    // Corrupt the state of an application in the storage of validator 0, before the chain is
+    // loaded by its worker.
+    let application_id = UserApplicationId::default();
+    let mut chain = builder.validator_storage(0).load_chain(chain_id).await?;
+    chain
+        .execution_state
+        .users
+        .try_load_entry_mut(&application_id)
+        .await?
+        .insert(b"key".to_vec(), b"diverged".to_vec())
+        .await?;
+    chain.save().await?;
+
+    let comparisons = client.compare_state_hashes().await?;
+    assert_eq!(comparisons.len(), 2);
+    assert_eq!(
+        comparisons[&builder.node(0).name()],
+        StateHashComparison::Diverged(vec![StateHashPart::Application(application_id)])
+    );
+    assert_eq!(
+        comparisons[&builder.node(1).name()],
+        StateHashComparison::Match
+    );
+    Ok(())
+}
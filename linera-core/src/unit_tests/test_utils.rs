@@ -10,7 +10,7 @@ use std::{
 
 use async_trait::async_trait;
 use futures::{
-    future::Either,
+    future::{self, Either},
     lock::{Mutex, MutexGuard},
     Future,
 };
@@ -20,7 +20,7 @@ use linera_base::{
     identifiers::{BlobId, ChainDescription, ChainId},
 };
 use linera_chain::{
-    data_types::BlockProposal,
+    data_types::{BlockProposal, LiteValue, LiteVote, ProposalContent},
     types::{
         CertificateKind, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate,
         LiteCertificate, Timeout, ValidatedBlock,
@@ -50,6 +50,7 @@ use {
 };
 
 use crate::{
+    chain_worker::StateSnapshotOptions,
     client::{ChainClient, Client},
     data_types::*,
     node::{
@@ -57,7 +58,7 @@ use crate::{
         ValidatorNodeProvider,
     },
     notifier::ChannelNotifier,
-    updater::DEFAULT_GRACE_PERIOD,
+    updater::{ValidatorRequestOptions, DEFAULT_GRACE_PERIOD},
     worker::{NetworkActions, Notification, ProcessableCertificate, WorkerState},
 };
 
@@ -70,10 +71,17 @@ pub enum FaultType {
     DontSendConfirmVote,
     DontProcessValidated,
     DontSendValidateVote,
+    Unresponsive,
+    Garbage,
+    Equivocating,
 }
 
 /// A validator used for testing. "Faulty" validators ignore block proposals (but not
 /// certificates or info queries) and have the wrong initial balance for all chains.
+/// "Unresponsive" validators never answer any request, and "garbage" validators process
+/// requests normally but answer them with chain information that doesn't match the signature.
+/// "Equivocating" validators vote like honest ones, but if a proposal is sent again in a round
+/// they already voted in, they vote for a different, made-up value.
 ///
 /// All methods are executed in spawned Tokio tasks, so that canceling a client task doesn't cause
 /// the validator's tasks to be canceled: In a real network, a validator also wouldn't cancel
@@ -83,6 +91,7 @@ where
     S: Storage,
 {
     state: WorkerState<S>,
+    key_pair: KeyPair,
     fault_type: FaultType,
     notifier: Arc<ChannelNotifier<Notification>>,
 }
@@ -155,6 +164,17 @@ where
         .await
     }
 
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<GenericCertificate<ConfirmedBlock>>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.spawn_and_receive(move |validator, sender| {
+            validator.do_handle_certificates(certificates, sender)
+        })
+        .await
+    }
+
     async fn handle_chain_info_query(
         &self,
         query: ChainInfoQuery,
@@ -250,10 +270,11 @@ impl<S> LocalValidatorClient<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
-    fn new(name: ValidatorName, state: WorkerState<S>) -> Self {
+    fn new(name: ValidatorName, key_pair: KeyPair, state: WorkerState<S>) -> Self {
         let client = LocalValidator {
             fault_type: FaultType::Honest,
             state,
+            key_pair,
             notifier: Arc::new(ChannelNotifier::default()),
         };
         Self {
@@ -266,6 +287,11 @@ where
         self.name
     }
 
+    /// Returns a copy of the validator's key pair, e.g. to forge signed messages.
+    pub async fn key_pair(&self) -> KeyPair {
+        self.client.lock().await.key_pair.copy()
+    }
+
     async fn set_fault_type(&self, fault_type: FaultType) {
         self.client.lock().await.fault_type = fault_type;
     }
@@ -292,6 +318,9 @@ where
         R: Future<Output = Result<(), T>> + Send,
         F: FnOnce(Self, oneshot::Sender<T>) -> R + Send + 'static,
     {
+        if self.fault_type().await == FaultType::Unresponsive {
+            return future::pending().await;
+        }
         let validator = self.clone();
         let (sender, receiver) = oneshot::channel();
         tokio::spawn(async move {
@@ -308,6 +337,10 @@ where
         sender: oneshot::Sender<Result<ChainInfoResponse, NodeError>>,
     ) -> Result<(), Result<ChainInfoResponse, NodeError>> {
         let mut validator = self.client.lock().await;
+        let content = proposal.content.clone();
+        let voted_in_round = validator.fault_type == FaultType::Equivocating
+            && Self::pending_vote_round(&validator, content.block.chain_id).await
+                == Some(content.round);
         let handle_block_proposal_result =
             Self::handle_block_proposal(proposal, &mut validator).await;
         let result = match handle_block_proposal_result {
@@ -315,10 +348,21 @@ where
                 handle_block_proposal_result.expect("handle_block_proposal_result should be Some")
             }
             _ => match validator.fault_type {
-                FaultType::Offline | FaultType::OfflineWithInfo => Err(NodeError::ClientIoError {
-                    error: "offline".to_string(),
-                }),
+                FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => {
+                    Err(NodeError::ClientIoError {
+                        error: "offline".to_string(),
+                    })
+                }
                 FaultType::Malicious => Err(ArithmeticError::Overflow.into()),
+                FaultType::Garbage => handle_block_proposal_result
+                    .expect("handle_block_proposal_result should be Some")
+                    .map(|(info, actions)| (Self::corrupt(info), actions)),
+                FaultType::Equivocating => match handle_block_proposal_result
+                    .expect("handle_block_proposal_result should be Some")
+                {
+                    Ok(_) if voted_in_round => Self::equivocate(&content, &validator).await,
+                    result => result,
+                },
                 FaultType::DontSendValidateVote => Err(NodeError::ClientIoError {
                     error: "refusing to validate".to_string(),
                 }),
@@ -337,11 +381,16 @@ where
         validator: &mut MutexGuard<'_, LocalValidator<S>>,
     ) -> Option<Result<(ChainInfoResponse, NetworkActions), NodeError>> {
         match validator.fault_type {
-            FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Malicious => None,
+            FaultType::Offline
+            | FaultType::OfflineWithInfo
+            | FaultType::Malicious
+            | FaultType::Unresponsive => None,
             FaultType::Honest
             | FaultType::DontSendConfirmVote
             | FaultType::DontProcessValidated
-            | FaultType::DontSendValidateVote => Some(
+            | FaultType::DontSendValidateVote
+            | FaultType::Garbage
+            | FaultType::Equivocating => Some(
                 validator
                     .state
                     .handle_block_proposal(proposal)
@@ -361,14 +410,16 @@ where
             | FaultType::DontSendConfirmVote
             | FaultType::Malicious
             | FaultType::DontProcessValidated
-            | FaultType::DontSendValidateVote => Some(
+            | FaultType::DontSendValidateVote
+            | FaultType::Garbage
+            | FaultType::Equivocating => Some(
                 validator
                     .state
                     .fully_handle_certificate_with_notifications(certificate, &validator.notifier)
                     .await
                     .map_err(Into::into),
             ),
-            FaultType::Offline | FaultType::OfflineWithInfo => None,
+            FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => None,
         }
     }
 
@@ -417,12 +468,18 @@ where
                 | FaultType::DontSendConfirmVote
                 | FaultType::DontProcessValidated
                 | FaultType::Malicious
-                | FaultType::DontSendValidateVote => {
+                | FaultType::DontSendValidateVote
+                | FaultType::Equivocating => {
                     handle_certificate_result.expect("handle_certificate_result should be Some")
                 }
-                FaultType::Offline | FaultType::OfflineWithInfo => Err(NodeError::ClientIoError {
-                    error: "offline".to_string(),
-                }),
+                FaultType::Garbage => handle_certificate_result
+                    .expect("handle_certificate_result should be Some")
+                    .map(Self::corrupt),
+                FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => {
+                    Err(NodeError::ClientIoError {
+                        error: "offline".to_string(),
+                    })
+                }
             },
         }
     }
@@ -439,6 +496,81 @@ where
         sender.send(result)
     }
 
+    async fn do_handle_certificates(
+        self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        sender: oneshot::Sender<Result<ChainInfoResponse, NodeError>>,
+    ) -> Result<(), Result<ChainInfoResponse, NodeError>> {
+        let validator = self.client.lock().await;
+        let result = match validator.fault_type {
+            FaultType::Offline | FaultType::OfflineWithInfo | FaultType::Unresponsive => {
+                Err(NodeError::ClientIoError {
+                    error: "offline".to_string(),
+                })
+            }
+            FaultType::Honest
+            | FaultType::DontSendConfirmVote
+            | FaultType::Malicious
+            | FaultType::DontProcessValidated
+            | FaultType::DontSendValidateVote
+            | FaultType::Garbage
+            | FaultType::Equivocating => {
+                let result = validator
+                    .state
+                    .fully_handle_confirmed_certificates_with_notifications(
+                        certificates,
+                        &validator.notifier,
+                    )
+                    .await
+                    .map_err(Into::into);
+                if validator.fault_type == FaultType::Garbage {
+                    result.map(Self::corrupt)
+                } else {
+                    result
+                }
+            }
+        };
+        sender.send(result)
+    }
+
+    /// Returns the round of the validator's current vote on the chain, if any.
+    async fn pending_vote_round(validator: &LocalValidator<S>, chain_id: ChainId) -> Option<Round> {
+        let query = ChainInfoQuery::new(chain_id).with_manager_values();
+        let (response, _) = validator.state.handle_chain_info_query(query).await.ok()?;
+        Some(response.info.manager.pending?.round)
+    }
+
+    /// Votes for a made-up value in the proposal's round, and returns the vote in a correctly
+    /// signed response.
+    async fn equivocate(
+        content: &ProposalContent,
+        validator: &LocalValidator<S>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), NodeError> {
+        let chain_id = content.block.chain_id;
+        let kind = if content.round == Round::Fast {
+            CertificateKind::Confirmed
+        } else {
+            CertificateKind::Validated
+        };
+        let value = LiteValue {
+            value_hash: CryptoHash::test_hash(format!("{content:?}")),
+            chain_id,
+            kind,
+        };
+        let vote = LiteVote::new(value, content.round, &validator.key_pair);
+        let query = ChainInfoQuery::new(chain_id).with_manager_values();
+        let (mut response, actions) = validator.state.handle_chain_info_query(query).await?;
+        response.info.manager.pending = Some(vote);
+        response.sign(&validator.key_pair);
+        Ok((response, actions))
+    }
+
+    /// Changes the chain information in the response, so that the signature is invalid.
+    fn corrupt(mut response: ChainInfoResponse) -> ChainInfoResponse {
+        response.info.chain_balance = Amount::MAX;
+        response
+    }
+
     async fn do_handle_chain_info_query(
         self,
         query: ChainInfoQuery,
@@ -663,6 +795,7 @@ pub struct TestBuilder<B: StorageBuilder> {
     validator_clients: Vec<LocalValidatorClient<B::Storage>>,
     validator_storages: HashMap<ValidatorName, B::Storage>,
     chain_client_storages: Vec<B::Storage>,
+    state_snapshots: StateSnapshotOptions,
 }
 
 #[async_trait]
@@ -739,6 +872,7 @@ where
         for (i, key_pair) in key_pairs.into_iter().enumerate() {
             let name = ValidatorName(key_pair.public());
             let storage = storage_builder.build().await?;
+            let validator_key_pair = key_pair.copy();
             let state = WorkerState::new(
                 format!("Node {}", i),
                 Some(key_pair),
@@ -746,8 +880,9 @@ where
                 NonZeroUsize::new(100).expect("Chain worker limit should not be zero"),
             )
             .with_allow_inactive_chains(false)
-            .with_allow_messages_from_deprecated_epochs(false);
-            let validator = LocalValidatorClient::new(name, state);
+            .with_allow_messages_from_deprecated_epochs(false)
+            .with_serve_state_hash_breakdowns(true);
+            let validator = LocalValidatorClient::new(name, validator_key_pair, state);
             if i < with_faulty_validators {
                 faulty_validators.insert(name);
                 validator.set_fault_type(FaultType::Malicious).await;
@@ -767,6 +902,7 @@ where
             validator_clients,
             validator_storages,
             chain_client_storages: Vec::new(),
+            state_snapshots: StateSnapshotOptions::default(),
         })
     }
 
@@ -776,6 +912,12 @@ where
         self
     }
 
+    /// Makes the clients created from now on keep snapshots of their chains' states.
+    pub fn with_state_snapshots(mut self, options: StateSnapshotOptions) -> Self {
+        self.state_snapshots = options;
+        self
+    }
+
     pub async fn set_fault_type(&mut self, indexes: impl AsRef<[usize]>, fault_type: FaultType) {
         let mut faulty_validators = vec![];
         for index in indexes.as_ref() {
@@ -871,6 +1013,11 @@ where
         &mut self.validator_clients[index]
     }
 
+    /// Returns the storage of the validator with the given `index`.
+    pub fn validator_storage(&self, index: usize) -> &B::Storage {
+        &self.validator_storages[&self.validator_clients[index].name]
+    }
+
     pub async fn make_storage(&mut self) -> anyhow::Result<B::Storage> {
         Ok(self
             .genesis_storage_builder
@@ -904,6 +1051,8 @@ where
             format!("Client node for {:.8}", chain_id),
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
+            ValidatorRequestOptions::default(),
+            self.state_snapshots,
         ));
         Ok(builder.create_chain_client(
             chain_id,
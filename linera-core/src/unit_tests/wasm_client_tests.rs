@@ -13,7 +13,7 @@
 #![allow(clippy::large_futures)]
 #![cfg(any(feature = "wasmer", feature = "wasmtime"))]
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, num::NonZeroU64};
 
 use assert_matches::assert_matches;
 use async_graphql::Request;
@@ -25,8 +25,8 @@ use linera_base::{
 };
 use linera_chain::data_types::{EventRecord, MessageAction, OutgoingMessage};
 use linera_execution::{
-    Message, MessageKind, Operation, QueryOutcome, ResourceControlPolicy, SystemMessage,
-    WasmRuntime,
+    Message, MessageKind, Operation, Query, QueryOutcome, QueryResponse, ResourceControlPolicy,
+    SystemMessage, WasmRuntime,
 };
 use serde_json::json;
 use test_case::test_case;
@@ -39,9 +39,14 @@ use crate::client::client_tests::RocksDbStorageBuilder;
 use crate::client::client_tests::ScyllaDbStorageBuilder;
 #[cfg(feature = "storage-service")]
 use crate::client::client_tests::ServiceStorageBuilder;
-use crate::client::{
-    client_tests::{MemoryStorageBuilder, StorageBuilder, TestBuilder},
-    ChainClientError,
+use crate::{
+    chain_worker::StateSnapshotOptions,
+    client::{
+        client_tests::{MemoryStorageBuilder, StorageBuilder, TestBuilder},
+        BlanketMessagePolicy, ChainClientError, MessagePolicy,
+    },
+    local_node::LocalNodeError,
+    worker::WorkerError,
 };
 
 #[cfg_attr(feature = "wasmer", test_case(WasmRuntime::Wasmer ; "wasmer"))]
@@ -668,6 +673,123 @@ where
     Ok(())
 }
 
+#[cfg_attr(feature = "wasmer", test_case(WasmRuntime::Wasmer ; "wasmer"))]
+#[cfg_attr(feature = "wasmtime", test_case(WasmRuntime::Wasmtime ; "wasmtime"))]
+#[test_log::test(tokio::test)]
+async fn test_memory_receive_tokens_of_unregistered_application(
+    wasm_runtime: WasmRuntime,
+) -> anyhow::Result<()> {
+    let storage_builder = MemoryStorageBuilder::with_wasm_runtime(wasm_runtime);
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let mut receiver = builder.add_root_chain(2, Amount::ONE).await?;
+
+    let (contract_path, service_path) =
+        linera_execution::wasm_test::get_example_bytecode_paths("fungible")?;
+    let (bytecode_id, _cert) = sender
+        .publish_bytecode(
+            Bytecode::load_from_file(contract_path).await?,
+            Bytecode::load_from_file(service_path).await?,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let bytecode_id = bytecode_id
+        .with_abi::<fungible::FungibleTokenAbi, fungible::Parameters, fungible::InitialState>();
+
+    let sender_owner = AccountOwner::User(Owner::from(sender.key_pair().await?.public()));
+    let receiver_owner = AccountOwner::User(Owner::from(receiver.key_pair().await?.public()));
+    let accounts = BTreeMap::from_iter([(sender_owner, Amount::from_tokens(1_000_000))]);
+    let state = fungible::InitialState { accounts };
+    let params = fungible::Parameters::new("FUN");
+    let (application_id, _cert) = sender
+        .create_application(bytecode_id, &params, &state, vec![])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let transfer = fungible::Operation::Transfer {
+        owner: sender_owner,
+        amount: 100.into(),
+        target_account: fungible::Account {
+            chain_id: receiver.chain_id(),
+            owner: receiver_owner,
+        },
+    };
+    let cert = sender
+        .execute_operation(Operation::user(application_id, &transfer)?)
+        .await
+        .unwrap()
+        .unwrap();
+    let block = cert.block();
+    let registration_id = (0..)
+        .zip(block.messages().iter().flatten())
+        .find_map(|(index, outgoing)| {
+            matches!(
+                outgoing.message,
+                Message::System(SystemMessage::RegisterApplications { .. })
+            )
+            .then(|| block.message_id(index))
+        })
+        .expect("the transfer should register the application on the receiver");
+
+    receiver.synchronize_from_validators().await.unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await
+        .unwrap();
+    // Reject the registration in a block of its own, so that the receiver never registers the
+    // application before the tokens arrive.
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Ignore, None);
+    receiver
+        .reject_message(registration_id)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None);
+
+    // The application is registered from the block that created it, and the tokens are
+    // credited instead of being parked.
+    let certs = receiver.process_inbox().await.unwrap().0;
+    assert_eq!(certs.len(), 1);
+    let body = &certs[0].block().body;
+    assert!(body
+        .incoming_bundles
+        .iter()
+        .all(|incoming| incoming.action == MessageAction::Accept));
+    assert!(body
+        .oracle_responses
+        .iter()
+        .flatten()
+        .any(|response| matches!(
+            response,
+            OracleResponse::Application(description)
+            if ApplicationId::from(&**description) == application_id.forget_abi()
+        )));
+
+    // The receiver owns exactly the 100 tokens.
+    let transfer_back = |amount: u128| fungible::Operation::Transfer {
+        owner: receiver_owner,
+        amount: amount.into(),
+        target_account: fungible::Account {
+            chain_id: sender.chain_id(),
+            owner: sender_owner,
+        },
+    };
+    assert!(receiver
+        .execute_operation(Operation::user(application_id, &transfer_back(101))?)
+        .await
+        .is_err());
+    receiver.clear_pending_block();
+    receiver
+        .execute_operation(Operation::user(application_id, &transfer_back(100))?)
+        .await
+        .unwrap()
+        .unwrap();
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "wasmer", test_case(WasmRuntime::Wasmer; "wasmer"))]
 #[cfg_attr(feature = "wasmtime", test_case(WasmRuntime::Wasmtime; "wasmtime"))]
 #[test_log::test(tokio::test)]
@@ -914,3 +1036,83 @@ async fn test_memory_fuel_limit(wasm_runtime: WasmRuntime) -> anyhow::Result<()>
 
     Ok(())
 }
+
+#[cfg_attr(feature = "wasmer", test_case(WasmRuntime::Wasmer ; "wasmer"))]
+#[cfg_attr(feature = "wasmtime", test_case(WasmRuntime::Wasmtime ; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_memory_query_application_at_past_heights(
+    wasm_runtime: WasmRuntime,
+) -> anyhow::Result<()> {
+    let storage_builder = MemoryStorageBuilder::with_wasm_runtime(wasm_runtime);
+    // Snapshot every other block, so that most queries re-execute a block on top of one.
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_state_snapshots(StateSnapshotOptions {
+            interval: NonZeroU64::new(2),
+            retained: 3,
+        });
+    let publisher = builder.add_root_chain(0, Amount::from_tokens(3)).await?;
+
+    let (contract_path, service_path) =
+        linera_execution::wasm_test::get_example_bytecode_paths("counter")?;
+    let (bytecode_id, _cert) = publisher
+        .publish_bytecode(
+            Bytecode::load_from_file(contract_path).await?,
+            Bytecode::load_from_file(service_path).await?,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let bytecode_id = bytecode_id.with_abi::<counter::CounterAbi, (), u64>();
+
+    let initial_value = 10_u64;
+    let (application_id, _) = publisher
+        .create_application(bytecode_id, &(), &initial_value, vec![])
+        .await
+        .unwrap()
+        .unwrap();
+    // The height after each block, and the counter's value at that height.
+    let mut heights = vec![(publisher.next_block_height(), initial_value)];
+    for value in initial_value + 1..=initial_value + 6 {
+        publisher
+            .execute_operation(Operation::user(application_id, &1_u64)?)
+            .await
+            .unwrap()
+            .unwrap();
+        heights.push((publisher.next_block_height(), value));
+    }
+
+    let query_value = |height| {
+        let query = Query::user(application_id, &Request::new("{ value }")).unwrap();
+        let publisher = &publisher;
+        async move {
+            let outcome = publisher.query_application(query, Some(height)).await?;
+            let QueryResponse::User(bytes) = outcome.response else {
+                panic!("Unexpected response for user query");
+            };
+            let response = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+            Ok::<_, anyhow::Error>(response["data"]["value"].as_u64())
+        }
+    };
+
+    // The last three snapshots cover at least the last five blocks.
+    for (height, value) in &heights[3..] {
+        assert_eq!(query_value(*height).await?, Some(*value));
+    }
+
+    // The state right after creating the application is six blocks behind.
+    let (height, _) = heights[0];
+    let error = query_value(height).await.unwrap_err();
+    assert_matches!(
+        error.downcast_ref::<ChainClientError>(),
+        Some(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
+            WorkerError::StateNotRetained {
+                height: refused_height,
+                oldest_retained_height,
+                ..
+            }
+        ))) if *refused_height == height && *oldest_retained_height > height
+    );
+
+    Ok(())
+}
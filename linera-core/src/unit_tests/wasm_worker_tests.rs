@@ -192,6 +192,7 @@ where
         parameters: parameters_bytes.clone(),
         instantiation_argument: initial_value_bytes.clone(),
         required_application_ids: vec![],
+        max_query_response_bytes: None,
     };
     let application_id = UserApplicationId {
         bytecode_id,
@@ -206,6 +207,7 @@ where
         creation: application_id.creation,
         required_application_ids: vec![],
         parameters: parameters_bytes,
+        max_query_response_bytes: None,
     };
     let create_block = make_first_block(creator_chain.into())
         .with_timestamp(2)
@@ -275,6 +277,7 @@ where
         height: run_block.height,
         round: Some(0),
         index: Some(0),
+        call_depth: 0,
     };
     let mut controller = ResourceController::default();
     creator_state
@@ -11,26 +11,28 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     iter,
     num::NonZeroUsize,
-    sync::{Arc, Mutex},
+    sync::{atomic::Ordering, mpsc, Arc, Mutex},
     time::Duration,
 };
 
 use assert_matches::assert_matches;
+use futures::future::Either;
 use linera_base::{
     crypto::{CryptoHash, *},
     data_types::*,
     hashed::Hashed,
     identifiers::{
         Account, AccountOwner, ChainDescription, ChainId, ChannelName, Destination,
-        GenericApplicationId, MessageId, Owner,
+        GenericApplicationId, MessageId, Owner, UserApplicationId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_chain::{
     data_types::{
         BlockExecutionOutcome, BlockProposal, ChainAndHeight, ChannelFullName, ExecutedBlock,
-        IncomingBundle, LiteValue, LiteVote, Medium, MessageAction, MessageBundle, Origin,
-        OutgoingMessage, PostedMessage, ProposedBlock, SignatureAggregator,
+        FeePayer, IncomingBundle, LiteValue, LiteVote, Medium, MessageAction, MessageBundle,
+        Origin, OutgoingMessage, PostedMessage, ProposedBlock, ResourceEstimate,
+        SignatureAggregator,
     },
     manager::LockingBlock,
     test::{make_child_block, make_first_block, BlockTestExt, MessageTestExt, VoteTestExt},
@@ -43,13 +45,15 @@ use linera_chain::{
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemMessage, SystemOperation,
+        AdminOperation, OpenChainConfig, Recipient, Sponsorship, SystemChannel, SystemMessage,
+        SystemOperation,
     },
-    test_utils::{ExpectedCall, RegisterMockApplication, SystemExecutionState},
-    ChannelSubscription, ExecutionError, Message, MessageKind, Query, QueryContext, QueryOutcome,
-    QueryResponse, SystemExecutionError, SystemQuery, SystemResponse,
+    test_utils::{ExpectedCall, MockApplication, RegisterMockApplication, SystemExecutionState},
+    ChannelSubscription, ContractRuntime as _, ExecutionError, Message, MessageKind, Operation,
+    Query, QueryContext, QueryOutcome, QueryResponse, ResourceControlPolicy, SystemExecutionError,
+    SystemQuery, SystemResponse,
 };
-use linera_storage::{DbStorage, Storage, TestClock};
+use linera_storage::{DbStorage, ShutdownMarker, Storage, TestClock};
 use linera_views::{
     memory::MemoryStore,
     random::generate_test_namespace,
@@ -58,6 +62,7 @@ use linera_views::{
 };
 use test_case::test_case;
 use test_log::test;
+use tokio::sync::oneshot;
 
 #[cfg(feature = "dynamodb")]
 use crate::test_utils::DynamoDbStorageBuilder;
@@ -70,9 +75,9 @@ use crate::{
     data_types::*,
     test_utils::{MemoryStorageBuilder, StorageBuilder},
     worker::{
-        Notification,
+        NetworkActions, Notification,
         Reason::{self, NewBlock, NewIncomingBundle},
-        WorkerError, WorkerState,
+        WorkerError, WorkerState, MAX_CHAINS_IN_SHUTDOWN_MARKER,
     },
 };
 
@@ -1161,6 +1166,113 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_rejected_block_proposal_leaves_no_writes<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let sender_key_pair = KeyPair::generate();
+    let (_, worker) = init_worker_with_chains(
+        storage.clone(),
+        vec![
+            (
+                ChainDescription::Root(1),
+                sender_key_pair.public().into(),
+                Amount::from_tokens(5),
+            ),
+            (
+                ChainDescription::Root(2),
+                PublicKey::test_key(2).into(),
+                Amount::ZERO,
+            ),
+        ],
+    )
+    .await;
+    let state_hash = storage
+        .load_chain(ChainId::root(1))
+        .await?
+        .execution_state
+        .crypto_hash()
+        .await?;
+
+    // The first transfer is executed before the second one fails.
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::ONE)
+        .with_simple_transfer(ChainId::root(2), Amount::from_tokens(1000))
+        .with_authenticated_signer(Some(sender_key_pair.public().into()))
+        .into_first_proposal(&sender_key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(block_proposal).await,
+        Err(WorkerError::ChainError(error)) if matches!(
+            &*error,
+            ChainError::ExecutionError(_, ChainExecutionContext::Operation(1))
+        )
+    );
+
+    let chain = storage.load_chain(ChainId::root(1)).await?;
+    assert_eq!(chain.execution_state.crypto_hash().await?, state_hash);
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        Amount::from_tokens(5)
+    );
+    assert!(chain.manager.validated_vote().is_none());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_accepted_block_proposal_leaves_no_writes<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let sender_key_pair = KeyPair::generate();
+    let (_, worker) = init_worker_with_chains(
+        storage.clone(),
+        vec![(
+            ChainDescription::Root(1),
+            sender_key_pair.public().into(),
+            Amount::from_tokens(5),
+        )],
+    )
+    .await;
+    let state_hash = storage
+        .load_chain(ChainId::root(1))
+        .await?
+        .execution_state
+        .crypto_hash()
+        .await?;
+
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_simple_transfer(ChainId::root(2), Amount::ONE)
+        .with_authenticated_signer(Some(sender_key_pair.public().into()))
+        .into_first_proposal(&sender_key_pair);
+    let (chain_info_response, _actions) = worker.handle_block_proposal(block_proposal).await?;
+    chain_info_response.check(&ValidatorName(worker.public_key()))?;
+
+    // Only the vote is saved: the execution of the block stays in the speculative chain state.
+    let chain = storage.load_chain(ChainId::root(1)).await?;
+    assert!(chain.manager.validated_vote().is_some());
+    assert_eq!(chain.execution_state.crypto_hash().await?, state_hash);
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        Amount::from_tokens(5)
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -1905,7 +2017,7 @@ where
     .await;
     assert_eq!(
         worker
-            .query_application(ChainId::root(1), Query::System(SystemQuery))
+            .query_application(ChainId::root(1), Query::System(SystemQuery), None)
             .await?,
         QueryOutcome {
             response: QueryResponse::System(SystemResponse {
@@ -1917,7 +2029,7 @@ where
     );
     assert_eq!(
         worker
-            .query_application(ChainId::root(2), Query::System(SystemQuery))
+            .query_application(ChainId::root(2), Query::System(SystemQuery), None)
             .await?,
         QueryOutcome {
             response: QueryResponse::System(SystemResponse {
@@ -1952,7 +2064,7 @@ where
     assert!(info.manager.pending.is_none());
     assert_eq!(
         worker
-            .query_application(ChainId::root(1), Query::System(SystemQuery))
+            .query_application(ChainId::root(1), Query::System(SystemQuery), None)
             .await?,
         QueryOutcome {
             response: QueryResponse::System(SystemResponse {
@@ -1993,7 +2105,7 @@ where
 
     assert_eq!(
         worker
-            .query_application(ChainId::root(2), Query::System(SystemQuery))
+            .query_application(ChainId::root(2), Query::System(SystemQuery), None)
             .await?,
         QueryOutcome {
             response: QueryResponse::System(SystemResponse {
@@ -2039,6 +2151,121 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_queries_pinned_to_height_during_block_execution<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let sender_key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker_with_chain(
+        storage_builder.build().await?,
+        ChainDescription::Root(1),
+        sender_key_pair.public().into(),
+        Amount::from_tokens(5),
+    )
+    .await;
+    let chain_id = ChainId::root(1);
+
+    // Prepare five blocks, each transferring one token away.
+    let mut certificates: Vec<ConfirmedBlockCertificate> = Vec::new();
+    for remaining in (0..5).rev() {
+        let certificate = make_simple_transfer_certificate(
+            ChainDescription::Root(1),
+            &sender_key_pair,
+            ChainId::root(2),
+            Amount::ONE,
+            Vec::new(),
+            &committee,
+            Amount::from_tokens(remaining),
+            &worker,
+            certificates.last(),
+        )
+        .await;
+        certificates.push(certificate);
+    }
+
+    let execute_blocks = async {
+        for certificate in certificates {
+            worker
+                .fully_handle_certificate_with_notifications(certificate, &())
+                .await?;
+            tokio::task::yield_now().await;
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    let run_queries = async {
+        for _ in 0..10 {
+            for height in 0..=5 {
+                // A query pinned to a height either sees exactly the state at that height, or
+                // fails because the chain is not there yet, or no longer keeps that state.
+                match worker
+                    .query_application(
+                        chain_id,
+                        Query::System(SystemQuery),
+                        Some(BlockHeight(height)),
+                    )
+                    .await
+                {
+                    Ok(outcome) => assert_eq!(
+                        outcome.response,
+                        QueryResponse::System(SystemResponse {
+                            chain_id,
+                            balance: Amount::from_tokens(5 - u128::from(height)),
+                        })
+                    ),
+                    Err(WorkerError::UnexpectedBlockHeight {
+                        expected_block_height,
+                        found_block_height,
+                    }) => {
+                        assert_eq!(expected_block_height, BlockHeight(height));
+                        assert!(found_block_height < BlockHeight(height));
+                    }
+                    Err(WorkerError::StateNotRetained {
+                        height: refused_height,
+                        oldest_retained_height,
+                        ..
+                    }) => {
+                        assert_eq!(refused_height, BlockHeight(height));
+                        assert!(oldest_retained_height > BlockHeight(height));
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    tokio::try_join!(execute_blocks, run_queries)?;
+
+    let outcome = worker
+        .query_application(chain_id, Query::System(SystemQuery), Some(BlockHeight(5)))
+        .await?;
+    assert_eq!(
+        outcome.response,
+        QueryResponse::System(SystemResponse {
+            chain_id,
+            balance: Amount::ZERO,
+        })
+    );
+    assert_matches!(
+        worker
+            .query_application(chain_id, Query::System(SystemQuery), Some(BlockHeight(2)))
+            .await,
+        Err(WorkerError::StateNotRetained {
+            chain_id: id,
+            height: BlockHeight(2),
+            oldest_retained_height: BlockHeight(5),
+        }) if id == chain_id
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -3418,6 +3645,78 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_weighted_round_leaders<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let chain_id = ChainId::root(0);
+    let key_pairs = generate_key_pairs(3);
+    let owner0 = Owner::from(key_pairs[0].public());
+    let owner1 = Owner::from(key_pairs[1].public());
+    let balances = vec![(ChainDescription::Root(0), owner0, Amount::from_tokens(2))];
+    let (committee, worker) = init_worker_with_chains(storage, balances).await;
+
+    // Add another owner with all the weight, and configure one multi-leader round.
+    let block0 = make_first_block(chain_id)
+        .with_operation(SystemOperation::ChangeOwnership {
+            super_owners: Vec::new(),
+            owners: vec![(owner0, 0), (owner1, 100)],
+            multi_leader_rounds: 1,
+            open_multi_leader_rounds: false,
+            timeout_config: TimeoutConfig::default(),
+        })
+        .with_authenticated_signer(Some(owner0));
+    let (executed_block0, _) = worker.stage_block_execution(block0, None).await?;
+    let value0 = Hashed::new(ConfirmedBlock::new(executed_block0));
+    let certificate0 = make_certificate(&committee, &worker, value0.clone());
+    let response = worker
+        .fully_handle_certificate_with_notifications(certificate0, &())
+        .await?;
+    assert_eq!(response.info.manager.current_round, Round::MultiLeader(0));
+    assert_eq!(response.info.manager.leader, None);
+
+    // In the multi-leader round, a key that is not an owner cannot propose.
+    let proposal =
+        make_child_block(&value0).into_proposal_with_round(&key_pairs[2], Round::MultiLeader(0));
+    let result = worker.handle_block_proposal(proposal).await;
+    assert_matches!(result, Err(WorkerError::InvalidOwner));
+
+    // In every single-leader round, the leader is owner 1, since owner 0 has no weight.
+    let value_timeout = Hashed::new(Timeout::new(chain_id, BlockHeight::from(1), Epoch::ZERO));
+    let mut round = Round::MultiLeader(0);
+    for expected_round in (0..5).map(Round::SingleLeader) {
+        let certificate_timeout =
+            make_certificate_with_round(&committee, &worker, value_timeout.clone(), round);
+        let (response, _) = worker
+            .handle_timeout_certificate(certificate_timeout)
+            .await?;
+        assert_eq!(response.info.manager.current_round, expected_round);
+        assert_eq!(response.info.manager.leader, Some(owner1));
+
+        // Neither owner 0 nor the unauthorized key can propose in this round.
+        for key_pair in [&key_pairs[0], &key_pairs[2]] {
+            let proposal =
+                make_child_block(&value0).into_proposal_with_round(key_pair, expected_round);
+            let result = worker.handle_block_proposal(proposal).await;
+            assert_matches!(result, Err(WorkerError::InvalidOwner));
+        }
+        round = expected_round;
+    }
+
+    // Owner 1 can.
+    let proposal = make_child_block(&value0)
+        .with_authenticated_signer(Some(owner1))
+        .into_proposal_with_round(&key_pairs[1], round);
+    worker.handle_block_proposal(proposal).await?;
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -3801,6 +4100,10 @@ where
         chain_id,
         next_block_height: BlockHeight(0),
         local_time,
+        block_hash: None,
+        block_timestamp: Timestamp::from(0),
+        maximum_response_size: u64::MAX,
+        authenticated_signer: None,
     });
 
     for query_context in query_contexts {
@@ -3816,12 +4119,15 @@ where
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
     for query_time in query_times {
         clock.set(query_time);
 
         assert_eq!(
-            worker.query_application(chain_id, query.clone()).await?,
+            worker
+                .query_application(chain_id, query.clone(), None)
+                .await?,
             QueryOutcome {
                 response: QueryResponse::User(vec![]),
                 operations: vec![],
@@ -3898,6 +4204,7 @@ where
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     let query_contexts_before_new_block =
@@ -3907,14 +4214,10 @@ where
                 chain_id,
                 next_block_height: BlockHeight(0),
                 local_time,
-            });
-    let query_contexts_after_new_block =
-        queries_after_new_block
-            .clone()
-            .map(|local_time| QueryContext {
-                chain_id,
-                next_block_height: BlockHeight(1),
-                local_time,
+                block_hash: None,
+                block_timestamp: Timestamp::from(0),
+                maximum_response_size: u64::MAX,
+                authenticated_signer: None,
             });
 
     for query_context in query_contexts_before_new_block {
@@ -3931,7 +4234,9 @@ where
         clock.set(local_time);
 
         assert_eq!(
-            worker.query_application(chain_id, query.clone()).await?,
+            worker
+                .query_application(chain_id, query.clone(), None)
+                .await?,
             QueryOutcome {
                 response: QueryResponse::User(vec![]),
                 operations: vec![],
@@ -3949,7 +4254,9 @@ where
         clock.set(local_time);
 
         assert_eq!(
-            worker.query_application(chain_id, query.clone()).await?,
+            worker
+                .query_application(chain_id, query.clone(), None)
+                .await?,
             QueryOutcome {
                 response: QueryResponse::User(vec![]),
                 operations: vec![],
@@ -3980,11 +4287,24 @@ where
         .with(block),
     ));
     let certificate = make_certificate(&committee, &worker, value);
+    let block_hash = certificate.hash();
     worker
         .handle_confirmed_certificate(certificate, None)
         .await?;
 
-    for query_context in query_contexts_after_new_block.clone() {
+    let query_contexts_after_new_block =
+        queries_after_new_block
+            .clone()
+            .map(|local_time| QueryContext {
+                chain_id,
+                next_block_height: BlockHeight(1),
+                local_time,
+                block_hash: Some(block_hash),
+                block_timestamp: Timestamp::from(BLOCK_TIMESTAMP),
+                maximum_response_size: u64::MAX,
+                authenticated_signer: None,
+            });
+    for query_context in query_contexts_after_new_block {
         application.expect_call(ExpectedCall::handle_query(
             move |_runtime, context, query| {
                 assert_eq!(context, query_context);
@@ -3998,7 +4318,9 @@ where
         clock.set(local_time);
 
         assert_eq!(
-            worker.query_application(chain_id, query.clone()).await?,
+            worker
+                .query_application(chain_id, query.clone(), None)
+                .await?,
             QueryOutcome {
                 response: QueryResponse::User(vec![]),
                 operations: vec![],
@@ -4013,3 +4335,874 @@ where
 
     Ok(())
 }
+
+/// Creates `count` consecutive certificates for the chain, each burning one atto.
+async fn make_burn_certificates<S>(
+    chain_description: ChainDescription,
+    key_pair: &KeyPair,
+    initial_balance: Amount,
+    count: u128,
+    committee: &Committee,
+    worker: &WorkerState<S>,
+) -> Vec<ConfirmedBlockCertificate>
+where
+    S: Storage,
+{
+    let mut certificates: Vec<ConfirmedBlockCertificate> = Vec::new();
+    for index in 1..=count {
+        let certificate = make_transfer_certificate(
+            chain_description,
+            key_pair,
+            None,
+            Recipient::Burn,
+            Amount::from_attos(1),
+            Vec::new(),
+            committee,
+            initial_balance.saturating_sub(Amount::from_attos(index)),
+            BTreeMap::new(),
+            worker,
+            certificates.last(),
+        )
+        .await;
+        certificates.push(certificate);
+    }
+    certificates
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_handle_confirmed_certificates_batches_commits<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let balance = Amount::from_tokens(1);
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        [1, 2].map(|index| {
+            (
+                ChainDescription::Root(index),
+                key_pair.public().into(),
+                balance,
+            )
+        }),
+    )
+    .await;
+
+    // Sync one chain block by block.
+    let certificates = make_burn_certificates(
+        ChainDescription::Root(1),
+        &key_pair,
+        balance,
+        500,
+        &committee,
+        &worker,
+    )
+    .await;
+    for certificate in certificates {
+        worker
+            .handle_confirmed_certificate(certificate, None)
+            .await?;
+    }
+    let individual_commits = worker.count_commits(ChainId::root(1)).await?;
+    assert_eq!(individual_commits, 500);
+
+    // Sync the other chain in a single batch.
+    let certificates = make_burn_certificates(
+        ChainDescription::Root(2),
+        &key_pair,
+        balance,
+        500,
+        &committee,
+        &worker,
+    )
+    .await;
+    let (response, _actions) = worker
+        .handle_confirmed_certificates(certificates, None)
+        .await?;
+    assert_eq!(response.info.next_block_height, BlockHeight::from(500));
+    assert_eq!(
+        response.info.chain_balance,
+        balance.saturating_sub(Amount::from_attos(500))
+    );
+    let batch_commits = worker.count_commits(ChainId::root(2)).await?;
+    assert_eq!(batch_commits, 5);
+    assert!(batch_commits * 10 <= individual_commits);
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_handle_confirmed_certificates_partial_failure<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let balance = Amount::from_tokens(1);
+    let chain_id = ChainId::root(1);
+    let (committee, worker) = init_worker_with_chains(
+        storage_builder.build().await?,
+        [1, 2].map(|index| {
+            (
+                ChainDescription::Root(index),
+                key_pair.public().into(),
+                balance,
+            )
+        }),
+    )
+    .await;
+    let mut certificates = make_burn_certificates(
+        ChainDescription::Root(1),
+        &key_pair,
+        balance,
+        300,
+        &committee,
+        &worker,
+    )
+    .await;
+    // Replace the certificate at index 250 with one for an unknown epoch.
+    certificates[250] = make_transfer_certificate_for_epoch(
+        ChainDescription::Root(1),
+        &key_pair,
+        Some(key_pair.public().into()),
+        None,
+        Recipient::Burn,
+        Amount::from_attos(1),
+        Vec::new(),
+        Epoch::from(1),
+        &committee,
+        balance.saturating_sub(Amount::from_attos(251)),
+        BTreeMap::new(),
+        &worker,
+        Some(&certificates[249]),
+    )
+    .await;
+
+    let result = worker
+        .handle_confirmed_certificates(certificates.clone(), None)
+        .await;
+    assert_matches!(
+        result,
+        Err(WorkerError::CertificateBatch { index: 250, error })
+            if matches!(*error, WorkerError::InvalidEpoch { .. })
+    );
+    // The certificate was rejected before it was executed: the ones before it were committed.
+    let tip = worker
+        .chain_state_view(chain_id)
+        .await?
+        .tip_state
+        .get()
+        .clone();
+    assert_eq!(tip.next_block_height, BlockHeight::from(250));
+    assert_eq!(tip.block_hash, Some(certificates[249].hash()));
+    assert_eq!(worker.count_commits(chain_id).await?, 3);
+
+    // A batch that doesn't form a hash chain is processed up to the first unlinked certificate.
+    let result = worker
+        .handle_confirmed_certificates(
+            vec![certificates[249].clone(), certificates[251].clone()],
+            None,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(WorkerError::CertificateBatch { index: 1, error })
+            if matches!(*error, WorkerError::InvalidBlockChaining)
+    );
+    let tip = worker
+        .chain_state_view(chain_id)
+        .await?
+        .tip_state
+        .get()
+        .clone();
+    assert_eq!(tip.next_block_height, BlockHeight::from(250));
+
+    // If a certificate fails during execution, its whole group is rolled back, and the error
+    // points at the start of the group.
+    let chain_id = ChainId::root(2);
+    let mut certificates = make_burn_certificates(
+        ChainDescription::Root(2),
+        &key_pair,
+        balance,
+        300,
+        &committee,
+        &worker,
+    )
+    .await;
+    // Replace the certificate at index 250 with one claiming an incorrect balance.
+    certificates[250] = make_transfer_certificate(
+        ChainDescription::Root(2),
+        &key_pair,
+        None,
+        Recipient::Burn,
+        Amount::from_attos(1),
+        Vec::new(),
+        &committee,
+        balance,
+        BTreeMap::new(),
+        &worker,
+        Some(&certificates[249]),
+    )
+    .await;
+    let result = worker
+        .handle_confirmed_certificates(certificates.clone(), None)
+        .await;
+    assert_matches!(
+        result,
+        Err(WorkerError::CertificateBatch { index: 200, error })
+            if matches!(*error, WorkerError::IncorrectOutcome { .. })
+    );
+    let tip = worker
+        .chain_state_view(chain_id)
+        .await?
+        .tip_state
+        .get()
+        .clone();
+    assert_eq!(tip.next_block_height, BlockHeight::from(200));
+    assert_eq!(tip.block_hash, Some(certificates[199].hash()));
+    // All the committed blocks are cached, not only the last one of each group.
+    for certificate in &certificates[100..200] {
+        assert_matches!(
+            worker
+                .full_certificate(certificate.lite_certificate())
+                .await,
+            Ok(Either::Left(_))
+        );
+    }
+    Ok(())
+}
+
+/// Registers a [`MockApplication`] on a chain that isn't loaded by a worker, keeping its
+/// execution state hash up to date.
+async fn register_mock_application<S>(
+    storage: &S,
+    chain_id: ChainId,
+) -> anyhow::Result<(UserApplicationId, MockApplication)>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let mut chain = storage.load_chain(chain_id).await?;
+    let (application_id, application) = chain.execution_state.register_mock_application().await?;
+    let state_hash = chain.execution_state.crypto_hash_mut().await?;
+    chain.execution_state_hash.set(Some(state_hash));
+    chain.save().await?;
+    Ok((application_id, application))
+}
+
+/// Proposes a block with an operation of the mock application that doesn't complete until a
+/// message is sent to the returned channel. Returns once the operation is being executed.
+async fn propose_slow_operation<S>(
+    worker: &WorkerState<S>,
+    key_pair: &KeyPair,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    application: &MockApplication,
+) -> anyhow::Result<(
+    tokio::task::JoinHandle<Result<(ChainInfoResponse, NetworkActions), WorkerError>>,
+    mpsc::Sender<()>,
+)>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let (started_sender, started_receiver) = oneshot::channel();
+    let (release_sender, release_receiver) = mpsc::channel();
+    let release_receiver = Mutex::new(release_receiver);
+    application.expect_call(ExpectedCall::execute_operation(move |_, _, _| {
+        started_sender.send(()).unwrap();
+        let _ = release_receiver.lock().unwrap().recv();
+        Ok(vec![])
+    }));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let proposal = make_first_block(chain_id)
+        .with_operation(Operation::User {
+            application_id,
+            bytes: vec![],
+        })
+        .into_first_proposal(key_pair);
+    let worker = worker.clone();
+    let handle = tokio::spawn(async move { worker.handle_block_proposal(proposal).await });
+    started_receiver.await?;
+    Ok((handle, release_sender))
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_shutdown_drains_in_flight_execution<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let chain_id = ChainId::root(1);
+    let (_committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        [(
+            ChainDescription::Root(1),
+            key_pair.public().into(),
+            Amount::ONE,
+        )],
+    )
+    .await;
+    let (application_id, application) = register_mock_application(&storage, chain_id).await?;
+    worker.check_previous_shutdown().await?;
+
+    let (in_flight, release) =
+        propose_slow_operation(&worker, &key_pair, chain_id, application_id, &application).await?;
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: BTreeSet::from([chain_id]),
+            truncated: false,
+        })
+    );
+
+    let shutdown = tokio::spawn({
+        let worker = worker.clone();
+        async move { worker.shutdown(Duration::from_secs(10)).await }
+    });
+    while !worker.shutting_down.load(Ordering::SeqCst) {
+        tokio::task::yield_now().await;
+    }
+
+    // New proposals are rejected, but the one being executed is still running.
+    let proposal = make_first_block(chain_id).into_first_proposal(&key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(proposal).await,
+        Err(WorkerError::ShuttingDown)
+    );
+    assert!(!shutdown.is_finished());
+
+    // Once the execution completes, the proposal is handled and the worker shuts down cleanly.
+    release.send(())?;
+    shutdown.await??;
+    let (response, _actions) = in_flight.await??;
+    assert!(response.info.manager.pending.is_some());
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Clean)
+    );
+    application.assert_no_more_expected_calls();
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_unclean_shutdown_checks_loaded_chains<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let chain_id = ChainId::root(1);
+    let (_committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        [(
+            ChainDescription::Root(1),
+            key_pair.public().into(),
+            Amount::ONE,
+        )],
+    )
+    .await;
+    let (application_id, application) = register_mock_application(&storage, chain_id).await?;
+    worker.check_previous_shutdown().await?;
+
+    // The execution doesn't complete in time, so the worker doesn't shut down cleanly.
+    let (in_flight, release) =
+        propose_slow_operation(&worker, &key_pair, chain_id, application_id, &application).await?;
+    assert_matches!(
+        worker.shutdown(Duration::from_millis(100)).await,
+        Err(WorkerError::ShutdownTimedOut)
+    );
+    assert_matches!(in_flight.await?, Err(WorkerError::ShuttingDown));
+    release.send(())?;
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: BTreeSet::from([chain_id]),
+            truncated: false,
+        })
+    );
+
+    // After a restart, the chain is checked and found consistent.
+    let (_committee, worker) = init_worker(storage.clone(), false, false);
+    assert!(worker.check_previous_shutdown().await?.is_empty());
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: BTreeSet::new(),
+            truncated: false,
+        })
+    );
+
+    // If the chain state was left inconsistent, it is reported, and the worker still runs.
+    let mut chain = storage.load_chain(chain_id).await?;
+    chain.tip_state.get_mut().next_block_height = BlockHeight(1);
+    chain.save().await?;
+    let marker = ShutdownMarker::Running {
+        chains: BTreeSet::from([chain_id, ChainId::root(2)]),
+        truncated: false,
+    };
+    storage
+        .write_shutdown_marker(worker.nickname(), &marker)
+        .await?;
+    let (_committee, worker) = init_worker(storage.clone(), false, false);
+    assert_eq!(
+        worker.check_previous_shutdown().await?,
+        BTreeSet::from([chain_id])
+    );
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: BTreeSet::new(),
+            truncated: false,
+        })
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_shutdown_marker_lists_a_bounded_number_of_chains<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let (_committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        [(
+            ChainDescription::Root(1),
+            PublicKey::test_key(1).into(),
+            Amount::ONE,
+        )],
+    )
+    .await;
+    worker.check_previous_shutdown().await?;
+    let listed_chains = (1..=MAX_CHAINS_IN_SHUTDOWN_MARKER as u32)
+        .map(|index| ChainId::root(index + 1))
+        .collect::<BTreeSet<_>>();
+    worker
+        .loaded_chains
+        .lock()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .chains = listed_chains.clone();
+
+    // Loading one more chain only records that the list is incomplete.
+    worker
+        .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(1)))
+        .await?;
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: listed_chains,
+            truncated: true,
+        })
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_shutdown_marker_is_not_rewritten_for_listed_chains<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let chain_id = ChainId::root(1);
+    let (_committee, worker) = init_worker_with_chains(
+        storage.clone(),
+        [(
+            ChainDescription::Root(1),
+            PublicKey::test_key(1).into(),
+            Amount::ONE,
+        )],
+    )
+    .await;
+    worker.check_previous_shutdown().await?;
+    worker
+        .loaded_chains
+        .lock()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .chains
+        .insert(chain_id);
+
+    // The chain is already listed, so loading it leaves the marker as it was.
+    worker
+        .handle_chain_info_query(ChainInfoQuery::new(chain_id))
+        .await?;
+    assert_eq!(
+        storage.read_shutdown_marker(worker.nickname()).await?,
+        Some(ShutdownMarker::Running {
+            chains: BTreeSet::new(),
+            truncated: false,
+        })
+    );
+    Ok(())
+}
+
+/// Tests that block proposals are admitted based on the resources they claim, rejected if
+/// their operations use more than claimed, and rejected without claims if the policy requires
+/// them.
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_resource_estimates_in_proposals<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let (committee, worker) = init_worker(storage.clone(), false, false);
+    let policy = ResourceControlPolicy {
+        maximum_fuel_per_block: 1_000,
+        resource_estimate_tolerance_percent: Some(10),
+        ..ResourceControlPolicy::default()
+    };
+    let committee = Committee::new(committee.validators().clone(), policy);
+    for index in [1, 2] {
+        storage
+            .create_chain(
+                committee.clone(),
+                ChainId::root(0),
+                ChainDescription::Root(index),
+                key_pair.public().into(),
+                Amount::ONE,
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+
+    // Each operation of the mock application uses 100 units of fuel.
+    let expect_operation = |application: &MockApplication| {
+        application.expect_call(ExpectedCall::execute_operation(|runtime, _, _| {
+            runtime.consume_fuel(100)?;
+            Ok(vec![])
+        }));
+        application.expect_call(ExpectedCall::default_finalize());
+    };
+    let propose = |chain_id, application_id, claimed_fuel| {
+        let block = make_first_block(chain_id).with_operation(Operation::User {
+            application_id,
+            bytes: vec![],
+        });
+        let estimates = vec![ResourceEstimate {
+            fuel: claimed_fuel,
+            ..ResourceEstimate::default()
+        }];
+        BlockProposal::new_initial_with_estimates(
+            Round::MultiLeader(0),
+            block,
+            estimates,
+            &key_pair,
+        )
+    };
+
+    let chain_id = ChainId::root(1);
+    let (application_id, application) = register_mock_application(&storage, chain_id).await?;
+
+    // A proposal without estimates is rejected without executing it.
+    let block = make_first_block(chain_id).with_operation(Operation::User {
+        application_id,
+        bytes: vec![],
+    });
+    let result = worker
+        .handle_block_proposal(BlockProposal::new_initial(
+            Round::MultiLeader(0),
+            block,
+            &key_pair,
+        ))
+        .await;
+    assert_matches!(result, Err(WorkerError::ChainError(error)) if matches!(
+        *error,
+        ChainError::MissingResourceEstimates
+    ));
+    application.assert_no_more_expected_calls();
+
+    // A proposal with a different number of estimates than operations is rejected without
+    // executing it.
+    let block = make_first_block(chain_id).with_operation(Operation::User {
+        application_id,
+        bytes: vec![],
+    });
+    let estimates = vec![ResourceEstimate::default(); 2];
+    let result = worker
+        .handle_block_proposal(BlockProposal::new_initial_with_estimates(
+            Round::MultiLeader(0),
+            block,
+            estimates,
+            &key_pair,
+        ))
+        .await;
+    assert_matches!(result, Err(WorkerError::ChainError(error)) if matches!(
+        *error,
+        ChainError::ResourceEstimateCountMismatch { estimates: 2, operations: 1 }
+    ));
+    application.assert_no_more_expected_calls();
+
+    // A proposal claiming more fuel than a block can use is rejected without executing it.
+    let result = worker
+        .handle_block_proposal(propose(chain_id, application_id, 1_001))
+        .await;
+    assert_matches!(result, Err(WorkerError::ChainError(error)) if matches!(
+        *error,
+        ChainError::ResourceEstimateOverBudget { claimed: 1_001, maximum: 1_000, .. }
+    ));
+    application.assert_no_more_expected_calls();
+
+    // A proposal underclaiming the fuel by more than the tolerance is rejected after execution.
+    expect_operation(&application);
+    let result = worker
+        .handle_block_proposal(propose(chain_id, application_id, 90))
+        .await;
+    assert_matches!(result, Err(WorkerError::ChainError(error)) if matches!(
+        *error,
+        ChainError::ResourceEstimateExceeded { index: 0, claimed: 90, actual: 100, .. }
+    ));
+    application.assert_no_more_expected_calls();
+
+    // A proposal with honest estimates is accepted.
+    expect_operation(&application);
+    let (response, _) = worker
+        .handle_block_proposal(propose(chain_id, application_id, 100))
+        .await?;
+    assert!(response.info.manager.pending.is_some());
+    application.assert_no_more_expected_calls();
+
+    // So is a proposal underclaiming within the tolerance.
+    let chain_id = ChainId::root(2);
+    let (application_id, application) = register_mock_application(&storage, chain_id).await?;
+    expect_operation(&application);
+    let (response, _) = worker
+        .handle_block_proposal(propose(chain_id, application_id, 91))
+        .await?;
+    assert!(response.info.manager.pending.is_some());
+    application.assert_no_more_expected_calls();
+
+    // The measured resources are reported when staging the execution of a block.
+    expect_operation(&application);
+    let block = make_first_block(chain_id).with_operation(Operation::User {
+        application_id,
+        bytes: vec![],
+    });
+    let (_, response) = worker.stage_block_execution(block, None).await?;
+    assert_eq!(
+        response.info.staged_resource_usage,
+        vec![ResourceEstimate {
+            fuel: 100,
+            ..ResourceEstimate::default()
+        }]
+    );
+    Ok(())
+}
+
+/// Tests that a sponsoring chain can pay for the fees of a block from the funds it set aside
+/// for the chain, and that proposals without a valid signature of the sponsorship's owner, or
+/// with a sponsorship that cannot pay, are rejected before the block is executed.
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_sponsored_block_proposals<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let sponsor_key_pair = KeyPair::generate();
+    let sponsor = ChainId::root(3);
+    let poor_sponsor_key_pair = KeyPair::generate();
+    let poor_sponsor = ChainId::root(4);
+    let (committee, worker) = init_worker(storage.clone(), false, false);
+    let policy = ResourceControlPolicy {
+        block: Amount::ONE,
+        operation: Amount::ONE,
+        ..ResourceControlPolicy::default()
+    };
+    let committee = Committee::new(committee.validators().clone(), policy);
+    let chain_id = ChainId::root(1);
+    storage
+        .create_chain(
+            committee.clone(),
+            ChainId::root(0),
+            ChainDescription::Root(1),
+            key_pair.public().into(),
+            Amount::from_tokens(5),
+            Timestamp::from(0),
+        )
+        .await?;
+    let (application_id, application) = register_mock_application(&storage, chain_id).await?;
+    let mut chain = storage.load_chain(chain_id).await?;
+    for (sponsor, key_pair, balance) in [
+        (sponsor, &sponsor_key_pair, Amount::from_tokens(3)),
+        (poor_sponsor, &poor_sponsor_key_pair, Amount::ONE),
+    ] {
+        let sponsorship = Sponsorship {
+            owner: key_pair.public().into(),
+            balance,
+        };
+        chain
+            .execution_state
+            .system
+            .sponsorships
+            .insert(&sponsor, sponsorship)?;
+    }
+    let state_hash = chain.execution_state.crypto_hash_mut().await?;
+    chain.execution_state_hash.set(Some(state_hash));
+    chain.save().await?;
+
+    // The mock application must not be called: each of these proposals is rejected before
+    // the block is executed.
+    let sponsored_operation = |fee_payer| {
+        make_first_block(chain_id)
+            .with_operation(Operation::User {
+                application_id,
+                bytes: vec![],
+            })
+            .with_fee_payer(Some(fee_payer))
+    };
+    let block = sponsored_operation(sponsor);
+    let unsigned_proposal = block.clone().into_first_proposal(&key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(unsigned_proposal).await,
+        Err(WorkerError::InvalidFeePayer)
+    );
+    let forged_proposal = block
+        .clone()
+        .into_first_proposal(&key_pair)
+        .with_fee_payer(&KeyPair::generate());
+    assert_matches!(
+        worker.handle_block_proposal(forged_proposal).await,
+        Err(WorkerError::InvalidFeePayer)
+    );
+    let mut misused_proposal = block.clone().into_first_proposal(&key_pair);
+    misused_proposal.fee_payer = Some(FeePayer::new(
+        &block.clone().with_timestamp(1),
+        &sponsor_key_pair,
+    ));
+    assert_matches!(
+        worker.handle_block_proposal(misused_proposal).await,
+        Err(WorkerError::InvalidFeePayer)
+    );
+    let other_sponsor_proposal = sponsored_operation(poor_sponsor)
+        .into_first_proposal(&key_pair)
+        .with_fee_payer(&sponsor_key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(other_sponsor_proposal).await,
+        Err(WorkerError::InvalidFeePayer)
+    );
+    let unknown_sponsor_proposal = sponsored_operation(ChainId::root(5))
+        .into_first_proposal(&key_pair)
+        .with_fee_payer(&sponsor_key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(unknown_sponsor_proposal).await,
+        Err(WorkerError::InvalidFeePayer)
+    );
+    let underfunded_proposal = sponsored_operation(poor_sponsor)
+        .into_first_proposal(&key_pair)
+        .with_fee_payer(&poor_sponsor_key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(underfunded_proposal).await,
+        Err(WorkerError::InsufficientSponsorFunding { sponsor, balance, fees })
+            if sponsor == poor_sponsor && balance == Amount::ONE && fees == Amount::from_tokens(2)
+    );
+    application.assert_no_more_expected_calls();
+
+    // With the signature of the sponsorship's owner, the block is accepted and the sponsorship
+    // pays for the block and the operation, while the chain only pays for the transfer.
+    let block = make_first_block(chain_id)
+        .with_simple_transfer(ChainId::root(2), Amount::ONE)
+        .with_fee_payer(Some(sponsor));
+    let proposal = block
+        .into_first_proposal(&key_pair)
+        .with_fee_payer(&sponsor_key_pair);
+    worker.handle_block_proposal(proposal).await?;
+    let chain = worker.chain_state_view(chain_id).await?;
+    let validated_certificate = make_certificate(
+        &committee,
+        &worker,
+        chain.manager.validated_vote().unwrap().value().clone(),
+    );
+    drop(chain);
+    worker
+        .handle_validated_certificate(validated_certificate)
+        .await?;
+    let chain = worker.chain_state_view(chain_id).await?;
+    let confirmed_certificate = make_certificate(
+        &committee,
+        &worker,
+        chain.manager.confirmed_vote().unwrap().value().clone(),
+    );
+    drop(chain);
+    worker
+        .handle_confirmed_certificate(confirmed_certificate, None)
+        .await?;
+
+    let chain = worker.chain_state_view(chain_id).await?;
+    assert_eq!(chain.tip_state.get().next_block_height, BlockHeight(1));
+    assert_eq!(
+        *chain.execution_state.system.balance.get(),
+        Amount::from_tokens(4)
+    );
+    let sponsorship = chain
+        .execution_state
+        .system
+        .sponsorships
+        .get(&sponsor)
+        .await?;
+    assert_eq!(
+        sponsorship.map(|sponsorship| sponsorship.balance),
+        Some(Amount::ONE)
+    );
+    Ok(())
+}
+
+/// Tests that a worker serving a subset of the chains, like a shard of a validator, rejects
+/// requests for the other chains.
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_chains_not_served_are_rejected<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let key_pair = KeyPair::generate();
+    let (_committee, worker) = init_worker_with_chains(
+        storage,
+        [1, 2].map(|index| {
+            (
+                ChainDescription::Root(index),
+                key_pair.public().into(),
+                Amount::ONE,
+            )
+        }),
+    )
+    .await;
+    let worker = worker.with_served_chains(|chain_id| chain_id == ChainId::root(1));
+
+    let (response, _) = worker
+        .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(1)))
+        .await?;
+    assert_eq!(response.info.chain_id, ChainId::root(1));
+
+    let result = worker
+        .handle_chain_info_query(ChainInfoQuery::new(ChainId::root(2)))
+        .await;
+    assert_matches!(
+        result,
+        Err(WorkerError::ChainNotServed(chain_id)) if chain_id == ChainId::root(2)
+    );
+    let result = worker
+        .handle_block_proposal(make_first_block(ChainId::root(2)).into_first_proposal(&key_pair))
+        .await;
+    assert_matches!(result, Err(WorkerError::ChainNotServed(_)));
+    Ok(())
+}
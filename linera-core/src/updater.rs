@@ -3,24 +3,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     fmt,
     hash::Hash,
     mem,
     ops::Range,
+    sync::Mutex,
 };
 
 use futures::{stream, stream::TryStreamExt, Future, StreamExt};
 use linera_base::{
     data_types::{Blob, BlockHeight, Round},
     identifiers::{BlobId, ChainId},
-    time::{timer::timeout, Duration, Instant},
+    time::{
+        timer::{sleep, timeout},
+        Duration, Instant,
+    },
 };
 use linera_chain::{
     data_types::{BlockProposal, LiteVote},
     types::{ConfirmedBlock, GenericCertificate, ValidatedBlock, ValidatedBlockCertificate},
 };
-use linera_execution::committee::Committee;
+use linera_execution::committee::{Committee, ValidatorName};
 use linera_storage::Storage;
 use thiserror::Error;
 
@@ -28,6 +32,7 @@ use crate::{
     client::ChainClientError,
     data_types::{ChainInfo, ChainInfoQuery},
     local_node::LocalNodeClient,
+    misbehavior::Misbehavior,
     node::{CrossChainMessageDelivery, NodeError, ValidatorNode},
     remote_node::RemoteNode,
 };
@@ -37,6 +42,134 @@ use crate::{
 pub const DEFAULT_GRACE_PERIOD: f64 = 0.2;
 /// The maximum timeout for requests to a stake-weighted quorum if no quorum is reached.
 const MAX_TIMEOUT: Duration = Duration::from_secs(60 * 60 * 24); // 1 day.
+/// The default number of times a request to a validator is retried after a transient failure.
+pub const DEFAULT_VALIDATOR_MAX_RETRIES: u32 = 3;
+/// The default delay before a request to a validator is retried for the first time.
+pub const DEFAULT_VALIDATOR_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// The number of consecutive failures after which a validator's circuit breaker opens.
+const CIRCUIT_BREAKER_THRESHOLD: u32 = 3;
+/// How long a validator is avoided after its circuit breaker opened.
+const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+/// The maximum number of confirmed certificates sent to a validator in a single request.
+const MAX_CERTIFICATES_PER_BATCH: usize = 50;
+
+/// How the requests to each individual validator are handled by `communicate_with_quorum`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidatorRequestOptions {
+    /// The maximum time to wait for a single attempt, if any.
+    pub timeout: Option<Duration>,
+    /// How many times to retry a request that timed out or failed with a network error.
+    pub max_retries: u32,
+    /// The delay before the first retry. It is doubled after each retry.
+    pub retry_delay: Duration,
+}
+
+impl Default for ValidatorRequestOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_retries: DEFAULT_VALIDATOR_MAX_RETRIES,
+            retry_delay: DEFAULT_VALIDATOR_RETRY_DELAY,
+        }
+    }
+}
+
+/// What went wrong with a validator while communicating with a quorum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidatorFault {
+    /// The validator didn't respond in time, even after retrying.
+    Timeout,
+    /// The validator returned an error, or a response that couldn't be verified.
+    Error(NodeError),
+    /// The validator returned a response that disagrees with the quorum.
+    Disagreement,
+}
+
+impl ValidatorFault {
+    /// Whether the fault suggests that the validator is unreachable or unreliable, as opposed
+    /// to a regular error that honest validators may return too.
+    fn is_unreliable(&self) -> bool {
+        match self {
+            ValidatorFault::Timeout => true,
+            ValidatorFault::Error(error) => {
+                is_transient(error)
+                    || matches!(
+                        error,
+                        NodeError::InvalidChainInfoResponse
+                            | NodeError::InvalidDecoding
+                            | NodeError::UnexpectedMessage
+                    )
+            }
+            ValidatorFault::Disagreement => false,
+        }
+    }
+}
+
+/// Which validators misbehaved while communicating with a quorum.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QuorumReport {
+    /// The validators that didn't contribute to the result, with the reason.
+    pub faults: BTreeMap<ValidatorName, ValidatorFault>,
+    /// The validators that were not contacted because their circuit breaker was open.
+    pub skipped: BTreeSet<ValidatorName>,
+}
+
+/// The per-validator circuit breakers.
+///
+/// A validator that failed too many times in a row is only contacted when a quorum can't be
+/// reached without it, until the cooldown has elapsed.
+#[derive(Debug, Default)]
+pub struct ValidatorHealth {
+    breakers: Mutex<HashMap<ValidatorName, CircuitBreaker>>,
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ValidatorHealth {
+    fn is_open(&self, name: &ValidatorName) -> bool {
+        self.breakers
+            .lock()
+            .expect("Panics should not happen while holding a lock to the circuit breakers")
+            .get(name)
+            .and_then(|breaker| breaker.open_until)
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+
+    fn record_success(&self, name: &ValidatorName) {
+        self.breakers
+            .lock()
+            .expect("Panics should not happen while holding a lock to the circuit breakers")
+            .remove(name);
+    }
+
+    fn record_failure(&self, name: ValidatorName) {
+        let mut breakers = self
+            .breakers
+            .lock()
+            .expect("Panics should not happen while holding a lock to the circuit breakers");
+        let breaker = breakers.entry(name).or_default();
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= CIRCUIT_BREAKER_THRESHOLD {
+            breaker.open_until = Some(Instant::now() + CIRCUIT_BREAKER_COOLDOWN);
+        }
+    }
+}
+
+impl QuorumReport {
+    fn log(&self) {
+        if !self.faults.is_empty() || !self.skipped.is_empty() {
+            tracing::warn!(
+                faults = ?self.faults,
+                skipped = ?self.skipped,
+                "Some validators misbehaved while communicating with a quorum"
+            );
+        }
+    }
+}
 
 /// Used for `communicate_chain_action`
 #[derive(Clone)]
@@ -102,6 +235,11 @@ pub enum CommunicationError<E: fmt::Debug> {
 /// Tries to stop early when a quorum is reached. If `grace_period` is specified, other validators
 /// are given additional time to contribute to the result. The grace period is calculated as a fraction
 /// (defaulting to `DEFAULT_GRACE_PERIOD`) of the time taken to reach quorum.
+///
+/// Requests to individual validators time out and are retried according to `request_options`.
+/// Validators whose circuit breaker in `health` is open are only contacted if no quorum can be
+/// reached without them. The validators that misbehaved are returned in a [`QuorumReport`],
+/// together with the result.
 pub async fn communicate_with_quorum<'a, A, V, K, F, R, G>(
     validator_clients: &'a [RemoteNode<A>],
     committee: &Committee,
@@ -109,7 +247,12 @@ pub async fn communicate_with_quorum<'a, A, V, K, F, R, G>(
     execute: F,
     // Grace period as a fraction of time taken to reach quorum
     grace_period: f64,
-) -> Result<(K, Vec<V>), CommunicationError<NodeError>>
+    request_options: &ValidatorRequestOptions,
+    health: &ValidatorHealth,
+) -> (
+    Result<(K, Vec<V>), CommunicationError<NodeError>>,
+    QuorumReport,
+)
 where
     A: ValidatorNode + Clone + 'static,
     F: Clone + Fn(RemoteNode<A>) -> R,
@@ -118,19 +261,33 @@ where
     K: Hash + PartialEq + Eq + Clone + 'static,
     V: 'static,
 {
-    let mut responses: futures::stream::FuturesUnordered<_> = validator_clients
+    // Validators with weight 0 should not happen but better prevent it because certificates
+    // are not allowed to include votes with weight 0.
+    let (mut skipped, mut contacted): (Vec<_>, Vec<_>) = validator_clients
         .iter()
-        .filter_map(|remote_node| {
-            if committee.weight(&remote_node.name) == 0 {
-                // This should not happen but better prevent it because certificates
-                // are not allowed to include votes with weight 0.
-                return None;
-            }
-            let execute = execute.clone();
-            let remote_node = remote_node.clone();
-            Some(async move { (remote_node.name, execute(remote_node).await) })
-        })
-        .collect();
+        .filter(|remote_node| committee.weight(&remote_node.name) > 0)
+        .partition(|remote_node| health.is_open(&remote_node.name));
+    let mut skipped_votes: u64 = skipped
+        .iter()
+        .map(|remote_node| committee.weight(&remote_node.name))
+        .sum();
+    if committee.total_votes() - skipped_votes < committee.quorum_threshold() {
+        // Avoiding the unreliable validators would make a quorum impossible.
+        contacted.append(&mut skipped);
+        skipped_votes = 0;
+    }
+
+    let request = |remote_node: &RemoteNode<A>| {
+        let execute = execute.clone();
+        let remote_node = remote_node.clone();
+        let request_options = *request_options;
+        async move {
+            let result = execute_with_retries(&remote_node, execute, request_options).await;
+            (remote_node.name, result)
+        }
+    };
+    let mut responses: futures::stream::FuturesUnordered<_> =
+        contacted.into_iter().map(&request).collect();
 
     let start_time = Instant::now();
     let mut end_time: Option<Instant> = None;
@@ -138,6 +295,7 @@ where
     let mut highest_key_score = 0;
     let mut value_scores = HashMap::new();
     let mut error_scores = HashMap::new();
+    let mut report = QuorumReport::default();
 
     'vote_wait: while let Ok(Some((name, result))) = timeout(
         end_time.map_or(MAX_TIMEOUT, |t| t.saturating_duration_since(Instant::now())),
@@ -148,29 +306,41 @@ where
         remaining_votes -= committee.weight(&name);
         match result {
             Ok(value) => {
+                health.record_success(&name);
                 let key = group_by(&value);
-                let entry = value_scores.entry(key.clone()).or_insert((0, Vec::new()));
+                let entry = value_scores
+                    .entry(key.clone())
+                    .or_insert((0, Vec::new(), Vec::new()));
                 entry.0 += committee.weight(&name);
                 entry.1.push(value);
+                entry.2.push(name);
                 highest_key_score = highest_key_score.max(entry.0);
             }
-            Err(err) => {
-                // TODO(#2857): Handle non-remote errors properly.
-                let err = match err {
-                    ChainClientError::RemoteNodeError(err) => err,
-                    err => NodeError::ResponseHandlingError {
-                        error: err.to_string(),
-                    },
-                };
-                let entry = error_scores.entry(err.clone()).or_insert(0);
-                *entry += committee.weight(&name);
-                if *entry >= committee.validity_threshold() {
-                    // At least one honest node returned this error.
-                    // No quorum can be reached, so return early.
-                    return Err(CommunicationError::Trusted(err));
+            Err(fault) => {
+                if fault.is_unreliable() {
+                    health.record_failure(name);
+                }
+                report.faults.insert(name, fault.clone());
+                if let ValidatorFault::Error(err) = fault {
+                    let entry = error_scores.entry(err.clone()).or_insert(0);
+                    *entry += committee.weight(&name);
+                    if *entry >= committee.validity_threshold() {
+                        // At least one honest node returned this error.
+                        // No quorum can be reached, so return early.
+                        report.log();
+                        return (Err(CommunicationError::Trusted(err)), report);
+                    }
                 }
             }
         }
+        // If no key can reach a quorum without the skipped validators, contact them too.
+        if !skipped.is_empty()
+            && highest_key_score + remaining_votes - skipped_votes < committee.quorum_threshold()
+        {
+            responses.extend(skipped.drain(..).map(&request));
+            skipped_votes = 0;
+        }
+
         // If it becomes clear that no key can reach a quorum, break early.
         if highest_key_score + remaining_votes < committee.quorum_threshold() {
             break 'vote_wait;
@@ -182,31 +352,103 @@ where
             end_time = Some(Instant::now() + start_time.elapsed().mul_f64(grace_period));
         }
     }
+    report.skipped = skipped.iter().map(|remote_node| remote_node.name).collect();
 
     let scores = value_scores
         .values()
-        .map(|(weight, values)| (*weight, values.len()))
+        .map(|(weight, values, _)| (*weight, values.len()))
         .collect();
     // If a key has a quorum, return it with its values.
-    if let Some((key, (_, values))) = value_scores
-        .into_iter()
-        .find(|(_, (score, _))| *score >= committee.quorum_threshold())
-    {
-        return Ok((key, values));
+    let quorum_key = value_scores
+        .iter()
+        .find(|(_, (score, _, _))| *score >= committee.quorum_threshold())
+        .map(|(key, _)| key.clone());
+    if let Some(key) = quorum_key {
+        let mut result = None;
+        for (other_key, (_, values, names)) in value_scores {
+            if other_key == key {
+                result = Some((other_key, values));
+            } else {
+                for name in names {
+                    report.faults.insert(name, ValidatorFault::Disagreement);
+                }
+            }
+        }
+        report.log();
+        return (Ok(result.expect("the quorum key is in the scores")), report);
     }
+    report.log();
 
     if error_scores.is_empty() {
-        return Err(CommunicationError::NoConsensus(
-            committee.quorum_threshold(),
-            scores,
-        ));
+        let error = CommunicationError::NoConsensus(committee.quorum_threshold(), scores);
+        return (Err(error), report);
     }
 
     // No specific error is available to report reliably.
     let mut sample = error_scores.into_iter().collect::<Vec<_>>();
     sample.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
     sample.truncate(4);
-    Err(CommunicationError::Sample(sample))
+    (Err(CommunicationError::Sample(sample)), report)
+}
+
+/// Executes a request to a single validator, retrying it with an exponential backoff if it
+/// timed out or failed with a transient error.
+async fn execute_with_retries<A, V, F, R>(
+    remote_node: &RemoteNode<A>,
+    execute: F,
+    options: ValidatorRequestOptions,
+) -> Result<V, ValidatorFault>
+where
+    A: ValidatorNode + Clone,
+    F: Fn(RemoteNode<A>) -> R,
+    R: Future<Output = Result<V, ChainClientError>>,
+{
+    let mut delay = options.retry_delay;
+    let mut retries = 0;
+    loop {
+        let attempt = execute(remote_node.clone());
+        let result = match options.timeout {
+            Some(duration) => timeout(duration, attempt).await.ok(),
+            None => Some(attempt.await),
+        };
+        let fault = match result {
+            Some(Ok(value)) => return Ok(value),
+            Some(Err(err)) => {
+                let err = into_node_error(err);
+                if !is_transient(&err) {
+                    return Err(ValidatorFault::Error(err));
+                }
+                ValidatorFault::Error(err)
+            }
+            None => ValidatorFault::Timeout,
+        };
+        if retries >= options.max_retries {
+            return Err(fault);
+        }
+        tracing::debug!(name = ?remote_node.name, ?fault, "Retrying request to validator");
+        retries += 1;
+        sleep(delay).await;
+        delay = delay.saturating_mul(2);
+    }
+}
+
+fn into_node_error(err: ChainClientError) -> NodeError {
+    // TODO(#2857): Handle non-remote errors properly.
+    match err {
+        ChainClientError::RemoteNodeError(err) => err,
+        err => NodeError::ResponseHandlingError {
+            error: err.to_string(),
+        },
+    }
+}
+
+/// Whether the error is caused by the network or by the validator throttling us, so that the
+/// request may succeed if retried after a delay.
+fn is_transient(err: &NodeError) -> bool {
+    matches!(
+        err,
+        NodeError::ClientIoError { .. } | NodeError::GrpcError { .. } | NodeError::Throttled
+    )
 }
 
 impl<A, S> ValidatorUpdater<A, S>
@@ -240,6 +482,53 @@ where
         }?)
     }
 
+    /// Sends consecutive confirmed certificates of a chain in batches.
+    ///
+    /// A certificate that fails in a batch is sent on its own, so that missing blobs can be
+    /// uploaded. If the validator can't handle batches at all, the remaining certificates are
+    /// sent one by one.
+    async fn send_confirmed_certificates(
+        &mut self,
+        certificates: Vec<GenericCertificate<ConfirmedBlock>>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<(), ChainClientError> {
+        let mut certificates = VecDeque::from(certificates);
+        while certificates.len() > 1 {
+            let count = certificates.len().min(MAX_CERTIFICATES_PER_BATCH);
+            let batch = certificates.range(..count).cloned().collect();
+            match self
+                .remote_node
+                .handle_confirmed_certificates(batch, delivery)
+                .await
+            {
+                Ok(_) => {
+                    certificates.drain(..count);
+                }
+                Err(NodeError::CertificateBatchFailed { index, .. }) if index < count => {
+                    certificates.drain(..index);
+                    let certificate = certificates
+                        .pop_front()
+                        .expect("the first uncommitted certificate is in the batch");
+                    self.send_confirmed_certificate(certificate, delivery)
+                        .await?;
+                }
+                Err(error) => {
+                    tracing::debug!(
+                        name = ?self.remote_node.name,
+                        %error,
+                        "Failed to send a batch of certificates; sending them one by one"
+                    );
+                    break;
+                }
+            }
+        }
+        for certificate in certificates {
+            self.send_confirmed_certificate(certificate, delivery)
+                .await?;
+        }
+        Ok(())
+    }
+
     async fn send_validated_certificate(
         &mut self,
         certificate: GenericCertificate<ValidatedBlock>,
@@ -356,9 +645,7 @@ where
             // Send the requested certificates in order.
             let storage = self.local_node.storage_client();
             let certs = storage.read_certificates(keys.into_iter()).await?;
-            for cert in certs {
-                self.send_confirmed_certificate(cert, delivery).await?;
-            }
+            self.send_confirmed_certificates(certs, delivery).await?;
         }
         if let Some(cert) = timeout {
             if cert.inner().chain_id == chain_id {
@@ -461,7 +748,12 @@ where
         };
         match vote {
             Some(vote) if vote.validator == self.remote_node.name => {
-                vote.check()?;
+                let misbehavior = &self.remote_node.misbehavior;
+                if let Err(error) = vote.check() {
+                    misbehavior.report(vote.validator, Misbehavior::InvalidVoteSignature { vote });
+                    return Err(error.into());
+                }
+                misbehavior.record_vote(target_block_height, &vote);
                 Ok(vote)
             }
             Some(_) | None => Err(NodeError::MissingVoteInValidatorResponse.into()),
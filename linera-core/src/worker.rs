@@ -3,9 +3,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    mem,
     num::NonZeroUsize,
-    sync::{Arc, Mutex, RwLock},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
     time::Duration,
 };
 
@@ -15,12 +19,16 @@ use linera_base::crypto::PublicKey;
 use linera_base::{
     crypto::{CryptoError, CryptoHash, KeyPair},
     data_types::{
-        ArithmeticError, Blob, BlockHeight, DecompressionError, Round, UserApplicationDescription,
+        Amount, ArithmeticError, Blob, BlockHeight, DecompressionError, Round,
+        UserApplicationDescription,
     },
-    doc_scalar,
+    doc_scalar, ensure,
     hashed::Hashed,
     identifiers::{BlobId, ChainId, Owner, UserApplicationId},
-    time::timer::{sleep, timeout},
+    time::{
+        timer::{sleep, timeout},
+        Instant,
+    },
 };
 use linera_chain::{
     data_types::{
@@ -37,8 +45,8 @@ use linera_execution::{
     committee::{Epoch, ValidatorName},
     ExecutionError, Query, QueryOutcome,
 };
-use linera_storage::Storage;
-use linera_views::views::ViewError;
+use linera_storage::{ShutdownMarker, Storage};
+use linera_views::views::{CryptoHashView as _, ViewError};
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -47,14 +55,17 @@ use tracing::{error, instrument, trace, warn, Instrument as _};
 #[cfg(with_metrics)]
 use {
     linera_base::prometheus_util::{
-        bucket_interval, register_histogram_vec, register_int_counter_vec,
+        bucket_interval, bucket_latencies, register_histogram_vec, register_int_counter_vec,
     },
     prometheus::{HistogramVec, IntCounterVec},
     std::sync::LazyLock,
 };
 
 use crate::{
-    chain_worker::{ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, DeliveryNotifier},
+    chain_worker::{
+        ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest, ChainWorkerScheduler,
+        DeliveryNotifier, StateSnapshotOptions,
+    },
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     join_set_ext::{JoinSet, JoinSetExt},
     notifier::Notifier,
@@ -65,6 +76,21 @@ use crate::{
 #[path = "unit_tests/worker_tests.rs"]
 mod worker_tests;
 
+/// The maximum number of chains listed in the [`ShutdownMarker`] of a running worker.
+pub const MAX_CHAINS_IN_SHUTDOWN_MARKER: usize = 1_000;
+
+/// The chains listed in the [`ShutdownMarker`] of a running worker.
+#[derive(Default)]
+struct LoadedChains {
+    chains: BTreeSet<ChainId>,
+    /// Whether more chains were loaded than listed.
+    truncated: bool,
+    /// Incremented whenever `chains` or `truncated` change.
+    version: u64,
+    /// The last version written to the [`ShutdownMarker`].
+    written_version: u64,
+}
+
 #[cfg(with_metrics)]
 static NUM_ROUNDS_IN_CERTIFICATE: LazyLock<HistogramVec> = LazyLock::new(|| {
     register_histogram_vec(
@@ -103,6 +129,16 @@ static CERTIFICATES_SIGNED: LazyLock<IntCounterVec> = LazyLock::new(|| {
     )
 });
 
+#[cfg(with_metrics)]
+static CERTIFICATE_HANDLING_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "certificate_handling_latency",
+        "Latency of handling a certificate",
+        &["certificate_value"],
+        bucket_latencies(500.0),
+    )
+});
+
 /// Instruct the networking layer to send cross-chain requests and/or push notifications.
 #[derive(Default, Debug)]
 pub struct NetworkActions {
@@ -170,6 +206,19 @@ pub enum WorkerError {
     #[error("Operations in the block are not authenticated by the proper signer: {0}")]
     InvalidSigner(Owner),
 
+    #[error("The fees of the block are not authorized by the owner of its sponsorship")]
+    InvalidFeePayer,
+
+    #[error(
+        "The sponsoring chain {sponsor} cannot pay for the block: its sponsorship {balance} is \
+         lower than the {fees} charged for any block with these operations"
+    )]
+    InsufficientSponsorFunding {
+        sponsor: ChainId,
+        balance: Amount,
+        fees: Amount,
+    },
+
     // Chaining
     #[error(
         "Was expecting block height {expected_block_height} but found {found_block_height} instead"
@@ -178,6 +227,15 @@ pub enum WorkerError {
         expected_block_height: BlockHeight,
         found_block_height: BlockHeight,
     },
+    #[error(
+        "The state of chain {chain_id} at height {height} is not retained; \
+         the oldest retained state is at height {oldest_retained_height}"
+    )]
+    StateNotRetained {
+        chain_id: ChainId,
+        height: BlockHeight,
+        oldest_retained_height: BlockHeight,
+    },
     #[error("Cannot confirm a block before its predecessors: {current_block_height:?}")]
     MissingEarlierBlocks { current_block_height: BlockHeight },
     #[error("Unexpected epoch {epoch:}: chain {chain_id:} is at {chain_epoch:}")]
@@ -227,6 +285,23 @@ pub enum WorkerError {
     TooManyPublishedBlobs(u64),
     #[error(transparent)]
     Decompression(#[from] DecompressionError),
+    #[error("Certificates from index {index} of the batch were not committed: {error}")]
+    CertificateBatch {
+        index: usize,
+        error: Box<WorkerError>,
+    },
+    #[error("The batch of certificates is empty")]
+    EmptyCertificateBatch,
+    #[error("The worker is shutting down")]
+    ShuttingDown,
+    #[error("Timed out waiting for the in-flight requests while shutting down")]
+    ShutdownTimedOut,
+    #[error("Chain {chain_id} is in an inconsistent state: {reason}")]
+    InconsistentChainState { chain_id: ChainId, reason: String },
+    #[error("This validator does not serve breakdowns of the execution state hash")]
+    StateHashBreakdownsNotServed,
+    #[error("Chain {0} is not served by this worker")]
+    ChainNotServed(ChainId),
 }
 
 impl From<ChainError> for WorkerError {
@@ -280,6 +355,40 @@ where
     chain_worker_tasks: Arc<Mutex<JoinSet>>,
     /// The cache of running [`ChainWorkerActor`]s.
     chain_workers: Arc<Mutex<LruCache<ChainId, ChainActorEndpoint<StorageClient>>>>,
+    /// Gives the [`ChainWorkerActor`]s their turns to handle a request.
+    chain_worker_scheduler: ChainWorkerScheduler,
+    /// Whether the worker is shutting down, and rejects new requests.
+    shutting_down: Arc<AtomicBool>,
+    /// Locked for reading by every request in flight, so that shutting down can wait for them.
+    in_flight_requests: Arc<tokio::sync::RwLock<()>>,
+    /// The chains loaded since the worker marked itself as running, if it keeps track of its
+    /// [`ShutdownMarker`].
+    loaded_chains: Arc<Mutex<Option<LoadedChains>>>,
+    /// Held while writing the [`ShutdownMarker`].
+    shutdown_marker_write: Arc<tokio::sync::Mutex<()>>,
+    /// The number of requests sent to the [`ChainWorkerActor`]s and not answered yet.
+    pending_requests: Arc<AtomicUsize>,
+    /// When the last confirmed block was processed successfully.
+    last_confirmed_block: Arc<Mutex<Option<Instant>>>,
+    /// Which chains the worker serves, if it is one shard of a validator.
+    served_chains: Option<Arc<dyn Fn(ChainId) -> bool + Send + Sync>>,
+}
+
+/// Counts a request sent to a [`ChainWorkerActor`] as pending until it is dropped, which
+/// also happens if the caller stops waiting for the response.
+struct PendingRequest<'a>(&'a AtomicUsize);
+
+impl<'a> PendingRequest<'a> {
+    fn new(pending_requests: &'a AtomicUsize) -> Self {
+        pending_requests.fetch_add(1, Ordering::Relaxed);
+        PendingRequest(pending_requests)
+    }
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 /// The sender endpoint for [`ChainWorkerRequest`]s.
@@ -308,6 +417,14 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_worker_scheduler: ChainWorkerScheduler::default(),
+            shutting_down: Arc::default(),
+            in_flight_requests: Arc::default(),
+            loaded_chains: Arc::default(),
+            shutdown_marker_write: Arc::default(),
+            pending_requests: Arc::default(),
+            last_confirmed_block: Arc::default(),
+            served_chains: None,
         }
     }
 
@@ -327,6 +444,14 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_worker_scheduler: ChainWorkerScheduler::default(),
+            shutting_down: Arc::default(),
+            in_flight_requests: Arc::default(),
+            loaded_chains: Arc::default(),
+            shutdown_marker_write: Arc::default(),
+            pending_requests: Arc::default(),
+            last_confirmed_block: Arc::default(),
+            served_chains: None,
         }
     }
 
@@ -349,6 +474,33 @@ where
         self
     }
 
+    /// Configures whether to answer queries for the hashes of the parts of the execution
+    /// state, to help diagnose state hash mismatches between validators.
+    #[instrument(level = "trace", skip(self, value))]
+    pub fn with_serve_state_hash_breakdowns(mut self, value: bool) -> Self {
+        self.chain_worker_config.serve_state_hash_breakdowns = value;
+        self
+    }
+
+    /// Configures whether to export the execution statistics of each chain as Prometheus
+    /// gauges. There is one set of gauges per chain, so this is only suitable for nodes
+    /// handling a moderate number of chains.
+    #[instrument(level = "trace", skip(self, value))]
+    pub fn with_export_chain_statistics(mut self, value: bool) -> Self {
+        self.chain_worker_config.export_chain_statistics = value;
+        self
+    }
+
+    /// Configures the maximal number of chains handling a request at the same time, or no
+    /// limit if [`None`].
+    ///
+    /// The requests of each chain are still handled one at a time, and in order.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_max_concurrent_chain_workers(mut self, limit: Option<NonZeroUsize>) -> Self {
+        self.chain_worker_scheduler = ChainWorkerScheduler::new(limit);
+        self
+    }
+
     #[instrument(level = "trace", skip(self, tracked_chains))]
     /// Configures the subset of chains that this worker is tracking.
     pub fn with_tracked_chains(
@@ -369,6 +521,25 @@ where
         self
     }
 
+    /// Returns an instance that only serves the chains for which `is_served` returns `true`,
+    /// so that the shards of a validator never write the state of the same chain.
+    #[instrument(level = "trace", skip(self, is_served))]
+    pub fn with_served_chains(
+        mut self,
+        is_served: impl Fn(ChainId) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.served_chains = Some(Arc::new(is_served));
+        self
+    }
+
+    /// Returns an instance that keeps snapshots of its chains' states as configured, to answer
+    /// application queries at past heights.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_state_snapshots(mut self, options: StateSnapshotOptions) -> Self {
+        self.chain_worker_config.state_snapshots = options;
+        self
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn nickname(&self) -> &str {
         &self.nickname
@@ -497,6 +668,33 @@ where
         .unwrap_or_else(|_| Err(WorkerError::JoinError))
     }
 
+    /// Processes a batch of confirmed certificates, and then all the resulting cross-chain
+    /// requests, like [`Self::fully_handle_certificate_with_notifications`].
+    #[instrument(level = "trace", skip(self, certificates, notifier))]
+    pub async fn fully_handle_confirmed_certificates_with_notifications(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        notifier: &impl Notifier,
+    ) -> Result<ChainInfoResponse, WorkerError> {
+        let notifications = (*notifier).clone();
+        let this = self.clone();
+        linera_base::task::spawn(async move {
+            let (response, actions) = this
+                .handle_confirmed_certificates(certificates, None)
+                .await?;
+            notifications.notify(&actions.notifications);
+            let mut requests = VecDeque::from(actions.cross_chain_requests);
+            while let Some(request) = requests.pop_front() {
+                let actions = this.handle_cross_chain_request(request).await?;
+                requests.extend(actions.cross_chain_requests);
+                notifications.notify(&actions.notifications);
+            }
+            Ok(response)
+        })
+        .await
+        .unwrap_or_else(|_| Err(WorkerError::JoinError))
+    }
+
     /// Tries to execute a block proposal without any verification other than block execution.
     #[instrument(level = "trace", skip(self, block))]
     pub async fn stage_block_execution(
@@ -515,14 +713,22 @@ where
     }
 
     /// Executes a [`Query`] for an application's state on a specific chain.
+    ///
+    /// The query reads the chain state at the tip when it is processed. If a `height` is
+    /// provided, the query fails unless the chain's next block height is exactly `height`.
     #[instrument(level = "trace", skip(self, chain_id, query))]
     pub async fn query_application(
         &self,
         chain_id: ChainId,
         query: Query,
+        height: Option<BlockHeight>,
     ) -> Result<QueryOutcome, WorkerError> {
         self.query_chain_worker(chain_id, move |callback| {
-            ChainWorkerRequest::QueryApplication { query, callback }
+            ChainWorkerRequest::QueryApplication {
+                query,
+                height,
+                callback,
+            }
         })
         .await
     }
@@ -566,6 +772,43 @@ where
 
         #[cfg(with_metrics)]
         NUM_BLOCKS.with_label_values(&[]).inc();
+        self.record_confirmed_block();
+
+        Ok((response, actions))
+    }
+
+    /// Processes a sequence of consecutive confirmed blocks of the same chain.
+    #[instrument(
+        level = "trace",
+        skip(self, certificates, notify_when_messages_are_delivered)
+    )]
+    async fn process_confirmed_blocks(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        let chain_id = certificates
+            .first()
+            .ok_or(WorkerError::EmptyCertificateBatch)?
+            .block()
+            .header
+            .chain_id;
+        #[cfg(with_metrics)]
+        let block_count = certificates.len() as u64;
+
+        let (response, actions) = self
+            .query_chain_worker(chain_id, move |callback| {
+                ChainWorkerRequest::ProcessConfirmedBlocks {
+                    certificates,
+                    notify_when_messages_are_delivered,
+                    callback,
+                }
+            })
+            .await?;
+
+        #[cfg(with_metrics)]
+        NUM_BLOCKS.with_label_values(&[]).inc_by(block_count);
+        self.record_confirmed_block();
 
         Ok((response, actions))
     }
@@ -634,6 +877,16 @@ where
         .await
     }
 
+    /// Returns how many times the state of a chain was persisted since its worker was loaded.
+    #[instrument(level = "trace", skip(self, chain_id))]
+    #[cfg(with_testing)]
+    pub async fn count_commits(&self, chain_id: ChainId) -> Result<u64, WorkerError> {
+        self.query_chain_worker(chain_id, move |callback| ChainWorkerRequest::CountCommits {
+            callback,
+        })
+        .await
+    }
+
     /// Returns a read-only view of the [`ChainStateView`] of a chain referenced by its
     /// [`ChainId`].
     ///
@@ -659,16 +912,65 @@ where
             oneshot::Sender<Result<Response, WorkerError>>,
         ) -> ChainWorkerRequest<StorageClient::Context>,
     ) -> Result<Response, WorkerError> {
+        if let Some(is_served) = &self.served_chains {
+            ensure!(is_served(chain_id), WorkerError::ChainNotServed(chain_id));
+        }
+        let _in_flight = self.start_request().await?;
         let chain_actor = self.get_chain_worker_endpoint(chain_id).await?;
         let (callback, response) = oneshot::channel();
 
-        chain_actor
-            .send(request_builder(callback))
-            .expect("`ChainWorkerActor` stopped executing unexpectedly");
+        if chain_actor.send(request_builder(callback)).is_err() {
+            return Err(self.stopped_chain_worker_error("stopped executing unexpectedly"));
+        }
 
+        let _pending = PendingRequest::new(&self.pending_requests);
         response
             .await
-            .expect("`ChainWorkerActor` stopped executing without responding")
+            .unwrap_or_else(|_| Err(self.stopped_chain_worker_error("stopped without responding")))
+    }
+
+    /// Returns the number of requests sent to the chain workers and not answered yet.
+    pub fn pending_requests(&self) -> usize {
+        self.pending_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns how long ago the last confirmed block was processed successfully, if any was.
+    pub fn last_confirmed_block_age(&self) -> Option<Duration> {
+        let last_confirmed_block = *self
+            .last_confirmed_block
+            .lock()
+            .expect("Panics should not happen while holding the lock");
+        last_confirmed_block.map(|instant| instant.elapsed())
+    }
+
+    fn record_confirmed_block(&self) {
+        *self
+            .last_confirmed_block
+            .lock()
+            .expect("Panics should not happen while holding the lock") = Some(Instant::now());
+    }
+
+    /// Returns the error for a request whose [`ChainWorkerActor`] stopped, which only happens
+    /// if it was aborted while shutting down.
+    fn stopped_chain_worker_error(&self, reason: &str) -> WorkerError {
+        assert!(
+            self.shutting_down.load(Ordering::SeqCst),
+            "`ChainWorkerActor` {reason}"
+        );
+        WorkerError::ShuttingDown
+    }
+
+    /// Registers a new request in flight, unless the worker is shutting down.
+    async fn start_request(&self) -> Result<tokio::sync::RwLockReadGuard<'_, ()>, WorkerError> {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(WorkerError::ShuttingDown);
+        }
+        let in_flight = self.in_flight_requests.read().await;
+        // Shutting down may have started while we were waiting for the lock.
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return Err(WorkerError::ShuttingDown);
+        }
+        Ok(in_flight)
     }
 
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, creating one and adding it
@@ -699,6 +1001,7 @@ where
                 .or_default()
                 .clone();
 
+            self.mark_chain_as_loaded(chain_id).await?;
             let actor = ChainWorkerActor::load(
                 self.chain_worker_config.clone(),
                 self.storage.clone(),
@@ -709,15 +1012,70 @@ where
             )
             .await?;
 
-            self.chain_worker_tasks
-                .lock()
-                .unwrap()
-                .spawn_task(actor.run(receiver).in_current_span());
+            self.chain_worker_tasks.lock().unwrap().spawn_task(
+                actor
+                    .run(receiver, self.chain_worker_scheduler.clone())
+                    .in_current_span(),
+            );
         }
 
         Ok(sender)
     }
 
+    /// Adds a chain to the ones listed in the [`ShutdownMarker`], if the worker keeps track of
+    /// it.
+    ///
+    /// At most [`MAX_CHAINS_IN_SHUTDOWN_MARKER`] chains are listed; the marker records whether
+    /// more were loaded.
+    ///
+    /// The marker is only written if the chain isn't listed yet, and chains loaded
+    /// concurrently share a single write: whoever gets to write lists all the chains loaded so
+    /// far.
+    async fn mark_chain_as_loaded(&self, chain_id: ChainId) -> Result<(), WorkerError> {
+        let version = {
+            let mut loaded_chains = self.loaded_chains.lock().unwrap();
+            let Some(loaded) = loaded_chains.as_mut() else {
+                return Ok(());
+            };
+            if !loaded.truncated && !loaded.chains.contains(&chain_id) {
+                if loaded.chains.len() >= MAX_CHAINS_IN_SHUTDOWN_MARKER {
+                    warn!(
+                        "{} loaded more than {MAX_CHAINS_IN_SHUTDOWN_MARKER} chains; \
+                         the others will not be checked after an unclean shutdown",
+                        self.nickname
+                    );
+                    loaded.truncated = true;
+                } else {
+                    loaded.chains.insert(chain_id);
+                }
+                loaded.version += 1;
+            }
+            loaded.version
+        };
+        let _write = self.shutdown_marker_write.lock().await;
+        let (marker, marker_version) = {
+            let loaded_chains = self.loaded_chains.lock().unwrap();
+            let Some(loaded) = loaded_chains
+                .as_ref()
+                .filter(|loaded| loaded.written_version < version)
+            else {
+                return Ok(());
+            };
+            let marker = ShutdownMarker::Running {
+                chains: loaded.chains.clone(),
+                truncated: loaded.truncated,
+            };
+            (marker, loaded.version)
+        };
+        self.storage
+            .write_shutdown_marker(&self.nickname, &marker)
+            .await?;
+        if let Some(loaded) = self.loaded_chains.lock().unwrap().as_mut() {
+            loaded.written_version = marker_version;
+        }
+        Ok(())
+    }
+
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, attempting to create one
     /// and add it to the cache if needed.
     ///
@@ -850,8 +1208,61 @@ where
             }
         }
 
-        self.process_confirmed_block(certificate, notify_when_messages_are_delivered)
-            .await
+        #[cfg(with_metrics)]
+        let start = Instant::now();
+        let result = self
+            .process_confirmed_block(certificate, notify_when_messages_are_delivered)
+            .await;
+        #[cfg(with_metrics)]
+        CERTIFICATE_HANDLING_LATENCY
+            .with_label_values(&["confirmed_block"])
+            .observe(start.elapsed().as_secs_f64() * 1000.0);
+        result
+    }
+
+    /// Processes a batch of confirmed block certificates, e.g. when a chain catches up.
+    ///
+    /// The certificates must be consecutive blocks of the same chain. They are executed in
+    /// order and the chain state is persisted in groups rather than after every block. If a
+    /// certificate fails, a [`WorkerError::CertificateBatch`] reports the index of the first
+    /// certificate that was not committed; all the ones before it are kept.
+    #[instrument(skip_all, fields(
+        nick = self.nickname,
+        count = certificates.len(),
+    ))]
+    pub async fn handle_confirmed_certificates(
+        &self,
+        mut certificates: Vec<ConfirmedBlockCertificate>,
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        trace!(
+            "{} <-- {} confirmed certificates",
+            self.nickname,
+            certificates.len()
+        );
+        // Only process the prefix of the batch that forms a hash chain.
+        let linked_count = certificates
+            .windows(2)
+            .position(|pair| {
+                let (previous, next) = (pair[0].block(), pair[1].block());
+                next.header.chain_id != previous.header.chain_id
+                    || previous.header.height.try_add_one().ok() != Some(next.header.height)
+                    || next.header.previous_block_hash != Some(pair[0].hash())
+            })
+            .map(|index| index + 1);
+        if let Some(index) = linked_count {
+            certificates.truncate(index);
+        }
+        let result = self
+            .process_confirmed_blocks(certificates, notify_when_messages_are_delivered)
+            .await?;
+        match linked_count {
+            Some(index) => Err(WorkerError::CertificateBatch {
+                index,
+                error: Box::new(WorkerError::InvalidBlockChaining),
+            }),
+            None => Ok(result),
+        }
     }
 
     /// Processes a validated block certificate.
@@ -870,10 +1281,15 @@ where
         let round = certificate.round;
         #[cfg(with_metrics)]
         let cert_str = certificate.inner().to_log_str();
+        #[cfg(with_metrics)]
+        let start = Instant::now();
 
         let (info, actions, _duplicated) = self.process_validated_block(certificate).await?;
         #[cfg(with_metrics)]
         {
+            CERTIFICATE_HANDLING_LATENCY
+                .with_label_values(&[cert_str])
+                .observe(start.elapsed().as_secs_f64() * 1000.0);
             if !_duplicated {
                 NUM_ROUNDS_IN_CERTIFICATE
                     .with_label_values(&[cert_str, round.type_name()])
@@ -894,7 +1310,14 @@ where
         certificate: TimeoutCertificate,
     ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
         trace!("{} <-- {:?}", self.nickname, certificate);
-        self.process_timeout(certificate).await
+        #[cfg(with_metrics)]
+        let start = Instant::now();
+        let result = self.process_timeout(certificate).await;
+        #[cfg(with_metrics)]
+        CERTIFICATE_HANDLING_LATENCY
+            .with_label_values(&["timeout"])
+            .observe(start.elapsed().as_secs_f64() * 1000.0);
+        result
     }
 
     #[instrument(skip_all, fields(
@@ -1036,6 +1459,134 @@ where
         }
     }
 
+    /// Checks whether the worker shut down cleanly the last time it ran, and marks it as
+    /// running.
+    ///
+    /// Workers are identified by their nickname. If the previous run didn't shut down cleanly,
+    /// the chains it had loaded are checked for consistency first. This must be called before
+    /// the worker handles any request; until then, the worker doesn't keep track of its
+    /// [`ShutdownMarker`].
+    ///
+    /// The chains that fail the check are logged and returned, so that the worker can still
+    /// serve the others.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn check_previous_shutdown(&self) -> Result<BTreeSet<ChainId>, WorkerError> {
+        let _write = self.shutdown_marker_write.lock().await;
+        let mut inconsistent_chains = BTreeSet::new();
+        if let Some(ShutdownMarker::Running { chains, truncated }) =
+            self.storage.read_shutdown_marker(&self.nickname).await?
+        {
+            warn!(
+                "{} did not shut down cleanly; checking {} chain(s)",
+                self.nickname,
+                chains.len()
+            );
+            if truncated {
+                warn!(
+                    "{} had loaded more chains than it listed; only those are checked",
+                    self.nickname
+                );
+            }
+            for chain_id in chains {
+                if let Err(error) = self.check_chain_consistency(chain_id).await {
+                    error!(
+                        "{} failed to check chain {chain_id}: {error}",
+                        self.nickname
+                    );
+                    inconsistent_chains.insert(chain_id);
+                }
+            }
+        }
+        let marker = ShutdownMarker::Running {
+            chains: BTreeSet::new(),
+            truncated: false,
+        };
+        self.storage
+            .write_shutdown_marker(&self.nickname, &marker)
+            .await?;
+        *self.loaded_chains.lock().unwrap() = Some(LoadedChains::default());
+        Ok(inconsistent_chains)
+    }
+
+    /// Checks that the persisted state of a chain agrees with its confirmed blocks.
+    async fn check_chain_consistency(&self, chain_id: ChainId) -> Result<(), WorkerError> {
+        let inconsistent =
+            |reason: String| WorkerError::InconsistentChainState { chain_id, reason };
+        let chain = self.storage.load_chain(chain_id).await?;
+        let tip = chain.tip_state.get();
+        let log_length = chain.confirmed_log.count();
+        if log_length as u64 != tip.next_block_height.0 {
+            return Err(inconsistent(format!(
+                "{log_length} confirmed blocks, but the next block height is {}",
+                tip.next_block_height
+            )));
+        }
+        let last_hash = match log_length.checked_sub(1) {
+            Some(index) => chain.confirmed_log.get(index).await?,
+            None => None,
+        };
+        if last_hash != tip.block_hash {
+            return Err(inconsistent(
+                "the last confirmed block is not the tip of the chain".to_owned(),
+            ));
+        }
+        if let Some(hash) = tip.block_hash {
+            if !self.storage.contains_certificate(hash).await? {
+                return Err(inconsistent(format!(
+                    "the certificate {hash} of the tip is missing"
+                )));
+            }
+        }
+        if let Some(state_hash) = *chain.execution_state_hash.get() {
+            if chain.execution_state.crypto_hash().await? != state_hash {
+                return Err(inconsistent(
+                    "the execution state does not match its hash".to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Shuts the worker down: new requests are rejected, and the requests in flight, including
+    /// their contract executions and storage writes, are given up to `drain_timeout` to
+    /// complete.
+    ///
+    /// If they all complete, the worker is marked as having shut down cleanly. Otherwise the
+    /// remaining [`ChainWorkerActor`]s are aborted and [`WorkerError::ShutdownTimedOut`] is
+    /// returned.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn shutdown(&self, drain_timeout: Duration) -> Result<(), WorkerError> {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let drain = async {
+            let _no_requests = self.in_flight_requests.write().await;
+            self.stop_chain_workers().await_all_tasks().await;
+        };
+        if timeout(drain_timeout, drain).await.is_err() {
+            warn!(
+                "{} timed out waiting for the in-flight requests to complete",
+                self.nickname
+            );
+            // Dropping the tasks aborts them.
+            drop(self.stop_chain_workers());
+            return Err(WorkerError::ShutdownTimedOut);
+        }
+        let _write = self.shutdown_marker_write.lock().await;
+        let keeps_track = self.loaded_chains.lock().unwrap().is_some();
+        if keeps_track {
+            self.storage
+                .write_shutdown_marker(&self.nickname, &ShutdownMarker::Clean)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Removes the endpoints of all the [`ChainWorkerActor`]s, so that they stop once they have
+    /// handled their queued requests, and returns their tasks.
+    fn stop_chain_workers(&self) -> JoinSet {
+        self.chain_workers.lock().unwrap().clear();
+        mem::take(&mut *self.chain_worker_tasks.lock().unwrap())
+    }
+
     /// Updates the received certificate trackers to at least the given values.
     pub async fn update_received_certificate_trackers(
         &self,
@@ -1,9 +1,16 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
-use linera_base::{data_types::UserApplicationDescription, identifiers::UserApplicationId};
+use linera_base::{
+    data_types::UserApplicationDescription,
+    ensure,
+    identifiers::{BytecodeId, UserApplicationId},
+};
 use linera_views::{
     context::Context,
     map_view::HashedMapView,
@@ -68,6 +75,7 @@ where
         application_id: UserApplicationId,
         parameters: Vec<u8>,
         required_application_ids: Vec<UserApplicationId>,
+        max_query_response_bytes: Option<u64>,
     ) -> Result<(), SystemExecutionError> {
         // Make sure that referenced applications ids have been registered.
         for required_id in &required_application_ids {
@@ -83,6 +91,7 @@ where
             parameters,
             creation,
             required_application_ids,
+            max_query_response_bytes,
         };
         self.known_applications
             .insert(&application_id, description)?;
@@ -100,6 +109,47 @@ where
             .ok_or_else(|| SystemExecutionError::UnknownApplicationId(Box::new(id)))
     }
 
+    /// Lists up to `limit` registered applications, starting after the application `after` if
+    /// provided. If `bytecode_id` is provided, only the applications using that bytecode are
+    /// listed.
+    ///
+    /// Applications are always listed in the same order, so the ID of the last application of a
+    /// page can be used as the `after` cursor to fetch the next page.
+    pub async fn list_applications(
+        &self,
+        after: Option<UserApplicationId>,
+        bytecode_id: Option<BytecodeId>,
+        limit: usize,
+    ) -> Result<Vec<(UserApplicationId, UserApplicationDescription)>, SystemExecutionError> {
+        let mut applications = Vec::new();
+        if limit == 0 {
+            return Ok(applications);
+        }
+        let visit = |id, description: Cow<UserApplicationDescription>| {
+            if bytecode_id.map_or(true, |bytecode_id| description.bytecode_id == bytecode_id) {
+                applications.push((id, description.into_owned()));
+            }
+            Ok(applications.len() < limit)
+        };
+        match after {
+            None => {
+                self.known_applications
+                    .for_each_index_value_while(visit)
+                    .await?
+            }
+            Some(after) => {
+                ensure!(
+                    self.known_applications.contains_key(&after).await?,
+                    SystemExecutionError::UnknownApplicationId(Box::new(after))
+                );
+                self.known_applications
+                    .for_each_index_value_after_while(&after, visit)
+                    .await?
+            }
+        }
+        Ok(applications)
+    }
+
     /// Retrieves the recursive dependencies of applications and apply a topological sort.
     pub async fn find_dependencies(
         &self,
@@ -0,0 +1,99 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Auditing of the inputs observed by user applications, to locate divergences between the
+//! executions of different validators.
+
+use linera_base::crypto::{BcsHashable, CryptoHash};
+use linera_views::batch::{Batch, WriteOperation};
+use serde::{Deserialize, Serialize};
+
+/// The running hashes of the payloads that crossed the boundary between the host and the
+/// user applications during a transaction, in order.
+///
+/// Each hash covers the previous one, so two executions observing the same inputs produce the
+/// same hashes, and the first differing index is where their inputs diverged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuestInputAudit {
+    hashes: Vec<CryptoHash>,
+}
+
+/// A payload crossing the boundary, chained to the hash of the previous one.
+#[derive(Serialize, Deserialize)]
+struct AuditEntry {
+    previous: Option<CryptoHash>,
+    label: String,
+    payload: Vec<u8>,
+}
+
+impl<'de> BcsHashable<'de> for AuditEntry {}
+
+impl GuestInputAudit {
+    /// Records a `payload` crossing the boundary, with a `label` naming the entrypoint or the
+    /// host call.
+    pub fn record(&mut self, label: &str, payload: &impl Serialize) {
+        let entry = AuditEntry {
+            previous: self.hashes.last().copied(),
+            label: label.to_owned(),
+            payload: bcs::to_bytes(payload).expect("serialization to BCS should not fail"),
+        };
+        self.hashes.push(CryptoHash::new(&entry));
+    }
+
+    /// Records the write operations of a `batch` written by an application.
+    pub fn record_batch(&mut self, label: &str, batch: &Batch) {
+        let operations = batch
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                WriteOperation::Delete { key } => (0u8, key, None),
+                WriteOperation::DeletePrefix { key_prefix } => (1, key_prefix, None),
+                WriteOperation::Put { key, value } => (2, key, Some(value)),
+            })
+            .collect::<Vec<_>>();
+        self.record(label, &operations);
+    }
+
+    /// Returns the running hashes, one per recorded payload.
+    pub fn hashes(&self) -> &[CryptoHash] {
+        &self.hashes
+    }
+
+    /// Returns the index of the first payload that differs from the ones recorded in `other`,
+    /// or `None` if the two audits are identical.
+    pub fn first_divergence(&self, other: &GuestInputAudit) -> Option<usize> {
+        let index = self
+            .hashes
+            .iter()
+            .zip(&other.hashes)
+            .position(|(hash, other_hash)| hash != other_hash)
+            .unwrap_or_else(|| self.hashes.len().min(other.hashes.len()));
+        (self.hashes != other.hashes).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_divergence() {
+        let mut audit = GuestInputAudit::default();
+        audit.record("execute_operation", &vec![1u8, 2, 3]);
+        audit.record("chain_id", &7u64);
+        let mut other = audit.clone();
+        assert_eq!(audit.first_divergence(&other), None);
+
+        audit.record("read_chain_balance", &1u64);
+        other.record("read_chain_balance", &2u64);
+        audit.record("read_system_timestamp", &0u64);
+        other.record("read_system_timestamp", &0u64);
+        assert_eq!(audit.first_divergence(&other), Some(2));
+        // The divergence is carried over to the following hashes.
+        assert_ne!(audit.hashes()[3], other.hashes()[3]);
+
+        let mut longer = audit.clone();
+        longer.record("write_batch", &Vec::<u8>::new());
+        assert_eq!(audit.first_divergence(&longer), Some(4));
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measurements of the execution time of contract code, reported by applications running in
+//! the test harness of the SDK.
+//!
+//! The clock used for the measurements is not deterministic, so the functions of the
+//! `linera:app/test-system-api` interface are only provided to the applications if the
+//! [`ExecutionRuntimeConfig`](crate::ExecutionRuntimeConfig) has a [`BenchmarkRecorder`].
+//! Otherwise, modules importing them are refused like those importing any unknown function.
+
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use linera_base::identifiers::UserApplicationId;
+
+/// The instant from which the monotonic clock provided to the applications counts.
+static CLOCK_START: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Returns the number of nanoseconds elapsed on the monotonic clock provided to the
+/// applications.
+pub(crate) fn monotonic_nanos() -> u64 {
+    CLOCK_START
+        .elapsed()
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+/// The execution time of a piece of contract code, measured by the application.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BenchmarkMeasurement {
+    /// The application that made the measurement.
+    pub application_id: UserApplicationId,
+    /// The label given to the measurement by the application.
+    pub label: String,
+    /// The measured execution time.
+    pub duration: Duration,
+}
+
+/// Collects the [`BenchmarkMeasurement`]s reported by the applications.
+///
+/// Clones share the same measurements.
+#[derive(Clone, Debug, Default)]
+pub struct BenchmarkRecorder {
+    measurements: Arc<Mutex<Vec<BenchmarkMeasurement>>>,
+}
+
+impl BenchmarkRecorder {
+    /// Records a measurement reported by an application.
+    pub fn record(&self, measurement: BenchmarkMeasurement) {
+        self.measurements
+            .lock()
+            .expect("Panics should not happen while holding a lock to the measurements")
+            .push(measurement);
+    }
+
+    /// Removes and returns the measurements recorded so far, in the order they were reported.
+    pub fn take_measurements(&self) -> Vec<BenchmarkMeasurement> {
+        std::mem::take(
+            &mut *self
+                .measurements
+                .lock()
+                .expect("Panics should not happen while holding a lock to the measurements"),
+        )
+    }
+}
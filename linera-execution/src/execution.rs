@@ -3,18 +3,21 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
-    mem, vec,
+    mem,
+    sync::Arc,
+    vec,
 };
 
 use futures::{stream::FuturesOrdered, FutureExt, StreamExt, TryStreamExt};
 use linera_base::{
-    data_types::{Amount, BlockHeight, Timestamp},
-    identifiers::{Account, AccountOwner, ChainId, Destination, Owner},
+    data_types::{Amount, BlockHeight, OracleResponse, Timestamp},
+    ensure,
+    identifiers::{Account, AccountOwner, BytecodeId, ChainId, Destination, MessageId, Owner},
 };
 use linera_views::{
     context::Context,
     key_value_store_view::KeyValueStoreView,
-    reentrant_collection_view::HashedReentrantCollectionView,
+    reentrant_collection_view::{HashedReentrantCollectionView, ReentrantCollectionView},
     views::{ClonableView, View},
 };
 use linera_views_derive::CryptoHashView;
@@ -25,16 +28,18 @@ use {
     },
     linera_base::data_types::Blob,
     linera_views::context::MemoryContext,
-    std::sync::Arc,
 };
 
 use super::{runtime::ServiceRuntimeRequest, ExecutionRequest};
 use crate::{
-    resources::ResourceController, system::SystemExecutionStateView, ContractSyncRuntime,
-    ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message,
-    MessageContext, MessageKind, Operation, OperationContext, Query, QueryContext, QueryOutcome,
-    RawExecutionOutcome, RawOutgoingMessage, ServiceSyncRuntime, SystemMessage, TransactionTracker,
-    UserApplicationDescription, UserApplicationId,
+    resources::{FeeAccount, ResourceController},
+    system::{DeadLetter, SystemExecutionStateView},
+    BenchmarkRecorder, ContractSyncRuntime, ExecutionError, ExecutionOutcome,
+    ExecutionRuntimeConfig, ExecutionRuntimeContext, Message, MessageContext, MessageKind,
+    Operation, OperationContext, Query, QueryContext, QueryOutcome, RawExecutionOutcome,
+    RawOutgoingMessage, ServiceQueryFilter, ServiceSyncRuntime, SystemExecutionError,
+    SystemMessage, SystemOperation, TransactionTracker, UserApplicationDescription,
+    UserApplicationId,
 };
 
 /// A view accessing the execution state of a chain.
@@ -44,6 +49,13 @@ pub struct ExecutionStateView<C> {
     pub system: SystemExecutionStateView<C>,
     /// User applications.
     pub users: HashedReentrantCollectionView<C, UserApplicationId, KeyValueStoreView<C>>,
+    /// The scratch space of the user applications, for data they can recompute.
+    ///
+    /// It is persisted by each validator independently and is not part of the state hash, so
+    /// its contents are best-effort: they may differ between validators, and may be wiped at
+    /// any time with [`ExecutionStateView::wipe_scratch`].
+    #[view(skip_hash)]
+    pub scratch: ReentrantCollectionView<C, UserApplicationId, KeyValueStoreView<C>>,
 }
 
 /// How to interact with a long-lived service runtime.
@@ -77,6 +89,7 @@ where
             height: application_description.creation.height,
             round: None,
             index: Some(0),
+            call_depth: 0,
         };
 
         let action = UserAction::Instantiate(context, instantiation_argument);
@@ -106,7 +119,7 @@ where
         let mut resource_controller = ResourceController {
             policy: Arc::new(policy),
             tracker,
-            account: None,
+            account: FeeAccount::default(),
         };
         let mut txn_tracker = TransactionTracker::new(next_message_index, None);
         self.run_user_action(
@@ -174,9 +187,20 @@ where
         refund_grant_to: Option<Account>,
         grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeeAccount>,
     ) -> Result<(), ExecutionError> {
-        let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
+        let ExecutionRuntimeConfig {
+            audit_guest_inputs,
+            track_view_accesses,
+            benchmark_recorder,
+            service_query_filter,
+        } = self.context().extra().execution_runtime_config();
+        if audit_guest_inputs {
+            txn_tracker.enable_guest_input_audit();
+        }
+        if track_view_accesses {
+            txn_tracker.enable_view_access_tracking();
+        }
         self.run_user_action_with_runtime(
             application_id,
             chain_id,
@@ -186,6 +210,8 @@ where
             grant,
             txn_tracker,
             resource_controller,
+            benchmark_recorder,
+            service_query_filter,
         )
         .await?;
         Ok(())
@@ -201,7 +227,9 @@ where
         refund_grant_to: Option<Account>,
         grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeeAccount>,
+        benchmark_recorder: Option<BenchmarkRecorder>,
+        service_query_filter: Option<Arc<dyn ServiceQueryFilter>>,
     ) -> Result<(), ExecutionError> {
         let mut cloned_grant = grant.as_ref().map(|x| **x);
         let initial_balance = resource_controller
@@ -213,6 +241,7 @@ where
             tracker: resource_controller.tracker,
             account: initial_balance,
         };
+        let fee_payer = resource_controller.account.sponsor();
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
         let txn_tracker_moved = mem::take(txn_tracker);
@@ -226,7 +255,10 @@ where
                 controller,
                 &action,
                 txn_tracker_moved,
-            );
+            )
+            .with_fee_payer(fee_payer)
+            .with_benchmark_recorder(benchmark_recorder)
+            .with_service_query_filter(service_query_filter);
 
             async move {
                 let code = codes.next().await.expect("we send this immediately below");
@@ -238,8 +270,9 @@ where
 
         contract_runtime_task.send(code)?;
 
+        let mut checkpoints = Vec::new();
         while let Some(request) = execution_state_receiver.next().await {
-            self.handle_request(request).await?;
+            self.handle_request(request, &mut checkpoints).await?;
         }
 
         let (controller, txn_tracker_moved) = contract_runtime_task.join().await?;
@@ -327,10 +360,30 @@ where
         local_time: Timestamp,
         operation: Operation,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeeAccount>,
     ) -> Result<(), ExecutionError> {
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match operation {
+            Operation::System(SystemOperation::RetryDeadLetter { message_id }) => {
+                self.retry_dead_letter(
+                    context,
+                    local_time,
+                    message_id,
+                    txn_tracker,
+                    resource_controller,
+                )
+                .await?;
+            }
+            Operation::System(SystemOperation::ExecuteScheduledMessage { message_id }) => {
+                self.execute_scheduled_message(
+                    context,
+                    local_time,
+                    message_id,
+                    txn_tracker,
+                    resource_controller,
+                )
+                .await?;
+            }
             Operation::System(op) => {
                 let new_application = self
                     .system
@@ -376,9 +429,9 @@ where
         context: MessageContext,
         local_time: Timestamp,
         message: Message,
-        grant: Option<&mut Amount>,
+        mut grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeeAccount>,
     ) -> Result<(), ExecutionError> {
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match message {
@@ -393,6 +446,13 @@ where
                 application_id,
                 bytes,
             } => {
+                self.register_unknown_application(
+                    application_id,
+                    grant.as_deref_mut(),
+                    txn_tracker,
+                    resource_controller,
+                )
+                .await?;
                 self.run_user_action(
                     application_id,
                     context.chain_id,
@@ -409,6 +469,161 @@ where
         Ok(())
     }
 
+    /// Registers an application that this chain has never seen, and its unknown dependencies,
+    /// using their descriptions in the locally stored states of their creator chains.
+    ///
+    /// The descriptions are recorded as oracle responses, so that the block can be replayed
+    /// without the creator chains' states. Reading them counts towards the bytes read by the
+    /// block.
+    async fn register_unknown_application(
+        &mut self,
+        id: UserApplicationId,
+        mut grant: Option<&mut Amount>,
+        txn_tracker: &mut TransactionTracker,
+        resource_controller: &mut ResourceController<FeeAccount>,
+    ) -> Result<(), ExecutionError> {
+        if self
+            .system
+            .registry
+            .known_applications
+            .contains_key(&id)
+            .await?
+        {
+            return Ok(());
+        }
+        let description = match txn_tracker.next_replayed_oracle_response()? {
+            Some(OracleResponse::Application(description)) => *description,
+            Some(_) => return Err(ExecutionError::OracleResponseMismatch),
+            None => self
+                .context()
+                .extra()
+                .describe_remote_application(id)
+                .await?
+                .ok_or_else(|| SystemExecutionError::UnknownApplicationId(Box::new(id)))?,
+        };
+        ensure!(
+            UserApplicationId::from(&description) == id,
+            ExecutionError::OracleResponseMismatch
+        );
+        let size = bcs::serialized_size(&description)?;
+        resource_controller
+            .with_state_and_grant(self, grant.as_deref_mut())
+            .await?
+            .track_bytes_read(size as u64)?;
+        txn_tracker.add_oracle_response(OracleResponse::Application(Box::new(description.clone())));
+        for required_id in &description.required_application_ids {
+            Box::pin(self.register_unknown_application(
+                *required_id,
+                grant.as_deref_mut(),
+                txn_tracker,
+                resource_controller,
+            ))
+            .await?;
+        }
+        self.system
+            .register_application(description, txn_tracker)
+            .await?;
+        Ok(())
+    }
+
+    /// Stores an incoming message in the dead-letter queue instead of executing it.
+    pub async fn park_message(
+        &mut self,
+        context: MessageContext,
+        grant: Amount,
+        message: Message,
+    ) -> Result<(), ExecutionError> {
+        assert_eq!(context.chain_id, self.context().extra().chain_id());
+        let dead_letter = DeadLetter {
+            certificate_hash: context.certificate_hash,
+            is_bouncing: context.is_bouncing,
+            authenticated_signer: context.authenticated_signer,
+            refund_grant_to: context.refund_grant_to,
+            grant,
+            message,
+        };
+        self.system
+            .dead_letters
+            .insert(&context.message_id, dead_letter)?;
+        Ok(())
+    }
+
+    /// Executes a message from the dead-letter queue, as part of the operation with the given
+    /// `context`, and removes it from the queue.
+    async fn retry_dead_letter(
+        &mut self,
+        context: OperationContext,
+        local_time: Timestamp,
+        message_id: MessageId,
+        txn_tracker: &mut TransactionTracker,
+        resource_controller: &mut ResourceController<FeeAccount>,
+    ) -> Result<(), ExecutionError> {
+        let dead_letter = self.system.take_dead_letter(message_id).await?;
+        let message_context = MessageContext {
+            chain_id: context.chain_id,
+            is_bouncing: dead_letter.is_bouncing,
+            authenticated_signer: dead_letter.authenticated_signer,
+            refund_grant_to: dead_letter.refund_grant_to,
+            height: context.height,
+            round: context.round,
+            certificate_hash: dead_letter.certificate_hash,
+            message_id,
+        };
+        let mut grant = dead_letter.grant;
+        Box::pin(self.execute_message(
+            message_context,
+            local_time,
+            dead_letter.message,
+            (grant > Amount::ZERO).then_some(&mut grant),
+            txn_tracker,
+            resource_controller,
+        ))
+        .await?;
+        if grant > Amount::ZERO && !resource_controller.policy.burn_unused_grants {
+            if let Some(refund_grant_to) = dead_letter.refund_grant_to {
+                self.send_refund(message_context, grant, refund_grant_to, txn_tracker)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes a due scheduled message, as part of the operation with the given `context`, and
+    /// removes it from the schedule.
+    async fn execute_scheduled_message(
+        &mut self,
+        context: OperationContext,
+        local_time: Timestamp,
+        message_id: MessageId,
+        txn_tracker: &mut TransactionTracker,
+        resource_controller: &mut ResourceController<FeeAccount>,
+    ) -> Result<(), ExecutionError> {
+        let timestamp = *self.system.timestamp.get();
+        let scheduled_message = self
+            .system
+            .take_due_scheduled_message(message_id, timestamp)
+            .await?;
+        let message_context = MessageContext {
+            chain_id: context.chain_id,
+            is_bouncing: false,
+            authenticated_signer: scheduled_message.authenticated_signer,
+            refund_grant_to: None,
+            height: context.height,
+            round: context.round,
+            certificate_hash: scheduled_message.certificate_hash,
+            message_id,
+        };
+        Box::pin(self.execute_message(
+            message_context,
+            local_time,
+            scheduled_message.message,
+            None,
+            txn_tracker,
+            resource_controller,
+        ))
+        .await
+    }
+
     pub async fn bounce_message(
         &self,
         context: MessageContext,
@@ -482,7 +697,7 @@ where
 
     pub async fn query_application(
         &mut self,
-        context: QueryContext,
+        mut context: QueryContext,
         query: Query,
         endpoint: Option<&mut ServiceRuntimeEndpoint>,
     ) -> Result<QueryOutcome, ExecutionError> {
@@ -495,21 +710,25 @@ where
             Query::User {
                 application_id,
                 bytes,
+                authenticated_signer,
             } => {
-                let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
+                context.authenticated_signer = authenticated_signer;
+                // Queries don't change the state, so their inputs aren't audited.
+                let max_call_depth = self.system.call_depth_limit();
                 let outcome = match endpoint {
                     Some(endpoint) => {
                         self.query_user_application_with_long_lived_service(
                             application_id,
                             context,
                             bytes,
+                            max_call_depth,
                             &mut endpoint.incoming_execution_requests,
                             &mut endpoint.runtime_request_sender,
                         )
                         .await?
                     }
                     None => {
-                        self.query_user_application(application_id, context, bytes)
+                        self.query_user_application(application_id, context, bytes, max_call_depth)
                             .await?
                     }
                 };
@@ -523,13 +742,15 @@ where
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        max_call_depth: u32,
     ) -> Result<QueryOutcome<Vec<u8>>, ExecutionError> {
         let (execution_state_sender, mut execution_state_receiver) =
             futures::channel::mpsc::unbounded();
         let (code, description) = self.load_service(application_id).await?;
 
         let service_runtime_task = linera_base::task::Blocking::spawn(move |mut codes| {
-            let mut runtime = ServiceSyncRuntime::new(execution_state_sender, context);
+            let mut runtime = ServiceSyncRuntime::new(execution_state_sender, context)
+                .with_max_call_depth(max_call_depth);
 
             async move {
                 let code = codes.next().await.expect("we send this immediately below");
@@ -541,8 +762,10 @@ where
 
         service_runtime_task.send(code)?;
 
+        // Services make no cross-application calls that could be rolled back.
+        let mut checkpoints = Vec::new();
         while let Some(request) = execution_state_receiver.next().await {
-            self.handle_request(request).await?;
+            self.handle_request(request, &mut checkpoints).await?;
         }
 
         service_runtime_task.join().await
@@ -553,6 +776,7 @@ where
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        max_call_depth: u32,
         incoming_execution_requests: &mut futures::channel::mpsc::UnboundedReceiver<
             ExecutionRequest,
         >,
@@ -566,15 +790,17 @@ where
                 application_id,
                 context,
                 query,
+                max_call_depth,
                 callback: outcome_sender,
             })
             .expect("Service runtime thread should only stop when `request_sender` is dropped");
 
+        let mut checkpoints = Vec::new();
         loop {
             futures::select! {
                 maybe_request = incoming_execution_requests.next() => {
                     if let Some(request) = maybe_request {
-                        self.handle_request(request).await?;
+                        self.handle_request(request, &mut checkpoints).await?;
                     }
                 }
                 outcome = &mut outcome_receiver => {
@@ -584,18 +810,51 @@ where
         }
     }
 
+    /// Lists up to `limit` of the applications registered on this chain, starting after the
+    /// application `after` and optionally only those using the given bytecode. See
+    /// [`ApplicationRegistryView::list_applications`](crate::ApplicationRegistryView::list_applications).
     pub async fn list_applications(
         &self,
+        after: Option<UserApplicationId>,
+        bytecode_id: Option<BytecodeId>,
+        limit: usize,
     ) -> Result<Vec<(UserApplicationId, UserApplicationDescription)>, ExecutionError> {
-        let mut applications = vec![];
-        for index in self.system.registry.known_applications.indices().await? {
-            let application_description =
-                self.system.registry.known_applications.get(&index).await?;
+        Ok(self
+            .system
+            .registry
+            .list_applications(after, bytecode_id, limit)
+            .await?)
+    }
 
-            if let Some(application_description) = application_description {
-                applications.push((index, application_description));
-            }
-        }
-        Ok(applications)
+    /// Returns whether the given application is registered on this chain, i.e. whether
+    /// contracts on this chain can call it.
+    pub async fn is_application_registered(
+        &self,
+        id: &UserApplicationId,
+    ) -> Result<bool, ExecutionError> {
+        Ok(self
+            .system
+            .registry
+            .known_applications
+            .contains_key(id)
+            .await?)
+    }
+
+    /// Returns the total size in bytes of the keys and values stored by the given
+    /// application.
+    pub async fn application_state_size(
+        &self,
+        id: &UserApplicationId,
+    ) -> Result<u64, ExecutionError> {
+        let Some(view) = self.users.try_load_entry(id).await? else {
+            return Ok(0);
+        };
+        let size = view.total_size();
+        Ok(u64::from(size.key) + u64::from(size.value))
+    }
+
+    /// Clears the scratch space of all the applications. The state hash is unchanged.
+    pub fn wipe_scratch(&mut self) {
+        self.scratch.clear();
     }
 }
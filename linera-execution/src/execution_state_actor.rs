@@ -11,12 +11,18 @@ use futures::channel::mpsc;
 #[cfg(with_metrics)]
 use linera_base::prometheus_util::{bucket_latencies, register_histogram_vec, MeasureLatency as _};
 use linera_base::{
-    data_types::{Amount, ApplicationPermissions, BlobContent, Timestamp},
+    data_types::{
+        Amount, ApplicationPermissions, BlobContent, BlockHeight, RemoteValue, Timestamp,
+    },
     hex_debug, hex_vec_debug,
-    identifiers::{Account, AccountOwner, BlobId, MessageId, Owner},
+    identifiers::{Account, AccountOwner, BlobId, ChainId, MessageId, Owner},
     ownership::ChainOwnership,
 };
-use linera_views::{batch::Batch, context::Context, views::View};
+use linera_views::{
+    batch::Batch,
+    context::Context,
+    views::{ClonableView, View},
+};
 use oneshot::Sender;
 #[cfg(with_metrics)]
 use prometheus::HistogramVec;
@@ -65,6 +71,7 @@ where
     ) -> Result<(UserContractCode, UserApplicationDescription), ExecutionError> {
         #[cfg(with_metrics)]
         let _latency = LOAD_CONTRACT_LATENCY.measure_latency();
+        self.system.check_application_permitted(&id)?;
         let description = self.system.registry.describe_application(id).await?;
         let code = self
             .context()
@@ -89,10 +96,15 @@ where
         Ok((code, description))
     }
 
+    /// Handles a request from the execution thread.
+    ///
+    /// The `checkpoints` are the copies of the execution state saved before the cross-application
+    /// calls in progress, so that a failed call can be rolled back.
     // TODO(#1416): Support concurrent I/O.
     pub(crate) async fn handle_request(
         &mut self,
         request: ExecutionRequest,
+        checkpoints: &mut Vec<Self>,
     ) -> Result<(), ExecutionError> {
         use ExecutionRequest::*;
         match request {
@@ -183,6 +195,11 @@ where
                 callback.respond(ownership);
             }
 
+            ApplicationStateSize { id, callback } => {
+                let size = self.application_state_size(&id).await?;
+                callback.respond(size);
+            }
+
             ContainsKey { id, key, callback } => {
                 let view = self.users.try_load_entry(&id).await?;
                 let result = match view {
@@ -252,6 +269,27 @@ where
             } => {
                 let mut view = self.users.try_load_entry_mut(&id).await?;
                 view.write_batch(batch).await?;
+                drop(view);
+                let size = self.application_state_size(&id).await?;
+                callback.respond(size);
+            }
+
+            ReadScratchValueBytes { id, key, callback } => {
+                let view = self.scratch.try_load_entry(&id).await?;
+                let result = match view {
+                    Some(view) => view.get(&key).await?,
+                    None => None,
+                };
+                callback.respond(result);
+            }
+
+            WriteScratchBatch {
+                id,
+                batch,
+                callback,
+            } => {
+                let mut view = self.scratch.try_load_entry_mut(&id).await?;
+                view.write_batch(batch).await?;
                 callback.respond(());
             }
 
@@ -309,6 +347,7 @@ where
                 next_message_id,
                 bytecode_id,
                 parameters,
+                instantiation_argument,
                 required_application_ids,
                 callback,
             } => {
@@ -318,7 +357,10 @@ where
                         next_message_id,
                         bytecode_id,
                         parameters,
+                        &instantiation_argument,
                         required_application_ids,
+                        // Applications created by contracts use the network-wide limit.
+                        None,
                     )
                     .await?;
                 callback.respond(Ok(create_application_result));
@@ -356,6 +398,40 @@ where
                 self.system.assert_blob_exists(blob_id).await?;
                 callback.respond(self.system.blob_used(None, blob_id).await?)
             }
+
+            ReadRemoteState {
+                chain_id,
+                application_id,
+                key,
+                height,
+                callback,
+            } => {
+                let value = self
+                    .context()
+                    .extra()
+                    .read_remote_state(chain_id, application_id, key, height)
+                    .await?;
+                callback.respond(value)
+            }
+
+            SaveCheckpoint { callback } => {
+                checkpoints.push(self.clone_unchecked()?);
+                callback.respond(());
+            }
+
+            RestoreCheckpoint { callback } => {
+                *self = checkpoints
+                    .pop()
+                    .ok_or(ExecutionError::MissingExecutionCheckpoint)?;
+                callback.respond(());
+            }
+
+            DiscardCheckpoint { callback } => {
+                checkpoints
+                    .pop()
+                    .ok_or(ExecutionError::MissingExecutionCheckpoint)?;
+                callback.respond(());
+            }
         }
 
         Ok(())
@@ -441,6 +517,12 @@ pub enum ExecutionRequest {
         callback: Sender<Option<Vec<u8>>>,
     },
 
+    ApplicationStateSize {
+        id: UserApplicationId,
+        #[debug(skip)]
+        callback: Sender<u64>,
+    },
+
     ContainsKey {
         id: UserApplicationId,
         key: Vec<u8>,
@@ -480,6 +562,21 @@ pub enum ExecutionRequest {
     },
 
     WriteBatch {
+        id: UserApplicationId,
+        batch: Batch,
+        #[debug(skip)]
+        callback: Sender<u64>,
+    },
+
+    ReadScratchValueBytes {
+        id: UserApplicationId,
+        #[debug(with = hex_debug)]
+        key: Vec<u8>,
+        #[debug(skip)]
+        callback: Sender<Option<Vec<u8>>>,
+    },
+
+    WriteScratchBatch {
         id: UserApplicationId,
         batch: Batch,
         #[debug(skip)]
@@ -513,6 +610,7 @@ pub enum ExecutionRequest {
         next_message_id: MessageId,
         bytecode_id: BytecodeId,
         parameters: Vec<u8>,
+        instantiation_argument: Vec<u8>,
         required_application_ids: Vec<UserApplicationId>,
         #[debug(skip)]
         callback: oneshot::Sender<Result<CreateApplicationResult, ExecutionError>>,
@@ -544,4 +642,32 @@ pub enum ExecutionRequest {
         #[debug(skip)]
         callback: Sender<bool>,
     },
+
+    ReadRemoteState {
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        #[debug(with = hex_debug)]
+        key: Vec<u8>,
+        height: BlockHeight,
+        #[debug(skip)]
+        callback: Sender<Option<RemoteValue>>,
+    },
+
+    /// Saves a copy of the execution state before a cross-application call.
+    SaveCheckpoint {
+        #[debug(skip)]
+        callback: Sender<()>,
+    },
+
+    /// Restores the execution state saved by the last checkpoint, after the call failed.
+    RestoreCheckpoint {
+        #[debug(skip)]
+        callback: Sender<()>,
+    },
+
+    /// Drops the last checkpoint, keeping the changes made by the call.
+    DiscardCheckpoint {
+        #[debug(skip)]
+        callback: Sender<()>,
+    },
 }
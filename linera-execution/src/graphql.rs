@@ -6,7 +6,7 @@ use std::collections::BTreeMap;
 use linera_base::{
     data_types::{Amount, Timestamp},
     doc_scalar,
-    identifiers::{AccountOwner, ChainDescription, ChainId},
+    identifiers::{AccountOwner, ChainDescription, ChainId, MessageId},
     ownership::ChainOwnership,
 };
 use linera_views::{context::Context, map_view::MapView};
@@ -102,4 +102,24 @@ impl<C: Send + Sync + Context> SystemExecutionStateView<C> {
     async fn _timestamp(&self) -> &Timestamp {
         self.timestamp.get()
     }
+
+    #[graphql(derived(name = "dead_letters"))]
+    async fn _dead_letters(&self) -> Result<Vec<MessageId>, async_graphql::Error> {
+        Ok(self.dead_letters.indices().await?)
+    }
+
+    #[graphql(derived(name = "scheduled_messages"))]
+    async fn _scheduled_messages(&self) -> Result<Vec<MessageId>, async_graphql::Error> {
+        Ok(self.scheduled_messages.indices().await?)
+    }
+
+    #[graphql(derived(name = "rejected_messages"))]
+    async fn _rejected_messages(&self) -> Result<Vec<MessageId>, async_graphql::Error> {
+        Ok(self.rejected_messages.indices().await?)
+    }
+
+    #[graphql(derived(name = "sponsorships"))]
+    async fn _sponsorships(&self) -> Result<Vec<ChainId>, async_graphql::Error> {
+        Ok(self.sponsorships.indices().await?)
+    }
 }
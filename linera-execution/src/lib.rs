@@ -8,13 +8,17 @@
 #![deny(clippy::large_futures)]
 
 mod applications;
+mod audit;
+mod benchmark;
 pub mod committee;
 mod execution;
 mod execution_state_actor;
 mod graphql;
 mod policy;
+mod query_authentication;
 mod resources;
 mod runtime;
+mod state_hash;
 pub mod system;
 #[cfg(with_testing)]
 pub mod test_utils;
@@ -22,6 +26,8 @@ mod transaction_tracker;
 mod util;
 mod wasm;
 
+#[cfg(with_testing)]
+use std::collections::BTreeMap;
 use std::{any::Any, fmt, str::FromStr, sync::Arc};
 
 use async_graphql::SimpleObject;
@@ -34,10 +40,11 @@ use derive_more::Display;
 use js_sys::wasm_bindgen::JsValue;
 use linera_base::{
     abi::Abi,
-    crypto::{BcsHashable, CryptoHash},
+    crypto::{BcsHashable, CryptoHash, PublicKey, Signature},
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, Blob, BlockHeight, DecompressionError,
-        Resources, SendMessageRequest, Timestamp, UserApplicationDescription,
+        MessageLimitError, MessageLimits, RemoteValue, Resources, SendMessageRequest, Timestamp,
+        UserApplicationDescription,
     },
     doc_scalar, hex_debug,
     identifiers::{
@@ -48,6 +55,7 @@ use linera_base::{
     task,
 };
 use linera_views::{batch::Batch, views::ViewError};
+use linera_witty::{WitLoad, WitStore, WitType};
 use serde::{Deserialize, Serialize};
 use system::OpenChainConfig;
 use thiserror::Error;
@@ -59,22 +67,30 @@ use crate::runtime::ContractSyncRuntime;
 pub use crate::wasm::test as wasm_test;
 #[cfg(with_wasm_runtime)]
 pub use crate::wasm::{
-    ContractEntrypoints, ContractSystemApi, ServiceEntrypoints, ServiceSystemApi, SystemApiData,
-    ViewSystemApi, WasmContractModule, WasmExecutionError, WasmServiceModule,
+    validate_bytecode, ContractEntrypoints, ContractSystemApi, ForbiddenImport,
+    ForbiddenImportReason, ServiceEntrypoints, ServiceSystemApi, SystemApiData, SystemApiVersion,
+    TestSystemApi, ViewSystemApi, WasmBacktrace, WasmContractModule, WasmExecutionError, WasmFrame,
+    WasmServiceModule,
 };
 pub use crate::{
     applications::ApplicationRegistryView,
+    audit::GuestInputAudit,
+    benchmark::{BenchmarkMeasurement, BenchmarkRecorder},
     execution::{ExecutionStateView, ServiceRuntimeEndpoint},
     execution_state_actor::ExecutionRequest,
-    policy::ResourceControlPolicy,
-    resources::{ResourceController, ResourceTracker},
+    policy::{ResourceControlPolicy, DEFAULT_MAXIMUM_CALL_DEPTH},
+    query_authentication::{
+        QueryAuthentication, QueryAuthenticationError, DEFAULT_QUERY_SIGNATURE_VALIDITY,
+    },
+    resources::{FeeAccount, HeapUsage, ResourceController, ResourceTracker},
     runtime::{
         ContractSyncRuntimeHandle, ServiceRuntimeRequest, ServiceSyncRuntime,
         ServiceSyncRuntimeHandle,
     },
+    state_hash::{StateHashBreakdown, StateHashPart},
     system::{
-        SystemExecutionError, SystemExecutionStateView, SystemMessage, SystemOperation,
-        SystemQuery, SystemResponse,
+        ApplicationAllowlist, DeadLetter, ScheduledMessage, SystemExecutionError,
+        SystemExecutionStateView, SystemMessage, SystemOperation, SystemQuery, SystemResponse,
     },
     transaction_tracker::TransactionTracker,
 };
@@ -83,6 +99,26 @@ pub use crate::{
 const MAX_EVENT_KEY_LEN: usize = 64;
 /// The maximum length of a stream name.
 const MAX_STREAM_NAME_LEN: usize = 64;
+/// The maximum number of events an application can emit in a single execution.
+const MAX_EVENTS_PER_EXECUTION: usize = 1_000;
+/// The fuel charged for verifying an Ed25519 signature on the host.
+pub const VERIFY_ED25519_FUEL: u64 = 50_000;
+/// The fuel charged for computing a Sha3-256 digest on the host.
+pub const SHA3_256_FUEL: u64 = 5_000;
+/// The fuel charged for computing a [`CryptoHash`] on the host.
+pub const BCS_HASH_FUEL: u64 = 5_000;
+/// The fuel charged for scheduling a message.
+pub const SCHEDULE_MESSAGE_FUEL: u64 = 10_000;
+/// The fuel charged for saving a checkpoint of the execution state before a call to another
+/// application.
+pub const CALL_CHECKPOINT_FUEL: u64 = 1_000;
+/// The fuel charged for each byte written so far in the block when saving a checkpoint, since
+/// the checkpoint copies the changes that are not stored yet.
+pub const CALL_CHECKPOINT_FUEL_PER_BYTE: u64 = 1;
+/// The maximum number of messages applications can schedule in a single block.
+const MAX_SCHEDULED_MESSAGES_PER_BLOCK: u32 = 100;
+/// The maximum size of a scheduled message in bytes.
+const MAX_SCHEDULED_MESSAGE_SIZE: usize = 4 * 1024;
 
 /// An implementation of [`UserContractModule`].
 #[derive(Clone)]
@@ -217,16 +253,29 @@ pub enum ExecutionError {
     },
     #[error("Attempt to write to storage from a contract")]
     ServiceWriteAttempt,
+    #[error("Contracts can't read the scratch space, which is not part of the state hash")]
+    ContractScratchReadAttempt,
     #[error("Failed to load bytecode from storage {0:?}")]
     ApplicationBytecodeNotFound(Box<UserApplicationDescription>),
     // TODO(#2927): support dynamic loading of modules on the Web
     #[error("Unsupported dynamic application load: {0:?}")]
     UnsupportedDynamicApplicationLoad(Box<UserApplicationId>),
+    #[error("Application {0} is not permitted to execute on this chain")]
+    ApplicationNotPermitted(Box<UserApplicationId>),
 
     #[error("Excessive number of bytes read from storage")]
     ExcessiveRead,
     #[error("Excessive number of bytes written to storage")]
     ExcessiveWrite,
+    #[error(
+        "Stored state of application {application_id} would grow to {size} bytes, \
+        exceeding the limit of {maximum_size} bytes"
+    )]
+    StateTooLarge {
+        application_id: Box<UserApplicationId>,
+        size: u64,
+        maximum_size: u64,
+    },
     #[error("Block execution required too much fuel")]
     MaximumFuelExceeded,
     #[error("Serialized size of the executed block exceeds limit")]
@@ -261,6 +310,14 @@ pub enum ExecutionError {
     EventKeyTooLong,
     #[error("Stream names can be at most {MAX_STREAM_NAME_LEN} bytes.")]
     StreamNameTooLong,
+    #[error("Applications can emit at most {MAX_EVENTS_PER_EXECUTION} events per execution.")]
+    TooManyEvents,
+    #[error("Scheduled messages can be at most {MAX_SCHEDULED_MESSAGE_SIZE} bytes.")]
+    ScheduledMessageTooLarge,
+    #[error(
+        "Applications can schedule at most {MAX_SCHEDULED_MESSAGES_PER_BLOCK} messages per block."
+    )]
+    TooManyScheduledMessages,
     // TODO(#2127): Remove this error and the unstable-oracles feature once there are fees
     // and enforced limits for all oracles.
     #[error("Unstable oracles are disabled on this network.")]
@@ -271,6 +328,40 @@ pub enum ExecutionError {
     ServiceModuleSend(#[from] linera_base::task::SendError<UserServiceCode>),
     #[error("Blobs not found: {0:?}")]
     BlobsNotFound(Vec<BlobId>),
+    #[error("Application {id} is not registered on this chain")]
+    UnknownApplication { id: Box<UserApplicationId> },
+    #[error("User application panicked at {file}:{line}:{column}: {message}")]
+    UserPanic {
+        message: String,
+        file: String,
+        line: u32,
+        column: u32,
+    },
+    #[error(
+        "Response of application {application_id} to a query has {size} bytes, \
+        exceeding the limit of {maximum_size} bytes"
+    )]
+    QueryResponseTooLarge {
+        application_id: Box<UserApplicationId>,
+        size: u64,
+        maximum_size: u64,
+    },
+    #[error("Nested application call at depth {depth} exceeds the maximum call depth")]
+    CallDepthExceeded { depth: u32 },
+    #[error(
+        "A failed call ran application {0}, which was loaded before the call, \
+        so its changes could not be rolled back"
+    )]
+    IrreversibleCallFailure(Box<UserApplicationId>),
+    #[error("There is no checkpoint of the execution state to restore or discard")]
+    MissingExecutionCheckpoint,
+    #[error("Query to the service of application {application_id} refused: {reason}")]
+    ServiceQueryRefused {
+        application_id: UserApplicationId,
+        reason: String,
+    },
+    #[error(transparent)]
+    MessageLimitExceeded(#[from] MessageLimitError),
 }
 
 impl From<ViewError> for ExecutionError {
@@ -282,12 +373,29 @@ impl From<ViewError> for ExecutionError {
     }
 }
 
+impl ExecutionError {
+    /// Returns the ID of the application that is not registered on the chain, if that is why
+    /// the execution failed.
+    pub fn unknown_application_id(&self) -> Option<UserApplicationId> {
+        match self {
+            ExecutionError::UnknownApplication { id }
+            | ExecutionError::SystemError(SystemExecutionError::UnknownApplicationId(id)) => {
+                Some(**id)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl From<SystemExecutionError> for ExecutionError {
     fn from(error: SystemExecutionError) -> Self {
         match error {
             SystemExecutionError::BlobsNotFound(blob_ids) => {
                 ExecutionError::BlobsNotFound(blob_ids)
             }
+            SystemExecutionError::ApplicationNotPermitted(application_id) => {
+                ExecutionError::ApplicationNotPermitted(application_id)
+            }
             error => ExecutionError::SystemError(error),
         }
     }
@@ -348,8 +456,93 @@ impl ApplicationCallOutcome {
 }
 
 /// Configuration options for the execution runtime available to applications.
-#[derive(Clone, Copy, Default)]
-pub struct ExecutionRuntimeConfig {}
+#[derive(Clone)]
+pub struct ExecutionRuntimeConfig {
+    /// Whether to hash every payload crossing the boundary with the user applications into a
+    /// [`GuestInputAudit`] of each transaction, to locate divergences between validators.
+    pub audit_guest_inputs: bool,
+    /// Whether to count the reads and writes to the sub-views of each application during a
+    /// transaction, and log them with the other execution traces.
+    pub track_view_accesses: bool,
+    /// Where to record the execution times measured by the contracts, if the functions of the
+    /// `linera:app/test-system-api` interface are available to them.
+    ///
+    /// Only the test harness of the SDK should set this: the measurements are not deterministic,
+    /// so validators must refuse the modules importing these functions.
+    pub benchmark_recorder: Option<BenchmarkRecorder>,
+    /// What decides whether the contracts may query a service, if anything. The queries
+    /// replayed from the oracle responses of a block are never refused.
+    pub service_query_filter: Option<Arc<dyn ServiceQueryFilter>>,
+}
+
+impl Default for ExecutionRuntimeConfig {
+    fn default() -> Self {
+        ExecutionRuntimeConfig {
+            audit_guest_inputs: false,
+            track_view_accesses: false,
+            benchmark_recorder: None,
+            service_query_filter: None,
+        }
+    }
+}
+
+/// Decides whether the contracts executed by a client may query the services of applications,
+/// e.g. to enforce the rate limits of a node service. Validators don't set one.
+pub trait ServiceQueryFilter: Send + Sync {
+    /// Returns why the query of a contract on `chain_id` to the service of `application_id`
+    /// is refused, if it is.
+    fn check(&self, chain_id: ChainId, application_id: UserApplicationId) -> Result<(), String>;
+}
+
+/// An [`ExecutionRuntimeConfig`] that can be replaced while the node is running.
+///
+/// The configuration is read once before executing each operation, message or query, so
+/// replacing it never affects the ones being executed. Clones share the same configuration.
+#[derive(Clone, Default)]
+pub struct SharedExecutionRuntimeConfig {
+    /// The configuration in effect, and how many times it was replaced.
+    inner: Arc<std::sync::RwLock<(ExecutionRuntimeConfig, u64)>>,
+}
+
+impl SharedExecutionRuntimeConfig {
+    /// Returns the configuration in effect.
+    pub fn get(&self) -> ExecutionRuntimeConfig {
+        self.inner
+            .read()
+            .expect("Panics should not happen while holding a lock to the configuration")
+            .0
+            .clone()
+    }
+
+    /// Returns the version of the configuration in effect, i.e. the number of times it was
+    /// replaced.
+    pub fn version(&self) -> u64 {
+        self.inner
+            .read()
+            .expect("Panics should not happen while holding a lock to the configuration")
+            .1
+    }
+
+    /// Replaces the configuration for the next operations, messages and queries, and returns
+    /// the version of the new configuration.
+    pub fn set(&self, config: ExecutionRuntimeConfig) -> u64 {
+        let mut inner = self
+            .inner
+            .write()
+            .expect("Panics should not happen while holding a lock to the configuration");
+        inner.0 = config;
+        inner.1 += 1;
+        inner.1
+    }
+}
+
+impl From<ExecutionRuntimeConfig> for SharedExecutionRuntimeConfig {
+    fn from(config: ExecutionRuntimeConfig) -> Self {
+        SharedExecutionRuntimeConfig {
+            inner: Arc::new(std::sync::RwLock::new((config, 0))),
+        }
+    }
+}
 
 /// Requirements for the `extra` field in our state views (and notably the
 /// [`ExecutionStateView`]).
@@ -378,6 +571,26 @@ pub trait ExecutionRuntimeContext {
 
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError>;
 
+    /// Reads the value under `key` in the state of an application on another chain, from the
+    /// state of that chain after the block at `height` stored locally, either as its current
+    /// state or as a snapshot. Never fetches anything over the network.
+    ///
+    /// Returns `None` if that state is not stored locally.
+    async fn read_remote_state(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        key: Vec<u8>,
+        height: BlockHeight,
+    ) -> Result<Option<RemoteValue>, ExecutionError>;
+
+    /// Returns the description of an application as registered in the state of its creator
+    /// chain stored locally, if available. Never fetches anything over the network.
+    async fn describe_remote_application(
+        &self,
+        id: UserApplicationId,
+    ) -> Result<Option<UserApplicationDescription>, ExecutionError>;
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
@@ -403,6 +616,9 @@ pub struct OperationContext {
     /// The current index of the operation.
     #[debug(skip_if = Option::is_none)]
     pub index: Option<u32>,
+    /// The number of applications in the call stack below this one: 0 for the transaction
+    /// entrypoint, 1 for an application it calls, and so on.
+    pub call_depth: u32,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -441,14 +657,26 @@ pub struct FinalizeContext {
     pub round: Option<u32>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, WitLoad, WitStore, WitType)]
 pub struct QueryContext {
     /// The current chain ID.
     pub chain_id: ChainId,
-    /// The height of the next block on this chain.
+    /// The height of the next block on this chain. The query is pinned to the chain state
+    /// before that block, which doesn't change while the query runs.
     pub next_block_height: BlockHeight,
     /// The local time in the node executing the query.
     pub local_time: Timestamp,
+    /// The hash of the latest block on this chain, if any. This is not known when the
+    /// service is queried by a contract during the execution of a block.
+    pub block_hash: Option<CryptoHash>,
+    /// The timestamp of the latest block on this chain.
+    pub block_timestamp: Timestamp,
+    /// The maximum size of the response to the query, in bytes. This is the network-wide
+    /// limit, or the lower limit the queried application was created with.
+    pub maximum_response_size: u64,
+    /// The owner who signed the query, if any. Unsigned queries aren't authenticated.
+    #[debug(skip_if = Option::is_none)]
+    pub authenticated_signer: Option<Owner>,
 }
 
 pub trait BaseRuntime {
@@ -493,6 +721,9 @@ pub trait BaseRuntime {
     /// Reads the current ownership configuration for this chain.
     fn chain_ownership(&mut self) -> Result<ChainOwnership, ExecutionError>;
 
+    /// Reads the total size in bytes of the keys and values stored by the current application.
+    fn read_own_state_size(&mut self) -> Result<u64, ExecutionError>;
+
     /// Tests whether a key exists in the key-value store
     #[cfg(feature = "test")]
     fn contains_key(&mut self, key: Vec<u8>) -> Result<bool, ExecutionError> {
@@ -644,12 +875,26 @@ pub trait ServiceRuntime: BaseRuntime {
 
     /// Schedules an operation to be included in the block proposed after execution.
     fn schedule_operation(&mut self, operation: Vec<u8>) -> Result<(), ExecutionError>;
+
+    /// Returns the context of the current query, describing the state of the chain it is
+    /// executed against.
+    fn query_context(&mut self) -> Result<QueryContext, ExecutionError>;
+
+    /// Reads the value under `key` in the scratch space of the current application.
+    ///
+    /// The scratch space is local to each validator or node and not part of the state hash,
+    /// so only services can read it: contracts must execute the same everywhere.
+    fn read_scratch_value_bytes(&mut self, key: Vec<u8>)
+        -> Result<Option<Vec<u8>>, ExecutionError>;
 }
 
 pub trait ContractRuntime: BaseRuntime {
     /// The authenticated signer for this execution, if there is one.
     fn authenticated_signer(&mut self) -> Result<Option<Owner>, ExecutionError>;
 
+    /// The chain sponsoring the fees of the block instead of the chain, if there is one.
+    fn fee_payer(&mut self) -> Result<Option<ChainId>, ExecutionError>;
+
     /// The current message ID, if there is one.
     fn message_id(&mut self) -> Result<Option<MessageId>, ExecutionError>;
 
@@ -667,15 +912,38 @@ pub trait ContractRuntime: BaseRuntime {
     /// Consumes some of the execution fuel.
     fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError>;
 
+    /// Records the heap usage of the current application, as reported by a contract built
+    /// with the `profiling` feature of the SDK.
+    fn record_heap_usage(&mut self, usage: HeapUsage) -> Result<(), ExecutionError>;
+
     /// Schedules a message to be sent.
     fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError>;
 
+    /// Schedules messages to be sent to several destinations, failing without sending any of
+    /// them if they exceed the [`MessageLimits`] together.
+    fn send_messages(
+        &mut self,
+        messages: Vec<SendMessageRequest<Vec<u8>>>,
+    ) -> Result<(), ExecutionError>;
+
+    /// Returns the limits on the messages the current application can send in this execution,
+    /// and how much of them it used.
+    fn message_limits(&mut self) -> Result<MessageLimits, ExecutionError>;
+
     /// Schedules to subscribe to some `channel` on a `chain`.
     fn subscribe(&mut self, chain: ChainId, channel: ChannelName) -> Result<(), ExecutionError>;
 
     /// Schedules to unsubscribe to some `channel` on a `chain`.
     fn unsubscribe(&mut self, chain: ChainId, channel: ChannelName) -> Result<(), ExecutionError>;
 
+    /// Schedules a `message` to the current application, to be executed in the first block of
+    /// this chain whose timestamp is not earlier than `due`.
+    ///
+    /// This charges [`SCHEDULE_MESSAGE_FUEL`] and counts against the [`MessageLimits`]. A block
+    /// can schedule at most `MAX_SCHEDULED_MESSAGES_PER_BLOCK` messages, of at most
+    /// `MAX_SCHEDULED_MESSAGE_SIZE` bytes each.
+    fn schedule_message(&mut self, due: Timestamp, message: Vec<u8>) -> Result<(), ExecutionError>;
+
     /// Transfers amount from source to destination.
     fn transfer(
         &mut self,
@@ -738,8 +1006,45 @@ pub trait ContractRuntime: BaseRuntime {
     /// Writes a batch of changes.
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError>;
 
+    /// Writes a batch of changes to the scratch space of the current application, which
+    /// doesn't count towards the application's state size.
+    fn write_scratch_batch(&mut self, batch: Batch) -> Result<(), ExecutionError>;
+
     /// Returns the round in which this block was validated.
     fn validation_round(&mut self) -> Result<Option<u32>, ExecutionError>;
+
+    /// Reads the value under `key` in the state of an application on another chain, as of
+    /// the last block of that chain whose messages the current block receives.
+    ///
+    /// Returns `None` if the block receives no messages from that chain, or if the state of
+    /// that chain at that height is not available locally. The result is recorded, so that
+    /// the other validators replay it instead of reading their own state.
+    fn try_read_remote_state(
+        &mut self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        key: Vec<u8>,
+    ) -> Result<Option<RemoteValue>, ExecutionError>;
+
+    /// Checks an Ed25519 `signature` of `message` by `public_key`, charging
+    /// [`VERIFY_ED25519_FUEL`]. Malformed public keys fail the check.
+    fn verify_ed25519(
+        &mut self,
+        public_key: PublicKey,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<bool, ExecutionError>;
+
+    /// Computes the Sha3-256 digest of `data`, charging [`SHA3_256_FUEL`].
+    fn sha3_256(&mut self, data: &[u8]) -> Result<Vec<u8>, ExecutionError>;
+
+    /// Computes the [`CryptoHash`] of the BCS `data` written by a hashable value, charging
+    /// [`BCS_HASH_FUEL`].
+    fn bcs_hash(&mut self, data: &[u8]) -> Result<CryptoHash, ExecutionError>;
+
+    /// Returns where to record the execution times measured by the contracts, if they are
+    /// allowed to measure them.
+    fn benchmark_recorder(&mut self) -> Option<BenchmarkRecorder>;
 }
 
 /// An operation to be executed in a block.
@@ -783,6 +1088,9 @@ pub enum Query {
         #[serde(with = "serde_bytes")]
         #[debug(with = "hex_debug")]
         bytes: Vec<u8>,
+        /// The owner who signed the query, if its signature was verified.
+        #[debug(skip_if = Option::is_none)]
+        authenticated_signer: Option<Owner>,
     },
 }
 
@@ -1049,23 +1357,56 @@ impl OperationContext {
 #[derive(Clone)]
 pub struct TestExecutionRuntimeContext {
     chain_id: ChainId,
-    execution_runtime_config: ExecutionRuntimeConfig,
+    execution_runtime_config: SharedExecutionRuntimeConfig,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
     blobs: Arc<DashMap<BlobId, Blob>>,
+    remote_states: Arc<DashMap<ChainId, RemoteChainState>>,
+    remote_applications: Arc<DashMap<UserApplicationId, UserApplicationDescription>>,
 }
 
+/// The state of another chain known to a [`TestExecutionRuntimeContext`].
+#[cfg(with_testing)]
+type RemoteChainState = (BlockHeight, BTreeMap<(UserApplicationId, Vec<u8>), Vec<u8>>);
+
 #[cfg(with_testing)]
 impl TestExecutionRuntimeContext {
-    pub fn new(chain_id: ChainId, execution_runtime_config: ExecutionRuntimeConfig) -> Self {
+    pub fn new(
+        chain_id: ChainId,
+        execution_runtime_config: impl Into<SharedExecutionRuntimeConfig>,
+    ) -> Self {
         Self {
             chain_id,
-            execution_runtime_config,
+            execution_runtime_config: execution_runtime_config.into(),
             user_contracts: Arc::default(),
             user_services: Arc::default(),
             blobs: Arc::default(),
+            remote_states: Arc::default(),
+            remote_applications: Arc::default(),
         }
     }
+
+    /// Makes the description of an application known as if it was registered in the locally
+    /// stored state of its creator chain.
+    pub fn add_remote_application(&self, description: UserApplicationDescription) {
+        self.remote_applications
+            .insert(UserApplicationId::from(&description), description);
+    }
+
+    /// Replaces the locally known state of another chain with the state after the block at
+    /// `height`, made of the values under the given keys of its applications.
+    pub fn set_remote_state(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+        values: impl IntoIterator<Item = (UserApplicationId, Vec<u8>, Vec<u8>)>,
+    ) {
+        let values = values
+            .into_iter()
+            .map(|(application_id, key, value)| ((application_id, key), value))
+            .collect();
+        self.remote_states.insert(chain_id, (height, values));
+    }
 }
 
 #[cfg(with_testing)]
@@ -1077,7 +1418,7 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
     }
 
     fn execution_runtime_config(&self) -> ExecutionRuntimeConfig {
-        self.execution_runtime_config
+        self.execution_runtime_config.get()
     }
 
     fn user_contracts(&self) -> &Arc<DashMap<UserApplicationId, UserContractCode>> {
@@ -1128,6 +1469,33 @@ impl ExecutionRuntimeContext for TestExecutionRuntimeContext {
         Ok(self.blobs.contains_key(&blob_id))
     }
 
+    async fn read_remote_state(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        key: Vec<u8>,
+        height: BlockHeight,
+    ) -> Result<Option<RemoteValue>, ExecutionError> {
+        Ok(self
+            .remote_states
+            .get(&chain_id)
+            .filter(|state| state.0 == height)
+            .map(|state| RemoteValue {
+                height,
+                value: state.1.get(&(application_id, key)).cloned(),
+            }))
+    }
+
+    async fn describe_remote_application(
+        &self,
+        id: UserApplicationId,
+    ) -> Result<Option<UserApplicationDescription>, ExecutionError> {
+        Ok(self
+            .remote_applications
+            .get(&id)
+            .map(|description| description.clone()))
+    }
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
@@ -1244,6 +1612,17 @@ impl Message {
             _ => None,
         }
     }
+
+    pub fn matches_schedule_message(&self) -> Option<(Timestamp, UserApplicationId, &[u8])> {
+        match self {
+            Message::System(SystemMessage::ScheduleMessage {
+                due,
+                application_id,
+                bytes,
+            }) => Some((*due, *application_id, bytes)),
+            _ => None,
+        }
+    }
 }
 
 impl From<SystemQuery> for Query {
@@ -1274,6 +1653,7 @@ impl Query {
         Ok(Query::User {
             application_id,
             bytes: serde_json::to_vec(&query)?,
+            authenticated_signer: None,
         })
     }
 
@@ -9,6 +9,9 @@ use async_graphql::InputObject;
 use linera_base::data_types::{Amount, ArithmeticError, Resources};
 use serde::{Deserialize, Serialize};
 
+/// The default maximum depth of nested application calls and queries.
+pub const DEFAULT_MAXIMUM_CALL_DEPTH: u64 = 64;
+
 /// A collection of prices and limits associated with block execution.
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject)]
 pub struct ResourceControlPolicy {
@@ -55,6 +58,35 @@ pub struct ResourceControlPolicy {
     pub maximum_bytes_read_per_block: u64,
     /// The maximum data to write per block
     pub maximum_bytes_written_per_block: u64,
+    /// The maximum size of the stored state of each application, in bytes.
+    pub maximum_application_state_size: u64,
+    /// The maximum number of operations per block.
+    pub maximum_operations_per_block: u64,
+    /// The maximum number of incoming message bundles per block.
+    pub maximum_incoming_bundles_per_block: u64,
+    /// The maximum number of incoming message bundles from the same origin per block. The
+    /// remaining bundles stay in the inbox for the next blocks.
+    pub maximum_incoming_bundles_per_origin: u64,
+    /// Whether the part of a message grant that was not spent on the receiving chain is
+    /// burned, instead of being refunded to the sender.
+    pub burn_unused_grants: bool,
+    /// The maximum size of the response to a query of an application, in bytes. Applications
+    /// may be created with a lower limit of their own.
+    pub maximum_query_response_size: u64,
+    /// The maximum number of messages an application can send per execution.
+    pub maximum_messages_per_execution: u64,
+    /// The maximum size of a message sent by an application, in bytes.
+    pub maximum_message_size: u64,
+    /// The maximum total size of the messages an application can send per execution, in bytes.
+    pub maximum_message_bytes_per_execution: u64,
+    /// The maximum depth of nested calls between applications, and of nested queries between
+    /// services. The application executing an operation or a message, or answering a query,
+    /// is at depth 0.
+    pub maximum_call_depth: u64,
+    /// If set, block proposals must claim the resources each of their operations uses, and
+    /// are rejected if an operation uses more than its claim, by more than this many percent
+    /// of the claim.
+    pub resource_estimate_tolerance_percent: Option<u64>,
 }
 
 impl fmt::Display for ResourceControlPolicy {
@@ -79,6 +111,17 @@ impl fmt::Display for ResourceControlPolicy {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_application_state_size,
+            maximum_operations_per_block,
+            maximum_incoming_bundles_per_block,
+            maximum_incoming_bundles_per_origin,
+            burn_unused_grants,
+            maximum_query_response_size,
+            maximum_messages_per_execution,
+            maximum_message_size,
+            maximum_message_bytes_per_execution,
+            maximum_call_depth,
+            resource_estimate_tolerance_percent,
         } = self;
         write!(
             f,
@@ -101,7 +144,28 @@ impl fmt::Display for ResourceControlPolicy {
             {maximum_bytecode_size} maximum size of service and contract bytecode\n\
             {maximum_block_proposal_size} maximum size of a block proposal\n\
             {maximum_bytes_read_per_block} maximum number bytes read per block\n\
-            {maximum_bytes_written_per_block} maximum number bytes written per block",
+            {maximum_bytes_written_per_block} maximum number bytes written per block\n\
+            {maximum_application_state_size} maximum size of the stored state of an application\n\
+            {maximum_operations_per_block} maximum number of operations per block\n\
+            {maximum_incoming_bundles_per_block} maximum number of incoming message bundles per block\n\
+            {maximum_incoming_bundles_per_origin} maximum number of incoming message bundles \
+            from the same origin per block\n\
+            unused message grants are {}\n\
+            {maximum_query_response_size} maximum size of the response to an application query\n\
+            {maximum_messages_per_execution} maximum number of messages sent per execution\n\
+            {maximum_message_size} maximum size of a message sent by an application\n\
+            {maximum_message_bytes_per_execution} maximum number of message bytes sent per execution\n\
+            {maximum_call_depth} maximum depth of nested application calls\n\
+            resource estimates in block proposals are {}",
+            if *burn_unused_grants {
+                "burned"
+            } else {
+                "refunded"
+            },
+            match resource_estimate_tolerance_percent {
+                Some(tolerance) => format!("required, with a tolerance of {tolerance}%"),
+                None => "optional".to_string(),
+            },
         )
     }
 }
@@ -128,6 +192,19 @@ impl Default for ResourceControlPolicy {
             maximum_block_proposal_size: u64::MAX,
             maximum_bytes_read_per_block: u64::MAX,
             maximum_bytes_written_per_block: u64::MAX,
+            maximum_application_state_size: u64::MAX,
+            maximum_operations_per_block: u64::MAX,
+            maximum_incoming_bundles_per_block: u64::MAX,
+            maximum_incoming_bundles_per_origin: u64::MAX,
+            burn_unused_grants: false,
+            maximum_query_response_size: u64::MAX,
+            maximum_messages_per_execution: u64::MAX,
+            maximum_message_size: u64::MAX,
+            maximum_message_bytes_per_execution: u64::MAX,
+            // Each nested call instantiates the called application, so the depth is always
+            // limited.
+            maximum_call_depth: DEFAULT_MAXIMUM_CALL_DEPTH,
+            resource_estimate_tolerance_percent: None,
         }
     }
 }
@@ -184,6 +261,11 @@ impl ResourceControlPolicy {
         self.fuel_unit.try_mul(u128::from(fuel))
     }
 
+    /// Returns the maximum depth of nested application calls, as a number of applications.
+    pub(crate) fn call_depth_limit(&self) -> u32 {
+        u32::try_from(self.maximum_call_depth).unwrap_or(u32::MAX)
+    }
+
     /// Returns how much fuel can be paid with the given balance.
     pub(crate) fn remaining_fuel(&self, balance: Amount) -> u64 {
         u64::try_from(balance.saturating_div(self.fuel_unit)).unwrap_or(u64::MAX)
@@ -251,6 +333,17 @@ impl ResourceControlPolicy {
             maximum_block_proposal_size: 13_000_000,
             maximum_bytes_read_per_block: 100_000_000,
             maximum_bytes_written_per_block: 10_000_000,
+            maximum_application_state_size: 100_000_000,
+            maximum_operations_per_block: 1_000,
+            maximum_incoming_bundles_per_block: 1_000,
+            maximum_incoming_bundles_per_origin: 100,
+            burn_unused_grants: false,
+            maximum_query_response_size: 1_000_000,
+            maximum_messages_per_execution: 1_000,
+            maximum_message_size: 1_000_000,
+            maximum_message_bytes_per_execution: 10_000_000,
+            maximum_call_depth: DEFAULT_MAXIMUM_CALL_DEPTH,
+            resource_estimate_tolerance_percent: None,
         }
     }
 }
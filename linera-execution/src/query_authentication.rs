@@ -0,0 +1,111 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signatures authenticating the owner sending a query to the service of an application.
+//!
+//! A signed query can be replayed by anyone who observes it, but only as long as its timestamp
+//! is close enough to the local time of the node running the query.
+
+use linera_base::{
+    crypto::{BcsSignable, CryptoError, KeyPair, PublicKey, Signature},
+    data_types::{TimeDelta, Timestamp},
+    identifiers::{ChainId, Owner, UserApplicationId},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(test)]
+#[path = "unit_tests/query_authentication_tests.rs"]
+mod tests;
+
+/// How far the timestamp of a signed query can be from the local time of the node, by default.
+pub const DEFAULT_QUERY_SIGNATURE_VALIDITY: TimeDelta = TimeDelta::from_micros(300_000_000);
+
+/// The value signed by an owner to authenticate a query.
+#[derive(Debug, Serialize, Deserialize)]
+struct SignedQuery {
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    #[serde(with = "serde_bytes")]
+    query: Vec<u8>,
+    timestamp: Timestamp,
+}
+
+impl<'de> BcsSignable<'de> for SignedQuery {}
+
+/// The signature of a query, sent alongside it to authenticate its owner.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct QueryAuthentication {
+    /// The public key of the owner sending the query.
+    pub public_key: PublicKey,
+    /// The signature of the chain ID, the application ID, the query bytes and the timestamp.
+    pub signature: Signature,
+    /// The time at which the query was signed.
+    pub timestamp: Timestamp,
+}
+
+/// An error verifying the signature of a query.
+#[derive(Debug, Error)]
+pub enum QueryAuthenticationError {
+    /// The query was signed too long ago, or too far in the future.
+    #[error("The query was signed at {timestamp}, too far from the local time {local_time}")]
+    Expired {
+        timestamp: Timestamp,
+        local_time: Timestamp,
+    },
+    /// The signature doesn't match the query or the public key.
+    #[error(transparent)]
+    InvalidSignature(#[from] CryptoError),
+}
+
+impl QueryAuthentication {
+    /// Signs the `query` to the application on the chain with the `key_pair`, at `timestamp`.
+    pub fn new(
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        query: &[u8],
+        timestamp: Timestamp,
+        key_pair: &KeyPair,
+    ) -> Self {
+        let value = SignedQuery {
+            chain_id,
+            application_id,
+            query: query.to_vec(),
+            timestamp,
+        };
+        QueryAuthentication {
+            public_key: key_pair.public(),
+            signature: Signature::new(&value, key_pair),
+            timestamp,
+        }
+    }
+
+    /// Verifies that this signs the `query` to the application on the chain, no further than
+    /// `validity` from the `local_time`, and returns the authenticated owner.
+    pub fn verify(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        query: &[u8],
+        local_time: Timestamp,
+        validity: TimeDelta,
+    ) -> Result<Owner, QueryAuthenticationError> {
+        let distance = local_time
+            .delta_since(self.timestamp)
+            .max(self.timestamp.delta_since(local_time));
+        if distance > validity {
+            return Err(QueryAuthenticationError::Expired {
+                timestamp: self.timestamp,
+                local_time,
+            });
+        }
+        let value = SignedQuery {
+            chain_id,
+            application_id,
+            query: query.to_vec(),
+            timestamp: self.timestamp,
+        };
+        self.signature.check(&value, self.public_key)?;
+        Ok(Owner::from(self.public_key))
+    }
+}
@@ -9,14 +9,15 @@ use custom_debug_derive::Debug;
 use linera_base::{
     data_types::{Amount, ArithmeticError},
     ensure,
-    identifiers::{AccountOwner, Owner},
+    identifiers::{AccountOwner, ChainId, Owner},
 };
 use linera_views::{context::Context, views::ViewError};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     system::SystemExecutionError, ExecutionError, ExecutionStateView, Message, Operation,
-    ResourceControlPolicy,
+    ResourceControlPolicy, CALL_CHECKPOINT_FUEL, CALL_CHECKPOINT_FUEL_PER_BYTE,
+    MAX_SCHEDULED_MESSAGES_PER_BLOCK, SCHEDULE_MESSAGE_FUEL,
 };
 
 #[derive(Clone, Debug, Default)]
@@ -58,6 +59,25 @@ pub struct ResourceTracker {
     pub message_bytes: u64,
     /// The amount allocated to message grants.
     pub grants: Amount,
+    /// The number of messages scheduled by applications.
+    pub scheduled_messages: u32,
+}
+
+/// The heap usage reported by a contract built with the `profiling` feature of the SDK.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HeapUsage {
+    /// The maximum number of bytes allocated at the same time.
+    pub peak_bytes: u64,
+    /// The total number of allocations performed.
+    pub allocations: u64,
+}
+
+impl HeapUsage {
+    /// Adds the heap usage of another contract instance, e.g. of a later transaction.
+    pub fn merge(&mut self, other: HeapUsage) {
+        self.peak_bytes = self.peak_bytes.max(other.peak_bytes);
+        self.allocations = self.allocations.saturating_add(other.allocations);
+    }
 }
 
 /// How to access the balance of an account.
@@ -185,6 +205,36 @@ where
         }
     }
 
+    /// Tracks a message scheduled by an application, charging [`SCHEDULE_MESSAGE_FUEL`].
+    pub(crate) fn track_scheduled_message(&mut self) -> Result<(), ExecutionError> {
+        let scheduled_messages = self
+            .tracker
+            .as_ref()
+            .scheduled_messages
+            .checked_add(1)
+            .ok_or(ArithmeticError::Overflow)?;
+        ensure!(
+            scheduled_messages <= MAX_SCHEDULED_MESSAGES_PER_BLOCK,
+            ExecutionError::TooManyScheduledMessages
+        );
+        self.tracker.as_mut().scheduled_messages = scheduled_messages;
+        self.track_fuel(SCHEDULE_MESSAGE_FUEL)
+    }
+
+    /// Tracks a checkpoint of the execution state saved before a call to another application,
+    /// charging [`CALL_CHECKPOINT_FUEL`] and [`CALL_CHECKPOINT_FUEL_PER_BYTE`] for each byte
+    /// written so far in the block.
+    pub(crate) fn track_call_checkpoint(&mut self) -> Result<(), ExecutionError> {
+        let fuel = self
+            .tracker
+            .as_ref()
+            .bytes_written
+            .checked_mul(CALL_CHECKPOINT_FUEL_PER_BYTE)
+            .and_then(|fuel| fuel.checked_add(CALL_CHECKPOINT_FUEL))
+            .ok_or(ArithmeticError::Overflow)?;
+        self.track_fuel(fuel)
+    }
+
     /// Tracks a number of fuel units used.
     pub(crate) fn track_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
         self.tracker.as_mut().fuel = self
@@ -340,6 +390,41 @@ impl AsRef<ResourceTracker> for ResourceTracker {
     }
 }
 
+/// The accounts paying for the resources used by a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeeAccount {
+    /// The chain's balance pays, then the account of the given owner, if any.
+    Chain(Option<Owner>),
+    /// The funds that the given chain set aside to sponsor this chain pay, instead of the
+    /// chain.
+    Sponsor(ChainId),
+}
+
+impl FeeAccount {
+    /// Returns the account paying for a block with the given authenticated signer and
+    /// sponsoring chain.
+    pub fn new(authenticated_signer: Option<Owner>, fee_payer: Option<ChainId>) -> Self {
+        match fee_payer {
+            Some(sponsor) => FeeAccount::Sponsor(sponsor),
+            None => FeeAccount::Chain(authenticated_signer),
+        }
+    }
+
+    /// Returns the sponsoring chain paying instead of the chain, if any.
+    pub fn sponsor(&self) -> Option<ChainId> {
+        match self {
+            FeeAccount::Chain(_) => None,
+            FeeAccount::Sponsor(sponsor) => Some(*sponsor),
+        }
+    }
+}
+
+impl Default for FeeAccount {
+    fn default() -> Self {
+        FeeAccount::Chain(None)
+    }
+}
+
 /// A temporary object holding a number of references to funding sources.
 pub struct Sources<'a> {
     sources: Vec<&'a mut Amount>,
@@ -355,10 +440,13 @@ impl BalanceHolder for Sources<'_> {
     }
 
     fn try_add_assign(&mut self, other: Amount) -> Result<(), ArithmeticError> {
-        // Try to credit the owner account first.
+        // Try to credit the owner account first. There is no source at all if a block's
+        // sponsorship ended before the transaction, and then nothing to credit.
         // TODO(#1648): This may need some additional design work.
-        let source = self.sources.last_mut().expect("at least one source");
-        source.try_add_assign(other)
+        match self.sources.last_mut() {
+            Some(source) => source.try_add_assign(other),
+            None => Ok(()),
+        }
     }
 
     fn try_sub_assign(&mut self, mut other: Amount) -> Result<(), ArithmeticError> {
@@ -377,7 +465,7 @@ impl BalanceHolder for Sources<'_> {
     }
 }
 
-impl ResourceController<Option<Owner>, ResourceTracker> {
+impl ResourceController<FeeAccount, ResourceTracker> {
     /// Provides a reference to the current execution state and obtains a temporary object
     /// where the accounting functions of [`ResourceController`] are available.
     pub async fn with_state<'a, C>(
@@ -402,23 +490,36 @@ impl ResourceController<Option<Owner>, ResourceTracker> {
         C: Context + Clone + Send + Sync + 'static,
     {
         let mut sources = Vec::new();
-        // First, use the grant (e.g. for messages) and otherwise use the chain account
-        // (e.g. for blocks and operations).
-        if let Some(grant) = grant {
-            sources.push(grant);
-        } else {
-            sources.push(view.system.balance.get_mut());
-        }
-        // Then the local account, if any. Currently, any negative fee (e.g. storage
-        // refund) goes preferably to this account.
-        if let Some(owner) = &self.account {
-            if let Some(balance) = view
-                .system
-                .balances
-                .get_mut(&AccountOwner::User(*owner))
-                .await?
-            {
-                sources.push(balance);
+        match self.account {
+            FeeAccount::Chain(owner) => {
+                // First, use the grant (e.g. for messages) and otherwise use the chain
+                // account (e.g. for blocks and operations).
+                if let Some(grant) = grant {
+                    sources.push(grant);
+                } else {
+                    sources.push(view.system.balance.get_mut());
+                }
+                // Then the local account, if any. Currently, any negative fee (e.g. storage
+                // refund) goes preferably to this account.
+                if let Some(owner) = owner {
+                    if let Some(balance) = view
+                        .system
+                        .balances
+                        .get_mut(&AccountOwner::User(owner))
+                        .await?
+                    {
+                        sources.push(balance);
+                    }
+                }
+            }
+            FeeAccount::Sponsor(sponsor) => {
+                // The grant is used first, then the sponsorship instead of the chain's balance.
+                if let Some(grant) = grant {
+                    sources.push(grant);
+                }
+                if let Some(sponsorship) = view.system.sponsorships.get_mut(&sponsor).await? {
+                    sources.push(&mut sponsorship.balance);
+                }
             }
         }
 
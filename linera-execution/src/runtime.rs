@@ -1,6 +1,8 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
 use std::{
     collections::{hash_map, BTreeMap, HashMap, HashSet},
     mem,
@@ -9,11 +11,13 @@ use std::{
 };
 
 use custom_debug_derive::Debug;
+#[cfg(with_metrics)]
+use linera_base::prometheus_util::{bucket_latencies, register_histogram_vec, MeasureLatency as _};
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{CryptoHash, PublicKey, Signature},
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, BlockHeight, OracleResponse, Resources,
-        SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, BlockHeight, MessageLimits,
+        OracleResponse, RemoteValue, Resources, SendMessageRequest, Timestamp,
     },
     ensure,
     identifiers::{
@@ -24,24 +28,51 @@ use linera_base::{
 };
 use linera_views::batch::Batch;
 use oneshot::Receiver;
+#[cfg(with_metrics)]
+use prometheus::HistogramVec;
+use serde::Serialize;
 
 use crate::{
     execution::UserAction,
     execution_state_actor::{ExecutionRequest, ExecutionStateSender},
     resources::ResourceController,
     system::CreateApplicationResult,
+    transaction_tracker::OutcomeCheckpoint,
     util::{ReceiverExt, UnboundedSenderExt},
-    BaseRuntime, BytecodeId, ContractRuntime, ExecutionError, FinalizeContext, MessageContext,
-    Operation, OperationContext, QueryContext, QueryOutcome, RawExecutionOutcome, ServiceRuntime,
-    TransactionTracker, UserApplicationDescription, UserApplicationId, UserContractCode,
-    UserContractInstance, UserServiceCode, UserServiceInstance, MAX_EVENT_KEY_LEN,
-    MAX_STREAM_NAME_LEN,
+    BaseRuntime, BenchmarkRecorder, BytecodeId, ContractRuntime, Destination, ExecutionError,
+    FinalizeContext, HeapUsage, MessageContext, MessageKind, Operation, OperationContext,
+    QueryContext, QueryOutcome, RawExecutionOutcome, RawOutgoingMessage, ServiceQueryFilter,
+    ServiceRuntime, SystemExecutionError, SystemMessage, TransactionTracker,
+    UserApplicationDescription, UserApplicationId, UserContractCode, UserContractInstance,
+    UserServiceCode, UserServiceInstance, BCS_HASH_FUEL, MAX_EVENTS_PER_EXECUTION,
+    MAX_EVENT_KEY_LEN, MAX_SCHEDULED_MESSAGE_SIZE, MAX_STREAM_NAME_LEN, SHA3_256_FUEL,
+    VERIFY_ED25519_FUEL,
 };
 
 #[cfg(test)]
 #[path = "unit_tests/runtime_tests.rs"]
 mod tests;
 
+#[cfg(with_metrics)]
+static CONTRACT_EXECUTION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "contract_execution_latency",
+        "Latency of running user contract code for an operation, a message or an instantiation",
+        &[],
+        bucket_latencies(2.5),
+    )
+});
+
+#[cfg(with_metrics)]
+static SERVICE_QUERY_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec(
+        "service_query_latency",
+        "Latency of running user service code for a query",
+        &[],
+        bucket_latencies(2.5),
+    )
+});
+
 #[derive(Debug)]
 pub struct SyncRuntime<UserInstance>(Option<SyncRuntimeHandle<UserInstance>>);
 
@@ -70,9 +101,19 @@ pub struct SyncRuntimeInternal<UserInstance> {
     round: Option<u32>,
     /// The current local time.
     local_time: Timestamp,
+    /// The hash of the latest block on this chain, if known. Only set for service queries.
+    #[debug(skip_if = Option::is_none)]
+    block_hash: Option<CryptoHash>,
+    /// The timestamp of the latest block on this chain. Only set for service queries.
+    block_timestamp: Timestamp,
+    /// The network-wide maximum size of the response to a query. Only set for service queries.
+    maximum_query_response_size: u64,
     /// The authenticated signer of the operation or message, if any.
     #[debug(skip_if = Option::is_none)]
     authenticated_signer: Option<Owner>,
+    /// The chain sponsoring the fees of the block instead of the chain, if any.
+    #[debug(skip_if = Option::is_none)]
+    fee_payer: Option<ChainId>,
     /// The current message being executed, if there is one.
     #[debug(skip_if = Option::is_none)]
     executing_message: Option<ExecutingMessage>,
@@ -93,6 +134,20 @@ pub struct SyncRuntimeInternal<UserInstance> {
     call_stack: Vec<ApplicationStatus>,
     /// The set of the IDs of the applications that are in the `call_stack`.
     active_applications: HashSet<UserApplicationId>,
+    /// The applications called by other applications in this transaction, in order.
+    called_applications: Vec<UserApplicationId>,
+    /// An application whose changes could not be rolled back after a failed call. The
+    /// transaction fails if it is set.
+    #[debug(skip_if = Option::is_none)]
+    irreversible_call: Option<UserApplicationId>,
+    /// The maximum number of applications in the `call_stack` below the current one.
+    max_call_depth: u32,
+    /// Where to record the execution times measured by the contracts, if they are allowed to.
+    #[debug(skip_if = Option::is_none)]
+    benchmark_recorder: Option<BenchmarkRecorder>,
+    /// What decides whether the contracts may query a service, if anything.
+    #[debug(skip)]
+    service_query_filter: Option<Arc<dyn ServiceQueryFilter>>,
     /// The tracking information for this transaction.
     transaction_tracker: TransactionTracker,
     /// The operations scheduled during this query.
@@ -121,6 +176,18 @@ struct ApplicationStatus {
     signer: Option<Owner>,
     /// The current execution outcome of the application.
     outcome: RawExecutionOutcome<Vec<u8>>,
+    /// The total size of the messages sent by the application in this execution, in bytes.
+    sent_message_bytes: u64,
+    /// The number of messages scheduled by the application in this execution.
+    scheduled_messages: u64,
+}
+
+/// The state of the runtime before a call to a different contract, restored if the call fails.
+struct CallCheckpoint {
+    call_stack_len: usize,
+    loaded_application_count: usize,
+    called_application_count: usize,
+    outcomes: OutcomeCheckpoint,
 }
 
 /// A loaded application instance.
@@ -128,6 +195,7 @@ struct ApplicationStatus {
 struct LoadedApplication<Instance> {
     instance: Arc<Mutex<Instance>>,
     parameters: Vec<u8>,
+    max_query_response_bytes: Option<u64>,
 }
 
 impl<Instance> LoadedApplication<Instance> {
@@ -136,6 +204,7 @@ impl<Instance> LoadedApplication<Instance> {
         LoadedApplication {
             instance: Arc::new(Mutex::new(instance)),
             parameters: description.parameters,
+            max_query_response_bytes: description.max_query_response_bytes,
         }
     }
 }
@@ -147,6 +216,7 @@ impl<Instance> Clone for LoadedApplication<Instance> {
         LoadedApplication {
             instance: self.instance.clone(),
             parameters: self.parameters.clone(),
+            max_query_response_bytes: self.max_query_response_bytes,
         }
     }
 }
@@ -188,12 +258,21 @@ struct QueryManager<T> {
     query_count: u32,
     /// The number of active queries.
     active_query_count: u32,
+    /// The keys or key prefixes of the queries in progress, if view accesses are tracked.
+    tracked_keys: BTreeMap<u32, Vec<Vec<u8>>>,
 }
 
 impl<T> QueryManager<T> {
-    fn register(&mut self, receiver: Receiver<T>) -> Result<u32, ExecutionError> {
+    fn register(
+        &mut self,
+        receiver: Receiver<T>,
+        tracked_keys: Option<Vec<Vec<u8>>>,
+    ) -> Result<u32, ExecutionError> {
         let id = self.query_count;
         self.pending_queries.insert(id, Promise::Pending(receiver));
+        if let Some(keys) = tracked_keys {
+            self.tracked_keys.insert(id, keys);
+        }
         self.query_count = self
             .query_count
             .checked_add(1)
@@ -215,6 +294,11 @@ impl<T> QueryManager<T> {
         Ok(value)
     }
 
+    /// Removes the keys of a query, if they are tracked.
+    fn take_tracked_keys(&mut self, id: u32) -> Option<Vec<Vec<u8>>> {
+        self.tracked_keys.remove(&id)
+    }
+
     fn force_all(&mut self) -> Result<(), ExecutionError> {
         for promise in self.pending_queries.values_mut() {
             promise.force()?;
@@ -302,7 +386,11 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
             height,
             round,
             local_time,
+            block_hash: None,
+            block_timestamp: Timestamp::from(0),
+            maximum_query_response_size: u64::MAX,
             authenticated_signer,
+            fee_payer: None,
             executing_message,
             execution_state_sender,
             is_finalizing: false,
@@ -310,6 +398,11 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
             loaded_applications: HashMap::new(),
             call_stack: Vec::new(),
             active_applications: HashSet::new(),
+            called_applications: Vec::new(),
+            irreversible_call: None,
+            max_call_depth: resource_controller.policy.call_depth_limit(),
+            benchmark_recorder: None,
+            service_query_filter: None,
             view_user_states: BTreeMap::new(),
             refund_grant_to,
             resource_controller,
@@ -318,6 +411,27 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
         }
     }
 
+    /// Records a `payload` crossing the boundary with the applications, if the inputs of the
+    /// transaction are audited.
+    fn audit(&mut self, label: &str, payload: &impl Serialize) {
+        if let Some(audit) = self.transaction_tracker.guest_input_audit_mut() {
+            audit.record(label, payload);
+        }
+    }
+
+    /// Records the value returned to an application by a host call, if the inputs of the
+    /// transaction are audited.
+    fn audit_result<T: Serialize>(
+        &mut self,
+        label: &str,
+        result: Result<T, ExecutionError>,
+    ) -> Result<T, ExecutionError> {
+        if let Ok(value) = &result {
+            self.audit(label, value);
+        }
+        result
+    }
+
     /// Returns the [`ApplicationStatus`] of the current application.
     ///
     /// The current application is the last to be pushed to the `call_stack`.
@@ -368,6 +482,36 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
         status
     }
 
+    /// Returns the limits on the messages the current application can send, and how much of
+    /// them it used in this execution.
+    fn message_limits(&mut self) -> MessageLimits {
+        let policy = &self.resource_controller.policy;
+        let maximum_messages = policy.maximum_messages_per_execution;
+        let maximum_message_size = policy.maximum_message_size;
+        let maximum_message_bytes = policy.maximum_message_bytes_per_execution;
+        let application = self.current_application();
+        MessageLimits {
+            maximum_messages,
+            maximum_message_size,
+            maximum_message_bytes,
+            sent_messages: (application.outcome.messages.len() as u64)
+                .saturating_add(application.scheduled_messages),
+            sent_message_bytes: application.sent_message_bytes,
+        }
+    }
+
+    /// Ensures that a new call doesn't exceed the maximum call depth.
+    ///
+    /// Returns the depth of the new call, i.e. the number of applications in the call stack.
+    fn check_call_depth(&self) -> Result<u32, ExecutionError> {
+        let depth = u32::try_from(self.call_stack.len()).unwrap_or(u32::MAX);
+        ensure!(
+            depth <= self.max_call_depth,
+            ExecutionError::CallDepthExceeded { depth }
+        );
+        Ok(depth)
+    }
+
     /// Ensures that a call to `application_id` is not-reentrant.
     ///
     /// Returns an error if there already is an entry for `application_id` in the call stack.
@@ -425,6 +569,7 @@ impl SyncRuntimeInternal<UserContractInstance> {
         callee_id: UserApplicationId,
     ) -> Result<(Arc<Mutex<UserContractInstance>>, OperationContext), ExecutionError> {
         self.check_for_reentrancy(callee_id)?;
+        let call_depth = self.check_call_depth()?;
 
         ensure!(
             !self.is_finalizing,
@@ -434,8 +579,17 @@ impl SyncRuntimeInternal<UserContractInstance> {
             }
         );
 
-        // Load the application.
-        let application = self.load_contract_instance(this, callee_id)?;
+        // Load the application, reporting unregistered callees so that the caller can handle it.
+        let application =
+            self.load_contract_instance(this, callee_id)
+                .map_err(|error| match error {
+                    ExecutionError::SystemError(SystemExecutionError::UnknownApplicationId(id))
+                        if *id == callee_id =>
+                    {
+                        ExecutionError::UnknownApplication { id }
+                    }
+                    error => error,
+                })?;
 
         let caller = self.current_application();
         let caller_id = caller.id;
@@ -453,7 +607,9 @@ impl SyncRuntimeInternal<UserContractInstance> {
             height: self.height,
             round: self.round,
             index: None,
+            call_depth,
         };
+        self.called_applications.push(callee_id);
         self.push_application(ApplicationStatus {
             caller_id: authenticated_caller_id,
             id: callee_id,
@@ -461,6 +617,8 @@ impl SyncRuntimeInternal<UserContractInstance> {
             // Allow further nested calls to be authenticated if this one is.
             signer: authenticated_signer,
             outcome: RawExecutionOutcome::default(),
+            sent_message_bytes: 0,
+            scheduled_messages: 0,
         });
         Ok((application.instance, callee_context))
     }
@@ -479,6 +637,65 @@ impl SyncRuntimeInternal<UserContractInstance> {
         Ok(())
     }
 
+    /// Saves the state of the runtime and of the execution state before a call to a different
+    /// contract.
+    ///
+    /// Copying the execution state costs more the more the block has changed it, so this is
+    /// charged in proportion to the bytes written so far.
+    fn save_checkpoint(&mut self) -> Result<CallCheckpoint, ExecutionError> {
+        self.resource_controller.track_call_checkpoint()?;
+        self.execution_state_sender
+            .send_request(|callback| ExecutionRequest::SaveCheckpoint { callback })?
+            .recv_response()?;
+        Ok(CallCheckpoint {
+            call_stack_len: self.call_stack.len(),
+            loaded_application_count: self.applications_to_finalize.len(),
+            called_application_count: self.called_applications.len(),
+            outcomes: self.transaction_tracker.outcome_checkpoint(),
+        })
+    }
+
+    /// Keeps the changes made since the last checkpoint, after a call succeeded.
+    fn discard_checkpoint(&mut self) -> Result<(), ExecutionError> {
+        self.execution_state_sender
+            .send_request(|callback| ExecutionRequest::DiscardCheckpoint { callback })?
+            .recv_response()
+    }
+
+    /// Rolls back a failed call to a different contract, so that the caller can handle the
+    /// error and call the same applications again.
+    ///
+    /// Every frame above the `checkpoint` is unwound, the outcomes of the calls made since then
+    /// are discarded, and the execution state is restored. The instances loaded during the call
+    /// are dropped without being finalized, so that their state is not stored. The instances
+    /// loaded before keep whatever they did in memory, so if the call ran any of them, the
+    /// transaction fails instead.
+    fn abort_call(&mut self, checkpoint: CallCheckpoint) -> Result<(), ExecutionError> {
+        self.execution_state_sender
+            .send_request(|callback| ExecutionRequest::RestoreCheckpoint { callback })?
+            .recv_response()?;
+        while self.call_stack.len() > checkpoint.call_stack_len {
+            self.pop_application();
+        }
+        self.transaction_tracker
+            .restore_outcomes(checkpoint.outcomes);
+        for id in self
+            .applications_to_finalize
+            .drain(checkpoint.loaded_application_count..)
+        {
+            self.loaded_applications.remove(&id);
+        }
+        for id in self
+            .called_applications
+            .drain(checkpoint.called_application_count..)
+        {
+            if self.loaded_applications.contains_key(&id) {
+                self.irreversible_call.get_or_insert(id);
+            }
+        }
+        Ok(())
+    }
+
     /// Handles a newly produced [`RawExecutionOutcome`], conditioning and adding it to the stack
     /// of outcomes.
     ///
@@ -562,6 +779,48 @@ impl<UserInstance> SyncRuntimeHandle<UserInstance> {
             .try_lock()
             .expect("Synchronous runtimes run on a single execution thread")
     }
+
+    /// Runs a host call, auditing its `argument` and its result.
+    fn audited_call<Argument: Serialize, Output: Serialize>(
+        &self,
+        label: &str,
+        argument: Argument,
+        call: impl FnOnce(
+            &mut SyncRuntimeInternal<UserInstance>,
+            Argument,
+        ) -> Result<Output, ExecutionError>,
+    ) -> Result<Output, ExecutionError> {
+        let mut this = self.inner();
+        this.audit(label, &argument);
+        let result = call(&mut this, argument);
+        this.audit_result(label, result)
+    }
+
+    /// Runs a host call, only auditing its `argument`.
+    fn audited_request<Argument: Serialize, Output>(
+        &self,
+        label: &str,
+        argument: Argument,
+        call: impl FnOnce(
+            &mut SyncRuntimeInternal<UserInstance>,
+            Argument,
+        ) -> Result<Output, ExecutionError>,
+    ) -> Result<Output, ExecutionError> {
+        let mut this = self.inner();
+        this.audit(label, &argument);
+        call(&mut this, argument)
+    }
+
+    /// Runs a host call without arguments, auditing its result.
+    fn audited_response<Output: Serialize>(
+        &self,
+        label: &str,
+        call: impl FnOnce(&mut SyncRuntimeInternal<UserInstance>) -> Result<Output, ExecutionError>,
+    ) -> Result<Output, ExecutionError> {
+        let mut this = self.inner();
+        let result = call(&mut this);
+        this.audit_result(label, result)
+    }
 }
 
 impl<UserInstance> BaseRuntime for SyncRuntimeHandle<UserInstance> {
@@ -576,125 +835,155 @@ impl<UserInstance> BaseRuntime for SyncRuntimeHandle<UserInstance> {
         <SyncRuntimeInternal<UserInstance> as BaseRuntime>::FindKeyValuesByPrefix;
 
     fn chain_id(&mut self) -> Result<ChainId, ExecutionError> {
-        self.inner().chain_id()
+        self.audited_response("chain_id", |this| this.chain_id())
     }
 
     fn block_height(&mut self) -> Result<BlockHeight, ExecutionError> {
-        self.inner().block_height()
+        self.audited_response("block_height", |this| this.block_height())
     }
 
     fn application_id(&mut self) -> Result<UserApplicationId, ExecutionError> {
-        self.inner().application_id()
+        self.audited_response("application_id", |this| this.application_id())
     }
 
     fn application_creator_chain_id(&mut self) -> Result<ChainId, ExecutionError> {
-        self.inner().application_creator_chain_id()
+        self.audited_response("application_creator_chain_id", |this| {
+            this.application_creator_chain_id()
+        })
     }
 
     fn application_parameters(&mut self) -> Result<Vec<u8>, ExecutionError> {
-        self.inner().application_parameters()
+        self.audited_response("application_parameters", |this| {
+            this.application_parameters()
+        })
     }
 
     fn read_system_timestamp(&mut self) -> Result<Timestamp, ExecutionError> {
-        self.inner().read_system_timestamp()
+        self.audited_response("read_system_timestamp", |this| this.read_system_timestamp())
     }
 
     fn read_chain_balance(&mut self) -> Result<Amount, ExecutionError> {
-        self.inner().read_chain_balance()
+        self.audited_response("read_chain_balance", |this| this.read_chain_balance())
     }
 
     fn read_owner_balance(&mut self, owner: AccountOwner) -> Result<Amount, ExecutionError> {
-        self.inner().read_owner_balance(owner)
+        self.audited_call("read_owner_balance", owner, |this, owner| {
+            this.read_owner_balance(owner)
+        })
     }
 
     fn read_owner_balances(&mut self) -> Result<Vec<(AccountOwner, Amount)>, ExecutionError> {
-        self.inner().read_owner_balances()
+        self.audited_response("read_owner_balances", |this| this.read_owner_balances())
     }
 
     fn read_balance_owners(&mut self) -> Result<Vec<AccountOwner>, ExecutionError> {
-        self.inner().read_balance_owners()
+        self.audited_response("read_balance_owners", |this| this.read_balance_owners())
     }
 
     fn chain_ownership(&mut self) -> Result<ChainOwnership, ExecutionError> {
-        self.inner().chain_ownership()
+        self.audited_response("chain_ownership", |this| this.chain_ownership())
+    }
+
+    fn read_own_state_size(&mut self) -> Result<u64, ExecutionError> {
+        self.audited_response("read_own_state_size", |this| this.read_own_state_size())
     }
 
     fn contains_key_new(&mut self, key: Vec<u8>) -> Result<Self::ContainsKey, ExecutionError> {
-        self.inner().contains_key_new(key)
+        self.audited_request("contains_key", key, |this, key| this.contains_key_new(key))
     }
 
     fn contains_key_wait(&mut self, promise: &Self::ContainsKey) -> Result<bool, ExecutionError> {
-        self.inner().contains_key_wait(promise)
+        self.audited_response("contains_key", |this| this.contains_key_wait(promise))
     }
 
     fn contains_keys_new(
         &mut self,
         keys: Vec<Vec<u8>>,
     ) -> Result<Self::ContainsKeys, ExecutionError> {
-        self.inner().contains_keys_new(keys)
+        self.audited_request("contains_keys", keys, |this, keys| {
+            this.contains_keys_new(keys)
+        })
     }
 
     fn contains_keys_wait(
         &mut self,
         promise: &Self::ContainsKeys,
     ) -> Result<Vec<bool>, ExecutionError> {
-        self.inner().contains_keys_wait(promise)
+        self.audited_response("contains_keys", |this| this.contains_keys_wait(promise))
     }
 
     fn read_multi_values_bytes_new(
         &mut self,
         keys: Vec<Vec<u8>>,
     ) -> Result<Self::ReadMultiValuesBytes, ExecutionError> {
-        self.inner().read_multi_values_bytes_new(keys)
+        self.audited_request("read_multi_values_bytes", keys, |this, keys| {
+            this.read_multi_values_bytes_new(keys)
+        })
     }
 
     fn read_multi_values_bytes_wait(
         &mut self,
         promise: &Self::ReadMultiValuesBytes,
     ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError> {
-        self.inner().read_multi_values_bytes_wait(promise)
+        self.audited_response("read_multi_values_bytes", |this| {
+            this.read_multi_values_bytes_wait(promise)
+        })
     }
 
     fn read_value_bytes_new(
         &mut self,
         key: Vec<u8>,
     ) -> Result<Self::ReadValueBytes, ExecutionError> {
-        self.inner().read_value_bytes_new(key)
+        self.audited_request("read_value_bytes", key, |this, key| {
+            this.read_value_bytes_new(key)
+        })
     }
 
     fn read_value_bytes_wait(
         &mut self,
         promise: &Self::ReadValueBytes,
     ) -> Result<Option<Vec<u8>>, ExecutionError> {
-        self.inner().read_value_bytes_wait(promise)
+        self.audited_response("read_value_bytes", |this| {
+            this.read_value_bytes_wait(promise)
+        })
     }
 
     fn find_keys_by_prefix_new(
         &mut self,
         key_prefix: Vec<u8>,
     ) -> Result<Self::FindKeysByPrefix, ExecutionError> {
-        self.inner().find_keys_by_prefix_new(key_prefix)
+        self.audited_request("find_keys_by_prefix", key_prefix, |this, key_prefix| {
+            this.find_keys_by_prefix_new(key_prefix)
+        })
     }
 
     fn find_keys_by_prefix_wait(
         &mut self,
         promise: &Self::FindKeysByPrefix,
     ) -> Result<Vec<Vec<u8>>, ExecutionError> {
-        self.inner().find_keys_by_prefix_wait(promise)
+        self.audited_response("find_keys_by_prefix", |this| {
+            this.find_keys_by_prefix_wait(promise)
+        })
     }
 
     fn find_key_values_by_prefix_new(
         &mut self,
         key_prefix: Vec<u8>,
     ) -> Result<Self::FindKeyValuesByPrefix, ExecutionError> {
-        self.inner().find_key_values_by_prefix_new(key_prefix)
+        self.audited_request(
+            "find_key_values_by_prefix",
+            key_prefix,
+            |this, key_prefix| this.find_key_values_by_prefix_new(key_prefix),
+        )
     }
 
     fn find_key_values_by_prefix_wait(
         &mut self,
         promise: &Self::FindKeyValuesByPrefix,
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ExecutionError> {
-        self.inner().find_key_values_by_prefix_wait(promise)
+        self.audited_response("find_key_values_by_prefix", |this| {
+            this.find_key_values_by_prefix_wait(promise)
+        })
     }
 
     fn query_service(
@@ -702,7 +991,11 @@ impl<UserInstance> BaseRuntime for SyncRuntimeHandle<UserInstance> {
         application_id: ApplicationId,
         query: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        self.inner().query_service(application_id, query)
+        self.audited_call(
+            "query_service",
+            (application_id, query),
+            |this, (application_id, query)| this.query_service(application_id, query),
+        )
     }
 
     fn http_post(
@@ -711,19 +1004,29 @@ impl<UserInstance> BaseRuntime for SyncRuntimeHandle<UserInstance> {
         content_type: String,
         payload: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        self.inner().http_post(url, content_type, payload)
+        self.audited_call(
+            "http_post",
+            (url, content_type, payload),
+            |this, (url, content_type, payload)| this.http_post(url, content_type, payload),
+        )
     }
 
     fn assert_before(&mut self, timestamp: Timestamp) -> Result<(), ExecutionError> {
-        self.inner().assert_before(timestamp)
+        self.audited_request("assert_before", timestamp, |this, timestamp| {
+            this.assert_before(timestamp)
+        })
     }
 
     fn read_data_blob(&mut self, hash: &CryptoHash) -> Result<Vec<u8>, ExecutionError> {
-        self.inner().read_data_blob(hash)
+        self.audited_call("read_data_blob", hash, |this, hash| {
+            this.read_data_blob(hash)
+        })
     }
 
     fn assert_data_blob_exists(&mut self, hash: &CryptoHash) -> Result<(), ExecutionError> {
-        self.inner().assert_data_blob_exists(hash)
+        self.audited_request("assert_data_blob_exists", hash, |this, hash| {
+            this.assert_data_blob_exists(hash)
+        })
     }
 }
 
@@ -792,20 +1095,35 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
             .recv_response()
     }
 
+    fn read_own_state_size(&mut self) -> Result<u64, ExecutionError> {
+        let id = self.application_id()?;
+        self.execution_state_sender
+            .send_request(|callback| ExecutionRequest::ApplicationStateSize { id, callback })?
+            .recv_response()
+    }
+
     fn contains_key_new(&mut self, key: Vec<u8>) -> Result<Self::ContainsKey, ExecutionError> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| vec![key.clone()]);
         let receiver = self
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ContainsKey { id, key, callback })?;
-        state.contains_key_queries.register(receiver)
+        state.contains_key_queries.register(receiver, tracked_keys)
     }
 
     fn contains_key_wait(&mut self, promise: &Self::ContainsKey) -> Result<bool, ExecutionError> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         let value = state.contains_key_queries.wait(*promise)?;
+        if let Some(keys) = state.contains_key_queries.take_tracked_keys(*promise) {
+            self.transaction_tracker
+                .record_view_reads(id, keys.iter().map(|key| (key, 0)));
+        }
         Ok(value)
     }
 
@@ -816,10 +1134,14 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| keys.clone());
         let receiver = self
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ContainsKeys { id, keys, callback })?;
-        state.contains_keys_queries.register(receiver)
+        state.contains_keys_queries.register(receiver, tracked_keys)
     }
 
     fn contains_keys_wait(
@@ -829,6 +1151,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         let value = state.contains_keys_queries.wait(*promise)?;
+        if let Some(keys) = state.contains_keys_queries.take_tracked_keys(*promise) {
+            self.transaction_tracker
+                .record_view_reads(id, keys.iter().map(|key| (key, 0)));
+        }
         Ok(value)
     }
 
@@ -839,10 +1165,16 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| keys.clone());
         let receiver = self.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::ReadMultiValuesBytes { id, keys, callback }
         })?;
-        state.read_multi_values_queries.register(receiver)
+        state
+            .read_multi_values_queries
+            .register(receiver, tracked_keys)
     }
 
     fn read_multi_values_bytes_wait(
@@ -858,6 +1190,13 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
                     .track_bytes_read(value.len() as u64)?;
             }
         }
+        if let Some(keys) = state.read_multi_values_queries.take_tracked_keys(*promise) {
+            let sizes = values
+                .iter()
+                .map(|value| value.as_ref().map_or(0, Vec::len));
+            self.transaction_tracker
+                .record_view_reads(id, keys.iter().zip(sizes));
+        }
         Ok(values)
     }
 
@@ -868,10 +1207,14 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| vec![key.clone()]);
         let receiver = self
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ReadValueBytes { id, key, callback })?;
-        state.read_value_queries.register(receiver)
+        state.read_value_queries.register(receiver, tracked_keys)
     }
 
     fn read_value_bytes_wait(
@@ -885,6 +1228,11 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
             self.resource_controller
                 .track_bytes_read(value.len() as u64)?;
         }
+        if let Some(keys) = state.read_value_queries.take_tracked_keys(*promise) {
+            let size = value.as_ref().map_or(0, Vec::len);
+            self.transaction_tracker
+                .record_view_reads(id, keys.iter().map(|key| (key, size)));
+        }
         Ok(value)
     }
 
@@ -895,6 +1243,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| vec![key_prefix.clone()]);
         let receiver = self.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::FindKeysByPrefix {
                 id,
@@ -902,7 +1254,7 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
                 callback,
             }
         })?;
-        state.find_keys_queries.register(receiver)
+        state.find_keys_queries.register(receiver, tracked_keys)
     }
 
     fn find_keys_by_prefix_wait(
@@ -918,6 +1270,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         }
         self.resource_controller
             .track_bytes_read(read_size as u64)?;
+        if let Some(prefixes) = state.find_keys_queries.take_tracked_keys(*promise) {
+            self.transaction_tracker
+                .record_view_reads(id, prefixes.iter().map(|prefix| (prefix, read_size)));
+        }
         Ok(keys)
     }
 
@@ -928,6 +1284,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         let id = self.application_id()?;
         let state = self.view_user_states.entry(id).or_default();
         self.resource_controller.track_read_operations(1)?;
+        let tracked_keys = self
+            .transaction_tracker
+            .tracks_view_accesses()
+            .then(|| vec![key_prefix.clone()]);
         let receiver = self.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::FindKeyValuesByPrefix {
                 id,
@@ -935,7 +1295,9 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
                 callback,
             }
         })?;
-        state.find_key_values_queries.register(receiver)
+        state
+            .find_key_values_queries
+            .register(receiver, tracked_keys)
     }
 
     fn find_key_values_by_prefix_wait(
@@ -951,6 +1313,10 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
         }
         self.resource_controller
             .track_bytes_read(read_size as u64)?;
+        if let Some(prefixes) = state.find_key_values_queries.take_tracked_keys(*promise) {
+            self.transaction_tracker
+                .record_view_reads(id, prefixes.iter().map(|prefix| (prefix, read_size)));
+        }
         Ok(key_values)
     }
 
@@ -963,6 +1329,7 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
             cfg!(feature = "unstable-oracles"),
             ExecutionError::UnstableOracle
         );
+        let maximum_response_size = self.resource_controller.policy.maximum_query_response_size;
         let response =
             if let Some(response) = self.transaction_tracker.next_replayed_oracle_response()? {
                 match response {
@@ -970,17 +1337,33 @@ impl<UserInstance> BaseRuntime for SyncRuntimeInternal<UserInstance> {
                     _ => return Err(ExecutionError::OracleResponseMismatch),
                 }
             } else {
+                if let Some(filter) = &self.service_query_filter {
+                    filter
+                        .check(self.chain_id, application_id)
+                        .map_err(|reason| ExecutionError::ServiceQueryRefused {
+                            application_id,
+                            reason,
+                        })?;
+                }
+                // The block being executed has no hash yet, so the service sees the
+                // timestamp of the chain before it.
                 let context = QueryContext {
                     chain_id: self.chain_id,
                     next_block_height: self.height,
                     local_time: self.local_time,
+                    block_hash: None,
+                    block_timestamp: self.read_system_timestamp()?,
+                    maximum_response_size,
+                    authenticated_signer: None,
                 };
                 let sender = self.execution_state_sender.clone();
 
                 let QueryOutcome {
                     response,
                     operations,
-                } = ServiceSyncRuntime::new(sender, context).run_query(application_id, query)?;
+                } = ServiceSyncRuntime::new(sender, context)
+                    .with_max_call_depth(self.max_call_depth)
+                    .run_query(application_id, query)?;
 
                 self.scheduled_operations.extend(operations);
                 response
@@ -1102,6 +1485,32 @@ impl ContractSyncRuntime {
         )))
     }
 
+    /// Sets the chain sponsoring the fees of the block instead of the chain, if any.
+    pub(crate) fn with_fee_payer(self, fee_payer: Option<ChainId>) -> Self {
+        self.inner().fee_payer = fee_payer;
+        self
+    }
+
+    /// Allows the contracts to measure their execution times, recording them in the
+    /// `benchmark_recorder`.
+    pub(crate) fn with_benchmark_recorder(
+        self,
+        benchmark_recorder: Option<BenchmarkRecorder>,
+    ) -> Self {
+        self.inner().benchmark_recorder = benchmark_recorder;
+        self
+    }
+
+    /// Refuses the queries of the contracts to services that the `service_query_filter`
+    /// doesn't allow.
+    pub(crate) fn with_service_query_filter(
+        self,
+        service_query_filter: Option<Arc<dyn ServiceQueryFilter>>,
+    ) -> Self {
+        self.inner().service_query_filter = service_query_filter;
+        self
+    }
+
     pub(crate) fn preload_contract(
         &self,
         id: UserApplicationId,
@@ -1157,11 +1566,20 @@ impl ContractSyncRuntimeHandle {
         };
 
         {
-            let runtime = self.inner();
+            let mut runtime = self.inner();
             assert_eq!(runtime.authenticated_signer, action.signer());
             assert_eq!(runtime.chain_id, chain_id);
             assert_eq!(runtime.height, action.height());
+            match &action {
+                UserAction::Instantiate(_, argument) => runtime.audit("instantiate", argument),
+                UserAction::Operation(_, operation) => {
+                    runtime.audit("execute_operation", operation)
+                }
+                UserAction::Message(_, message) => runtime.audit("execute_message", message),
+            }
         }
+        #[cfg(with_metrics)]
+        let _execution_latency = CONTRACT_EXECUTION_LATENCY.measure_latency();
         self.execute(application_id, action.signer(), move |code| match action {
             UserAction::Instantiate(context, argument) => code.instantiate(context, argument),
             UserAction::Operation(context, operation) => {
@@ -1169,10 +1587,33 @@ impl ContractSyncRuntimeHandle {
             }
             UserAction::Message(context, message) => code.execute_message(context, message),
         })?;
+        if let Some(id) = self.inner().irreversible_call {
+            return Err(ExecutionError::IrreversibleCallFailure(Box::new(id)));
+        }
         self.finalize(finalize_context)?;
         Ok(())
     }
 
+    /// Executes a call to a different contract, leaving the rollback to the caller if it fails.
+    fn execute_call(
+        &mut self,
+        authenticated: bool,
+        callee_id: UserApplicationId,
+        argument: Vec<u8>,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        let (contract, context) =
+            self.inner()
+                .prepare_for_call(self.clone(), authenticated, callee_id)?;
+
+        let value = contract
+            .try_lock()
+            .expect("Applications should not have reentrant calls")
+            .execute_operation(context, argument)?;
+
+        self.inner().finish_call()?;
+        Ok(value)
+    }
+
     /// Notifies all loaded applications that execution is finalizing.
     fn finalize(&mut self, context: FinalizeContext) -> Result<(), ExecutionError> {
         let applications = mem::take(&mut self.inner().applications_to_finalize)
@@ -1208,6 +1649,8 @@ impl ContractSyncRuntimeHandle {
                 parameters: application.parameters.clone(),
                 signer,
                 outcome: RawExecutionOutcome::default(),
+                sent_message_bytes: 0,
+                scheduled_messages: 0,
             };
 
             runtime.push_application(status);
@@ -1229,6 +1672,10 @@ impl ContractSyncRuntimeHandle {
         assert_eq!(application_status.parameters, contract.parameters);
         assert_eq!(application_status.signer, signer);
         assert!(runtime.call_stack.is_empty());
+        assert!(
+            runtime.active_applications.is_empty(),
+            "Failed calls should have been removed from the active applications"
+        );
 
         runtime.handle_outcome(application_status.outcome, signer, application_id)?;
 
@@ -1238,26 +1685,32 @@ impl ContractSyncRuntimeHandle {
 
 impl ContractRuntime for ContractSyncRuntimeHandle {
     fn authenticated_signer(&mut self) -> Result<Option<Owner>, ExecutionError> {
-        Ok(self.inner().authenticated_signer)
+        self.audited_response("authenticated_signer", |this| Ok(this.authenticated_signer))
+    }
+
+    fn fee_payer(&mut self) -> Result<Option<ChainId>, ExecutionError> {
+        self.audited_response("fee_payer", |this| Ok(this.fee_payer))
     }
 
     fn message_id(&mut self) -> Result<Option<MessageId>, ExecutionError> {
-        Ok(self.inner().executing_message.map(|metadata| metadata.id))
+        self.audited_response("message_id", |this| {
+            Ok(this.executing_message.map(|metadata| metadata.id))
+        })
     }
 
     fn message_is_bouncing(&mut self) -> Result<Option<bool>, ExecutionError> {
-        Ok(self
-            .inner()
-            .executing_message
-            .map(|metadata| metadata.is_bouncing))
+        self.audited_response("message_is_bouncing", |this| {
+            Ok(this.executing_message.map(|metadata| metadata.is_bouncing))
+        })
     }
 
     fn authenticated_caller_id(&mut self) -> Result<Option<UserApplicationId>, ExecutionError> {
-        let mut this = self.inner();
-        if this.call_stack.len() <= 1 {
-            return Ok(None);
-        }
-        Ok(this.current_application().caller_id)
+        self.audited_response("authenticated_caller_id", |this| {
+            if this.call_stack.len() <= 1 {
+                return Ok(None);
+            }
+            Ok(this.current_application().caller_id)
+        })
     }
 
     fn remaining_fuel(&mut self) -> Result<u64, ExecutionError> {
@@ -1269,30 +1722,103 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         this.resource_controller.track_fuel(fuel)
     }
 
-    fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError> {
+    fn record_heap_usage(&mut self, usage: HeapUsage) -> Result<(), ExecutionError> {
         let mut this = self.inner();
-        let application = this.current_application_mut();
+        let id = this.current_application().id;
+        this.transaction_tracker.record_heap_usage(id, usage);
+        Ok(())
+    }
 
-        application.outcome.messages.push(message.into());
+    fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError> {
+        self.audited_request("send_message", message, |this, message| {
+            let size = message.message.len() as u64;
+            this.message_limits().check(size)?;
+            let application = this.current_application_mut();
 
-        Ok(())
+            application.outcome.messages.push(message.into());
+            application.sent_message_bytes = application.sent_message_bytes.saturating_add(size);
+
+            Ok(())
+        })
+    }
+
+    fn send_messages(
+        &mut self,
+        messages: Vec<SendMessageRequest<Vec<u8>>>,
+    ) -> Result<(), ExecutionError> {
+        self.audited_request("send_messages", messages, |this, messages| {
+            this.message_limits()
+                .check_all(messages.iter().map(|message| message.message.len() as u64))?;
+            let application = this.current_application_mut();
+
+            for message in messages {
+                let size = message.message.len() as u64;
+                application.outcome.messages.push(message.into());
+                application.sent_message_bytes =
+                    application.sent_message_bytes.saturating_add(size);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn message_limits(&mut self) -> Result<MessageLimits, ExecutionError> {
+        self.audited_response("message_limits", |this| Ok(this.message_limits()))
     }
 
     fn subscribe(&mut self, chain: ChainId, channel: ChannelName) -> Result<(), ExecutionError> {
-        let mut this = self.inner();
-        let application = this.current_application_mut();
+        self.audited_request("subscribe", (chain, channel), |this, (chain, channel)| {
+            let application = this.current_application_mut();
 
-        application.outcome.subscribe.push((channel, chain));
+            application.outcome.subscribe.push((channel, chain));
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn unsubscribe(&mut self, chain: ChainId, channel: ChannelName) -> Result<(), ExecutionError> {
-        let mut this = self.inner();
-        let application = this.current_application_mut();
+        self.audited_request("unsubscribe", (chain, channel), |this, (chain, channel)| {
+            let application = this.current_application_mut();
 
-        application.outcome.unsubscribe.push((channel, chain));
+            application.outcome.unsubscribe.push((channel, chain));
+
+            Ok(())
+        })
+    }
 
+    fn schedule_message(&mut self, due: Timestamp, message: Vec<u8>) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        this.audit("schedule_message", &(due, &message));
+        ensure!(
+            message.len() <= MAX_SCHEDULED_MESSAGE_SIZE,
+            ExecutionError::ScheduledMessageTooLarge
+        );
+        let size = message.len() as u64;
+        this.message_limits().check(size)?;
+        this.resource_controller.track_scheduled_message()?;
+        let application = this.current_application_mut();
+        application.scheduled_messages = application.scheduled_messages.saturating_add(1);
+        application.sent_message_bytes = application.sent_message_bytes.saturating_add(size);
+        let current_application = this.current_application();
+        let application_id = current_application.id;
+        let signer = current_application.signer;
+        let chain_id = this.chain_id;
+
+        let message = RawOutgoingMessage {
+            destination: Destination::Recipient(chain_id),
+            authenticated: signer.is_some(),
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            message: SystemMessage::ScheduleMessage {
+                due,
+                application_id,
+                bytes: message,
+            },
+        };
+        let outcome = RawExecutionOutcome::default()
+            .with_message(message)
+            .with_authenticated_signer(signer);
+        this.transaction_tracker.add_system_outcome(outcome)?;
         Ok(())
     }
 
@@ -1303,6 +1829,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         amount: Amount,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.audit("transfer", &(&source, &destination, amount));
         let current_application = this.current_application();
         let application_id = current_application.id;
         let signer = current_application.signer;
@@ -1331,6 +1858,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         amount: Amount,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.audit("claim", &(&source, &destination, amount));
         let current_application = this.current_application();
         let application_id = current_application.id;
         let signer = current_application.signer;
@@ -1358,18 +1886,28 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         callee_id: UserApplicationId,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        let (contract, context) =
-            self.inner()
-                .prepare_for_call(self.clone(), authenticated, callee_id)?;
+        let checkpoint = {
+            let mut this = self.inner();
+            this.audit(
+                "try_call_application",
+                &(authenticated, callee_id, &argument),
+            );
+            this.save_checkpoint()?
+        };
 
-        let value = contract
-            .try_lock()
-            .expect("Applications should not have reentrant calls")
-            .execute_operation(context, argument)?;
+        let result = self.execute_call(authenticated, callee_id, argument);
 
-        self.inner().finish_call()?;
-
-        Ok(value)
+        let mut this = self.inner();
+        match result {
+            Ok(value) => {
+                this.discard_checkpoint()?;
+                this.audit_result("try_call_application", Ok(value))
+            }
+            Err(error) => {
+                this.abort_call(checkpoint)?;
+                Err(error)
+            }
+        }
     }
 
     fn emit(
@@ -1379,6 +1917,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         value: Vec<u8>,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.audit("emit", &(&name, &key, &value));
         ensure!(
             key.len() <= MAX_EVENT_KEY_LEN,
             ExecutionError::EventKeyTooLong
@@ -1388,6 +1927,10 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
             ExecutionError::StreamNameTooLong
         );
         let application = this.current_application_mut();
+        ensure!(
+            application.outcome.events.len() < MAX_EVENTS_PER_EXECUTION,
+            ExecutionError::TooManyEvents
+        );
         application.outcome.events.push((name, key, value));
         Ok(())
     }
@@ -1399,6 +1942,10 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         balance: Amount,
     ) -> Result<(MessageId, ChainId), ExecutionError> {
         let mut this = self.inner();
+        this.audit(
+            "open_chain",
+            &(&ownership, &application_permissions, balance),
+        );
         let message_id = MessageId {
             chain_id: this.chain_id,
             height: this.height,
@@ -1419,11 +1966,12 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
             .with_message(open_chain_message)
             .with_message(subscribe_message);
         this.transaction_tracker.add_system_outcome(outcome)?;
-        Ok((message_id, chain_id))
+        this.audit_result("open_chain", Ok((message_id, chain_id)))
     }
 
     fn close_chain(&mut self) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.audit("close_chain", &());
         let application_id = this.current_application().id;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::CloseChain {
@@ -1438,6 +1986,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         application_permissions: ApplicationPermissions,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.audit("change_application_permissions", &application_permissions);
         let application_id = this.current_application().id;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::ChangeApplicationPermissions {
@@ -1455,8 +2004,17 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         argument: Vec<u8>,
         required_application_ids: Vec<UserApplicationId>,
     ) -> Result<UserApplicationId, ExecutionError> {
+        self.inner().audit(
+            "create_application",
+            &(
+                &bytecode_id,
+                &parameters,
+                &argument,
+                &required_application_ids,
+            ),
+        );
         let chain_id = self.inner().chain_id;
-        let height = self.block_height()?;
+        let height = self.inner().height;
         let index = self.inner().transaction_tracker.next_message_index();
 
         let message_id = MessageId {
@@ -1476,6 +2034,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
                 next_message_id: message_id,
                 bytecode_id,
                 parameters,
+                instantiation_argument: argument.clone(),
                 required_application_ids,
                 callback,
             })?
@@ -1497,14 +2056,18 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
             .expect("Applications should not have reentrant calls")
             .instantiate(context, argument)?;
 
-        self.inner().finish_call()?;
-
-        Ok(app_id)
+        let mut this = self.inner();
+        this.finish_call()?;
+        this.audit_result("create_application", Ok(app_id))
     }
 
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        if let Some(audit) = this.transaction_tracker.guest_input_audit_mut() {
+            audit.record_batch("write_batch", &batch);
+        }
         let id = this.application_id()?;
+        this.transaction_tracker.record_view_writes(id, &batch);
         let state = this.view_user_states.entry(id).or_default();
         state.force_all_pending_queries()?;
         this.resource_controller.track_write_operations(
@@ -1515,51 +2078,176 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         )?;
         this.resource_controller
             .track_bytes_written(batch.size() as u64)?;
-        this.execution_state_sender
+        // The batch is applied before checking the new state size. If the limit is exceeded,
+        // the error aborts the transaction, so the whole batch is discarded with it.
+        let size = this
+            .execution_state_sender
             .send_request(|callback| ExecutionRequest::WriteBatch {
                 id,
                 batch,
                 callback,
             })?
             .recv_response()?;
+        let maximum_size = this
+            .resource_controller
+            .policy
+            .maximum_application_state_size;
+        ensure!(
+            size <= maximum_size,
+            ExecutionError::StateTooLarge {
+                application_id: Box::new(id),
+                size,
+                maximum_size,
+            }
+        );
         Ok(())
     }
 
+    fn write_scratch_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        if let Some(audit) = this.transaction_tracker.guest_input_audit_mut() {
+            audit.record_batch("write_scratch_batch", &batch);
+        }
+        let id = this.application_id()?;
+        this.resource_controller.track_write_operations(
+            batch
+                .num_operations()
+                .try_into()
+                .map_err(|_| ExecutionError::from(ArithmeticError::Overflow))?,
+        )?;
+        this.resource_controller
+            .track_bytes_written(batch.size() as u64)?;
+        this.execution_state_sender
+            .send_request(|callback| ExecutionRequest::WriteScratchBatch {
+                id,
+                batch,
+                callback,
+            })?
+            .recv_response()
+    }
+
     fn validation_round(&mut self) -> Result<Option<u32>, ExecutionError> {
+        self.audited_response("validation_round", |this| {
+            let round =
+                if let Some(response) = this.transaction_tracker.next_replayed_oracle_response()? {
+                    match response {
+                        OracleResponse::Round(round) => round,
+                        _ => return Err(ExecutionError::OracleResponseMismatch),
+                    }
+                } else {
+                    this.round
+                };
+            this.transaction_tracker
+                .add_oracle_response(OracleResponse::Round(round));
+            Ok(round)
+        })
+    }
+
+    fn try_read_remote_state(
+        &mut self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        key: Vec<u8>,
+    ) -> Result<Option<RemoteValue>, ExecutionError> {
+        self.audited_call(
+            "try_read_remote_state",
+            (chain_id, application_id, key),
+            |this, (chain_id, application_id, key)| {
+                let replayed_value =
+                    match this.transaction_tracker.next_replayed_oracle_response()? {
+                        Some(OracleResponse::RemoteState(value)) => Some(value),
+                        Some(_) => return Err(ExecutionError::OracleResponseMismatch),
+                        None => None,
+                    };
+                let value = match (
+                    replayed_value,
+                    this.transaction_tracker.received_height(chain_id),
+                ) {
+                    // The block was executed before: The other chain may have moved on since,
+                    // so the value that was read then is used again, even if it was missing.
+                    (Some(value), received_height) => {
+                        let recorded_height = value.as_ref().map(|value| value.height);
+                        ensure!(
+                            recorded_height.is_none() || recorded_height == received_height,
+                            ExecutionError::OracleResponseMismatch
+                        );
+                        value
+                    }
+                    (None, None) => None,
+                    (None, Some(height)) => this
+                        .execution_state_sender
+                        .send_request(|callback| ExecutionRequest::ReadRemoteState {
+                            chain_id,
+                            application_id,
+                            key,
+                            height,
+                            callback,
+                        })?
+                        .recv_response()?,
+                };
+                this.transaction_tracker
+                    .add_oracle_response(OracleResponse::RemoteState(value.clone()));
+                Ok(value)
+            },
+        )
+    }
+
+    fn verify_ed25519(
+        &mut self,
+        public_key: PublicKey,
+        message: &[u8],
+        signature: Signature,
+    ) -> Result<bool, ExecutionError> {
+        self.consume_fuel(VERIFY_ED25519_FUEL)?;
         let mut this = self.inner();
-        let round =
-            if let Some(response) = this.transaction_tracker.next_replayed_oracle_response()? {
-                match response {
-                    OracleResponse::Round(round) => round,
-                    _ => return Err(ExecutionError::OracleResponseMismatch),
-                }
-            } else {
-                this.round
-            };
-        this.transaction_tracker
-            .add_oracle_response(OracleResponse::Round(round));
-        Ok(round)
+        this.audit("verify_ed25519", &(&public_key, message, &signature));
+        this.audit_result(
+            "verify_ed25519",
+            Ok(signature.verify_raw_bytes(message, public_key)),
+        )
+    }
+
+    fn sha3_256(&mut self, data: &[u8]) -> Result<Vec<u8>, ExecutionError> {
+        self.consume_fuel(SHA3_256_FUEL)?;
+        let mut this = self.inner();
+        this.audit("sha3_256", &data);
+        this.audit_result(
+            "sha3_256",
+            Ok(CryptoHash::from_raw_bytes(data).as_bytes().to_vec()),
+        )
+    }
+
+    fn bcs_hash(&mut self, data: &[u8]) -> Result<CryptoHash, ExecutionError> {
+        self.consume_fuel(BCS_HASH_FUEL)?;
+        let mut this = self.inner();
+        this.audit("bcs_hash", &data);
+        this.audit_result("bcs_hash", Ok(CryptoHash::from_raw_bytes(data)))
+    }
+
+    fn benchmark_recorder(&mut self) -> Option<BenchmarkRecorder> {
+        self.inner().benchmark_recorder.clone()
     }
 }
 
 impl ServiceSyncRuntime {
     /// Creates a new [`ServiceSyncRuntime`] ready to execute using a provided [`QueryContext`].
     pub fn new(execution_state_sender: ExecutionStateSender, context: QueryContext) -> Self {
-        let runtime = SyncRuntime(Some(
-            SyncRuntimeInternal::new(
-                context.chain_id,
-                context.next_block_height,
-                None,
-                context.local_time,
-                None,
-                None,
-                execution_state_sender,
-                None,
-                ResourceController::default(),
-                TransactionTracker::default(),
-            )
-            .into(),
-        ));
+        let mut internal = SyncRuntimeInternal::new(
+            context.chain_id,
+            context.next_block_height,
+            None,
+            context.local_time,
+            context.authenticated_signer,
+            None,
+            execution_state_sender,
+            None,
+            ResourceController::default(),
+            TransactionTracker::default(),
+        );
+        internal.block_hash = context.block_hash;
+        internal.block_timestamp = context.block_timestamp;
+        internal.maximum_query_response_size = context.maximum_response_size;
+        let runtime = SyncRuntime(Some(internal.into()));
 
         ServiceSyncRuntime {
             runtime,
@@ -1567,6 +2255,12 @@ impl ServiceSyncRuntime {
         }
     }
 
+    /// Sets the maximum depth of nested queries between services.
+    pub fn with_max_call_depth(mut self, max_call_depth: u32) -> Self {
+        self.handle_mut().inner().max_call_depth = max_call_depth;
+        self
+    }
+
     /// Loads a service into the runtime's memory.
     pub(crate) fn preload_service(
         &self,
@@ -1600,10 +2294,12 @@ impl ServiceSyncRuntime {
                 application_id,
                 context,
                 query,
+                max_call_depth,
                 callback,
             } = request;
 
             self.prepare_for_query(context);
+            self.handle_mut().inner().max_call_depth = max_call_depth;
 
             let _ = callback.send(self.run_query(application_id, query));
         }
@@ -1657,29 +2353,40 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
     ) -> Result<Vec<u8>, ExecutionError> {
         let (query_context, service) = {
             let mut this = self.inner();
+            this.check_call_depth()?;
 
             // Load the application.
             let application = this.load_service_instance(self.clone(), queried_id)?;
             // Make the call to user code.
-            let query_context = QueryContext {
-                chain_id: this.chain_id,
-                next_block_height: this.height,
-                local_time: this.local_time,
-            };
             this.push_application(ApplicationStatus {
                 caller_id: None,
                 id: queried_id,
                 parameters: application.parameters,
                 signer: None,
                 outcome: RawExecutionOutcome::default(),
+                sent_message_bytes: 0,
+                scheduled_messages: 0,
             });
-            (query_context, application.instance)
+            (this.query_context(), application.instance)
         };
+        #[cfg(with_metrics)]
+        let _query_latency = SERVICE_QUERY_LATENCY.measure_latency();
         let response = service
             .try_lock()
             .expect("Applications should not have reentrant calls")
-            .handle_query(query_context, argument)?;
+            .handle_query(query_context, argument);
         self.inner().pop_application();
+        let response = response?;
+        let size = response.len() as u64;
+        let maximum_size = query_context.maximum_response_size;
+        ensure!(
+            size <= maximum_size,
+            ExecutionError::QueryResponseTooLarge {
+                application_id: Box::new(queried_id),
+                size,
+                maximum_size,
+            }
+        );
         Ok(response)
     }
 
@@ -1703,6 +2410,68 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
 
         Ok(())
     }
+
+    fn query_context(&mut self) -> Result<QueryContext, ExecutionError> {
+        Ok(self.inner().query_context())
+    }
+
+    fn read_scratch_value_bytes(
+        &mut self,
+        key: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, ExecutionError> {
+        self.inner().read_scratch_value_bytes(key)
+    }
+}
+
+impl SyncRuntimeInternal<UserServiceInstance> {
+    /// Returns the [`QueryContext`] the service runtime was created with, at the current
+    /// local time, with the response size limit of the application being queried. Only the
+    /// application queried first sees the authenticated signer of the query.
+    fn query_context(&self) -> QueryContext {
+        let maximum_response_size = self
+            .call_stack
+            .last()
+            .and_then(|status| self.loaded_applications.get(&status.id))
+            .and_then(|application| application.max_query_response_bytes)
+            .map_or(self.maximum_query_response_size, |limit| {
+                limit.min(self.maximum_query_response_size)
+            });
+        // Services queried by other services can't tell whether the owner meant to query them.
+        let authenticated_signer = self
+            .authenticated_signer
+            .filter(|_| self.call_stack.len() <= 1);
+        QueryContext {
+            chain_id: self.chain_id,
+            next_block_height: self.height,
+            local_time: self.local_time,
+            block_hash: self.block_hash,
+            block_timestamp: self.block_timestamp,
+            maximum_response_size,
+            authenticated_signer,
+        }
+    }
+
+    /// Reads the value under `key` in the scratch space of the current application.
+    fn read_scratch_value_bytes(
+        &mut self,
+        key: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, ExecutionError> {
+        let id = self.application_id()?;
+        self.resource_controller.track_read_operations(1)?;
+        let value = self
+            .execution_state_sender
+            .send_request(move |callback| ExecutionRequest::ReadScratchValueBytes {
+                id,
+                key,
+                callback,
+            })?
+            .recv_response()?;
+        if let Some(value) = &value {
+            self.resource_controller
+                .track_bytes_read(value.len() as u64)?;
+        }
+        Ok(value)
+    }
 }
 
 /// A request to the service runtime actor.
@@ -1711,6 +2480,7 @@ pub enum ServiceRuntimeRequest {
         application_id: UserApplicationId,
         context: QueryContext,
         query: Vec<u8>,
+        max_call_depth: u32,
         callback: oneshot::Sender<Result<QueryOutcome<Vec<u8>>, ExecutionError>>,
     },
 }
@@ -0,0 +1,193 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Breakdowns of the execution state hash, to locate the parts of the state on which the
+//! executions of different validators diverged.
+
+use std::{collections::BTreeMap, fmt};
+
+use linera_base::crypto::CryptoHash;
+use linera_views::{
+    context::Context,
+    views::{HashableView, ViewError},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{ExecutionRuntimeContext, ExecutionStateView, UserApplicationId};
+
+/// The hashes of the parts of an execution state: one per field of the system state, and
+/// one per user application.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateHashBreakdown {
+    /// The hashes of the fields of the system state, by field name.
+    pub system: BTreeMap<String, CryptoHash>,
+    /// The hashes of the states of the user applications.
+    pub applications: BTreeMap<UserApplicationId, CryptoHash>,
+}
+
+/// A part of the execution state, as named in a [`StateHashBreakdown`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StateHashPart {
+    /// A field of the system state.
+    System(String),
+    /// The state of a user application.
+    Application(UserApplicationId),
+}
+
+impl fmt::Display for StateHashPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateHashPart::System(field) => write!(f, "system.{field}"),
+            StateHashPart::Application(application_id) => {
+                write!(f, "application {application_id:?}")
+            }
+        }
+    }
+}
+
+impl StateHashBreakdown {
+    /// Returns the parts whose hashes differ from the ones in `other`, including the parts
+    /// present in only one of the two breakdowns.
+    pub fn diverging_parts(&self, other: &StateHashBreakdown) -> Vec<StateHashPart> {
+        let system = diverging_keys(&self.system, &other.system)
+            .into_iter()
+            .map(|field| StateHashPart::System(field.clone()));
+        let applications = diverging_keys(&self.applications, &other.applications)
+            .into_iter()
+            .map(|application_id| StateHashPart::Application(*application_id));
+        system.chain(applications).collect()
+    }
+}
+
+/// Returns the keys of `left` and `right` that are not mapped to the same hash in both.
+fn diverging_keys<'a, K: Ord>(
+    left: &'a BTreeMap<K, CryptoHash>,
+    right: &'a BTreeMap<K, CryptoHash>,
+) -> Vec<&'a K> {
+    let mut keys = left
+        .iter()
+        .filter(|(key, hash)| right.get(key) != Some(hash))
+        .map(|(key, _)| key)
+        .chain(right.keys().filter(|key| !left.contains_key(key)))
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Converts the output of a view hasher into a [`CryptoHash`].
+fn sub_hash(output: impl AsRef<[u8]>) -> CryptoHash {
+    CryptoHash::try_from(output.as_ref()).expect("view hashes are Sha3-256 values")
+}
+
+impl<C> ExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Computes the hashes of the parts of the execution state, from which the execution
+    /// state hash is derived.
+    pub async fn state_hash_breakdown(&self) -> Result<StateHashBreakdown, ViewError> {
+        let system = &self.system;
+        let system_hashes = [
+            ("description", system.description.hash().await?),
+            ("epoch", system.epoch.hash().await?),
+            ("admin_id", system.admin_id.hash().await?),
+            ("subscriptions", system.subscriptions.hash().await?),
+            ("committees", system.committees.hash().await?),
+            ("ownership", system.ownership.hash().await?),
+            ("balance", system.balance.hash().await?),
+            ("balances", system.balances.hash().await?),
+            ("timestamp", system.timestamp.hash().await?),
+            ("registry", system.registry.hash().await?),
+            ("closed", system.closed.hash().await?),
+            (
+                "application_permissions",
+                system.application_permissions.hash().await?,
+            ),
+            ("used_blobs", system.used_blobs.hash().await?),
+            ("dead_letters", system.dead_letters.hash().await?),
+            (
+                "scheduled_messages",
+                system.scheduled_messages.hash().await?,
+            ),
+            (
+                "scheduled_message_queue",
+                system.scheduled_message_queue.hash().await?,
+            ),
+            (
+                "application_allowlist",
+                system.application_allowlist.hash().await?,
+            ),
+            ("rejected_messages", system.rejected_messages.hash().await?),
+            ("sponsorships", system.sponsorships.hash().await?),
+        ]
+        .into_iter()
+        .map(|(field, output)| (field.to_owned(), sub_hash(output)))
+        .collect();
+        let mut applications = BTreeMap::new();
+        for (application_id, view) in self.users.try_load_all_entries().await? {
+            applications.insert(application_id, sub_hash(view.hash().await?));
+        }
+        Ok(StateHashBreakdown {
+            system: system_hashes,
+            applications,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::identifiers::{ApplicationId, BytecodeId, ChainId, MessageId};
+
+    use super::*;
+
+    fn application_id(index: u32) -> UserApplicationId {
+        ApplicationId {
+            bytecode_id: BytecodeId::new(
+                CryptoHash::test_hash("contract"),
+                CryptoHash::test_hash("service"),
+            ),
+            creation: MessageId {
+                chain_id: ChainId::root(0),
+                height: 0.into(),
+                index,
+            },
+        }
+    }
+
+    #[test]
+    fn test_diverging_parts() {
+        let mut breakdown = StateHashBreakdown::default();
+        breakdown
+            .system
+            .insert("balance".to_owned(), CryptoHash::test_hash("balance"));
+        breakdown
+            .applications
+            .insert(application_id(0), CryptoHash::test_hash("app 0"));
+        breakdown
+            .applications
+            .insert(application_id(1), CryptoHash::test_hash("app 1"));
+        let mut other = breakdown.clone();
+        assert!(breakdown.diverging_parts(&other).is_empty());
+
+        other
+            .applications
+            .insert(application_id(1), CryptoHash::test_hash("other app 1"));
+        other
+            .applications
+            .insert(application_id(2), CryptoHash::test_hash("app 2"));
+        other.system.remove("balance");
+        assert_eq!(
+            breakdown.diverging_parts(&other),
+            vec![
+                StateHashPart::System("balance".to_owned()),
+                StateHashPart::Application(application_id(1)),
+                StateHashPart::Application(application_id(2)),
+            ]
+        );
+        assert_eq!(
+            StateHashPart::System("balance".to_owned()).to_string(),
+            "system.balance"
+        );
+    }
+}
@@ -9,17 +9,19 @@ mod tests;
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
     iter,
 };
 
-use async_graphql::Enum;
+use async_graphql::{Enum, InputObject};
 use custom_debug_derive::Debug;
 use linera_base::{
+    abi_descriptor::{AbiDescriptor, AbiDescriptorError},
     crypto::CryptoHash,
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, BlobContent, OracleResponse, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, BlobContent, CompressedBytecode,
+        OracleResponse, Timestamp,
     },
     ensure, hex_debug,
     identifiers::{
@@ -29,10 +31,11 @@ use linera_base::{
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_views::{
+    common::CustomSerialize,
     context::Context,
     map_view::HashedMapView,
     register_view::HashedRegisterView,
-    set_view::HashedSetView,
+    set_view::{HashedCustomSetView, HashedSetView},
     views::{ClonableView, HashableView, View, ViewError},
 };
 use serde::{Deserialize, Serialize};
@@ -45,9 +48,9 @@ use crate::test_utils::SystemExecutionState;
 use crate::{
     committee::{Committee, Epoch},
     ApplicationRegistryView, ChannelName, ChannelSubscription, Destination,
-    ExecutionRuntimeContext, MessageContext, MessageKind, OperationContext, QueryContext,
-    QueryOutcome, RawExecutionOutcome, RawOutgoingMessage, TransactionTracker,
-    UserApplicationDescription, UserApplicationId,
+    ExecutionRuntimeContext, Message, MessageContext, MessageKind, OperationContext, QueryContext,
+    QueryOutcome, RawExecutionOutcome, RawOutgoingMessage, ResourceControlPolicy,
+    TransactionTracker, UserApplicationDescription, UserApplicationId,
 };
 
 /// The relative index of the `OpenChain` message created by the `OpenChain` operation.
@@ -98,6 +101,121 @@ pub struct SystemExecutionStateView<C> {
     pub application_permissions: HashedRegisterView<C, ApplicationPermissions>,
     /// Blobs that have been used or published on this chain.
     pub used_blobs: HashedSetView<C, BlobId>,
+    /// Incoming messages that were parked instead of being executed, until they are retried.
+    pub dead_letters: HashedMapView<C, MessageId, DeadLetter>,
+    /// Messages that applications scheduled to themselves, until they are due and executed.
+    pub scheduled_messages: HashedMapView<C, MessageId, ScheduledMessage>,
+    /// The keys of the `scheduled_messages`, in order of their due time.
+    pub scheduled_message_queue: HashedCustomSetView<C, ScheduledMessageKey>,
+    /// The applications allowed to execute on this chain. If `None`, all applications are.
+    pub application_allowlist: HashedRegisterView<C, Option<ApplicationAllowlist>>,
+    /// Incoming messages that the chain owners rejected before they were executed. Each one is
+    /// removed once its bundle is received in a block.
+    pub rejected_messages: HashedSetView<C, MessageId>,
+    /// Funds set aside by other chains to pay for the fees of blocks on this chain, indexed by
+    /// the sponsoring chain.
+    pub sponsorships: HashedMapView<C, ChainId, Sponsorship>,
+}
+
+/// An incoming message that was parked by a block proposer instead of being executed.
+///
+/// It can be executed later with [`SystemOperation::RetryDeadLetter`], e.g. once the bug that
+/// made it fail has been fixed.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// The hash of the certificate that created the message.
+    pub certificate_hash: CryptoHash,
+    /// Whether the message was bouncing back to its sender.
+    pub is_bouncing: bool,
+    /// The authenticated signer of the message, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub authenticated_signer: Option<Owner>,
+    /// Where to send a refund for the unused part of the grant, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub refund_grant_to: Option<Account>,
+    /// The grant attached to the message, to pay for its execution.
+    pub grant: Amount,
+    /// The message itself.
+    pub message: Message,
+}
+
+/// A message that an application scheduled to itself, to be executed once the block timestamp
+/// reaches `due`.
+///
+/// Once due, every block must execute it with [`SystemOperation::ExecuteScheduledMessage`], or
+/// park it with [`SystemOperation::ParkScheduledMessage`] if it fails.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ScheduledMessage {
+    /// The earliest block timestamp at which the message is executed.
+    pub due: Timestamp,
+    /// The hash of the certificate that scheduled the message.
+    pub certificate_hash: CryptoHash,
+    /// The authenticated signer of the transaction that scheduled the message, if any.
+    #[debug(skip_if = Option::is_none)]
+    pub authenticated_signer: Option<Owner>,
+    /// The message itself.
+    pub message: Message,
+}
+
+/// Funds that a sponsoring chain set aside on another chain, to pay for the execution fees of
+/// its blocks.
+///
+/// A block is paid for by the sponsorship if it names the sponsoring chain as its fee payer,
+/// and if the proposal is signed by the sponsorship's `owner`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Sponsorship {
+    /// The owner who signs the blocks whose fees are paid by the sponsorship.
+    pub owner: Owner,
+    /// The funds left to pay for fees.
+    pub balance: Amount,
+}
+
+/// The key of a [`ScheduledMessage`] in the queue of scheduled messages, ordered by due time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ScheduledMessageKey {
+    /// The earliest block timestamp at which the message is executed.
+    pub due: Timestamp,
+    /// The ID of the `ScheduleMessage` system message that scheduled it.
+    pub message_id: MessageId,
+}
+
+/// The due time is serialized in big-endian order, so that keys are visited in order of their
+/// due time.
+impl CustomSerialize for ScheduledMessageKey {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        let mut bytes = self.due.micros().to_be_bytes().to_vec();
+        bcs::serialize_into(&mut bytes, &self.message_id)?;
+        Ok(bytes)
+    }
+
+    fn from_custom_bytes(bytes: &[u8]) -> Result<Self, ViewError> {
+        let (due, message_id) = bytes.split_at(bytes.len().min(8));
+        let due = <[u8; 8]>::try_from(due).map_err(|_| ViewError::InconsistentEntries)?;
+        Ok(ScheduledMessageKey {
+            due: Timestamp::from(u64::from_be_bytes(due)),
+            message_id: bcs::from_bytes(message_id)?,
+        })
+    }
+}
+
+/// The applications that are allowed to execute on a chain, either individually or because
+/// they were created from an allowed bytecode.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, InputObject)]
+pub struct ApplicationAllowlist {
+    /// These applications are allowed.
+    #[graphql(default)]
+    pub applications: BTreeSet<UserApplicationId>,
+    /// All applications created from these bytecodes are allowed.
+    #[graphql(default)]
+    pub bytecodes: BTreeSet<BytecodeId>,
+}
+
+impl ApplicationAllowlist {
+    /// Returns whether the application is allowed to execute.
+    pub fn allows(&self, application_id: &UserApplicationId) -> bool {
+        self.applications.contains(application_id)
+            || self.bytecodes.contains(&application_id.bytecode_id)
+    }
 }
 
 /// The configuration for a new chain.
@@ -183,6 +301,10 @@ pub enum SystemOperation {
         instantiation_argument: Vec<u8>,
         #[debug(skip_if = Vec::is_empty)]
         required_application_ids: Vec<UserApplicationId>,
+        /// The maximum size in bytes of the responses to queries of the application, if it
+        /// overrides the network-wide limit.
+        #[debug(skip_if = Option::is_none)]
+        max_query_response_bytes: Option<u64>,
     },
     /// Requests a message from another chain to register a user application on this chain.
     RequestApplication {
@@ -191,6 +313,36 @@ pub enum SystemOperation {
     },
     /// Operations that are only allowed on the admin chain.
     Admin(AdminOperation),
+    /// Executes an incoming message that was previously parked in the dead-letter queue.
+    RetryDeadLetter { message_id: MessageId },
+    /// Executes a scheduled message that is due. Blocks must contain this operation, or
+    /// `ParkScheduledMessage`, for every scheduled message whose due time is not later than the
+    /// block timestamp.
+    ExecuteScheduledMessage { message_id: MessageId },
+    /// Restricts the applications that can execute on this chain, or lifts the restriction
+    /// if `None`. Only chain owners can change the allowlist.
+    ChangeApplicationAllowlist(Option<ApplicationAllowlist>),
+    /// Rejects an incoming message that is waiting in an inbox. When its bundle is accepted,
+    /// the message is skipped, and bounced if it is tracked. The other messages from the same
+    /// origin remain executable. Protected messages are executed anyway. Only chain owners can
+    /// reject messages.
+    RejectMessage { message_id: MessageId },
+    /// Moves a scheduled message that is due, but fails to execute, to the dead-letter queue,
+    /// from where it can be retried with `RetryDeadLetter`. Only chain owners can park
+    /// scheduled messages.
+    ParkScheduledMessage { message_id: MessageId },
+    /// Takes `amount` units of value out of this chain's balance, to pay for the fees of the
+    /// blocks on chain `chain_id` that `owner` signs as sponsor. Adds to the existing
+    /// sponsorship of that chain, if any, and makes `owner` its signer. Only chain owners can
+    /// sponsor other chains.
+    Sponsor {
+        chain_id: ChainId,
+        owner: Owner,
+        amount: Amount,
+    },
+    /// Ends the sponsorship of chain `chain_id` by this chain. The funds that were not used
+    /// are credited back to this chain's balance. Only chain owners can end sponsorships.
+    EndSponsorship { chain_id: ChainId },
 }
 
 /// Operations that are only allowed on the admin chain.
@@ -252,6 +404,23 @@ pub enum SystemMessage {
     /// Requests a `RegisterApplication` message from the target chain to register the specified
     /// application on the sender chain.
     RequestApplication(UserApplicationId),
+    /// Registers a message that an application scheduled to itself, to be executed once the
+    /// block timestamp reaches `due`. Only accepted from the same chain. Blocks must receive
+    /// the bundles with these messages as soon as they are in the inbox.
+    ScheduleMessage {
+        due: Timestamp,
+        application_id: UserApplicationId,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        bytes: Vec<u8>,
+    },
+    /// Adds `amount` units of value to the sender's sponsorship of the recipient chain, and
+    /// makes `owner` its signer -- unless the message is bouncing, in which case `amount` is
+    /// credited back to the sender's balance.
+    Sponsor { owner: Owner, amount: Amount },
+    /// Ends the sender's sponsorship of the recipient chain and credits the funds that were
+    /// not used back to the sender's balance.
+    EndSponsorship,
 }
 
 /// A query to the system state.
@@ -411,6 +580,37 @@ pub enum SystemExecutionError {
     UnknownApplicationId(Box<UserApplicationId>),
     #[error("Chain is not active yet.")]
     InactiveChain,
+    #[error("No parked message with ID {0:?}")]
+    DeadLetterNotFound(MessageId),
+    #[error("No scheduled message with ID {0:?}")]
+    ScheduledMessageNotFound(MessageId),
+    #[error("Scheduled message {message_id:?} is not due before {due}")]
+    ScheduledMessageNotDue {
+        message_id: MessageId,
+        due: Timestamp,
+    },
+    #[error("Messages can only be scheduled by the chain itself, not by {0}")]
+    ScheduledMessageFromOtherChain(ChainId),
+    #[error("Only chain owners can park scheduled messages")]
+    UnauthorizedScheduledMessageParking,
+    #[error("Operation {0} is executed by the execution state, not the system")]
+    OperationNotExecutedBySystem(&'static str),
+    #[error("Only chain owners can change the application allowlist")]
+    UnauthorizedAllowlistChange,
+    #[error("Only chain owners can sponsor other chains")]
+    UnauthorizedSponsorship,
+    #[error("Sponsorship must have positive amount")]
+    IncorrectSponsorshipAmount,
+    #[error("Application {0} is not permitted to execute on this chain")]
+    ApplicationNotPermitted(Box<UserApplicationId>),
+    #[error("Invalid {argument} for an application of bytecode {bytecode_id:?}: {error}")]
+    AbiMismatch {
+        bytecode_id: Box<BytecodeId>,
+        argument: &'static str,
+        error: AbiDescriptorError,
+    },
+    #[error("Only chain owners can reject incoming messages")]
+    UnauthorizedMessageRejection,
 
     #[error("Blobs not found: {0:?}")]
     BlobsNotFound(Vec<BlobId>),
@@ -449,6 +649,23 @@ where
         Some((*epoch, committee))
     }
 
+    /// Returns the maximum size of the response to an application query allowed by the
+    /// current committee.
+    pub fn maximum_query_response_size(&self) -> u64 {
+        self.current_committee().map_or(u64::MAX, |(_, committee)| {
+            committee.policy().maximum_query_response_size
+        })
+    }
+
+    /// Returns the maximum depth of nested queries between services allowed by the current
+    /// committee.
+    pub(crate) fn call_depth_limit(&self) -> u32 {
+        self.current_committee().map_or(
+            ResourceControlPolicy::default().call_depth_limit(),
+            |(_, committee)| committee.policy().call_depth_limit(),
+        )
+    }
+
     /// Executes the sender's side of an operation and returns a list of actions to be
     /// taken.
     pub async fn execute_operation(
@@ -490,10 +707,68 @@ where
             ChangeApplicationPermissions(application_permissions) => {
                 self.application_permissions.set(application_permissions);
             }
+            ChangeApplicationAllowlist(allowlist) => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    SystemExecutionError::UnauthorizedAllowlistChange
+                );
+                self.application_allowlist.set(allowlist);
+            }
+            RejectMessage { message_id } => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    SystemExecutionError::UnauthorizedMessageRejection
+                );
+                self.rejected_messages.insert(&message_id)?;
+            }
             CloseChain => {
                 let messages = self.close_chain(context.chain_id).await?;
                 outcome.messages.extend(messages);
             }
+            Sponsor {
+                chain_id,
+                owner,
+                amount,
+            } => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    SystemExecutionError::UnauthorizedSponsorship
+                );
+                ensure!(
+                    amount > Amount::ZERO,
+                    SystemExecutionError::IncorrectSponsorshipAmount
+                );
+                self.debit(None, amount).await?;
+                outcome.messages.push(RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Tracked,
+                    message: SystemMessage::Sponsor { owner, amount },
+                });
+            }
+            EndSponsorship { chain_id } => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    SystemExecutionError::UnauthorizedSponsorship
+                );
+                // The message is protected, so that the sponsored chain can't keep the funds.
+                outcome.messages.push(RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    message: SystemMessage::EndSponsorship,
+                });
+            }
             Transfer {
                 owner,
                 amount,
@@ -640,6 +915,7 @@ where
                 parameters,
                 instantiation_argument,
                 required_application_ids,
+                max_query_response_bytes,
             } => {
                 let next_message_id = context.next_message_id(txn_tracker.next_message_index());
                 let CreateApplicationResult {
@@ -651,7 +927,9 @@ where
                         next_message_id,
                         bytecode_id,
                         parameters,
+                        &instantiation_argument,
                         required_application_ids,
+                        max_query_response_bytes,
                     )
                     .await?;
                 self.record_bytecode_blobs(blobs_to_register, txn_tracker)
@@ -679,6 +957,41 @@ where
                 self.read_blob_content(blob_id).await?;
                 self.blob_used(Some(txn_tracker), blob_id).await?;
             }
+            RetryDeadLetter { .. } => {
+                // The message may be addressed to a user application, so this is handled by
+                // `ExecutionStateView::execute_operation`.
+                return Err(SystemExecutionError::OperationNotExecutedBySystem(
+                    "RetryDeadLetter",
+                ));
+            }
+            ExecuteScheduledMessage { .. } => {
+                // The message is addressed to a user application, so this is handled by
+                // `ExecutionStateView::execute_operation`.
+                return Err(SystemExecutionError::OperationNotExecutedBySystem(
+                    "ExecuteScheduledMessage",
+                ));
+            }
+            ParkScheduledMessage { message_id } => {
+                ensure!(
+                    context
+                        .authenticated_signer
+                        .is_some_and(|signer| self.ownership.get().verify_owner(&signer)),
+                    SystemExecutionError::UnauthorizedScheduledMessageParking
+                );
+                let timestamp = *self.timestamp.get();
+                let scheduled_message = self
+                    .take_due_scheduled_message(message_id, timestamp)
+                    .await?;
+                let dead_letter = DeadLetter {
+                    certificate_hash: scheduled_message.certificate_hash,
+                    is_bouncing: false,
+                    authenticated_signer: scheduled_message.authenticated_signer,
+                    refund_grant_to: None,
+                    grant: Amount::ZERO,
+                    message: scheduled_message.message,
+                };
+                self.dead_letters.insert(&message_id, dead_letter)?;
+            }
         }
 
         txn_tracker.add_system_outcome(outcome)?;
@@ -870,11 +1183,40 @@ where
             }
             RegisterApplications { applications } => {
                 for application in applications {
-                    self.check_and_record_bytecode_blobs(&application.bytecode_id, txn_tracker)
-                        .await?;
-                    self.registry
-                        .register_application(application.clone())
-                        .await?;
+                    self.register_application(application, txn_tracker).await?;
+                }
+            }
+            Sponsor { owner, amount } => {
+                if context.is_bouncing {
+                    let new_balance = self.balance.get().saturating_add(amount);
+                    self.balance.set(new_balance);
+                } else {
+                    let sponsor = context.message_id.chain_id;
+                    let balance = match self.sponsorships.get(&sponsor).await? {
+                        Some(sponsorship) => sponsorship.balance.saturating_add(amount),
+                        None => amount,
+                    };
+                    self.sponsorships
+                        .insert(&sponsor, Sponsorship { owner, balance })?;
+                }
+            }
+            EndSponsorship => {
+                let sponsor = context.message_id.chain_id;
+                if let Some(sponsorship) = self.sponsorships.get(&sponsor).await? {
+                    self.sponsorships.remove(&sponsor)?;
+                    if sponsorship.balance > Amount::ZERO {
+                        outcome.messages.push(RawOutgoingMessage {
+                            destination: Destination::Recipient(sponsor),
+                            authenticated: false,
+                            grant: Amount::ZERO,
+                            kind: MessageKind::Tracked,
+                            message: SystemMessage::Credit {
+                                amount: sponsorship.balance,
+                                source: None,
+                                target: None,
+                            },
+                        });
+                    }
                 }
             }
             RequestApplication(application_id) => {
@@ -894,6 +1236,28 @@ where
                 };
                 outcome.messages.push(message);
             }
+            ScheduleMessage {
+                due,
+                application_id,
+                bytes,
+            } => {
+                ensure!(
+                    context.message_id.chain_id == context.chain_id,
+                    SystemExecutionError::ScheduledMessageFromOtherChain(
+                        context.message_id.chain_id
+                    )
+                );
+                let scheduled_message = ScheduledMessage {
+                    due,
+                    certificate_hash: context.certificate_hash,
+                    authenticated_signer: context.authenticated_signer,
+                    message: Message::User {
+                        application_id,
+                        bytes,
+                    },
+                };
+                self.register_scheduled_message(context.message_id, scheduled_message)?;
+            }
             // These messages are executed immediately when cross-chain requests are received.
             Subscribe { .. } | Unsubscribe { .. } | OpenChain(_) => {}
             // This message is only a placeholder: Its ID is part of the application ID.
@@ -1026,12 +1390,104 @@ where
         Ok(messages)
     }
 
+    /// Removes the parked message with the given ID from the dead-letter queue and returns it.
+    pub async fn take_dead_letter(
+        &mut self,
+        message_id: MessageId,
+    ) -> Result<DeadLetter, SystemExecutionError> {
+        let dead_letter = self
+            .dead_letters
+            .get(&message_id)
+            .await?
+            .ok_or(SystemExecutionError::DeadLetterNotFound(message_id))?;
+        self.dead_letters.remove(&message_id)?;
+        Ok(dead_letter)
+    }
+
+    /// Returns an error if the application allowlist of this chain doesn't allow the
+    /// application to execute.
+    pub fn check_application_permitted(
+        &self,
+        application_id: &UserApplicationId,
+    ) -> Result<(), SystemExecutionError> {
+        if let Some(allowlist) = self.application_allowlist.get() {
+            ensure!(
+                allowlist.allows(application_id),
+                SystemExecutionError::ApplicationNotPermitted(Box::new(*application_id))
+            );
+        }
+        Ok(())
+    }
+
+    /// Registers a message that an application of this chain scheduled to itself, when the
+    /// `ScheduleMessage` system message with the given ID is executed.
+    fn register_scheduled_message(
+        &mut self,
+        message_id: MessageId,
+        scheduled_message: ScheduledMessage,
+    ) -> Result<(), ViewError> {
+        self.scheduled_message_queue.insert(&ScheduledMessageKey {
+            due: scheduled_message.due,
+            message_id,
+        })?;
+        self.scheduled_messages
+            .insert(&message_id, scheduled_message)
+    }
+
+    /// Returns the IDs of the scheduled messages that are due at the given block `timestamp`,
+    /// in order of their due time.
+    pub async fn due_scheduled_messages(
+        &self,
+        timestamp: Timestamp,
+    ) -> Result<Vec<MessageId>, ViewError> {
+        let mut message_ids = Vec::new();
+        self.scheduled_message_queue
+            .for_each_index_while(|key| {
+                if key.due > timestamp {
+                    return Ok(false);
+                }
+                message_ids.push(key.message_id);
+                Ok(true)
+            })
+            .await?;
+        Ok(message_ids)
+    }
+
+    /// Removes the scheduled message with the given ID and returns it, if it is due at the
+    /// given block `timestamp`.
+    pub async fn take_due_scheduled_message(
+        &mut self,
+        message_id: MessageId,
+        timestamp: Timestamp,
+    ) -> Result<ScheduledMessage, SystemExecutionError> {
+        let scheduled_message = self
+            .scheduled_messages
+            .get(&message_id)
+            .await?
+            .ok_or(SystemExecutionError::ScheduledMessageNotFound(message_id))?;
+        ensure!(
+            scheduled_message.due <= timestamp,
+            SystemExecutionError::ScheduledMessageNotDue {
+                message_id,
+                due: scheduled_message.due,
+            }
+        );
+        self.scheduled_messages.remove(&message_id)?;
+        self.scheduled_message_queue.remove(&ScheduledMessageKey {
+            due: scheduled_message.due,
+            message_id,
+        })?;
+        Ok(scheduled_message)
+    }
+
     pub async fn create_application(
         &mut self,
         next_message_id: MessageId,
         bytecode_id: BytecodeId,
         parameters: Vec<u8>,
+        instantiation_argument: &[u8],
         required_application_ids: Vec<UserApplicationId>,
+        max_query_response_bytes: Option<u64>,
     ) -> Result<CreateApplicationResult, SystemExecutionError> {
         let id = UserApplicationId {
             bytecode_id,
@@ -1050,8 +1506,15 @@ where
                 blobs_to_register.push(service_bytecode_blob_id);
             }
         }
+        self.check_abi(bytecode_id, &parameters, instantiation_argument)
+            .await?;
         self.registry
-            .register_new_application(id, parameters.clone(), required_application_ids.clone())
+            .register_new_application(
+                id,
+                parameters.clone(),
+                required_application_ids.clone(),
+                max_query_response_bytes,
+            )
             .await?;
         // Send a message to ourself to increment the message ID.
         let message = RawOutgoingMessage {
@@ -1069,6 +1532,46 @@ where
         })
     }
 
+    /// Checks the `parameters` and `instantiation_argument` of a new application against the
+    /// ABI descriptor embedded in its contract bytecode, if any.
+    async fn check_abi(
+        &mut self,
+        bytecode_id: BytecodeId,
+        parameters: &[u8],
+        instantiation_argument: &[u8],
+    ) -> Result<(), SystemExecutionError> {
+        let contract_bytecode_blob_id =
+            BlobId::new(bytecode_id.contract_blob_hash, BlobType::ContractBytecode);
+        let contract_blob = self.read_blob_content(contract_bytecode_blob_id).await?;
+        let compressed_bytecode = CompressedBytecode {
+            compressed_bytes: contract_blob.into_bytes().into_vec(),
+        };
+        let abi_mismatch = |argument, error| SystemExecutionError::AbiMismatch {
+            bytecode_id: Box::new(bytecode_id),
+            argument,
+            error,
+        };
+        let Ok(bytecode) = compressed_bytecode.decompress() else {
+            // Invalid bytecode is reported when the application is loaded.
+            return Ok(());
+        };
+        if !bytecode.as_ref().starts_with(AbiDescriptor::WASM_MAGIC) {
+            return Ok(());
+        }
+        let Some(descriptor) = AbiDescriptor::from_wasm(bytecode.as_ref())
+            .map_err(|error| abi_mismatch("contract bytecode", error))?
+        else {
+            return Ok(());
+        };
+        descriptor
+            .check_parameters(parameters)
+            .map_err(|error| abi_mismatch("parameters", error))?;
+        descriptor
+            .check_instantiation_argument(instantiation_argument)
+            .map_err(|error| abi_mismatch("instantiation argument", error))?;
+        Ok(())
+    }
+
     /// Records a blob that is used in this block. If this is the first use on this chain, creates
     /// an oracle response for it.
     pub(crate) async fn blob_used(
@@ -1164,6 +1667,20 @@ where
         Ok(())
     }
 
+    /// Registers an application created on another chain, after its dependencies, if it is
+    /// permitted on this chain and its bytecode is available.
+    pub(crate) async fn register_application(
+        &mut self,
+        application: UserApplicationDescription,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<(), SystemExecutionError> {
+        self.check_application_permitted(&UserApplicationId::from(&application))?;
+        self.check_and_record_bytecode_blobs(&application.bytecode_id, txn_tracker)
+            .await?;
+        self.registry.register_application(application).await?;
+        Ok(())
+    }
+
     async fn check_and_record_bytecode_blobs(
         &mut self,
         bytecode_id: &BytecodeId,
@@ -57,6 +57,7 @@ pub fn create_dummy_user_application_description(
             },
             required_application_ids: vec![],
             parameters: vec![],
+            max_query_response_bytes: None,
         },
         contract_blob,
         service_blob,
@@ -70,6 +71,7 @@ pub fn create_dummy_operation_context() -> OperationContext {
         height: BlockHeight(0),
         round: Some(0),
         index: Some(0),
+        call_depth: 0,
         authenticated_signer: None,
         authenticated_caller_id: None,
     }
@@ -99,6 +101,10 @@ pub fn create_dummy_query_context() -> QueryContext {
         chain_id: ChainId::root(0),
         next_block_height: BlockHeight(0),
         local_time: Timestamp::from(0),
+        block_hash: None,
+        block_timestamp: Timestamp::from(0),
+        maximum_response_size: u64::MAX,
+        authenticated_signer: None,
     }
 }
 
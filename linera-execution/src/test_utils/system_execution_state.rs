@@ -26,10 +26,11 @@ use crate::{
     applications::ApplicationRegistry,
     committee::{Committee, Epoch},
     execution::UserAction,
-    system::SystemChannel,
+    system::{ApplicationAllowlist, SystemChannel},
     ChannelSubscription, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext,
     ExecutionStateView, OperationContext, ResourceControlPolicy, ResourceController,
-    ResourceTracker, TestExecutionRuntimeContext, UserApplicationDescription, UserContractCode,
+    ResourceTracker, SharedExecutionRuntimeConfig, TestExecutionRuntimeContext,
+    UserApplicationDescription, UserContractCode,
 };
 
 /// A system execution state, not represented as a view but as a simple struct.
@@ -50,6 +51,8 @@ pub struct SystemExecutionState {
     #[debug(skip_if = Not::not)]
     pub closed: bool,
     pub application_permissions: ApplicationPermissions,
+    #[debug(skip_if = Option::is_none)]
+    pub application_allowlist: Option<ApplicationAllowlist>,
     #[debug(skip_if = Vec::is_empty)]
     pub extra_blobs: Vec<Blob>,
     #[debug(skip_if = BTreeMap::is_empty)]
@@ -95,7 +98,7 @@ impl SystemExecutionState {
     pub async fn into_view_with(
         self,
         chain_id: ChainId,
-        execution_runtime_config: ExecutionRuntimeConfig,
+        execution_runtime_config: impl Into<SharedExecutionRuntimeConfig>,
     ) -> ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>> {
         // Destructure, to make sure we don't miss any fields.
         let SystemExecutionState {
@@ -112,6 +115,7 @@ impl SystemExecutionState {
             used_blobs,
             closed,
             application_permissions,
+            application_allowlist,
             extra_blobs,
             mock_applications,
         } = self;
@@ -172,6 +176,7 @@ impl SystemExecutionState {
         view.system
             .application_permissions
             .set(application_permissions);
+        view.system.application_allowlist.set(application_allowlist);
         view
     }
 }
@@ -1,19 +1,28 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::vec;
+use std::{collections::BTreeMap, vec};
 
 use custom_debug_derive::Debug;
 use linera_base::{
-    data_types::{Amount, ArithmeticError, OracleResponse},
+    data_types::{Amount, ArithmeticError, BlockHeight, OracleResponse},
     ensure,
-    identifiers::ApplicationId,
+    identifiers::{ApplicationId, ChainId},
 };
+use linera_views::{access_tracking::ViewAccessReport, batch::Batch};
 
 use crate::{
-    ExecutionError, ExecutionOutcome, RawExecutionOutcome, SystemExecutionError, SystemMessage,
+    ExecutionError, ExecutionOutcome, GuestInputAudit, HeapUsage, RawExecutionOutcome,
+    SystemExecutionError, SystemMessage,
 };
 
+/// The outcomes of a [`TransactionTracker`] at some point of the execution.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OutcomeCheckpoint {
+    outcome_count: usize,
+    next_message_index: u32,
+}
+
 /// Tracks oracle responses and execution outcomes of an ongoing transaction execution, as well
 /// as replayed oracle responses.
 #[derive(Debug, Default)]
@@ -25,6 +34,18 @@ pub struct TransactionTracker {
     #[debug(skip_if = Vec::is_empty)]
     outcomes: Vec<ExecutionOutcome>,
     next_message_index: u32,
+    /// The audit of the inputs observed by the applications, if enabled.
+    #[debug(skip_if = Option::is_none)]
+    guest_input_audit: Option<GuestInputAudit>,
+    /// The accesses to the views of each application, if tracked.
+    #[debug(skip_if = Option::is_none)]
+    view_access_reports: Option<BTreeMap<ApplicationId, ViewAccessReport>>,
+    /// The heap usage reported by each application built with the `profiling` feature.
+    #[debug(skip_if = BTreeMap::is_empty)]
+    heap_usage: BTreeMap<ApplicationId, HeapUsage>,
+    /// The height of the last block of each chain whose messages the block receives.
+    #[debug(skip_if = BTreeMap::is_empty)]
+    received_heights: BTreeMap<ChainId, BlockHeight>,
 }
 
 impl TransactionTracker {
@@ -34,6 +55,84 @@ impl TransactionTracker {
             next_message_index,
             oracle_responses: Vec::new(),
             outcomes: Vec::new(),
+            guest_input_audit: None,
+            view_access_reports: None,
+            heap_usage: BTreeMap::new(),
+            received_heights: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the height of the last block of each chain whose messages the block receives, at
+    /// which the applications can read the state of these chains.
+    pub fn set_received_heights(&mut self, received_heights: BTreeMap<ChainId, BlockHeight>) {
+        self.received_heights = received_heights;
+    }
+
+    /// Returns the height of the last block of `chain_id` whose messages the block receives.
+    pub fn received_height(&self, chain_id: ChainId) -> Option<BlockHeight> {
+        self.received_heights.get(&chain_id).copied()
+    }
+
+    /// Starts auditing the inputs observed by the applications, unless already enabled.
+    pub fn enable_guest_input_audit(&mut self) {
+        self.guest_input_audit
+            .get_or_insert_with(GuestInputAudit::default);
+    }
+
+    /// Returns the audit of the inputs observed by the applications, if enabled.
+    pub fn guest_input_audit(&self) -> Option<&GuestInputAudit> {
+        self.guest_input_audit.as_ref()
+    }
+
+    pub(crate) fn guest_input_audit_mut(&mut self) -> Option<&mut GuestInputAudit> {
+        self.guest_input_audit.as_mut()
+    }
+
+    /// Starts tracking the accesses to the views of the applications, unless already enabled.
+    pub fn enable_view_access_tracking(&mut self) {
+        self.view_access_reports.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Returns whether the accesses to the views of the applications are tracked.
+    pub fn tracks_view_accesses(&self) -> bool {
+        self.view_access_reports.is_some()
+    }
+
+    /// Returns the accesses to the views of each application, if tracked.
+    pub fn view_access_reports(&self) -> Option<&BTreeMap<ApplicationId, ViewAccessReport>> {
+        self.view_access_reports.as_ref()
+    }
+
+    /// Returns the heap usage reported by each application during the transaction.
+    pub fn heap_usage(&self) -> &BTreeMap<ApplicationId, HeapUsage> {
+        &self.heap_usage
+    }
+
+    /// Records the heap usage reported by an application. Each instance reports its usage
+    /// since it was created, so a later report replaces the earlier ones.
+    pub(crate) fn record_heap_usage(&mut self, id: ApplicationId, usage: HeapUsage) {
+        self.heap_usage.insert(id, usage);
+    }
+
+    /// Records reads of the given keys, with the number of bytes returned for each, if the
+    /// accesses to the views are tracked.
+    pub(crate) fn record_view_reads<'a>(
+        &mut self,
+        id: ApplicationId,
+        reads: impl IntoIterator<Item = (&'a Vec<u8>, usize)>,
+    ) {
+        if let Some(reports) = &mut self.view_access_reports {
+            let report = reports.entry(id).or_default();
+            for (key, bytes) in reads {
+                report.record_read(key, bytes);
+            }
+        }
+    }
+
+    /// Records the writes of a batch, if the accesses to the views are tracked.
+    pub(crate) fn record_view_writes(&mut self, id: ApplicationId, batch: &Batch) {
+        if let Some(reports) = &mut self.view_access_reports {
+            reports.entry(id).or_default().record_batch(batch);
         }
     }
 
@@ -77,6 +176,23 @@ impl TransactionTracker {
         Ok(())
     }
 
+    /// Returns the point to roll the outcomes back to with
+    /// [`restore_outcomes`][`Self::restore_outcomes`].
+    pub(crate) fn outcome_checkpoint(&self) -> OutcomeCheckpoint {
+        OutcomeCheckpoint {
+            outcome_count: self.outcomes.len(),
+            next_message_index: self.next_message_index,
+        }
+    }
+
+    /// Discards the outcomes added since the `checkpoint`.
+    ///
+    /// The oracle responses are kept, since replaying the transaction observes them again.
+    pub(crate) fn restore_outcomes(&mut self, checkpoint: OutcomeCheckpoint) {
+        self.outcomes.truncate(checkpoint.outcome_count);
+        self.next_message_index = checkpoint.next_message_index;
+    }
+
     pub fn add_oracle_response(&mut self, oracle_response: OracleResponse) {
         self.oracle_responses.push(oracle_response);
     }
@@ -120,6 +236,10 @@ impl TransactionTracker {
             oracle_responses,
             outcomes,
             next_message_index,
+            guest_input_audit: _,
+            view_access_reports: _,
+            heap_usage: _,
+            received_heights: _,
         } = self;
         if let Some(mut responses) = replaying_oracle_responses {
             ensure!(
@@ -39,6 +39,7 @@ fn app_description(index: u32, deps: Vec<u32>) -> UserApplicationDescription {
         creation: message_id(index),
         parameters: vec![],
         required_application_ids: deps.into_iter().map(app_id).collect(),
+        max_query_response_bytes: None,
     }
 }
 
@@ -118,3 +119,73 @@ async fn test_topological_sort_with_loop() {
         Vec::from_iter([2, 3, 1, 0].into_iter().map(app_id))
     );
 }
+
+#[tokio::test]
+async fn test_list_applications_with_pagination() {
+    let mut view = ApplicationRegistryView::new().await;
+    view.import(registry((0..5).map(|index| (index, vec![]))))
+        .unwrap();
+    let other_bytecode_id = BytecodeId::new(
+        CryptoHash::test_hash("other contract"),
+        CryptoHash::test_hash("other service"),
+    );
+    for index in 5..7 {
+        let description = UserApplicationDescription {
+            bytecode_id: other_bytecode_id,
+            ..app_description(index, vec![])
+        };
+        view.register_application(description).await.unwrap();
+    }
+
+    let all_applications = view
+        .list_applications(None, None, usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(all_applications.len(), 7);
+    for (id, description) in &all_applications {
+        assert_eq!(*id, UserApplicationId::from(description));
+    }
+
+    // Paging through the applications with a cursor visits each of them once, in order.
+    let mut pages = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = view.list_applications(cursor, None, 3).await.unwrap();
+        let Some((last_id, _)) = page.last() else {
+            break;
+        };
+        cursor = Some(*last_id);
+        pages.push(page);
+    }
+    assert_eq!(
+        pages.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![3, 3, 1]
+    );
+    assert_eq!(pages.concat(), all_applications);
+
+    // Applications can be filtered by bytecode.
+    let other_applications = view
+        .list_applications(None, Some(other_bytecode_id), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(other_applications.len(), 2);
+    assert!(other_applications
+        .iter()
+        .all(|(_, description)| description.bytecode_id == other_bytecode_id));
+    let first_page = view
+        .list_applications(None, Some(bytecode_id()), 4)
+        .await
+        .unwrap();
+    let (cursor, _) = first_page.last().unwrap();
+    let second_page = view
+        .list_applications(Some(*cursor), Some(bytecode_id()), 4)
+        .await
+        .unwrap();
+    assert_eq!(first_page.len() + second_page.len(), 5);
+
+    // An unknown cursor is an error.
+    assert!(view
+        .list_applications(Some(app_id(7)), None, 3)
+        .await
+        .is_err());
+}
@@ -0,0 +1,63 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::data_types::Bytecode;
+
+use super::ModuleCache;
+
+fn cache_with_max_size(max_size: u64) -> ModuleCache<usize> {
+    ModuleCache {
+        max_size,
+        ..ModuleCache::default()
+    }
+}
+
+/// Checks that a removed module is compiled again, and that it no longer counts towards the
+/// size of the cache.
+#[test]
+fn test_removed_module_is_rebuilt() {
+    let mut cache = cache_with_max_size(10);
+    let bytecode = Bytecode::new(vec![1; 4]);
+    let mut builds = 0;
+    let mut build = |bytecode: Bytecode| {
+        builds += 1;
+        Ok::<_, ()>(bytecode.as_ref().len())
+    };
+
+    assert_eq!(
+        cache.get_or_insert_with(bytecode.clone(), &mut build),
+        Ok(4)
+    );
+    assert_eq!(
+        cache.get_or_insert_with(bytecode.clone(), &mut build),
+        Ok(4)
+    );
+    assert_eq!(cache.total_size, 4);
+
+    assert_eq!(cache.remove(&bytecode), Some(4));
+    assert_eq!(cache.remove(&bytecode), None);
+    assert_eq!(cache.total_size, 0);
+    assert_eq!(cache.get(&bytecode), None);
+
+    assert_eq!(cache.get_or_insert_with(bytecode, &mut build), Ok(4));
+    assert_eq!(builds, 2);
+}
+
+/// Checks that the least recently used modules are evicted to stay within the maximum size.
+#[test]
+fn test_cache_size_is_bounded() {
+    let mut cache = cache_with_max_size(10);
+    let bytecodes = (0..3)
+        .map(|byte| Bytecode::new(vec![byte; 4]))
+        .collect::<Vec<_>>();
+
+    for (index, bytecode) in bytecodes.iter().enumerate() {
+        cache.insert(bytecode.clone(), index);
+    }
+    cache.insert(bytecodes[2].clone(), 2);
+
+    assert_eq!(cache.total_size, 8);
+    assert_eq!(cache.get(&bytecodes[0]), None);
+    assert_eq!(cache.get(&bytecodes[1]), Some(1));
+    assert_eq!(cache.get(&bytecodes[2]), Some(2));
+}
@@ -0,0 +1,88 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    crypto::KeyPair,
+    data_types::{TimeDelta, Timestamp},
+    identifiers::{ApplicationId, ChainId, Owner},
+};
+
+use super::{QueryAuthentication, QueryAuthenticationError, DEFAULT_QUERY_SIGNATURE_VALIDITY};
+
+const QUERY: &[u8] = br#"{"query":"{ orders }"}"#;
+
+/// Signs the [`QUERY`] at the `signing_time`, and verifies the signature against `query` at the
+/// `local_time`.
+fn sign_and_verify(
+    key_pair: &KeyPair,
+    query: &[u8],
+    signing_time: Timestamp,
+    local_time: Timestamp,
+) -> Result<Owner, QueryAuthenticationError> {
+    let chain_id = ChainId::root(0);
+    let application_id = ApplicationId::default();
+    let authentication =
+        QueryAuthentication::new(chain_id, application_id, QUERY, signing_time, key_pair);
+    authentication.verify(
+        chain_id,
+        application_id,
+        query,
+        local_time,
+        DEFAULT_QUERY_SIGNATURE_VALIDITY,
+    )
+}
+
+#[test]
+fn test_valid_signature_authenticates_the_owner() {
+    let key_pair = KeyPair::generate();
+    let signing_time = Timestamp::from(1_000_000_000);
+    let local_time = signing_time.saturating_add(TimeDelta::from_secs(10));
+
+    let owner = sign_and_verify(&key_pair, QUERY, signing_time, local_time).unwrap();
+
+    assert_eq!(owner, Owner::from(key_pair.public()));
+}
+
+#[test]
+fn test_expired_signature_is_rejected() {
+    let key_pair = KeyPair::generate();
+    let signing_time = Timestamp::from(1_000_000_000);
+    let local_time = signing_time
+        .saturating_add(DEFAULT_QUERY_SIGNATURE_VALIDITY)
+        .saturating_add_micros(1);
+
+    let result = sign_and_verify(&key_pair, QUERY, signing_time, local_time);
+
+    assert!(matches!(
+        result,
+        Err(QueryAuthenticationError::Expired { timestamp, .. }) if timestamp == signing_time
+    ));
+}
+
+#[test]
+fn test_signature_from_the_future_is_rejected() {
+    let key_pair = KeyPair::generate();
+    let local_time = Timestamp::from(1_000_000_000);
+    let signing_time = local_time.saturating_add(TimeDelta::from_secs(3_600));
+
+    let result = sign_and_verify(&key_pair, QUERY, signing_time, local_time);
+
+    assert!(matches!(
+        result,
+        Err(QueryAuthenticationError::Expired { .. })
+    ));
+}
+
+#[test]
+fn test_tampered_query_is_rejected() {
+    let key_pair = KeyPair::generate();
+    let time = Timestamp::from(1_000_000_000);
+    let tampered_query = br#"{"query":"{ allOrders }"}"#;
+
+    let result = sign_and_verify(&key_pair, tampered_query, time, time);
+
+    assert!(matches!(
+        result,
+        Err(QueryAuthenticationError::InvalidSignature(_))
+    ));
+}
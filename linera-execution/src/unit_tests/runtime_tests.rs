@@ -131,7 +131,7 @@ async fn test_write_batch() {
         assert_eq!(batch, expected_batch);
 
         callback
-            .send(())
+            .send(0)
             .expect("Failed to notify that writing the batch finished");
     });
 
@@ -1,7 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use assert_matches::assert_matches;
 use linera_base::{
+    abi::{AbiSchema, AbiSchemas, Schema},
+    abi_descriptor::AbiDescriptor,
     data_types::{Blob, BlockHeight, Bytecode},
     identifiers::ApplicationId,
 };
@@ -24,6 +27,7 @@ async fn new_view_and_context() -> (
         height: BlockHeight::from(7),
         round: Some(0),
         index: Some(2),
+        call_depth: 0,
     };
     let state = SystemExecutionState {
         description: Some(description),
@@ -50,6 +54,7 @@ async fn application_message_index() -> anyhow::Result<()> {
         parameters: vec![],
         instantiation_argument: vec![],
         required_application_ids: vec![],
+        max_query_response_bytes: None,
     };
     let mut txn_tracker = TransactionTracker::default();
     view.context()
@@ -81,6 +86,76 @@ async fn application_message_index() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns an empty WebAssembly module with an ABI descriptor expecting `()` parameters and a
+/// `u64` instantiation argument.
+fn module_with_abi_descriptor() -> Bytecode {
+    const SCHEMAS: Option<AbiSchemas> = Some(AbiSchemas {
+        operation: Schema::ANY,
+        message: Schema::ANY,
+        query: Schema::ANY,
+        parameters: <()>::SCHEMA,
+        instantiation_argument: u64::SCHEMA,
+    });
+    let section = AbiSchemas::encode::<{ AbiSchemas::encoded_len(&SCHEMAS) }>(&SCHEMAS);
+    let name = AbiDescriptor::SECTION_NAME.as_bytes();
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    module.extend([0, (1 + name.len() + section.len()) as u8, name.len() as u8]);
+    module.extend(name);
+    module.extend(section);
+    Bytecode::new(module)
+}
+
+#[tokio::test]
+async fn application_creation_checks_abi() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let contract_blob = Blob::new_contract_bytecode(module_with_abi_descriptor().compress());
+    let service_blob = Blob::new_service_bytecode(Bytecode::new(b"service".into()).compress());
+    let bytecode_id = BytecodeId::new(contract_blob.id().hash, service_blob.id().hash);
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+    let create_application = |instantiation_argument: &str| SystemOperation::CreateApplication {
+        bytecode_id,
+        parameters: b"null".to_vec(),
+        instantiation_argument: instantiation_argument.as_bytes().to_vec(),
+        required_application_ids: vec![],
+        max_query_response_bytes: None,
+    };
+
+    let result = view
+        .system
+        .execute_operation(
+            context,
+            create_application("\"ten\""),
+            &mut TransactionTracker::default(),
+        )
+        .await;
+    let Err(SystemExecutionError::AbiMismatch {
+        argument, error, ..
+    }) = result
+    else {
+        panic!("Unexpected result: {result:?}");
+    };
+    assert_eq!(argument, "instantiation argument");
+    assert_eq!(
+        error.to_string(),
+        "At `.`: expected a number, found a string"
+    );
+
+    let new_application = view
+        .system
+        .execute_operation(
+            context,
+            create_application("10"),
+            &mut TransactionTracker::default(),
+        )
+        .await?;
+    assert!(new_application.is_some());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn open_chain_message_index() {
     let (mut view, context) = new_view_and_context().await;
@@ -134,3 +209,139 @@ async fn empty_accounts_are_removed() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that a chain can set funds aside to sponsor another chain, and get back the funds
+/// that were not used once the sponsorship ends.
+#[tokio::test]
+async fn sponsorship_is_funded_and_ended() -> anyhow::Result<()> {
+    let (mut sponsor_view, mut context) = new_view_and_context().await;
+    let sponsor = context.chain_id;
+    let chain_owner = Owner::from(linera_base::crypto::PublicKey::test_key(0));
+    let sponsorship_owner = Owner::from(linera_base::crypto::PublicKey::test_key(1));
+    sponsor_view
+        .system
+        .ownership
+        .set(ChainOwnership::single(chain_owner));
+    sponsor_view.system.balance.set(Amount::from_tokens(10));
+    let chain_id = ChainId::root(1);
+    let operation = SystemOperation::Sponsor {
+        chain_id,
+        owner: sponsorship_owner,
+        amount: Amount::from_tokens(3),
+    };
+
+    // Only the owners of the sponsoring chain can sponsor another chain.
+    let result = sponsor_view
+        .system
+        .execute_operation(
+            context,
+            operation.clone(),
+            &mut TransactionTracker::default(),
+        )
+        .await;
+    assert_matches!(result, Err(SystemExecutionError::UnauthorizedSponsorship));
+
+    // The funds are taken out of the sponsoring chain's balance and sent to the other chain.
+    context.authenticated_signer = Some(chain_owner);
+    let mut txn_tracker = TransactionTracker::default();
+    sponsor_view
+        .system
+        .execute_operation(context, operation, &mut txn_tracker)
+        .await?;
+    assert_eq!(*sponsor_view.system.balance.get(), Amount::from_tokens(7));
+    let [ExecutionOutcome::System(result)] = &txn_tracker.destructure()?.0[..] else {
+        panic!("Unexpected outcome");
+    };
+    let [sponsor_message] = &result.messages[..] else {
+        panic!("Unexpected messages");
+    };
+    assert_eq!(
+        sponsor_message.destination,
+        Destination::Recipient(chain_id)
+    );
+    let message = SystemMessage::Sponsor {
+        owner: sponsorship_owner,
+        amount: Amount::from_tokens(3),
+    };
+    assert_eq!(sponsor_message.message, message);
+
+    // The sponsored chain adds the funds to the sponsorship, each time they are received.
+    let mut view = SystemExecutionState {
+        description: Some(ChainDescription::Root(1)),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+    let message_context = MessageContext {
+        chain_id,
+        is_bouncing: false,
+        authenticated_signer: None,
+        refund_grant_to: None,
+        height: BlockHeight::from(3),
+        round: None,
+        certificate_hash: CryptoHash::test_hash("sponsor certificate"),
+        message_id: MessageId {
+            chain_id: sponsor,
+            height: context.height,
+            index: 0,
+        },
+    };
+    for _ in 0..2 {
+        view.system
+            .execute_message(
+                message_context,
+                message.clone(),
+                &mut TransactionTracker::default(),
+            )
+            .await?;
+    }
+    assert_eq!(
+        view.system.sponsorships.get(&sponsor).await?,
+        Some(Sponsorship {
+            owner: sponsorship_owner,
+            balance: Amount::from_tokens(6),
+        })
+    );
+
+    // If the sponsored chain rejects the funds, they are credited back to the sponsoring chain.
+    let bouncing_context = MessageContext {
+        chain_id: sponsor,
+        is_bouncing: true,
+        ..message_context
+    };
+    sponsor_view
+        .system
+        .execute_message(
+            bouncing_context,
+            message,
+            &mut TransactionTracker::default(),
+        )
+        .await?;
+    assert_eq!(*sponsor_view.system.balance.get(), Amount::from_tokens(10));
+    assert!(sponsor_view.system.sponsorships.indices().await?.is_empty());
+
+    // Ending the sponsorship sends the funds that were not used back to the sponsoring chain.
+    let outcome = view
+        .system
+        .execute_message(
+            message_context,
+            SystemMessage::EndSponsorship,
+            &mut TransactionTracker::default(),
+        )
+        .await?;
+    assert!(view.system.sponsorships.indices().await?.is_empty());
+    let [credit] = &outcome.messages[..] else {
+        panic!("Unexpected messages");
+    };
+    assert_eq!(credit.destination, Destination::Recipient(sponsor));
+    assert_eq!(
+        credit.message,
+        SystemMessage::Credit {
+            target: None,
+            amount: Amount::from_tokens(6),
+            source: None,
+        }
+    );
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Symbolized backtraces of the traps of Wasm modules.
+//!
+//! The names of the functions are read from the `name` section of the module, and their
+//! source locations from its DWARF sections, if the runtime has access to them. Modules
+//! without a `name` section trap without a backtrace, so that nothing is allocated to
+//! describe frames that would only show offsets.
+
+use std::fmt::{self, Display, Formatter};
+
+use super::WasmExecutionError;
+
+/// The frames of the call stack of a Wasm module when it trapped, innermost first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmBacktrace {
+    pub frames: Vec<WasmFrame>,
+}
+
+/// A frame of a [`WasmBacktrace`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmFrame {
+    /// The name of the function, if it is in the `name` section of the module.
+    pub function_name: Option<String>,
+    /// The offset of the instruction in the module, if known.
+    pub module_offset: Option<usize>,
+    /// The source file of the instruction, if the module has DWARF sections.
+    pub file: Option<String>,
+    /// The source line of the instruction, if the module has DWARF sections.
+    pub line: Option<u32>,
+}
+
+impl WasmBacktrace {
+    /// Returns the symbolized backtrace of `error` if it is a trap of a module with a `name`
+    /// section, together with the message of the trap.
+    ///
+    /// Errors returned by the host functions are not traps of the module, and are left to be
+    /// reported as they are.
+    pub(crate) fn of_trap(error: &WasmExecutionError) -> Option<(String, Self)> {
+        match error {
+            #[cfg(with_wasmer)]
+            WasmExecutionError::ExecuteModuleInWasmer(error)
+            | WasmExecutionError::ExecuteModule(linera_witty::RuntimeError::Wasmer(error)) => {
+                Some((error.message(), Self::from_wasmer(error)?))
+            }
+            #[cfg(with_wasmtime)]
+            WasmExecutionError::ExecuteModule(linera_witty::RuntimeError::Wasmtime(error)) => {
+                Some((error.root_cause().to_string(), Self::from_wasmtime(error)?))
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(with_wasmer)]
+    fn from_wasmer(error: &::wasmer::RuntimeError) -> Option<Self> {
+        if std::error::Error::source(error).is_some() {
+            return None;
+        }
+        let trace = error.trace();
+        if trace.iter().all(|frame| frame.function_name().is_none()) {
+            return None;
+        }
+        let frames = trace
+            .iter()
+            .map(|frame| WasmFrame {
+                function_name: frame.function_name().map(str::to_owned),
+                module_offset: Some(frame.module_offset()),
+                file: None,
+                line: None,
+            })
+            .collect();
+        Some(WasmBacktrace { frames })
+    }
+
+    #[cfg(with_wasmtime)]
+    fn from_wasmtime(error: &anyhow::Error) -> Option<Self> {
+        error.downcast_ref::<::wasmtime::Trap>()?;
+        let backtrace = error.downcast_ref::<::wasmtime::WasmBacktrace>()?;
+        if backtrace
+            .frames()
+            .iter()
+            .all(|frame| frame.func_name().is_none())
+        {
+            return None;
+        }
+        let frames = backtrace
+            .frames()
+            .iter()
+            .map(|frame| {
+                let symbol = frame
+                    .symbols()
+                    .iter()
+                    .find(|symbol| symbol.file().is_some());
+                WasmFrame {
+                    function_name: frame.func_name().map(str::to_owned),
+                    module_offset: frame.module_offset(),
+                    file: symbol.and_then(|symbol| symbol.file()).map(str::to_owned),
+                    line: symbol.and_then(|symbol| symbol.line()),
+                }
+            })
+            .collect();
+        Some(WasmBacktrace { frames })
+    }
+}
+
+impl Display for WasmBacktrace {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        for (index, frame) in self.frames.iter().enumerate() {
+            write!(formatter, "\n  {index}: {frame}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for WasmFrame {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}",
+            self.function_name.as_deref().unwrap_or("<unknown>")
+        )?;
+        match (&self.file, self.line, self.module_offset) {
+            (Some(file), Some(line), _) => write!(formatter, " at {file}:{line}"),
+            (Some(file), None, _) => write!(formatter, " at {file}"),
+            (None, _, Some(offset)) => write!(formatter, " at offset {offset:#x}"),
+            (None, _, None) => Ok(()),
+        }
+    }
+}
@@ -10,11 +10,14 @@
 
 #![cfg(with_wasm_runtime)]
 
+mod backtrace;
 mod entrypoints;
 mod module_cache;
 mod sanitizer;
 #[macro_use]
 mod system_api;
+mod validation;
+mod version;
 #[cfg(with_wasmer)]
 mod wasmer;
 #[cfg(with_wasmtime)]
@@ -35,8 +38,13 @@ use {
 
 use self::sanitizer::sanitize;
 pub use self::{
+    backtrace::{WasmBacktrace, WasmFrame},
     entrypoints::{ContractEntrypoints, ServiceEntrypoints},
-    system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi},
+    system_api::{
+        ContractSystemApi, ServiceSystemApi, SystemApiData, TestSystemApi, ViewSystemApi,
+    },
+    validation::{validate_bytecode, ForbiddenImport, ForbiddenImportReason},
+    version::SystemApiVersion,
 };
 use crate::{
     ContractSyncRuntimeHandle, ExecutionError, ServiceSyncRuntimeHandle, UserContractInstance,
@@ -182,6 +190,15 @@ impl WasmServiceModule {
         )
         .await
     }
+
+    /// Removes the modules compiled from `service_bytecode` from the caches of all the
+    /// runtimes, e.g. because the bytecode was replaced and will not be used anymore.
+    pub async fn evict_from_cache(service_bytecode: &Bytecode) {
+        #[cfg(with_wasmer)]
+        Self::evict_from_wasmer_cache(service_bytecode).await;
+        #[cfg(with_wasmtime)]
+        Self::evict_from_wasmtime_cache(service_bytecode).await;
+    }
 }
 
 impl UserServiceModule for WasmServiceModule {
@@ -296,6 +313,20 @@ pub enum WasmExecutionError {
     UnknownPromise,
     #[error("Attempt to call incorrect `wait` function for a promise")]
     IncorrectPromise,
+    #[error(
+        "Wasm module has forbidden imports: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    ForbiddenImports(Vec<ForbiddenImport>),
+    #[error("Wasm module imports from an unsupported version of a system interface: `{0}`")]
+    UnsupportedSystemApiVersion(String),
+    #[error("Wasm module imports from several versions of the system interfaces")]
+    MixedSystemApiVersions,
+    #[error("Wasm module trapped: {message}{backtrace}")]
+    Trap {
+        message: String,
+        backtrace: WasmBacktrace,
+    },
 }
 
 #[cfg(with_wasmer)]
@@ -10,6 +10,10 @@
 use linera_base::data_types::Bytecode;
 use lru::LruCache;
 
+#[cfg(test)]
+#[path = "../unit_tests/module_cache_tests.rs"]
+mod module_cache_tests;
+
 /// The default maximum size of the bytecodes stored in cache.
 const DEFAULT_MAX_CACHE_SIZE: u64 = 512 /* MiB */ * 1024 /* KiB */ * 1024 /* bytes */;
 
@@ -58,11 +62,25 @@ impl<Module: Clone> ModuleCache<Module> {
     pub fn insert(&mut self, bytecode: Bytecode, module: Module) {
         let bytecode_size = bytecode.as_ref().len() as u64;
 
+        if self.modules.contains(&bytecode) {
+            self.modules.put(bytecode, module);
+            return;
+        }
+
         if self.total_size + bytecode_size > self.max_size {
-            self.reduce_size_to(self.max_size - bytecode_size);
+            self.reduce_size_to(self.max_size.saturating_sub(bytecode_size));
         }
 
         self.modules.put(bytecode, module);
+        self.total_size += bytecode_size;
+    }
+
+    /// Removes the `Module` compiled from `bytecode` from the cache, so that it is compiled
+    /// again the next time it is requested.
+    pub fn remove(&mut self, bytecode: &Bytecode) -> Option<Module> {
+        let module = self.modules.pop(bytecode)?;
+        self.total_size -= bytecode.as_ref().len() as u64;
+        Some(module)
     }
 
     /// Evicts entries from the cache so that the total size of cached bytecodes is less than
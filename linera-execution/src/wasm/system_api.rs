@@ -1,11 +1,14 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{any::Any, collections::HashMap, marker::PhantomData};
+use std::{any::Any, collections::HashMap, marker::PhantomData, time::Duration};
 
 use linera_base::{
-    crypto::CryptoHash,
-    data_types::{Amount, ApplicationPermissions, BlockHeight, SendMessageRequest, Timestamp},
+    crypto::{CryptoHash, PublicKey, Signature},
+    data_types::{
+        Amount, ApplicationPermissions, BlockHeight, CallApplicationError, MessageLimits,
+        RemoteValue, SendMessageRequest, Timestamp,
+    },
     identifiers::{
         Account, AccountOwner, ApplicationId, ChainId, ChannelName, MessageId, Owner, StreamName,
     },
@@ -15,17 +18,26 @@ use linera_views::batch::{Batch, WriteOperation};
 use linera_witty::{wit_export, Instance, RuntimeError};
 use tracing::log;
 
-use super::WasmExecutionError;
+use super::{WasmBacktrace, WasmExecutionError};
 use crate::{
-    BaseRuntime, BytecodeId, ContractRuntime, ContractSyncRuntimeHandle, ExecutionError,
-    ServiceRuntime, ServiceSyncRuntimeHandle,
+    benchmark, BaseRuntime, BenchmarkMeasurement, BytecodeId, ContractRuntime,
+    ContractSyncRuntimeHandle, ExecutionError, HeapUsage, QueryContext, ServiceRuntime,
+    ServiceSyncRuntimeHandle,
 };
 
+/// The peak heap usage in bytes above which reports from profiled contracts are logged as warnings.
+const HEAP_USAGE_WARNING_THRESHOLD: u64 = 8 * 1024 * 1024;
+
 /// Common host data used as the `UserData` of the system API implementations.
 pub struct SystemApiData<Runtime> {
     runtime: Runtime,
     active_promises: HashMap<u32, Box<dyn Any + Send + Sync>>,
     promise_counter: u32,
+    /// The panic reported by the application with `report-panic`, before trapping.
+    panic: Option<ExecutionError>,
+    /// The error reported by the application with `report-error`, failing the current
+    /// entrypoint once it returns.
+    error: Option<ExecutionError>,
 }
 
 impl<Runtime> SystemApiData<Runtime> {
@@ -35,6 +47,8 @@ impl<Runtime> SystemApiData<Runtime> {
             runtime,
             active_promises: HashMap::new(),
             promise_counter: 0,
+            panic: None,
+            error: None,
         }
     }
 
@@ -43,6 +57,51 @@ impl<Runtime> SystemApiData<Runtime> {
         &mut self.runtime
     }
 
+    /// Converts the `error` with which a call into the application failed into an
+    /// [`ExecutionError`], preferring the panic reported by the application, if any, to the
+    /// trap that followed it.
+    ///
+    /// Traps of modules with a `name` section are reported as [`WasmExecutionError::Trap`]s
+    /// with their symbolized backtrace, using the message of the panic if there was one.
+    pub(crate) fn entrypoint_error(
+        &mut self,
+        error: impl Into<WasmExecutionError>,
+    ) -> ExecutionError {
+        let reported_error = self.error.take();
+        let panic = self.panic.take();
+        if panic.is_none() {
+            if let Some(reported_error) = reported_error {
+                return reported_error;
+            }
+        }
+        let error = error.into();
+        match (WasmBacktrace::of_trap(&error), panic) {
+            (Some((_, backtrace)), Some(panic)) => WasmExecutionError::Trap {
+                message: panic.to_string(),
+                backtrace,
+            }
+            .into(),
+            (Some((message, backtrace)), None) => {
+                WasmExecutionError::Trap { message, backtrace }.into()
+            }
+            (None, Some(panic)) => panic,
+            (None, None) => error.into(),
+        }
+    }
+
+    /// Converts the `result` of a call into the application into the result of the
+    /// entrypoint, which fails if the application reported an error before returning.
+    pub(crate) fn entrypoint_result<T>(
+        &mut self,
+        result: Result<T, impl Into<WasmExecutionError>>,
+    ) -> Result<T, ExecutionError> {
+        let output = result.map_err(|error| self.entrypoint_error(error))?;
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(output),
+        }
+    }
+
     /// Registers a `promise` internally, returning an ID that is unique for the lifetime of this
     /// [`SystemApiData`].
     fn register_promise<Promise>(&mut self, promise: Promise) -> Result<u32, RuntimeError>
@@ -138,6 +197,16 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Returns the chain sponsoring the fees of the block instead of the chain, if there is
+    /// one.
+    fn fee_payer(caller: &mut Caller) -> Result<Option<ChainId>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .fee_payer()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Retrieves the current system time, i.e. the timestamp of the block in which this is called.
     fn read_system_timestamp(caller: &mut Caller) -> Result<Timestamp, RuntimeError> {
         caller
@@ -198,6 +267,15 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    fn read_own_state_size(caller: &mut Caller) -> Result<u64, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_own_state_size()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Schedules a message to be sent to this application on another chain.
     fn send_message(
         caller: &mut Caller,
@@ -210,6 +288,28 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Schedules messages to be sent to this application on other chains, all or none of them.
+    fn send_messages(
+        caller: &mut Caller,
+        messages: Vec<SendMessageRequest<Vec<u8>>>,
+    ) -> Result<(), RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .send_messages(messages)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Returns the limits on the messages the application can send in this execution, and how
+    /// much of them it used.
+    fn message_limits(caller: &mut Caller) -> Result<MessageLimits, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .message_limits()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Subscribes to a message channel from another chain.
     fn subscribe(
         caller: &mut Caller,
@@ -265,6 +365,20 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Schedules a `message` to the current application, to be executed in the first block
+    /// whose timestamp is not earlier than `due`.
+    fn schedule_message(
+        caller: &mut Caller,
+        due: Timestamp,
+        message: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .schedule_message(due, message)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Retrieves the owner configuration for the current chain.
     fn get_chain_ownership(caller: &mut Caller) -> Result<ChainOwnership, RuntimeError> {
         caller
@@ -336,18 +450,25 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
-    /// Calls another application.
+    /// Calls another application. Returns an error if the application isn't registered on
+    /// the current chain.
     fn try_call_application(
         caller: &mut Caller,
         authenticated: bool,
         callee_id: ApplicationId,
         argument: Vec<u8>,
-    ) -> Result<Vec<u8>, RuntimeError> {
-        caller
-            .user_data_mut()
-            .runtime
-            .try_call_application(authenticated, callee_id, argument)
-            .map_err(|error| RuntimeError::Custom(error.into()))
+    ) -> Result<Result<Vec<u8>, CallApplicationError>, RuntimeError> {
+        match caller.user_data_mut().runtime.try_call_application(
+            authenticated,
+            callee_id,
+            argument,
+        ) {
+            Ok(response) => Ok(Ok(response)),
+            Err(ExecutionError::UnknownApplication { id }) => {
+                Ok(Err(CallApplicationError::UnknownApplication(*id)))
+            }
+            Err(error) => Err(RuntimeError::Custom(error.into())),
+        }
     }
 
     /// Adds an item to an event stream.
@@ -420,6 +541,22 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Reads the value under `key` in the state of an application on another chain, as of the
+    /// last block of that chain whose messages the current block receives, if that state is
+    /// available locally.
+    fn try_read_remote_state(
+        caller: &mut Caller,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        key: Vec<u8>,
+    ) -> Result<Option<RemoteValue>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .try_read_remote_state(chain_id, application_id, key)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Logs a `message` with the provided information `level`.
     fn log(_caller: &mut Caller, message: String, level: log::Level) -> Result<(), RuntimeError> {
         match level {
@@ -432,6 +569,31 @@ where
         Ok(())
     }
 
+    /// Records the panic of the application at the given location, so that the trap
+    /// following it is reported as an [`ExecutionError::UserPanic`].
+    fn report_panic(
+        caller: &mut Caller,
+        message: String,
+        file: String,
+        line: u32,
+        column: u32,
+    ) -> Result<(), RuntimeError> {
+        caller.user_data_mut().panic = Some(ExecutionError::UserPanic {
+            message,
+            file,
+            line,
+            column,
+        });
+        Ok(())
+    }
+
+    /// Records an error of the application, so that the current entrypoint fails with an
+    /// [`ExecutionError::UserError`] once it returns.
+    fn report_error(caller: &mut Caller, message: String) -> Result<(), RuntimeError> {
+        caller.user_data_mut().error = Some(ExecutionError::UserError(message));
+        Ok(())
+    }
+
     /// Consume some fuel.
     ///
     /// This is intended for the metering instrumentation, but if the user wants to donate
@@ -452,6 +614,110 @@ where
             .validation_round()
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
+
+    /// Records the heap usage reported by a contract built with the `profiling` feature of the
+    /// SDK in the transaction tracker, logging a warning if its peak is above
+    /// [`HEAP_USAGE_WARNING_THRESHOLD`].
+    fn record_heap_usage(
+        caller: &mut Caller,
+        peak_bytes: u64,
+        allocations: u64,
+    ) -> Result<(), RuntimeError> {
+        let runtime = caller.user_data_mut().runtime_mut();
+        let application_id = runtime
+            .application_id()
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+        runtime
+            .record_heap_usage(HeapUsage {
+                peak_bytes,
+                allocations,
+            })
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+        if peak_bytes > HEAP_USAGE_WARNING_THRESHOLD {
+            tracing::warn!(
+                ?application_id,
+                peak_bytes,
+                allocations,
+                "Contract heap usage is above {HEAP_USAGE_WARNING_THRESHOLD} bytes"
+            );
+        } else {
+            tracing::debug!(
+                ?application_id,
+                peak_bytes,
+                allocations,
+                "Contract heap usage"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks an Ed25519 `signature` of `message` by `public_key`.
+    fn verify_ed25519(
+        caller: &mut Caller,
+        public_key: PublicKey,
+        message: Vec<u8>,
+        signature: Signature,
+    ) -> Result<bool, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .verify_ed25519(public_key, &message, signature)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Computes the Sha3-256 digest of `data`.
+    fn sha3_256(caller: &mut Caller, data: Vec<u8>) -> Result<Vec<u8>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .sha3_256(&data)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Computes the [`CryptoHash`] of the BCS `data` written by a hashable value.
+    fn bcs_hash(caller: &mut Caller, data: Vec<u8>) -> Result<CryptoHash, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime_mut()
+            .bcs_hash(&data)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+}
+
+/// The functions added to the system API made available to contracts in its
+/// [`SystemApiVersion::V2`](super::SystemApiVersion::V2).
+///
+/// The functions that the version has in common with the previous one are the ones of
+/// [`ContractSystemApi`], made available under the name of the version when a module
+/// compiled against it is instantiated.
+#[derive(Default)]
+pub struct ContractSystemApiV2<Caller>(PhantomData<Caller>);
+
+#[wit_export(package = "linera:app", interface = "contract-system-api@2.0.0")]
+impl<Caller, Runtime> ContractSystemApiV2<Caller>
+where
+    Caller: Instance<UserData = SystemApiData<Runtime>>,
+    Runtime: ContractRuntime + 'static,
+{
+    /// Returns the balances of all accounts on the chain.
+    fn read_owner_balances(
+        caller: &mut Caller,
+    ) -> Result<Vec<(AccountOwner, Amount)>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_owner_balances()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Returns the owners of accounts on this chain.
+    fn read_balance_owners(caller: &mut Caller) -> Result<Vec<AccountOwner>, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_balance_owners()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
 }
 
 /// An implementation of the system API made available to services.
@@ -482,6 +748,16 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Returns the context of the current query, with the state of the chain it is
+    /// executed against.
+    fn get_query_context(caller: &mut Caller) -> Result<QueryContext, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .query_context()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Returns the ID of the current application.
     fn get_application_id(caller: &mut Caller) -> Result<ApplicationId, RuntimeError> {
         caller
@@ -559,6 +835,15 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    fn read_own_state_size(caller: &mut Caller) -> Result<u64, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .read_own_state_size()
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Schedules an operation to be included in the block being built by this query.
     fn schedule_operation(caller: &mut Caller, operation: Vec<u8>) -> Result<(), RuntimeError> {
         caller
@@ -656,6 +941,13 @@ where
         }
         Ok(())
     }
+
+    /// Records an error of the service, so that the current query fails with an
+    /// [`ExecutionError::UserError`] once it returns.
+    fn report_error(caller: &mut Caller, message: String) -> Result<(), RuntimeError> {
+        caller.user_data_mut().error = Some(ExecutionError::UserError(message));
+        Ok(())
+    }
 }
 
 /// An implementation of the system API used to access the view storage for both contracts and
@@ -816,6 +1108,63 @@ where
         WriteBatch::write_batch(&mut caller.user_data_mut().runtime, Batch { operations })
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
+
+    /// Reads the value under `key` in the scratch space of the application, which is not part
+    /// of the state hash and may have been wiped. Only services can read it.
+    fn scratch_read(caller: &mut Caller, key: Vec<u8>) -> Result<Option<Vec<u8>>, RuntimeError> {
+        WriteBatch::read_scratch_value_bytes(&mut caller.user_data_mut().runtime, key)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Writes a batch of `operations` to the scratch space of the application.
+    fn scratch_write_batch(
+        caller: &mut Caller,
+        operations: Vec<WriteOperation>,
+    ) -> Result<(), RuntimeError> {
+        WriteBatch::write_scratch_batch(&mut caller.user_data_mut().runtime, Batch { operations })
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+}
+
+/// An implementation of the functions only available to contracts running in the test harness
+/// of the SDK, to measure the execution time of their code.
+#[derive(Default)]
+pub struct TestSystemApi<Caller>(PhantomData<Caller>);
+
+#[linera_witty::wit_export(package = "linera:app")]
+impl<Caller, Runtime> TestSystemApi<Caller>
+where
+    Caller: Instance<UserData = SystemApiData<Runtime>>,
+    Runtime: ContractRuntime + 'static,
+{
+    /// Returns the number of nanoseconds elapsed on a monotonic clock of the host.
+    fn monotonic_nanos(_caller: &mut Caller) -> Result<u64, RuntimeError> {
+        Ok(benchmark::monotonic_nanos())
+    }
+
+    /// Records that the code measured under `label` took `nanos` nanoseconds to execute.
+    fn record_benchmark(
+        caller: &mut Caller,
+        label: String,
+        nanos: u64,
+    ) -> Result<(), RuntimeError> {
+        let mut data = caller.user_data_mut();
+        let runtime = data.runtime_mut();
+        let recorder = runtime.benchmark_recorder().ok_or_else(|| {
+            RuntimeError::Custom(anyhow::anyhow!(
+                "Benchmarks are only available in the test harness"
+            ))
+        })?;
+        let application_id = runtime
+            .application_id()
+            .map_err(|error| RuntimeError::Custom(error.into()))?;
+        recorder.record(BenchmarkMeasurement {
+            application_id,
+            label,
+            duration: Duration::from_nanos(nanos),
+        });
+        Ok(())
+    }
 }
 
 // TODO(#1977): Remove once the WIT interface does not include `write-batch` in the service system
@@ -825,16 +1174,42 @@ where
 pub trait WriteBatch {
     /// Writes a [`Batch`] of operations to storage.
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError>;
+
+    /// Writes a [`Batch`] of operations to the scratch space of the application.
+    fn write_scratch_batch(&mut self, batch: Batch) -> Result<(), ExecutionError>;
+
+    /// Reads the value under `key` in the scratch space of the application.
+    fn read_scratch_value_bytes(&mut self, key: Vec<u8>)
+        -> Result<Option<Vec<u8>>, ExecutionError>;
 }
 
 impl WriteBatch for ContractSyncRuntimeHandle {
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
         ContractRuntime::write_batch(self, batch)
     }
+
+    fn write_scratch_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
+        ContractRuntime::write_scratch_batch(self, batch)
+    }
+
+    fn read_scratch_value_bytes(&mut self, _: Vec<u8>) -> Result<Option<Vec<u8>>, ExecutionError> {
+        Err(ExecutionError::ContractScratchReadAttempt)
+    }
 }
 
 impl WriteBatch for ServiceSyncRuntimeHandle {
     fn write_batch(&mut self, _: Batch) -> Result<(), ExecutionError> {
         Err(ExecutionError::ServiceWriteAttempt)
     }
+
+    fn write_scratch_batch(&mut self, _: Batch) -> Result<(), ExecutionError> {
+        Err(ExecutionError::ServiceWriteAttempt)
+    }
+
+    fn read_scratch_value_bytes(
+        &mut self,
+        key: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, ExecutionError> {
+        ServiceRuntime::read_scratch_value_bytes(self, key)
+    }
 }
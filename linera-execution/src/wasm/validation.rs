@@ -0,0 +1,396 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local validation of application bytecodes, so that invalid modules can be rejected before
+//! they are published, and checks of the imports of modules before they are instantiated.
+
+use std::{collections::BTreeSet, fmt};
+
+use anyhow::{anyhow, bail};
+use linera_base::{abi_descriptor::AbiDescriptor, data_types::Bytecode};
+use wasmparser::{ExternalKind, Parser, Payload, TypeRef, Validator};
+
+use super::{SystemApiVersion, WasmExecutionError};
+
+/// The functions that a contract module must export.
+const CONTRACT_ENTRYPOINTS: &[&str] = &[
+    "linera:app/contract-entrypoints#instantiate",
+    "linera:app/contract-entrypoints#execute-operation",
+    "linera:app/contract-entrypoints#execute-message",
+    "linera:app/contract-entrypoints#finalize",
+];
+
+/// The functions that a service module must export.
+const SERVICE_ENTRYPOINTS: &[&str] = &["linera:app/service-entrypoints#handle-query"];
+
+/// The interface from which contract modules may import functions only when running in the
+/// test harness of the SDK.
+pub(crate) const TEST_IMPORT_NAMESPACE: &str = "linera:app/test-system-api";
+
+/// Returns the interfaces from which a contract module compiled against the `version` of the
+/// system interfaces may import functions, including the [`TEST_IMPORT_NAMESPACE`] if the host
+/// provides it.
+pub(crate) fn contract_import_namespaces(
+    version: SystemApiVersion,
+    with_test_system_api: bool,
+) -> Vec<String> {
+    let mut namespaces = vec![
+        version.namespace("contract-system-api"),
+        version.namespace("view-system-api"),
+    ];
+    if with_test_system_api {
+        namespaces.push(TEST_IMPORT_NAMESPACE.to_owned());
+    }
+    namespaces
+}
+
+/// Returns the interfaces from which a service module compiled against the `version` of the
+/// system interfaces may import functions.
+pub(crate) fn service_import_namespaces(version: SystemApiVersion) -> Vec<String> {
+    vec![
+        version.namespace("service-system-api"),
+        version.namespace("view-system-api"),
+    ]
+}
+
+/// An import of a Wasm module that the host refuses to provide.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForbiddenImport {
+    /// The namespace of the import.
+    pub module: String,
+    /// The name of the import in its namespace.
+    pub name: String,
+    /// Why the import is refused.
+    pub reason: ForbiddenImportReason,
+}
+
+/// The reason why an import of a Wasm module is refused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ForbiddenImportReason {
+    /// The import is not from one of the system interfaces.
+    ForeignNamespace,
+    /// The system interface has no function with that name.
+    UnknownFunction,
+    /// The type of the import doesn't match the one of the system function.
+    WrongType,
+}
+
+impl fmt::Display for ForbiddenImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            ForbiddenImportReason::ForeignNamespace => "not a system interface",
+            ForbiddenImportReason::UnknownFunction => "unknown system function",
+            ForbiddenImportReason::WrongType => "wrong type",
+        };
+        write!(f, "`{}::{}` ({reason})", self.module, self.name)
+    }
+}
+
+/// How the host provides a function imported by a module.
+pub(crate) enum HostFunction {
+    /// The host has no function with that name.
+    Missing,
+    /// The host function has a different type than the import.
+    WrongType,
+    /// The host function can be used for the import.
+    Matching,
+}
+
+/// Checks that the `contract` and `service` bytecodes are valid WebAssembly modules that
+/// export the entrypoints of a Linera application and only import functions of a single
+/// supported version of the system interfaces, and that the ABI descriptor of the contract, if
+/// any, is well-formed.
+pub fn validate_bytecode(
+    contract: &Bytecode,
+    service: &Bytecode,
+) -> Result<(), WasmExecutionError> {
+    let contract_imports = validate_module(contract.as_ref(), CONTRACT_ENTRYPOINTS)
+        .map_err(WasmExecutionError::LoadContractModule)?;
+    AbiDescriptor::from_wasm(contract.as_ref())
+        .map_err(|error| WasmExecutionError::LoadContractModule(error.into()))?;
+    let service_imports = validate_module(service.as_ref(), SERVICE_ENTRYPOINTS)
+        .map_err(WasmExecutionError::LoadServiceModule)?;
+    let contract_version = SystemApiVersion::of_imports(
+        contract_imports
+            .iter()
+            .map(|(module, _, _)| module.as_str()),
+    )?;
+    let service_version =
+        SystemApiVersion::of_imports(service_imports.iter().map(|(module, _, _)| module.as_str()))?;
+    check_imports(
+        &contract_import_namespaces(contract_version, false),
+        contract_imports,
+    )?;
+    check_imports(&service_import_namespaces(service_version), service_imports)?;
+    Ok(())
+}
+
+/// Checks that a module only imports functions of the system interfaces in `namespaces`,
+/// provided by the host with the expected types.
+///
+/// All the offending imports are reported in a single [`WasmExecutionError::ForbiddenImports`].
+pub(crate) fn check_imports(
+    namespaces: &[String],
+    imports: impl IntoIterator<Item = (String, String, HostFunction)>,
+) -> Result<(), WasmExecutionError> {
+    let forbidden_imports = imports
+        .into_iter()
+        .filter_map(|(module, name, host_function)| {
+            let reason = if !namespaces.contains(&module) {
+                ForbiddenImportReason::ForeignNamespace
+            } else {
+                match host_function {
+                    HostFunction::Missing => ForbiddenImportReason::UnknownFunction,
+                    HostFunction::WrongType => ForbiddenImportReason::WrongType,
+                    HostFunction::Matching => return None,
+                }
+            };
+            Some(ForbiddenImport {
+                module,
+                name,
+                reason,
+            })
+        })
+        .collect::<Vec<_>>();
+    if !forbidden_imports.is_empty() {
+        return Err(WasmExecutionError::ForbiddenImports(forbidden_imports));
+    }
+    Ok(())
+}
+
+/// Checks that `bytecode` is a valid WebAssembly module that exports all the functions in
+/// `entrypoints`, and returns its imports.
+fn validate_module(
+    bytecode: &[u8],
+    entrypoints: &[&str],
+) -> anyhow::Result<Vec<(String, String, HostFunction)>> {
+    Validator::new()
+        .validate_all(bytecode)
+        .map_err(|error| anyhow!("invalid Wasm module: {error}"))?;
+
+    let mut exported_functions = BTreeSet::new();
+    let mut imports = Vec::new();
+    for payload in Parser::default().parse_all(bytecode) {
+        match payload? {
+            Payload::ExportSection(exports) => {
+                for export in exports {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        exported_functions.insert(export.name);
+                    }
+                }
+            }
+            Payload::ImportSection(section) => {
+                for import in section {
+                    let import = import?;
+                    // The system interfaces only provide functions. The types of the functions
+                    // are checked against the ones of a Wasm runtime when instantiating.
+                    let host_function = match import.ty {
+                        TypeRef::Func(_) => HostFunction::Matching,
+                        _ => HostFunction::WrongType,
+                    };
+                    imports.push((
+                        import.module.to_owned(),
+                        import.name.to_owned(),
+                        host_function,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let missing = entrypoints
+        .iter()
+        .filter(|entrypoint| !exported_functions.contains(*entrypoint))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        bail!("missing exported entrypoints: {missing:?}");
+    }
+    Ok(imports)
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::data_types::Bytecode;
+
+    use super::{validate_bytecode, ForbiddenImport, ForbiddenImportReason};
+    use crate::WasmExecutionError;
+
+    fn module_exporting(names: &[&str]) -> Bytecode {
+        module_importing_and_exporting("", names)
+    }
+
+    fn module_importing_and_exporting(imports: &str, names: &[&str]) -> Bytecode {
+        let exports = names
+            .iter()
+            .map(|name| format!("(func (export \"{name}\"))"))
+            .collect::<String>();
+        let module = format!("(module {imports} {exports})");
+        Bytecode::new(::wasmer::wat2wasm(module.as_bytes()).unwrap().into_owned())
+    }
+
+    #[test]
+    fn accepts_modules_with_all_entrypoints() {
+        let contract = module_exporting(super::CONTRACT_ENTRYPOINTS);
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        validate_bytecode(&contract, &service).unwrap();
+    }
+
+    #[test]
+    fn rejects_contract_with_missing_entrypoint() {
+        let contract = module_exporting(&super::CONTRACT_ENTRYPOINTS[1..]);
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(error, WasmExecutionError::LoadContractModule(_)));
+        assert!(error.to_string().contains("#instantiate"));
+    }
+
+    #[test]
+    fn rejects_contract_with_invalid_abi_descriptor() {
+        let mut contract = module_exporting(super::CONTRACT_ENTRYPOINTS)
+            .as_ref()
+            .to_vec();
+        // A custom section named `linera:abi` with an unsupported version.
+        contract.extend([0, 12, 10]);
+        contract.extend(b"linera:abi");
+        contract.push(0xff);
+        let contract = Bytecode::new(contract);
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(error, WasmExecutionError::LoadContractModule(_)));
+        assert!(error.to_string().contains("Invalid ABI descriptor"));
+    }
+
+    #[test]
+    fn rejects_invalid_service_module() {
+        let contract = module_exporting(super::CONTRACT_ENTRYPOINTS);
+        let service = Bytecode::new(b"not a Wasm module".to_vec());
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(error, WasmExecutionError::LoadServiceModule(_)));
+    }
+
+    #[test]
+    fn accepts_imports_from_system_interfaces() {
+        let contract = module_importing_and_exporting(
+            r#"(import "linera:app/contract-system-api" "get-chain-id" (func (param i32)))
+               (import "linera:app/view-system-api" "read-value-bytes" (func (param i32 i32 i32)))"#,
+            super::CONTRACT_ENTRYPOINTS,
+        );
+        let service = module_importing_and_exporting(
+            r#"(import "linera:app/service-system-api" "get-chain-id" (func (param i32)))"#,
+            super::SERVICE_ENTRYPOINTS,
+        );
+
+        validate_bytecode(&contract, &service).unwrap();
+    }
+
+    #[test]
+    fn rejects_all_imports_outside_of_system_interfaces() {
+        let contract = module_importing_and_exporting(
+            r#"(import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+               (import "linera:app/contract-system-api" "get-chain-id" (func (param i32)))
+               (import "linera:app/contract-system-api" "memory" (memory 1))
+               (import "env" "random" (func (result i64)))"#,
+            super::CONTRACT_ENTRYPOINTS,
+        );
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        let WasmExecutionError::ForbiddenImports(imports) = &error else {
+            panic!("Unexpected error: {error}");
+        };
+        let forbidden_import = |module: &str, name: &str, reason| ForbiddenImport {
+            module: module.to_owned(),
+            name: name.to_owned(),
+            reason,
+        };
+        assert_eq!(
+            *imports,
+            [
+                forbidden_import(
+                    "wasi_snapshot_preview1",
+                    "fd_write",
+                    ForbiddenImportReason::ForeignNamespace
+                ),
+                forbidden_import(
+                    "linera:app/contract-system-api",
+                    "memory",
+                    ForbiddenImportReason::WrongType
+                ),
+                forbidden_import("env", "random", ForbiddenImportReason::ForeignNamespace),
+            ]
+        );
+        assert!(error.to_string().contains("`env::random`"));
+    }
+
+    #[test]
+    fn accepts_imports_from_later_versions_of_system_interfaces() {
+        let contract = module_importing_and_exporting(
+            r#"(import "linera:app/contract-system-api@2.0.0" "read-balance-owners" (func (param i32)))
+               (import "linera:app/view-system-api@2.0.0" "read-value-bytes" (func (param i32 i32 i32)))"#,
+            super::CONTRACT_ENTRYPOINTS,
+        );
+        let service = module_importing_and_exporting(
+            r#"(import "linera:app/service-system-api" "get-chain-id" (func (param i32)))"#,
+            super::SERVICE_ENTRYPOINTS,
+        );
+
+        validate_bytecode(&contract, &service).unwrap();
+    }
+
+    #[test]
+    fn rejects_contract_importing_unknown_version_of_system_interfaces() {
+        let contract = module_importing_and_exporting(
+            r#"(import "linera:app/contract-system-api@9.0.0" "get-chain-id" (func (param i32)))"#,
+            super::CONTRACT_ENTRYPOINTS,
+        );
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(
+            error,
+            WasmExecutionError::UnsupportedSystemApiVersion(namespace)
+                if namespace == "linera:app/contract-system-api@9.0.0"
+        ));
+    }
+
+    #[test]
+    fn rejects_contract_importing_test_system_api() {
+        let contract = module_importing_and_exporting(
+            r#"(import "linera:app/test-system-api" "monotonic-nanos" (func (result i64)))"#,
+            super::CONTRACT_ENTRYPOINTS,
+        );
+        let service = module_exporting(super::SERVICE_ENTRYPOINTS);
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(
+            error,
+            WasmExecutionError::ForbiddenImports(imports)
+                if imports.len() == 1
+                    && imports[0].module == super::TEST_IMPORT_NAMESPACE
+                    && imports[0].reason == ForbiddenImportReason::ForeignNamespace
+        ));
+    }
+
+    #[test]
+    fn rejects_service_importing_contract_system_api() {
+        let contract = module_exporting(super::CONTRACT_ENTRYPOINTS);
+        let service = module_importing_and_exporting(
+            r#"(import "linera:app/contract-system-api" "transfer" (func (param i32)))"#,
+            super::SERVICE_ENTRYPOINTS,
+        );
+
+        let error = validate_bytecode(&contract, &service).unwrap_err();
+        assert!(matches!(
+            error,
+            WasmExecutionError::ForbiddenImports(imports)
+                if imports.len() == 1
+                    && imports[0].reason == ForbiddenImportReason::ForeignNamespace
+        ));
+    }
+}
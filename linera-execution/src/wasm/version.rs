@@ -0,0 +1,152 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versions of the system interfaces from which applications import functions.
+
+use std::collections::BTreeSet;
+
+use super::WasmExecutionError;
+
+/// The WIT package of the system interfaces.
+const PACKAGE: &str = "linera:app";
+
+/// The system interfaces that are versioned. The entrypoints exported by the applications and
+/// the interface of the test harness are the same in all the versions.
+const VERSIONED_INTERFACES: &[&str] = &[
+    "contract-system-api",
+    "service-system-api",
+    "view-system-api",
+];
+
+/// A version of the system interfaces provided to applications.
+///
+/// Modules select the version they were compiled against through the names of the interfaces
+/// they import functions from: the interfaces of the first version have no suffix, as in
+/// `linera:app/contract-system-api`, while later versions are suffixed with their number, as in
+/// `linera:app/contract-system-api@2.0.0`. The host serves all the versions side by side, so
+/// that the published applications keep running when the interfaces are extended.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SystemApiVersion {
+    /// The interfaces as they were first published.
+    #[default]
+    V1,
+    /// Adds `read-owner-balances` and `read-balance-owners` to the contract system API, and
+    /// retires its `send-message` function in favor of `send-messages`.
+    V2,
+}
+
+impl SystemApiVersion {
+    /// Returns the suffix of the names of the interfaces in this version.
+    fn suffix(self) -> &'static str {
+        match self {
+            SystemApiVersion::V1 => "",
+            SystemApiVersion::V2 => "@2.0.0",
+        }
+    }
+
+    /// Returns the name under which the system `interface` (e.g. `contract-system-api`) is
+    /// imported in this version.
+    pub fn namespace(self, interface: &str) -> String {
+        format!("{PACKAGE}/{interface}{}", self.suffix())
+    }
+
+    /// Returns the functions of the first version of the contract system API that this version
+    /// no longer provides.
+    pub(crate) fn retired_contract_functions(self) -> &'static [&'static str] {
+        match self {
+            SystemApiVersion::V1 => &[],
+            SystemApiVersion::V2 => &["send-message"],
+        }
+    }
+
+    /// Detects the version of the system interfaces that a module importing from the
+    /// `namespaces` was compiled against.
+    ///
+    /// Namespaces that aren't system interfaces are ignored, and modules without any import
+    /// from the system interfaces are considered to use the first version. Modules importing
+    /// from an unknown version, e.g. one released after this host was built, or from several
+    /// versions at once are refused.
+    pub fn of_imports<'a>(
+        namespaces: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, WasmExecutionError> {
+        let mut versions = BTreeSet::new();
+        for namespace in namespaces {
+            let Some(interface) = namespace
+                .strip_prefix(PACKAGE)
+                .and_then(|rest| rest.strip_prefix('/'))
+            else {
+                continue;
+            };
+            let (name, suffix) = match interface.find('@') {
+                Some(position) => interface.split_at(position),
+                None => (interface, ""),
+            };
+            if !VERSIONED_INTERFACES.contains(&name) {
+                continue;
+            }
+            let version = [SystemApiVersion::V1, SystemApiVersion::V2]
+                .into_iter()
+                .find(|version| version.suffix() == suffix)
+                .ok_or_else(|| {
+                    WasmExecutionError::UnsupportedSystemApiVersion(namespace.to_owned())
+                })?;
+            versions.insert(version);
+        }
+        let mut versions = versions.into_iter();
+        match (versions.next(), versions.next()) {
+            (None, _) => Ok(SystemApiVersion::default()),
+            (Some(version), None) => Ok(version),
+            (Some(_), Some(_)) => Err(WasmExecutionError::MixedSystemApiVersions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::SystemApiVersion;
+    use crate::WasmExecutionError;
+
+    #[test]
+    fn detects_version_from_system_interfaces_only() {
+        assert_eq!(
+            SystemApiVersion::of_imports(["env", "linera:app/contract-entrypoints"]).unwrap(),
+            SystemApiVersion::V1
+        );
+        assert_eq!(
+            SystemApiVersion::of_imports([
+                "linera:app/contract-system-api",
+                "linera:app/test-system-api",
+                "linera:app/view-system-api",
+            ])
+            .unwrap(),
+            SystemApiVersion::V1
+        );
+        assert_eq!(
+            SystemApiVersion::of_imports([
+                "linera:app/contract-system-api@2.0.0",
+                "linera:app/test-system-api",
+                "linera:app/view-system-api@2.0.0",
+            ])
+            .unwrap(),
+            SystemApiVersion::V2
+        );
+    }
+
+    #[test]
+    fn refuses_unknown_and_mixed_versions() {
+        assert_matches!(
+            SystemApiVersion::of_imports(["linera:app/service-system-api@3.0.0"]),
+            Err(WasmExecutionError::UnsupportedSystemApiVersion(namespace))
+                if namespace == "linera:app/service-system-api@3.0.0"
+        );
+        assert_matches!(
+            SystemApiVersion::of_imports([
+                "linera:app/contract-system-api",
+                "linera:app/view-system-api@2.0.0",
+            ]),
+            Err(WasmExecutionError::MixedSystemApiVersions)
+        );
+    }
+}
@@ -8,15 +8,21 @@ use std::{marker::Unpin, sync::LazyLock};
 use linera_base::data_types::Bytecode;
 use linera_witty::{
     wasmer::{EntrypointInstance, InstanceBuilder},
-    ExportTo,
+    ExportTo, Instance as _,
 };
 use tokio::sync::Mutex;
 use wasm_instrument::{gas_metering, parity_wasm};
 
 use super::{
     module_cache::ModuleCache,
-    system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
-    ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
+    system_api::{
+        ContractSystemApi, ContractSystemApiV2, ServiceSystemApi, SystemApiData, TestSystemApi,
+        ViewSystemApi, WriteBatch,
+    },
+    validation::{
+        check_imports, contract_import_namespaces, service_import_namespaces, HostFunction,
+    },
+    ContractEntrypoints, ServiceEntrypoints, SystemApiVersion, WasmExecutionError,
 };
 use crate::{
     wasm::{WasmContractModule, WasmServiceModule},
@@ -77,14 +83,35 @@ where
     pub fn prepare(
         contract_engine: wasmer::Engine,
         contract_module: &wasmer::Module,
-        runtime: Runtime,
+        mut runtime: Runtime,
     ) -> Result<Self, WasmExecutionError> {
+        let with_test_system_api = runtime.benchmark_recorder().is_some();
+        let version = system_api_version(contract_module)?;
         let system_api_data = SystemApiData::new(runtime);
         let mut instance_builder = InstanceBuilder::new(contract_engine, system_api_data);
 
         ContractSystemApi::export_to(&mut instance_builder)?;
         ViewSystemApi::export_to(&mut instance_builder)?;
+        alias_interface(
+            &mut instance_builder,
+            "contract-system-api",
+            version,
+            version.retired_contract_functions(),
+        );
+        alias_interface(&mut instance_builder, "view-system-api", version, &[]);
+        match version {
+            SystemApiVersion::V1 => {}
+            SystemApiVersion::V2 => ContractSystemApiV2::export_to(&mut instance_builder)?,
+        }
+        if with_test_system_api {
+            TestSystemApi::export_to(&mut instance_builder)?;
+        }
 
+        check_module_imports(
+            &contract_import_namespaces(version, with_test_system_api),
+            contract_module,
+            &instance_builder,
+        )?;
         let instance = instance_builder.instantiate(contract_module)?;
 
         Ok(Self { instance })
@@ -102,6 +129,11 @@ impl WasmServiceModule {
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmer { module })
     }
+
+    /// Removes the Wasmer module compiled from `service_bytecode` from the cache.
+    pub(super) async fn evict_from_wasmer_cache(service_bytecode: &Bytecode) {
+        SERVICE_CACHE.lock().await.remove(service_bytecode);
+    }
 }
 
 impl<Runtime> WasmerServiceInstance<Runtime>
@@ -113,18 +145,86 @@ where
         service_module: &wasmer::Module,
         runtime: Runtime,
     ) -> Result<Self, WasmExecutionError> {
+        let version = system_api_version(service_module)?;
         let system_api_data = SystemApiData::new(runtime);
         let mut instance_builder = InstanceBuilder::new(SERVICE_ENGINE.clone(), system_api_data);
 
         ServiceSystemApi::export_to(&mut instance_builder)?;
         ViewSystemApi::export_to(&mut instance_builder)?;
+        alias_interface(&mut instance_builder, "service-system-api", version, &[]);
+        alias_interface(&mut instance_builder, "view-system-api", version, &[]);
 
+        check_module_imports(
+            &service_import_namespaces(version),
+            service_module,
+            &instance_builder,
+        )?;
         let instance = instance_builder.instantiate(service_module)?;
 
         Ok(Self { instance })
     }
 }
 
+/// Detects the version of the system interfaces that the `module` was compiled against.
+fn system_api_version(module: &wasmer::Module) -> Result<SystemApiVersion, WasmExecutionError> {
+    let namespaces = module
+        .imports()
+        .map(|import| import.module().to_owned())
+        .collect::<Vec<_>>();
+    SystemApiVersion::of_imports(namespaces.iter().map(String::as_str))
+}
+
+/// Makes the functions of the first version of the system `interface` exported to the
+/// `instance_builder` also available under the name of the interface in `version`, except for
+/// the `retired` ones.
+fn alias_interface<UserData: 'static>(
+    instance_builder: &mut InstanceBuilder<UserData>,
+    interface: &str,
+    version: SystemApiVersion,
+    retired: &[&str],
+) {
+    if version == SystemApiVersion::V1 {
+        return;
+    }
+    let namespace = version.namespace(interface);
+    let Some(exports) = instance_builder
+        .imports()
+        .get_namespace_exports(&SystemApiVersion::V1.namespace(interface))
+    else {
+        return;
+    };
+    for (name, function) in exports.iter() {
+        if !retired.contains(&name.as_str()) {
+            instance_builder.define(&namespace, name, function.clone());
+        }
+    }
+}
+
+/// Checks that the `module` only imports functions from the system interfaces in
+/// `namespaces`, with the types of the functions exported to the `instance_builder`.
+fn check_module_imports<UserData: 'static>(
+    namespaces: &[String],
+    module: &wasmer::Module,
+    instance_builder: &InstanceBuilder<UserData>,
+) -> Result<(), WasmExecutionError> {
+    let imports = module.imports().map(|import| {
+        let host_function = match instance_builder
+            .imports()
+            .get_export(import.module(), import.name())
+        {
+            None => HostFunction::Missing,
+            Some(export) if export.ty(instance_builder) == *import.ty() => HostFunction::Matching,
+            Some(_) => HostFunction::WrongType,
+        };
+        (
+            import.module().to_owned(),
+            import.name().to_owned(),
+            host_function,
+        )
+    });
+    check_imports(namespaces, imports)
+}
+
 impl<Runtime> crate::UserContract for WasmerContractInstance<Runtime>
 where
     Runtime: ContractRuntime + Unpin + 'static,
@@ -134,10 +234,8 @@ where
         _context: OperationContext,
         argument: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .instantiate(argument)
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).instantiate(argument);
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn execute_operation(
@@ -145,9 +243,8 @@ where
         _context: OperationContext,
         operation: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ContractEntrypoints::new(&mut self.instance)
-            .execute_operation(operation)
-            .map_err(WasmExecutionError::from)?)
+        let result = ContractEntrypoints::new(&mut self.instance).execute_operation(operation);
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn execute_message(
@@ -155,17 +252,13 @@ where
         _context: MessageContext,
         message: Vec<u8>,
     ) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .execute_message(message)
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).execute_message(message);
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn finalize(&mut self, _context: FinalizeContext) -> Result<(), ExecutionError> {
-        ContractEntrypoints::new(&mut self.instance)
-            .finalize()
-            .map_err(WasmExecutionError::from)?;
-        Ok(())
+        let result = ContractEntrypoints::new(&mut self.instance).finalize();
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 }
 
@@ -175,9 +268,8 @@ impl<Runtime: 'static> crate::UserService for WasmerServiceInstance<Runtime> {
         _context: QueryContext,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ServiceEntrypoints::new(&mut self.instance)
-            .handle_query(argument)
-            .map_err(WasmExecutionError::from)?)
+        let result = ServiceEntrypoints::new(&mut self.instance).handle_query(argument);
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 }
 
@@ -231,12 +323,29 @@ pub fn add_metering(bytecode: Bytecode) -> anyhow::Result<Bytecode> {
         }
     }
 
+    // Parsing the `name` section lets the instrumentation renumber the functions it names
+    // after inserting the import of `consume-fuel`, so that trap backtraces stay correct.
+    let module = parity_wasm::deserialize_buffer::<parity_wasm::elements::Module>(&bytecode.bytes)?
+        .parse_names()
+        .unwrap_or_else(|(_, module)| module);
+    // The fuel is consumed through the contract system API of the version the module imports
+    // from, so that the instrumented module doesn't mix versions. Modules importing from an
+    // unsupported version are refused when instantiated.
+    let version = SystemApiVersion::of_imports(
+        module
+            .import_section()
+            .into_iter()
+            .flat_map(|section| section.entries())
+            .map(|entry| entry.module()),
+    )
+    .unwrap_or_default();
+    let fuel_namespace = match version {
+        SystemApiVersion::V1 => "linera:app/contract-system-api",
+        SystemApiVersion::V2 => "linera:app/contract-system-api@2.0.0",
+    };
     let instrumented_module = gas_metering::inject(
-        parity_wasm::deserialize_buffer(&bytecode.bytes)?,
-        gas_metering::host_function::Injector::new(
-            "linera:app/contract-system-api",
-            "consume-fuel",
-        ),
+        module,
+        gas_metering::host_function::Injector::new(fuel_namespace, "consume-fuel"),
         &WasmtimeRules,
     )
     .map_err(|_| anyhow::anyhow!("failed to instrument module"))?;
@@ -8,12 +8,18 @@ use std::sync::LazyLock;
 use linera_base::data_types::Bytecode;
 use linera_witty::{wasmtime::EntrypointInstance, ExportTo, Instance};
 use tokio::sync::Mutex;
-use wasmtime::{AsContextMut, Config, Engine, Linker, Module, Store};
+use wasmtime::{AsContextMut, Config, Engine, Extern, ExternType, Linker, Module, Store};
 
 use super::{
     module_cache::ModuleCache,
-    system_api::{ContractSystemApi, ServiceSystemApi, SystemApiData, ViewSystemApi, WriteBatch},
-    ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
+    system_api::{
+        ContractSystemApi, ContractSystemApiV2, ServiceSystemApi, SystemApiData, TestSystemApi,
+        ViewSystemApi, WriteBatch,
+    },
+    validation::{
+        check_imports, contract_import_namespaces, service_import_namespaces, HostFunction,
+    },
+    ContractEntrypoints, ServiceEntrypoints, SystemApiVersion, WasmExecutionError,
 };
 use crate::{
     wasm::{WasmContractModule, WasmServiceModule},
@@ -112,14 +118,43 @@ where
     Runtime: ContractRuntime + WriteBatch + 'static,
 {
     /// Prepares a runtime instance to call into the Wasm contract.
-    pub fn prepare(contract_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
+    pub fn prepare(
+        contract_module: &Module,
+        mut runtime: Runtime,
+    ) -> Result<Self, WasmExecutionError> {
         let mut linker = Linker::new(&CONTRACT_ENGINE);
+        let with_test_system_api = runtime.benchmark_recorder().is_some();
+        let version =
+            SystemApiVersion::of_imports(contract_module.imports().map(|import| import.module()))?;
 
         ContractSystemApi::export_to(&mut linker)?;
         ViewSystemApi::export_to(&mut linker)?;
+        match version {
+            SystemApiVersion::V1 => {}
+            SystemApiVersion::V2 => ContractSystemApiV2::export_to(&mut linker)?,
+        }
+        if with_test_system_api {
+            TestSystemApi::export_to(&mut linker)?;
+        }
 
         let user_data = SystemApiData::new(runtime);
         let mut store = Store::new(&CONTRACT_ENGINE, user_data);
+        alias_interface(
+            &mut linker,
+            &mut store,
+            "contract-system-api",
+            version,
+            version.retired_contract_functions(),
+        )
+        .map_err(WasmExecutionError::LoadContractModule)?;
+        alias_interface(&mut linker, &mut store, "view-system-api", version, &[])
+            .map_err(WasmExecutionError::LoadContractModule)?;
+        check_module_imports(
+            &contract_import_namespaces(version, with_test_system_api),
+            contract_module,
+            &linker,
+            &mut store,
+        )?;
         let instance = linker
             .instantiate(&mut store, contract_module)
             .map_err(WasmExecutionError::LoadContractModule)?;
@@ -142,6 +177,11 @@ impl WasmServiceModule {
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmtime { module })
     }
+
+    /// Removes the Wasmtime module compiled from `service_bytecode` from the cache.
+    pub(super) async fn evict_from_wasmtime_cache(service_bytecode: &Bytecode) {
+        SERVICE_CACHE.lock().await.remove(service_bytecode);
+    }
 }
 
 impl<Runtime> WasmtimeServiceInstance<Runtime>
@@ -151,12 +191,24 @@ where
     /// Prepares a runtime instance to call into the Wasm service.
     pub fn prepare(service_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
         let mut linker = Linker::new(&SERVICE_ENGINE);
+        let version =
+            SystemApiVersion::of_imports(service_module.imports().map(|import| import.module()))?;
 
         ServiceSystemApi::export_to(&mut linker)?;
         ViewSystemApi::export_to(&mut linker)?;
 
         let user_data = SystemApiData::new(runtime);
         let mut store = Store::new(&SERVICE_ENGINE, user_data);
+        alias_interface(&mut linker, &mut store, "service-system-api", version, &[])
+            .map_err(WasmExecutionError::LoadServiceModule)?;
+        alias_interface(&mut linker, &mut store, "view-system-api", version, &[])
+            .map_err(WasmExecutionError::LoadServiceModule)?;
+        check_module_imports(
+            &service_import_namespaces(version),
+            service_module,
+            &linker,
+            &mut store,
+        )?;
         let instance = linker
             .instantiate(&mut store, service_module)
             .map_err(WasmExecutionError::LoadServiceModule)?;
@@ -167,6 +219,61 @@ where
     }
 }
 
+/// Makes the functions of the first version of the system `interface` defined in the `linker`
+/// also available under the name of the interface in `version`, except for the `retired` ones.
+fn alias_interface<UserData>(
+    linker: &mut Linker<UserData>,
+    store: &mut Store<UserData>,
+    interface: &str,
+    version: SystemApiVersion,
+    retired: &[&str],
+) -> anyhow::Result<()> {
+    if version == SystemApiVersion::V1 {
+        return Ok(());
+    }
+    let original = SystemApiVersion::V1.namespace(interface);
+    let namespace = version.namespace(interface);
+    let names = linker
+        .iter(&mut *store)
+        .filter(|(module, name, _)| *module == original && !retired.contains(name))
+        .map(|(_, name, _)| name.to_owned())
+        .collect::<Vec<_>>();
+    for name in names {
+        linker.alias(&original, &name, &namespace, &name)?;
+    }
+    Ok(())
+}
+
+/// Checks that the `module` only imports functions from the system interfaces in
+/// `namespaces`, with the types of the functions defined in the `linker`.
+fn check_module_imports<UserData>(
+    namespaces: &[String],
+    module: &Module,
+    linker: &Linker<UserData>,
+    store: &mut Store<UserData>,
+) -> Result<(), WasmExecutionError> {
+    let imports = module.imports().map(|import| {
+        let host_function = match (
+            linker.get(&mut *store, import.module(), import.name()),
+            import.ty(),
+        ) {
+            (None, _) => HostFunction::Missing,
+            (Some(Extern::Func(function)), ExternType::Func(expected))
+                if function.ty(&*store).matches(&expected) =>
+            {
+                HostFunction::Matching
+            }
+            (Some(_), _) => HostFunction::WrongType,
+        };
+        (
+            import.module().to_owned(),
+            import.name().to_owned(),
+            host_function,
+        )
+    });
+    check_imports(namespaces, imports)
+}
+
 impl<Runtime> crate::UserContract for WasmtimeContractInstance<Runtime>
 where
     Runtime: ContractRuntime + 'static,
@@ -179,8 +286,7 @@ where
         self.configure_initial_fuel()?;
         let result = ContractEntrypoints::new(&mut self.instance).instantiate(argument);
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn execute_operation(
@@ -191,7 +297,7 @@ where
         self.configure_initial_fuel()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_operation(operation);
         self.persist_remaining_fuel()?;
-        Ok(result.map_err(WasmExecutionError::from)?)
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn execute_message(
@@ -202,16 +308,14 @@ where
         self.configure_initial_fuel()?;
         let result = ContractEntrypoints::new(&mut self.instance).execute_message(message);
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 
     fn finalize(&mut self, _context: FinalizeContext) -> Result<(), ExecutionError> {
         self.configure_initial_fuel()?;
         let result = ContractEntrypoints::new(&mut self.instance).finalize();
         self.persist_remaining_fuel()?;
-        result.map_err(WasmExecutionError::from)?;
-        Ok(())
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 }
 
@@ -224,8 +328,7 @@ where
         _context: QueryContext,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
-        Ok(ServiceEntrypoints::new(&mut self.instance)
-            .handle_query(argument)
-            .map_err(WasmExecutionError::from)?)
+        let result = ServiceEntrypoints::new(&mut self.instance).handle_query(argument);
+        self.instance.user_data_mut().entrypoint_result(result)
     }
 }
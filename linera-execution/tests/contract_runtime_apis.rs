@@ -26,11 +26,17 @@ use linera_execution::{
         create_dummy_message_context, create_dummy_operation_context, test_accounts_strategy,
         ExpectedCall, RegisterMockApplication, SystemExecutionState,
     },
-    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, Message, MessageContext,
-    Operation, OperationContext, ResourceController, SystemExecutionError,
-    SystemExecutionStateView, TestExecutionRuntimeContext, TransactionTracker,
+    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig,
+    GuestInputAudit, Message, MessageContext, Operation, OperationContext, ResourceController,
+    SystemExecutionError, SystemExecutionStateView, TestExecutionRuntimeContext,
+    TransactionTracker,
+};
+use linera_views::{
+    access_tracking::{ViewAccessStats, ViewPath},
+    batch::Batch,
+    context::MemoryContext,
+    views::MIN_VIEW_TAG,
 };
-use linera_views::context::MemoryContext;
 use test_case::test_matrix;
 use test_strategy::proptest;
 
@@ -620,6 +626,148 @@ async fn test_read_balance_owners_system_api(
     .unwrap();
 }
 
+/// Tests that perturbing the result of a host call makes the audits of the inputs observed by
+/// the application diverge at that call.
+#[test_log::test(tokio::test)]
+async fn test_guest_input_audit_locates_divergence() -> anyhow::Result<()> {
+    let audit = run_audited_balance_read(Amount::ONE, true).await?;
+    let same_audit = run_audited_balance_read(Amount::ONE, true).await?;
+    let perturbed_audit = run_audited_balance_read(Amount::from_tokens(2), true).await?;
+
+    let audit = audit.expect("Inputs should be audited");
+    let perturbed_audit = perturbed_audit.expect("Inputs should be audited");
+    // The operation, the chain ID, the chain balance and the system time.
+    assert_eq!(audit.hashes().len(), 4);
+    assert_eq!(same_audit.as_ref(), Some(&audit));
+    assert_eq!(audit.first_divergence(&perturbed_audit), Some(2));
+    assert_eq!(audit.hashes()[..2], perturbed_audit.hashes()[..2]);
+    assert_ne!(audit.hashes()[3], perturbed_audit.hashes()[3]);
+
+    assert_eq!(run_audited_balance_read(Amount::ONE, false).await?, None);
+    Ok(())
+}
+
+/// Tests that the accesses of an application to its views are grouped by sub-view, when
+/// enabled.
+#[test_log::test(tokio::test)]
+async fn test_view_access_tracking() -> anyhow::Result<()> {
+    let mut view = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..SystemExecutionState::default()
+    }
+    .into_view_with(
+        ChainId::root(0),
+        ExecutionRuntimeConfig {
+            track_view_accesses: true,
+            ..ExecutionRuntimeConfig::default()
+        },
+    )
+    .await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            // An entry of the third field of the root view.
+            let entry_key = vec![MIN_VIEW_TAG, 2, 0, 0, 0, 42];
+            assert_eq!(runtime.read_value_bytes(entry_key)?, None);
+            // The first field of the root view.
+            let register_key = vec![MIN_VIEW_TAG, 0, 0, 0, 0];
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(register_key, vec![1, 2, 3]);
+            runtime.write_batch(batch)?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    let reports = tracker
+        .view_access_reports()
+        .expect("View accesses should be tracked");
+    let report = &reports[&application_id];
+    assert_eq!(
+        report.paths().collect::<Vec<_>>(),
+        [&ViewPath::from([0]), &ViewPath::from([2])]
+    );
+    assert_eq!(
+        report.get(&ViewPath::from([2])),
+        Some(&ViewAccessStats {
+            reads: 1,
+            ..ViewAccessStats::default()
+        })
+    );
+    assert_eq!(
+        report.get(&ViewPath::from([0])),
+        Some(&ViewAccessStats {
+            writes: 1,
+            bytes_written: 8,
+            ..ViewAccessStats::default()
+        })
+    );
+    Ok(())
+}
+
+/// Executes an operation reading the `chain_balance` through the system API, and returns the
+/// audit of the inputs observed by the application, if `audit_guest_inputs` is set.
+async fn run_audited_balance_read(
+    chain_balance: Amount,
+    audit_guest_inputs: bool,
+) -> anyhow::Result<Option<GuestInputAudit>> {
+    let mut view = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        balance: chain_balance,
+        ..SystemExecutionState::default()
+    }
+    .into_view_with(
+        ChainId::root(0),
+        ExecutionRuntimeConfig {
+            audit_guest_inputs,
+            ..ExecutionRuntimeConfig::default()
+        },
+    )
+    .await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.chain_id()?;
+            runtime.read_chain_balance()?;
+            runtime.read_system_timestamp()?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![1, 2, 3],
+        },
+        &mut tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    Ok(tracker.guest_input_audit().cloned())
+}
+
 /// A test helper representing a transfer endpoint.
 #[derive(Clone, Copy, Debug)]
 enum TransferTestEndpoint {
@@ -653,6 +801,7 @@ impl TransferTestEndpoint {
             },
             parameters: vec![],
             required_application_ids: vec![],
+            max_query_response_bytes: None,
         }
     }
 
@@ -13,9 +13,13 @@ use linera_base::{
     identifiers::{Account, AccountOwner, ChainDescription, ChainId, MessageId, Owner},
 };
 use linera_execution::{
-    test_utils::{ExpectedCall, RegisterMockApplication, SystemExecutionState},
-    ContractRuntime, ExecutionError, ExecutionOutcome, Message, MessageContext,
-    RawExecutionOutcome, ResourceControlPolicy, ResourceController, TransactionTracker,
+    system::Sponsorship,
+    test_utils::{
+        create_dummy_operation_context, ExpectedCall, RegisterMockApplication, SystemExecutionState,
+    },
+    ContractRuntime, ExecutionError, ExecutionOutcome, FeeAccount, Message, MessageContext,
+    Operation, OperationContext, RawExecutionOutcome, ResourceControlPolicy, ResourceController,
+    TransactionTracker,
 };
 use test_case::test_case;
 
@@ -151,6 +155,15 @@ async fn test_fee_consumption(
         maximum_block_proposal_size: 53,
         maximum_bytes_read_per_block: 59,
         maximum_bytes_written_per_block: 61,
+        maximum_application_state_size: 67,
+        maximum_operations_per_block: 71,
+        maximum_incoming_bundles_per_block: 73,
+        maximum_incoming_bundles_per_origin: 79,
+        burn_unused_grants: false,
+        maximum_query_response_size: 83,
+        maximum_messages_per_execution: 89,
+        maximum_message_size: 97,
+        maximum_message_bytes_per_execution: 101,
     };
 
     let consumed_fees = spends
@@ -167,7 +180,7 @@ async fn test_fee_consumption(
     };
     let mut controller = ResourceController {
         policy: Arc::new(prices),
-        account: authenticated_signer,
+        account: FeeAccount::Chain(authenticated_signer),
         ..ResourceController::default()
     };
 
@@ -290,6 +303,83 @@ async fn test_fee_consumption(
     Ok(())
 }
 
+/// Tests that a sponsoring chain pays for the fees of an operation instead of the chain and
+/// the authenticated signer, and that the application sees it as the fee payer.
+#[tokio::test]
+async fn test_fee_consumption_by_sponsor() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..SystemExecutionState::default()
+    };
+    let (application_id, application) = state.register_mock_application().await?;
+    let mut view = state.into_view().await;
+
+    let signer = Owner::from(PublicKey::test_key(0));
+    let sponsor = ChainId::root(1);
+    view.system.balance.set(Amount::from_tokens(10));
+    view.system
+        .balances
+        .insert(&AccountOwner::User(signer), Amount::from_tokens(10))?;
+    view.system.sponsorships.insert(
+        &sponsor,
+        Sponsorship {
+            owner: Owner::from(PublicKey::test_key(1)),
+            balance: Amount::from_tokens(10),
+        },
+    )?;
+
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            fuel_unit: Amount::ONE,
+            ..ResourceControlPolicy::default()
+        }),
+        account: FeeAccount::new(Some(signer), Some(sponsor)),
+        ..ResourceController::default()
+    };
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_eq!(runtime.authenticated_signer()?, Some(signer));
+            assert_eq!(runtime.fee_payer()?, Some(sponsor));
+            runtime.consume_fuel(3)?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = OperationContext {
+        authenticated_signer: Some(signer),
+        ..create_dummy_operation_context()
+    };
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        context,
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+
+    assert_eq!(*view.system.balance.get(), Amount::from_tokens(10));
+    assert_eq!(
+        view.system
+            .balances
+            .get(&AccountOwner::User(signer))
+            .await?,
+        Some(Amount::from_tokens(10))
+    );
+    let sponsorship = view.system.sponsorships.get(&sponsor).await?;
+    assert_eq!(
+        sponsorship.map(|sponsorship| sponsorship.balance),
+        Some(Amount::from_tokens(7))
+    );
+    Ok(())
+}
+
 /// A runtime operation that costs some amount of fees.
 pub enum FeeSpend {
     /// Consume some execution fuel.
@@ -0,0 +1,7 @@
+;; A contract exporting the same entrypoint twice, which makes it an invalid Wasm module.
+(module
+  (func $finalize)
+  (export "linera:app/contract-entrypoints#finalize" (func $finalize))
+  (export "linera:app/contract-entrypoints#finalize" (func $finalize))
+  (memory (export "memory") 1)
+)
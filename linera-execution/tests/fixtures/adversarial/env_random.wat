@@ -0,0 +1,5 @@
+;; A contract importing a source of randomness from the default `env` namespace.
+(module
+  (import "env" "random" (func (result i64)))
+  (memory (export "memory") 1)
+)
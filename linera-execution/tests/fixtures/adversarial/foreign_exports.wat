@@ -0,0 +1,7 @@
+;; A contract importing functions exported by other modules: the entrypoint of a contract, and
+;; a function of the service system API.
+(module
+  (import "linera:app/contract-entrypoints" "execute-operation" (func (param i32 i32) (result i32)))
+  (import "linera:app/service-system-api" "get-chain-id" (func (param i32)))
+  (memory (export "memory") 1)
+)
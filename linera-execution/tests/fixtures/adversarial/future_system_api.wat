@@ -0,0 +1,5 @@
+;; A contract compiled against a version of the system interfaces that doesn't exist yet.
+(module
+  (import "linera:app/contract-system-api@99.0.0" "read-system-timestamp" (func (result i64)))
+  (memory (export "memory") 1)
+)
@@ -0,0 +1,6 @@
+;; A contract importing functions from two different versions of the system interfaces.
+(module
+  (import "linera:app/contract-system-api" "read-system-timestamp" (func (result i64)))
+  (import "linera:app/view-system-api@2.0.0" "read-value-bytes" (func (param i32 i32 i32)))
+  (memory (export "memory") 1)
+)
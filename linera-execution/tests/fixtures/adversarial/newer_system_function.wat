@@ -0,0 +1,6 @@
+;; A contract compiled against the first version of the system interfaces, importing a function
+;; that was only added to the contract system API in its second version.
+(module
+  (import "linera:app/contract-system-api" "read-balance-owners" (func (param i32)))
+  (memory (export "memory") 1)
+)
@@ -0,0 +1,6 @@
+;; A contract compiled against the second version of the system interfaces, importing a function
+;; that was retired from the contract system API in that version.
+(module
+  (import "linera:app/contract-system-api@2.0.0" "send-message" (func (param i32)))
+  (memory (export "memory") 1)
+)
@@ -0,0 +1,5 @@
+;; A contract importing a function that the contract system API doesn't have.
+(module
+  (import "linera:app/contract-system-api" "escape-sandbox" (func))
+  (memory (export "memory") 1)
+)
@@ -0,0 +1,6 @@
+;; A contract importing a function of the contract system API with the wrong type: the system
+;; function takes no parameters and returns an `i64`.
+(module
+  (import "linera:app/contract-system-api" "read-system-timestamp" (func (param i32) (result f32)))
+  (memory (export "memory") 1)
+)
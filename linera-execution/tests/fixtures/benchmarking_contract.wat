@@ -0,0 +1,22 @@
+;; A contract whose operations measure how long it takes to read the monotonic clock of the
+;; test harness, reporting the measurement to the host like the SDK's `bench::measure` does.
+(module
+  (import "linera:app/test-system-api" "monotonic-nanos"
+    (func $monotonic_nanos (result i64)))
+  (import "linera:app/test-system-api" "record-benchmark"
+    (func $record_benchmark (param i32 i32 i64)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "clock")
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (i32.const 1024))
+  (func (export "cabi_free") (param i32))
+  (func (export "linera:app/contract-entrypoints#execute-operation") (param i32 i32) (result i32)
+    (local $start i64)
+    (local.set $start (call $monotonic_nanos))
+    (call $record_benchmark
+      (i32.const 16) (i32.const 5)
+      (i64.sub (call $monotonic_nanos) (local.get $start)))
+    ;; The returned empty list is at an address still filled with zeros.
+    (i32.const 64))
+  (func (export "linera:app/contract-entrypoints#finalize"))
+)
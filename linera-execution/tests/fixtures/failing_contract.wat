@@ -0,0 +1,16 @@
+;; A contract whose operations fail, reporting the error to the host like the SDK does when
+;; an operation can't be deserialized, before returning normally.
+(module
+  (import "linera:app/contract-system-api" "report-error"
+    (func $report_error (param i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "Failed to deserialize operation")
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (i32.const 1024))
+  (func (export "cabi_free") (param i32))
+  (func (export "linera:app/contract-entrypoints#execute-operation") (param i32 i32) (result i32)
+    (call $report_error
+      (i32.const 16) (i32.const 31))
+    ;; The returned empty list is at an address still filled with zeros.
+    (i32.const 64))
+)
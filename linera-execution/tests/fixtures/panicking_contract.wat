@@ -0,0 +1,18 @@
+;; A contract whose operations panic, reporting the panic to the host like the panic hook of
+;; the SDK before trapping.
+(module
+  (import "linera:app/contract-system-api" "report-panic"
+    (func $report_panic (param i32 i32 i32 i32 i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "Pledge is empty")
+  (data (i32.const 32) "src/contract.rs")
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (i32.const 1024))
+  (func (export "cabi_free") (param i32))
+  (func (export "linera:app/contract-entrypoints#execute-operation") (param i32 i32) (result i32)
+    (call $report_panic
+      (i32.const 16) (i32.const 15)
+      (i32.const 32) (i32.const 15)
+      (i32.const 42) (i32.const 9))
+    (unreachable))
+)
@@ -0,0 +1,18 @@
+;; A contract compiled against the first version of the system interfaces, whose operations read
+;; the system timestamp and the balance of the chain.
+(module
+  (import "linera:app/contract-system-api" "read-system-timestamp"
+    (func $read_system_timestamp (result i64)))
+  (import "linera:app/contract-system-api" "read-chain-balance"
+    (func $read_chain_balance (param i32)))
+  (memory (export "memory") 1)
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (i32.const 1024))
+  (func (export "cabi_free") (param i32))
+  (func (export "linera:app/contract-entrypoints#execute-operation") (param i32 i32) (result i32)
+    (drop (call $read_system_timestamp))
+    (call $read_chain_balance (i32.const 16))
+    ;; The returned empty list is at an address still filled with zeros.
+    (i32.const 64))
+  (func (export "linera:app/contract-entrypoints#finalize"))
+)
@@ -0,0 +1,19 @@
+;; A contract compiled against the second version of the system interfaces, whose operations
+;; read the system timestamp, available in all the versions, and the owners of the accounts of
+;; the chain, only available since the second version.
+(module
+  (import "linera:app/contract-system-api@2.0.0" "read-system-timestamp"
+    (func $read_system_timestamp (result i64)))
+  (import "linera:app/contract-system-api@2.0.0" "read-balance-owners"
+    (func $read_balance_owners (param i32)))
+  (memory (export "memory") 1)
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (i32.const 1024))
+  (func (export "cabi_free") (param i32))
+  (func (export "linera:app/contract-entrypoints#execute-operation") (param i32 i32) (result i32)
+    (drop (call $read_system_timestamp))
+    (call $read_balance_owners (i32.const 16))
+    ;; The returned empty list is at an address still filled with zeros.
+    (i32.const 64))
+  (func (export "linera:app/contract-entrypoints#finalize"))
+)
@@ -43,6 +43,7 @@ async fn test_read_chain_balance_system_api(chain_balance: Amount) {
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     view.query_application(context, query, None).await.unwrap();
@@ -77,6 +78,7 @@ async fn test_read_owner_balance_system_api(
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     view.query_application(context, query, None).await.unwrap();
@@ -109,6 +111,7 @@ async fn test_read_owner_balance_returns_zero_for_missing_accounts(missing_accou
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     view.query_application(context, query, None).await.unwrap();
@@ -144,6 +147,7 @@ async fn test_read_owner_balances_system_api(
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     view.query_application(context, query, None).await.unwrap();
@@ -179,6 +183,7 @@ async fn test_read_balance_owners_system_api(
     let query = Query::User {
         application_id,
         bytes: vec![],
+        authenticated_signer: None,
     };
 
     view.query_application(context, query, None).await.unwrap();
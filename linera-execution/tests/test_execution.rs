@@ -3,35 +3,47 @@
 
 #![allow(clippy::field_reassign_with_default)]
 
-use std::{collections::BTreeMap, vec};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    vec,
+};
 
 use anyhow::Context as _;
 use assert_matches::assert_matches;
 use futures::{stream, StreamExt, TryStreamExt};
 use linera_base::{
-    crypto::PublicKey,
+    crypto::{CryptoHash, KeyPair, PublicKey, Signature, TestString},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, MessageLimitError, OracleResponse,
+        RemoteValue, Resources, SendMessageRequest, Timestamp,
     },
     identifiers::{
-        Account, AccountOwner, ChainDescription, ChainId, Destination, MessageId, Owner,
+        Account, AccountOwner, ApplicationId, BytecodeId, ChainDescription, ChainId, Destination,
+        MessageId, Owner, StreamName,
     },
     ownership::ChainOwnership,
 };
 use linera_execution::{
     committee::{Committee, Epoch},
-    system::{SystemExecutionError, SystemMessage},
+    system::{ApplicationAllowlist, ScheduledMessage, SystemExecutionError, SystemMessage},
     test_utils::{
-        create_dummy_message_context, create_dummy_operation_context,
-        create_dummy_user_application_registrations, ExpectedCall, RegisterMockApplication,
-        SystemExecutionState,
+        create_dummy_message_context, create_dummy_operation_context, create_dummy_query_context,
+        create_dummy_user_application_description, create_dummy_user_application_registrations,
+        ExpectedCall, MockApplication, RegisterMockApplication, SystemExecutionState,
     },
-    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, ExecutionRuntimeContext,
-    Message, MessageKind, Operation, OperationContext, Query, QueryContext, QueryOutcome,
-    QueryResponse, RawExecutionOutcome, RawOutgoingMessage, ResourceControlPolicy,
-    ResourceController, SystemOperation, TransactionTracker,
+    BaseRuntime, ContractRuntime, ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, HeapUsage, Message, MessageKind, Operation, OperationContext, Query,
+    QueryContext, QueryOutcome, QueryResponse, RawExecutionOutcome, RawOutgoingMessage,
+    ResourceControlPolicy, ResourceController, ServiceRuntime, SharedExecutionRuntimeConfig,
+    SystemOperation, TransactionTracker, BCS_HASH_FUEL, CALL_CHECKPOINT_FUEL,
+    CALL_CHECKPOINT_FUEL_PER_BYTE, SCHEDULE_MESSAGE_FUEL, SHA3_256_FUEL, VERIFY_ED25519_FUEL,
+};
+use linera_views::{
+    batch::Batch,
+    context::Context,
+    views::{CryptoHashView as _, View},
 };
-use linera_views::{batch::Batch, context::Context, views::View};
 use test_case::test_case;
 
 #[tokio::test]
@@ -209,6 +221,10 @@ async fn test_simple_user_operation() -> anyhow::Result<()> {
         chain_id: ChainId::root(0),
         next_block_height: BlockHeight(0),
         local_time: Timestamp::from(0),
+        block_hash: None,
+        block_timestamp: Timestamp::from(0),
+        maximum_response_size: u64::MAX,
+        authenticated_signer: None,
     };
     let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
     assert_eq!(
@@ -216,7 +232,8 @@ async fn test_simple_user_operation() -> anyhow::Result<()> {
             context,
             Query::User {
                 application_id: caller_id,
-                bytes: vec![]
+                bytes: vec![],
+                authenticated_signer: None,
             },
             Some(&mut service_runtime_endpoint),
         )
@@ -233,7 +250,8 @@ async fn test_simple_user_operation() -> anyhow::Result<()> {
             context,
             Query::User {
                 application_id: caller_id,
-                bytes: vec![]
+                bytes: vec![],
+                authenticated_signer: None,
             },
             Some(&mut service_runtime_endpoint),
         )
@@ -247,6 +265,85 @@ async fn test_simple_user_operation() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Tests that query responses are limited by the network-wide limit, or by the lower limit an
+/// application was created with.
+#[tokio::test]
+async fn test_query_response_size_limits() -> anyhow::Result<()> {
+    const CEILING: u64 = 100;
+
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let mut applications = Vec::new();
+    for (index, max_query_response_bytes) in [None, Some(10), Some(1_000)].into_iter().enumerate() {
+        let (mut description, contract, service) =
+            create_dummy_user_application_description(index as u64);
+        description.max_query_response_bytes = max_query_response_bytes;
+        applications.push(
+            view.register_mock_application_with(description, contract, service)
+                .await?,
+        );
+    }
+    let [default, small, large] =
+        <[_; 3]>::try_from(applications).expect("three applications were registered");
+
+    // A limit above the network-wide one is clamped to it.
+    let cases = [
+        (&default, CEILING, CEILING),
+        (&default, CEILING + 1, CEILING),
+        (&small, 10, 10),
+        (&small, 11, 10),
+        (&large, CEILING + 1, CEILING),
+    ];
+    for ((_, application), size, maximum_size) in cases {
+        application.expect_call(ExpectedCall::handle_query(
+            move |_runtime, context, _query| {
+                assert_eq!(context.maximum_response_size, maximum_size);
+                Ok(vec![0; size as usize])
+            },
+        ));
+    }
+
+    let context = QueryContext {
+        maximum_response_size: CEILING,
+        ..create_dummy_query_context()
+    };
+    let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
+    for ((application_id, _), size, maximum_size) in cases {
+        let application_id = *application_id;
+        let result = view
+            .query_application(
+                context,
+                Query::User {
+                    application_id,
+                    bytes: vec![],
+                    authenticated_signer: None,
+                },
+                Some(&mut service_runtime_endpoint),
+            )
+            .await;
+        if size <= maximum_size {
+            assert_eq!(
+                result?.response,
+                QueryResponse::User(vec![0; size as usize])
+            );
+        } else {
+            assert_matches!(
+                result,
+                Err(ExecutionError::QueryResponseTooLarge {
+                    application_id: failed_id,
+                    size: actual_size,
+                    maximum_size: allowed_size,
+                }) if *failed_id == application_id
+                    && actual_size == size
+                    && allowed_size == maximum_size
+            );
+        }
+    }
+    Ok(())
+}
+
 /// A cross-application call to start or end a session.
 ///
 /// Here a session is a test scenario where the transaction is prevented from succeeding while
@@ -434,6 +531,200 @@ async fn test_simulated_session_leak() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Tests that a failed cross-application call is rolled back together with the calls it made,
+/// so that the caller can handle the error and call the same applications again.
+#[tokio::test]
+async fn test_failed_simulated_session_is_rolled_back() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (middle_id, middle_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+
+    let error_message = "Failed to start session";
+    let dummy_message = SendMessageRequest {
+        destination: Destination::from(ChainId::root(1)),
+        authenticated: false,
+        is_tracked: false,
+        grant: Resources::default(),
+        message: b"msg".to_vec(),
+    };
+    let target_key = vec![0];
+    let middle_key = vec![1];
+
+    // The middle application forwards the call to the target application, which succeeds
+    // after sending a message and writing to its storage. Then the middle application does
+    // the same, and fails.
+    middle_application.expect_call(ExpectedCall::execute_operation({
+        let middle_key = middle_key.clone();
+        let dummy_message = dummy_message.clone();
+        move |runtime, _context, argument| {
+            runtime.try_call_application(false, target_id, argument)?;
+            runtime.send_message(dummy_message)?;
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(middle_key, vec![1]);
+            runtime.write_batch(batch)?;
+            Err(ExecutionError::UserError(error_message.to_owned()))
+        }
+    }));
+    target_application.expect_call(ExpectedCall::execute_operation({
+        let target_key = target_key.clone();
+        move |runtime, _context, argument| {
+            assert_eq!(argument, &[SessionCall::StartSession as u8]);
+            runtime.send_message(dummy_message)?;
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(target_key, vec![1]);
+            runtime.write_batch(batch)?;
+            Ok(vec![])
+        }
+    }));
+
+    caller_application.expect_call(ExpectedCall::execute_operation({
+        let middle_application = middle_application.clone();
+        let target_application = target_application.clone();
+        let middle_key = middle_key.clone();
+        let target_key = target_key.clone();
+        move |runtime, _context, _operation| {
+            assert_matches!(
+                runtime.try_call_application(
+                    false,
+                    middle_id,
+                    vec![SessionCall::StartSession as u8],
+                ),
+                Err(ExecutionError::UserError(message)) if message == error_message
+            );
+
+            // The instances that ran the failed call were dropped, so new ones are loaded, and
+            // they don't see the writes of the failed call.
+            middle_application.expect_call(ExpectedCall::execute_operation(
+                move |runtime, _context, argument| {
+                    runtime.try_call_application(false, target_id, argument)?;
+                    assert_eq!(runtime.read_value_bytes(middle_key)?, None);
+                    Ok(vec![])
+                },
+            ));
+            middle_application.expect_call(ExpectedCall::default_finalize());
+            target_application.expect_call(ExpectedCall::execute_operation(
+                move |runtime, _context, argument| {
+                    assert_eq!(argument, &[SessionCall::EndSession as u8]);
+                    assert_eq!(runtime.read_value_bytes(target_key)?, None);
+                    Ok(vec![])
+                },
+            ));
+            target_application.expect_call(ExpectedCall::default_finalize());
+
+            runtime.try_call_application(false, middle_id, vec![SessionCall::EndSession as u8])?;
+            Ok(vec![])
+        }
+    }));
+    caller_application.expect_call(ExpectedCall::default_finalize());
+
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id: caller_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    // Neither message was sent.
+    let account = Account {
+        chain_id: ChainId::root(0),
+        owner: None,
+    };
+    let outcome = RawExecutionOutcome::default().with_refund_grant_to(Some(account));
+    assert_eq!(txn_tracker.next_message_index(), 0);
+    let (outcomes, _, _) = txn_tracker.destructure().unwrap();
+    assert_eq!(
+        outcomes,
+        vec![
+            ExecutionOutcome::User(target_id, outcome.clone()),
+            ExecutionOutcome::User(middle_id, outcome.clone()),
+            ExecutionOutcome::User(caller_id, outcome.clone()),
+            ExecutionOutcome::User(target_id, outcome.clone()),
+            ExecutionOutcome::User(middle_id, outcome.clone()),
+            ExecutionOutcome::User(caller_id, outcome),
+        ]
+    );
+
+    // Nothing was written to the storage of the applications.
+    for (id, key) in [(target_id, target_key), (middle_id, middle_key)] {
+        if let Some(state) = view.users.try_load_entry(&id).await? {
+            assert_eq!(state.get(&key).await?, None);
+        }
+    }
+    middle_application.assert_no_more_expected_calls();
+    target_application.assert_no_more_expected_calls();
+    Ok(())
+}
+
+/// Tests that a failed call makes the transaction fail if it ran an application that was
+/// loaded before the call, since the changes that application made in memory can't be rolled
+/// back.
+#[tokio::test]
+async fn test_failed_call_to_loaded_application_is_irreversible() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+
+    let error_message = "Failed to end session";
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(
+                false,
+                target_id,
+                vec![SessionCall::StartSession as u8],
+            )?;
+            assert_matches!(
+                runtime.try_call_application(
+                    false,
+                    target_id,
+                    vec![SessionCall::EndSession as u8],
+                ),
+                Err(ExecutionError::UserError(message)) if message == error_message
+            );
+            Ok(vec![])
+        },
+    ));
+    target_application.expect_call(ExpectedCall::execute_operation(
+        |_runtime, _context, _argument| Ok(vec![]),
+    ));
+    target_application.expect_call(ExpectedCall::execute_operation(
+        move |_runtime, _context, _argument| {
+            Err(ExecutionError::UserError(error_message.to_owned()))
+        },
+    ));
+
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id: caller_id,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut ResourceController::default(),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::IrreversibleCallFailure(id)) if *id == target_id
+    );
+    Ok(())
+}
+
 /// Tests if `finalize` can cause execution to fail.
 #[tokio::test]
 async fn test_rejecting_block_from_finalize() -> anyhow::Result<()> {
@@ -911,112 +1202,358 @@ async fn test_cross_application_error() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Tests if an application is scheduled to be registered together with any messages it sends to
-/// other chains.
+/// Tests that a chain of cross-application calls fails once it exceeds the maximum call depth,
+/// and that each callee sees its depth in its context.
 #[tokio::test]
-async fn test_simple_message() -> anyhow::Result<()> {
+async fn test_cross_application_call_depth_limit() -> anyhow::Result<()> {
     let mut state = SystemExecutionState::default();
     state.description = Some(ChainDescription::Root(0));
     let mut view = state.into_view().await;
 
-    let (application_id, application) = view.register_mock_application().await?;
-
-    let destination_chain = ChainId::from(ChainDescription::Root(1));
-    let dummy_message = SendMessageRequest {
-        destination: Destination::from(destination_chain),
-        authenticated: false,
-        is_tracked: false,
-        grant: Resources::default(),
-        message: b"msg".to_vec(),
-    };
-
-    let fee_policy = ResourceControlPolicy::default();
-    let expected_dummy_message =
-        RawOutgoingMessage::from(dummy_message.clone()).into_priced(&fee_policy)?;
+    let mut applications = Vec::new();
+    for _ in 0..5 {
+        applications.push(view.register_mock_application().await?);
+    }
 
-    application.expect_call(ExpectedCall::execute_operation(
-        move |runtime, _context, _operation| {
-            runtime.send_message(dummy_message)?;
-            Ok(vec![])
-        },
-    ));
-    application.expect_call(ExpectedCall::default_finalize());
+    // Each application calls the next one. The last one is never reached.
+    for (depth, pair) in applications.windows(2).enumerate() {
+        let [(_, application), (next_id, _)] = pair else {
+            unreachable!("windows have two elements");
+        };
+        let next_id = *next_id;
+        application.expect_call(ExpectedCall::execute_operation(
+            move |runtime, context, _operation| {
+                assert_eq!(context.call_depth, depth as u32);
+                runtime.try_call_application(/* authenticated */ false, next_id, vec![])
+            },
+        ));
+    }
 
     let context = create_dummy_operation_context();
-    let mut controller = ResourceController::default();
-    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
-    view.execute_operation(
-        context,
-        Timestamp::from(0),
-        Operation::User {
-            application_id,
-            bytes: vec![],
-        },
-        &mut txn_tracker,
-        &mut controller,
-    )
-    .await?;
-    view.update_execution_outcomes_with_app_registrations(&mut txn_tracker)
-        .await?;
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            maximum_call_depth: 3,
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    assert_matches!(
+        view.execute_operation(
+            context,
+            Timestamp::from(0),
+            Operation::User {
+                application_id: applications[0].0,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await,
+        Err(ExecutionError::CallDepthExceeded { depth: 4 })
+    );
 
-    let application_description = view
-        .system
-        .registry
-        .describe_application(application_id)
-        .await?;
-    let registration_message = RawOutgoingMessage {
-        destination: Destination::from(destination_chain),
-        authenticated: false,
-        grant: Amount::ZERO,
-        kind: MessageKind::Simple,
-        message: SystemMessage::RegisterApplications {
-            applications: vec![application_description],
-        },
+    Ok(())
+}
+
+/// Tests that services forwarding a query to each other fail once the nested queries exceed
+/// the maximum call depth, with and without a long-lived service runtime.
+#[tokio::test]
+async fn test_nested_query_depth_limit() -> anyhow::Result<()> {
+    let policy = ResourceControlPolicy {
+        maximum_call_depth: 3,
+        ..ResourceControlPolicy::default()
     };
-    let account = Account {
-        chain_id: ChainId::root(0),
-        owner: None,
+    let state = SystemExecutionState {
+        committees: BTreeMap::from([(Epoch::ZERO, Committee::new(BTreeMap::new(), policy))]),
+        ..SystemExecutionState::new(Epoch::ZERO, ChainDescription::Root(0), ChainId::root(0))
     };
+    let mut view = state.into_view().await;
 
-    let (outcomes, _, _) = txn_tracker.destructure().unwrap();
-    assert_eq!(
-        outcomes,
-        &[
-            ExecutionOutcome::System(
-                RawExecutionOutcome::default().with_message(registration_message)
-            ),
-            ExecutionOutcome::User(
-                application_id,
-                RawExecutionOutcome::default()
-                    .with_message(expected_dummy_message)
-                    .with_refund_grant_to(Some(account))
-            ),
-            ExecutionOutcome::User(
-                application_id,
-                RawExecutionOutcome::default().with_refund_grant_to(Some(account))
-            ),
-        ]
-    );
+    let mut applications = Vec::new();
+    for _ in 0..5 {
+        applications.push(view.register_mock_application().await?);
+    }
+
+    let context = create_dummy_query_context();
+    let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
+    for endpoint in [None, Some(&mut service_runtime_endpoint)] {
+        // Each service forwards the query to the next one. The last one is never reached.
+        for pair in applications.windows(2) {
+            let [(_, application), (next_id, _)] = pair else {
+                unreachable!("windows have two elements");
+            };
+            let next_id = *next_id;
+            application.expect_call(ExpectedCall::handle_query(
+                move |runtime, _context, query| runtime.try_query_application(next_id, query),
+            ));
+        }
+
+        let result = view
+            .query_application(
+                context,
+                Query::User {
+                    application_id: applications[0].0,
+                    bytes: vec![],
+                    authenticated_signer: None,
+                },
+                endpoint,
+            )
+            .await;
+        assert_matches!(result, Err(ExecutionError::CallDepthExceeded { depth: 4 }));
+    }
 
     Ok(())
 }
 
-/// Tests if a message is scheduled to be sent while an application is handling a cross-application
-/// call.
+/// Tests that replacing the runtime configuration while an operation executes only affects
+/// the next operations.
 #[tokio::test]
-async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
+async fn test_runtime_config_is_replaced_between_operations() -> anyhow::Result<()> {
     let mut state = SystemExecutionState::default();
     state.description = Some(ChainDescription::Root(0));
-    let mut view = state.into_view().await;
+    let shared_config = SharedExecutionRuntimeConfig::from(ExecutionRuntimeConfig::default());
+    let mut view = state
+        .into_view_with(ChainId::root(0), shared_config.clone())
+        .await;
 
     let (caller_id, caller_application) = view.register_mock_application().await?;
     let (target_id, target_application) = view.register_mock_application().await?;
 
-    caller_application.expect_call(ExpectedCall::execute_operation(
+    // The caller enables the audit, but the current operation still runs without it.
+    caller_application.expect_call(ExpectedCall::execute_operation({
+        let shared_config = shared_config.clone();
         move |runtime, _context, _operation| {
-            runtime.try_call_application(/* authenticated */ false, target_id, vec![])?;
-            Ok(vec![])
-        },
+            let version = shared_config.set(ExecutionRuntimeConfig {
+                audit_guest_inputs: true,
+                ..ExecutionRuntimeConfig::default()
+            });
+            assert_eq!(version, 1);
+            runtime.try_call_application(/* authenticated */ false, target_id, vec![])
+        }
+    }));
+    target_application.expect_call(ExpectedCall::execute_operation(
+        |_runtime, _context, _argument| Ok(vec![]),
+    ));
+    target_application.expect_call(ExpectedCall::default_finalize());
+    caller_application.expect_call(ExpectedCall::default_finalize());
+
+    let operation = Operation::User {
+        application_id: caller_id,
+        bytes: vec![],
+    };
+    let mut controller = ResourceController::default();
+    let mut tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        operation.clone(),
+        &mut tracker,
+        &mut controller,
+    )
+    .await?;
+    assert!(tracker.guest_input_audit().is_none());
+
+    // The next operation uses the new configuration.
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(/* authenticated */ false, target_id, vec![])
+        },
+    ));
+    target_application.expect_call(ExpectedCall::execute_operation(
+        |_runtime, _context, _argument| Ok(vec![]),
+    ));
+    target_application.expect_call(ExpectedCall::default_finalize());
+    caller_application.expect_call(ExpectedCall::default_finalize());
+    let mut tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        operation,
+        &mut tracker,
+        &mut controller,
+    )
+    .await?;
+    assert!(tracker.guest_input_audit().is_some());
+    assert_eq!(shared_config.version(), 1);
+
+    Ok(())
+}
+
+/// Tests that calling an application that isn't registered on the chain fails with an error
+/// that the caller can handle, and that fails the execution otherwise.
+#[tokio::test]
+async fn test_calling_unknown_application() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let unknown_id = ApplicationId {
+        bytecode_id: BytecodeId::new(
+            CryptoHash::test_hash("unknown contract"),
+            CryptoHash::test_hash("unknown service"),
+        ),
+        creation: MessageId {
+            chain_id: ChainId::root(1),
+            height: BlockHeight::from(7),
+            index: 3,
+        },
+    };
+    assert!(view.is_application_registered(&caller_id).await?);
+    assert!(!view.is_application_registered(&unknown_id).await?);
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_matches!(
+                runtime.try_call_application(/* authenticated */ false, unknown_id, vec![]),
+                Err(ExecutionError::UnknownApplication { id }) if *id == unknown_id
+            );
+            Ok(vec![])
+        },
+    ));
+    caller_application.expect_call(ExpectedCall::default_finalize());
+
+    let operation = Operation::User {
+        application_id: caller_id,
+        bytes: vec![],
+    };
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        operation.clone(),
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(/* authenticated */ false, unknown_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+
+    assert_matches!(
+        view.execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            operation,
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut ResourceController::default(),
+        )
+        .await,
+        Err(ExecutionError::UnknownApplication { id }) if *id == unknown_id
+    );
+
+    Ok(())
+}
+
+/// Tests if an application is scheduled to be registered together with any messages it sends to
+/// other chains.
+#[tokio::test]
+async fn test_simple_message() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    let destination_chain = ChainId::from(ChainDescription::Root(1));
+    let dummy_message = SendMessageRequest {
+        destination: Destination::from(destination_chain),
+        authenticated: false,
+        is_tracked: false,
+        grant: Resources::default(),
+        message: b"msg".to_vec(),
+    };
+
+    let fee_policy = ResourceControlPolicy::default();
+    let expected_dummy_message =
+        RawOutgoingMessage::from(dummy_message.clone()).into_priced(&fee_policy)?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.send_message(dummy_message)?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = create_dummy_operation_context();
+    let mut controller = ResourceController::default();
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        context,
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+    view.update_execution_outcomes_with_app_registrations(&mut txn_tracker)
+        .await?;
+
+    let application_description = view
+        .system
+        .registry
+        .describe_application(application_id)
+        .await?;
+    let registration_message = RawOutgoingMessage {
+        destination: Destination::from(destination_chain),
+        authenticated: false,
+        grant: Amount::ZERO,
+        kind: MessageKind::Simple,
+        message: SystemMessage::RegisterApplications {
+            applications: vec![application_description],
+        },
+    };
+    let account = Account {
+        chain_id: ChainId::root(0),
+        owner: None,
+    };
+
+    let (outcomes, _, _) = txn_tracker.destructure().unwrap();
+    assert_eq!(
+        outcomes,
+        &[
+            ExecutionOutcome::System(
+                RawExecutionOutcome::default().with_message(registration_message)
+            ),
+            ExecutionOutcome::User(
+                application_id,
+                RawExecutionOutcome::default()
+                    .with_message(expected_dummy_message)
+                    .with_refund_grant_to(Some(account))
+            ),
+            ExecutionOutcome::User(
+                application_id,
+                RawExecutionOutcome::default().with_refund_grant_to(Some(account))
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+/// Tests if a message is scheduled to be sent while an application is handling a cross-application
+/// call.
+#[tokio::test]
+async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(/* authenticated */ false, target_id, vec![])?;
+            Ok(vec![])
+        },
     ));
 
     let destination_chain = ChainId::from(ChainDescription::Root(1));
@@ -1684,3 +2221,1411 @@ async fn test_message_receipt_spending_chain_balance(
 
     Ok(execution_result)
 }
+
+/// Tests that the size of an application's stored state follows insertions, overwrites and
+/// deletions.
+#[tokio::test]
+async fn test_application_state_size() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            assert_eq!(runtime.read_own_state_size()?, 0);
+
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(b"a".to_vec(), vec![0; 10]);
+            batch.put_key_value_bytes(b"b1".to_vec(), vec![0; 4]);
+            batch.put_key_value_bytes(b"b2".to_vec(), vec![0; 4]);
+            runtime.write_batch(batch)?;
+            assert_eq!(runtime.read_own_state_size()?, 11 + 6 + 6);
+
+            let mut batch = Batch::new();
+            batch.delete_key_prefix(b"b".to_vec());
+            runtime.write_batch(batch)?;
+            assert_eq!(runtime.read_own_state_size()?, 11);
+
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(b"a".to_vec(), vec![0; 2]);
+            batch.delete_key(b"missing".to_vec());
+            runtime.write_batch(batch)?;
+            assert_eq!(runtime.read_own_state_size()?, 3);
+
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    assert_eq!(view.application_state_size(&application_id).await?, 3);
+    Ok(())
+}
+
+/// Tests that a batch making an application's stored state exceed the limit of the policy is
+/// rejected, even if only its last write exceeds the limit.
+#[tokio::test]
+async fn test_application_state_size_limit() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(b"a".to_vec(), vec![0; 10]);
+            batch.put_key_value_bytes(b"b".to_vec(), vec![0; 10]);
+            runtime.write_batch(batch)?;
+            Ok(vec![])
+        },
+    ));
+
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            maximum_application_state_size: 16,
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+
+    assert_matches!(
+        result,
+        Err(ExecutionError::StateTooLarge { application_id: id, size: 22, maximum_size: 16 })
+            if *id == application_id
+    );
+    Ok(())
+}
+
+/// Tests that an application can't emit more than the maximum number of events in a single
+/// execution.
+#[test_case(1_000, true; "at the limit")]
+#[test_case(1_001, false; "above the limit")]
+#[tokio::test]
+async fn test_event_count_limit(event_count: u32, should_succeed: bool) -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            for index in 0..event_count {
+                runtime.emit(
+                    StreamName(b"transfers".to_vec()),
+                    index.to_le_bytes().to_vec(),
+                    vec![],
+                )?;
+            }
+            Ok(vec![])
+        },
+    ));
+    if should_succeed {
+        application.expect_call(ExpectedCall::default_finalize());
+    }
+
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id,
+                bytes: vec![],
+            },
+            &mut txn_tracker,
+            &mut ResourceController::default(),
+        )
+        .await;
+
+    if should_succeed {
+        result?;
+        let (outcomes, _, _) = txn_tracker.destructure()?;
+        let emitted_events = outcomes
+            .iter()
+            .map(|outcome| match outcome {
+                ExecutionOutcome::System(outcome) => outcome.events.len(),
+                ExecutionOutcome::User(_, outcome) => outcome.events.len(),
+            })
+            .sum::<usize>();
+        assert_eq!(emitted_events, 1_000);
+    } else {
+        assert_matches!(result, Err(ExecutionError::TooManyEvents));
+    }
+    Ok(())
+}
+
+/// Tests that the messages sent by an application in one execution are limited in number and
+/// size, and that checking the queried limits beforehand predicts the decision of the host.
+#[test_case(&[10, 10, 5], None; "at the limits")]
+#[test_case(
+    &[1, 1, 1, 1],
+    Some(MessageLimitError::TooManyMessages { maximum_messages: 3 });
+    "too many messages"
+)]
+#[test_case(
+    &[11],
+    Some(MessageLimitError::MessageTooLarge { size: 11, maximum_size: 10 });
+    "message too large"
+)]
+#[test_case(
+    &[10, 10, 6],
+    Some(MessageLimitError::TooManyMessageBytes { maximum_bytes: 25 });
+    "too many message bytes"
+)]
+#[tokio::test]
+async fn test_message_limits(
+    message_sizes: &'static [usize],
+    expected_error: Option<MessageLimitError>,
+) -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            for &size in message_sizes {
+                let pre_check = runtime.message_limits()?.check(size as u64);
+                let result = runtime.send_message(SendMessageRequest {
+                    destination: Destination::Recipient(ChainId::root(1)),
+                    authenticated: false,
+                    is_tracked: false,
+                    grant: Resources::default(),
+                    message: vec![0; size],
+                });
+                if let Err(error) = pre_check {
+                    assert_matches!(
+                        result,
+                        Err(ExecutionError::MessageLimitExceeded(host_error))
+                            if host_error == error
+                    );
+                    return Err(error.into());
+                }
+                result?;
+            }
+            Ok(vec![])
+        },
+    ));
+    if expected_error.is_none() {
+        application.expect_call(ExpectedCall::default_finalize());
+    }
+
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            maximum_messages_per_execution: 3,
+            maximum_message_size: 10,
+            maximum_message_bytes_per_execution: 25,
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id,
+                bytes: vec![],
+            },
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await;
+
+    match expected_error {
+        None => {
+            result?;
+            let (outcomes, _, _) = txn_tracker.destructure()?;
+            let sent_messages = outcomes
+                .iter()
+                .map(|outcome| match outcome {
+                    ExecutionOutcome::System(_) => 0,
+                    ExecutionOutcome::User(_, outcome) => outcome.messages.len(),
+                })
+                .sum::<usize>();
+            assert_eq!(sent_messages, message_sizes.len());
+        }
+        Some(expected_error) => assert_matches!(
+            result,
+            Err(ExecutionError::MessageLimitExceeded(error)) if error == expected_error
+        ),
+    }
+    Ok(())
+}
+
+/// Tests that an application can send a distinct message to each of its subscribers in a single
+/// batch, and that a batch exceeding the message limits is not sent at all.
+#[tokio::test]
+async fn test_sending_a_batch_of_messages() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+    let payments = [
+        (ChainId::root(1), Amount::from_tokens(1)),
+        (ChainId::root(2), Amount::from_tokens(2)),
+        (ChainId::root(3), Amount::from_tokens(3)),
+    ];
+    let requests = move || {
+        payments
+            .iter()
+            .map(|(chain_id, amount)| SendMessageRequest {
+                destination: Destination::Recipient(*chain_id),
+                authenticated: false,
+                is_tracked: false,
+                grant: Resources::default(),
+                message: bcs::to_bytes(amount).unwrap(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let mut too_many = requests();
+            too_many.extend(requests());
+            assert_matches!(
+                runtime.send_messages(too_many),
+                Err(ExecutionError::MessageLimitExceeded(
+                    MessageLimitError::TooManyMessages {
+                        maximum_messages: 5
+                    }
+                ))
+            );
+            assert_eq!(runtime.message_limits()?.sent_messages, 0);
+
+            runtime.send_messages(requests())?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            maximum_messages_per_execution: 5,
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+
+    let (outcomes, _, _) = txn_tracker.destructure()?;
+    let sent_payments = outcomes
+        .iter()
+        .flat_map(|outcome| match outcome {
+            ExecutionOutcome::System(_) => Vec::new(),
+            ExecutionOutcome::User(_, outcome) => outcome
+                .messages
+                .iter()
+                .map(|message| {
+                    (
+                        message.destination.clone(),
+                        bcs::from_bytes::<Amount>(&message.message).unwrap(),
+                    )
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sent_payments,
+        payments
+            .iter()
+            .map(|(chain_id, amount)| (Destination::Recipient(*chain_id), *amount))
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+/// Executes an operation of a mock application that only accepts `payload`s signed by the
+/// holder of `public_key`, returning the fuel that was consumed.
+async fn execute_signed_operation(
+    public_key: PublicKey,
+    signature: Signature,
+    payload: &[u8],
+) -> Result<u64, ExecutionError> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view
+        .register_mock_application()
+        .await
+        .expect("Mock application should be registered");
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, operation| {
+            let (public_key, signature, payload): (PublicKey, Signature, Vec<u8>) =
+                bcs::from_bytes(&operation).expect("Operation should be deserializable");
+            if !runtime.verify_ed25519(public_key, &payload, signature)? {
+                return Err(ExecutionError::UserError(
+                    "Operation is not signed by its author".to_owned(),
+                ));
+            }
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: bcs::to_bytes(&(public_key, signature, payload)).unwrap(),
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+
+    Ok(controller.tracker.fuel)
+}
+
+/// Tests that a contract can authorize an operation by verifying a signature on the host, for a
+/// fixed amount of fuel.
+#[tokio::test]
+async fn test_operation_authorized_by_signature() -> anyhow::Result<()> {
+    let key_pair = KeyPair::generate();
+    let payload = b"transfer 10 tokens";
+    let signature = Signature::new_for_raw_bytes(payload, &key_pair);
+
+    let fuel = execute_signed_operation(key_pair.public(), signature, payload).await?;
+    assert_eq!(fuel, VERIFY_ED25519_FUEL);
+    Ok(())
+}
+
+/// Tests that an operation with a signature of another payload or by another signer is rejected.
+#[tokio::test]
+async fn test_operation_with_invalid_signature() -> anyhow::Result<()> {
+    let key_pair = KeyPair::generate();
+    let payload = b"transfer 10 tokens";
+    let signature = Signature::new_for_raw_bytes(payload, &key_pair);
+
+    let result =
+        execute_signed_operation(key_pair.public(), signature, b"transfer 1000 tokens").await;
+    assert_matches!(result, Err(ExecutionError::UserError(_)));
+
+    let other_key = KeyPair::generate().public();
+    let result = execute_signed_operation(other_key, signature, payload).await;
+    assert_matches!(result, Err(ExecutionError::UserError(_)));
+    Ok(())
+}
+
+/// Tests that a malformed public key fails the signature check instead of aborting execution.
+#[tokio::test]
+async fn test_operation_signed_with_malformed_key() -> anyhow::Result<()> {
+    let key_pair = KeyPair::generate();
+    let payload = b"transfer 10 tokens";
+    let signature = Signature::new_for_raw_bytes(payload, &key_pair);
+
+    // These are the encodings of y-coordinates 2 and 7, which aren't on the curve.
+    for first_byte in [2, 7] {
+        let mut malformed_key = PublicKey([0; 32]);
+        malformed_key.0[0] = first_byte;
+        let result = execute_signed_operation(malformed_key, signature, payload).await;
+        assert_matches!(result, Err(ExecutionError::UserError(_)));
+    }
+    Ok(())
+}
+
+/// Tests that the hashes computed on the host match the ones computed natively, and that each
+/// of them is charged a fixed amount of fuel.
+#[tokio::test]
+async fn test_host_hashes() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _operation| {
+            let value = TestString::new("hello");
+            let mut hashable_bytes = Vec::new();
+            linera_base::crypto::Hashable::write(&value, &mut hashable_bytes);
+            assert_eq!(runtime.bcs_hash(&hashable_bytes)?, CryptoHash::new(&value));
+
+            let digest = runtime.sha3_256(b"hello")?;
+            assert_eq!(
+                digest,
+                CryptoHash::from_raw_bytes(b"hello").as_bytes().to_vec()
+            );
+            assert_eq!(digest.len(), 32);
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+
+    assert_eq!(controller.tracker.fuel, BCS_HASH_FUEL + SHA3_256_FUEL);
+    Ok(())
+}
+
+/// Tests that each call to another application is charged for saving a checkpoint of the
+/// execution state, in proportion to the bytes written so far in the block.
+#[tokio::test]
+async fn test_call_checkpoint_fuel() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(false, target_id, vec![])?;
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![], vec![0; 1_000]);
+            runtime.write_batch(batch)?;
+            runtime.try_call_application(false, target_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+    for _ in 0..2 {
+        target_application.expect_call(ExpectedCall::execute_operation(
+            |_runtime, _context, _argument| Ok(vec![]),
+        ));
+    }
+    target_application.expect_call(ExpectedCall::default_finalize());
+    caller_application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id: caller_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+
+    let bytes_written = controller.tracker.bytes_written;
+    assert!(bytes_written >= 1_000);
+    assert_eq!(
+        controller.tracker.fuel,
+        2 * CALL_CHECKPOINT_FUEL + bytes_written * CALL_CHECKPOINT_FUEL_PER_BYTE
+    );
+    Ok(())
+}
+
+/// Tests that the heap usage reported by the applications is recorded in the transaction
+/// tracker, with a later report of the same application replacing the earlier one.
+#[tokio::test]
+async fn test_record_heap_usage() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.record_heap_usage(HeapUsage {
+                peak_bytes: 100,
+                allocations: 1,
+            })?;
+            runtime.try_call_application(false, target_id, vec![])?;
+            runtime.record_heap_usage(HeapUsage {
+                peak_bytes: 300,
+                allocations: 5,
+            })?;
+            Ok(vec![])
+        },
+    ));
+    target_application.expect_call(ExpectedCall::execute_operation(
+        |runtime, _context, _argument| {
+            runtime.record_heap_usage(HeapUsage {
+                peak_bytes: 200,
+                allocations: 2,
+            })?;
+            Ok(vec![])
+        },
+    ));
+    target_application.expect_call(ExpectedCall::default_finalize());
+    caller_application.expect_call(ExpectedCall::default_finalize());
+
+    let mut tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id: caller_id,
+            bytes: vec![],
+        },
+        &mut tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    let expected = BTreeMap::from([
+        (
+            caller_id,
+            HeapUsage {
+                peak_bytes: 300,
+                allocations: 5,
+            },
+        ),
+        (
+            target_id,
+            HeapUsage {
+                peak_bytes: 200,
+                allocations: 2,
+            },
+        ),
+    ]);
+    assert_eq!(tracker.heap_usage(), &expected);
+    Ok(())
+}
+
+/// Tests that a message scheduled by an application is registered when the chain executes it,
+/// and delivered back to the application only once it is due.
+#[tokio::test]
+async fn test_scheduled_message() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+    let due = Timestamp::from(100);
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.schedule_message(due, b"timeout".to_vec())?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    let mut txn_tracker = TransactionTracker::new(0, Some(Vec::new()));
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+
+    let schedule_message = SystemMessage::ScheduleMessage {
+        due,
+        application_id,
+        bytes: b"timeout".to_vec(),
+    };
+    let (outcomes, _, _) = txn_tracker.destructure()?;
+    assert_eq!(
+        outcomes[0],
+        ExecutionOutcome::System(
+            RawExecutionOutcome::default().with_message(RawOutgoingMessage {
+                destination: Destination::from(ChainId::root(0)),
+                authenticated: false,
+                grant: Amount::ZERO,
+                kind: MessageKind::Protected,
+                message: schedule_message.clone(),
+            })
+        )
+    );
+
+    // The chain registers the scheduled message when a block executes its own message.
+    let message_context = create_dummy_message_context(None);
+    let message_id = message_context.message_id;
+    let certificate_hash = message_context.certificate_hash;
+    view.execute_message(
+        message_context,
+        Timestamp::from(0),
+        Message::System(schedule_message),
+        None,
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+    assert_eq!(
+        view.system.scheduled_messages.get(&message_id).await?,
+        Some(ScheduledMessage {
+            due,
+            certificate_hash,
+            authenticated_signer: None,
+            message: Message::User {
+                application_id,
+                bytes: b"timeout".to_vec(),
+            },
+        })
+    );
+
+    // The system state alone can't execute it, since it is addressed to an application.
+    let result = view
+        .system
+        .execute_operation(
+            create_dummy_operation_context(),
+            SystemOperation::ExecuteScheduledMessage { message_id },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(SystemExecutionError::OperationNotExecutedBySystem(
+            "ExecuteScheduledMessage"
+        ))
+    );
+    assert_eq!(
+        view.system
+            .due_scheduled_messages(Timestamp::from(99))
+            .await?,
+        vec![]
+    );
+    assert_eq!(
+        view.system.due_scheduled_messages(due).await?,
+        vec![message_id]
+    );
+
+    let execute_scheduled_message =
+        Operation::System(SystemOperation::ExecuteScheduledMessage { message_id });
+    view.system.timestamp.set(Timestamp::from(99));
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            execute_scheduled_message.clone(),
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::SystemError(
+            SystemExecutionError::ScheduledMessageNotDue { due: not_due, .. }
+        )) if not_due == due
+    );
+
+    application.expect_call(ExpectedCall::execute_message(
+        move |_runtime, context, message| {
+            assert_eq!(context.message_id, message_id);
+            assert!(!context.is_bouncing);
+            assert_eq!(message, b"timeout");
+            Ok(())
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    view.system.timestamp.set(due);
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        execute_scheduled_message,
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+    assert!(view.system.scheduled_messages.indices().await?.is_empty());
+    assert!(view.system.due_scheduled_messages(due).await?.is_empty());
+
+    Ok(())
+}
+
+/// Tests that scheduled messages are limited in size and number, and count against the
+/// message limits of the application.
+#[tokio::test]
+async fn test_scheduled_message_limits() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+    let due = Timestamp::from(100);
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let limits = runtime.message_limits()?;
+            runtime.schedule_message(due, b"timeout".to_vec())?;
+            let scheduled_limits = runtime.message_limits()?;
+            assert_eq!(scheduled_limits.sent_messages, limits.sent_messages + 1);
+            assert_eq!(
+                scheduled_limits.sent_message_bytes,
+                limits.sent_message_bytes + 7
+            );
+            let result = runtime.schedule_message(due, vec![0; 4 * 1024 + 1]);
+            assert_matches!(result, Err(ExecutionError::ScheduledMessageTooLarge));
+            for _ in 1..100 {
+                runtime.schedule_message(due, vec![])?;
+            }
+            let result = runtime.schedule_message(due, vec![]);
+            assert_matches!(result, Err(ExecutionError::TooManyScheduledMessages));
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut controller = ResourceController::default();
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+    assert_eq!(controller.tracker.scheduled_messages, 100);
+    assert!(controller.tracker.fuel >= 100 * SCHEDULE_MESSAGE_FUEL);
+
+    Ok(())
+}
+
+/// Tests that the system state alone refuses to retry dead letters, since they may be
+/// addressed to applications.
+#[tokio::test]
+async fn test_retry_dead_letter_not_executed_by_system() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let message_id = create_dummy_message_context(None).message_id;
+    let result = view
+        .system
+        .execute_operation(
+            create_dummy_operation_context(),
+            SystemOperation::RetryDeadLetter { message_id },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(SystemExecutionError::OperationNotExecutedBySystem(
+            "RetryDeadLetter"
+        ))
+    );
+    Ok(())
+}
+
+/// Tests that other chains can't schedule messages on a chain.
+#[tokio::test]
+async fn test_scheduled_message_from_other_chain() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, _application) = view.register_mock_application().await?;
+
+    let mut message_context = create_dummy_message_context(None);
+    message_context.message_id.chain_id = ChainId::root(1);
+    let result = view
+        .execute_message(
+            message_context,
+            Timestamp::from(0),
+            Message::System(SystemMessage::ScheduleMessage {
+                due: Timestamp::from(100),
+                application_id,
+                bytes: vec![],
+            }),
+            None,
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut ResourceController::default(),
+        )
+        .await;
+
+    assert_matches!(
+        result,
+        Err(ExecutionError::SystemError(
+            SystemExecutionError::ScheduledMessageFromOtherChain(chain_id)
+        )) if chain_id == ChainId::root(1)
+    );
+    assert!(view.system.scheduled_messages.indices().await?.is_empty());
+    Ok(())
+}
+
+/// Tests that applications missing from the allowlist can't execute, not even when called by
+/// an allowed application.
+#[tokio::test]
+async fn test_application_allowlist() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (caller_id, caller_application) = view.register_mock_application().await?;
+    let (target_id, target_application) = view.register_mock_application().await?;
+    view.system
+        .application_allowlist
+        .set(Some(ApplicationAllowlist {
+            applications: BTreeSet::from([caller_id]),
+            bytecodes: BTreeSet::new(),
+        }));
+
+    let mut controller = ResourceController::default();
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id: target_id,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::ApplicationNotPermitted(id)) if *id == target_id
+    );
+
+    let result = view
+        .execute_message(
+            create_dummy_message_context(None),
+            Timestamp::from(0),
+            Message::User {
+                application_id: target_id,
+                bytes: vec![],
+            },
+            None,
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::ApplicationNotPermitted(id)) if *id == target_id
+    );
+
+    caller_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.try_call_application(/* authenticated */ false, target_id, vec![])?;
+            Ok(vec![])
+        },
+    ));
+    let result = view
+        .execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id: caller_id,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new(0, Some(Vec::new())),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::ApplicationNotPermitted(id)) if *id == target_id
+    );
+
+    // Allowing the bytecode allows all the applications created from it.
+    view.system
+        .application_allowlist
+        .set(Some(ApplicationAllowlist {
+            applications: BTreeSet::new(),
+            bytecodes: BTreeSet::from([target_id.bytecode_id]),
+        }));
+    target_application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    target_application.expect_call(ExpectedCall::default_finalize());
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id: target_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Tests that only chain owners can change the application allowlist.
+#[tokio::test]
+async fn test_changing_application_allowlist_requires_owner() -> anyhow::Result<()> {
+    let owner = Owner::from(PublicKey::test_key(0));
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    state.ownership = ChainOwnership::single(owner);
+    let mut view = state.into_view().await;
+
+    let allowlist = ApplicationAllowlist::default();
+    let operation = Operation::System(SystemOperation::ChangeApplicationAllowlist(Some(
+        allowlist.clone(),
+    )));
+    let mut controller = ResourceController::default();
+    for signer in [None, Some(Owner::from(PublicKey::test_key(1)))] {
+        let context = OperationContext {
+            authenticated_signer: signer,
+            ..create_dummy_operation_context()
+        };
+        let result = view
+            .execute_operation(
+                context,
+                Timestamp::from(0),
+                operation.clone(),
+                &mut TransactionTracker::new(0, Some(Vec::new())),
+                &mut controller,
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(ExecutionError::SystemError(
+                SystemExecutionError::UnauthorizedAllowlistChange
+            ))
+        );
+    }
+    assert_eq!(*view.system.application_allowlist.get(), None);
+
+    let context = OperationContext {
+        authenticated_signer: Some(owner),
+        ..create_dummy_operation_context()
+    };
+    view.execute_operation(
+        context,
+        Timestamp::from(0),
+        operation,
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut controller,
+    )
+    .await?;
+    assert_eq!(*view.system.application_allowlist.get(), Some(allowlist));
+
+    Ok(())
+}
+
+/// Tests reading the state of other chains: available at the height of the last block the
+/// transaction receives messages from, stale because that chain moved on, untracked, or from a
+/// chain the block receives no messages from.
+#[tokio::test]
+async fn test_reading_remote_state() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+    let (synchronized, stale, untracked, silent) = (
+        ChainId::root(1),
+        ChainId::root(2),
+        ChainId::root(3),
+        ChainId::root(4),
+    );
+    let extra = view.context().extra().clone();
+    extra.set_remote_state(
+        synchronized,
+        BlockHeight(3),
+        [(application_id, b"total".to_vec(), b"42".to_vec())],
+    );
+    extra.set_remote_state(
+        stale,
+        BlockHeight(5),
+        [(application_id, b"total".to_vec(), b"7".to_vec())],
+    );
+    let received_heights = BTreeMap::from([
+        (synchronized, BlockHeight(3)),
+        (stale, BlockHeight(4)),
+        (untracked, BlockHeight(1)),
+    ]);
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_eq!(
+                runtime.try_read_remote_state(synchronized, application_id, b"total".to_vec())?,
+                Some(RemoteValue {
+                    height: BlockHeight(3),
+                    value: Some(b"42".to_vec()),
+                })
+            );
+            assert_eq!(
+                runtime.try_read_remote_state(synchronized, application_id, b"other".to_vec())?,
+                Some(RemoteValue {
+                    height: BlockHeight(3),
+                    value: None,
+                })
+            );
+            assert_eq!(
+                runtime.try_read_remote_state(silent, application_id, b"total".to_vec())?,
+                None
+            );
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut txn_tracker = TransactionTracker::new(0, None);
+    txn_tracker.set_received_heights(received_heights.clone());
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    let total = RemoteValue {
+        height: BlockHeight(3),
+        value: Some(b"42".to_vec()),
+    };
+    let (_, oracle_responses, _) = txn_tracker.destructure()?;
+    assert_eq!(
+        oracle_responses,
+        [
+            OracleResponse::RemoteState(Some(total.clone())),
+            OracleResponse::RemoteState(Some(RemoteValue {
+                height: BlockHeight(3),
+                value: None,
+            })),
+            OracleResponse::RemoteState(None),
+        ]
+    );
+
+    // If the state of a chain at the received height is not available locally, because it
+    // moved on without a snapshot or because it is not tracked, the application reads nothing
+    // and the execution goes on. That is recorded, so that other validators agree on it.
+    for chain_id in [stale, untracked] {
+        application.expect_call(ExpectedCall::execute_operation(
+            move |runtime, _context, _operation| {
+                assert_eq!(
+                    runtime.try_read_remote_state(chain_id, application_id, b"total".to_vec())?,
+                    None
+                );
+                Ok(vec![])
+            },
+        ));
+        application.expect_call(ExpectedCall::default_finalize());
+
+        let mut txn_tracker = TransactionTracker::new(0, None);
+        txn_tracker.set_received_heights(received_heights.clone());
+        view.execute_operation(
+            create_dummy_operation_context(),
+            Timestamp::from(0),
+            Operation::User {
+                application_id,
+                bytes: vec![],
+            },
+            &mut txn_tracker,
+            &mut ResourceController::default(),
+        )
+        .await?;
+        let (_, oracle_responses, _) = txn_tracker.destructure()?;
+        assert_eq!(oracle_responses, [OracleResponse::RemoteState(None)]);
+    }
+
+    // A validator whose state of the first chain moved on since the proposal replays the value
+    // that was read.
+    extra.set_remote_state(
+        synchronized,
+        BlockHeight(4),
+        [(application_id, b"total".to_vec(), b"43".to_vec())],
+    );
+    let replayed_total = total.clone();
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_eq!(
+                runtime.try_read_remote_state(synchronized, application_id, b"total".to_vec())?,
+                Some(replayed_total)
+            );
+            assert_eq!(
+                runtime.try_read_remote_state(silent, application_id, b"total".to_vec())?,
+                None
+            );
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut txn_tracker = TransactionTracker::new(
+        0,
+        Some(vec![
+            OracleResponse::RemoteState(Some(total.clone())),
+            OracleResponse::RemoteState(None),
+        ]),
+    );
+    txn_tracker.set_received_heights(received_heights.clone());
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    // A validator that has the state replays a value that was missing on the proposer.
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            assert_eq!(
+                runtime.try_read_remote_state(synchronized, application_id, b"total".to_vec())?,
+                None
+            );
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let mut txn_tracker = TransactionTracker::new(0, Some(vec![OracleResponse::RemoteState(None)]));
+    txn_tracker.set_received_heights(received_heights.clone());
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    // A recorded value at another height than the block receives is rejected, and so is a
+    // recorded value for a chain the block receives no messages from.
+    for chain_id in [stale, silent] {
+        application.expect_call(ExpectedCall::execute_operation(
+            move |runtime, _context, _operation| {
+                runtime.try_read_remote_state(chain_id, application_id, b"total".to_vec())?;
+                Ok(vec![])
+            },
+        ));
+
+        let mut txn_tracker = TransactionTracker::new(
+            0,
+            Some(vec![OracleResponse::RemoteState(Some(total.clone()))]),
+        );
+        txn_tracker.set_received_heights(received_heights.clone());
+        let result = view
+            .execute_operation(
+                create_dummy_operation_context(),
+                Timestamp::from(0),
+                Operation::User {
+                    application_id,
+                    bytes: vec![],
+                },
+                &mut txn_tracker,
+                &mut ResourceController::default(),
+            )
+            .await;
+        assert_matches!(result, Err(ExecutionError::OracleResponseMismatch));
+    }
+    Ok(())
+}
+
+/// Tests that a message for an application the chain never registered registers it using the
+/// locally stored state of its creator chain, and that the description is recorded so that the
+/// message can be replayed without that state.
+#[tokio::test]
+async fn test_message_registers_unknown_application() -> anyhow::Result<()> {
+    let (description, contract_blob, service_blob) = create_dummy_user_application_description(0);
+    let application_id = ApplicationId::from(&description);
+    let message = Message::User {
+        application_id,
+        bytes: vec![],
+    };
+    let application = MockApplication::default();
+    let new_view = || async {
+        let mut state = SystemExecutionState::default();
+        state.description = Some(ChainDescription::Root(0));
+        let view = state.into_view().await;
+        let extra = view.context().extra().clone();
+        extra
+            .user_contracts()
+            .insert(application_id, application.clone().into());
+        extra
+            .add_blobs([contract_blob.clone(), service_blob.clone()])
+            .await?;
+        anyhow::Ok(view)
+    };
+    let mut view = new_view().await?;
+
+    // The creator chain's state is not available, so the message fails.
+    let result = view
+        .execute_message(
+            create_dummy_message_context(None),
+            Timestamp::from(0),
+            message.clone(),
+            None,
+            &mut TransactionTracker::new(0, None),
+            &mut ResourceController::default(),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(error) if error.unknown_application_id() == Some(application_id)
+    );
+    assert!(!view.is_application_registered(&application_id).await?);
+
+    view.context()
+        .extra()
+        .add_remote_application(description.clone());
+    application.expect_call(ExpectedCall::execute_message(
+        |_runtime, _context, _message| Ok(()),
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+    let mut txn_tracker = TransactionTracker::new(0, None);
+    view.execute_message(
+        create_dummy_message_context(None),
+        Timestamp::from(0),
+        message.clone(),
+        None,
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+    assert_eq!(
+        view.system
+            .registry
+            .describe_application(application_id)
+            .await?,
+        description
+    );
+    let (_, oracle_responses, _) = txn_tracker.destructure()?;
+    assert_eq!(
+        oracle_responses,
+        [
+            OracleResponse::Application(Box::new(description.clone())),
+            OracleResponse::Blob(contract_blob.id()),
+            OracleResponse::Blob(service_blob.id()),
+        ]
+    );
+
+    // Replaying the message only needs the recorded description.
+    let mut view = new_view().await?;
+    application.expect_call(ExpectedCall::execute_message(
+        |_runtime, _context, _message| Ok(()),
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+    let mut txn_tracker = TransactionTracker::new(0, Some(oracle_responses));
+    view.execute_message(
+        create_dummy_message_context(None),
+        Timestamp::from(0),
+        message,
+        None,
+        &mut txn_tracker,
+        &mut ResourceController::default(),
+    )
+    .await?;
+    txn_tracker.destructure()?;
+    assert!(view.is_application_registered(&application_id).await?);
+
+    Ok(())
+}
+
+/// Tests that the scratch space written by the contract of an application is readable by its
+/// service, and that neither writing nor wiping it changes the state hash.
+#[tokio::test]
+async fn test_scratch_writes_leave_state_hash_unchanged() -> anyhow::Result<()> {
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+
+    let (application_id, application) = view.register_mock_application().await?;
+    let state_hash = view.crypto_hash_mut().await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(b"index".to_vec(), vec![1, 2, 3]);
+            runtime.write_scratch_batch(batch)?;
+            assert_eq(runtime.read_value_bytes(b"index".to_vec())?, None);
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut ResourceController::default(),
+    )
+    .await?;
+
+    assert_eq!(view.crypto_hash_mut().await?, state_hash);
+    assert_eq!(view.application_state_size(&application_id).await?, 0);
+
+    let query = Query::User {
+        application_id,
+        bytes: vec![],
+        authenticated_signer: None,
+    };
+    let context = create_dummy_query_context();
+    for expected_value in [vec![1, 2, 3], vec![]] {
+        application.expect_call(ExpectedCall::handle_query(|runtime, _context, _query| {
+            let value = runtime.read_scratch_value_bytes(b"index".to_vec())?;
+            Ok(value.unwrap_or_default())
+        }));
+        let outcome = view.query_application(context, query.clone(), None).await?;
+        assert_eq!(outcome.response, QueryResponse::User(expected_value));
+
+        view.wipe_scratch();
+        assert_eq!(view.crypto_hash_mut().await?, state_hash);
+    }
+    Ok(())
+}
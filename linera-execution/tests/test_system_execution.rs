@@ -40,6 +40,7 @@ async fn test_simple_system_operation() -> anyhow::Result<()> {
         height: BlockHeight(0),
         round: Some(0),
         index: Some(0),
+        call_depth: 0,
         authenticated_signer: Some(owner),
         authenticated_caller_id: None,
     };
@@ -126,6 +127,10 @@ async fn test_simple_system_query() -> anyhow::Result<()> {
         chain_id: ChainId::root(0),
         next_block_height: BlockHeight(0),
         local_time: Timestamp::from(0),
+        block_hash: None,
+        block_timestamp: Timestamp::from(0),
+        maximum_response_size: u64::MAX,
+        authenticated_signer: None,
     };
     let QueryOutcome {
         response,
@@ -5,16 +5,21 @@
 
 use std::sync::Arc;
 
+use assert_matches::assert_matches;
 use linera_base::{
-    data_types::{Amount, BlockHeight, Timestamp},
+    data_types::{Amount, BlockHeight, Bytecode, Timestamp},
     identifiers::{Account, ChainDescription, ChainId},
 };
 use linera_execution::{
-    test_utils::{create_dummy_user_application_description, SystemExecutionState},
-    ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, OperationContext,
-    Query, QueryContext, QueryOutcome, QueryResponse, RawExecutionOutcome, ResourceControlPolicy,
-    ResourceController, ResourceTracker, TransactionTracker, WasmContractModule, WasmRuntime,
-    WasmServiceModule,
+    test_utils::{
+        create_dummy_operation_context, create_dummy_user_application_description,
+        SystemExecutionState,
+    },
+    BenchmarkRecorder, ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, FeeAccount, ForbiddenImport, ForbiddenImportReason, Operation,
+    OperationContext, Query, QueryContext, QueryOutcome, QueryResponse, RawExecutionOutcome,
+    ResourceControlPolicy, ResourceController, ResourceTracker, TransactionTracker,
+    WasmContractModule, WasmExecutionError, WasmRuntime, WasmServiceModule,
 };
 use linera_views::{context::Context as _, views::View};
 use serde_json::json;
@@ -71,6 +76,7 @@ async fn test_fuel_for_counter_wasm_application(
         height: BlockHeight(0),
         round: Some(0),
         index: Some(0),
+        call_depth: 0,
         authenticated_signer: None,
         authenticated_caller_id: None,
     };
@@ -84,7 +90,7 @@ async fn test_fuel_for_counter_wasm_application(
     let mut controller = ResourceController {
         policy: Arc::new(policy),
         tracker: ResourceTracker::default(),
-        account: None,
+        account: FeeAccount::default(),
     };
 
     for increment in &increments {
@@ -128,6 +134,10 @@ async fn test_fuel_for_counter_wasm_application(
         chain_id: ChainId::root(0),
         next_block_height: BlockHeight(0),
         local_time: Timestamp::from(0),
+        block_hash: None,
+        block_timestamp: Timestamp::from(0),
+        maximum_response_size: u64::MAX,
+        authenticated_signer: None,
     };
     let mut service_runtime_endpoint = context.spawn_service_runtime_actor();
     let expected_value = async_graphql::Response::new(
@@ -156,3 +166,282 @@ async fn test_fuel_for_counter_wasm_application(
     assert!(operations.is_empty());
     Ok(())
 }
+
+/// Tests that contracts importing functions that the host doesn't provide are refused before
+/// being instantiated, with the list of all the offending imports.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_forbidden_imports(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    use ForbiddenImportReason::*;
+
+    let fixtures = [
+        (
+            "wasi_imports",
+            vec![
+                ("wasi_snapshot_preview1", "fd_write", ForeignNamespace),
+                ("wasi_snapshot_preview1", "random_get", ForeignNamespace),
+            ],
+        ),
+        ("env_random", vec![("env", "random", ForeignNamespace)]),
+        (
+            "foreign_exports",
+            vec![
+                (
+                    "linera:app/contract-entrypoints",
+                    "execute-operation",
+                    ForeignNamespace,
+                ),
+                (
+                    "linera:app/service-system-api",
+                    "get-chain-id",
+                    ForeignNamespace,
+                ),
+            ],
+        ),
+        (
+            "unknown_system_function",
+            vec![(
+                "linera:app/contract-system-api",
+                "escape-sandbox",
+                UnknownFunction,
+            )],
+        ),
+        (
+            "wrong_signature",
+            vec![(
+                "linera:app/contract-system-api",
+                "read-system-timestamp",
+                WrongType,
+            )],
+        ),
+        (
+            "newer_system_function",
+            vec![(
+                "linera:app/contract-system-api",
+                "read-balance-owners",
+                UnknownFunction,
+            )],
+        ),
+        (
+            "retired_system_function",
+            vec![(
+                "linera:app/contract-system-api@2.0.0",
+                "send-message",
+                UnknownFunction,
+            )],
+        ),
+    ];
+
+    for (fixture, expected_imports) in fixtures {
+        let expected_imports = expected_imports
+            .into_iter()
+            .map(|(module, name, reason)| ForbiddenImport {
+                module: module.to_owned(),
+                name: name.to_owned(),
+                reason,
+            })
+            .collect::<Vec<_>>();
+        let contract = load_adversarial_contract(fixture, wasm_runtime).await?;
+        assert_matches!(
+            execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await,
+            Err(ExecutionError::WasmError(WasmExecutionError::ForbiddenImports(imports)))
+                if imports == expected_imports,
+            "unexpected result for fixture {fixture}"
+        );
+    }
+    Ok(())
+}
+
+/// Tests that contracts compiled against the different versions of the system interfaces can
+/// run side by side in the same process.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_system_api_versions(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let v1_contract = load_contract_fixture("system_api_v1_contract", wasm_runtime).await?;
+    let v2_contract = load_contract_fixture("system_api_v2_contract", wasm_runtime).await?;
+
+    for contract in [v1_contract.clone(), v2_contract, v1_contract] {
+        execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await?;
+    }
+    Ok(())
+}
+
+/// Tests that contracts compiled against an unknown version of the system interfaces, or
+/// against several versions at once, are refused before being instantiated.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_unsupported_system_api_versions(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract = load_adversarial_contract("future_system_api", wasm_runtime).await?;
+    assert_matches!(
+        execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await,
+        Err(ExecutionError::WasmError(WasmExecutionError::UnsupportedSystemApiVersion(namespace)))
+            if namespace == "linera:app/contract-system-api@99.0.0"
+    );
+
+    let contract = load_adversarial_contract("mixed_system_api_versions", wasm_runtime).await?;
+    assert_matches!(
+        execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await,
+        Err(ExecutionError::WasmError(
+            WasmExecutionError::MixedSystemApiVersions
+        ))
+    );
+    Ok(())
+}
+
+/// Tests that contracts measuring their execution time are refused by the runtime of the
+/// validators, and that their measurements are recorded by the runtime of the test harness.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_benchmark_measurements(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract = load_contract_fixture("benchmarking_contract", wasm_runtime).await?;
+    assert_matches!(
+        execute_operation_with_contract(contract.clone(), ExecutionRuntimeConfig::default()).await,
+        Err(ExecutionError::WasmError(WasmExecutionError::ForbiddenImports(imports)))
+            if imports.len() == 2
+                && imports
+                    .iter()
+                    .all(|import| import.reason == ForbiddenImportReason::ForeignNamespace)
+    );
+
+    let recorder = BenchmarkRecorder::default();
+    let config = ExecutionRuntimeConfig {
+        benchmark_recorder: Some(recorder.clone()),
+        ..ExecutionRuntimeConfig::default()
+    };
+    execute_operation_with_contract(contract, config).await?;
+    let measurements = recorder.take_measurements();
+    assert_eq!(measurements.len(), 1);
+    assert_eq!(measurements[0].label, "clock");
+    Ok(())
+}
+
+/// Tests that contracts exporting the same function twice are refused when they are loaded.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test)]
+async fn test_duplicate_exports(wasm_runtime: WasmRuntime) {
+    assert_matches!(
+        load_adversarial_contract("duplicate_exports", wasm_runtime).await,
+        Err(WasmExecutionError::LoadContractModule(_))
+    );
+}
+
+/// Tests that the panics reported by contracts before trapping are returned as user errors,
+/// with their location.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test)]
+async fn test_reported_panic(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract = load_contract_fixture("panicking_contract", wasm_runtime).await?;
+    assert_matches!(
+        execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await,
+        Err(ExecutionError::UserPanic { message, file, line, column })
+            if message == "Pledge is empty"
+                && file == "src/contract.rs"
+                && line == 42
+                && column == 9
+    );
+    Ok(())
+}
+
+/// Tests that the panics of contracts built with the names of their functions are reported
+/// with a backtrace naming the panicking function and its callers.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test)]
+async fn test_symbolized_panic_backtrace(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract = load_contract_fixture("symbolized_panicking_contract", wasm_runtime).await?;
+    let result = execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await;
+    let Err(ExecutionError::WasmError(WasmExecutionError::Trap { message, backtrace })) = result
+    else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert_eq!(
+        message,
+        "User application panicked at src/contract.rs:42:9: Pledge is empty"
+    );
+    let function_names = backtrace
+        .frames
+        .iter()
+        .map(|frame| frame.function_name.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        function_names,
+        [Some("check_pledge"), Some("execute_operation")]
+    );
+    assert!(backtrace.to_string().contains("0: check_pledge at "));
+    Ok(())
+}
+
+/// Tests that the errors reported by contracts before returning fail the operation with a
+/// user error.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test)]
+async fn test_reported_error(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let contract = load_contract_fixture("failing_contract", wasm_runtime).await?;
+    assert_matches!(
+        execute_operation_with_contract(contract, ExecutionRuntimeConfig::default()).await,
+        Err(ExecutionError::UserError(message))
+            if message == "Failed to deserialize operation"
+    );
+    Ok(())
+}
+
+/// Loads the contract of an adversarial fixture, written in the WebAssembly text format.
+async fn load_adversarial_contract(
+    fixture: &str,
+    wasm_runtime: WasmRuntime,
+) -> Result<WasmContractModule, WasmExecutionError> {
+    load_contract_fixture(&format!("adversarial/{fixture}"), wasm_runtime).await
+}
+
+/// Loads the contract of a fixture written in the WebAssembly text format.
+async fn load_contract_fixture(
+    fixture: &str,
+    wasm_runtime: WasmRuntime,
+) -> Result<WasmContractModule, WasmExecutionError> {
+    let text =
+        std::fs::read(format!("tests/fixtures/{fixture}.wat")).expect("Failed to read fixture");
+    let bytes = wasmer::wat2wasm(&text).expect("Fixtures should be well-formed WAT");
+    WasmContractModule::new(Bytecode::new(bytes.into_owned()), wasm_runtime).await
+}
+
+/// Registers an application with the given `contract`, and executes an operation with it
+/// using the runtime `config`.
+async fn execute_operation_with_contract(
+    contract: WasmContractModule,
+    config: ExecutionRuntimeConfig,
+) -> Result<(), ExecutionError> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state.into_view_with(ChainId::root(0), config).await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = view.system.registry.register_application(app_desc).await?;
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.into());
+    view.context()
+        .extra()
+        .add_blobs([contract_blob, service_blob])
+        .await?;
+
+    view.execute_operation(
+        create_dummy_operation_context(),
+        Timestamp::from(0),
+        Operation::User {
+            application_id: app_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(0, Some(Vec::new())),
+        &mut ResourceController::default(),
+    )
+    .await
+}
@@ -112,6 +112,27 @@ impl ValidatorNode for Client {
         }
     }
 
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        match self {
+            Client::Grpc(grpc_client) => {
+                grpc_client
+                    .handle_confirmed_certificates(certificates, delivery)
+                    .await
+            }
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => {
+                simple_client
+                    .handle_confirmed_certificates(certificates, delivery)
+                    .await
+            }
+        }
+    }
+
     async fn handle_validated_certificate(
         &self,
         certificate: ValidatedBlockCertificate,
@@ -1,7 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::identifiers::ChainId;
+use linera_base::{
+    crypto::{BcsHashable, CryptoHash},
+    identifiers::ChainId,
+};
 use linera_execution::committee::ValidatorName;
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +45,20 @@ pub struct NotificationConfig {
     pub(crate) notification_queue_size: usize,
 }
 
+/// Limits on the requests that a validator accepts on its public endpoint. Requests above the
+/// rate limits are rejected with [`NodeError::Throttled`](linera_core::node::NodeError).
+///
+/// The limits are read when the proxy starts, and are not reloaded while it is running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestLimitsConfig {
+    /// The maximum number of requests per second on a single connection.
+    pub max_requests_per_second_per_connection: Option<u32>,
+    /// The maximum number of requests per second from a single IP address.
+    pub max_requests_per_second_per_ip: Option<u32>,
+    /// The maximum size of a request, in bytes.
+    pub max_request_size: Option<usize>,
+}
+
 pub type ShardId = usize;
 
 /// The network configuration of a shard.
@@ -229,15 +246,28 @@ impl std::str::FromStr for NetworkProtocol {
     }
 }
 
+/// What the assignment of a chain to a shard is derived from.
+#[derive(Serialize, Deserialize)]
+struct ShardAssignment {
+    /// The validator's name, to randomise the assignment across validators.
+    name: ValidatorName,
+    chain_id: ChainId,
+}
+
+impl<'de> BcsHashable<'de> for ShardAssignment {}
+
 impl<P> ValidatorInternalNetworkPreConfig<P> {
-    /// Static shard assignment
+    /// Static shard assignment.
+    ///
+    /// It is a cryptographic hash of the validator's name and the chain ID, so that the proxy
+    /// and the shards agree on it even if they were built with different compilers.
     pub fn get_shard_id(&self, chain_id: ChainId) -> ShardId {
-        use std::hash::{Hash, Hasher};
-        let mut s = std::collections::hash_map::DefaultHasher::new();
-        // Use the validator public key to randomise shard assignment.
-        self.name.hash(&mut s);
-        chain_id.hash(&mut s);
-        (s.finish() as ShardId) % self.shards.len()
+        let hash = CryptoHash::new(&ShardAssignment {
+            name: self.name,
+            chain_id,
+        });
+        let [word, ..] = <[u64; 4]>::from(hash);
+        (word % self.shards.len() as u64) as ShardId
     }
 
     pub fn shard(&self, shard_id: ShardId) -> &ShardConfig {
@@ -37,8 +37,8 @@ use super::{
     transport, GRPC_MAX_MESSAGE_SIZE,
 };
 use crate::{
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest, NodeOptions,
+    HandleConfirmedCertificateRequest, HandleConfirmedCertificatesRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest, NodeOptions,
 };
 
 #[derive(Clone)]
@@ -86,7 +86,14 @@ impl GrpcClient {
                 info!("gRPC request interrupted: {}; retrying", status);
                 true
             }
-            Code::Ok | Code::Cancelled | Code::ResourceExhausted => {
+            Code::ResourceExhausted => {
+                info!(
+                    "gRPC request throttled by the validator: {}; retrying",
+                    status
+                );
+                true
+            }
+            Code::Ok | Code::Cancelled => {
                 error!("Unexpected gRPC status: {}; retrying", status);
                 true
             }
@@ -129,6 +136,9 @@ impl GrpcClient {
                     linera_base::time::timer::sleep(delay).await;
                     continue;
                 }
+                Err(s) if s.code() == Code::ResourceExhausted => {
+                    return Err(NodeError::Throttled);
+                }
                 Err(s) => {
                     return Err(NodeError::GrpcError {
                         error: format!("remote request [{handler}] failed with status: {s:?}"),
@@ -241,6 +251,24 @@ impl ValidatorNode for GrpcClient {
         )?)
     }
 
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<GenericCertificate<ConfirmedBlock>>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
+        let wait_for_outgoing_messages: bool = delivery.wait_for_outgoing_messages();
+        let request = HandleConfirmedCertificatesRequest {
+            certificates,
+            wait_for_outgoing_messages,
+        };
+        GrpcClient::try_into_chain_info(client_delegate!(
+            self,
+            handle_confirmed_certificates,
+            request
+        )?)
+    }
+
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
     async fn handle_validated_certificate(
         &self,
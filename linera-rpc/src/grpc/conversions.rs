@@ -26,8 +26,8 @@ use tonic::{Code, Status};
 
 use super::api::{self, PendingBlobRequest};
 use crate::{
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    HandleConfirmedCertificateRequest, HandleConfirmedCertificatesRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest,
 };
 
 #[derive(Error, Debug)]
@@ -203,6 +203,10 @@ impl TryFrom<BlockProposal> for api::BlockProposal {
                 .validated_block_certificate
                 .map(|cert| bincode::serialize(&cert))
                 .transpose()?,
+            fee_payer: block_proposal
+                .fee_payer
+                .map(|fee_payer| bincode::serialize(&fee_payer))
+                .transpose()?,
         })
     }
 }
@@ -225,6 +229,10 @@ impl TryFrom<api::BlockProposal> for BlockProposal {
                 .validated_block_certificate
                 .map(|bytes| bincode::deserialize(&bytes))
                 .transpose()?,
+            fee_payer: block_proposal
+                .fee_payer
+                .map(|bytes| bincode::deserialize(&bytes))
+                .transpose()?,
         })
     }
 }
@@ -416,6 +424,56 @@ impl TryFrom<HandleConfirmedCertificateRequest> for api::HandleConfirmedCertific
     }
 }
 
+impl TryFrom<api::HandleConfirmedCertificatesRequest> for HandleConfirmedCertificatesRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: api::HandleConfirmedCertificatesRequest) -> Result<Self, Self::Error> {
+        let certificates = request
+            .certificates
+            .into_iter()
+            .map(ConfirmedBlockCertificate::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let req_chain_id: ChainId = request
+            .chain_id
+            .ok_or(GrpcProtoConversionError::MissingField)?
+            .try_into()?;
+
+        ensure!(
+            certificates
+                .iter()
+                .all(|certificate| certificate.inner().chain_id() == req_chain_id),
+            GrpcProtoConversionError::InconsistentChainId
+        );
+        Ok(HandleConfirmedCertificatesRequest {
+            certificates,
+            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+        })
+    }
+}
+
+impl TryFrom<HandleConfirmedCertificatesRequest> for api::HandleConfirmedCertificatesRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: HandleConfirmedCertificatesRequest) -> Result<Self, Self::Error> {
+        let chain_id = request
+            .certificates
+            .first()
+            .ok_or(GrpcProtoConversionError::MissingField)?
+            .inner()
+            .chain_id();
+        Ok(Self {
+            chain_id: Some(chain_id.into()),
+            certificates: request
+                .certificates
+                .into_iter()
+                .map(api::Certificate::try_from)
+                .collect::<Result<_, _>>()?,
+            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+        })
+    }
+}
+
 impl TryFrom<HandleValidatedCertificateRequest> for api::HandleValidatedCertificateRequest {
     type Error = GrpcProtoConversionError;
 
@@ -567,6 +625,7 @@ impl TryFrom<api::ChainInfoQuery> for ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_state_hash_breakdown: chain_info_query.request_state_hash_breakdown,
         })
     }
 }
@@ -596,6 +655,7 @@ impl TryFrom<ChainInfoQuery> for api::ChainInfoQuery {
             request_manager_values: chain_info_query.request_manager_values,
             request_leader_timeout: chain_info_query.request_leader_timeout,
             request_fallback: chain_info_query.request_fallback,
+            request_state_hash_breakdown: chain_info_query.request_state_hash_breakdown,
         })
     }
 }
@@ -967,14 +1027,14 @@ impl TryFrom<api::CertificatesBatchResponse> for Vec<Certificate> {
 
 #[cfg(test)]
 pub mod tests {
-    use std::{borrow::Cow, fmt::Debug};
+    use std::{borrow::Cow, collections::BTreeMap, fmt::Debug};
 
     use linera_base::{
         crypto::{BcsSignable, CryptoHash, KeyPair},
         data_types::{Amount, Blob, Round, Timestamp},
     };
     use linera_chain::{
-        data_types::{BlockExecutionOutcome, ProposedBlock},
+        data_types::{BlockExecutionOutcome, FeePayer, ProposedBlock},
         test::make_first_block,
         types::CertificateKind,
     };
@@ -1063,6 +1123,9 @@ pub mod tests {
             requested_sent_certificate_hashes: vec![],
             count_received_log: 0,
             requested_received_log: vec![],
+            requested_state_hash_breakdown: None,
+            staged_resource_usage: Vec::new(),
+            staged_heap_usage: BTreeMap::new(),
         });
 
         let chain_info_response_none = ChainInfoResponse {
@@ -1101,6 +1164,7 @@ pub mod tests {
             request_manager_values: false,
             request_leader_timeout: false,
             request_fallback: true,
+            request_state_hash_breakdown: true,
         };
         round_trip_check::<_, api::ChainInfoQuery>(chain_info_query_some);
     }
@@ -1172,6 +1236,35 @@ pub mod tests {
         round_trip_check::<_, api::HandleValidatedCertificateRequest>(request);
     }
 
+    #[test]
+    pub fn test_confirmed_certificates() {
+        let key_pair = KeyPair::generate();
+        let certificates = (0..3)
+            .map(|index| {
+                ConfirmedBlockCertificate::new(
+                    Hashed::new(ConfirmedBlock::new(
+                        BlockExecutionOutcome {
+                            state_hash: CryptoHash::new(&Foo(format!("state {index}"))),
+                            ..BlockExecutionOutcome::default()
+                        }
+                        .with(get_block()),
+                    )),
+                    Round::Fast,
+                    vec![(
+                        ValidatorName::from(key_pair.public()),
+                        Signature::new(&Foo("test".into()), &key_pair),
+                    )],
+                )
+            })
+            .collect();
+        let request = HandleConfirmedCertificatesRequest {
+            certificates,
+            wait_for_outgoing_messages: true,
+        };
+
+        round_trip_check::<_, api::HandleConfirmedCertificatesRequest>(request);
+    }
+
     #[test]
     pub fn test_cross_chain_request() {
         let cross_chain_request_update_recipient = CrossChainRequest::UpdateRecipient {
@@ -1218,11 +1311,13 @@ pub mod tests {
                 block: get_block(),
                 round: Round::SingleLeader(4),
                 outcome: Some(outcome),
+                estimates: Vec::new(),
             },
             owner: Owner::from(public_key),
             public_key,
             signature: Signature::new(&Foo("test".into()), &KeyPair::generate()),
             validated_block_certificate: Some(cert),
+            fee_payer: Some(FeePayer::new(&get_block(), &KeyPair::generate())),
         };
 
         round_trip_check::<_, api::BlockProposal>(block_proposal);
@@ -50,8 +50,8 @@ use super::{
 };
 use crate::{
     config::{CrossChainConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig},
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    HandleConfirmedCertificateRequest, HandleConfirmedCertificatesRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest,
 };
 
 type CrossChainSender = mpsc::Sender<(linera_core::data_types::CrossChainRequest, ShardId)>;
@@ -574,6 +574,56 @@ where
         }
     }
 
+    #[instrument(
+        target = "grpc_server",
+        skip_all,
+        err,
+        fields(
+            nickname = self.state.nickname(),
+            chain_id = ?request.get_ref().chain_id()
+        )
+    )]
+    async fn handle_confirmed_certificates(
+        &self,
+        request: Request<api::HandleConfirmedCertificatesRequest>,
+    ) -> Result<Response<ChainInfoResult>, Status> {
+        let start = Instant::now();
+        let HandleConfirmedCertificatesRequest {
+            certificates,
+            wait_for_outgoing_messages,
+        } = request.into_inner().try_into()?;
+        trace!(count = certificates.len(), "Handling certificates");
+        let (sender, receiver) = wait_for_outgoing_messages.then(oneshot::channel).unzip();
+        match self
+            .state
+            .clone()
+            .handle_confirmed_certificates(certificates, sender)
+            .await
+        {
+            Ok((info, actions)) => {
+                Self::log_request_success_and_latency(start, "handle_confirmed_certificates");
+                self.handle_network_actions(actions);
+                if let Some(receiver) = receiver {
+                    if let Err(e) = receiver.await {
+                        error!("Failed to wait for message delivery: {e}");
+                    }
+                }
+                Ok(Response::new(info.try_into()?))
+            }
+            Err(error) => {
+                #[cfg(with_metrics)]
+                {
+                    SERVER_REQUEST_ERROR
+                        .with_label_values(&["handle_confirmed_certificates"])
+                        .inc();
+                }
+                let nickname = self.state.nickname();
+                error!(nickname, %error, "Failed to handle confirmed certificates");
+                Ok(Response::new(NodeError::from(error).try_into()?))
+            }
+        }
+    }
+
     #[instrument(
         target = "grpc_server",
         skip_all,
@@ -828,6 +878,12 @@ impl GrpcProxyable for api::HandleConfirmedCertificateRequest {
     }
 }
 
+impl GrpcProxyable for api::HandleConfirmedCertificatesRequest {
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id.clone()?.try_into().ok()
+    }
+}
+
 impl GrpcProxyable for api::HandleTimeoutCertificateRequest {
     fn chain_id(&self) -> Option<ChainId> {
         self.chain_id.clone()?.try_into().ok()
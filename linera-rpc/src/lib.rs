@@ -12,6 +12,7 @@
 pub mod config;
 pub mod mass_client;
 pub mod node_provider;
+pub mod throttle;
 
 pub mod client;
 
@@ -38,6 +39,13 @@ pub struct HandleConfirmedCertificateRequest {
     pub wait_for_outgoing_messages: bool,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct HandleConfirmedCertificatesRequest {
+    pub certificates: Vec<linera_chain::types::ConfirmedBlockCertificate>,
+    pub wait_for_outgoing_messages: bool,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct HandleValidatedCertificateRequest {
@@ -19,8 +19,8 @@ use linera_version::VersionInfo;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
-    HandleValidatedCertificateRequest,
+    HandleConfirmedCertificateRequest, HandleConfirmedCertificatesRequest, HandleLiteCertRequest,
+    HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest,
 };
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -60,6 +60,9 @@ pub enum RpcMessage {
 
     // Internal to a validator
     CrossChainRequest(Box<CrossChainRequest>),
+
+    // Inbound, added after the other messages to keep their serialization unchanged
+    ConfirmedCertificates(Box<HandleConfirmedCertificatesRequest>),
 }
 
 impl RpcMessage {
@@ -75,6 +78,12 @@ impl RpcMessage {
             TimeoutCertificate(request) => request.certificate.inner().chain_id(),
             ValidatedCertificate(request) => request.certificate.inner().chain_id(),
             ConfirmedCertificate(request) => request.certificate.inner().chain_id(),
+            ConfirmedCertificates(request) => {
+                return request
+                    .certificates
+                    .first()
+                    .map(|certificate| certificate.inner().chain_id());
+            }
             ChainInfoQuery(query) => query.chain_id,
             CrossChainRequest(request) => request.target_chain_id(),
             DownloadPendingBlob(request) => request.0,
@@ -125,6 +134,7 @@ impl RpcMessage {
             | TimeoutCertificate(_)
             | ValidatedCertificate(_)
             | ConfirmedCertificate(_)
+            | ConfirmedCertificates(_)
             | ChainInfoQuery(_)
             | CrossChainRequest(_)
             | Vote(_)
@@ -27,8 +27,8 @@ use linera_version::VersionInfo;
 use super::{codec, transport::TransportProtocol};
 use crate::{
     config::ValidatorPublicNetworkPreConfig, mass_client, HandleConfirmedCertificateRequest,
-    HandleLiteCertRequest, HandleTimeoutCertificateRequest, HandleValidatedCertificateRequest,
-    RpcMessage,
+    HandleConfirmedCertificatesRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
+    HandleValidatedCertificateRequest, RpcMessage,
 };
 
 #[derive(Clone)]
@@ -126,6 +126,21 @@ impl ValidatorNode for SimpleClient {
         self.query(request).await
     }
 
+    /// Processes a batch of confirmed certificates.
+    async fn handle_confirmed_certificates(
+        &self,
+        certificates: Vec<ConfirmedBlockCertificate>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleConfirmedCertificatesRequest {
+            certificates,
+            wait_for_outgoing_messages,
+        };
+        let request = RpcMessage::ConfirmedCertificates(Box::new(request));
+        self.query(request).await
+    }
+
     /// Processes a timeout certificate.
     async fn handle_timeout_certificate(
         &self,
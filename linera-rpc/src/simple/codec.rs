@@ -17,8 +17,19 @@ const PREFIX_SIZE: u8 = mem::size_of::<u32>() as u8;
 ///
 /// The frames are length-delimited by a [`u32`] prefix, and the payload is deserialized by
 /// [`bincode`].
-#[derive(Clone, Copy, Debug)]
-pub struct Codec;
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Codec {
+    /// The maximum size of the payload of incoming messages, if any.
+    max_incoming_size: Option<usize>,
+}
+
+impl Codec {
+    /// Returns a codec that rejects incoming messages whose payload is larger than
+    /// `max_incoming_size` bytes, before buffering them.
+    pub fn with_max_incoming_size(max_incoming_size: Option<usize>) -> Self {
+        Codec { max_incoming_size }
+    }
+}
 
 impl Encoder<RpcMessage> for Codec {
     type Error = Error;
@@ -66,6 +77,17 @@ impl Decoder for Codec {
             .try_into()
             .expect("u32 should fit in a usize");
 
+        if let Some(max) = self.max_incoming_size {
+            if payload_size > max {
+                // Drop what was received, since the rest of the frame won't be read.
+                buffer.clear();
+                return Err(Error::IncomingMessageTooBig {
+                    size: payload_size,
+                    max,
+                });
+            }
+        }
+
         let frame_size = PREFIX_SIZE as usize + payload_size;
 
         if buffer.len() < frame_size {
@@ -99,6 +121,9 @@ pub enum Error {
         message is {size} bytes but can't be larger than {max} bytes.",
         max = u32::MAX)]
     MessageTooBig { size: usize },
+
+    #[error("Incoming message of {size} bytes is larger than the limit of {max} bytes")]
+    IncomingMessageTooBig { size: usize, max: usize },
 }
 
 impl From<Error> for NodeError {
@@ -152,7 +177,7 @@ mod tests {
         buffer.extend_from_slice(&payload);
         buffer.extend_from_slice(&trailing_bytes);
 
-        let result = Codec.decode(&mut buffer);
+        let result = Codec::default().decode(&mut buffer);
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), Some(message));
@@ -185,7 +210,7 @@ mod tests {
         let frame_start = buffer.len();
         let prefix_end = frame_start + PREFIX_SIZE as usize;
 
-        let result = Codec.encode(message, &mut buffer);
+        let result = Codec::default().encode(message, &mut buffer);
 
         assert!(matches!(result, Ok(())));
         assert_eq!(&buffer[..frame_start], &leading_bytes);
@@ -17,7 +17,7 @@ use tracing::{debug, error, info, instrument, warn};
 
 use super::transport::{MessageHandler, ServerHandle, TransportProtocol};
 use crate::{
-    config::{CrossChainConfig, ShardId, ValidatorInternalNetworkPreConfig},
+    config::{CrossChainConfig, RequestLimitsConfig, ShardId, ValidatorInternalNetworkPreConfig},
     RpcMessage,
 };
 
@@ -172,7 +172,14 @@ where
             cross_chain_sender,
         };
         // Launch server for the appropriate protocol.
-        protocol.spawn_server(address, state, shutdown_signal, join_set)
+        // Shards are only reached through the proxy, which enforces the request limits.
+        protocol.spawn_server(
+            address,
+            state,
+            RequestLimitsConfig::default(),
+            shutdown_signal,
+            join_set,
+        )
     }
 }
 
@@ -319,6 +326,35 @@ where
                     }
                 }
             }
+            RpcMessage::ConfirmedCertificates(request) => {
+                let (sender, receiver) = request
+                    .wait_for_outgoing_messages
+                    .then(oneshot::channel)
+                    .unzip();
+                match self
+                    .server
+                    .state
+                    .handle_confirmed_certificates(request.certificates, sender)
+                    .await
+                {
+                    Ok((info, actions)) => {
+                        // Cross-shard requests
+                        self.handle_network_actions(actions);
+                        if let Some(receiver) = receiver {
+                            if let Err(e) = receiver.await {
+                                error!("Failed to wait for message delivery: {e}");
+                            }
+                        }
+                        // Response
+                        Ok(Some(RpcMessage::ChainInfoResponse(Box::new(info))))
+                    }
+                    Err(error) => {
+                        let nickname = self.server.state.nickname();
+                        error!(nickname, %error, "Failed to handle confirmed certificates");
+                        Err(error.into())
+                    }
+                }
+            }
             RpcMessage::ChainInfoQuery(message) => {
                 match self.server.state.handle_chain_info_query(*message).await {
                     Ok((info, actions)) => {
@@ -22,7 +22,9 @@ use tokio_util::{codec::Framed, sync::CancellationToken, udp::UdpFramed};
 use tracing::{error, warn};
 
 use crate::{
+    config::RequestLimitsConfig,
     simple::{codec, codec::Codec},
+    throttle::RequestThrottle,
     RpcMessage,
 };
 
@@ -129,7 +131,7 @@ impl TransportProtocol {
             TransportProtocol::Udp => {
                 let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
 
-                UdpFramed::new(socket, Codec)
+                UdpFramed::new(socket, Codec::default())
                     .with(move |message| future::ready(Ok((message, address))))
                     .map_ok(|(message, _address)| message)
                     .left_stream()
@@ -137,7 +139,7 @@ impl TransportProtocol {
             TransportProtocol::Tcp => {
                 let stream = TcpStream::connect(address).await?;
 
-                Framed::new(stream, Codec).right_stream()
+                Framed::new(stream, Codec::default()).right_stream()
             }
         };
 
@@ -155,20 +157,27 @@ impl TransportProtocol {
         Ok(pool)
     }
 
-    /// Runs a server for this protocol and the given message handler.
+    /// Runs a server for this protocol and the given message handler, rejecting the requests
+    /// that exceed the given `limits`.
     pub fn spawn_server<S>(
         self,
         address: impl ToSocketAddrs + Send + 'static,
         state: S,
+        limits: RequestLimitsConfig,
         shutdown_signal: CancellationToken,
         join_set: &mut JoinSet<()>,
     ) -> ServerHandle
     where
         S: MessageHandler + Send + 'static,
     {
+        let throttle = RequestThrottle::new(limits);
         let handle = match self {
-            Self::Udp => join_set.spawn_task(UdpServer::run(address, state, shutdown_signal)),
-            Self::Tcp => join_set.spawn_task(TcpServer::run(address, state, shutdown_signal)),
+            Self::Udp => {
+                join_set.spawn_task(UdpServer::run(address, state, throttle, shutdown_signal))
+            }
+            Self::Tcp => {
+                join_set.spawn_task(TcpServer::run(address, state, throttle, shutdown_signal))
+            }
         };
         ServerHandle { handle }
     }
@@ -182,7 +191,7 @@ struct UdpConnectionPool {
 impl UdpConnectionPool {
     async fn new() -> Result<Self, std::io::Error> {
         let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
-        let transport = UdpFramed::new(socket, Codec);
+        let transport = UdpFramed::new(socket, Codec::default());
         Ok(Self { transport })
     }
 }
@@ -205,6 +214,7 @@ impl ConnectionPool for UdpConnectionPool {
 /// Server implementation for UDP.
 pub struct UdpServer<State> {
     handler: State,
+    throttle: RequestThrottle,
     udp_sink: SharedUdpSink,
     udp_stream: SplitStream<UdpFramed<Codec>>,
     active_handlers: HashMap<SocketAddr, TaskHandle<()>>,
@@ -222,9 +232,10 @@ where
     pub async fn run(
         address: impl ToSocketAddrs,
         state: State,
+        throttle: RequestThrottle,
         shutdown_signal: CancellationToken,
     ) -> Result<(), std::io::Error> {
-        let mut server = Self::bind(address, state).await?;
+        let mut server = Self::bind(address, state, throttle).await?;
 
         loop {
             tokio::select! { biased;
@@ -243,12 +254,18 @@ where
 
     /// Creates a [`UpdServer`] bound to the provided `address`, handling messages using the
     /// provided `handler`.
-    async fn bind(address: impl ToSocketAddrs, handler: State) -> Result<Self, std::io::Error> {
+    async fn bind(
+        address: impl ToSocketAddrs,
+        handler: State,
+        throttle: RequestThrottle,
+    ) -> Result<Self, std::io::Error> {
         let socket = UdpSocket::bind(address).await?;
-        let (udp_sink, udp_stream) = UdpFramed::new(socket, Codec).split();
+        let codec = Codec::with_max_incoming_size(throttle.limits().max_request_size);
+        let (udp_sink, udp_stream) = UdpFramed::new(socket, codec).split();
 
         Ok(UdpServer {
             handler,
+            throttle,
             udp_sink: Arc::new(Mutex::new(udp_sink)),
             udp_stream,
             active_handlers: HashMap::new(),
@@ -261,9 +278,14 @@ where
         let previous_task = self.active_handlers.remove(&peer);
         let mut state = self.handler.clone();
         let udp_sink = self.udp_sink.clone();
+        let throttled = self.throttle.check_request(peer);
 
         let new_task = self.join_set.spawn_task(async move {
-            if let Some(reply) = state.handle_message(message).await {
+            let reply = match throttled {
+                Ok(()) => state.handle_message(message).await,
+                Err(error) => Some(error.into()),
+            };
+            if let Some(reply) = reply {
                 if let Some(task) = previous_task {
                     if let Err(error) = task.await {
                         warn!("Message handler task panicked: {}", error);
@@ -293,6 +315,11 @@ where
                 self.shutdown().await;
                 Err(io_error)
             }
+            codec::Error::IncomingMessageTooBig { size, max } => {
+                RequestThrottle::record_oversized_request(size, max);
+                warn!("Rejected an oversized message of {size} bytes");
+                Ok(())
+            }
             other_error => {
                 warn!("Received an invalid message: {other_error}");
                 Ok(())
@@ -334,7 +361,7 @@ impl TcpConnectionPool {
             match TcpStream::connect(address).await {
                 Ok(s) => {
                     self.streams
-                        .insert(address.to_string(), Framed::new(s, Codec));
+                        .insert(address.to_string(), Framed::new(s, Codec::default()));
                 }
                 Err(error) => {
                     error!("Failed to open connection to {}: {}", address, error);
@@ -366,7 +393,9 @@ impl ConnectionPool for TcpConnectionPool {
 /// Server implementation for TCP.
 pub struct TcpServer<State> {
     connection: Framed<TcpStream, Codec>,
+    peer: SocketAddr,
     handler: State,
+    throttle: RequestThrottle,
     shutdown_signal: CancellationToken,
 }
 
@@ -381,13 +410,14 @@ where
     pub async fn run(
         address: impl ToSocketAddrs,
         handler: State,
+        throttle: RequestThrottle,
         shutdown_signal: CancellationToken,
     ) -> Result<(), std::io::Error> {
         let listener = TcpListener::bind(address).await?;
 
         let mut accept_stream = stream::try_unfold(listener, |listener| async move {
-            let (socket, _) = listener.accept().await?;
-            Ok::<_, io::Error>(Some((socket, listener)))
+            let connection = listener.accept().await?;
+            Ok::<_, io::Error>(Some((connection, listener)))
         });
         let mut accept_stream = pin!(accept_stream);
 
@@ -402,10 +432,12 @@ where
                     return Ok(());
                 }
                 maybe_socket = accept_stream.next() => match maybe_socket {
-                    Some(Ok(socket)) => {
+                    Some(Ok((socket, peer))) => {
                         let server = TcpServer::new_connection(
                             socket,
+                            peer,
                             handler.clone(),
+                            throttle.clone(),
                             connection_shutdown_signal.clone(),
                         );
                         join_set.spawn_task(server.serve());
@@ -432,12 +464,17 @@ where
     /// [`TcpStream`].
     fn new_connection(
         tcp_stream: TcpStream,
+        peer: SocketAddr,
         handler: State,
+        throttle: RequestThrottle,
         shutdown_signal: CancellationToken,
     ) -> Self {
+        let codec = Codec::with_max_incoming_size(throttle.limits().max_request_size);
         TcpServer {
-            connection: Framed::new(tcp_stream, Codec),
+            connection: Framed::new(tcp_stream, codec),
+            peer,
             handler,
+            throttle,
             shutdown_signal,
         }
     }
@@ -460,7 +497,7 @@ where
                 result = self.connection.next() => match result {
                     Some(Ok(message)) => self.handle_message(message).await,
                     Some(Err(error)) => {
-                        self.handle_error(error);
+                        self.handle_error(error).await;
                         return;
                     }
                     None => break,
@@ -471,7 +508,11 @@ where
 
     /// Handles a single request message from a client.
     async fn handle_message(&mut self, message: RpcMessage) {
-        if let Some(reply) = self.handler.handle_message(message).await {
+        let reply = match self.throttle.check_request(self.peer) {
+            Ok(()) => self.handler.handle_message(message).await,
+            Err(error) => Some(error.into()),
+        };
+        if let Some(reply) = reply {
             if let Err(error) = self.connection.send(reply).await {
                 error!("Failed to send query response: {error}");
             }
@@ -481,8 +522,17 @@ where
     /// Handles an error received while attempting to receive from the connection.
     ///
     /// Ignores a successful connection termination, while logging an unexpected connection
-    /// termination or any other error.
-    fn handle_error(&self, error: codec::Error) {
+    /// termination or any other error. An oversized request is answered with
+    /// [`NodeError::RequestTooLarge`](linera_core::node::NodeError::RequestTooLarge) before the
+    /// connection is closed.
+    async fn handle_error(&mut self, error: codec::Error) {
+        if let codec::Error::IncomingMessageTooBig { size, max } = error {
+            let reply: RpcMessage = RequestThrottle::record_oversized_request(size, max).into();
+            if let Err(error) = self.connection.send(reply).await {
+                error!("Failed to send error response: {error}");
+            }
+            return;
+        }
         if !matches!(
             &error,
             codec::Error::IoError(error)
@@ -493,3 +543,138 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener as StdTcpListener;
+
+    use async_trait::async_trait;
+    use futures::{SinkExt as _, StreamExt as _};
+    use linera_base::data_types::BlobContent;
+    use linera_core::node::NodeError;
+    use tokio::task::JoinSet;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{MessageHandler, TransportProtocol};
+    use crate::{config::RequestLimitsConfig, RpcMessage};
+
+    /// A handler that replies to each request with the request itself.
+    #[derive(Clone)]
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MessageHandler for EchoHandler {
+        async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+            Some(message)
+        }
+    }
+
+    /// Starts a TCP server with the given `limits` on a free local port, and returns its address.
+    fn start_server(
+        limits: RequestLimitsConfig,
+        shutdown_signal: CancellationToken,
+        join_set: &mut JoinSet<()>,
+    ) -> String {
+        let port = StdTcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let address = format!("127.0.0.1:{port}");
+        TransportProtocol::Tcp.spawn_server(
+            address.clone(),
+            EchoHandler,
+            limits,
+            shutdown_signal,
+            join_set,
+        );
+        address
+    }
+
+    /// Sends `count` requests through a single connection, and returns the number of requests
+    /// that were throttled.
+    async fn send_requests(address: &str, count: usize) -> usize {
+        let mut connection = loop {
+            match TransportProtocol::Tcp.connect(address.to_owned()).await {
+                Ok(connection) => break connection,
+                Err(_) => tokio::task::yield_now().await,
+            }
+        };
+        let mut throttled = 0;
+        for _ in 0..count {
+            connection.send(RpcMessage::VersionInfoQuery).await.unwrap();
+            match connection.next().await.unwrap().unwrap() {
+                RpcMessage::VersionInfoQuery => {}
+                RpcMessage::Error(error) if matches!(*error, NodeError::Throttled) => {
+                    throttled += 1
+                }
+                reply => panic!("Unexpected reply: {reply:?}"),
+            }
+        }
+        throttled
+    }
+
+    #[tokio::test]
+    async fn throttling_a_connection_does_not_affect_other_clients() {
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let limits = RequestLimitsConfig {
+            max_requests_per_second_per_connection: Some(5),
+            ..RequestLimitsConfig::default()
+        };
+        let address = start_server(limits, shutdown_signal.clone(), &mut join_set);
+
+        let (hammering, well_behaved) =
+            tokio::join!(send_requests(&address, 100), send_requests(&address, 3));
+        assert!(hammering > 0);
+        assert_eq!(well_behaved, 0);
+
+        shutdown_signal.cancel();
+    }
+
+    #[tokio::test]
+    async fn throttles_connections_from_the_same_ip_address() {
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let limits = RequestLimitsConfig {
+            max_requests_per_second_per_ip: Some(5),
+            ..RequestLimitsConfig::default()
+        };
+        let address = start_server(limits, shutdown_signal.clone(), &mut join_set);
+
+        let mut throttled = 0;
+        for _ in 0..10 {
+            throttled += send_requests(&address, 2).await;
+        }
+        assert!(throttled > 0);
+
+        shutdown_signal.cancel();
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_requests() {
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let limits = RequestLimitsConfig {
+            max_request_size: Some(1024),
+            ..RequestLimitsConfig::default()
+        };
+        let address = start_server(limits, shutdown_signal.clone(), &mut join_set);
+        assert_eq!(send_requests(&address, 1).await, 0);
+
+        let mut connection = TransportProtocol::Tcp.connect(address).await.unwrap();
+        let blob = BlobContent::new_data(vec![0; 4096]);
+        connection
+            .send(RpcMessage::UploadBlob(Box::new(blob)))
+            .await
+            .unwrap();
+        let reply = connection.next().await.unwrap().unwrap();
+        assert!(matches!(
+            reply,
+            RpcMessage::Error(error)
+                if matches!(*error, NodeError::RequestTooLarge { max: 1024, .. })
+        ));
+
+        shutdown_signal.cancel();
+    }
+}
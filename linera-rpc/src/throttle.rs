@@ -0,0 +1,220 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Enforcement of the request rate limits of a validator's public endpoint.
+
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use linera_base::time::{Duration, Instant};
+use linera_core::node::NodeError;
+#[cfg(with_metrics)]
+use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec};
+
+use crate::config::RequestLimitsConfig;
+
+/// The duration of the windows in which the requests are counted.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// The number of tracked peers above which the expired windows are removed.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// The number of requests rejected because of the request limits, by the kind of limit.
+#[cfg(with_metrics)]
+pub(crate) static THROTTLED_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "throttled_requests",
+        "Number of requests rejected because of the request limits",
+        &["limit"],
+    )
+});
+
+/// Counts the requests of each connection and IP address, and rejects the ones exceeding the
+/// configured [`RequestLimitsConfig`]. Clones share the same counters.
+#[derive(Clone, Debug, Default)]
+pub struct RequestThrottle {
+    limits: RequestLimitsConfig,
+    connections: Arc<Mutex<RequestCounters<SocketAddr>>>,
+    ips: Arc<Mutex<RequestCounters<IpAddr>>>,
+}
+
+impl RequestThrottle {
+    /// Creates a new [`RequestThrottle`] enforcing the given `limits`.
+    pub fn new(limits: RequestLimitsConfig) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the limits enforced by this throttle.
+    pub fn limits(&self) -> &RequestLimitsConfig {
+        &self.limits
+    }
+
+    /// Records a request received on the connection from `peer`. Returns
+    /// [`NodeError::Throttled`] if the connection or its IP address exceeded its rate limit, in
+    /// which case the request must be rejected.
+    pub fn check_request(&self, peer: SocketAddr) -> Result<(), NodeError> {
+        self.check_request_at(peer, Instant::now())
+    }
+
+    fn check_request_at(&self, peer: SocketAddr, now: Instant) -> Result<(), NodeError> {
+        if let Some(limit) = self.limits.max_requests_per_second_per_connection {
+            let mut connections = self
+                .connections
+                .lock()
+                .expect("Panics should not happen while holding the request counters");
+            if !connections.try_count(peer, limit, now) {
+                #[cfg(with_metrics)]
+                THROTTLED_REQUESTS.with_label_values(&["connection"]).inc();
+                return Err(NodeError::Throttled);
+            }
+        }
+        if let Some(limit) = self.limits.max_requests_per_second_per_ip {
+            let mut ips = self
+                .ips
+                .lock()
+                .expect("Panics should not happen while holding the request counters");
+            if !ips.try_count(peer.ip(), limit, now) {
+                #[cfg(with_metrics)]
+                THROTTLED_REQUESTS.with_label_values(&["ip"]).inc();
+                return Err(NodeError::Throttled);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a request rejected because it is larger than
+    /// [`RequestLimitsConfig::max_request_size`], and returns the error to reply with.
+    pub fn record_oversized_request(size: usize, max: usize) -> NodeError {
+        #[cfg(with_metrics)]
+        THROTTLED_REQUESTS.with_label_values(&["size"]).inc();
+        NodeError::RequestTooLarge { size, max }
+    }
+}
+
+/// Rejects the gRPC requests exceeding the rate limits with a `RESOURCE_EXHAUSTED` status,
+/// which clients interpret as [`NodeError::Throttled`]. Requests whose peer address is unknown
+/// are let through.
+#[cfg(with_server)]
+impl tonic::service::Interceptor for RequestThrottle {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+        if let Some(peer) = request.remote_addr() {
+            self.check_request(peer)
+                .map_err(|error| tonic::Status::resource_exhausted(error.to_string()))?;
+        }
+        Ok(request)
+    }
+}
+
+/// The number of requests of each peer in its current window.
+#[derive(Debug)]
+struct RequestCounters<K> {
+    windows: HashMap<K, (Instant, u32)>,
+}
+
+impl<K> Default for RequestCounters<K> {
+    fn default() -> Self {
+        Self {
+            windows: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> RequestCounters<K> {
+    /// Counts a request from `key` at the time `now`, unless `key` already made `limit`
+    /// requests in its current window. Returns whether the request was counted.
+    fn try_count(&mut self, key: K, limit: u32, now: Instant) -> bool {
+        if self.windows.len() >= PRUNE_THRESHOLD {
+            self.windows
+                .retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+        let (start, count) = self.windows.entry(key).or_insert((now, 0));
+        if now.duration_since(*start) >= WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use linera_base::time::{Duration, Instant};
+    use linera_core::node::NodeError;
+
+    use super::{RequestThrottle, WINDOW};
+    use crate::config::RequestLimitsConfig;
+
+    fn peer(address: &str) -> SocketAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn throttles_connections_until_the_next_window() {
+        let throttle = RequestThrottle::new(RequestLimitsConfig {
+            max_requests_per_second_per_connection: Some(2),
+            ..RequestLimitsConfig::default()
+        });
+        let now = Instant::now();
+        let busy = peer("127.0.0.1:1000");
+        let quiet = peer("127.0.0.1:1001");
+
+        throttle.check_request_at(busy, now).unwrap();
+        throttle.check_request_at(busy, now).unwrap();
+        assert!(matches!(
+            throttle.check_request_at(busy, now),
+            Err(NodeError::Throttled)
+        ));
+        throttle.check_request_at(quiet, now).unwrap();
+
+        let later = now + WINDOW;
+        throttle.check_request_at(busy, later).unwrap();
+    }
+
+    #[test]
+    fn throttles_ip_addresses_across_connections() {
+        let throttle = RequestThrottle::new(RequestLimitsConfig {
+            max_requests_per_second_per_ip: Some(3),
+            ..RequestLimitsConfig::default()
+        });
+        let now = Instant::now();
+
+        for port in 1000..1003 {
+            throttle
+                .check_request_at(peer(&format!("10.0.0.1:{port}")), now)
+                .unwrap();
+        }
+        assert!(matches!(
+            throttle.check_request_at(peer("10.0.0.1:1003"), now),
+            Err(NodeError::Throttled)
+        ));
+        throttle
+            .check_request_at(peer("10.0.0.2:1000"), now + Duration::from_millis(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let throttle = RequestThrottle::default();
+        let now = Instant::now();
+        for _ in 0..1_000 {
+            throttle
+                .check_request_at(peer("127.0.0.1:1000"), now)
+                .unwrap();
+        }
+    }
+}
@@ -8,8 +8,10 @@ mod utils;
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use syn::{
-    parse_macro_input, Fields, ItemEnum,
     __private::{quote::quote, TokenStream2},
+    ext::IdentExt,
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, GenericParam, ItemEnum,
+    Path, Type,
 };
 
 use crate::utils::{concat, snakify};
@@ -127,11 +129,185 @@ fn generate_mutation_root_code(input: ItemEnum, crate_root: &str) -> TokenStream
     }
 }
 
+#[proc_macro_derive(ContractError)]
+pub fn derive_contract_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_contract_error_code(input, "linera_sdk").into()
+}
+
+fn generate_contract_error_code(input: DeriveInput, crate_root: &str) -> TokenStream2 {
+    let crate_root = Ident::new(crate_root, Span::call_site());
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crate_root::ContractError for #name #type_generics #where_clause {}
+    }
+}
+
+#[proc_macro_derive(ContractAbi, attributes(contract_abi))]
+pub fn derive_contract_abi(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_contract_abi_code(input, "linera_sdk")
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `ContractAbi` with the types given by the `#[contract_abi(..)]` attribute.
+///
+/// The optional `crate` argument, e.g. `crate = linera_base`, replaces the crate containing the
+/// `abi` module, so that ABIs can be defined without depending on `linera-sdk`.
+fn generate_contract_abi_code(input: DeriveInput, crate_root: &str) -> syn::Result<TokenStream2> {
+    let mut crate_root = Path::from(Ident::new(crate_root, Span::call_site()));
+    let mut operation = None;
+    let mut response = None;
+    for attribute in &input.attrs {
+        if !attribute.path().is_ident("contract_abi") {
+            continue;
+        }
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("operation") {
+                operation = Some(meta.value()?.parse::<Type>()?);
+            } else if meta.path.is_ident("response") {
+                response = Some(meta.value()?.parse::<Type>()?);
+            } else if meta.path.is_ident("crate") {
+                crate_root = meta.value()?.parse::<Path>()?;
+            } else {
+                return Err(meta.error("expected `operation`, `response` or `crate`"));
+            }
+            Ok(())
+        })?;
+    }
+    let missing = |argument| {
+        let message = format!("missing `{argument}` in the `#[contract_abi(..)]` attribute");
+        syn::Error::new_spanned(&input.ident, message)
+    };
+    let operation = operation.ok_or_else(|| missing("operation"))?;
+    let response = response.ok_or_else(|| missing("response"))?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #crate_root::abi::ContractAbi for #name #type_generics #where_clause {
+            type Operation = #operation;
+            type Response = #response;
+        }
+    })
+}
+
+#[proc_macro_derive(AbiSchema)]
+pub fn derive_abi_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_abi_schema_code(input, "linera_sdk").into()
+}
+
+fn generate_abi_schema_code(mut input: DeriveInput, crate_root: &str) -> TokenStream2 {
+    let crate_root = Ident::new(crate_root, Span::call_site());
+    let abi = quote! { #crate_root::abi };
+    let schema = if has_serde_attributes(&input) {
+        // Serde attributes change the JSON representation in ways that are not described.
+        quote! { #abi::Schema::ANY }
+    } else {
+        match &input.data {
+            Data::Struct(data) => fields_schema(&data.fields, &abi, quote! { #abi::Schema::NULL }),
+            Data::Enum(data) => {
+                let variants = data.variants.iter().map(|variant| {
+                    let name = variant.ident.unraw().to_string();
+                    let content =
+                        fields_schema(&variant.fields, &abi, quote! { #abi::Schema::UNIT_VARIANT });
+                    quote! { (#name, #content) }
+                });
+                quote! { #abi::Schema::enumeration(&[#(#variants),*]) }
+            }
+            Data::Union(_) => quote! { #abi::Schema::ANY },
+        }
+    };
+
+    let type_parameters = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|parameter| match parameter {
+            GenericParam::Type(parameter) => Some(parameter.ident.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+    for parameter in type_parameters {
+        where_clause
+            .predicates
+            .push(parse_quote! { #parameter: #abi::AbiSchema });
+    }
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #abi::AbiSchema for #name #type_generics #where_clause {
+            const SCHEMA: #abi::Schema = #schema;
+        }
+    }
+}
+
+/// Returns the schema of a struct or of the content of an enum variant, following the
+/// JSON representation used by `serde`.
+fn fields_schema(fields: &Fields, abi: &TokenStream2, unit: TokenStream2) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let fields = named.named.iter().map(|field| {
+                let name = field
+                    .ident
+                    .as_ref()
+                    .expect("named fields always have names")
+                    .unraw()
+                    .to_string();
+                let ty = &field.ty;
+                quote! { (#name, <#ty as #abi::AbiSchema>::SCHEMA) }
+            });
+            quote! { #abi::Schema::object(&[#(#fields),*]) }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let ty = &unnamed.unnamed[0].ty;
+            quote! { <#ty as #abi::AbiSchema>::SCHEMA }
+        }
+        Fields::Unnamed(unnamed) => {
+            let items = unnamed.unnamed.iter().map(|field| {
+                let ty = &field.ty;
+                quote! { <#ty as #abi::AbiSchema>::SCHEMA }
+            });
+            quote! { #abi::Schema::tuple(&[#(#items),*]) }
+        }
+        Fields::Unit => unit,
+    }
+}
+
+/// Returns `true` if the type, or any of its variants or fields, has a `#[serde(..)]`
+/// attribute.
+fn has_serde_attributes(input: &DeriveInput) -> bool {
+    let is_serde = |attributes: &[Attribute]| {
+        attributes
+            .iter()
+            .any(|attribute| attribute.path().is_ident("serde"))
+    };
+    let fields_have_serde = |fields: &Fields| fields.iter().any(|field| is_serde(&field.attrs));
+    is_serde(&input.attrs)
+        || match &input.data {
+            Data::Struct(data) => fields_have_serde(&data.fields),
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .any(|variant| is_serde(&variant.attrs) || fields_have_serde(&variant.fields)),
+            Data::Union(_) => false,
+        }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use syn::{parse_quote, ItemEnum, __private::quote::quote};
+    use syn::{__private::quote::quote, parse_quote, DeriveInput, ItemEnum};
 
-    use crate::generate_mutation_root_code;
+    use crate::{
+        generate_abi_schema_code, generate_contract_abi_code, generate_contract_error_code,
+        generate_mutation_root_code,
+    };
 
     fn assert_eq_no_whitespace(mut actual: String, mut expected: String) {
         // Intentionally left here for debugging purposes
@@ -211,4 +387,113 @@ pub mod tests {
 
         assert_eq_no_whitespace(output.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn test_derive_contract_error() {
+        let error: DeriveInput = parse_quote! {
+            enum SomeError<T> {
+                Invalid(T),
+                Empty,
+            }
+        };
+
+        let output = generate_contract_error_code(error, "linera_sdk");
+
+        let expected = quote! {
+            impl<T> linera_sdk::ContractError for SomeError<T> {}
+        };
+
+        assert_eq_no_whitespace(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_derive_contract_abi() {
+        let abi: DeriveInput = parse_quote! {
+            #[contract_abi(operation = Operation, response = Option<u64>, crate = linera_base)]
+            struct CounterAbi;
+        };
+
+        let output = generate_contract_abi_code(abi, "linera_sdk").unwrap();
+
+        let expected = quote! {
+            impl linera_base::abi::ContractAbi for CounterAbi {
+                type Operation = Operation;
+                type Response = Option<u64>;
+            }
+        };
+
+        assert_eq_no_whitespace(output.to_string(), expected.to_string());
+
+        let abi: DeriveInput = parse_quote! {
+            #[contract_abi(operation = Operation)]
+            struct CounterAbi;
+        };
+        let error = generate_contract_abi_code(abi, "linera_sdk").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "missing `response` in the `#[contract_abi(..)]` attribute"
+        );
+    }
+
+    #[test]
+    fn test_derive_abi_schema() {
+        let argument: DeriveInput = parse_quote! {
+            enum Argument<T> {
+                Single(T),
+                Pair(u32, String),
+                Named { r#type: bool },
+                Empty,
+            }
+        };
+
+        let output = generate_abi_schema_code(argument, "linera_sdk");
+
+        let expected = quote! {
+            impl<T> linera_sdk::abi::AbiSchema for Argument<T>
+            where
+                T: linera_sdk::abi::AbiSchema
+            {
+                const SCHEMA: linera_sdk::abi::Schema = linera_sdk::abi::Schema::enumeration(&[
+                    ("Single", <T as linera_sdk::abi::AbiSchema>::SCHEMA),
+                    (
+                        "Pair",
+                        linera_sdk::abi::Schema::tuple(&[
+                            <u32 as linera_sdk::abi::AbiSchema>::SCHEMA,
+                            <String as linera_sdk::abi::AbiSchema>::SCHEMA
+                        ])
+                    ),
+                    (
+                        "Named",
+                        linera_sdk::abi::Schema::object(&[(
+                            "type",
+                            <bool as linera_sdk::abi::AbiSchema>::SCHEMA
+                        )])
+                    ),
+                    ("Empty", linera_sdk::abi::Schema::UNIT_VARIANT)
+                ]);
+            }
+        };
+
+        assert_eq_no_whitespace(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_derive_abi_schema_with_serde_attributes() {
+        let parameters: DeriveInput = parse_quote! {
+            struct Parameters {
+                #[serde(default)]
+                limit: u64,
+            }
+        };
+
+        let output = generate_abi_schema_code(parameters, "linera_sdk");
+
+        let expected = quote! {
+            impl linera_sdk::abi::AbiSchema for Parameters {
+                const SCHEMA: linera_sdk::abi::Schema = linera_sdk::abi::Schema::ANY;
+            }
+        };
+
+        assert_eq_no_whitespace(output.to_string(), expected.to_string());
+    }
 }
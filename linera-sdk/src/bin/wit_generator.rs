@@ -16,7 +16,7 @@ use anyhow::{ensure, Context, Result};
 use clap::Parser as _;
 use linera_execution::{
     ContractEntrypoints, ContractSyncRuntimeHandle, ContractSystemApi, ServiceEntrypoints,
-    ServiceSyncRuntimeHandle, ServiceSystemApi, SystemApiData, ViewSystemApi,
+    ServiceSyncRuntimeHandle, ServiceSystemApi, SystemApiData, TestSystemApi, ViewSystemApi,
 };
 use linera_witty::wit_generation::{StubInstance, WitInterfaceWriter, WitWorldWriter};
 
@@ -59,11 +59,15 @@ fn run_operation(options: WitGeneratorOptions, mut operation: impl Operation) ->
     let view_system_api = WitInterfaceWriter::new::<
         ViewSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>,
     >();
+    let test_system_api = WitInterfaceWriter::new::<
+        TestSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>,
+    >();
 
     let contract_world = WitWorldWriter::new("linera:app", "contract")
         .export::<ContractEntrypoints<StubInstance>>()
         .import::<ContractSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>>()
-        .import::<ViewSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>>();
+        .import::<ViewSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>>()
+        .import::<TestSystemApi<StubInstance<SystemApiData<ContractSyncRuntimeHandle>>>>();
     let service_world = WitWorldWriter::new("linera:app", "service")
         .export::<ServiceEntrypoints<StubInstance>>()
         .import::<ServiceSystemApi<StubInstance<SystemApiData<ServiceSyncRuntimeHandle>>>>()
@@ -90,6 +94,10 @@ fn run_operation(options: WitGeneratorOptions, mut operation: impl Operation) ->
         &options.base_directory.join("view-system-api.wit"),
         view_system_api.generate_file_contents(),
     )?;
+    operation.run_for_file(
+        &options.base_directory.join("test-system-api.wit"),
+        test_system_api.generate_file_contents(),
+    )?;
 
     operation.run_for_file(
         &options.base_directory.join("contract.wit"),
@@ -0,0 +1,142 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Guards for contracts that only serve some callers.
+
+use linera_base::identifiers::{ApplicationId, BytecodeId, Owner};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::ContractError;
+
+/// The reasons why a contract refuses to serve the current call.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Error, PartialEq, Serialize)]
+pub enum CallerError {
+    /// The contract is not being called by an application, or the calling application did
+    /// not authenticate the call.
+    #[error("The call is not authenticated by a calling application")]
+    NoAuthenticatedCaller,
+    /// The contract is being called by another application than the expected one.
+    #[error("Expected a call from application {expected}, but the caller is {caller}")]
+    UnexpectedCaller {
+        /// The application allowed to call.
+        expected: ApplicationId,
+        /// The application that made the call.
+        caller: ApplicationId,
+    },
+    /// The calling application was not created from the expected bytecode.
+    #[error(
+        "Expected a call from an application with bytecode {expected}, but the caller is {caller}"
+    )]
+    UnexpectedCallerBytecode {
+        /// The bytecode of the applications allowed to call.
+        expected: BytecodeId,
+        /// The application that made the call.
+        caller: ApplicationId,
+    },
+    /// No signer authenticated the current transaction, or the calling application did not
+    /// forward the signer's authentication.
+    #[error("The call is not authenticated by a signer")]
+    NoAuthenticatedSigner,
+}
+
+impl ContractError for CallerError {}
+
+/// Checks that the authenticated `caller` is the `expected` application.
+pub(crate) fn check_caller(
+    caller: Option<ApplicationId>,
+    expected: ApplicationId,
+) -> Result<(), CallerError> {
+    let caller = caller.ok_or(CallerError::NoAuthenticatedCaller)?;
+    if caller != expected {
+        return Err(CallerError::UnexpectedCaller { expected, caller });
+    }
+    Ok(())
+}
+
+/// Checks that the authenticated `caller` was created from the `expected` bytecode, and
+/// returns it.
+pub(crate) fn check_caller_bytecode(
+    caller: Option<ApplicationId>,
+    expected: BytecodeId,
+) -> Result<ApplicationId, CallerError> {
+    let caller = caller.ok_or(CallerError::NoAuthenticatedCaller)?;
+    if caller.bytecode_id != expected {
+        return Err(CallerError::UnexpectedCallerBytecode { expected, caller });
+    }
+    Ok(caller)
+}
+
+/// Checks that there is an authenticated `signer`, and returns it.
+pub(crate) fn check_signer(signer: Option<Owner>) -> Result<Owner, CallerError> {
+    signer.ok_or(CallerError::NoAuthenticatedSigner)
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        crypto::CryptoHash,
+        data_types::BlockHeight,
+        identifiers::{ApplicationId, BytecodeId, ChainId, MessageId, Owner},
+    };
+
+    use super::{check_caller, check_caller_bytecode, check_signer, CallerError};
+
+    fn application_id(bytecode: &str, index: u32) -> ApplicationId {
+        ApplicationId {
+            bytecode_id: BytecodeId::new(
+                CryptoHash::test_hash(format!("{bytecode} contract")),
+                CryptoHash::test_hash(format!("{bytecode} service")),
+            ),
+            creation: MessageId {
+                chain_id: ChainId::root(0),
+                height: BlockHeight(0),
+                index,
+            },
+        }
+    }
+
+    #[test]
+    fn test_only_the_expected_caller_is_served() {
+        let expected = application_id("counter", 0);
+        let other = application_id("counter", 1);
+        assert_eq!(check_caller(Some(expected), expected), Ok(()));
+        assert_eq!(
+            check_caller(Some(other), expected),
+            Err(CallerError::UnexpectedCaller {
+                expected,
+                caller: other
+            })
+        );
+        assert_eq!(
+            check_caller(None, expected),
+            Err(CallerError::NoAuthenticatedCaller)
+        );
+    }
+
+    #[test]
+    fn test_any_instance_of_the_expected_bytecode_is_served() {
+        let caller = application_id("counter", 1);
+        let bytecode_id = application_id("counter", 0).bytecode_id;
+        let other = application_id("meta-counter", 0);
+        assert_eq!(check_caller_bytecode(Some(caller), bytecode_id), Ok(caller));
+        assert_eq!(
+            check_caller_bytecode(Some(other), bytecode_id),
+            Err(CallerError::UnexpectedCallerBytecode {
+                expected: bytecode_id,
+                caller: other
+            })
+        );
+        assert_eq!(
+            check_caller_bytecode(None, bytecode_id),
+            Err(CallerError::NoAuthenticatedCaller)
+        );
+    }
+
+    #[test]
+    fn test_the_signer_must_be_authenticated() {
+        let owner = Owner(CryptoHash::test_hash("owner"));
+        assert_eq!(check_signer(Some(owner)), Ok(owner));
+        assert_eq!(check_signer(None), Err(CallerError::NoAuthenticatedSigner));
+    }
+}
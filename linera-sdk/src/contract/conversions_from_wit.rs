@@ -5,7 +5,9 @@
 
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Amount, BlockHeight, TimeDelta, Timestamp},
+    data_types::{
+        Amount, BlockHeight, CallApplicationError, MessageLimits, RemoteValue, TimeDelta, Timestamp,
+    },
     identifiers::{ApplicationId, BytecodeId, ChainId, MessageId, Owner},
     ownership::{
         ChainOwnership, ChangeApplicationPermissionsError, CloseChainError, TimeoutConfig,
@@ -130,6 +132,37 @@ impl From<wit_system_api::ChainOwnership> for ChainOwnership {
     }
 }
 
+impl From<wit_system_api::MessageLimits> for MessageLimits {
+    fn from(limits: wit_system_api::MessageLimits) -> Self {
+        MessageLimits {
+            maximum_messages: limits.maximum_messages,
+            maximum_message_size: limits.maximum_message_size,
+            maximum_message_bytes: limits.maximum_message_bytes,
+            sent_messages: limits.sent_messages,
+            sent_message_bytes: limits.sent_message_bytes,
+        }
+    }
+}
+
+impl From<wit_system_api::RemoteValue> for RemoteValue {
+    fn from(remote_value: wit_system_api::RemoteValue) -> Self {
+        RemoteValue {
+            height: remote_value.height.into(),
+            value: remote_value.value,
+        }
+    }
+}
+
+impl From<wit_system_api::CallApplicationError> for CallApplicationError {
+    fn from(guest: wit_system_api::CallApplicationError) -> Self {
+        match guest {
+            wit_system_api::CallApplicationError::UnknownApplication(application_id) => {
+                CallApplicationError::UnknownApplication(application_id.into())
+            }
+        }
+    }
+}
+
 impl From<wit_system_api::CloseChainError> for CloseChainError {
     fn from(guest: wit_system_api::CloseChainError) -> Self {
         match guest {
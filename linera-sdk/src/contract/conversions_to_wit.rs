@@ -4,7 +4,7 @@
 //! Conversions from types declared in [`linera-sdk`] to types generated by [`wit-bindgen`].
 
 use linera_base::{
-    crypto::CryptoHash,
+    crypto::{CryptoHash, PublicKey, Signature},
     data_types::{
         Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, TimeDelta,
         Timestamp,
@@ -31,6 +31,36 @@ impl From<CryptoHash> for wit_system_api::CryptoHash {
     }
 }
 
+impl From<PublicKey> for wit_system_api::PublicKey {
+    fn from(public_key: PublicKey) -> Self {
+        let parts = <[u64; 4]>::from(public_key);
+
+        wit_system_api::PublicKey {
+            part1: parts[0],
+            part2: parts[1],
+            part3: parts[2],
+            part4: parts[3],
+        }
+    }
+}
+
+impl From<Signature> for wit_system_api::Signature {
+    fn from(signature: Signature) -> Self {
+        let parts = <[u64; 8]>::from(signature);
+
+        wit_system_api::Signature {
+            part1: parts[0],
+            part2: parts[1],
+            part3: parts[2],
+            part4: parts[3],
+            part5: parts[4],
+            part6: parts[5],
+            part7: parts[6],
+            part8: parts[7],
+        }
+    }
+}
+
 impl From<ChainId> for wit_system_api::CryptoHash {
     fn from(chain_id: ChainId) -> Self {
         chain_id.0.into()
@@ -0,0 +1,73 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed errors returned by contracts to their callers.
+
+use std::fmt::Debug;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// An error that a contract returns to its callers in its response, instead of panicking.
+///
+/// The response of an operation that can fail is then a `Result<T, Error>`, so that the
+/// users and the applications calling the contract receive the error itself rather than an
+/// opaque failure of the transaction. The errors are usually returned with
+/// [`ensure!`](crate::ensure!) and [`bail!`](crate::bail!), and this trait can be
+/// implemented with `#[derive(ContractError)]`.
+///
+/// Returning an error does not revert the changes already made by the transaction, so the
+/// errors should be returned before any change. Entrypoints that can't return an error,
+/// such as [`Contract::execute_message`](crate::Contract::execute_message), can
+/// [`abort`](ContractError::abort) the transaction instead.
+pub trait ContractError: Debug + Serialize + DeserializeOwned {
+    /// Aborts the current transaction because of this error.
+    ///
+    /// Like other panics, the error is reported to the host together with the location of
+    /// the call.
+    #[track_caller]
+    fn abort(self) -> ! {
+        panic!("{self:?}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::ContractError;
+    use crate::{bail, ensure};
+
+    #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    enum CounterError {
+        Overflow,
+        Negative { value: i64 },
+    }
+
+    impl ContractError for CounterError {}
+
+    fn increment(value: i64, increment: i64) -> Result<i64, CounterError> {
+        ensure!(increment >= 0, CounterError::Negative { value: increment });
+        match value.checked_add(increment) {
+            Some(value) => Ok(value),
+            None => bail!(CounterError::Overflow),
+        }
+    }
+
+    #[test]
+    fn test_guards_return_typed_errors() {
+        assert_eq!(increment(1, 2), Ok(3));
+        assert_eq!(increment(1, -2), Err(CounterError::Negative { value: -2 }));
+        assert_eq!(increment(i64::MAX, 1), Err(CounterError::Overflow));
+
+        // The caller can decode the error from the serialized response.
+        let bytes = bcs::to_bytes(&increment(i64::MAX, 1)).unwrap();
+        let response = bcs::from_bytes::<Result<i64, CounterError>>(&bytes).unwrap();
+        assert_eq!(response, Err(CounterError::Overflow));
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow")]
+    fn test_abort_panics_with_the_error() {
+        CounterError::Overflow.abort();
+    }
+}
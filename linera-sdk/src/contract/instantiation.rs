@@ -0,0 +1,256 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Guards ensuring that an application is only instantiated once.
+
+use linera_views::{
+    batch::Batch,
+    store::{ReadableKeyValueStore as _, WritableKeyValueStore as _},
+    views::MIN_VIEW_TAG,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use super::ok_or_report;
+use crate::{views::KeyValueStoreError, Contract, KeyValueStore};
+
+/// The key where the instantiation argument of the application is recorded.
+///
+/// Views never use keys starting with a tag below [`MIN_VIEW_TAG`], so this key can't clash
+/// with the application state stored in a root view.
+const INSTANTIATION_ARGUMENT_KEY: &[u8] = &[MIN_VIEW_TAG - 1, 0];
+
+/// An error preventing an application from being instantiated.
+#[derive(Debug, Error)]
+pub enum InstantiationError {
+    /// The application was already instantiated.
+    #[error("The application was already instantiated")]
+    AlreadyInstantiated,
+
+    /// The instantiation marker could not be accessed.
+    #[error(transparent)]
+    Storage(#[from] KeyValueStoreError),
+
+    /// The instantiation argument could not be serialized.
+    #[error("Failed to serialize the instantiation argument: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Instantiates the `contract` with the `argument`, unless the application was already
+/// instantiated.
+///
+/// Applications that expose another path to [`Contract::instantiate`], such as an operation
+/// resetting their state, should go through this function so that the existing state can't be
+/// overwritten. The `store` is the one returned by the contract runtime.
+pub async fn instantiate_once<Application: Contract>(
+    contract: &mut Application,
+    store: &KeyValueStore,
+    argument: Application::InstantiationArgument,
+) -> Result<(), InstantiationError> {
+    let serialized_argument = serde_json::to_vec(&argument)?;
+    record_instantiation(store, &serialized_argument).await?;
+    contract.instantiate(argument).await;
+    Ok(())
+}
+
+/// Records that the application is instantiated with the serialized `argument`, failing
+/// if it was already instantiated.
+pub(crate) async fn record_instantiation(
+    store: &KeyValueStore,
+    argument: &[u8],
+) -> Result<(), InstantiationError> {
+    if store.contains_key(INSTANTIATION_ARGUMENT_KEY).await? {
+        return Err(InstantiationError::AlreadyInstantiated);
+    }
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(INSTANTIATION_ARGUMENT_KEY.to_vec(), argument.to_vec());
+    store.write_batch(batch).await?;
+    Ok(())
+}
+
+/// Reads the argument the application was instantiated with, if it was recorded.
+///
+/// Fails the current operation or message if the recorded argument can't be read.
+pub(crate) async fn read_instantiation_argument<Argument: DeserializeOwned>(
+    store: &KeyValueStore,
+) -> Option<Argument> {
+    let bytes = ok_or_report(
+        store.read_value_bytes(INSTANTIATION_ARGUMENT_KEY).await,
+        "Failed to read the instantiation argument",
+    )??;
+    ok_or_report(
+        serde_json::from_slice(&bytes),
+        "Failed to deserialize instantiation argument",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        abi::{ContractAbi, WithContractAbi},
+        identifiers::ApplicationId,
+    };
+    use linera_views::views::View as _;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{util::BlockingWait, views::RegisterView, ContractRuntime};
+
+    /// A contract exposing its instantiation through an operation.
+    struct ResettableContract {
+        value: RegisterView<u64>,
+        runtime: ContractRuntime<Self>,
+        instantiations: usize,
+        last_error: Option<InstantiationError>,
+    }
+
+    struct ResettableAbi;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    enum Operation {
+        Increment(u64),
+        Reset(u64),
+    }
+
+    impl ContractAbi for ResettableAbi {
+        type Operation = Operation;
+        type Response = ();
+    }
+
+    impl WithContractAbi for ResettableContract {
+        type Abi = ResettableAbi;
+    }
+
+    impl Contract for ResettableContract {
+        type Message = ();
+        type Parameters = ();
+        type InstantiationArgument = u64;
+
+        async fn load(runtime: ContractRuntime<Self>) -> Self {
+            let value = RegisterView::load(runtime.root_view_storage_context())
+                .await
+                .expect("Failed to load state");
+            ResettableContract {
+                value,
+                runtime,
+                instantiations: 0,
+                last_error: None,
+            }
+        }
+
+        async fn instantiate(&mut self, value: u64) {
+            self.instantiations += 1;
+            self.value.set(value);
+        }
+
+        async fn execute_operation(&mut self, operation: Operation) {
+            match operation {
+                Operation::Increment(increment) => {
+                    let value = self.value.get() + increment;
+                    self.value.set(value);
+                }
+                Operation::Reset(value) => {
+                    let store = self.runtime.key_value_store();
+                    self.last_error = instantiate_once(self, &store, value).await.err();
+                }
+            }
+        }
+
+        async fn execute_message(&mut self, _message: ()) {}
+
+        async fn store(self) {}
+    }
+
+    fn create_and_instantiate(runtime: ContractRuntime<ResettableContract>) -> ResettableContract {
+        let store = runtime.key_value_store();
+        let mut contract = ResettableContract::load(runtime).blocking_wait();
+        instantiate_once(&mut contract, &store, 7)
+            .blocking_wait()
+            .expect("First instantiation should succeed");
+        assert_eq!(contract.runtime.instantiation_argument(), Some(7));
+        contract
+    }
+
+    #[test]
+    fn test_reinstantiation_by_operation_replay() {
+        let mut contract = create_and_instantiate(ContractRuntime::new());
+        contract
+            .execute_operation(Operation::Increment(3))
+            .blocking_wait();
+
+        contract
+            .execute_operation(Operation::Reset(0))
+            .blocking_wait();
+
+        assert!(matches!(
+            contract.last_error,
+            Some(InstantiationError::AlreadyInstantiated)
+        ));
+        assert_eq!(contract.instantiations, 1);
+        assert_eq!(*contract.value.get(), 10);
+        assert_eq!(contract.runtime.instantiation_argument(), Some(7));
+    }
+
+    #[test]
+    fn test_reinstantiation_by_cross_application_call() {
+        let mut contract = create_and_instantiate(
+            ContractRuntime::new().with_authenticated_caller_id(ApplicationId::default()),
+        );
+
+        contract
+            .execute_operation(Operation::Reset(0))
+            .blocking_wait();
+
+        assert!(matches!(
+            contract.last_error,
+            Some(InstantiationError::AlreadyInstantiated)
+        ));
+        assert_eq!(contract.instantiations, 1);
+        assert_eq!(*contract.value.get(), 7);
+        assert_eq!(contract.runtime.instantiation_argument(), Some(7));
+    }
+
+    /// Tests that a corrupted instantiation argument fails the contract, without panicking by
+    /// default.
+    #[cfg(not(feature = "strict-panics"))]
+    #[test]
+    fn test_corrupted_instantiation_argument_is_reported() {
+        let mut runtime = ContractRuntime::<ResettableContract>::new();
+        write_corrupted_instantiation_argument(&runtime.key_value_store());
+
+        assert_eq!(runtime.instantiation_argument(), None);
+        assert_eq!(
+            crate::failure::take_reported_failure().as_deref(),
+            Some("Failed to deserialize instantiation argument: expected value at line 1 column 1")
+        );
+    }
+
+    /// Tests that a corrupted instantiation argument aborts the contract in strict mode.
+    #[cfg(feature = "strict-panics")]
+    #[test]
+    #[should_panic(expected = "Failed to deserialize instantiation argument")]
+    fn test_corrupted_instantiation_argument_panics_in_strict_mode() {
+        let mut runtime = ContractRuntime::<ResettableContract>::new();
+        write_corrupted_instantiation_argument(&runtime.key_value_store());
+
+        runtime.instantiation_argument();
+    }
+
+    fn write_corrupted_instantiation_argument(store: &KeyValueStore) {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(
+            INSTANTIATION_ARGUMENT_KEY.to_vec(),
+            b"\x00corrupted".to_vec(),
+        );
+        store
+            .write_batch(batch)
+            .blocking_wait()
+            .expect("Failed to write to the mock store");
+    }
+
+    #[test]
+    fn test_instantiation_argument_is_missing_before_instantiation() {
+        let mut runtime = ContractRuntime::<ResettableContract>::new();
+        assert_eq!(runtime.instantiation_argument(), None);
+    }
+}
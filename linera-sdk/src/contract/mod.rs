@@ -3,8 +3,11 @@
 
 //! Types and macros useful for writing an application contract.
 
+mod caller;
 mod conversions_from_wit;
 mod conversions_to_wit;
+mod error;
+mod instantiation;
 #[cfg(not(with_testing))]
 mod runtime;
 #[cfg(with_testing)]
@@ -12,13 +15,20 @@ mod test_runtime;
 #[doc(hidden)]
 pub mod wit;
 
+use std::fmt::Display;
+
 #[cfg(not(with_testing))]
 pub use self::runtime::ContractRuntime;
 #[cfg(with_testing)]
 pub use self::test_runtime::MockContractRuntime;
 #[doc(hidden)]
 pub use self::wit::export_contract;
-use crate::{log::ContractLogger, util::BlockingWait};
+pub use self::{
+    caller::CallerError,
+    error::ContractError,
+    instantiation::{instantiate_once, InstantiationError},
+};
+use crate::{failure, log::ContractLogger, util::BlockingWait, KeyValueStore};
 
 /// Inside tests, use the [`MockContractRuntime`] instead of the real [`ContractRuntime`].
 #[cfg(with_testing)]
@@ -35,6 +45,15 @@ macro_rules! contract {
         #[doc(hidden)]
         static mut CONTRACT: Option<$contract> = None;
 
+        /// The description of the contract's ABI, read by validators when the application
+        /// is created.
+        #[doc(hidden)]
+        #[used]
+        #[cfg_attr(target_arch = "wasm32", link_section = "linera:abi")]
+        static LINERA_ABI_DESCRIPTOR: [u8; $crate::abi::AbiSchemas::encoded_len(
+            &<$contract as $crate::Contract>::ABI_SCHEMAS,
+        )] = $crate::abi::AbiSchemas::encode(&<$contract as $crate::Contract>::ABI_SCHEMAS);
+
         /// Export the contract interface.
         $crate::export_contract!($contract with_types_in $crate::contract::wit);
 
@@ -44,11 +63,18 @@ macro_rules! contract {
         {
             fn instantiate(argument: Vec<u8>) {
                 use $crate::util::BlockingWait;
+                if !$crate::contract::record_instantiation(&argument) {
+                    return;
+                }
                 $crate::contract::run_async_entrypoint::<$contract, _, _>(
                     unsafe { &mut CONTRACT },
                     move |contract| {
-                        let argument = $crate::serde_json::from_slice(&argument)
-                            .expect("Failed to deserialize instantiation argument");
+                        let Some(argument) = $crate::contract::ok_or_report(
+                            $crate::serde_json::from_slice(&argument),
+                            "Failed to deserialize instantiation argument",
+                        ) else {
+                            return;
+                        };
 
                         contract.instantiate(argument).blocking_wait()
                     },
@@ -60,14 +86,26 @@ macro_rules! contract {
                 $crate::contract::run_async_entrypoint::<$contract, _, _>(
                     unsafe { &mut CONTRACT },
                     move |contract| {
-                        let operation: <$contract as $crate::abi::ContractAbi>::Operation =
-                            $crate::bcs::from_bytes(&operation)
-                                .expect("Failed to deserialize operation");
+                        let Some(operation) = $crate::contract::ok_or_report::<
+                            <$contract as $crate::abi::ContractAbi>::Operation,
+                            _,
+                        >(
+                            <$contract as $crate::abi::ContractAbi>::deserialize_operation(
+                                &operation,
+                            ),
+                            "Failed to deserialize operation",
+                        ) else {
+                            return Vec::new();
+                        };
 
                         let response = contract.execute_operation(operation).blocking_wait();
+                        $crate::contract::check_invariants(contract);
 
-                        $crate::bcs::to_bytes(&response)
-                            .expect("Failed to serialize contract's `Response`")
+                        $crate::contract::ok_or_report(
+                            <$contract as $crate::abi::ContractAbi>::serialize_response(&response),
+                            "Failed to serialize contract's `Response`",
+                        )
+                        .unwrap_or_default()
                     },
                 )
             }
@@ -77,11 +115,18 @@ macro_rules! contract {
                 $crate::contract::run_async_entrypoint::<$contract, _, _>(
                     unsafe { &mut CONTRACT },
                     move |contract| {
-                        let message: <$contract as $crate::Contract>::Message =
-                            $crate::bcs::from_bytes(&message)
-                                .expect("Failed to deserialize message");
+                        let Some(message) = $crate::contract::ok_or_report::<
+                            <$contract as $crate::Contract>::Message,
+                            _,
+                        >(
+                            $crate::bcs::from_bytes(&message),
+                            "Failed to deserialize message",
+                        ) else {
+                            return;
+                        };
 
-                        contract.execute_message(message).blocking_wait()
+                        contract.execute_message(message).blocking_wait();
+                        $crate::contract::check_invariants(contract);
                     },
                 )
             }
@@ -93,6 +138,8 @@ macro_rules! contract {
                     .expect("Calling `store` on a `Contract` instance that wasn't loaded");
 
                 contract.store().blocking_wait();
+
+                $crate::contract::report_heap_usage();
             }
         }
 
@@ -118,5 +165,165 @@ where
     let contract =
         contract.get_or_insert_with(|| Contract::load(ContractRuntime::new()).blocking_wait());
 
-    entrypoint(contract).into()
+    let output = entrypoint(contract).into();
+
+    report_heap_usage();
+
+    output
+}
+
+/// Records that the application is instantiated with the serialized `argument`.
+///
+/// Fails before any code of the application is executed if the application was already
+/// instantiated, returning `false`.
+#[doc(hidden)]
+pub fn record_instantiation(argument: &[u8]) -> bool {
+    ok_or_report(
+        instantiation::record_instantiation(&KeyValueStore::for_contracts(), argument)
+            .blocking_wait(),
+        "Refusing to instantiate the application",
+    )
+    .is_some()
+}
+
+/// Returns the value of `result`, or fails the current operation, message or instantiation with
+/// its error, prefixed by `context`.
+///
+/// The failure is reported to the host, unless the `strict-panics` feature is enabled, in which
+/// case this function panics.
+#[doc(hidden)]
+pub fn ok_or_report<T, E: Display>(result: Result<T, E>, context: &str) -> Option<T> {
+    failure::ok_or_report(result, context, wit::contract_system_api::report_error)
+}
+
+/// Fails the current operation or message if the invariants of the `contract`'s state don't
+/// hold, if the `invariant-checks` feature is enabled.
+#[doc(hidden)]
+#[inline(always)]
+pub fn check_invariants<Contract: crate::Contract>(contract: &Contract) {
+    #[cfg(feature = "invariant-checks")]
+    ok_or_report(
+        contract.assert_invariants().blocking_wait(),
+        "Invariant violated",
+    );
+    #[cfg(not(feature = "invariant-checks"))]
+    let _ = contract;
+}
+
+/// Reports the heap usage of the contract to the host if the `profiling` feature is enabled.
+#[doc(hidden)]
+#[inline(always)]
+pub fn report_heap_usage() {
+    #[cfg(feature = "profiling")]
+    crate::profiling::report_heap_usage();
+}
+
+#[cfg(all(test, feature = "invariant-checks", not(feature = "strict-panics")))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use linera_base::abi::{ContractAbi, WithContractAbi};
+    use serde::{Deserialize, Serialize};
+
+    use super::check_invariants;
+    use crate::{failure, util::BlockingWait, Contract, ContractRuntime};
+
+    /// A token whose total supply should always be the sum of its balances.
+    struct TokenContract {
+        balances: BTreeMap<u8, u64>,
+        total_supply: u64,
+    }
+
+    struct TokenAbi;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    enum Operation {
+        Mint {
+            owner: u8,
+            amount: u64,
+        },
+        /// Mints tokens without updating the total supply.
+        BrokenMint {
+            owner: u8,
+            amount: u64,
+        },
+    }
+
+    impl ContractAbi for TokenAbi {
+        type Operation = Operation;
+        type Response = ();
+    }
+
+    impl WithContractAbi for TokenContract {
+        type Abi = TokenAbi;
+    }
+
+    impl Contract for TokenContract {
+        type Message = ();
+        type Parameters = ();
+        type InstantiationArgument = ();
+
+        async fn load(_runtime: ContractRuntime<Self>) -> Self {
+            TokenContract {
+                balances: BTreeMap::new(),
+                total_supply: 0,
+            }
+        }
+
+        async fn instantiate(&mut self, _argument: ()) {}
+
+        async fn execute_operation(&mut self, operation: Operation) {
+            match operation {
+                Operation::Mint { owner, amount } => {
+                    *self.balances.entry(owner).or_default() += amount;
+                    self.total_supply += amount;
+                }
+                Operation::BrokenMint { owner, amount } => {
+                    *self.balances.entry(owner).or_default() += amount;
+                }
+            }
+        }
+
+        async fn execute_message(&mut self, _message: ()) {}
+
+        async fn assert_invariants(&self) -> Result<(), String> {
+            let sum = self.balances.values().sum::<u64>();
+            if sum != self.total_supply {
+                return Err(format!(
+                    "total supply {} is not the sum of the balances {sum}",
+                    self.total_supply
+                ));
+            }
+            Ok(())
+        }
+
+        async fn store(self) {}
+    }
+
+    /// Tests that an operation breaking an invariant fails, while correct ones don't.
+    #[test]
+    fn test_broken_invariant_fails_the_operation() {
+        let mut contract = TokenContract::load(ContractRuntime::new()).blocking_wait();
+
+        contract
+            .execute_operation(Operation::Mint {
+                owner: 1,
+                amount: 10,
+            })
+            .blocking_wait();
+        check_invariants(&contract);
+        assert_eq!(failure::take_reported_failure(), None);
+
+        contract
+            .execute_operation(Operation::BrokenMint {
+                owner: 2,
+                amount: 5,
+            })
+            .blocking_wait();
+        check_invariants(&contract);
+        assert_eq!(
+            failure::take_reported_failure().as_deref(),
+            Some("Invariant violated: total supply 10 is not the sum of the balances 15")
+        );
+    }
 }
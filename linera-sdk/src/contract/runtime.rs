@@ -5,8 +5,10 @@
 
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
+    crypto::{BcsHashable, CryptoHash, Hashable as _, PublicKey, Signature},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, CallApplicationError, MessageLimitError,
+        MessageLimits, RemoteValue, Resources, SendMessageRequest, Timestamp,
     },
     identifiers::{
         Account, AccountOwner, ApplicationId, BytecodeId, ChainId, ChannelName, Destination,
@@ -14,10 +16,18 @@ use linera_base::{
     },
     ownership::{ChainOwnership, ChangeApplicationPermissionsError, CloseChainError},
 };
+use linera_views::batch::Batch;
 use serde::Serialize;
 
-use super::wit::contract_system_api as wit;
-use crate::{Contract, DataBlobHash, KeyValueStore, ViewStorageContext};
+use super::{
+    caller::{self, CallerError},
+    instantiation,
+    wit::{
+        contract_system_api as wit,
+        view_system_api::{self as view_wit, WriteOperation},
+    },
+};
+use crate::{util::BlockingWait, Contract, DataBlobHash, KeyValueStore, ViewStorageContext};
 
 /// The common runtime to interface with the host executing the contract.
 ///
@@ -32,6 +42,7 @@ where
     application_creator_chain_id: Option<ChainId>,
     chain_id: Option<ChainId>,
     authenticated_signer: Option<Option<Owner>>,
+    fee_payer: Option<Option<ChainId>>,
     block_height: Option<BlockHeight>,
     message_is_bouncing: Option<Option<bool>>,
     message_id: Option<Option<MessageId>>,
@@ -51,6 +62,7 @@ where
             application_creator_chain_id: None,
             chain_id: None,
             authenticated_signer: None,
+            fee_payer: None,
             block_height: None,
             message_is_bouncing: None,
             message_id: None,
@@ -69,6 +81,14 @@ where
         ViewStorageContext::new_unsafe(self.key_value_store(), Vec::new(), ())
     }
 
+    /// Returns the argument the application was instantiated with.
+    ///
+    /// Returns `None` if the application was instantiated before the argument started being
+    /// recorded.
+    pub fn instantiation_argument(&mut self) -> Option<Application::InstantiationArgument> {
+        instantiation::read_instantiation_argument(&self.key_value_store()).blocking_wait()
+    }
+
     /// Returns the application parameters provided when the application was created.
     pub fn application_parameters(&mut self) -> Application::Parameters {
         self.application_parameters
@@ -108,6 +128,14 @@ where
             .get_or_insert_with(|| wit::authenticated_signer().map(Owner::from))
     }
 
+    /// Returns the chain sponsoring the fees of the block instead of the chain, if there is
+    /// one.
+    pub fn fee_payer(&mut self) -> Option<ChainId> {
+        *self
+            .fee_payer
+            .get_or_insert_with(|| wit::fee_payer().map(ChainId::from))
+    }
+
     /// Returns the height of the current block that is executing.
     pub fn block_height(&mut self) -> BlockHeight {
         *self
@@ -117,6 +145,12 @@ where
 
     /// Returns the ID of the incoming message that is being handled, or [`None`] if not executing
     /// an incoming message.
+    ///
+    /// The ID contains the origin chain, the height of the block that created the message and
+    /// the message's index in that block. Each message is executed at most once by a receiving
+    /// chain, and messages from the same origin are executed in increasing order of their IDs.
+    /// See [`ProcessedMessages`](crate::views::ProcessedMessages) for a helper to detect
+    /// duplicate requests at the application level.
     pub fn message_id(&mut self) -> Option<MessageId> {
         *self
             .message_id
@@ -139,6 +173,30 @@ where
             .get_or_insert_with(|| wit::authenticated_caller_id().map(ApplicationId::from))
     }
 
+    /// Returns an error unless the current call comes from the application `application_id`,
+    /// and that application authenticated the call.
+    pub fn require_caller<Abi>(
+        &mut self,
+        application_id: ApplicationId<Abi>,
+    ) -> Result<(), CallerError> {
+        caller::check_caller(self.authenticated_caller_id(), application_id.forget_abi())
+    }
+
+    /// Returns the calling application if it was created from the bytecode `bytecode_id` and
+    /// authenticated the call, or an error otherwise.
+    pub fn require_caller_bytecode<Abi, Parameters, InstantiationArgument>(
+        &mut self,
+        bytecode_id: BytecodeId<Abi, Parameters, InstantiationArgument>,
+    ) -> Result<ApplicationId, CallerError> {
+        caller::check_caller_bytecode(self.authenticated_caller_id(), bytecode_id.forget_abi())
+    }
+
+    /// Returns the authenticated signer, or an error if there is none, e.g. because the
+    /// calling application did not forward the signer's authentication.
+    pub fn require_authenticated_signer(&mut self) -> Result<Owner, CallerError> {
+        caller::check_signer(self.authenticated_signer())
+    }
+
     /// Retrieves the current system time, i.e. the timestamp of the block in which this is called.
     pub fn system_time(&mut self) -> Timestamp {
         *self
@@ -156,6 +214,11 @@ where
         wit::read_owner_balance(owner.into()).into()
     }
 
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    pub fn state_size(&mut self) -> u64 {
+        wit::read_own_state_size()
+    }
+
     /// Schedules a message to be sent to this application on another chain.
     pub fn send_message(
         &mut self,
@@ -173,6 +236,66 @@ where
         MessageBuilder::new(message)
     }
 
+    /// Schedules messages to be sent to this application on other chains, each one to its own
+    /// destination.
+    ///
+    /// If the messages together exceed the [`MessageLimits`] of the current execution, the host
+    /// fails the execution without sending any of them.
+    pub fn send_messages<Recipient>(
+        &mut self,
+        messages: impl IntoIterator<Item = (Recipient, Application::Message)>,
+    ) where
+        Recipient: Into<Destination>,
+    {
+        let raw_messages = Self::messages_into_raw(messages);
+
+        wit::send_messages(&raw_messages)
+    }
+
+    /// Schedules messages to be sent to this application on other chains, each one to its own
+    /// destination, unless they exceed the [`MessageLimits`] of the current execution together.
+    ///
+    /// The limits are checked before sending any of the messages, so either all of them or none
+    /// of them are sent.
+    pub fn try_send_messages<Recipient>(
+        &mut self,
+        messages: impl IntoIterator<Item = (Recipient, Application::Message)>,
+    ) -> Result<(), MessageLimitError>
+    where
+        Recipient: Into<Destination>,
+    {
+        let raw_messages = Self::messages_into_raw(messages);
+
+        let limits = MessageLimits::from(wit::message_limits());
+        limits.check_all(
+            raw_messages
+                .iter()
+                .map(|message| message.message.len() as u64),
+        )?;
+
+        wit::send_messages(&raw_messages);
+        Ok(())
+    }
+
+    /// Serializes the `messages` into [`SendMessageRequest`]s for the host.
+    fn messages_into_raw<Recipient>(
+        messages: impl IntoIterator<Item = (Recipient, Application::Message)>,
+    ) -> Vec<wit::SendMessageRequest>
+    where
+        Recipient: Into<Destination>,
+    {
+        messages
+            .into_iter()
+            .map(|(destination, message)| MessageBuilder::new(message).into_raw(destination).into())
+            .collect()
+    }
+
+    /// Returns the limits on the messages this application can send in the current execution,
+    /// and how much of them it already used.
+    pub fn message_limits(&mut self) -> MessageLimits {
+        wit::message_limits().into()
+    }
+
     /// Subscribes to a message channel from another chain.
     pub fn subscribe(&mut self, chain: ChainId, channel: ChannelName) {
         wit::subscribe(chain.into(), &channel.into());
@@ -198,6 +321,19 @@ where
         wit::claim(source.into(), destination.into(), amount.into())
     }
 
+    /// Schedules a `message` to this application on the current chain, to be executed in the
+    /// first block whose timestamp is not earlier than `due`.
+    ///
+    /// Once due, the message must be executed by the next block of the chain, so this can be
+    /// used to implement timeouts without relying on another party.
+    ///
+    /// Scheduled messages cost fuel and count against the [`MessageLimits`]. A block can
+    /// schedule at most 100 messages, of at most 4 KiB each.
+    pub fn schedule_message(&mut self, due: Timestamp, message: Application::Message) {
+        let bytes = bcs::to_bytes(&message).expect("Failed to serialize scheduled message");
+        wit::schedule_message(due.into(), &bytes)
+    }
+
     /// Retrieves the owner configuration for the current chain.
     pub fn chain_ownership(&mut self) -> ChainOwnership {
         wit::get_chain_ownership().into()
@@ -266,20 +402,71 @@ where
     }
 
     /// Calls another application.
+    ///
+    /// Panics if the application isn't registered on the current chain.
+    ///
+    /// The operation must have the type given by the ABI of the `application` ID:
+    ///
+    /// ```no_run
+    /// # use linera_sdk::{abi::ContractAbi, base::ApplicationId, Contract, ContractRuntime};
+    /// struct CounterAbi;
+    ///
+    /// impl ContractAbi for CounterAbi {
+    ///     type Operation = u64;
+    ///     type Response = u64;
+    /// }
+    ///
+    /// fn increment<C: Contract>(
+    ///     runtime: &mut ContractRuntime<C>,
+    ///     counter: ApplicationId<CounterAbi>,
+    /// ) -> u64 {
+    ///     runtime.call_application(true, counter, &1)
+    /// }
+    /// ```
+    ///
+    /// Operations of another type are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// # use linera_sdk::{abi::ContractAbi, base::ApplicationId, Contract, ContractRuntime};
+    /// # struct CounterAbi;
+    /// # impl ContractAbi for CounterAbi {
+    /// #     type Operation = u64;
+    /// #     type Response = u64;
+    /// # }
+    /// fn increment<C: Contract>(
+    ///     runtime: &mut ContractRuntime<C>,
+    ///     counter: ApplicationId<CounterAbi>,
+    /// ) -> u64 {
+    ///     runtime.call_application(true, counter, &"increment".to_owned())
+    /// }
+    /// ```
     pub fn call_application<A: ContractAbi + Send>(
         &mut self,
         authenticated: bool,
         application: ApplicationId<A>,
         call: &A::Operation,
     ) -> A::Response {
-        let call_bytes = bcs::to_bytes(call)
+        self.try_call_application(authenticated, application, call)
+            .unwrap_or_else(|error| panic!("Failed to call application: {error}"))
+    }
+
+    /// Calls another application. Returns an error if the application isn't registered on
+    /// the current chain.
+    pub fn try_call_application<A: ContractAbi + Send>(
+        &mut self,
+        authenticated: bool,
+        application: ApplicationId<A>,
+        call: &A::Operation,
+    ) -> Result<A::Response, CallApplicationError> {
+        let call_bytes = A::serialize_operation(call)
             .expect("Failed to serialize `Operation` type for a cross-application call");
 
         let response_bytes =
-            wit::try_call_application(authenticated, application.forget_abi().into(), &call_bytes);
+            wit::try_call_application(authenticated, application.forget_abi().into(), &call_bytes)
+                .map_err(CallApplicationError::from)?;
 
-        bcs::from_bytes(&response_bytes)
-            .expect("Failed to deserialize `Response` type from cross-application call")
+        Ok(A::deserialize_response(&response_bytes)
+            .expect("Failed to deserialize `Response` type from cross-application call"))
     }
 
     /// Adds a new item to an event stream.
@@ -287,6 +474,14 @@ where
         wit::emit(&name.into(), key, value);
     }
 
+    /// Adds a new item to an event stream, with the `event` serialized using BCS.
+    ///
+    /// At most 1 000 events can be emitted in a single execution of the contract.
+    pub fn emit_event(&mut self, name: StreamName, key: &[u8], event: &impl Serialize) {
+        let value = bcs::to_bytes(event).expect("Failed to serialize event");
+        self.emit(name, key, &value);
+    }
+
     /// Queries an application service as an oracle and returns the response.
     ///
     /// Should only be used with queries where it is very likely that all validators will compute
@@ -338,6 +533,64 @@ where
     pub fn validation_round(&mut self) -> Option<u32> {
         wit::validation_round()
     }
+
+    /// Writes a `batch` of changes to the scratch space of the application.
+    ///
+    /// The scratch space is kept by each validator for data the service can use, such as
+    /// indexes or memoized results. It is not part of the state hash and may be wiped at any
+    /// time, so only the service can read it.
+    pub fn scratch_write_batch(&mut self, batch: Batch) {
+        let operations = batch
+            .operations
+            .into_iter()
+            .map(WriteOperation::from)
+            .collect::<Vec<_>>();
+        view_wit::scratch_write_batch(&operations);
+    }
+
+    /// Reads the value under `key` in the state of an application on another chain, without
+    /// waiting for a message from it.
+    ///
+    /// The state is the one after the last block of that chain whose messages the current
+    /// block receives, so it may be stale. Returns `None` if the current block receives no
+    /// messages from that chain, or if the validator executing the block doesn't have the
+    /// state of that chain at that height, e.g. because that chain has moved on since. The
+    /// host never fetches the state over the network.
+    pub fn try_read_remote_state<A>(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId<A>,
+        key: &[u8],
+    ) -> Option<RemoteValue> {
+        wit::try_read_remote_state(chain_id.into(), application_id.forget_abi().into(), key)
+            .map(RemoteValue::from)
+    }
+
+    /// Checks an Ed25519 `signature` of `message` by `public_key`.
+    ///
+    /// The check runs natively on the host, which is much cheaper than verifying the signature
+    /// in WebAssembly. Malformed public keys fail the check.
+    pub fn verify_ed25519(
+        &mut self,
+        public_key: PublicKey,
+        message: &[u8],
+        signature: Signature,
+    ) -> bool {
+        wit::verify_ed25519(public_key.into(), message, signature.into())
+    }
+
+    /// Computes the Sha3-256 digest of `data` on the host.
+    pub fn sha3_256(&mut self, data: &[u8]) -> Vec<u8> {
+        wit::sha3_256(data)
+    }
+
+    /// Computes the [`CryptoHash`] of a `value` on the host, which is the same as
+    /// [`CryptoHash::new`].
+    pub fn bcs_hash<'de, T: BcsHashable<'de>>(&mut self, value: &T) -> CryptoHash {
+        let mut bytes = Vec::new();
+        value.write(&mut bytes);
+        wit::bcs_hash(&bytes).into()
+    }
 }
 
 /// A helper type that uses the builder pattern to configure how a message is sent, and then
@@ -387,18 +640,41 @@ where
     }
 
     /// Schedules this `Message` to be sent to the `destination`.
+    ///
+    /// If the message exceeds the [`MessageLimits`] of the current execution, the host fails the
+    /// execution.
     pub fn send_to(self, destination: impl Into<Destination>) {
+        let raw_message = self.into_raw(destination);
+
+        wit::send_message(&raw_message.into())
+    }
+
+    /// Schedules this `Message` to be sent to the `destination`, unless it exceeds the
+    /// [`MessageLimits`] of the current execution.
+    ///
+    /// Unlike [`MessageBuilder::send_to`], this returns an error that the contract can handle
+    /// instead of failing the execution.
+    pub fn try_send_to(self, destination: impl Into<Destination>) -> Result<(), MessageLimitError> {
+        let raw_message = self.into_raw(destination);
+
+        let limits = MessageLimits::from(wit::message_limits());
+        limits.check(raw_message.message.len() as u64)?;
+
+        wit::send_message(&raw_message.into());
+        Ok(())
+    }
+
+    /// Serializes the message into a [`SendMessageRequest`] to the `destination`.
+    fn into_raw(self, destination: impl Into<Destination>) -> SendMessageRequest<Vec<u8>> {
         let serialized_message =
             bcs::to_bytes(&self.message).expect("Failed to serialize message to be sent");
 
-        let raw_message = SendMessageRequest {
+        SendMessageRequest {
             destination: destination.into(),
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
             message: serialized_message,
-        };
-
-        wit::send_message(&raw_message.into())
+        }
     }
 }
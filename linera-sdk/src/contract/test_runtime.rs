@@ -4,14 +4,17 @@
 //! Runtime types to simulate interfacing with the host executing the contract.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
     sync::{Arc, Mutex, MutexGuard},
 };
 
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
+    crypto::{BcsHashable, CryptoHash, PublicKey, Signature},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, CallApplicationError, MessageLimitError,
+        MessageLimits, RemoteValue, Resources, SendMessageRequest, Timestamp,
     },
     identifiers::{
         Account, AccountOwner, ApplicationId, BytecodeId, ChainId, ChannelName, Destination,
@@ -19,9 +22,19 @@ use linera_base::{
     },
     ownership::{ChainOwnership, ChangeApplicationPermissionsError, CloseChainError},
 };
+use linera_views::{
+    access_tracking::ViewAccessReport, batch::Batch, store::WritableKeyValueStore as _,
+};
 use serde::Serialize;
 
-use crate::{Contract, DataBlobHash, KeyValueStore, ViewStorageContext};
+use super::{
+    caller::{self, CallerError},
+    instantiation,
+};
+use crate::{
+    test::ExecutionOutcome, util::BlockingWait, Contract, DataBlobHash, KeyValueStore,
+    ViewStorageContext,
+};
 
 struct ExpectedCreateApplicationCall {
     bytecode_id: BytecodeId,
@@ -41,6 +54,7 @@ where
     application_creator_chain_id: Option<ChainId>,
     chain_id: Option<ChainId>,
     authenticated_signer: Option<Option<Owner>>,
+    fee_payer: Option<Option<ChainId>>,
     block_height: Option<BlockHeight>,
     round: Option<u32>,
     message_id: Option<Option<MessageId>>,
@@ -49,24 +63,30 @@ where
     timestamp: Option<Timestamp>,
     chain_balance: Option<Amount>,
     owner_balances: Option<HashMap<AccountOwner, Amount>>,
+    state_size: Option<u64>,
     chain_ownership: Option<ChainOwnership>,
     can_close_chain: Option<bool>,
     can_change_application_permissions: Option<bool>,
     call_application_handler: Option<CallApplicationHandler>,
+    unregistered_applications: HashSet<ApplicationId>,
     send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Application::Message>>>>,
+    message_limits: Arc<Mutex<MessageLimits>>,
     subscribe_requests: Vec<(ChainId, ChannelName)>,
     unsubscribe_requests: Vec<(ChainId, ChannelName)>,
     outgoing_transfers: HashMap<Account, Amount>,
     events: Vec<(StreamName, Vec<u8>, Vec<u8>)>,
     claim_requests: Vec<ClaimRequest>,
+    scheduled_messages: Vec<(Timestamp, Application::Message)>,
     expected_service_queries: VecDeque<(ApplicationId, String, String)>,
     expected_post_requests: VecDeque<(String, Vec<u8>, Vec<u8>)>,
     expected_read_data_blob_requests: VecDeque<(DataBlobHash, Vec<u8>)>,
     expected_assert_data_blob_exists_requests: VecDeque<(DataBlobHash, Option<()>)>,
+    expected_remote_state_reads: VecDeque<(ChainId, ApplicationId, Vec<u8>, Option<RemoteValue>)>,
     expected_open_chain_calls:
         VecDeque<(ChainOwnership, ApplicationPermissions, Amount, MessageId)>,
     expected_create_application_calls: VecDeque<ExpectedCreateApplicationCall>,
     key_value_store: KeyValueStore,
+    scratch: KeyValueStore,
 }
 
 impl<Application> Default for MockContractRuntime<Application>
@@ -90,6 +110,7 @@ where
             application_creator_chain_id: None,
             chain_id: None,
             authenticated_signer: None,
+            fee_payer: None,
             block_height: None,
             round: None,
             message_id: None,
@@ -98,23 +119,29 @@ where
             timestamp: None,
             chain_balance: None,
             owner_balances: None,
+            state_size: None,
             chain_ownership: None,
             can_close_chain: None,
             can_change_application_permissions: None,
             call_application_handler: None,
+            unregistered_applications: HashSet::new(),
             send_message_requests: Arc::default(),
+            message_limits: Arc::default(),
             subscribe_requests: Vec::new(),
             unsubscribe_requests: Vec::new(),
             outgoing_transfers: HashMap::new(),
             events: Vec::new(),
             claim_requests: Vec::new(),
+            scheduled_messages: Vec::new(),
             expected_service_queries: VecDeque::new(),
             expected_post_requests: VecDeque::new(),
             expected_read_data_blob_requests: VecDeque::new(),
             expected_assert_data_blob_exists_requests: VecDeque::new(),
+            expected_remote_state_reads: VecDeque::new(),
             expected_open_chain_calls: VecDeque::new(),
             expected_create_application_calls: VecDeque::new(),
             key_value_store: KeyValueStore::mock().to_mut(),
+            scratch: KeyValueStore::mock().to_mut(),
         }
     }
 
@@ -128,6 +155,40 @@ where
         ViewStorageContext::new_unsafe(self.key_value_store(), Vec::new(), ())
     }
 
+    /// Returns the accesses to the application's storage recorded so far, grouped by
+    /// sub-view.
+    pub fn view_access_report(&self) -> ViewAccessReport {
+        self.key_value_store.view_access_report()
+    }
+
+    /// Returns the key-value store holding the scratch space of the application.
+    pub fn scratch_key_value_store(&self) -> KeyValueStore {
+        self.scratch.clone()
+    }
+
+    /// Writes a `batch` of changes to the scratch space of the application.
+    pub fn scratch_write_batch(&mut self, batch: Batch) {
+        self.scratch
+            .write_batch(batch)
+            .blocking_wait()
+            .expect("Failed to write to the mock scratch space");
+    }
+
+    /// Clears the scratch space of the application, like a validator may do at any time.
+    pub fn wipe_scratch(&mut self) {
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(Vec::new());
+        self.scratch_write_batch(batch);
+    }
+
+    /// Returns the argument the application was instantiated with.
+    ///
+    /// Returns `None` if the application was instantiated before the argument started being
+    /// recorded.
+    pub fn instantiation_argument(&mut self) -> Option<Application::InstantiationArgument> {
+        instantiation::read_instantiation_argument(&self.key_value_store()).blocking_wait()
+    }
+
     /// Configures the application parameters to return during the test.
     pub fn with_application_parameters(
         mut self,
@@ -243,6 +304,27 @@ where
         )
     }
 
+    /// Configures the chain sponsoring the fees of the block to return during the test.
+    pub fn with_fee_payer(mut self, fee_payer: impl Into<Option<ChainId>>) -> Self {
+        self.fee_payer = Some(fee_payer.into());
+        self
+    }
+
+    /// Configures the chain sponsoring the fees of the block to return during the test.
+    pub fn set_fee_payer(&mut self, fee_payer: impl Into<Option<ChainId>>) -> &mut Self {
+        self.fee_payer = Some(fee_payer.into());
+        self
+    }
+
+    /// Returns the chain sponsoring the fees of the block instead of the chain, if there is
+    /// one.
+    pub fn fee_payer(&mut self) -> Option<ChainId> {
+        self.fee_payer.expect(
+            "Fee payer has not been mocked, \
+            please call `MockContractRuntime::set_fee_payer` first",
+        )
+    }
+
     /// Configures the block height to return during the test.
     pub fn with_block_height(mut self, block_height: BlockHeight) -> Self {
         self.block_height = Some(block_height);
@@ -350,6 +432,30 @@ where
         )
     }
 
+    /// Returns an error unless the current call comes from the application `application_id`,
+    /// and that application authenticated the call.
+    pub fn require_caller<Abi>(
+        &mut self,
+        application_id: ApplicationId<Abi>,
+    ) -> Result<(), CallerError> {
+        caller::check_caller(self.authenticated_caller_id(), application_id.forget_abi())
+    }
+
+    /// Returns the calling application if it was created from the bytecode `bytecode_id` and
+    /// authenticated the call, or an error otherwise.
+    pub fn require_caller_bytecode<Abi, Parameters, InstantiationArgument>(
+        &mut self,
+        bytecode_id: BytecodeId<Abi, Parameters, InstantiationArgument>,
+    ) -> Result<ApplicationId, CallerError> {
+        caller::check_caller_bytecode(self.authenticated_caller_id(), bytecode_id.forget_abi())
+    }
+
+    /// Returns the authenticated signer, or an error if there is none, e.g. because the
+    /// calling application did not forward the signer's authentication.
+    pub fn require_authenticated_signer(&mut self) -> Result<Owner, CallerError> {
+        caller::check_signer(self.authenticated_signer())
+    }
+
     /// Configures the system time to return during the test.
     pub fn with_system_time(mut self, timestamp: Timestamp) -> Self {
         self.timestamp = Some(timestamp);
@@ -395,6 +501,26 @@ where
         )
     }
 
+    /// Configures the size of the application's stored state to return during the test.
+    pub fn with_state_size(mut self, state_size: u64) -> Self {
+        self.state_size = Some(state_size);
+        self
+    }
+
+    /// Configures the size of the application's stored state to return during the test.
+    pub fn set_state_size(&mut self, state_size: u64) -> &mut Self {
+        self.state_size = Some(state_size);
+        self
+    }
+
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    pub fn state_size(&mut self) -> u64 {
+        self.state_size.expect(
+            "State size has not been mocked, \
+            please call `MockContractRuntime::set_state_size` first",
+        )
+    }
+
     /// Configures the balances on the chain to use during the test.
     pub fn with_owner_balances(
         mut self,
@@ -464,7 +590,99 @@ where
         &mut self,
         message: Application::Message,
     ) -> MessageBuilder<Application::Message> {
-        MessageBuilder::new(message, self.send_message_requests.clone())
+        MessageBuilder::new(
+            message,
+            self.send_message_requests.clone(),
+            self.message_limits.clone(),
+        )
+    }
+
+    /// Schedules messages to be sent to this application on other chains, each one to its own
+    /// destination.
+    ///
+    /// # Panics
+    ///
+    /// If the messages together exceed the configured [`MessageLimits`], like the host would fail
+    /// the execution.
+    pub fn send_messages<Recipient>(
+        &mut self,
+        messages: impl IntoIterator<Item = (Recipient, Application::Message)>,
+    ) where
+        Recipient: Into<Destination>,
+    {
+        if let Err(error) = self.try_send_messages(messages) {
+            panic!("{error}");
+        }
+    }
+
+    /// Schedules messages to be sent to this application on other chains, each one to its own
+    /// destination, unless they exceed the configured [`MessageLimits`] together.
+    ///
+    /// Either all of the messages or none of them are sent.
+    pub fn try_send_messages<Recipient>(
+        &mut self,
+        messages: impl IntoIterator<Item = (Recipient, Application::Message)>,
+    ) -> Result<(), MessageLimitError>
+    where
+        Recipient: Into<Destination>,
+    {
+        let requests = messages
+            .into_iter()
+            .map(|(destination, message)| SendMessageRequest {
+                destination: destination.into(),
+                authenticated: false,
+                is_tracked: false,
+                grant: Resources::default(),
+                message,
+            })
+            .collect::<Vec<_>>();
+        let sizes = requests
+            .iter()
+            .map(|request| {
+                bcs::serialized_size(&request.message).expect("Failed to serialize message") as u64
+            })
+            .collect::<Vec<_>>();
+
+        let mut limits = self.lock_message_limits();
+        limits.check_all(sizes.iter().copied())?;
+        for size in sizes {
+            limits.record(size);
+        }
+        drop(limits);
+
+        self.created_send_message_requests().extend(requests);
+        Ok(())
+    }
+
+    /// Configures the limits on the messages the application can send during the test.
+    ///
+    /// The `sent_messages` and `sent_message_bytes` of the `limits` are updated as messages
+    /// are sent. By default, there are no limits.
+    pub fn with_message_limits(self, limits: MessageLimits) -> Self {
+        *self.lock_message_limits() = limits;
+        self
+    }
+
+    /// Configures the limits on the messages the application can send during the test.
+    ///
+    /// The `sent_messages` and `sent_message_bytes` of the `limits` are updated as messages
+    /// are sent. By default, there are no limits.
+    pub fn set_message_limits(&mut self, limits: MessageLimits) -> &mut Self {
+        *self.lock_message_limits() = limits;
+        self
+    }
+
+    /// Returns the limits on the messages this application can send in the current execution,
+    /// and how much of them it already used.
+    pub fn message_limits(&mut self) -> MessageLimits {
+        *self.lock_message_limits()
+    }
+
+    /// Locks the shared [`MessageLimits`] of the test.
+    fn lock_message_limits(&self) -> MutexGuard<'_, MessageLimits> {
+        self.message_limits
+            .try_lock()
+            .expect("Unit test should be single-threaded")
     }
 
     /// Returns the list of [`SendMessageRequest`]s created so far during the test.
@@ -476,6 +694,22 @@ where
             .expect("Unit test should be single-threaded")
     }
 
+    /// Returns the messages sent and the events emitted so far during the test, and clears them
+    /// so that the next call only returns the ones produced afterwards.
+    ///
+    /// The usage of the [`MessageLimits`] is reset as well, since the next messages belong to
+    /// another execution.
+    pub fn take_execution_outcome(&mut self) -> ExecutionOutcome<Application::Message> {
+        let messages = mem::take(&mut *self.created_send_message_requests());
+        {
+            let mut limits = self.lock_message_limits();
+            limits.sent_messages = 0;
+            limits.sent_message_bytes = 0;
+        }
+        let events = mem::take(&mut self.events);
+        ExecutionOutcome::new(messages, events)
+    }
+
     /// Subscribes to a message channel from another chain.
     pub fn subscribe(&mut self, chain: ChainId, channel: ChannelName) {
         self.subscribe_requests.push((chain, channel));
@@ -564,6 +798,17 @@ where
         &self.claim_requests
     }
 
+    /// Schedules a `message` to this application on the current chain, to be executed in the
+    /// first block whose timestamp is not earlier than `due`.
+    pub fn schedule_message(&mut self, due: Timestamp, message: Application::Message) {
+        self.scheduled_messages.push((due, message));
+    }
+
+    /// Returns the messages scheduled during the test so far, with their due timestamps.
+    pub fn scheduled_messages(&self) -> &[(Timestamp, Application::Message)] {
+        &self.scheduled_messages
+    }
+
     /// Configures the chain ownership configuration to return during the test.
     pub fn with_chain_ownership(mut self, chain_ownership: ChainOwnership) -> Self {
         self.chain_ownership = Some(chain_ownership);
@@ -771,24 +1016,92 @@ where
         self
     }
 
+    /// Configures an application as not registered on the chain, so that calls to it fail.
+    pub fn with_unregistered_application<A>(mut self, application: ApplicationId<A>) -> Self {
+        self.unregistered_applications
+            .insert(application.forget_abi());
+        self
+    }
+
+    /// Configures an application as not registered on the chain, so that calls to it fail.
+    pub fn set_unregistered_application<A>(&mut self, application: ApplicationId<A>) -> &mut Self {
+        self.unregistered_applications
+            .insert(application.forget_abi());
+        self
+    }
+
     /// Calls another application.
+    ///
+    /// Panics if the application isn't registered on the current chain.
+    ///
+    /// The operation must have the type given by the ABI of the `application` ID:
+    ///
+    /// ```no_run
+    /// # use linera_sdk::{abi::ContractAbi, base::ApplicationId, Contract, ContractRuntime};
+    /// struct CounterAbi;
+    ///
+    /// impl ContractAbi for CounterAbi {
+    ///     type Operation = u64;
+    ///     type Response = u64;
+    /// }
+    ///
+    /// fn increment<C: Contract>(
+    ///     runtime: &mut ContractRuntime<C>,
+    ///     counter: ApplicationId<CounterAbi>,
+    /// ) -> u64 {
+    ///     runtime.call_application(true, counter, &1)
+    /// }
+    /// ```
+    ///
+    /// Operations of another type are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// # use linera_sdk::{abi::ContractAbi, base::ApplicationId, Contract, ContractRuntime};
+    /// # struct CounterAbi;
+    /// # impl ContractAbi for CounterAbi {
+    /// #     type Operation = u64;
+    /// #     type Response = u64;
+    /// # }
+    /// fn increment<C: Contract>(
+    ///     runtime: &mut ContractRuntime<C>,
+    ///     counter: ApplicationId<CounterAbi>,
+    /// ) -> u64 {
+    ///     runtime.call_application(true, counter, &"increment".to_owned())
+    /// }
+    /// ```
     pub fn call_application<A: ContractAbi + Send>(
         &mut self,
         authenticated: bool,
         application: ApplicationId<A>,
         call: &A::Operation,
     ) -> A::Response {
-        let call_bytes = bcs::to_bytes(call)
+        self.try_call_application(authenticated, application, call)
+            .unwrap_or_else(|error| panic!("Failed to call application: {error}"))
+    }
+
+    /// Calls another application. Returns an error if the application isn't registered on
+    /// the current chain.
+    pub fn try_call_application<A: ContractAbi + Send>(
+        &mut self,
+        authenticated: bool,
+        application: ApplicationId<A>,
+        call: &A::Operation,
+    ) -> Result<A::Response, CallApplicationError> {
+        let application_id = application.forget_abi();
+        if self.unregistered_applications.contains(&application_id) {
+            return Err(CallApplicationError::UnknownApplication(application_id));
+        }
+        let call_bytes = A::serialize_operation(call)
             .expect("Failed to serialize `Operation` type for a cross-application call");
 
         let handler = self.call_application_handler.as_mut().expect(
             "Handler for `call_application` has not been mocked, \
             please call `MockContractRuntime::set_call_application_handler` first",
         );
-        let response_bytes = handler(authenticated, application.forget_abi(), call_bytes);
+        let response_bytes = handler(authenticated, application_id, call_bytes);
 
-        bcs::from_bytes(&response_bytes)
-            .expect("Failed to deserialize `Response` type from cross-application call")
+        Ok(A::deserialize_response(&response_bytes)
+            .expect("Failed to deserialize `Response` type from cross-application call"))
     }
 
     /// Adds a new item to an event stream.
@@ -796,6 +1109,12 @@ where
         self.events.push((name, key.to_vec(), value.to_vec()));
     }
 
+    /// Adds a new item to an event stream, with the `event` serialized using BCS.
+    pub fn emit_event(&mut self, name: StreamName, key: &[u8], event: &impl Serialize) {
+        let value = bcs::to_bytes(event).expect("Failed to serialize event");
+        self.emit(name, key, &value);
+    }
+
     /// Adds an expected `query_service` call`, and the response it should return in the test.
     pub fn add_expected_service_query<A: ServiceAbi + Send>(
         &mut self,
@@ -831,6 +1150,23 @@ where
             .push_back((hash, response));
     }
 
+    /// Adds an expected `try_read_remote_state` call, and the response it should return in the
+    /// test.
+    pub fn add_expected_remote_state_read<A>(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId<A>,
+        key: Vec<u8>,
+        response: Option<RemoteValue>,
+    ) {
+        self.expected_remote_state_reads.push_back((
+            chain_id,
+            application_id.forget_abi(),
+            key,
+            response,
+        ));
+    }
+
     /// Queries an application service as an oracle and returns the response.
     ///
     /// Should only be used with queries where it is very likely that all validators will compute
@@ -898,6 +1234,50 @@ where
     pub fn validation_round(&mut self) -> Option<u32> {
         self.round
     }
+
+    /// Reads the value under `key` in the state of an application on another chain, without
+    /// waiting for a message from it.
+    ///
+    /// The state is the one after the last block of that chain whose messages the current
+    /// block receives, so it may be stale. Returns `None` if the current block receives no
+    /// messages from that chain. If a validator doesn't have the state of that chain at that
+    /// height, the block fails on it instead.
+    pub fn try_read_remote_state<A>(
+        &mut self,
+        chain_id: ChainId,
+        application_id: ApplicationId<A>,
+        key: &[u8],
+    ) -> Option<RemoteValue> {
+        let maybe_read = self.expected_remote_state_reads.pop_front();
+        let (expected_chain_id, expected_application_id, expected_key, response) =
+            maybe_read.expect("Unexpected try_read_remote_state call");
+        assert_eq!(chain_id, expected_chain_id);
+        assert_eq!(application_id.forget_abi(), expected_application_id);
+        assert_eq!(key, expected_key);
+        response
+    }
+
+    /// Checks an Ed25519 `signature` of `message` by `public_key`.
+    ///
+    /// Malformed public keys fail the check.
+    pub fn verify_ed25519(
+        &mut self,
+        public_key: PublicKey,
+        message: &[u8],
+        signature: Signature,
+    ) -> bool {
+        signature.verify_raw_bytes(message, public_key)
+    }
+
+    /// Computes the Sha3-256 digest of `data`.
+    pub fn sha3_256(&mut self, data: &[u8]) -> Vec<u8> {
+        CryptoHash::from_raw_bytes(data).as_bytes().to_vec()
+    }
+
+    /// Computes the [`CryptoHash`] of a `value`, which is the same as [`CryptoHash::new`].
+    pub fn bcs_hash<'de, T: BcsHashable<'de>>(&mut self, value: &T) -> CryptoHash {
+        CryptoHash::new(value)
+    }
 }
 
 /// A type alias for the handler for cross-application calls.
@@ -915,6 +1295,7 @@ where
     grant: Resources,
     message: Message,
     send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Message>>>>,
+    message_limits: Arc<Mutex<MessageLimits>>,
 }
 
 impl<Message> MessageBuilder<Message>
@@ -925,6 +1306,7 @@ where
     pub(crate) fn new(
         message: Message,
         send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Message>>>>,
+        message_limits: Arc<Mutex<MessageLimits>>,
     ) -> Self {
         MessageBuilder {
             authenticated: false,
@@ -932,6 +1314,7 @@ where
             grant: Resources::default(),
             message,
             send_message_requests,
+            message_limits,
         }
     }
 
@@ -955,7 +1338,28 @@ where
     }
 
     /// Schedules this `Message` to be sent to the `destination`.
+    ///
+    /// # Panics
+    ///
+    /// If the message exceeds the configured [`MessageLimits`], like the host would fail the
+    /// execution.
     pub fn send_to(self, destination: impl Into<Destination>) {
+        if let Err(error) = self.try_send_to(destination) {
+            panic!("{error}");
+        }
+    }
+
+    /// Schedules this `Message` to be sent to the `destination`, unless it exceeds the
+    /// configured [`MessageLimits`].
+    pub fn try_send_to(self, destination: impl Into<Destination>) -> Result<(), MessageLimitError> {
+        let size = bcs::serialized_size(&self.message).expect("Failed to serialize message") as u64;
+        let mut limits = self
+            .message_limits
+            .try_lock()
+            .expect("Unit test should be single-threaded");
+        limits.check(size)?;
+        limits.record(size);
+
         let request = SendMessageRequest {
             destination: destination.into(),
             authenticated: self.authenticated,
@@ -968,6 +1372,7 @@ where
             .try_lock()
             .expect("Unit test should be single-threaded")
             .push(request);
+        Ok(())
     }
 }
 
@@ -978,3 +1383,130 @@ pub struct ClaimRequest {
     destination: Account,
     amount: Amount,
 }
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        abi::{ContractAbi, WithContractAbi},
+        data_types::{MessageLimitError, MessageLimits},
+        identifiers::ChainId,
+    };
+
+    use super::MockContractRuntime;
+    use crate::Contract;
+
+    /// A contract that only sends raw bytes as messages.
+    struct SenderContract;
+
+    struct SenderAbi;
+
+    impl ContractAbi for SenderAbi {
+        type Operation = ();
+        type Response = ();
+    }
+
+    impl WithContractAbi for SenderContract {
+        type Abi = SenderAbi;
+    }
+
+    impl Contract for SenderContract {
+        type Message = Vec<u8>;
+        type Parameters = ();
+        type InstantiationArgument = ();
+
+        async fn load(_runtime: MockContractRuntime<Self>) -> Self {
+            SenderContract
+        }
+
+        async fn instantiate(&mut self, _argument: ()) {}
+
+        async fn execute_operation(&mut self, _operation: ()) {}
+
+        async fn execute_message(&mut self, _message: Vec<u8>) {}
+
+        async fn store(self) {}
+    }
+
+    /// Returns a runtime allowing two messages of up to 10 bytes, with 15 bytes in total.
+    ///
+    /// A message of `n` bytes is serialized with a one-byte length prefix.
+    fn limited_runtime() -> MockContractRuntime<SenderContract> {
+        MockContractRuntime::new().with_message_limits(MessageLimits {
+            maximum_messages: 2,
+            maximum_message_size: 10,
+            maximum_message_bytes: 15,
+            ..MessageLimits::default()
+        })
+    }
+
+    #[test]
+    fn test_message_limits_are_checked_before_sending() {
+        let mut runtime = limited_runtime();
+        let destination = ChainId::root(1);
+
+        assert_eq!(
+            runtime
+                .prepare_message(vec![0; 10])
+                .try_send_to(destination),
+            Err(MessageLimitError::MessageTooLarge {
+                size: 11,
+                maximum_size: 10
+            })
+        );
+        runtime.prepare_message(vec![0; 9]).send_to(destination);
+        assert_eq!(
+            runtime.prepare_message(vec![0; 5]).try_send_to(destination),
+            Err(MessageLimitError::TooManyMessageBytes { maximum_bytes: 15 })
+        );
+        assert_eq!(
+            runtime.prepare_message(vec![0; 4]).try_send_to(destination),
+            Ok(())
+        );
+        assert_eq!(
+            runtime.prepare_message(vec![]).try_send_to(destination),
+            Err(MessageLimitError::TooManyMessages {
+                maximum_messages: 2
+            })
+        );
+
+        let limits = runtime.message_limits();
+        assert_eq!(limits.sent_messages, 2);
+        assert_eq!(limits.sent_message_bytes, 15);
+        assert_eq!(runtime.take_execution_outcome().messages().len(), 2);
+        assert_eq!(runtime.message_limits().sent_messages, 0);
+    }
+
+    #[test]
+    fn test_batch_of_messages_is_sent_entirely_or_not_at_all() {
+        let mut runtime = limited_runtime();
+
+        assert_eq!(
+            runtime.try_send_messages([
+                (ChainId::root(1), vec![0; 9]),
+                (ChainId::root(2), vec![0; 5])
+            ]),
+            Err(MessageLimitError::TooManyMessageBytes { maximum_bytes: 15 })
+        );
+        assert_eq!(runtime.message_limits().sent_messages, 0);
+        assert!(runtime.created_send_message_requests().is_empty());
+
+        runtime.send_messages([(ChainId::root(1), vec![1]), (ChainId::root(2), vec![2, 2])]);
+
+        let requests = runtime.created_send_message_requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].destination, ChainId::root(1).into());
+        assert_eq!(requests[0].message, vec![1]);
+        assert_eq!(requests[1].destination, ChainId::root(2).into());
+        assert_eq!(requests[1].message, vec![2, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Applications can send at most 2 messages per execution")]
+    fn test_sending_too_many_messages_panics() {
+        let mut runtime = limited_runtime();
+
+        for _ in 0..3 {
+            runtime.send_message(ChainId::root(1), vec![]);
+        }
+    }
+}
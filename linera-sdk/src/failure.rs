@@ -0,0 +1,102 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Failures of an application caused by malformed inputs, outputs or state, rather than by the
+//! logic of the application itself.
+//!
+//! By default, the failure is reported to the host, which fails the current operation, message
+//! or query with a user error, and the SDK stops running the application. With the
+//! `strict-panics` feature, the SDK panics instead, aborting the instance with a trap.
+
+#[cfg(with_testing)]
+use std::cell::RefCell;
+use std::fmt::Display;
+
+#[cfg(with_testing)]
+thread_local! {
+    /// The last failure reported in this thread, since the host is not available in tests.
+    static REPORTED_FAILURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Returns the value of `result`, or fails with its error, prefixed by `context`.
+///
+/// The `report` function sends the failure message to the host.
+pub(crate) fn ok_or_report<T, E: Display>(
+    result: Result<T, E>,
+    context: &str,
+    report: impl FnOnce(&str),
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            fail(&format!("{context}: {error}"), report);
+            None
+        }
+    }
+}
+
+/// Fails with the `message`, using `report` to send it to the host unless strict panics are
+/// enabled.
+pub(crate) fn fail(message: &str, report: impl FnOnce(&str)) {
+    if cfg!(feature = "strict-panics") {
+        panic!("{message}");
+    }
+
+    #[cfg(not(with_testing))]
+    report(message);
+
+    #[cfg(with_testing)]
+    {
+        let _ = report;
+        REPORTED_FAILURE.with(|failure| *failure.borrow_mut() = Some(message.to_owned()));
+    }
+}
+
+/// Returns the last failure reported in this thread, if any.
+#[cfg(test)]
+pub(crate) fn take_reported_failure() -> Option<String> {
+    REPORTED_FAILURE.with(|failure| failure.borrow_mut().take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that errors are reported as failures by default.
+    #[cfg(not(feature = "strict-panics"))]
+    #[test]
+    fn error_is_reported() {
+        let result: Result<u8, _> = Err("unexpected end of input");
+
+        assert_eq!(
+            ok_or_report(result, "Failed to deserialize operation", |_| ()),
+            None
+        );
+        assert_eq!(
+            take_reported_failure().as_deref(),
+            Some("Failed to deserialize operation: unexpected end of input")
+        );
+    }
+
+    /// Tests that values are returned without reporting a failure.
+    #[test]
+    fn value_is_returned() {
+        let result: Result<u8, &str> = Ok(7);
+
+        assert_eq!(
+            ok_or_report(result, "Failed to deserialize operation", |_| ()),
+            Some(7)
+        );
+        assert_eq!(take_reported_failure(), None);
+    }
+
+    /// Tests that errors abort with a panic in strict mode.
+    #[cfg(feature = "strict-panics")]
+    #[test]
+    #[should_panic(expected = "Failed to deserialize operation: unexpected end of input")]
+    fn error_panics_in_strict_mode() {
+        let result: Result<u8, _> = Err("unexpected end of input");
+
+        ok_or_report(result, "Failed to deserialize operation", |_| ());
+    }
+}
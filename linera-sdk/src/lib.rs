@@ -35,10 +35,14 @@ pub mod contract;
 #[cfg(feature = "ethereum")]
 pub mod ethereum;
 mod extensions;
+mod failure;
 pub mod graphql;
 mod log;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reproducible_build;
 pub mod service;
-#[cfg(with_testing)]
 pub mod test;
 pub mod views;
 
@@ -46,22 +50,23 @@ use std::fmt::Debug;
 
 pub use bcs;
 pub use linera_base::{
-    abi,
-    data_types::{Resources, SendMessageRequest},
+    abi, bail,
+    data_types::{MessageLimitError, MessageLimits, Resources, SendMessageRequest},
     ensure,
 };
 use linera_base::{
-    abi::{ContractAbi, ServiceAbi, WithContractAbi, WithServiceAbi},
+    abi::{AbiSchemas, ContractAbi, ServiceAbi, WithContractAbi, WithServiceAbi},
     crypto::CryptoHash,
     doc_scalar,
 };
+pub use linera_sdk_derive::{AbiSchema, ContractAbi, ContractError};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 pub use serde_json;
 
 #[doc(hidden)]
 pub use self::{contract::export_contract, service::export_service};
 pub use self::{
-    contract::ContractRuntime,
+    contract::{CallerError, ContractError, ContractRuntime},
     extensions::{FromBcsBytes, ToBcsBytes},
     log::{ContractLogger, ServiceLogger},
     service::ServiceRuntime,
@@ -100,13 +105,24 @@ pub trait Contract: WithContractAbi + ContractAbi + Sized {
     /// instead.
     type InstantiationArgument: Serialize + DeserializeOwned + Debug;
 
+    /// The schemas of the types of the application, embedded in the contract bytecode.
+    ///
+    /// When set, for instance with [`AbiSchemas::new`], validators reject the creation of the
+    /// application if its parameters or instantiation argument don't match the schemas.
+    /// The types implement [`abi::AbiSchema`], which can be derived with
+    /// `#[derive(AbiSchema)]`.
+    const ABI_SCHEMAS: Option<AbiSchemas> = None;
+
     /// Creates an in-memory instance of the contract handler.
     async fn load(runtime: ContractRuntime<Self>) -> Self;
 
     /// Instantiates the application on the chain that created it.
     ///
     /// This is only called once when the application is created and only on the microchain that
-    /// created the application.
+    /// created the application. The argument is recorded, so that later attempts to instantiate
+    /// the application fail without calling this method, and can be read back with
+    /// [`ContractRuntime::instantiation_argument`]. Other paths leading to this method should
+    /// go through [`contract::instantiate_once`].
     async fn instantiate(&mut self, argument: Self::InstantiationArgument);
 
     /// Applies an operation from the current block.
@@ -128,6 +144,17 @@ pub trait Contract: WithContractAbi + ContractAbi + Sized {
     /// chain.
     async fn execute_message(&mut self, message: Self::Message);
 
+    /// Checks that the invariants of the application's state hold, e.g. that the total supply
+    /// of a token is the sum of its balances.
+    ///
+    /// This is only called if the SDK's `invariant-checks` feature is enabled, after each
+    /// operation and message executed without failing, so it costs nothing otherwise. If an
+    /// invariant doesn't hold, the operation or message fails with the returned error, and the
+    /// changes it made to the state are not saved.
+    async fn assert_invariants(&self) -> Result<(), String> {
+        Ok(())
+    }
+
     /// Finishes the execution of the current transaction.
     ///
     /// This is called once at the end of the transaction, to allow all applications that
@@ -148,6 +175,17 @@ pub trait Service: WithServiceAbi + ServiceAbi + Sized {
     /// Immutable parameters specific to this application.
     type Parameters: Serialize + DeserializeOwned + Send + Sync + Clone + Debug + 'static;
 
+    /// Deprecated names of query variants and fields, paired with their current names.
+    ///
+    /// Incoming JSON queries using a deprecated name are rewritten to use the current name
+    /// before being deserialized, so that renaming a [`ServiceAbi::Query`] variant or field
+    /// doesn't break existing clients. A warning is logged whenever a deprecated name is used.
+    ///
+    /// A name is only rewritten where the query type declares a variant or field with the
+    /// current name, at any depth. Keys of maps are left as they are, and so is anything below
+    /// a type that doesn't declare its names, such as an untagged enum.
+    const DEPRECATED_QUERY_NAMES: &'static [(&'static str, &'static str)] = &[];
+
     /// Creates an in-memory instance of the service handler.
     async fn new(runtime: ServiceRuntime<Self>) -> Self;
 
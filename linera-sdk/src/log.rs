@@ -25,7 +25,7 @@ impl ContractLogger {
         INSTALL_LOGGER.call_once(|| {
             log::set_logger(&CONTRACT_LOGGER).expect("Failed to initialize contract logger");
             log::set_max_level(LevelFilter::Trace);
-            panic::set_hook(Box::new(log_panic));
+            panic::set_hook(Box::new(report_contract_panic));
         });
     }
 }
@@ -73,3 +73,25 @@ impl Log for ServiceLogger {
 fn log_panic(info: &PanicHookInfo<'_>) {
     log::error!("{info}");
 }
+
+/// Logs a panic of a contract, and reports it to the host with its location, so that the
+/// trap following the panic fails the transaction with a user error.
+fn report_contract_panic(info: &PanicHookInfo<'_>) {
+    log_panic(info);
+    let (file, line, column) = info.location().map_or(("<unknown>", 0, 0), |location| {
+        (location.file(), location.line(), location.column())
+    });
+    contract_system_api::report_panic(&panic_message(info), file, line, column);
+}
+
+/// Returns the message of a panic, if it has one.
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Heap profiling of applications, enabled with the `profiling` feature.
+//!
+//! When the feature is enabled, the SDK installs a global allocator that keeps track of the
+//! heap usage of the application, and contracts report it to the host after each entrypoint
+//! call. Applications that declare their own `#[global_allocator]` can't enable this feature.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+#[global_allocator]
+static ALLOCATOR: ProfilingAllocator<System> = ProfilingAllocator::new(System);
+
+/// A snapshot of the heap usage of the application.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HeapUsage {
+    /// The number of bytes currently allocated.
+    pub current_bytes: u64,
+    /// The maximum number of bytes allocated at the same time.
+    pub peak_bytes: u64,
+    /// The total number of allocations performed.
+    pub allocations: u64,
+}
+
+/// Returns the heap usage of the application so far.
+pub fn heap_usage() -> HeapUsage {
+    ALLOCATOR.usage()
+}
+
+/// Resets the peak heap usage to the number of bytes currently allocated.
+pub fn reset_peak_heap_usage() {
+    ALLOCATOR.reset_peak();
+}
+
+/// Reports the heap usage of the contract to the host.
+pub(crate) fn report_heap_usage() {
+    let usage = heap_usage();
+    crate::contract::wit::contract_system_api::record_heap_usage(
+        usage.peak_bytes,
+        usage.allocations,
+    );
+}
+
+/// A [`GlobalAlloc`] wrapper that counts the allocations performed by the `Inner` allocator.
+pub struct ProfilingAllocator<Inner> {
+    inner: Inner,
+    current_bytes: AtomicU64,
+    peak_bytes: AtomicU64,
+    allocations: AtomicU64,
+}
+
+impl<Inner> ProfilingAllocator<Inner> {
+    /// Creates a new [`ProfilingAllocator`] wrapping the `inner` allocator.
+    pub const fn new(inner: Inner) -> Self {
+        ProfilingAllocator {
+            inner,
+            current_bytes: AtomicU64::new(0),
+            peak_bytes: AtomicU64::new(0),
+            allocations: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the heap usage tracked by this allocator.
+    pub fn usage(&self) -> HeapUsage {
+        HeapUsage {
+            current_bytes: self.current_bytes.load(Ordering::Relaxed),
+            peak_bytes: self.peak_bytes.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Resets the peak heap usage to the number of bytes currently allocated.
+    pub fn reset_peak(&self) {
+        self.peak_bytes.store(
+            self.current_bytes.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn record_allocation(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.add_bytes(size);
+    }
+
+    fn add_bytes(&self, size: usize) {
+        let size = size as u64;
+        let current = self.current_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_bytes.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn remove_bytes(&self, size: usize) {
+        self.current_bytes.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+}
+
+unsafe impl<Inner: GlobalAlloc> GlobalAlloc for ProfilingAllocator<Inner> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = self.inner.alloc(layout);
+        if !pointer.is_null() {
+            self.record_allocation(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = self.inner.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            self.record_allocation(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        self.inner.dealloc(pointer, layout);
+        self.remove_bytes(layout.size());
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = self.inner.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            self.remove_bytes(layout.size());
+            self.add_bytes(new_size);
+        }
+        new_pointer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{heap_usage, reset_peak_heap_usage};
+
+    /// Other tests may allocate concurrently, so the measurements are only approximate.
+    const TOLERANCE: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_peak_heap_usage_of_large_allocation() {
+        const SIZE: usize = 10 * 1024 * 1024;
+
+        reset_peak_heap_usage();
+        let before = heap_usage();
+
+        let buffer = vec![1_u8; SIZE];
+        let during = heap_usage();
+        drop(buffer);
+        let after = heap_usage();
+
+        let size = SIZE as u64;
+        assert!(during.allocations > before.allocations);
+        assert!(during.peak_bytes + TOLERANCE >= before.current_bytes + size);
+        assert!(during.peak_bytes <= before.current_bytes + size + TOLERANCE);
+        assert!(during.current_bytes.saturating_sub(after.current_bytes) + TOLERANCE >= size);
+        assert!(after.peak_bytes >= during.peak_bytes);
+    }
+}
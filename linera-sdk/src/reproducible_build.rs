@@ -0,0 +1,621 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reproducible builds of an application's bytecode, to check that a published
+//! [`BytecodeId`] corresponds to a given source revision.
+//!
+//! The contract and service binaries are rebuilt with a pinned toolchain and fixed flags, with
+//! the paths of the build machine remapped to fixed prefixes. The resulting modules are then
+//! normalized: custom sections that only hold debugging information are stripped, and the
+//! remaining custom sections are sorted by name after the standard sections.
+//!
+//! A publisher who wants their bytecode to be verifiable should publish the output of
+//! [`ReproducibleBuild::build`].
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cargo_toml::Manifest;
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Blob, Bytecode},
+    identifiers::{BlobType, BytecodeId},
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The header of a WebAssembly module: the magic number, followed by version 1.
+const WASM_HEADER: [u8; 8] = *b"\0asm\x01\0\0\0";
+
+/// The identifier of custom sections.
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// The prefix to which the workspace of the built crate is remapped.
+const SOURCE_PREFIX: &str = "/source";
+
+/// The prefix to which the Cargo home directory is remapped.
+const CARGO_HOME_PREFIX: &str = "/cargo";
+
+/// The compilation flags used for every reproducible build.
+const FIXED_RUSTFLAGS: [&str; 4] = ["-C", "codegen-units=1", "-C", "debuginfo=0"];
+
+/// An error during a reproducible build or its verification.
+#[derive(Debug, Error)]
+pub enum ReproducibleBuildError {
+    /// An I/O error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The toolchain file could not be parsed.
+    #[error("invalid toolchain file: {0}")]
+    InvalidToolchainFile(#[from] toml::de::Error),
+    /// The Cargo manifest of the crate could not be loaded.
+    #[error("invalid Cargo manifest: {0}")]
+    InvalidManifest(#[from] cargo_toml::Error),
+    /// The output of `cargo metadata` could not be parsed.
+    #[error("invalid Cargo metadata: {0}")]
+    InvalidMetadata(#[from] serde_json::Error),
+    /// The crate doesn't declare exactly one contract and one service binary.
+    #[error("could not find the contract and service binaries of {0}")]
+    MissingBinaries(PathBuf),
+    /// A Cargo command failed.
+    #[error("`cargo {command}` failed:\n{stderr}")]
+    CargoFailed {
+        /// The Cargo sub-command that failed.
+        command: &'static str,
+        /// The error output of the command.
+        stderr: String,
+    },
+    /// A bytecode is not a valid WebAssembly module.
+    #[error("invalid WebAssembly module: {0}")]
+    InvalidWasm(&'static str),
+}
+
+/// A pinned Rust toolchain, used to rebuild the bytecode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PinnedToolchain {
+    /// The toolchain to use, as given to `rustup`, e.g. `1.81.0`.
+    pub channel: String,
+    /// Additional compilation flags, appended to the fixed ones.
+    pub rustflags: Vec<String>,
+}
+
+/// The contents of a `rust-toolchain.toml` file that matter for a build.
+#[derive(Deserialize)]
+struct ToolchainFile {
+    toolchain: ToolchainSection,
+}
+
+#[derive(Deserialize)]
+struct ToolchainSection {
+    channel: String,
+}
+
+impl PinnedToolchain {
+    /// Creates a [`PinnedToolchain`] for the given channel, without additional flags.
+    pub fn new(channel: impl Into<String>) -> Self {
+        PinnedToolchain {
+            channel: channel.into(),
+            rustflags: Vec::new(),
+        }
+    }
+
+    /// Reads the channel pinned by a `rust-toolchain.toml` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ReproducibleBuildError> {
+        let contents = std::fs::read_to_string(path)?;
+        let file: ToolchainFile = toml::from_str(&contents)?;
+        Ok(Self::new(file.toolchain.channel))
+    }
+}
+
+/// The parts of the output of `cargo metadata` that are needed for a build.
+#[derive(Deserialize)]
+struct CargoMetadata {
+    workspace_root: PathBuf,
+    target_directory: PathBuf,
+}
+
+/// A reproducible build of the contract and service of an application crate.
+#[derive(Clone, Debug)]
+pub struct ReproducibleBuild {
+    crate_path: PathBuf,
+    toolchain: PinnedToolchain,
+}
+
+impl ReproducibleBuild {
+    /// Creates a [`ReproducibleBuild`] of the crate in `crate_path`, using `toolchain`.
+    pub fn new(crate_path: impl Into<PathBuf>, toolchain: PinnedToolchain) -> Self {
+        ReproducibleBuild {
+            crate_path: crate_path.into(),
+            toolchain,
+        }
+    }
+
+    /// Builds the crate, and returns its normalized contract and service bytecodes.
+    pub fn build(&self) -> Result<(Bytecode, Bytecode), ReproducibleBuildError> {
+        let (contract_binary, service_binary) = self.find_binaries()?;
+        let metadata = self.metadata()?;
+        let target_directory = metadata.target_directory.join("reproducible");
+
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")));
+        let mut rustflags = FIXED_RUSTFLAGS
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect::<Vec<_>>();
+        rustflags.push(format!(
+            "--remap-path-prefix={}={SOURCE_PREFIX}",
+            metadata.workspace_root.display()
+        ));
+        if let Some(cargo_home) = cargo_home {
+            rustflags.push(format!(
+                "--remap-path-prefix={}={CARGO_HOME_PREFIX}",
+                cargo_home.display()
+            ));
+        }
+        rustflags.extend(self.toolchain.rustflags.iter().cloned());
+
+        let output = Command::new("cargo")
+            .arg(format!("+{}", self.toolchain.channel))
+            .args(["build", "--release", "--locked"])
+            .args(["--target", "wasm32-unknown-unknown"])
+            .arg("--target-dir")
+            .arg(&target_directory)
+            .env("CARGO_ENCODED_RUSTFLAGS", rustflags.join("\x1f"))
+            .env_remove("RUSTFLAGS")
+            .current_dir(&self.crate_path)
+            .output()?;
+        if !output.status.success() {
+            return Err(ReproducibleBuildError::CargoFailed {
+                command: "build",
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+
+        let output_directory = target_directory.join("wasm32-unknown-unknown/release");
+        let load = |binary: &str| -> Result<Bytecode, ReproducibleBuildError> {
+            let bytes = std::fs::read(output_directory.join(format!("{binary}.wasm")))?;
+            Ok(Bytecode::new(normalize(&bytes)?))
+        };
+        Ok((load(&contract_binary)?, load(&service_binary)?))
+    }
+
+    /// Builds the crate, and compares the result with the published `bytecode_id`.
+    ///
+    /// If the published contract and service bytecodes are provided, the report lists the
+    /// sections that differ from the rebuilt ones.
+    pub fn verify(
+        &self,
+        bytecode_id: BytecodeId,
+        published: Option<(&Bytecode, &Bytecode)>,
+    ) -> Result<VerificationReport, ReproducibleBuildError> {
+        let (contract, service) = self.build()?;
+        let (published_contract, published_service) = published.unzip();
+        Ok(VerificationReport {
+            contract: verify_module(
+                &contract,
+                BlobType::ContractBytecode,
+                bytecode_id.contract_blob_hash,
+                published_contract,
+            )?,
+            service: verify_module(
+                &service,
+                BlobType::ServiceBytecode,
+                bytecode_id.service_blob_hash,
+                published_service,
+            )?,
+        })
+    }
+
+    /// Returns the names of the contract and service binaries declared in the manifest.
+    fn find_binaries(&self) -> Result<(String, String), ReproducibleBuildError> {
+        let manifest = Manifest::from_path(self.crate_path.join("Cargo.toml"))?;
+        let binaries = manifest
+            .bin
+            .into_iter()
+            .filter_map(|binary| binary.name)
+            .collect::<Vec<_>>();
+        let find = |suffix: &str| {
+            let mut matches = binaries.iter().filter(|name| name.ends_with(suffix));
+            match (matches.next(), matches.next()) {
+                (Some(name), None) => Ok(name.clone()),
+                _ => Err(ReproducibleBuildError::MissingBinaries(
+                    self.crate_path.clone(),
+                )),
+            }
+        };
+        Ok((find("contract")?, find("service")?))
+    }
+
+    /// Runs `cargo metadata` for the crate.
+    fn metadata(&self) -> Result<CargoMetadata, ReproducibleBuildError> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .current_dir(&self.crate_path)
+            .output()?;
+        if !output.status.success() {
+            return Err(ReproducibleBuildError::CargoFailed {
+                command: "metadata",
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// The result of comparing rebuilt bytecodes with a published [`BytecodeId`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationReport {
+    /// The comparison of the contract bytecode.
+    pub contract: ModuleReport,
+    /// The comparison of the service bytecode.
+    pub service: ModuleReport,
+}
+
+impl VerificationReport {
+    /// Returns `true` if both bytecodes match the published ones.
+    pub fn matches(&self) -> bool {
+        self.contract.matches() && self.service.matches()
+    }
+}
+
+/// The result of comparing one rebuilt module with its published version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ModuleReport {
+    /// The hash of the published bytecode blob.
+    pub expected_hash: CryptoHash,
+    /// The hash of the blob of the rebuilt bytecode.
+    pub actual_hash: CryptoHash,
+    /// The sections that differ, if the published bytecode was provided.
+    ///
+    /// This is empty when the hashes match. If the hashes differ but no section does, the
+    /// published bytecode was not normalized.
+    pub differences: Vec<SectionDifference>,
+}
+
+impl ModuleReport {
+    /// Returns `true` if the rebuilt bytecode matches the published one.
+    pub fn matches(&self) -> bool {
+        self.expected_hash == self.actual_hash
+    }
+}
+
+/// A section that differs between a published module and a rebuilt one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SectionDifference {
+    /// The name of the section, e.g. `code` or `custom "producers"`.
+    pub section: String,
+    /// How the section differs.
+    pub change: SectionChange,
+}
+
+/// How a section differs between a published module and a rebuilt one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SectionChange {
+    /// The section is only in the published module.
+    Missing,
+    /// The section is only in the rebuilt module.
+    Unexpected,
+    /// The section is in both modules, with different contents.
+    Changed {
+        /// The size of the section in the published module.
+        expected_size: usize,
+        /// The size of the section in the rebuilt module.
+        actual_size: usize,
+    },
+}
+
+/// Returns the [`BytecodeId`] under which the given bytecodes would be published.
+pub fn bytecode_id_of(contract: &Bytecode, service: &Bytecode) -> BytecodeId {
+    BytecodeId::new(
+        Blob::new_contract_bytecode(contract.compress()).id().hash,
+        Blob::new_service_bytecode(service.compress()).id().hash,
+    )
+}
+
+/// Compares a rebuilt module with the published blob with the hash `expected_hash`.
+pub fn verify_module(
+    rebuilt: &Bytecode,
+    blob_type: BlobType,
+    expected_hash: CryptoHash,
+    published: Option<&Bytecode>,
+) -> Result<ModuleReport, ReproducibleBuildError> {
+    let compressed = rebuilt.compress();
+    let blob = match blob_type {
+        BlobType::ContractBytecode => Blob::new_contract_bytecode(compressed),
+        BlobType::ServiceBytecode => Blob::new_service_bytecode(compressed),
+        BlobType::Data => Blob::new_data(rebuilt.bytes.clone()),
+    };
+    let actual_hash = blob.id().hash;
+    let differences = match published {
+        Some(published) if actual_hash != expected_hash => {
+            diff_sections(&normalize(&published.bytes)?, &rebuilt.bytes)?
+        }
+        _ => Vec::new(),
+    };
+    Ok(ModuleReport {
+        expected_hash,
+        actual_hash,
+        differences,
+    })
+}
+
+/// Normalizes a WebAssembly module.
+///
+/// Custom sections with debugging information are removed, since they contain the paths of
+/// the build machine. The other custom sections are moved after the standard ones, sorted by
+/// name.
+pub fn normalize(bytes: &[u8]) -> Result<Vec<u8>, ReproducibleBuildError> {
+    let (standard, mut custom): (Vec<_>, Vec<_>) = parse_sections(bytes)?
+        .into_iter()
+        .partition(|section| section.id != CUSTOM_SECTION_ID);
+    custom.retain(|section| !section.holds_debug_info());
+    custom.sort_by(|first, second| first.name.cmp(&second.name));
+
+    let mut normalized = WASM_HEADER.to_vec();
+    for section in standard.iter().chain(&custom) {
+        normalized.push(section.id);
+        let size = u32::try_from(section.contents.len())
+            .map_err(|_| ReproducibleBuildError::InvalidWasm("section too large"))?;
+        write_u32(size, &mut normalized);
+        normalized.extend_from_slice(section.contents);
+    }
+    Ok(normalized)
+}
+
+/// Lists the sections that differ between the `expected` and the `actual` modules.
+pub fn diff_sections(
+    expected: &[u8],
+    actual: &[u8],
+) -> Result<Vec<SectionDifference>, ReproducibleBuildError> {
+    let expected = labeled_sections(expected)?;
+    let actual = labeled_sections(actual)?;
+    let actual_contents = actual.iter().cloned().collect::<BTreeMap<_, _>>();
+    let expected_contents = expected.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    let mut differences = Vec::new();
+    for (label, contents) in &expected {
+        let change = match actual_contents.get(label) {
+            None => SectionChange::Missing,
+            Some(actual) if actual != contents => SectionChange::Changed {
+                expected_size: contents.len(),
+                actual_size: actual.len(),
+            },
+            Some(_) => continue,
+        };
+        differences.push(SectionDifference {
+            section: label.clone(),
+            change,
+        });
+    }
+    for (label, _) in actual {
+        if !expected_contents.contains_key(&label) {
+            differences.push(SectionDifference {
+                section: label,
+                change: SectionChange::Unexpected,
+            });
+        }
+    }
+    Ok(differences)
+}
+
+/// A section of a WebAssembly module.
+struct Section<'a> {
+    id: u8,
+    /// The name of a custom section.
+    name: Option<String>,
+    contents: &'a [u8],
+}
+
+impl Section<'_> {
+    /// Returns `true` if this is a custom section with debugging information.
+    fn holds_debug_info(&self) -> bool {
+        self.name.as_deref().is_some_and(|name| {
+            name.starts_with(".debug")
+                || name == "sourceMappingURL"
+                || name == "external_debug_info"
+        })
+    }
+
+    /// Returns a human-readable name for this section.
+    fn label(&self) -> String {
+        let label = match self.id {
+            CUSTOM_SECTION_ID => {
+                return format!("custom {:?}", self.name.as_deref().unwrap_or_default())
+            }
+            1 => "type",
+            2 => "import",
+            3 => "function",
+            4 => "table",
+            5 => "memory",
+            6 => "global",
+            7 => "export",
+            8 => "start",
+            9 => "element",
+            10 => "code",
+            11 => "data",
+            12 => "data count",
+            13 => "tag",
+            id => return format!("unknown section {id}"),
+        };
+        label.to_owned()
+    }
+}
+
+/// Splits a WebAssembly module into its sections.
+fn parse_sections(bytes: &[u8]) -> Result<Vec<Section<'_>>, ReproducibleBuildError> {
+    let body = bytes
+        .strip_prefix(&WASM_HEADER)
+        .ok_or(ReproducibleBuildError::InvalidWasm("missing module header"))?;
+    let mut offset = 0;
+    let mut sections = Vec::new();
+    while offset < body.len() {
+        let id = body[offset];
+        offset += 1;
+        let size = read_u32(body, &mut offset)? as usize;
+        let contents = body
+            .get(offset..offset + size)
+            .ok_or(ReproducibleBuildError::InvalidWasm("truncated section"))?;
+        offset += size;
+        let name = if id == CUSTOM_SECTION_ID {
+            let mut name_offset = 0;
+            let length = read_u32(contents, &mut name_offset)? as usize;
+            let name = contents.get(name_offset..name_offset + length).ok_or(
+                ReproducibleBuildError::InvalidWasm("truncated section name"),
+            )?;
+            let name = std::str::from_utf8(name)
+                .map_err(|_| ReproducibleBuildError::InvalidWasm("invalid section name"))?;
+            Some(name.to_owned())
+        } else {
+            None
+        };
+        sections.push(Section { id, name, contents });
+    }
+    Ok(sections)
+}
+
+/// Splits a WebAssembly module into its sections, with unique labels.
+fn labeled_sections(bytes: &[u8]) -> Result<Vec<(String, &[u8])>, ReproducibleBuildError> {
+    let mut occurrences = BTreeMap::<String, usize>::new();
+    Ok(parse_sections(bytes)?
+        .into_iter()
+        .map(|section| {
+            let mut label = section.label();
+            let count = occurrences.entry(label.clone()).or_default();
+            *count += 1;
+            if *count > 1 {
+                label = format!("{label} #{count}");
+            }
+            (label, section.contents)
+        })
+        .collect())
+}
+
+/// Reads an unsigned LEB128-encoded 32-bit integer.
+fn read_u32(bytes: &[u8], offset: &mut usize) -> Result<u32, ReproducibleBuildError> {
+    let mut value = 0_u32;
+    for shift in (0..32).step_by(7) {
+        let byte = *bytes
+            .get(*offset)
+            .ok_or(ReproducibleBuildError::InvalidWasm(
+                "unexpected end of module",
+            ))?;
+        *offset += 1;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ReproducibleBuildError::InvalidWasm("integer too large"))
+}
+
+/// Writes an unsigned LEB128-encoded 32-bit integer.
+fn write_u32(mut value: u32, output: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes a module with the given sections.
+    fn module(sections: &[(u8, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut bytes = WASM_HEADER.to_vec();
+        for (id, name, payload) in sections {
+            let mut contents = Vec::new();
+            if let Some(name) = name {
+                write_u32(name.len() as u32, &mut contents);
+                contents.extend_from_slice(name.as_bytes());
+            }
+            contents.extend_from_slice(payload);
+            bytes.push(*id);
+            write_u32(contents.len() as u32, &mut bytes);
+            bytes.extend(contents);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_normalize_strips_debug_info_and_sorts_custom_sections() {
+        let code = [0x01_u8, 0x02, 0x03];
+        let bytes = module(&[
+            (0, Some("producers"), b"rustc"),
+            (1, None, &[0x60, 0x00, 0x00]),
+            (0, Some(".debug_info"), b"/home/user/app/src/lib.rs"),
+            (10, None, &code),
+            (0, Some("name"), b"names"),
+        ]);
+        let expected = module(&[
+            (1, None, &[0x60, 0x00, 0x00]),
+            (10, None, &code),
+            (0, Some("name"), b"names"),
+            (0, Some("producers"), b"rustc"),
+        ]);
+
+        let normalized = normalize(&bytes).unwrap();
+        assert_eq!(normalized, expected);
+        assert_eq!(normalize(&normalized).unwrap(), normalized);
+    }
+
+    #[test]
+    fn test_diff_sections() {
+        let published = module(&[
+            (1, None, &[0x60, 0x00, 0x00]),
+            (10, None, &[0x01, 0x02]),
+            (0, Some("producers"), b"rustc 1.81.0"),
+        ]);
+        let rebuilt = module(&[
+            (1, None, &[0x60, 0x00, 0x00]),
+            (10, None, &[0x01, 0x02, 0x03]),
+            (11, None, &[0x00]),
+        ]);
+
+        assert!(diff_sections(&published, &published).unwrap().is_empty());
+        assert_eq!(
+            diff_sections(&published, &rebuilt).unwrap(),
+            vec![
+                SectionDifference {
+                    section: "code".to_owned(),
+                    change: SectionChange::Changed {
+                        expected_size: 2,
+                        actual_size: 3
+                    },
+                },
+                SectionDifference {
+                    section: "custom \"producers\"".to_owned(),
+                    change: SectionChange::Missing,
+                },
+                SectionDifference {
+                    section: "data".to_owned(),
+                    change: SectionChange::Unexpected,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_modules_are_rejected() {
+        assert!(matches!(
+            normalize(b"\0asm\x02\0\0\0"),
+            Err(ReproducibleBuildError::InvalidWasm(_))
+        ));
+        let mut truncated = module(&[(10, None, &[0x01, 0x02])]);
+        truncated.pop();
+        assert!(matches!(
+            normalize(&truncated),
+            Err(ReproducibleBuildError::InvalidWasm(_))
+        ));
+    }
+}
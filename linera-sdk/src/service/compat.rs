@@ -0,0 +1,484 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compatibility of service queries with clients using deprecated names.
+
+use std::cell::RefCell;
+
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer, StringDeserializer},
+        DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, Unexpected, VariantAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+use serde_json::{Error, Map, Value};
+
+/// Deserializes the JSON `query`, replacing its deprecated names with their current names,
+/// according to the `renames` list of `(deprecated, current)` pairs.
+///
+/// Names are only renamed where the type being deserialized declares them: a deprecated name is
+/// replaced if it is the tag of an enum that has a variant with the current name, or a key of a
+/// struct that has a field with the current name. The keys of maps are never renamed, even if
+/// they match a deprecated name.
+///
+/// Returns the query along with the deprecated names that were found in it.
+pub(crate) fn deserialize_renamed<T: DeserializeOwned>(
+    query: Value,
+    renames: &[(&'static str, &'static str)],
+) -> Result<(T, Vec<&'static str>), Error> {
+    let found = RefCell::new(Vec::new());
+    let context = RenameContext {
+        renames,
+        found: &found,
+    };
+    let query = T::deserialize(context.nested(query))?;
+    Ok((query, found.into_inner()))
+}
+
+/// The renames to apply, and the deprecated names found so far.
+#[derive(Clone, Copy)]
+struct RenameContext<'a> {
+    renames: &'a [(&'static str, &'static str)],
+    found: &'a RefCell<Vec<&'static str>>,
+}
+
+impl<'a> RenameContext<'a> {
+    /// Wraps a nested `value` so that it is deserialized with the same renames.
+    fn nested(self, value: Value) -> Renaming<'a> {
+        Renaming {
+            value,
+            context: self,
+        }
+    }
+
+    /// Returns the current name for `name` if it is deprecated and the current name is one of
+    /// the `declared` names, recording the deprecated name.
+    fn rename(self, name: &str, declared: &[&str]) -> Option<&'static str> {
+        let &(deprecated, current) = self
+            .renames
+            .iter()
+            .find(|(deprecated, current)| *deprecated == name && declared.contains(current))?;
+        self.found.borrow_mut().push(deprecated);
+        Some(current)
+    }
+
+    /// Renames the keys of `object` that are deprecated names of the `declared` names, without
+    /// looking into their values.
+    fn rename_keys(self, object: &mut Map<String, Value>, declared: &[&str]) {
+        for &(deprecated, current) in self.renames {
+            if !declared.contains(&current) || object.contains_key(current) {
+                continue;
+            }
+            if let Some(field) = object.remove(deprecated) {
+                self.found.borrow_mut().push(deprecated);
+                object.insert(current.to_string(), field);
+            }
+        }
+    }
+
+    /// Visits the `entries` of a map or struct, deserializing their values with the same renames.
+    fn visit_entries<'de, V: Visitor<'de>>(
+        self,
+        entries: Map<String, Value>,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| (key, self.nested(value)));
+        let mut deserializer = MapDeserializer::<_, Error>::new(entries);
+        let value = visitor.visit_map(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    /// Visits the `elements` of a sequence, deserializing them with the same renames.
+    fn visit_elements<'de, V: Visitor<'de>>(
+        self,
+        elements: Vec<Value>,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let elements = elements.into_iter().map(|value| self.nested(value));
+        let mut deserializer = SeqDeserializer::<_, Error>::new(elements);
+        let value = visitor.visit_seq(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+}
+
+/// A deserializer of a JSON [`Value`] that renames the deprecated names declared by the types
+/// it deserializes.
+struct Renaming<'a> {
+    value: Value,
+    context: RenameContext<'a>,
+}
+
+impl<'de> Deserializer<'de> for Renaming<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Without a declared type, nothing below this point can be renamed.
+        self.value.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.value.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Array(elements) => self.context.visit_elements(elements, visitor),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Object(entries) => self.context.visit_entries(entries, visitor),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Object(mut entries) => {
+                self.context.rename_keys(&mut entries, fields);
+                self.context.visit_entries(entries, visitor)
+            }
+            Value::Array(elements) => self.context.visit_elements(elements, visitor),
+            value => value.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let (variant, content) = match self.value {
+            Value::String(variant) => (variant, None),
+            Value::Object(object) if object.len() == 1 => {
+                let (variant, content) = object.into_iter().next().expect("Object has one entry");
+                (variant, Some(self.context.nested(content)))
+            }
+            value => return value.deserialize_enum(name, variants, visitor),
+        };
+        let variant = match self.context.rename(&variant, variants) {
+            Some(current) => current.to_owned(),
+            None => variant,
+        };
+        visitor.visit_enum(RenamingEnum { variant, content })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Renaming<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// An externally tagged enum value, whose content is deserialized with the same renames.
+struct RenamingEnum<'a> {
+    variant: String,
+    content: Option<Renaming<'a>>,
+}
+
+/// The content of an externally tagged enum value, or `None` for a unit variant.
+struct RenamingVariant<'a>(Option<Renaming<'a>>);
+
+impl<'de, 'a> EnumAccess<'de> for RenamingEnum<'a> {
+    type Error = Error;
+    type Variant = RenamingVariant<'a>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), Error> {
+        let variant = seed.deserialize(StringDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, RenamingVariant(self.content)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for RenamingVariant<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            Some(content) => Deserialize::deserialize(content),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Error> {
+        let content = self.content("newtype variant")?;
+        seed.deserialize(content)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        let content = self.content("tuple variant")?;
+        content.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let content = self.content("struct variant")?;
+        content.deserialize_struct("", fields, visitor)
+    }
+}
+
+impl<'a> RenamingVariant<'a> {
+    /// Returns the content of a variant that is expected to be `kind`.
+    fn content(self, kind: &str) -> Result<Renaming<'a>, Error> {
+        self.0
+            .ok_or_else(|| de::Error::invalid_type(Unexpected::UnitVariant, &kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::deserialize_renamed;
+    use crate::{
+        abi::{ServiceAbi, WithServiceAbi},
+        service::deserialize_query,
+        util::BlockingWait as _,
+        Service, ServiceRuntime,
+    };
+
+    /// A query type whose variants and fields were renamed.
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    enum Query {
+        Balance { owner: String },
+        Balances { owners: BTreeMap<String, u64> },
+        Owners(BTreeMap<String, u64>),
+        Lookup(Filter),
+        Heartbeat,
+    }
+
+    /// A struct passed as a query argument, whose field was renamed.
+    #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+    struct Filter {
+        owner: String,
+    }
+
+    struct TestAbi;
+
+    impl ServiceAbi for TestAbi {
+        type Query = Query;
+        type QueryResponse = String;
+    }
+
+    struct TestService;
+
+    impl WithServiceAbi for TestService {
+        type Abi = TestAbi;
+    }
+
+    impl Service for TestService {
+        type Parameters = ();
+
+        const DEPRECATED_QUERY_NAMES: &'static [(&'static str, &'static str)] = &[
+            ("GetBalance", "Balance"),
+            ("owner_id", "owner"),
+            ("Ping", "Heartbeat"),
+        ];
+
+        async fn new(_runtime: ServiceRuntime<Self>) -> Self {
+            TestService
+        }
+
+        async fn handle_query(&self, query: Query) -> String {
+            match query {
+                Query::Balance { owner } => format!("balance of {owner}"),
+                Query::Balances { owners } | Query::Owners(owners) => {
+                    format!("balances of {} owners", owners.len())
+                }
+                Query::Lookup(filter) => format!("lookup of {}", filter.owner),
+                Query::Heartbeat => "alive".to_owned(),
+            }
+        }
+    }
+
+    /// Tests that queries using the deprecated names are handled by the renamed variants.
+    #[test]
+    fn deprecated_query_is_handled_by_new_variant() {
+        let old_query = json!({ "GetBalance": { "owner_id": "alice" } }).to_string();
+        let query = deserialize_query::<TestService>(old_query.as_bytes())
+            .expect("Query with deprecated names should be valid");
+        assert_eq!(
+            query,
+            Query::Balance {
+                owner: "alice".to_owned()
+            }
+        );
+
+        let service = TestService::new(ServiceRuntime::new()).blocking_wait();
+        let response = service.handle_query(query).blocking_wait();
+        assert_eq!(response, "balance of alice");
+
+        let old_query = json!("Ping").to_string();
+        let query = deserialize_query::<TestService>(old_query.as_bytes())
+            .expect("Query with deprecated names should be valid");
+        assert_eq!(query, Query::Heartbeat);
+    }
+
+    /// Tests that malformed queries fail without panicking by default.
+    #[cfg(not(feature = "strict-panics"))]
+    #[test]
+    fn malformed_query_is_reported() {
+        let malformed_query = json!({ "GetBalance": { "owner_id": 42 } }).to_string();
+
+        assert_eq!(
+            deserialize_query::<TestService>(malformed_query.as_bytes()),
+            None
+        );
+        let failure =
+            crate::failure::take_reported_failure().expect("Malformed query should be reported");
+        assert!(failure.contains("is invalid and could not be deserialized"));
+        assert!(failure.ends_with("invalid type: integer `42`, expected a string"));
+    }
+
+    /// Tests that malformed queries abort the service in strict mode.
+    #[cfg(feature = "strict-panics")]
+    #[test]
+    #[should_panic(expected = "is invalid and could not be deserialized")]
+    fn malformed_query_panics_in_strict_mode() {
+        let malformed_query = json!({ "GetBalance": { "owner_id": 42 } }).to_string();
+
+        deserialize_query::<TestService>(malformed_query.as_bytes());
+    }
+
+    /// Tests that queries using the current names are left unchanged.
+    #[test]
+    fn current_query_is_not_renamed() {
+        let query = json!({ "Balance": { "owner": "alice" } });
+
+        let (query, found) =
+            deserialize_renamed::<Query>(query, TestService::DEPRECATED_QUERY_NAMES)
+                .expect("Query with current names should be valid");
+
+        assert!(found.is_empty());
+        assert_eq!(
+            query,
+            Query::Balance {
+                owner: "alice".to_owned()
+            }
+        );
+    }
+
+    /// Tests that the deprecated names found in the query are reported.
+    #[test]
+    fn deprecated_names_are_reported() {
+        let query = json!({ "GetBalance": { "owner_id": "alice" } });
+
+        let (_, mut found) =
+            deserialize_renamed::<Query>(query, TestService::DEPRECATED_QUERY_NAMES)
+                .expect("Query with deprecated names should be valid");
+        found.sort();
+
+        assert_eq!(found, ["GetBalance", "owner_id"]);
+    }
+
+    /// Tests that the fields of a struct passed as an argument are renamed, since the struct
+    /// declares them.
+    #[test]
+    fn struct_argument_is_renamed() {
+        let query = json!({ "Lookup": { "owner_id": "alice" } });
+
+        let (query, found) =
+            deserialize_renamed::<Query>(query, TestService::DEPRECATED_QUERY_NAMES)
+                .expect("Query with deprecated names should be valid");
+
+        assert_eq!(found, ["owner_id"]);
+        assert_eq!(
+            query,
+            Query::Lookup(Filter {
+                owner: "alice".to_owned()
+            })
+        );
+    }
+
+    /// Tests that the keys of user maps are not renamed, even if they match a deprecated name.
+    #[test]
+    fn user_map_is_not_renamed() {
+        let owners = BTreeMap::from([("GetBalance".to_owned(), 2), ("owner_id".to_owned(), 1)]);
+
+        let queries = [
+            (
+                json!({ "Balances": { "owners": { "owner_id": 1, "GetBalance": 2 } } }),
+                Query::Balances {
+                    owners: owners.clone(),
+                },
+            ),
+            (
+                json!({ "Owners": { "owner_id": 1, "GetBalance": 2 } }),
+                Query::Owners(owners),
+            ),
+        ];
+
+        for (json_query, expected) in queries {
+            let (query, found) = deserialize_renamed::<Query>(
+                json_query.clone(),
+                TestService::DEPRECATED_QUERY_NAMES,
+            )
+            .expect("Query with a user map should be valid");
+            assert!(found.is_empty());
+            assert_eq!(query, expected);
+
+            let query = deserialize_query::<TestService>(json_query.to_string().as_bytes())
+                .expect("Query with a user map should be valid");
+            assert_eq!(query, expected);
+        }
+    }
+}
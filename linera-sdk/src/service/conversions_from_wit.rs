@@ -9,7 +9,7 @@ use linera_base::{
     identifiers::{AccountOwner, ApplicationId, BytecodeId, ChainId, MessageId, Owner},
 };
 
-use super::wit::service_system_api as wit_system_api;
+use super::{wit::service_system_api as wit_system_api, QueryContext};
 
 impl From<wit_system_api::CryptoHash> for ChainId {
     fn from(hash_value: wit_system_api::CryptoHash) -> Self {
@@ -98,3 +98,16 @@ impl From<wit_system_api::Timestamp> for Timestamp {
         Timestamp::from(timestamp.inner0)
     }
 }
+
+impl From<wit_system_api::QueryContext> for QueryContext {
+    fn from(context: wit_system_api::QueryContext) -> Self {
+        QueryContext {
+            chain_id: context.chain_id.into(),
+            next_block_height: context.next_block_height.into(),
+            block_hash: context.block_hash.map(CryptoHash::from),
+            block_timestamp: context.block_timestamp.into(),
+            maximum_response_size: context.maximum_response_size,
+            authenticated_signer: context.authenticated_signer.map(Owner::from),
+        }
+    }
+}
@@ -3,6 +3,7 @@
 
 //! Types and macros useful for writing an application service.
 
+mod compat;
 mod conversions_from_wit;
 mod conversions_to_wit;
 #[cfg(not(with_testing))]
@@ -12,18 +13,44 @@ mod test_runtime;
 #[doc(hidden)]
 pub mod wit;
 
+use std::fmt::Display;
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{BlockHeight, Timestamp},
+    identifiers::{ChainId, Owner},
+};
+
 #[cfg(not(with_testing))]
 pub use self::runtime::ServiceRuntime;
 #[cfg(with_testing)]
 pub use self::test_runtime::MockServiceRuntime;
 #[doc(hidden)]
 pub use self::wit::export_service;
-use crate::util::BlockingWait as _;
+use crate::{failure, util::BlockingWait as _};
 
 /// Inside tests, use the [`MockServiceRuntime`] instead of the real [`ServiceRuntime`].
 #[cfg(with_testing)]
 pub type ServiceRuntime<Application> = MockServiceRuntime<Application>;
 
+/// The state of the chain a query is executed against, so that services can report how fresh
+/// their responses are.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct QueryContext {
+    /// The ID of the chain.
+    pub chain_id: ChainId,
+    /// The height of the next block that can be added to the chain.
+    pub next_block_height: BlockHeight,
+    /// The hash of the latest block on the chain, if any.
+    pub block_hash: Option<CryptoHash>,
+    /// The timestamp of the latest block on the chain.
+    pub block_timestamp: Timestamp,
+    /// The maximum size in bytes of the response to the query. Larger responses are rejected.
+    pub maximum_response_size: u64,
+    /// The owner who signed the query, if the query was signed.
+    pub authenticated_signer: Option<Owner>,
+}
+
 /// Declares an implementation of the [`Service`][`crate::Service`] trait, exporting it from the
 /// Wasm module.
 ///
@@ -43,14 +70,19 @@ macro_rules! service {
             fn handle_query(argument: Vec<u8>) -> Vec<u8> {
                 use $crate::util::BlockingWait as _;
                 $crate::ServiceLogger::install();
-                let request = $crate::serde_json::from_slice(&argument)
-                    .unwrap_or_else(|_| panic!("Query {argument:?} is invalid and could not be deserialized"));
+                let Some(request) = $crate::service::deserialize_query::<$service>(&argument)
+                else {
+                    return Vec::new();
+                };
                 let response = $crate::service::run_async_entrypoint(
                     unsafe { &mut SERVICE },
                     move |service| service.handle_query(request).blocking_wait(),
                 );
-                $crate::serde_json::to_vec(&response)
-                    .expect("Failed to deserialize query response")
+                $crate::service::ok_or_report(
+                    $crate::serde_json::to_vec(&response),
+                    "Failed to serialize query response",
+                )
+                .unwrap_or_default()
             }
         }
 
@@ -61,6 +93,46 @@ macro_rules! service {
     };
 }
 
+/// Deserializes a JSON query for the `Service`, after replacing the deprecated names listed in
+/// [`Service::DEPRECATED_QUERY_NAMES`](crate::Service::DEPRECATED_QUERY_NAMES).
+///
+/// Fails the query if it is invalid, returning `None`.
+#[doc(hidden)]
+pub fn deserialize_query<Service>(argument: &[u8]) -> Option<Service::Query>
+where
+    Service: crate::Service,
+{
+    if Service::DEPRECATED_QUERY_NAMES.is_empty() {
+        return ok_or_report(serde_json::from_slice(argument), &invalid_query(argument));
+    }
+
+    let query: serde_json::Value =
+        ok_or_report(serde_json::from_slice(argument), &invalid_query(argument))?;
+    let (query, deprecated_names) = ok_or_report(
+        compat::deserialize_renamed(query, Service::DEPRECATED_QUERY_NAMES),
+        &invalid_query(argument),
+    )?;
+    for name in deprecated_names {
+        log::warn!("Query uses the deprecated name {name:?}");
+    }
+    Some(query)
+}
+
+/// Returns the context of the failure to deserialize the query `argument`.
+fn invalid_query(argument: &[u8]) -> String {
+    format!("Query {argument:?} is invalid and could not be deserialized")
+}
+
+/// Returns the value of `result`, or fails the current query with its error, prefixed by
+/// `context`.
+///
+/// The failure is reported to the host, unless the `strict-panics` feature is enabled, in which
+/// case this function panics.
+#[doc(hidden)]
+pub fn ok_or_report<T, E: Display>(result: Result<T, E>, context: &str) -> Option<T> {
+    failure::ok_or_report(result, context, wit::service_system_api::report_error)
+}
+
 /// Runs an asynchronous entrypoint in a blocking manner, by repeatedly polling the entrypoint
 /// future.
 pub fn run_async_entrypoint<Service, Output>(
@@ -12,7 +12,10 @@ use linera_base::{
 };
 use serde::Serialize;
 
-use super::wit::service_system_api as wit;
+use super::{
+    wit::{service_system_api as wit, view_system_api as view_wit},
+    QueryContext,
+};
 use crate::{DataBlobHash, KeyValueStore, Service, ViewStorageContext};
 
 /// The runtime available during execution of a query.
@@ -24,6 +27,7 @@ where
     application_id: Mutex<Option<ApplicationId<Application::Abi>>>,
     chain_id: Mutex<Option<ChainId>>,
     next_block_height: Mutex<Option<BlockHeight>>,
+    query_context: Mutex<Option<QueryContext>>,
     timestamp: Mutex<Option<Timestamp>>,
     chain_balance: Mutex<Option<Amount>>,
     owner_balances: Mutex<Option<Vec<(AccountOwner, Amount)>>>,
@@ -41,6 +45,7 @@ where
             application_id: Mutex::new(None),
             chain_id: Mutex::new(None),
             next_block_height: Mutex::new(None),
+            query_context: Mutex::new(None),
             timestamp: Mutex::new(None),
             chain_balance: Mutex::new(None),
             owner_balances: Mutex::new(None),
@@ -58,6 +63,12 @@ where
         ViewStorageContext::new_unsafe(self.key_value_store(), Vec::new(), ())
     }
 
+    /// Reads the value under `key` in the scratch space of the application, as written by its
+    /// contract on this validator or node. It may have been wiped.
+    pub fn scratch_read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        view_wit::scratch_read(key)
+    }
+
     /// Returns the application parameters provided when the application was created.
     pub fn application_parameters(&self) -> Application::Parameters {
         Self::fetch_value_through_cache(&self.application_parameters, || {
@@ -85,6 +96,11 @@ where
         })
     }
 
+    /// Returns the state of the chain the current query is executed against.
+    pub fn query_context(&self) -> QueryContext {
+        Self::fetch_value_through_cache(&self.query_context, || wit::get_query_context().into())
+    }
+
     /// Retrieves the current system time, i.e. the timestamp of the block in which this is called.
     pub fn system_time(&self) -> Timestamp {
         Self::fetch_value_through_cache(&self.timestamp, || wit::read_system_timestamp().into())
@@ -120,6 +136,11 @@ where
         })
     }
 
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    pub fn state_size(&self) -> u64 {
+        wit::read_own_state_size()
+    }
+
     /// Schedules an operation to be included in the block being built.
     ///
     /// The operation is specified as an opaque blob of bytes.
@@ -11,9 +11,11 @@ use linera_base::{
     hex,
     identifiers::{AccountOwner, ApplicationId, ChainId},
 };
+use linera_views::{access_tracking::ViewAccessReport, store::ReadableKeyValueStore as _};
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{DataBlobHash, KeyValueStore, Service, ViewStorageContext};
+use super::QueryContext;
+use crate::{util::BlockingWait, DataBlobHash, KeyValueStore, Service, ViewStorageContext};
 
 /// The runtime available during execution of a query.
 pub struct MockServiceRuntime<Application>
@@ -24,14 +26,17 @@ where
     application_id: Mutex<Option<ApplicationId<Application::Abi>>>,
     chain_id: Mutex<Option<ChainId>>,
     next_block_height: Mutex<Option<BlockHeight>>,
+    query_context: Mutex<Option<QueryContext>>,
     timestamp: Mutex<Option<Timestamp>>,
     chain_balance: Mutex<Option<Amount>>,
     owner_balances: Mutex<Option<HashMap<AccountOwner, Amount>>>,
+    state_size: Mutex<Option<u64>>,
     query_application_handler: Mutex<Option<QueryApplicationHandler>>,
     url_blobs: Mutex<Option<HashMap<String, Vec<u8>>>>,
     blobs: Mutex<Option<HashMap<DataBlobHash, Vec<u8>>>>,
     scheduled_operations: Mutex<Vec<Vec<u8>>>,
     key_value_store: KeyValueStore,
+    scratch: KeyValueStore,
 }
 
 impl<Application> Default for MockServiceRuntime<Application>
@@ -54,14 +59,17 @@ where
             application_id: Mutex::new(None),
             chain_id: Mutex::new(None),
             next_block_height: Mutex::new(None),
+            query_context: Mutex::new(None),
             timestamp: Mutex::new(None),
             chain_balance: Mutex::new(None),
             owner_balances: Mutex::new(None),
+            state_size: Mutex::new(None),
             query_application_handler: Mutex::new(None),
             url_blobs: Mutex::new(None),
             blobs: Mutex::new(None),
             scheduled_operations: Mutex::new(vec![]),
             key_value_store: KeyValueStore::mock(),
+            scratch: KeyValueStore::mock(),
         }
     }
 
@@ -75,6 +83,39 @@ where
         ViewStorageContext::new_unsafe(self.key_value_store(), Vec::new(), ())
     }
 
+    /// Configures the service to read the storage contents of `key_value_store`, for
+    /// instance the one written by a
+    /// [`MockContractRuntime`](crate::contract::MockContractRuntime) of the same
+    /// application.
+    ///
+    /// The service still can't modify the storage.
+    pub fn with_key_value_store(mut self, key_value_store: &KeyValueStore) -> Self {
+        self.key_value_store = key_value_store.to_read_only();
+        self
+    }
+
+    /// Configures the service to read the scratch space in `key_value_store`, for instance the
+    /// one written by a [`MockContractRuntime`](crate::contract::MockContractRuntime) of the
+    /// same application.
+    pub fn with_scratch_key_value_store(mut self, key_value_store: &KeyValueStore) -> Self {
+        self.scratch = key_value_store.to_read_only();
+        self
+    }
+
+    /// Returns the accesses to the application's storage recorded so far, grouped by
+    /// sub-view.
+    pub fn view_access_report(&self) -> ViewAccessReport {
+        self.key_value_store.view_access_report()
+    }
+
+    /// Reads the value under `key` in the scratch space of the application.
+    pub fn scratch_read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.scratch
+            .read_value_bytes(key)
+            .blocking_wait()
+            .expect("Failed to read from the mock scratch space")
+    }
+
     /// Configures the application parameters to return during the test.
     pub fn with_application_parameters(
         self,
@@ -165,6 +206,27 @@ where
         )
     }
 
+    /// Configures the query context to return during the test.
+    pub fn with_query_context(self, query_context: QueryContext) -> Self {
+        *self.query_context.lock().unwrap() = Some(query_context);
+        self
+    }
+
+    /// Configures the query context to return during the test.
+    pub fn set_query_context(&self, query_context: QueryContext) -> &Self {
+        *self.query_context.lock().unwrap() = Some(query_context);
+        self
+    }
+
+    /// Returns the state of the chain the current query is executed against.
+    pub fn query_context(&self) -> QueryContext {
+        Self::fetch_mocked_value(
+            &self.query_context,
+            "Query context has not been mocked, \
+            please call `MockServiceRuntime::set_query_context` first",
+        )
+    }
+
     /// Configures the system time to return during the test.
     pub fn with_system_time(self, timestamp: Timestamp) -> Self {
         *self.timestamp.lock().unwrap() = Some(timestamp);
@@ -207,6 +269,27 @@ where
         )
     }
 
+    /// Configures the size of the application's stored state to return during the test.
+    pub fn with_state_size(self, state_size: u64) -> Self {
+        *self.state_size.lock().unwrap() = Some(state_size);
+        self
+    }
+
+    /// Configures the size of the application's stored state to return during the test.
+    pub fn set_state_size(&self, state_size: u64) -> &Self {
+        *self.state_size.lock().unwrap() = Some(state_size);
+        self
+    }
+
+    /// Returns the total size in bytes of the keys and values stored by this application.
+    pub fn state_size(&self) -> u64 {
+        Self::fetch_mocked_value(
+            &self.state_size,
+            "State size has not been mocked, \
+            please call `MockServiceRuntime::set_state_size` first",
+        )
+    }
+
     /// Configures the balances on the chain to use during the test.
     pub fn with_owner_balances(
         self,
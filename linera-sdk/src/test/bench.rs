@@ -0,0 +1,89 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measurements of the execution time of contract code, for benchmarks running the contract
+//! with the `TestValidator`.
+//!
+//! [`std::time::Instant`] isn't available to contracts compiled to WebAssembly, so the
+//! [`Instant`] of this module reads a monotonic clock provided by the host instead. Only the
+//! test harness of the SDK provides it: validators refuse to load contracts calling the
+//! functions of this module, so these calls should only be compiled in the builds used for
+//! benchmarks, for instance behind a feature of the application.
+//!
+//! ```ignore
+//! #[cfg(feature = "benchmark")]
+//! let outcome = linera_sdk::test::bench::measure_and_record("settle orders", || {
+//!     self.settle_orders(&orders)
+//! });
+//! ```
+//!
+//! The recorded measurements are then returned by
+//! `TestValidator::take_benchmark_measurements` in the integration tests.
+
+use std::time::Duration;
+
+/// A measurement of a monotonic clock, like [`std::time::Instant`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Instant {
+    nanos: u64,
+}
+
+impl Instant {
+    /// Returns the current time of the monotonic clock.
+    pub fn now() -> Self {
+        Instant {
+            nanos: monotonic_nanos(),
+        }
+    }
+
+    /// Returns the time elapsed from `earlier` to this instant, or zero if `earlier` is later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_nanos(self.nanos.saturating_sub(earlier.nanos))
+    }
+
+    /// Returns the time elapsed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+}
+
+/// Runs `code`, and returns its output with its execution time.
+pub fn measure<T>(code: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let output = code();
+    (output, start.elapsed())
+}
+
+/// Runs `code`, records its execution time under `label`, and returns its output.
+pub fn measure_and_record<T>(label: &str, code: impl FnOnce() -> T) -> T {
+    let (output, duration) = measure(code);
+    record(label, duration);
+    output
+}
+
+/// Records that the code measured under `label` took `duration` to execute, in the results of
+/// the test harness.
+///
+/// Outside of WebAssembly, the measurement is discarded.
+pub fn record(label: &str, duration: Duration) {
+    let nanos = duration.as_nanos().try_into().unwrap_or(u64::MAX);
+    #[cfg(target_arch = "wasm32")]
+    crate::contract::wit::test_system_api::record_benchmark(label, nanos);
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = (label, nanos);
+}
+
+/// Returns the number of nanoseconds elapsed on the monotonic clock.
+#[cfg(target_arch = "wasm32")]
+fn monotonic_nanos() -> u64 {
+    crate::contract::wit::test_system_api::monotonic_nanos()
+}
+
+/// Returns the number of nanoseconds elapsed on the monotonic clock.
+#[cfg(not(target_arch = "wasm32"))]
+fn monotonic_nanos() -> u64 {
+    use std::sync::LazyLock;
+
+    static START: LazyLock<std::time::Instant> = LazyLock::new(std::time::Instant::now);
+    START.elapsed().as_nanos().try_into().unwrap_or(u64::MAX)
+}
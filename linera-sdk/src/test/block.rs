@@ -71,6 +71,7 @@ impl BlockBuilder {
                 previous_block_hash,
                 height,
                 authenticated_signer: Some(owner),
+                fee_payer: None,
                 timestamp: Timestamp::from(0),
             },
             validator,
@@ -148,12 +149,23 @@ impl BlockBuilder {
         &mut self,
         application_id: ApplicationId<Abi>,
         operation: impl ToBcsBytes,
+    ) -> &mut Self {
+        let bytes = operation
+            .to_bcs_bytes()
+            .expect("Failed to serialize operation");
+        self.with_raw_operation(application_id, bytes)
+    }
+
+    /// Adds a user operation to this block, already serialized into `bytes`, for instance
+    /// with [`ContractAbi::serialize_operation`](crate::abi::ContractAbi::serialize_operation).
+    pub fn with_raw_operation<Abi>(
+        &mut self,
+        application_id: ApplicationId<Abi>,
+        bytes: Vec<u8>,
     ) -> &mut Self {
         self.block.operations.push(Operation::User {
             application_id: application_id.forget_abi(),
-            bytes: operation
-                .to_bcs_bytes()
-                .expect("Failed to serialize operation"),
+            bytes,
         });
         self
     }
@@ -209,9 +221,40 @@ impl BlockBuilder {
         self.with_incoming_bundles(bundles)
     }
 
+    /// Adds operations in front of the block to execute the scheduled messages that are due at
+    /// its timestamp, as every block must.
+    async fn with_due_scheduled_messages(&mut self) -> anyhow::Result<()> {
+        let chain = self
+            .validator
+            .worker()
+            .chain_state_view(self.block.chain_id)
+            .await?;
+        if chain.is_closed() {
+            return Ok(());
+        }
+        let message_ids = chain
+            .execution_state
+            .system
+            .due_scheduled_messages(self.block.timestamp)
+            .await?;
+        drop(chain);
+
+        let operations = message_ids
+            .into_iter()
+            .map(|message_id| {
+                Operation::from(SystemOperation::ExecuteScheduledMessage { message_id })
+            })
+            .filter(|operation| !self.block.operations.contains(operation))
+            .collect::<Vec<_>>();
+        self.block.operations.splice(0..0, operations);
+        Ok(())
+    }
+
     /// Tries to sign the prepared block with the [`TestValidator`]'s keys and return the
     /// resulting [`Certificate`]. Returns an error if block execution fails.
-    pub(crate) async fn try_sign(self) -> anyhow::Result<ConfirmedBlockCertificate> {
+    pub(crate) async fn try_sign(mut self) -> anyhow::Result<ConfirmedBlockCertificate> {
+        self.with_due_scheduled_messages().await?;
+
         let (executed_block, _) = self
             .validator
             .worker()
@@ -372,6 +372,7 @@ impl ActiveChain {
                     parameters,
                     instantiation_argument,
                     required_application_ids,
+                    max_query_response_bytes: None,
                 });
             })
             .await;
@@ -479,7 +480,9 @@ impl ActiveChain {
                 Query::User {
                     application_id: application_id.forget_abi(),
                     bytes: query_bytes,
+                    authenticated_signer: None,
                 },
+                None,
             )
             .await
             .expect("Failed to query application");
@@ -7,35 +7,43 @@
 //! directory (i.e., beside the `src` directory). Linera application integration tests should be
 //! executed targeting the host architecture, instead of targeting `wasm32-unknown-unknown` like
 //! done for unit tests.
+//!
+//! The [`bench`] module is also available outside of tests, to the contracts measuring their
+//! execution time in benchmarks.
 
-#![cfg(any(with_testing, with_wasm_runtime))]
-
+pub mod bench;
 #[cfg(with_integration_testing)]
 mod block;
 #[cfg(with_integration_testing)]
 mod chain;
+#[cfg(with_testing)]
 mod mock_stubs;
+#[cfg(with_testing)]
+mod outcome;
 #[cfg(with_integration_testing)]
 mod validator;
 
 #[cfg(with_integration_testing)]
 pub use {
     linera_chain::data_types::{Medium, MessageAction},
-    linera_execution::QueryOutcome,
+    linera_execution::{BenchmarkMeasurement, Operation, QueryOutcome},
 };
 
-#[cfg(with_testing)]
-pub use self::mock_stubs::*;
 #[cfg(with_integration_testing)]
 pub use self::{block::BlockBuilder, chain::ActiveChain, validator::TestValidator};
+#[cfg(with_testing)]
+pub use self::{mock_stubs::*, outcome::ExecutionOutcome};
+#[cfg(with_testing)]
 use crate::{Contract, ContractRuntime, Service, ServiceRuntime};
 
 /// Creates a [`ContractRuntime`] to use in tests.
+#[cfg(with_testing)]
 pub fn test_contract_runtime<Application: Contract>() -> ContractRuntime<Application> {
     ContractRuntime::new()
 }
 
 /// Creates a [`ServiceRuntime`] to use in tests.
+#[cfg(with_testing)]
 pub fn test_service_runtime<Application: Service>() -> ServiceRuntime<Application> {
     ServiceRuntime::new()
 }
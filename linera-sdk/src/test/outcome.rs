@@ -0,0 +1,176 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helper type to check the outcome of executing a contract in unit tests.
+
+use std::fmt::Debug;
+
+use linera_base::{
+    data_types::SendMessageRequest,
+    identifiers::{Destination, StreamName},
+};
+
+/// The messages and events produced by a contract while running with a
+/// [`MockContractRuntime`](crate::contract::MockContractRuntime).
+///
+/// Obtained with [`MockContractRuntime::take_execution_outcome`].
+///
+/// [`MockContractRuntime::take_execution_outcome`]:
+/// crate::contract::MockContractRuntime::take_execution_outcome
+#[derive(Debug)]
+pub struct ExecutionOutcome<Message> {
+    messages: Vec<SendMessageRequest<Message>>,
+    events: Vec<(StreamName, Vec<u8>, Vec<u8>)>,
+}
+
+impl<Message> ExecutionOutcome<Message> {
+    /// Creates a new [`ExecutionOutcome`] with the given sent messages and emitted events.
+    pub fn new(
+        messages: Vec<SendMessageRequest<Message>>,
+        events: Vec<(StreamName, Vec<u8>, Vec<u8>)>,
+    ) -> Self {
+        ExecutionOutcome { messages, events }
+    }
+
+    /// Returns the requests to send messages, in the order they were made.
+    pub fn messages(&self) -> &[SendMessageRequest<Message>] {
+        &self.messages
+    }
+
+    /// Returns the messages sent to `destination`, in the order they were sent.
+    pub fn messages_to(&self, destination: impl Into<Destination>) -> Vec<&Message> {
+        let destination = destination.into();
+        self.messages
+            .iter()
+            .filter(|request| request.destination == destination)
+            .map(|request| &request.message)
+            .collect()
+    }
+
+    /// Returns the emitted events, as triples of stream name, key and value.
+    pub fn events(&self) -> &[(StreamName, Vec<u8>, Vec<u8>)] {
+        &self.events
+    }
+
+    /// Asserts that no messages were sent.
+    pub fn assert_no_messages(&self)
+    where
+        Message: Debug,
+    {
+        assert!(
+            self.messages.is_empty(),
+            "Expected no messages to be sent, but got: {:#?}",
+            self.messages
+        );
+    }
+
+    /// Asserts that `expected_message` was sent to `destination`.
+    pub fn assert_message_to(&self, destination: impl Into<Destination>, expected_message: &Message)
+    where
+        Message: Debug + PartialEq,
+    {
+        let destination = destination.into();
+        let sent_messages = self.messages_to(destination.clone());
+        assert!(
+            sent_messages.contains(&expected_message),
+            "Expected message {expected_message:?} to be sent to {destination:?}, \
+            but the messages sent there were: {sent_messages:#?}",
+        );
+    }
+
+    /// Asserts that an event with the given `key` and `value` was emitted on the stream
+    /// `name`.
+    pub fn assert_event(&self, name: &StreamName, key: &[u8], value: &[u8]) {
+        assert!(
+            self.events
+                .iter()
+                .any(|(stream, event_key, event_value)| stream == name
+                    && event_key == key
+                    && event_value == value),
+            "Expected an event with key {key:?} and value {value:?} on stream {name:?}, \
+            but the emitted events were: {:#?}",
+            self.events
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        data_types::{Resources, SendMessageRequest},
+        identifiers::{ChainId, Destination, StreamName},
+    };
+
+    use super::ExecutionOutcome;
+
+    fn message_to(chain_id: ChainId, message: u8) -> SendMessageRequest<u8> {
+        SendMessageRequest {
+            destination: Destination::from(chain_id),
+            authenticated: false,
+            is_tracked: false,
+            grant: Resources::default(),
+            message,
+        }
+    }
+
+    fn sample_outcome() -> ExecutionOutcome<u8> {
+        ExecutionOutcome::new(
+            vec![
+                message_to(ChainId::root(1), 10),
+                message_to(ChainId::root(2), 20),
+                message_to(ChainId::root(1), 11),
+            ],
+            vec![(
+                StreamName(b"stream".to_vec()),
+                b"key".to_vec(),
+                b"value".to_vec(),
+            )],
+        )
+    }
+
+    #[test]
+    fn test_messages_to() {
+        let outcome = sample_outcome();
+
+        assert_eq!(outcome.messages().len(), 3);
+        assert_eq!(outcome.messages_to(ChainId::root(1)), vec![&10, &11]);
+        assert_eq!(outcome.messages_to(ChainId::root(2)), vec![&20]);
+        assert!(outcome.messages_to(ChainId::root(3)).is_empty());
+    }
+
+    #[test]
+    fn test_assert_message_to() {
+        let outcome = sample_outcome();
+
+        outcome.assert_message_to(ChainId::root(1), &11);
+        outcome.assert_message_to(ChainId::root(2), &20);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected message 20 to be sent")]
+    fn test_assert_message_to_wrong_destination() {
+        sample_outcome().assert_message_to(ChainId::root(1), &20);
+    }
+
+    #[test]
+    fn test_assert_no_messages() {
+        ExecutionOutcome::<u8>::new(vec![], vec![]).assert_no_messages();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected no messages to be sent")]
+    fn test_assert_no_messages_with_messages() {
+        sample_outcome().assert_no_messages();
+    }
+
+    #[test]
+    fn test_assert_event() {
+        sample_outcome().assert_event(&StreamName(b"stream".to_vec()), b"key", b"value");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected an event with key")]
+    fn test_assert_event_with_wrong_value() {
+        sample_outcome().assert_event(&StreamName(b"stream".to_vec()), b"key", b"other");
+    }
+}
@@ -5,8 +5,12 @@
 //!
 //! The [`TestValidator`] is a minimal validator with a single shard. Micro-chains can be added to
 //! it, and blocks can be added to each microchain individually.
+//!
+//! The WebAssembly runtime used to execute the applications can be selected by setting the
+//! `LINERA_WASM_RUNTIME` environment variable to `wasmer` or `wasmtime`, provided the
+//! corresponding feature of this crate is enabled.
 
-use std::{num::NonZeroUsize, sync::Arc};
+use std::{env, num::NonZeroUsize, sync::Arc};
 
 use dashmap::DashMap;
 use futures::FutureExt as _;
@@ -20,7 +24,7 @@ use linera_core::worker::WorkerState;
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::{OpenChainConfig, SystemOperation, OPEN_CHAIN_MESSAGE_INDEX},
-    WasmRuntime,
+    BenchmarkMeasurement, BenchmarkRecorder, ExecutionRuntimeConfig, WasmRuntime,
 };
 use linera_storage::{DbStorage, Storage, TestClock};
 use linera_views::memory::MemoryStore;
@@ -29,6 +33,9 @@ use serde::Serialize;
 use super::ActiveChain;
 use crate::ContractAbi;
 
+/// The environment variable that selects the WebAssembly runtime used by the tests.
+const WASM_RUNTIME_VARIABLE: &str = "LINERA_WASM_RUNTIME";
+
 /// A minimal validator implementation suited for tests.
 ///
 /// ```rust
@@ -49,6 +56,7 @@ pub struct TestValidator {
     worker: WorkerState<DbStorage<MemoryStore, TestClock>>,
     clock: TestClock,
     chains: Arc<DashMap<ChainId, ActiveChain>>,
+    benchmark_recorder: BenchmarkRecorder,
 }
 
 impl Clone for TestValidator {
@@ -60,6 +68,7 @@ impl Clone for TestValidator {
             worker: self.worker.clone(),
             clock: self.clock.clone(),
             chains: self.chains.clone(),
+            benchmark_recorder: self.benchmark_recorder.clone(),
         }
     }
 }
@@ -69,10 +78,15 @@ impl TestValidator {
     pub async fn new() -> Self {
         let key_pair = KeyPair::generate();
         let committee = Committee::make_simple(vec![ValidatorName(key_pair.public())]);
-        let wasm_runtime = Some(WasmRuntime::default());
+        let wasm_runtime = Some(Self::wasm_runtime());
+        let benchmark_recorder = BenchmarkRecorder::default();
         let storage = DbStorage::<MemoryStore, _>::make_test_storage(wasm_runtime)
             .now_or_never()
-            .expect("execution of DbStorage::new should not await anything");
+            .expect("execution of DbStorage::new should not await anything")
+            .with_execution_runtime_config(ExecutionRuntimeConfig {
+                benchmark_recorder: Some(benchmark_recorder.clone()),
+                ..ExecutionRuntimeConfig::default()
+            });
         let clock = storage.clock().clone();
         let worker = WorkerState::new(
             "Single validator node".to_string(),
@@ -88,12 +102,25 @@ impl TestValidator {
             worker,
             clock,
             chains: Arc::default(),
+            benchmark_recorder,
         };
 
         validator.create_admin_chain().await;
         validator
     }
 
+    /// Returns the WebAssembly runtime selected by the `LINERA_WASM_RUNTIME` environment
+    /// variable, or the default one if the variable isn't set.
+    fn wasm_runtime() -> WasmRuntime {
+        match env::var(WASM_RUNTIME_VARIABLE) {
+            Ok(runtime) => runtime.parse().unwrap_or_else(|error| {
+                panic!("Invalid `{WASM_RUNTIME_VARIABLE}` environment variable: {error}")
+            }),
+            Err(env::VarError::NotPresent) => WasmRuntime::default(),
+            Err(error) => panic!("Invalid `{WASM_RUNTIME_VARIABLE}` environment variable: {error}"),
+        }
+    }
+
     /// Creates a new [`TestValidator`] with a single microchain with the bytecode of the crate
     /// calling this method published on it.
     ///
@@ -154,6 +181,15 @@ impl TestValidator {
         &self.clock
     }
 
+    /// Removes and returns the execution times measured by the contracts with the
+    /// [`bench`](crate::test::bench) module so far, in the order they were recorded.
+    ///
+    /// Each block is executed once to be signed and once more when its certificate is handled,
+    /// so the measurements taken during a block are recorded twice.
+    pub fn take_benchmark_measurements(&self) -> Vec<BenchmarkMeasurement> {
+        self.benchmark_recorder.take_measurements()
+    }
+
     /// Returns the keys this test validator uses for signing certificates.
     pub fn key_pair(&self) -> &KeyPair {
         &self.key_pair
@@ -75,12 +75,37 @@ where
 /// Unit tests for the helpers defined in the `util` module.
 #[cfg(test)]
 mod tests {
-    use std::task::{Context, Poll};
-
-    use futures::{future::poll_fn, task::noop_waker, FutureExt as _};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+        thread,
+        time::Duration,
+    };
+
+    use futures::{
+        channel::oneshot,
+        future::poll_fn,
+        task::{noop_waker, waker, ArcWake},
+        FutureExt as _,
+    };
 
     use super::{yield_once, BlockingWait};
 
+    /// A waker that counts how many times it was woken.
+    #[derive(Default)]
+    struct CountingWaker {
+        wake_count: AtomicUsize,
+    }
+
+    impl ArcWake for CountingWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.wake_count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
     /// Tests the behavior of the [`YieldOnce`] future.
     ///
     /// Checks the internal state before and after the first and second polls, and ensures that
@@ -100,6 +125,64 @@ mod tests {
         assert_eq!(future.yielded, true);
     }
 
+    /// Tests that [`YieldOnce`] schedules exactly one wake-up, and that polling it again after
+    /// it completed, possibly with another waker, neither panics nor wakes anything.
+    #[test]
+    fn yield_once_wakes_once_and_tolerates_polls_after_completion() {
+        let first_waker = Arc::new(CountingWaker::default());
+        let second_waker = Arc::new(CountingWaker::default());
+        let mut future = yield_once();
+
+        let waker_handle = waker(first_waker.clone());
+        let mut context = Context::from_waker(&waker_handle);
+        assert!(future.poll_unpin(&mut context).is_pending());
+        assert_eq!(first_waker.wake_count.load(Ordering::SeqCst), 1);
+
+        let waker_handle = waker(second_waker.clone());
+        let mut context = Context::from_waker(&waker_handle);
+        for _ in 0..3 {
+            assert!(future.poll_unpin(&mut context).is_ready());
+        }
+        assert_eq!(first_waker.wake_count.load(Ordering::SeqCst), 1);
+        assert_eq!(second_waker.wake_count.load(Ordering::SeqCst), 0);
+    }
+
+    /// Tests that [`BlockingWait`] doesn't depend on wake-ups, so that a future that completes
+    /// from another thread, with its waker invoked from there, is still driven to completion.
+    #[test]
+    fn blocking_wait_completes_futures_woken_from_other_threads() {
+        let (sender, receiver) = oneshot::channel();
+
+        let sending_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender
+                .send(42_u32)
+                .expect("Receiver should still be waiting");
+        });
+
+        assert_eq!(receiver.blocking_wait(), Ok(42));
+        sending_thread
+            .join()
+            .expect("Sending thread should not panic");
+    }
+
+    /// Tests that [`BlockingWait`] drives futures that yield several times and only make
+    /// progress on some of their polls.
+    #[test]
+    fn blocking_wait_drives_interleaved_yields() {
+        let steps = AtomicUsize::new(0);
+        let step = || async {
+            for _ in 0..3 {
+                yield_once().await;
+                steps.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+
+        futures::future::join(step(), step()).blocking_wait();
+
+        assert_eq!(steps.load(Ordering::SeqCst), 6);
+    }
+
     /// Tests the behavior of the [`BlockingWait`] extension.
     #[test]
     fn blocking_wait_blocks_until_future_is_ready() {
@@ -0,0 +1,83 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Paginated reads of the most recent entries of a [`LogView`].
+
+use linera_views::views::ViewError;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::LogView;
+
+/// A page of entries read from the tail of a [`LogView`], newest first.
+///
+/// Since a log is append-only, entries are identified by their index and a cursor remains
+/// valid when new entries are pushed: requesting the next page with [`LogPage::next_cursor`]
+/// continues exactly where the previous page stopped.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LogPage<T> {
+    /// The entries of the page, starting from the most recent one.
+    pub entries: Vec<T>,
+    /// The cursor to request the next (older) page with, or `None` if this page reaches the
+    /// beginning of the log.
+    pub next_cursor: Option<usize>,
+}
+
+impl<T> LogPage<T>
+where
+    T: Clone + DeserializeOwned + Serialize + Send + Sync,
+{
+    /// Reads at most `limit` entries of `log` that are older than `cursor`, newest first.
+    ///
+    /// The cursor is the index of the first entry that is excluded from the page. If it is
+    /// `None`, the page starts with the last entry of the log.
+    pub async fn read_tail(
+        log: &LogView<T>,
+        cursor: Option<usize>,
+        limit: usize,
+    ) -> Result<Self, ViewError> {
+        let end = cursor.map_or(log.count(), |cursor| cursor.min(log.count()));
+        let start = end.saturating_sub(limit);
+        let entries = log.iter_rev(start..end).await?.collect();
+        let next_cursor = (start > 0).then_some(start);
+        Ok(LogPage {
+            entries,
+            next_cursor,
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use linera_views::views::View;
+
+    use super::*;
+    use crate::views::{KeyValueStore, ViewStorageContext};
+
+    #[tokio::test]
+    async fn test_pages_are_stable_when_entries_are_pushed() -> anyhow::Result<()> {
+        let store = KeyValueStore::mock().to_mut();
+        let context = ViewStorageContext::new_unsafe(store, Vec::new(), ());
+        let mut log = LogView::load(context).await?;
+
+        let page = LogPage::read_tail(&log, None, 2).await?;
+        assert_eq!(page, LogPage::default());
+
+        for value in 0..5_u32 {
+            log.push(value);
+        }
+        let page = LogPage::read_tail(&log, None, 2).await?;
+        assert_eq!(page.entries, vec![4, 3]);
+        assert_eq!(page.next_cursor, Some(3));
+
+        log.push(5);
+        let page = LogPage::read_tail(&log, page.next_cursor, 2).await?;
+        assert_eq!(page.entries, vec![2, 1]);
+        assert_eq!(page.next_cursor, Some(1));
+
+        let page = LogPage::read_tail(&log, page.next_cursor, 2).await?;
+        assert_eq!(page.entries, vec![0]);
+        assert_eq!(page.next_cursor, None);
+
+        Ok(())
+    }
+}
@@ -13,14 +13,16 @@ use std::{
 
 use futures::FutureExt as _;
 use linera_views::{
+    access_tracking::{AccessTrackingStore, ViewAccessReport},
     batch::Batch,
     memory::{create_test_memory_store, MemoryStore},
     store::{ReadableKeyValueStore, WritableKeyValueStore},
 };
 
-/// A mock [`KeyValueStore`] implementation using a [`MemoryStore`].
+/// A mock [`KeyValueStore`] implementation using a [`MemoryStore`], recording the accesses
+/// to it.
 pub(super) struct MockKeyValueStore {
-    store: MemoryStore,
+    store: AccessTrackingStore<MemoryStore>,
     contains_key_promises: PromiseRegistry<bool>,
     contains_keys_promises: PromiseRegistry<Vec<bool>>,
     read_multi_promises: PromiseRegistry<Vec<Option<Vec<u8>>>>,
@@ -32,7 +34,7 @@ pub(super) struct MockKeyValueStore {
 impl Default for MockKeyValueStore {
     fn default() -> Self {
         MockKeyValueStore {
-            store: create_test_memory_store(),
+            store: AccessTrackingStore::new(create_test_memory_store()),
             contains_key_promises: PromiseRegistry::default(),
             contains_keys_promises: PromiseRegistry::default(),
             read_multi_promises: PromiseRegistry::default(),
@@ -72,6 +74,16 @@ impl<T> PromiseRegistry<T> {
 }
 
 impl MockKeyValueStore {
+    /// Returns the accesses to the storage recorded so far, grouped by sub-view.
+    pub(crate) fn view_access_report(&self) -> ViewAccessReport {
+        self.store.access_report()
+    }
+
+    /// Returns the accesses to the storage recorded so far, and starts a new report.
+    pub(crate) fn take_view_access_report(&self) -> ViewAccessReport {
+        self.store.take_access_report()
+    }
+
     /// Checks if `key` is present in the storage, returning a promise to retrieve the final
     /// value.
     pub(crate) fn contains_key_new(&self, key: &[u8]) -> u32 {
@@ -5,8 +5,10 @@
 
 mod aliases;
 mod conversions_to_wit;
+mod log_page;
 #[cfg(with_testing)]
 mod mock_key_value_store;
+mod processed_messages;
 mod system_api;
 
 pub use linera_views::{
@@ -21,5 +23,7 @@ pub use self::{
         CustomMapView, CustomSetView, LogView, MapView, QueueView, ReadGuardedView, RegisterView,
         SetView,
     },
-    system_api::{KeyValueStore, ViewStorageContext},
+    log_page::LogPage,
+    processed_messages::ProcessedMessages,
+    system_api::{KeyValueStore, KeyValueStoreError, ViewStorageContext},
 };
@@ -0,0 +1,79 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A view to help contracts handle incoming messages idempotently.
+
+use linera_base::identifiers::MessageId;
+use linera_views::views::{View, ViewError};
+
+use super::{SetView, ViewStorageContext};
+
+/// Records the [`MessageId`]s of the incoming messages already handled by a contract.
+///
+/// The protocol executes each message at most once per receiving chain: a message is identified
+/// by its origin chain, the height of the block that created it and its index in that block,
+/// and a bundle that was already consumed from an inbox can't be consumed again. This view is
+/// useful for applications that need to additionally detect the same logical request arriving
+/// through different messages, e.g. after a retry by the sender.
+#[derive(View)]
+#[view(context = "ViewStorageContext")]
+pub struct ProcessedMessages {
+    processed: SetView<MessageId>,
+}
+
+impl ProcessedMessages {
+    /// Records `message_id` as processed.
+    ///
+    /// Returns `false` if the message had already been recorded, in which case it should be
+    /// ignored by the caller.
+    pub async fn mark_as_processed(&mut self, message_id: MessageId) -> Result<bool, ViewError> {
+        if self.processed.contains(&message_id).await? {
+            return Ok(false);
+        }
+        self.processed.insert(&message_id)?;
+        Ok(true)
+    }
+
+    /// Returns `true` if `message_id` was already recorded as processed.
+    pub async fn contains(&self, message_id: &MessageId) -> Result<bool, ViewError> {
+        self.processed.contains(message_id).await
+    }
+
+    /// Returns the number of processed messages that were recorded.
+    pub async fn count(&self) -> Result<usize, ViewError> {
+        self.processed.count().await
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use linera_base::{data_types::BlockHeight, identifiers::ChainId};
+
+    use super::*;
+    use crate::views::KeyValueStore;
+
+    #[tokio::test]
+    async fn test_duplicate_message_is_detected() -> anyhow::Result<()> {
+        let store = KeyValueStore::mock().to_mut();
+        let context = ViewStorageContext::new_unsafe(store, Vec::new(), ());
+        let mut processed = ProcessedMessages::load(context).await?;
+
+        let message_id = MessageId {
+            chain_id: ChainId::root(1),
+            height: BlockHeight(3),
+            index: 2,
+        };
+        let other_message_id = MessageId {
+            index: 3,
+            ..message_id
+        };
+
+        assert!(processed.mark_as_processed(message_id).await?);
+        assert!(!processed.mark_as_processed(message_id).await?);
+        assert!(!processed.contains(&other_message_id).await?);
+        assert!(processed.mark_as_processed(other_message_id).await?);
+        assert_eq!(processed.count().await?, 2);
+
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@
 use std::sync::Arc;
 
 use linera_base::ensure;
+#[cfg(with_testing)]
+use linera_views::access_tracking::ViewAccessReport;
 use linera_views::{
     batch::Batch,
     store::{ReadableKeyValueStore, WithError, WritableKeyValueStore},
@@ -77,6 +79,42 @@ impl KeyValueStore {
             },
         }
     }
+
+    /// Returns a mocked [`KeyValueStore`] that shares the memory storage with this instance but
+    /// only allows read operations, like the storage of a service.
+    #[cfg(with_testing)]
+    pub fn to_read_only(&self) -> Self {
+        let WitInterface::Mock { store, .. } = &self.wit_api else {
+            panic!("Real `KeyValueStore` should not be used in unit tests");
+        };
+
+        KeyValueStore {
+            wit_api: WitInterface::Mock {
+                store: store.clone(),
+                read_only: true,
+            },
+        }
+    }
+
+    /// Returns the accesses to this mocked [`KeyValueStore`] recorded so far, grouped by
+    /// sub-view.
+    #[cfg(with_testing)]
+    pub fn view_access_report(&self) -> ViewAccessReport {
+        let WitInterface::Mock { store, .. } = &self.wit_api else {
+            panic!("Real `KeyValueStore` should not be used in unit tests");
+        };
+        store.view_access_report()
+    }
+
+    /// Returns the accesses to this mocked [`KeyValueStore`] recorded so far, and starts a
+    /// new report.
+    #[cfg(with_testing)]
+    pub fn take_view_access_report(&self) -> ViewAccessReport {
+        let WitInterface::Mock { store, .. } = &self.wit_api else {
+            panic!("Real `KeyValueStore` should not be used in unit tests");
+        };
+        store.take_view_access_report()
+    }
 }
 
 impl WithError for KeyValueStore {
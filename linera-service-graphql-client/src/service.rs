@@ -228,6 +228,7 @@ mod from {
                 height,
                 timestamp,
                 authenticated_signer,
+                fee_payer,
                 previous_block_hash,
                 state_hash,
                 bundles_hash,
@@ -250,6 +251,7 @@ mod from {
                 height,
                 timestamp,
                 authenticated_signer,
+                fee_payer,
                 previous_block_hash,
                 state_hash,
                 bundles_hash,
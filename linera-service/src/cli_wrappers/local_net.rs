@@ -23,6 +23,11 @@ use linera_client::storage::{StorageConfig, StorageConfigNamespace};
 use linera_execution::ResourceControlPolicy;
 #[cfg(all(feature = "storage-service", with_testing))]
 use linera_storage_service::common::storage_service_test_endpoint;
+#[cfg(feature = "storage-service")]
+use linera_storage_service::{
+    child::{StorageService, StorageServiceGuard},
+    common::get_service_storage_binary,
+};
 #[cfg(all(feature = "scylladb", with_testing))]
 use linera_views::{scylla_db::ScyllaDbStore, store::TestKeyValueStore as _};
 use tempfile::{tempdir, TempDir};
@@ -76,7 +81,11 @@ async fn make_testing_config(database: Database) -> Result<StorageConfig> {
             #[cfg(feature = "dynamodb")]
             {
                 let use_localstack = true;
-                Ok(StorageConfig::DynamoDb { use_localstack })
+                let read_consistency = Default::default();
+                Ok(StorageConfig::DynamoDb {
+                    use_localstack,
+                    read_consistency,
+                })
             }
             #[cfg(not(feature = "dynamodb"))]
             panic!("Database::DynamoDb is selected without the feature aws");
@@ -114,6 +123,25 @@ impl StorageConfigBuilder {
     }
 }
 
+/// Starts a storage service as a child process, keeping the data in memory, or in a RocksDB
+/// database if `rocks_db_path` is set.
+///
+/// Returns the configuration to access the service, and the guard that terminates it when
+/// dropped.
+#[cfg(feature = "storage-service")]
+pub async fn start_storage_service(
+    rocks_db_path: Option<PathBuf>,
+) -> Result<(StorageConfig, StorageServiceGuard)> {
+    let endpoint = linera_base::port::get_free_endpoint().await?;
+    let binary = get_service_storage_binary().await?.display().to_string();
+    let mut service = StorageService::new(&endpoint, binary);
+    if let Some(path) = rocks_db_path {
+        service = service.with_rocks_db_path(path);
+    }
+    let guard = service.run().await?;
+    Ok((StorageConfig::Service { endpoint }, guard))
+}
+
 /// Path used for the run can come from a path whose lifetime is controlled
 /// by an external user or as a temporary directory
 #[derive(Clone)]
@@ -377,6 +405,16 @@ impl LocalNet {
         crate::util::read_json(path.join("genesis.json"))
     }
 
+    /// Returns the configuration of the given validator's servers.
+    #[cfg(with_testing)]
+    pub fn validator_server_config(
+        &self,
+        validator: usize,
+    ) -> Result<linera_client::config::ValidatorServerConfig> {
+        let path = self.path_provider.path();
+        crate::util::read_json(path.join(format!("server_{validator}.json")))
+    }
+
     pub fn proxy_port(validator: usize) -> usize {
         9000 + validator * 100
     }
@@ -389,11 +427,13 @@ impl LocalNet {
         10000 + validator * 100
     }
 
-    fn proxy_metrics_port(validator: usize) -> usize {
+    /// Returns the port where the proxy of the given validator serves its metrics.
+    pub fn proxy_metrics_port(validator: usize) -> usize {
         11000 + validator * 100
     }
 
-    fn shard_metrics_port(validator: usize, shard: usize) -> usize {
+    /// Returns the port where the given shard of the given validator serves its metrics.
+    pub fn shard_metrics_port(validator: usize, shard: usize) -> usize {
         11000 + validator * 100 + shard + 1
     }
 
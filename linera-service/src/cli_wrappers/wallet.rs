@@ -4,6 +4,7 @@
 use std::{
     borrow::Cow,
     collections::HashMap,
+    convert::Infallible,
     env,
     marker::PhantomData,
     mem,
@@ -13,25 +14,29 @@ use std::{
     time::Duration,
 };
 
-use anyhow::{bail, ensure, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use async_graphql::InputType;
 use async_tungstenite::tungstenite::{client::IntoClientRequest as _, http::HeaderValue};
-use futures::{SinkExt as _, Stream, StreamExt as _, TryStreamExt as _};
+use futures::{stream::BoxStream, SinkExt as _, Stream, StreamExt as _, TryStreamExt as _};
 use linera_base::{
-    abi::ContractAbi,
+    abi::{ContractAbi, EventAbi},
     command::{resolve_binary, CommandExt},
     crypto::CryptoHash,
-    data_types::{Amount, Bytecode},
-    identifiers::{Account, ApplicationId, BytecodeId, ChainId, MessageId, Owner},
+    data_types::{Amount, BlockHeight, Bytecode},
+    identifiers::{Account, ApplicationId, BytecodeId, ChainId, MessageId, Owner, StreamName},
 };
 use linera_client::{config::GenesisConfig, wallet::Wallet};
 use linera_core::worker::Notification;
 use linera_execution::{committee::ValidatorName, system::SystemChannel, ResourceControlPolicy};
 use linera_version::VersionInfo;
-use serde::{de::DeserializeOwned, ser::Serialize};
+use serde::{de::DeserializeOwned, ser::Serialize, Deserialize};
 use serde_json::{json, Value};
 use tempfile::TempDir;
-use tokio::process::{Child, Command};
+use tokio::{
+    process::{Child, Command},
+    sync::mpsc,
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{error, info, warn};
 
 use crate::{
@@ -254,6 +259,17 @@ impl ClientWrapper {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_application_state_size,
+            maximum_operations_per_block,
+            maximum_incoming_bundles_per_block,
+            maximum_incoming_bundles_per_origin,
+            burn_unused_grants,
+            maximum_query_response_size,
+            maximum_messages_per_execution,
+            maximum_message_size,
+            maximum_message_bytes_per_execution,
+            maximum_call_depth,
+            resource_estimate_tolerance_percent,
         } = policy;
         let mut command = self.command().await?;
         command
@@ -303,7 +319,46 @@ impl ClientWrapper {
             .args([
                 "--maximum-bytes-written-per-block",
                 &maximum_bytes_written_per_block.to_string(),
+            ])
+            .args([
+                "--maximum-application-state-size",
+                &maximum_application_state_size.to_string(),
+            ])
+            .args([
+                "--maximum-operations-per-block",
+                &maximum_operations_per_block.to_string(),
+            ])
+            .args([
+                "--maximum-incoming-bundles-per-block",
+                &maximum_incoming_bundles_per_block.to_string(),
+            ])
+            .args([
+                "--maximum-incoming-bundles-per-origin",
+                &maximum_incoming_bundles_per_origin.to_string(),
+            ])
+            .args([
+                "--maximum-query-response-size",
+                &maximum_query_response_size.to_string(),
+            ])
+            .args([
+                "--maximum-messages-per-execution",
+                &maximum_messages_per_execution.to_string(),
+            ])
+            .args(["--maximum-message-size", &maximum_message_size.to_string()])
+            .args([
+                "--maximum-message-bytes-per-execution",
+                &maximum_message_bytes_per_execution.to_string(),
+            ])
+            .args(["--maximum-call-depth", &maximum_call_depth.to_string()]);
+        if burn_unused_grants {
+            command.arg("--burn-unused-grants");
+        }
+        if let Some(tolerance) = resource_estimate_tolerance_percent {
+            command.args([
+                "--resource-estimate-tolerance-percent",
+                &tolerance.to_string(),
             ]);
+        }
         if let Some(seed) = self.testing_prng_seed {
             command.arg("--testing-prng-seed").arg(seed.to_string());
         }
@@ -391,6 +446,40 @@ impl ClientWrapper {
         Ok(stdout.trim().parse::<ApplicationId>()?.with_abi())
     }
 
+    /// Runs `linera publish-and-create --json`, reading the parameters and the optional
+    /// instantiation argument from JSON files.
+    pub async fn publish_and_create_from_files<A: ContractAbi>(
+        &self,
+        contract: PathBuf,
+        service: PathBuf,
+        parameters_path: &Path,
+        argument_path: Option<&Path>,
+        publisher: impl Into<Option<ChainId>>,
+    ) -> Result<(BytecodeId, ApplicationId<A>)> {
+        let mut command = self.command().await?;
+        command
+            .arg("publish-and-create")
+            .args([contract, service])
+            .args(publisher.into().iter().map(ChainId::to_string))
+            .arg("--json-parameters-path")
+            .arg(parameters_path);
+        if let Some(argument_path) = argument_path {
+            command.arg("--json-argument-path").arg(argument_path);
+        }
+        command.arg("--json");
+        let stdout = command.spawn_and_wait_for_stdout().await?;
+        let output: Value = serde_json::from_str(stdout.trim())?;
+        let bytecode_id = output["bytecode_id"]
+            .as_str()
+            .context("missing bytecode ID")?
+            .parse::<BytecodeId>()?;
+        let application_id = output["application_id"]
+            .as_str()
+            .context("missing application ID")?
+            .parse::<ApplicationId>()?;
+        Ok((bytecode_id, application_id.with_abi()))
+    }
+
     /// Runs `linera publish-bytecode`.
     pub async fn publish_bytecode<Abi, Parameters, InstantiationArgument>(
         &self,
@@ -607,6 +696,18 @@ impl ClientWrapper {
         Ok(())
     }
 
+    /// Runs `linera inbox` and returns the pending messages it lists, one per line.
+    pub async fn inbox(&self, chain_id: ChainId) -> Result<Vec<String>> {
+        let stdout = self
+            .command()
+            .await?
+            .arg("inbox")
+            .arg(chain_id.to_string())
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(stdout.lines().map(str::to_string).collect())
+    }
+
     /// Runs `linera transfer`.
     pub async fn transfer(&self, amount: Amount, from: ChainId, to: ChainId) -> Result<()> {
         self.command()
@@ -620,6 +721,45 @@ impl ClientWrapper {
         Ok(())
     }
 
+    /// Runs `linera query-message-status` and returns the status it prints.
+    pub async fn query_message_status(
+        &self,
+        certificate_hash: CryptoHash,
+        index: u32,
+        recipient: ChainId,
+    ) -> Result<String> {
+        let stdout = self
+            .command()
+            .await?
+            .arg("query-message-status")
+            .arg(certificate_hash.to_string())
+            .arg(index.to_string())
+            .arg(recipient.to_string())
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(stdout.trim().to_string())
+    }
+
+    /// Runs `linera verify-chain`, optionally reading the certificates from or saving them to
+    /// a file, and returns the report it prints.
+    pub async fn verify_chain(
+        &self,
+        chain_id: ChainId,
+        certificates: Option<&Path>,
+        save_certificates: Option<&Path>,
+    ) -> Result<String> {
+        let mut command = self.command().await?;
+        command.arg("verify-chain").arg(chain_id.to_string());
+        if let Some(path) = certificates {
+            command.arg("--certificates").arg(path);
+        }
+        if let Some(path) = save_certificates {
+            command.arg("--save-certificates").arg(path);
+        }
+        let stdout = command.spawn_and_wait_for_stdout().await?;
+        Ok(stdout.trim().to_string())
+    }
+
     /// Runs `linera transfer` with no logging.
     pub async fn transfer_with_silent_logs(
         &self,
@@ -785,6 +925,49 @@ impl ClientWrapper {
         Ok(())
     }
 
+    /// Runs `linera rotate-key`.
+    pub async fn rotate_key(&self, chain_id: ChainId) -> Result<Owner> {
+        let stdout = self
+            .command()
+            .await?
+            .arg("rotate-key")
+            .arg(chain_id.to_string())
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(Owner::from_str(stdout.trim())?)
+    }
+
+    /// Runs `linera transfer-chain-ownership`.
+    pub async fn transfer_chain_ownership(
+        &self,
+        chain_id: ChainId,
+        new_owner: Owner,
+    ) -> Result<()> {
+        self.command()
+            .await?
+            .arg("transfer-chain-ownership")
+            .args(["--chain-id", &chain_id.to_string()])
+            .args(["--new-owner", &new_owner.to_string()])
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(())
+    }
+
+    /// Runs `linera close-chain`, with `--pending-messages` if given.
+    pub async fn close_chain(
+        &self,
+        chain_id: ChainId,
+        pending_messages: Option<&str>,
+    ) -> Result<()> {
+        let mut command = self.command().await?;
+        command.arg("close-chain").arg(chain_id.to_string());
+        if let Some(pending_messages) = pending_messages {
+            command.args(["--pending-messages", pending_messages]);
+        }
+        command.spawn_and_wait_for_stdout().await?;
+        Ok(())
+    }
+
     /// Runs `linera wallet forget-chain CHAIN_ID`.
     pub async fn forget_chain(&self, chain_id: ChainId) -> Result<()> {
         let mut command = self.command().await?;
@@ -929,6 +1112,18 @@ impl ClientWrapper {
         Ok(chain_id)
     }
 
+    /// Runs `linera assign` for a chain that was transferred to `owner`.
+    pub async fn assign_chain(&self, owner: Owner, chain_id: ChainId) -> Result<()> {
+        self.command()
+            .await?
+            .arg("assign")
+            .args(["--owner", &owner.to_string()])
+            .args(["--chain-id", &chain_id.to_string()])
+            .spawn_and_wait_for_stdout()
+            .await?;
+        Ok(())
+    }
+
     pub async fn build_application(
         &self,
         path: &Path,
@@ -1086,6 +1281,17 @@ impl NodeService {
         self.child.ensure_is_running()
     }
 
+    /// Returns the metrics served by the node service, in the Prometheus text format.
+    pub async fn metrics(&self) -> Result<String> {
+        let response = reqwest_client()
+            .get(format!("http://localhost:{}/metrics", self.port))
+            .send()
+            .await
+            .context("metrics: failed to get metrics")?
+            .error_for_status()?;
+        Ok(response.text().await?)
+    }
+
     pub async fn process_inbox(&self, chain_id: &ChainId) -> Result<Vec<CryptoHash>> {
         let query = format!("mutation {{ processInbox(chainId: \"{chain_id}\") }}");
         let mut data = self.query_node(query).await?;
@@ -1169,52 +1375,7 @@ impl NodeService {
     }
 
     pub async fn query_node(&self, query: impl AsRef<str>) -> Result<Value> {
-        let n_try = 5;
-        let query = query.as_ref();
-        for i in 0..n_try {
-            linera_base::time::timer::sleep(Duration::from_secs(i)).await;
-            let url = format!("http://localhost:{}/", self.port);
-            let client = reqwest_client();
-            let result = client
-                .post(url)
-                .json(&json!({ "query": query }))
-                .send()
-                .await;
-            if matches!(result, Err(ref error) if error.is_timeout()) {
-                warn!("Timeout when sending query {query:?} to the node service");
-                continue;
-            }
-            let response = result.with_context(|| {
-                format!(
-                    "query_node: failed to post query={}",
-                    truncate_query_output(query)
-                )
-            })?;
-            anyhow::ensure!(
-                response.status().is_success(),
-                "Query \"{}\" failed: {}",
-                truncate_query_output(query),
-                response
-                    .text()
-                    .await
-                    .unwrap_or_else(|error| format!("Could not get response text: {error}"))
-            );
-            let value: Value = response.json().await.context("invalid JSON")?;
-            if let Some(errors) = value.get("errors") {
-                warn!(
-                    "Query \"{}\" failed: {}",
-                    truncate_query_output(query),
-                    errors
-                );
-            } else {
-                return Ok(value["data"].clone());
-            }
-        }
-        bail!(
-            "Query \"{}\" failed after {} retries.",
-            truncate_query_output(query),
-            n_try
-        );
+        query_node_service(self.port, query.as_ref()).await
     }
 
     pub async fn create_application<
@@ -1319,49 +1480,361 @@ impl NodeService {
         chain_id: ChainId,
     ) -> Result<impl Stream<Item = Result<Notification>>> {
         let query = format!("subscription {{ notifications(chainId: \"{chain_id}\") }}",);
-        let url = format!("ws://localhost:{}/ws", self.port);
-        let mut request = url.into_client_request()?;
-        request.headers_mut().insert(
-            "Sec-WebSocket-Protocol",
-            HeaderValue::from_str("graphql-transport-ws")?,
+        subscribe_to_node_service(self.port, query, "notifications").await
+    }
+
+    /// Subscribes to the node service and returns a stream of the blocks executed on a chain.
+    pub async fn blocks(&self, chain_id: ChainId) -> Result<impl Stream<Item = Result<Value>>> {
+        let query = format!(
+            "subscription {{ blocks(chainId: \"{chain_id}\") {{ \
+                height hash stateHash skipped \
+                events {{ transactionIndex event {{ key value }} }} \
+            }} }}"
         );
-        let (mut websocket, _) = async_tungstenite::tokio::connect_async(request).await?;
-        let init_json = json!({
-          "type": "connection_init",
-          "payload": {}
-        });
-        websocket.send(init_json.to_string().into()).await?;
-        let text = websocket
-            .next()
-            .await
-            .context("Failed to establish connection")??
-            .into_text()?;
-        ensure!(
-            text == "{\"type\":\"connection_ack\"}",
-            "Unexpected response: {text}"
+        subscribe_to_node_service(self.port, query, "blocks").await
+    }
+
+    /// Subscribes to the events emitted by an application on a chain, decoded using the
+    /// application's ABI.
+    ///
+    /// Each event is returned exactly once, in the order in which it was emitted. If the
+    /// connection to the node service is lost, the subscription is re-established and the
+    /// events emitted in the meantime are read with the `events` query, resuming after the last
+    /// block that was seen. The stream ends with an error if the node service can't be reached
+    /// anymore.
+    ///
+    /// Only the events emitted in the blocks executed after this call returns are included.
+    pub async fn subscribe_events<A: EventAbi>(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId<A>,
+    ) -> Result<UnboundedReceiverStream<Result<ApplicationEvent<A::Event>>>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut subscriber = EventSubscriber {
+            port: self.port,
+            chain_id,
+            application_id: application_id.forget_abi(),
+            next_height: None,
+            sender,
+        };
+        let notifications = subscriber.connect().await?;
+        tokio::spawn(subscriber.run(notifications));
+        Ok(UnboundedReceiverStream::new(receiver))
+    }
+}
+
+/// An event emitted by an application, decoded using the application's [`EventAbi`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApplicationEvent<Event> {
+    /// The height of the block in which the event was emitted.
+    pub height: BlockHeight,
+    /// The index of the transaction that emitted the event, within its block.
+    pub transaction_index: u32,
+    /// The name of the stream the event was emitted on.
+    pub stream_name: StreamName,
+    /// The key of the event.
+    pub key: Vec<u8>,
+    /// The event itself.
+    pub event: Event,
+}
+
+/// The fields of an `IndexedEvent` read by [`EventSubscriber`].
+const INDEXED_EVENT_FIELDS: &str =
+    "height transactionIndex event { streamId { streamName } key value }";
+
+/// The number of blocks whose events are read at once when catching up with a chain.
+const EVENT_PAGE_SIZE: u32 = 100;
+
+/// The number of attempts to reconnect to the node service after which an event subscription
+/// gives up.
+const EVENT_SUBSCRIPTION_RETRIES: u32 = 30;
+
+/// The delay between two attempts to re-establish an event subscription.
+const EVENT_SUBSCRIPTION_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// An `IndexedEvent`, as returned by the node service.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawIndexedEvent {
+    height: BlockHeight,
+    transaction_index: u32,
+    event: RawEventRecord,
+}
+
+/// An `EventRecord`, as returned by the node service.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEventRecord {
+    stream_id: RawStreamId,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+/// A `StreamId`, as returned by the node service.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawStreamId {
+    stream_name: StreamName,
+}
+
+/// A `BlockNotification`, as returned by the node service.
+#[derive(Deserialize)]
+struct RawBlockNotification {
+    height: BlockHeight,
+    events: Vec<RawIndexedEvent>,
+}
+
+/// The stream of the blocks executed on a chain, as notified by the node service.
+type BlockNotifications = BoxStream<'static, Result<RawBlockNotification>>;
+
+/// A subscription to the events of an application, kept across reconnections to the node
+/// service.
+struct EventSubscriber<Event> {
+    port: u16,
+    chain_id: ChainId,
+    application_id: ApplicationId,
+    /// The height of the first block whose events haven't been delivered yet, or `None` before
+    /// the first connection.
+    next_height: Option<BlockHeight>,
+    sender: mpsc::UnboundedSender<Result<ApplicationEvent<Event>>>,
+}
+
+impl<Event: DeserializeOwned> EventSubscriber<Event> {
+    /// Subscribes to the new blocks of the chain, then delivers the events emitted since the
+    /// last connection.
+    async fn connect(&mut self) -> Result<BlockNotifications> {
+        let query = format!(
+            "subscription {{ blocks(chainId: \"{}\", applicationId: \"{}\") {{ \
+                height events {{ {INDEXED_EVENT_FIELDS} }} \
+            }} }}",
+            self.chain_id, self.application_id
         );
-        let query_json = json!({
-          "id": "1",
-          "type": "start",
-          "payload": {
-            "query": query,
-            "variables": {},
-            "operationName": null
-          }
-        });
-        websocket.send(query_json.to_string().into()).await?;
-        Ok(websocket
-            .map_err(anyhow::Error::from)
-            .and_then(|message| async {
-                let text = message.into_text()?;
-                let value: Value = serde_json::from_str(&text).context("invalid JSON")?;
-                if let Some(errors) = value["payload"].get("errors") {
-                    bail!("Notification subscription failed: {errors:?}");
+        let notifications = subscribe_to_node_service(self.port, query, "blocks").await?;
+        // Blocks added from now on are notified, so reading the events up to the current tip
+        // leaves no gap.
+        let query = format!(
+            "query {{ chain(chainId: \"{}\") {{ tipState {{ nextBlockHeight }} }} }}",
+            self.chain_id
+        );
+        let mut data = query_node_service(self.port, &query).await?;
+        let tip: BlockHeight =
+            serde_json::from_value(data["chain"]["tipState"]["nextBlockHeight"].take())?;
+        self.next_height.get_or_insert(tip);
+        self.catch_up(tip).await?;
+        Ok(notifications.boxed())
+    }
+
+    /// Delivers the events until the receiver is dropped, reconnecting to the node service
+    /// whenever the connection is lost.
+    async fn run(mut self, mut notifications: BlockNotifications) {
+        loop {
+            let error = match self.forward(notifications).await {
+                Ok(never) => match never {},
+                Err(error) => error,
+            };
+            if self.sender.is_closed() {
+                return;
+            }
+            warn!(
+                "Lost the subscription to the events of application {} on chain {}: {error:#}",
+                self.application_id, self.chain_id
+            );
+            notifications = match self.reconnect().await {
+                Some(notifications) => notifications,
+                None => return,
+            };
+        }
+    }
+
+    /// Tries to connect again to the node service. Returns `None` if the receiver was dropped
+    /// or if the node service couldn't be reached after [`EVENT_SUBSCRIPTION_RETRIES`]
+    /// attempts, in which case the error is delivered.
+    async fn reconnect(&mut self) -> Option<BlockNotifications> {
+        let mut attempts = 0;
+        loop {
+            linera_base::time::timer::sleep(EVENT_SUBSCRIPTION_RETRY_DELAY).await;
+            if self.sender.is_closed() {
+                return None;
+            }
+            attempts += 1;
+            match self.connect().await {
+                Ok(notifications) => return Some(notifications),
+                Err(error) if attempts < EVENT_SUBSCRIPTION_RETRIES => {
+                    warn!("Failed to reconnect to the node service: {error:#}");
                 }
-                serde_json::from_value(value["payload"]["data"]["notifications"].clone())
-                    .context("Failed to deserialize notification")
-            }))
+                Err(error) => {
+                    let error = error.context(format!(
+                        "Failed to reach the node service after {attempts} attempts"
+                    ));
+                    let _ = self.sender.send(Err(error));
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Delivers the events of the new blocks until the connection is lost.
+    async fn forward(&mut self, mut notifications: BlockNotifications) -> Result<Infallible> {
+        while let Some(notification) = notifications.next().await {
+            let notification = notification?;
+            if notification.height < self.next_height.unwrap_or_default() {
+                continue;
+            }
+            // Some notifications may have been skipped.
+            self.catch_up(notification.height).await?;
+            self.deliver(notification.events)?;
+            self.next_height = Some(notification.height.try_add_one()?);
+        }
+        bail!("The node service closed the subscription")
+    }
+
+    /// Delivers the events emitted in the blocks from the next height to `to_height`
+    /// (excluded), one page at a time.
+    async fn catch_up(&mut self, to_height: BlockHeight) -> Result<()> {
+        let mut from_height = self.next_height.unwrap_or_default();
+        while from_height < to_height {
+            let query = format!(
+                "query {{ events(chainId: \"{}\", fromHeight: {from_height}, \
+                    toHeight: {to_height}, limit: {EVENT_PAGE_SIZE}, applicationId: \"{}\") {{ \
+                    events {{ {INDEXED_EVENT_FIELDS} }} nextHeight \
+                }} }}",
+                self.chain_id, self.application_id
+            );
+            let mut data = query_node_service(self.port, &query).await?;
+            let events = serde_json::from_value(data["events"]["events"].take())?;
+            self.deliver(events)?;
+            let next_height: Option<BlockHeight> =
+                serde_json::from_value(data["events"]["nextHeight"].take())?;
+            from_height = next_height.unwrap_or(to_height);
+            self.next_height = Some(from_height);
+        }
+        Ok(())
+    }
+
+    /// Decodes the `events` and sends them to the receiver.
+    fn deliver(&self, events: Vec<RawIndexedEvent>) -> Result<()> {
+        for RawIndexedEvent {
+            height,
+            transaction_index,
+            event,
+        } in events
+        {
+            let event = bcs::from_bytes(&event.value)
+                .with_context(|| format!("Failed to decode an event emitted at height {height}"))
+                .map(|decoded| ApplicationEvent {
+                    height,
+                    transaction_index,
+                    stream_name: event.stream_id.stream_name,
+                    key: event.key,
+                    event: decoded,
+                });
+            self.sender
+                .send(event)
+                .map_err(|_| anyhow!("The event subscription was dropped"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a GraphQL query or mutation on the node service listening on `port`, retrying a few
+/// times if it fails.
+async fn query_node_service(port: u16, query: &str) -> Result<Value> {
+    let n_try = 5;
+    for i in 0..n_try {
+        linera_base::time::timer::sleep(Duration::from_secs(i)).await;
+        let url = format!("http://localhost:{port}/");
+        let client = reqwest_client();
+        let result = client
+            .post(url)
+            .json(&json!({ "query": query }))
+            .send()
+            .await;
+        if matches!(result, Err(ref error) if error.is_timeout()) {
+            warn!("Timeout when sending query {query:?} to the node service");
+            continue;
+        }
+        let response = result.with_context(|| {
+            format!(
+                "query_node: failed to post query={}",
+                truncate_query_output(query)
+            )
+        })?;
+        anyhow::ensure!(
+            response.status().is_success(),
+            "Query \"{}\" failed: {}",
+            truncate_query_output(query),
+            response
+                .text()
+                .await
+                .unwrap_or_else(|error| format!("Could not get response text: {error}"))
+        );
+        let value: Value = response.json().await.context("invalid JSON")?;
+        if let Some(errors) = value.get("errors") {
+            warn!(
+                "Query \"{}\" failed: {}",
+                truncate_query_output(query),
+                errors
+            );
+        } else {
+            return Ok(value["data"].clone());
+        }
     }
+    bail!(
+        "Query \"{}\" failed after {} retries.",
+        truncate_query_output(query),
+        n_try
+    );
+}
+
+/// Runs a GraphQL subscription on the node service listening on `port` and returns the stream
+/// of values of the given `field`.
+async fn subscribe_to_node_service<T: DeserializeOwned>(
+    port: u16,
+    query: String,
+    field: &'static str,
+) -> Result<impl Stream<Item = Result<T>>> {
+    let url = format!("ws://localhost:{port}/ws");
+    let mut request = url.into_client_request()?;
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_str("graphql-transport-ws")?,
+    );
+    let (mut websocket, _) = async_tungstenite::tokio::connect_async(request).await?;
+    let init_json = json!({
+      "type": "connection_init",
+      "payload": {}
+    });
+    websocket.send(init_json.to_string().into()).await?;
+    let text = websocket
+        .next()
+        .await
+        .context("Failed to establish connection")??
+        .into_text()?;
+    ensure!(
+        text == "{\"type\":\"connection_ack\"}",
+        "Unexpected response: {text}"
+    );
+    let query_json = json!({
+      "id": "1",
+      "type": "start",
+      "payload": {
+        "query": query,
+        "variables": {},
+        "operationName": null
+      }
+    });
+    websocket.send(query_json.to_string().into()).await?;
+    Ok(websocket
+        .map_err(anyhow::Error::from)
+        .and_then(|message| async {
+            let text = message.into_text()?;
+            let value: Value = serde_json::from_str(&text).context("invalid JSON")?;
+            if let Some(errors) = value["payload"].get("errors") {
+                bail!("Notification subscription failed: {errors:?}");
+            }
+            serde_json::from_value(value["payload"]["data"][field].clone())
+                .context("Failed to deserialize notification")
+        }))
 }
 
 /// A running faucet service.
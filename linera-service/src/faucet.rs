@@ -1,26 +1,35 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{net::SocketAddr, num::NonZeroU16, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU16,
+    sync::Arc,
+};
 
 use async_graphql::{EmptySubscription, Error, Schema, SimpleObject};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{Extension, Router};
+use axum::{
+    extract::ConnectInfo, http::StatusCode, response, response::IntoResponse, Extension, Json,
+    Router,
+};
 use futures::lock::Mutex;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Amount, ApplicationPermissions, Timestamp},
-    identifiers::{ChainId, MessageId, Owner},
+    data_types::{Amount, ApplicationPermissions, TimeDelta, Timestamp},
+    identifiers::{Account, ChainId, MessageId, Owner},
     ownership::ChainOwnership,
 };
 use linera_client::{
     chain_listener::{ChainListener, ChainListenerConfig, ClientContext},
     config::GenesisConfig,
 };
-use linera_core::data_types::ClientOutcome;
+use linera_core::{client::ChainClient, data_types::ClientOutcome};
 use linera_execution::committee::ValidatorName;
 use linera_storage::{Clock as _, Storage};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tower_http::cors::CorsLayer;
 use tracing::info;
 
@@ -45,6 +54,7 @@ pub struct MutationRoot<C> {
     end_timestamp: Timestamp,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    rate_limiter: Arc<Mutex<ClaimRateLimiter>>,
 }
 
 /// The result of a successful `claim` mutation.
@@ -58,6 +68,109 @@ pub struct ClaimOutcome {
     pub certificate_hash: CryptoHash,
 }
 
+/// The body of a request to the `/claim` endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClaimRequest {
+    /// Who receives the tokens.
+    pub recipient: ClaimRecipient,
+}
+
+/// The recipient of the tokens given away by the faucet.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ClaimRecipient {
+    /// A new chain is created for this owner, with the tokens as its initial balance.
+    Owner(Owner),
+    /// The tokens are transferred to this existing account.
+    Account(Account),
+}
+
+/// The response of the `/claim` endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ClaimResponse {
+    /// The chain that received the tokens.
+    pub chain_id: ChainId,
+    /// The hash of the faucet chain's certificate that sent the tokens.
+    pub certificate_hash: CryptoHash,
+    /// The ID of the message that created the new chain, if a chain was created.
+    pub message_id: Option<MessageId>,
+}
+
+/// An error returned by the `/claim` endpoint.
+#[derive(Debug)]
+pub enum ClaimError {
+    /// The recipient or the IP address claimed tokens too recently.
+    RateLimited { retry_at: Timestamp },
+    /// The tokens could not be sent.
+    Failed(Error),
+}
+
+impl IntoResponse for ClaimError {
+    fn into_response(self) -> response::Response {
+        let (status, error) = match self {
+            ClaimError::RateLimited { retry_at } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many claims; try again at {retry_at}."),
+            ),
+            ClaimError::Failed(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.message),
+        };
+        (status, json!({ "error": error }).to_string()).into_response()
+    }
+}
+
+/// Limits how often tokens can be claimed for the same recipient or from the same IP address.
+pub struct ClaimRateLimiter {
+    interval: TimeDelta,
+    recipients: HashMap<ClaimRecipient, Timestamp>,
+    addresses: HashMap<IpAddr, Timestamp>,
+}
+
+impl ClaimRateLimiter {
+    /// Creates a rate limiter allowing one claim per `interval` for each recipient and address.
+    pub fn new(interval: TimeDelta) -> Self {
+        Self {
+            interval,
+            recipients: HashMap::new(),
+            addresses: HashMap::new(),
+        }
+    }
+
+    /// Records a claim at time `now`, unless the recipient or the address claimed less than
+    /// one interval ago. In that case, returns when the claim will be allowed.
+    fn try_claim(
+        &mut self,
+        recipient: ClaimRecipient,
+        address: Option<IpAddr>,
+        now: Timestamp,
+    ) -> Result<(), Timestamp> {
+        let interval = self.interval;
+        self.recipients
+            .retain(|_, last_claim| last_claim.saturating_add(interval) > now);
+        self.addresses
+            .retain(|_, last_claim| last_claim.saturating_add(interval) > now);
+        let last_claim = address
+            .and_then(|address| self.addresses.get(&address))
+            .into_iter()
+            .chain(self.recipients.get(&recipient))
+            .max();
+        if let Some(last_claim) = last_claim {
+            return Err(last_claim.saturating_add(interval));
+        }
+        self.recipients.insert(recipient, now);
+        if let Some(address) = address {
+            self.addresses.insert(address, now);
+        }
+        Ok(())
+    }
+
+    /// Forgets a claim recorded by `try_claim`, e.g. because sending the tokens failed.
+    fn cancel_claim(&mut self, recipient: ClaimRecipient, address: Option<IpAddr>) {
+        self.recipients.remove(&recipient);
+        if let Some(address) = address {
+            self.addresses.remove(&address);
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, SimpleObject)]
 pub struct Validator {
     pub name: ValidatorName,
@@ -111,7 +224,93 @@ where
 {
     async fn do_claim(&self, owner: Owner) -> Result<ClaimOutcome, Error> {
         let client = self.context.lock().await.make_chain_client(self.chain_id)?;
+        self.check_unlocked_balance(&client).await?;
+
+        let ownership = ChainOwnership::single(owner);
+        let result = client
+            .open_chain(ownership, ApplicationPermissions::default(), self.amount)
+            .await;
+        self.context.lock().await.update_wallet(&client).await?;
+        let (message_id, certificate) = match result? {
+            ClientOutcome::Committed(result) => result,
+            ClientOutcome::WaitForTimeout(timeout) => {
+                return Err(Self::not_leader_error(timeout.timestamp));
+            }
+        };
+        let chain_id = ChainId::child(message_id);
+        Ok(ClaimOutcome {
+            message_id,
+            chain_id,
+            certificate_hash: certificate.hash(),
+        })
+    }
+
+    /// Transfers tokens to an existing account and returns the hash of the certificate.
+    async fn do_transfer(&self, account: Account) -> Result<CryptoHash, Error> {
+        let client = self.context.lock().await.make_chain_client(self.chain_id)?;
+        self.check_unlocked_balance(&client).await?;
+
+        let result = client.transfer_to_account(None, self.amount, account).await;
+        self.context.lock().await.update_wallet(&client).await?;
+        match result? {
+            ClientOutcome::Committed(certificate) => Ok(certificate.hash()),
+            ClientOutcome::WaitForTimeout(timeout) => {
+                Err(Self::not_leader_error(timeout.timestamp))
+            }
+        }
+    }
 
+    /// Handles a request to the `/claim` endpoint from the given IP address, if known.
+    async fn do_rate_limited_claim(
+        &self,
+        request: ClaimRequest,
+        address: Option<IpAddr>,
+    ) -> Result<ClaimResponse, ClaimError> {
+        let recipient = request.recipient;
+        let now = {
+            let context = self.context.lock().await;
+            let client = context
+                .make_chain_client(self.chain_id)
+                .map_err(|error| ClaimError::Failed(error.into()))?;
+            client.storage_client().clock().current_time()
+        };
+        self.rate_limiter
+            .lock()
+            .await
+            .try_claim(recipient, address, now)
+            .map_err(|retry_at| ClaimError::RateLimited { retry_at })?;
+        let result = match recipient {
+            ClaimRecipient::Owner(owner) => {
+                self.do_claim(owner).await.map(|outcome| ClaimResponse {
+                    chain_id: outcome.chain_id,
+                    certificate_hash: outcome.certificate_hash,
+                    message_id: Some(outcome.message_id),
+                })
+            }
+            ClaimRecipient::Account(account) => {
+                self.do_transfer(account)
+                    .await
+                    .map(|certificate_hash| ClaimResponse {
+                        chain_id: account.chain_id,
+                        certificate_hash,
+                        message_id: None,
+                    })
+            }
+        };
+        if result.is_err() {
+            self.rate_limiter
+                .lock()
+                .await
+                .cancel_claim(recipient, address);
+        }
+        result.map_err(ClaimError::Failed)
+    }
+
+    /// Returns an error if sending tokens now would exceed the unlocked part of the balance.
+    async fn check_unlocked_balance(
+        &self,
+        client: &ChainClient<C::ValidatorNodeProvider, C::Storage>,
+    ) -> Result<(), Error> {
         if self.start_timestamp < self.end_timestamp {
             let local_time = client.storage_client().clock().current_time();
             if local_time < self.end_timestamp {
@@ -135,28 +334,14 @@ where
                 }
             }
         }
+        Ok(())
+    }
 
-        let ownership = ChainOwnership::single(owner);
-        let result = client
-            .open_chain(ownership, ApplicationPermissions::default(), self.amount)
-            .await;
-        self.context.lock().await.update_wallet(&client).await?;
-        let (message_id, certificate) = match result? {
-            ClientOutcome::Committed(result) => result,
-            ClientOutcome::WaitForTimeout(timeout) => {
-                return Err(Error::new(format!(
-                    "This faucet is using a multi-owner chain and is not the leader right now. \
-                    try again at {}",
-                    timeout.timestamp,
-                )));
-            }
-        };
-        let chain_id = ChainId::child(message_id);
-        Ok(ClaimOutcome {
-            message_id,
-            chain_id,
-            certificate_hash: certificate.hash(),
-        })
+    fn not_leader_error(timestamp: Timestamp) -> Error {
+        Error::new(format!(
+            "This faucet is using a multi-owner chain and is not the leader right now. \
+            try again at {timestamp}",
+        ))
     }
 }
 
@@ -187,6 +372,7 @@ where
     end_timestamp: Timestamp,
     start_timestamp: Timestamp,
     start_balance: Amount,
+    rate_limiter: Arc<Mutex<ClaimRateLimiter>>,
 }
 
 impl<C> Clone for FaucetService<C>
@@ -205,6 +391,7 @@ where
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
+            rate_limiter: Arc::clone(&self.rate_limiter),
         }
     }
 }
@@ -221,6 +408,7 @@ where
         context: C,
         amount: Amount,
         end_timestamp: Timestamp,
+        claim_interval: TimeDelta,
         genesis_config: Arc<GenesisConfig>,
         config: ChainListenerConfig,
         storage: C::Storage,
@@ -241,18 +429,24 @@ where
             end_timestamp,
             start_timestamp,
             start_balance,
+            rate_limiter: Arc::new(Mutex::new(ClaimRateLimiter::new(claim_interval))),
         })
     }
 
-    pub fn schema(&self) -> Schema<QueryRoot<C>, MutationRoot<C>, EmptySubscription> {
-        let mutation_root = MutationRoot {
+    fn mutation_root(&self) -> MutationRoot<C> {
+        MutationRoot {
             chain_id: self.chain_id,
             context: Arc::clone(&self.context),
             amount: self.amount,
             end_timestamp: self.end_timestamp,
             start_timestamp: self.start_timestamp,
             start_balance: self.start_balance,
-        };
+            rate_limiter: Arc::clone(&self.rate_limiter),
+        }
+    }
+
+    pub fn schema(&self) -> Schema<QueryRoot<C>, MutationRoot<C>, EmptySubscription> {
+        let mutation_root = self.mutation_root();
         let query_root = QueryRoot {
             genesis_config: Arc::clone(&self.genesis_config),
             context: Arc::clone(&self.context),
@@ -269,6 +463,7 @@ where
         let app = Router::new()
             .route("/", index_handler)
             .route("/ready", axum::routing::get(|| async { "ready!" }))
+            .route("/claim", axum::routing::post(Self::claim_handler))
             .route_service("/ws", GraphQLSubscription::new(self.schema()))
             .layer(Extension(self.clone()))
             .layer(CorsLayer::permissive());
@@ -281,7 +476,7 @@ where
 
         axum::serve(
             tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?,
-            app,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
         )
         .await?;
 
@@ -293,4 +488,19 @@ where
         let schema = service.0.schema();
         schema.execute(request.into_inner()).await.into()
     }
+
+    /// Sends tokens to the recipient in the request body, rate-limited by recipient and by the
+    /// client's IP address.
+    async fn claim_handler(
+        service: Extension<Self>,
+        ConnectInfo(address): ConnectInfo<SocketAddr>,
+        Json(request): Json<ClaimRequest>,
+    ) -> Result<Json<ClaimResponse>, ClaimError> {
+        let response = service
+            .0
+            .mutation_root()
+            .do_rate_limited_claim(request, Some(address.ip()))
+            .await?;
+        Ok(Json(response))
+    }
 }
@@ -0,0 +1,367 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health and readiness endpoints of the node service and of the validators, for load
+//! balancers and orchestrators.
+//!
+//! `/health` answers as long as the process serves requests. `/ready` runs the readiness
+//! checks, and answers `200 OK` if they all pass or `503 Service Unavailable` otherwise, with
+//! the status of each check:
+//!
+//! ```json
+//! {
+//!   "ready": false,
+//!   "checks": {
+//!     "blocks_behind": { "status": "ok", "detail": "0 blocks behind the validators" },
+//!     "certificate_age": { "status": "skipped" },
+//!     "queue_depth": { "status": "ok", "detail": "3 pending requests" },
+//!     "storage": { "status": "failing", "detail": "..." },
+//!     "wasm_runtime": { "status": "ok" }
+//!   }
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use futures::lock::Mutex;
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{BlobId, BlobType},
+    time::{Duration, Instant},
+};
+use linera_client::{
+    chain_listener::ClientContext, client_options::ReadinessConfig, Error as ClientError,
+};
+use linera_core::worker::WorkerState;
+use linera_storage::Storage;
+use serde::{Deserialize, Serialize};
+use tokio::net::ToSocketAddrs;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+#[cfg(test)]
+#[path = "unit_tests/health.rs"]
+mod tests;
+
+/// The status of a readiness check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failing,
+    /// The check does not apply to this service, or is disabled.
+    Skipped,
+}
+
+/// The result of a readiness check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub status: CheckStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CheckReport {
+    fn new(status: CheckStatus, detail: impl Into<Option<String>>) -> Self {
+        CheckReport {
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn skipped() -> Self {
+        Self::new(CheckStatus::Skipped, None)
+    }
+}
+
+/// The result of all the readiness checks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: BTreeMap<String, CheckReport>,
+}
+
+impl ReadinessReport {
+    /// Returns the HTTP status code answering a readiness request.
+    pub fn status_code(&self) -> StatusCode {
+        if self.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+/// The state of a service that its readiness depends on.
+#[async_trait]
+pub trait ReadinessProbe: Send + Sync + 'static {
+    /// Reads a key from the storage, to check that it can be reached.
+    async fn ping_storage(&self) -> Result<(), String>;
+
+    /// Returns whether a Wasm runtime is initialized to execute applications.
+    fn has_wasm_runtime(&self) -> bool;
+
+    /// Returns the number of requests waiting for the chain workers.
+    async fn queue_depth(&self) -> Option<usize>;
+
+    /// Returns how long ago the last certificate was processed, if any was.
+    fn last_certificate_age(&self) -> Option<Duration>;
+
+    /// Returns how many blocks the local node is missing compared to the validators, on the
+    /// chain where it is the most behind, or [`None`] if it doesn't follow any chain.
+    async fn blocks_behind(&self) -> Result<Option<u64>, String>;
+}
+
+/// Reads a key that is never written from the `storage`, to check that it can be reached.
+pub async fn ping_storage<S: Storage>(storage: &S) -> Result<(), String> {
+    let sentinel_blob_id = BlobId::new(CryptoHash::from([0; 4]), BlobType::Data);
+    storage
+        .contains_blob(sentinel_blob_id)
+        .await
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+/// Runs the readiness checks of a service, and serves the health and readiness endpoints.
+pub struct HealthChecker<P> {
+    probe: P,
+    config: ReadinessConfig,
+    /// The last number of blocks behind the validators, reused for the sync check interval.
+    blocks_behind: Mutex<Option<(Instant, Result<Option<u64>, String>)>>,
+}
+
+impl<P: ReadinessProbe> HealthChecker<P> {
+    pub fn new(probe: P, config: ReadinessConfig) -> Self {
+        HealthChecker {
+            probe,
+            config,
+            blocks_behind: Mutex::new(None),
+        }
+    }
+
+    /// Runs all the readiness checks.
+    pub async fn readiness(&self) -> ReadinessReport {
+        let mut checks = BTreeMap::new();
+
+        let storage = match self.probe.ping_storage().await {
+            Ok(()) => CheckReport::new(CheckStatus::Ok, None),
+            Err(error) => CheckReport::new(CheckStatus::Failing, error),
+        };
+        checks.insert("storage".to_owned(), storage);
+
+        let wasm_runtime = if self.probe.has_wasm_runtime() {
+            CheckReport::new(CheckStatus::Ok, None)
+        } else {
+            CheckReport::new(
+                CheckStatus::Failing,
+                "no Wasm runtime is initialized".to_owned(),
+            )
+        };
+        checks.insert("wasm_runtime".to_owned(), wasm_runtime);
+
+        let queue_depth = match self.probe.queue_depth().await {
+            None => CheckReport::skipped(),
+            Some(depth) => {
+                let status = if depth <= self.config.max_queue_depth {
+                    CheckStatus::Ok
+                } else {
+                    CheckStatus::Failing
+                };
+                CheckReport::new(status, format!("{depth} pending requests"))
+            }
+        };
+        checks.insert("queue_depth".to_owned(), queue_depth);
+
+        let certificate_age = match self.config.max_certificate_age {
+            None => CheckReport::skipped(),
+            Some(max_age) => match self.probe.last_certificate_age() {
+                Some(age) if age <= max_age => CheckReport::new(
+                    CheckStatus::Ok,
+                    format!("last certificate processed {}s ago", age.as_secs()),
+                ),
+                Some(age) => CheckReport::new(
+                    CheckStatus::Failing,
+                    format!("last certificate processed {}s ago", age.as_secs()),
+                ),
+                None => CheckReport::new(
+                    CheckStatus::Failing,
+                    "no certificate processed yet".to_owned(),
+                ),
+            },
+        };
+        checks.insert("certificate_age".to_owned(), certificate_age);
+
+        let blocks_behind = match self.blocks_behind().await {
+            Ok(None) => CheckReport::skipped(),
+            Ok(Some(blocks)) => {
+                let status = if blocks <= self.config.max_blocks_behind {
+                    CheckStatus::Ok
+                } else {
+                    CheckStatus::Failing
+                };
+                CheckReport::new(status, format!("{blocks} blocks behind the validators"))
+            }
+            Err(error) => CheckReport::new(CheckStatus::Failing, error),
+        };
+        checks.insert("blocks_behind".to_owned(), blocks_behind);
+
+        let ready = checks
+            .values()
+            .all(|check| check.status != CheckStatus::Failing);
+        ReadinessReport { ready, checks }
+    }
+
+    /// Returns the number of blocks behind the validators, asking the validators again only
+    /// once the sync check interval has elapsed.
+    async fn blocks_behind(&self) -> Result<Option<u64>, String> {
+        let mut cached = self.blocks_behind.lock().await;
+        if let Some((checked_at, blocks_behind)) = &*cached {
+            if checked_at.elapsed() < self.config.sync_check_interval {
+                return blocks_behind.clone();
+            }
+        }
+        let blocks_behind = self.probe.blocks_behind().await;
+        *cached = Some((Instant::now(), blocks_behind.clone()));
+        blocks_behind
+    }
+
+    /// Returns the routes of the `/health` and `/ready` endpoints.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route(
+                "/health",
+                get(|| async { Json(serde_json::json!({ "status": "ok" })) }),
+            )
+            .route("/ready", get(Self::ready_handler))
+            .with_state(Arc::new(self))
+    }
+
+    async fn ready_handler(
+        State(checker): State<Arc<Self>>,
+    ) -> (StatusCode, Json<ReadinessReport>) {
+        let report = checker.readiness().await;
+        (report.status_code(), Json(report))
+    }
+}
+
+/// Serves the health and readiness endpoints of `checker` on `address`, until the
+/// `shutdown_signal` is received.
+pub fn start_health<P: ReadinessProbe>(
+    address: impl ToSocketAddrs + Debug + Send + 'static,
+    checker: HealthChecker<P>,
+    shutdown_signal: CancellationToken,
+) {
+    info!("Starting to serve health checks on {:?}", address);
+    let router = checker.router();
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(
+            tokio::net::TcpListener::bind(address).await.unwrap(),
+            router,
+        )
+        .with_graceful_shutdown(shutdown_signal.cancelled_owned())
+        .await
+        {
+            panic!("Error serving health checks: {}", e);
+        }
+    });
+}
+
+/// The readiness of the node service: its storage, its local node, and how far its chains
+/// are behind the validators.
+pub struct NodeServiceProbe<C: ClientContext> {
+    storage: C::Storage,
+    context: Arc<Mutex<C>>,
+}
+
+impl<C: ClientContext> NodeServiceProbe<C> {
+    pub fn new(storage: C::Storage, context: Arc<Mutex<C>>) -> Self {
+        NodeServiceProbe { storage, context }
+    }
+}
+
+#[async_trait]
+impl<C: ClientContext> ReadinessProbe for NodeServiceProbe<C> {
+    async fn ping_storage(&self) -> Result<(), String> {
+        ping_storage(&self.storage).await
+    }
+
+    fn has_wasm_runtime(&self) -> bool {
+        self.storage.wasm_runtime().is_some()
+    }
+
+    async fn queue_depth(&self) -> Option<usize> {
+        let clients = self.context.lock().await.clients().ok()?;
+        // All the chain clients share the same local node.
+        let client = clients.first()?;
+        Some(client.pending_requests())
+    }
+
+    fn last_certificate_age(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn blocks_behind(&self) -> Result<Option<u64>, String> {
+        let clients = self
+            .context
+            .lock()
+            .await
+            .clients()
+            .map_err(|error: ClientError| error.to_string())?;
+        let mut most_blocks_behind = None;
+        for client in clients {
+            let blocks_behind = client
+                .blocks_behind_validators()
+                .await
+                .map_err(|error| format!("chain {}: {error}", client.chain_id()))?;
+            most_blocks_behind = most_blocks_behind.max(Some(blocks_behind));
+        }
+        Ok(most_blocks_behind)
+    }
+}
+
+/// The readiness of the shards of a validator run by the same process.
+///
+/// The shards are the reference for the chains they serve, so the number of blocks behind
+/// the validators is not checked.
+pub struct ValidatorProbe<S: Storage> {
+    storage: S,
+    workers: Vec<WorkerState<S>>,
+}
+
+impl<S: Storage> ValidatorProbe<S> {
+    pub fn new(storage: S, workers: Vec<WorkerState<S>>) -> Self {
+        ValidatorProbe { storage, workers }
+    }
+}
+
+#[async_trait]
+impl<S> ReadinessProbe for ValidatorProbe<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    async fn ping_storage(&self) -> Result<(), String> {
+        ping_storage(&self.storage).await
+    }
+
+    fn has_wasm_runtime(&self) -> bool {
+        self.storage.wasm_runtime().is_some()
+    }
+
+    async fn queue_depth(&self) -> Option<usize> {
+        Some(self.workers.iter().map(WorkerState::pending_requests).sum())
+    }
+
+    fn last_certificate_age(&self) -> Option<Duration> {
+        self.workers
+            .iter()
+            .filter_map(WorkerState::last_confirmed_block_age)
+            .min()
+    }
+
+    async fn blocks_behind(&self) -> Result<Option<u64>, String> {
+        Ok(None)
+    }
+}
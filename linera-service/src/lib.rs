@@ -8,9 +8,15 @@
 
 pub mod cli_wrappers;
 pub mod faucet;
+pub mod health;
 pub mod node_service;
 pub mod project;
 #[cfg(with_metrics)]
 pub mod prometheus_server;
+pub mod query_rate_limit;
+pub mod runtime_config;
+pub mod state_export;
+#[cfg(all(with_testing, feature = "storage-service"))]
+pub mod test_utils;
 pub mod util;
 pub mod wallet;
@@ -17,7 +17,7 @@ use colored::Colorize;
 use futures::{lock::Mutex, FutureExt as _, StreamExt};
 use linera_base::{
     crypto::{CryptoHash, CryptoRng},
-    data_types::{ApplicationPermissions, Timestamp},
+    data_types::{ApplicationPermissions, TimeDelta, Timestamp},
     identifiers::{AccountOwner, ChainDescription, ChainId, MessageId, Owner},
     ownership::ChainOwnership,
 };
@@ -29,21 +29,25 @@ use linera_client::{
         WalletCommand,
     },
     config::{CommitteeConfig, GenesisConfig},
+    key_store,
     persistent::{self, Persist},
+    service_override::ServiceOverrideWatcher,
     storage::Runnable,
     wallet::{UserChain, Wallet},
 };
 use linera_core::{
-    client,
-    data_types::{ChainInfoQuery, ClientOutcome},
+    chain_worker::StateSnapshotOptions,
+    client::{self, ProposalIssueSeverity, StateHashComparison},
+    data_types::{ChainInfoQuery, ClientOutcome, MessageState},
     node::{CrossChainMessageDelivery, ValidatorNodeProvider},
     remote_node::RemoteNode,
     worker::Reason,
-    JoinSetExt as _, DEFAULT_GRACE_PERIOD,
+    JoinSetExt as _, ValidatorRequestOptions, DEFAULT_GRACE_PERIOD,
 };
 use linera_execution::{
     committee::{Committee, ValidatorName, ValidatorState},
-    Message, ResourceControlPolicy, SystemMessage,
+    Message, ResourceControlPolicy, SystemMessage, WithWasmDefault as _,
+    DEFAULT_MAXIMUM_CALL_DEPTH,
 };
 use linera_service::{
     cli_wrappers,
@@ -88,6 +92,14 @@ fn deserialize_response(response: RpcMessage) -> Option<ChainInfoResponse> {
 
 struct Job(ClientOptions);
 
+/// Asks a yes-or-no question on the terminal, defaulting to no.
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    eprint!("{prompt}");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Vec<u8>> {
     let value = match (string, path) {
         (Some(_), Some(_)) => bail!("cannot have both a json string and file"),
@@ -135,14 +147,7 @@ impl Runnable for Job {
                 );
                 let time_start = Instant::now();
                 let certificate = context
-                    .apply_client_command(&chain_client, |chain_client| {
-                        let chain_client = chain_client.clone();
-                        async move {
-                            chain_client
-                                .transfer_to_account(owner, amount, recipient)
-                                .await
-                        }
-                    })
+                    .transfer(&chain_client, owner, amount, recipient)
                     .await
                     .context("Failed to make transfer")?;
                 let time_total = time_start.elapsed();
@@ -246,6 +251,12 @@ impl Runnable for Job {
                 ownership_config,
             } => context.change_ownership(chain_id, ownership_config).await?,
 
+            RotateKey { chain_id } => {
+                let owner = context.rotate_key(chain_id).await?;
+                // Print the new owner on stdout for scripting purposes.
+                println!("{}", owner);
+            }
+
             ChangeApplicationPermissions {
                 chain_id,
                 application_permissions_config,
@@ -276,30 +287,27 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
-            CloseChain { chain_id } => {
-                let chain_client = context.make_chain_client(chain_id)?;
-                info!("Closing chain {}", chain_id);
-                let time_start = Instant::now();
-                let result = context
-                    .apply_client_command(&chain_client, |chain_client| {
-                        let chain_client = chain_client.clone();
-                        async move { chain_client.close_chain().await }
-                    })
-                    .await;
-                let certificate = match result {
-                    Ok(Some(certificate)) => certificate,
-                    Ok(None) => {
-                        tracing::info!("Chain is already closed; nothing to do.");
-                        return Ok(());
-                    }
-                    Err(error) => Err(error).context("Failed to close chain")?,
-                };
-                let time_total = time_start.elapsed();
-                info!(
-                    "Closing chain confirmed after {} ms",
-                    time_total.as_millis()
-                );
-                debug!("{:?}", certificate);
+            TransferChainOwnership {
+                chain_id,
+                new_owner,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                context
+                    .transfer_chain_ownership(chain_id, new_owner)
+                    .await?;
+            }
+
+            CloseChain {
+                chain_id,
+                pending_messages,
+            } => {
+                if context
+                    .close_chain(chain_id, pending_messages)
+                    .await?
+                    .is_none()
+                {
+                    info!("Chain is already closed; nothing to do.");
+                }
             }
 
             LocalBalance { account } => {
@@ -382,6 +390,142 @@ impl Runnable for Job {
                 );
             }
 
+            Inbox { chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                info!("Listing the pending messages of chain {}", chain_id);
+                let messages = context.pending_messages(chain_id).await?;
+                info!("Found {} pending messages", messages.len());
+                for message in messages {
+                    let mut description = match &message.preview {
+                        Some(preview) => preview.clone(),
+                        None => format!("message of application {:?}", message.application_id),
+                    };
+                    if message.rejected {
+                        description.push_str(" (rejected)");
+                    }
+                    println!(
+                        "chain {}, height {}, index {}: {}",
+                        message.origin.sender, message.height, message.index, description
+                    );
+                }
+            }
+
+            QueryMessageStatus {
+                certificate_hash,
+                index,
+                recipient,
+            } => {
+                let recipient = recipient.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(recipient)?;
+                info!("Synchronizing chain {}", recipient);
+                chain_client.synchronize_from_validators().await?;
+                context.update_and_save_wallet(&chain_client).await?;
+                let status = chain_client.message_status(certificate_hash, index).await?;
+                match (status.state, status.height) {
+                    (MessageState::NotReceived, _) => println!("not received"),
+                    (MessageState::Pending, _) => println!("pending"),
+                    (MessageState::Executed, Some(height)) => {
+                        println!("executed at height {height}")
+                    }
+                    (MessageState::Executed, None) => println!("executed"),
+                    (MessageState::Rejected, Some(height)) => {
+                        println!("rejected at height {height}")
+                    }
+                    (MessageState::Rejected, None) => println!("skipped"),
+                    (MessageState::Parked, Some(height)) => println!("parked at height {height}"),
+                    (MessageState::Parked, None) => println!("parked"),
+                }
+            }
+
+            VerifyChain {
+                chain_id,
+                certificates,
+                save_certificates,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let time_start = Instant::now();
+                let certificates = match certificates {
+                    Some(path) => {
+                        info!(
+                            "Reading the certificates of chain {} from {:?}",
+                            chain_id, path
+                        );
+                        serde_json::from_slice(&fs_err::read(path)?)?
+                    }
+                    None => {
+                        info!("Downloading the certificates of chain {}", chain_id);
+                        context.download_chain_certificates(chain_id).await?
+                    }
+                };
+                if let Some(path) = save_certificates {
+                    fs_err::write(path, serde_json::to_vec(&certificates)?)?;
+                }
+                let num_blocks = certificates.len();
+                info!("Verifying {} blocks of chain {}", num_blocks, chain_id);
+                // Execute the blocks again in a storage that knows only the genesis config.
+                let storage = linera_client::storage::create_memory_storage(
+                    context.wallet().genesis_config(),
+                    options.max_stream_queries,
+                    options.wasm_runtime.with_wasm_default(),
+                )
+                .await?;
+                let mut num_verified = 0;
+                let chain_info = context
+                    .verify_chain(storage, chain_id, certificates, |certificate| {
+                        num_verified += 1;
+                        info!(
+                            "Verified block {} of chain {} ({}/{})",
+                            certificate.block().header.height,
+                            chain_id,
+                            num_verified,
+                            num_blocks
+                        );
+                    })
+                    .await
+                    .context("Failed to verify chain")?;
+                let time_total = time_start.elapsed();
+                info!(
+                    "Verified {} blocks of chain {} in {} ms",
+                    num_blocks,
+                    chain_id,
+                    time_total.as_millis()
+                );
+                println!("Verified blocks: {}", num_blocks);
+                println!("Next block height: {}", chain_info.next_block_height);
+                if let Some(block_hash) = chain_info.block_hash {
+                    println!("Last block hash: {}", block_hash);
+                }
+                if let Some(state_hash) = chain_info.state_hash {
+                    println!("State hash: {}", state_hash);
+                }
+            }
+
+            CompareStateHashes { chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Synchronizing chain {}", chain_id);
+                chain_client.synchronize_from_validators().await?;
+                context.update_and_save_wallet(&chain_client).await?;
+                let comparisons = chain_client.compare_state_hashes().await?;
+                for (name, comparison) in comparisons {
+                    match comparison {
+                        StateHashComparison::Match => println!("{name}: match"),
+                        StateHashComparison::Diverged(parts) => {
+                            println!("{name}: diverged");
+                            for part in parts {
+                                println!("  {part}");
+                            }
+                        }
+                        StateHashComparison::OtherHeight(height) => {
+                            println!("{name}: at next block height {height}")
+                        }
+                        StateHashComparison::Unavailable(reason) => {
+                            println!("{name}: unavailable ({reason})")
+                        }
+                    }
+                }
+            }
+
             QueryValidator {
                 address,
                 chain_id,
@@ -622,6 +766,18 @@ impl Runnable for Job {
                                     maximum_block_proposal_size,
                                     maximum_bytes_read_per_block,
                                     maximum_bytes_written_per_block,
+                                    maximum_application_state_size,
+                                    maximum_operations_per_block,
+                                    maximum_incoming_bundles_per_block,
+                                    maximum_incoming_bundles_per_origin,
+                                    burn_unused_grants,
+                                    maximum_query_response_size,
+                                    maximum_messages_per_execution,
+                                    maximum_message_size,
+                                    maximum_message_bytes_per_execution,
+                                    maximum_call_depth,
+                                    resource_estimate_tolerance_percent,
+                                    optional_resource_estimates,
                                 } => {
                                     if let Some(block) = block {
                                         policy.block = block;
@@ -692,6 +848,64 @@ impl Runnable for Job {
                                         policy.maximum_bytes_written_per_block =
                                             maximum_bytes_written_per_block;
                                     }
+                                    if let Some(maximum_application_state_size) =
+                                        maximum_application_state_size
+                                    {
+                                        policy.maximum_application_state_size =
+                                            maximum_application_state_size;
+                                    }
+                                    if let Some(maximum_operations_per_block) =
+                                        maximum_operations_per_block
+                                    {
+                                        policy.maximum_operations_per_block =
+                                            maximum_operations_per_block;
+                                    }
+                                    if let Some(maximum_incoming_bundles_per_block) =
+                                        maximum_incoming_bundles_per_block
+                                    {
+                                        policy.maximum_incoming_bundles_per_block =
+                                            maximum_incoming_bundles_per_block;
+                                    }
+                                    if let Some(maximum_incoming_bundles_per_origin) =
+                                        maximum_incoming_bundles_per_origin
+                                    {
+                                        policy.maximum_incoming_bundles_per_origin =
+                                            maximum_incoming_bundles_per_origin;
+                                    }
+                                    if let Some(burn_unused_grants) = burn_unused_grants {
+                                        policy.burn_unused_grants = burn_unused_grants;
+                                    }
+                                    if let Some(maximum_query_response_size) =
+                                        maximum_query_response_size
+                                    {
+                                        policy.maximum_query_response_size =
+                                            maximum_query_response_size;
+                                    }
+                                    if let Some(maximum_messages_per_execution) =
+                                        maximum_messages_per_execution
+                                    {
+                                        policy.maximum_messages_per_execution =
+                                            maximum_messages_per_execution;
+                                    }
+                                    if let Some(maximum_message_size) = maximum_message_size {
+                                        policy.maximum_message_size = maximum_message_size;
+                                    }
+                                    if let Some(maximum_message_bytes_per_execution) =
+                                        maximum_message_bytes_per_execution
+                                    {
+                                        policy.maximum_message_bytes_per_execution =
+                                            maximum_message_bytes_per_execution;
+                                    }
+                                    if let Some(maximum_call_depth) = maximum_call_depth {
+                                        policy.maximum_call_depth = maximum_call_depth;
+                                    }
+                                    if resource_estimate_tolerance_percent.is_some() {
+                                        policy.resource_estimate_tolerance_percent =
+                                            resource_estimate_tolerance_percent;
+                                    }
+                                    if optional_resource_estimates {
+                                        policy.resource_estimate_tolerance_percent = None;
+                                    }
                                     info!("{policy}");
                                     if committee.policy() == &policy {
                                         return Ok(ClientOutcome::Committed(None));
@@ -860,9 +1074,37 @@ impl Runnable for Job {
                 info!("Notification stream ended.");
             }
 
-            Service { config, port } => {
+            Service {
+                config,
+                readiness,
+                query_rate_limits,
+                port,
+                dev: _,
+                service_overrides,
+            } => {
+                let mut join_set = JoinSet::new();
+                if !service_overrides.is_empty() {
+                    ensure!(
+                        !options.long_lived_services,
+                        "Overridden services cannot be reloaded with `--long-lived-services`"
+                    );
+                    warn!("Some queries are answered by services that were not published");
+                    let mut watcher =
+                        ServiceOverrideWatcher::new(storage.clone(), service_overrides);
+                    watcher.reload().await?;
+                    join_set.spawn_task(watcher.run());
+                }
                 let default_chain = context.wallet().default_chain();
-                let service = NodeService::new(config, port, default_chain, storage, context).await;
+                let service = NodeService::new(
+                    config,
+                    readiness,
+                    query_rate_limits,
+                    port,
+                    default_chain,
+                    storage,
+                    context,
+                )
+                .await;
                 service.run().await?;
             }
 
@@ -871,6 +1113,7 @@ impl Runnable for Job {
                 port,
                 amount,
                 limit_rate_until,
+                claim_interval_secs,
                 config,
             } => {
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
@@ -889,6 +1132,7 @@ impl Runnable for Job {
                     context,
                     amount,
                     end_timestamp,
+                    TimeDelta::from_secs(claim_interval_secs),
                     genesis_config,
                     config,
                     storage,
@@ -950,6 +1194,7 @@ impl Runnable for Job {
                 json_argument,
                 json_argument_path,
                 required_application_ids,
+                max_query_response_bytes,
             } => {
                 let start_time = Instant::now();
                 let creator = creator.unwrap_or_else(|| context.default_chain());
@@ -975,6 +1220,7 @@ impl Runnable for Job {
                                     parameters,
                                     argument,
                                     required_application_ids.unwrap_or_default(),
+                                    max_query_response_bytes,
                                 )
                                 .await
                         }
@@ -998,6 +1244,8 @@ impl Runnable for Job {
                 json_argument,
                 json_argument_path,
                 required_application_ids,
+                max_query_response_bytes,
+                json,
             } => {
                 let start_time = Instant::now();
                 let publisher = publisher.unwrap_or_else(|| context.default_chain());
@@ -1023,6 +1271,7 @@ impl Runnable for Job {
                                     parameters,
                                     argument,
                                     required_application_ids.unwrap_or_default(),
+                                    max_query_response_bytes,
                                 )
                                 .await
                         }
@@ -1034,7 +1283,15 @@ impl Runnable for Job {
                     "Application published and created in {} ms",
                     start_time.elapsed().as_millis()
                 );
-                println!("{}", application_id);
+                if json {
+                    let output = serde_json::json!({
+                        "bytecode_id": bytecode_id.to_string(),
+                        "application_id": application_id.to_string(),
+                    });
+                    println!("{output}");
+                } else {
+                    println!("{}", application_id);
+                }
             }
 
             RequestApplication {
@@ -1065,22 +1322,33 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
-            Assign { owner, message_id } => {
+            Assign {
+                owner,
+                message_id,
+                chain_id,
+            } => {
                 let start_time = Instant::now();
-                let chain_id = ChainId::child(message_id);
+                let chain_id = match message_id {
+                    Some(message_id) => ChainId::child(message_id),
+                    None => chain_id.expect("either a message ID or a chain ID is required"),
+                };
                 info!(
                     "Linking chain {chain_id} to its corresponding key in the wallet, owned by \
                     {owner}",
                 );
-                Self::assign_new_chain_to_key(
-                    chain_id,
-                    message_id,
-                    storage,
-                    owner,
-                    None,
-                    &mut context,
-                )
-                .await?;
+                if let Some(message_id) = message_id {
+                    Self::assign_new_chain_to_key(
+                        chain_id,
+                        message_id,
+                        storage,
+                        owner,
+                        None,
+                        &mut context,
+                    )
+                    .await?;
+                } else {
+                    context.assign_chain(owner, chain_id).await?;
+                }
                 println!("{}", chain_id);
                 context.save_wallet().await?;
                 info!(
@@ -1099,6 +1367,7 @@ impl Runnable for Job {
                     json_argument,
                     json_argument_path,
                     required_application_ids,
+                    max_query_response_bytes,
                 } => {
                     let start_time = Instant::now();
                     let publisher = publisher.unwrap_or_else(|| context.default_chain());
@@ -1129,6 +1398,7 @@ impl Runnable for Job {
                                         parameters,
                                         argument,
                                         required_application_ids.unwrap_or_default(),
+                                        max_query_response_bytes,
                                     )
                                     .await
                             }
@@ -1145,11 +1415,30 @@ impl Runnable for Job {
                 _ => unreachable!("other project commands do not require storage"),
             },
 
-            RetryPendingBlock { chain_id } => {
+            RetryPendingBlock { chain_id, yes } => {
                 let start_time = Instant::now();
                 let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
                 info!("Committing pending block for chain {}", chain_id);
                 let chain_client = context.make_chain_client(chain_id)?;
+                chain_client.synchronize_from_validators().await?;
+                let pending = (*chain_client.pending_proposal()).clone();
+                if let Some(block) = pending {
+                    let issues = chain_client.validate_proposal(&block, true).await?;
+                    for issue in &issues {
+                        eprintln!("{}: {issue}", issue.severity());
+                    }
+                    let expect_rejection = issues
+                        .iter()
+                        .any(|issue| issue.severity() == ProposalIssueSeverity::Error);
+                    if expect_rejection
+                        && !yes
+                        && !confirm(
+                            "The pending block will be rejected. Propose it anyway? [y/N] ",
+                        )?
+                    {
+                        bail!("The pending block was not proposed");
+                    }
+                }
                 match chain_client.process_pending_block().await? {
                     ClientOutcome::Committed(Some(certificate)) => {
                         info!("Pending block committed successfully.");
@@ -1251,6 +1540,8 @@ impl Job {
             "Temporary client for fetching the parent chain",
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
             DEFAULT_GRACE_PERIOD,
+            ValidatorRequestOptions::default(),
+            StateSnapshotOptions::default(),
         );
 
         // Take the latest committee we know of.
@@ -1259,7 +1550,11 @@ impl Job {
         let nodes: Vec<_> = if let Some(validators) = validators {
             node_provider
                 .make_nodes_from_list(validators)?
-                .map(|(name, node)| RemoteNode { name, node })
+                .map(|(name, node)| RemoteNode {
+                    name,
+                    node,
+                    misbehavior: client.misbehavior_log().clone(),
+                })
                 .collect()
         } else {
             let info = client.local_node().handle_chain_info_query(query).await?;
@@ -1268,7 +1563,11 @@ impl Job {
                 .context("Invalid chain info response; missing latest committee")?;
             node_provider
                 .make_nodes(committee)?
-                .map(|(name, node)| RemoteNode { name, node })
+                .map(|(name, node)| RemoteNode {
+                    name,
+                    node,
+                    misbehavior: client.misbehavior_log().clone(),
+                })
                 .collect()
         };
 
@@ -1415,13 +1714,19 @@ fn log_file_name_for(command: &ClientCommand) -> Cow<'static, str> {
         | ClientCommand::OpenChain { .. }
         | ClientCommand::OpenMultiOwnerChain { .. }
         | ClientCommand::ChangeOwnership { .. }
+        | ClientCommand::RotateKey { .. }
         | ClientCommand::ChangeApplicationPermissions { .. }
+        | ClientCommand::TransferChainOwnership { .. }
         | ClientCommand::CloseChain { .. }
         | ClientCommand::LocalBalance { .. }
         | ClientCommand::QueryBalance { .. }
         | ClientCommand::SyncBalance { .. }
         | ClientCommand::Sync { .. }
         | ClientCommand::ProcessInbox { .. }
+        | ClientCommand::Inbox { .. }
+        | ClientCommand::QueryMessageStatus { .. }
+        | ClientCommand::VerifyChain { .. }
+        | ClientCommand::CompareStateHashes { .. }
         | ClientCommand::QueryValidator { .. }
         | ClientCommand::QueryValidators { .. }
         | ClientCommand::SetValidator { .. }
@@ -1504,6 +1809,17 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
             maximum_block_proposal_size,
             maximum_bytes_read_per_block,
             maximum_bytes_written_per_block,
+            maximum_application_state_size,
+            maximum_operations_per_block,
+            maximum_incoming_bundles_per_block,
+            maximum_incoming_bundles_per_origin,
+            burn_unused_grants,
+            maximum_query_response_size,
+            maximum_messages_per_execution,
+            maximum_message_size,
+            maximum_message_bytes_per_execution,
+            maximum_call_depth,
+            resource_estimate_tolerance_percent,
             testing_prng_seed,
             network_name,
         } => {
@@ -1514,11 +1830,23 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
             let maximum_bytes_read_per_block = maximum_bytes_read_per_block.unwrap_or(u64::MAX);
             let maximum_bytes_written_per_block =
                 maximum_bytes_written_per_block.unwrap_or(u64::MAX);
+            let maximum_application_state_size = maximum_application_state_size.unwrap_or(u64::MAX);
             let maximum_executed_block_size = maximum_executed_block_size.unwrap_or(u64::MAX);
             let maximum_blob_size = maximum_blob_size.unwrap_or(u64::MAX);
             let maximum_published_blobs = maximum_published_blobs.unwrap_or(u64::MAX);
             let maximum_bytecode_size = maximum_bytecode_size.unwrap_or(u64::MAX);
             let maximum_block_proposal_size = maximum_block_proposal_size.unwrap_or(u64::MAX);
+            let maximum_operations_per_block = maximum_operations_per_block.unwrap_or(u64::MAX);
+            let maximum_incoming_bundles_per_block =
+                maximum_incoming_bundles_per_block.unwrap_or(u64::MAX);
+            let maximum_incoming_bundles_per_origin =
+                maximum_incoming_bundles_per_origin.unwrap_or(u64::MAX);
+            let maximum_query_response_size = maximum_query_response_size.unwrap_or(u64::MAX);
+            let maximum_messages_per_execution = maximum_messages_per_execution.unwrap_or(u64::MAX);
+            let maximum_message_size = maximum_message_size.unwrap_or(u64::MAX);
+            let maximum_message_bytes_per_execution =
+                maximum_message_bytes_per_execution.unwrap_or(u64::MAX);
+            let maximum_call_depth = maximum_call_depth.unwrap_or(DEFAULT_MAXIMUM_CALL_DEPTH);
             let policy = ResourceControlPolicy {
                 block: *block_price,
                 fuel_unit: *fuel_unit_price,
@@ -1539,6 +1867,17 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
                 maximum_block_proposal_size,
                 maximum_bytes_read_per_block,
                 maximum_bytes_written_per_block,
+                maximum_application_state_size,
+                maximum_operations_per_block,
+                maximum_incoming_bundles_per_block,
+                maximum_incoming_bundles_per_origin,
+                burn_unused_grants: *burn_unused_grants,
+                maximum_query_response_size,
+                maximum_messages_per_execution,
+                maximum_message_size,
+                maximum_message_bytes_per_execution,
+                maximum_call_depth,
+                resource_estimate_tolerance_percent: *resource_estimate_tolerance_percent,
             };
             let timestamp = start_timestamp
                 .map(|st| {
@@ -1717,7 +2056,10 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
         ClientCommand::Storage(command) => {
             let storage_config = command.storage_config()?;
             let common_config = CommonStoreConfig::default();
-            let full_storage_config = storage_config.add_common_config(common_config).await?;
+            let full_storage_config = storage_config
+                .add_common_config(common_config)
+                .await?
+                .with_encryption(options.storage_encryption_config()?);
             let start_time = Instant::now();
             match command {
                 DatabaseToolCommand::DeleteAll { .. } => {
@@ -1777,6 +2119,103 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
                     );
                     println!("The list of namespaces is {:?}", namespaces);
                 }
+                DatabaseToolCommand::Migrate { dry_run, .. } => {
+                    let report = full_storage_config.migrate(*dry_run).await?;
+                    info!("Migration done in {} ms", start_time.elapsed().as_millis());
+                    if report.applied.is_empty() {
+                        println!(
+                            "The storage format version {} is up to date",
+                            report.initial_version
+                        );
+                    } else {
+                        let verb = if *dry_run {
+                            "Would convert"
+                        } else {
+                            "Converted"
+                        };
+                        println!(
+                            "{verb} {} entries to migrate from the storage format version {}: {}",
+                            report.entries,
+                            report.initial_version,
+                            report.applied.join(", ")
+                        );
+                    }
+                }
+                #[cfg(feature = "rocksdb")]
+                DatabaseToolCommand::Stats {
+                    prefix_length,
+                    sample_size,
+                    ..
+                } => {
+                    let progress = |keys: u64| info!("Scanned {keys} keys");
+                    let statistics = full_storage_config
+                        .rocks_db_statistics(*prefix_length, *sample_size, progress)
+                        .await?;
+                    info!(
+                        "Statistics computed in {} ms",
+                        start_time.elapsed().as_millis()
+                    );
+                    for (prefix, stats) in &statistics.prefixes {
+                        let sampled = if stats.complete { "" } else { " (sampled)" };
+                        println!(
+                            "{}: {} keys{sampled}, {} bytes of keys, {} bytes of values",
+                            hex::encode(prefix),
+                            stats.keys,
+                            stats.key_bytes,
+                            stats.value_bytes
+                        );
+                    }
+                    let estimate = |value: Option<u64>| {
+                        value.map_or_else(|| "unknown".to_string(), |value| value.to_string())
+                    };
+                    println!(
+                        "RocksDB estimates {} keys and {} bytes of live data, in {} bytes of \
+                         SST files",
+                        estimate(statistics.estimated_keys),
+                        estimate(statistics.estimated_live_data_size),
+                        estimate(statistics.sst_files_size)
+                    );
+                }
+                #[cfg(feature = "rocksdb")]
+                DatabaseToolCommand::Compact { chain_id, .. } => {
+                    full_storage_config.rocks_db_compact(*chain_id).await?;
+                    info!("Compaction done in {} ms", start_time.elapsed().as_millis());
+                }
+                #[cfg(not(feature = "rocksdb"))]
+                DatabaseToolCommand::Stats { .. } | DatabaseToolCommand::Compact { .. } => {
+                    bail!("This command requires the `rocksdb` feature");
+                }
+                DatabaseToolCommand::Verify { .. } => {
+                    let progress = |chains: usize| {
+                        if chains % 100 == 0 {
+                            info!("Verified {chains} chains");
+                        }
+                    };
+                    let report = full_storage_config.verify(progress).await?;
+                    info!(
+                        "Verification done in {} ms",
+                        start_time.elapsed().as_millis()
+                    );
+                    for mismatch in &report.mismatches {
+                        println!("{mismatch}");
+                    }
+                    println!(
+                        "{} of {} chains match their latest block",
+                        report.chains - report.mismatches.len(),
+                        report.chains
+                    );
+                    if !report.is_consistent() {
+                        return Ok(1);
+                    }
+                }
+                DatabaseToolCommand::ResetStatistics { chain_id, .. } => {
+                    let chains = full_storage_config.reset_statistics(*chain_id).await?;
+                    info!(
+                        "Statistics reset in {} ms",
+                        start_time.elapsed().as_millis()
+                    );
+                    println!("Reset the execution statistics of {chains} chains");
+                }
             }
             Ok(0)
         }
@@ -1786,22 +2225,32 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
                 chain_id,
                 short,
                 owned,
+                reports,
             } => {
                 let start_time = Instant::now();
+                let wallet = options.wallet().await?;
+                if *reports {
+                    let reports = wallet.misbehavior_reports().iter().filter(|report| {
+                        chain_id.map_or(true, |chain_id| report.misbehavior.chain_id() == chain_id)
+                    });
+                    wallet::pretty_print_reports(reports);
+                    info!("Wallet shown in {} ms", start_time.elapsed().as_millis());
+                    return Ok(0);
+                }
                 let chain_ids = if let Some(chain_id) = chain_id {
                     ensure!(!owned, "Cannot specify both --owned and a chain ID");
                     vec![*chain_id]
                 } else if *owned {
-                    options.wallet().await?.owned_chain_ids()
+                    wallet.owned_chain_ids()
                 } else {
-                    options.wallet().await?.chain_ids()
+                    wallet.chain_ids()
                 };
                 if *short {
                     for chain_id in chain_ids {
                         println!("{chain_id}");
                     }
                 } else {
-                    wallet::pretty_print(&*options.wallet().await?, chain_ids);
+                    wallet::pretty_print(&wallet, chain_ids);
                 }
                 info!("Wallet shown in {} ms", start_time.elapsed().as_millis());
                 Ok(0)
@@ -1846,6 +2295,19 @@ async fn run(options: &ClientOptions) -> Result<i32, anyhow::Error> {
                 Ok(0)
             }
 
+            WalletCommand::Encrypt => {
+                let start_time = Instant::now();
+                let mut wallet = options.wallet().await?;
+                let passphrase = key_store::read_passphrase("New wallet passphrase: ")?;
+                ensure!(!passphrase.is_empty(), "The passphrase must not be empty");
+                wallet.mutate(|w| w.encrypt(&passphrase)).await??;
+                info!(
+                    "Wallet keys encrypted in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                Ok(0)
+            }
+
             WalletCommand::Init {
                 genesis_config_path,
                 faucet,
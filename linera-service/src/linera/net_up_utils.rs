@@ -1,12 +1,16 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(any(feature = "kubernetes", feature = "storage-service"))]
+use std::path::PathBuf;
 use std::{num::NonZeroU16, str::FromStr};
 
 use colored::Colorize as _;
 use linera_base::{data_types::Amount, identifiers::ChainId, time::Duration};
 use linera_client::storage::{StorageConfig, StorageConfigNamespace};
 use linera_execution::ResourceControlPolicy;
+#[cfg(feature = "kubernetes")]
+use linera_service::cli_wrappers::local_kubernetes_net::LocalKubernetesNetConfig;
 use linera_service::{
     cli_wrappers::{
         local_net::{Database, LocalNetConfig, PathProvider, StorageConfigBuilder},
@@ -15,17 +19,12 @@ use linera_service::{
     },
     util::listen_for_shutdown_signals,
 };
-#[cfg(feature = "storage-service")]
-use linera_storage_service::{
-    child::{StorageService, StorageServiceGuard},
-    common::get_service_storage_binary,
-};
 use tokio_util::sync::CancellationToken;
 use tracing::info;
-#[cfg(feature = "kubernetes")]
+#[cfg(feature = "storage-service")]
 use {
-    linera_service::cli_wrappers::local_kubernetes_net::LocalKubernetesNetConfig,
-    std::path::PathBuf,
+    linera_service::cli_wrappers::local_net::start_storage_service,
+    linera_storage_service::child::StorageServiceGuard,
 };
 
 struct StorageConfigProvider {
@@ -39,25 +38,7 @@ impl StorageConfigProvider {
     pub async fn new(storage: &Option<String>) -> anyhow::Result<StorageConfigProvider> {
         match storage {
             #[cfg(feature = "storage-service")]
-            None => {
-                let service_endpoint = linera_base::port::get_free_endpoint().await?;
-                let binary = get_service_storage_binary().await?.display().to_string();
-                let service = StorageService::new(&service_endpoint, binary);
-                let _service_guard = service.run().await?;
-                let _service_guard = Some(_service_guard);
-                let storage_config = StorageConfig::Service {
-                    endpoint: service_endpoint,
-                };
-                let namespace = "table_default".to_string();
-                let storage = StorageConfigNamespace {
-                    storage_config,
-                    namespace,
-                };
-                Ok(StorageConfigProvider {
-                    storage,
-                    _service_guard,
-                })
-            }
+            None => Self::with_storage_service(None, "table_default".to_string()).await,
             #[cfg(not(feature = "storage-service"))]
             None => {
                 panic!("When storage is not selected, the storage-service needs to be enabled");
@@ -65,10 +46,21 @@ impl StorageConfigProvider {
             #[cfg(feature = "storage-service")]
             Some(storage) => {
                 let storage = StorageConfigNamespace::from_str(storage)?;
-                Ok(StorageConfigProvider {
-                    storage,
-                    _service_guard: None,
-                })
+                // Memory and RocksDB storage cannot be shared by the processes of the network,
+                // so they are accessed through a storage service.
+                match storage.storage_config {
+                    StorageConfig::Memory => {
+                        Self::with_storage_service(None, storage.namespace).await
+                    }
+                    #[cfg(feature = "rocksdb")]
+                    StorageConfig::RocksDb { path, .. } => {
+                        Self::with_storage_service(Some(path), storage.namespace).await
+                    }
+                    _ => Ok(StorageConfigProvider {
+                        storage,
+                        _service_guard: None,
+                    }),
+                }
             }
             #[cfg(not(feature = "storage-service"))]
             Some(storage) => {
@@ -78,6 +70,22 @@ impl StorageConfigProvider {
         }
     }
 
+    #[cfg(feature = "storage-service")]
+    async fn with_storage_service(
+        rocks_db_path: Option<PathBuf>,
+        namespace: String,
+    ) -> anyhow::Result<StorageConfigProvider> {
+        let (storage_config, service_guard) = start_storage_service(rocks_db_path).await?;
+        let storage = StorageConfigNamespace {
+            storage_config,
+            namespace,
+        };
+        Ok(StorageConfigProvider {
+            storage,
+            _service_guard: Some(service_guard),
+        })
+    }
+
     pub fn storage_config(&self) -> StorageConfig {
         self.storage.storage_config.clone()
     }
@@ -7,46 +7,82 @@ use async_graphql::{
     futures_util::Stream,
     parser::types::{DocumentOperations, ExecutableDocument, OperationType},
     resolver_utils::ContainerType,
-    Error, MergedObject, OutputType, Request, ScalarType, Schema, ServerError, SimpleObject,
-    Subscription,
+    Error, ErrorExtensions as _, MergedObject, OutputType, Request, ScalarType, Schema,
+    ServerError, SimpleObject, Subscription,
 };
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
-use axum::{extract::Path, http::StatusCode, response, response::IntoResponse, Extension, Router};
-use futures::{lock::Mutex, Future};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query as UrlQuery},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response,
+    response::IntoResponse,
+    Extension, Router,
+};
+use futures::{lock::Mutex, Future, StreamExt as _};
 use linera_base::{
-    crypto::{CryptoError, CryptoHash},
-    data_types::{Amount, ApplicationPermissions, Bytecode, TimeDelta, UserApplicationDescription},
+    crypto::{CryptoError, CryptoHash, PublicKey, Signature},
+    data_types::{
+        Amount, ApplicationPermissions, BlockHeight, Bytecode, Round, TimeDelta, Timestamp,
+        UserApplicationDescription,
+    },
     ensure,
     hashed::Hashed,
-    identifiers::{ApplicationId, BytecodeId, ChainId, Owner, UserApplicationId},
+    identifiers::{
+        ApplicationId, BytecodeId, ChainId, GenericApplicationId, MessageId, Owner,
+        UserApplicationId,
+    },
     ownership::{ChainOwnership, TimeoutConfig},
     BcsHexParseError,
 };
 use linera_chain::{
-    types::{ConfirmedBlock, GenericCertificate},
-    ChainStateView,
+    data_types::{EventRecord, Origin, PendingMessage},
+    types::{Block, ConfirmedBlock, GenericCertificate},
+    BlockHistoryDirection, ChainStateView,
+};
+use linera_client::{
+    chain_listener::{ChainListener, ChainListenerConfig, ClientContext},
+    client_options::{QueryRateLimitConfig, ReadinessConfig},
 };
-use linera_client::chain_listener::{ChainListener, ChainListenerConfig, ClientContext};
 use linera_core::{
     client::{ChainClient, ChainClientError},
-    data_types::ClientOutcome,
-    worker::Notification,
+    data_types::{ClientOutcome, MessageStatus},
+    worker::{Notification, Reason},
 };
 use linera_execution::{
-    committee::{Committee, Epoch},
-    system::{AdminOperation, Recipient, SystemChannel},
-    Operation, Query, QueryOutcome, QueryResponse, SystemOperation,
+    committee::{Committee, Epoch, ValidatorName},
+    system::{AdminOperation, ApplicationAllowlist, Recipient, SystemChannel},
+    ExecutionRuntimeConfig, Operation, Query, QueryAuthentication, QueryAuthenticationError,
+    QueryOutcome, QueryResponse, SystemOperation, DEFAULT_QUERY_SIGNATURE_VALIDITY,
 };
 use linera_sdk::base::BlobContent;
 use linera_storage::Storage;
+use linera_views::views::ViewError;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error as ThisError;
-use tokio::sync::OwnedRwLockReadGuard;
+use tokio::sync::{broadcast, mpsc, OwnedRwLockReadGuard};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::CorsLayer;
 use tracing::{debug, error, info, instrument, trace};
 
-use crate::util;
+use crate::{
+    health::{HealthChecker, NodeServiceProbe},
+    query_rate_limit::{QueryOrigin, QueryRateLimiter, QueryThrottled},
+    state_export::{
+        format_entries, resolve_sub_view, select_keys, sub_view_prefix, ExportFormat,
+        StateExportError, EXPORT_CHUNK_SIZE, SUB_VIEWS_QUERY,
+    },
+    util,
+};
+
+/// The number of block notifications buffered for each subscriber. If a subscriber falls further
+/// behind, the oldest notifications are dropped.
+const BLOCK_NOTIFICATION_BUFFER_SIZE: usize = 64;
+
+/// The maximum number of blocks returned in a single page by the `blockPage` query.
+const MAX_BLOCK_PAGE_SIZE: u32 = 100;
 
 #[derive(SimpleObject, Serialize, Deserialize, Clone)]
 pub struct Chains {
@@ -59,6 +95,7 @@ pub struct QueryRoot<C> {
     context: Arc<Mutex<C>>,
     port: NonZeroU16,
     default_chain: Option<ChainId>,
+    query_limiter: Arc<QueryRateLimiter>,
 }
 
 /// Our root GraphQL subscription type.
@@ -95,10 +132,30 @@ enum NodeServiceError {
     ApplicationServiceError { errors: Vec<String> },
     #[error("chain ID not found: {chain_id}")]
     UnknownChainId { chain_id: String },
+    #[error("application {application_id} is not registered on chain {chain_id}")]
+    UnknownApplicationId {
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+    },
     #[error("malformed chain ID: {0}")]
     InvalidChainId(CryptoError),
     #[error("unexpected application operations added during non-mutation query")]
     UnexpectedOperationsFromQuery,
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
+    #[error("the state at height {requested} is not available, the chain is at height {current}")]
+    HeightNotAvailable {
+        requested: BlockHeight,
+        current: BlockHeight,
+    },
+    #[error("invalid query signature headers: {error}")]
+    InvalidQuerySignatureHeaders { error: String },
+    #[error(transparent)]
+    QueryAuthentication(#[from] QueryAuthenticationError),
+    #[error(transparent)]
+    QueryThrottled(#[from] QueryThrottled),
+    #[error(transparent)]
+    StateExport(#[from] StateExportError),
 }
 
 impl From<ServerError> for NodeServiceError {
@@ -112,6 +169,7 @@ impl From<ServerError> for NodeServiceError {
 impl IntoResponse for NodeServiceError {
     fn into_response(self) -> response::Response {
         let tuple = match self {
+            NodeServiceError::QueryThrottled(throttled) => return throttled.into_response(),
             NodeServiceError::BcsHexError(e) => (StatusCode::BAD_REQUEST, vec![e.to_string()]),
             NodeServiceError::QueryStringError(e) => (StatusCode::BAD_REQUEST, vec![e.to_string()]),
             NodeServiceError::ChainClientError(e) => {
@@ -123,14 +181,18 @@ impl IntoResponse for NodeServiceError {
             NodeServiceError::JsonError(e) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, vec![e.to_string()])
             }
-            NodeServiceError::UnexpectedOperationsFromQuery => {
+            NodeServiceError::UnexpectedOperationsFromQuery | NodeServiceError::ViewError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, vec![self.to_string()])
             }
+            NodeServiceError::HeightNotAvailable { .. }
+            | NodeServiceError::UnknownApplicationId { .. }
+            | NodeServiceError::StateExport(StateExportError::UnknownSubView(_)) => {
+                (StatusCode::NOT_FOUND, vec![self.to_string()])
+            }
             NodeServiceError::MissingOperation
             | NodeServiceError::HeterogeneousOperations
-            | NodeServiceError::UnsupportedQueryType => {
-                (StatusCode::BAD_REQUEST, vec![self.to_string()])
-            }
+            | NodeServiceError::UnsupportedQueryType
+            | NodeServiceError::StateExport(_) => (StatusCode::BAD_REQUEST, vec![self.to_string()]),
             NodeServiceError::GraphQLParseError { error } => (StatusCode::BAD_REQUEST, vec![error]),
             NodeServiceError::ApplicationServiceError { errors } => {
                 (StatusCode::BAD_REQUEST, errors)
@@ -143,6 +205,12 @@ impl IntoResponse for NodeServiceError {
                 StatusCode::BAD_REQUEST,
                 vec!["invalid chain ID".to_string()],
             ),
+            NodeServiceError::InvalidQuerySignatureHeaders { .. } => {
+                (StatusCode::BAD_REQUEST, vec![self.to_string()])
+            }
+            NodeServiceError::QueryAuthentication(_) => {
+                (StatusCode::UNAUTHORIZED, vec![self.to_string()])
+            }
         };
         let tuple = (tuple.0, json!({"error": tuple.1}).to_string());
         tuple.into_response()
@@ -154,13 +222,72 @@ impl<C> SubscriptionRoot<C>
 where
     C: ClientContext,
 {
-    /// Subscribes to notifications from the specified chain.
+    /// Subscribes to notifications from the specified chain. If the subscriber falls behind,
+    /// only the latest pending notification of each kind is kept.
     async fn notifications(
         &self,
         chain_id: ChainId,
     ) -> Result<impl Stream<Item = Notification>, Error> {
         let client = self.context.lock().await.make_chain_client(chain_id)?;
-        Ok(client.subscribe().await?)
+        Ok(client.subscribe_coalesced().await?)
+    }
+
+    /// Subscribes to the blocks executed on the specified chain, optionally only including the
+    /// events emitted by one application.
+    async fn blocks(
+        &self,
+        chain_id: ChainId,
+        application_id: Option<UserApplicationId>,
+    ) -> Result<impl Stream<Item = BlockNotification>, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let mut notifications = client.subscribe().await?;
+        let application_id = application_id.map(GenericApplicationId::User);
+        let (sender, receiver) = broadcast::channel(BLOCK_NOTIFICATION_BUFFER_SIZE);
+        // The task stops as soon as the subscriber is gone, even if the chain has no new blocks.
+        let subscription = CancellationToken::new();
+        let unsubscribed = subscription.clone();
+        tokio::spawn(async move {
+            loop {
+                let notification = tokio::select! {
+                    () = unsubscribed.cancelled() => break,
+                    notification = notifications.next() => notification,
+                };
+                let Some(notification) = notification else {
+                    break;
+                };
+                let Reason::NewBlock { hash, .. } = notification.reason else {
+                    continue;
+                };
+                let value = match client.read_hashed_confirmed_block(hash).await {
+                    Ok(value) => value,
+                    Err(error) => {
+                        error!("Failed to read the block {hash} of chain {chain_id}: {error}");
+                        continue;
+                    }
+                };
+                let notification =
+                    BlockNotification::new(hash, value.inner().block(), application_id);
+                if sender.send(notification).is_err() {
+                    break; // The subscriber is gone.
+                }
+            }
+        });
+        Ok(futures::stream::unfold(
+            (receiver, subscription.drop_guard()),
+            |(mut receiver, guard)| async move {
+                let mut skipped = 0;
+                loop {
+                    match receiver.recv().await {
+                        Ok(mut notification) => {
+                            notification.skipped = skipped;
+                            return Some((notification, (receiver, guard)));
+                        }
+                        Err(broadcast::error::RecvError::Lagged(count)) => skipped += count,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            },
+        ))
     }
 }
 
@@ -505,6 +632,17 @@ where
         self.execute_system_operation(operation, chain_id).await
     }
 
+    /// Restricts the applications that can execute on this chain. Without an `allowlist`,
+    /// all applications can execute again.
+    async fn change_application_allowlist(
+        &self,
+        chain_id: ChainId,
+        allowlist: Option<ApplicationAllowlist>,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::ChangeApplicationAllowlist(allowlist);
+        self.execute_system_operation(operation, chain_id).await
+    }
+
     /// (admin chain only) Registers a new committee. This will notify the subscribers of
     /// the admin chain so that they can migrate to the new epoch (by accepting the
     /// notification as an "incoming message" in a next block).
@@ -596,7 +734,8 @@ where
         .map(|_| CryptoHash::new(&BlobContent::new_data(bytes)))
     }
 
-    /// Creates a new application.
+    /// Creates a new application. Its query responses can be limited to a smaller size than
+    /// the network-wide limit.
     async fn create_application(
         &self,
         chain_id: ChainId,
@@ -604,6 +743,7 @@ where
         parameters: String,
         instantiation_argument: String,
         required_application_ids: Vec<UserApplicationId>,
+        max_query_response_bytes: Option<u64>,
     ) -> Result<ApplicationId, Error> {
         self.apply_client_command(&chain_id, move |client| {
             let parameters = parameters.as_bytes().to_vec();
@@ -616,6 +756,7 @@ where
                         parameters,
                         instantiation_argument,
                         required_application_ids,
+                        max_query_response_bytes,
                     )
                     .await
                     .map_err(Error::from)
@@ -649,6 +790,42 @@ where
             util::wait_for_next_round(&mut stream, timeout).await;
         }
     }
+
+    /// Executes an incoming message that was previously parked in the chain's dead-letter
+    /// queue.
+    async fn retry_dead_letter(
+        &self,
+        chain_id: ChainId,
+        message_id: MessageId,
+    ) -> Result<CryptoHash, Error> {
+        self.apply_client_command(&chain_id, move |client| async move {
+            let result = client
+                .retry_dead_letter(message_id)
+                .await
+                .map_err(Error::from)
+                .map(|outcome| outcome.map(|certificate| certificate.hash()));
+            (result, client)
+        })
+        .await
+    }
+
+    /// Rejects an incoming message that the chain has not executed yet. The message is skipped
+    /// when its bundle is accepted, and bounced if it is tracked.
+    async fn reject_message(
+        &self,
+        chain_id: ChainId,
+        message_id: MessageId,
+    ) -> Result<CryptoHash, Error> {
+        self.apply_client_command(&chain_id, move |client| async move {
+            let result = client
+                .reject_message(message_id)
+                .await
+                .map_err(Error::from)
+                .map(|outcome| outcome.map(|certificate| certificate.hash()));
+            (result, client)
+        })
+        .await
+    }
 }
 
 #[async_graphql::Object(cache_control(no_cache))]
@@ -665,13 +842,24 @@ where
         Ok(ChainStateExtendedView::new(view))
     }
 
-    async fn applications(&self, chain_id: ChainId) -> Result<Vec<ApplicationOverview>, Error> {
+    /// Returns the applications registered on the given chain.
+    ///
+    /// At most `limit` applications are returned, starting after the application `after` if
+    /// provided, so that the ID of the last application of a page can be used to fetch the next
+    /// one. If `bytecode_id` is provided, only the applications using that bytecode are returned.
+    async fn applications(
+        &self,
+        chain_id: ChainId,
+        bytecode_id: Option<BytecodeId>,
+        after: Option<UserApplicationId>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ApplicationOverview>, Error> {
         let client = self.context.lock().await.make_chain_client(chain_id)?;
         let applications = client
             .chain_state_view()
             .await?
             .execution_state
-            .list_applications()
+            .list_applications(after, bytecode_id, limit.unwrap_or(usize::MAX))
             .await?;
 
         let overviews = applications
@@ -682,6 +870,85 @@ where
         Ok(overviews)
     }
 
+    /// Returns whether an application is registered on the given chain, so that contracts
+    /// on that chain can call it.
+    async fn is_application_registered(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+    ) -> Result<bool, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let registered = client
+            .chain_state_view()
+            .await?
+            .execution_state
+            .is_application_registered(&application_id)
+            .await?;
+        Ok(registered)
+    }
+
+    /// Returns the total size in bytes of the keys and values stored by an application on
+    /// the given chain.
+    async fn application_state_size(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+    ) -> Result<u64, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let size = client
+            .chain_state_view()
+            .await?
+            .execution_state
+            .application_state_size(&application_id)
+            .await?;
+        Ok(size)
+    }
+
+    /// Runs a GraphQL query against the service of an application on the given chain, and
+    /// returns the data of the response. If a `height` is given, the query is pinned to the
+    /// chain state before the block at that height, and fails if that state is not available.
+    async fn query_application(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        query: String,
+        height: Option<BlockHeight>,
+    ) -> Result<async_graphql::Value, Error> {
+        check_query_rate_limit(&self.context, &self.query_limiter, chain_id, application_id)
+            .await
+            .map_err(|error| match error {
+                NodeServiceError::QueryThrottled(throttled) => {
+                    Error::from(throttled).extend_with(|_, extensions| {
+                        extensions.set("code", "THROTTLED");
+                    })
+                }
+                error => error.into(),
+            })?;
+        let request = Request::new(query);
+        let QueryOutcome {
+            response,
+            operations,
+        } = query_user_application(
+            &self.context,
+            application_id,
+            &request,
+            chain_id,
+            height,
+            None,
+        )
+        .await?;
+        ensure!(
+            operations.is_empty(),
+            NodeServiceError::UnexpectedOperationsFromQuery
+        );
+        let response = serde_json::from_slice::<async_graphql::Response>(&response)?;
+        if response.is_err() {
+            let errors = response.errors.iter().map(|e| e.to_string()).collect();
+            return Err(NodeServiceError::ApplicationServiceError { errors }.into());
+        }
+        Ok(response.data)
+    }
+
     async fn chains(&self) -> Result<Chains, Error> {
         Ok(Chains {
             list: self.context.lock().await.wallet().chain_ids(),
@@ -689,6 +956,24 @@ where
         })
     }
 
+    /// Returns an overview of the given chains, or of all the chains in the wallet.
+    async fn chain_summaries(
+        &self,
+        chain_ids: Option<Vec<ChainId>>,
+    ) -> Result<Vec<ChainSummary>, Error> {
+        let chain_ids = match chain_ids {
+            Some(chain_ids) => chain_ids,
+            None => self.context.lock().await.wallet().chain_ids(),
+        };
+        let mut summaries = Vec::with_capacity(chain_ids.len());
+        for chain_id in chain_ids {
+            let client = self.context.lock().await.make_chain_client(chain_id)?;
+            let view = client.chain_state_view().await?;
+            summaries.push(ChainSummary::new(&view));
+        }
+        Ok(summaries)
+    }
+
     async fn block(
         &self,
         hash: Option<CryptoHash>,
@@ -735,6 +1020,163 @@ where
         }
     }
 
+    /// Returns a page of at most `limit` blocks of the given chain, from the newest to the
+    /// oldest, starting below the height `before` if provided, or with the latest block.
+    async fn block_page(
+        &self,
+        chain_id: ChainId,
+        before: Option<BlockHeight>,
+        limit: Option<u32>,
+    ) -> Result<BlockPage, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let limit = limit.unwrap_or(10).min(MAX_BLOCK_PAGE_SIZE);
+        let (start, hashes) = {
+            let view = client.chain_state_view().await?;
+            let next_height = view.tip_state.get().next_block_height;
+            let end = before.map_or(next_height, |height| height.min(next_height));
+            let start = BlockHeight(end.0.saturating_sub(limit.into()));
+            let hashes = view
+                .confirmed_log
+                .read(usize::try_from(start)?..usize::try_from(end)?)
+                .await?;
+            (start, hashes)
+        };
+        let mut blocks = Vec::with_capacity(hashes.len());
+        for hash in hashes.into_iter().rev() {
+            blocks.push(client.read_hashed_confirmed_block(hash).await?);
+        }
+        Ok(BlockPage {
+            blocks,
+            next_before: (start > BlockHeight::ZERO).then_some(start),
+        })
+    }
+
+    /// Returns a page of the metadata of at most `limit` blocks of the given chain, listed in
+    /// the given `direction`, starting at the height `from_height` if provided, or with the
+    /// first block in that direction.
+    ///
+    /// If `application_id` is provided, only the blocks with operations of that application
+    /// are listed.
+    async fn block_history(
+        &self,
+        chain_id: ChainId,
+        from_height: Option<BlockHeight>,
+        limit: Option<u32>,
+        direction: Option<BlockHistoryDirection>,
+        application_id: Option<UserApplicationId>,
+    ) -> Result<BlockHistory, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let limit = limit.unwrap_or(10).min(MAX_BLOCK_PAGE_SIZE);
+        let page = client
+            .chain_state_view()
+            .await?
+            .block_history(
+                from_height,
+                usize::try_from(limit)?,
+                direction.unwrap_or_default(),
+                application_id,
+            )
+            .await?;
+        let mut blocks = Vec::with_capacity(page.blocks.len());
+        for (_, hash) in page.blocks {
+            let value = client.read_hashed_confirmed_block(hash).await?;
+            blocks.push(BlockMetadata::new(&value));
+        }
+        Ok(BlockHistory {
+            blocks,
+            next_height: page.next_height,
+        })
+    }
+
+    /// Returns the certificate of the confirmed block with the given hash.
+    async fn certificate(
+        &self,
+        chain_id: ChainId,
+        hash: CryptoHash,
+    ) -> Result<CertificateOverview, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let certificate = client.storage_client().read_certificate(hash).await?;
+        Ok(CertificateOverview::new(certificate))
+    }
+
+    /// Returns a page of the events emitted in the blocks of the given chain with heights from
+    /// `from_height` to `to_height` (excluded), or to the latest block. At most `limit` blocks
+    /// are read per page. If `application_id` is provided, only the events emitted by that
+    /// application are returned.
+    async fn events(
+        &self,
+        chain_id: ChainId,
+        from_height: BlockHeight,
+        to_height: Option<BlockHeight>,
+        limit: Option<u32>,
+        application_id: Option<UserApplicationId>,
+    ) -> Result<EventPage, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let limit = limit.unwrap_or(10).clamp(1, MAX_BLOCK_PAGE_SIZE);
+        let (hashes, next_height) = {
+            let view = client.chain_state_view().await?;
+            let next_height = view.tip_state.get().next_block_height;
+            let to_height = to_height.map_or(next_height, |height| height.min(next_height));
+            if from_height >= to_height {
+                return Ok(EventPage::default());
+            }
+            let page_end = from_height.saturating_add(BlockHeight::from(u64::from(limit)));
+            let (page_end, next_height) = if page_end < to_height {
+                (page_end, Some(page_end))
+            } else {
+                (to_height, None)
+            };
+            let hashes = view
+                .confirmed_log
+                .read(usize::try_from(from_height)?..usize::try_from(page_end)?)
+                .await?;
+            (hashes, next_height)
+        };
+        let application_id = application_id.map(GenericApplicationId::User);
+        let mut events = Vec::new();
+        for hash in hashes {
+            let value = client.read_hashed_confirmed_block(hash).await?;
+            events.extend(IndexedEvent::from_block(
+                value.inner().block(),
+                application_id,
+            ));
+        }
+        Ok(EventPage {
+            events,
+            next_height,
+        })
+    }
+
+    /// Returns the status of the `index`th message sent in the block with the given
+    /// certificate hash, as seen by its recipient `chain_id`.
+    async fn message_status(
+        &self,
+        chain_id: ChainId,
+        certificate_hash: CryptoHash,
+        index: u32,
+    ) -> Result<MessageStatus, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        Ok(client.message_status(certificate_hash, index).await?)
+    }
+
+    /// Returns the number of incoming message bundles waiting to be executed by the given
+    /// chain, for each origin with a non-empty inbox.
+    async fn inbox_depths(&self, chain_id: ChainId) -> Result<Vec<InboxDepth>, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let depths = client.chain_state_view().await?.inbox_depths().await?;
+        Ok(depths
+            .into_iter()
+            .map(|(origin, depth)| InboxDepth { origin, depth })
+            .collect())
+    }
+
+    /// Returns the messages received by the given chain but not executed yet, read from its
+    /// inboxes without executing anything. System messages come with a readable preview.
+    async fn pending_messages(&self, chain_id: ChainId) -> Result<Vec<PendingMessage>, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        Ok(client.pending_messages().await?)
+    }
+
     /// Returns the version information on this node service.
     async fn version(&self) -> linera_version::VersionInfo {
         linera_version::VersionInfo::default()
@@ -811,6 +1253,143 @@ where
     }
 }
 
+/// The number of incoming message bundles waiting in the inbox for one origin.
+#[derive(SimpleObject)]
+pub struct InboxDepth {
+    /// The origin of the bundles.
+    origin: Origin,
+    /// The number of bundles received but not executed yet.
+    depth: usize,
+}
+
+/// An overview of the state of a chain.
+#[derive(SimpleObject)]
+pub struct ChainSummary {
+    /// The ID of the chain.
+    chain_id: ChainId,
+    /// The height of the next block.
+    next_block_height: BlockHeight,
+    /// The hash of the latest block, if any.
+    block_hash: Option<CryptoHash>,
+    /// The timestamp of the latest block.
+    timestamp: Timestamp,
+    /// The balance of the chain account.
+    balance: Amount,
+    /// The owners of the chain.
+    ownership: ChainOwnership,
+}
+
+impl ChainSummary {
+    fn new<C>(view: &ChainStateView<C>) -> Self
+    where
+        C: linera_views::context::Context + Clone + Send + Sync + 'static,
+        C::Extra: linera_execution::ExecutionRuntimeContext,
+    {
+        let tip_state = view.tip_state.get();
+        ChainSummary {
+            chain_id: view.chain_id(),
+            next_block_height: tip_state.next_block_height,
+            block_hash: tip_state.block_hash,
+            timestamp: *view.execution_state.system.timestamp.get(),
+            balance: *view.execution_state.system.balance.get(),
+            ownership: view.manager.ownership.get().clone(),
+        }
+    }
+}
+
+/// A page of blocks of a chain, from the newest to the oldest.
+#[derive(SimpleObject)]
+pub struct BlockPage {
+    /// The blocks in this page.
+    blocks: Vec<Hashed<ConfirmedBlock>>,
+    /// The height to pass as `before` to fetch the next page, if there are older blocks.
+    next_before: Option<BlockHeight>,
+}
+
+/// A page of the metadata of the blocks of a chain.
+#[derive(SimpleObject)]
+pub struct BlockHistory {
+    /// The metadata of the blocks in this page.
+    blocks: Vec<BlockMetadata>,
+    /// The height to pass as `fromHeight` to fetch the next page, if there are more blocks.
+    next_height: Option<BlockHeight>,
+}
+
+/// A page of the events emitted in the blocks of a chain.
+#[derive(Default, SimpleObject)]
+pub struct EventPage {
+    /// The events emitted in the blocks of this page.
+    events: Vec<IndexedEvent>,
+    /// The height to pass as `fromHeight` to fetch the next page, if there are more blocks in
+    /// the requested range.
+    next_height: Option<BlockHeight>,
+}
+
+/// The metadata of a confirmed block.
+#[derive(SimpleObject)]
+pub struct BlockMetadata {
+    /// The height of the block.
+    height: BlockHeight,
+    /// The hash of the block.
+    hash: CryptoHash,
+    /// The timestamp when the block was created.
+    timestamp: Timestamp,
+    /// The number of operations in the block.
+    operation_count: u32,
+    /// The user who signed the block proposal, if any.
+    proposer: Option<Owner>,
+}
+
+impl BlockMetadata {
+    fn new(value: &Hashed<ConfirmedBlock>) -> Self {
+        let block = value.inner().block();
+        BlockMetadata {
+            height: block.header.height,
+            hash: value.hash(),
+            timestamp: block.header.timestamp,
+            operation_count: block.body.operations.len() as u32,
+            proposer: block.header.authenticated_signer,
+        }
+    }
+}
+
+/// A certificate for a confirmed block.
+#[derive(SimpleObject)]
+pub struct CertificateOverview {
+    /// The certified block.
+    block: Hashed<ConfirmedBlock>,
+    /// The round in which the block was certified.
+    round: Round,
+    /// The signatures of the validators that certified the block.
+    signatures: Vec<ValidatorSignature>,
+}
+
+impl CertificateOverview {
+    fn new(certificate: GenericCertificate<ConfirmedBlock>) -> Self {
+        let (block, round, signatures) = certificate.destructure();
+        CertificateOverview {
+            block,
+            round,
+            signatures: signatures
+                .into_iter()
+                .map(|(validator, signature)| ValidatorSignature {
+                    validator,
+                    signature,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The signature of a validator on a certified value.
+#[derive(SimpleObject)]
+pub struct ValidatorSignature {
+    /// The validator that signed the value.
+    validator: ValidatorName,
+    /// The signature.
+    signature: Signature,
+}
+
 #[derive(SimpleObject)]
 pub struct ApplicationOverview {
     id: UserApplicationId,
@@ -838,6 +1417,68 @@ impl ApplicationOverview {
     }
 }
 
+/// An event emitted in a block, together with its position in the chain.
+#[derive(Clone, SimpleObject)]
+pub struct IndexedEvent {
+    /// The height of the block in which the event was emitted.
+    height: BlockHeight,
+    /// The index of the transaction that emitted the event, within its block.
+    transaction_index: u32,
+    /// The event itself.
+    event: EventRecord,
+}
+
+impl IndexedEvent {
+    /// Returns the events emitted in the `block`, optionally only by the given application.
+    fn from_block(
+        block: &Block,
+        application_id: Option<GenericApplicationId>,
+    ) -> impl Iterator<Item = IndexedEvent> + '_ {
+        (0u32..)
+            .zip(&block.body.events)
+            .flat_map(move |(transaction_index, events)| {
+                events
+                    .iter()
+                    .filter(move |event| {
+                        application_id.map_or(true, |id| event.stream_id.application_id == id)
+                    })
+                    .map(move |event| IndexedEvent {
+                        height: block.header.height,
+                        transaction_index,
+                        event: event.clone(),
+                    })
+            })
+    }
+}
+
+/// A notification about a block executed on a chain.
+#[derive(Clone, SimpleObject)]
+pub struct BlockNotification {
+    /// The height of the block.
+    height: BlockHeight,
+    /// The hash of the block.
+    hash: CryptoHash,
+    /// The hash of the chain's execution state after the block.
+    state_hash: CryptoHash,
+    /// The events emitted in the block.
+    events: Vec<IndexedEvent>,
+    /// The number of notifications that were dropped right before this one because the
+    /// subscriber didn't keep up.
+    skipped: u64,
+}
+
+impl BlockNotification {
+    fn new(hash: CryptoHash, block: &Block, application_id: Option<GenericApplicationId>) -> Self {
+        BlockNotification {
+            height: block.header.height,
+            hash,
+            state_hash: block.header.state_hash,
+            events: IndexedEvent::from_block(block, application_id).collect(),
+            skipped: 0,
+        }
+    }
+}
+
 /// Given a parsed GraphQL query (or `ExecutableDocument`), returns the `OperationType`.
 ///
 /// Errors:
@@ -858,6 +1499,189 @@ fn operation_type(document: &ExecutableDocument) -> Result<OperationType, NodeSe
     }
 }
 
+/// The URL parameters accepted when querying an application.
+#[derive(Debug, Deserialize)]
+struct ApplicationQueryParams {
+    /// The height of the next block on the chain, to pin the query to the state before it.
+    height: Option<BlockHeight>,
+}
+
+/// The URL parameters accepted when exporting the state of an application.
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    /// The name of the field holding the sub-view in the application's state.
+    sub_view: String,
+    /// The format of the exported entries.
+    #[serde(default)]
+    format: ExportFormat,
+    /// The hex-encoded key after which to resume the export.
+    after: Option<String>,
+    /// The maximum number of entries to export.
+    limit: Option<usize>,
+    /// The height of the next block on the chain, to pin the export to the state before it.
+    height: Option<BlockHeight>,
+}
+
+/// The response header with the key to resume an export from, if it is incomplete.
+const EXPORT_NEXT_AFTER_HEADER: &str = "x-linera-next-after";
+
+/// The request header with the hex-encoded public key of the owner signing a query.
+const QUERY_PUBLIC_KEY_HEADER: &str = "x-linera-query-public-key";
+
+/// The request header with the hex-encoded signature of a query.
+const QUERY_SIGNATURE_HEADER: &str = "x-linera-query-signature";
+
+/// The request header with the time at which a query was signed, in microseconds since the
+/// Unix epoch.
+const QUERY_TIMESTAMP_HEADER: &str = "x-linera-query-timestamp";
+
+/// Returns the owner who signed the `body` of a query to the application, or `None` if the
+/// request has none of the signature headers.
+///
+/// The signature covers the chain ID, the application ID, the raw body and the timestamp, and
+/// is only accepted within [`DEFAULT_QUERY_SIGNATURE_VALIDITY`] of the local time.
+fn authenticate_query(
+    headers: &HeaderMap,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    body: &[u8],
+) -> Result<Option<Owner>, NodeServiceError> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|error| NodeServiceError::InvalidQuerySignatureHeaders {
+                        error: format!("{name}: {error}"),
+                    })
+            })
+            .transpose()
+    };
+    let (public_key, signature, timestamp) = match (
+        header(QUERY_PUBLIC_KEY_HEADER)?,
+        header(QUERY_SIGNATURE_HEADER)?,
+        header(QUERY_TIMESTAMP_HEADER)?,
+    ) {
+        (None, None, None) => return Ok(None),
+        (Some(public_key), Some(signature), Some(timestamp)) => (public_key, signature, timestamp),
+        _ => {
+            return Err(NodeServiceError::InvalidQuerySignatureHeaders {
+                error: format!(
+                    "{QUERY_PUBLIC_KEY_HEADER}, {QUERY_SIGNATURE_HEADER} and \
+                    {QUERY_TIMESTAMP_HEADER} must be provided together"
+                ),
+            })
+        }
+    };
+    let invalid = |name: &str, error: String| NodeServiceError::InvalidQuerySignatureHeaders {
+        error: format!("{name}: {error}"),
+    };
+    let authentication = QueryAuthentication {
+        public_key: public_key
+            .parse::<PublicKey>()
+            .map_err(|error| invalid(QUERY_PUBLIC_KEY_HEADER, error.to_string()))?,
+        signature: signature
+            .parse::<Signature>()
+            .map_err(|error| invalid(QUERY_SIGNATURE_HEADER, error.to_string()))?,
+        timestamp: timestamp
+            .parse::<u64>()
+            .map_err(|error| invalid(QUERY_TIMESTAMP_HEADER, error.to_string()))?
+            .into(),
+    };
+    let owner = authentication.verify(
+        chain_id,
+        application_id,
+        body,
+        Timestamp::now(),
+        DEFAULT_QUERY_SIGNATURE_VALIDITY,
+    )?;
+    Ok(Some(owner))
+}
+
+/// Queries a user application on the given chain, returning the raw [`QueryOutcome`].
+///
+/// The chain tip is resolved once when the query starts, and the whole query reads that state.
+/// If a `height` is given, the query only succeeds if that is the height of the next block.
+async fn query_user_application<C: ClientContext>(
+    context: &Mutex<C>,
+    application_id: UserApplicationId,
+    request: &Request,
+    chain_id: ChainId,
+    height: Option<BlockHeight>,
+    authenticated_signer: Option<Owner>,
+) -> Result<QueryOutcome<Vec<u8>>, NodeServiceError> {
+    let bytes = serde_json::to_vec(&request)?;
+    let query = Query::User {
+        application_id,
+        bytes,
+        authenticated_signer,
+    };
+    let client = context
+        .lock()
+        .await
+        .make_chain_client(chain_id)
+        .map_err(|_| NodeServiceError::UnknownChainId {
+            chain_id: chain_id.to_string(),
+        })?;
+    let QueryOutcome {
+        response,
+        operations,
+    } = client.query_application(query, height).await?;
+    match response {
+        QueryResponse::System(_) => {
+            unreachable!("cannot get a system response for a user query")
+        }
+        QueryResponse::User(user_response_bytes) => Ok(QueryOutcome {
+            response: user_response_bytes,
+            operations,
+        }),
+    }
+}
+
+/// Counts a front-end query to an application against its rate limit on the chain, if it has
+/// one. The application must be registered on a chain of the wallet, so that the limiter only
+/// keeps budgets for queries that can be answered.
+async fn check_query_rate_limit<C: ClientContext>(
+    context: &Mutex<C>,
+    query_limiter: &QueryRateLimiter,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+) -> Result<(), NodeServiceError> {
+    if query_limiter
+        .limit(application_id, QueryOrigin::Direct)
+        .is_none()
+    {
+        return Ok(());
+    }
+    let client = context
+        .lock()
+        .await
+        .make_chain_client(chain_id)
+        .map_err(|_| NodeServiceError::UnknownChainId {
+            chain_id: chain_id.to_string(),
+        })?;
+    let is_registered = client
+        .chain_state_view()
+        .await
+        .map_err(ChainClientError::from)?
+        .execution_state
+        .system
+        .registry
+        .known_applications
+        .contains_key(&application_id)
+        .await?;
+    ensure!(
+        is_registered,
+        NodeServiceError::UnknownApplicationId {
+            chain_id,
+            application_id,
+        }
+    );
+    query_limiter.check(chain_id, application_id, QueryOrigin::Direct)?;
+    Ok(())
+}
+
 /// The `NodeService` is a server that exposes a web-server to the client.
 /// The node service is primarily used to explore the state of a chain in GraphQL.
 pub struct NodeService<C>
@@ -865,10 +1689,12 @@ where
     C: ClientContext,
 {
     config: ChainListenerConfig,
+    readiness: ReadinessConfig,
     port: NonZeroU16,
     default_chain: Option<ChainId>,
     storage: C::Storage,
     context: Arc<Mutex<C>>,
+    query_limiter: Arc<QueryRateLimiter>,
 }
 
 impl<C> Clone for NodeService<C>
@@ -878,10 +1704,12 @@ where
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
+            readiness: self.readiness.clone(),
             port: self.port,
             default_chain: self.default_chain,
             storage: self.storage.clone(),
             context: Arc::clone(&self.context),
+            query_limiter: Arc::clone(&self.query_limiter),
         }
     }
 }
@@ -893,17 +1721,30 @@ where
     /// Creates a new instance of the node service given a client chain and a port.
     pub async fn new(
         config: ChainListenerConfig,
+        readiness: ReadinessConfig,
+        query_rate_limits: QueryRateLimitConfig,
         port: NonZeroU16,
         default_chain: Option<ChainId>,
         storage: C::Storage,
         context: C,
     ) -> Self {
+        let query_limiter = Arc::new(QueryRateLimiter::new(query_rate_limits));
+        if query_limiter.limit_internal_queries() {
+            // The contracts executed by this node query the services through the runtime.
+            let runtime_config = storage.execution_runtime_config();
+            runtime_config.set(ExecutionRuntimeConfig {
+                service_query_filter: Some(query_limiter.clone()),
+                ..runtime_config.get()
+            });
+        }
         Self {
             config,
+            readiness,
             port,
             default_chain,
             storage,
             context: Arc::new(Mutex::new(context)),
+            query_limiter,
         }
     }
 
@@ -913,6 +1754,7 @@ where
                 context: Arc::clone(&self.context),
                 port: self.port,
                 default_chain: self.default_chain,
+                query_limiter: Arc::clone(&self.query_limiter),
             },
             MutationRoot {
                 context: Arc::clone(&self.context),
@@ -932,13 +1774,28 @@ where
         let application_handler =
             axum::routing::get(util::graphiql).post(Self::application_handler);
 
+        let health_checker = HealthChecker::new(
+            NodeServiceProbe::new(self.storage.clone(), Arc::clone(&self.context)),
+            self.readiness.clone(),
+        );
+
         let app = Router::new()
             .route("/", index_handler)
             .route(
                 "/chains/:chain_id/applications/:application_id",
                 application_handler,
             )
-            .route("/ready", axum::routing::get(|| async { "ready!" }))
+            .route(
+                "/chains/:chain_id/applications/:application_id/export",
+                axum::routing::get(Self::export_handler),
+            )
+            .merge(health_checker.router());
+        #[cfg(with_metrics)]
+        let app = app.route(
+            "/metrics",
+            axum::routing::get(crate::prometheus_server::serve_metrics),
+        );
+        let app = app
             .route_service("/ws", GraphQLSubscription::new(self.schema()))
             .layer(Extension(self.clone()))
             // TODO(#551): Provide application authentication.
@@ -958,18 +1815,26 @@ where
         Ok(())
     }
 
-    /// Handles queries for user applications.
+    /// Handles queries for user applications, optionally pinned to a block height.
     async fn user_application_query(
         &self,
         application_id: UserApplicationId,
         request: &Request,
         chain_id: ChainId,
+        height: Option<BlockHeight>,
+        authenticated_signer: Option<Owner>,
     ) -> Result<async_graphql::Response, NodeServiceError> {
         let QueryOutcome {
             response: user_response_bytes,
             operations,
         } = self
-            .query_user_application(application_id, request, chain_id)
+            .query_user_application(
+                application_id,
+                request,
+                chain_id,
+                height,
+                authenticated_signer,
+            )
             .await?;
 
         ensure!(
@@ -986,13 +1851,20 @@ where
         application_id: UserApplicationId,
         request: &Request,
         chain_id: ChainId,
+        authenticated_signer: Option<Owner>,
     ) -> Result<async_graphql::Response, NodeServiceError> {
         debug!("Request: {:?}", &request);
         let QueryOutcome {
             response,
             operations,
         } = self
-            .query_user_application(application_id, request, chain_id)
+            .query_user_application(
+                application_id,
+                request,
+                chain_id,
+                None,
+                authenticated_signer,
+            )
             .await?;
         let graphql_response = serde_json::from_slice::<async_graphql::Response>(&response)?;
         if graphql_response.is_err() {
@@ -1032,33 +1904,18 @@ where
         application_id: UserApplicationId,
         request: &Request,
         chain_id: ChainId,
+        height: Option<BlockHeight>,
+        authenticated_signer: Option<Owner>,
     ) -> Result<QueryOutcome<Vec<u8>>, NodeServiceError> {
-        let bytes = serde_json::to_vec(&request)?;
-        let query = Query::User {
+        query_user_application(
+            &self.context,
             application_id,
-            bytes,
-        };
-        let client = self
-            .context
-            .lock()
-            .await
-            .make_chain_client(chain_id)
-            .map_err(|_| NodeServiceError::UnknownChainId {
-                chain_id: chain_id.to_string(),
-            })?;
-        let QueryOutcome {
-            response,
-            operations,
-        } = client.query_application(query).await?;
-        match response {
-            QueryResponse::System(_) => {
-                unreachable!("cannot get a system response for a user query")
-            }
-            QueryResponse::User(user_response_bytes) => Ok(QueryOutcome {
-                response: user_response_bytes,
-                operations,
-            }),
-        }
+            request,
+            chain_id,
+            height,
+            authenticated_signer,
+        )
+        .await
     }
 
     /// Executes a GraphQL query and generates a response for our `Schema`.
@@ -1073,31 +1930,62 @@ where
 
     /// Executes a GraphQL query against an application.
     /// Pattern matches on the `OperationType` of the query and routes the query
-    /// accordingly.
+    /// accordingly. Queries can be pinned to a block height with the `height` URL parameter.
+    ///
+    /// If the request has the `x-linera-query-*` signature headers, the owner who signed the
+    /// body is passed to the application as the authenticated signer of the query.
+    ///
+    /// Queries and mutations are rejected with `429 Too Many Requests` if the application
+    /// exceeds its rate limit on this chain.
     async fn application_handler(
         Path((chain_id, application_id)): Path<(String, String)>,
+        UrlQuery(params): UrlQuery<ApplicationQueryParams>,
         service: Extension<Self>,
-        request: GraphQLRequest,
+        headers: HeaderMap,
+        body: Bytes,
     ) -> Result<GraphQLResponse, NodeServiceError> {
-        let mut request = request.into_inner();
+        let mut request = serde_json::from_slice::<Request>(&body).map_err(|error| {
+            NodeServiceError::GraphQLParseError {
+                error: error.to_string(),
+            }
+        })?;
 
         let parsed_query = request.parsed_query()?;
         let operation_type = operation_type(parsed_query)?;
 
         let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
         let application_id: UserApplicationId = application_id.parse()?;
+        check_query_rate_limit(
+            &service.0.context,
+            &service.0.query_limiter,
+            chain_id,
+            application_id,
+        )
+        .await?;
+        let authenticated_signer = authenticate_query(&headers, chain_id, application_id, &body)?;
 
         let response = match operation_type {
             OperationType::Query => {
                 service
                     .0
-                    .user_application_query(application_id, &request, chain_id)
+                    .user_application_query(
+                        application_id,
+                        &request,
+                        chain_id,
+                        params.height,
+                        authenticated_signer,
+                    )
                     .await?
             }
             OperationType::Mutation => {
                 service
                     .0
-                    .user_application_mutation(application_id, &request, chain_id)
+                    .user_application_mutation(
+                        application_id,
+                        &request,
+                        chain_id,
+                        authenticated_signer,
+                    )
                     .await?
             }
             OperationType::Subscription => return Err(NodeServiceError::UnsupportedQueryType),
@@ -1105,4 +1993,113 @@ where
 
         Ok(response.into())
     }
+
+    /// Exports the entries of a sub-view of an application's state, as JSON lines or CSV.
+    ///
+    /// The sub-view is looked up by name in the application's GraphQL schema. Its entries are
+    /// read from a snapshot of the chain state, which doesn't change while they are streamed,
+    /// [`EXPORT_CHUNK_SIZE`] at a time. If `limit` is set and the response has an
+    /// `x-linera-next-after` header, the next page is requested with `after` set to its value.
+    async fn export_handler(
+        Path((chain_id, application_id)): Path<(String, String)>,
+        UrlQuery(params): UrlQuery<ExportParams>,
+        service: Extension<Self>,
+    ) -> Result<response::Response, NodeServiceError> {
+        let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
+        let application_id: UserApplicationId = application_id.parse()?;
+        let after = params.after.as_deref().map(hex::decode).transpose()?;
+
+        let QueryOutcome { response, .. } = service
+            .0
+            .query_user_application(
+                application_id,
+                &Request::new(SUB_VIEWS_QUERY),
+                chain_id,
+                params.height,
+                None,
+            )
+            .await?;
+        let (field, kind) = resolve_sub_view(&params.sub_view, &response)?;
+        let prefix = sub_view_prefix(field, kind);
+
+        let client = service
+            .0
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .map_err(|_| NodeServiceError::UnknownChainId {
+                chain_id: chain_id.to_string(),
+            })?;
+        let chain = client
+            .chain_state_view()
+            .await
+            .map_err(ChainClientError::from)?;
+        if let Some(requested) = params.height {
+            let current = chain.tip_state.get().next_block_height;
+            ensure!(
+                requested == current,
+                NodeServiceError::HeightNotAvailable { requested, current }
+            );
+        }
+        let keys = match chain
+            .execution_state
+            .users
+            .try_load_entry(&application_id)
+            .await?
+        {
+            Some(view) => view.find_keys_by_prefix(&prefix).await?,
+            None => Vec::new(),
+        };
+        let (keys, next_after) = select_keys(keys, after.as_deref(), params.limit);
+
+        let format = params.format;
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            if sender.send(Ok(format.header().to_owned())).await.is_err() {
+                return;
+            }
+            for chunk in keys.chunks(EXPORT_CHUNK_SIZE) {
+                let full_keys = chunk
+                    .iter()
+                    .map(|key| [prefix.as_slice(), key.as_slice()].concat())
+                    .collect();
+                let result = match chain
+                    .execution_state
+                    .users
+                    .try_load_entry(&application_id)
+                    .await
+                {
+                    Ok(Some(view)) => view.multi_get(full_keys).await,
+                    Ok(None) => Ok(Vec::new()),
+                    Err(error) => Err(error),
+                };
+                let result = result.map(|values| {
+                    let entries = chunk
+                        .iter()
+                        .zip(&values)
+                        .filter_map(|(key, value)| Some((key.as_slice(), value.as_deref()?)));
+                    format_entries(format, entries)
+                });
+                let failed = result.is_err();
+                if sender.send(result).await.is_err() || failed {
+                    return; // The client is gone, or the export can't continue.
+                }
+            }
+        });
+
+        let mut response = (
+            [(header::CONTENT_TYPE, format.content_type())],
+            Body::from_stream(ReceiverStream::new(receiver)),
+        )
+            .into_response();
+        if let Some(next_after) = next_after {
+            let value = HeaderValue::from_str(&hex::encode(next_after))
+                .expect("hexadecimal strings are valid header values");
+            response
+                .headers_mut()
+                .insert(EXPORT_NEXT_AFTER_HEADER, value);
+        }
+        Ok(response)
+    }
 }
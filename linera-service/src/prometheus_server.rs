@@ -28,14 +28,15 @@ pub fn start_metrics(
     });
 }
 
-async fn serve_metrics() -> Result<String, AxumError> {
+/// Returns the metrics of this process in the Prometheus text format.
+pub(crate) async fn serve_metrics() -> Result<String, AxumError> {
     let metric_families = prometheus::gather();
     Ok(prometheus::TextEncoder::new()
         .encode_to_string(&metric_families)
         .map_err(anyhow::Error::from)?)
 }
 
-struct AxumError(anyhow::Error);
+pub(crate) struct AxumError(anyhow::Error);
 
 impl IntoResponse for AxumError {
     fn into_response(self) -> axum::response::Response {
@@ -24,7 +24,8 @@ use linera_client::config::GenesisConfig;
 use linera_core::{notifier::ChannelNotifier, JoinSetExt as _};
 use linera_rpc::{
     config::{
-        ShardConfig, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        RequestLimitsConfig, ShardConfig, TlsConfig, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig,
     },
     grpc::{
         api::{
@@ -41,6 +42,7 @@ use linera_rpc::{
         GrpcProtoConversionError, GrpcProxyable, GRPC_CHUNKED_MESSAGE_FILL_LIMIT,
         GRPC_MAX_MESSAGE_SIZE,
     },
+    throttle::RequestThrottle,
 };
 use linera_sdk::{base::Blob, views::ViewError};
 use linera_storage::Storage;
@@ -49,6 +51,7 @@ use tokio::{select, task::JoinSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tonic::{
+    service::interceptor::InterceptedService,
     transport::{Channel, Identity, Server, ServerTlsConfig},
     Request, Response, Status,
 };
@@ -155,6 +158,7 @@ struct GrpcProxyInner<S> {
     worker_connection_pool: GrpcConnectionPool,
     notifier: ChannelNotifier<Result<Notification, Status>>,
     tls: TlsConfig,
+    request_throttle: RequestThrottle,
     storage: S,
 }
 
@@ -169,6 +173,7 @@ where
         connect_timeout: Duration,
         timeout: Duration,
         tls: TlsConfig,
+        request_limits: RequestLimitsConfig,
         storage: S,
     ) -> Self {
         Self(Arc::new(GrpcProxyInner {
@@ -180,14 +185,21 @@ where
                 .with_timeout(timeout),
             notifier: ChannelNotifier::default(),
             tls,
+            request_throttle: RequestThrottle::new(request_limits),
             storage,
         }))
     }
 
-    fn as_validator_node(&self) -> ValidatorNodeServer<Self> {
-        ValidatorNodeServer::new(self.clone())
+    fn as_validator_node(&self) -> InterceptedService<ValidatorNodeServer<Self>, RequestThrottle> {
+        let throttle = self.0.request_throttle.clone();
+        let max_request_size = throttle
+            .limits()
+            .max_request_size
+            .unwrap_or(GRPC_MAX_MESSAGE_SIZE);
+        let server = ValidatorNodeServer::new(self.clone())
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
-            .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE)
+            .max_decoding_message_size(max_request_size);
+        InterceptedService::new(server, throttle)
     }
 
     fn as_notifier_service(&self) -> NotifierServiceServer<Self> {
@@ -403,6 +415,18 @@ where
         )
     }
 
+    #[instrument(skip_all, err(Display))]
+    async fn handle_confirmed_certificates(
+        &self,
+        request: Request<api::HandleConfirmedCertificatesRequest>,
+    ) -> Result<Response<ChainInfoResult>, Status> {
+        let (mut client, inner) = self.worker_client(request).await?;
+        Self::log_and_return_proxy_request_outcome(
+            client.handle_confirmed_certificates(inner).await,
+            "handle_confirmed_certificates",
+        )
+    }
+
     #[instrument(skip_all, err(Display))]
     async fn handle_validated_certificate(
         &self,
@@ -15,7 +15,7 @@ use linera_client::{
 use linera_core::{node::NodeError, JoinSetExt as _};
 use linera_rpc::{
     config::{
-        NetworkProtocol, ShardConfig, ValidatorInternalNetworkPreConfig,
+        NetworkProtocol, RequestLimitsConfig, ShardConfig, ValidatorInternalNetworkPreConfig,
         ValidatorPublicNetworkPreConfig,
     },
     simple::{MessageHandler, TransportProtocol},
@@ -61,6 +61,11 @@ pub struct ProxyOptions {
     #[arg(long = "storage")]
     storage_config: StorageConfigNamespace,
 
+    /// Path to a JSON file with the keys encrypting the values of the storage. The values are
+    /// not encrypted by default.
+    #[arg(long = "storage-encryption-config")]
+    storage_encryption_config_path: Option<PathBuf>,
+
     /// The maximal number of simultaneous queries to the database
     #[arg(long)]
     max_concurrent_queries: Option<usize>,
@@ -144,6 +149,7 @@ where
                     context.send_timeout,
                     context.recv_timeout,
                     tls,
+                    context.config.request_limits,
                     storage,
                 ))
             }
@@ -163,6 +169,7 @@ where
                 genesis_config: context.genesis_config,
                 send_timeout: context.send_timeout,
                 recv_timeout: context.recv_timeout,
+                request_limits: context.config.request_limits,
                 storage,
             })),
             _ => {
@@ -188,6 +195,7 @@ where
     genesis_config: GenesisConfig,
     send_timeout: Duration,
     recv_timeout: Duration,
+    request_limits: RequestLimitsConfig,
     storage: S,
 }
 
@@ -252,9 +260,16 @@ where
             shutdown_signal.clone(),
         );
 
+        let request_limits = self.request_limits;
         self.public_config
             .protocol
-            .spawn_server(address, self, shutdown_signal, &mut join_set)
+            .spawn_server(
+                address,
+                self,
+                request_limits,
+                shutdown_signal,
+                &mut join_set,
+            )
             .join()
             .await?;
 
@@ -335,6 +350,7 @@ where
             | LiteCertificate(_)
             | TimeoutCertificate(_)
             | ConfirmedCertificate(_)
+            | ConfirmedCertificates(_)
             | ValidatedCertificate(_)
             | ChainInfoQuery(_)
             | CrossChainRequest(_)
@@ -386,7 +402,16 @@ impl ProxyOptions {
             max_stream_queries: self.max_stream_queries,
             cache_size: self.cache_size,
         };
-        let full_storage_config = self.storage_config.add_common_config(common_config).await?;
+        let encryption_config = self
+            .storage_encryption_config_path
+            .as_ref()
+            .map(util::read_json)
+            .transpose()?;
+        let full_storage_config = self
+            .storage_config
+            .add_common_config(common_config)
+            .await?
+            .with_encryption(encryption_config);
         let genesis_config: GenesisConfig = util::read_json(&self.genesis_config_path)?;
         run_with_storage(
             full_storage_config,
@@ -0,0 +1,234 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limits on the queries of the node service to the applications, so that a front-end
+//! querying one application too often doesn't starve the other applications of the node.
+//!
+//! Each application on each chain has a token bucket for the queries of the front-ends, sent
+//! to its endpoint or through the `queryApplication` field of the GraphQL API of the node
+//! service, and a separate one for the queries of the contracts to its service while the node
+//! service executes blocks. A front-end query that finds its bucket empty is rejected with
+//! `429 Too Many Requests`.
+
+use std::{collections::HashMap, fmt, num::NonZeroUsize, sync::Mutex};
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use linera_base::{
+    identifiers::{ChainId, UserApplicationId},
+    time::{Duration, Instant},
+};
+use linera_client::client_options::{QueryRateLimitConfig, RateLimit};
+use linera_execution::ServiceQueryFilter;
+use lru::LruCache;
+use serde_json::json;
+use thiserror::Error as ThisError;
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec,
+    std::sync::LazyLock,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/query_rate_limit.rs"]
+mod tests;
+
+/// The number of token buckets kept by the limiter. The bucket used the least recently is
+/// dropped to make room for a new one, so an application that hasn't been queried for a while
+/// may get a full budget again.
+const MAX_BUCKETS: usize = 10_000;
+
+#[cfg(with_metrics)]
+static THROTTLED_QUERIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "node_service_throttled_queries",
+        "The number of queries to applications rejected by the rate limits of the node service",
+        &["origin"],
+    )
+});
+
+/// How a query reached an application, which decides the budget it counts against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QueryOrigin {
+    /// A query of a front-end, sent to the endpoint of the application or through the
+    /// `queryApplication` field of the GraphQL API of the node service.
+    Direct,
+    /// A query of a contract to the service of the application, while the node service
+    /// executes a block.
+    Internal,
+}
+
+impl fmt::Display for QueryOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryOrigin::Direct => write!(f, "direct"),
+            QueryOrigin::Internal => write!(f, "internal"),
+        }
+    }
+}
+
+/// A query rejected because its application received too many queries.
+#[derive(Clone, Debug, PartialEq, ThisError)]
+#[error(
+    "too many {origin} queries to application {application_id} on chain {chain_id}, \
+    retry in {retry_after:?}"
+)]
+pub struct QueryThrottled {
+    pub chain_id: ChainId,
+    pub application_id: UserApplicationId,
+    pub origin: QueryOrigin,
+    /// How long until the next query is accepted.
+    pub retry_after: Duration,
+}
+
+impl IntoResponse for QueryThrottled {
+    fn into_response(self) -> Response {
+        let retry_after_secs = self.retry_after.as_secs_f64().ceil() as u64;
+        let body = json!({ "error": [self.to_string()] }).to_string();
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, body).into_response();
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(retry_after_secs.max(1)),
+        );
+        response
+    }
+}
+
+/// The tokens left to an application, each of them allowing one query.
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            limit,
+            tokens: f64::from(limit.burst),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.limit.per_second)
+            .min(f64::from(self.limit.burst));
+        self.last_refill = now;
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.limit.per_second,
+            ))
+        }
+    }
+}
+
+/// Enforces the rate limits of the queries to the applications.
+pub struct QueryRateLimiter {
+    default_limit: Option<RateLimit>,
+    overrides: HashMap<UserApplicationId, RateLimit>,
+    internal_limit: Option<RateLimit>,
+    buckets: Mutex<LruCache<(ChainId, UserApplicationId, QueryOrigin), TokenBucket>>,
+}
+
+impl QueryRateLimiter {
+    pub fn new(config: QueryRateLimitConfig) -> Self {
+        let overrides = config
+            .overrides
+            .into_iter()
+            .map(|rate_limit| (rate_limit.application_id, rate_limit.limit))
+            .collect();
+        QueryRateLimiter {
+            default_limit: config.default_limit,
+            overrides,
+            internal_limit: config.internal_limit,
+            buckets: Mutex::new(LruCache::new(
+                NonZeroUsize::try_from(MAX_BUCKETS).expect("The number of buckets is not zero"),
+            )),
+        }
+    }
+
+    /// Returns the rate limit of the queries to an application, if any.
+    pub fn limit(
+        &self,
+        application_id: UserApplicationId,
+        origin: QueryOrigin,
+    ) -> Option<RateLimit> {
+        match origin {
+            QueryOrigin::Direct => self
+                .overrides
+                .get(&application_id)
+                .copied()
+                .or(self.default_limit),
+            QueryOrigin::Internal => self.internal_limit,
+        }
+    }
+
+    /// Returns whether the queries of the contracts to the services are limited.
+    pub fn limit_internal_queries(&self) -> bool {
+        self.internal_limit.is_some()
+    }
+
+    /// Counts a query to an application, or rejects it if its budget is exhausted.
+    ///
+    /// Callers check first that the application exists on the chain, so that the buckets are
+    /// not taken by queries to made-up applications.
+    pub fn check(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        origin: QueryOrigin,
+    ) -> Result<(), QueryThrottled> {
+        self.check_at(chain_id, application_id, origin, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        origin: QueryOrigin,
+        now: Instant,
+    ) -> Result<(), QueryThrottled> {
+        let Some(limit) = self.limit(application_id, origin) else {
+            return Ok(());
+        };
+        let result = self
+            .buckets
+            .lock()
+            .unwrap()
+            .get_or_insert_mut((chain_id, application_id, origin), || {
+                TokenBucket::new(limit, now)
+            })
+            .try_take(now);
+        result.map_err(|retry_after| {
+            #[cfg(with_metrics)]
+            THROTTLED_QUERIES
+                .with_label_values(&[&origin.to_string()])
+                .inc();
+            QueryThrottled {
+                chain_id,
+                application_id,
+                origin,
+                retry_after,
+            }
+        })
+    }
+}
+
+impl ServiceQueryFilter for QueryRateLimiter {
+    fn check(&self, chain_id: ChainId, application_id: UserApplicationId) -> Result<(), String> {
+        QueryRateLimiter::check(self, chain_id, application_id, QueryOrigin::Internal)
+            .map_err(|throttled| throttled.to_string())
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reloading the configuration of the execution runtime of a validator while it is running.
+//!
+//! The configuration is read from a TOML file, e.g.:
+//!
+//! ```toml
+//! audit_guest_inputs = false
+//! track_view_accesses = false
+//! ```
+//!
+//! Missing fields take their default values. A new version of the file only replaces the
+//! configuration once it is validated, and the operations, messages and queries being
+//! executed finish with the configuration they started with.
+//!
+//! Only `audit_guest_inputs` and `track_view_accesses` can be reloaded. The other limits are
+//! deliberately not part of this file:
+//! - The fees and limits of the resource control policy, including the maximum depth of nested
+//!   calls, affect the outcome of blocks, so they belong to the committee and change through a
+//!   new epoch on the admin chain, without a restart either.
+//! - The Wasm runtimes have no limits configurable by the validator: they keep the defaults of
+//!   Wasmer and Wasmtime, and the fuel is metered by the resource control policy.
+//! - The request limits of the proxy are read from the server configuration when the proxy
+//!   starts. The proxy can be restarted to change them without interrupting the shards.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use linera_execution::ExecutionRuntimeConfig;
+use linera_storage::Storage;
+use serde::Deserialize;
+use thiserror::Error as ThisError;
+use tracing::{info, warn};
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::register_int_gauge_vec, prometheus::IntGaugeVec,
+    std::sync::LazyLock,
+};
+
+#[cfg(test)]
+#[path = "unit_tests/runtime_config.rs"]
+mod tests;
+
+/// How often the configuration file is checked for changes by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[cfg(with_metrics)]
+static EXECUTION_RUNTIME_CONFIG_VERSION: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec(
+        "execution_runtime_config_version",
+        "The version of the configuration of the execution runtime in effect",
+        &[],
+    )
+});
+
+#[derive(Debug, ThisError)]
+pub enum RuntimeConfigError {
+    #[error("failed to read the runtime configuration in {path:?}: {error}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    #[error("invalid runtime configuration: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error(
+        "the maximum call depth is now set by the committee's resource control policy, \
+        e.g. with `linera resource-control-policy --maximum-call-depth`"
+    )]
+    MaxCallDepthIsInPolicy,
+}
+
+/// The fields of an [`ExecutionRuntimeConfig`] that can be set in a configuration file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfigFile {
+    pub audit_guest_inputs: bool,
+    pub track_view_accesses: bool,
+}
+
+impl RuntimeConfigFile {
+    /// Parses and validates the contents of a configuration file.
+    pub fn parse(contents: &str) -> Result<Self, RuntimeConfigError> {
+        let table: toml::Table = toml::from_str(contents)?;
+        if table.contains_key("max_call_depth") {
+            return Err(RuntimeConfigError::MaxCallDepthIsInPolicy);
+        }
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// Applies this configuration to `config`, keeping the fields that cannot be set in a file.
+    fn apply_to(&self, config: ExecutionRuntimeConfig) -> ExecutionRuntimeConfig {
+        ExecutionRuntimeConfig {
+            audit_guest_inputs: self.audit_guest_inputs,
+            track_view_accesses: self.track_view_accesses,
+            ..config
+        }
+    }
+}
+
+/// Replaces the [`execution_runtime_config`][`Storage::execution_runtime_config`] of a
+/// storage whenever its configuration file changes.
+pub struct RuntimeConfigWatcher<S> {
+    storage: S,
+    path: PathBuf,
+    poll_interval: Duration,
+    /// The modification time and the size of the file when it was last read.
+    file_version: Option<(SystemTime, u64)>,
+    /// The configuration last read from the file.
+    loaded: Option<RuntimeConfigFile>,
+}
+
+impl<S: Storage> RuntimeConfigWatcher<S> {
+    /// Creates a watcher for the configuration file at `path`. Nothing is loaded until
+    /// [`reload`][`Self::reload`] or [`run`][`Self::run`] is called.
+    pub fn new(storage: S, path: impl Into<PathBuf>) -> Self {
+        RuntimeConfigWatcher {
+            storage,
+            path: path.into(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            file_version: None,
+            loaded: None,
+        }
+    }
+
+    /// Sets how often the file is checked for changes.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Reads the file if it changed since it was last read, and replaces the configuration
+    /// if it is valid and different. Returns the version of the new configuration, if any.
+    ///
+    /// An invalid file leaves the configuration as it was.
+    pub async fn reload(&mut self) -> Result<Option<u64>, RuntimeConfigError> {
+        let io_error = |error| RuntimeConfigError::Io {
+            path: self.path.clone(),
+            error,
+        };
+        let metadata = tokio::fs::metadata(&self.path).await.map_err(io_error)?;
+        let file_version = Some((metadata.modified().map_err(io_error)?, metadata.len()));
+        if self.loaded.is_some() && file_version == self.file_version {
+            return Ok(None);
+        }
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(io_error)?;
+        self.file_version = file_version;
+        let config = RuntimeConfigFile::parse(&contents)?;
+        if self.loaded.as_ref() == Some(&config) {
+            return Ok(None);
+        }
+
+        let shared_config = self.storage.execution_runtime_config();
+        let version = shared_config.set(config.apply_to(shared_config.get()));
+        #[cfg(with_metrics)]
+        EXECUTION_RUNTIME_CONFIG_VERSION
+            .with_label_values(&[])
+            .set(version as i64);
+        info!(
+            version,
+            path = ?self.path,
+            "Loaded the configuration of the execution runtime: {config:?}"
+        );
+        self.loaded = Some(config);
+        Ok(Some(version))
+    }
+
+    /// Checks the file for changes every poll interval, forever. Invalid versions of the file
+    /// are logged, and the previous configuration stays in effect.
+    pub async fn run(mut self) {
+        loop {
+            tokio::time::sleep(self.poll_interval).await;
+            if let Err(error) = self.reload().await {
+                warn!("Failed to reload the configuration of the execution runtime: {error}");
+            }
+        }
+    }
+}
@@ -16,6 +16,7 @@ use linera_chain::{
 };
 use linera_client::{
     chain_listener::{ChainListenerConfig, ClientContext},
+    client_options::{QueryRateLimitConfig, ReadinessConfig},
     wallet::Wallet,
     Error,
 };
@@ -69,6 +70,14 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn handle_confirmed_certificates(
+        &self,
+        _: Vec<GenericCertificate<ConfirmedBlock>>,
+        _delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn handle_validated_certificate(
         &self,
         _: GenericCertificate<ValidatedBlock>,
@@ -218,6 +227,8 @@ async fn main() -> std::io::Result<()> {
     };
     let service = NodeService::new(
         config,
+        ReadinessConfig::default(),
+        QueryRateLimitConfig::default(),
         std::num::NonZeroU16::new(8080).unwrap(),
         None,
         storage,
@@ -6,46 +6,70 @@
 
 use std::{
     borrow::Cow,
-    num::{NonZeroU16, NonZeroUsize},
+    num::{NonZeroU16, NonZeroU64, NonZeroUsize},
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{bail, Context};
+use anyhow::{bail, ensure, Context};
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
 use linera_base::crypto::{CryptoRng, KeyPair};
 use linera_client::{
+    any_store::AnyStore,
+    client_options::ReadinessConfig,
     config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
     persistent::{self, Persist},
-    storage::{full_initialize_storage, run_with_storage, Runnable, StorageConfigNamespace},
+    storage::{
+        connect_storage, full_initialize_storage, reencrypt_storage, Runnable,
+        StorageConfigNamespace,
+    },
+};
+use linera_core::{
+    chain_worker::{StateSnapshotOptions, DEFAULT_RETAINED_STATE_SNAPSHOTS},
+    worker::WorkerState,
+    JoinSetExt as _,
 };
-use linera_core::{worker::WorkerState, JoinSetExt as _};
 use linera_execution::{committee::ValidatorName, WasmRuntime, WithWasmDefault};
 use linera_rpc::{
     config::{
-        CrossChainConfig, NetworkProtocol, NotificationConfig, ShardConfig, ShardId, TlsConfig,
-        ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        CrossChainConfig, NetworkProtocol, NotificationConfig, RequestLimitsConfig, ShardConfig,
+        ShardId, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
     },
     grpc, simple,
 };
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
-use linera_service::util;
-use linera_storage::Storage;
-use linera_views::store::CommonStoreConfig;
+use linera_service::{
+    health::{self, HealthChecker, ValidatorProbe},
+    runtime_config::RuntimeConfigWatcher,
+    util,
+};
+use linera_storage::{DbStorage, Storage, WallClock};
+use linera_views::{
+    encryption::{EncryptionConfig, ReEncryptionProgress},
+    store::CommonStoreConfig,
+};
 use serde::Deserialize;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 struct ServerContext {
     server_config: ValidatorServerConfig,
     cross_chain_config: CrossChainConfig,
     notification_config: NotificationConfig,
-    shard: Option<usize>,
+    shards: Vec<ShardId>,
     grace_period: Duration,
     max_loaded_chains: NonZeroUsize,
+    shutdown_timeout: Duration,
+    serve_state_hash_breakdowns: bool,
+    state_snapshots: StateSnapshotOptions,
+    export_chain_statistics: bool,
+    max_concurrent_chain_workers: Option<NonZeroUsize>,
+    execution_runtime_config_path: Option<PathBuf>,
+    health_port: Option<u16>,
+    readiness: ReadinessConfig,
 }
 
 impl ServerContext {
@@ -58,7 +82,8 @@ impl ServerContext {
     where
         S: Storage + Clone + Send + Sync + 'static,
     {
-        let shard = self.server_config.internal_network.shard(shard_id);
+        let internal_network = self.server_config.internal_network.clone();
+        let shard = internal_network.shard(shard_id).clone();
         info!("Shard booted on {}", shard.host);
         let state = WorkerState::new(
             format!("Shard {} @ {}:{}", shard_id, local_ip_addr, shard.port),
@@ -68,8 +93,13 @@ impl ServerContext {
         )
         .with_allow_inactive_chains(false)
         .with_allow_messages_from_deprecated_epochs(false)
-        .with_grace_period(self.grace_period);
-        (state, shard_id, shard.clone())
+        .with_grace_period(self.grace_period)
+        .with_serve_state_hash_breakdowns(self.serve_state_hash_breakdowns)
+        .with_state_snapshots(self.state_snapshots)
+        .with_export_chain_statistics(self.export_chain_statistics)
+        .with_max_concurrent_chain_workers(self.max_concurrent_chain_workers)
+        .with_served_chains(move |chain_id| internal_network.get_shard_id(chain_id) == shard_id);
+        (state, shard_id, shard)
     }
 
     fn spawn_simple<S>(
@@ -194,20 +224,56 @@ impl Runnable for ServerContext {
 
         tokio::spawn(util::listen_for_shutdown_signals(shutdown_notifier.clone()));
 
+        if let Some(path) = &self.execution_runtime_config_path {
+            let mut watcher = RuntimeConfigWatcher::new(storage.clone(), path);
+            watcher.reload().await?;
+            tokio::spawn(watcher.run());
+        }
+
         // Run the server
-        let states = match self.shard {
-            Some(shard) => {
-                info!("Running shard number {}", shard);
-                vec![self.make_shard_state(&listen_address, shard, storage)]
-            }
-            None => {
-                info!("Running all shards");
-                let num_shards = self.server_config.internal_network.shards.len();
-                (0..num_shards)
-                    .map(|shard| self.make_shard_state(&listen_address, shard, storage.clone()))
-                    .collect()
+        let num_shards = self.server_config.internal_network.shards.len();
+        let shards = if self.shards.is_empty() {
+            info!("Running all shards");
+            (0..num_shards).collect()
+        } else {
+            info!("Running shards {:?}", self.shards);
+            for &shard in &self.shards {
+                ensure!(
+                    shard < num_shards,
+                    "Shard {shard} does not exist: the validator has {num_shards} shards"
+                );
             }
+            self.shards.clone()
         };
+        let states = shards
+            .into_iter()
+            .map(|shard| self.make_shard_state(&listen_address, shard, storage.clone()))
+            .collect::<Vec<_>>();
+
+        for (state, _, _) in &states {
+            if let Err(error) = state.check_previous_shutdown().await {
+                error!(
+                    "Failed to check the previous shutdown of {}: {error}",
+                    state.nickname()
+                );
+            }
+        }
+        let workers = states
+            .iter()
+            .map(|(state, _, _)| state.clone())
+            .collect::<Vec<_>>();
+
+        if let Some(port) = self.health_port {
+            let checker = HealthChecker::new(
+                ValidatorProbe::new(storage, workers.clone()),
+                self.readiness.clone(),
+            );
+            health::start_health(
+                (listen_address.clone(), port),
+                checker,
+                shutdown_notifier.clone(),
+            );
+        }
 
         let mut join_set = match self.server_config.internal_network.protocol {
             NetworkProtocol::Simple(protocol) => {
@@ -221,6 +287,12 @@ impl Runnable for ServerContext {
 
         join_set.await_all_tasks().await;
 
+        for worker in workers {
+            if let Err(error) = worker.shutdown(self.shutdown_timeout).await {
+                error!("Failed to shut down {} cleanly: {error}", worker.nickname());
+            }
+        }
+
         Ok(())
     }
 }
@@ -272,6 +344,10 @@ struct ValidatorOptions {
 
     /// The public name and the port of each of the shards
     shards: Vec<ShardConfig>,
+
+    /// The limits on the requests accepted by the proxy.
+    #[serde(default)]
+    request_limits: RequestLimitsConfig,
 }
 
 fn make_server_config<R: CryptoRng>(
@@ -302,6 +378,7 @@ fn make_server_config<R: CryptoRng>(
             validator,
             key,
             internal_network,
+            request_limits: options.request_limits,
         },
     )?)
 }
@@ -319,6 +396,18 @@ enum ServerCommand {
         #[arg(long = "storage")]
         storage_config: StorageConfigNamespace,
 
+        /// Path to a JSON file with the keys encrypting the values of the storage. The values
+        /// are not encrypted by default.
+        #[arg(long = "storage-encryption-config")]
+        storage_encryption_config_path: Option<PathBuf>,
+
+        /// Rewrite in the background the values of the storage that are encrypted with a
+        /// retired key, saving the progress in the given JSON file so that it resumes after a
+        /// restart. The writes of other processes are not excluded: they must be stopped, or
+        /// be shards run by this process.
+        #[arg(long = "reencrypt-storage")]
+        reencryption_progress_path: Option<PathBuf>,
+
         /// Configuration for cross-chain requests
         #[command(flatten)]
         cross_chain_config: CrossChainConfig,
@@ -331,9 +420,10 @@ enum ServerCommand {
         #[arg(long = "genesis")]
         genesis_config_path: PathBuf,
 
-        /// Runs a specific shard (from 0 to shards-1)
-        #[arg(long)]
-        shard: Option<usize>,
+        /// Runs a specific shard (from 0 to shards-1). Can be repeated to run a set of shards
+        /// in this process. All the shards are run if none is specified.
+        #[arg(long = "shard")]
+        shards: Vec<ShardId>,
 
         /// Blocks with a timestamp this far in the future will still be accepted, but the validator
         /// will wait until that timestamp before voting.
@@ -348,6 +438,52 @@ enum ServerCommand {
         #[arg(long, default_value = "400")]
         max_loaded_chains: NonZeroUsize,
 
+        /// How long to wait for the requests in flight to complete when shutting down.
+        #[arg(long = "shutdown-timeout-ms", default_value = "10000", value_parser = util::parse_millis)]
+        shutdown_timeout: Duration,
+
+        /// Answer queries for the hashes of the parts of the execution state, to help diagnose
+        /// state hash mismatches between validators.
+        #[arg(long)]
+        serve_state_hash_breakdowns: bool,
+
+        /// Keep a snapshot of the state of each chain every this many blocks, so that
+        /// applications can be queried at past heights. By default, only the current state is
+        /// kept.
+        #[arg(long)]
+        state_snapshot_interval: Option<NonZeroU64>,
+
+        /// How many snapshots to keep for each chain. Queries at heights before the oldest one
+        /// fail.
+        #[arg(long, default_value_t = DEFAULT_RETAINED_STATE_SNAPSHOTS)]
+        retained_state_snapshots: usize,
+
+        /// Export the execution statistics of each chain as Prometheus gauges, labelled by
+        /// chain ID. This adds a set of gauges per chain.
+        #[arg(long)]
+        export_chain_statistics: bool,
+
+        /// The maximal number of chains handling a request at the same time. The chains take
+        /// turns, so that a busy chain cannot delay the others. No limit by default.
+        #[arg(long)]
+        max_concurrent_chain_workers: Option<NonZeroUsize>,
+
+        /// Path to a TOML file with the configuration of the execution runtime, i.e. whether
+        /// to audit the inputs of the applications and to track their view accesses. The file is
+        /// loaded again whenever it changes, without interrupting the operations being executed.
+        /// The resource control policy and the request limits of the proxy are not set there.
+        #[arg(long = "execution-runtime-config")]
+        execution_runtime_config_path: Option<PathBuf>,
+
+        /// The port on which to serve the `/health` and `/ready` endpoints of the shards run
+        /// by this process. Not served by default.
+        #[arg(long)]
+        health_port: Option<u16>,
+
+        /// Thresholds of the readiness checks
+        #[command(flatten)]
+        readiness: ReadinessConfig,
+
         /// The maximal number of simultaneous queries to the database
         #[arg(long)]
         max_concurrent_queries: Option<usize>,
@@ -385,6 +521,11 @@ enum ServerCommand {
         #[arg(long = "storage")]
         storage_config: StorageConfigNamespace,
 
+        /// Path to a JSON file with the keys encrypting the values of the storage. The values
+        /// are not encrypted by default.
+        #[arg(long = "storage-encryption-config")]
+        storage_encryption_config_path: Option<PathBuf>,
+
         /// Path to the file describing the initial user chains (aka genesis state)
         #[arg(long = "genesis")]
         genesis_config_path: PathBuf,
@@ -465,7 +606,7 @@ fn main() {
 fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
     match command {
         ServerCommand::Run {
-            shard,
+            shards,
             server_config_path,
             ..
         } => {
@@ -473,10 +614,11 @@ fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
                 util::read_json(server_config_path).expect("Failed to read server config");
             let name = &server_config.validator.name;
 
-            if let Some(shard) = shard {
-                format!("validator-{name}-shard-{shard}")
-            } else {
+            if shards.is_empty() {
                 format!("validator-{name}")
+            } else {
+                let shards = shards.iter().map(ShardId::to_string).collect::<Vec<_>>();
+                format!("validator-{name}-shard-{}", shards.join("-"))
             }
             .into()
         }
@@ -486,18 +628,69 @@ fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
     }
 }
 
+/// Reads the keys encrypting the values of the storage.
+fn read_storage_encryption_config(path: &Path) -> EncryptionConfig {
+    util::read_json(path).expect("Failed to read the storage encryption config")
+}
+
+/// Rewrites under the current key the values of the storage that are encrypted with a retired
+/// key, resuming from the progress saved in `progress_path` and saving it there after each
+/// chunk of values.
+async fn reencrypt_storage_in_background(
+    storage: DbStorage<AnyStore, WallClock>,
+    progress_path: PathBuf,
+) {
+    let mut progress = if progress_path.exists() {
+        match util::read_json::<ReEncryptionProgress>(&progress_path) {
+            Ok(progress) => progress,
+            Err(error) => {
+                error!(%error, "Failed to read the progress of the storage re-encryption");
+                return;
+            }
+        }
+    } else {
+        ReEncryptionProgress::default()
+    };
+    let checkpoint = |progress: &ReEncryptionProgress| {
+        let result = serde_json::to_vec(progress)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(fs_err::write(&progress_path, bytes)?));
+        if let Err(error) = result {
+            warn!(%error, "Failed to save the progress of the storage re-encryption");
+        }
+    };
+    match reencrypt_storage(&storage, &mut progress, checkpoint).await {
+        Ok(()) => info!(
+            rewritten_values = progress.rewritten_values,
+            "Re-encrypted the storage under the current key"
+        ),
+        Err(error) => error!(%error, "Failed to re-encrypt the storage"),
+    }
+}
+
 async fn run(options: ServerOptions) {
     match options.command {
         ServerCommand::Run {
             server_config_path,
             storage_config,
+            storage_encryption_config_path,
+            reencryption_progress_path,
             cross_chain_config,
             notification_config,
             genesis_config_path,
-            shard,
+            shards,
             grace_period,
             wasm_runtime,
             max_loaded_chains,
+            shutdown_timeout,
+            serve_state_hash_breakdowns,
+            state_snapshot_interval,
+            retained_state_snapshots,
+            export_chain_statistics,
+            max_concurrent_chain_workers,
+            execution_runtime_config_path,
+            health_port,
+            readiness,
             max_concurrent_queries,
             max_stream_queries,
             cache_size,
@@ -520,9 +713,20 @@ async fn run(options: ServerOptions) {
                 server_config,
                 cross_chain_config,
                 notification_config,
-                shard,
+                shards,
                 grace_period,
                 max_loaded_chains,
+                shutdown_timeout,
+                serve_state_hash_breakdowns,
+                state_snapshots: StateSnapshotOptions {
+                    interval: state_snapshot_interval,
+                    retained: retained_state_snapshots,
+                },
+                export_chain_statistics,
+                max_concurrent_chain_workers,
+                execution_runtime_config_path,
+                health_port,
+                readiness,
             };
             let wasm_runtime = wasm_runtime.with_wasm_default();
             let common_config = CommonStoreConfig {
@@ -530,15 +734,23 @@ async fn run(options: ServerOptions) {
                 max_stream_queries,
                 cache_size,
             };
+            let encryption_config =
+                storage_encryption_config_path.map(|path| read_storage_encryption_config(&path));
             let full_storage_config = storage_config
                 .add_common_config(common_config)
                 .await
-                .unwrap();
-            run_with_storage(full_storage_config, &genesis_config, wasm_runtime, job)
-                .boxed()
-                .await
                 .unwrap()
+                .with_encryption(encryption_config);
+            let storage = connect_storage(full_storage_config, &genesis_config, wasm_runtime)
+                .await
                 .unwrap();
+            if let Some(progress_path) = reencryption_progress_path {
+                tokio::spawn(reencrypt_storage_in_background(
+                    storage.clone(),
+                    progress_path,
+                ));
+            }
+            job.run(storage).boxed().await.unwrap();
         }
 
         ServerCommand::Generate {
@@ -581,6 +793,7 @@ async fn run(options: ServerOptions) {
 
         ServerCommand::Initialize {
             storage_config,
+            storage_encryption_config_path,
             genesis_config_path,
             max_concurrent_queries,
             max_stream_queries,
@@ -593,10 +806,13 @@ async fn run(options: ServerOptions) {
                 max_stream_queries,
                 cache_size,
             };
+            let encryption_config =
+                storage_encryption_config_path.map(|path| read_storage_encryption_config(&path));
             let full_storage_config = storage_config
                 .add_common_config(common_config)
                 .await
-                .unwrap();
+                .unwrap()
+                .with_encryption(encryption_config);
             full_initialize_storage(full_storage_config, &genesis_config)
                 .await
                 .unwrap();
@@ -722,6 +938,36 @@ mod test {
                         metrics_port: Some(5002),
                     },
                 ],
+                request_limits: RequestLimitsConfig::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_validator_options_with_request_limits() {
+        let toml_str = r#"
+            server_config_path = "server.json"
+            host = "host"
+            port = 9000
+            internal_host = "internal_host"
+            internal_port = 10000
+            metrics_host = "metrics_host"
+            metrics_port = 5000
+            external_protocol = { Grpc = "ClearText" }
+            internal_protocol = { Grpc = "ClearText" }
+            shards = []
+
+            [request_limits]
+            max_requests_per_second_per_connection = 100
+            max_requests_per_second_per_ip = 1000
+        "#;
+        let options: ValidatorOptions = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            options.request_limits,
+            RequestLimitsConfig {
+                max_requests_per_second_per_connection: Some(100),
+                max_requests_per_second_per_ip: Some(1000),
+                max_request_size: None,
             }
         );
     }
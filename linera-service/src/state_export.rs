@@ -0,0 +1,283 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bulk export of the entries of an application's sub-views, as JSON lines or CSV.
+//!
+//! Applications built with `linera-views` lay out their state in a predictable way, so the
+//! entries of a sub-view can be read directly from the key-value store of the application,
+//! without knowing its types:
+//! * the field with index `n` of a `RootView` lives under the key prefix
+//!   `[MIN_VIEW_TAG] ++ bcs(n)`, where `n` is serialized as a 32-bit integer;
+//! * a `MapView` stores each value under the serialized key of its entry;
+//! * a `LogView` stores each value under `[MIN_VIEW_TAG + 1] ++ bcs(index)`.
+//!
+//! Keys and values are exported as hexadecimal strings of their BCS serialization.
+//!
+//! Sub-views are selected by name: the index of a field is its position among the fields of
+//! the application's GraphQL query root. This requires the service to use its state as the
+//! query root, exposing all of its fields in order, e.g. with `#[derive(SimpleObject)]`.
+
+use linera_views::views::MIN_VIEW_TAG;
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+#[cfg(test)]
+#[path = "unit_tests/state_export.rs"]
+mod tests;
+
+/// The number of entries read from storage at once while streaming an export.
+pub const EXPORT_CHUNK_SIZE: usize = 1_000;
+
+/// The GraphQL query listing the fields of an application's query root, with their types.
+pub const SUB_VIEWS_QUERY: &str =
+    "query { __schema { queryType { fields { name type { name ofType { name } } } } } }";
+
+/// The tag of the entries of a `LogView`, following its tag for the entry count.
+const LOG_INDEX_TAG: u8 = MIN_VIEW_TAG + 1;
+
+/// The header line of the CSV format.
+const CSV_HEADER: &str = "key,value\n";
+
+/// The kinds of sub-views whose entries can be exported.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubViewKind {
+    /// A `MapView`, exported with the serialized keys of its entries.
+    Map,
+    /// A `LogView`, exported with the serialized indices of its entries.
+    Log,
+}
+
+impl SubViewKind {
+    /// Returns the kind of the sub-view with the given GraphQL type name, if it is supported.
+    fn from_type_name(type_name: &str) -> Option<Self> {
+        if ["MapView_", "CustomMapView_", "ByteMapView_"]
+            .iter()
+            .any(|prefix| type_name.starts_with(prefix))
+        {
+            Some(SubViewKind::Map)
+        } else if type_name.starts_with("LogView_") {
+            Some(SubViewKind::Log)
+        } else {
+            None
+        }
+    }
+}
+
+/// The formats in which entries can be exported.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object with a `key` and a `value` per line.
+    #[default]
+    #[serde(rename = "jsonl")]
+    JsonLines,
+    /// A `key,value` header, then one line per entry.
+    Csv,
+}
+
+impl ExportFormat {
+    /// Returns the MIME type of an export in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv",
+        }
+    }
+
+    /// Returns the text preceding the entries of an export in this format.
+    pub fn header(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "",
+            ExportFormat::Csv => CSV_HEADER,
+        }
+    }
+}
+
+/// An error when reading back an export.
+#[derive(Debug, ThisError)]
+pub enum StateExportError {
+    #[error("invalid hexadecimal string: {0}")]
+    InvalidHex(#[from] hex::FromHexError),
+    #[error("invalid JSON line: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("the CSV header is missing")]
+    MissingCsvHeader,
+    #[error("invalid CSV line: {0:?}")]
+    InvalidCsvLine(String),
+    #[error("the application's GraphQL schema doesn't list the fields of its query root")]
+    InvalidSchema,
+    #[error("the application's state has no sub-view named {0:?}")]
+    UnknownSubView(String),
+    #[error("the sub-view {name:?} of type {type_name} can't be exported")]
+    UnsupportedSubView { name: String, type_name: String },
+}
+
+/// One exported entry, as written in the JSON lines format.
+#[derive(Debug, Deserialize, Serialize)]
+struct ExportedEntry {
+    key: String,
+    value: String,
+}
+
+/// The response to [`SUB_VIEWS_QUERY`].
+#[derive(Debug, Deserialize)]
+struct SchemaResponse {
+    data: SchemaData,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaData {
+    #[serde(rename = "__schema")]
+    schema: Schema,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Schema {
+    query_type: QueryType,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryType {
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: FieldType,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldType {
+    name: Option<String>,
+    of_type: Option<Box<FieldType>>,
+}
+
+impl FieldType {
+    /// Returns the name of the type, looking through non-null wrappers.
+    fn name(&self) -> Option<&str> {
+        match (&self.name, &self.of_type) {
+            (Some(name), _) => Some(name),
+            (None, Some(of_type)) => of_type.name(),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Finds the index and kind of the sub-view with the given name, from the application's
+/// response to [`SUB_VIEWS_QUERY`].
+pub fn resolve_sub_view(
+    name: &str,
+    response: &[u8],
+) -> Result<(u32, SubViewKind), StateExportError> {
+    let response = serde_json::from_slice::<SchemaResponse>(response)
+        .map_err(|_| StateExportError::InvalidSchema)?;
+    let fields = response.data.schema.query_type.fields;
+    let (index, field) = fields
+        .iter()
+        .enumerate()
+        .find(|(_, field)| field.name == name)
+        .ok_or_else(|| StateExportError::UnknownSubView(name.to_owned()))?;
+    let type_name = field.field_type.name().unwrap_or_default();
+    let kind = SubViewKind::from_type_name(type_name).ok_or_else(|| {
+        StateExportError::UnsupportedSubView {
+            name: name.to_owned(),
+            type_name: type_name.to_owned(),
+        }
+    })?;
+    let index = u32::try_from(index).map_err(|_| StateExportError::InvalidSchema)?;
+    Ok((index, kind))
+}
+
+/// Returns the key prefix of the entries of the sub-view stored in the given field of an
+/// application's `RootView`.
+pub fn sub_view_prefix(field: u32, kind: SubViewKind) -> Vec<u8> {
+    let mut prefix = vec![MIN_VIEW_TAG];
+    prefix.extend(field.to_le_bytes());
+    if kind == SubViewKind::Log {
+        prefix.push(LOG_INDEX_TAG);
+    }
+    prefix
+}
+
+/// Selects the keys of a page of an export, from all the keys of a sub-view in order: the
+/// keys after `after` if given, and at most `limit` of them.
+///
+/// Also returns the key to resume the export from, if the page doesn't reach the end.
+pub fn select_keys(
+    mut keys: Vec<Vec<u8>>,
+    after: Option<&[u8]>,
+    limit: Option<usize>,
+) -> (Vec<Vec<u8>>, Option<Vec<u8>>) {
+    if let Some(after) = after {
+        let start = keys.partition_point(|key| key.as_slice() <= after);
+        keys.drain(..start);
+    }
+    let limit = limit.unwrap_or(usize::MAX).max(1);
+    if keys.len() <= limit {
+        return (keys, None);
+    }
+    keys.truncate(limit);
+    let next_after = keys.last().cloned();
+    (keys, next_after)
+}
+
+/// Formats entries as lines of an export, without the header of the format.
+pub fn format_entries<'a>(
+    format: ExportFormat,
+    entries: impl IntoIterator<Item = (&'a [u8], &'a [u8])>,
+) -> String {
+    entries
+        .into_iter()
+        .map(|(key, value)| format_entry(format, key, value))
+        .collect()
+}
+
+/// Reads back the entries of an export.
+pub fn import_entries(
+    format: ExportFormat,
+    body: &str,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StateExportError> {
+    let mut lines = body.lines();
+    if format == ExportFormat::Csv && lines.next() != Some(CSV_HEADER.trim_end()) {
+        return Err(StateExportError::MissingCsvHeader);
+    }
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (key, value) = match format {
+                ExportFormat::JsonLines => {
+                    let entry = serde_json::from_str::<ExportedEntry>(line)?;
+                    (entry.key, entry.value)
+                }
+                ExportFormat::Csv => {
+                    let (key, value) = line
+                        .split_once(',')
+                        .ok_or_else(|| StateExportError::InvalidCsvLine(line.to_string()))?;
+                    (key.to_string(), value.to_string())
+                }
+            };
+            Ok((hex::decode(key)?, hex::decode(value)?))
+        })
+        .collect()
+}
+
+fn format_entry(format: ExportFormat, key: &[u8], value: &[u8]) -> String {
+    let entry = ExportedEntry {
+        key: hex::encode(key),
+        value: hex::encode(value),
+    };
+    match format {
+        ExportFormat::JsonLines => {
+            let mut line =
+                serde_json::to_string(&entry).expect("serializing strings should not fail");
+            line.push('\n');
+            line
+        }
+        ExportFormat::Csv => format!("{},{}\n", entry.key, entry.value),
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A local Linera network that applications can use in their end-to-end tests.
+
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use linera_base::data_types::Amount;
+use linera_execution::ResourceControlPolicy;
+use linera_storage_service::child::StorageServiceGuard;
+
+use crate::cli_wrappers::{
+    local_net::{
+        start_storage_service, Database, LocalNet, LocalNetConfig, PathProvider,
+        StorageConfigBuilder,
+    },
+    ClientWrapper, LineraNet, LineraNetConfig, Network, NetworkConfig,
+};
+
+/// Where a [`LocalNetwork`] stores the state of its validators.
+pub enum LocalNetworkStorage {
+    /// Keep the state in memory.
+    Memory,
+    /// Keep the state in a RocksDB database at the given path.
+    RocksDb { path: PathBuf },
+}
+
+/// A set of validators running locally, with a genesis configuration and a wallet owning the
+/// initial chains.
+///
+/// The validators run as child processes sharing a storage service. All the processes and
+/// the temporary files are cleaned up when the network is dropped. Since the validators
+/// listen on fixed ports, only one network can run at a time.
+pub struct LocalNetwork {
+    net: LocalNet,
+    client: ClientWrapper,
+    _storage_service: StorageServiceGuard,
+}
+
+impl LocalNetwork {
+    /// Starts a network of `num_validators` validators with `num_shards` shards each, keeping
+    /// their state in memory.
+    pub async fn new(num_validators: usize, num_shards: usize) -> Result<Self> {
+        Self::with_storage(num_validators, num_shards, LocalNetworkStorage::Memory).await
+    }
+
+    /// Starts a network of `num_validators` validators with `num_shards` shards each, using
+    /// the given `storage`.
+    pub async fn with_storage(
+        num_validators: usize,
+        num_shards: usize,
+        storage: LocalNetworkStorage,
+    ) -> Result<Self> {
+        ensure!(
+            num_validators > 0,
+            "The network must have at least one validator"
+        );
+        ensure!(num_shards > 0, "Validators must have at least one shard");
+
+        let rocks_db_path = match storage {
+            LocalNetworkStorage::Memory => None,
+            LocalNetworkStorage::RocksDb { path } => Some(path),
+        };
+        let (storage_config, storage_service) = start_storage_service(rocks_db_path).await?;
+        let config = LocalNetConfig {
+            database: Database::Service,
+            network: NetworkConfig {
+                internal: Network::Grpc,
+                external: Network::Grpc,
+            },
+            testing_prng_seed: Some(37),
+            namespace: linera_views::random::generate_test_namespace(),
+            num_other_initial_chains: 2,
+            initial_amount: Amount::from_tokens(1_000_000),
+            num_initial_validators: num_validators,
+            num_shards,
+            policy: ResourceControlPolicy::devnet(),
+            storage_config_builder: StorageConfigBuilder::ExistingConfig { storage_config },
+            path_provider: PathProvider::create_temporary_directory()?,
+        };
+        let (net, client) = config.instantiate().await?;
+        Ok(Self {
+            net,
+            client,
+            _storage_service: storage_service,
+        })
+    }
+
+    /// Returns the client whose wallet owns the initial chains.
+    pub fn client(&self) -> &ClientWrapper {
+        &self.client
+    }
+
+    /// Creates a client with a new, empty wallet.
+    pub async fn make_client(&mut self) -> ClientWrapper {
+        self.net.make_client().await
+    }
+
+    /// Returns the underlying network, e.g. to stop or restart validators.
+    pub fn net_mut(&mut self) -> &mut LocalNet {
+        &mut self.net
+    }
+
+    /// Returns an error if any process of the network has stopped.
+    pub async fn ensure_is_running(&mut self) -> Result<()> {
+        self.net.ensure_is_running().await
+    }
+
+    /// Stops all the validators, waiting for their processes to exit.
+    pub async fn terminate(mut self) -> Result<()> {
+        self.net.terminate().await
+    }
+}
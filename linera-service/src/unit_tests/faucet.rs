@@ -5,12 +5,13 @@
 
 use std::sync::Arc;
 
+use assert_matches::assert_matches;
 use async_trait::async_trait;
 use futures::lock::Mutex;
 use linera_base::{
     crypto::{KeyPair, PublicKey},
-    data_types::{Amount, Timestamp},
-    identifiers::ChainId,
+    data_types::{Amount, TimeDelta, Timestamp},
+    identifiers::{Account, ChainId},
 };
 use linera_client::{chain_listener, wallet::Wallet};
 use linera_core::{
@@ -20,7 +21,7 @@ use linera_core::{
 use linera_storage::{DbStorage, TestClock};
 use linera_views::memory::MemoryStore;
 
-use super::MutationRoot;
+use super::{ClaimError, ClaimRateLimiter, ClaimRecipient, ClaimRequest, MutationRoot};
 
 struct ClientContext {
     client: ChainClient<TestProvider, TestStorage>,
@@ -89,6 +90,7 @@ async fn test_faucet_rate_limiting() {
         end_timestamp: Timestamp::from(6000),
         start_timestamp: Timestamp::from(0),
         start_balance: Amount::from_tokens(6),
+        rate_limiter: Arc::new(Mutex::new(ClaimRateLimiter::new(TimeDelta::from_micros(0)))),
     };
     // The faucet is releasing one token every 1000 microseconds. So at 1000 one claim should
     // succeed. At 3000, two more should have been unlocked.
@@ -114,3 +116,63 @@ fn test_multiply() {
     assert_eq!(mul((1 << 127) + (1 << 63), 1 << 63), [1 << 62, 1 << 62, 0]);
     assert_eq!(mul(u128::MAX, u64::MAX), [u64::MAX - 1, u64::MAX, 1]);
 }
+
+#[tokio::test]
+async fn test_faucet_claim_endpoint_rate_limiting() {
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    clock.set(Timestamp::from(0));
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await.unwrap();
+    let client = builder
+        .add_root_chain(1, Amount::from_tokens(6))
+        .await
+        .unwrap();
+    builder.add_root_chain(2, Amount::ZERO).await.unwrap();
+    let chain_id = client.chain_id();
+    let context = ClientContext {
+        client,
+        update_calls: 0,
+    };
+    let root = MutationRoot {
+        chain_id,
+        context: Arc::new(Mutex::new(context)),
+        amount: Amount::from_tokens(1),
+        end_timestamp: Timestamp::from(0),
+        start_timestamp: Timestamp::from(0),
+        start_balance: Amount::from_tokens(6),
+        rate_limiter: Arc::new(Mutex::new(ClaimRateLimiter::new(TimeDelta::from_micros(
+            1000,
+        )))),
+    };
+    let claim = |recipient, address: [u8; 4]| {
+        root.do_rate_limited_claim(ClaimRequest { recipient }, Some(address.into()))
+    };
+    let owner = ClaimRecipient::Owner(PublicKey::test_key(1).into());
+    let account = ClaimRecipient::Account(Account::chain(ChainId::root(2)));
+
+    // The first claim creates a new chain.
+    clock.set(Timestamp::from(100));
+    let response = claim(owner, [10, 0, 0, 1]).await.unwrap();
+    assert!(response.message_id.is_some());
+    assert_ne!(response.chain_id, chain_id);
+
+    // The same recipient can't claim again from another address, and no other recipient can
+    // claim from the same address.
+    assert_matches!(
+        claim(owner, [10, 0, 0, 2]).await,
+        Err(ClaimError::RateLimited { retry_at }) if retry_at == Timestamp::from(1100)
+    );
+    assert_matches!(
+        claim(account, [10, 0, 0, 1]).await,
+        Err(ClaimError::RateLimited { .. })
+    );
+
+    // Other recipients and addresses are not affected: the tokens are transferred to the account.
+    let response = claim(account, [10, 0, 0, 2]).await.unwrap();
+    assert_eq!(response.message_id, None);
+    assert_eq!(response.chain_id, ChainId::root(2));
+
+    // After the interval, the first recipient can claim again.
+    clock.set(Timestamp::from(1100));
+    assert!(claim(owner, [10, 0, 0, 1]).await.is_ok());
+}
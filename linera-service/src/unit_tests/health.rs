@@ -0,0 +1,167 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode};
+use linera_base::time::Duration;
+use linera_client::client_options::ReadinessConfig;
+
+use super::{CheckStatus, HealthChecker, ReadinessProbe};
+
+/// A probe reporting whatever the test sets.
+#[derive(Clone, Default)]
+struct StubProbe {
+    state: Arc<StubState>,
+}
+
+#[derive(Default)]
+struct StubState {
+    storage_failing: AtomicBool,
+    queue_depth: AtomicUsize,
+    certificate_age_secs: AtomicU64,
+    blocks_behind: AtomicU64,
+    blocks_behind_calls: AtomicUsize,
+}
+
+#[async_trait]
+impl ReadinessProbe for StubProbe {
+    async fn ping_storage(&self) -> Result<(), String> {
+        if self.state.storage_failing.load(Ordering::SeqCst) {
+            Err("storage unreachable".to_owned())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn has_wasm_runtime(&self) -> bool {
+        true
+    }
+
+    async fn queue_depth(&self) -> Option<usize> {
+        Some(self.state.queue_depth.load(Ordering::SeqCst))
+    }
+
+    fn last_certificate_age(&self) -> Option<Duration> {
+        let age = self.state.certificate_age_secs.load(Ordering::SeqCst);
+        Some(Duration::from_secs(age))
+    }
+
+    async fn blocks_behind(&self) -> Result<Option<u64>, String> {
+        self.state
+            .blocks_behind_calls
+            .fetch_add(1, Ordering::SeqCst);
+        Ok(Some(self.state.blocks_behind.load(Ordering::SeqCst)))
+    }
+}
+
+fn config() -> ReadinessConfig {
+    ReadinessConfig {
+        max_queue_depth: 10,
+        max_blocks_behind: 5,
+        max_certificate_age: Some(Duration::from_secs(60)),
+        sync_check_interval: Duration::ZERO,
+    }
+}
+
+/// Tests that the service stops being ready when its storage cannot be reached.
+#[tokio::test]
+async fn test_readiness_flips_when_storage_fails() {
+    let probe = StubProbe::default();
+    let checker = Arc::new(HealthChecker::new(probe.clone(), config()));
+
+    let (status, report) = HealthChecker::ready_handler(State(checker.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(report.ready);
+    assert!(report
+        .checks
+        .values()
+        .all(|check| check.status == CheckStatus::Ok));
+
+    probe.state.storage_failing.store(true, Ordering::SeqCst);
+
+    let (status, report) = HealthChecker::ready_handler(State(checker.clone())).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!report.ready);
+    let storage = &report.checks["storage"];
+    assert_eq!(storage.status, CheckStatus::Failing);
+    assert_eq!(storage.detail.as_deref(), Some("storage unreachable"));
+    assert_eq!(report.checks["queue_depth"].status, CheckStatus::Ok);
+
+    probe.state.storage_failing.store(false, Ordering::SeqCst);
+
+    let (status, _) = HealthChecker::ready_handler(State(checker)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Tests the thresholds of the queue depth, certificate age and synchronization checks.
+#[tokio::test]
+async fn test_readiness_thresholds() {
+    let probe = StubProbe::default();
+    let checker = HealthChecker::new(probe.clone(), config());
+
+    probe.state.queue_depth.store(11, Ordering::SeqCst);
+    let report = checker.readiness().await;
+    assert!(!report.ready);
+    assert_eq!(report.checks["queue_depth"].status, CheckStatus::Failing);
+    probe.state.queue_depth.store(10, Ordering::SeqCst);
+    assert!(checker.readiness().await.ready);
+
+    probe.state.certificate_age_secs.store(61, Ordering::SeqCst);
+    let report = checker.readiness().await;
+    assert!(!report.ready);
+    assert_eq!(
+        report.checks["certificate_age"].status,
+        CheckStatus::Failing
+    );
+    probe.state.certificate_age_secs.store(0, Ordering::SeqCst);
+
+    probe.state.blocks_behind.store(6, Ordering::SeqCst);
+    let report = checker.readiness().await;
+    assert!(!report.ready);
+    assert_eq!(report.checks["blocks_behind"].status, CheckStatus::Failing);
+    probe.state.blocks_behind.store(5, Ordering::SeqCst);
+    assert!(checker.readiness().await.ready);
+}
+
+/// Tests that the certificate age is not checked unless a maximum is configured.
+#[tokio::test]
+async fn test_certificate_age_skipped_by_default() {
+    let probe = StubProbe::default();
+    let config = ReadinessConfig {
+        max_certificate_age: None,
+        ..config()
+    };
+    let checker = HealthChecker::new(probe.clone(), config);
+
+    probe
+        .state
+        .certificate_age_secs
+        .store(u64::MAX / 2, Ordering::SeqCst);
+    let report = checker.readiness().await;
+    assert!(report.ready);
+    assert_eq!(
+        report.checks["certificate_age"].status,
+        CheckStatus::Skipped
+    );
+}
+
+/// Tests that the validators are not asked again within the sync check interval.
+#[tokio::test]
+async fn test_blocks_behind_cached_for_sync_check_interval() {
+    let probe = StubProbe::default();
+    let config = ReadinessConfig {
+        sync_check_interval: Duration::from_secs(3600),
+        ..config()
+    };
+    let checker = HealthChecker::new(probe.clone(), config);
+
+    assert!(checker.readiness().await.ready);
+    probe.state.blocks_behind.store(100, Ordering::SeqCst);
+    assert!(checker.readiness().await.ready);
+    assert_eq!(probe.state.blocks_behind_calls.load(Ordering::SeqCst), 1);
+}
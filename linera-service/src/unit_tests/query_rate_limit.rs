@@ -0,0 +1,311 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use linera_base::{
+    crypto::CryptoHash,
+    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId, UserApplicationId},
+    time::{Duration, Instant},
+};
+use linera_client::client_options::{ApplicationRateLimit, QueryRateLimitConfig, RateLimit};
+use linera_execution::ServiceQueryFilter;
+
+use super::{QueryOrigin, QueryRateLimiter, QueryThrottled};
+
+fn application_id(index: u64) -> UserApplicationId {
+    ApplicationId {
+        bytecode_id: BytecodeId::new(
+            CryptoHash::test_hash("contract"),
+            CryptoHash::test_hash("service"),
+        ),
+        creation: MessageId {
+            chain_id: ChainId::root(0),
+            height: index.into(),
+            index: 0,
+        },
+    }
+}
+
+/// A limit that doesn't refill during a test.
+fn burst_of(burst: u32) -> RateLimit {
+    RateLimit {
+        per_second: 0.001,
+        burst,
+    }
+}
+
+/// Sends `count` concurrent queries to an application and returns how many were accepted
+/// and how many were throttled.
+async fn send_concurrent_queries(
+    limiter: &Arc<QueryRateLimiter>,
+    chain_id: ChainId,
+    application_id: UserApplicationId,
+    origin: QueryOrigin,
+    count: usize,
+) -> (usize, usize) {
+    let tasks = (0..count)
+        .map(|_| {
+            let limiter = Arc::clone(limiter);
+            tokio::spawn(async move { limiter.check(chain_id, application_id, origin) })
+        })
+        .collect::<Vec<_>>();
+    let mut accepted = 0;
+    let mut throttled = 0;
+    for task in tasks {
+        match task.await.unwrap() {
+            Ok(()) => accepted += 1,
+            Err(error) => {
+                assert_eq!(error.application_id, application_id);
+                assert_eq!(error.origin, origin);
+                throttled += 1;
+            }
+        }
+    }
+    (accepted, throttled)
+}
+
+/// Tests that the queries beyond the budget of an application are throttled, without
+/// affecting the other applications and chains.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_queries_are_throttled() {
+    let limiter = Arc::new(QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: Some(burst_of(3)),
+        ..QueryRateLimitConfig::default()
+    }));
+    let chain_id = ChainId::root(0);
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id(1),
+        QueryOrigin::Direct,
+        20,
+    )
+    .await;
+    assert_eq!(outcome, (3, 17));
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id(2),
+        QueryOrigin::Direct,
+        20,
+    )
+    .await;
+    assert_eq!(outcome, (3, 17));
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        ChainId::root(1),
+        application_id(1),
+        QueryOrigin::Direct,
+        20,
+    )
+    .await;
+    assert_eq!(outcome, (3, 17));
+}
+
+/// Tests that the queries passed through by the node service have their own budget.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_internal_queries_have_a_separate_budget() {
+    let limiter = Arc::new(QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: Some(burst_of(2)),
+        internal_limit: Some(burst_of(5)),
+        ..QueryRateLimitConfig::default()
+    }));
+    let chain_id = ChainId::root(0);
+    let application_id = application_id(1);
+
+    let outcome =
+        send_concurrent_queries(&limiter, chain_id, application_id, QueryOrigin::Direct, 10).await;
+    assert_eq!(outcome, (2, 8));
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id,
+        QueryOrigin::Internal,
+        10,
+    )
+    .await;
+    assert_eq!(outcome, (5, 5));
+}
+
+/// Tests that the queries of the contracts to the services count against the internal budget
+/// only, and are all allowed without an internal limit.
+#[test]
+fn test_service_queries_use_the_internal_budget() {
+    let limiter = QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: Some(burst_of(1)),
+        internal_limit: Some(burst_of(2)),
+        ..QueryRateLimitConfig::default()
+    });
+    let chain_id = ChainId::root(0);
+    let application_id = application_id(1);
+    assert!(limiter.limit_internal_queries());
+
+    for _ in 0..2 {
+        assert!(ServiceQueryFilter::check(&limiter, chain_id, application_id).is_ok());
+    }
+    let reason = ServiceQueryFilter::check(&limiter, chain_id, application_id).unwrap_err();
+    assert!(reason.contains("internal"), "{reason}");
+    assert!(limiter
+        .check(chain_id, application_id, QueryOrigin::Direct)
+        .is_ok());
+
+    let limiter = QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: Some(burst_of(1)),
+        ..QueryRateLimitConfig::default()
+    });
+    assert!(!limiter.limit_internal_queries());
+    for _ in 0..10 {
+        assert!(ServiceQueryFilter::check(&limiter, chain_id, application_id).is_ok());
+    }
+}
+
+/// Tests that the limit of an application can be overridden, and that applications are not
+/// limited without a default limit.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_overridden_limits() {
+    let limiter = Arc::new(QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: None,
+        overrides: vec![ApplicationRateLimit {
+            application_id: application_id(1),
+            limit: burst_of(4),
+        }],
+        internal_limit: None,
+    }));
+    let chain_id = ChainId::root(0);
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id(1),
+        QueryOrigin::Direct,
+        10,
+    )
+    .await;
+    assert_eq!(outcome, (4, 6));
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id(2),
+        QueryOrigin::Direct,
+        10,
+    )
+    .await;
+    assert_eq!(outcome, (10, 0));
+
+    let outcome = send_concurrent_queries(
+        &limiter,
+        chain_id,
+        application_id(1),
+        QueryOrigin::Internal,
+        10,
+    )
+    .await;
+    assert_eq!(outcome, (10, 0));
+}
+
+/// Tests that the budget of an application refills at the configured rate.
+#[test]
+fn test_budget_refills() {
+    let limiter = QueryRateLimiter::new(QueryRateLimitConfig {
+        default_limit: Some(RateLimit {
+            per_second: 2.0,
+            burst: 2,
+        }),
+        ..QueryRateLimitConfig::default()
+    });
+    let chain_id = ChainId::root(0);
+    let application_id = application_id(1);
+    let origin = QueryOrigin::Direct;
+    let start = Instant::now();
+
+    assert!(limiter
+        .check_at(chain_id, application_id, origin, start)
+        .is_ok());
+    assert!(limiter
+        .check_at(chain_id, application_id, origin, start)
+        .is_ok());
+    let error = limiter
+        .check_at(chain_id, application_id, origin, start)
+        .unwrap_err();
+    assert_eq!(error.retry_after, Duration::from_millis(500));
+
+    let later = start + Duration::from_millis(500);
+    assert!(limiter
+        .check_at(chain_id, application_id, origin, later)
+        .is_ok());
+    assert!(limiter
+        .check_at(chain_id, application_id, origin, later)
+        .is_err());
+
+    let much_later = later + Duration::from_secs(60);
+    for _ in 0..2 {
+        assert!(limiter
+            .check_at(chain_id, application_id, origin, much_later)
+            .is_ok());
+    }
+    assert!(limiter
+        .check_at(chain_id, application_id, origin, much_later)
+        .is_err());
+}
+
+/// Tests that throttled queries are answered with `429 Too Many Requests`.
+#[test]
+fn test_throttled_response() {
+    let throttled = QueryThrottled {
+        chain_id: ChainId::root(0),
+        application_id: application_id(1),
+        origin: QueryOrigin::Direct,
+        retry_after: Duration::from_millis(1500),
+    };
+    let response = throttled.into_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+}
+
+/// Tests the parsing of the rate limits given on the command line.
+#[test]
+fn test_parse_rate_limits() {
+    assert_eq!(
+        "10".parse::<RateLimit>().unwrap(),
+        RateLimit {
+            per_second: 10.0,
+            burst: 10,
+        }
+    );
+    assert_eq!(
+        "0.5/3".parse::<RateLimit>().unwrap(),
+        RateLimit {
+            per_second: 0.5,
+            burst: 3,
+        }
+    );
+    assert_eq!("0.5".parse::<RateLimit>().unwrap().burst, 1);
+    assert!("0".parse::<RateLimit>().is_err());
+    assert!("10/0".parse::<RateLimit>().is_err());
+    assert!("fast".parse::<RateLimit>().is_err());
+
+    let application_id = application_id(1);
+    assert_eq!(
+        format!("{application_id}=5/7")
+            .parse::<ApplicationRateLimit>()
+            .unwrap(),
+        ApplicationRateLimit {
+            application_id,
+            limit: RateLimit {
+                per_second: 5.0,
+                burst: 7,
+            },
+        }
+    );
+    assert!("5/7".parse::<ApplicationRateLimit>().is_err());
+}
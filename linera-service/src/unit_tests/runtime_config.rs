@@ -0,0 +1,72 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_storage::{DbStorage, Storage as _, TestClock};
+use linera_views::memory::MemoryStore;
+
+use super::{RuntimeConfigError, RuntimeConfigFile, RuntimeConfigWatcher};
+
+#[test]
+fn test_parse_runtime_config() {
+    assert_eq!(
+        RuntimeConfigFile::parse("").unwrap(),
+        RuntimeConfigFile::default()
+    );
+    assert_eq!(
+        RuntimeConfigFile::parse("track_view_accesses = true").unwrap(),
+        RuntimeConfigFile {
+            audit_guest_inputs: false,
+            track_view_accesses: true,
+        }
+    );
+    assert!(matches!(
+        RuntimeConfigFile::parse("track_view_access = true"),
+        Err(RuntimeConfigError::Toml(_))
+    ));
+    assert!(matches!(
+        RuntimeConfigFile::parse("track_view_accesses = 1"),
+        Err(RuntimeConfigError::Toml(_))
+    ));
+    assert!(matches!(
+        RuntimeConfigFile::parse("max_call_depth = 3"),
+        Err(RuntimeConfigError::MaxCallDepthIsInPolicy)
+    ));
+}
+
+/// Checks that the configuration of the storage is only replaced by new valid versions of
+/// the file.
+#[tokio::test]
+async fn test_runtime_config_is_reloaded_when_the_file_changes() -> anyhow::Result<()> {
+    let storage = DbStorage::<MemoryStore, TestClock>::make_test_storage(None).await;
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("runtime.toml");
+    let mut watcher = RuntimeConfigWatcher::new(storage.clone(), &path);
+    let shared_config = storage.execution_runtime_config();
+
+    assert!(matches!(
+        watcher.reload().await,
+        Err(RuntimeConfigError::Io { .. })
+    ));
+
+    tokio::fs::write(&path, "track_view_accesses = true").await?;
+    assert_eq!(watcher.reload().await?, Some(1));
+    assert!(shared_config.get().track_view_accesses);
+    assert_eq!(watcher.reload().await?, None);
+
+    // An invalid version of the file is refused.
+    tokio::fs::write(&path, "track_view_accesses = \"yes\"").await?;
+    assert!(watcher.reload().await.is_err());
+    assert!(shared_config.get().track_view_accesses);
+    assert_eq!(shared_config.version(), 1);
+
+    // Writing the same configuration again doesn't create a new version.
+    tokio::fs::write(&path, "track_view_accesses = true\n").await?;
+    assert_eq!(watcher.reload().await?, None);
+
+    tokio::fs::write(&path, "audit_guest_inputs = true").await?;
+    assert_eq!(watcher.reload().await?, Some(2));
+    let config = shared_config.get();
+    assert!(!config.track_view_accesses);
+    assert!(config.audit_guest_inputs);
+    Ok(())
+}
@@ -0,0 +1,188 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_views::{
+    batch::Batch,
+    context::{create_test_memory_context, Context, MemoryContext},
+    log_view::LogView,
+    map_view::MapView,
+    register_view::RegisterView,
+    store::KeyValueIterable as _,
+    views::{RootView, View},
+};
+
+use super::{
+    format_entries, import_entries, resolve_sub_view, select_keys, sub_view_prefix, ExportFormat,
+    StateExportError, SubViewKind, EXPORT_CHUNK_SIZE,
+};
+
+const NUM_ENTRIES: u64 = 10_000;
+
+#[derive(RootView)]
+struct TestState<C> {
+    counter: RegisterView<C, u64>,
+    entries: MapView<C, u64, String>,
+    history: LogView<C, u32>,
+}
+
+/// Creates a stored state with [`NUM_ENTRIES`] entries in its map, and a few in its log.
+async fn create_state() -> anyhow::Result<MemoryContext<()>> {
+    let context = create_test_memory_context();
+    let mut state = TestState::load(context.clone()).await?;
+    state.counter.set(7);
+    for index in 0..NUM_ENTRIES {
+        state.entries.insert(&index, format!("entry {index}"))?;
+    }
+    for value in [10, 20, 30] {
+        state.history.push(value);
+    }
+    state.save().await?;
+    Ok(context)
+}
+
+/// Reads the stored entries of a sub-view, as the node service does with an application's
+/// key-value store.
+async fn read_entries(
+    context: &MemoryContext<()>,
+    prefix: &[u8],
+) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let key_values = context.find_key_values_by_prefix(prefix).await?;
+    Ok(key_values.into_iterator_owned().collect::<Result<_, _>>()?)
+}
+
+/// Exports one page of entries as the node service does, returning its body and the key to
+/// resume from.
+fn export_page(
+    entries: &[(Vec<u8>, Vec<u8>)],
+    format: ExportFormat,
+    after: Option<&[u8]>,
+    limit: Option<usize>,
+) -> (String, Option<Vec<u8>>) {
+    let keys = entries.iter().map(|(key, _)| key.clone()).collect();
+    let (keys, next_after) = select_keys(keys, after, limit);
+    let start = after.map_or(0, |after| {
+        entries.partition_point(|(key, _)| key.as_slice() <= after)
+    });
+    let mut body = format.header().to_owned();
+    for chunk in entries[start..start + keys.len()].chunks(EXPORT_CHUNK_SIZE) {
+        let chunk = chunk
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()));
+        body.push_str(&format_entries(format, chunk));
+    }
+    (body, next_after)
+}
+
+/// Exports the entries page by page, and reads each page back.
+fn export_and_import(
+    entries: &[(Vec<u8>, Vec<u8>)],
+    format: ExportFormat,
+    limit: Option<usize>,
+) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut imported = Vec::new();
+    let mut after = None;
+    loop {
+        let (body, next_after) = export_page(entries, format, after.as_deref(), limit);
+        imported.extend(import_entries(format, &body)?);
+        match next_after {
+            Some(key) => after = Some(key),
+            None => return Ok(imported),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_export_and_reimport_map_view() -> anyhow::Result<()> {
+    let context = create_state().await?;
+    let prefix = sub_view_prefix(1, SubViewKind::Map);
+    let entries = read_entries(&context, &prefix).await?;
+    assert_eq!(entries.len(), NUM_ENTRIES as usize);
+
+    for format in [ExportFormat::JsonLines, ExportFormat::Csv] {
+        assert_eq!(export_and_import(&entries, format, Some(3_000))?, entries);
+        let imported = export_and_import(&entries, format, None)?;
+        assert_eq!(imported, entries);
+
+        // Re-import the entries into a fresh store, and compare the resulting map.
+        let target = create_test_memory_context();
+        let mut batch = Batch::new();
+        for (key, value) in imported {
+            batch.put_key_value_bytes([prefix.as_slice(), &key].concat(), value);
+        }
+        target.write_batch(batch).await?;
+        let state = TestState::load(target).await?;
+        assert_eq!(state.entries.count().await?, NUM_ENTRIES as usize);
+        for index in [0, 1, 4_999, NUM_ENTRIES - 1] {
+            assert_eq!(
+                state.entries.get(&index).await?,
+                Some(format!("entry {index}"))
+            );
+        }
+        assert_eq!(*state.counter.get(), 0);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_log_view() -> anyhow::Result<()> {
+    let context = create_state().await?;
+    let entries = read_entries(&context, &sub_view_prefix(2, SubViewKind::Log)).await?;
+
+    let (body, next_after) = export_page(&entries, ExportFormat::Csv, None, Some(2));
+    let imported = import_entries(ExportFormat::Csv, &body)?;
+    let values = imported
+        .iter()
+        .map(|(key, value)| Ok((bcs::from_bytes(key)?, bcs::from_bytes(value)?)))
+        .collect::<Result<Vec<(usize, u32)>, bcs::Error>>()?;
+    assert_eq!(values, vec![(0, 10), (1, 20)]);
+    assert_eq!(next_after, Some(bcs::to_bytes(&1_usize)?));
+    Ok(())
+}
+
+#[test]
+fn test_resolve_sub_view_by_name() {
+    let response = br#"{"data": {"__schema": {"queryType": {"fields": [
+        {"name": "counter", "type": {"name": null, "ofType": {"name": "Int"}}},
+        {"name": "entries", "type": {"name": null, "ofType": {"name": "MapView_Int_String_1a2b"}}},
+        {"name": "history", "type": {"name": "LogView_Int_3c4d", "ofType": null}}
+    ]}}}}"#;
+    assert_eq!(
+        resolve_sub_view("entries", response).unwrap(),
+        (1, SubViewKind::Map)
+    );
+    assert_eq!(
+        resolve_sub_view("history", response).unwrap(),
+        (2, SubViewKind::Log)
+    );
+    assert!(matches!(
+        resolve_sub_view("counter", response),
+        Err(StateExportError::UnsupportedSubView { .. })
+    ));
+    assert!(matches!(
+        resolve_sub_view("missing", response),
+        Err(StateExportError::UnknownSubView(_))
+    ));
+    assert!(matches!(
+        resolve_sub_view("entries", b"{}"),
+        Err(StateExportError::InvalidSchema)
+    ));
+}
+
+#[test]
+fn test_import_rejects_malformed_exports() {
+    assert!(matches!(
+        import_entries(ExportFormat::Csv, "00,01\n"),
+        Err(StateExportError::MissingCsvHeader)
+    ));
+    assert!(matches!(
+        import_entries(ExportFormat::Csv, "key,value\n0001\n"),
+        Err(StateExportError::InvalidCsvLine(_))
+    ));
+    assert!(matches!(
+        import_entries(
+            ExportFormat::JsonLines,
+            "{\"key\":\"zz\",\"value\":\"00\"}\n"
+        ),
+        Err(StateExportError::InvalidHex(_))
+    ));
+}
@@ -7,6 +7,7 @@ use comfy_table::{
 };
 use linera_base::identifiers::{ChainId, Owner};
 pub use linera_client::wallet::*;
+use linera_core::misbehavior::MisbehaviorReport;
 
 pub fn pretty_print(wallet: &Wallet, chain_ids: impl IntoIterator<Item = ChainId>) {
     let mut table = Table::new();
@@ -32,6 +33,27 @@ pub fn pretty_print(wallet: &Wallet, chain_ids: impl IntoIterator<Item = ChainId
     println!("{}", table);
 }
 
+pub fn pretty_print_reports<'a>(reports: impl IntoIterator<Item = &'a MisbehaviorReport>) {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Validator").add_attribute(Attribute::Bold),
+            Cell::new("Timestamp").add_attribute(Attribute::Bold),
+            Cell::new("Misbehavior").add_attribute(Attribute::Bold),
+        ]);
+    for report in reports {
+        table.add_row(vec![
+            Cell::new(report.validator.to_string()),
+            Cell::new(report.timestamp.to_string()),
+            Cell::new(report.misbehavior.to_string()),
+        ]);
+    }
+    println!("{}", table);
+}
+
 fn update_table_with_chain(
     table: &mut Table,
     chain_id: ChainId,
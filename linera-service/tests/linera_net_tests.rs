@@ -29,7 +29,7 @@ use linera_base::{
     command::resolve_binary,
     crypto::CryptoHash,
     data_types::Amount,
-    identifiers::{Account, AccountOwner, ApplicationId, ChainId},
+    identifiers::{Account, AccountOwner, ApplicationId, ChainId, StreamName},
 };
 use linera_chain::data_types::{Medium, Origin};
 use linera_core::worker::{Notification, Reason};
@@ -56,6 +56,8 @@ use linera_service::cli_wrappers::Network;
 use linera_service::cli_wrappers::{
     docker::BuildArg, local_kubernetes_net::SharedLocalKubernetesNetTestingConfig,
 };
+#[cfg(feature = "storage-service")]
+use linera_service::test_utils::LocalNetwork;
 use linera_service::{
     cli_wrappers::{
         local_net::{get_node_port, ProcessInbox},
@@ -528,6 +530,235 @@ async fn test_wasm_end_to_end_counter(config: impl LineraNetConfig) -> Result<()
     Ok(())
 }
 
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_wasm_end_to_end_verify_chain(config: impl LineraNetConfig) -> Result<()> {
+    use counter::CounterAbi;
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let chain1 = client.load_wallet()?.default_chain().unwrap();
+    let (_, chain2) = client.open_chain(chain1, None, Amount::ONE).await?;
+
+    // Publish the bytecode on one chain and use it on the other one, so that the verifier
+    // has to fetch it.
+    let (contract, service) = client.build_example("counter").await?;
+    let bytecode_id = client
+        .publish_bytecode::<CounterAbi, (), u64>(contract, service, chain1)
+        .await?;
+    let application_id = client
+        .create_application(&bytecode_id, &(), &35, &[], chain2)
+        .await?;
+    let port = get_node_port().await;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+    let application = node_service
+        .make_application(&chain2, &application_id)
+        .await?;
+    application.mutate("increment(value: 5)").await?;
+    node_service.ensure_is_running()?;
+    drop(node_service);
+
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("certificates.json");
+    let report = client.verify_chain(chain2, None, Some(&path)).await?;
+    let mut certificates: Value = serde_json::from_slice(&fs_err::read(&path)?)?;
+    let certificates = certificates.as_array_mut().unwrap();
+    assert!(certificates.len() >= 2);
+    assert!(
+        report.contains(&format!("Verified blocks: {}", certificates.len())),
+        "{report}"
+    );
+    client.verify_chain(chain2, Some(&path), None).await?;
+    client.verify_chain(chain1, None, None).await?;
+
+    // Change the round of the last certificate, so that its signatures become invalid.
+    let last = certificates.last_mut().unwrap();
+    last["round"] = json!({ "SingleLeader": 7 });
+    fs_err::write(&path, serde_json::to_vec(&certificates)?)?;
+    assert!(client
+        .verify_chain(chain2, Some(&path), None)
+        .await
+        .is_err());
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_wasm_end_to_end_publish_and_create_from_files(
+    config: impl LineraNetConfig,
+) -> Result<()> {
+    use counter::CounterAbi;
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let original_counter_value = 35;
+    let chain = client.load_wallet()?.default_chain().unwrap();
+    let (contract, service) = client.build_example("counter").await?;
+
+    let parameters_path = client.path_provider.path().join("counter_parameters.json");
+    fs_err::write(&parameters_path, serde_json::to_string(&())?)?;
+    let argument_path = client.path_provider.path().join("counter_argument.json");
+    fs_err::write(
+        &argument_path,
+        serde_json::to_string(&original_counter_value)?,
+    )?;
+
+    let (bytecode_id, application_id) = client
+        .publish_and_create_from_files::<CounterAbi>(
+            contract,
+            service,
+            &parameters_path,
+            Some(&argument_path),
+            None,
+        )
+        .await?;
+    assert_eq!(application_id.bytecode_id.forget_abi(), bytecode_id);
+
+    let port = get_node_port().await;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+
+    let application = node_service
+        .make_application(&chain, &application_id)
+        .await?;
+    let counter_value: u64 = application.query_json("value").await?;
+    assert_eq!(counter_value, original_counter_value);
+
+    node_service.ensure_is_running()?;
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "storage-service")]
+#[test_log::test(tokio::test)]
+async fn test_wasm_local_network_api() -> Result<()> {
+    use counter::CounterAbi;
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let mut network = LocalNetwork::new(1, 1).await?;
+    let client = network.client();
+
+    let chain = client.load_wallet()?.default_chain().unwrap();
+    let (contract, service) = client.build_example("counter").await?;
+    let application_id = client
+        .publish_and_create::<CounterAbi, (), u64>(contract, service, &(), &42, &[], None)
+        .await?;
+
+    let port = get_node_port().await;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+    let application = node_service
+        .make_application(&chain, &application_id)
+        .await?;
+    let counter_value: u64 = application.query_json("value").await?;
+    assert_eq!(counter_value, 42);
+
+    node_service.ensure_is_running()?;
+
+    network.ensure_is_running().await?;
+    network.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_wasm_end_to_end_node_service_explorer_queries(
+    config: impl LineraNetConfig,
+) -> Result<()> {
+    use counter::CounterAbi;
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let chain = client.load_wallet()?.default_chain().unwrap();
+    let (contract, service) = client.build_example("counter").await?;
+    let application_id = client
+        .publish_and_create::<CounterAbi, (), u64>(contract, service, &(), &35, &[], None)
+        .await?;
+    let port = get_node_port().await;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+
+    let application = node_service
+        .make_application(&chain, &application_id)
+        .await?;
+    application.mutate("increment(value: 5)").await?;
+
+    // Query the chain, its latest blocks and the application's state in a single request.
+    let raw_app_id = application_id.forget_abi();
+    let query = format!(
+        "query {{ \
+            chainSummaries(chainIds: [\"{chain}\"]) {{ chainId nextBlockHeight blockHash }} \
+            block(chainId: \"{chain}\") {{ hash value {{ block {{ header {{ height }} }} }} }} \
+            blockPage(chainId: \"{chain}\", limit: 2) {{ blocks {{ hash }} nextBefore }} \
+            queryApplication(\
+                chainId: \"{chain}\", \
+                applicationId: \"{raw_app_id}\", \
+                query: \"query {{ value }}\"\
+            ) \
+        }}"
+    );
+    let data = node_service.query_node(query).await?;
+
+    let summary = &data["chainSummaries"][0];
+    let latest_hash = &data["block"]["hash"];
+    assert_eq!(summary["chainId"], json!(chain));
+    assert_eq!(&summary["blockHash"], latest_hash);
+    let height: BlockHeight =
+        serde_json::from_value(data["block"]["value"]["block"]["header"]["height"].clone())?;
+    let next_height: BlockHeight = serde_json::from_value(summary["nextBlockHeight"].clone())?;
+    assert_eq!(height.try_add_one()?, next_height);
+
+    let page = &data["blockPage"];
+    assert_eq!(page["blocks"].as_array().unwrap().len(), 2);
+    assert_eq!(&page["blocks"][0]["hash"], latest_hash);
+    let next_before: BlockHeight = serde_json::from_value(page["nextBefore"].clone())?;
+    assert_eq!(next_before, BlockHeight(next_height.0 - 2));
+
+    assert_eq!(data["queryApplication"]["value"], json!(40));
+
+    // The latest block is certified by the validators.
+    let query = format!(
+        "query {{ certificate(chainId: \"{chain}\", hash: {latest_hash}) {{ \
+            block {{ hash }} signatures {{ validator }} \
+        }} }}"
+    );
+    let data = node_service.query_node(query).await?;
+    assert_eq!(&data["certificate"]["block"]["hash"], latest_hash);
+    assert!(!data["certificate"]["signatures"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    node_service.ensure_is_running()?;
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
 #[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
 #[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
@@ -2700,6 +2931,333 @@ async fn test_end_to_end_change_ownership(config: impl LineraNetConfig) -> Resul
     Ok(())
 }
 
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_query_message_status(config: impl LineraNetConfig) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    // Create runner and clients.
+    let (mut net, client1) = config.instantiate().await?;
+    let client2 = net.make_client().await;
+    client2.wallet_init(&[], FaucetOption::None).await?;
+
+    let chain1 = client1.load_wallet()?.default_chain().unwrap();
+    let chain2 = client1.open_and_assign(&client2, Amount::ONE).await?;
+    // Make sure that the transfer is the only transaction in its block.
+    client1.process_inbox(chain1).await?;
+
+    client1.transfer(Amount::ONE, chain1, chain2).await?;
+    let certificate_hash = client1
+        .load_wallet()?
+        .get(chain1)
+        .unwrap()
+        .block_hash
+        .unwrap();
+
+    // The recipient has received the message but not executed it yet.
+    assert_eq!(
+        client2
+            .query_message_status(certificate_hash, 0, chain2)
+            .await?,
+        "pending"
+    );
+
+    client2.process_inbox(chain2).await?;
+    let next_height = client2
+        .load_wallet()?
+        .get(chain2)
+        .unwrap()
+        .next_block_height;
+    let height = next_height.try_sub_one()?;
+    assert_eq!(
+        client2
+            .query_message_status(certificate_hash, 0, chain2)
+            .await?,
+        format!("executed at height {height}")
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_metrics(config: LocalNetConfig) -> Result<()> {
+    use linera_service::cli_wrappers::local_net::LocalNet;
+
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let num_shards = config.num_shards;
+    let (mut net, client1) = config.instantiate().await?;
+    let client2 = net.make_client().await;
+    client2.wallet_init(&[], FaucetOption::None).await?;
+
+    let chain1 = client1.load_wallet()?.default_chain().unwrap();
+    let chain2 = client1.open_and_assign(&client2, Amount::ONE).await?;
+    client1.transfer(Amount::ONE, chain1, chain2).await?;
+
+    // The shards of the first validator have executed blocks and received messages.
+    let mut validator_metrics = String::new();
+    for shard in 0..num_shards {
+        let url = format!(
+            "http://localhost:{}/metrics",
+            LocalNet::shard_metrics_port(0, shard)
+        );
+        validator_metrics.push_str(&reqwest::get(url).await?.error_for_status()?.text().await?);
+    }
+    for name in [
+        "linera_num_blocks_executed",
+        "linera_block_execution_latency",
+        "linera_certificate_handling_latency",
+        "linera_inbox_backlog",
+        "linera_server_request_count",
+    ] {
+        assert!(
+            validator_metrics.contains(name),
+            "missing validator metric {name}"
+        );
+    }
+
+    // The node service serves the metrics of the client.
+    let port = get_node_port().await;
+    let mut node_service = client2.run_node_service(port, ProcessInbox::Skip).await?;
+    node_service.process_inbox(&chain2).await?;
+    let client_metrics = node_service.metrics().await?;
+    assert!(
+        client_metrics.contains("linera_process_inbox_latency"),
+        "missing client metric linera_process_inbox_latency"
+    );
+
+    node_service.ensure_is_running()?;
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_rotate_key(config: impl LineraNetConfig) -> Result<()> {
+    use std::collections::BTreeSet;
+
+    use linera_base::ownership::ChainOwnership;
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    // Create runner and clients.
+    let (mut net, client1) = config.instantiate().await?;
+    let client2 = net.make_client().await;
+    client2.wallet_init(&[], FaucetOption::None).await?;
+
+    let chain1 = client1.load_wallet()?.default_chain().unwrap();
+    let chain2 = client1.open_and_assign(&client2, Amount::ONE).await?;
+    let old_owner = client1.get_owner().unwrap();
+
+    let new_owner = client1.rotate_key(chain1).await?;
+    assert_ne!(new_owner, old_owner);
+
+    // The wallet only holds the new key, which is now the chain's only owner.
+    let wallet = client1.load_wallet()?;
+    let key_pair = wallet.get(chain1).unwrap().key_pair.as_ref().unwrap();
+    assert_eq!(Owner::from(key_pair.public()), new_owner);
+    assert!(wallet.key_pair_for_owner(&old_owner).is_none());
+    assert!(wallet.unassigned_key_pairs.is_empty());
+
+    // Blocks are now signed with the new key.
+    client1
+        .transfer(Amount::from_tokens(2), chain1, chain2)
+        .await?;
+    client2.sync(chain2).await?;
+    client2.process_inbox(chain2).await?;
+    assert_eq!(
+        client2.local_balance(Account::chain(chain2)).await?,
+        Amount::from_tokens(3)
+    );
+
+    // The validators agree that the old key is not an owner anymore.
+    let port = get_node_port().await;
+    let mut node_service = client1.run_node_service(port, ProcessInbox::Skip).await?;
+    let query = format!("query {{ chain(chainId: \"{chain1}\") {{ manager {{ ownership }} }} }}");
+    let data = node_service.query_node(query).await?;
+    let ownership: ChainOwnership =
+        serde_json::from_value(data["chain"]["manager"]["ownership"].clone())?;
+    assert_eq!(ownership.super_owners, BTreeSet::from([new_owner]));
+    assert!(ownership.owners.is_empty());
+
+    node_service.ensure_is_running()?;
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_transfer_chain_ownership(config: impl LineraNetConfig) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    // Create runner and clients.
+    let (mut net, client1) = config.instantiate().await?;
+    let client2 = net.make_client().await;
+    client2.wallet_init(&[], FaucetOption::None).await?;
+
+    let chain1 = client1.load_wallet()?.default_chain().unwrap();
+    let (_, chain2) = client1
+        .open_chain(chain1, None, Amount::from_tokens(3))
+        .await?;
+    let new_owner = client2.keygen().await?;
+
+    client1.transfer_chain_ownership(chain2, new_owner).await?;
+
+    // The old owner still tracks the chain, but its key is gone and it can't propose.
+    let wallet = client1.load_wallet()?;
+    assert!(wallet.get(chain2).unwrap().key_pair.is_none());
+    assert!(client1.transfer(Amount::ONE, chain2, chain1).await.is_err());
+
+    // The new owner adopts the chain and can propose blocks.
+    client2.assign_chain(new_owner, chain2).await?;
+    let wallet = client2.load_wallet()?;
+    let key_pair = wallet.get(chain2).unwrap().key_pair.as_ref().unwrap();
+    assert_eq!(Owner::from(key_pair.public()), new_owner);
+    client2.transfer(Amount::ONE, chain2, chain1).await?;
+
+    client1.sync(chain1).await?;
+    client1.process_inbox(chain1).await?;
+    let wallet = client2.load_wallet()?;
+    assert_eq!(
+        wallet.get(chain2).unwrap().next_block_height,
+        BlockHeight(2)
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_close_chain_with_pending_messages(
+    config: impl LineraNetConfig,
+) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let chain1 = client.load_wallet()?.default_chain().unwrap();
+    let (_, chain2) = client.open_chain(chain1, None, Amount::ONE).await?;
+    let (_, chain3) = client.open_chain(chain1, None, Amount::ONE).await?;
+    client
+        .transfer(Amount::from_tokens(2), chain1, chain2)
+        .await?;
+
+    // Closing a chain with a non-empty inbox requires choosing what to do with it.
+    assert!(client.close_chain(chain2, None).await.is_err());
+
+    // Bounced transfers are refunded to the sender.
+    client.sync(chain1).await?;
+    client.process_inbox(chain1).await?;
+    let balance = client.local_balance(Account::chain(chain1)).await?;
+    client.close_chain(chain2, Some("bounce")).await?;
+    assert!(client
+        .transfer(Amount::from_millis(1), chain2, chain1)
+        .await
+        .is_err());
+    client.sync(chain1).await?;
+    client.process_inbox(chain1).await?;
+    let refunded_balance = client.local_balance(Account::chain(chain1)).await?;
+    assert!(refunded_balance > balance.saturating_add(Amount::ONE));
+
+    // Processed transfers are kept by the closed chain.
+    client
+        .transfer(Amount::from_tokens(2), chain1, chain3)
+        .await?;
+    client.sync(chain1).await?;
+    client.process_inbox(chain1).await?;
+    let balance = client.local_balance(Account::chain(chain1)).await?;
+    client.close_chain(chain3, Some("process")).await?;
+    client.sync(chain1).await?;
+    client.process_inbox(chain1).await?;
+    let final_balance = client.local_balance(Account::chain(chain1)).await?;
+    assert!(final_balance <= balance);
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_inbox(config: impl LineraNetConfig) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let chain1 = client.load_wallet()?.default_chain().unwrap();
+    let (_, chain2) = client.open_chain(chain1, None, Amount::ZERO).await?;
+    client.process_inbox(chain2).await?;
+    assert!(client.inbox(chain2).await?.is_empty());
+
+    client.transfer(Amount::ONE, chain1, chain2).await?;
+    client
+        .transfer(Amount::from_tokens(2), chain1, chain2)
+        .await?;
+
+    // Both transfers are listed before they are claimed.
+    let messages = client.inbox(chain2).await?;
+    assert_eq!(messages.len(), 2);
+    assert!(messages
+        .iter()
+        .all(|message| message.contains(&chain1.to_string()) && message.contains("Credit")));
+    assert_eq!(
+        client.local_balance(Account::chain(chain2)).await?,
+        Amount::ZERO
+    );
+
+    client.process_inbox(chain2).await?;
+    assert!(client.inbox(chain2).await?.is_empty());
+    assert_eq!(
+        client.local_balance(Account::chain(chain2)).await?,
+        Amount::from_tokens(3)
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
 #[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
 #[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
@@ -3219,3 +3777,118 @@ async fn test_end_to_end_repeated_transfers(config: impl LineraNetConfig) -> Res
 
     Ok(())
 }
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_node_service_block_notifications(config: impl LineraNetConfig) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+    let chain_id = client.load_wallet()?.default_chain().unwrap();
+    let port = get_node_port().await;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+
+    let mut chain = node_service
+        .query_node(&format!(
+            "query {{ chain(chainId: \"{chain_id}\") {{ tipState {{ nextBlockHeight }} }} }}"
+        ))
+        .await?;
+    let next_height = serde_json::from_value::<BlockHeight>(
+        chain["chain"]["tipState"]["nextBlockHeight"].take(),
+    )?;
+
+    let mut blocks = Box::pin(node_service.blocks(chain_id).await?);
+
+    // Each published blob creates a new block.
+    node_service.publish_data_blob(&chain_id, vec![1]).await?;
+    node_service.publish_data_blob(&chain_id, vec![2]).await?;
+
+    for expected_height in [next_height, next_height + BlockHeight(1)] {
+        let mut block = linera_base::time::timer::timeout(Duration::from_secs(10), blocks.next())
+            .await?
+            .expect("the block notification stream ended")?;
+        let height = serde_json::from_value::<BlockHeight>(block["height"].take())?;
+        assert_eq!(height, expected_height);
+        assert_eq!(block["skipped"], json!(0));
+        serde_json::from_value::<CryptoHash>(block["stateHash"].take())?;
+    }
+
+    node_service.ensure_is_running()?;
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_test_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[cfg_attr(feature = "kubernetes", test_case(SharedLocalKubernetesNetTestingConfig::new(Network::Grpc, BuildArg::Build) ; "kubernetes_grpc"))]
+#[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_node_service_event_subscription(config: impl LineraNetConfig) -> Result<()> {
+    use counter::{CounterAbi, STREAM_NAME};
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+    let chain_id = client.load_wallet()?.default_chain().unwrap();
+    let (contract, service) = client.build_example("counter").await?;
+    let application_id = client
+        .publish_and_create::<CounterAbi, (), u64>(contract, service, &(), &0, &[], None)
+        .await?;
+
+    let port = get_node_port().await;
+    let node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+    let application = node_service
+        .make_application(&chain_id, &application_id)
+        .await?;
+    let mut events = node_service
+        .subscribe_events(chain_id, application_id)
+        .await?;
+
+    application.mutate("increment(value: 1)").await?;
+    let first_event = linera_base::time::timer::timeout(Duration::from_secs(10), events.next())
+        .await?
+        .expect("the event stream ended")?;
+
+    // Restart the node service, forcing the subscription to reconnect.
+    node_service.terminate().await?;
+    let mut node_service = client.run_node_service(port, ProcessInbox::Skip).await?;
+    application.mutate("increment(value: 2)").await?;
+    application.mutate("increment(value: 3)").await?;
+
+    let mut received = vec![first_event];
+    for _ in 0..2 {
+        let event = linera_base::time::timer::timeout(Duration::from_secs(30), events.next())
+            .await?
+            .expect("the event stream ended")?;
+        received.push(event);
+    }
+    let first_height = received[0].height;
+    for (offset, (event, expected_value)) in received.iter().zip([1, 3, 6]).enumerate() {
+        assert_eq!(event.height, first_height + BlockHeight(offset as u64));
+        assert_eq!(event.transaction_index, 0);
+        assert_eq!(event.stream_name, StreamName(STREAM_NAME.to_vec()));
+        assert_eq!(event.event, expected_value);
+    }
+    // No event is delivered twice.
+    assert!(
+        linera_base::time::timer::timeout(Duration::from_secs(2), events.next())
+            .await
+            .is_err()
+    );
+
+    node_service.ensure_is_running()?;
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
@@ -235,6 +235,69 @@ async fn test_end_to_end_reconfiguration(config: LocalNetConfig) -> Result<()> {
     Ok(())
 }
 
+/// Test if a client that didn't follow several committee changes can still receive messages
+/// from the latest epoch and migrate its chain.
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_service_grpc"))]
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Tcp) ; "storage_service_tcp"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_committee_rotation_with_idle_client(config: LocalNetConfig) -> Result<()> {
+    use linera_base::{crypto::KeyPair, identifiers::Owner};
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+
+    let client_2 = net.make_client().await;
+    client_2.wallet_init(&[], FaucetOption::None).await?;
+    let chain_1 = ChainId::root(0);
+    let chain_2 = client
+        .open_and_assign(&client_2, Amount::from_tokens(3))
+        .await?;
+
+    // Add two validators and remove one of the initial ones, while the second client is idle.
+    for i in 4..6 {
+        net.generate_validator_config(i).await?;
+        net.start_validator(i).await?;
+        client
+            .set_validator(net.validator_name(i).unwrap(), LocalNet::proxy_port(i), 100)
+            .await?;
+        client.finalize_committee().await?;
+    }
+    client
+        .remove_validator(net.validator_name(0).unwrap())
+        .await?;
+    client.finalize_committee().await?;
+    net.remove_validator(0)?;
+
+    // Send tokens to the second chain from the latest epoch.
+    let recipient = AccountOwner::User(Owner::from(KeyPair::generate().public()));
+    client
+        .transfer_with_accounts(
+            Amount::from_tokens(5),
+            Account::chain(chain_1),
+            Account::owner(chain_2, recipient),
+        )
+        .await?;
+
+    // The second client follows the committee changes on the admin chain, so it accepts the
+    // transfer, and its chain migrates to the latest epoch.
+    client_2.sync(chain_2).await?;
+    client_2.process_inbox(chain_2).await?;
+    assert_eq!(
+        client_2
+            .local_balance(Account::owner(chain_2, recipient))
+            .await?,
+        Amount::from_tokens(5),
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 /// Test if it's possible to receive epoch change messages for past epochs.
 ///
 /// The epoch change messages are protected, and can't be rejected.
@@ -517,6 +580,47 @@ async fn test_end_to_end_retry_notification_stream(config: LocalNetConfig) -> Re
     Ok(())
 }
 
+#[cfg_attr(feature = "storage-service", test_case(Database::Service, Network::Grpc ; "storage_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(Database::ScyllaDb, Network::Grpc ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(Database::DynamoDb, Network::Grpc ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_cross_shard_transfer(database: Database, network: Network) -> Result<()> {
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let config = LocalNetConfig {
+        num_initial_validators: 1,
+        num_shards: 2,
+        num_other_initial_chains: 8,
+        ..LocalNetConfig::new_test(database, network)
+    };
+    let (mut net, client) = config.instantiate().await?;
+
+    // Find two chains served by different shards of the validator.
+    let internal_network = net.validator_server_config(0)?.internal_network;
+    let chains = (0..=8).map(ChainId::root).collect::<Vec<_>>();
+    let (sender, recipient) = chains
+        .iter()
+        .flat_map(|sender| chains.iter().map(move |recipient| (*sender, *recipient)))
+        .find(|(sender, recipient)| {
+            internal_network.get_shard_id(*sender) != internal_network.get_shard_id(*recipient)
+        })
+        .expect("the initial chains should not all be assigned to the same shard");
+
+    let balance = client.query_balance(Account::chain(recipient)).await?;
+    client.transfer(Amount::ONE, sender, recipient).await?;
+    client.process_inbox(recipient).await?;
+    assert_eq!(
+        client.query_balance(Account::chain(recipient)).await?,
+        balance.try_add(Amount::ONE)?
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_service_grpc"))]
 #[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
 #[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
@@ -558,6 +662,96 @@ async fn test_end_to_end_retry_pending_block(config: LocalNetConfig) -> Result<(
     Ok(())
 }
 
+/// Transfers a token from the default chain of the wallet to the `recipient` chain using the
+/// `ChainClient` library API, and processes the inbox of the recipient.
+struct ChainClientTransfer {
+    options: linera_client::client_options::ClientOptions,
+    recipient: ChainId,
+}
+
+#[async_trait::async_trait]
+impl linera_client::storage::Runnable for ChainClientTransfer {
+    type Output = Result<()>;
+
+    async fn run<S>(self, storage: S) -> Result<()>
+    where
+        S: linera_storage::Storage + Clone + Send + Sync + 'static,
+    {
+        use futures::StreamExt as _;
+        use linera_client::chain_client::{ChainClient, ChainClientBuilder};
+        use linera_core::worker::Reason;
+
+        let ChainClientTransfer { options, recipient } = self;
+        let wallet = options.wallet().await?;
+        let sender = ChainClientBuilder::new(storage, options, wallet).build()?;
+        let notifications = sender.subscribe_notifications().await?;
+
+        let certificate = sender
+            .transfer(None, Amount::ONE, Account::chain(recipient))
+            .await?;
+        let new_block_hash = notifications
+            .filter_map(|notification| async move {
+                match notification.reason {
+                    Reason::NewBlock { hash, .. } => Some(hash),
+                    _ => None,
+                }
+            })
+            .boxed()
+            .next()
+            .await;
+        assert_eq!(new_block_hash, Some(certificate.hash()));
+
+        let receiver = ChainClient::new(sender.context().clone(), recipient).await?;
+        let certificates = receiver.process_inbox().await?;
+        assert_eq!(certificates.len(), 1);
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "storage-service", test_case(LocalNetConfig::new_test(Database::Service, Network::Grpc) ; "storage_service_grpc"))]
+#[cfg_attr(feature = "scylladb", test_case(LocalNetConfig::new_test(Database::ScyllaDb, Network::Grpc) ; "scylladb_grpc"))]
+#[cfg_attr(feature = "dynamodb", test_case(LocalNetConfig::new_test(Database::DynamoDb, Network::Grpc) ; "aws_grpc"))]
+#[test_log::test(tokio::test)]
+async fn test_end_to_end_chain_client_library(config: LocalNetConfig) -> Result<()> {
+    use clap::Parser as _;
+    use linera_client::client_options::ClientOptions;
+
+    let _guard = INTEGRATION_TEST_GUARD.lock().await;
+    tracing::info!("Starting test {}", test_name!());
+
+    let (mut net, client) = config.instantiate().await?;
+    let recipient = client.open_and_assign(&client, Amount::ZERO).await?;
+
+    let wallet_path = client.wallet_path();
+    let options = ClientOptions::try_parse_from([
+        "linera",
+        "--wallet",
+        wallet_path.to_str().unwrap(),
+        "--storage",
+        client.storage_path(),
+        "wallet",
+        "show",
+    ])?;
+    options
+        .run_with_storage(ChainClientTransfer {
+            options: options.clone(),
+            recipient,
+        })
+        .await??;
+
+    // The wallet and the storage used by the command-line tool saw the library's blocks.
+    client.sync(recipient).await?;
+    assert_eq!(
+        client.local_balance(Account::chain(recipient)).await?,
+        Amount::ONE
+    );
+
+    net.ensure_is_running().await?;
+    net.terminate().await?;
+
+    Ok(())
+}
+
 #[cfg_attr(feature = "storage-service", test_case(Database::Service, Network::Grpc ; "storage_service_grpc"))]
 #[cfg_attr(feature = "scylladb", test_case(Database::ScyllaDb, Network::Grpc ; "scylladb_grpc"))]
 #[cfg_attr(feature = "dynamodb", test_case(Database::DynamoDb, Network::Grpc ; "aws_grpc"))]
@@ -1,6 +1,8 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::path::PathBuf;
+
 use anyhow::{bail, Result};
 use linera_base::{command::CommandExt, time::Duration};
 use tokio::process::{Child, Command};
@@ -11,6 +13,7 @@ use crate::client::{storage_service_check_absence, storage_service_check_validit
 pub struct StorageService {
     endpoint: String,
     binary: String,
+    rocks_db_path: Option<PathBuf>,
 }
 
 /// A storage service running as a child process.
@@ -27,12 +30,27 @@ impl StorageService {
         Self {
             endpoint: endpoint.to_string(),
             binary,
+            rocks_db_path: None,
         }
     }
 
+    /// Stores the data in a RocksDB database at the given path instead of in memory.
+    pub fn with_rocks_db_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rocks_db_path = Some(path.into());
+        self
+    }
+
     async fn command(&self) -> Command {
         let mut command = Command::new(&self.binary);
-        command.args(["memory", "--endpoint", &self.endpoint]);
+        match &self.rocks_db_path {
+            None => {
+                command.arg("memory");
+            }
+            Some(path) => {
+                command.arg("rocksdb").arg("--path").arg(path);
+            }
+        }
+        command.args(["--endpoint", &self.endpoint]);
         command.kill_on_drop(true);
         command
     }
@@ -71,6 +71,8 @@ impl WithError for ServiceStoreClientInternal {
 
 impl ReadableKeyValueStore for ServiceStoreClientInternal {
     const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    // The server uses a memory or RocksDB store.
+    const SORTED_SEARCHES: bool = true;
     type Keys = Vec<Vec<u8>>;
     type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
 
@@ -3,30 +3,31 @@
 
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::BTreeSet, fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Blob, TimeDelta, Timestamp},
+    data_types::{Blob, BlockHeight, TimeDelta, Timestamp, UserApplicationDescription},
     hashed::Hashed,
     identifiers::{BlobId, ChainId, UserApplicationId},
 };
 use linera_chain::{
     types::{ConfirmedBlock, ConfirmedBlockCertificate, LiteCertificate},
-    ChainStateView,
+    ChainStateView, ChainStatistics,
 };
 use linera_execution::{
-    committee::Epoch, BlobState, ExecutionRuntimeConfig, UserContractCode, UserServiceCode,
-    WasmRuntime,
+    committee::Epoch, BlobState, ExecutionRuntimeConfig, SharedExecutionRuntimeConfig,
+    UserContractCode, UserServiceCode, WasmRuntime,
 };
 use linera_views::{
     backends::dual::{DualStoreRootKeyAssignment, StoreInUse},
     batch::Batch,
-    context::ViewContext,
-    store::KeyValueStore,
-    views::{View, ViewError},
+    context::{Context as _, ViewContext},
+    overlay::OverlayStore,
+    store::{KeyIterable as _, KeyValueStore},
+    views::{RootView, View, ViewError},
 };
 use serde::{Deserialize, Serialize};
 #[cfg(with_testing)]
@@ -43,7 +44,11 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::{ChainRuntimeContext, Clock, Storage};
+use crate::{
+    migration::{self, MigrationError, MigrationReport, STORAGE_FORMAT_VERSION},
+    verification::{self, VerificationReport},
+    ChainRuntimeContext, Clock, ShutdownMarker, Storage,
+};
 
 /// The metric counting how often a blob is tested for existence from storage
 #[cfg(with_metrics)]
@@ -232,26 +237,45 @@ pub struct DbStorage<Store, Clock> {
     wasm_runtime: Option<WasmRuntime>,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
-    execution_runtime_config: ExecutionRuntimeConfig,
+    execution_runtime_config: SharedExecutionRuntimeConfig,
+    service_overrides: Arc<DashMap<UserApplicationId, UserServiceCode>>,
+    application_descriptions: Arc<DashMap<UserApplicationId, UserApplicationDescription>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-enum BaseKey {
+pub(crate) enum BaseKey {
     ChainState(ChainId),
     Certificate(CryptoHash),
     ConfirmedBlock(CryptoHash),
     Blob(BlobId),
     BlobState(BlobId),
+    ShutdownMarker(String),
+    FormatVersion,
+    ChainStateSnapshot(ChainId, BlockHeight),
+    MigrationProgress,
+    MigrationLease,
+}
+
+/// The number of key-values copied per batch when taking a snapshot of a chain state.
+const SNAPSHOT_CHUNK_SIZE: usize = 1_000;
+
+/// The prefix of the keys of [`BaseKey::ConfirmedBlock`], i.e. the BCS encoding of its
+/// variant index.
+pub(crate) const CONFIRMED_BLOCK_KEY_PREFIX: &[u8] = &[2];
+
+/// Returns the root key under which the state of the given chain is stored.
+pub fn chain_state_root_key(chain_id: ChainId) -> Result<Vec<u8>, bcs::Error> {
+    bcs::to_bytes(&BaseKey::ChainState(chain_id))
 }
 
 /// An implementation of [`DualStoreRootKeyAssignment`] that stores the
-/// chain states into the first store.
+/// chain states and their snapshots into the first store.
 pub struct ChainStatesFirstAssignment;
 
 impl DualStoreRootKeyAssignment for ChainStatesFirstAssignment {
     fn assigned_store(root_key: &[u8]) -> Result<StoreInUse, bcs::Error> {
         let store = match bcs::from_bytes(root_key)? {
-            BaseKey::ChainState(_) => StoreInUse::First,
+            BaseKey::ChainState(_) | BaseKey::ChainStateSnapshot(..) => StoreInUse::First,
             _ => StoreInUse::Second,
         };
         Ok(store)
@@ -379,6 +403,7 @@ where
     Store::Error: Send + Sync,
 {
     type Context = ViewContext<ChainRuntimeContext<Self>, Store>;
+    type SpeculativeContext = ViewContext<ChainRuntimeContext<Self>, OverlayStore<Store>>;
     type Clock = C;
 
     fn clock(&self) -> &C {
@@ -391,19 +416,87 @@ where
     ) -> Result<ChainStateView<Self::Context>, ViewError> {
         #[cfg(with_metrics)]
         let _metric = LOAD_CHAIN_LATENCY.measure_latency();
-        let runtime_context = ChainRuntimeContext {
-            storage: self.clone(),
-            chain_id,
-            execution_runtime_config: self.execution_runtime_config,
-            user_contracts: self.user_contracts.clone(),
-            user_services: self.user_services.clone(),
-        };
-        let root_key = bcs::to_bytes(&BaseKey::ChainState(chain_id))?;
-        let store = self.store.clone_with_root_key(&root_key)?;
+        let store = self.chain_state_store(chain_id)?;
+        let runtime_context = self.chain_runtime_context(chain_id);
+        let context = ViewContext::create_root_context(store, runtime_context).await?;
+        ChainStateView::load(context).await
+    }
+
+    async fn load_speculative_chain(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<ChainStateView<Self::SpeculativeContext>, ViewError> {
+        let store = OverlayStore::new(self.chain_state_store(chain_id)?);
+        let runtime_context = self.chain_runtime_context(chain_id);
         let context = ViewContext::create_root_context(store, runtime_context).await?;
         ChainStateView::load(context).await
     }
 
+    async fn write_chain_snapshot(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<(), ViewError> {
+        let chain = self.load_chain(chain_id).await?;
+        let source = self.chain_state_store(chain_id)?;
+        let target = self.chain_snapshot_store(chain_id, height)?;
+        // Remove the leftovers of a previous snapshot at the same height, if any.
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(Vec::new());
+        target.write_batch(batch).await?;
+        // The tip state is copied last, so that an incomplete snapshot is never loaded.
+        for prefix in [
+            chain.execution_state.context().base_key(),
+            chain.tip_state.context().base_key(),
+        ] {
+            let keys = source
+                .find_keys_by_prefix(&prefix)
+                .await?
+                .iterator()
+                .map(|suffix| -> Result<_, ViewError> { Ok([&prefix[..], suffix?].concat()) })
+                .collect::<Result<Vec<_>, _>>()?;
+            for chunk in keys.chunks(SNAPSHOT_CHUNK_SIZE) {
+                let values = source.read_multi_values_bytes(chunk.to_vec()).await?;
+                let mut batch = Batch::new();
+                for (key, value) in chunk.iter().zip(values) {
+                    if let Some(value) = value {
+                        batch.put_key_value_bytes(key.clone(), value);
+                    }
+                }
+                target.write_batch(batch).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_chain_snapshot(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<Option<ChainStateView<Self::SpeculativeContext>>, ViewError> {
+        let store = OverlayStore::new(self.chain_snapshot_store(chain_id, height)?);
+        let runtime_context = self.chain_runtime_context(chain_id);
+        let context = ViewContext::create_root_context(store, runtime_context).await?;
+        let chain = ChainStateView::load(context).await?;
+        if chain.tip_state.get().next_block_height != height {
+            // Nothing was written under this root key, or not at that height.
+            return Ok(None);
+        }
+        Ok(Some(chain))
+    }
+
+    async fn delete_chain_snapshot(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<(), ViewError> {
+        let mut batch = Batch::new();
+        batch.delete_key_prefix(Vec::new());
+        let store = self.chain_snapshot_store(chain_id, height)?;
+        store.write_batch(batch).await?;
+        Ok(())
+    }
+
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError> {
         let blob_key = bcs::to_bytes(&BaseKey::Blob(blob_id))?;
         let test = self.store.contains_key(&blob_key).await?;
@@ -704,9 +797,42 @@ where
         Ok(certificates)
     }
 
+    async fn read_shutdown_marker(
+        &self,
+        worker: &str,
+    ) -> Result<Option<ShutdownMarker>, ViewError> {
+        let marker_key = bcs::to_bytes(&BaseKey::ShutdownMarker(worker.to_owned()))?;
+        Ok(self.store.read_value::<ShutdownMarker>(&marker_key).await?)
+    }
+
+    async fn write_shutdown_marker(
+        &self,
+        worker: &str,
+        marker: &ShutdownMarker,
+    ) -> Result<(), ViewError> {
+        let marker_key = bcs::to_bytes(&BaseKey::ShutdownMarker(worker.to_owned()))?;
+        let mut batch = Batch::new();
+        batch.put_key_value(marker_key, marker)?;
+        self.write_batch(batch).await
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.wasm_runtime
     }
+
+    fn execution_runtime_config(&self) -> &SharedExecutionRuntimeConfig {
+        &self.execution_runtime_config
+    }
+
+    fn service_overrides(&self) -> &Arc<DashMap<UserApplicationId, UserServiceCode>> {
+        &self.service_overrides
+    }
+
+    fn application_descriptions(
+        &self,
+    ) -> &Arc<DashMap<UserApplicationId, UserApplicationDescription>> {
+        &self.application_descriptions
+    }
 }
 
 impl<Store, C> DbStorage<Store, C>
@@ -749,16 +875,150 @@ where
         Ok(())
     }
 
-    fn create(store: Store, wasm_runtime: Option<WasmRuntime>, clock: C) -> Self {
+    /// Returns the store holding the state of the given chain.
+    fn chain_state_store(&self, chain_id: ChainId) -> Result<Store, ViewError> {
+        let root_key = chain_state_root_key(chain_id)?;
+        Ok(self.store.clone_with_root_key(&root_key)?)
+    }
+
+    /// Returns the store holding the snapshot of the given chain's state at `height`.
+    fn chain_snapshot_store(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+    ) -> Result<Store, ViewError> {
+        let root_key = bcs::to_bytes(&BaseKey::ChainStateSnapshot(chain_id, height))?;
+        Ok(self.store.clone_with_root_key(&root_key)?)
+    }
+
+    /// Returns the context to execute the applications of the given chain.
+    fn chain_runtime_context(&self, chain_id: ChainId) -> ChainRuntimeContext<Self>
+    where
+        C: Clone,
+    {
+        ChainRuntimeContext {
+            storage: self.clone(),
+            chain_id,
+            execution_runtime_config: self.execution_runtime_config.clone(),
+            user_contracts: self.user_contracts.clone(),
+            user_services: self.user_services.clone(),
+            service_overrides: self.service_overrides.clone(),
+            application_descriptions: self.application_descriptions.clone(),
+        }
+    }
+
+    pub(crate) fn create(store: Store, wasm_runtime: Option<WasmRuntime>, clock: C) -> Self {
         Self {
             store: Arc::new(store),
             clock,
             wasm_runtime,
             user_contracts: Arc::new(DashMap::new()),
             user_services: Arc::new(DashMap::new()),
-            execution_runtime_config: ExecutionRuntimeConfig::default(),
+            execution_runtime_config: ExecutionRuntimeConfig::default().into(),
+            service_overrides: Arc::new(DashMap::new()),
+            application_descriptions: Arc::new(DashMap::new()),
         }
     }
+
+    /// Sets the configuration of the execution runtime available to applications.
+    pub fn with_execution_runtime_config(
+        mut self,
+        execution_runtime_config: ExecutionRuntimeConfig,
+    ) -> Self {
+        self.execution_runtime_config = execution_runtime_config.into();
+        self
+    }
+
+    /// Returns the key-value store holding the storage.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns the version of the storage format of the database, or `None` if the database
+    /// was created before the format was versioned.
+    pub async fn format_version(&self) -> Result<Option<u32>, ViewError> {
+        migration::read_format_version(&*self.store).await
+    }
+
+    /// Checks that the database uses the storage format of this release, i.e. that it was
+    /// not written by a newer release and that no migration is pending.
+    pub async fn check_format_version(&self) -> Result<(), MigrationError> {
+        migration::check_format_version(&*self.store).await
+    }
+
+    /// Applies the pending migrations of the storage format on behalf of `owner`, failing if
+    /// the database was written by a newer release or is being migrated by another process.
+    /// With `dry_run`, the migrations are only reported and nothing is written.
+    ///
+    /// This must only be done while no validator or client uses the database.
+    pub async fn migrate(
+        &self,
+        owner: &str,
+        dry_run: bool,
+    ) -> Result<MigrationReport, MigrationError> {
+        migration::run_migrations(
+            &*self.store,
+            &migration::migrations(),
+            || self.clock.current_time(),
+            owner,
+            dry_run,
+        )
+        .await
+    }
+
+    /// Writes the current version of the storage format, in a newly created database.
+    async fn write_current_format_version(store: &Store) -> Result<(), Store::Error> {
+        let mut batch = Batch::new();
+        migration::put_format_version(&mut batch, STORAGE_FORMAT_VERSION)?;
+        store.write_batch(batch).await
+    }
+}
+
+impl<Store, C> DbStorage<Store, C>
+where
+    Store: KeyValueStore + Clone + Send + Sync + 'static,
+    C: Clock + Clone + Send + Sync + 'static,
+    Store::Error: Send + Sync,
+{
+    /// Checks that the state of every chain with blocks matches the state hash certified in
+    /// its latest block. The `progress` function is called with the number of chains verified
+    /// so far.
+    pub async fn verify_chain_tips(
+        &self,
+        mut progress: impl FnMut(usize),
+    ) -> Result<VerificationReport, ViewError> {
+        let chain_ids = verification::chains_with_blocks(&*self.store).await?;
+        let mut report = VerificationReport::default();
+        for chain_id in chain_ids {
+            if let Some(mismatch) = verification::check_chain_tip(self, chain_id).await? {
+                report.mismatches.push(mismatch);
+            }
+            report.chains += 1;
+            progress(report.chains);
+        }
+        Ok(report)
+    }
+
+    /// Resets the execution statistics of the given chain, or of every chain with blocks.
+    /// Returns the number of chains whose statistics were reset.
+    ///
+    /// Validators keep the states of their active chains in memory, so this should only be
+    /// done while they are stopped.
+    pub async fn reset_chain_statistics(
+        &self,
+        chain_id: Option<ChainId>,
+    ) -> Result<usize, ViewError> {
+        let chain_ids = match chain_id {
+            Some(chain_id) => BTreeSet::from([chain_id]),
+            None => verification::chains_with_blocks(&*self.store).await?,
+        };
+        for chain_id in &chain_ids {
+            let mut chain = self.load_chain(*chain_id).await?;
+            chain.execution_statistics.set(ChainStatistics::default());
+            chain.save().await?;
+        }
+        Ok(chain_ids.len())
+    }
 }
 
 impl<Store> DbStorage<Store, WallClock>
@@ -772,7 +1032,11 @@ where
         root_key: &[u8],
         wasm_runtime: Option<WasmRuntime>,
     ) -> Result<Self, Store::Error> {
+        let is_new = !Store::exists(&config, namespace).await?;
         let store = Store::maybe_create_and_connect(&config, namespace, root_key).await?;
+        if is_new {
+            Self::write_current_format_version(&store).await?;
+        }
         Ok(Self::create(store, wasm_runtime, WallClock))
     }
 
@@ -816,6 +1080,104 @@ where
         clock: TestClock,
     ) -> Result<Self, Store::Error> {
         let store = Store::recreate_and_connect(&config, namespace, root_key).await?;
+        Self::write_current_format_version(&store).await?;
         Ok(Self::create(store, wasm_runtime, clock))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use linera_base::{
+        crypto::{CryptoHash, PublicKey},
+        data_types::{Amount, BlockHeight, RemoteValue, Timestamp},
+        identifiers::{ApplicationId, BytecodeId, ChainDescription, ChainId, MessageId, Owner},
+    };
+    use linera_execution::{committee::Committee, ExecutionRuntimeContext as _};
+    use linera_views::{memory::MemoryStore, store::TestKeyValueStore as _, views::RootView as _};
+
+    use super::*;
+
+    /// Sets the value under the key `total` of the application, and the next block height of
+    /// the chain.
+    async fn set_total(
+        storage: &DbStorage<MemoryStore, TestClock>,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        next_block_height: BlockHeight,
+        total: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut chain = storage.load_chain(chain_id).await?;
+        chain.tip_state.get_mut().next_block_height = next_block_height;
+        chain
+            .execution_state
+            .users
+            .try_load_entry_mut(&application_id)
+            .await?
+            .insert(b"total".to_vec(), total.to_vec())?;
+        chain.save().await?;
+        Ok(())
+    }
+
+    /// Tests that the state of another chain can be read at the requested height from the
+    /// stored chain state, or from a snapshot once that chain has moved on, and that it is
+    /// reported as missing otherwise.
+    #[tokio::test]
+    async fn test_read_remote_state() -> anyhow::Result<()> {
+        let store = MemoryStore::new_test_store().await?;
+        let storage = DbStorage::create(store, None, TestClock::new());
+        let description = ChainDescription::Root(1);
+        let chain_id = ChainId::from(description);
+        storage
+            .create_chain(
+                Committee::make_simple(Vec::new()),
+                ChainId::root(0),
+                description,
+                Owner::from(PublicKey::test_key(0)),
+                Amount::ZERO,
+                Timestamp::from(0),
+            )
+            .await?;
+        let application_id = ApplicationId {
+            bytecode_id: BytecodeId::new(
+                CryptoHash::test_hash("contract"),
+                CryptoHash::test_hash("service"),
+            ),
+            creation: MessageId {
+                chain_id,
+                height: BlockHeight(0),
+                index: 0,
+            },
+        };
+        let context = storage.chain_runtime_context(ChainId::root(0));
+        let read =
+            |height| context.read_remote_state(chain_id, application_id, b"total".to_vec(), height);
+
+        set_total(&storage, chain_id, application_id, BlockHeight(2), b"42").await?;
+        let total_42 = RemoteValue {
+            height: BlockHeight(1),
+            value: Some(b"42".to_vec()),
+        };
+        assert_eq!(read(BlockHeight(1)).await?, Some(total_42.clone()));
+        assert_eq!(read(BlockHeight(2)).await?, None);
+
+        // Once the chain has moved on, only a snapshot at the requested height can be read.
+        storage
+            .write_chain_snapshot(chain_id, BlockHeight(2))
+            .await?;
+        set_total(&storage, chain_id, application_id, BlockHeight(3), b"43").await?;
+        assert_eq!(read(BlockHeight(1)).await?, Some(total_42));
+        assert_eq!(
+            read(BlockHeight(2)).await?,
+            Some(RemoteValue {
+                height: BlockHeight(2),
+                value: Some(b"43".to_vec()),
+            })
+        );
+
+        storage
+            .delete_chain_snapshot(chain_id, BlockHeight(2))
+            .await?;
+        assert_eq!(read(BlockHeight(1)).await?, None);
+        Ok(())
+    }
+}
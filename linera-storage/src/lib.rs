@@ -6,14 +6,18 @@
 #![deny(clippy::large_futures)]
 
 mod db_storage;
+mod migration;
+mod verification;
 
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
 use async_trait::async_trait;
 use dashmap::{mapref::entry::Entry, DashMap};
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Amount, Blob, BlockHeight, TimeDelta, Timestamp, UserApplicationDescription},
+    data_types::{
+        Amount, Blob, BlockHeight, RemoteValue, TimeDelta, Timestamp, UserApplicationDescription,
+    },
     hashed::Hashed,
     identifiers::{
         BlobId, ChainDescription, ChainId, GenericApplicationId, Owner, UserApplicationId,
@@ -29,12 +33,14 @@ use linera_execution::{
     committee::{Committee, Epoch},
     system::SystemChannel,
     BlobState, ChannelSubscription, ExecutionError, ExecutionRuntimeConfig,
-    ExecutionRuntimeContext, UserContractCode, UserServiceCode, WasmRuntime,
+    ExecutionRuntimeContext, SharedExecutionRuntimeConfig, UserContractCode, UserServiceCode,
+    WasmRuntime,
 };
 use linera_views::{
     context::Context,
     views::{CryptoHashView, RootView, ViewError},
 };
+use serde::{Deserialize, Serialize};
 #[cfg(with_wasm_runtime)]
 use {
     linera_base::{data_types::CompressedBytecode, identifiers::BlobType},
@@ -43,11 +49,15 @@ use {
 
 #[cfg(with_testing)]
 pub use crate::db_storage::TestClock;
-pub use crate::db_storage::{ChainStatesFirstAssignment, DbStorage, WallClock};
 #[cfg(with_metrics)]
 pub use crate::db_storage::{
     READ_CERTIFICATE_COUNTER, READ_HASHED_CONFIRMED_BLOCK_COUNTER, WRITE_CERTIFICATE_COUNTER,
 };
+pub use crate::{
+    db_storage::{chain_state_root_key, ChainStatesFirstAssignment, DbStorage, WallClock},
+    migration::{Migration, MigrationError, MigrationReport, STORAGE_FORMAT_VERSION},
+    verification::{ChainTipMismatch, VerificationReport},
+};
 
 /// Communicate with a persistent storage using the "views" abstraction.
 #[cfg_attr(not(web), async_trait)]
@@ -56,6 +66,13 @@ pub trait Storage: Sized {
     /// The low-level storage implementation in use.
     type Context: Context<Extra = ChainRuntimeContext<Self>> + Clone + Send + Sync + 'static;
 
+    /// The storage context of the chain states loaded for speculative execution.
+    type SpeculativeContext: Context<Extra = ChainRuntimeContext<Self>>
+        + Clone
+        + Send
+        + Sync
+        + 'static;
+
     /// The clock type being used.
     type Clock: Clock;
 
@@ -75,6 +92,41 @@ pub trait Storage: Sized {
     /// [`create_chain`][`Self::create_chain`].
     async fn load_chain(&self, id: ChainId) -> Result<ChainStateView<Self::Context>, ViewError>;
 
+    /// Loads the view of a chain state whose writes are buffered in memory and never reach
+    /// persistent storage, to execute blocks speculatively.
+    ///
+    /// Saving the returned view is harmless, and dropping it discards all its changes.
+    async fn load_speculative_chain(
+        &self,
+        id: ChainId,
+    ) -> Result<ChainStateView<Self::SpeculativeContext>, ViewError>;
+
+    /// Copies the persisted execution state and tip state of a chain into a snapshot, to be
+    /// loaded again with [`load_chain_snapshot`][`Self::load_chain_snapshot`] once the chain
+    /// has moved on. The rest of the chain state is left out, and the copy is written in
+    /// chunks.
+    ///
+    /// The state must have been saved at `height`, i.e. with that next block height.
+    async fn write_chain_snapshot(&self, id: ChainId, height: BlockHeight)
+        -> Result<(), ViewError>;
+
+    /// Loads the snapshot of a chain state taken at `height`, with the same guarantees as
+    /// [`load_speculative_chain`][`Self::load_speculative_chain`].
+    ///
+    /// Returns `None` if there is no such snapshot.
+    async fn load_chain_snapshot(
+        &self,
+        id: ChainId,
+        height: BlockHeight,
+    ) -> Result<Option<ChainStateView<Self::SpeculativeContext>>, ViewError>;
+
+    /// Deletes the snapshot of a chain state taken at `height`, if any.
+    async fn delete_chain_snapshot(
+        &self,
+        id: ChainId,
+        height: BlockHeight,
+    ) -> Result<(), ViewError>;
+
     /// Tests the existence of a blob with the given blob ID.
     async fn contains_blob(&self, blob_id: BlobId) -> Result<bool, ViewError>;
 
@@ -163,6 +215,17 @@ pub trait Storage: Sized {
         hashes: I,
     ) -> Result<Vec<ConfirmedBlockCertificate>, ViewError>;
 
+    /// Reads the [`ShutdownMarker`] last written by the worker with the given name, if any.
+    async fn read_shutdown_marker(&self, worker: &str)
+        -> Result<Option<ShutdownMarker>, ViewError>;
+
+    /// Writes the [`ShutdownMarker`] of the worker with the given name.
+    async fn write_shutdown_marker(
+        &self,
+        worker: &str,
+        marker: &ShutdownMarker,
+    ) -> Result<(), ViewError>;
+
     /// Loads the view of a chain state and checks that it is active.
     ///
     /// # Notes
@@ -255,6 +318,26 @@ pub trait Storage: Sized {
     /// Selects the WebAssembly runtime to use for applications (if any).
     fn wasm_runtime(&self) -> Option<WasmRuntime>;
 
+    /// Returns the configuration of the execution runtime available to applications, which
+    /// can be replaced while the storage is in use.
+    fn execution_runtime_config(&self) -> &SharedExecutionRuntimeConfig;
+
+    /// Returns the services that answer the queries of some applications instead of the
+    /// services in their published bytecode.
+    ///
+    /// This is only meant for developing applications, e.g. to try changes to a service
+    /// without publishing it again. Contracts always execute the published bytecode.
+    fn service_overrides(&self) -> &Arc<DashMap<UserApplicationId, UserServiceCode>>;
+
+    /// Returns the descriptions of applications created on other chains that are known
+    /// without loading the states of their creator chains.
+    ///
+    /// Executing a message for an application that the chain has never registered looks it up
+    /// here first.
+    fn application_descriptions(
+        &self,
+    ) -> &Arc<DashMap<UserApplicationId, UserApplicationDescription>>;
+
     /// Creates a [`UserContractCode`] instance using the bytecode in storage referenced
     /// by the `application_description`.
     #[cfg(with_wasm_runtime)]
@@ -341,13 +424,31 @@ pub trait Storage: Sized {
     }
 }
 
+/// Records whether a worker shut down cleanly, so that the chains it was working on can be
+/// checked when it restarts after a crash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownMarker {
+    /// The worker is running, or it stopped without draining its in-flight requests. The
+    /// chains it loaded may have been left in an inconsistent state.
+    Running {
+        /// The chains the worker loaded, up to a limit.
+        chains: BTreeSet<ChainId>,
+        /// Whether the worker loaded more chains than listed.
+        truncated: bool,
+    },
+    /// The worker finished all its in-flight requests before stopping.
+    Clean,
+}
+
 #[derive(Clone)]
 pub struct ChainRuntimeContext<S> {
     storage: S,
     chain_id: ChainId,
-    execution_runtime_config: ExecutionRuntimeConfig,
+    execution_runtime_config: SharedExecutionRuntimeConfig,
     user_contracts: Arc<DashMap<UserApplicationId, UserContractCode>>,
     user_services: Arc<DashMap<UserApplicationId, UserServiceCode>>,
+    service_overrides: Arc<DashMap<UserApplicationId, UserServiceCode>>,
+    application_descriptions: Arc<DashMap<UserApplicationId, UserApplicationDescription>>,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -360,8 +461,8 @@ where
         self.chain_id
     }
 
-    fn execution_runtime_config(&self) -> linera_execution::ExecutionRuntimeConfig {
-        self.execution_runtime_config
+    fn execution_runtime_config(&self) -> ExecutionRuntimeConfig {
+        self.execution_runtime_config.get()
     }
 
     fn user_contracts(&self) -> &Arc<DashMap<UserApplicationId, UserContractCode>> {
@@ -390,7 +491,11 @@ where
         &self,
         description: &UserApplicationDescription,
     ) -> Result<UserServiceCode, ExecutionError> {
-        match self.user_services.entry(description.into()) {
+        let application_id = UserApplicationId::from(description);
+        if let Some(service) = self.service_overrides.get(&application_id) {
+            return Ok(service.clone());
+        }
+        match self.user_services.entry(application_id) {
             Entry::Occupied(entry) => Ok(entry.get().clone()),
             Entry::Vacant(entry) => {
                 let service = self.storage.load_service(description).await?;
@@ -408,6 +513,59 @@ where
         self.storage.contains_blob(blob_id).await
     }
 
+    async fn read_remote_state(
+        &self,
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+        key: Vec<u8>,
+        height: BlockHeight,
+    ) -> Result<Option<RemoteValue>, ExecutionError> {
+        let next_height = height.try_add_one()?;
+        let chain = self.storage.load_chain(chain_id).await?;
+        let tip_height = chain.tip_state.get().next_block_height;
+        let value = if !chain.is_active() || tip_height < next_height {
+            // The chain is not tracked, or its local state is older than requested.
+            return Ok(None);
+        } else if tip_height == next_height {
+            read_application_value(&chain, application_id, &key).await?
+        } else {
+            // The chain has moved on: Only a snapshot of its state at that height can be read.
+            match self
+                .storage
+                .load_chain_snapshot(chain_id, next_height)
+                .await?
+            {
+                Some(snapshot) => read_application_value(&snapshot, application_id, &key).await?,
+                None => return Ok(None),
+            }
+        };
+        Ok(Some(RemoteValue { height, value }))
+    }
+
+    async fn describe_remote_application(
+        &self,
+        id: UserApplicationId,
+    ) -> Result<Option<UserApplicationDescription>, ExecutionError> {
+        if let Some(description) = self.application_descriptions.get(&id) {
+            return Ok(Some(description.clone()));
+        }
+        let chain = self.storage.load_chain(id.creation.chain_id).await?;
+        let Some(description) = chain
+            .execution_state
+            .system
+            .registry
+            .known_applications
+            .get(&id)
+            .await?
+        else {
+            return Ok(None);
+        };
+        // Descriptions never change once registered, so they can be cached for all chains.
+        self.application_descriptions
+            .insert(id, description.clone());
+        Ok(Some(description))
+    }
+
     #[cfg(with_testing)]
     async fn add_blobs(
         &self,
@@ -418,6 +576,27 @@ where
     }
 }
 
+/// Reads the value under `key` in the state of an application on `chain`.
+async fn read_application_value<C>(
+    chain: &ChainStateView<C>,
+    application_id: UserApplicationId,
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, ViewError>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    match chain
+        .execution_state
+        .users
+        .try_load_entry(&application_id)
+        .await?
+    {
+        Some(view) => view.get(key).await,
+        None => Ok(None),
+    }
+}
+
 /// A clock that can be used to get the current `Timestamp`.
 #[cfg_attr(not(web), async_trait)]
 #[cfg_attr(web, async_trait(?Send))]
@@ -0,0 +1,559 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versioning of the storage format, and migrations of the databases written by older
+//! releases.
+//!
+//! The version of the storage format is written at the creation of a namespace. Databases
+//! created before the format was versioned have no version, and are treated as version `0`.
+//! Migration number `n` in [`migrations`] converts a database from version `n` to version
+//! `n + 1`.
+//!
+//! Migrations are only applied by an explicit command, while no validator or client uses the
+//! database: opening a database whose format is not current fails with
+//! [`MigrationError::MigrationPending`]. A migration is written in batches of at most
+//! [`MIGRATION_BATCH_SIZE`] entries, each of them recording the progress, so that an
+//! interrupted migration resumes where it stopped. A lease prevents two processes from
+//! migrating the same database at once.
+
+use linera_base::data_types::{TimeDelta, Timestamp};
+use linera_views::{
+    batch::Batch,
+    store::{KeyValueIterable as _, RestrictedKeyValueStore},
+    views::ViewError,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::db_storage::BaseKey;
+
+/// The version of the storage format written by this release.
+pub const STORAGE_FORMAT_VERSION: u32 = 1;
+
+/// The maximum number of converted entries written in a single batch.
+pub const MIGRATION_BATCH_SIZE: usize = 1000;
+
+/// How many seconds a migration lease is valid without being renewed.
+const MIGRATION_LEASE_SECONDS: u64 = 60;
+
+/// An error preventing the migration of a database to the current storage format.
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    /// The database was written by a newer release, whose format this release can't read.
+    #[error(
+        "the database uses the storage format version {found}, but this release only supports \
+        versions up to {supported}"
+    )]
+    FutureVersion { found: u32, supported: u32 },
+    /// The database must be migrated before it is used.
+    #[error(
+        "the database uses the storage format version {found} but this release uses version \
+        {current}; run `linera storage migrate` first"
+    )]
+    MigrationPending { found: u32, current: u32 },
+    /// Another process is migrating the database.
+    #[error("the database is being migrated by {owner} until {expiry}")]
+    Locked { owner: String, expiry: Timestamp },
+    /// The database could not be read or written.
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
+}
+
+/// A change of the storage format, from the previous version to the next one.
+///
+/// A migration converts the entries whose keys start with its [`Migration::key_prefix`], one
+/// at a time. Entries converted in an interrupted run may be seen again when it is resumed,
+/// so the conversion of the last batch must be idempotent.
+pub trait Migration: Send + Sync {
+    /// A short description of the change, for the logs.
+    fn description(&self) -> &'static str;
+
+    /// The prefix of the keys of the entries to convert, or `None` if the change only
+    /// concerns the version of the format.
+    fn key_prefix(&self) -> Option<Vec<u8>>;
+
+    /// Adds the conversion of one entry to the `batch`.
+    fn convert(&self, key: &[u8], value: &[u8], batch: &mut Batch) -> Result<(), ViewError>;
+}
+
+/// The migrations of the storage format, in order.
+pub(crate) fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![Box::new(VersionTag)]
+}
+
+/// The outcome of a migration of the storage format.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MigrationReport {
+    /// The version of the storage format found in the database.
+    pub initial_version: u32,
+    /// The descriptions of the migrations that were applied, or would be in a dry run.
+    pub applied: Vec<&'static str>,
+    /// The number of entries converted by the migrations.
+    pub entries: usize,
+}
+
+/// How far the migration to a version got, written together with each batch.
+#[derive(Debug, Deserialize, Serialize)]
+struct MigrationProgress {
+    /// The version the database is being migrated to.
+    version: u32,
+    /// The last converted key.
+    last_key: Vec<u8>,
+}
+
+/// The lease of the process migrating a database.
+#[derive(Debug, Deserialize, Serialize)]
+struct MigrationLease {
+    owner: String,
+    expiry: Timestamp,
+}
+
+/// Reads the version of the storage format of a database, if it was written.
+pub(crate) async fn read_format_version<Store>(store: &Store) -> Result<Option<u32>, ViewError>
+where
+    Store: RestrictedKeyValueStore,
+{
+    let key = bcs::to_bytes(&BaseKey::FormatVersion)?;
+    Ok(store.read_value::<u32>(&key).await?)
+}
+
+/// Adds the write of the version of the storage format to a batch.
+pub(crate) fn put_format_version(batch: &mut Batch, version: u32) -> Result<(), bcs::Error> {
+    let key = bcs::to_bytes(&BaseKey::FormatVersion)?;
+    batch.put_key_value(key, &version)
+}
+
+/// Checks that the database uses the storage format of this release.
+pub(crate) async fn check_format_version<Store>(store: &Store) -> Result<(), MigrationError>
+where
+    Store: RestrictedKeyValueStore,
+{
+    let found = read_format_version(store).await?.unwrap_or(0);
+    if found > STORAGE_FORMAT_VERSION {
+        return Err(MigrationError::FutureVersion {
+            found,
+            supported: STORAGE_FORMAT_VERSION,
+        });
+    }
+    if found < STORAGE_FORMAT_VERSION {
+        return Err(MigrationError::MigrationPending {
+            found,
+            current: STORAGE_FORMAT_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Applies the `migrations` that are pending for the database in `store`, on behalf of
+/// `owner`. With `dry_run`, the migrations are only reported and nothing is written.
+pub(crate) async fn run_migrations<Store>(
+    store: &Store,
+    migrations: &[Box<dyn Migration>],
+    now: impl Fn() -> Timestamp,
+    owner: &str,
+    dry_run: bool,
+) -> Result<MigrationReport, MigrationError>
+where
+    Store: RestrictedKeyValueStore,
+{
+    let target_version = migrations.len() as u32;
+    let initial_version = read_format_version(store).await?.unwrap_or(0);
+    if initial_version > target_version {
+        return Err(MigrationError::FutureVersion {
+            found: initial_version,
+            supported: target_version,
+        });
+    }
+    let mut report = MigrationReport {
+        initial_version,
+        ..MigrationReport::default()
+    };
+    if initial_version == target_version {
+        return Ok(report);
+    }
+    let mut migrator = Migrator {
+        store,
+        now,
+        owner,
+        dry_run,
+    };
+    if !dry_run {
+        migrator.acquire_lease().await?;
+    }
+    let progress_key = bcs::to_bytes(&BaseKey::MigrationProgress)?;
+    let progress = store
+        .read_value::<MigrationProgress>(&progress_key)
+        .await
+        .map_err(ViewError::from)?;
+    for (version, migration) in (1..).zip(migrations).skip(initial_version as usize) {
+        let resume_after = progress
+            .as_ref()
+            .filter(|progress| progress.version == version)
+            .map(|progress| progress.last_key.clone());
+        report.entries += migrator
+            .migrate_entries(version, &**migration, resume_after)
+            .await?;
+        report.applied.push(migration.description());
+        let mut batch = Batch::new();
+        put_format_version(&mut batch, version)?;
+        batch.delete_key(progress_key.clone());
+        migrator.write(batch).await?;
+    }
+    migrator.release_lease().await?;
+    Ok(report)
+}
+
+/// The state of a run of the migrations.
+struct Migrator<'a, Store, Now> {
+    store: &'a Store,
+    now: Now,
+    owner: &'a str,
+    dry_run: bool,
+}
+
+impl<Store, Now> Migrator<'_, Store, Now>
+where
+    Store: RestrictedKeyValueStore,
+    Now: Fn() -> Timestamp,
+{
+    /// Converts the entries of one migration, in batches, resuming after `resume_after`.
+    /// Returns the number of converted entries.
+    ///
+    /// The keys are scanned one sub-prefix at a time, so that the entries held in memory are
+    /// bounded by the size of one sub-prefix rather than by the whole database.
+    async fn migrate_entries(
+        &mut self,
+        version: u32,
+        migration: &dyn Migration,
+        resume_after: Option<Vec<u8>>,
+    ) -> Result<usize, MigrationError> {
+        let Some(prefix) = migration.key_prefix() else {
+            return Ok(0);
+        };
+        let first_byte = resume_after
+            .as_ref()
+            .and_then(|key| key.get(prefix.len()).copied())
+            .unwrap_or(0);
+        let progress_key = bcs::to_bytes(&BaseKey::MigrationProgress)?;
+        let mut count = 0;
+        for byte in first_byte..=u8::MAX {
+            let mut sub_prefix = prefix.clone();
+            sub_prefix.push(byte);
+            let key_values = self
+                .store
+                .find_key_values_by_prefix(&sub_prefix)
+                .await
+                .map_err(ViewError::from)?;
+            let mut batch = Batch::new();
+            let mut last_key = None;
+            for entry in key_values.iterator() {
+                let (suffix, value) = entry.map_err(ViewError::from)?;
+                let key = [&sub_prefix[..], suffix].concat();
+                if resume_after
+                    .as_ref()
+                    .is_some_and(|resume_after| key <= *resume_after)
+                {
+                    continue;
+                }
+                migration.convert(&key, value, &mut batch)?;
+                count += 1;
+                last_key = Some(key);
+                if batch.operations.len() >= MIGRATION_BATCH_SIZE {
+                    let progress = MigrationProgress {
+                        version,
+                        last_key: last_key.take().expect("an entry was converted"),
+                    };
+                    batch.put_key_value(progress_key.clone(), &progress)?;
+                    self.write(std::mem::take(&mut batch)).await?;
+                }
+            }
+            if let Some(last_key) = last_key {
+                let progress = MigrationProgress { version, last_key };
+                batch.put_key_value(progress_key.clone(), &progress)?;
+                self.write(batch).await?;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Writes a batch of the migration, after checking that this process still holds the
+    /// lease, and renews it.
+    async fn write(&mut self, mut batch: Batch) -> Result<(), MigrationError> {
+        if self.dry_run {
+            return Ok(());
+        }
+        self.check_lease().await?;
+        let lease_key = bcs::to_bytes(&BaseKey::MigrationLease)?;
+        batch.put_key_value(lease_key, &self.new_lease())?;
+        self.store
+            .write_batch(batch)
+            .await
+            .map_err(ViewError::from)?;
+        Ok(())
+    }
+
+    /// Deletes the lease at the end of the migration.
+    async fn release_lease(&self) -> Result<(), MigrationError> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let mut batch = Batch::new();
+        batch.delete_key(bcs::to_bytes(&BaseKey::MigrationLease)?);
+        self.store
+            .write_batch(batch)
+            .await
+            .map_err(ViewError::from)?;
+        Ok(())
+    }
+
+    /// Takes the lease of the database, unless another process holds an unexpired one.
+    ///
+    /// Stores have no conditional writes, so the lease is read again after it is written to
+    /// detect a concurrent migration.
+    async fn acquire_lease(&mut self) -> Result<(), MigrationError> {
+        self.check_lease().await?;
+        let lease_key = bcs::to_bytes(&BaseKey::MigrationLease)?;
+        let mut batch = Batch::new();
+        batch.put_key_value(lease_key, &self.new_lease())?;
+        self.store
+            .write_batch(batch)
+            .await
+            .map_err(ViewError::from)?;
+        self.check_lease().await
+    }
+
+    /// Fails if another process holds an unexpired lease.
+    async fn check_lease(&self) -> Result<(), MigrationError> {
+        let lease_key = bcs::to_bytes(&BaseKey::MigrationLease)?;
+        let lease = self
+            .store
+            .read_value::<MigrationLease>(&lease_key)
+            .await
+            .map_err(ViewError::from)?;
+        match lease {
+            Some(lease) if lease.owner != self.owner && lease.expiry > (self.now)() => {
+                Err(MigrationError::Locked {
+                    owner: lease.owner,
+                    expiry: lease.expiry,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn new_lease(&self) -> MigrationLease {
+        MigrationLease {
+            owner: self.owner.to_owned(),
+            expiry: (self.now)().saturating_add(TimeDelta::from_secs(MIGRATION_LEASE_SECONDS)),
+        }
+    }
+}
+
+/// Records the version of the format in databases created before it was versioned. Their
+/// layout is the same as version `1`.
+struct VersionTag;
+
+impl Migration for VersionTag {
+    fn description(&self) -> &'static str {
+        "record the storage format version"
+    }
+
+    fn key_prefix(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn convert(&self, _key: &[u8], _value: &[u8], _batch: &mut Batch) -> Result<(), ViewError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::data_types::Blob;
+    use linera_views::{
+        memory::MemoryStore,
+        store::{TestKeyValueStore as _, WritableKeyValueStore as _},
+    };
+
+    use super::*;
+    use crate::{DbStorage, Storage as _, TestClock};
+
+    /// A migration to version `2` for the tests, replacing the values under the prefix `[9]`
+    /// by their lengths.
+    struct ValueLengths;
+
+    impl Migration for ValueLengths {
+        fn description(&self) -> &'static str {
+            "store the lengths of the values"
+        }
+
+        fn key_prefix(&self) -> Option<Vec<u8>> {
+            Some(vec![9])
+        }
+
+        fn convert(&self, key: &[u8], value: &[u8], batch: &mut Batch) -> Result<(), ViewError> {
+            batch.put_key_value(key.to_vec(), &(value.len() as u64))?;
+            Ok(())
+        }
+    }
+
+    fn test_migrations() -> Vec<Box<dyn Migration>> {
+        vec![Box::new(VersionTag), Box::new(ValueLengths)]
+    }
+
+    /// Creates a database of version `1` with `count` entries under the prefix `[9]`.
+    async fn fixture(count: usize) -> anyhow::Result<MemoryStore> {
+        let store = MemoryStore::new_test_store().await?;
+        let mut batch = Batch::new();
+        put_format_version(&mut batch, 1)?;
+        for index in 0..count {
+            let key = [&[9], &bcs::to_bytes(&(index as u32))?[..]].concat();
+            batch.put_key_value_bytes(key, vec![0; index % 7]);
+        }
+        store.write_batch(batch).await?;
+        Ok(store)
+    }
+
+    async fn check_converted(store: &MemoryStore, count: usize) -> anyhow::Result<()> {
+        for index in 0..count {
+            let key = [&[9], &bcs::to_bytes(&(index as u32))?[..]].concat();
+            assert_eq!(
+                store.read_value::<u64>(&key).await?,
+                Some((index % 7) as u64)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_match_the_format_version() {
+        assert_eq!(migrations().len(), STORAGE_FORMAT_VERSION as usize);
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_databases_must_be_migrated() -> anyhow::Result<()> {
+        let store = MemoryStore::new_test_store().await?;
+        let blob = Blob::new_data(b"blob".to_vec());
+        let storage = DbStorage::create(store, None, TestClock::new());
+        storage.write_blob(&blob).await?;
+
+        assert!(matches!(
+            storage.check_format_version().await,
+            Err(MigrationError::MigrationPending { found: 0, current })
+                if current == STORAGE_FORMAT_VERSION
+        ));
+
+        // A dry run reports the migration without writing anything.
+        let report = storage.migrate("test", true).await?;
+        assert_eq!(report.initial_version, 0);
+        assert_eq!(report.applied, ["record the storage format version"]);
+        assert_eq!(storage.format_version().await?, None);
+
+        let report = storage.migrate("test", false).await?;
+        assert_eq!(report.applied.len(), 1);
+        storage.check_format_version().await?;
+        assert_eq!(storage.read_blob(blob.id()).await?.bytes(), blob.bytes());
+
+        // Migrating again does nothing.
+        let report = storage.migrate("test", false).await?;
+        assert_eq!(report.initial_version, STORAGE_FORMAT_VERSION);
+        assert!(report.applied.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrations_are_written_in_batches() -> anyhow::Result<()> {
+        let count = 2 * MIGRATION_BATCH_SIZE + 10;
+        let store = fixture(count).await?;
+        let now = || Timestamp::from(0);
+
+        let report = run_migrations(&store, &test_migrations(), now, "dry run", true).await?;
+        assert_eq!(report.entries, count);
+        assert_eq!(read_format_version(&store).await?, Some(1));
+
+        let report = run_migrations(&store, &test_migrations(), now, "test", false).await?;
+        assert_eq!(report.initial_version, 1);
+        assert_eq!(report.applied, ["store the lengths of the values"]);
+        assert_eq!(report.entries, count);
+        assert_eq!(read_format_version(&store).await?, Some(2));
+        check_converted(&store, count).await?;
+        let progress_key = bcs::to_bytes(&BaseKey::MigrationProgress)?;
+        let lease_key = bcs::to_bytes(&BaseKey::MigrationLease)?;
+        assert!(!store.contains_key(&progress_key).await?);
+        assert!(!store.contains_key(&lease_key).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_migrations_resume() -> anyhow::Result<()> {
+        let count = 3 * MIGRATION_BATCH_SIZE;
+        let store = fixture(count).await?;
+        let now = || Timestamp::from(0);
+
+        // Simulate a run interrupted after its first batch.
+        let mut migrator = Migrator {
+            store: &store,
+            now,
+            owner: "first",
+            dry_run: false,
+        };
+        let progress_key = bcs::to_bytes(&BaseKey::MigrationProgress)?;
+        let key_values = store.find_key_values_by_prefix(&[9]).await?;
+        let mut batch = Batch::new();
+        let mut last_key = Vec::new();
+        for (suffix, value) in key_values.iter().take(MIGRATION_BATCH_SIZE) {
+            last_key = [&[9], &suffix[..]].concat();
+            ValueLengths.convert(&last_key, value, &mut batch)?;
+        }
+        let progress = MigrationProgress {
+            version: 2,
+            last_key,
+        };
+        batch.put_key_value(progress_key, &progress)?;
+        migrator.write(batch).await?;
+
+        // The lease of the interrupted run blocks others until it expires.
+        let result = run_migrations(&store, &test_migrations(), now, "second", false).await;
+        assert!(matches!(result, Err(MigrationError::Locked { owner, .. }) if owner == "first"));
+
+        let later =
+            || Timestamp::from(0).saturating_add(TimeDelta::from_secs(MIGRATION_LEASE_SECONDS));
+        let report = run_migrations(&store, &test_migrations(), later, "second", false).await?;
+        assert_eq!(report.entries, count - MIGRATION_BATCH_SIZE);
+        assert_eq!(read_format_version(&store).await?, Some(2));
+        check_converted(&store, count).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_future_versions_are_refused() -> anyhow::Result<()> {
+        let store = MemoryStore::new_test_store().await?;
+        let mut batch = Batch::new();
+        put_format_version(&mut batch, STORAGE_FORMAT_VERSION + 1)?;
+        store.write_batch(batch).await?;
+        let storage = DbStorage::create(store, None, TestClock::new());
+
+        let result = storage.migrate("test", false).await;
+        assert!(matches!(
+            result,
+            Err(MigrationError::FutureVersion { found, supported })
+                if found == STORAGE_FORMAT_VERSION + 1 && supported == STORAGE_FORMAT_VERSION
+        ));
+        assert!(matches!(
+            storage.check_format_version().await,
+            Err(MigrationError::FutureVersion { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_new_databases_use_the_current_format() -> anyhow::Result<()> {
+        let storage = DbStorage::<MemoryStore, TestClock>::make_test_storage(None).await;
+        assert_eq!(
+            storage.format_version().await?,
+            Some(STORAGE_FORMAT_VERSION)
+        );
+        storage.check_format_version().await?;
+        Ok(())
+    }
+}
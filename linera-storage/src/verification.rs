@@ -0,0 +1,287 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consistency checks between the chain states stored in a database and their latest
+//! confirmed blocks.
+//!
+//! The state of a chain is written separately from its blocks, so a crash or a corrupted
+//! database can leave a chain whose state doesn't match the state hash certified in its
+//! latest block.
+
+use std::collections::BTreeSet;
+
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::types::ConfirmedBlock;
+use linera_views::{
+    store::{KeyIterable as _, ReadableKeyValueStore},
+    views::ViewError,
+};
+
+use crate::{db_storage::CONFIRMED_BLOCK_KEY_PREFIX, Storage};
+
+/// The number of confirmed blocks read at once when listing the chains.
+const READ_BLOCKS_BATCH_SIZE: usize = 100;
+
+/// A chain whose stored state doesn't match its latest confirmed block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ChainTipMismatch {
+    /// The latest block of the chain is missing.
+    MissingBlock {
+        chain_id: ChainId,
+        block_hash: CryptoHash,
+    },
+    /// The chain expects another next block height than the one following its latest block.
+    Height {
+        chain_id: ChainId,
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        next_block_height: BlockHeight,
+    },
+    /// The state hash certified in the latest block differs from the hash of the stored
+    /// execution state.
+    StateHash {
+        chain_id: ChainId,
+        block_hash: CryptoHash,
+        block_state_hash: CryptoHash,
+        stored_state_hash: Option<CryptoHash>,
+    },
+}
+
+impl std::fmt::Display for ChainTipMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainTipMismatch::MissingBlock {
+                chain_id,
+                block_hash,
+            } => write!(
+                f,
+                "chain {chain_id}: the latest block {block_hash} is missing"
+            ),
+            ChainTipMismatch::Height {
+                chain_id,
+                block_hash,
+                block_height,
+                next_block_height,
+            } => write!(
+                f,
+                "chain {chain_id}: the latest block {block_hash} has height {block_height}, \
+                but the next block height is {next_block_height}"
+            ),
+            ChainTipMismatch::StateHash {
+                chain_id,
+                block_hash,
+                block_state_hash,
+                stored_state_hash,
+            } => write!(
+                f,
+                "chain {chain_id}: the latest block {block_hash} has the state hash \
+                {block_state_hash}, but the stored state hash is {stored_state_hash:?}"
+            ),
+        }
+    }
+}
+
+/// The outcome of the verification of the chain states.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerificationReport {
+    /// The number of chains with blocks that were verified.
+    pub chains: usize,
+    /// The chains whose state doesn't match their latest block.
+    pub mismatches: Vec<ChainTipMismatch>,
+}
+
+impl VerificationReport {
+    /// Returns whether all the chains match their latest block.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Returns the IDs of the chains with at least one confirmed block in the `store`.
+pub(crate) async fn chains_with_blocks<Store>(store: &Store) -> Result<BTreeSet<ChainId>, ViewError>
+where
+    Store: ReadableKeyValueStore,
+{
+    let keys = store
+        .find_keys_by_prefix(CONFIRMED_BLOCK_KEY_PREFIX)
+        .await?
+        .iterator()
+        .map(|key| {
+            key.map(|key| {
+                let mut full_key = CONFIRMED_BLOCK_KEY_PREFIX.to_vec();
+                full_key.extend_from_slice(key);
+                full_key
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut chain_ids = BTreeSet::new();
+    for keys in keys.chunks(READ_BLOCKS_BATCH_SIZE) {
+        for bytes in store.read_multi_values_bytes(keys.to_vec()).await? {
+            let Some(bytes) = bytes else {
+                continue;
+            };
+            let block = bcs::from_bytes::<ConfirmedBlock>(&bytes)?;
+            chain_ids.insert(block.block().header.chain_id);
+        }
+    }
+    Ok(chain_ids)
+}
+
+/// Checks that the stored state of the chain matches its latest confirmed block, if any.
+pub(crate) async fn check_chain_tip<S>(
+    storage: &S,
+    chain_id: ChainId,
+) -> Result<Option<ChainTipMismatch>, ViewError>
+where
+    S: Storage + Sync,
+{
+    let chain = storage.load_chain(chain_id).await?;
+    let tip = chain.tip_state.get();
+    let Some(block_hash) = tip.block_hash else {
+        return Ok(None);
+    };
+    let block = match storage.read_hashed_confirmed_block(block_hash).await {
+        Ok(block) => block,
+        Err(ViewError::NotFound(_)) => {
+            return Ok(Some(ChainTipMismatch::MissingBlock {
+                chain_id,
+                block_hash,
+            }))
+        }
+        Err(error) => return Err(error),
+    };
+    let header = &block.inner().block().header;
+    if header.height.try_add_one().ok() != Some(tip.next_block_height) {
+        return Ok(Some(ChainTipMismatch::Height {
+            chain_id,
+            block_hash,
+            block_height: header.height,
+            next_block_height: tip.next_block_height,
+        }));
+    }
+    let stored_state_hash = *chain.execution_state_hash.get();
+    if stored_state_hash != Some(header.state_hash) {
+        return Ok(Some(ChainTipMismatch::StateHash {
+            chain_id,
+            block_hash,
+            block_state_hash: header.state_hash,
+            stored_state_hash,
+        }));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::hashed::Hashed;
+    use linera_chain::{
+        data_types::{BlockExecutionOutcome, ExecutedBlock},
+        test::make_first_block,
+    };
+    use linera_views::{
+        batch::Batch,
+        memory::MemoryStore,
+        store::{TestKeyValueStore as _, WritableKeyValueStore as _},
+        views::RootView as _,
+    };
+
+    use super::*;
+    use crate::{db_storage::BaseKey, DbStorage, TestClock};
+
+    /// Creates a database where the chain has a first block with the `block_state_hash`, and
+    /// a state pointing to the block `tip` with the `stored_state_hash`.
+    async fn storage_with_chain(
+        chain_id: ChainId,
+        block_state_hash: CryptoHash,
+        tip: Option<CryptoHash>,
+        stored_state_hash: CryptoHash,
+    ) -> anyhow::Result<(DbStorage<MemoryStore, TestClock>, CryptoHash)> {
+        let store = MemoryStore::new_test_store().await?;
+        let storage = DbStorage::create(store.clone(), None, TestClock::new());
+        let block = Hashed::new(ConfirmedBlock::new(ExecutedBlock {
+            block: make_first_block(chain_id),
+            outcome: BlockExecutionOutcome {
+                state_hash: block_state_hash,
+                ..BlockExecutionOutcome::default()
+            },
+        }));
+        let mut batch = Batch::new();
+        let key = bcs::to_bytes(&BaseKey::ConfirmedBlock(block.hash()))?;
+        batch.put_key_value(key, block.inner())?;
+        store.write_batch(batch).await?;
+
+        let mut chain = storage.load_chain(chain_id).await?;
+        let tip_state = chain.tip_state.get_mut();
+        tip_state.block_hash = Some(tip.unwrap_or(block.hash()));
+        tip_state.next_block_height = BlockHeight::from(1);
+        chain.execution_state_hash.set(Some(stored_state_hash));
+        chain.save().await?;
+        Ok((storage, block.hash()))
+    }
+
+    #[test]
+    fn test_confirmed_block_key_prefix() {
+        let key = bcs::to_bytes(&BaseKey::ConfirmedBlock(CryptoHash::test_hash("block"))).unwrap();
+        assert!(key.starts_with(CONFIRMED_BLOCK_KEY_PREFIX));
+    }
+
+    #[tokio::test]
+    async fn test_consistent_chain_is_verified() -> anyhow::Result<()> {
+        let chain_id = ChainId::root(0);
+        let state_hash = CryptoHash::test_hash("state");
+        let (storage, _) = storage_with_chain(chain_id, state_hash, None, state_hash).await?;
+
+        let mut verified_chains = 0;
+        let report = storage
+            .verify_chain_tips(|chains| verified_chains = chains)
+            .await?;
+
+        assert_eq!(report.chains, 1);
+        assert!(report.is_consistent());
+        assert_eq!(verified_chains, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_hash_mismatch_is_reported() -> anyhow::Result<()> {
+        let chain_id = ChainId::root(0);
+        let block_state_hash = CryptoHash::test_hash("certified state");
+        let stored_state_hash = CryptoHash::test_hash("corrupted state");
+        let (storage, block_hash) =
+            storage_with_chain(chain_id, block_state_hash, None, stored_state_hash).await?;
+
+        let report = storage.verify_chain_tips(|_| ()).await?;
+
+        assert_eq!(
+            report.mismatches,
+            [ChainTipMismatch::StateHash {
+                chain_id,
+                block_hash,
+                block_state_hash,
+                stored_state_hash: Some(stored_state_hash),
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_tip_block_is_reported() -> anyhow::Result<()> {
+        let chain_id = ChainId::root(0);
+        let state_hash = CryptoHash::test_hash("state");
+        let missing_hash = CryptoHash::test_hash("missing block");
+        let (storage, _) =
+            storage_with_chain(chain_id, state_hash, Some(missing_hash), state_hash).await?;
+
+        let report = storage.verify_chain_tips(|_| ()).await?;
+
+        assert_eq!(report.chains, 1);
+        assert_eq!(
+            report.mismatches,
+            [ChainTipMismatch::MissingBlock {
+                chain_id,
+                block_hash: missing_hash,
+            }]
+        );
+        Ok(())
+    }
+}
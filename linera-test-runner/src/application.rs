@@ -0,0 +1,155 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+
+use crate::cargo::{CargoCommand, WASM_TARGET};
+
+/// An application crate whose test matrix is run.
+#[derive(Clone, Debug)]
+pub struct Application {
+    /// The name of the crate.
+    pub name: String,
+    /// The directory of the crate.
+    pub path: PathBuf,
+    /// The name of the binary with the contract.
+    pub contract_binary: String,
+    /// The name of the binary with the service.
+    pub service_binary: String,
+    /// Whether the crate has a library target, which may contain unit tests.
+    pub has_library: bool,
+    /// The names of the integration tests of the crate.
+    pub integration_tests: Vec<String>,
+    /// The directory where `cargo` places its build artifacts for the crate.
+    pub target_directory: PathBuf,
+    /// The directories of the local crates that the application may depend on, including
+    /// its own. Changes to these invalidate the cached results.
+    pub local_sources: Vec<PathBuf>,
+}
+
+/// The subset of the output of `cargo metadata` that we need.
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    manifest_path: PathBuf,
+    /// The registry or repository of the package, or `None` for local crates.
+    source: Option<String>,
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+}
+
+impl Target {
+    fn is(&self, kind: &str) -> bool {
+        self.kind.iter().any(|target_kind| target_kind == kind)
+    }
+}
+
+impl Application {
+    /// Loads the description of the application crate in `path` from `cargo metadata`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Failed to find application at {}", path.display()))?;
+        let manifest_path = path.join("Cargo.toml");
+        ensure!(
+            manifest_path.is_file(),
+            "{} is not a crate: it has no Cargo.toml",
+            path.display()
+        );
+
+        let output = CargoCommand::new(&path, "metadata")
+            .args(["--format-version", "1"])
+            .run()?;
+        ensure!(
+            output.success,
+            "Failed to read the metadata of {}:\n{}",
+            path.display(),
+            output.stderr
+        );
+        let metadata: Metadata = serde_json::from_str(&output.stdout)
+            .context("Failed to parse the output of `cargo metadata`")?;
+        let mut local_sources = metadata
+            .packages
+            .iter()
+            .filter(|package| package.source.is_none())
+            .filter_map(|package| package.manifest_path.parent().map(Path::to_path_buf))
+            .collect::<Vec<_>>();
+        local_sources.sort();
+        let Some(package) = metadata
+            .packages
+            .into_iter()
+            .find(|package| package.manifest_path == manifest_path)
+        else {
+            bail!("Failed to find the package of {}", manifest_path.display());
+        };
+
+        let binaries = package
+            .targets
+            .iter()
+            .filter(|target| target.is("bin"))
+            .map(|target| target.name.clone())
+            .collect::<Vec<_>>();
+        let contract_binary = Self::find_binary(&binaries, "contract")?;
+        let service_binary = Self::find_binary(&binaries, "service")?;
+        let has_library = package.targets.iter().any(|target| target.is("lib"));
+        let integration_tests = package
+            .targets
+            .iter()
+            .filter(|target| target.is("test"))
+            .map(|target| target.name.clone())
+            .collect();
+
+        Ok(Application {
+            name: package.name,
+            path,
+            contract_binary,
+            service_binary,
+            has_library,
+            integration_tests,
+            target_directory: metadata.target_directory,
+            local_sources,
+        })
+    }
+
+    /// Returns the path of the compiled WebAssembly module of `binary`.
+    pub fn wasm_artifact(&self, binary: &str) -> PathBuf {
+        self.target_directory
+            .join(WASM_TARGET)
+            .join("release")
+            .join(format!("{binary}.wasm"))
+    }
+
+    /// Returns the paths of the compiled contract and service modules.
+    pub fn wasm_artifacts(&self) -> [PathBuf; 2] {
+        [
+            self.wasm_artifact(&self.contract_binary),
+            self.wasm_artifact(&self.service_binary),
+        ]
+    }
+
+    /// Finds the only binary whose name ends with `suffix`.
+    fn find_binary(binaries: &[String], suffix: &str) -> Result<String> {
+        let mut candidates = binaries.iter().filter(|name| name.ends_with(suffix));
+        match (candidates.next(), candidates.next()) {
+            (Some(binary), None) => Ok(binary.clone()),
+            (None, _) => bail!("The application has no binary ending with `{suffix}`"),
+            (Some(_), Some(_)) => {
+                bail!("The application has several binaries ending with `{suffix}`")
+            }
+        }
+    }
+}
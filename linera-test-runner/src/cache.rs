@@ -0,0 +1,289 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{application::Application, report::Step};
+
+/// The directories that never contain sources of the application.
+const IGNORED_DIRECTORIES: [&str; 3] = ["target", "node_modules", "web-frontend"];
+
+/// A digest of the sources an application is built from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint(String);
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of the sources of `application` and of its local dependencies.
+    ///
+    /// Files are identified by their path, size and modification time, so that the sources
+    /// don't need to be read.
+    pub fn compute(application: &Application) -> Result<Self> {
+        let mut files = Vec::new();
+        for directory in &application.local_sources {
+            collect_files(directory, &mut files)?;
+        }
+        if let Some(lock_file) = find_lock_file(&application.path) {
+            files.push(lock_file);
+        }
+        files.sort();
+        files.dedup();
+
+        let mut hasher = Sha3_256::new();
+        for file in files {
+            let metadata = fs::metadata(&file)
+                .with_context(|| format!("Failed to read the metadata of {}", file.display()))?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            hasher.update(file.to_string_lossy().as_bytes());
+            hasher.update(metadata.len().to_le_bytes());
+            hasher.update(modified.as_nanos().to_le_bytes());
+        }
+        let digest = hasher.finalize();
+        Ok(Fingerprint(
+            digest.iter().map(|byte| format!("{byte:02x}")).collect(),
+        ))
+    }
+}
+
+/// The WebAssembly artifacts and the passed steps of previous runs, for the sources with a
+/// given fingerprint.
+pub struct ArtifactCache {
+    directory: PathBuf,
+}
+
+/// The record of the cache for one application.
+#[derive(Default, Serialize, Deserialize)]
+struct CacheRecord {
+    fingerprint: Option<Fingerprint>,
+    passed_steps: BTreeSet<String>,
+}
+
+impl ArtifactCache {
+    /// Creates a cache stored in `directory`.
+    pub fn new(directory: PathBuf) -> Self {
+        ArtifactCache { directory }
+    }
+
+    /// Returns `true` if `step` passed in a previous run with the same `fingerprint`.
+    pub fn has_passed(
+        &self,
+        application: &Application,
+        fingerprint: &Fingerprint,
+        step: Step,
+    ) -> bool {
+        let record = self.read_record(application);
+        record.fingerprint.as_ref() == Some(fingerprint)
+            && record.passed_steps.contains(&step.key())
+    }
+
+    /// Remembers that `step` passed with the sources of the given `fingerprint`.
+    pub fn record_pass(
+        &self,
+        application: &Application,
+        fingerprint: &Fingerprint,
+        step: Step,
+    ) -> Result<()> {
+        let mut record = self.read_record(application);
+        if record.fingerprint.as_ref() != Some(fingerprint) {
+            record = CacheRecord {
+                fingerprint: Some(fingerprint.clone()),
+                passed_steps: BTreeSet::new(),
+            };
+        }
+        record.passed_steps.insert(step.key());
+        self.write_record(application, &record)
+    }
+
+    /// Copies the cached WebAssembly artifacts back to the target directory, if they were built
+    /// from the sources of the given `fingerprint`. Returns `true` if they were restored.
+    pub fn restore_artifacts(
+        &self,
+        application: &Application,
+        fingerprint: &Fingerprint,
+    ) -> Result<bool> {
+        if self.read_record(application).fingerprint.as_ref() != Some(fingerprint) {
+            return Ok(false);
+        }
+        let directory = self.application_directory(application);
+        let artifacts = application.wasm_artifacts();
+        if !artifacts
+            .iter()
+            .all(|artifact| cached_artifact(&directory, artifact).is_file())
+        {
+            return Ok(false);
+        }
+        for artifact in &artifacts {
+            if let Some(parent) = artifact.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::copy(cached_artifact(&directory, artifact), artifact)
+                .with_context(|| format!("Failed to restore {}", artifact.display()))?;
+        }
+        Ok(true)
+    }
+
+    /// Stores the freshly built WebAssembly artifacts of `application`.
+    pub fn store_artifacts(&self, application: &Application) -> Result<()> {
+        let directory = self.application_directory(application);
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+        for artifact in application.wasm_artifacts() {
+            fs::copy(&artifact, cached_artifact(&directory, &artifact))
+                .with_context(|| format!("Failed to cache {}", artifact.display()))?;
+        }
+        Ok(())
+    }
+
+    fn application_directory(&self, application: &Application) -> PathBuf {
+        self.directory.join(&application.name)
+    }
+
+    fn record_path(&self, application: &Application) -> PathBuf {
+        self.application_directory(application).join("record.json")
+    }
+
+    /// Reads the record of `application`, considering a missing or corrupted record as empty.
+    fn read_record(&self, application: &Application) -> CacheRecord {
+        fs::read(self.record_path(application))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_record(&self, application: &Application, record: &CacheRecord) -> Result<()> {
+        let directory = self.application_directory(application);
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+        let path = self.record_path(application);
+        fs::write(&path, serde_json::to_vec_pretty(record)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Returns the location of `artifact` in the cache directory of its application.
+fn cached_artifact(directory: &Path, artifact: &Path) -> PathBuf {
+    directory.join(artifact.file_name().unwrap_or_default())
+}
+
+/// Recursively lists the files in `directory`, skipping hidden and ignored directories.
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(directory)
+        .with_context(|| format!("Failed to list {}", directory.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && !IGNORED_DIRECTORIES.contains(&name.as_ref()) {
+                collect_files(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the `Cargo.lock` file of the workspace containing `path`.
+fn find_lock_file(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lock_file| lock_file.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_application(directory: &Path) -> Application {
+        let path = directory.join("app");
+        fs::create_dir_all(path.join("src")).unwrap();
+        fs::write(path.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        fs::write(path.join("src/contract.rs"), "fn main() {}\n").unwrap();
+        Application {
+            name: "app".to_owned(),
+            path: path.clone(),
+            contract_binary: "app_contract".to_owned(),
+            service_binary: "app_service".to_owned(),
+            has_library: false,
+            integration_tests: Vec::new(),
+            target_directory: path.join("target"),
+            local_sources: vec![path],
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_sources() {
+        let directory = tempfile::tempdir().unwrap();
+        let application = test_application(directory.path());
+        let fingerprint = Fingerprint::compute(&application).unwrap();
+        assert_eq!(Fingerprint::compute(&application).unwrap(), fingerprint);
+
+        fs::create_dir_all(application.target_directory.join("release")).unwrap();
+        fs::write(application.target_directory.join("release/app"), "binary").unwrap();
+        assert_eq!(Fingerprint::compute(&application).unwrap(), fingerprint);
+
+        fs::write(application.path.join("src/service.rs"), "fn main() {}\n").unwrap();
+        assert_ne!(Fingerprint::compute(&application).unwrap(), fingerprint);
+    }
+
+    #[test]
+    fn test_artifacts_and_passed_steps_are_restored() {
+        let directory = tempfile::tempdir().unwrap();
+        let application = test_application(directory.path());
+        let cache = ArtifactCache::new(directory.path().join("cache"));
+        let fingerprint = Fingerprint::compute(&application).unwrap();
+        let other_fingerprint = Fingerprint("other".to_owned());
+
+        assert!(!cache.restore_artifacts(&application, &fingerprint).unwrap());
+        for artifact in application.wasm_artifacts() {
+            fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+            fs::write(&artifact, b"\0asm").unwrap();
+        }
+        cache.store_artifacts(&application).unwrap();
+        cache
+            .record_pass(&application, &fingerprint, Step::BuildWasm)
+            .unwrap();
+
+        for artifact in application.wasm_artifacts() {
+            fs::remove_file(artifact).unwrap();
+        }
+        assert!(!cache
+            .restore_artifacts(&application, &other_fingerprint)
+            .unwrap());
+        assert!(cache.restore_artifacts(&application, &fingerprint).unwrap());
+        for artifact in application.wasm_artifacts() {
+            assert_eq!(fs::read(artifact).unwrap(), b"\0asm");
+        }
+
+        assert!(cache.has_passed(&application, &fingerprint, Step::BuildWasm));
+        assert!(!cache.has_passed(&application, &fingerprint, Step::UnitTests));
+        assert!(!cache.has_passed(&application, &other_fingerprint, Step::BuildWasm));
+
+        cache
+            .record_pass(&application, &other_fingerprint, Step::UnitTests)
+            .unwrap();
+        assert!(!cache.has_passed(&application, &fingerprint, Step::BuildWasm));
+        assert!(cache.has_passed(&application, &other_fingerprint, Step::UnitTests));
+    }
+}
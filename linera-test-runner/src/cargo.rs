@@ -0,0 +1,73 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    path::Path,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use tracing::debug;
+
+/// The WebAssembly target the applications are compiled to.
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// A `cargo` invocation in the directory of an application.
+pub struct CargoCommand {
+    command: Command,
+    description: String,
+}
+
+/// The outcome of a finished `cargo` invocation.
+pub struct CargoOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl CargoCommand {
+    /// Prepares a `cargo` invocation with the given subcommand, running in `directory`.
+    pub fn new(directory: &Path, subcommand: &str) -> Self {
+        let mut command = Command::new("cargo");
+        command.current_dir(directory).arg(subcommand);
+        CargoCommand {
+            command,
+            description: format!("cargo {subcommand}"),
+        }
+    }
+
+    /// Adds arguments to the invocation.
+    pub fn args<'a>(mut self, args: impl IntoIterator<Item = &'a str>) -> Self {
+        for arg in args {
+            self.command.arg(arg);
+            self.description.push(' ');
+            self.description.push_str(arg);
+        }
+        self
+    }
+
+    /// Sets an environment variable for the invocation.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.command.env(key, value);
+        self.description = format!("{key}={value} {}", self.description);
+        self
+    }
+
+    /// Runs the invocation to completion and captures its output.
+    pub fn run(mut self) -> Result<CargoOutput> {
+        debug!("Running `{}`", self.description);
+        let start = Instant::now();
+        let output = self
+            .command
+            .output()
+            .with_context(|| format!("Failed to run `{}`", self.description))?;
+        Ok(CargoOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            duration: start.elapsed(),
+        })
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! This crate provides a tool to run the test matrix of a Linera application.
+//!
+//! For a given application crate, the runner:
+//! 1. compiles the contract and service binaries to WebAssembly, reusing cached artifacts when
+//!    the sources didn't change;
+//! 2. runs the unit tests of the application, which use the mock system API;
+//! 3. runs the integration tests of the application once for each WebAssembly runtime
+//!    (Wasmer and Wasmtime);
+//! 4. prints a summary of the steps, with the failing tests of each step.
+//!
+//! ```bash
+//! cargo run -p linera-test-runner -- examples/counter
+//! ```
+
+#![allow(missing_docs)]
+
+pub mod application;
+pub mod cache;
+pub mod cargo;
+pub mod options;
+pub mod report;
+pub mod runner;
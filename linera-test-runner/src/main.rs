@@ -0,0 +1,36 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fs, process};
+
+use anyhow::{Context, Result};
+use linera_test_runner::{options::TestRunnerOptions, report::Summary, runner::TestRunner};
+use tracing::error;
+
+fn run(options: &TestRunnerOptions) -> Result<Summary> {
+    let runner = TestRunner::new(options)?;
+    let summary = runner.run()?;
+    println!("{summary}");
+    if let Some(path) = &options.summary_json {
+        let json = serde_json::to_string_pretty(&summary)?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write the summary to {}", path.display()))?;
+    }
+    Ok(summary)
+}
+
+fn main() {
+    let options = TestRunnerOptions::init();
+
+    linera_base::tracing::init("test-runner");
+
+    let error_code = match run(&options) {
+        Ok(summary) if summary.is_success() => 0,
+        Ok(_) => 1,
+        Err(msg) => {
+            error!("Error: {msg:?}");
+            2
+        }
+    };
+    process::exit(error_code);
+}
@@ -0,0 +1,67 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, path::PathBuf};
+
+use linera_version::VersionInfo;
+use serde::Serialize;
+
+#[derive(clap::Parser)]
+#[command(
+    name = "linera-test-runner",
+    version = VersionInfo::default_clap_str(),
+    about = "Executable to run the test matrix of a Linera application.",
+)]
+pub struct TestRunnerOptions {
+    /// The directory of the application crate to test.
+    pub application: PathBuf,
+
+    /// The list of comma separated WebAssembly runtimes to run the integration tests with.
+    #[arg(long, value_delimiter = ',', default_values = ["wasmer", "wasmtime"])]
+    pub runtimes: Vec<Runtime>,
+
+    /// The directory where the WebAssembly artifacts and the results of the previous runs are
+    /// cached. Defaults to a `linera-test-runner` directory in the target directory of the
+    /// application.
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Rebuild the application and rerun all tests, ignoring the cached results.
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Also write the summary of the run as JSON to the given file.
+    #[arg(long)]
+    pub summary_json: Option<PathBuf>,
+}
+
+impl TestRunnerOptions {
+    pub fn init() -> Self {
+        <TestRunnerOptions as clap::Parser>::parse()
+    }
+}
+
+/// A WebAssembly runtime the integration tests can run with.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    Wasmer,
+    Wasmtime,
+}
+
+impl Runtime {
+    /// The name of the runtime, as used by the `linera-sdk` feature and by the
+    /// `LINERA_WASM_RUNTIME` environment variable.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Runtime::Wasmer => "wasmer",
+            Runtime::Wasmtime => "wasmtime",
+        }
+    }
+}
+
+impl fmt::Display for Runtime {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.name())
+    }
+}
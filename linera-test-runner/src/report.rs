@@ -0,0 +1,340 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, time::Duration};
+
+use serde::{Serialize, Serializer};
+
+use crate::options::Runtime;
+
+/// A step of the test matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "runtime", rename_all = "kebab-case")]
+pub enum Step {
+    /// Compiling the contract and service to WebAssembly.
+    BuildWasm,
+    /// Running the unit tests, which use the mock system API.
+    UnitTests,
+    /// Running the integration tests with a WebAssembly runtime.
+    IntegrationTests(Runtime),
+}
+
+impl Step {
+    /// A unique name for the step, used to remember its results.
+    pub fn key(&self) -> String {
+        match self {
+            Step::BuildWasm => "build-wasm".to_owned(),
+            Step::UnitTests => "unit-tests".to_owned(),
+            Step::IntegrationTests(runtime) => format!("integration-tests-{runtime}"),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::BuildWasm => write!(formatter, "build-wasm"),
+            Step::UnitTests => write!(formatter, "unit-tests"),
+            Step::IntegrationTests(runtime) => write!(formatter, "integration-tests ({runtime})"),
+        }
+    }
+}
+
+/// The outcome of a step.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum StepStatus {
+    /// The step ran and succeeded.
+    Passed,
+    /// The step succeeded in a previous run and its inputs didn't change.
+    Cached,
+    /// The step ran and failed.
+    Failed { failures: Vec<Failure> },
+    /// The step didn't run.
+    Skipped { reason: String },
+}
+
+/// A failing test, or a compilation error.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    /// The name of the failing test, or `compilation` for compilation errors.
+    pub name: String,
+    /// The output explaining the failure.
+    pub message: String,
+}
+
+/// The report of a single step.
+#[derive(Clone, Debug, Serialize)]
+pub struct StepReport {
+    pub step: Step,
+    #[serde(flatten)]
+    pub status: StepStatus,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+}
+
+/// The report of a whole run of the test matrix.
+#[derive(Clone, Debug, Serialize)]
+pub struct Summary {
+    pub application: String,
+    pub steps: Vec<StepReport>,
+}
+
+impl Summary {
+    /// Returns `true` if no step failed.
+    pub fn is_success(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|report| !matches!(report.status, StepStatus::Failed { .. }))
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(formatter, "Test matrix of `{}`:", self.application)?;
+        let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+        for report in &self.steps {
+            let status = match &report.status {
+                StepStatus::Passed => "passed",
+                StepStatus::Cached => "cached",
+                StepStatus::Failed { .. } => "FAILED",
+                StepStatus::Skipped { .. } => "skipped",
+            };
+            writeln!(
+                formatter,
+                "  {:<30} {:<8} ({:.1}s)",
+                report.step.to_string(),
+                status,
+                report.duration.as_secs_f64()
+            )?;
+            match &report.status {
+                StepStatus::Passed | StepStatus::Cached => passed += 1,
+                StepStatus::Failed { failures } => {
+                    failed += 1;
+                    for failure in failures {
+                        let first_line = failure.message.lines().next().unwrap_or_default();
+                        writeln!(formatter, "      {}: {}", failure.name, first_line)?;
+                    }
+                }
+                StepStatus::Skipped { reason } => {
+                    skipped += 1;
+                    writeln!(formatter, "      {reason}")?;
+                }
+            }
+        }
+        write!(
+            formatter,
+            "{passed} step(s) passed, {failed} failed, {skipped} skipped"
+        )
+    }
+}
+
+fn serialize_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Extracts the failures from the output of a failed `cargo build` or `cargo test`.
+///
+/// Failing tests are read from the output of the test harness. If there are none, the
+/// compilation errors are reported instead, and as a last resort the end of the error output.
+pub fn parse_failures(stdout: &str, stderr: &str) -> Vec<Failure> {
+    let failures = parse_test_failures(stdout);
+    if !failures.is_empty() {
+        return failures;
+    }
+    let failures = parse_compilation_errors(stderr);
+    if !failures.is_empty() {
+        return failures;
+    }
+    let lines = stderr.lines().collect::<Vec<_>>();
+    let tail = &lines[lines.len().saturating_sub(20)..];
+    vec![Failure {
+        name: "cargo".to_owned(),
+        message: tail.join("\n"),
+    }]
+}
+
+/// Parses the `test <name> ... FAILED` lines and the `---- <name> stdout ----` sections
+/// printed by the test harness.
+fn parse_test_failures(stdout: &str) -> Vec<Failure> {
+    let mut failures = Vec::<Failure>::new();
+    for line in stdout.lines() {
+        if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.strip_suffix(" ... FAILED"))
+        {
+            failures.push(Failure {
+                name: name.to_owned(),
+                message: String::new(),
+            });
+        }
+    }
+
+    let mut current_section: Option<(&str, Vec<&str>)> = None;
+    let mut sections = Vec::new();
+    for line in stdout.lines() {
+        let header = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"));
+        if header.is_some() || line == "failures:" {
+            sections.extend(current_section.take());
+        }
+        if let Some(name) = header {
+            current_section = Some((name, Vec::new()));
+        } else if let Some((_, lines)) = &mut current_section {
+            lines.push(line);
+        }
+    }
+    sections.extend(current_section);
+
+    for (name, lines) in sections {
+        if let Some(failure) = failures.iter_mut().find(|failure| failure.name == name) {
+            failure.message = lines.join("\n").trim().to_owned();
+        }
+    }
+    failures
+}
+
+/// Parses the errors printed by the compiler, each ending with an empty line.
+fn parse_compilation_errors(stderr: &str) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut current_error: Option<Vec<&str>> = None;
+    for line in stderr.lines() {
+        let is_error = (line.starts_with("error[") || line.starts_with("error:"))
+            && !line.starts_with("error: could not compile")
+            && !line.starts_with("error: aborting due to");
+        if is_error {
+            failures.extend(current_error.take());
+            current_error = Some(vec![line]);
+        } else if line.trim().is_empty() {
+            failures.extend(current_error.take());
+        } else if let Some(lines) = &mut current_error {
+            lines.push(line);
+        }
+    }
+    failures.extend(current_error);
+    failures
+        .into_iter()
+        .map(|lines| Failure {
+            name: "compilation".to_owned(),
+            message: lines.join("\n"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_failures() {
+        let stdout = "\
+running 3 tests
+test single_transaction ... ok
+test check_increment ... FAILED
+test check_query ... FAILED
+
+failures:
+
+---- check_increment stdout ----
+thread 'check_increment' panicked at tests/single_chain.rs:42:5:
+assertion `left == right` failed
+  left: 2
+ right: 3
+
+---- check_query stdout ----
+thread 'check_query' panicked at tests/single_chain.rs:60:9:
+Query failed
+
+failures:
+    check_increment
+    check_query
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let failures = parse_failures(
+            stdout,
+            "error: test failed, to rerun pass `--test single_chain`",
+        );
+        assert_eq!(
+            failures,
+            vec![
+                Failure {
+                    name: "check_increment".to_owned(),
+                    message: "thread 'check_increment' panicked at tests/single_chain.rs:42:5:\n\
+                        assertion `left == right` failed\n  left: 2\n right: 3"
+                        .to_owned(),
+                },
+                Failure {
+                    name: "check_query".to_owned(),
+                    message: "thread 'check_query' panicked at tests/single_chain.rs:60:9:\n\
+                        Query failed"
+                        .to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_compilation_errors() {
+        let stderr = "\
+   Compiling counter v0.1.0 (/linera-protocol/examples/counter)
+error[E0425]: cannot find value `valu` in this scope
+  --> src/contract.rs:45:22
+   |
+45 |         self.state.value.set(valu);
+   |                              ^^^^ help: a local variable with a similar name exists: `value`
+
+error: could not compile `counter` (bin \"counter_contract\") due to 1 previous error
+";
+        let failures = parse_failures("", stderr);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "compilation");
+        assert!(failures[0].message.starts_with(
+            "error[E0425]: cannot find value `valu` in this scope\n  --> src/contract.rs:45:22"
+        ));
+    }
+
+    #[test]
+    fn test_unrecognized_failure_keeps_the_end_of_the_output() {
+        let failures = parse_failures("", "Killed");
+        assert_eq!(
+            failures,
+            vec![Failure {
+                name: "cargo".to_owned(),
+                message: "Killed".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_summary_success() {
+        let mut summary = Summary {
+            application: "counter".to_owned(),
+            steps: vec![
+                StepReport {
+                    step: Step::BuildWasm,
+                    status: StepStatus::Cached,
+                    duration: Duration::ZERO,
+                },
+                StepReport {
+                    step: Step::IntegrationTests(Runtime::Wasmtime),
+                    status: StepStatus::Skipped {
+                        reason: "No integration tests".to_owned(),
+                    },
+                    duration: Duration::ZERO,
+                },
+            ],
+        };
+        assert!(summary.is_success());
+
+        summary.steps.push(StepReport {
+            step: Step::UnitTests,
+            status: StepStatus::Failed {
+                failures: Vec::new(),
+            },
+            duration: Duration::from_secs(1),
+        });
+        assert!(!summary.is_success());
+    }
+}
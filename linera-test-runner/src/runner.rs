@@ -0,0 +1,184 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::{
+    application::Application,
+    cache::{ArtifactCache, Fingerprint},
+    cargo::{CargoCommand, CargoOutput, WASM_TARGET},
+    options::{Runtime, TestRunnerOptions},
+    report::{parse_failures, Step, StepReport, StepStatus, Summary},
+};
+
+/// The environment variable read by `linera-sdk` to select the WebAssembly runtime of the
+/// test validator.
+const WASM_RUNTIME_VARIABLE: &str = "LINERA_WASM_RUNTIME";
+
+/// Runs the steps of the test matrix of an application.
+pub struct TestRunner {
+    application: Application,
+    runtimes: Vec<Runtime>,
+    cache: Option<ArtifactCache>,
+    fingerprint: Fingerprint,
+}
+
+impl TestRunner {
+    /// Prepares the test matrix of the application selected by the `options`.
+    pub fn new(options: &TestRunnerOptions) -> Result<Self> {
+        let application = Application::load(&options.application)?;
+        let mut runtimes = options.runtimes.clone();
+        runtimes.sort();
+        runtimes.dedup();
+        let cache = (!options.no_cache).then(|| {
+            let directory = options
+                .cache_dir
+                .clone()
+                .unwrap_or_else(|| application.target_directory.join("linera-test-runner"));
+            ArtifactCache::new(directory)
+        });
+        let fingerprint = Fingerprint::compute(&application)?;
+        Ok(TestRunner {
+            application,
+            runtimes,
+            cache,
+            fingerprint,
+        })
+    }
+
+    /// Runs all the steps, continuing after failures, and returns their summary.
+    pub fn run(&self) -> Result<Summary> {
+        let mut steps = Vec::new();
+
+        let build = self.build_wasm()?;
+        let build_failed = matches!(build.status, StepStatus::Failed { .. });
+        steps.push(build);
+
+        steps.push(self.run_step(Step::UnitTests, |runner| runner.unit_tests())?);
+
+        for &runtime in &self.runtimes {
+            let step = Step::IntegrationTests(runtime);
+            let report = if self.application.integration_tests.is_empty() {
+                Self::skipped(step, "The application has no integration tests")
+            } else if build_failed {
+                Self::skipped(step, "The WebAssembly build failed")
+            } else {
+                self.run_step(step, |runner| runner.integration_tests(runtime))?
+            };
+            steps.push(report);
+        }
+
+        Ok(Summary {
+            application: self.application.name.clone(),
+            steps,
+        })
+    }
+
+    /// Compiles the contract and service to WebAssembly, unless the artifacts of the same
+    /// sources are cached.
+    fn build_wasm(&self) -> Result<StepReport> {
+        if let Some(cache) = &self.cache {
+            if cache.has_passed(&self.application, &self.fingerprint, Step::BuildWasm)
+                && cache.restore_artifacts(&self.application, &self.fingerprint)?
+            {
+                info!("Restored the cached WebAssembly artifacts");
+                return Ok(Self::cached(Step::BuildWasm));
+            }
+        }
+        let report = self.run_step(Step::BuildWasm, |runner| {
+            CargoCommand::new(&runner.application.path, "build")
+                .args(["--release", "--target", WASM_TARGET])
+                .args(["-p", &runner.application.name])
+                .args(["--bin", &runner.application.contract_binary])
+                .args(["--bin", &runner.application.service_binary])
+                .run()
+        })?;
+        if report.status == StepStatus::Passed {
+            if let Some(cache) = &self.cache {
+                cache.store_artifacts(&self.application)?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Runs the unit tests in the binaries and the library of the application.
+    fn unit_tests(&self) -> Result<CargoOutput> {
+        let mut command = CargoCommand::new(&self.application.path, "test").args([
+            "-p",
+            &self.application.name,
+            "--bins",
+        ]);
+        if self.application.has_library {
+            command = command.args(["--lib"]);
+        }
+        command.run()
+    }
+
+    /// Runs the integration tests of the application with the given runtime.
+    fn integration_tests(&self, runtime: Runtime) -> Result<CargoOutput> {
+        let feature = format!("linera-sdk/{runtime}");
+        let mut command = CargoCommand::new(&self.application.path, "test")
+            .args(["-p", &self.application.name, "--features", &feature])
+            .env(WASM_RUNTIME_VARIABLE, runtime.name());
+        for test in &self.application.integration_tests {
+            command = command.args(["--test", test]);
+        }
+        command.run()
+    }
+
+    /// Runs a step with `cargo`, unless it already passed with the same sources.
+    fn run_step(
+        &self,
+        step: Step,
+        run: impl FnOnce(&Self) -> Result<CargoOutput>,
+    ) -> Result<StepReport> {
+        if step != Step::BuildWasm {
+            if let Some(cache) = &self.cache {
+                if cache.has_passed(&self.application, &self.fingerprint, step) {
+                    info!("Skipping {step}, which already passed with the same sources");
+                    return Ok(Self::cached(step));
+                }
+            }
+        }
+
+        info!("Running {step}");
+        let output = run(self)?;
+        let status = if output.success {
+            if let Some(cache) = &self.cache {
+                cache.record_pass(&self.application, &self.fingerprint, step)?;
+            }
+            StepStatus::Passed
+        } else {
+            warn!("{step} failed");
+            StepStatus::Failed {
+                failures: parse_failures(&output.stdout, &output.stderr),
+            }
+        };
+        Ok(StepReport {
+            step,
+            status,
+            duration: output.duration,
+        })
+    }
+
+    fn cached(step: Step) -> StepReport {
+        StepReport {
+            step,
+            status: StepStatus::Cached,
+            duration: Duration::ZERO,
+        }
+    }
+
+    fn skipped(step: Step, reason: &str) -> StepReport {
+        StepReport {
+            step,
+            status: StepStatus::Skipped {
+                reason: reason.to_owned(),
+            },
+            duration: Duration::ZERO,
+        }
+    }
+}
@@ -43,6 +43,22 @@ fn custom_attribute(attributes: &[Attribute], key: &str) -> Option<LitStr> {
         .next()
 }
 
+/// Returns whether the `field` is marked with `#[view(skip_hash)]`, to be left out of the
+/// hash of the view.
+fn is_hash_skipped(field: &syn::Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("view"))
+        .any(|attribute| match attribute.parse_args::<syn::Path>() {
+            Ok(path) if path.is_ident("skip_hash") => true,
+            _ => panic!(
+                r#"Invalid `view` attribute syntax on a field. \
+                Expected syntax: `#[view(skip_hash)]`"#,
+            ),
+        })
+}
+
 fn context_and_constraints(
     attributes: &[Attribute],
     template_vect: &[syn::Ident],
@@ -292,7 +308,7 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
 
     let mut field_hashes_mut = Vec::new();
     let mut field_hashes = Vec::new();
-    for e in input.fields {
+    for e in input.fields.iter().filter(|field| !is_hash_skipped(field)) {
         let name = e.clone().ident.unwrap();
         field_hashes_mut.push(quote! { hasher.write_all(self.#name.hash_mut().await?.as_ref())?; });
         field_hashes.push(quote! { hasher.write_all(self.#name.hash().await?.as_ref())?; });
@@ -506,6 +522,20 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_generate_hash_view_code_skips_fields() {
+        let input: ItemStruct = parse_quote! {
+            struct TestView<C> {
+                register: RegisterView<C, usize>,
+                #[view(skip_hash)]
+                scratch: RegisterView<C, usize>,
+            }
+        };
+        let code = pretty(generate_hash_view_code(input));
+        assert!(code.contains("self.register.hash()"));
+        assert!(!code.contains("scratch"));
+    }
+
     #[test]
     fn test_generate_save_delete_view_code() {
         for context in SpecificContextInfo::test_cases() {
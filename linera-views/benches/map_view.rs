@@ -0,0 +1,123 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linera_base::time::{Duration, Instant};
+use linera_views::{
+    batch::Batch,
+    context::{create_test_memory_context, MemoryContext},
+    map_view::MapView,
+    views::View,
+};
+use tokio::runtime::Runtime;
+
+/// The number of entries loaded into the map.
+const N_ENTRIES: u64 = 50_000;
+
+/// Benchmarks staging many entries with [`MapView::extend`] and flushing them, against
+/// inserting the entries one at a time.
+fn bench_load_entries(criterion: &mut Criterion) {
+    criterion.bench_function("map_view_load_entries_with_extend", |bencher| {
+        bencher
+            .to_async(Runtime::new().expect("Failed to create Tokio runtime"))
+            .iter_custom(|iterations| async move {
+                let mut total_time = Duration::ZERO;
+
+                for _ in 0..iterations {
+                    let mut view = create_map_view().await;
+
+                    let measurement = Instant::now();
+                    view.extend((0..N_ENTRIES).map(|index| (index, index)))
+                        .expect("Failed to serialize indices");
+                    black_box(flush(&mut view));
+                    total_time += measurement.elapsed();
+                }
+
+                total_time
+            })
+    });
+
+    criterion.bench_function("map_view_load_entries_with_insert", |bencher| {
+        bencher
+            .to_async(Runtime::new().expect("Failed to create Tokio runtime"))
+            .iter_custom(|iterations| async move {
+                let mut total_time = Duration::ZERO;
+
+                for _ in 0..iterations {
+                    let mut view = create_map_view().await;
+
+                    let measurement = Instant::now();
+                    for index in 0..N_ENTRIES {
+                        view.insert(&index, index)
+                            .expect("Failed to serialize index");
+                    }
+                    black_box(flush(&mut view));
+                    total_time += measurement.elapsed();
+                }
+
+                total_time
+            })
+    });
+}
+
+/// Benchmarks removing many entries with [`MapView::remove_many`] and flushing the removals,
+/// against removing the entries one at a time.
+fn bench_remove_entries(criterion: &mut Criterion) {
+    criterion.bench_function("map_view_remove_entries_with_remove_many", |bencher| {
+        bencher
+            .to_async(Runtime::new().expect("Failed to create Tokio runtime"))
+            .iter_custom(|iterations| async move {
+                let mut total_time = Duration::ZERO;
+
+                for _ in 0..iterations {
+                    let mut view = create_map_view().await;
+
+                    let measurement = Instant::now();
+                    view.remove_many((0..N_ENTRIES).rev())
+                        .expect("Failed to serialize indices");
+                    black_box(flush(&mut view));
+                    total_time += measurement.elapsed();
+                }
+
+                total_time
+            })
+    });
+
+    criterion.bench_function("map_view_remove_entries_with_remove", |bencher| {
+        bencher
+            .to_async(Runtime::new().expect("Failed to create Tokio runtime"))
+            .iter_custom(|iterations| async move {
+                let mut total_time = Duration::ZERO;
+
+                for _ in 0..iterations {
+                    let mut view = create_map_view().await;
+
+                    let measurement = Instant::now();
+                    for index in (0..N_ENTRIES).rev() {
+                        view.remove(&index).expect("Failed to serialize index");
+                    }
+                    black_box(flush(&mut view));
+                    total_time += measurement.elapsed();
+                }
+
+                total_time
+            })
+    });
+}
+
+/// Creates an empty [`MapView`] in memory.
+async fn create_map_view() -> MapView<MemoryContext<()>, u64, u64> {
+    MapView::load(create_test_memory_context())
+        .await
+        .expect("Failed to create `MapView`")
+}
+
+/// Builds the batch with the staged changes of the `view`.
+fn flush(view: &mut MapView<MemoryContext<()>, u64, u64>) -> Batch {
+    let mut batch = Batch::new();
+    view.flush(&mut batch).expect("Failed to flush `MapView`");
+    batch
+}
+
+criterion_group!(benches, bench_load_entries, bench_remove_entries);
+criterion_main!(benches);
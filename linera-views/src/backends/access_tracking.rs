@@ -0,0 +1,354 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Count the reads and writes to a store for each sub-view, to find out which parts of a
+//! view are the most used.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(with_testing)]
+use crate::memory::MemoryStore;
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{
+        KeyIterable as _, KeyValueIterable as _, ReadableKeyValueStore, WithError,
+        WritableKeyValueStore,
+    },
+    views::MIN_VIEW_TAG,
+};
+
+/// The number of bytes of a field index in the keys of a view. The derive macros serialize
+/// the index of each field as an `i32`.
+const FIELD_INDEX_SIZE: usize = 4;
+
+/// The path of a sub-view, as the indices of the nested fields leading to it from the root
+/// view.
+///
+/// The path is derived from the prefix of a key: each field of a view derived with
+/// `RootView` or `View` has its keys prefixed by `MIN_VIEW_TAG` followed by the index of
+/// the field.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ViewPath(pub Vec<u32>);
+
+impl ViewPath {
+    /// Returns the path of the sub-view owning `key`, looking at most `depth` fields deep.
+    pub fn from_key(key: &[u8], depth: usize) -> Self {
+        let mut path = Vec::new();
+        let mut rest = key;
+        while path.len() < depth {
+            let Some((&MIN_VIEW_TAG, tail)) = rest.split_first() else {
+                break;
+            };
+            let Some(index) = tail.get(..FIELD_INDEX_SIZE) else {
+                break;
+            };
+            let index = u32::from_le_bytes(index.try_into().expect("slice has the right size"));
+            path.push(index);
+            rest = &tail[FIELD_INDEX_SIZE..];
+        }
+        ViewPath(path)
+    }
+}
+
+impl fmt::Display for ViewPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for index in &self.0 {
+            write!(f, "/{index}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> From<[u32; N]> for ViewPath {
+    fn from(indices: [u32; N]) -> Self {
+        ViewPath(indices.to_vec())
+    }
+}
+
+/// The accesses to one sub-view.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ViewAccessStats {
+    /// The number of read operations.
+    pub reads: u64,
+    /// The number of write operations.
+    pub writes: u64,
+    /// The number of bytes returned by the reads, keys included for prefix searches.
+    pub bytes_read: u64,
+    /// The number of bytes of the keys and values written.
+    pub bytes_written: u64,
+}
+
+/// The accesses to a store, grouped by sub-view.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ViewAccessReport {
+    /// How many nested fields are used to tell the sub-views apart.
+    depth: usize,
+    /// The accesses to each sub-view that was used.
+    stats: BTreeMap<ViewPath, ViewAccessStats>,
+}
+
+impl Default for ViewAccessReport {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DEPTH)
+    }
+}
+
+impl ViewAccessReport {
+    /// By default, only the fields of the root view are told apart.
+    pub const DEFAULT_DEPTH: usize = 1;
+
+    /// Creates an empty report, grouping the accesses by sub-views at most `depth` fields
+    /// deep.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    /// Records a read of `key`, returning `bytes` bytes.
+    pub fn record_read(&mut self, key: &[u8], bytes: usize) {
+        let stats = self.stats_mut(key);
+        stats.reads += 1;
+        stats.bytes_read += bytes as u64;
+    }
+
+    /// Records a write to `key`, of `bytes` bytes.
+    pub fn record_write(&mut self, key: &[u8], bytes: usize) {
+        let stats = self.stats_mut(key);
+        stats.writes += 1;
+        stats.bytes_written += bytes as u64;
+    }
+
+    /// Records the writes of a batch.
+    pub fn record_batch(&mut self, batch: &Batch) {
+        for operation in &batch.operations {
+            match operation {
+                WriteOperation::Put { key, value } => {
+                    self.record_write(key, key.len() + value.len())
+                }
+                WriteOperation::Delete { key } => self.record_write(key, key.len()),
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    self.record_write(key_prefix, key_prefix.len())
+                }
+            }
+        }
+    }
+
+    /// Adds the accesses recorded by another report.
+    pub fn merge(&mut self, other: &ViewAccessReport) {
+        for (path, other_stats) in &other.stats {
+            let stats = self.stats.entry(path.clone()).or_default();
+            stats.reads += other_stats.reads;
+            stats.writes += other_stats.writes;
+            stats.bytes_read += other_stats.bytes_read;
+            stats.bytes_written += other_stats.bytes_written;
+        }
+    }
+
+    /// Returns the accesses to the sub-view at `path`, if any.
+    pub fn get(&self, path: &ViewPath) -> Option<&ViewAccessStats> {
+        self.stats.get(path)
+    }
+
+    /// Returns the paths of the sub-views that were accessed.
+    pub fn paths(&self) -> impl Iterator<Item = &ViewPath> {
+        self.stats.keys()
+    }
+
+    /// Returns the accesses to each sub-view, ordered by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&ViewPath, &ViewAccessStats)> {
+        self.stats.iter()
+    }
+
+    /// Returns whether no access was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    /// Forgets the recorded accesses.
+    pub fn clear(&mut self) {
+        self.stats.clear();
+    }
+
+    fn stats_mut(&mut self, key: &[u8]) -> &mut ViewAccessStats {
+        let path = ViewPath::from_key(key, self.depth);
+        self.stats.entry(path).or_default()
+    }
+}
+
+impl fmt::Display for ViewAccessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (path, stats) in &self.stats {
+            writeln!(
+                f,
+                "{path}: {} reads ({} bytes), {} writes ({} bytes)",
+                stats.reads, stats.bytes_read, stats.writes, stats.bytes_written
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A store recording the accesses to a base store in a [`ViewAccessReport`].
+///
+/// Clones of the store share the same report.
+#[derive(Clone)]
+pub struct AccessTrackingStore<S> {
+    /// The store being accessed.
+    base: S,
+    report: Arc<Mutex<ViewAccessReport>>,
+}
+
+impl<S> WithError for AccessTrackingStore<S>
+where
+    S: WithError,
+{
+    type Error = S::Error;
+}
+
+impl<S> ReadableKeyValueStore for AccessTrackingStore<S>
+where
+    S: ReadableKeyValueStore + Send + Sync,
+{
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = S::SORTED_SEARCHES;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.base.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let value = self.base.read_value_bytes(key).await?;
+        let bytes = value.as_ref().map_or(0, Vec::len);
+        self.report().record_read(key, bytes);
+        Ok(value)
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        let result = self.base.contains_key(key).await?;
+        self.report().record_read(key, 0);
+        Ok(result)
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        let results = self.base.contains_keys(keys.clone()).await?;
+        let mut report = self.report();
+        for key in &keys {
+            report.record_read(key, 0);
+        }
+        Ok(results)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let values = self.base.read_multi_values_bytes(keys.clone()).await?;
+        let mut report = self.report();
+        for (key, value) in keys.iter().zip(&values) {
+            report.record_read(key, value.as_ref().map_or(0, Vec::len));
+        }
+        Ok(values)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        let keys = self
+            .base
+            .find_keys_by_prefix(key_prefix)
+            .await?
+            .iterator()
+            .map(|key| key.map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = keys.iter().map(Vec::len).sum();
+        self.report().record_read(key_prefix, bytes);
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        let key_values = self
+            .base
+            .find_key_values_by_prefix(key_prefix)
+            .await?
+            .into_iterator_owned()
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = key_values
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum();
+        self.report().record_read(key_prefix, bytes);
+        Ok(key_values)
+    }
+}
+
+impl<S> WritableKeyValueStore for AccessTrackingStore<S>
+where
+    S: WritableKeyValueStore + Send + Sync,
+{
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        self.report().record_batch(&batch);
+        self.base.write_batch(batch).await
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        self.base.clear_journal().await
+    }
+}
+
+impl<S> AccessTrackingStore<S> {
+    /// Creates a new store recording the accesses to `base`, grouped by the fields of the
+    /// root view.
+    pub fn new(base: S) -> Self {
+        Self::with_depth(base, ViewAccessReport::DEFAULT_DEPTH)
+    }
+
+    /// Creates a new store recording the accesses to `base`, grouped by sub-views at most
+    /// `depth` fields deep.
+    pub fn with_depth(base: S, depth: usize) -> Self {
+        Self {
+            base,
+            report: Arc::new(Mutex::new(ViewAccessReport::new(depth))),
+        }
+    }
+
+    /// Returns the base store.
+    pub fn base(&self) -> &S {
+        &self.base
+    }
+
+    /// Returns a copy of the accesses recorded so far.
+    pub fn access_report(&self) -> ViewAccessReport {
+        self.report().clone()
+    }
+
+    /// Returns the accesses recorded so far, and starts a new report.
+    pub fn take_access_report(&self) -> ViewAccessReport {
+        let mut report = self.report();
+        let depth = report.depth;
+        std::mem::replace(&mut *report, ViewAccessReport::new(depth))
+    }
+
+    fn report(&self) -> std::sync::MutexGuard<'_, ViewAccessReport> {
+        self.report
+            .lock()
+            .expect("AccessTrackingStore lock should not be poisoned")
+    }
+}
+
+/// A memory store recording its accesses.
+#[cfg(with_testing)]
+pub type AccessTrackingMemoryStore = AccessTrackingStore<MemoryStore>;
@@ -72,6 +72,7 @@ where
     } else {
         S2::MAX_KEY_SIZE
     };
+    const SORTED_SEARCHES: bool = S1::SORTED_SEARCHES && S2::SORTED_SEARCHES;
 
     type Keys = DualStoreKeys<S1::Keys, S2::Keys>;
     type KeyValues = DualStoreKeyValues<S1::KeyValues, S2::KeyValues>;
@@ -3,12 +3,14 @@
 
 //! Implements [`crate::store::KeyValueStore`] for the DynamoDB database.
 
-use std::{collections::HashMap, env, sync::Arc};
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+use std::{collections::HashMap, env, future::Future, sync::Arc, time::Duration};
 
 use async_lock::{Semaphore, SemaphoreGuard};
 use async_trait::async_trait;
 use aws_sdk_dynamodb::{
-    error::SdkError,
+    error::{ProvideErrorMetadata, SdkError},
     operation::{
         batch_write_item::BatchWriteItemError,
         create_table::CreateTableError,
@@ -28,7 +30,10 @@ use aws_sdk_dynamodb::{
 use aws_smithy_types::error::operation::BuildError;
 use futures::future::{join_all, FutureExt as _};
 use linera_base::ensure;
+use rand::Rng as _;
 use thiserror::Error;
+#[cfg(with_metrics)]
+use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec};
 
 #[cfg(with_metrics)]
 use crate::metering::MeteredStore;
@@ -156,6 +161,37 @@ const TEST_DYNAMO_DB_MAX_STREAM_QUERIES: usize = 10;
 /// See <https://docs.aws.amazon.com/amazondynamodb/latest/APIReference/API_TransactWriteItems.html>
 const MAX_TRANSACT_WRITE_ITEM_SIZE: usize = 100;
 
+#[cfg(with_metrics)]
+/// The number of DynamoDB requests that were throttled.
+static THROTTLED_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "dynamo_db_throttled_requests",
+        "Number of DynamoDB requests that were throttled",
+        &[],
+    )
+});
+
+#[cfg(with_metrics)]
+/// The number of DynamoDB requests that failed because they were throttled too many times.
+static THROTTLING_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "dynamo_db_throttling_failures",
+        "Number of DynamoDB requests that failed because they were throttled too many times",
+        &[],
+    )
+});
+
+/// The error codes of the requests that DynamoDB throttles.
+const THROTTLING_ERROR_CODES: [&str; 3] = [
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "ThrottlingException",
+];
+
+/// The reasons for which DynamoDB cancels the transactions that it throttles.
+const THROTTLING_CANCELLATION_REASONS: [&str; 2] =
+    ["ProvisionedThroughputExceeded", "ThrottlingError"];
+
 /// Keys of length 0 are not allowed, so we extend by having a prefix on start
 fn extend_root_key(root_key: &[u8]) -> Vec<u8> {
     let mut vec = vec![0];
@@ -313,6 +349,127 @@ impl TransactionBuilder {
     }
 }
 
+/// The consistency of the reads performed on DynamoDB.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DynamoDbReadConsistency {
+    /// Strongly consistent reads, which always observe the writes that completed before them.
+    #[default]
+    Strong,
+    /// Eventually consistent reads, which cost half as much but may return stale values.
+    Eventual,
+}
+
+/// How to retry the requests that DynamoDB throttles because they exceed the provisioned
+/// throughput or the account quotas.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DynamoDbRetryConfig {
+    /// The number of times a throttled request is retried before failing with
+    /// [`DynamoDbStoreInternalError::Throttled`].
+    pub max_retries: u32,
+    /// The longest delay before the first retry. It doubles with each retry.
+    pub base_delay: Duration,
+    /// The longest delay before any retry.
+    pub max_delay: Duration,
+}
+
+impl Default for DynamoDbRetryConfig {
+    fn default() -> Self {
+        DynamoDbRetryConfig {
+            max_retries: 10,
+            base_delay: Duration::from_millis(25),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl DynamoDbRetryConfig {
+    /// Returns the delay before the retry with the given index, counting from zero.
+    ///
+    /// The delay is random, up to an exponentially growing bound, so that the requests
+    /// throttled together are not all retried together.
+    fn delay(&self, retry: u32) -> Duration {
+        let bound = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        bound.mul_f64(rand::thread_rng().gen())
+    }
+}
+
+/// An error of a DynamoDB request, which may be due to throttling.
+trait MaybeThrottled {
+    /// Returns whether DynamoDB throttled the request, so that it may succeed later.
+    fn is_throttled(&self) -> bool;
+}
+
+impl MaybeThrottled for SdkError<GetItemError> {
+    fn is_throttled(&self) -> bool {
+        has_throttling_error_code(self)
+    }
+}
+
+impl MaybeThrottled for SdkError<QueryError> {
+    fn is_throttled(&self) -> bool {
+        has_throttling_error_code(self)
+    }
+}
+
+impl MaybeThrottled for SdkError<TransactWriteItemsError> {
+    fn is_throttled(&self) -> bool {
+        if has_throttling_error_code(self) {
+            return true;
+        }
+        let Some(TransactWriteItemsError::TransactionCanceledException(exception)) =
+            self.as_service_error()
+        else {
+            return false;
+        };
+        exception.cancellation_reasons().iter().any(|reason| {
+            reason
+                .code()
+                .is_some_and(|code| THROTTLING_CANCELLATION_REASONS.contains(&code))
+        })
+    }
+}
+
+fn has_throttling_error_code(error: &impl ProvideErrorMetadata) -> bool {
+    error
+        .code()
+        .is_some_and(|code| THROTTLING_ERROR_CODES.contains(&code))
+}
+
+/// Sends a request with `send`, and sends it again after a delay as long as DynamoDB
+/// throttles it, within the retry budget of the `config`.
+async fn retry_throttled<T, E, F>(
+    config: &DynamoDbRetryConfig,
+    mut send: impl FnMut() -> F,
+) -> Result<T, DynamoDbStoreInternalError>
+where
+    F: Future<Output = Result<T, E>>,
+    E: MaybeThrottled,
+    DynamoDbStoreInternalError: From<E>,
+{
+    let mut retry = 0;
+    loop {
+        match send().await {
+            Err(error) if error.is_throttled() => {
+                #[cfg(with_metrics)]
+                THROTTLED_REQUESTS.with_label_values(&[]).inc();
+                if retry == config.max_retries {
+                    #[cfg(with_metrics)]
+                    THROTTLING_FAILURES.with_label_values(&[]).inc();
+                    return Err(DynamoDbStoreInternalError::Throttled {
+                        attempts: retry.saturating_add(1),
+                    });
+                }
+                tokio::time::sleep(config.delay(retry)).await;
+                retry += 1;
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
 /// A DynamoDB client.
 #[derive(Clone, Debug)]
 pub struct DynamoDbStoreInternal {
@@ -320,6 +477,8 @@ pub struct DynamoDbStoreInternal {
     namespace: String,
     semaphore: Option<Arc<Semaphore>>,
     max_stream_queries: usize,
+    consistent_read: bool,
+    retry_config: DynamoDbRetryConfig,
     root_key: Vec<u8>,
 }
 
@@ -330,6 +489,10 @@ pub struct DynamoDbStoreInternalConfig {
     config: aws_sdk_dynamodb::Config,
     /// The common configuration of the key value store
     common_config: CommonStoreInternalConfig,
+    /// The consistency of the reads
+    read_consistency: DynamoDbReadConsistency,
+    /// How to retry the throttled requests
+    retry_config: DynamoDbRetryConfig,
 }
 
 impl AdminKeyValueStore for DynamoDbStoreInternal {
@@ -351,6 +514,8 @@ impl AdminKeyValueStore for DynamoDbStoreInternal {
             .max_concurrent_queries
             .map(|n| Arc::new(Semaphore::new(n)));
         let max_stream_queries = config.common_config.max_stream_queries;
+        let consistent_read = config.read_consistency == DynamoDbReadConsistency::Strong;
+        let retry_config = config.retry_config;
         let namespace = namespace.to_string();
         let root_key = root_key.to_vec();
         Ok(Self {
@@ -358,6 +523,8 @@ impl AdminKeyValueStore for DynamoDbStoreInternal {
             namespace,
             semaphore,
             max_stream_queries,
+            consistent_read,
+            retry_config,
             root_key,
         })
     }
@@ -367,12 +534,16 @@ impl AdminKeyValueStore for DynamoDbStoreInternal {
         let namespace = self.namespace.clone();
         let semaphore = self.semaphore.clone();
         let max_stream_queries = self.max_stream_queries;
+        let consistent_read = self.consistent_read;
+        let retry_config = self.retry_config;
         let root_key = root_key.to_vec();
         Ok(Self {
             client,
             namespace,
             semaphore,
             max_stream_queries,
+            consistent_read,
+            retry_config,
             root_key,
         })
     }
@@ -579,21 +750,25 @@ impl DynamoDbStoreInternal {
     ) -> Result<QueryOutput, DynamoDbStoreInternalError> {
         let _guard = self.acquire().await;
         let big_root = extend_root_key(root_key);
-        let response = self
-            .client
-            .query()
-            .table_name(&self.namespace)
-            .projection_expression(attribute_str)
-            .key_condition_expression(format!(
-                "{PARTITION_ATTRIBUTE} = :partition and begins_with({KEY_ATTRIBUTE}, :prefix)"
-            ))
-            .expression_attribute_values(":partition", AttributeValue::B(Blob::new(big_root)))
-            .expression_attribute_values(":prefix", AttributeValue::B(Blob::new(key_prefix)))
-            .set_exclusive_start_key(start_key_map)
-            .send()
-            .boxed()
-            .await?;
-        Ok(response)
+        retry_throttled(&self.retry_config, || {
+            self.client
+                .query()
+                .table_name(&self.namespace)
+                .consistent_read(self.consistent_read)
+                .projection_expression(attribute_str)
+                .key_condition_expression(format!(
+                    "{PARTITION_ATTRIBUTE} = :partition and begins_with({KEY_ATTRIBUTE}, :prefix)"
+                ))
+                .expression_attribute_values(
+                    ":partition",
+                    AttributeValue::B(Blob::new(big_root.clone())),
+                )
+                .expression_attribute_values(":prefix", AttributeValue::B(Blob::new(key_prefix)))
+                .set_exclusive_start_key(start_key_map.clone())
+                .send()
+                .boxed()
+        })
+        .await
     }
 
     async fn read_value_bytes_general(
@@ -601,14 +776,16 @@ impl DynamoDbStoreInternal {
         key_db: HashMap<String, AttributeValue>,
     ) -> Result<Option<Vec<u8>>, DynamoDbStoreInternalError> {
         let _guard = self.acquire().await;
-        let response = self
-            .client
-            .get_item()
-            .table_name(&self.namespace)
-            .set_key(Some(key_db))
-            .send()
-            .boxed()
-            .await?;
+        let response = retry_throttled(&self.retry_config, || {
+            self.client
+                .get_item()
+                .table_name(&self.namespace)
+                .consistent_read(self.consistent_read)
+                .set_key(Some(key_db.clone()))
+                .send()
+                .boxed()
+        })
+        .await?;
 
         match response.item {
             Some(mut item) => {
@@ -624,15 +801,17 @@ impl DynamoDbStoreInternal {
         key_db: HashMap<String, AttributeValue>,
     ) -> Result<bool, DynamoDbStoreInternalError> {
         let _guard = self.acquire().await;
-        let response = self
-            .client
-            .get_item()
-            .table_name(&self.namespace)
-            .set_key(Some(key_db))
-            .projection_expression(PARTITION_ATTRIBUTE)
-            .send()
-            .boxed()
-            .await?;
+        let response = retry_throttled(&self.retry_config, || {
+            self.client
+                .get_item()
+                .table_name(&self.namespace)
+                .consistent_read(self.consistent_read)
+                .set_key(Some(key_db.clone()))
+                .projection_expression(PARTITION_ATTRIBUTE)
+                .send()
+                .boxed()
+        })
+        .await?;
 
         Ok(response.item.is_some())
     }
@@ -843,6 +1022,8 @@ impl WithError for DynamoDbStoreInternal {
 
 impl ReadableKeyValueStore for DynamoDbStoreInternal {
     const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    // Queries return the items in ascending order of their sort key.
+    const SORTED_SEARCHES: bool = true;
     type Keys = DynamoDbKeys;
     type KeyValues = DynamoDbKeyValues;
 
@@ -939,12 +1120,16 @@ impl DirectWritableKeyValueStore for DynamoDbStoreInternal {
         }
         if !builder.transacts.is_empty() {
             let _guard = self.acquire().await;
-            self.client
-                .transact_write_items()
-                .set_transact_items(Some(builder.transacts))
-                .send()
-                .boxed()
-                .await?;
+            // A transaction is applied entirely or not at all, so sending it again never
+            // applies some of its items twice.
+            retry_throttled(&self.retry_config, || {
+                self.client
+                    .transact_write_items()
+                    .set_transact_items(Some(builder.transacts.clone()))
+                    .send()
+                    .boxed()
+            })
+            .await?;
         }
         Ok(())
     }
@@ -1056,6 +1241,14 @@ pub enum DynamoDbStoreInternalError {
     /// An error occurred while building an object
     #[error(transparent)]
     Build(#[from] Box<BuildError>),
+
+    /// DynamoDB kept throttling a request after all the retries allowed by the
+    /// [`DynamoDbRetryConfig`].
+    #[error("DynamoDB throttled the request {attempts} times in a row")]
+    Throttled {
+        /// The number of times the request was sent.
+        attempts: u32,
+    },
 }
 
 impl<InnerError> From<SdkError<InnerError>> for DynamoDbStoreInternalError
@@ -1126,6 +1319,8 @@ impl TestKeyValueStore for JournalingKeyValueStore<DynamoDbStoreInternal> {
         Ok(DynamoDbStoreInternalConfig {
             config,
             common_config,
+            read_consistency: DynamoDbReadConsistency::Strong,
+            retry_config: DynamoDbRetryConfig::default(),
         })
     }
 }
@@ -1165,20 +1360,157 @@ impl DynamoDbStoreConfig {
         let inner_config = DynamoDbStoreInternalConfig {
             config,
             common_config: common_config.reduced(),
+            read_consistency: DynamoDbReadConsistency::default(),
+            retry_config: DynamoDbRetryConfig::default(),
         };
         DynamoDbStoreConfig {
             inner_config,
             cache_size: common_config.cache_size,
         }
     }
+
+    /// Sets the consistency of the reads. Strongly consistent reads are used by default, so
+    /// that chain states are never read stale right after being written.
+    pub fn with_read_consistency(mut self, read_consistency: DynamoDbReadConsistency) -> Self {
+        self.inner_config.read_consistency = read_consistency;
+        self
+    }
+
+    /// Sets how to retry the requests that DynamoDB throttles.
+    pub fn with_retry_config(mut self, retry_config: DynamoDbRetryConfig) -> Self {
+        self.inner_config.retry_config = retry_config;
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeMap, time::Duration};
+
     use bcs::serialized_size;
+    use futures::future;
 
+    use super::{retry_throttled, DynamoDbRetryConfig, DynamoDbStoreInternalError, MaybeThrottled};
     use crate::common::get_uleb128_size;
 
+    /// The errors of a mock DynamoDB client.
+    #[derive(Debug)]
+    enum MockError {
+        Throttled,
+        MissingValue,
+    }
+
+    impl MaybeThrottled for MockError {
+        fn is_throttled(&self) -> bool {
+            matches!(self, MockError::Throttled)
+        }
+    }
+
+    impl From<MockError> for DynamoDbStoreInternalError {
+        fn from(error: MockError) -> Self {
+            match error {
+                MockError::Throttled => panic!("Throttling errors should be retried"),
+                MockError::MissingValue => DynamoDbStoreInternalError::MissingValue,
+            }
+        }
+    }
+
+    /// A mock DynamoDB table, which throttles the first requests it receives.
+    #[derive(Default)]
+    struct MockTable {
+        throttled_requests: u32,
+        received_requests: u32,
+        writes: BTreeMap<Vec<u8>, u32>,
+    }
+
+    impl MockTable {
+        fn throttling(throttled_requests: u32) -> Self {
+            MockTable {
+                throttled_requests,
+                ..MockTable::default()
+            }
+        }
+
+        /// Applies all the writes of a transaction, unless the request is throttled.
+        fn transact_write_items(
+            &mut self,
+            keys: &[Vec<u8>],
+        ) -> future::Ready<Result<(), MockError>> {
+            self.received_requests += 1;
+            if self.received_requests <= self.throttled_requests {
+                return future::ready(Err(MockError::Throttled));
+            }
+            for key in keys {
+                *self.writes.entry(key.clone()).or_default() += 1;
+            }
+            future::ready(Ok(()))
+        }
+    }
+
+    fn retry_config(max_retries: u32) -> DynamoDbRetryConfig {
+        DynamoDbRetryConfig {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_throttled_writes_are_retried_once_each() {
+        let keys = vec![vec![1], vec![2], vec![3]];
+        let mut table = MockTable::throttling(3);
+        retry_throttled(&retry_config(3), || table.transact_write_items(&keys))
+            .await
+            .unwrap();
+        assert_eq!(table.received_requests, 4);
+        assert_eq!(
+            table.writes,
+            BTreeMap::from([(vec![1], 1), (vec![2], 1), (vec![3], 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_throttling_fails_after_the_retry_budget() {
+        let mut table = MockTable::throttling(10);
+        let result =
+            retry_throttled(&retry_config(3), || table.transact_write_items(&[vec![1]])).await;
+        assert!(matches!(
+            result,
+            Err(DynamoDbStoreInternalError::Throttled { attempts: 4 })
+        ));
+        assert_eq!(table.received_requests, 4);
+        assert!(table.writes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_retried() {
+        let mut requests = 0;
+        let result = retry_throttled(&retry_config(3), || {
+            requests += 1;
+            future::ready(Err::<(), _>(MockError::MissingValue))
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(DynamoDbStoreInternalError::MissingValue)
+        ));
+        assert_eq!(requests, 1);
+    }
+
+    #[test]
+    fn test_retry_delays_are_bounded() {
+        let config = DynamoDbRetryConfig {
+            max_retries: 10,
+            base_delay: Duration::from_millis(25),
+            max_delay: Duration::from_secs(1),
+        };
+        for retry in 0..config.max_retries {
+            let bound = Duration::from_millis(25 << retry).min(config.max_delay);
+            assert!(config.delay(retry) <= bound);
+        }
+        assert!(config.delay(u32::MAX) <= config.max_delay);
+    }
+
     #[test]
     fn test_serialization_len() {
         for n in [0, 10, 127, 128, 129, 16383, 16384, 20000] {
@@ -0,0 +1,704 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encrypt the values of a given store at rest, with support for rotating the encryption key.
+//!
+//! Every value is encrypted with AES-256-GCM and stored in an envelope made of the id of the
+//! key that was used, the nonce and the ciphertext. Keys are left in clear so that prefix
+//! searches keep working; they are authenticated as associated data together with the root
+//! key, so that an encrypted value cannot be moved to a different key or root key.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, iter,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use async_lock::RwLock;
+use linera_base::ensure;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{
+        AdminKeyValueStore, KeyIterable as _, KeyValueIterable as _, KeyValueStoreError,
+        ReadableKeyValueStore, WithError, WritableKeyValueStore,
+    },
+};
+#[cfg(with_testing)]
+use crate::{memory::MemoryStore, store::TestKeyValueStore};
+
+/// The size of the key id at the start of an envelope.
+const KEY_ID_SIZE: usize = 4;
+
+/// The size of the nonce following the key id in an envelope.
+const NONCE_SIZE: usize = 12;
+
+/// The size of the authentication tag at the end of an envelope.
+const TAG_SIZE: usize = 16;
+
+/// The number of bytes added to every value by the encryption.
+pub const ENVELOPE_OVERHEAD: usize = KEY_ID_SIZE + NONCE_SIZE + TAG_SIZE;
+
+/// The composed error type built from the inner error type.
+#[derive(Error, Debug)]
+pub enum EncryptionError<E> {
+    /// inner store error
+    #[error(transparent)]
+    InnerStoreError(#[from] E),
+
+    /// The value was encrypted with a key that is not configured
+    #[error("the value was encrypted with the unknown key {0}")]
+    UnknownKey(u32),
+
+    /// The value is too short to be an encrypted envelope
+    #[error("the value is too short to be an encrypted envelope")]
+    TooShortValue,
+
+    /// The value could not be encrypted
+    #[error("failed to encrypt the value")]
+    EncryptionFailed,
+
+    /// The value could not be decrypted or authenticated
+    #[error("failed to decrypt the value")]
+    DecryptionFailed,
+
+    /// Two configured keys have the same id
+    #[error("the key id {0} is configured more than once")]
+    DuplicateKeyId(u32),
+}
+
+impl<E: KeyValueStoreError> From<bcs::Error> for EncryptionError<E> {
+    fn from(error: bcs::Error) -> Self {
+        let error = E::from(error);
+        EncryptionError::InnerStoreError(error)
+    }
+}
+
+impl<E: KeyValueStoreError + 'static> KeyValueStoreError for EncryptionError<E> {
+    const BACKEND: &'static str = "encryption";
+}
+
+/// An AES-256 key, together with the id that is written next to the values it encrypts.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EncryptionKey {
+    /// The id of the key. It must be unique among the keys of a configuration.
+    pub id: u32,
+    /// The secret key material.
+    pub bytes: [u8; 32],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The keys of an [`EncryptedStore`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    /// The key used to encrypt the values being written.
+    pub current_key: EncryptionKey,
+    /// The previous keys, still used to decrypt the values that have not been re-encrypted.
+    pub retired_keys: Vec<EncryptionKey>,
+}
+
+impl EncryptionConfig {
+    /// Creates a configuration with a single key.
+    pub fn new(current_key: EncryptionKey) -> Self {
+        EncryptionConfig {
+            current_key,
+            retired_keys: Vec::new(),
+        }
+    }
+
+    /// Returns the configuration where `current_key` replaces the current key, which is
+    /// retired.
+    pub fn rotate(mut self, current_key: EncryptionKey) -> Self {
+        let retired_key = std::mem::replace(&mut self.current_key, current_key);
+        self.retired_keys.push(retired_key);
+        self
+    }
+
+    /// Creates a configuration with a fixed key, to be used in tests.
+    #[cfg(with_testing)]
+    pub fn new_for_testing() -> Self {
+        EncryptionConfig::new(EncryptionKey {
+            id: 0,
+            bytes: [0; 32],
+        })
+    }
+}
+
+/// The configuration type for the `EncryptedStore`.
+pub struct EncryptedStoreConfig<C> {
+    /// The inner configuration of the `EncryptedStore`.
+    pub inner_config: C,
+    /// The keys used by the `EncryptedStore`.
+    pub encryption: EncryptionConfig,
+}
+
+/// The number of values re-encrypted at once. It bounds the time during which the writes
+/// wait for a chunk of re-encrypted values to be written.
+const REENCRYPTION_CHUNK_SIZE: usize = 100;
+
+/// The root key under which the root keys holding encrypted values are recorded, so that
+/// they can all be re-encrypted. The root keys used by the storage never start with this byte.
+const ROOT_KEY_REGISTRY: &[u8] = b"\xffencrypted_root_keys";
+
+/// The prefix of the keys of the root key registry.
+const REGISTERED_ROOT_KEY_PREFIX: &[u8] = &[0];
+
+/// The locks shared by the stores connected to the same namespace in this process, indexed
+/// by the name of the inner store and the namespace.
+static REWRITE_LOCKS: LazyLock<Mutex<HashMap<(String, String), Arc<RwLock<()>>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Returns the lock shared by the stores connected to the given namespace in this process.
+fn shared_rewrite_lock(store_name: String, namespace: &str) -> Arc<RwLock<()>> {
+    let mut locks = REWRITE_LOCKS
+        .lock()
+        .expect("REWRITE_LOCKS lock should not be poisoned");
+    locks
+        .entry((store_name, namespace.to_string()))
+        .or_default()
+        .clone()
+}
+
+/// The ciphers of the configured keys.
+struct Keyring {
+    /// The id of the key used to encrypt new values.
+    current_id: u32,
+    /// The ciphers of all the configured keys, indexed by key id.
+    ciphers: HashMap<u32, Aes256Gcm>,
+}
+
+/// The progress of re-encrypting the values of an [`EncryptedStore`] under its current key.
+///
+/// It can be persisted after each chunk of values, so that an interrupted re-encryption
+/// resumes where it stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReEncryptionProgress {
+    /// The root key being visited. Root keys are visited in increasing order.
+    pub root_key: Option<Vec<u8>>,
+    /// The first byte of the next keys to visit under the root key. It is 256 once all the
+    /// keys of the root key have been visited.
+    pub next_prefix: u16,
+    /// The last key visited with the current prefix, if any.
+    pub last_key: Option<Vec<u8>>,
+    /// The number of values rewritten so far.
+    pub rewritten_values: u64,
+    /// Whether all the root keys have been visited.
+    pub complete: bool,
+}
+
+impl ReEncryptionProgress {
+    /// Returns whether all the keys have been visited.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+/// A key-value store whose values are encrypted before reaching the wrapped store.
+///
+/// Values written by previous keys remain readable as long as the keys are listed as retired
+/// in the [`EncryptionConfig`]. [`EncryptedStore::reencrypt`] rewrites them under the current
+/// key while the store is in use, after which the retired keys can be dropped.
+#[derive(Clone)]
+pub struct EncryptedStore<K> {
+    /// The underlying store of the transformed store.
+    store: K,
+    /// The store recording the root keys that hold encrypted values.
+    registry: K,
+    /// The root key of the underlying store, authenticated together with the keys.
+    root_key: Vec<u8>,
+    /// The configured keys.
+    keyring: Arc<Keyring>,
+    /// Held for reading by writes and for writing while a chunk of re-encrypted values is
+    /// written, so that a re-encrypted value never overwrites a concurrent write. It is
+    /// shared by the stores connected to the same namespace in this process.
+    rewrite_lock: Arc<RwLock<()>>,
+    /// The root keys known to be recorded in the registry.
+    registered_root_keys: Arc<Mutex<HashSet<Vec<u8>>>>,
+}
+
+impl<K> WithError for EncryptedStore<K>
+where
+    K: WithError,
+    K::Error: 'static,
+{
+    type Error = EncryptionError<K::Error>;
+}
+
+impl<K> ReadableKeyValueStore for EncryptedStore<K>
+where
+    K: ReadableKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    // Keys are not encrypted.
+    const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = K::SORTED_SEARCHES;
+    type Keys = K::Keys;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let envelope = self.store.read_value_bytes(key).await?;
+        envelope
+            .map(|envelope| self.decrypt(key, &envelope))
+            .transpose()
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        Ok(self.store.contains_key(key).await?)
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        Ok(self.store.contains_keys(keys).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let envelopes = self.store.read_multi_values_bytes(keys.clone()).await?;
+        keys.iter()
+            .zip(envelopes)
+            .map(|(key, envelope)| {
+                envelope
+                    .map(|envelope| self.decrypt(key, &envelope))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        Ok(self.store.find_keys_by_prefix(key_prefix).await?)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        let key_envelopes = self.store.find_key_values_by_prefix(key_prefix).await?;
+        let mut key_values = Vec::new();
+        for entry in key_envelopes.into_iterator_owned() {
+            let (suffix, envelope) = entry?;
+            let mut key = key_prefix.to_vec();
+            key.extend(&suffix);
+            let value = self.decrypt(&key, &envelope)?;
+            key_values.push((suffix, value));
+        }
+        Ok(key_values)
+    }
+}
+
+impl<K> WritableKeyValueStore for EncryptedStore<K>
+where
+    K: WritableKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    const MAX_VALUE_SIZE: usize = K::MAX_VALUE_SIZE - ENVELOPE_OVERHEAD;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let mut batch_new = Batch::new();
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Delete { key } => {
+                    batch_new.delete_key(key);
+                }
+                WriteOperation::Put { key, value } => {
+                    let envelope = self.encrypt(&key, &value)?;
+                    batch_new.put_key_value_bytes(key, envelope);
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    batch_new.delete_key_prefix(key_prefix);
+                }
+            }
+        }
+        self.register_root_key().await?;
+        let _guard = self.rewrite_lock.read().await;
+        Ok(self.store.write_batch(batch_new).await?)
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        Ok(self.store.clear_journal().await?)
+    }
+}
+
+impl<K> AdminKeyValueStore for EncryptedStore<K>
+where
+    K: AdminKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    type Config = EncryptedStoreConfig<K::Config>;
+
+    fn get_name() -> String {
+        format!("encrypted {}", K::get_name())
+    }
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, Self::Error> {
+        let store = K::connect(&config.inner_config, namespace, root_key).await?;
+        EncryptedStore::new(store, namespace, root_key, &config.encryption)
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, Self::Error> {
+        let store = self.store.clone_with_root_key(root_key)?;
+        Ok(Self {
+            store,
+            registry: self.registry.clone(),
+            root_key: root_key.to_vec(),
+            keyring: self.keyring.clone(),
+            rewrite_lock: self.rewrite_lock.clone(),
+            registered_root_keys: self.registered_root_keys.clone(),
+        })
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        Ok(K::list_all(&config.inner_config).await?)
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(K::delete_all(&config.inner_config).await?)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        Ok(K::exists(&config.inner_config, namespace).await?)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(K::create(&config.inner_config, namespace).await?)
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        Ok(K::delete(&config.inner_config, namespace).await?)
+    }
+}
+
+#[cfg(with_testing)]
+impl<K> TestKeyValueStore for EncryptedStore<K>
+where
+    K: TestKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    async fn new_test_config() -> Result<EncryptedStoreConfig<K::Config>, Self::Error> {
+        let inner_config = K::new_test_config().await?;
+        Ok(EncryptedStoreConfig {
+            inner_config,
+            encryption: EncryptionConfig::new_for_testing(),
+        })
+    }
+}
+
+impl<K> EncryptedStore<K>
+where
+    K: AdminKeyValueStore,
+{
+    /// Creates a new store that encrypts the values written to `store` with the given keys.
+    /// The `store` must be connected to the given namespace and root key.
+    pub fn new(
+        store: K,
+        namespace: &str,
+        root_key: &[u8],
+        config: &EncryptionConfig,
+    ) -> Result<Self, EncryptionError<K::Error>> {
+        let mut ciphers = HashMap::new();
+        for key in iter::once(&config.current_key).chain(&config.retired_keys) {
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.bytes));
+            ensure!(
+                ciphers.insert(key.id, cipher).is_none(),
+                EncryptionError::DuplicateKeyId(key.id)
+            );
+        }
+        let keyring = Keyring {
+            current_id: config.current_key.id,
+            ciphers,
+        };
+        let registry = store.clone_with_root_key(ROOT_KEY_REGISTRY)?;
+        Ok(EncryptedStore {
+            store,
+            registry,
+            root_key: root_key.to_vec(),
+            keyring: Arc::new(keyring),
+            rewrite_lock: shared_rewrite_lock(K::get_name(), namespace),
+            registered_root_keys: Arc::default(),
+        })
+    }
+}
+
+impl<K> EncryptedStore<K>
+where
+    K: WithError,
+{
+    /// Returns the id of the key that encrypted the given envelope.
+    pub fn read_key_id(envelope: &[u8]) -> Result<u32, EncryptionError<K::Error>> {
+        ensure!(
+            envelope.len() >= ENVELOPE_OVERHEAD,
+            EncryptionError::TooShortValue
+        );
+        let mut bytes = [0; KEY_ID_SIZE];
+        bytes.copy_from_slice(&envelope[..KEY_ID_SIZE]);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    /// Returns the data authenticated together with the value of the given key: the root key,
+    /// prefixed by its length, and the key.
+    fn associated_data(&self, key: &[u8]) -> Vec<u8> {
+        let root_key_len = u32::try_from(self.root_key.len()).expect("root keys are short");
+        let mut associated_data = Vec::with_capacity(4 + self.root_key.len() + key.len());
+        associated_data.extend_from_slice(&root_key_len.to_be_bytes());
+        associated_data.extend_from_slice(&self.root_key);
+        associated_data.extend_from_slice(key);
+        associated_data
+    }
+
+    fn encrypt(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>, EncryptionError<K::Error>> {
+        let current_id = self.keyring.current_id;
+        let cipher = &self.keyring.ciphers[&current_id];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: value,
+            aad: &self.associated_data(key),
+        };
+        let ciphertext = cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+        let mut envelope = Vec::with_capacity(KEY_ID_SIZE + NONCE_SIZE + ciphertext.len());
+        envelope.extend_from_slice(&current_id.to_be_bytes());
+        envelope.extend_from_slice(&nonce);
+        envelope.extend_from_slice(&ciphertext);
+        Ok(envelope)
+    }
+
+    fn decrypt(&self, key: &[u8], envelope: &[u8]) -> Result<Vec<u8>, EncryptionError<K::Error>> {
+        let key_id = Self::read_key_id(envelope)?;
+        let cipher = self
+            .keyring
+            .ciphers
+            .get(&key_id)
+            .ok_or(EncryptionError::UnknownKey(key_id))?;
+        let nonce = Nonce::from_slice(&envelope[KEY_ID_SIZE..KEY_ID_SIZE + NONCE_SIZE]);
+        let payload = Payload {
+            msg: &envelope[KEY_ID_SIZE + NONCE_SIZE..],
+            aad: &self.associated_data(key),
+        };
+        cipher
+            .decrypt(nonce, payload)
+            .map_err(|_| EncryptionError::DecryptionFailed)
+    }
+
+    /// Returns whether the given key of this store is physically stored under another of the
+    /// given root keys, which happens with the backends that prefix the keys with their root
+    /// key.
+    fn is_under_other_root_key(&self, key: &[u8], root_keys: &[Vec<u8>]) -> bool {
+        let full_key = [self.root_key.as_slice(), key].concat();
+        iter::once(ROOT_KEY_REGISTRY)
+            .chain(root_keys.iter().map(Vec::as_slice))
+            .any(|root_key| root_key.len() > self.root_key.len() && full_key.starts_with(root_key))
+    }
+}
+
+impl<K> EncryptedStore<K>
+where
+    K: WritableKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    /// Records the root key of this store in the registry, unless it is known to be there.
+    async fn register_root_key(&self) -> Result<(), EncryptionError<K::Error>> {
+        {
+            let registered_root_keys = self
+                .registered_root_keys
+                .lock()
+                .expect("registered_root_keys lock should not be poisoned");
+            if registered_root_keys.contains(&self.root_key) {
+                return Ok(());
+            }
+        }
+        let key = [REGISTERED_ROOT_KEY_PREFIX, &self.root_key].concat();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(key, Vec::new());
+        self.registry.write_batch(batch).await?;
+        self.registered_root_keys
+            .lock()
+            .expect("registered_root_keys lock should not be poisoned")
+            .insert(self.root_key.clone());
+        Ok(())
+    }
+}
+
+impl<K> EncryptedStore<K>
+where
+    K: AdminKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    /// Returns the root keys under which encrypted values were written, in increasing order.
+    pub async fn registered_root_keys(&self) -> Result<Vec<Vec<u8>>, EncryptionError<K::Error>> {
+        let keys = self
+            .registry
+            .find_keys_by_prefix(REGISTERED_ROOT_KEY_PREFIX)
+            .await?;
+        let mut root_keys = keys
+            .iterator()
+            .map(|root_key| root_key.map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, _>>()?;
+        root_keys.sort();
+        Ok(root_keys)
+    }
+
+    /// Rewrites under the current key the values of the given keys that were encrypted with a
+    /// retired key, and returns how many were rewritten. A value is only rewritten if it did
+    /// not change since it was read.
+    async fn reencrypt_keys(
+        &self,
+        keys: &[Vec<u8>],
+        root_keys: &[Vec<u8>],
+    ) -> Result<u64, EncryptionError<K::Error>> {
+        let envelopes = self.store.read_multi_values_bytes(keys.to_vec()).await?;
+        let mut rewrites = Vec::new();
+        for (key, envelope) in keys.iter().zip(envelopes) {
+            let Some(envelope) = envelope else {
+                continue;
+            };
+            // The values stored under another root key are re-encrypted with that root key.
+            let is_foreign = self.is_under_other_root_key(key, root_keys);
+            let key_id = match Self::read_key_id(&envelope) {
+                Ok(key_id) => key_id,
+                Err(EncryptionError::TooShortValue) if is_foreign => continue,
+                Err(error) => return Err(error),
+            };
+            if key_id == self.keyring.current_id {
+                continue;
+            }
+            let value = match self.decrypt(key, &envelope) {
+                Ok(value) => value,
+                Err(EncryptionError::DecryptionFailed) if is_foreign => continue,
+                Err(error) => return Err(error),
+            };
+            let new_envelope = self.encrypt(key, &value)?;
+            rewrites.push((key.clone(), envelope, new_envelope));
+        }
+        if rewrites.is_empty() {
+            return Ok(0);
+        }
+        let _guard = self.rewrite_lock.write().await;
+        // The values written since they were read are already under the current key.
+        let keys = rewrites.iter().map(|(key, _, _)| key.clone()).collect();
+        let envelopes = self.store.read_multi_values_bytes(keys).await?;
+        let mut batch = Batch::new();
+        for ((key, old_envelope, new_envelope), envelope) in rewrites.into_iter().zip(envelopes) {
+            if envelope.as_ref() == Some(&old_envelope) {
+                batch.put_key_value_bytes(key, new_envelope);
+            }
+        }
+        let rewritten_values = batch.operations.len() as u64;
+        if !batch.is_empty() {
+            self.store.write_batch(batch).await?;
+        }
+        Ok(rewritten_values)
+    }
+
+    /// Rewrites under the current key the values of this store whose keys start with the
+    /// next prefix of `progress`, in chunks, then advances it. The `checkpoint` function is
+    /// called with the updated progress after each chunk. The values physically stored under
+    /// one of the other `root_keys` are left to them.
+    ///
+    /// If `progress` is about another root key, the keys of this store are visited from the
+    /// start.
+    pub async fn reencrypt_next_prefix(
+        &self,
+        progress: &mut ReEncryptionProgress,
+        root_keys: &[Vec<u8>],
+        checkpoint: &mut (impl FnMut(&ReEncryptionProgress) + Send),
+    ) -> Result<(), EncryptionError<K::Error>> {
+        if progress.root_key.as_ref() != Some(&self.root_key) {
+            progress.root_key = Some(self.root_key.clone());
+            progress.next_prefix = 0;
+            progress.last_key = None;
+        }
+        let Ok(prefix) = u8::try_from(progress.next_prefix) else {
+            return Ok(());
+        };
+        let key_prefix = [prefix];
+        let suffixes = self.store.find_keys_by_prefix(&key_prefix).await?;
+        let mut keys = Vec::new();
+        for suffix in suffixes.iterator() {
+            let key = [key_prefix.as_slice(), suffix?].concat();
+            if progress
+                .last_key
+                .as_ref()
+                .map_or(true, |last_key| key > *last_key)
+            {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        for chunk in keys.chunks(REENCRYPTION_CHUNK_SIZE) {
+            progress.rewritten_values += self.reencrypt_keys(chunk, root_keys).await?;
+            progress.last_key = chunk.last().cloned();
+            checkpoint(progress);
+        }
+        progress.next_prefix += 1;
+        progress.last_key = None;
+        checkpoint(progress);
+        Ok(())
+    }
+
+    /// Rewrites under the current key all the values that were encrypted with a retired key,
+    /// under every root key of the namespace, starting from `progress`. This is meant to run
+    /// as a background task while the store is in use. The `checkpoint` function is called
+    /// with the updated progress after each chunk of values, so that it can be persisted.
+    ///
+    /// All the processes using the namespace must already use the current key. Writes are
+    /// only excluded while a chunk is rewritten if they come from this process, because the
+    /// stores have no conditional writes: the other processes must be stopped, or use other
+    /// namespaces. The value of the empty key, if any, is left unchanged.
+    pub async fn reencrypt(
+        &self,
+        progress: &mut ReEncryptionProgress,
+        mut checkpoint: impl FnMut(&ReEncryptionProgress) + Send,
+    ) -> Result<(), EncryptionError<K::Error>> {
+        if progress.is_complete() {
+            return Ok(());
+        }
+        let root_keys = self.registered_root_keys().await?;
+        for root_key in &root_keys {
+            if progress
+                .root_key
+                .as_ref()
+                .is_some_and(|current_root_key| root_key < current_root_key)
+            {
+                continue;
+            }
+            let store = self.clone_with_root_key(root_key)?;
+            loop {
+                store
+                    .reencrypt_next_prefix(progress, &root_keys, &mut checkpoint)
+                    .await?;
+                if progress.next_prefix > u16::from(u8::MAX) {
+                    break;
+                }
+            }
+        }
+        progress.complete = true;
+        checkpoint(progress);
+        Ok(())
+    }
+}
+
+/// A memory store with encrypted values.
+#[cfg(with_testing)]
+pub type EncryptedMemoryStore = EncryptedStore<MemoryStore>;
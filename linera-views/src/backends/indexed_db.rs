@@ -93,6 +93,8 @@ impl WithError for IndexedDbStore {
 
 impl LocalReadableKeyValueStore for IndexedDbStore {
     const MAX_KEY_SIZE: usize = usize::MAX;
+    // Binary keys are ordered byte by byte.
+    const SORTED_SEARCHES: bool = true;
     type Keys = Vec<Vec<u8>>;
     type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
 
@@ -132,6 +132,7 @@ where
 {
     /// The size constant do not change
     const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = K::SORTED_SEARCHES;
     /// The basic types do not change
     type Keys = K::Keys;
     type KeyValues = K::KeyValues;
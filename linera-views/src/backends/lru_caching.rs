@@ -116,6 +116,7 @@ where
 {
     // The LRU cache does not change the underlying store's size limits.
     const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = K::SORTED_SEARCHES;
     type Keys = K::Keys;
     type KeyValues = K::KeyValues;
 
@@ -282,6 +282,7 @@ where
     K: ReadableKeyValueStore + Send + Sync,
 {
     const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = K::SORTED_SEARCHES;
     type Keys = K::Keys;
     type KeyValues = K::KeyValues;
 
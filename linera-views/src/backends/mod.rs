@@ -12,8 +12,14 @@ pub mod memory;
 
 pub mod lru_caching;
 
+pub mod overlay;
+
 pub mod dual;
 
+pub mod access_tracking;
+
+pub mod encryption;
+
 #[cfg(with_scylladb)]
 pub mod scylla_db;
 
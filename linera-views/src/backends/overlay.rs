@@ -0,0 +1,330 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Buffer the writes to a given store in memory, so that they can be either committed or
+//! discarded as a whole.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+#[cfg(with_testing)]
+use crate::memory::MemoryStore;
+use crate::{
+    batch::{Batch, WriteOperation},
+    common::get_interval,
+    store::{
+        KeyIterable as _, KeyValueIterable as _, ReadableKeyValueStore, WithError,
+        WritableKeyValueStore,
+    },
+};
+
+/// The writes buffered by an [`OverlayStore`].
+#[derive(Default)]
+struct OverlayChanges {
+    /// The keys written since the prefixes were deleted, with `None` for deleted keys.
+    updates: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The deleted prefixes. None of them is a prefix of another one.
+    deleted_prefixes: BTreeSet<Vec<u8>>,
+}
+
+impl OverlayChanges {
+    /// Applies the operations of a batch.
+    fn apply(&mut self, batch: Batch) {
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Put { key, value } => {
+                    self.updates.insert(key, Some(value));
+                }
+                WriteOperation::Delete { key } => {
+                    self.updates.insert(key, None);
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    self.delete_prefix(key_prefix);
+                }
+            }
+        }
+    }
+
+    /// Deletes all the keys starting with `key_prefix`.
+    fn delete_prefix(&mut self, key_prefix: Vec<u8>) {
+        let interval = get_interval(key_prefix.clone());
+        let keys = self
+            .updates
+            .range(interval.clone())
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.updates.remove(&key);
+        }
+        if self.is_prefix_deleted(&key_prefix) {
+            return;
+        }
+        let prefixes = self
+            .deleted_prefixes
+            .range(interval)
+            .cloned()
+            .collect::<Vec<_>>();
+        for prefix in prefixes {
+            self.deleted_prefixes.remove(&prefix);
+        }
+        self.deleted_prefixes.insert(key_prefix);
+    }
+
+    /// Returns whether `key` starts with one of the deleted prefixes.
+    fn is_prefix_deleted(&self, key: &[u8]) -> bool {
+        // Since the deleted prefixes don't contain each other, only the largest one not
+        // greater than `key` can be a prefix of it.
+        self.deleted_prefixes
+            .range::<[u8], _>(..=key)
+            .next_back()
+            .is_some_and(|prefix| key.starts_with(prefix))
+    }
+
+    /// Returns the value of `key` if it was changed by the overlay, or `None` if it has to
+    /// be read from the base store.
+    fn query(&self, key: &[u8]) -> Option<Option<&Vec<u8>>> {
+        match self.updates.get(key) {
+            Some(value) => Some(value.as_ref()),
+            None if self.is_prefix_deleted(key) => Some(None),
+            None => None,
+        }
+    }
+
+    /// Returns whether the value of `key` in the base store is hidden by the overlay.
+    fn shadows(&self, key: &[u8]) -> bool {
+        self.updates.contains_key(key) || self.is_prefix_deleted(key)
+    }
+
+    /// Returns the batch that applies the buffered writes to the base store.
+    fn into_batch(self) -> Batch {
+        let mut batch = Batch::new();
+        for key_prefix in self.deleted_prefixes {
+            batch.delete_key_prefix(key_prefix);
+        }
+        for (key, value) in self.updates {
+            match value {
+                Some(value) => batch.put_key_value_bytes(key, value),
+                None => batch.delete_key(key),
+            }
+        }
+        batch
+    }
+}
+
+/// A store reading through to a base store, while buffering its writes in memory.
+///
+/// Nothing is written to the base store until [`OverlayStore::commit`] is called, and
+/// [`OverlayStore::discard`] drops the buffered writes. Clones of the store share the same
+/// buffer.
+#[derive(Clone)]
+pub struct OverlayStore<S> {
+    /// The store that is read for the keys not changed by the overlay.
+    base: S,
+    changes: Arc<Mutex<OverlayChanges>>,
+}
+
+impl<S> WithError for OverlayStore<S>
+where
+    S: WithError,
+{
+    type Error = S::Error;
+}
+
+impl<S> ReadableKeyValueStore for OverlayStore<S>
+where
+    S: ReadableKeyValueStore + Send + Sync,
+{
+    // The overlay does not change the underlying store's size limits.
+    const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    // The results are collected in a `BTreeMap`.
+    const SORTED_SEARCHES: bool = true;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.base.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        if let Some(value) = self.changes().query(key) {
+            return Ok(value.cloned());
+        }
+        self.base.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        if let Some(value) = self.changes().query(key) {
+            return Ok(value.is_some());
+        }
+        self.base.contains_key(key).await
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        let mut results = vec![false; keys.len()];
+        let mut indices = Vec::new();
+        let mut base_keys = Vec::new();
+        {
+            let changes = self.changes();
+            for (index, key) in keys.into_iter().enumerate() {
+                match changes.query(&key) {
+                    Some(value) => results[index] = value.is_some(),
+                    None => {
+                        indices.push(index);
+                        base_keys.push(key);
+                    }
+                }
+            }
+        }
+        if !base_keys.is_empty() {
+            let base_results = self.base.contains_keys(base_keys).await?;
+            for (index, result) in indices.into_iter().zip(base_results) {
+                results[index] = result;
+            }
+        }
+        Ok(results)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let mut results = vec![None; keys.len()];
+        let mut indices = Vec::new();
+        let mut base_keys = Vec::new();
+        {
+            let changes = self.changes();
+            for (index, key) in keys.into_iter().enumerate() {
+                match changes.query(&key) {
+                    Some(value) => results[index] = value.cloned(),
+                    None => {
+                        indices.push(index);
+                        base_keys.push(key);
+                    }
+                }
+            }
+        }
+        if !base_keys.is_empty() {
+            let base_values = self.base.read_multi_values_bytes(base_keys).await?;
+            for (index, value) in indices.into_iter().zip(base_values) {
+                results[index] = value;
+            }
+        }
+        Ok(results)
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        let base_keys = self.base.find_keys_by_prefix(key_prefix).await?;
+        let mut keys = BTreeSet::new();
+        let changes = self.changes();
+        for suffix in base_keys.iterator() {
+            let suffix = suffix?;
+            if !changes.shadows(&[key_prefix, suffix].concat()) {
+                keys.insert(suffix.to_vec());
+            }
+        }
+        let len = key_prefix.len();
+        for (key, value) in changes.updates.range(get_interval(key_prefix.to_vec())) {
+            if value.is_some() {
+                keys.insert(key[len..].to_vec());
+            }
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        let base_key_values = self.base.find_key_values_by_prefix(key_prefix).await?;
+        let mut key_values = BTreeMap::new();
+        let changes = self.changes();
+        for entry in base_key_values.into_iterator_owned() {
+            let (suffix, value) = entry?;
+            if !changes.shadows(&[key_prefix, &suffix[..]].concat()) {
+                key_values.insert(suffix, value);
+            }
+        }
+        let len = key_prefix.len();
+        for (key, value) in changes.updates.range(get_interval(key_prefix.to_vec())) {
+            if let Some(value) = value {
+                key_values.insert(key[len..].to_vec(), value.clone());
+            }
+        }
+        Ok(key_values.into_iter().collect())
+    }
+}
+
+impl<S> WritableKeyValueStore for OverlayStore<S>
+where
+    S: WritableKeyValueStore + Send + Sync,
+{
+    // The overlay does not change the underlying store's size limits.
+    const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        self.changes().apply(batch);
+        Ok(())
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        // The overlay has no journal, and must not write to the base store.
+        Ok(())
+    }
+}
+
+impl<S> OverlayStore<S> {
+    /// Creates a new overlay on top of the given store, with no buffered writes.
+    pub fn new(base: S) -> Self {
+        Self {
+            base,
+            changes: Arc::default(),
+        }
+    }
+
+    /// Returns the base store.
+    pub fn base(&self) -> &S {
+        &self.base
+    }
+
+    /// Returns whether writes are buffered.
+    pub fn has_changes(&self) -> bool {
+        let changes = self.changes();
+        !changes.updates.is_empty() || !changes.deleted_prefixes.is_empty()
+    }
+
+    /// Drops the buffered writes.
+    pub fn discard(&self) {
+        *self.changes() = OverlayChanges::default();
+    }
+
+    /// Takes the buffered writes, as a batch to apply to the base store.
+    pub fn take_batch(&self) -> Batch {
+        std::mem::take(&mut *self.changes()).into_batch()
+    }
+
+    fn changes(&self) -> std::sync::MutexGuard<'_, OverlayChanges> {
+        self.changes
+            .lock()
+            .expect("OverlayStore lock should not be poisoned")
+    }
+}
+
+impl<S> OverlayStore<S>
+where
+    S: WritableKeyValueStore + Send + Sync,
+{
+    /// Writes the buffered writes to the base store.
+    pub async fn commit(&self) -> Result<(), S::Error> {
+        let batch = self.take_batch();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.base.write_batch(batch).await
+    }
+}
+
+/// An overlay on top of a memory store.
+#[cfg(with_testing)]
+pub type OverlayMemoryStore = OverlayStore<MemoryStore>;
@@ -4,6 +4,7 @@
 //! Implements [`crate::store::KeyValueStore`] for the RocksDB database.
 
 use std::{
+    collections::BTreeMap,
     ffi::OsString,
     ops::{Bound, Bound::Excluded},
     path::PathBuf,
@@ -20,7 +21,7 @@ use crate::metering::MeteredStore;
 use crate::store::TestKeyValueStore;
 use crate::{
     batch::{Batch, WriteOperation},
-    common::get_upper_bound,
+    common::{get_upper_bound, get_upper_bound_option},
     lru_caching::{LruCachingConfig, LruCachingStore},
     store::{
         AdminKeyValueStore, CommonStoreInternalConfig, KeyValueStoreError, ReadableKeyValueStore,
@@ -41,6 +42,9 @@ const MAX_VALUE_SIZE: usize = 3221225072;
 // 8388608 and so for offset reason we decrease by 400
 const MAX_KEY_SIZE: usize = 8388208;
 
+/// The number of keys scanned between two progress reports when computing statistics.
+const STATISTICS_PROGRESS_INTERVAL: u64 = 100_000;
+
 /// The RocksDB client that we use.
 type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 
@@ -245,6 +249,112 @@ impl RocksDbStoreExecutor {
         self.db.write(inner_batch)?;
         Ok(())
     }
+
+    fn statistics_internal(
+        &self,
+        prefix_len: usize,
+        sample_size: Option<u64>,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<RocksDbStatistics, RocksDbStoreInternalError> {
+        let property = |name: &str| self.db.property_int_value(name);
+        let mut statistics = RocksDbStatistics {
+            estimated_keys: property("rocksdb.estimate-num-keys")?,
+            estimated_live_data_size: property("rocksdb.estimate-live-data-size")?,
+            sst_files_size: property("rocksdb.total-sst-files-size")?,
+            ..RocksDbStatistics::default()
+        };
+        let root_len = self.root_key.len();
+        let mut scanned_keys = 0;
+        let mut iter = self.db.raw_iterator();
+        iter.seek(&self.root_key);
+        while let Some(full_key) = iter.key() {
+            if !full_key.starts_with(&self.root_key) {
+                break;
+            }
+            let key = &full_key[root_len..];
+            let prefix = &key[..prefix_len.min(key.len())];
+            let value_len = iter.value().map_or(0, <[u8]>::len);
+            let stats = statistics.prefixes.entry(prefix.to_vec()).or_default();
+            if sample_size.is_some_and(|sample_size| stats.keys >= sample_size) {
+                stats.complete = false;
+                if prefix.len() == prefix_len {
+                    // Skip the remaining keys of the prefix.
+                    match get_upper_bound_option(&full_key[..root_len + prefix_len]) {
+                        Some(upper_bound) => iter.seek(&upper_bound),
+                        None => break,
+                    }
+                } else {
+                    iter.next();
+                }
+                continue;
+            }
+            stats.keys += 1;
+            stats.key_bytes += key.len() as u64;
+            stats.value_bytes += value_len as u64;
+            scanned_keys += 1;
+            if scanned_keys % STATISTICS_PROGRESS_INTERVAL == 0 {
+                progress(scanned_keys);
+            }
+            iter.next();
+        }
+        iter.status()?;
+        progress(scanned_keys);
+        Ok(statistics)
+    }
+
+    fn compact_internal(
+        &self,
+        key_prefix: Option<Vec<u8>>,
+    ) -> Result<(), RocksDbStoreInternalError> {
+        let Some(key_prefix) = key_prefix else {
+            self.db.compact_range::<&[u8], &[u8]>(None, None);
+            return Ok(());
+        };
+        check_key_size(&key_prefix)?;
+        let mut start = self.root_key.clone();
+        start.extend(key_prefix);
+        let end = get_upper_bound_option(&start);
+        self.db.compact_range(Some(&start), end.as_ref());
+        Ok(())
+    }
+}
+
+/// The keys sharing a common prefix in a RocksDB store.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrefixStatistics {
+    /// The number of keys that were scanned.
+    pub keys: u64,
+    /// The total size of the scanned keys, in bytes, without the root key.
+    pub key_bytes: u64,
+    /// The total size of the scanned values, in bytes.
+    pub value_bytes: u64,
+    /// Whether all the keys with the prefix were scanned, instead of only a sample.
+    pub complete: bool,
+}
+
+impl Default for PrefixStatistics {
+    fn default() -> Self {
+        PrefixStatistics {
+            keys: 0,
+            key_bytes: 0,
+            value_bytes: 0,
+            complete: true,
+        }
+    }
+}
+
+/// Statistics of the keys of a RocksDB store, to find out where the space is used.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RocksDbStatistics {
+    /// The keys of the store, grouped by their first bytes.
+    pub prefixes: BTreeMap<Vec<u8>, PrefixStatistics>,
+    /// The number of keys estimated by RocksDB, which includes the deleted keys that were
+    /// not compacted yet.
+    pub estimated_keys: Option<u64>,
+    /// The size of the live data estimated by RocksDB, in bytes.
+    pub estimated_live_data_size: Option<u64>,
+    /// The total size of the SST files of the namespace, in bytes.
+    pub sst_files_size: Option<u64>,
 }
 
 /// The inner client
@@ -305,12 +415,51 @@ impl RocksDbStoreInternal {
     }
 }
 
+impl RocksDbStoreInternal {
+    /// Scans the keys of the store, grouping them by their first `prefix_len` bytes.
+    ///
+    /// With a `sample_size`, at most that many keys are scanned for each prefix, and the
+    /// rest are skipped. The `progress` function is called from time to time with the number of
+    /// keys scanned so far.
+    pub async fn statistics(
+        &self,
+        prefix_len: usize,
+        sample_size: Option<u64>,
+        mut progress: impl FnMut(u64) + Send + 'static,
+    ) -> Result<RocksDbStatistics, RocksDbStoreInternalError> {
+        let executor = self.executor.clone();
+        self.spawn_mode
+            .spawn(
+                move |(prefix_len, sample_size)| {
+                    executor.statistics_internal(prefix_len, sample_size, &mut progress)
+                },
+                (prefix_len, sample_size),
+            )
+            .await
+    }
+
+    /// Compacts the keys of the store starting with `key_prefix`, discarding the deleted
+    /// entries and rewriting the SST files holding them. Without a prefix, the whole namespace
+    /// is compacted, whatever the root key.
+    pub async fn compact(
+        &self,
+        key_prefix: Option<&[u8]>,
+    ) -> Result<(), RocksDbStoreInternalError> {
+        let executor = self.executor.clone();
+        let key_prefix = key_prefix.map(<[u8]>::to_vec);
+        self.spawn_mode
+            .spawn(move |x| executor.compact_internal(x), key_prefix)
+            .await
+    }
+}
+
 impl WithError for RocksDbStoreInternal {
     type Error = RocksDbStoreInternalError;
 }
 
 impl ReadableKeyValueStore for RocksDbStoreInternal {
     const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = true;
     type Keys = Vec<Vec<u8>>;
     type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
 
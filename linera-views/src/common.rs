@@ -13,7 +13,10 @@ use std::{
 
 use serde::de::DeserializeOwned;
 
-use crate::views::ViewError;
+use crate::{
+    store::{KeyIterable, KeyValueIterable},
+    views::ViewError,
+};
 
 #[doc(hidden)]
 pub type HasherOutputSize = <sha3::Sha3_256 as sha3::digest::OutputSizeUser>::OutputSize;
@@ -129,6 +132,68 @@ where
     }
 }
 
+/// Iterates over the results of a search query, in lexicographic order of the keys.
+///
+/// Views merge the stored keys with their staged updates, which is only correct if the stored
+/// keys are visited in lexicographic order. The results of stores that don't return them in
+/// that order are collected and sorted first.
+pub(crate) enum SortedResults<I, T> {
+    /// The results, as returned by a store that sorts them.
+    Stored(I),
+    /// The results of a store that doesn't sort them, sorted.
+    Sorted(std::vec::IntoIter<T>),
+}
+
+impl<I, T, E> Iterator for SortedResults<I, T>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SortedResults::Stored(results) => results.next(),
+            SortedResults::Sorted(results) => results.next().map(Ok),
+        }
+    }
+}
+
+/// Iterates over the keys returned by a search query in lexicographic order, sorting them
+/// only if the store didn't.
+pub(crate) fn sorted_keys<K, E>(
+    keys: &K,
+    sorted: bool,
+) -> Result<SortedResults<K::Iterator<'_>, &[u8]>, E>
+where
+    K: KeyIterable<E>,
+{
+    if sorted {
+        return Ok(SortedResults::Stored(keys.iterator()));
+    }
+    let mut keys = keys.iterator().collect::<Result<Vec<_>, _>>()?;
+    keys.sort_unstable();
+    Ok(SortedResults::Sorted(keys.into_iter()))
+}
+
+/// Iterates over the key-value pairs returned by a search query in lexicographic order of the
+/// keys, sorting them only if the store didn't.
+pub(crate) fn sorted_key_values<K, E>(
+    key_values: K,
+    sorted: bool,
+) -> Result<SortedResults<K::IteratorOwned, (Vec<u8>, Vec<u8>)>, E>
+where
+    K: KeyValueIterable<E>,
+{
+    if sorted {
+        return Ok(SortedResults::Stored(key_values.into_iterator_owned()));
+    }
+    let mut key_values = key_values
+        .into_iterator_owned()
+        .collect::<Result<Vec<_>, _>>()?;
+    key_values.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+    Ok(SortedResults::Sorted(key_values.into_iter()))
+}
+
 /// `SuffixClosedSetIterator` iterates over the entries of a container ordered
 /// lexicographically.
 ///
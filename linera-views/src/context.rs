@@ -22,6 +22,9 @@ pub trait Context: Clone {
     /// The maximal size of keys that can be stored.
     const MAX_KEY_SIZE: usize;
 
+    /// Whether the search operations return the keys in lexicographic order.
+    const SORTED_SEARCHES: bool;
+
     /// User-provided data to be carried along.
     type Extra: Clone + Send + Sync;
 
@@ -202,6 +205,7 @@ where
 {
     const MAX_VALUE_SIZE: usize = S::MAX_VALUE_SIZE;
     const MAX_KEY_SIZE: usize = S::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = S::SORTED_SEARCHES;
     type Extra = E;
     type Error = S::Error;
     type Keys = S::Keys;
@@ -53,6 +53,130 @@ impl<K: async_graphql::OutputType, V: async_graphql::OutputType> async_graphql::
     }
 }
 
+/// A GraphQL-visible item of a page, together with the cursor to resume after it.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name_type)]
+pub struct Edge<T: async_graphql::OutputType + Send + Sync> {
+    pub cursor: String,
+    pub node: T,
+}
+
+impl<T: async_graphql::OutputType> async_graphql::TypeName for Edge<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("Edge_{}_{:08x}", mangle(T::type_name()), hash_name::<T>()).into()
+    }
+}
+
+/// Where a page of items ends, and whether more items follow.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name = "ViewPageInfo")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+/// A page of GraphQL-visible items, in the style of a Relay connection.
+#[derive(async_graphql::SimpleObject)]
+#[graphql(name_type)]
+pub struct Connection<T: async_graphql::OutputType + Send + Sync> {
+    pub edges: Vec<Edge<T>>,
+    pub page_info: PageInfo,
+    /// The number of items in the view, if the view keeps count of them.
+    pub total_count: Option<usize>,
+}
+
+impl<T: async_graphql::OutputType> async_graphql::TypeName for Connection<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!(
+            "Connection_{}_{:08x}",
+            mangle(T::type_name()),
+            hash_name::<T>()
+        )
+        .into()
+    }
+}
+
+impl<T: async_graphql::OutputType + Send + Sync> Connection<T> {
+    pub(crate) fn new(
+        edges: Vec<Edge<T>>,
+        has_next_page: bool,
+        total_count: Option<usize>,
+    ) -> Self {
+        let end_cursor = edges.last().map(|edge| edge.cursor.clone());
+        Connection {
+            edges,
+            page_info: PageInfo {
+                has_next_page,
+                end_cursor,
+            },
+            total_count,
+        }
+    }
+}
+
+/// Turns a serialized key into an opaque GraphQL cursor.
+pub(crate) fn encode_cursor(key: &[u8]) -> String {
+    hex::encode(key)
+}
+
+/// Recovers the serialized key of a cursor returned by [`encode_cursor`].
+pub(crate) fn decode_cursor(cursor: &str) -> Result<Vec<u8>, async_graphql::Error> {
+    hex::decode(cursor).map_err(|_| async_graphql::Error::new(format!("Invalid cursor {cursor}")))
+}
+
+/// The largest number of items in a page, which is also the size of a page when `first` is
+/// not given.
+pub(crate) const MAX_PAGE_SIZE: usize = 1_000;
+
+/// Returns the number of items of a page requested with `first`.
+pub(crate) fn page_size(first: Option<usize>) -> usize {
+    first.map_or(MAX_PAGE_SIZE, |first| first.min(MAX_PAGE_SIZE))
+}
+
+/// The page of serialized keys following a cursor, collected while the keys of a view are
+/// visited in increasing order, e.g. with `for_each_key_while`.
+///
+/// The scan stops right after the page, so the values of the other keys are never read.
+pub(crate) struct KeyPage {
+    after: Option<Vec<u8>>,
+    size: usize,
+    keys: Vec<Vec<u8>>,
+    has_next_page: bool,
+}
+
+impl KeyPage {
+    /// Prepares the page of at most `first` keys following the `after` cursor.
+    pub(crate) fn new(
+        first: Option<usize>,
+        after: Option<String>,
+    ) -> Result<Self, async_graphql::Error> {
+        Ok(KeyPage {
+            after: after.as_deref().map(decode_cursor).transpose()?,
+            size: page_size(first),
+            keys: Vec::new(),
+            has_next_page: false,
+        })
+    }
+
+    /// Visits the next key of the view, and returns whether the scan must go on.
+    pub(crate) fn visit(&mut self, key: &[u8]) -> bool {
+        if self.after.as_deref().is_some_and(|after| key <= after) {
+            return true;
+        }
+        if self.keys.len() == self.size {
+            self.has_next_page = true;
+            return false;
+        }
+        self.keys.push(key.to_vec());
+        true
+    }
+
+    /// Returns the keys of the page, and whether more keys follow.
+    pub(crate) fn finish(self) -> (Vec<Vec<u8>>, bool) {
+        (self.keys, self.has_next_page)
+    }
+}
+
 /// A struct to use to filter map values via GraphQL.
 pub struct MapFilters<K: async_graphql::InputType> {
     pub keys: Option<Vec<K>>,
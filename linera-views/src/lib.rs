@@ -46,6 +46,7 @@ The specific functionalities of the trait `View` are the following:
 The following views implement the `View` trait:
 * `RegisterView` implements the storing of a single data.
 * `LogView` implements a log, which is a list of entries that can be expanded.
+* `AuditLogView` implements a log whose entries are hash-chained, so that their inclusion can be proven.
 * `QueueView` implements a queue, which is a list of entries that can be expanded and reduced.
 * `MapView` implements a map with keys and values.
 * `SetView` implements a set with keys.
@@ -102,10 +103,12 @@ pub use backends::metering;
 pub use backends::rocks_db;
 #[cfg(with_scylladb)]
 pub use backends::scylla_db;
-pub use backends::{journaling, lru_caching, memory, value_splitting};
+pub use backends::{
+    access_tracking, encryption, journaling, lru_caching, memory, overlay, value_splitting,
+};
 pub use views::{
-    bucket_queue_view, collection_view, hashable_wrapper, key_value_store_view, log_view, map_view,
-    queue_view, reentrant_collection_view, register_view, set_view,
+    audit_log_view, bucket_queue_view, collection_view, hashable_wrapper, key_value_store_view,
+    log_view, map_view, queue_view, reentrant_collection_view, register_view, set_view,
 };
 /// Re-exports used by the derive macros of this library.
 #[doc(hidden)]
@@ -78,6 +78,10 @@ pub trait LocalReadableKeyValueStore: WithError {
     /// The maximal size of keys that can be stored.
     const MAX_KEY_SIZE: usize;
 
+    /// Whether the search operations return the keys in lexicographic order. Views sort the
+    /// keys found in other stores themselves.
+    const SORTED_SEARCHES: bool = false;
+
     /// Returns type for key search operations.
     type Keys: KeyIterable<Self::Error>;
 
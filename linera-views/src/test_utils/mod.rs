@@ -450,6 +450,76 @@ pub async fn big_read_multi_values<C: LocalKeyValueStore>(
     assert_eq!(values, values_read);
 }
 
+/// Writes batches through a store and checks that their effects are visible right away when
+/// reading through a freshly connected store, i.e. without relying on any cache.
+pub async fn run_read_your_writes<C: LocalKeyValueStore>(config: C::Config) {
+    let mut rng = make_deterministic_rng();
+    let namespace = generate_test_namespace();
+    let root_key = &[];
+    let key_prefix = vec![0, 1];
+    let mut state = BTreeMap::new();
+    let mut store = C::recreate_and_connect(&config, &namespace, root_key)
+        .await
+        .unwrap();
+    for _ in 0..10 {
+        // Reading first, so that the previous values are in the cache of the store, if any.
+        assert_eq!(read_key_values_prefix(&store, &key_prefix).await, state);
+        let mut batch = Batch::new();
+        let deleted_keys = state
+            .keys()
+            .filter(|_| rng.gen_range(0..4) == 0)
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &deleted_keys {
+            batch.delete_key(key.clone());
+            state.remove(key);
+        }
+        for _ in 0..20 {
+            let key = get_small_key_space(&mut rng, &key_prefix, 3);
+            let value = get_random_byte_vector(&mut rng, &[], 10);
+            batch.put_key_value_bytes(key.clone(), value.clone());
+            state.insert(key, value);
+        }
+        store.write_batch(batch).await.unwrap();
+        drop(store);
+
+        store = C::connect(&config, &namespace, root_key).await.unwrap();
+        let keys = state.keys().cloned().collect::<Vec<_>>();
+        let values = state.values().cloned().map(Some).collect::<Vec<_>>();
+        assert_eq!(
+            store.read_multi_values_bytes(keys.clone()).await.unwrap(),
+            values
+        );
+        for (key, value) in &state {
+            assert_eq!(
+                store.read_value_bytes(key).await.unwrap().as_ref(),
+                Some(value)
+            );
+        }
+        let deleted_keys = deleted_keys
+            .into_iter()
+            .filter(|key| !state.contains_key(key))
+            .collect::<Vec<_>>();
+        for found in store.contains_keys(deleted_keys).await.unwrap() {
+            assert!(!found);
+        }
+        let mut found_keys = Vec::new();
+        for key in store
+            .find_keys_by_prefix(&key_prefix)
+            .await
+            .unwrap()
+            .iterator()
+        {
+            let mut big_key = key_prefix.clone();
+            big_key.extend(key.unwrap());
+            found_keys.push(big_key);
+        }
+        found_keys.sort();
+        assert_eq!(found_keys, keys);
+        assert_eq!(read_key_values_prefix(&store, &key_prefix).await, state);
+    }
+}
+
 /// That test is especially challenging for ScyllaDB.
 /// In its default settings, Scylla has a limitation to 10000 tombstones.
 /// A tombstone is an indication that the data has been deleted. That
@@ -0,0 +1,413 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::RangeBounds;
+
+use async_trait::async_trait;
+use linera_base::crypto::{BcsHashable, CryptoHash};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    batch::Batch,
+    context::Context,
+    log_view::LogView,
+    views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
+};
+
+/// Key tags to create the sub-keys of an `AuditLogView` on top of the base key.
+#[repr(u8)]
+enum KeyTag {
+    /// Prefix for the log of the entries.
+    Entries = MIN_VIEW_TAG,
+    /// Prefix for the log of the hashes of the entries.
+    Hashes,
+}
+
+/// The hashes stored for each entry of an [`AuditLogView`].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct EntryHashes {
+    /// The hash of the entry.
+    entry: CryptoHash,
+    /// The root hash of the log up to and including the entry.
+    chain: CryptoHash,
+}
+
+/// The value hashed to append an entry to a log with the `previous` root hash.
+#[derive(Serialize, Deserialize)]
+struct AuditLogLink {
+    previous: CryptoHash,
+    entry: CryptoHash,
+}
+
+impl<'de> BcsHashable<'de> for AuditLogLink {}
+
+/// Returns the root hash of an empty audit log.
+pub fn empty_root_hash() -> CryptoHash {
+    CryptoHash::from([0; 4])
+}
+
+/// Returns the hash of an entry of an audit log.
+fn entry_hash<T: Serialize>(entry: &T) -> Result<CryptoHash, bcs::Error> {
+    Ok(CryptoHash::from_raw_bytes(&bcs::to_bytes(entry)?))
+}
+
+/// Returns the root hash of a log with the `previous` root hash, after appending the entry
+/// with the given hash.
+fn chain_hash(previous: CryptoHash, entry: CryptoHash) -> CryptoHash {
+    CryptoHash::new(&AuditLogLink { previous, entry })
+}
+
+/// A proof that an entry is at some index of an [`AuditLogView`] with a given root hash.
+///
+/// The proof contains the hashes of all the entries appended after the proven one, so its size
+/// grows with their number.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// The index of the proven entry.
+    pub index: usize,
+    /// The root hash of the log before the proven entry was appended.
+    pub previous_root_hash: CryptoHash,
+    /// The hashes of the entries appended after the proven one, in order.
+    pub later_entry_hashes: Vec<CryptoHash>,
+}
+
+/// An error verifying an [`InclusionProof`].
+#[derive(Debug, Error)]
+pub enum InclusionProofError {
+    /// The entry and the proof lead to a different root hash.
+    #[error("The proof leads to the root hash {computed}, instead of {expected}")]
+    RootHashMismatch {
+        /// The root hash the entry was expected to be part of.
+        expected: CryptoHash,
+        /// The root hash obtained from the entry and the proof.
+        computed: CryptoHash,
+    },
+    /// The entry could not be serialized.
+    #[error(transparent)]
+    BcsError(#[from] bcs::Error),
+}
+
+/// Verifies that the `proof` shows that `entry` is part of the audit log with the given
+/// `root_hash`.
+///
+/// This doesn't need access to the storage, so third parties can check the proofs off-chain.
+/// The `root_hash` is the [`HashableView`] hash of the log, from which the state hash of the
+/// chain that is committed in certificates is computed.
+pub fn verify_inclusion<T: Serialize>(
+    root_hash: CryptoHash,
+    entry: &T,
+    proof: &InclusionProof,
+) -> Result<(), InclusionProofError> {
+    let mut computed = chain_hash(proof.previous_root_hash, entry_hash(entry)?);
+    for later_entry_hash in &proof.later_entry_hashes {
+        computed = chain_hash(computed, *later_entry_hash);
+    }
+    if computed != root_hash {
+        return Err(InclusionProofError::RootHashMismatch {
+            expected: root_hash,
+            computed,
+        });
+    }
+    Ok(())
+}
+
+/// A view of an append-only log of values of type `T`, where each entry is chained with the
+/// hash of the previous ones.
+///
+/// The hash of the view is the root hash of the log, and the inclusion of any entry in it can
+/// be proven with an [`InclusionProof`].
+#[derive(Debug)]
+pub struct AuditLogView<C, T> {
+    entries: LogView<C, T>,
+    hashes: LogView<C, EntryHashes>,
+}
+
+#[async_trait]
+impl<C, T> View<C> for AuditLogView<C, T>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    T: Send + Sync + Serialize,
+{
+    const NUM_INIT_KEYS: usize =
+        LogView::<C, T>::NUM_INIT_KEYS + LogView::<C, EntryHashes>::NUM_INIT_KEYS;
+
+    fn context(&self) -> &C {
+        self.entries.context()
+    }
+
+    fn pre_load(context: &C) -> Result<Vec<Vec<u8>>, ViewError> {
+        let mut keys = LogView::<C, T>::pre_load(
+            &context.clone_with_base_key(context.base_tag(KeyTag::Entries as u8)),
+        )?;
+        keys.extend(LogView::<C, EntryHashes>::pre_load(
+            &context.clone_with_base_key(context.base_tag(KeyTag::Hashes as u8)),
+        )?);
+        Ok(keys)
+    }
+
+    fn post_load(context: C, values: &[Option<Vec<u8>>]) -> Result<Self, ViewError> {
+        let middle = LogView::<C, T>::NUM_INIT_KEYS;
+        if values.len() < Self::NUM_INIT_KEYS {
+            return Err(ViewError::PostLoadValuesError);
+        }
+        let entries = LogView::post_load(
+            context.clone_with_base_key(context.base_tag(KeyTag::Entries as u8)),
+            &values[..middle],
+        )?;
+        let hashes = LogView::post_load(
+            context.clone_with_base_key(context.base_tag(KeyTag::Hashes as u8)),
+            &values[middle..Self::NUM_INIT_KEYS],
+        )?;
+        Ok(AuditLogView { entries, hashes })
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let keys = Self::pre_load(&context)?;
+        let values = context.read_multi_values_bytes(keys).await?;
+        Self::post_load(context, &values)
+    }
+
+    fn rollback(&mut self) {
+        self.entries.rollback();
+        self.hashes.rollback();
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        self.entries.has_pending_changes().await || self.hashes.has_pending_changes().await
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        let deleted_entries = self.entries.flush(batch)?;
+        let deleted_hashes = self.hashes.flush(batch)?;
+        Ok(deleted_entries && deleted_hashes)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.hashes.clear();
+    }
+}
+
+impl<C, T> ClonableView<C> for AuditLogView<C, T>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    T: Clone + Send + Sync + Serialize,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        Ok(AuditLogView {
+            entries: self.entries.clone_unchecked()?,
+            hashes: self.hashes.clone_unchecked()?,
+        })
+    }
+}
+
+impl<C, T> AuditLogView<C, T>
+where
+    C: Context,
+{
+    /// Reads the number of entries in the log.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::audit_log_view::AuditLogView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = AuditLogView::load(context).await.unwrap();
+    /// log.append(34).await.unwrap();
+    /// log.append(42).await.unwrap();
+    /// assert_eq!(log.count(), 2);
+    /// # })
+    /// ```
+    pub fn count(&self) -> usize {
+        self.entries.count()
+    }
+
+    /// Obtains the extra data.
+    pub fn extra(&self) -> &C::Extra {
+        self.entries.extra()
+    }
+}
+
+impl<C, T> AuditLogView<C, T>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    T: Clone + DeserializeOwned + Serialize + Send,
+{
+    /// Appends an entry to the end of the log.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::audit_log_view::AuditLogView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = AuditLogView::load(context).await.unwrap();
+    /// let empty_root_hash = log.root_hash().await.unwrap();
+    /// log.append(34).await.unwrap();
+    /// assert_ne!(log.root_hash().await.unwrap(), empty_root_hash);
+    /// # })
+    /// ```
+    pub async fn append(&mut self, entry: T) -> Result<(), ViewError> {
+        let previous = self.root_hash().await?;
+        let entry_hash = entry_hash(&entry)?;
+        self.hashes.push(EntryHashes {
+            entry: entry_hash,
+            chain: chain_hash(previous, entry_hash),
+        });
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Reads the entries in the given range (including staged ones).
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::audit_log_view::AuditLogView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = AuditLogView::load(context).await.unwrap();
+    /// log.append(34).await.unwrap();
+    /// log.append(42).await.unwrap();
+    /// log.append(56).await.unwrap();
+    /// assert_eq!(log.get(1..).await.unwrap(), vec![42, 56]);
+    /// # })
+    /// ```
+    pub async fn get<R>(&self, range: R) -> Result<Vec<T>, ViewError>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.entries.read(range).await
+    }
+
+    /// Returns the root hash of the log, which chains the hashes of all its entries (including
+    /// staged ones).
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::audit_log_view::{empty_root_hash, AuditLogView};
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let log = AuditLogView::<_, u32>::load(context).await.unwrap();
+    /// assert_eq!(log.root_hash().await.unwrap(), empty_root_hash());
+    /// # })
+    /// ```
+    pub async fn root_hash(&self) -> Result<CryptoHash, ViewError> {
+        match self.hashes.count().checked_sub(1) {
+            None => Ok(empty_root_hash()),
+            Some(last) => Ok(self.entry_hashes(last).await?.chain),
+        }
+    }
+
+    /// Creates a proof that the entry with the given index is part of the log with the current
+    /// root hash, or returns `None` if there is no such entry.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::audit_log_view::{verify_inclusion, AuditLogView};
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = AuditLogView::load(context).await.unwrap();
+    /// log.append(34).await.unwrap();
+    /// log.append(42).await.unwrap();
+    /// let proof = log.prove_inclusion(0).await.unwrap().unwrap();
+    /// let root_hash = log.root_hash().await.unwrap();
+    /// assert!(verify_inclusion(root_hash, &34, &proof).is_ok());
+    /// assert!(log.prove_inclusion(2).await.unwrap().is_none());
+    /// # })
+    /// ```
+    pub async fn prove_inclusion(&self, index: usize) -> Result<Option<InclusionProof>, ViewError> {
+        if index >= self.hashes.count() {
+            return Ok(None);
+        }
+        let previous_root_hash = match index.checked_sub(1) {
+            None => empty_root_hash(),
+            Some(previous) => self.entry_hashes(previous).await?.chain,
+        };
+        let later_entry_hashes = self
+            .hashes
+            .read(index + 1..)
+            .await?
+            .into_iter()
+            .map(|hashes| hashes.entry)
+            .collect();
+        Ok(Some(InclusionProof {
+            index,
+            previous_root_hash,
+            later_entry_hashes,
+        }))
+    }
+
+    /// Reads the hashes stored for the entry with the given index, which must exist.
+    async fn entry_hashes(&self, index: usize) -> Result<EntryHashes, ViewError> {
+        self.hashes
+            .get(index)
+            .await?
+            .ok_or(ViewError::MissingEntries)
+    }
+}
+
+#[async_trait]
+impl<C, T> HashableView<C> for AuditLogView<C, T>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    T: Send + Sync + Clone + Serialize + DeserializeOwned,
+{
+    type Hasher = sha3::Sha3_256;
+
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        self.hash().await
+    }
+
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        Ok(*self.root_hash().await?.as_bytes())
+    }
+}
+
+mod graphql {
+    use std::borrow::Cow;
+
+    use linera_base::crypto::CryptoHash;
+
+    use super::AuditLogView;
+    use crate::{
+        context::Context,
+        graphql::{hash_name, mangle},
+    };
+
+    impl<C: Send + Sync, T: async_graphql::OutputType> async_graphql::TypeName for AuditLogView<C, T> {
+        fn type_name() -> Cow<'static, str> {
+            format!(
+                "AuditLogView_{}_{:08x}",
+                mangle(T::type_name()),
+                hash_name::<T>()
+            )
+            .into()
+        }
+    }
+
+    #[async_graphql::Object(cache_control(no_cache), name_type)]
+    impl<C: Context, T: async_graphql::OutputType> AuditLogView<C, T>
+    where
+        C: Send + Sync,
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync,
+    {
+        async fn entries(
+            &self,
+            start: Option<usize>,
+            end: Option<usize>,
+        ) -> async_graphql::Result<Vec<T>> {
+            Ok(self
+                .get(start.unwrap_or_default()..end.unwrap_or_else(|| self.count()))
+                .await?)
+        }
+
+        #[graphql(derived(name = "root_hash"))]
+        async fn root_hash_(&self) -> async_graphql::Result<CryptoHash> {
+            Ok(self.root_hash().await?)
+        }
+    }
+}
@@ -5,7 +5,7 @@
 use std::sync::LazyLock;
 use std::{
     borrow::Borrow,
-    collections::{btree_map, BTreeMap},
+    collections::{btree_map, BTreeMap, BTreeSet},
     io::Write,
     marker::PhantomData,
     mem,
@@ -22,10 +22,9 @@ use {
 
 use crate::{
     batch::Batch,
-    common::{CustomSerialize, HasherOutput, Update},
+    common::{sorted_keys, CustomSerialize, HasherOutput, Update},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
-    store::KeyIterable,
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
 
@@ -229,6 +228,98 @@ where
         self.do_load_entry_mut(short_key).await
     }
 
+    /// Loads the subviews for the data at several indices in the collection, reading the
+    /// missing ones from storage at once. Absent entries are added to the collection with
+    /// default values, like with [`Self::load_entry_mut`]. The resulting views can be
+    /// modified at the same time, and are returned in the order of `short_keys`.
+    ///
+    /// Fails with [`ViewError::DuplicateCollectionEntry`] if a short key is requested more
+    /// than once, since the same view can't be modified through two references.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::{create_test_memory_context, MemoryContext};
+    /// # use linera_views::collection_view::ByteCollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{View, ViewError};
+    /// # let context = create_test_memory_context();
+    /// let mut view: ByteCollectionView<_, RegisterView<_, String>> =
+    ///     ByteCollectionView::load(context).await.unwrap();
+    /// let mut subviews = view.try_load_entries_mut(&[&[0, 1], &[0, 2]]).await.unwrap();
+    /// *subviews[0].get_mut() = "Bonjour".to_string();
+    /// *subviews[1].get_mut() = "Hello".to_string();
+    /// assert_eq!(view.keys().await.unwrap(), vec![vec![0, 1], vec![0, 2]]);
+    /// assert!(matches!(
+    ///     view.try_load_entries_mut(&[&[0, 1], &[0, 1]]).await,
+    ///     Err(ViewError::DuplicateCollectionEntry(short_key)) if short_key == vec![0, 1]
+    /// ));
+    /// # })
+    /// ```
+    pub async fn try_load_entries_mut(
+        &mut self,
+        short_keys: &[&[u8]],
+    ) -> Result<Vec<&mut W>, ViewError> {
+        let mut requested = BTreeSet::new();
+        for short_key in short_keys {
+            if !requested.insert(*short_key) {
+                return Err(ViewError::DuplicateCollectionEntry(short_key.to_vec()));
+            }
+        }
+        let updates = self.updates.get_mut();
+        let mut keys = Vec::new();
+        let mut short_keys_to_load = Vec::new();
+        for short_key in short_keys {
+            let key = self
+                .context
+                .base_tag_index(KeyTag::Subview as u8, short_key);
+            let context = self.context.clone_with_base_key(key);
+            match updates.entry(short_key.to_vec()) {
+                btree_map::Entry::Occupied(mut entry) => {
+                    if let Update::Removed = entry.get() {
+                        entry.insert(Update::Set(W::new(context)?));
+                    }
+                }
+                btree_map::Entry::Vacant(entry) => {
+                    if self.delete_storage_first {
+                        entry.insert(Update::Set(W::new(context)?));
+                    } else {
+                        keys.extend(W::pre_load(&context)?);
+                        short_keys_to_load.push(short_key.to_vec());
+                    }
+                }
+            }
+        }
+        let values = self.context.read_multi_values_bytes(keys).await?;
+        for (position, short_key) in short_keys_to_load.into_iter().enumerate() {
+            let loaded_values =
+                &values[position * W::NUM_INIT_KEYS..(position + 1) * W::NUM_INIT_KEYS];
+            let key = self
+                .context
+                .base_tag_index(KeyTag::Subview as u8, &short_key);
+            let context = self.context.clone_with_base_key(key);
+            let view = W::post_load(context, loaded_values)?;
+            updates.insert(short_key, Update::Set(view));
+        }
+
+        let mut views = updates
+            .iter_mut()
+            .filter(|(short_key, _)| requested.contains(short_key.as_slice()))
+            .map(|(short_key, update)| {
+                let Update::Set(view) = update else {
+                    unreachable!("All the requested entries should have been loaded");
+                };
+                (short_key.as_slice(), view)
+            })
+            .collect::<BTreeMap<_, _>>();
+        Ok(short_keys
+            .iter()
+            .map(|short_key| {
+                views
+                    .remove(*short_key)
+                    .expect("All the requested entries should have been loaded")
+            })
+            .collect())
+    }
+
     /// Loads a subview for the data at the given index in the collection. If an entry
     /// is absent then a default entry is added to the collection. The resulting view
     /// is read-only.
@@ -490,7 +581,8 @@ where
         let mut update = updates.next();
         if !self.delete_storage_first {
             let base = self.get_index_key(&[]);
-            for index in self.context.find_keys_by_prefix(&base).await?.iterator() {
+            let keys = self.context.find_keys_by_prefix(&base).await?;
+            for index in sorted_keys(&keys, C::SORTED_SEARCHES)? {
                 let index = index?;
                 loop {
                     match update {
@@ -586,6 +678,39 @@ where
         Ok(keys)
     }
 
+    /// Loads all the entries of the collection, including the staged ones, reading the
+    /// missing ones from storage at once. Entries are returned in the lexicographic order
+    /// of their short keys.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::{create_test_memory_context, MemoryContext};
+    /// # use linera_views::collection_view::ByteCollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut view: ByteCollectionView<_, RegisterView<_, String>> =
+    ///     ByteCollectionView::load(context).await.unwrap();
+    /// *view.load_entry_mut(&[0, 2]).await.unwrap().get_mut() = "Hello".to_string();
+    /// view.load_entry_mut(&[0, 1]).await.unwrap();
+    /// let entries = view.load_all_entries().await.unwrap();
+    /// assert_eq!(entries.len(), 2);
+    /// assert_eq!(entries[0].0, vec![0, 1]);
+    /// assert_eq!(entries[1].1.get(), "Hello");
+    /// # })
+    /// ```
+    pub async fn load_all_entries(&mut self) -> Result<Vec<(Vec<u8>, &W)>, ViewError> {
+        let short_keys = self.keys().await?;
+        let views = {
+            let short_keys = short_keys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            self.try_load_entries_mut(&short_keys).await?
+        };
+        Ok(short_keys
+            .into_iter()
+            .zip(views)
+            .map(|(short_key, view)| (short_key, &*view))
+            .collect())
+    }
+
     /// Returns the number of entries in the collection.
     /// ```rust
     /// # tokio_test::block_on(async {
@@ -783,6 +908,46 @@ where
         self.collection.load_entry_mut(&short_key).await
     }
 
+    /// Loads the subviews for the data at several distinct indices in the collection,
+    /// reading the missing ones from storage at once. Absent entries are added to the
+    /// collection with default values. The resulting views can be modified at the same time,
+    /// and are returned in the order of `indices`.
+    ///
+    /// Fails with [`ViewError::DuplicateCollectionEntry`] if an index is requested more than
+    /// once.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::{create_test_memory_context, MemoryContext};
+    /// # use linera_views::collection_view::CollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut view: CollectionView<_, u64, RegisterView<_, u64>> =
+    ///     CollectionView::load(context).await.unwrap();
+    /// *view.load_entry_mut(&23).await.unwrap().get_mut() = 100;
+    /// let mut subviews = view.try_load_entries_mut(&[&23, &24]).await.unwrap();
+    /// *subviews[0].get_mut() -= 40;
+    /// *subviews[1].get_mut() += 40;
+    /// assert_eq!(*view.load_entry_mut(&23).await.unwrap().get(), 60);
+    /// assert_eq!(*view.load_entry_mut(&24).await.unwrap().get(), 40);
+    /// # })
+    /// ```
+    pub async fn try_load_entries_mut<Q>(
+        &mut self,
+        indices: &[&Q],
+    ) -> Result<Vec<&mut W>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_keys = indices
+            .iter()
+            .map(|index| C::derive_short_key(*index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let short_keys = short_keys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        self.collection.try_load_entries_mut(&short_keys).await
+    }
+
     /// Loads a subview for the data at the given index in the collection. If an entry
     /// is absent then a default entry is added to the collection. The resulting view
     /// is read-only.
@@ -959,6 +1124,36 @@ where
     pub async fn count(&self) -> Result<usize, ViewError> {
         self.collection.count().await
     }
+
+    /// Loads all the entries of the collection, including the staged ones, reading the
+    /// missing ones from storage at once. Entries are returned in the order determined by
+    /// the serialization of their indices.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::{create_test_memory_context, MemoryContext};
+    /// # use linera_views::collection_view::CollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut view: CollectionView<_, u64, RegisterView<_, String>> =
+    ///     CollectionView::load(context).await.unwrap();
+    /// *view.load_entry_mut(&23).await.unwrap().get_mut() = "Hello".to_string();
+    /// view.load_entry_mut(&25).await.unwrap();
+    /// view.remove_entry(&25).unwrap();
+    /// let entries = view.load_all_entries().await.unwrap();
+    /// assert_eq!(entries.len(), 1);
+    /// assert_eq!(entries[0].0, 23);
+    /// assert_eq!(entries[0].1.get(), "Hello");
+    /// # })
+    /// ```
+    pub async fn load_all_entries(&mut self) -> Result<Vec<(I, &W)>, ViewError> {
+        self.collection
+            .load_all_entries()
+            .await?
+            .into_iter()
+            .map(|(short_key, view)| Ok((C::deserialize_value(&short_key)?, view)))
+            .collect()
+    }
 }
 
 impl<C, I, W> CollectionView<C, I, W>
@@ -1460,7 +1655,10 @@ mod graphql {
     use super::{CollectionView, CustomCollectionView, ReadGuardedView};
     use crate::{
         context::Context,
-        graphql::{hash_name, mangle, missing_key_error, Entry, MapFilters, MapInput},
+        graphql::{
+            encode_cursor, hash_name, mangle, missing_key_error, Connection, Edge, Entry, KeyPage,
+            MapFilters, MapInput,
+        },
         views::View,
     };
 
@@ -1551,6 +1749,36 @@ mod graphql {
 
             Ok(values)
         }
+
+        /// Returns the `first` entries following the `after` cursor, in the order of the
+        /// serialized keys. Pages hold at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<Entry<K, ReadGuardedView<V>>>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.collection
+                .for_each_key_while(|key| Ok(page.visit(key)))
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                let key = C::deserialize_value(&short_key)?;
+                let value = self
+                    .collection
+                    .try_load_entry(&short_key)
+                    .await?
+                    .ok_or_else(|| missing_key_error(&key))?;
+                edges.push(Edge {
+                    cursor: encode_cursor(&short_key),
+                    node: Entry { key, value },
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
+        }
     }
 
     impl<C: Send + Sync, K: async_graphql::OutputType, V: async_graphql::OutputType>
@@ -1618,5 +1846,35 @@ mod graphql {
 
             Ok(values)
         }
+
+        /// Returns the `first` entries following the `after` cursor, in the order of the
+        /// serialized keys. Pages hold at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<Entry<K, ReadGuardedView<V>>>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.collection
+                .for_each_key_while(|key| Ok(page.visit(key)))
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                let key = K::from_custom_bytes(&short_key)?;
+                let value = self
+                    .collection
+                    .try_load_entry(&short_key)
+                    .await?
+                    .ok_or_else(|| missing_key_error(&key))?;
+                edges.push(Edge {
+                    cursor: encode_cursor(&short_key),
+                    node: Entry { key, value },
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
+        }
     }
 }
@@ -1210,6 +1210,7 @@ where
     ViewError: From<C::Error>,
 {
     const MAX_KEY_SIZE: usize = C::MAX_KEY_SIZE;
+    const SORTED_SEARCHES: bool = C::SORTED_SEARCHES;
     type Keys = Vec<Vec<u8>>;
     type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
 
@@ -161,7 +161,7 @@ where
         self.new_values.push(value);
     }
 
-    /// Reads the size of the log.
+    /// Reads the size of the log, including the staged values. This does not access storage.
     /// ```rust
     /// # tokio_test::block_on(async {
     /// # use linera_views::context::create_test_memory_context;
@@ -336,6 +336,53 @@ where
             )
         }
     }
+
+    /// Iterates over the logged values in the given range (including staged ones), starting
+    /// from the most recent one.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::log_view::LogView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = LogView::load(context).await.unwrap();
+    /// log.push(34);
+    /// log.push(42);
+    /// log.push(56);
+    /// let values = log.iter_rev(1..).await.unwrap().collect::<Vec<_>>();
+    /// assert_eq!(values, vec![56, 42]);
+    /// # })
+    /// ```
+    pub async fn iter_rev<R>(
+        &self,
+        range: R,
+    ) -> Result<std::iter::Rev<std::vec::IntoIter<T>>, ViewError>
+    where
+        R: RangeBounds<usize>,
+    {
+        Ok(self.read(range).await?.into_iter().rev())
+    }
+
+    /// Reads the last `count` logged values (including staged ones), starting from the most
+    /// recent one. Fewer values are returned if the log is shorter than `count`.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::log_view::LogView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut log = LogView::load(context).await.unwrap();
+    /// log.push(34);
+    /// log.push(42);
+    /// log.push(56);
+    /// assert_eq!(log.read_back(2).await.unwrap(), vec![56, 42]);
+    /// assert_eq!(log.read_back(5).await.unwrap(), vec![56, 42, 34]);
+    /// # })
+    /// ```
+    pub async fn read_back(&self, count: usize) -> Result<Vec<T>, ViewError> {
+        let start = self.count().saturating_sub(count);
+        Ok(self.iter_rev(start..).await?.collect())
+    }
 }
 
 #[async_trait]
@@ -370,7 +417,7 @@ mod graphql {
     use super::LogView;
     use crate::{
         context::Context,
-        graphql::{hash_name, mangle},
+        graphql::{decode_cursor, encode_cursor, hash_name, mangle, page_size, Connection, Edge},
     };
 
     impl<C: Send + Sync, T: async_graphql::OutputType> async_graphql::TypeName for LogView<C, T> {
@@ -399,5 +446,35 @@ mod graphql {
                 .read(start.unwrap_or_default()..end.unwrap_or_else(|| self.count()))
                 .await?)
         }
+
+        /// Returns the `first` entries following the `after` cursor. Cursors are the
+        /// serialized indices of the entries, and pages hold at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> async_graphql::Result<Connection<T>> {
+            let total_count = self.count();
+            let start = match after {
+                Some(cursor) => bcs::from_bytes::<usize>(&decode_cursor(&cursor)?)?
+                    .saturating_add(1)
+                    .min(total_count),
+                None => 0,
+            };
+            let end = total_count.min(start.saturating_add(page_size(first)));
+            let edges = self
+                .read(start..end)
+                .await?
+                .into_iter()
+                .zip(start..)
+                .map(|(node, index)| {
+                    Ok(Edge {
+                        cursor: encode_cursor(&bcs::to_bytes(&index)?),
+                        node,
+                    })
+                })
+                .collect::<Result<Vec<_>, bcs::Error>>()?;
+            Ok(Connection::new(edges, end < total_count, Some(total_count)))
+        }
     }
 }
@@ -49,12 +49,11 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     batch::Batch,
     common::{
-        from_bytes_option, get_interval, CustomSerialize, DeletionSet, HasherOutput,
-        SuffixClosedSetIterator, Update,
+        from_bytes_option, get_interval, sorted_key_values, sorted_keys, CustomSerialize,
+        DeletionSet, HasherOutput, SuffixClosedSetIterator, Update,
     },
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
-    store::{KeyIterable, KeyValueIterable},
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
 
@@ -140,23 +139,31 @@ where
 
     fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
         let mut delete_view = false;
+        // The base key is computed once rather than for each of the possibly many updates.
+        let base_key = self.context.base_key();
+        let base_index = |index: &[u8]| {
+            let mut key = Vec::with_capacity(base_key.len() + index.len());
+            key.extend_from_slice(&base_key);
+            key.extend_from_slice(index);
+            key
+        };
         if self.deletion_set.delete_storage_first {
             delete_view = true;
-            batch.delete_key_prefix(self.context.base_key());
+            batch.delete_key_prefix(base_key.clone());
             for (index, update) in mem::take(&mut self.updates) {
                 if let Update::Set(value) = update {
-                    let key = self.context.base_index(&index);
+                    let key = base_index(&index);
                     batch.put_key_value(key, &value)?;
                     delete_view = false;
                 }
             }
         } else {
             for index in mem::take(&mut self.deletion_set.deleted_prefixes) {
-                let key = self.context.base_index(&index);
+                let key = base_index(&index);
                 batch.delete_key_prefix(key);
             }
             for (index, update) in mem::take(&mut self.updates) {
-                let key = self.context.base_index(&index);
+                let key = base_index(&index);
                 match update {
                     Update::Removed => batch.delete_key(key),
                     Update::Set(value) => batch.put_key_value(key, &value)?,
@@ -230,6 +237,82 @@ where
         }
     }
 
+    /// Inserts or resets the values of many keys of the map, with the same result as
+    /// inserting them one at a time. If a key appears several times, its last value is kept.
+    ///
+    /// The entries are staged together, which is faster than inserting them one at a time,
+    /// especially if they are sorted by key.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.extend([(vec![0, 2], "Bonjour"), (vec![0, 1], "Hello")]);
+    /// assert_eq!(map.keys().await.unwrap(), vec![vec![0, 1], vec![0, 2]]);
+    /// # })
+    /// ```
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = (Vec<u8>, V)>) {
+        let updates = entries
+            .into_iter()
+            .map(|(short_key, value)| (short_key, Update::Set(value)));
+        self.stage_updates(updates);
+    }
+
+    /// Removes many values, with the same result as removing them one at a time.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.extend([(vec![0, 1], "Hello"), (vec![0, 2], "Bonjour")]);
+    /// map.remove_many([vec![0, 1], vec![0, 3]]);
+    /// assert_eq!(map.keys().await.unwrap(), vec![vec![0, 2]]);
+    /// # })
+    /// ```
+    pub fn remove_many(&mut self, short_keys: impl IntoIterator<Item = Vec<u8>>) {
+        let mut removed_keys = Vec::new();
+        for short_key in short_keys {
+            if self.deletion_set.contains_prefix_of(&short_key) {
+                // Optimization: No need to mark `short_key` for deletion as we are going to remove a range of keys containing it.
+                self.updates.remove(&short_key);
+            } else {
+                removed_keys.push((short_key, Update::Removed));
+            }
+        }
+        self.stage_updates(removed_keys);
+    }
+
+    /// Stages many `updates` at once, the last update of a key taking precedence.
+    fn stage_updates(&mut self, updates: impl IntoIterator<Item = (Vec<u8>, Update<V>)>) {
+        let mut updates = updates.into_iter().collect::<Vec<_>>();
+        if !updates.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            // The sort is stable, so the updates of a key stay in order, and only the last
+            // one is kept.
+            updates.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+            updates.dedup_by(|(later_key, later_update), (key, update)| {
+                let is_duplicate = later_key == key;
+                if is_duplicate {
+                    mem::swap(later_update, update);
+                }
+                is_duplicate
+            });
+        }
+        // Building a tree from sorted keys takes linear time.
+        let mut updates = updates.into_iter().collect::<BTreeMap<_, _>>();
+        if updates.len() < self.updates.len() {
+            for (short_key, update) in updates {
+                self.updates.insert(short_key, update);
+            }
+        } else {
+            // Merges both trees in linear time, `updates` overriding the staged updates.
+            self.updates.append(&mut updates);
+        }
+    }
+
     /// Removes a value. If absent then nothing is done.
     /// ```rust
     /// # tokio_test::block_on(async {
@@ -441,7 +524,8 @@ where
                 .range(get_interval(prefix.clone()));
             let mut suffix_closed_set = SuffixClosedSetIterator::new(prefix_len, iter);
             let base = self.context.base_index(&prefix);
-            for index in self.context.find_keys_by_prefix(&base).await?.iterator() {
+            let keys = self.context.find_keys_by_prefix(&base).await?;
+            for index in sorted_keys(&keys, C::SORTED_SEARCHES)? {
                 let index = index?;
                 loop {
                     match update {
@@ -633,12 +717,8 @@ where
                 .range(get_interval(prefix.clone()));
             let mut suffix_closed_set = SuffixClosedSetIterator::new(prefix_len, iter);
             let base = self.context.base_index(&prefix);
-            for entry in self
-                .context
-                .find_key_values_by_prefix(&base)
-                .await?
-                .into_iterator_owned()
-            {
+            let key_values = self.context.find_key_values_by_prefix(&base).await?;
+            for entry in sorted_key_values(key_values, C::SORTED_SEARCHES)? {
                 let (index, bytes) = entry?;
                 loop {
                     match update {
@@ -1010,6 +1090,36 @@ where
     }
 }
 
+#[cfg(with_testing)]
+impl<C, I, V> MapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Serialize + Sync,
+    V: Send + Sync + Serialize,
+{
+    /// Loads a map from `context` and stages the given `entries`, to set up tests.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # let context = create_test_memory_context();
+    /// let map = MapView::<_, u32, _>::from_entries(context, [(1, "One"), (2, "Two")])
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(map.indices().await.unwrap(), vec![1, 2]);
+    /// # })
+    /// ```
+    pub async fn from_entries(
+        context: C,
+        entries: impl IntoIterator<Item = (I, V)>,
+    ) -> Result<Self, ViewError> {
+        let mut map = Self::load(context).await?;
+        map.extend(entries)?;
+        Ok(map)
+    }
+}
+
 impl<C, I, V> MapView<C, I, V>
 where
     C: Context + Sync,
@@ -1063,6 +1173,64 @@ where
         Ok(())
     }
 
+    /// Inserts or resets the values of many indices, with the same result as inserting them
+    /// one at a time. If an index appears several times, its last value is kept.
+    ///
+    /// All the indices are serialized before any entry is staged, so that the map is left
+    /// unchanged if one of them fails to serialize.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map: MapView<_, u32, _> = MapView::load(context).await.unwrap();
+    /// map.extend((0..100).map(|index: u32| (index, index + 1)))
+    ///     .unwrap();
+    /// assert_eq!(map.get(&(37 as u32)).await.unwrap(), Some(38));
+    /// # })
+    /// ```
+    pub fn extend<Q>(&mut self, entries: impl IntoIterator<Item = (Q, V)>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|(index, value)| Ok((C::derive_short_key(&index)?, value)))
+            .collect::<Result<Vec<_>, ViewError>>()?;
+        self.map.extend(entries);
+        Ok(())
+    }
+
+    /// Removes the values of many indices, with the same result as removing them one at a
+    /// time.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map: MapView<_, u32, _> = MapView::load(context).await.unwrap();
+    /// map.extend((0..100).map(|index: u32| (index, index + 1)))
+    ///     .unwrap();
+    /// map.remove_many(10..100).unwrap();
+    /// assert_eq!(map.count().await.unwrap(), 10);
+    /// # })
+    /// ```
+    pub fn remove_many<Q>(&mut self, indices: impl IntoIterator<Item = Q>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize,
+    {
+        let short_keys = indices
+            .into_iter()
+            .map(|index| C::derive_short_key(&index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.map.remove_many(short_keys);
+        Ok(())
+    }
+
     /// Obtains the extra data.
     pub fn extra(&self) -> &C::Extra {
         self.map.extra()
@@ -1297,6 +1465,74 @@ where
         Ok(())
     }
 
+    /// Applies a function on each index/value pair that comes after `index`, in the order
+    /// determined by serialization. If the function returns false, then the loop ends
+    /// prematurely.
+    ///
+    /// The map is not scanned from the start: the pairs are read by prefix of the serialized
+    /// `index`, from longer to shorter prefixes, so that the pairs read before the
+    /// function returns false are mostly the ones it is applied to.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map: MapView<_, u8, _> = MapView::load(context).await.unwrap();
+    /// for index in 0..10u8 {
+    ///     map.insert(&index, index.to_string());
+    /// }
+    /// let mut values = Vec::new();
+    /// map.for_each_index_value_after_while(&3u8, |_index, value| {
+    ///     values.push(value.into_owned());
+    ///     Ok(values.len() < 2)
+    /// })
+    /// .await
+    /// .unwrap();
+    /// assert_eq!(values, vec!["4".to_string(), "5".to_string()]);
+    /// # })
+    /// ```
+    pub async fn for_each_index_value_after_while<'a, Q, F>(
+        &'a self,
+        index: &Q,
+        mut f: F,
+    ) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+        F: FnMut(I, Cow<'a, V>) -> Result<bool, ViewError> + Send,
+    {
+        let start = C::derive_short_key(index)?;
+        // The length of the prefix of `start` whose pairs were all visited already.
+        let mut visited_len = None;
+        let mut prefix_len = start.len();
+        loop {
+            let prefix = start[..prefix_len].to_vec();
+            let mut go_on = true;
+            self.map
+                .for_each_key_value_while(
+                    |suffix, value| {
+                        let key = [&prefix, suffix].concat();
+                        if key <= start
+                            || visited_len.is_some_and(|len| key.starts_with(&start[..len]))
+                        {
+                            return Ok(true);
+                        }
+                        let index = C::deserialize_value(&key)?;
+                        go_on = f(index, value)?;
+                        Ok(go_on)
+                    },
+                    prefix.clone(),
+                )
+                .await?;
+            if !go_on || prefix_len == 0 {
+                return Ok(());
+            }
+            visited_len = Some(prefix_len);
+            prefix_len /= 2;
+        }
+    }
+
     /// Applies a function on each index/value pair. Indices and values are
     /// visited in an order determined by serialization.
     /// ```rust
@@ -1558,6 +1794,64 @@ where
         Ok(())
     }
 
+    /// Inserts or resets the values of many indices, with the same result as inserting them
+    /// one at a time. If an index appears several times, its last value is kept.
+    ///
+    /// All the indices are serialized before any entry is staged, so that the map is left
+    /// unchanged if one of them fails to serialize.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map: CustomMapView<_, u128, _> = CustomMapView::load(context).await.unwrap();
+    /// map.extend((0..100).map(|index: u128| (index, index + 1)))
+    ///     .unwrap();
+    /// assert_eq!(map.get(&(37 as u128)).await.unwrap(), Some(38));
+    /// # })
+    /// ```
+    pub fn extend<Q>(&mut self, entries: impl IntoIterator<Item = (Q, V)>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + CustomSerialize,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|(index, value)| Ok((index.to_custom_bytes()?, value)))
+            .collect::<Result<Vec<_>, ViewError>>()?;
+        self.map.extend(entries);
+        Ok(())
+    }
+
+    /// Removes the values of many indices, with the same result as removing them one at a
+    /// time.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::create_test_memory_context;
+    /// # use linera_views::map_view::CustomMapView;
+    /// # use linera_views::views::View;
+    /// # let context = create_test_memory_context();
+    /// let mut map: CustomMapView<_, u128, _> = CustomMapView::load(context).await.unwrap();
+    /// map.extend((0..100).map(|index: u128| (index, index + 1)))
+    ///     .unwrap();
+    /// map.remove_many(10..100).unwrap();
+    /// assert_eq!(map.count().await.unwrap(), 10);
+    /// # })
+    /// ```
+    pub fn remove_many<Q>(&mut self, indices: impl IntoIterator<Item = Q>) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + CustomSerialize,
+    {
+        let short_keys = indices
+            .into_iter()
+            .map(|index| index.to_custom_bytes())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.map.remove_many(short_keys);
+        Ok(())
+    }
+
     /// Obtains the extra data.
     pub fn extra(&self) -> &C::Extra {
         self.map.extra()
@@ -1952,7 +2246,10 @@ mod graphql {
     use super::{ByteMapView, CustomMapView, MapView};
     use crate::{
         context::Context,
-        graphql::{hash_name, mangle, Entry, MapInput},
+        graphql::{
+            encode_cursor, hash_name, mangle, missing_key_error, Connection, Edge, Entry, KeyPage,
+            MapInput,
+        },
     };
 
     impl<C: Send + Sync, V: async_graphql::OutputType> async_graphql::TypeName for ByteMapView<C, V> {
@@ -2022,6 +2319,33 @@ mod graphql {
 
             Ok(entries)
         }
+
+        /// Returns the `first` entries following the `after` cursor, in key order. Pages hold
+        /// at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<Entry<Vec<u8>, V>>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.for_each_key_while(|key| Ok(page.visit(key)), Vec::new())
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for key in keys {
+                let value = self
+                    .get(&key)
+                    .await?
+                    .ok_or_else(|| missing_key_error(&key))?;
+                edges.push(Edge {
+                    cursor: encode_cursor(&key),
+                    node: Entry { key, value },
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
+        }
     }
 
     impl<C: Send + Sync, I: async_graphql::OutputType, V: async_graphql::OutputType>
@@ -2099,6 +2423,36 @@ mod graphql {
 
             Ok(values)
         }
+
+        /// Returns the `first` entries following the `after` cursor, in the order of the
+        /// serialized keys. Pages hold at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<Entry<I, V>>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.map
+                .for_each_key_while(|key| Ok(page.visit(key)), Vec::new())
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                let key = C::deserialize_value(&short_key)?;
+                let value = self
+                    .map
+                    .get(&short_key)
+                    .await?
+                    .ok_or_else(|| missing_key_error(&key))?;
+                edges.push(Edge {
+                    cursor: encode_cursor(&short_key),
+                    node: Entry { key, value },
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
+        }
     }
 
     impl<C: Send + Sync, I: async_graphql::OutputType, V: async_graphql::OutputType>
@@ -2169,6 +2523,36 @@ mod graphql {
 
             Ok(values)
         }
+
+        /// Returns the `first` entries following the `after` cursor, in the order of the
+        /// serialized keys. Pages hold at most 1000 entries.
+        async fn entries_connection(
+            &self,
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<Entry<I, V>>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.map
+                .for_each_key_while(|key| Ok(page.visit(key)), Vec::new())
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                let key = I::from_custom_bytes(&short_key)?;
+                let value = self
+                    .map
+                    .get(&short_key)
+                    .await?
+                    .ok_or_else(|| missing_key_error(&key))?;
+                edges.push(Edge {
+                    cursor: encode_cursor(&short_key),
+                    node: Entry { key, value },
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
+        }
     }
 }
 
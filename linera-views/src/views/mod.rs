@@ -23,6 +23,9 @@ pub mod register_view;
 /// The `LogView` implements a log list that can be pushed.
 pub mod log_view;
 
+/// The `AuditLogView` implements an append-only log whose entries are hash-chained, with inclusion proofs.
+pub mod audit_log_view;
+
 /// The `BucketQueueView` implements a queue that can push on the back and delete on the front and group data in buckets.
 pub mod bucket_queue_view;
 
@@ -158,6 +161,10 @@ pub enum ViewError {
     /// Some blobs were not found.
     #[error("Blobs not found: {0:?}")]
     BlobsNotFound(Vec<BlobId>),
+
+    /// The same entry of a collection view was requested more than once in the same call.
+    #[error("The collection entry {0:?} was requested more than once")]
+    DuplicateCollectionEntry(Vec<u8>),
 }
 
 impl ViewError {
@@ -23,10 +23,9 @@ use {
 
 use crate::{
     batch::Batch,
-    common::{CustomSerialize, HasherOutput, Update},
+    common::{sorted_keys, CustomSerialize, HasherOutput, Update},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
-    store::KeyIterable,
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
 
@@ -898,7 +897,8 @@ where
         let mut update = updates.next();
         if !self.delete_storage_first {
             let base = self.get_index_key(&[]);
-            for index in self.context.find_keys_by_prefix(&base).await?.iterator() {
+            let keys = self.context.find_keys_by_prefix(&base).await?;
+            for index in sorted_keys(&keys, C::SORTED_SEARCHES)? {
                 let index = index?;
                 loop {
                     match update {
@@ -15,10 +15,9 @@ use {
 
 use crate::{
     batch::Batch,
-    common::{CustomSerialize, HasherOutput, Update},
+    common::{sorted_keys, CustomSerialize, HasherOutput, Update},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
-    store::KeyIterable,
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
 
@@ -284,7 +283,8 @@ where
         let mut update = updates.next();
         if !self.delete_storage_first {
             let base = self.context.base_key();
-            for index in self.context.find_keys_by_prefix(&base).await?.iterator() {
+            let keys = self.context.find_keys_by_prefix(&base).await?;
+            for index in sorted_keys(&keys, C::SORTED_SEARCHES)? {
                 let index = index?;
                 loop {
                     match update {
@@ -967,71 +967,115 @@ mod graphql {
     use std::borrow::Cow;
 
     use super::{CustomSetView, SetView};
-    use crate::context::Context;
+    use crate::{
+        common::CustomSerialize,
+        context::Context,
+        graphql::{encode_cursor, hash_name, mangle, Connection, Edge, KeyPage},
+    };
 
-    impl<C: Context, I: async_graphql::OutputType> async_graphql::OutputType for SetView<C, I>
-    where
-        C: Send + Sync,
-        I: serde::ser::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync,
-    {
+    impl<C: Send + Sync, I: async_graphql::OutputType> async_graphql::TypeName for SetView<C, I> {
         fn type_name() -> Cow<'static, str> {
-            format!("[{}]", I::qualified_type_name()).into()
-        }
-
-        fn qualified_type_name() -> String {
-            format!("[{}]!", I::qualified_type_name())
+            format!(
+                "SetView_{}_{:08x}",
+                mangle(I::type_name()),
+                hash_name::<I>()
+            )
+            .into()
         }
+    }
 
-        fn create_type_info(registry: &mut async_graphql::registry::Registry) -> String {
-            I::create_type_info(registry);
-            Self::qualified_type_name()
+    #[async_graphql::Object(cache_control(no_cache), name_type)]
+    impl<C, I> SetView<C, I>
+    where
+        C: Context + Send + Sync,
+        I: async_graphql::OutputType
+            + serde::ser::Serialize
+            + serde::de::DeserializeOwned
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+    {
+        async fn elements(&self, count: Option<usize>) -> Result<Vec<I>, async_graphql::Error> {
+            let mut indices = self.indices().await?;
+            if let Some(count) = count {
+                indices.truncate(count);
+            }
+            Ok(indices)
         }
 
-        async fn resolve(
+        /// Returns the `first` elements following the `after` cursor, in the order of the
+        /// serialized elements. Pages hold at most 1000 elements.
+        async fn entries_connection(
             &self,
-            ctx: &async_graphql::ContextSelectionSet<'_>,
-            field: &async_graphql::Positioned<async_graphql::parser::types::Field>,
-        ) -> async_graphql::ServerResult<async_graphql::Value> {
-            let indices = self
-                .indices()
-                .await
-                .map_err(|e| async_graphql::Error::from(e).into_server_error(ctx.item.pos))?;
-            let indices_len = indices.len();
-            async_graphql::resolver_utils::resolve_list(ctx, field, indices, Some(indices_len))
-                .await
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<I>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.set
+                .for_each_key_while(|key| Ok(page.visit(key)))
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                edges.push(Edge {
+                    node: C::deserialize_value(&short_key)?,
+                    cursor: encode_cursor(&short_key),
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
         }
     }
 
-    impl<C: Context, I: async_graphql::OutputType> async_graphql::OutputType for CustomSetView<C, I>
-    where
-        C: Send + Sync,
-        I: crate::common::CustomSerialize + Clone + Send + Sync,
-    {
+    impl<C: Send + Sync, I: async_graphql::OutputType> async_graphql::TypeName for CustomSetView<C, I> {
         fn type_name() -> Cow<'static, str> {
-            format!("[{}]", I::qualified_type_name()).into()
-        }
-
-        fn qualified_type_name() -> String {
-            format!("[{}]!", I::qualified_type_name())
+            format!(
+                "CustomSetView_{}_{:08x}",
+                mangle(I::type_name()),
+                hash_name::<I>()
+            )
+            .into()
         }
+    }
 
-        fn create_type_info(registry: &mut async_graphql::registry::Registry) -> String {
-            I::create_type_info(registry);
-            Self::qualified_type_name()
+    #[async_graphql::Object(cache_control(no_cache), name_type)]
+    impl<C, I> CustomSetView<C, I>
+    where
+        C: Context + Send + Sync,
+        I: async_graphql::OutputType + CustomSerialize + Clone + Send + Sync + 'static,
+    {
+        async fn elements(&self, count: Option<usize>) -> Result<Vec<I>, async_graphql::Error> {
+            let mut indices = self.indices().await?;
+            if let Some(count) = count {
+                indices.truncate(count);
+            }
+            Ok(indices)
         }
 
-        async fn resolve(
+        /// Returns the `first` elements following the `after` cursor, in the order of the
+        /// serialized elements. Pages hold at most 1000 elements.
+        async fn entries_connection(
             &self,
-            ctx: &async_graphql::ContextSelectionSet<'_>,
-            field: &async_graphql::Positioned<async_graphql::parser::types::Field>,
-        ) -> async_graphql::ServerResult<async_graphql::Value> {
-            let indices = self
-                .indices()
-                .await
-                .map_err(|e| async_graphql::Error::from(e).into_server_error(ctx.item.pos))?;
-            let indices_len = indices.len();
-            async_graphql::resolver_utils::resolve_list(ctx, field, indices, Some(indices_len))
-                .await
+            first: Option<usize>,
+            after: Option<String>,
+        ) -> Result<Connection<I>, async_graphql::Error> {
+            let mut page = KeyPage::new(first, after)?;
+            self.set
+                .for_each_key_while(|key| Ok(page.visit(key)))
+                .await?;
+            let (keys, has_next_page) = page.finish();
+
+            let mut edges = vec![];
+            for short_key in keys {
+                edges.push(Edge {
+                    node: I::from_custom_bytes(&short_key)?,
+                    cursor: encode_cursor(&short_key),
+                })
+            }
+
+            Ok(Connection::new(edges, has_next_page, None))
         }
     }
 }
@@ -0,0 +1,105 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use linera_base::crypto::CryptoHash;
+use linera_views::{
+    audit_log_view::{empty_root_hash, verify_inclusion, AuditLogView, InclusionProofError},
+    context::{create_test_memory_context, Context},
+    views::{HashableView, RootView, View, ViewError},
+};
+use linera_views_derive::RootView;
+
+#[derive(RootView)]
+struct AuditedState<C> {
+    log: AuditLogView<C, String>,
+}
+
+fn entry(index: usize) -> String {
+    format!("Transfer number {index}")
+}
+
+/// Creates a state whose log has `count` entries, with the first `saved` of them persisted.
+async fn create_state<C>(context: C, count: usize, saved: usize) -> Result<AuditedState<C>>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    ViewError: From<C::Error>,
+{
+    let mut state = AuditedState::load(context.clone()).await?;
+    for index in 0..saved {
+        state.log.append(entry(index)).await?;
+    }
+    state.save().await?;
+    let mut state = AuditedState::load(context).await?;
+    for index in saved..count {
+        state.log.append(entry(index)).await?;
+    }
+    Ok(state)
+}
+
+#[tokio::test]
+async fn test_inclusion_proofs_verify_against_the_root_hash() -> Result<()> {
+    let state = create_state(create_test_memory_context(), 8, 5).await?;
+    let root_hash = state.log.root_hash().await?;
+    assert_eq!(
+        CryptoHash::try_from(state.log.hash().await?.as_slice())?,
+        root_hash
+    );
+    assert_eq!(
+        state.log.get(..).await?,
+        (0..8).map(entry).collect::<Vec<_>>()
+    );
+
+    for index in [0, 3, 4, 5, 7] {
+        let proof = state
+            .log
+            .prove_inclusion(index)
+            .await?
+            .expect("entry should exist");
+        assert_eq!(proof.index, index);
+        assert_eq!(proof.later_entry_hashes.len(), 7 - index);
+        verify_inclusion(root_hash, &entry(index), &proof)?;
+    }
+    assert!(state.log.prove_inclusion(8).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_entry_is_rejected() -> Result<()> {
+    let state = create_state(create_test_memory_context(), 4, 4).await?;
+    let root_hash = state.log.root_hash().await?;
+    let proof = state.log.prove_inclusion(2).await?.unwrap();
+
+    assert!(matches!(
+        verify_inclusion(root_hash, &"Tampered transfer".to_string(), &proof),
+        Err(InclusionProofError::RootHashMismatch { expected, .. }) if expected == root_hash
+    ));
+    assert!(matches!(
+        verify_inclusion(root_hash, &entry(1), &proof),
+        Err(InclusionProofError::RootHashMismatch { .. })
+    ));
+
+    let mut truncated_proof = proof.clone();
+    truncated_proof.later_entry_hashes.pop();
+    assert!(matches!(
+        verify_inclusion(root_hash, &entry(2), &truncated_proof),
+        Err(InclusionProofError::RootHashMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_root_hash_is_independent_of_saving() -> Result<()> {
+    let unsaved = create_state(create_test_memory_context(), 6, 0).await?;
+    let partially_saved = create_state(create_test_memory_context(), 6, 3).await?;
+    let mut cleared = create_state(create_test_memory_context(), 6, 6).await?;
+
+    let root_hash = unsaved.log.root_hash().await?;
+    assert_eq!(partially_saved.log.root_hash().await?, root_hash);
+    assert_eq!(cleared.log.root_hash().await?, root_hash);
+
+    cleared.log.clear();
+    assert_eq!(cleared.log.count(), 0);
+    assert_eq!(cleared.log.root_hash().await?, empty_root_hash());
+    Ok(())
+}
@@ -0,0 +1,257 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_graphql::{EmptyMutation, EmptySubscription, Schema};
+use linera_views::{
+    collection_view::CollectionView,
+    context::{create_test_memory_context, MemoryContext},
+    log_view::LogView,
+    map_view::MapView,
+    register_view::RegisterView,
+    set_view::SetView,
+    views::{RootView, View},
+};
+use linera_views_derive::RootView;
+use serde::{de::DeserializeOwned, Deserialize};
+
+const ENTRIES: usize = 1000;
+const PAGE_SIZE: usize = 100;
+/// The largest number of items in a page.
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(RootView, async_graphql::SimpleObject)]
+#[view(context = "MemoryContext<()>")]
+struct PagedState {
+    map: MapView<MemoryContext<()>, u32, String>,
+    log: LogView<MemoryContext<()>, u32>,
+    collection: CollectionView<MemoryContext<()>, u32, RegisterView<MemoryContext<()>, u32>>,
+    set: SetView<MemoryContext<()>, u32>,
+}
+
+type PagedSchema = Schema<PagedState, EmptyMutation, EmptySubscription>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page<T> {
+    edges: Vec<Edge<T>>,
+    page_info: PageInfo,
+    total_count: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Edge<T> {
+    cursor: String,
+    node: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Entry<V> {
+    key: u32,
+    value: V,
+}
+
+/// Creates a schema over a state with `ENTRIES` items in each view.
+async fn create_schema() -> Result<PagedSchema> {
+    let context = create_test_memory_context();
+    let mut state = PagedState::load(context.clone()).await?;
+    for index in 0..ENTRIES as u32 {
+        state.map.insert(&index, format!("Value {index}"))?;
+        state.log.push(index);
+        state.collection.load_entry_mut(&index).await?.set(index);
+        state.set.insert(&index)?;
+    }
+    state.save().await?;
+    let state = PagedState::load(context).await?;
+    Ok(Schema::new(state, EmptyMutation, EmptySubscription))
+}
+
+/// Queries one page of the `entriesConnection` field of the view `field`.
+async fn query_page<T: DeserializeOwned>(
+    schema: &PagedSchema,
+    field: &str,
+    node: &str,
+    after: Option<&str>,
+) -> Result<Page<T>> {
+    let after = after
+        .map(|cursor| format!(", after: \"{cursor}\""))
+        .unwrap_or_default();
+    let query = format!(
+        "{{ {field} {{ entriesConnection(first: {PAGE_SIZE}{after}) {{ \
+         edges {{ cursor node {node} }} pageInfo {{ hasNextPage endCursor }} totalCount }} }} }}"
+    );
+    let response = schema.execute(query).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let mut data: BTreeMap<String, BTreeMap<String, Page<T>>> =
+        async_graphql::from_value(response.data)?;
+    Ok(data
+        .remove(field)
+        .and_then(|mut view| view.remove("entriesConnection"))
+        .expect("the queried field should be in the response"))
+}
+
+/// Follows the cursors of `field` page by page and returns all the nodes. Only views that
+/// keep count of their items report a `total_count`.
+async fn query_all_pages<T: DeserializeOwned>(
+    schema: &PagedSchema,
+    field: &str,
+    node: &str,
+    total_count: Option<usize>,
+) -> Result<Vec<T>> {
+    let mut nodes = Vec::new();
+    let mut after = None;
+    let mut pages = 0;
+    loop {
+        let page = query_page::<T>(schema, field, node, after.as_deref()).await?;
+        pages += 1;
+        assert_eq!(page.total_count, total_count);
+        assert_eq!(page.edges.len(), PAGE_SIZE);
+        assert_eq!(
+            page.page_info.end_cursor.as_ref(),
+            page.edges.last().map(|edge| &edge.cursor)
+        );
+        nodes.extend(page.edges.into_iter().map(|edge| edge.node));
+        if !page.page_info.has_next_page {
+            break;
+        }
+        after = page.page_info.end_cursor;
+    }
+    assert_eq!(pages, ENTRIES / PAGE_SIZE);
+    Ok(nodes)
+}
+
+#[tokio::test]
+async fn test_graphql_schema_exposes_connections() -> Result<()> {
+    let sdl = create_schema().await?.sdl();
+    assert_eq!(
+        sdl.matches("entriesConnection(first: Int, after: String): Connection_")
+            .count(),
+        4
+    );
+    assert!(sdl.contains("type ViewPageInfo"));
+    assert!(sdl.contains("hasNextPage: Boolean!"));
+    assert!(sdl.contains("endCursor: String"));
+    assert!(sdl.contains("totalCount: Int\n"));
+    assert!(sdl.contains("elements(count: Int): [Int!]!"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_map_view_pages() -> Result<()> {
+    let schema = create_schema().await?;
+    let entries = query_all_pages::<Entry<String>>(&schema, "map", "{ key value }", None).await?;
+
+    // Keys are in the order of their serialization, as in `MapView::indices`.
+    let keys = entries.iter().map(|entry| entry.key).collect::<Vec<_>>();
+    let mut expected_keys = (0..ENTRIES as u32).collect::<Vec<_>>();
+    expected_keys.sort_by_key(|key| bcs::to_bytes(key).unwrap());
+    assert_eq!(keys, expected_keys);
+    for entry in entries {
+        assert_eq!(entry.value, format!("Value {}", entry.key));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_collection_view_pages() -> Result<()> {
+    let schema = create_schema().await?;
+    let entries =
+        query_all_pages::<Entry<u32>>(&schema, "collection", "{ key value }", None).await?;
+
+    let mut expected_keys = (0..ENTRIES as u32).collect::<Vec<_>>();
+    expected_keys.sort_by_key(|key| bcs::to_bytes(key).unwrap());
+    assert_eq!(
+        entries,
+        expected_keys
+            .into_iter()
+            .map(|key| Entry { key, value: key })
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_log_view_pages() -> Result<()> {
+    let schema = create_schema().await?;
+    let entries = query_all_pages::<u32>(&schema, "log", "", Some(ENTRIES)).await?;
+    assert_eq!(entries, (0..ENTRIES as u32).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_set_view_pages() -> Result<()> {
+    let schema = create_schema().await?;
+    let elements = query_all_pages::<u32>(&schema, "set", "", None).await?;
+
+    let mut expected_elements = (0..ENTRIES as u32).collect::<Vec<_>>();
+    expected_elements.sort_by_key(|element| bcs::to_bytes(element).unwrap());
+    assert_eq!(elements, expected_elements);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_page_size_is_capped() -> Result<()> {
+    let context = create_test_memory_context();
+    let mut state = PagedState::load(context.clone()).await?;
+    for index in 0..=MAX_PAGE_SIZE as u32 {
+        state.map.insert(&index, format!("Value {index}"))?;
+    }
+    state.save().await?;
+    let schema = Schema::new(
+        PagedState::load(context).await?,
+        EmptyMutation,
+        EmptySubscription,
+    );
+
+    for arguments in ["", "(first: 5000)"] {
+        let query = format!(
+            "{{ map {{ entriesConnection{arguments} {{ \
+             edges {{ cursor node {{ key value }} }} pageInfo {{ hasNextPage endCursor }} \
+             totalCount }} }} }}"
+        );
+        let response = schema.execute(query).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let mut data: BTreeMap<String, BTreeMap<String, Page<Entry<String>>>> =
+            async_graphql::from_value(response.data)?;
+        let page = data
+            .remove("map")
+            .and_then(|mut view| view.remove("entriesConnection"))
+            .expect("the queried field should be in the response");
+        assert_eq!(page.edges.len(), MAX_PAGE_SIZE);
+        assert!(page.page_info.has_next_page);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_graphql_pages_past_the_end_and_invalid_cursors() -> Result<()> {
+    let schema = create_schema().await?;
+    let first = query_page::<Entry<String>>(&schema, "map", "{ key value }", None).await?;
+    let mut after = first.page_info.end_cursor;
+    for _ in 1..ENTRIES / PAGE_SIZE {
+        let page =
+            query_page::<Entry<String>>(&schema, "map", "{ key value }", after.as_deref()).await?;
+        after = page.page_info.end_cursor;
+    }
+    let last =
+        query_page::<Entry<String>>(&schema, "map", "{ key value }", after.as_deref()).await?;
+    assert!(last.edges.is_empty());
+    assert!(!last.page_info.has_next_page);
+    assert_eq!(last.page_info.end_cursor, None);
+    assert_eq!(last.total_count, None);
+
+    let response = schema
+        .execute(r#"{ map { entriesConnection(first: 1, after: "not hex") { totalCount } } }"#)
+        .await;
+    assert_eq!(response.errors.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,371 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks the views against simple models built with standard collections.
+//!
+//! Random sequences of operations are applied to a view and to its model, including saving,
+//! rolling back and reloading the view. After each step, the content of the view must match
+//! the model. Whenever the view is saved, its hash must match the hash of a view built
+//! directly from the model, and flushing it again must have no effect.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use linera_views::{
+    batch::Batch,
+    bucket_queue_view::HashedBucketQueueView,
+    collection_view::HashedCollectionView,
+    context::{create_test_memory_context, Context},
+    key_value_store_view::KeyValueStoreView,
+    log_view::HashedLogView,
+    map_view::HashedMapView,
+    queue_view::HashedQueueView,
+    reentrant_collection_view::HashedReentrantCollectionView,
+    register_view::HashedRegisterView,
+    set_view::HashedSetView,
+    views::{CryptoHashRootView, CryptoHashView, RootView, View, ViewError},
+};
+#[cfg(with_rocksdb)]
+use linera_views::{context::ViewContext, rocks_db::RocksDbStore, store::TestKeyValueStore as _};
+use proptest::{collection::vec, prelude::any};
+use test_strategy::{proptest, Arbitrary};
+
+/// The number of distinct keys used by the operations. It is small, so that operations often
+/// affect the same entries.
+const KEYS: u8 = 4;
+
+/// The maximal number of operations in a test case.
+const MAX_OPERATIONS: usize = 40;
+
+/// A view with one field of each view type.
+#[derive(CryptoHashRootView)]
+pub struct ModelCheckedView<C> {
+    pub register: HashedRegisterView<C, u16>,
+    pub log: HashedLogView<C, u16>,
+    pub queue: HashedQueueView<C, u16>,
+    pub bucket_queue: HashedBucketQueueView<C, u16, 3>,
+    pub map: HashedMapView<C, u8, u16>,
+    pub set: HashedSetView<C, u8>,
+    pub collection: HashedCollectionView<C, u8, HashedRegisterView<C, u16>>,
+    pub reentrant_collection: HashedReentrantCollectionView<C, u8, HashedLogView<C, u16>>,
+    pub key_value_store: KeyValueStoreView<C>,
+}
+
+/// The expected content of a [`ModelCheckedView`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Model {
+    register: u16,
+    log: Vec<u16>,
+    queue: VecDeque<u16>,
+    bucket_queue: VecDeque<u16>,
+    map: BTreeMap<u8, u16>,
+    set: BTreeSet<u8>,
+    collection: BTreeMap<u8, u16>,
+    reentrant_collection: BTreeMap<u8, Vec<u16>>,
+    key_value_store: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// An operation on a [`ModelCheckedView`].
+///
+/// Operations only carry small values, so that failing test cases shrink to readable ones.
+#[derive(Arbitrary, Clone, Debug)]
+enum ViewOperation {
+    SetRegister(u16),
+    PushLog(u16),
+    PushQueue(u16),
+    DeleteQueueFront,
+    PushBucketQueue(u16),
+    DeleteBucketQueueFront,
+    InsertMap(#[strategy(0..KEYS)] u8, u16),
+    RemoveMap(#[strategy(0..KEYS)] u8),
+    InsertSet(#[strategy(0..KEYS)] u8),
+    RemoveSet(#[strategy(0..KEYS)] u8),
+    SetCollectionEntry(#[strategy(0..KEYS)] u8, u16),
+    ResetCollectionEntry(#[strategy(0..KEYS)] u8),
+    RemoveCollectionEntry(#[strategy(0..KEYS)] u8),
+    PushReentrantCollectionEntry(#[strategy(0..KEYS)] u8, u16),
+    RemoveReentrantCollectionEntry(#[strategy(0..KEYS)] u8),
+    InsertKeyValue(
+        #[strategy(vec(0..KEYS, 1..3))] Vec<u8>,
+        #[strategy(vec(any::<u8>(), 0..3))] Vec<u8>,
+    ),
+    RemoveKeyValue(#[strategy(vec(0..KEYS, 1..3))] Vec<u8>),
+    RemoveKeyValuesByPrefix(#[strategy(vec(0..KEYS, 1..2))] Vec<u8>),
+    /// Resets the whole view to its default value.
+    Clear,
+    /// Saves the staged changes to storage.
+    #[weight(2)]
+    Save,
+    /// Discards the staged changes.
+    Rollback,
+    /// Loads the view again from storage, discarding the staged changes.
+    Reload,
+}
+
+impl Model {
+    /// Applies a staged change to the model. Operations that don't change the content are
+    /// handled by [`check_view_semantics`].
+    fn apply(&mut self, operation: &ViewOperation) {
+        use ViewOperation::*;
+        match operation {
+            SetRegister(value) => self.register = *value,
+            PushLog(value) => self.log.push(*value),
+            PushQueue(value) => self.queue.push_back(*value),
+            DeleteQueueFront => {
+                self.queue.pop_front();
+            }
+            PushBucketQueue(value) => self.bucket_queue.push_back(*value),
+            DeleteBucketQueueFront => {
+                self.bucket_queue.pop_front();
+            }
+            InsertMap(key, value) => {
+                self.map.insert(*key, *value);
+            }
+            RemoveMap(key) => {
+                self.map.remove(key);
+            }
+            InsertSet(key) => {
+                self.set.insert(*key);
+            }
+            RemoveSet(key) => {
+                self.set.remove(key);
+            }
+            SetCollectionEntry(key, value) => {
+                self.collection.insert(*key, *value);
+            }
+            ResetCollectionEntry(key) => {
+                self.collection.insert(*key, 0);
+            }
+            RemoveCollectionEntry(key) => {
+                self.collection.remove(key);
+            }
+            PushReentrantCollectionEntry(key, value) => {
+                self.reentrant_collection
+                    .entry(*key)
+                    .or_default()
+                    .push(*value);
+            }
+            RemoveReentrantCollectionEntry(key) => {
+                self.reentrant_collection.remove(key);
+            }
+            InsertKeyValue(key, value) => {
+                self.key_value_store.insert(key.clone(), value.clone());
+            }
+            RemoveKeyValue(key) => {
+                self.key_value_store.remove(key);
+            }
+            RemoveKeyValuesByPrefix(prefix) => {
+                self.key_value_store
+                    .retain(|key, _| !key.starts_with(prefix));
+            }
+            Clear => *self = Model::default(),
+            Save | Rollback | Reload => {}
+        }
+    }
+}
+
+impl<C> ModelCheckedView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    ViewError: From<C::Error>,
+{
+    /// Applies a staged change to the view.
+    async fn apply(&mut self, operation: &ViewOperation) -> Result<(), ViewError> {
+        use ViewOperation::*;
+        match operation {
+            SetRegister(value) => self.register.set(*value),
+            PushLog(value) => self.log.push(*value),
+            PushQueue(value) => self.queue.push_back(*value),
+            DeleteQueueFront => self.queue.delete_front(),
+            PushBucketQueue(value) => self.bucket_queue.push_back(*value),
+            DeleteBucketQueueFront => self.bucket_queue.delete_front().await?,
+            InsertMap(key, value) => self.map.insert(key, *value)?,
+            RemoveMap(key) => self.map.remove(key)?,
+            InsertSet(key) => self.set.insert(key)?,
+            RemoveSet(key) => self.set.remove(key)?,
+            SetCollectionEntry(key, value) => {
+                self.collection.load_entry_mut(key).await?.set(*value);
+            }
+            ResetCollectionEntry(key) => self.collection.reset_entry_to_default(key)?,
+            RemoveCollectionEntry(key) => self.collection.remove_entry(key)?,
+            PushReentrantCollectionEntry(key, value) => {
+                self.reentrant_collection
+                    .try_load_entry_mut(key)
+                    .await?
+                    .push(*value);
+            }
+            RemoveReentrantCollectionEntry(key) => self.reentrant_collection.remove_entry(key)?,
+            InsertKeyValue(key, value) => {
+                self.key_value_store
+                    .insert(key.clone(), value.clone())
+                    .await?;
+            }
+            RemoveKeyValue(key) => self.key_value_store.remove(key.clone()).await?,
+            RemoveKeyValuesByPrefix(prefix) => {
+                self.key_value_store
+                    .remove_by_prefix(prefix.clone())
+                    .await?;
+            }
+            Clear => self.clear(),
+            Save | Rollback | Reload => {}
+        }
+        Ok(())
+    }
+
+    /// Creates a view in the given `context` with the content of the `model`, without saving
+    /// it.
+    async fn from_model(context: C, model: &Model) -> Result<Self, ViewError> {
+        let mut view = Self::load(context).await?;
+        view.register.set(model.register);
+        for value in &model.log {
+            view.log.push(*value);
+        }
+        for value in &model.queue {
+            view.queue.push_back(*value);
+        }
+        for value in &model.bucket_queue {
+            view.bucket_queue.push_back(*value);
+        }
+        for (key, value) in &model.map {
+            view.map.insert(key, *value)?;
+        }
+        for key in &model.set {
+            view.set.insert(key)?;
+        }
+        for (key, value) in &model.collection {
+            view.collection.load_entry_mut(key).await?.set(*value);
+        }
+        for (key, values) in &model.reentrant_collection {
+            let mut entry = view.reentrant_collection.try_load_entry_mut(key).await?;
+            for value in values {
+                entry.push(*value);
+            }
+        }
+        for (key, value) in &model.key_value_store {
+            view.key_value_store
+                .insert(key.clone(), value.clone())
+                .await?;
+        }
+        Ok(view)
+    }
+
+    /// Reads the whole content of the view.
+    async fn to_model(&self) -> Result<Model, ViewError> {
+        let mut collection = BTreeMap::new();
+        for key in self.collection.indices().await? {
+            let entry = self
+                .collection
+                .try_load_entry(&key)
+                .await?
+                .expect("listed entries should exist");
+            collection.insert(key, *entry.get());
+        }
+        let mut reentrant_collection = BTreeMap::new();
+        for key in self.reentrant_collection.indices().await? {
+            let entry = self
+                .reentrant_collection
+                .try_load_entry(&key)
+                .await?
+                .expect("listed entries should exist");
+            reentrant_collection.insert(key, entry.read(..).await?);
+        }
+        Ok(Model {
+            register: *self.register.get(),
+            log: self.log.read(..).await?,
+            queue: self.queue.elements().await?.into(),
+            bucket_queue: self.bucket_queue.elements().await?.into(),
+            map: self.map.index_values().await?.into_iter().collect(),
+            set: self.set.indices().await?.into_iter().collect(),
+            collection,
+            reentrant_collection,
+            key_value_store: self
+                .key_value_store
+                .index_values()
+                .await?
+                .into_iter()
+                .collect(),
+        })
+    }
+}
+
+/// Applies the `operations` to a view stored in `context`, which must be empty, and checks
+/// its behavior against the model.
+async fn check_view_semantics<C>(context: C, operations: &[ViewOperation]) -> Result<(), ViewError>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    ViewError: From<C::Error>,
+{
+    let mut view = ModelCheckedView::load(context.clone()).await?;
+    let mut staged = Model::default();
+    let mut saved = Model::default();
+
+    for (step, operation) in operations.iter().enumerate() {
+        match operation {
+            ViewOperation::Save => {
+                // The hash only depends on the content, not on the operations leading to it.
+                let hash = view.crypto_hash_mut().await?;
+                let reference_hash =
+                    ModelCheckedView::from_model(create_test_memory_context(), &staged)
+                        .await?
+                        .crypto_hash_mut()
+                        .await?;
+                assert_eq!(hash, reference_hash, "hash mismatch at step {step}");
+
+                view.save().await?;
+                saved = staged.clone();
+
+                // Once saved, flushing again has no effect.
+                assert!(
+                    !view.has_pending_changes().await,
+                    "pending changes after saving at step {step}"
+                );
+                let mut batch = Batch::new();
+                view.flush(&mut batch)?;
+                assert!(
+                    batch.is_empty(),
+                    "non-empty flush after saving at step {step}"
+                );
+
+                // The saved view has the same content and hash.
+                let mut reloaded_view = ModelCheckedView::load(context.clone()).await?;
+                assert_eq!(reloaded_view.to_model().await?, saved, "at step {step}");
+                assert_eq!(
+                    reloaded_view.crypto_hash_mut().await?,
+                    hash,
+                    "hash changed after saving at step {step}"
+                );
+            }
+            ViewOperation::Rollback => {
+                view.rollback();
+                staged = saved.clone();
+            }
+            ViewOperation::Reload => {
+                view = ModelCheckedView::load(context.clone()).await?;
+                staged = saved.clone();
+            }
+            operation => {
+                view.apply(operation).await?;
+                staged.apply(operation);
+            }
+        }
+        assert_eq!(view.to_model().await?, staged, "at step {step}");
+    }
+    Ok(())
+}
+
+#[proptest(async = "tokio")]
+async fn test_view_semantics_in_memory(
+    #[strategy(vec(any::<ViewOperation>(), 0..MAX_OPERATIONS))] operations: Vec<ViewOperation>,
+) {
+    let context = create_test_memory_context();
+    check_view_semantics(context, &operations).await.unwrap();
+}
+
+#[cfg(with_rocksdb)]
+#[proptest(async = "tokio")]
+async fn test_view_semantics_in_rocks_db(
+    #[strategy(vec(any::<ViewOperation>(), 0..MAX_OPERATIONS))] operations: Vec<ViewOperation>,
+) {
+    let store = RocksDbStore::new_test_store().await.unwrap();
+    let context = ViewContext::create_root_context(store, ()).await.unwrap();
+    check_view_semantics(context, &operations).await.unwrap();
+}
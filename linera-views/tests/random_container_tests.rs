@@ -5,15 +5,19 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use anyhow::Result;
 use linera_views::{
+    batch::Batch,
     bucket_queue_view::HashedBucketQueueView,
-    collection_view::HashedCollectionView,
-    context::{create_test_memory_context, Context},
+    collection_view::{ByteCollectionView, HashedCollectionView},
+    context::{create_test_memory_context, Context, ViewContext},
     key_value_store_view::{KeyValueStoreView, SizeData},
-    map_view::HashedByteMapView,
+    map_view::{ByteMapView, HashedByteMapView, MapView},
+    memory::{MemoryStore, MemoryStoreError, TEST_MEMORY_MAX_STREAM_QUERIES},
     queue_view::HashedQueueView,
-    random::make_deterministic_rng,
+    random::{generate_test_namespace, make_deterministic_rng},
     reentrant_collection_view::HashedReentrantCollectionView,
     register_view::RegisterView,
+    set_view::ByteSetView,
+    store::{ReadableKeyValueStore, WithError, WritableKeyValueStore},
     views::{CryptoHashRootView, CryptoHashView, RootView, View, ViewError},
 };
 use rand::{distributions::Uniform, Rng, RngCore};
@@ -403,6 +407,87 @@ async fn map_view_mutability() -> Result<()> {
     Ok(())
 }
 
+fn random_short_keys<R: RngCore + Clone>(rng: &mut R, count: usize) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|_| {
+            let len = rng.gen_range(1..6);
+            rng.clone()
+                .sample_iter(Uniform::from(0..4))
+                .take(len)
+                .collect()
+        })
+        .collect()
+}
+
+/// Checks that `extend` and `remove_many` stage the same updates and produce the same batches
+/// as repeated calls to `insert` and `remove`, with sorted and unsorted keys.
+#[tokio::test]
+async fn map_view_bulk_operations_check() -> Result<()> {
+    let mut rng = make_deterministic_rng();
+    let context = create_test_memory_context();
+    for _ in 0..20 {
+        let mut bulk_map = ByteMapView::<_, u8>::load(context.clone()).await?;
+        let mut repeated_map = ByteMapView::<_, u8>::load(context.clone()).await?;
+        for _ in 0..10 {
+            match rng.gen_range(0..3) {
+                0 => {
+                    let count = rng.gen_range(0..20);
+                    let mut entries = random_short_keys(&mut rng, count)
+                        .into_iter()
+                        .map(|key| (key, rng.gen::<u8>()))
+                        .collect::<Vec<_>>();
+                    if rng.gen::<bool>() {
+                        entries.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+                        entries.dedup_by(|(key1, _), (key2, _)| key1 == key2);
+                    }
+                    for (key, value) in entries.clone() {
+                        repeated_map.insert(key, value);
+                    }
+                    bulk_map.extend(entries);
+                }
+                1 => {
+                    let count = rng.gen_range(0..20);
+                    let keys = random_short_keys(&mut rng, count);
+                    for key in keys.clone() {
+                        repeated_map.remove(key);
+                    }
+                    bulk_map.remove_many(keys);
+                }
+                _ => {
+                    let key_prefix = vec![rng.gen_range(0..4)];
+                    repeated_map.remove_by_prefix(key_prefix.clone());
+                    bulk_map.remove_by_prefix(key_prefix);
+                }
+            }
+            assert_eq!(
+                bulk_map.key_values().await?,
+                repeated_map.key_values().await?
+            );
+        }
+        let mut bulk_batch = Batch::new();
+        bulk_map.flush(&mut bulk_batch)?;
+        let mut repeated_batch = Batch::new();
+        repeated_map.flush(&mut repeated_batch)?;
+        assert_eq!(bulk_batch, repeated_batch);
+        context.write_batch(repeated_batch).await?;
+    }
+    Ok(())
+}
+
+/// Checks that a map built with `from_entries` keeps the last value of repeated indices.
+#[tokio::test]
+async fn map_view_extend_check() -> Result<()> {
+    let context = create_test_memory_context();
+    let entries = (0..1000u32).rev().map(|index| (index % 300, index));
+    let map = MapView::<_, u32, u32>::from_entries(context, entries).await?;
+    let index_values = map.index_values().await?;
+    assert_eq!(index_values.len(), 300);
+    assert!(index_values
+        .into_iter()
+        .all(|(index, value)| value == index));
+    Ok(())
+}
+
 #[derive(CryptoHashRootView)]
 pub struct BucketQueueStateView<C> {
     pub queue: HashedBucketQueueView<C, u8, 5>,
@@ -766,3 +851,143 @@ async fn reentrant_collection_view_check() -> Result<()> {
     }
     Ok(())
 }
+
+/// A memory store returning the results of search queries in reverse lexicographic order, in
+/// order to check that the views don't depend on the order chosen by the backend.
+#[derive(Clone)]
+struct ReversedMemoryStore(MemoryStore);
+
+impl WithError for ReversedMemoryStore {
+    type Error = MemoryStoreError;
+}
+
+impl ReadableKeyValueStore for ReversedMemoryStore {
+    const MAX_KEY_SIZE: usize = MemoryStore::MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.0.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, MemoryStoreError> {
+        self.0.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, MemoryStoreError> {
+        self.0.contains_key(key).await
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, MemoryStoreError> {
+        self.0.contains_keys(keys).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, MemoryStoreError> {
+        self.0.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<Vec<u8>>, MemoryStoreError> {
+        let mut keys = self.0.find_keys_by_prefix(key_prefix).await?;
+        keys.reverse();
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, MemoryStoreError> {
+        let mut key_values = self.0.find_key_values_by_prefix(key_prefix).await?;
+        key_values.reverse();
+        Ok(key_values)
+    }
+}
+
+impl WritableKeyValueStore for ReversedMemoryStore {
+    const MAX_VALUE_SIZE: usize = MemoryStore::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), MemoryStoreError> {
+        self.0.write_batch(batch).await
+    }
+
+    async fn clear_journal(&self) -> Result<(), MemoryStoreError> {
+        self.0.clear_journal().await
+    }
+}
+
+fn create_test_reversed_memory_context() -> ViewContext<(), ReversedMemoryStore> {
+    let namespace = generate_test_namespace();
+    let store =
+        MemoryStore::new_for_testing(TEST_MEMORY_MAX_STREAM_QUERIES, &namespace, &[]).unwrap();
+    ViewContext::new_unsafe(ReversedMemoryStore(store), Vec::new(), ())
+}
+
+#[derive(RootView)]
+struct IterationOrderStateView<C> {
+    pub map: ByteMapView<C, u8>,
+    pub set: ByteSetView<C>,
+    pub collection: ByteCollectionView<C, RegisterView<C, u8>>,
+}
+
+/// Randomly interleaves insertions, deletions and flushes, and checks after each step that the
+/// views visit their keys in the same order as a `BTreeMap` model.
+async fn run_iteration_order_check<C, R>(context: C, rng: &mut R) -> Result<()>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    ViewError: From<C::Error>,
+    R: RngCore,
+{
+    let mut view = IterationOrderStateView::load(context).await?;
+    let mut model = BTreeMap::<Vec<u8>, u8>::new();
+    for _ in 0..200 {
+        let len = rng.gen_range(1..4);
+        let key = (0..len).map(|_| rng.gen_range(0..4)).collect::<Vec<u8>>();
+        match rng.gen_range(0..5) {
+            0 | 1 => {
+                let value = rng.gen::<u8>();
+                view.map.insert(key.clone(), value);
+                view.set.insert(key.clone());
+                *view.collection.load_entry_mut(&key).await?.get_mut() = value;
+                model.insert(key, value);
+            }
+            2 => {
+                view.map.remove(key.clone());
+                view.set.remove(key.clone());
+                view.collection.remove_entry(key.clone());
+                model.remove(&key);
+            }
+            3 => {
+                let key_prefix = key[..1].to_vec();
+                view.map.remove_by_prefix(key_prefix.clone());
+                for key in model.keys().filter(|key| key.starts_with(&key_prefix)) {
+                    view.set.remove(key.clone());
+                    view.collection.remove_entry(key.clone());
+                }
+                remove_by_prefix(&mut model, key_prefix);
+            }
+            _ => view.save().await?,
+        }
+        let keys = model.keys().cloned().collect::<Vec<_>>();
+        let key_values = model.clone().into_iter().collect::<Vec<_>>();
+        assert_eq!(view.map.keys().await?, keys);
+        assert_eq!(view.map.key_values().await?, key_values);
+        assert_eq!(view.set.keys().await?, keys);
+        assert_eq!(view.collection.keys().await?, keys);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn iteration_order_check() -> Result<()> {
+    let mut rng = make_deterministic_rng();
+    for _ in 0..5 {
+        run_iteration_order_check(create_test_memory_context(), &mut rng).await?;
+        run_iteration_order_check(create_test_reversed_memory_context(), &mut rng).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(with_rocksdb)]
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use linera_views::{
+    batch::Batch,
+    rocks_db::{PrefixStatistics, RocksDbStoreInternal},
+    store::{
+        AdminKeyValueStore as _, ReadableKeyValueStore as _, TestKeyValueStore as _,
+        WritableKeyValueStore as _,
+    },
+};
+
+/// Creates a store with ten keys of 2 bytes and values of 100 bytes under the prefix `[1]`,
+/// and three keys of 3 bytes and values of 10 bytes under the prefix `[2]`.
+async fn populated_store() -> RocksDbStoreInternal {
+    let store = RocksDbStoreInternal::new_test_store().await.unwrap();
+    let mut batch = Batch::new();
+    for index in 0..10 {
+        batch.put_key_value_bytes(vec![1, index], vec![0; 100]);
+    }
+    for index in 0..3 {
+        batch.put_key_value_bytes(vec![2, index, index], vec![0; 10]);
+    }
+    store.write_batch(batch).await.unwrap();
+    store
+}
+
+fn prefix_statistics(keys: u64, key_bytes: u64, value_bytes: u64) -> PrefixStatistics {
+    PrefixStatistics {
+        keys,
+        key_bytes,
+        value_bytes,
+        complete: true,
+    }
+}
+
+#[tokio::test]
+async fn test_rocks_db_statistics_group_keys_by_prefix() {
+    let store = populated_store().await;
+    let scanned_keys = Arc::new(AtomicU64::new(0));
+    let progress = {
+        let scanned_keys = scanned_keys.clone();
+        move |keys| scanned_keys.store(keys, Ordering::Relaxed)
+    };
+
+    let statistics = store.statistics(1, None, progress).await.unwrap();
+
+    assert_eq!(
+        statistics.prefixes.into_iter().collect::<Vec<_>>(),
+        [
+            (vec![1], prefix_statistics(10, 20, 1000)),
+            (vec![2], prefix_statistics(3, 9, 30)),
+        ]
+    );
+    assert_eq!(scanned_keys.load(Ordering::Relaxed), 13);
+}
+
+#[tokio::test]
+async fn test_rocks_db_statistics_sample_each_prefix() {
+    let store = populated_store().await;
+
+    let statistics = store.statistics(1, Some(4), |_| ()).await.unwrap();
+
+    let mut sampled = prefix_statistics(4, 8, 400);
+    sampled.complete = false;
+    assert_eq!(
+        statistics.prefixes.into_iter().collect::<Vec<_>>(),
+        [(vec![1], sampled), (vec![2], prefix_statistics(3, 9, 30))]
+    );
+}
+
+#[tokio::test]
+async fn test_rocks_db_statistics_are_restricted_to_the_root_key() {
+    let store = populated_store().await;
+    let other_store = store.clone_with_root_key(&[7]).unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], vec![0; 5]);
+    other_store.write_batch(batch).await.unwrap();
+
+    let statistics = other_store.statistics(1, None, |_| ()).await.unwrap();
+
+    assert_eq!(
+        statistics.prefixes.into_iter().collect::<Vec<_>>(),
+        [(vec![1], prefix_statistics(1, 2, 5))]
+    );
+}
+
+#[tokio::test]
+async fn test_rocks_db_compaction_keeps_live_keys() {
+    let store = populated_store().await;
+    let mut batch = Batch::new();
+    batch.delete_key_prefix(vec![1]);
+    store.write_batch(batch).await.unwrap();
+
+    store.compact(Some(&[1])).await.unwrap();
+    store.compact(None).await.unwrap();
+
+    assert!(store.find_keys_by_prefix(&[1]).await.unwrap().is_empty());
+    assert_eq!(store.find_keys_by_prefix(&[2]).await.unwrap().len(), 3);
+    let statistics = store.statistics(1, None, |_| ()).await.unwrap();
+    assert_eq!(
+        statistics.prefixes.into_iter().collect::<Vec<_>>(),
+        [(vec![2], prefix_statistics(3, 9, 30))]
+    );
+    assert!(statistics.sst_files_size.is_some());
+}
@@ -4,13 +4,21 @@
 use linera_views::{
     batch::Batch,
     context::{create_test_memory_context, Context as _},
+    encryption::{
+        EncryptedMemoryStore, EncryptedStore, EncryptionConfig, EncryptionError, EncryptionKey,
+        ReEncryptionProgress, ENVELOPE_OVERHEAD,
+    },
     key_value_store_view::ViewContainer,
     memory::MemoryStore,
+    overlay::OverlayStore,
     random::make_deterministic_rng,
-    store::TestKeyValueStore as _,
+    store::{
+        AdminKeyValueStore as _, ReadableKeyValueStore as _, TestKeyValueStore as _,
+        WritableKeyValueStore as _,
+    },
     test_utils::{
-        big_read_multi_values, get_random_test_scenarios, run_big_write_read, run_reads,
-        run_writes_from_blank, run_writes_from_state,
+        big_read_multi_values, get_random_test_scenarios, get_small_key_space, run_big_write_read,
+        run_read_your_writes, run_reads, run_writes_from_blank, run_writes_from_state,
     },
     value_splitting::create_value_splitting_memory_store,
 };
@@ -45,6 +53,36 @@ async fn test_read_multi_values_scylla_db() {
     big_read_multi_values::<ScyllaDbStore>(config, 22200000, 200).await;
 }
 
+#[tokio::test]
+async fn test_read_your_writes_memory() {
+    let config = MemoryStore::new_test_config().await.unwrap();
+    run_read_your_writes::<MemoryStore>(config).await;
+}
+
+#[cfg(with_rocksdb)]
+#[tokio::test]
+async fn test_read_your_writes_rocks_db() {
+    use linera_views::rocks_db::RocksDbStore;
+    let config = RocksDbStore::new_test_config().await.unwrap();
+    run_read_your_writes::<RocksDbStore>(config).await;
+}
+
+#[cfg(with_dynamodb)]
+#[tokio::test]
+async fn test_read_your_writes_dynamo_db() {
+    use linera_views::dynamo_db::DynamoDbStore;
+    let config = DynamoDbStore::new_test_config().await.unwrap();
+    run_read_your_writes::<DynamoDbStore>(config).await;
+}
+
+#[cfg(with_scylladb)]
+#[tokio::test]
+async fn test_read_your_writes_scylla_db() {
+    use linera_views::scylla_db::ScyllaDbStore;
+    let config = ScyllaDbStore::new_test_config().await.unwrap();
+    run_read_your_writes::<ScyllaDbStore>(config).await;
+}
+
 #[tokio::test]
 async fn test_reads_test_memory() {
     for scenario in get_random_test_scenarios() {
@@ -295,3 +333,461 @@ async fn test_scylla_db_writes_from_state() {
         .unwrap();
     run_writes_from_state(&store).await;
 }
+
+#[tokio::test]
+async fn test_reads_overlay_memory() {
+    for scenario in get_random_test_scenarios() {
+        let store = OverlayStore::new(MemoryStore::new_test_store().await.unwrap());
+        run_reads(store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_overlay_memory_writes_from_blank() {
+    let store = OverlayStore::new(MemoryStore::new_test_store().await.unwrap());
+    run_writes_from_blank(&store).await;
+}
+
+#[tokio::test]
+async fn test_overlay_memory_writes_from_state() {
+    let store = OverlayStore::new(MemoryStore::new_test_store().await.unwrap());
+    run_writes_from_state(&store).await;
+}
+
+/// Creates a memory store with the given key-value pairs.
+async fn create_memory_store(key_values: &[(Vec<u8>, Vec<u8>)]) -> MemoryStore {
+    let store = MemoryStore::new_test_store().await.unwrap();
+    let mut batch = Batch::new();
+    for (key, value) in key_values {
+        batch.put_key_value_bytes(key.clone(), value.clone());
+    }
+    store.write_batch(batch).await.unwrap();
+    store
+}
+
+/// Creates an overlay on top of a memory store with the given key-value pairs.
+async fn create_overlay_memory_store(
+    key_values: &[(Vec<u8>, Vec<u8>)],
+) -> OverlayStore<MemoryStore> {
+    OverlayStore::new(create_memory_store(key_values).await)
+}
+
+#[tokio::test]
+async fn test_overlay_prefix_scans_shadow_base_keys() {
+    let base_key_values = vec![
+        (vec![1, 0], vec![10]),
+        (vec![1, 1], vec![11]),
+        (vec![1, 2, 0], vec![120]),
+        (vec![1, 2, 1], vec![121]),
+        (vec![2, 0], vec![20]),
+    ];
+    let store = create_overlay_memory_store(&base_key_values).await;
+
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 1], vec![111]);
+    batch.delete_key(vec![1, 0]);
+    batch.delete_key_prefix(vec![1, 2]);
+    batch.put_key_value_bytes(vec![1, 2, 5], vec![125]);
+    batch.put_key_value_bytes(vec![1, 3], vec![13]);
+    store.write_batch(batch).await.unwrap();
+
+    let expected_key_values = vec![
+        (vec![1], vec![111]),
+        (vec![2, 5], vec![125]),
+        (vec![3], vec![13]),
+    ];
+    assert_eq!(
+        store.find_key_values_by_prefix(&[1]).await.unwrap(),
+        expected_key_values
+    );
+    assert_eq!(
+        store.find_keys_by_prefix(&[1]).await.unwrap(),
+        vec![vec![1], vec![2, 5], vec![3]]
+    );
+    assert_eq!(
+        store.find_keys_by_prefix(&[1, 2]).await.unwrap(),
+        vec![vec![5]]
+    );
+    assert_eq!(
+        store.find_key_values_by_prefix(&[2]).await.unwrap(),
+        vec![(vec![0], vec![20])]
+    );
+    assert_eq!(store.read_value_bytes(&[1, 2, 0]).await.unwrap(), None);
+    assert_eq!(
+        store
+            .read_multi_values_bytes(vec![vec![1, 0], vec![1, 1], vec![2, 0]])
+            .await
+            .unwrap(),
+        vec![None, Some(vec![111]), Some(vec![20])]
+    );
+    assert_eq!(
+        store
+            .contains_keys(vec![vec![1, 0], vec![1, 2, 1], vec![1, 2, 5], vec![2, 0]])
+            .await
+            .unwrap(),
+        vec![false, false, true, true]
+    );
+
+    // Deleting a shorter prefix hides the keys written before.
+    let mut batch = Batch::new();
+    batch.delete_key_prefix(vec![1]);
+    batch.put_key_value_bytes(vec![1, 4], vec![14]);
+    store.write_batch(batch).await.unwrap();
+    assert_eq!(
+        store.find_key_values_by_prefix(&[1]).await.unwrap(),
+        vec![(vec![4], vec![14])]
+    );
+
+    // Nothing reached the base store.
+    assert!(store.has_changes());
+    let base_key_values_by_prefix = store.base().find_key_values_by_prefix(&[]).await.unwrap();
+    assert_eq!(base_key_values_by_prefix, base_key_values);
+
+    store.commit().await.unwrap();
+    assert!(!store.has_changes());
+    assert_eq!(
+        store.base().find_key_values_by_prefix(&[]).await.unwrap(),
+        vec![(vec![1, 4], vec![14]), (vec![2, 0], vec![20])]
+    );
+}
+
+#[tokio::test]
+async fn test_overlay_discard() {
+    let base_key_values = vec![(vec![0, 1], vec![1]), (vec![0, 2], vec![2])];
+    let store = create_overlay_memory_store(&base_key_values).await;
+    let mut batch = Batch::new();
+    batch.delete_key_prefix(vec![0]);
+    batch.put_key_value_bytes(vec![0, 3], vec![3]);
+    store.write_batch(batch).await.unwrap();
+    assert_eq!(
+        store.find_key_values_by_prefix(&[0]).await.unwrap(),
+        vec![(vec![3], vec![3])]
+    );
+
+    store.discard();
+    assert!(!store.has_changes());
+    assert_eq!(
+        store.find_key_values_by_prefix(&[]).await.unwrap(),
+        base_key_values
+    );
+    store.commit().await.unwrap();
+    assert_eq!(
+        store.base().find_key_values_by_prefix(&[]).await.unwrap(),
+        base_key_values
+    );
+}
+
+#[tokio::test]
+async fn test_overlay_matches_direct_writes() {
+    use rand::Rng as _;
+
+    let mut rng = make_deterministic_rng();
+    let key_prefix = vec![0];
+    let base_key_values = (0..100)
+        .map(|_| {
+            let key = get_small_key_space(&mut rng, &key_prefix, 3);
+            (key, vec![rng.gen()])
+        })
+        .collect::<Vec<_>>();
+    let store = create_overlay_memory_store(&base_key_values).await;
+    let reference = create_memory_store(&base_key_values).await;
+
+    for _ in 0..20 {
+        let mut batch = Batch::new();
+        for _ in 0..30 {
+            let key = get_small_key_space(&mut rng, &key_prefix, 3);
+            match rng.gen_range(0..10) {
+                0 => batch.delete_key_prefix(key[..rng.gen_range(2..key.len())].to_vec()),
+                1..=3 => batch.delete_key(key),
+                _ => batch.put_key_value_bytes(key, vec![rng.gen()]),
+            }
+        }
+        store.write_batch(batch.clone()).await.unwrap();
+        reference.write_batch(batch).await.unwrap();
+
+        for prefix_len in 1..=3 {
+            let prefix = get_small_key_space(&mut rng, &key_prefix, 3)[..prefix_len].to_vec();
+            assert_eq!(
+                store.find_key_values_by_prefix(&prefix).await.unwrap(),
+                reference.find_key_values_by_prefix(&prefix).await.unwrap()
+            );
+            assert_eq!(
+                store.find_keys_by_prefix(&prefix).await.unwrap(),
+                reference.find_keys_by_prefix(&prefix).await.unwrap()
+            );
+        }
+    }
+
+    assert_eq!(
+        store.base().find_key_values_by_prefix(&[]).await.unwrap(),
+        base_key_values_sorted(base_key_values)
+    );
+    store.commit().await.unwrap();
+    assert_eq!(
+        store.base().find_key_values_by_prefix(&[]).await.unwrap(),
+        reference.find_key_values_by_prefix(&[]).await.unwrap()
+    );
+}
+
+/// Returns the key-value pairs sorted by key, keeping the last value of each key.
+fn base_key_values_sorted(key_values: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    key_values
+        .into_iter()
+        .collect::<std::collections::BTreeMap<_, _>>()
+        .into_iter()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_reads_encrypted_memory() {
+    for scenario in get_random_test_scenarios() {
+        let store = EncryptedMemoryStore::new_test_store().await.unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
+#[tokio::test]
+async fn test_encrypted_memory_writes_from_blank() {
+    let store = EncryptedMemoryStore::new_test_store().await.unwrap();
+    run_writes_from_blank(&store).await;
+}
+
+#[tokio::test]
+async fn test_encrypted_memory_writes_from_state() {
+    let store = EncryptedMemoryStore::new_test_store().await.unwrap();
+    run_writes_from_state(&store).await;
+}
+
+/// The namespace passed to the encrypted stores of the tests.
+const TEST_NAMESPACE: &str = "test_encryption";
+
+/// Returns a test key with the given id.
+fn test_encryption_key(id: u32) -> EncryptionKey {
+    EncryptionKey {
+        id,
+        bytes: [id as u8; 32],
+    }
+}
+
+/// Returns the ids of the keys that encrypted the values of the given store.
+async fn read_encryption_key_ids(store: &MemoryStore) -> Vec<u32> {
+    store
+        .find_key_values_by_prefix(&[])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, envelope)| EncryptedMemoryStore::read_key_id(&envelope).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_encrypted_store_round_trip() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let store = EncryptedStore::new(
+        base.clone(),
+        TEST_NAMESPACE,
+        &[],
+        &EncryptionConfig::new_for_testing(),
+    )
+    .unwrap();
+    let value = b"some secret value".to_vec();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0, 1], value.clone());
+    store.write_batch(batch).await.unwrap();
+    assert_eq!(
+        store.read_value_bytes(&[0, 1]).await.unwrap(),
+        Some(value.clone())
+    );
+
+    let envelope = base.read_value_bytes(&[0, 1]).await.unwrap().unwrap();
+    assert_eq!(envelope.len(), value.len() + ENVELOPE_OVERHEAD);
+    assert!(!envelope.windows(value.len()).any(|window| window == value));
+
+    // An envelope copied to another key does not authenticate.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0, 2], envelope);
+    base.write_batch(batch).await.unwrap();
+    assert!(matches!(
+        store.read_value_bytes(&[0, 2]).await,
+        Err(EncryptionError::DecryptionFailed)
+    ));
+}
+
+#[tokio::test]
+async fn test_encrypted_store_authenticates_root_key() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let config = EncryptionConfig::new_for_testing();
+    let base1 = base.clone_with_root_key(&[1]).unwrap();
+    let store1 = EncryptedStore::new(base1.clone(), TEST_NAMESPACE, &[1], &config).unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0, 1], b"some secret value".to_vec());
+    store1.write_batch(batch).await.unwrap();
+
+    // An envelope copied to the same key under another root key does not authenticate.
+    let envelope = base1.read_value_bytes(&[0, 1]).await.unwrap().unwrap();
+    let base2 = base.clone_with_root_key(&[2]).unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![0, 1], envelope);
+    base2.write_batch(batch).await.unwrap();
+    let store2 = store1.clone_with_root_key(&[2]).unwrap();
+    assert!(matches!(
+        store2.read_value_bytes(&[0, 1]).await,
+        Err(EncryptionError::DecryptionFailed)
+    ));
+}
+
+#[tokio::test]
+async fn test_encrypted_store_reads_retired_keys() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let old_config = EncryptionConfig::new(test_encryption_key(1));
+    let old_store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &old_config).unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 0], vec![10]);
+    batch.put_key_value_bytes(vec![1, 1], vec![11]);
+    old_store.write_batch(batch).await.unwrap();
+
+    let config = old_config.rotate(test_encryption_key(2));
+    let store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &config).unwrap();
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![1, 1], vec![111]);
+    batch.put_key_value_bytes(vec![1, 2], vec![12]);
+    store.write_batch(batch).await.unwrap();
+    assert_eq!(
+        store.find_key_values_by_prefix(&[1]).await.unwrap(),
+        vec![
+            (vec![0], vec![10]),
+            (vec![1], vec![111]),
+            (vec![2], vec![12])
+        ]
+    );
+    assert_eq!(read_encryption_key_ids(&base).await, vec![1, 2, 2]);
+
+    // Without the retired key, only the values written under the current key are readable.
+    let config = EncryptionConfig::new(test_encryption_key(2));
+    let store = EncryptedStore::new(base, TEST_NAMESPACE, &[], &config).unwrap();
+    assert!(matches!(
+        store.read_value_bytes(&[1, 0]).await,
+        Err(EncryptionError::UnknownKey(1))
+    ));
+    assert_eq!(
+        store
+            .read_multi_values_bytes(vec![vec![1, 1], vec![1, 2], vec![1, 3]])
+            .await
+            .unwrap(),
+        vec![Some(vec![111]), Some(vec![12]), None]
+    );
+}
+
+#[tokio::test]
+async fn test_encrypted_store_rejects_duplicate_key_ids() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let config = EncryptionConfig::new(test_encryption_key(1)).rotate(test_encryption_key(1));
+    assert!(matches!(
+        EncryptedStore::new(base, TEST_NAMESPACE, &[], &config),
+        Err(EncryptionError::DuplicateKeyId(1))
+    ));
+}
+
+#[tokio::test]
+async fn test_encrypted_store_resumes_reencryption() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let old_config = EncryptionConfig::new(test_encryption_key(1));
+    let old_store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &old_config).unwrap();
+    let mut key_values = (0..=u8::MAX)
+        .step_by(16)
+        .flat_map(|prefix| (0..3).map(move |index| (vec![prefix, index], vec![prefix, index, 7])))
+        .collect::<Vec<_>>();
+    let mut batch = Batch::new();
+    for (key, value) in &key_values {
+        batch.put_key_value_bytes(key.clone(), value.clone());
+    }
+    old_store.write_batch(batch).await.unwrap();
+
+    let config = old_config.rotate(test_encryption_key(2));
+    let store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &config).unwrap();
+    let mut progress = ReEncryptionProgress::default();
+    for _ in 0..100 {
+        store
+            .reencrypt_next_prefix(&mut progress, &[], &mut |_| {})
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        progress,
+        ReEncryptionProgress {
+            root_key: Some(Vec::new()),
+            next_prefix: 100,
+            last_key: None,
+            rewritten_values: 21,
+            complete: false,
+        }
+    );
+
+    // Values written in the meantime are already under the current key.
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![200, 9], vec![200, 9, 7]);
+    store.write_batch(batch).await.unwrap();
+    key_values.push((vec![200, 9], vec![200, 9, 7]));
+    key_values.sort();
+
+    // The interrupted re-encryption is resumed by another instance from the saved progress.
+    let saved_progress = bcs::to_bytes(&progress).unwrap();
+    let store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &config).unwrap();
+    let mut progress = bcs::from_bytes::<ReEncryptionProgress>(&saved_progress).unwrap();
+    let mut checkpoints = Vec::new();
+    store
+        .reencrypt(&mut progress, |progress| checkpoints.push(progress.clone()))
+        .await
+        .unwrap();
+    assert!(progress.is_complete());
+    assert_eq!(progress.rewritten_values, 48);
+    // One checkpoint per remaining prefix, per non-empty chunk, and at the end.
+    assert_eq!(checkpoints.len(), 156 + 10 + 1);
+    assert_eq!(checkpoints.last(), Some(&progress));
+    assert!(read_encryption_key_ids(&base)
+        .await
+        .into_iter()
+        .all(|key_id| key_id == 2));
+
+    // The retired key is no longer needed.
+    let config = EncryptionConfig::new(test_encryption_key(2));
+    let store = EncryptedStore::new(base, TEST_NAMESPACE, &[], &config).unwrap();
+    assert_eq!(
+        store.find_key_values_by_prefix(&[]).await.unwrap(),
+        key_values
+    );
+}
+
+#[tokio::test]
+async fn test_encrypted_store_reencrypts_all_root_keys() {
+    let base = MemoryStore::new_test_store().await.unwrap();
+    let old_config = EncryptionConfig::new(test_encryption_key(1));
+    let old_store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &old_config).unwrap();
+    let root_keys = [vec![1], vec![1, 5], vec![2]];
+    for root_key in &root_keys {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![3, 4], root_key.clone());
+        let store = old_store.clone_with_root_key(root_key).unwrap();
+        store.write_batch(batch).await.unwrap();
+    }
+
+    let config = old_config.rotate(test_encryption_key(2));
+    let store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &config).unwrap();
+    assert_eq!(store.registered_root_keys().await.unwrap(), root_keys);
+    let mut progress = ReEncryptionProgress::default();
+    store.reencrypt(&mut progress, |_| {}).await.unwrap();
+    assert!(progress.is_complete());
+    assert_eq!(progress.rewritten_values, 3);
+
+    let config = EncryptionConfig::new(test_encryption_key(2));
+    let store = EncryptedStore::new(base.clone(), TEST_NAMESPACE, &[], &config).unwrap();
+    for root_key in &root_keys {
+        let base = base.clone_with_root_key(root_key).unwrap();
+        assert_eq!(read_encryption_key_ids(&base).await, vec![2]);
+        let store = store.clone_with_root_key(root_key).unwrap();
+        assert_eq!(
+            store.read_value_bytes(&[3, 4]).await.unwrap(),
+            Some(root_key.clone())
+        );
+    }
+}
@@ -14,21 +14,22 @@ use linera_views::scylla_db::ScyllaDbStore;
 #[cfg(any(with_scylladb, with_rocksdb, with_dynamodb))]
 use linera_views::store::AdminKeyValueStore as _;
 use linera_views::{
+    access_tracking::{AccessTrackingStore, ViewPath},
     batch::{
         Batch, WriteOperation,
         WriteOperation::{Delete, DeletePrefix, Put},
     },
-    collection_view::HashedCollectionView,
+    collection_view::{CollectionView, HashedCollectionView},
     context::{create_test_memory_context, Context, MemoryContext, ViewContext},
     key_value_store_view::{KeyValueStoreView, ViewContainer},
-    log_view::HashedLogView,
+    log_view::{HashedLogView, LogView},
     lru_caching::{LruCachingMemoryStore, LruCachingStore},
     map_view::{ByteMapView, HashedMapView},
     memory::MemoryStore,
     queue_view::HashedQueueView,
     random::make_deterministic_rng,
     reentrant_collection_view::HashedReentrantCollectionView,
-    register_view::HashedRegisterView,
+    register_view::{HashedRegisterView, RegisterView},
     set_view::HashedSetView,
     store::TestKeyValueStore as _,
     test_utils::{
@@ -652,6 +653,51 @@ async fn test_byte_map_view() -> Result<()> {
     Ok(())
 }
 
+#[derive(CryptoHashRootView)]
+pub struct LogStateView<C> {
+    pub log: LogView<C, u32>,
+}
+
+#[tokio::test]
+async fn test_log_view_reverse_reads() -> Result<()> {
+    let context = create_test_memory_context();
+    {
+        let view = LogStateView::load(context.clone()).await?;
+        assert_eq!(view.log.count(), 0);
+        assert_eq!(view.log.read_back(3).await?, Vec::<u32>::new());
+        assert_eq!(view.log.iter_rev(..).await?.count(), 0);
+    }
+    {
+        let mut view = LogStateView::load(context.clone()).await?;
+        for value in 0..4 {
+            view.log.push(value);
+        }
+        view.save().await?;
+    }
+    {
+        let mut view = LogStateView::load(context.clone()).await?;
+        for value in 4..6 {
+            view.log.push(value);
+        }
+        assert_eq!(view.log.count(), 6);
+        assert_eq!(view.log.read_back(0).await?, Vec::<u32>::new());
+        assert_eq!(view.log.read_back(3).await?, vec![5, 4, 3]);
+        assert_eq!(view.log.read_back(10).await?, vec![5, 4, 3, 2, 1, 0]);
+        let values = view.log.iter_rev(2..5).await?.collect::<Vec<_>>();
+        assert_eq!(values, vec![4, 3, 2]);
+        let values = view.log.iter_rev(..=1).await?.collect::<Vec<_>>();
+        assert_eq!(values, vec![1, 0]);
+        let values = view.log.iter_rev(5..20).await?.collect::<Vec<_>>();
+        assert_eq!(values, vec![5]);
+
+        view.log.clear();
+        view.log.push(7);
+        assert_eq!(view.log.count(), 1);
+        assert_eq!(view.log.read_back(3).await?, vec![7]);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 async fn test_views_in_lru_memory_param(config: &TestConfig) -> Result<()> {
     tracing::warn!("Testing config {:?} with lru memory", config);
@@ -1205,3 +1251,100 @@ async fn check_large_write_memory() -> Result<()> {
     let mut store = MemoryTestStorage::new().await;
     check_large_write(&mut store, vector).await
 }
+
+#[tokio::test]
+async fn check_view_access_report_of_one_map_entry() -> Result<()> {
+    let store = AccessTrackingStore::new(MemoryStore::new_test_store().await?);
+    let context = ViewContext::create_root_context(store.clone(), ()).await?;
+    {
+        let mut view = StateView::load(context.clone()).await?;
+        view.x1.set(1);
+        view.map.insert("a", 1)?;
+        view.map.insert("b", 2)?;
+        view.save().await?;
+    }
+    let view = StateView::load(context).await?;
+    store.take_access_report();
+
+    assert_eq!(view.map.get("b").await?, Some(2));
+
+    // The map is the fourth field of the root view.
+    let map_path = ViewPath::from([3]);
+    let report = store.access_report();
+    assert_eq!(report.paths().collect::<Vec<_>>(), [&map_path]);
+    let stats = report.get(&map_path).unwrap();
+    assert_eq!(stats.reads, 1);
+    assert_eq!(stats.writes, 0);
+    assert_eq!(stats.bytes_read, bcs::to_bytes(&2usize)?.len() as u64);
+    Ok(())
+}
+
+#[derive(RootView)]
+struct BalancesView<C> {
+    balances: CollectionView<C, String, RegisterView<C, u64>>,
+}
+
+#[tokio::test]
+async fn check_loading_several_collection_entries() -> Result<()> {
+    let context = create_test_memory_context();
+    {
+        let mut view = BalancesView::load(context.clone()).await?;
+        *view.balances.load_entry_mut("alice").await?.get_mut() = 100;
+        *view.balances.load_entry_mut("bob").await?.get_mut() = 50;
+        view.save().await?;
+    }
+    let mut view = BalancesView::load(context.clone()).await?;
+
+    // A transfer from a saved entry to a new one.
+    {
+        let mut accounts = view
+            .balances
+            .try_load_entries_mut(&["alice", "carol"])
+            .await?;
+        *accounts[0].get_mut() -= 30;
+        *accounts[1].get_mut() += 30;
+    }
+    assert!(matches!(
+        view.balances.try_load_entries_mut(&["bob", "bob"]).await,
+        Err(ViewError::DuplicateCollectionEntry(_))
+    ));
+
+    // A removed entry is absent from the bulk read, and is loaded again with its default value.
+    view.balances.remove_entry("bob")?;
+    let entries = view
+        .balances
+        .load_all_entries()
+        .await?
+        .into_iter()
+        .map(|(name, balance)| (name, *balance.get()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [("alice".to_string(), 70), ("carol".to_string(), 30)]
+    );
+    let accounts = view
+        .balances
+        .try_load_entries_mut(&["bob", "alice"])
+        .await?;
+    assert_eq!(*accounts[0].get(), 0);
+    assert_eq!(*accounts[1].get(), 70);
+    view.save().await?;
+
+    let mut view = BalancesView::load(context).await?;
+    let entries = view
+        .balances
+        .load_all_entries()
+        .await?
+        .into_iter()
+        .map(|(name, balance)| (name, *balance.get()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            ("alice".to_string(), 70),
+            ("bob".to_string(), 0),
+            ("carol".to_string(), 30)
+        ]
+    );
+    Ok(())
+}
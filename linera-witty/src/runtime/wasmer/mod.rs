@@ -50,6 +50,11 @@ impl<UserData: 'static> InstanceBuilder<UserData> {
         &self.store
     }
 
+    /// Returns the imports defined so far for the Wasm guest instance.
+    pub fn imports(&self) -> &Imports {
+        &self.imports
+    }
+
     /// Creates a [`FunctionEnv`] representing the instance of this [`InstanceBuilder`].
     ///
     /// This can be used when exporting host functions that may perform reentrant calls.